@@ -165,6 +165,8 @@ interface FilterDropdownState {
   allValues: string[];
   /** Whether column has blank cells */
   hasBlanks: boolean;
+  /** Distinct values the backend left out of allValues */
+  moreCount: number;
   /** Set of currently checked values */
   checkedValues: Set<string>;
   /** Whether blanks are checked */
@@ -184,6 +186,7 @@ const FilterDropdownOverlay: React.FC<OverlayProps> = ({ onClose, data, anchorRe
   const [state, setState] = useState<FilterDropdownState>({
     allValues: [],
     hasBlanks: false,
+    moreCount: 0,
     checkedValues: new Set(),
     blanksChecked: true,
     searchText: "",
@@ -236,6 +239,7 @@ const FilterDropdownOverlay: React.FC<OverlayProps> = ({ onClose, data, anchorRe
       setState({
         allValues: values,
         hasBlanks: result.hasBlanks,
+        moreCount: result.moreCount ?? 0,
         checkedValues: checked,
         blanksChecked,
         searchText: "",
@@ -247,6 +251,37 @@ const FilterDropdownOverlay: React.FC<OverlayProps> = ({ onClose, data, anchorRe
     return () => { cancelled = true; };
   }, [relativeCol]);
 
+  // The loaded list is truncated: look up matches for the search text on the
+  // backend and merge them in, checked unless a value filter excludes them.
+  useEffect(() => {
+    if (state.loading || state.moreCount === 0 || !state.searchText) return;
+    let cancelled = false;
+    const search = state.searchText;
+
+    const timer = setTimeout(async () => {
+      const result = await getColumnUniqueValues(relativeCol, search);
+      if (cancelled || !result.success) return;
+      const filterValues = getAutoFilterInfo()?.criteria[relativeCol]?.values;
+      setState((prev) => {
+        const known = new Set(prev.allValues);
+        const added = result.values.map((v) => v.value).filter((v) => !known.has(v));
+        if (added.length === 0) return prev;
+        const checked = new Set(prev.checkedValues);
+        for (const v of added) {
+          if (!filterValues || filterValues.length === 0 || filterValues.includes(v)) {
+            checked.add(v);
+          }
+        }
+        return { ...prev, allValues: [...prev.allValues, ...added], checkedValues: checked };
+      });
+    }, 200);
+
+    return () => {
+      cancelled = true;
+      clearTimeout(timer);
+    };
+  }, [relativeCol, state.loading, state.moreCount, state.searchText]);
+
   // Focus search on mount
   useEffect(() => {
     const timer = setTimeout(() => {
@@ -509,6 +544,10 @@ const FilterDropdownOverlay: React.FC<OverlayProps> = ({ onClose, data, anchorRe
         {filteredValues.length === 0 && !state.hasBlanks && (
           <EmptyMessage>No matching values</EmptyMessage>
         )}
+
+        {state.moreCount > 0 && !state.searchText && (
+          <EmptyMessage>and {state.moreCount.toLocaleString()} more</EmptyMessage>
+        )}
       </CheckboxList>
 
       {/* Expression Filter - takes precedence over checkboxes when non-empty */}
@@ -695,7 +695,7 @@ describe("getColumnUniqueValues", () => {
     const { getColumnUniqueValues } = await import("../filterStore");
     const result = await getColumnUniqueValues(2);
 
-    expect(mockGetFilterUniqueValues).toHaveBeenCalledWith(2);
+    expect(mockGetFilterUniqueValues).toHaveBeenCalledWith(2, undefined);
     expect(result).toEqual(expected);
   });
 });
@@ -267,8 +267,8 @@ export async function reapplyFilter(): Promise<void> {
 /**
  * Get unique values for a column in the AutoFilter range.
  */
-export async function getColumnUniqueValues(relativeColIndex: number) {
-  return getFilterUniqueValues(relativeColIndex);
+export async function getColumnUniqueValues(relativeColIndex: number, search?: string) {
+  return getFilterUniqueValues(relativeColIndex, search);
 }
 
 /**
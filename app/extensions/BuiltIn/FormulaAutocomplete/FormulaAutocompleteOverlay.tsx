@@ -7,7 +7,7 @@ import type { OverlayProps } from "@api/uiTypes";
 import { getCachedLocale } from "@api/locale";
 import { useAutocompleteStore } from "./useAutocompleteStore";
 import type { ScoredSuggestion } from "../../_shared/lib/functionCatalog";
-import type { FunctionInfo } from "@api/types";
+import type { FunctionInfo, FunctionSignature } from "@api/types";
 import * as S from "./FormulaAutocompleteOverlay.styles";
 
 const DROPDOWN_WIDTH = 340;
@@ -31,6 +31,7 @@ export function FormulaAutocompleteOverlay(_props: OverlayProps): React.ReactEle
     accept,
     argumentHintVisible,
     argumentHintFunction,
+    argumentHintSignature,
     argumentHintIndex,
   } = useAutocompleteStore();
 
@@ -106,10 +107,17 @@ export function FormulaAutocompleteOverlay(_props: OverlayProps): React.ReactEle
           style={{ left: dropdownX, top: hintY }}
           onMouseDown={preventBlur}
         >
-          <ArgumentHint
-            func={argumentHintFunction}
-            activeArgIndex={argumentHintIndex}
-          />
+          {argumentHintSignature?.name === argumentHintFunction.name ? (
+            <SignatureHint
+              signature={argumentHintSignature}
+              activeArgIndex={argumentHintIndex}
+            />
+          ) : (
+            <ArgumentHint
+              func={argumentHintFunction}
+              activeArgIndex={argumentHintIndex}
+            />
+          )}
         </S.ArgumentHintContainer>
       )}
     </>
@@ -268,6 +276,75 @@ function ArgumentHint({
   );
 }
 
+/**
+ * Intellisense card built from the backend's structured signature: same
+ * layout as ArgumentHint, but the active parameter follows repeating groups
+ * (SUMIFS' criteria_range/criteria pairs) and its label shows the type hint.
+ */
+function SignatureHint({
+  signature,
+  activeArgIndex,
+}: {
+  signature: FunctionSignature;
+  activeArgIndex: number;
+}): React.ReactElement {
+  const { name, args, description } = signature;
+  const displaySep = (getCachedLocale()?.listSeparator ?? ",") + " ";
+  const activeIdx = resolveSignatureArgIndex(signature, activeArgIndex);
+  const active = activeIdx >= 0 ? args[activeIdx] : undefined;
+  // The "..." marker follows the last repeating parameter.
+  const lastVariadic = args.map((a) => a.variadic).lastIndexOf(true);
+
+  return (
+    <>
+      <S.SignatureLine>
+        <S.FnName>{name}</S.FnName>(
+        {args.map((arg, i) => {
+          const text = arg.optional ? `[${arg.name}]` : arg.name;
+          return (
+            <React.Fragment key={i}>
+              {i > 0 && displaySep}
+              {i === activeIdx ? (
+                <S.ActiveArg>{text}</S.ActiveArg>
+              ) : (
+                <S.InactiveArg>{text}</S.InactiveArg>
+              )}
+              {i === lastVariadic && <S.InactiveArg>{displaySep}...</S.InactiveArg>}
+            </React.Fragment>
+          );
+        })}
+        )
+      </S.SignatureLine>
+      {active && (
+        <S.ActiveParamLabel>
+          <strong>{active.name}</strong>
+          <S.ParamOptionalNote>{active.typeHint}</S.ParamOptionalNote>
+          {active.optional && <S.ParamOptionalNote>optional</S.ParamOptionalNote>}
+        </S.ActiveParamLabel>
+      )}
+      {description && <S.HintDescription>{description}</S.HintDescription>}
+    </>
+  );
+}
+
+/**
+ * Map an argument index onto the signature's parameter list. Past the listed
+ * parameters, cycle through the repeating group (value/result pairs keep
+ * alternating); functions without one clamp to their last parameter.
+ */
+function resolveSignatureArgIndex(signature: FunctionSignature, activeArgIndex: number): number {
+  const { args } = signature;
+  if (args.length === 0) return -1;
+  if (activeArgIndex < 0) return 0;
+  if (activeArgIndex < args.length) return activeArgIndex;
+
+  const first = args.findIndex((a) => a.variadic);
+  if (first < 0) return args.length - 1;
+  const groupLen = args.slice(first).findIndex((a) => !a.variadic);
+  const len = groupLen < 0 ? args.length - first : groupLen;
+  return first + ((activeArgIndex - first) % len);
+}
+
 /**
  * Pick which listed parameter is "active" for the given argument index,
  * clamping into range and keeping the last repeatable parameter highlighted
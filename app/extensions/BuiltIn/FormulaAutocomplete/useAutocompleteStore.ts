@@ -13,9 +13,16 @@ import type { AutocompleteInputPayload } from "@api/formulaAutocomplete";
 import { isFormulaExpectingReference } from "@api/types";
 import { parseTokenAtCursor } from "./tokenParser";
 import type { TokenContext } from "./tokenParser";
-import { filterSuggestions, loadFunctionCatalog, loadNamedRanges, getFunctionByName } from "../../_shared/lib/functionCatalog";
+import {
+  filterSuggestions,
+  loadFunctionCatalog,
+  loadNamedRanges,
+  getFunctionByName,
+  getCachedFunctionSignature,
+  loadFunctionSignature,
+} from "../../_shared/lib/functionCatalog";
 import type { ScoredSuggestion } from "../../_shared/lib/functionCatalog";
-import type { FunctionInfo } from "@api/types";
+import type { FunctionInfo, FunctionSignature } from "@api/types";
 
 // ============================================================================
 // Store Interface
@@ -41,6 +48,8 @@ interface AutocompleteState {
   argumentHintVisible: boolean;
   /** The function whose arguments to display */
   argumentHintFunction: FunctionInfo | null;
+  /** Structured signature of that function, once fetched (built-ins only) */
+  argumentHintSignature: FunctionSignature | null;
   /** Which argument is active (0-based) */
   argumentHintIndex: number;
 
@@ -71,6 +80,7 @@ export const useAutocompleteStore = create<AutocompleteState>((set, get) => ({
   source: null,
   argumentHintVisible: false,
   argumentHintFunction: null,
+  argumentHintSignature: null,
   argumentHintIndex: -1,
   currentValue: "",
   currentCursorPosition: 0,
@@ -98,7 +108,12 @@ export const useAutocompleteStore = create<AutocompleteState>((set, get) => ({
       const state = get();
       if (state.visible || state.argumentHintVisible) {
         get().dismiss();
-        set({ argumentHintVisible: false, argumentHintFunction: null, argumentHintIndex: -1 });
+        set({
+          argumentHintVisible: false,
+          argumentHintFunction: null,
+          argumentHintSignature: null,
+          argumentHintIndex: -1,
+        });
       }
       return;
     }
@@ -115,18 +130,29 @@ export const useAutocompleteStore = create<AutocompleteState>((set, get) => ({
     if (context.enclosingFunction) {
       const fnInfo = getFunctionByName(context.enclosingFunction);
       if (fnInfo) {
+        const signature = getCachedFunctionSignature(fnInfo.name);
         set({
           argumentHintVisible: true,
           argumentHintFunction: fnInfo,
+          argumentHintSignature: signature ?? null,
           argumentHintIndex: context.argumentIndex,
           anchorRect,
           currentValue: value,
           currentCursorPosition: cursorPosition,
         });
+        if (signature === undefined) {
+          // Fetch once; apply only if the hint still shows the same function.
+          loadFunctionSignature(fnInfo.name).then((sig) => {
+            if (sig && get().argumentHintFunction?.name === fnInfo.name) {
+              set({ argumentHintSignature: sig });
+            }
+          });
+        }
       } else {
         set({
           argumentHintVisible: false,
           argumentHintFunction: null,
+          argumentHintSignature: null,
           argumentHintIndex: -1,
         });
       }
@@ -134,6 +160,7 @@ export const useAutocompleteStore = create<AutocompleteState>((set, get) => ({
       set({
         argumentHintVisible: false,
         argumentHintFunction: null,
+        argumentHintSignature: null,
         argumentHintIndex: -1,
       });
     }
@@ -296,6 +323,7 @@ export const useAutocompleteStore = create<AutocompleteState>((set, get) => ({
       source: null,
       argumentHintVisible: false,
       argumentHintFunction: null,
+      argumentHintSignature: null,
       argumentHintIndex: -1,
       currentValue: "",
       currentCursorPosition: 0,
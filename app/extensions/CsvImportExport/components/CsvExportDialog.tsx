@@ -5,14 +5,11 @@
 import React, { useState, useCallback } from "react";
 import type { DialogProps } from "@api/uiTypes";
 import {
-  getViewportCells,
   showToast,
   restoreFocusToGrid,
 } from "@api";
-import { getGridBounds } from "@api/lib";
 import { save } from "@tauri-apps/plugin-dialog";
 import { csvBackend } from "../lib/csvBackend";
-import { exportToCsv, createDefaultExportOptions } from "../lib/csvExporter";
 
 // ============================================================================
 // Styles
@@ -151,50 +148,28 @@ export const CsvExportDialog: React.FC<DialogProps> = ({ onClose }) => {
   const [delimiter, setDelimiter] = useState(",");
   const [encoding, setEncoding] = useState("utf-8");
   const [textQualifier, setTextQualifier] = useState('"');
+  const [usePrintArea, setUsePrintArea] = useState(true);
   const [exporting, setExporting] = useState(false);
 
   const handleExport = useCallback(async () => {
     setExporting(true);
     try {
-      // Get grid bounds to know the data extent
-      const [maxRow, maxCol] = await getGridBounds();
-      if (maxRow === 0 && maxCol === 0) {
+      // The backend reads display values for the print area (when set and
+      // enabled) or the used range, with print-title rows/columns included.
+      const result = await csvBackend.invoke<{ content: string; rowCount: number }>("export_csv", {
+        options: {
+          delimiter,
+          textQualifier,
+          lineEnding: "\r\n",
+          usePrintArea,
+        },
+      });
+      if (result.rowCount === 0) {
         showToast("No data to export.", { type: "warning", duration: 3000 });
         setExporting(false);
         return;
       }
 
-      // Fetch all cells in the used range
-      const cells = await getViewportCells(0, 0, maxRow - 1, maxCol - 1);
-
-      // Build 2D array from sparse cell data
-      const data: string[][] = [];
-      for (let r = 0; r < maxRow; r++) {
-        const row: string[] = [];
-        for (let c = 0; c < maxCol; c++) {
-          row.push("");
-        }
-        data.push(row);
-      }
-
-      for (const cell of cells) {
-        if (cell.row < maxRow && cell.col < maxCol) {
-          data[cell.row][cell.col] = cell.display ?? "";
-        }
-      }
-
-      // Trim trailing empty rows
-      while (data.length > 0 && data[data.length - 1].every((c) => c === "")) {
-        data.pop();
-      }
-
-      // Generate CSV text
-      const csvText = exportToCsv(data, {
-        delimiter,
-        textQualifier,
-        lineEnding: "\r\n",
-      });
-
       // Choose file extension based on delimiter
       const ext = delimiter === "\t" ? "tsv" : "csv";
       const filterName = delimiter === "\t" ? "Tab-Separated Values" : "CSV File";
@@ -211,11 +186,11 @@ export const CsvExportDialog: React.FC<DialogProps> = ({ onClose }) => {
       if (path) {
         await csvBackend.invoke("write_text_file", {
           path,
-          content: csvText,
+          content: result.content,
           encoding: encoding || null,
         });
 
-        showToast(`Exported ${data.length} rows to CSV.`, { type: "success", duration: 3000 });
+        showToast(`Exported ${result.rowCount} rows to CSV.`, { type: "success", duration: 3000 });
 
         restoreFocusToGrid();
         onClose();
@@ -225,7 +200,7 @@ export const CsvExportDialog: React.FC<DialogProps> = ({ onClose }) => {
     } finally {
       setExporting(false);
     }
-  }, [delimiter, encoding, textQualifier, onClose]);
+  }, [delimiter, encoding, textQualifier, usePrintArea, onClose]);
 
   return (
     <div style={S.backdrop} onMouseDown={(e) => e.target === e.currentTarget && onClose()}>
@@ -283,6 +258,17 @@ export const CsvExportDialog: React.FC<DialogProps> = ({ onClose }) => {
               <option value="">None</option>
             </select>
           </div>
+
+          {/* Range */}
+          <label style={S.radioRow}>
+            <input
+              type="checkbox"
+              style={S.radio}
+              checked={usePrintArea}
+              onChange={(e) => setUsePrintArea(e.target.checked)}
+            />
+            Export print area only (when one is set)
+          </label>
         </div>
 
         {/* Footer */}
@@ -67,9 +67,9 @@ export async function validationCommitGuard(
     return null; // On error, allow the commit
   }
 
-  // 2. If valid, allow the commit
+  // 2. If valid, allow the commit (with the table column's coerced value, if any)
   if (result.isValid) {
-    return null;
+    return result.coercedValue ? { action: "allow", newValue: result.coercedValue } : null;
   }
 
   // 3. If no error alert configured or show_alert is false, allow anyway
@@ -12,9 +12,13 @@ import {
   evalFormulaStepIn,
   evalFormulaStepOut,
   evalFormulaRestart,
+  evalFormulaExpandNode,
   evalFormulaClose,
 } from "@api";
-import type { EvalStepState } from "@api";
+import type { EvalNodeInfo, EvalStepState } from "@api";
+
+/** Values fetched per "Show more" click. */
+const EXPAND_PAGE = 100;
 
 // ============================================================================
 // Styles (CSS variables from app theme)
@@ -130,6 +134,27 @@ const styles = {
     fontFamily: "Consolas, 'Courier New', monospace",
     fontWeight: 600,
   },
+  valuesList: {
+    display: "flex",
+    flexDirection: "column" as const,
+    gap: 6,
+    maxHeight: 160,
+    overflowY: "auto" as const,
+  },
+  valueNode: {
+    fontSize: 12,
+    fontFamily: "Consolas, 'Courier New', monospace",
+    wordBreak: "break-all" as const,
+  },
+  linkBtn: {
+    background: "transparent",
+    border: "none",
+    padding: 0,
+    marginLeft: 6,
+    color: v("--accent-primary"),
+    cursor: "pointer",
+    fontSize: 12,
+  },
   stepInHint: {
     fontSize: 11,
     color: v("--text-secondary"),
@@ -201,6 +226,12 @@ export function EvaluateFormulaDialog(props: DialogProps): React.ReactElement |
   const [evalState, setEvalState] = useState<EvalStepState | null>(null);
   const [isLoading, setIsLoading] = useState(false);
   const [error, setError] = useState<string | null>(null);
+  // Values loaded via "Show more", by node id; reset on every step.
+  const [expanded, setExpanded] = useState<Record<string, string[]>>({});
+
+  useEffect(() => {
+    setExpanded({});
+  }, [evalState]);
 
   // Initialize session on mount
   useEffect(() => {
@@ -313,8 +344,51 @@ export function EvaluateFormulaDialog(props: DialogProps): React.ReactElement |
     runCommand(evalFormulaRestart);
   }, [runCommand]);
 
+  const handleShowMore = useCallback(
+    async (node: EvalNodeInfo) => {
+      if (!evalState?.sessionId) return;
+      const loaded = expanded[node.nodeId] ?? node.preview?.values ?? [];
+      try {
+        const page = await evalFormulaExpandNode(
+          evalState.sessionId,
+          node.nodeId,
+          loaded.length,
+          EXPAND_PAGE,
+        );
+        setExpanded((prev) => ({ ...prev, [node.nodeId]: [...loaded, ...page.values] }));
+      } catch (err) {
+        setError(String(err));
+      }
+    },
+    [evalState, expanded],
+  );
+
   // --- Render helpers ---
 
+  function renderNode(node: EvalNodeInfo): React.ReactNode {
+    const preview = node.preview;
+    const values = expanded[node.nodeId] ?? preview?.values ?? [];
+    const more = preview ? preview.total - values.length : 0;
+    return (
+      <div key={node.nodeId} style={styles.valueNode}>
+        <span style={styles.refLabel}>{node.address ?? node.label}</span>
+        {preview && (
+          <>
+            {" = {"}
+            {values.join(", ")}
+            {more > 0 ? `, … ${more} more` : ""}
+            {"}"}
+            {more > 0 && (
+              <button style={styles.linkBtn} onClick={() => handleShowMore(node)}>
+                Show more
+              </button>
+            )}
+          </>
+        )}
+      </div>
+    );
+  }
+
   function renderFormula(): React.ReactNode {
     if (!evalState) return null;
 
@@ -379,6 +453,18 @@ export function EvaluateFormulaDialog(props: DialogProps): React.ReactElement |
             </div>
           </div>
 
+          {/* Referenced ranges and array intermediates */}
+          {evalState &&
+            (evalState.references.length > 0 || evalState.arrayResults.length > 0) && (
+              <div style={styles.evalSection}>
+                <span style={styles.evalLabel}>Values:</span>
+                <div style={styles.valuesList}>
+                  {evalState.references.map(renderNode)}
+                  {evalState.arrayResults.map(renderNode)}
+                </div>
+              </div>
+            )}
+
           {/* Result of last evaluation */}
           {evalState?.evaluationResult && (
             <div style={styles.resultRow}>
@@ -87,7 +87,7 @@ async function followHyperlink(row: number, col: number): Promise<boolean> {
 
 async function navigateToInternalRef(hyperlink: Hyperlink): Promise<void> {
   const ref = hyperlink.internalRef;
-  if (!ref) return;
+  if (!ref || hyperlink.broken) return;
 
   // Switch sheet if needed
   if (ref.sheetName) {
@@ -30,7 +30,7 @@ import {
   removeColPageBreak,
   resetAllPageBreaks,
   getPageSetup,
-  setPrintArea,
+  setPrintAreas,
   clearPrintArea,
   setPrintTitleRows,
   clearPrintTitleRows,
@@ -230,9 +230,17 @@ async function handleSetPrintArea(): Promise<void> {
       alert("Select a range of cells first to set as print area.");
       return;
     }
-    const rangeStr = await setPrintArea(
-      bounds.startRow, bounds.startCol, bounds.endRow, bounds.endCol,
-    );
+    // A multi-rect (Ctrl+Click) selection becomes a multi-area print area.
+    const ranges = [bounds];
+    for (const r of currentSelection?.additionalRanges ?? []) {
+      ranges.push({
+        startRow: Math.min(r.startRow, r.endRow),
+        startCol: Math.min(r.startCol, r.endCol),
+        endRow: Math.max(r.startRow, r.endRow),
+        endCol: Math.max(r.startCol, r.endCol),
+      });
+    }
+    const rangeStr = await setPrintAreas(ranges);
     console.log("[Print] Print area set to:", rangeStr);
     await refreshPageBreakData();
     window.dispatchEvent(new Event("app:grid-refresh"));
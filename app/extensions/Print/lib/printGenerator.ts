@@ -46,7 +46,8 @@ interface PrintBounds {
 
 function parsePrintArea(printArea: string): PrintBounds | null {
   if (!printArea || !printArea.trim()) return null;
-  const match = printArea.trim().match(/^([A-Z]+)(\d+):([A-Z]+)(\d+)$/i);
+  // A multi-area print area ("A1:F20,H1:J5") prints its first area.
+  const match = printArea.split(",")[0].trim().match(/^([A-Z]+)(\d+):([A-Z]+)(\d+)$/i);
   if (!match) return null;
   return {
     startCol: colToIndex(match[1].toUpperCase()),
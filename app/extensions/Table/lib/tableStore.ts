@@ -22,6 +22,10 @@ import {
   enforceTableHeader as backendEnforceTableHeader,
   setCalculatedColumn as backendSetCalculatedColumn,
   convertFormulaToTableRefs as backendConvertFormulaToTableRefs,
+  setTableColumnDataType as backendSetColumnDataType,
+  setTableDataTypePolicy as backendSetDataTypePolicy,
+  type ColumnDataType,
+  type DataTypePolicy,
   type Table,
   type TableResult,
   type TableStyleOptions,
@@ -146,6 +150,37 @@ export async function toggleTotalsRowAsync(
   return null;
 }
 
+/**
+ * Set or clear a column's data type via the backend. The result lists the
+ * existing cells that do not match the new type.
+ */
+export async function setColumnDataTypeAsync(
+  tableId: string,
+  columnName: string,
+  dataType: ColumnDataType | null,
+): Promise<TableResult> {
+  const result = await backendSetColumnDataType({ tableId, columnName, dataType });
+  if (result.success) {
+    await refreshCache();
+  }
+  return result;
+}
+
+/**
+ * Choose whether nonconforming input is rejected or coerced, via the backend.
+ */
+export async function setDataTypePolicyAsync(
+  tableId: string,
+  policy: DataTypePolicy,
+): Promise<Table | null> {
+  const result = await backendSetDataTypePolicy(tableId, policy);
+  if (result.success && result.table) {
+    await refreshCache();
+    return result.table;
+  }
+  return null;
+}
+
 /**
  * Resize a table via the backend.
  */
@@ -2,8 +2,8 @@
 // PURPOSE: Fetch, cache, and filter the formula function catalog and named ranges.
 // CONTEXT: Provides ranked/filtered results for the autocomplete dropdown.
 
-import { getAllFunctions, getAllNamedRanges } from "@api/lib";
-import type { FunctionInfo } from "@api/types";
+import { getAllFunctions, getAllNamedRanges, getFunctionSignature } from "@api/lib";
+import type { FunctionInfo, FunctionSignature } from "@api/types";
 import type { NamedRange } from "@api/lib";
 import {
   getAllCustomFunctions,
@@ -493,3 +493,36 @@ export function getFunctionByName(name: string): FunctionInfo | undefined {
   );
   return customFn ? customToFunctionInfo(customFn) : undefined;
 }
+
+// ============================================================================
+// Signatures (argument hints)
+// ============================================================================
+
+/** Fetched signatures by upper-cased name; null marks "no built-in signature". */
+const cachedSignatures = new Map<string, FunctionSignature | null>();
+
+/**
+ * Cached structured signature for a function (synchronous).
+ * Returns undefined if it has not been fetched yet.
+ */
+export function getCachedFunctionSignature(name: string): FunctionSignature | null | undefined {
+  return cachedSignatures.get(name.toUpperCase());
+}
+
+/**
+ * Fetch a function's structured signature from the backend. Cached per name;
+ * resolves to null for custom functions and unknown names.
+ */
+export async function loadFunctionSignature(name: string): Promise<FunctionSignature | null> {
+  const upper = name.toUpperCase();
+  const cached = cachedSignatures.get(upper);
+  if (cached !== undefined) return cached;
+  try {
+    const signature = await getFunctionSignature(upper);
+    cachedSignatures.set(upper, signature);
+    return signature;
+  } catch (error) {
+    console.error("[FormulaAutocomplete] Failed to load signature for", upper, error);
+    return null;
+  }
+}
@@ -249,6 +249,7 @@ export function PivotOptionsDialog({
   const [fillEmptyCells, setFillEmptyCells] = useState(false);
   const [emptyCellText, setEmptyCellText] = useState("");
   const [refreshOnOpen, setRefreshOnOpen] = useState(false);
+  const [autoRefresh, setAutoRefresh] = useState(false);
 
   // Layout & Format state
   const [reportLayout, setReportLayout] = useState<ReportLayout>("compact");
@@ -266,6 +267,7 @@ export function PivotOptionsDialog({
       .then((info: PivotTableInfo) => {
         setAllowMultipleFilters(info.allowMultipleFiltersPerField);
         setRefreshOnOpen(info.refreshOnOpen);
+        setAutoRefresh(info.autoRefresh);
         setLoading(false);
       })
       .catch((err) => {
@@ -295,6 +297,7 @@ export function PivotOptionsDialog({
         pivotId,
         allowMultipleFiltersPerField: allowMultipleFilters,
         refreshOnOpen,
+        autoRefresh,
       });
 
       // Update layout
@@ -319,7 +322,7 @@ export function PivotOptionsDialog({
   }, [
     pivotId, onClose,
     showRowGrandTotals, showColumnGrandTotals, allowMultipleFilters,
-    showFieldHeaders, fillEmptyCells, emptyCellText, refreshOnOpen,
+    showFieldHeaders, fillEmptyCells, emptyCellText, refreshOnOpen, autoRefresh,
     reportLayout, subtotalLocation, repeatRowLabels,
   ]);
 
@@ -452,6 +455,14 @@ export function PivotOptionsDialog({
                       />
                       <span>Refresh data when opening the file</span>
                     </label>
+                    <label className={styles.checkboxItem}>
+                      <input
+                        type="checkbox"
+                        checked={autoRefresh}
+                        onChange={(e) => setAutoRefresh(e.target.checked)}
+                      />
+                      <span>Refresh data when source cells change</span>
+                    </label>
                   </div>
                 </>
               )}
@@ -107,6 +107,7 @@ import {
   getPivotLayoutRanges as apiGetPivotLayoutRanges,
   updatePivotLayout as apiUpdatePivotLayout,
   getPivotHierarchies as apiGetPivotHierarchies,
+  getPivotSourceFields as apiGetPivotSourceFields,
   addPivotHierarchy as apiAddPivotHierarchy,
   removePivotHierarchy as apiRemovePivotHierarchy,
   movePivotField as apiMovePivotField,
@@ -119,6 +120,7 @@ import {
   setPivotItemVisibility as apiSetPivotItemVisibility,
   getAllPivotTables as apiGetAllPivotTables,
   refreshAllPivotTables as apiRefreshAllPivotTables,
+  getStaleObjects as apiGetStaleObjects,
   setPivotItemExpanded as apiSetPivotItemExpanded,
   expandCollapseLevel as apiExpandCollapseLevel,
   expandCollapseAll as apiExpandCollapseAll,
@@ -578,6 +580,14 @@ export async function getPivotHierarchies(pivotId: PivotId): Promise<PivotHierar
   return apiGetPivotHierarchies<PivotHierarchiesInfo>(pivotId);
 }
 
+/**
+ * Gets field-list metadata for a pivot's source fields: data types, sample
+ * values, distinct/blank counts, and renamed (blank or duplicate) headers.
+ */
+export async function getPivotSourceFields(pivotId: PivotId): Promise<PivotSourceFieldsInfo> {
+  return apiGetPivotSourceFields<PivotSourceFieldsInfo>(pivotId);
+}
+
 /**
  * Adds a field to a hierarchy (row, column, data, or filter).
  */
@@ -773,6 +783,13 @@ export async function refreshAllPivotTables(): Promise<PivotViewResponse[]> {
   return results;
 }
 
+/**
+ * Gets the pivot tables whose source cells changed since their last refresh.
+ */
+export async function getStalePivotTables(): Promise<PivotId[]> {
+  return apiGetStaleObjects<PivotId[]>();
+}
+
 // ============================================================================
 // EXPAND/COLLAPSE AND GROUPING API FUNCTIONS
 // ============================================================================
@@ -1484,6 +1501,7 @@ import type {
   FieldUniqueValuesResponse,
   PivotLayoutType,
   SubtotalLocationType,
+  GrowPolicy,
   AggregationFunction,
   PivotFilterType,
   SortBy,
@@ -1504,6 +1522,10 @@ import type {
   DataHierarchyInfo,
   RowColumnHierarchyInfo,
   PivotHierarchiesInfo,
+  PivotSourceFieldsInfo,
+  SourceFieldDetails,
+  SourceFieldType,
+  HeaderRename,
   UpdatePivotPropertiesRequest,
   ChangePivotDataSourceRequest,
   UpdatePivotLayoutRequest,
@@ -1593,6 +1615,7 @@ export type {
   FieldUniqueValuesResponse,
   PivotLayoutType,
   SubtotalLocationType,
+  GrowPolicy,
   AggregationFunction,
   PivotFilterType,
   SortBy,
@@ -1613,6 +1636,10 @@ export type {
   DataHierarchyInfo,
   RowColumnHierarchyInfo,
   PivotHierarchiesInfo,
+  PivotSourceFieldsInfo,
+  SourceFieldDetails,
+  SourceFieldType,
+  HeaderRename,
   UpdatePivotPropertiesRequest,
   ChangePivotDataSourceRequest,
   UpdatePivotLayoutRequest,
@@ -1683,6 +1710,7 @@ registerPivotApi({
   getLayoutRanges: getPivotLayoutRanges,
   updateLayout: updatePivotLayout,
   getHierarchies: getPivotHierarchies,
+  getSourceFields: getPivotSourceFields,
   addHierarchy: addPivotHierarchy,
   removeHierarchy: removePivotHierarchy,
   moveField: movePivotField,
@@ -1695,6 +1723,7 @@ registerPivotApi({
   setItemVisibility: setPivotItemVisibility,
   getAll: getAllPivotTables,
   refreshAll: refreshAllPivotTables,
+  getStale: getStalePivotTables,
   setItemExpanded: setPivotItemExpanded,
   expandCollapseLevel,
   expandCollapseAll,
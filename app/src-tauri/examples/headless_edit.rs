@@ -0,0 +1,26 @@
+//! FILENAME: examples/headless_edit.rs
+//! Open a workbook, enter a value or formula into one cell, recalculate and
+//! save — no window involved.
+//!
+//! cargo run --example headless_edit --features headless -- \
+//!     <input.xlsx> <sheet> <cell> <input> [output.xlsx]
+//!
+//! Without an output path the workbook is saved back over the input.
+
+use app_lib::WorkbookSession;
+
+fn main() -> Result<(), String> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let [input_path, sheet, cell, input, rest @ ..] = args.as_slice() else {
+        return Err("usage: headless_edit <input.xlsx> <sheet> <cell> <input> [output.xlsx]".to_string());
+    };
+    let output_path = rest.first().unwrap_or(input_path);
+
+    let session = WorkbookSession::open(input_path)?;
+    session.set_cell(sheet, cell, input)?;
+    session.recalculate()?;
+    println!("{}!{} = {}", sheet, cell, session.get_display(sheet, cell)?);
+    session.save(output_path)?;
+    println!("saved {}", output_path);
+    Ok(())
+}
@@ -24,6 +24,7 @@ use crate::{
     evaluate_formula_multi_sheet, format_cell_value, get_column_row_dependents,
     get_recalculation_order, AppState,
 };
+use crate::sheet_access::{check_sheet_index, sheet_at};
 use engine::{Cell, CellValue, Grid, StyleRegistry};
 
 // ============================================================================
@@ -64,6 +65,7 @@ fn build_cell_data(
         sheet_index: None,
         rich_text: None,
         accounting_layout: None,
+        error_detail: None,
     })
 }
 
@@ -99,9 +101,7 @@ enum SetOp {
 /// Mirrors scenario_manager::scenario_show's recalc loop.
 #[allow(clippy::too_many_arguments)]
 fn apply_set_ops_and_recalc(
-    grids: &mut Vec<Grid>,
-    active_grid: &mut Grid,
-    active_sheet: usize,
+    grids: &mut [Grid],
     sheet_idx: usize,
     sheet_names: &[String],
     styles: &StyleRegistry,
@@ -115,18 +115,8 @@ fn apply_set_ops_and_recalc(
     let mut changed: Vec<(u32, u32)> = Vec::new();
     for ((r, c), op) in ops {
         match op {
-            SetOp::Set(cell) => {
-                grids[sheet_idx].set_cell(*r, *c, cell.clone());
-                if sheet_idx == active_sheet {
-                    active_grid.set_cell(*r, *c, cell.clone());
-                }
-            }
-            SetOp::Clear => {
-                grids[sheet_idx].clear_cell(*r, *c);
-                if sheet_idx == active_sheet {
-                    active_grid.clear_cell(*r, *c);
-                }
-            }
+            SetOp::Set(cell) => grids[sheet_idx].set_cell(*r, *c, cell.clone()),
+            SetOp::Clear => grids[sheet_idx].clear_cell(*r, *c),
         }
         if !changed.contains(&(*r, *c)) {
             changed.push((*r, *c));
@@ -152,14 +142,16 @@ fn apply_set_ops_and_recalc(
     for &(r, c) in &all_affected {
         if let Some(cell) = grids[sheet_idx].get_cell(r, c).cloned() {
             if let Some(formula) = cell.formula_string() {
-                let new_value =
-                    evaluate_formula_multi_sheet(&grids[..], sheet_names, sheet_idx, &formula);
+                let new_value = evaluate_formula_multi_sheet(
+                    grids,
+                    sheet_names,
+                    sheet_idx,
+                    &formula,
+                    engine::EvalContext::at(r, c),
+                );
                 let mut updated = cell;
                 updated.value = new_value;
-                grids[sheet_idx].set_cell(r, c, updated.clone());
-                if sheet_idx == active_sheet {
-                    active_grid.set_cell(r, c, updated);
-                }
+                grids[sheet_idx].set_cell(r, c, updated);
             }
         }
     }
@@ -189,6 +181,7 @@ fn apply_set_ops_and_recalc(
                 sheet_index: None,
                 rich_text: None,
                 accounting_layout: None,
+                error_detail: None,
             });
         }
     }
@@ -205,10 +198,10 @@ fn apply_set_ops_and_recalc(
 pub fn anim_snapshot(state: State<AppState>, params: AnimSnapshotParams) -> AnimSnapshotResult {
     let sheet_idx = params.sheet_index;
     let grids = state.grids.lock().unwrap();
-    if sheet_idx >= grids.len() {
+    if let Err(e) = check_sheet_index(grids.len(), sheet_idx) {
         return AnimSnapshotResult {
             success: false,
-            error: Some(format!("Sheet index {} out of range", sheet_idx)),
+            error: Some(e.to_string()),
         };
     }
     let saved: Vec<((u32, u32), Option<Cell>)> = params
@@ -240,9 +233,7 @@ pub fn anim_apply_frame(
     let sheet_idx = params.sheet_index;
 
     // Lock order matches scenario_show to avoid cross-path deadlocks.
-    let mut grid = state.grid.lock().unwrap();
     let mut grids = state.grids.lock().unwrap();
-    let active_sheet = *state.active_sheet.lock().unwrap();
     let sheet_names = state.sheet_names.lock().unwrap();
     let styles = state.style_registry.lock().unwrap();
     let dependents_map = state.dependents.lock().unwrap();
@@ -251,10 +242,10 @@ pub fn anim_apply_frame(
     let merged_regions = state.merged_regions.lock().unwrap();
     let locale = state.locale.lock().unwrap();
 
-    if sheet_idx >= grids.len() {
+    if let Err(e) = check_sheet_index(grids.len(), sheet_idx) {
         return AnimationFrameResult {
             updated_cells: Vec::new(),
-            error: Some(format!("Sheet index {} out of range", sheet_idx)),
+            error: Some(e.to_string()),
         };
     }
 
@@ -275,8 +266,6 @@ pub fn anim_apply_frame(
 
     let updated_cells = apply_set_ops_and_recalc(
         &mut grids,
-        &mut grid,
-        active_sheet,
         sheet_idx,
         &sheet_names,
         &styles,
@@ -316,9 +305,7 @@ pub fn anim_restore(state: State<AppState>, params: AnimRestoreParams) -> Animat
 
     let sheet_idx = params.sheet_index;
 
-    let mut grid = state.grid.lock().unwrap();
     let mut grids = state.grids.lock().unwrap();
-    let active_sheet = *state.active_sheet.lock().unwrap();
     let sheet_names = state.sheet_names.lock().unwrap();
     let styles = state.style_registry.lock().unwrap();
     let dependents_map = state.dependents.lock().unwrap();
@@ -327,10 +314,10 @@ pub fn anim_restore(state: State<AppState>, params: AnimRestoreParams) -> Animat
     let merged_regions = state.merged_regions.lock().unwrap();
     let locale = state.locale.lock().unwrap();
 
-    if sheet_idx >= grids.len() {
+    if let Err(e) = check_sheet_index(grids.len(), sheet_idx) {
         return AnimationFrameResult {
             updated_cells: Vec::new(),
-            error: Some(format!("Sheet index {} out of range", sheet_idx)),
+            error: Some(e.to_string()),
         };
     }
 
@@ -347,8 +334,6 @@ pub fn anim_restore(state: State<AppState>, params: AnimRestoreParams) -> Animat
 
     let updated_cells = apply_set_ops_and_recalc(
         &mut grids,
-        &mut grid,
-        active_sheet,
         sheet_idx,
         &sheet_names,
         &styles,
@@ -382,6 +367,9 @@ pub fn anim_reroll_and_read(
     ribbon_filter_state: State<'_, crate::ribbon_filter::RibbonFilterState>,
     params: AnimRerollParams,
 ) -> AnimRerollResult {
+    if let Err(e) = check_sheet_index(state.grids.lock().unwrap().len(), params.sheet_index) {
+        return AnimRerollResult { value: None, error: Some(e.to_string()) };
+    }
     crate::calculation::recalculate_sheet_values(
         &state,
         &user_files_state,
@@ -391,13 +379,11 @@ pub fn anim_reroll_and_read(
     );
 
     let grids = state.grids.lock().unwrap();
-    if params.sheet_index >= grids.len() {
-        return AnimRerollResult {
-            value: None,
-            error: Some(format!("Sheet index {} out of range", params.sheet_index)),
-        };
-    }
-    let value = grids[params.sheet_index]
+    let grid = match sheet_at(&grids, params.sheet_index) {
+        Ok(grid) => grid,
+        Err(e) => return AnimRerollResult { value: None, error: Some(e.to_string()) },
+    };
+    let value = grid
         .get_cell(params.outcome_row, params.outcome_col)
         .and_then(|c| match &c.value {
             CellValue::Number(n) => Some(*n),
@@ -495,25 +481,22 @@ mod tests {
     }
 
     /// A1 (literal) with B1 = A1*2 depending on it.
-    fn model() -> (Vec<Grid>, Grid, crate::DependencyMap) {
+    fn model() -> (Vec<Grid>, crate::DependencyMap) {
         let mut g = Grid::new();
         g.set_cell(0, 0, Cell::new_number(10.0)); // A1 = 10
         g.set_cell(0, 1, Cell::new_formula("A1*2".to_string())); // B1 = A1*2
-        let mut active = Grid::new();
-        active.set_cell(0, 0, Cell::new_number(10.0));
-        active.set_cell(0, 1, Cell::new_formula("A1*2".to_string()));
 
         let mut deps = crate::DependencyMap::default();
         let mut b1 = crate::CoordSet::default();
         b1.insert((0, 1)); // B1 depends on A1
         deps.insert((0, 0), b1);
 
-        (vec![g], active, deps)
+        (vec![g], deps)
     }
 
     #[test]
     fn apply_then_restore_round_trips_literal_and_dependent() {
-        let (mut grids, mut active, deps) = model();
+        let (mut grids, deps) = model();
         let coldeps = crate::StripeDependentsMap::default();
         let rowdeps = crate::StripeDependentsMap::default();
         let merged = HashSet::new();
@@ -529,7 +512,7 @@ mod tests {
         a1.style_index = 0;
         let apply_ops = vec![((0, 0), SetOp::Set(a1))];
         apply_set_ops_and_recalc(
-            &mut grids, &mut active, 0, 0, &names, &styles, &deps, &coldeps, &rowdeps,
+            &mut grids, 0, &names, &styles, &deps, &coldeps, &rowdeps,
             &merged, &locale(), &apply_ops,
         );
         assert!(matches!(grids[0].get_cell(0, 0).unwrap().value, CellValue::Number(n) if (n - 99.0).abs() < 1e-9));
@@ -544,7 +527,7 @@ mod tests {
             ))
             .collect();
         apply_set_ops_and_recalc(
-            &mut grids, &mut active, 0, 0, &names, &styles, &deps, &coldeps, &rowdeps,
+            &mut grids, 0, &names, &styles, &deps, &coldeps, &rowdeps,
             &merged, &locale(), &restore_ops,
         );
 
@@ -560,7 +543,7 @@ mod tests {
 
     #[test]
     fn restore_clears_a_cell_that_was_originally_empty() {
-        let (mut grids, mut active, deps) = model();
+        let (mut grids, deps) = model();
         let coldeps = crate::StripeDependentsMap::default();
         let rowdeps = crate::StripeDependentsMap::default();
         let merged = HashSet::new();
@@ -574,7 +557,7 @@ mod tests {
 
         let apply_ops = vec![((0, 2), SetOp::Set(Cell::new_number(5.0)))];
         apply_set_ops_and_recalc(
-            &mut grids, &mut active, 0, 0, &names, &styles, &deps, &coldeps, &rowdeps,
+            &mut grids, 0, &names, &styles, &deps, &coldeps, &rowdeps,
             &merged, &locale(), &apply_ops,
         );
         assert!(grids[0].get_cell(0, 2).is_some());
@@ -587,7 +570,7 @@ mod tests {
             ))
             .collect();
         let updated = apply_set_ops_and_recalc(
-            &mut grids, &mut active, 0, 0, &names, &styles, &deps, &coldeps, &rowdeps,
+            &mut grids, 0, &names, &styles, &deps, &coldeps, &rowdeps,
             &merged, &locale(), &restore_ops,
         );
         assert!(grids[0].get_cell(0, 2).is_none(), "C1 must be empty again after restore");
@@ -129,6 +129,9 @@ pub struct CellData {
     /// When present, the renderer draws symbol at left edge and value at right edge.
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub accounting_layout: Option<AccountingLayout>,
+    /// Why the cell's formula returned its error value, when known.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error_detail: Option<String>,
 }
 
 fn default_span() -> u32 {
@@ -466,6 +469,42 @@ pub struct DimensionData {
     pub dimension_type: String,
 }
 
+/// Why a row or column is hidden. When several apply, the first listed wins.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum HiddenReason {
+    /// Hidden by the user (Hide Rows / Hide Columns).
+    Manual,
+    /// Filtered out by an AutoFilter or Advanced Filter (rows only).
+    Filter,
+    /// Inside a collapsed outline group.
+    Group,
+}
+
+/// Header metadata for one row or column (see `get_axis_metadata`).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AxisEntry {
+    pub index: u32,
+    /// Effective size in pixels: the custom size, else the sheet default.
+    pub size: f64,
+    /// True when `size` comes from a custom height/width.
+    pub custom_size: bool,
+    pub hidden: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hidden_reason: Option<HiddenReason>,
+    /// Deepest outline level containing this line (0 = ungrouped).
+    pub outline_level: u8,
+    /// Inside at least one collapsed group.
+    pub collapsed: bool,
+    /// First line of at least one group.
+    pub group_start: bool,
+    /// Last line of at least one group.
+    pub group_end: bool,
+    /// Summary line carrying a group's expand/collapse button.
+    pub is_button: bool,
+}
+
 /// A single border side for formatting parameters.
 #[derive(Debug, Clone, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
@@ -515,6 +554,11 @@ pub struct FormattingParams {
     pub locked: Option<bool>,
     /// Whether the formula is hidden when sheet is protected.
     pub formula_hidden: Option<bool>,
+    /// `cols` are whole columns (`rows` is ignored): the formatting becomes the
+    /// columns' default style rather than being written to every cell.
+    pub full_columns: bool,
+    /// `rows` are whole rows (`cols` is ignored).
+    pub full_rows: bool,
 }
 
 /// Result from apply_formatting that includes both updated cells and new styles.
@@ -554,6 +598,76 @@ pub struct FunctionListResult {
     pub functions: Vec<FunctionInfo>,
 }
 
+/// What a formula completion refers to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum CompletionKind {
+    Function,
+    DefinedName,
+    Table,
+    /// A table column (or special item like `#Headers`) inside `Table[...]`.
+    Column,
+    Sheet,
+}
+
+/// One ranked completion for the token at the cursor.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CompletionItem {
+    pub label: String,
+    pub kind: CompletionKind,
+    /// Text to splice over the replacement span (e.g. "SUM(", "'My Sheet'!").
+    pub insert_text: String,
+    /// Syntax, refers-to formula, or sheet name, for display.
+    pub detail: Option<String>,
+}
+
+/// Completions plus the span (character offsets, end exclusive) they replace.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CompletionResult {
+    pub items: Vec<CompletionItem>,
+    pub replace_start: usize,
+    pub replace_end: usize,
+}
+
+/// What a formula diagnostic reports.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum FormulaIssueKind {
+    /// The formula does not parse.
+    Syntax,
+    /// A name that is neither a defined name nor a LET/LAMBDA local.
+    UnknownName,
+    /// A structured reference to a table that does not exist, or `[@Col]`
+    /// outside any table.
+    UnknownTable,
+    /// A structured reference naming a column or section the table lacks.
+    UnknownColumn,
+}
+
+/// One problem in a formula, with the span (character offsets, end
+/// exclusive) to underline.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FormulaIssue {
+    pub kind: FormulaIssueKind,
+    pub message: String,
+    pub start: usize,
+    pub end: usize,
+    /// The offending text; None when the formula ended early.
+    pub token: Option<String>,
+    /// What the parser would have accepted instead (syntax errors only).
+    pub expected: Vec<String>,
+}
+
+/// Result of validate_formula. No issues means the formula is valid.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FormulaValidation {
+    pub issues: Vec<FormulaIssue>,
+}
+
 /// Result from update_cell that includes both updated cells and optional dimension changes.
 /// Dimension changes are only present when UI formulas (like SET.ROW.HEIGHT) are evaluated.
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -580,6 +694,48 @@ pub struct SpillRangeInfo {
     pub end_col: u32,
 }
 
+/// How much of the viewport a `ViewportSnapshot` carries.
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "camelCase")]
+pub enum SnapshotKind {
+    /// Every cell in the rectangle.
+    #[default]
+    Full,
+    /// Only cells that changed since the caller's revision; `cleared_cells`
+    /// lists the ones that became empty.
+    Delta,
+    /// Nothing changed since the caller's revision; every list is empty.
+    NotModified,
+}
+
+/// Everything the grid renderer needs for one viewport, assembled in a single
+/// pass (see `get_viewport_snapshot`). Indicator lists only cover cells inside
+/// the requested rectangle; `merged_regions` holds every region intersecting it.
+/// `kind` says whether `cells` is complete or a delta against the caller's
+/// revision; the other lists are always complete unless `NotModified`.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ViewportSnapshot {
+    pub sheet_index: usize,
+    /// The sheet's viewport revision; pass it back as `known_revision`.
+    pub revision: u64,
+    pub kind: SnapshotKind,
+    pub cells: Vec<CellData>,
+    /// Delta only: changed cells in the rectangle that are now empty.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub cleared_cells: Vec<(u32, u32)>,
+    pub conditional_formats: Vec<crate::conditional_formatting::CellConditionalFormat>,
+    pub comment_indicators: Vec<crate::comments::CommentIndicator>,
+    pub hyperlink_indicators: Vec<crate::hyperlinks::HyperlinkIndicator>,
+    /// Cells showing an in-cell validation dropdown arrow, as (row, col).
+    pub dropdown_cells: Vec<(u32, u32)>,
+    pub merged_regions: Vec<MergedRegion>,
+    /// Rows hidden by filters or collapsed groups, sorted.
+    pub hidden_rows: Vec<u32>,
+    /// Columns hidden by collapsed groups, sorted.
+    pub hidden_cols: Vec<u32>,
+}
+
 /// A merged cell region definition.
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash)]
 #[serde(rename_all = "camelCase")]
@@ -590,6 +746,55 @@ pub struct MergedRegion {
     pub end_col: u32,
 }
 
+/// How merge_cells divides the selection.
+/// Matches Excel's Merge Cells / Merge Across.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "camelCase")]
+pub enum MergeMode {
+    /// One region covering the whole selection (default behavior)
+    #[default]
+    MergeAll,
+    /// One region per row of the selection (typical for headers)
+    MergeAcross,
+}
+
+/// What merge_cells does with values outside each region's top-left cell.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "camelCase")]
+pub enum MergeContentPolicy {
+    /// Keep the top-left value and discard the rest, like Excel (default)
+    #[default]
+    KeepTopLeft,
+    /// Refuse the merge when it would discard data, unless `force` is set
+    RefuseIfMultiple,
+}
+
+/// Optional parameters for merge_cells. Omitted = plain Merge Cells.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MergeOptions {
+    #[serde(default)]
+    pub mode: MergeMode,
+    #[serde(default)]
+    pub content_policy: MergeContentPolicy,
+    /// Merge anyway under `RefuseIfMultiple`
+    #[serde(default)]
+    pub force: bool,
+    /// Merge & Center: center the kept value horizontally
+    #[serde(default)]
+    pub center: bool,
+}
+
+/// A value dropped (or, for a refused merge, that would be dropped) by a merge.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DiscardedCell {
+    pub row: u32,
+    pub col: u32,
+    pub display: String,
+    pub formula: Option<String>,
+}
+
 /// Result of merge operations.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -597,6 +802,10 @@ pub struct MergeResult {
     pub success: bool,
     pub merged_regions: Vec<MergedRegion>,
     pub updated_cells: Vec<CellData>,
+    /// Values the merge discarded. When a `RefuseIfMultiple` merge is
+    /// refused (`success: false`), the values it would have discarded.
+    #[serde(default)]
+    pub discarded_cells: Vec<DiscardedCell>,
 }
 
 // ============================================================================
@@ -762,6 +971,10 @@ pub struct SortRangeParams {
     /// Sort orientation (rows or columns)
     #[serde(default)]
     pub orientation: SortOrientation,
+    /// Move comments, notes, hyperlinks and data validation with their rows
+    /// (row sorts only). `None` means "only when the range lies inside a table".
+    #[serde(default)]
+    pub sort_preserves_annotations: Option<bool>,
 }
 
 /// Result of sort_range command.
@@ -1040,6 +1253,32 @@ pub struct FormulaShiftResult {
     pub formulas: Vec<String>,
 }
 
+// ============================================================================
+// Row/Column Deletion
+// ============================================================================
+
+/// A formula cell whose reference lost all its cells to a row/column deletion
+/// and now evaluates to #REF!.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RefErrorCell {
+    pub sheet_index: usize,
+    pub row: u32,
+    pub col: u32,
+    /// The rewritten formula (including the "=" prefix)
+    pub formula: String,
+}
+
+/// Result of delete_rows / delete_columns.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DeleteLinesResult {
+    /// Every cell of the active sheet after the shift
+    pub updated_cells: Vec<CellData>,
+    /// Formula cells on any sheet that became #REF!, for the UI to warn about
+    pub ref_errors: Vec<RefErrorCell>,
+}
+
 /// Convert NumberFormat to a display name.
 fn format_number_format_name(format: &NumberFormat) -> String {
     match format {
@@ -1085,6 +1324,7 @@ fn format_number_format_name(format: &NumberFormat) -> String {
         }
         NumberFormat::Date { format } => format!("Date ({})", format),
         NumberFormat::Time { format } => format!("Time ({})", format),
+        NumberFormat::DateTime { format } => format!("Date & Time ({})", format),
         NumberFormat::Custom { format } => format.clone(),
     }
 }
@@ -1345,6 +1585,56 @@ pub struct EvalStepState {
     pub evaluation_result: Option<String>,
     /// Error message if something went wrong
     pub error: Option<String>,
+    /// Id of the underlined node; stable across steps within a frame
+    pub node_id: Option<String>,
+    /// Reference nodes at or directly under the underlined node, resolved
+    /// before it is evaluated
+    pub references: Vec<EvalNodeInfo>,
+    /// Already-evaluated nodes of the current frame whose result is an array
+    pub array_results: Vec<EvalNodeInfo>,
+    /// Evaluation steps taken so far in this session
+    pub steps_used: usize,
+    /// Step budget of the session
+    pub step_limit: usize,
+}
+
+/// A reference or array-valued node in the Evaluate Formula debugger.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EvalNodeInfo {
+    pub node_id: String,
+    /// The node as written (e.g., "A1:A500000")
+    pub label: String,
+    /// Resolved reference (e.g., "Sheet1!$A$1:$A$500000"); None for arrays
+    pub address: Option<String>,
+    pub preview: Option<EvalValuePreview>,
+}
+
+/// Truncated, row-major preview of an array or range value.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EvalValuePreview {
+    pub rows: usize,
+    pub cols: usize,
+    /// rows * cols
+    pub total: usize,
+    /// The first values, formatted for display
+    pub values: Vec<String>,
+    /// More values exist than are listed
+    pub truncated: bool,
+}
+
+/// One page of a node's values, requested lazily by the dialog.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EvalNodeExpansion {
+    pub node_id: String,
+    pub rows: usize,
+    pub cols: usize,
+    pub total: usize,
+    /// Row-major index of the first value in `values`
+    pub offset: usize,
+    pub values: Vec<String>,
 }
 
 // ============================================================================
@@ -1638,6 +1928,42 @@ impl Default for PageSetup {
     }
 }
 
+// ============================================================================
+// Sheet View Options
+// ============================================================================
+
+/// On-screen view preferences, stored per sheet and saved with the workbook
+/// (xlsx `<sheetView>` zoomScale, showGridLines, showRowColHeaders, rightToLeft).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SheetViewOptions {
+    /// Zoom factor (1.0 = 100%)
+    #[serde(default = "default_view_zoom")]
+    pub zoom: f64,
+    #[serde(default = "default_view_flag")]
+    pub show_gridlines: bool,
+    /// Whether row and column headings are shown
+    #[serde(default = "default_view_flag")]
+    pub show_headings: bool,
+    /// Right-to-left layout (column A at the right edge)
+    #[serde(default)]
+    pub right_to_left: bool,
+}
+
+fn default_view_zoom() -> f64 { 1.0 }
+fn default_view_flag() -> bool { true }
+
+impl Default for SheetViewOptions {
+    fn default() -> Self {
+        Self {
+            zoom: default_view_zoom(),
+            show_gridlines: true,
+            show_headings: true,
+            right_to_left: false,
+        }
+    }
+}
+
 /// Data needed to render a print preview or execute a print.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -2119,6 +2445,7 @@ pub struct LocaleSettingsData {
     pub date_format: String,
     pub currency_symbol: String,
     pub currency_position: String,
+    pub function_language: String,
 }
 
 impl From<&engine::LocaleSettings> for LocaleSettingsData {
@@ -2135,6 +2462,7 @@ impl From<&engine::LocaleSettings> for LocaleSettingsData {
                 engine::LocaleCurrencyPosition::Before => "before".to_string(),
                 engine::LocaleCurrencyPosition::After => "after".to_string(),
             },
+            function_language: locale.function_language.clone(),
         }
     }
 }
@@ -2147,6 +2475,14 @@ pub struct SupportedLocaleEntry {
     pub display_name: String,
 }
 
+/// A function-name language entry for the settings UI dropdown.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FunctionLanguageEntry {
+    pub language: String,
+    pub display_name: String,
+}
+
 // ============================================================================
 // Named Cell Styles
 // ============================================================================
@@ -2218,11 +2554,23 @@ pub struct DefaultDimensions {
     pub default_column_width: f64,
 }
 
+/// A sheet's own default column width and row height in pixels (xlsx
+/// `<sheetFormatPr>`). `None` falls back to the workbook default.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SheetDefaults {
+    #[serde(default)]
+    pub default_col_width: Option<f64>,
+    #[serde(default)]
+    pub default_row_height: Option<f64>,
+}
+
 // ============================================================================
 // Workbook Properties (document metadata)
 // ============================================================================
 
 /// Workbook-level document properties (author, title, subject, etc.).
+/// `created` and `last_modified` are maintained by save.
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct WorkbookProperties {
@@ -2236,4 +2584,101 @@ pub struct WorkbookProperties {
     pub created: String,
     /// ISO 8601 date string
     pub last_modified: String,
+    #[serde(default)]
+    pub last_modified_by: String,
+    #[serde(default)]
+    pub status: String,
+    #[serde(default)]
+    pub company: String,
+    #[serde(default)]
+    pub manager: String,
+    #[serde(default)]
+    pub hyperlink_base: String,
+    #[serde(default)]
+    pub custom: Vec<CustomProperty>,
+}
+
+/// A user-defined document property.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CustomProperty {
+    pub name: String,
+    pub value: CustomPropertyValue,
+}
+
+/// Serialized as `{ "type": "text" | "number" | "bool" | "date", "value": ... }`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", content = "value", rename_all = "camelCase")]
+pub enum CustomPropertyValue {
+    Text(String),
+    Number(f64),
+    Bool(bool),
+    /// ISO 8601 date-time string
+    Date(String),
+}
+
+impl From<&WorkbookProperties> for persistence::WorkbookProperties {
+    fn from(p: &WorkbookProperties) -> Self {
+        persistence::WorkbookProperties {
+            title: p.title.clone(),
+            author: p.author.clone(),
+            subject: p.subject.clone(),
+            description: p.description.clone(),
+            keywords: p.keywords.clone(),
+            category: p.category.clone(),
+            created: p.created.clone(),
+            last_modified: p.last_modified.clone(),
+            last_modified_by: p.last_modified_by.clone(),
+            status: p.status.clone(),
+            company: p.company.clone(),
+            manager: p.manager.clone(),
+            hyperlink_base: p.hyperlink_base.clone(),
+            custom: p
+                .custom
+                .iter()
+                .map(|c| persistence::CustomProperty {
+                    name: c.name.clone(),
+                    value: match &c.value {
+                        CustomPropertyValue::Text(s) => persistence::CustomPropertyValue::Text(s.clone()),
+                        CustomPropertyValue::Number(n) => persistence::CustomPropertyValue::Number(*n),
+                        CustomPropertyValue::Bool(b) => persistence::CustomPropertyValue::Bool(*b),
+                        CustomPropertyValue::Date(d) => persistence::CustomPropertyValue::Date(d.clone()),
+                    },
+                })
+                .collect(),
+        }
+    }
+}
+
+impl From<persistence::WorkbookProperties> for WorkbookProperties {
+    fn from(p: persistence::WorkbookProperties) -> Self {
+        WorkbookProperties {
+            title: p.title,
+            author: p.author,
+            subject: p.subject,
+            description: p.description,
+            keywords: p.keywords,
+            category: p.category,
+            created: p.created,
+            last_modified: p.last_modified,
+            last_modified_by: p.last_modified_by,
+            status: p.status,
+            company: p.company,
+            manager: p.manager,
+            hyperlink_base: p.hyperlink_base,
+            custom: p
+                .custom
+                .into_iter()
+                .map(|c| CustomProperty {
+                    name: c.name,
+                    value: match c.value {
+                        persistence::CustomPropertyValue::Text(s) => CustomPropertyValue::Text(s),
+                        persistence::CustomPropertyValue::Number(n) => CustomPropertyValue::Number(n),
+                        persistence::CustomPropertyValue::Bool(b) => CustomPropertyValue::Bool(b),
+                        persistence::CustomPropertyValue::Date(d) => CustomPropertyValue::Date(d),
+                    },
+                })
+                .collect(),
+        }
+    }
 }
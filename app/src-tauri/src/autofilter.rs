@@ -386,6 +386,9 @@ pub struct ApplyAutoFilterParams {
     pub criteria: Option<FilterCriteria>,
 }
 
+/// Upper bound on distinct values returned to the filter dropdown.
+pub const MAX_UNIQUE_VALUES: usize = 10_000;
+
 /// Result of getting unique values for a column.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -393,6 +396,10 @@ pub struct UniqueValuesResult {
     pub success: bool,
     pub values: Vec<UniqueValue>,
     pub has_blanks: bool,
+    /// Distinct values left out of `values` once MAX_UNIQUE_VALUES is reached
+    /// (shown as "and N more" in the dropdown).
+    #[serde(default)]
+    pub more_count: u32,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub error: Option<String>,
 }
@@ -517,23 +524,13 @@ fn wildcard_match(value: &str, pattern: &str) -> bool {
 
 /// Calculate average of numeric values in a column.
 fn calculate_column_average(grid: &Grid, col: u32, start_row: u32, end_row: u32) -> Option<f64> {
-    let mut sum = 0.0;
-    let mut count = 0;
-
-    for row in start_row..=end_row {
-        if let Some(cell) = grid.cells.get(&(row, col)) {
-            if let CellValue::Number(n) = cell.value {
-                sum += n;
-                count += 1;
-            }
+    let mut stats = crate::commands::analysis::NumericStats::default();
+    crate::commands::analysis::for_each_cell_in(grid, start_row, col, end_row, col, |_, _, cell| {
+        if let CellValue::Number(n) = cell.value {
+            stats.add(n);
         }
-    }
-
-    if count > 0 {
-        Some(sum / count as f64)
-    } else {
-        None
-    }
+    });
+    stats.average()
 }
 
 /// Convert an Excel date serial number to a chrono NaiveDate.
@@ -941,6 +938,43 @@ fn recompute_hidden_rows(
     auto_filter.hidden_rows = hidden;
 }
 
+/// Every row of `sheet` hidden by the AutoFilter, the Advanced Filter or a
+/// collapsed outline group - the set SUBTOTAL/AGGREGATE 101-series codes skip.
+/// Takes the filter and outline locks briefly; call it BEFORE locking grids.
+pub(crate) fn sheet_hidden_rows(state: &AppState, sheet: usize) -> HashSet<u32> {
+    let mut hidden: HashSet<u32> = HashSet::new();
+    if let Some(af) = state.auto_filters.lock().unwrap().get(&sheet) {
+        hidden.extend(af.hidden_rows.iter());
+    }
+    if let Some(rows) = state.advanced_filter_hidden_rows.lock().unwrap().get(&sheet) {
+        hidden.extend(rows.iter());
+    }
+    if let Some(outline) = state.outlines.lock().unwrap().get(&sheet) {
+        hidden.extend(outline.get_hidden_rows());
+    }
+    hidden
+}
+
+/// After the filtered rows of `sheet` changed, re-evaluate its table totals
+/// rows and SUBTOTAL/AGGREGATE cells and push the new values to the frontend.
+/// Call with no locks held.
+pub(crate) fn refresh_filtered_totals(state: &AppState, sheet: usize) {
+    let mut changes = crate::change_events::ChangeBatch::new();
+    for cell in crate::tables::recalculate_subtotal_cells(state, sheet) {
+        changes.push_cell(sheet, cell);
+    }
+    changes.flush();
+}
+
+/// Pass a filter command's result through, re-evaluating the active sheet's
+/// table totals once the command has released its locks.
+fn with_refreshed_totals(state: &AppState, result: AutoFilterResult) -> AutoFilterResult {
+    if result.success {
+        refresh_filtered_totals(state, *state.active_sheet.lock().unwrap());
+    }
+    result
+}
+
 // ============================================================================
 // TAURI COMMANDS
 // ============================================================================
@@ -950,6 +984,13 @@ fn recompute_hidden_rows(
 pub fn apply_auto_filter(
     state: State<AppState>,
     params: ApplyAutoFilterParams,
+) -> AutoFilterResult {
+    with_refreshed_totals(&state, apply_auto_filter_internal(&state, params))
+}
+
+pub(crate) fn apply_auto_filter_internal(
+    state: &AppState,
+    params: ApplyAutoFilterParams,
 ) -> AutoFilterResult {
     let active_sheet = *state.active_sheet.lock().unwrap();
     let mut auto_filters = state.auto_filters.lock().unwrap();
@@ -1017,6 +1058,13 @@ pub fn apply_auto_filter(
 pub fn clear_column_criteria(
     state: State<AppState>,
     column_index: u32,
+) -> AutoFilterResult {
+    with_refreshed_totals(&state, clear_column_criteria_internal(&state, column_index))
+}
+
+pub(crate) fn clear_column_criteria_internal(
+    state: &AppState,
+    column_index: u32,
 ) -> AutoFilterResult {
     let active_sheet = *state.active_sheet.lock().unwrap();
     let mut auto_filters = state.auto_filters.lock().unwrap();
@@ -1064,6 +1112,12 @@ pub fn clear_column_criteria(
 #[tauri::command]
 pub fn clear_auto_filter_criteria(
     state: State<AppState>,
+) -> AutoFilterResult {
+    with_refreshed_totals(&state, clear_auto_filter_criteria_internal(&state))
+}
+
+pub(crate) fn clear_auto_filter_criteria_internal(
+    state: &AppState,
 ) -> AutoFilterResult {
     let active_sheet = *state.active_sheet.lock().unwrap();
     let mut auto_filters = state.auto_filters.lock().unwrap();
@@ -1100,6 +1154,12 @@ pub fn clear_auto_filter_criteria(
 #[tauri::command]
 pub fn reapply_auto_filter(
     state: State<AppState>,
+) -> AutoFilterResult {
+    with_refreshed_totals(&state, reapply_auto_filter_internal(&state))
+}
+
+pub(crate) fn reapply_auto_filter_internal(
+    state: &AppState,
 ) -> AutoFilterResult {
     let active_sheet = *state.active_sheet.lock().unwrap();
     let mut auto_filters = state.auto_filters.lock().unwrap();
@@ -1146,6 +1206,12 @@ pub fn reapply_auto_filter(
 #[tauri::command]
 pub fn remove_auto_filter(
     state: State<AppState>,
+) -> AutoFilterResult {
+    with_refreshed_totals(&state, remove_auto_filter_internal(&state))
+}
+
+pub(crate) fn remove_auto_filter_internal(
+    state: &AppState,
 ) -> AutoFilterResult {
     let active_sheet = *state.active_sheet.lock().unwrap();
     let mut auto_filters = state.auto_filters.lock().unwrap();
@@ -1230,12 +1296,15 @@ pub fn set_advanced_filter_hidden_rows(
     rows: Vec<u32>,
 ) {
     let active_sheet = *state.active_sheet.lock().unwrap();
-    let mut adv_hidden = state.advanced_filter_hidden_rows.lock().unwrap();
-    if rows.is_empty() {
-        adv_hidden.remove(&active_sheet);
-    } else {
-        adv_hidden.insert(active_sheet, rows);
+    {
+        let mut adv_hidden = state.advanced_filter_hidden_rows.lock().unwrap();
+        if rows.is_empty() {
+            adv_hidden.remove(&active_sheet);
+        } else {
+            adv_hidden.insert(active_sheet, rows);
+        }
     }
+    refresh_filtered_totals(&state, active_sheet);
 }
 
 /// Clear advanced filter hidden rows for the active sheet.
@@ -1244,8 +1313,8 @@ pub fn clear_advanced_filter_hidden_rows(
     state: State<AppState>,
 ) {
     let active_sheet = *state.active_sheet.lock().unwrap();
-    let mut adv_hidden = state.advanced_filter_hidden_rows.lock().unwrap();
-    adv_hidden.remove(&active_sheet);
+    state.advanced_filter_hidden_rows.lock().unwrap().remove(&active_sheet);
+    refresh_filtered_totals(&state, active_sheet);
 }
 
 /// Check if a specific row is hidden by the AutoFilter.
@@ -1263,59 +1332,83 @@ pub fn is_row_filtered(
 }
 
 /// Get unique values for a column in the AutoFilter range.
+///
+/// Counts come from one pass over the rows that stay visible under every
+/// other column's filter and the Advanced Filter; the column's own filter is
+/// ignored so unchecked values can be re-selected. An optional `search`
+/// narrows the list to values containing it (case-insensitive). At most
+/// MAX_UNIQUE_VALUES entries are returned; `more_count` reports the rest.
 #[tauri::command]
 pub fn get_filter_unique_values(
     state: State<AppState>,
     column_index: u32,
+    search: Option<String>,
+) -> UniqueValuesResult {
+    get_filter_unique_values_internal(&state, column_index, search.as_deref())
+}
+
+pub(crate) fn get_filter_unique_values_internal(
+    state: &AppState,
+    column_index: u32,
+    search: Option<&str>,
 ) -> UniqueValuesResult {
+    let fail = |msg: &str| UniqueValuesResult {
+        success: false,
+        values: Vec::new(),
+        has_blanks: false,
+        more_count: 0,
+        error: Some(msg.to_string()),
+    };
+
     let active_sheet = *state.active_sheet.lock().unwrap();
+    let adv_hidden: HashSet<u32> = state
+        .advanced_filter_hidden_rows
+        .lock()
+        .unwrap()
+        .get(&active_sheet)
+        .map(|rows| rows.iter().copied().collect())
+        .unwrap_or_default();
     let auto_filters = state.auto_filters.lock().unwrap();
     let grids = state.grids.lock().unwrap();
     let style_registry = state.style_registry.lock().unwrap();
     let locale = state.locale.lock().unwrap();
-    let _theme = state.theme.lock().unwrap();
+    let theme = state.theme.lock().unwrap();
 
     let auto_filter = match auto_filters.get(&active_sheet) {
         Some(af) => af,
-        None => {
-            return UniqueValuesResult {
-                success: false,
-                values: Vec::new(),
-                has_blanks: false,
-                error: Some("No AutoFilter exists for this sheet".to_string()),
-            };
-        }
+        None => return fail("No AutoFilter exists for this sheet"),
     };
 
     if active_sheet >= grids.len() {
-        return UniqueValuesResult {
-            success: false,
-            values: Vec::new(),
-            has_blanks: false,
-            error: Some("Invalid sheet index".to_string()),
-        };
+        return fail("Invalid sheet index");
     }
 
     let abs_col = auto_filter.start_col + column_index;
     if abs_col > auto_filter.end_col {
-        return UniqueValuesResult {
-            success: false,
-            values: Vec::new(),
-            has_blanks: false,
-            error: Some("Column index out of range".to_string()),
-        };
+        return fail("Column index out of range");
     }
 
     let grid = &grids[active_sheet];
+
+    // Rows hidden by the other columns' filters (cascading counts).
+    let mut others = auto_filter.clone();
+    if others.column_filters.remove(&column_index).is_some() {
+        recompute_hidden_rows(grid, &style_registry, &theme, &mut others, &locale);
+    }
+
+    let needle = search.map(str::trim).filter(|s| !s.is_empty()).map(str::to_lowercase);
     let mut value_counts: HashMap<String, u32> = HashMap::new();
     let mut has_blanks = false;
 
-    // Skip header row, collect values from data rows
+    // Skip header row, collect values from visible data rows
     for row in (auto_filter.start_row + 1)..=auto_filter.end_row {
+        if others.hidden_rows.contains(&row) || adv_hidden.contains(&row) {
+            continue;
+        }
         let value = get_cell_filter_value(grid, row, abs_col, &style_registry, &locale);
         if value.is_empty() {
-            has_blanks = true;
-        } else {
+            has_blanks |= needle.is_none();
+        } else if needle.as_ref().is_none_or(|n| value.to_lowercase().contains(n.as_str())) {
             *value_counts.entry(value).or_insert(0) += 1;
         }
     }
@@ -1328,10 +1421,14 @@ pub fn get_filter_unique_values(
     // Sort by value
     values.sort_by(|a, b| a.value.cmp(&b.value));
 
+    let more_count = values.len().saturating_sub(MAX_UNIQUE_VALUES) as u32;
+    values.truncate(MAX_UNIQUE_VALUES);
+
     UniqueValuesResult {
         success: true,
         values,
         has_blanks,
+        more_count,
         error: None,
     }
 }
@@ -1343,6 +1440,16 @@ pub fn set_column_filter_values(
     column_index: u32,
     values: Vec<String>,
     include_blanks: bool,
+) -> AutoFilterResult {
+    let result = set_column_filter_values_internal(&state, column_index, values, include_blanks);
+    with_refreshed_totals(&state, result)
+}
+
+pub(crate) fn set_column_filter_values_internal(
+    state: &AppState,
+    column_index: u32,
+    values: Vec<String>,
+    include_blanks: bool,
 ) -> AutoFilterResult {
     let active_sheet = *state.active_sheet.lock().unwrap();
     let mut auto_filters = state.auto_filters.lock().unwrap();
@@ -1410,6 +1517,17 @@ pub fn set_column_custom_filter(
     criterion1: String,
     criterion2: Option<String>,
     operator: Option<FilterOperator>,
+) -> AutoFilterResult {
+    let result = set_column_custom_filter_internal(&state, column_index, criterion1, criterion2, operator);
+    with_refreshed_totals(&state, result)
+}
+
+pub(crate) fn set_column_custom_filter_internal(
+    state: &AppState,
+    column_index: u32,
+    criterion1: String,
+    criterion2: Option<String>,
+    operator: Option<FilterOperator>,
 ) -> AutoFilterResult {
     let active_sheet = *state.active_sheet.lock().unwrap();
     let mut auto_filters = state.auto_filters.lock().unwrap();
@@ -1472,6 +1590,16 @@ pub fn set_column_top_bottom_filter(
     column_index: u32,
     filter_on: FilterOn,
     value: u32,
+) -> AutoFilterResult {
+    let result = set_column_top_bottom_filter_internal(&state, column_index, filter_on, value);
+    with_refreshed_totals(&state, result)
+}
+
+pub(crate) fn set_column_top_bottom_filter_internal(
+    state: &AppState,
+    column_index: u32,
+    filter_on: FilterOn,
+    value: u32,
 ) -> AutoFilterResult {
     let active_sheet = *state.active_sheet.lock().unwrap();
     let mut auto_filters = state.auto_filters.lock().unwrap();
@@ -1864,6 +1992,7 @@ pub fn run_advanced_filter(
                     adv_hidden.insert(active_sheet, hidden_rows.clone());
                 }
             }
+            refresh_filtered_totals(&state, active_sheet);
             AdvancedFilterResult {
                 success: true,
                 match_count: matched_rows.len() as u32,
@@ -2097,6 +2226,15 @@ pub fn set_column_dynamic_filter(
     state: State<AppState>,
     column_index: u32,
     dynamic_criteria: DynamicFilterCriteria,
+) -> AutoFilterResult {
+    let result = set_column_dynamic_filter_internal(&state, column_index, dynamic_criteria);
+    with_refreshed_totals(&state, result)
+}
+
+pub(crate) fn set_column_dynamic_filter_internal(
+    state: &AppState,
+    column_index: u32,
+    dynamic_criteria: DynamicFilterCriteria,
 ) -> AutoFilterResult {
     let active_sheet = *state.active_sheet.lock().unwrap();
     let mut auto_filters = state.auto_filters.lock().unwrap();
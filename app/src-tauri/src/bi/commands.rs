@@ -2815,30 +2815,13 @@ pub async fn bi_insert_result(
         }
     }
 
-    // Sync to active grid if this is the active sheet
-    {
-        let active_sheet = *state.active_sheet.lock().unwrap();
-        if request.sheet_index == active_sheet {
-            let grids = state.grids.lock().unwrap();
-            if let Some(src_grid) = grids.get(request.sheet_index) {
-                let mut active_grid = state.grid.lock().unwrap();
-                for ((r, c), cell) in src_grid.cells.iter() {
-                    if *r >= start_row && *r <= end_row && *c >= start_col && *c <= end_col {
-                        active_grid.set_cell(*r, *c, cell.clone());
-                    }
-                }
-            }
-        }
-    }
-
     // Generate region ID
     let region_id = identity::EntityId::from_bytes(identity::generate_uuid_v7());
 
     // Create protected region
     {
         let mut regions = state.protected_regions.lock().unwrap();
-        regions.retain(|r| !(r.region_type == "bi" && r.owner_id == region_id));
-        regions.push(ProtectedRegion {
+        regions.upsert(ProtectedRegion {
             id: format!("bi-{}", region_id),
             region_type: "bi".to_string(),
             owner_id: region_id,
@@ -3040,31 +3023,6 @@ pub async fn bi_refresh_connection(
             }
         }
 
-        // Sync to active grid
-        {
-            let active_sheet = *state.active_sheet.lock().unwrap();
-            if active_query.sheet_index == active_sheet {
-                let grids = state.grids.lock().unwrap();
-                if let Some(src_grid) = grids.get(active_query.sheet_index) {
-                    let mut active_grid = state.grid.lock().unwrap();
-                    for r in active_query.start_row..=active_query.end_row {
-                        for c in active_query.start_col..=active_query.end_col {
-                            active_grid.set_cell(r, c, Cell::new());
-                        }
-                    }
-                    let write_end_row = std::cmp::max(active_query.end_row, new_end_row);
-                    let write_end_col = std::cmp::max(active_query.end_col, new_end_col);
-                    for r in start_row..=write_end_row {
-                        for c in start_col..=write_end_col {
-                            if let Some(cell) = src_grid.cells.get(&(r, c)) {
-                                active_grid.set_cell(r, c, cell.clone());
-                            }
-                        }
-                    }
-                }
-            }
-        }
-
         // Update protected region bounds
         {
             let mut regions = state.protected_regions.lock().unwrap();
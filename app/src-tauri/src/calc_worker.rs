@@ -0,0 +1,455 @@
+//! FILENAME: app/src-tauri/src/calc_worker.rs
+//! PURPOSE: Recalculation split into snapshot, compute and apply phases, so a
+//! long pass runs without holding the grid locks, reports progress and can be
+//! cancelled.
+//! CONTEXT: `snapshot_sheet` copies everything a sheet's formulas read in one
+//! short lock window. `compute` evaluates against that copy with no AppState
+//! lock held (GETPIVOTDATA locks the pivot store per call). `apply` writes the
+//! results back in a second short window, skipping every result that an edit
+//! made during the pass has invalidated; those cells join the dirty set.
+//! The engine Grid has no copy-on-write row sharing, so the snapshot clones
+//! the grids outright - still far cheaper than evaluating under the lock.
+
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+
+use rustc_hash::{FxHashMap, FxHashSet};
+use serde::Serialize;
+
+use crate::calculation::{cell_value_as_f64, evaluate_single_formula, partition_formula_cells, stored_result, CalculationSettings, IterationSettings};
+use crate::control_values::ControlValuesMap;
+use crate::persistence::UserFilesState;
+use crate::pivot::types::PivotState;
+use crate::{log_info, log_warn, AppState, CoordSet, DependencyMap, ErrorDetailMap, ExtractedRefs};
+
+/// Event carrying a `CalcProgress` while a background calculation runs.
+pub const CALC_PROGRESS_EVENT: &str = "calc-progress";
+
+/// Formula cells evaluated between two progress events.
+pub const PROGRESS_EVENT_INTERVAL: usize = 1000;
+
+/// Error returned by a calculation that `cancel_calculation` stopped.
+pub const CALCULATION_CANCELLED: &str = "Calculation cancelled";
+
+/// How far a calculation pass has got.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CalcProgress {
+    pub evaluated: usize,
+    pub total: usize,
+}
+
+/// The background calculation currently running, if any.
+#[derive(Default)]
+pub struct CalcWorkerState {
+    running: Mutex<Option<Arc<AtomicBool>>>,
+}
+
+impl CalcWorkerState {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register a new pass and return its cancellation flag. A pass still
+    /// running is cancelled: the new one supersedes it.
+    pub fn begin(&self) -> Arc<AtomicBool> {
+        let token = Arc::new(AtomicBool::new(false));
+        if let Some(previous) = self.running.lock().unwrap().replace(token.clone()) {
+            previous.store(true, Ordering::SeqCst);
+        }
+        token
+    }
+
+    /// Unregister the pass owning `token` (a superseding pass keeps its own).
+    pub fn finish(&self, token: &Arc<AtomicBool>) {
+        let mut running = self.running.lock().unwrap();
+        if running.as_ref().is_some_and(|current| Arc::ptr_eq(current, token)) {
+            *running = None;
+        }
+    }
+
+    /// Ask the running pass to stop. Returns false when none is running.
+    pub fn cancel(&self) -> bool {
+        match self.running.lock().unwrap().as_ref() {
+            Some(token) => {
+                token.store(true, Ordering::SeqCst);
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.running.lock().unwrap().is_some()
+    }
+}
+
+/// Everything the formulas of one sheet read, copied out of AppState.
+pub(crate) struct SheetSnapshot {
+    pub sheet_index: usize,
+    grids: Vec<engine::Grid>,
+    sheet_names: Vec<String>,
+    styles: engine::StyleRegistry,
+    user_files: HashMap<String, Vec<u8>>,
+    tables: crate::tables::TableStorage,
+    table_names: crate::tables::TableNameRegistry,
+    named_ranges: HashMap<String, crate::named_ranges::NamedRange>,
+    row_heights: HashMap<u32, f64>,
+    column_widths: HashMap<u32, f64>,
+    hidden_rows: HashSet<u32>,
+    gather_data: HashMap<String, engine::GatherRegionData>,
+    control_values: Option<Arc<ControlValuesMap>>,
+    cube: Option<Arc<engine::CubePrefetch>>,
+    settings: CalculationSettings,
+}
+
+/// Copy what the formulas of `sheet_index` read. Filtered rows and writeback
+/// data are gathered before the grid lock (canonical lock order), which is
+/// then held only for the clone. None when the sheet does not exist or has no
+/// formulas.
+pub(crate) fn snapshot_sheet(
+    state: &AppState,
+    user_files_state: &UserFilesState,
+    sheet_index: usize,
+    control_values: Option<Arc<ControlValuesMap>>,
+    cube: Option<Arc<engine::CubePrefetch>>,
+) -> Option<SheetSnapshot> {
+    let hidden_rows = crate::autofilter::sheet_hidden_rows(state, sheet_index);
+    let gather_data = crate::calp_commands::build_gather_data(state);
+    let active_sheet = *state.active_sheet.lock().unwrap();
+
+    let grids = state.grids.lock().unwrap();
+    let has_formulas = grids
+        .get(sheet_index)
+        .is_some_and(|grid| grid.cells.values().any(|cell| cell.ast.is_some()));
+    if !has_formulas {
+        return None;
+    }
+    let sheet_names = state.sheet_names.lock().unwrap();
+    let styles = state.style_registry.lock().unwrap();
+    let user_files = user_files_state.files.lock().unwrap();
+    let settings = state.calculation_settings.lock().unwrap().clone();
+    let tables = state.tables.lock().unwrap();
+    let table_names = state.table_names.lock().unwrap();
+    let named_ranges = state.named_ranges.lock().unwrap();
+    // The active sheet's dimensions live in the swapped-in maps.
+    let (row_heights, column_widths) = if sheet_index == active_sheet {
+        (state.row_heights.lock().unwrap().clone(), state.column_widths.lock().unwrap().clone())
+    } else {
+        (
+            state.all_row_heights.lock().unwrap().get(sheet_index).cloned().unwrap_or_default(),
+            state.all_column_widths.lock().unwrap().get(sheet_index).cloned().unwrap_or_default(),
+        )
+    };
+
+    Some(SheetSnapshot {
+        sheet_index,
+        grids: grids.clone(),
+        sheet_names: sheet_names.clone(),
+        styles: styles.clone(),
+        user_files: user_files.clone(),
+        tables: tables.clone(),
+        table_names: table_names.clone(),
+        named_ranges: named_ranges.clone(),
+        row_heights,
+        column_widths,
+        hidden_rows,
+        gather_data,
+        control_values,
+        cube,
+        settings,
+    })
+}
+
+/// Values computed by one pass over a snapshot.
+pub(crate) struct SheetResults {
+    /// Evaluation units in order: a single cell, or a circular group that was
+    /// iterated together.
+    units: Vec<Vec<(u32, u32)>>,
+    /// What each formula cell reads, for invalidation at apply time.
+    refs: FxHashMap<(u32, u32), ExtractedRefs>,
+    values: FxHashMap<(u32, u32), engine::CellValue>,
+    error_details: ErrorDetailMap,
+    /// UI side-effects of the cells that requested any (see
+    /// `crate::process_ui_effects`).
+    ui_effects: FxHashMap<(u32, u32), Vec<engine::UiEffect>>,
+}
+
+impl SheetResults {
+    /// Formula cells whose result an edit made during the pass may have
+    /// changed: the edited cells plus everything downstream of them, walked
+    /// in evaluation order. `edited_elsewhere` holds (SHEET NAME, row, col).
+    fn invalidated_by(&self, edited_here: &CoordSet, edited_elsewhere: &HashSet<(String, u32, u32)>) -> CoordSet {
+        let mut affected = edited_here.clone();
+        if affected.is_empty() && edited_elsewhere.is_empty() {
+            return affected;
+        }
+        let mut rows: FxHashSet<u32> = affected.iter().map(|&(row, _)| row).collect();
+        let mut cols: FxHashSet<u32> = affected.iter().map(|&(_, col)| col).collect();
+        for unit in &self.units {
+            let hit = unit.iter().any(|pos| {
+                affected.contains(pos)
+                    || self.refs.get(pos).is_some_and(|refs| {
+                        refs.cells.iter().any(|cell| affected.contains(cell))
+                            || refs.rows.iter().any(|row| rows.contains(row))
+                            || refs.columns.iter().any(|col| cols.contains(col))
+                            || (!edited_elsewhere.is_empty() && !refs.sheet_3d_cells.is_empty())
+                            || refs.cross_sheet_cells.iter().any(|(sheet, row, col)| {
+                                edited_elsewhere.contains(&(sheet.to_uppercase(), *row, *col))
+                            })
+                    })
+            });
+            if hit {
+                for &(row, col) in unit {
+                    affected.insert((row, col));
+                    rows.insert(row);
+                    cols.insert(col);
+                }
+            }
+        }
+        affected
+    }
+}
+
+/// Evaluate every formula on the snapshot's sheet, writing results into the
+/// snapshot so later formulas see them. Calls `on_progress` after each cell
+/// and returns None as soon as `cancel` is set.
+pub(crate) fn compute(
+    snapshot: &mut SheetSnapshot,
+    pivot_state: &PivotState,
+    cancel: &AtomicBool,
+    on_progress: &mut dyn FnMut(CalcProgress),
+) -> Option<SheetResults> {
+    // PERF-03: one lookup-index cache for the whole pass (lookup_cache.rs).
+    let _lookup_pass = engine::begin_lookup_pass();
+    let SheetSnapshot {
+        sheet_index: sheet,
+        ref mut grids,
+        ref sheet_names,
+        ref styles,
+        ref user_files,
+        ref tables,
+        ref table_names,
+        ref named_ranges,
+        ref row_heights,
+        ref column_widths,
+        ref hidden_rows,
+        ref gather_data,
+        ref control_values,
+        ref cube,
+        ref settings,
+    } = *snapshot;
+    let IterationSettings { enabled: iteration_enabled, max_iterations, max_change } = settings.iterative.clone();
+    let precision_as_displayed = settings.precision_as_displayed;
+
+    let formula_cells: Vec<_> = grids[sheet]
+        .cells
+        .iter()
+        .filter_map(|(&(row, col), cell)| cell.formula_string().map(|f| (row, col, f)))
+        .collect();
+
+    // Same-sheet dependency map for evaluation ordering.
+    let mut refs = FxHashMap::default();
+    let mut local_deps = DependencyMap::default();
+    for (row, col, _f) in &formula_cells {
+        if let Some(ast) = grids[sheet].get_cell(*row, *col).and_then(|cell| cell.ast.as_ref()) {
+            let cell_refs = crate::extract_all_references(ast, &grids[sheet]);
+            if !cell_refs.cells.is_empty() {
+                local_deps.insert((*row, *col), cell_refs.cells.clone());
+            }
+            refs.insert((*row, *col), cell_refs);
+        }
+    }
+    let (non_circular, circular_groups) = partition_formula_cells(&formula_cells, &local_deps);
+
+    // The pivot store is locked per lookup, never for the whole pass.
+    let pivot_data_fn = |data_field: &str, pivot_row: u32, pivot_col: u32, pairs: &[(&str, &str)]| -> Option<f64> {
+        let pivot_tables = pivot_state.pivot_tables.lock().unwrap();
+        let pivot_views = pivot_state.views.lock().unwrap();
+        crate::pivot::operations::lookup_pivot_data(&pivot_tables, &pivot_views, data_field, pivot_row, pivot_col, pairs)
+    };
+    let gather_fn = |region_id: &str| -> engine::GatherRegionData {
+        gather_data.get(region_id).cloned().unwrap_or_default()
+    };
+    let evaluate = |grids: &[engine::Grid], row: u32, col: u32, formula: &str| {
+        evaluate_single_formula(
+            row, col, formula,
+            grids, sheet_names, sheet,
+            styles, user_files, &pivot_data_fn, &gather_fn,
+            tables, table_names, named_ranges,
+            row_heights, column_widths, hidden_rows,
+            cube.as_ref(),
+            control_values.as_ref(),
+        )
+    };
+    let store = |grid: &mut engine::Grid, row: u32, col: u32, value: engine::CellValue| {
+        if let Some(cell) = grid.get_cell(row, col) {
+            let mut updated = cell.clone();
+            updated.value = value;
+            grid.set_cell(row, col, updated);
+        }
+    };
+
+    let total = formula_cells.len();
+    let mut evaluated = 0;
+    let mut units = Vec::with_capacity(non_circular.len() + circular_groups.len());
+    let mut error_details = ErrorDetailMap::default();
+    let mut ui_effects = FxHashMap::default();
+    let mut record_ui_effects = |row: u32, col: u32| {
+        let effects = engine::take_ui_effects();
+        if effects.is_empty() {
+            ui_effects.remove(&(row, col));
+        } else {
+            ui_effects.insert((row, col), effects);
+        }
+    };
+
+    for (row, col, formula) in &non_circular {
+        if cancel.load(Ordering::SeqCst) {
+            return None;
+        }
+        let result = evaluate(grids, *row, *col, formula);
+        crate::record_error_detail(&mut error_details, (sheet, *row, *col), &result);
+        record_ui_effects(*row, *col);
+        let style_index = grids[sheet].get_cell(*row, *col).map_or(0, |c| c.style_index);
+        store(&mut grids[sheet], *row, *col, stored_result(result, styles.get(style_index), precision_as_displayed));
+        units.push(vec![(*row, *col)]);
+        evaluated += 1;
+        on_progress(CalcProgress { evaluated, total });
+    }
+
+    for group in &circular_groups {
+        if cancel.load(Ordering::SeqCst) {
+            return None;
+        }
+        if !iteration_enabled {
+            // Iteration disabled: every cell in the circular group is #CIRC!
+            for (row, col, _formula) in group {
+                store(&mut grids[sheet], *row, *col, engine::CellValue::Error(engine::CellError::Circular));
+            }
+        } else {
+            log_info!("CALC", "Iterating circular group of {} cells (max_iterations={}, max_change={})",
+                group.len(), max_iterations, max_change);
+
+            for iteration in 0..max_iterations {
+                if cancel.load(Ordering::SeqCst) {
+                    return None;
+                }
+                let mut max_delta: f64 = 0.0;
+                for (row, col, formula) in group {
+                    let old_value = grids[sheet].get_cell(*row, *col)
+                        .map(|c| cell_value_as_f64(&c.value))
+                        .unwrap_or(0.0);
+                    let new_result = evaluate(grids, *row, *col, formula);
+                    crate::record_error_detail(&mut error_details, (sheet, *row, *col), &new_result);
+                    record_ui_effects(*row, *col);
+                    let style_index = grids[sheet].get_cell(*row, *col).map_or(0, |c| c.style_index);
+                    let new_result = stored_result(new_result, styles.get(style_index), precision_as_displayed);
+                    let delta = (cell_value_as_f64(&new_result) - old_value).abs();
+                    store(&mut grids[sheet], *row, *col, new_result);
+                    max_delta = max_delta.max(delta);
+                }
+                if max_delta < max_change {
+                    log_info!("CALC", "Circular group converged after {} iterations (max_delta={})",
+                        iteration + 1, max_delta);
+                    break;
+                }
+            }
+        }
+        units.push(group.iter().map(|(row, col, _)| (*row, *col)).collect());
+        evaluated += group.len();
+        on_progress(CalcProgress { evaluated, total });
+    }
+
+    let values = units
+        .iter()
+        .flatten()
+        .filter_map(|&pos| grids[sheet].cells.get(&pos).map(|cell| (pos, cell.value.clone())))
+        .collect();
+    Some(SheetResults { units, refs, values, error_details, ui_effects })
+}
+
+/// Outcome of writing a pass's results back.
+#[derive(Debug, Default)]
+pub(crate) struct AppliedResults {
+    /// Formula cells that received their new value.
+    pub written: Vec<(u32, u32)>,
+    /// Formula cells left as they were because an edit made during the pass
+    /// changed their inputs; they belong in the dirty set.
+    pub invalidated: CoordSet,
+}
+
+/// Cells of `live` that differ from their snapshot copy `before`. With
+/// `recomputed`, formula cells compare by formula only: their snapshot values
+/// are the pass's own results.
+fn edited_cells(live: &engine::Grid, before: &engine::Grid, recomputed: bool) -> CoordSet {
+    let mut edited: CoordSet = live
+        .cells
+        .iter()
+        .filter(|(pos, cell)| match before.cells.get(*pos) {
+            None => true,
+            Some(old) if recomputed && old.ast.is_some() => cell.ast != old.ast,
+            Some(old) => cell.ast != old.ast || cell.value != old.value,
+        })
+        .map(|(&pos, _)| pos)
+        .collect();
+    edited.extend(before.cells.keys().filter(|pos| !live.cells.contains_key(*pos)));
+    edited
+}
+
+/// Write the results of `compute` into AppState, except those invalidated
+/// by edits that arrived while it ran. Holds the grid lock only for the
+/// comparison and the writes.
+pub(crate) fn apply(state: &AppState, snapshot: &SheetSnapshot, results: SheetResults) -> AppliedResults {
+    let sheet = snapshot.sheet_index;
+    let mut grids = state.grids.lock().unwrap();
+    let sheet_names = state.sheet_names.lock().unwrap();
+    if *sheet_names != snapshot.sheet_names || grids.len() != snapshot.grids.len() {
+        // Sheets were added, removed or renamed meanwhile, so the results may
+        // belong to a different sheet now. The structural change recalculates
+        // the sheets it touched itself.
+        log_warn!("CALC", "sheet structure changed during recalculation of sheet {}; results dropped", sheet);
+        return AppliedResults::default();
+    }
+
+    let edited_here = edited_cells(&grids[sheet], &snapshot.grids[sheet], true);
+    let mut edited_elsewhere = HashSet::new();
+    for (index, (live, before)) in grids.iter().zip(&snapshot.grids).enumerate() {
+        if index != sheet {
+            let name = sheet_names[index].to_uppercase();
+            edited_elsewhere.extend(
+                edited_cells(live, before, false).into_iter().map(|(row, col)| (name.clone(), row, col)),
+            );
+        }
+    }
+    let invalidated = results.invalidated_by(&edited_here, &edited_elsewhere);
+
+    let mut error_details = state.error_details.lock().unwrap();
+    let mut written = Vec::with_capacity(results.values.len());
+    for &(row, col) in results.units.iter().flatten() {
+        if invalidated.contains(&(row, col)) {
+            continue;
+        }
+        let (Some(value), Some(cell)) = (results.values.get(&(row, col)), grids[sheet].get_cell(row, col)) else {
+            continue;
+        };
+        let mut updated = cell.clone();
+        updated.value = value.clone();
+        grids[sheet].set_cell(row, col, updated);
+        match results.error_details.get(&(sheet, row, col)) {
+            Some(detail) => error_details.insert((sheet, row, col), detail.clone()),
+            None => error_details.remove(&(sheet, row, col)),
+        };
+        let effects = results.ui_effects.get(&(row, col)).cloned().unwrap_or_default();
+        crate::process_ui_effects(state, sheet, row, col, effects);
+        written.push((row, col));
+    }
+
+    // Only cells that still hold a formula can be dirty.
+    let invalidated = invalidated
+        .into_iter()
+        .filter(|&(row, col)| grids[sheet].get_cell(row, col).is_some_and(|cell| cell.ast.is_some()))
+        .collect();
+    AppliedResults { written, invalidated }
+}
@@ -1,9 +1,11 @@
 //! FILENAME: app/src-tauri/src/calculation.rs
-// PURPOSE: Calculation mode commands for manual/automatic recalculation.
+// PURPOSE: Calculation settings (mode, iteration, precision) and recalculation commands.
 
 use serde::{Serialize, Deserialize};
 use tauri::State;
+use std::sync::atomic::AtomicBool;
 use crate::{AppState, evaluate_formula_with_pivot, format_cell_value};
+use crate::calc_worker::{self, CalcProgress, CalcWorkerState, CALC_PROGRESS_EVENT, PROGRESS_EVENT_INTERVAL};
 use crate::api_types::CellData;
 use crate::{log_enter, log_exit, log_enter_info, log_exit_info, log_warn, log_info};
 use crate::persistence::UserFilesState;
@@ -11,11 +13,60 @@ use crate::pivot::types::PivotState;
 use engine;
 
 // ============================================================================
-// ITERATION SETTINGS
+// CALCULATION SETTINGS
 // ============================================================================
 
+/// When formulas recalculate.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum CalculationMode {
+    /// Every edit recalculates its dependents.
+    #[default]
+    Automatic,
+    /// Automatic, except what-if data tables wait for an explicit request.
+    /// Data tables are only ever computed on demand, so edits behave exactly
+    /// as in Automatic.
+    AutomaticExceptTables,
+    /// Nothing recalculates until Calculate Now; edits leave dirty cells.
+    Manual,
+}
+
+impl CalculationMode {
+    /// Whether edits recalculate their dependents immediately.
+    pub fn is_automatic(self) -> bool {
+        !matches!(self, CalculationMode::Manual)
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            CalculationMode::Automatic => "automatic",
+            CalculationMode::AutomaticExceptTables => "automaticExceptTables",
+            CalculationMode::Manual => "manual",
+        }
+    }
+
+    /// Parses a mode name; also accepts the xlsx `calcMode` spellings.
+    pub fn parse(mode: &str) -> Option<Self> {
+        match mode.to_lowercase().as_str() {
+            "automatic" | "auto" => Some(CalculationMode::Automatic),
+            "automaticexcepttables" | "autonotable" => Some(CalculationMode::AutomaticExceptTables),
+            "manual" => Some(CalculationMode::Manual),
+            _ => None,
+        }
+    }
+
+    /// The xlsx `calcMode` attribute value.
+    fn xlsx_name(self) -> &'static str {
+        match self {
+            CalculationMode::Automatic => "auto",
+            CalculationMode::AutomaticExceptTables => "autoNoTable",
+            CalculationMode::Manual => "manual",
+        }
+    }
+}
+
 /// Settings for iterative calculation (circular reference resolution).
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct IterationSettings {
     pub enabled: bool,
@@ -23,60 +74,244 @@ pub struct IterationSettings {
     pub max_change: f64,
 }
 
+impl Default for IterationSettings {
+    fn default() -> Self {
+        Self { enabled: false, max_iterations: 100, max_change: 0.001 }
+    }
+}
+
+/// Multi-threaded calculation settings.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MultiThreadSettings {
+    pub enabled: bool,
+    /// Maximum calculation threads; None = one per processor.
+    pub thread_cap: Option<u32>,
+}
+
+impl Default for MultiThreadSettings {
+    fn default() -> Self {
+        Self { enabled: true, thread_cap: None }
+    }
+}
+
+impl MultiThreadSettings {
+    /// Number of threads a recalculation may use.
+    pub fn effective_threads(&self) -> usize {
+        if !self.enabled {
+            return 1;
+        }
+        match self.thread_cap {
+            Some(cap) => cap.max(1) as usize,
+            None => std::thread::available_parallelism().map_or(1, |n| n.get()),
+        }
+    }
+}
+
+/// Workbook-level calculation settings. Persisted with the workbook (the
+/// xlsx `<calcPr>` element in .xlsx files).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CalculationSettings {
+    pub mode: CalculationMode,
+    /// Recalculate all formulas before saving.
+    pub recalc_before_save: bool,
+    pub iterative: IterationSettings,
+    pub multi_threaded: MultiThreadSettings,
+    /// Store formula results rounded to the precision their format shows.
+    pub precision_as_displayed: bool,
+}
+
+/// Excel's defaults: automatic, recalculate before save, no iteration,
+/// multi-threaded on all processors, full precision.
+impl Default for CalculationSettings {
+    fn default() -> Self {
+        Self {
+            mode: CalculationMode::Automatic,
+            recalc_before_save: true,
+            iterative: IterationSettings::default(),
+            multi_threaded: MultiThreadSettings::default(),
+            precision_as_displayed: false,
+        }
+    }
+}
+
+impl CalculationSettings {
+    pub fn to_saved(&self) -> persistence::SavedCalcProperties {
+        persistence::SavedCalcProperties {
+            calc_mode: self.mode.xlsx_name().to_string(),
+            calc_on_save: self.recalc_before_save,
+            iterate: self.iterative.enabled,
+            iterate_count: self.iterative.max_iterations,
+            iterate_delta: self.iterative.max_change,
+            full_precision: !self.precision_as_displayed,
+            concurrent_calc: self.multi_threaded.enabled,
+            concurrent_manual_count: self.multi_threaded.thread_cap,
+        }
+    }
+
+    /// Unknown modes and out-of-range values fall back to the defaults.
+    pub fn from_saved(saved: &persistence::SavedCalcProperties) -> Self {
+        let defaults = Self::default();
+        let settings = Self {
+            mode: CalculationMode::parse(&saved.calc_mode).unwrap_or_default(),
+            recalc_before_save: saved.calc_on_save,
+            iterative: IterationSettings {
+                enabled: saved.iterate,
+                max_iterations: saved.iterate_count,
+                max_change: saved.iterate_delta,
+            },
+            multi_threaded: MultiThreadSettings {
+                enabled: saved.concurrent_calc,
+                thread_cap: saved.concurrent_manual_count,
+            },
+            precision_as_displayed: !saved.full_precision,
+        };
+        Self {
+            iterative: if validate_iteration(&settings.iterative).is_ok() {
+                settings.iterative
+            } else {
+                defaults.iterative
+            },
+            multi_threaded: if validate_multi_thread(&settings.multi_threaded).is_ok() {
+                settings.multi_threaded
+            } else {
+                defaults.multi_threaded
+            },
+            ..settings
+        }
+    }
+}
+
+/// Partial update for `update_calculation_settings`: omitted fields keep
+/// their current value.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CalculationSettingsUpdate {
+    #[serde(default)]
+    pub mode: Option<CalculationMode>,
+    #[serde(default)]
+    pub recalc_before_save: Option<bool>,
+    #[serde(default)]
+    pub iterative: Option<IterationSettings>,
+    #[serde(default)]
+    pub multi_threaded: Option<MultiThreadSettings>,
+    #[serde(default)]
+    pub precision_as_displayed: Option<bool>,
+}
+
+fn validate_iteration(settings: &IterationSettings) -> Result<(), String> {
+    if !(1..=32767).contains(&settings.max_iterations) {
+        return Err("Maximum iterations must be between 1 and 32767".to_string());
+    }
+    if !settings.max_change.is_finite() || settings.max_change < 0.0 {
+        return Err("Maximum change must be a non-negative number".to_string());
+    }
+    Ok(())
+}
+
+fn validate_multi_thread(settings: &MultiThreadSettings) -> Result<(), String> {
+    match settings.thread_cap {
+        Some(cap) if !(1..=1024).contains(&cap) => {
+            Err("Thread count must be between 1 and 1024".to_string())
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Rounds a formula result to its displayed precision when the workbook
+/// calculates with precision as displayed; otherwise returns it unchanged.
+pub(crate) fn stored_result(
+    value: engine::CellValue,
+    style: &engine::CellStyle,
+    precision_as_displayed: bool,
+) -> engine::CellValue {
+    match value {
+        engine::CellValue::Number(n) if precision_as_displayed => {
+            engine::CellValue::Number(engine::round_to_displayed(n, &style.number_format))
+        }
+        other => other,
+    }
+}
+
 // ============================================================================
-// CALCULATION MODE COMMANDS
+// CALCULATION SETTINGS COMMANDS
 // ============================================================================
 
-/// Set the calculation mode ("automatic" or "manual")
+/// Get the workbook calculation settings.
 #[tauri::command]
-pub fn set_calculation_mode(state: State<AppState>, mode: String) -> String {
-    log_enter_info!("CMD", "set_calculation_mode", "mode={}", mode);
-
-    let valid_mode = match mode.to_lowercase().as_str() {
-        "automatic" | "auto" => "automatic".to_string(),
-        "manual" => "manual".to_string(),
-        _ => {
-            log_warn!("CMD", "invalid calculation mode: {}, defaulting to automatic", mode);
-            "automatic".to_string()
-        }
-    };
+pub fn get_calculation_settings(state: State<AppState>) -> CalculationSettings {
+    state.calculation_settings.lock().unwrap().clone()
+}
+
+/// Update the workbook calculation settings. Fields left out of `update`
+/// keep their value; an invalid field rejects the whole update.
+#[tauri::command]
+pub fn update_calculation_settings(
+    state: State<AppState>,
+    update: CalculationSettingsUpdate,
+) -> Result<CalculationSettings, String> {
+    update_calculation_settings_internal(&state, update)
+}
+
+pub(crate) fn update_calculation_settings_internal(
+    state: &AppState,
+    update: CalculationSettingsUpdate,
+) -> Result<CalculationSettings, String> {
+    log_enter_info!("CMD", "update_calculation_settings", "{:?}", update);
+    if let Some(ref iterative) = update.iterative {
+        validate_iteration(iterative)?;
+    }
+    if let Some(ref multi_threaded) = update.multi_threaded {
+        validate_multi_thread(multi_threaded)?;
+    }
+
+    let mut settings = state.calculation_settings.lock().unwrap();
+    if let Some(mode) = update.mode {
+        settings.mode = mode;
+    }
+    if let Some(recalc_before_save) = update.recalc_before_save {
+        settings.recalc_before_save = recalc_before_save;
+    }
+    if let Some(iterative) = update.iterative {
+        settings.iterative = iterative;
+    }
+    if let Some(multi_threaded) = update.multi_threaded {
+        settings.multi_threaded = multi_threaded;
+    }
+    if let Some(precision_as_displayed) = update.precision_as_displayed {
+        settings.precision_as_displayed = precision_as_displayed;
+    }
 
-    let mut calc_mode = state.calculation_mode.lock().unwrap();
-    *calc_mode = valid_mode.clone();
+    log_exit_info!("CMD", "update_calculation_settings", "mode={}", settings.mode.as_str());
+    Ok(settings.clone())
+}
 
-    log_exit_info!("CMD", "set_calculation_mode", "set to {}", valid_mode);
-    valid_mode
+/// Set the calculation mode ("automatic", "automaticExceptTables" or "manual").
+#[tauri::command]
+pub fn set_calculation_mode(state: State<AppState>, mode: String) -> String {
+    let valid_mode = CalculationMode::parse(&mode).unwrap_or_else(|| {
+        log_warn!("CMD", "invalid calculation mode: {}, defaulting to automatic", mode);
+        CalculationMode::Automatic
+    });
+    let update = CalculationSettingsUpdate { mode: Some(valid_mode), ..Default::default() };
+    let _ = update_calculation_settings_internal(&state, update);
+    valid_mode.as_str().to_string()
 }
 
 /// Get the current calculation mode
 #[tauri::command]
 pub fn get_calculation_mode(state: State<AppState>) -> String {
     log_enter!("CMD", "get_calculation_mode");
-
-    let calc_mode = state.calculation_mode.lock().unwrap();
-    let mode = calc_mode.clone();
-
+    let mode = state.calculation_settings.lock().unwrap().mode.as_str().to_string();
     log_exit!("CMD", "get_calculation_mode", "mode={}", mode);
     mode
 }
 
-// ============================================================================
-// ITERATION SETTINGS COMMANDS
-// ============================================================================
-
 /// Get the current iterative calculation settings.
 #[tauri::command]
 pub fn get_iteration_settings(state: State<AppState>) -> IterationSettings {
-    log_enter!("CMD", "get_iteration_settings");
-
-    let enabled = *state.iteration_enabled.lock().unwrap();
-    let max_iterations = *state.max_iterations.lock().unwrap();
-    let max_change = *state.max_change.lock().unwrap();
-
-    let settings = IterationSettings { enabled, max_iterations, max_change };
-    log_exit!("CMD", "get_iteration_settings", "enabled={} max_iterations={} max_change={}",
-        settings.enabled, settings.max_iterations, settings.max_change);
-    settings
+    state.calculation_settings.lock().unwrap().iterative.clone()
 }
 
 /// Set the iterative calculation settings.
@@ -86,17 +321,10 @@ pub fn set_iteration_settings(
     enabled: bool,
     max_iterations: u32,
     max_change: f64,
-) -> IterationSettings {
-    log_enter_info!("CMD", "set_iteration_settings",
-        "enabled={} max_iterations={} max_change={}", enabled, max_iterations, max_change);
-
-    *state.iteration_enabled.lock().unwrap() = enabled;
-    *state.max_iterations.lock().unwrap() = max_iterations;
-    *state.max_change.lock().unwrap() = max_change;
-
-    let settings = IterationSettings { enabled, max_iterations, max_change };
-    log_exit_info!("CMD", "set_iteration_settings", "applied");
-    settings
+) -> Result<IterationSettings, String> {
+    let iterative = IterationSettings { enabled, max_iterations, max_change };
+    let update = CalculationSettingsUpdate { iterative: Some(iterative), ..Default::default() };
+    update_calculation_settings_internal(&state, update).map(|s| s.iterative)
 }
 
 // ============================================================================
@@ -105,11 +333,18 @@ pub fn set_iteration_settings(
 
 /// Get the current calculation state.
 /// Returns "done", "calculating", or "pending".
-/// Currently always returns "done" since calculation is synchronous,
-/// but having this API ready enables future async calculation.
+/// "calculating" means a background pass is running; "pending" means dirty
+/// formula cells are waiting: edits made in manual mode, or results a
+/// cancelled or interrupted pass left for the next recalculation.
 #[tauri::command]
-pub fn get_calculation_state(_state: State<AppState>) -> String {
-    "done".to_string()
+pub fn get_calculation_state(state: State<AppState>, worker: State<CalcWorkerState>) -> String {
+    if worker.is_running() {
+        "calculating".to_string()
+    } else if state.dirty_cells.lock().unwrap().is_empty() {
+        "done".to_string()
+    } else {
+        "pending".to_string()
+    }
 }
 
 // ============================================================================
@@ -118,7 +353,7 @@ pub fn get_calculation_state(_state: State<AppState>) -> String {
 
 /// Evaluate a single formula cell, returning its CellValue.
 /// Helper shared by calculate_now for both normal and iterative evaluation.
-fn evaluate_single_formula(
+pub(crate) fn evaluate_single_formula(
     row: u32,
     col: u32,
     formula: &str,
@@ -134,6 +369,7 @@ fn evaluate_single_formula(
     named_ranges_map: &std::collections::HashMap<String, crate::named_ranges::NamedRange>,
     row_heights: &std::collections::HashMap<u32, f64>,
     column_widths: &std::collections::HashMap<u32, f64>,
+    hidden_rows: &std::collections::HashSet<u32>,
     cube: Option<&std::sync::Arc<engine::CubePrefetch>>,
     control_values: Option<&std::sync::Arc<crate::control_values::ControlValuesMap>>,
 ) -> engine::CellValue {
@@ -167,7 +403,7 @@ fn evaluate_single_formula(
                 current_col: Some(col),
                 row_heights: Some(row_heights.clone()),
                 column_widths: Some(column_widths.clone()),
-                hidden_rows: None,
+                hidden_rows: (!hidden_rows.is_empty()).then(|| hidden_rows.clone()),
                 control_values: control_values.cloned(),
             };
             evaluate_formula_with_pivot(
@@ -187,7 +423,7 @@ fn evaluate_single_formula(
 }
 
 /// Extract the numeric value from a CellValue, returning 0.0 for non-numeric values.
-fn cell_value_as_f64(value: &engine::CellValue) -> f64 {
+pub(crate) fn cell_value_as_f64(value: &engine::CellValue) -> f64 {
     match value {
         engine::CellValue::Number(n) => *n,
         engine::CellValue::Boolean(b) => if *b { 1.0 } else { 0.0 },
@@ -198,7 +434,7 @@ fn cell_value_as_f64(value: &engine::CellValue) -> f64 {
 /// Detect circular groups among formula cells using the dependency maps.
 /// Returns (non_circular_cells_in_order, circular_groups) where each circular
 /// group is a Vec of (row, col, formula) that must be iterated together.
-fn partition_formula_cells(
+pub(crate) fn partition_formula_cells(
     formula_cells: &[(u32, u32, String)],
     dependencies_map: &crate::DependencyMap,
 ) -> (Vec<(u32, u32, String)>, Vec<Vec<(u32, u32, String)>>) {
@@ -316,232 +552,163 @@ fn partition_formula_cells(
 /// Recalculate all formulas in the grid.
 /// When iterative calculation is enabled, circular references are resolved
 /// by repeatedly evaluating the circular group until convergence.
+///
+/// Runs on a blocking worker thread: the grid locks are held only to snapshot
+/// the workbook and to apply the results, so other commands keep responding.
+/// Emits `calc-progress` events while evaluating; `cancel_calculation` stops
+/// the pass and leaves the previous values and the dirty set as they were.
 #[tauri::command]
-pub fn calculate_now(state: State<AppState>, user_files_state: State<UserFilesState>, pivot_state: State<'_, PivotState>, pane_control_state: State<'_, crate::pane_control::PaneControlState>, ribbon_filter_state: State<'_, crate::ribbon_filter::RibbonFilterState>, cube_results: Option<engine::CubePrefetch>) -> Result<Vec<CellData>, String> {
-    // PERF-03: one lookup-index cache for the whole pass (lookup_cache.rs).
-    let _lookup_pass = engine::begin_lookup_pass();
+pub async fn calculate_now(app: tauri::AppHandle, cube_results: Option<engine::CubePrefetch>) -> Result<Vec<CellData>, String> {
+    tauri::async_runtime::spawn_blocking(move || calculate_now_blocking(&app, cube_results))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+/// Worker-thread body of `calculate_now`.
+fn calculate_now_blocking(app: &tauri::AppHandle, cube_results: Option<engine::CubePrefetch>) -> Result<Vec<CellData>, String> {
+    use tauri::{Emitter, Manager};
+    let state = app.state::<AppState>();
+    let user_files_state = app.state::<UserFilesState>();
+    let pivot_state = app.state::<PivotState>();
+    let pane_control_state = app.state::<crate::pane_control::PaneControlState>();
+    let ribbon_filter_state = app.state::<crate::ribbon_filter::RibbonFilterState>();
+    let worker = app.state::<CalcWorkerState>();
+
+    let cancel = worker.begin();
+    let mut on_progress = |progress: CalcProgress| {
+        if progress.evaluated % PROGRESS_EVENT_INTERVAL == 0 || progress.evaluated == progress.total {
+            let _ = app.emit(CALC_PROGRESS_EVENT, progress);
+        }
+    };
+    let result = calculate_and_refresh_pivots(&state, &user_files_state, &pivot_state, &pane_control_state, &ribbon_filter_state, cube_results, &cancel, &mut on_progress);
+    worker.finish(&cancel);
+    result
+}
+
+/// `calculate_now_impl` plus the pivot refresh that follows it. Recalculate
+/// before save runs this in place, without the worker thread.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn calculate_and_refresh_pivots(
+    state: &AppState,
+    user_files_state: &UserFilesState,
+    pivot_state: &PivotState,
+    pane_control_state: &crate::pane_control::PaneControlState,
+    ribbon_filter_state: &crate::ribbon_filter::RibbonFilterState,
+    cube_results: Option<engine::CubePrefetch>,
+    cancel: &AtomicBool,
+    on_progress: &mut dyn FnMut(CalcProgress),
+) -> Result<Vec<CellData>, String> {
+    let updated_cells = calculate_now_impl(state, user_files_state, pivot_state, pane_control_state, ribbon_filter_state, cube_results, cancel, on_progress)?;
+
+    // Pivots fed by recalculated cells go stale. Calculate Now is also how a
+    // manual-mode workbook brings its auto-refresh pivots up to date.
+    let control_states = Some((pane_control_state, ribbon_filter_state));
+    crate::pivot::source_watch::source_cells_changed(state, pivot_state, control_states, &updated_cells);
+    crate::pivot::source_watch::refresh_stale_auto_pivots(state, pivot_state, control_states);
+
+    Ok(updated_cells)
+}
+
+/// Stop the running background calculation. Returns false when none is running.
+#[tauri::command]
+pub fn cancel_calculation(worker: State<CalcWorkerState>) -> bool {
+    log_info!("CALC", "cancel_calculation");
+    worker.cancel()
+}
+
+/// Body of `calculate_now` — every lock is acquired AND dropped inside, so the
+/// command wrapper can refresh source-watched pivots afterwards. Evaluation
+/// runs on a snapshot with no lock held (calc_worker.rs); a cancelled pass
+/// returns `CALCULATION_CANCELLED` without touching the grid.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn calculate_now_impl(
+    state: &AppState,
+    user_files_state: &UserFilesState,
+    pivot_state: &PivotState,
+    pane_control_state: &crate::pane_control::PaneControlState,
+    ribbon_filter_state: &crate::ribbon_filter::RibbonFilterState,
+    cube_results: Option<engine::CubePrefetch>,
+    cancel: &AtomicBool,
+    on_progress: &mut dyn FnMut(CalcProgress),
+) -> Result<Vec<CellData>, String> {
     // Pre-fetched CUBE data for this full recalc (built async by cube_prefetch_all
     // on the frontend before calling). Shared via Arc so each formula's eval gets
     // it cheaply; None => cube cells preserve their last value (see eval_cube).
     let cube_arc = cube_results.map(std::sync::Arc::new);
     // GET.CONTROLVALUE snapshot: built ONCE per recalc, BEFORE the grid locks
-    // below (canonical lock order: control stores first, grids last).
+    // (canonical lock order: control stores first, grids last).
     let control_values = crate::control_values::build_control_values(
-        &state, &pane_control_state, &ribbon_filter_state,
+        state, pane_control_state, ribbon_filter_state,
     );
-    let mut grid = state.grid.lock().unwrap();
-    let mut grids = state.grids.lock().unwrap();
-    let sheet_names = state.sheet_names.lock().unwrap();
     let active_sheet = *state.active_sheet.lock().unwrap();
 
-    // The active-sheet mirror (state.grid) is the source of truth; grids[i]
-    // can lag behind it (see get_watch_cells note in commands/data.rs).
-    // Formula evaluation below reads the ACTIVE sheet through `grids`, so a
-    // stale grids[active] silently recalculates from old values (BUG-0016).
-    // Sync it from the mirror before evaluating.
-    if active_sheet < grids.len() {
-        grids[active_sheet] = grid.clone();
+    let mut changes = crate::change_events::ChangeBatch::new();
+    changes.set_calc_state("calculating");
+    changes.flush();
+
+    let applied = match calc_worker::snapshot_sheet(state, user_files_state, active_sheet, Some(control_values.clone()), cube_arc) {
+        Some(mut snapshot) => {
+            let Some(results) = calc_worker::compute(&mut snapshot, pivot_state, cancel, on_progress) else {
+                log_info!("CALC", "calculation cancelled; previous values kept");
+                let mut changes = crate::change_events::ChangeBatch::new();
+                changes.set_calc_state(if state.dirty_cells.lock().unwrap().is_empty() { "done" } else { "pending" });
+                changes.flush();
+                return Err(calc_worker::CALCULATION_CANCELLED.to_string());
+            };
+            calc_worker::apply(state, &snapshot, results)
+        }
+        None => calc_worker::AppliedResults::default(),
+    };
+    if !applied.invalidated.is_empty() {
+        log_warn!("CALC", "{} formula cells changed inputs during recalculation; left dirty", applied.invalidated.len());
     }
+
+    let mut grids = state.grids.lock().unwrap();
+    let sheet_names = state.sheet_names.lock().unwrap();
     let mut styles = state.style_registry.lock().unwrap();
-    let user_files = user_files_state.files.lock().unwrap();
     let locale = state.locale.lock().unwrap();
 
-    // Read iteration settings
-    let iteration_enabled = *state.iteration_enabled.lock().unwrap();
-    let max_iterations = *state.max_iterations.lock().unwrap();
-    let max_change = *state.max_change.lock().unwrap();
-
-    // Build pivot data lookup closure for GETPIVOTDATA
-    let pivot_tables = pivot_state.pivot_tables.lock().unwrap();
-    let pivot_views = pivot_state.views.lock().unwrap();
-    let pivot_data_fn = |data_field: &str, pivot_row: u32, pivot_col: u32, pairs: &[(&str, &str)]| -> Option<f64> {
-        crate::pivot::operations::lookup_pivot_data(
-            &pivot_tables,
-            &pivot_views,
-            data_field,
-            pivot_row,
-            pivot_col,
-            pairs,
-        )
-    };
-
-    // Pre-fetch writeback submissions once per recalculation pass so GATHER
-    // formulas see current data (empty map, no registry I/O, when the
-    // workbook has no writeback regions).
-    let gather_data = crate::calp_commands::build_gather_data(&state);
-    let gather_fn = |region_id: &str| -> engine::GatherRegionData {
-        gather_data.get(region_id).cloned().unwrap_or_default()
-    };
-
-    let mut updated_cells = Vec::new();
-
-    // Collect all cells with formulas
-    let formula_cells: Vec<_> = grid
+    // A full pass re-derives the volatile set too (the load path clears the
+    // dependency maps and leaves them to this recalculation).
+    *state.volatile_cells.lock().unwrap() = grids[active_sheet]
         .cells
         .iter()
-        .filter_map(|(&(row, col), cell)| {
-            cell.formula_string().map(|f| (row, col, f))
-        })
+        .filter(|(_, cell)| cell.get_ast().is_some_and(engine::is_volatile))
+        .map(|(&pos, _)| pos)
         .collect();
 
-    // Lock table state once for all formula evaluations
-    let tables_map = state.tables.lock().unwrap();
-    let table_names_map = state.table_names.lock().unwrap();
-    let named_ranges_map = state.named_ranges.lock().unwrap();
-    let mut row_heights = state.row_heights.lock().unwrap();
-    let mut column_widths = state.column_widths.lock().unwrap();
-    let dependencies_map = state.dependencies.lock().unwrap();
-
-    // Partition formula cells into non-circular (topological order) and circular groups
-    let (non_circular, circular_groups) = partition_formula_cells(&formula_cells, &dependencies_map);
-    drop(dependencies_map);
-
-    // Phase 1: Evaluate non-circular formulas in topological order (single pass)
-    for (row, col, formula) in &non_circular {
-        let result = evaluate_single_formula(
-            *row, *col, formula,
-            &grids, &sheet_names, active_sheet,
-            &styles, &user_files, &pivot_data_fn, &gather_fn,
-            &tables_map, &table_names_map, &named_ranges_map,
-            &row_heights, &column_widths,
-            cube_arc.as_ref(),
-            Some(&control_values),
-        );
-
-        if let Some(cell) = grid.get_cell(*row, *col) {
-            let mut updated = cell.clone();
-            updated.value = result;
-            grid.set_cell(*row, *col, updated.clone());
-            if active_sheet < grids.len() {
-                grids[active_sheet].set_cell(*row, *col, updated.clone());
-            }
-
-            let style = styles.get(updated.style_index);
-            let display = format_cell_value(&updated.value, style, &locale);
-            updated_cells.push(CellData {
-                row: *row,
-                col: *col,
-                display,
+    let updated_cells: Vec<CellData> = applied
+        .written
+        .iter()
+        .filter_map(|&(row, col)| {
+            let cell = grids[active_sheet].get_cell(row, col)?;
+            let style = styles.get(cell.style_index);
+            Some(CellData {
+                row,
+                col,
+                display: format_cell_value(&cell.value, style, &locale),
                 display_color: None,
-                formula: updated.formula_string().map(|f| format!("={}", f)),
-                style_index: updated.style_index,
+                formula: cell.formula_string().map(|f| format!("={}", f)),
+                style_index: cell.style_index,
                 row_span: 1,
                 col_span: 1,
                 sheet_index: None,
                 rich_text: None,
                 accounting_layout: None,
-            });
-        }
-    }
-
-    // Phase 2: Handle circular groups
-    for group in &circular_groups {
-        if !iteration_enabled {
-            // Iteration disabled: set all cells in the circular group to #CIRC! error
-            for (row, col, _formula) in group {
-                if let Some(cell) = grid.get_cell(*row, *col) {
-                    let mut updated = cell.clone();
-                    updated.value = engine::CellValue::Error(engine::CellError::Circular);
-                    grid.set_cell(*row, *col, updated.clone());
-                    if active_sheet < grids.len() {
-                        grids[active_sheet].set_cell(*row, *col, updated.clone());
-                    }
-
-                    let style = styles.get(updated.style_index);
-                    let display = format_cell_value(&updated.value, style, &locale);
-                    updated_cells.push(CellData {
-                        row: *row,
-                        col: *col,
-                        display,
-                        display_color: None,
-                        formula: updated.formula_string().map(|f| format!("={}", f)),
-                        style_index: updated.style_index,
-                        row_span: 1,
-                        col_span: 1,
-                        sheet_index: None,
-                        rich_text: None,
-                        accounting_layout: None,
-                    });
-                }
-            }
-        } else {
-            // Iteration enabled: iterate the circular group until convergence
-            log_info!("CALC", "Iterating circular group of {} cells (max_iterations={}, max_change={})",
-                group.len(), max_iterations, max_change);
-
-            for iteration in 0..max_iterations {
-                let mut max_delta: f64 = 0.0;
-
-                for (row, col, formula) in group {
-                    let old_value = grid.get_cell(*row, *col)
-                        .map(|c| cell_value_as_f64(&c.value))
-                        .unwrap_or(0.0);
-
-                    let new_result = evaluate_single_formula(
-                        *row, *col, formula,
-                        &grids, &sheet_names, active_sheet,
-                        &styles, &user_files, &pivot_data_fn, &gather_fn,
-                        &tables_map, &table_names_map, &named_ranges_map,
-                        &row_heights, &column_widths,
-                        cube_arc.as_ref(),
-                        Some(&control_values),
-                    );
-
-                    let new_numeric = cell_value_as_f64(&new_result);
-
-                    if let Some(cell) = grid.get_cell(*row, *col) {
-                        let mut updated = cell.clone();
-                        updated.value = new_result;
-                        grid.set_cell(*row, *col, updated.clone());
-                        if active_sheet < grids.len() {
-                            grids[active_sheet].set_cell(*row, *col, updated);
-                        }
-                    }
-
-                    let delta = (new_numeric - old_value).abs();
-                    if delta > max_delta {
-                        max_delta = delta;
-                    }
-                }
-
-                if max_delta < max_change {
-                    log_info!("CALC", "Circular group converged after {} iterations (max_delta={})",
-                        iteration + 1, max_delta);
-                    break;
-                }
-            }
-
-            // Collect final values for all cells in the group
-            for (row, col, _formula) in group {
-                if let Some(cell) = grid.get_cell(*row, *col) {
-                    let style = styles.get(cell.style_index);
-                    let display = format_cell_value(&cell.value, style, &locale);
-                    updated_cells.push(CellData {
-                        row: *row,
-                        col: *col,
-                        display,
-                        display_color: None,
-                        formula: cell.formula_string().map(|f| format!("={}", f)),
-                        style_index: cell.style_index,
-                        row_span: 1,
-                        col_span: 1,
-                        sheet_index: None,
-                        rich_text: None,
-                        accounting_layout: None,
-                    });
-                }
-            }
-        }
-    }
+                error_detail: None,
+            })
+        })
+        .collect();
 
     // Re-evaluate all computed properties for this sheet
     {
+        let mut row_heights = state.row_heights.lock().unwrap();
+        let mut column_widths = state.column_widths.lock().unwrap();
         let mut cp_storage = state.computed_properties.lock().unwrap();
-        let (_dim_changes, _style_refresh) =
+        let (dim_changes, _style_refresh) =
             crate::computed_properties::re_evaluate_all_properties(
                 &mut cp_storage,
                 &mut grids,
-                &mut grid,
                 &sheet_names,
                 active_sheet,
                 &mut row_heights,
@@ -550,23 +717,36 @@ pub fn calculate_now(state: State<AppState>, user_files_state: State<UserFilesSt
                 Some(&control_values),
             );
         // Note: calculate_now returns Vec<CellData>, not UpdateCellResult.
-        // Dimension changes and style refresh are handled by the frontend
-        // re-fetching viewport data after recalculation.
+        // Dimension changes are pushed as a dimensions-changed event; style
+        // refresh is handled by the frontend re-fetching viewport data.
+        changes.push_dimensions(active_sheet, dim_changes);
     }
 
+    // Every formula was just re-evaluated: only cells whose inputs were
+    // edited while the pass ran are still pending.
+    let mut dirty_cells = state.dirty_cells.lock().unwrap();
+    dirty_cells.clear();
+    dirty_cells.extend(applied.invalidated.iter().map(|&(row, col)| (active_sheet, row, col)));
+    changes.set_calc_state(if dirty_cells.is_empty() { "done" } else { "pending" });
+    drop(dirty_cells);
+    changes.flush();
+
     Ok(updated_cells)
 }
 
 /// Evaluate all formula cells on one sheet (active or not), writing results
-/// into grids[sheet_index] (and the active-sheet mirror when applicable).
+/// into grids[sheet_index].
 ///
 /// calculate_now only ever evaluates the ACTIVE sheet; .calp refresh and
 /// override revert/accept write formula cells (value Empty pending recalc)
 /// into arbitrary sheets, which would otherwise display empty until the user
-/// manually recalculated there. Builds a local same-sheet dependency map for
-/// evaluation order — the AppState dependency maps describe only the active
+/// manually recalculated there. Orders evaluation by a local same-sheet
+/// dependency map — the AppState dependency maps describe only the active
 /// sheet. Computed properties are not re-evaluated here (active-sheet
 /// machinery; the frontend recalc path covers them).
+///
+/// Structural changes call this synchronously, but evaluation still runs on
+/// a snapshot (calc_worker.rs), so the grid locks are free meanwhile.
 pub(crate) fn recalculate_sheet_values(
     state: &AppState,
     user_files_state: &UserFilesState,
@@ -574,26 +754,260 @@ pub(crate) fn recalculate_sheet_values(
     sheet_index: usize,
     control_states: Option<(&crate::pane_control::PaneControlState, &crate::ribbon_filter::RibbonFilterState)>,
 ) {
-    // PERF-03: one lookup-index cache for the whole pass (lookup_cache.rs).
-    let _lookup_pass = engine::begin_lookup_pass();
     // GET.CONTROLVALUE snapshot: built BEFORE any grid locks (canonical lock
     // order). None (states unreachable at the call site) => those formulas
     // evaluate to #N/A for this pass (v1).
     let control_values =
         crate::control_values::build_control_values_from_states(state, control_states);
-    let mut grid_mirror = state.grid.lock().unwrap();
-    let mut grids = state.grids.lock().unwrap();
-    let sheet_names = state.sheet_names.lock().unwrap();
-    let active_sheet = *state.active_sheet.lock().unwrap();
-    if sheet_index >= grids.len() {
+    let Some(mut snapshot) = calc_worker::snapshot_sheet(state, user_files_state, sheet_index, control_values, None) else {
         return;
+    };
+    let Some(results) = calc_worker::compute(&mut snapshot, pivot_state, &AtomicBool::new(false), &mut |_| {}) else {
+        return;
+    };
+    let applied = calc_worker::apply(state, &snapshot, results);
+
+    let mut dirty_cells = state.dirty_cells.lock().unwrap();
+    for &(row, col) in &applied.written {
+        dirty_cells.remove(&(sheet_index, row, col));
     }
+    dirty_cells.extend(applied.invalidated.iter().map(|&(row, col)| (sheet_index, row, col)));
+}
+
+/// Recalculate all formula cells in the current sheet (same as calculate_now for single-sheet)
+#[tauri::command]
+pub async fn calculate_sheet(app: tauri::AppHandle) -> Result<Vec<CellData>, String> {
+    log_enter_info!("CMD", "calculate_sheet");
+
+    // For now, calculate_sheet does the same as calculate_now since we have a single sheet
+    let result = calculate_now(app, None).await;
+
+    log_exit_info!("CMD", "calculate_sheet", "done");
+    result
+}
+
+/// Evaluation order for a ranged recalculation: every formula cell inside the
+/// rectangle plus everything downstream of it (cell, column and row
+/// dependents), topologically ordered. Cells outside the rectangle that are
+/// not downstream of it are never visited.
+pub(crate) fn range_recalc_order(
+    grid: &engine::Grid,
+    (start_row, start_col, end_row, end_col): (u32, u32, u32, u32),
+    dependents: &crate::DependencyMap,
+    column_dependents: &crate::StripeDependentsMap,
+    row_dependents: &crate::StripeDependentsMap,
+) -> Vec<(u32, u32)> {
+    let mut seeds: Vec<(u32, u32)> = grid
+        .cells
+        .iter()
+        .filter(|(&(r, c), cell)| {
+            r >= start_row && r <= end_row && c >= start_col && c <= end_col && cell.formula_string().is_some()
+        })
+        .map(|(&pos, _)| pos)
+        .collect();
+    seeds.sort_unstable();
+    seeds_recalc_order(&seeds, true, dependents, column_dependents, row_dependents)
+}
+
+/// `recalc_order_from_seeds` plus the column/row (whole-stripe) dependents,
+/// appended after the topological order, mirroring the edit cascade. With
+/// `include_seeds` false the seeds only feed the order (cells whose value
+/// changed without needing evaluation, e.g. cleared cells).
+pub(crate) fn seeds_recalc_order(
+    seeds: &[(u32, u32)],
+    include_seeds: bool,
+    dependents: &crate::DependencyMap,
+    column_dependents: &crate::StripeDependentsMap,
+    row_dependents: &crate::StripeDependentsMap,
+) -> Vec<(u32, u32)> {
+    let mut order = crate::recalc_order_from_seeds(seeds, dependents, include_seeds);
+    let mut seen: crate::CoordSet = order.iter().copied().collect();
+    if !include_seeds {
+        for &seed in seeds {
+            for dep in crate::get_column_row_dependents(seed, column_dependents, row_dependents) {
+                if seen.insert(dep) {
+                    order.push(dep);
+                }
+            }
+        }
+    }
+    let mut i = 0;
+    while i < order.len() {
+        for dep in crate::get_column_row_dependents(order[i], column_dependents, row_dependents) {
+            if seen.insert(dep) {
+                order.push(dep);
+            }
+        }
+        i += 1;
+    }
+    order
+}
+
+/// Recalculate only the formula cells inside a rectangle of a sheet (the
+/// active one when `sheet_index` is omitted) plus their downstream
+/// dependents, on that sheet and on every sheet that reads it
+/// ("recalculate selection"; also the pass table/pivot refresh paths should
+/// use instead of a whole-sheet recalc). Explicit invocation always
+/// calculates, even in manual mode; the recalculated cells are no longer
+/// dirty afterwards. Returns only the cells whose value changed.
+#[tauri::command]
+pub fn calculate_range(
+    state: State<AppState>,
+    user_files_state: State<UserFilesState>,
+    pivot_state: State<'_, PivotState>,
+    pane_control_state: State<'_, crate::pane_control::PaneControlState>,
+    ribbon_filter_state: State<'_, crate::ribbon_filter::RibbonFilterState>,
+    sheet_index: Option<usize>,
+    start_row: u32,
+    start_col: u32,
+    end_row: u32,
+    end_col: u32,
+) -> Result<Vec<CellData>, String> {
+    log_enter_info!("CMD", "calculate_range", "sheet={:?} ({},{})..({},{})", sheet_index, start_row, start_col, end_row, end_col);
+    let sheet = sheet_index.unwrap_or_else(|| *state.active_sheet.lock().unwrap());
+    let rect = (
+        start_row.min(end_row),
+        start_col.min(end_col),
+        start_row.max(end_row),
+        start_col.max(end_col),
+    );
+    let updated_cells = calculate_range_internal(
+        &state,
+        &user_files_state,
+        &pivot_state,
+        Some((&pane_control_state, &ribbon_filter_state)),
+        sheet,
+        rect,
+    )?;
+    log_exit_info!("CMD", "calculate_range", "changed={}", updated_cells.len());
+    Ok(updated_cells)
+}
+
+/// Body of `calculate_range`. The range's order is evaluated first; then the
+/// formulas on other sheets that read any evaluated cell are recalculated
+/// together with their own downstream cells, sheet by sheet, until nothing
+/// new is reached. Each cell is evaluated at most once per call. Cells of the
+/// active sheet come back with `sheet_index: None`, others with their sheet.
+pub(crate) fn calculate_range_internal(
+    state: &AppState,
+    user_files_state: &UserFilesState,
+    pivot_state: &PivotState,
+    control_states: Option<(&crate::pane_control::PaneControlState, &crate::ribbon_filter::RibbonFilterState)>,
+    sheet: usize,
+    rect: (u32, u32, u32, u32),
+) -> Result<Vec<CellData>, String> {
+    let sheet_count = state.grids.lock().unwrap().len();
+    crate::sheet_access::check_sheet_index(sheet_count, sheet)?;
+    let _lookup_pass = engine::begin_lookup_pass();
+    let control_values = crate::control_values::build_control_values_from_states(state, control_states)
+        .unwrap_or_default();
+    let active_sheet = *state.active_sheet.lock().unwrap();
+    let sheet_names = state.sheet_names.lock().unwrap().clone();
+
+    let mut evaluated: rustc_hash::FxHashSet<(usize, u32, u32)> = rustc_hash::FxHashSet::default();
+    let mut updated_cells = Vec::new();
+    let mut pending: std::collections::BTreeMap<usize, Vec<(u32, u32)>> = std::collections::BTreeMap::new();
+    let mut next = Some((sheet, None));
+    while let Some((current, seeds)) = next.take() {
+        let order = sheet_recalc_order(state, current, active_sheet, rect, seeds.as_deref());
+        let order: Vec<(u32, u32)> =
+            order.into_iter().filter(|&(row, col)| evaluated.insert((current, row, col))).collect();
+        let mut cells = recalculate_in_order(state, user_files_state, pivot_state, &control_values, current, &order);
+        if current != active_sheet {
+            for cell in &mut cells {
+                cell.sheet_index = Some(current);
+            }
+        }
+        updated_cells.extend(cells);
+
+        let cross_sheet_dependents = state.cross_sheet_dependents.lock().unwrap();
+        let name = sheet_names.get(current).cloned().unwrap_or_default();
+        for &(row, col) in &order {
+            let Some(deps) = cross_sheet_dependents.get(&(name.clone(), row, col)) else { continue };
+            for &(dep_sheet, dep_row, dep_col) in deps {
+                if dep_sheet < sheet_count && !evaluated.contains(&(dep_sheet, dep_row, dep_col)) {
+                    pending.entry(dep_sheet).or_default().push((dep_row, dep_col));
+                }
+            }
+        }
+        drop(cross_sheet_dependents);
+        next = pending.pop_first().map(|(dep_sheet, mut seeds)| {
+            seeds.sort_unstable();
+            seeds.dedup();
+            (dep_sheet, Some(seeds))
+        });
+    }
+    Ok(updated_cells)
+}
+
+/// Topological order for one step of `calculate_range_internal`: the formula
+/// cells of `rect` (or the given `seeds`) on `sheet` plus their downstream
+/// cells there. The dependency maps only describe the active sheet, so for
+/// any other sheet equivalent maps are built from its formulas first.
+fn sheet_recalc_order(
+    state: &AppState,
+    sheet: usize,
+    active_sheet: usize,
+    rect: (u32, u32, u32, u32),
+    seeds: Option<&[(u32, u32)]>,
+) -> Vec<(u32, u32)> {
+    let grids = state.grids.lock().unwrap();
+    let grid = &grids[sheet];
+    let order_with = |dependents: &crate::DependencyMap,
+                      column_dependents: &crate::StripeDependentsMap,
+                      row_dependents: &crate::StripeDependentsMap| match seeds {
+        Some(seeds) => seeds_recalc_order(seeds, true, dependents, column_dependents, row_dependents),
+        None => range_recalc_order(grid, rect, dependents, column_dependents, row_dependents),
+    };
+    if sheet == active_sheet {
+        let dependents = state.dependents.lock().unwrap();
+        let column_dependents = state.column_dependents.lock().unwrap();
+        let row_dependents = state.row_dependents.lock().unwrap();
+        return order_with(&dependents, &column_dependents, &row_dependents);
+    }
+
+    let mut dependencies = crate::DependencyMap::default();
+    let mut dependents = crate::DependencyMap::default();
+    let mut column_dependencies = crate::StripeDependenciesMap::default();
+    let mut column_dependents = crate::StripeDependentsMap::default();
+    let mut row_dependencies = crate::StripeDependenciesMap::default();
+    let mut row_dependents = crate::StripeDependentsMap::default();
+    for (&pos, cell) in &grid.cells {
+        let Some(ast) = &cell.ast else { continue };
+        let refs = crate::extract_all_references(ast, grid);
+        if !refs.cells.is_empty() {
+            crate::update_dependencies(pos, refs.cells, &mut dependencies, &mut dependents);
+        }
+        if !refs.columns.is_empty() {
+            crate::update_column_dependencies(pos, refs.columns, &mut column_dependencies, &mut column_dependents);
+        }
+        if !refs.rows.is_empty() {
+            crate::update_row_dependencies(pos, refs.rows, &mut row_dependencies, &mut row_dependents);
+        }
+    }
+    order_with(&dependents, &column_dependents, &row_dependents)
+}
+
+/// Evaluate the formula cells of `order` (a topological order on `sheet`,
+/// e.g. from `range_recalc_order`) in one pass. Cells without a formula are
+/// skipped; every cell of `order` is no longer dirty afterwards. Returns only
+/// the cells whose value changed.
+pub(crate) fn recalculate_in_order(
+    state: &AppState,
+    user_files_state: &UserFilesState,
+    pivot_state: &PivotState,
+    control_values: &std::sync::Arc<crate::control_values::ControlValuesMap>,
+    sheet: usize,
+    order: &[(u32, u32)],
+) -> Vec<CellData> {
+    let hidden_rows = crate::autofilter::sheet_hidden_rows(state, sheet);
+    let is_active = sheet == *state.active_sheet.lock().unwrap();
+    let mut grids = state.grids.lock().unwrap();
+    let sheet_names = state.sheet_names.lock().unwrap();
     let styles = state.style_registry.lock().unwrap();
     let user_files = user_files_state.files.lock().unwrap();
-
-    let iteration_enabled = *state.iteration_enabled.lock().unwrap();
-    let max_iterations = *state.max_iterations.lock().unwrap();
-    let max_change = *state.max_change.lock().unwrap();
+    let locale = state.locale.lock().unwrap();
+    let precision_as_displayed = state.calculation_settings.lock().unwrap().precision_as_displayed;
 
     let pivot_tables = pivot_state.pivot_tables.lock().unwrap();
     let pivot_views = pivot_state.views.lock().unwrap();
@@ -607,157 +1021,159 @@ pub(crate) fn recalculate_sheet_values(
             pairs,
         )
     };
-
     let gather_data = crate::calp_commands::build_gather_data(state);
     let gather_fn = |region_id: &str| -> engine::GatherRegionData {
         gather_data.get(region_id).cloned().unwrap_or_default()
     };
-
-    let formula_cells: Vec<_> = grids[sheet_index]
-        .cells
-        .iter()
-        .filter_map(|(&(row, col), cell)| {
-            cell.formula_string().map(|f| (row, col, f))
-        })
-        .collect();
-    if formula_cells.is_empty() {
-        return;
-    }
-
     let tables_map = state.tables.lock().unwrap();
     let table_names_map = state.table_names.lock().unwrap();
     let named_ranges_map = state.named_ranges.lock().unwrap();
-    let (column_widths, row_heights) = {
-        let all_cw = state.all_column_widths.lock().unwrap();
-        let all_rh = state.all_row_heights.lock().unwrap();
+    // The active sheet's dimensions live in the swapped-in maps.
+    let (row_heights, column_widths) = if is_active {
+        (state.row_heights.lock().unwrap().clone(), state.column_widths.lock().unwrap().clone())
+    } else {
         (
-            all_cw.get(sheet_index).cloned().unwrap_or_default(),
-            all_rh.get(sheet_index).cloned().unwrap_or_default(),
+            state.all_row_heights.lock().unwrap().get(sheet).cloned().unwrap_or_default(),
+            state.all_column_widths.lock().unwrap().get(sheet).cloned().unwrap_or_default(),
         )
     };
 
-    // Local same-sheet dependency map for evaluation ordering.
-    let mut local_deps = crate::DependencyMap::default();
-    for (row, col, _f) in &formula_cells {
-        if let Some(cell) = grids[sheet_index].get_cell(*row, *col) {
-            if let Some(ast) = &cell.ast {
-                let refs = crate::extract_all_references(ast, &grids[sheet_index]);
-                if !refs.cells.is_empty() {
-                    local_deps.insert((*row, *col), refs.cells);
-                }
-            }
-        }
-    }
-    let (non_circular, circular_groups) = partition_formula_cells(&formula_cells, &local_deps);
+    let mut error_details = state.error_details.lock().unwrap();
 
-    for (row, col, formula) in &non_circular {
+    let mut updated_cells = Vec::new();
+    for (row, col) in order {
+        let Some(cell) = grids[sheet].get_cell(*row, *col) else { continue };
+        let Some(formula) = cell.formula_string() else { continue };
         let result = evaluate_single_formula(
-            *row, *col, formula,
-            &grids, &sheet_names, sheet_index,
+            *row, *col, &formula,
+            &grids, &sheet_names, sheet,
             &styles, &user_files, &pivot_data_fn, &gather_fn,
             &tables_map, &table_names_map, &named_ranges_map,
-            &row_heights, &column_widths,
+            &row_heights, &column_widths, &hidden_rows,
             None,
-            control_values.as_ref(),
+            Some(control_values),
         );
-        if let Some(cell) = grids[sheet_index].get_cell(*row, *col) {
-            let mut updated = cell.clone();
-            updated.value = result;
-            grids[sheet_index].set_cell(*row, *col, updated.clone());
-            if sheet_index == active_sheet {
-                grid_mirror.set_cell(*row, *col, updated);
-            }
+        crate::record_error_detail(&mut error_details, (sheet, *row, *col), &result);
+        crate::process_ui_effects(state, sheet, *row, *col, engine::take_ui_effects());
+        let result = stored_result(result, styles.get(cell.style_index), precision_as_displayed);
+        if result == cell.value {
+            continue;
         }
+        let mut updated = cell.clone();
+        updated.value = result;
+        grids[sheet].set_cell(*row, *col, updated.clone());
+
+        let style = styles.get(updated.style_index);
+        let display = format_cell_value(&updated.value, style, &locale);
+        updated_cells.push(CellData {
+            row: *row,
+            col: *col,
+            display,
+            display_color: None,
+            formula: updated.formula_string().map(|f| format!("={}", f)),
+            style_index: updated.style_index,
+            row_span: 1,
+            col_span: 1,
+            sheet_index: None,
+            rich_text: None,
+            accounting_layout: None,
+            error_detail: None,
+        });
     }
 
-    for group in &circular_groups {
-        if !iteration_enabled {
-            for (row, col, _formula) in group {
-                if let Some(cell) = grids[sheet_index].get_cell(*row, *col) {
-                    let mut updated = cell.clone();
-                    updated.value = engine::CellValue::Error(engine::CellError::Circular);
-                    grids[sheet_index].set_cell(*row, *col, updated.clone());
-                    if sheet_index == active_sheet {
-                        grid_mirror.set_cell(*row, *col, updated);
-                    }
-                }
-            }
-        } else {
-            for _iteration in 0..max_iterations {
-                let mut max_delta: f64 = 0.0;
-                for (row, col, formula) in group {
-                    let old_value = grids[sheet_index].get_cell(*row, *col)
-                        .map(|c| cell_value_as_f64(&c.value))
-                        .unwrap_or(0.0);
-                    let new_result = evaluate_single_formula(
-                        *row, *col, formula,
-                        &grids, &sheet_names, sheet_index,
-                        &styles, &user_files, &pivot_data_fn, &gather_fn,
-                        &tables_map, &table_names_map, &named_ranges_map,
-                        &row_heights, &column_widths,
-                        None,
-                        control_values.as_ref(),
-                    );
-                    let new_numeric = cell_value_as_f64(&new_result);
-                    if let Some(cell) = grids[sheet_index].get_cell(*row, *col) {
-                        let mut updated = cell.clone();
-                        updated.value = new_result;
-                        grids[sheet_index].set_cell(*row, *col, updated.clone());
-                        if sheet_index == active_sheet {
-                            grid_mirror.set_cell(*row, *col, updated);
-                        }
-                    }
-                    let delta = (new_numeric - old_value).abs();
-                    if delta > max_delta {
-                        max_delta = delta;
-                    }
-                }
-                if max_delta < max_change {
-                    break;
-                }
-            }
+    {
+        let mut dirty = state.dirty_cells.lock().unwrap();
+        for (row, col) in order {
+            dirty.remove(&(sheet, *row, *col));
         }
     }
+
+    updated_cells
 }
 
-/// Recalculate all formula cells in the current sheet (same as calculate_now for single-sheet)
-#[tauri::command]
-pub fn calculate_sheet(state: State<AppState>, user_files_state: State<UserFilesState>, pivot_state: State<'_, PivotState>, pane_control_state: State<'_, crate::pane_control::PaneControlState>, ribbon_filter_state: State<'_, crate::ribbon_filter::RibbonFilterState>) -> Result<Vec<CellData>, String> {
-    log_enter_info!("CMD", "calculate_sheet");
+/// Recalculate everything downstream of `changed` cells on the active sheet
+/// after a bulk edit that wrote no formulas (clearing a range): one pass over
+/// the combined order instead of a cascade per cell. Sheets whose formulas
+/// read the cells are recalculated once each. In manual mode the dependents
+/// are only marked dirty. Returns the active sheet's cells whose value changed.
+pub(crate) fn recalc_dependents_of(
+    state: &AppState,
+    user_files_state: &UserFilesState,
+    pivot_state: &PivotState,
+    control_states: Option<(&crate::pane_control::PaneControlState, &crate::ribbon_filter::RibbonFilterState)>,
+    changed: &[(u32, u32)],
+) -> Vec<CellData> {
+    if changed.is_empty() {
+        return Vec::new();
+    }
+    let active_sheet = *state.active_sheet.lock().unwrap();
+    let sheet_name = state.sheet_names.lock().unwrap().get(active_sheet).cloned().unwrap_or_default();
+    let (mut order, cross_sheet) = {
+        let dependents = state.dependents.lock().unwrap();
+        let column_dependents = state.column_dependents.lock().unwrap();
+        let row_dependents = state.row_dependents.lock().unwrap();
+        let cross_sheet_dependents = state.cross_sheet_dependents.lock().unwrap();
+        let order = seeds_recalc_order(changed, false, &dependents, &column_dependents, &row_dependents);
+        let mut cross_sheet: Vec<(usize, u32, u32)> = Vec::new();
+        for &(row, col) in changed.iter().chain(&order) {
+            if let Some(deps) = cross_sheet_dependents.get(&(sheet_name.clone(), row, col)) {
+                cross_sheet.extend(deps.iter().copied());
+            }
+        }
+        (order, cross_sheet)
+    };
+    // Explicitly sheet-qualified references to the same sheet
+    for &(sheet, row, col) in &cross_sheet {
+        if sheet == active_sheet && !order.contains(&(row, col)) {
+            order.push((row, col));
+        }
+    }
 
-    // For now, calculate_sheet does the same as calculate_now since we have a single sheet
-    let result = calculate_now(state, user_files_state, pivot_state, pane_control_state, ribbon_filter_state, None);
+    if !state.calculation_settings.lock().unwrap().mode.is_automatic() {
+        let mut dirty = state.dirty_cells.lock().unwrap();
+        dirty.extend(order.iter().map(|&(row, col)| (active_sheet, row, col)));
+        dirty.extend(cross_sheet);
+        return Vec::new();
+    }
 
-    log_exit_info!("CMD", "calculate_sheet", "done");
-    result
+    let control_values = crate::control_values::build_control_values_from_states(state, control_states)
+        .unwrap_or_default();
+    let updated_cells = recalculate_in_order(state, user_files_state, pivot_state, &control_values, active_sheet, &order);
+
+    let mut other_sheets: Vec<usize> =
+        cross_sheet.iter().map(|&(sheet, _, _)| sheet).filter(|&sheet| sheet != active_sheet).collect();
+    other_sheets.sort_unstable();
+    other_sheets.dedup();
+    for sheet in other_sheets {
+        recalculate_sheet_values(state, user_files_state, pivot_state, sheet, control_states);
+    }
+    updated_cells
 }
 
 // ============================================================================
-// PRECISION AS DISPLAYED
+// PRECISION AS DISPLAYED / CALCULATE BEFORE SAVE
 // ============================================================================
 
 #[tauri::command]
 pub fn get_precision_as_displayed(state: State<AppState>) -> bool {
-    *state.precision_as_displayed.lock().unwrap()
+    state.calculation_settings.lock().unwrap().precision_as_displayed
 }
 
 #[tauri::command]
 pub fn set_precision_as_displayed(state: State<AppState>, enabled: bool) -> bool {
-    *state.precision_as_displayed.lock().unwrap() = enabled;
+    let update = CalculationSettingsUpdate { precision_as_displayed: Some(enabled), ..Default::default() };
+    let _ = update_calculation_settings_internal(&state, update);
     enabled
 }
 
-// ============================================================================
-// CALCULATE BEFORE SAVE
-// ============================================================================
-
 #[tauri::command]
 pub fn get_calculate_before_save(state: State<AppState>) -> bool {
-    *state.calculate_before_save.lock().unwrap()
+    state.calculation_settings.lock().unwrap().recalc_before_save
 }
 
 #[tauri::command]
 pub fn set_calculate_before_save(state: State<AppState>, enabled: bool) -> bool {
-    *state.calculate_before_save.lock().unwrap() = enabled;
+    let update = CalculationSettingsUpdate { recalc_before_save: Some(enabled), ..Default::default() };
+    let _ = update_calculation_settings_internal(&state, update);
     enabled
 }
@@ -5,6 +5,7 @@ use serde::{Deserialize, Serialize};
 use tauri::State;
 
 use crate::AppState;
+use crate::sheet_access::check_sheet_indices;
 use crate::bi::types::BiState;
 
 use calp::manifest::SubscriptionManifest;
@@ -212,7 +213,7 @@ struct PublishAssembly {
 }
 
 /// Build the publish carrier. ONE collector — the same enriched builder as the
-/// .cala save path (active-sheet mirror content, notes/hyperlinks/hidden rows/
+/// .cala save path (live sheet content, notes/hyperlinks/hidden rows/
 /// page setup, CF/DV, controls, charts, sparklines, tables, named ranges,
 /// slicers, ribbon filters, theme, extension data) — so package fidelity
 /// automatically tracks file fidelity. Core publish writes the subset the
@@ -243,11 +244,7 @@ fn assemble_publish_workbook(
     workbook.pane_controls =
         crate::persistence::collect_pane_controls_for_save(pane_control_state);
 
-    for &idx in sheet_indices {
-        if idx >= workbook.sheets.len() {
-            return Err(format!("Sheet index {} out of range", idx));
-        }
-    }
+    check_sheet_indices(workbook.sheets.len(), sheet_indices)?;
 
     // Standalone module scripts / notebooks live in ScriptState, not AppState.
     // With these present, the publish request's None ("all from the workbook")
@@ -1296,10 +1293,25 @@ fn materialize_pulled_sheet_state(
         }
     }
     {
-        let mut v = state.show_gridlines.lock().map_err(|e| e.to_string())?;
+        let mut v = state.sheet_view_options.lock().map_err(|e| e.to_string())?;
         for (idx, p) in &targets {
-            ensure_slot(&mut v, *idx, true);
-            v[*idx] = p.show_gridlines;
+            ensure_slot(&mut v, *idx, crate::api_types::SheetViewOptions::default());
+            v[*idx] = crate::api_types::SheetViewOptions {
+                zoom: p.zoom,
+                show_gridlines: p.show_gridlines,
+                show_headings: p.show_headings,
+                right_to_left: p.right_to_left,
+            };
+        }
+    }
+    {
+        let mut v = state.sheet_defaults.lock().map_err(|e| e.to_string())?;
+        for (idx, p) in &targets {
+            ensure_slot(&mut v, *idx, crate::api_types::SheetDefaults::default());
+            v[*idx] = crate::api_types::SheetDefaults {
+                default_col_width: p.default_column_width,
+                default_row_height: p.default_row_height,
+            };
         }
     }
     {
@@ -1358,6 +1370,17 @@ fn materialize_pulled_sheet_state(
             };
         }
     }
+    {
+        // Print area / titles are the sheet's built-in print names.
+        let setups: Vec<(usize, crate::api_types::PageSetup)> = {
+            let page_setups = state.page_setups.lock().map_err(|e| e.to_string())?;
+            targets.iter().map(|(idx, _)| (*idx, page_setups[*idx].clone())).collect()
+        };
+        let mut names = state.named_ranges.lock().map_err(|e| e.to_string())?;
+        for (idx, setup) in &setups {
+            crate::commands::print::adopt_page_setup_print_names(&mut names, *idx, setup);
+        }
+    }
     {
         let mut notes_storage = state.notes.lock().map_err(|e| e.to_string())?;
         for (idx, p) in &targets {
@@ -1416,6 +1439,8 @@ fn materialize_pulled_sheet_state(
                         internal_ref: None,
                         display_text: h.display_text.clone(),
                         tooltip: h.tooltip.clone(),
+                        broken: false,
+                        from_formula: false,
                     },
                 );
             }
@@ -3102,6 +3127,7 @@ fn write_override_value(grid: &mut engine::Grid, row: u32, col: u32, value: &cal
                 value: cell_value,
                 style_index,
                 rich_text: None,
+                quote_prefix: false,
             });
         }
         calp::OverrideValue::Formula { formula } => {
@@ -3112,6 +3138,7 @@ fn write_override_value(grid: &mut engine::Grid, row: u32, col: u32, value: &cal
                         value: engine::CellValue::Empty,
                         style_index,
                         rich_text: None,
+                        quote_prefix: false,
                     });
                 }
                 Err(_) => {
@@ -3124,6 +3151,7 @@ fn write_override_value(grid: &mut engine::Grid, row: u32, col: u32, value: &cal
                         value: engine::CellValue::Text(format!("={}", formula)),
                         style_index,
                         rich_text: None,
+                        quote_prefix: false,
                     });
                 }
             }
@@ -3174,13 +3202,6 @@ fn apply_override_value_to_grid(
         }
     }
 
-    // Keep the active-sheet mirror in sync.
-    let active = state.active_sheet.lock().map(|a| *a).unwrap_or(usize::MAX);
-    if active == sheet_index {
-        if let Ok(mut grid) = state.grid.lock() {
-            write_override_value(&mut grid, position.0, position.1, value);
-        }
-    }
     true
 }
 
@@ -3660,7 +3681,7 @@ pub fn calp_refresh_apply(
     }
 
     // Materialize new/updated sheets into grids.
-    let active_grid_after_materialize = {
+    {
         let mut grids = state.grids.lock().map_err(|e| e.to_string())?;
         let mut sheet_names = state.sheet_names.lock().map_err(|e| e.to_string())?;
         let mut sheet_ids = state.sheet_ids.lock().map_err(|e| e.to_string())?;
@@ -3709,6 +3730,7 @@ pub fn calp_refresh_apply(
                         let local_sid = sub.sheets[pos].local_sheet_id;
                         if let Some(grid_idx) = sheet_ids.iter().position(|id| *id == local_sid) {
                             grids[grid_idx] = grid;
+                            grids[grid_idx].mark_all_changed();
                             all_cw[grid_idx] = pulled.sheet.column_widths.clone();
                             all_rh[grid_idx] = pulled.sheet.row_heights.clone();
                         }
@@ -3723,39 +3745,6 @@ pub fn calp_refresh_apply(
                 }
             }
         }
-
-        // Snapshot the active sheet ONLY when it was actually refreshed.
-        // state.grid is the authoritative mirror for the active sheet and
-        // grids[active] can legitimately lag behind it (BUG-0016) — an
-        // unconditional sync would regress unrefreshed active-sheet content.
-        // (sheet_ids and subs are the guards already held by this block.)
-        let active = *state.active_sheet.lock().map_err(|e| e.to_string())?;
-        let active_was_refreshed = sheet_ids.get(active).map_or(false, |active_sid| {
-            payloads.iter().any(|payload| {
-                let sub = match subs.subscriptions.get(payload.subscription_index) {
-                    Some(s) => s,
-                    None => return false,
-                };
-                payload.pull_result.sheets.iter().any(|pulled| {
-                    sub.sheets.iter().any(|s| {
-                        s.package_sheet_id == pulled.package_sheet_id
-                            && s.local_sheet_id == *active_sid
-                    })
-                })
-            })
-        });
-        if active_was_refreshed {
-            grids.get(active).cloned()
-        } else {
-            None
-        }
-    };
-
-    // Sync the active-sheet mirror: state.grid is the read path for the
-    // active sheet, and calculate_now copies it back over grids[active] —
-    // without this sync a refreshed active sheet reverts on the next recalc.
-    if let Some(grid) = active_grid_after_materialize {
-        *state.grid.lock().map_err(|e| e.to_string())? = grid;
     }
 
     // Map each refreshed package sheet id -> its LOCAL sheet index, so named
@@ -5013,6 +5002,7 @@ pub fn calp_dev_refresh(state: State<AppState>, window: tauri::Window) -> Result
                 // Replace the existing grid in-place.
                 if let Some(grid_idx) = sheet_ids.iter().position(|id| *id == local_sid) {
                     grids[grid_idx] = grid;
+                    grids[grid_idx].mark_all_changed();
                     all_cw[grid_idx] = pulled.sheet.column_widths.clone();
                     all_rh[grid_idx] = pulled.sheet.row_heights.clone();
                     map.insert(pulled.source_sheet_id, grid_idx);
@@ -8794,6 +8784,11 @@ fn restore_pulled_pivots(
                 def.destination_sheet = Some(renamed.clone());
             }
         }
+        if let Some(ref source) = def.source_sheet {
+            if let Some(renamed) = sheet_rename_map.get(source) {
+                def.source_sheet = Some(renamed.clone());
+            }
+        }
 
         // For BI pivots, ensure the source display shows the model name, not a grid range
         if saved.source_type == "bi" && def.source_range_display.is_none() {
@@ -8838,7 +8833,6 @@ fn restore_pulled_pivots(
         if let Some(dest_grid) = grids.get_mut(dest_sheet_idx) {
             let _merged = write_pivot_to_grid(
                 dest_grid,
-                None, // no active_grid dual-write needed
                 &view,
                 def.destination,
                 &mut shared_styles,
@@ -9704,6 +9698,11 @@ pub fn calp_reset_subscription(
                         def.destination_sheet = Some(local.clone());
                     }
                 }
+                if let Some(ref source) = def.source_sheet {
+                    if let Some(local) = pkg_name_to_local.get(source) {
+                        def.source_sheet = Some(local.clone());
+                    }
+                }
                 Some((def.id, def))
             })
             .collect()
@@ -9779,6 +9778,7 @@ pub fn calp_reset_subscription(
             }
             if *idx < grids.len() {
                 grids[*idx] = grid;
+                grids[*idx].mark_all_changed();
             }
             if *idx < all_cw.len() {
                 all_cw[*idx] = pulled.sheet.column_widths.clone();
@@ -9788,16 +9788,10 @@ pub fn calp_reset_subscription(
             }
         }
     }
-    // Sync the active-sheet mirrors (grid, widths, heights) if the active
-    // sheet was among the reset sheets — the mirrors are the live copies while
-    // a sheet is active; its all_* slots are shadowed.
-    if let Some((idx, _, pulled)) = targets.iter().find(|(idx, _, _)| *idx == active_idx) {
-        {
-            let grids = state.grids.lock().map_err(|e| e.to_string())?;
-            if let Some(grid) = grids.get(*idx) {
-                *state.grid.lock().map_err(|e| e.to_string())? = grid.clone();
-            }
-        }
+    // Sync the active-sheet dimension mirrors if the active sheet was among
+    // the reset sheets — they are the live copies while a sheet is active;
+    // its all_* slots are shadowed.
+    if let Some((_, _, pulled)) = targets.iter().find(|(idx, _, _)| *idx == active_idx) {
         *state.column_widths.lock().map_err(|e| e.to_string())? =
             pulled.sheet.column_widths.clone();
         *state.row_heights.lock().map_err(|e| e.to_string())? =
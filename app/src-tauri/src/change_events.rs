@@ -0,0 +1,305 @@
+//! FILENAME: app/src-tauri/src/change_events.rs
+//! PURPOSE: Push-based change notifications for state the frontend cannot see
+//! in a command's direct response (cross-sheet recalculation results,
+//! dimension changes from computed properties, object regions, calc state).
+//!
+//! Producers collect changes into a `ChangeBatch` and flush it once at the
+//! end of the operation, so a 10k-cell recalc becomes ONE `cells-changed`
+//! event per sheet instead of 10k. Every emitted event carries a
+//! process-wide sequence number; a gap on the frontend means an event was
+//! missed and the viewport should be resynced (`get_change_sequence`).
+
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use crate::api_types::CellData;
+
+pub const CELLS_CHANGED: &str = "cells-changed";
+pub const DIMENSIONS_CHANGED: &str = "dimensions-changed";
+pub const REGIONS_CHANGED: &str = "regions-changed";
+pub const CALC_STATE_CHANGED: &str = "calc-state-changed";
+
+/// Last sequence number handed out (0 = nothing emitted yet).
+static SEQUENCE: AtomicU64 = AtomicU64::new(0);
+
+/// App handle for emission from sync call sites. Installed once from `run()`.
+static CHANGE_EVENTS_APP: std::sync::OnceLock<tauri::AppHandle> = std::sync::OnceLock::new();
+
+/// Install the app handle. Until this runs (and in unit tests) flushing only
+/// assigns sequence numbers and returns the events.
+pub fn set_app_handle(app: tauri::AppHandle) {
+    let _ = CHANGE_EVENTS_APP.set(app);
+}
+
+fn next_sequence() -> u64 {
+    SEQUENCE.fetch_add(1, Ordering::SeqCst) + 1
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CellsChangedPayload {
+    pub seq: u64,
+    pub sheet_index: usize,
+    pub cells: Vec<CellData>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DimensionsChangedPayload {
+    pub seq: u64,
+    pub sheet_index: usize,
+    pub dimensions: Vec<crate::api_types::DimensionData>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RegionsChangedPayload {
+    pub seq: u64,
+    pub sheet_index: usize,
+    /// Region kinds touched on the sheet (e.g. "pivot", "report", "merge").
+    pub kinds: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CalcStateChangedPayload {
+    pub seq: u64,
+    /// "done", "calculating" or "pending" (same vocabulary as
+    /// get_calculation_state).
+    pub state: String,
+}
+
+/// One flushed event, ready to emit.
+#[derive(Debug, Clone)]
+pub enum ChangeEvent {
+    Cells(CellsChangedPayload),
+    Dimensions(DimensionsChangedPayload),
+    Regions(RegionsChangedPayload),
+    CalcState(CalcStateChangedPayload),
+}
+
+impl ChangeEvent {
+    pub fn name(&self) -> &'static str {
+        match self {
+            ChangeEvent::Cells(_) => CELLS_CHANGED,
+            ChangeEvent::Dimensions(_) => DIMENSIONS_CHANGED,
+            ChangeEvent::Regions(_) => REGIONS_CHANGED,
+            ChangeEvent::CalcState(_) => CALC_STATE_CHANGED,
+        }
+    }
+
+    pub fn seq(&self) -> u64 {
+        match self {
+            ChangeEvent::Cells(p) => p.seq,
+            ChangeEvent::Dimensions(p) => p.seq,
+            ChangeEvent::Regions(p) => p.seq,
+            ChangeEvent::CalcState(p) => p.seq,
+        }
+    }
+}
+
+/// Accumulates changes for one operation. Cells are grouped per sheet and
+/// de-duplicated (last write wins); nothing is emitted until `flush`.
+#[derive(Debug, Default)]
+pub struct ChangeBatch {
+    cells: BTreeMap<usize, BTreeMap<(u32, u32), CellData>>,
+    dimensions: BTreeMap<usize, Vec<crate::api_types::DimensionData>>,
+    regions: BTreeMap<usize, Vec<String>>,
+    calc_state: Option<String>,
+}
+
+impl ChangeBatch {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cells.is_empty()
+            && self.dimensions.is_empty()
+            && self.regions.is_empty()
+            && self.calc_state.is_none()
+    }
+
+    pub fn push_cell(&mut self, sheet_index: usize, cell: CellData) {
+        self.cells
+            .entry(sheet_index)
+            .or_default()
+            .insert((cell.row, cell.col), cell);
+    }
+
+    /// Queue the cells of a command response that belong to sheets OTHER than
+    /// `active_sheet` — the ones the frontend's active-sheet response handler
+    /// ignores. Cells without a sheet index are on the active sheet.
+    pub fn push_off_sheet_cells(&mut self, active_sheet: usize, cells: &[CellData]) {
+        for cell in cells {
+            if let Some(sheet) = cell.sheet_index {
+                if sheet != active_sheet {
+                    self.push_cell(sheet, cell.clone());
+                }
+            }
+        }
+    }
+
+    pub fn push_dimensions(&mut self, sheet_index: usize, dims: Vec<crate::api_types::DimensionData>) {
+        if !dims.is_empty() {
+            self.dimensions.entry(sheet_index).or_default().extend(dims);
+        }
+    }
+
+    pub fn push_region(&mut self, sheet_index: usize, kind: &str) {
+        let kinds = self.regions.entry(sheet_index).or_default();
+        if !kinds.iter().any(|k| k == kind) {
+            kinds.push(kind.to_string());
+        }
+    }
+
+    pub fn set_calc_state(&mut self, state: &str) {
+        self.calc_state = Some(state.to_string());
+    }
+
+    /// Turn the batch into events (one per sheet and kind), assigning
+    /// sequence numbers in emission order, and emit them when the app handle
+    /// is installed. Returns the events for callers/tests.
+    pub fn flush(self) -> Vec<ChangeEvent> {
+        let mut events = Vec::new();
+        for (sheet_index, cells) in self.cells {
+            events.push(ChangeEvent::Cells(CellsChangedPayload {
+                seq: next_sequence(),
+                sheet_index,
+                cells: cells.into_values().collect(),
+            }));
+        }
+        for (sheet_index, dimensions) in self.dimensions {
+            events.push(ChangeEvent::Dimensions(DimensionsChangedPayload {
+                seq: next_sequence(),
+                sheet_index,
+                dimensions,
+            }));
+        }
+        for (sheet_index, kinds) in self.regions {
+            events.push(ChangeEvent::Regions(RegionsChangedPayload {
+                seq: next_sequence(),
+                sheet_index,
+                kinds,
+            }));
+        }
+        if let Some(state) = self.calc_state {
+            events.push(ChangeEvent::CalcState(CalcStateChangedPayload {
+                seq: next_sequence(),
+                state,
+            }));
+        }
+
+        if let Some(app) = CHANGE_EVENTS_APP.get() {
+            use tauri::Emitter;
+            for event in &events {
+                let _ = match event {
+                    ChangeEvent::Cells(p) => app.emit(CELLS_CHANGED, p),
+                    ChangeEvent::Dimensions(p) => app.emit(DIMENSIONS_CHANGED, p),
+                    ChangeEvent::Regions(p) => app.emit(REGIONS_CHANGED, p),
+                    ChangeEvent::CalcState(p) => app.emit(CALC_STATE_CHANGED, p),
+                };
+            }
+        }
+        events
+    }
+}
+
+/// Sequence number of the most recently emitted change event. The frontend
+/// compares it with the last `seq` it received to detect missed events.
+#[tauri::command]
+pub fn get_change_sequence() -> u64 {
+    SEQUENCE.load(Ordering::SeqCst)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cell(sheet_index: Option<usize>, row: u32, col: u32, display: &str) -> CellData {
+        CellData {
+            row,
+            col,
+            display: display.to_string(),
+            display_color: None,
+            formula: None,
+            style_index: 0,
+            row_span: 1,
+            col_span: 1,
+            sheet_index,
+            rich_text: None,
+            accounting_layout: None,
+            error_detail: None,
+        }
+    }
+
+    /// A real edit of Sheet1!A1 (active) where Sheet2!B1 = Sheet1!A1 * 2 and
+    /// Sheet3!A1 = Sheet2!B1 + 1: the cascade crosses into both sheets, and
+    /// each becomes one cells-changed event; the active-sheet edit does not.
+    #[test]
+    fn test_cross_sheet_cascade_emits_one_event_per_other_sheet() {
+        use crate::commands::data::{flush_edit_changes, update_cell_impl};
+        use crate::sheets::{add_sheet_internal, set_active_sheet_internal};
+        let state = crate::create_app_state();
+        let file_state = crate::persistence::FileState::default();
+        let user_files = crate::persistence::UserFilesState { files: std::sync::Mutex::new(std::collections::HashMap::new()) };
+        let slicer = crate::slicer::SlicerState::new();
+        let pivot = crate::pivot::types::PivotState::new();
+        let pane = crate::pane_control::PaneControlState::new();
+        let ribbon = crate::ribbon_filter::RibbonFilterState::new();
+        let edit = |sheet: usize, row: u32, col: u32, value: &str| {
+            set_active_sheet_internal(&state, sheet).unwrap();
+            update_cell_impl(&state, &file_state, &user_files, &slicer, &pivot, &pane, &ribbon, row, col, value.to_string(), None, None)
+                .unwrap()
+        };
+        add_sheet_internal(&state, Some("Sheet2".to_string())).unwrap();
+        add_sheet_internal(&state, Some("Sheet3".to_string())).unwrap();
+        edit(0, 0, 0, "1");
+        edit(1, 0, 1, "=Sheet1!A1*2");
+        edit(2, 0, 0, "=Sheet2!B1+1");
+
+        let result = edit(0, 0, 0, "5");
+        let events = flush_edit_changes(&state, &result.cells);
+
+        let got: Vec<(usize, Vec<(u32, u32, &str)>)> = events
+            .iter()
+            .map(|event| match event {
+                ChangeEvent::Cells(p) => {
+                    (p.sheet_index, p.cells.iter().map(|c| (c.row, c.col, c.display.as_str())).collect())
+                }
+                other => panic!("expected cells-changed, got {:?}", other),
+            })
+            .collect();
+        assert_eq!(got, vec![(1, vec![(0, 1, "10")]), (2, vec![(0, 0, "11")])]);
+        // Sequence numbers are consecutive within a flush.
+        assert_eq!(events[1].seq(), events[0].seq() + 1);
+        assert!(get_change_sequence() >= events[1].seq());
+    }
+
+    #[test]
+    fn test_large_recalc_is_batched_into_one_event() {
+        let mut batch = ChangeBatch::new();
+        for r in 0..10_000u32 {
+            batch.push_cell(2, cell(Some(2), r, 0, "x"));
+        }
+        // A re-evaluated cell appears once, with its final value.
+        batch.push_cell(2, cell(Some(2), 0, 0, "final"));
+        let events = batch.flush();
+        assert_eq!(events.len(), 1);
+        match &events[0] {
+            ChangeEvent::Cells(p) => {
+                assert_eq!(p.cells.len(), 10_000);
+                assert_eq!(p.cells[0].display, "final");
+            }
+            other => panic!("expected cells-changed, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_empty_batch_emits_nothing() {
+        let batch = ChangeBatch::new();
+        assert!(batch.is_empty());
+        assert!(batch.flush().is_empty());
+    }
+}
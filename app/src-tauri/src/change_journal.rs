@@ -0,0 +1,377 @@
+//! FILENAME: app/src-tauri/src/change_journal.rs
+//! PURPOSE: Opt-in change journal for the session ("who changed this value").
+//! When tracking is on, every committed undo transaction appends one entry
+//! per changed cell (old and new value/formula, the IPC command that
+//! committed it) to a bounded in-memory ring. Undo and redo append entries of
+//! their own instead of rewriting earlier ones.
+//! CONTEXT: A second consumer of the UndoStack's `CellChange`s: the stack
+//! logs committed transactions (`set_commit_log`) and `journal_invokes`
+//! drains them around every IPC command, reading the new values from the
+//! grid. Transactions committed outside a command (scripts, MCP, background
+//! tasks) are drained at the next invoke with no command name. Like undo,
+//! cell changes are attributed to the active sheet. Nothing is persisted.
+
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use engine::{Cell, CellChange};
+use serde::Serialize;
+use tauri::{Manager, State};
+
+use crate::pivot::utils::{col_index_to_letter, parse_cell_ref};
+use crate::sheets::SheetsResult;
+use crate::{format_cell_value, AppState};
+
+/// Ring size when `set_change_tracking` is not given one.
+pub const DEFAULT_JOURNAL_CAPACITY: usize = 1000;
+
+/// One cell change in the journal.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ChangeEntry {
+    /// Increases by one per entry for the whole session.
+    pub seq: u64,
+    /// RFC 3339, UTC.
+    pub timestamp: String,
+    pub sheet_index: usize,
+    pub sheet_name: String,
+    pub row: u32,
+    pub col: u32,
+    /// A1 address of the cell ("B3").
+    pub cell: String,
+    pub old_value: String,
+    pub old_formula: Option<String>,
+    pub new_value: String,
+    pub new_formula: Option<String>,
+    /// IPC command that committed the change ("undo"/"redo" for those);
+    /// None when it was committed outside a command.
+    pub command: Option<String>,
+    /// Undo description of the transaction ("Paste 10 cells").
+    pub description: String,
+}
+
+/// Tracking state returned by `set_change_tracking`.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ChangeTrackingState {
+    pub enabled: bool,
+    pub capacity: usize,
+    pub entry_count: usize,
+}
+
+/// The bounded journal. Entries stay queryable after tracking is turned off.
+#[derive(Debug)]
+pub struct ChangeJournal {
+    enabled: bool,
+    capacity: usize,
+    entries: VecDeque<ChangeEntry>,
+    next_seq: u64,
+}
+
+impl Default for ChangeJournal {
+    fn default() -> Self {
+        ChangeJournal {
+            enabled: false,
+            capacity: DEFAULT_JOURNAL_CAPACITY,
+            entries: VecDeque::new(),
+            next_seq: 1,
+        }
+    }
+}
+
+impl ChangeJournal {
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// Drop every entry (a new workbook session); tracking stays as it is.
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    fn tracking_state(&self) -> ChangeTrackingState {
+        ChangeTrackingState { enabled: self.enabled, capacity: self.capacity, entry_count: self.entries.len() }
+    }
+
+    fn push(&mut self, mut entry: ChangeEntry) {
+        entry.seq = self.next_seq;
+        self.next_seq += 1;
+        while self.entries.len() >= self.capacity {
+            self.entries.pop_front();
+        }
+        self.entries.push_back(entry);
+    }
+
+    /// The last `limit` entries matching the filters, oldest first.
+    pub fn query(&self, sheet: Option<usize>, cell: Option<(u32, u32)>, limit: Option<usize>) -> Vec<ChangeEntry> {
+        let matching: Vec<&ChangeEntry> = self
+            .entries
+            .iter()
+            .filter(|e| sheet.is_none_or(|s| e.sheet_index == s))
+            .filter(|e| cell.is_none_or(|(row, col)| e.row == row && e.col == col))
+            .collect();
+        let skip = limit.map(|n| matching.len().saturating_sub(n)).unwrap_or(0);
+        matching.into_iter().skip(skip).cloned().collect()
+    }
+}
+
+/// One side (old or new) of an entry: display value and formula.
+type CellSide = (String, Option<String>);
+
+fn describe_cell(cell: Option<&Cell>, styles: &engine::StyleRegistry, locale: &engine::LocaleSettings) -> CellSide {
+    match cell {
+        Some(cell) => (
+            format_cell_value(&cell.value, styles.get(cell.style_index), locale),
+            cell.formula_string().map(|f| format!("={}", f)),
+        ),
+        None => (String::new(), None),
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn new_entry(
+    sheet_index: usize,
+    sheet_name: &str,
+    row: u32,
+    col: u32,
+    old: CellSide,
+    new: CellSide,
+    command: Option<&str>,
+    description: &str,
+) -> ChangeEntry {
+    ChangeEntry {
+        seq: 0,
+        timestamp: chrono::Utc::now().to_rfc3339(),
+        sheet_index,
+        sheet_name: sheet_name.to_string(),
+        row,
+        col,
+        cell: format!("{}{}", col_index_to_letter(col), row + 1),
+        old_value: old.0,
+        old_formula: old.1,
+        new_value: new.0,
+        new_formula: new.1,
+        command: command.map(String::from),
+        description: description.to_string(),
+    }
+}
+
+fn active_sheet_name(state: &AppState, sheet_index: usize) -> String {
+    state
+        .sheet_names
+        .lock()
+        .unwrap()
+        .get(sheet_index)
+        .cloned()
+        .unwrap_or_else(|| format!("Sheet{}", sheet_index + 1))
+}
+
+/// Journal the transactions committed since the last call, crediting them to
+/// `command`. A cell changed several times in one transaction gets a single
+/// entry from its first previous state to its current one.
+pub(crate) fn record_committed(state: &AppState, command: Option<&str>) {
+    if !state.change_journal.lock().unwrap().is_enabled() {
+        return;
+    }
+    let transactions = state.undo_stack.lock().unwrap().take_committed();
+    if transactions.is_empty() {
+        return;
+    }
+
+    let sheet_index = *state.active_sheet.lock().unwrap();
+    let sheet_name = active_sheet_name(state, sheet_index);
+    let entries = {
+        let styles = state.style_registry.lock().unwrap();
+        let locale = state.locale.lock().unwrap();
+        let grid = state.get_active_grid();
+
+        let mut entries = Vec::new();
+        for transaction in &transactions {
+            let mut seen: HashSet<(u32, u32)> = HashSet::new();
+            for change in &transaction.changes {
+                let CellChange::SetCell { row, col, previous } = change else { continue };
+                if !seen.insert((*row, *col)) {
+                    continue;
+                }
+                entries.push(new_entry(
+                    sheet_index,
+                    &sheet_name,
+                    *row,
+                    *col,
+                    describe_cell(previous.as_ref(), &styles, &locale),
+                    describe_cell(grid.get_cell(*row, *col), &styles, &locale),
+                    command,
+                    &transaction.description,
+                ));
+            }
+        }
+        entries
+    };
+
+    let mut journal = state.change_journal.lock().unwrap();
+    for entry in entries {
+        journal.push(entry);
+    }
+}
+
+/// Journal an undo or redo as events of its own: `edits` are the restored
+/// cells as (row, col, before, after) on `sheet_index`, in the order they
+/// were applied. A cell restored several times gets one entry.
+pub(crate) fn record_undo_redo(
+    state: &AppState,
+    is_undo: bool,
+    description: &str,
+    sheet_index: usize,
+    edits: &[(u32, u32, Option<Cell>, Option<Cell>)],
+) {
+    if edits.is_empty() || !state.change_journal.lock().unwrap().is_enabled() {
+        return;
+    }
+    let command = if is_undo { "undo" } else { "redo" };
+    let sheet_name = active_sheet_name(state, sheet_index);
+
+    let mut merged: Vec<(u32, u32, &Option<Cell>, &Option<Cell>)> = Vec::new();
+    let mut position: HashMap<(u32, u32), usize> = HashMap::new();
+    for (row, col, before, after) in edits {
+        match position.get(&(*row, *col)) {
+            Some(&i) => merged[i].3 = after,
+            None => {
+                position.insert((*row, *col), merged.len());
+                merged.push((*row, *col, before, after));
+            }
+        }
+    }
+
+    let entries: Vec<ChangeEntry> = {
+        let styles = state.style_registry.lock().unwrap();
+        let locale = state.locale.lock().unwrap();
+        merged
+            .into_iter()
+            .map(|(row, col, before, after)| {
+                new_entry(
+                    sheet_index,
+                    &sheet_name,
+                    row,
+                    col,
+                    describe_cell(before.as_ref(), &styles, &locale),
+                    describe_cell(after.as_ref(), &styles, &locale),
+                    Some(command),
+                    description,
+                )
+            })
+            .collect()
+    };
+    let mut journal = state.change_journal.lock().unwrap();
+    for entry in entries {
+        journal.push(entry);
+    }
+}
+
+/// Wrap the IPC handler so that committed transactions are journaled with
+/// the command that committed them. Transactions pending before the command
+/// ran came from outside any command and are journaled without a name.
+pub fn journal_invokes<R: tauri::Runtime>(
+    handler: impl Fn(tauri::ipc::Invoke<R>) -> bool + Send + Sync + 'static,
+) -> impl Fn(tauri::ipc::Invoke<R>) -> bool + Send + Sync + 'static {
+    move |invoke| {
+        let webview = invoke.message.webview();
+        let command = invoke.message.command().to_string();
+        let state = webview.state::<AppState>();
+        record_committed(&state, None);
+        let handled = handler(invoke);
+        record_committed(&state, Some(&command));
+        handled
+    }
+}
+
+pub(crate) fn set_change_tracking_internal(state: &AppState, enabled: bool, capacity: Option<usize>) -> ChangeTrackingState {
+    state.undo_stack.lock().unwrap().set_commit_log(enabled);
+    let mut journal = state.change_journal.lock().unwrap();
+    journal.enabled = enabled;
+    if let Some(capacity) = capacity {
+        journal.capacity = capacity.max(1);
+        while journal.entries.len() > journal.capacity {
+            journal.entries.pop_front();
+        }
+    }
+    journal.tracking_state()
+}
+
+/// Turn the change journal on or off; `capacity` resizes the ring (oldest
+/// entries are dropped first).
+#[tauri::command]
+pub fn set_change_tracking(state: State<AppState>, enabled: bool, capacity: Option<usize>) -> ChangeTrackingState {
+    set_change_tracking_internal(&state, enabled, capacity)
+}
+
+pub(crate) fn get_change_history_internal(
+    state: &AppState,
+    sheet: Option<usize>,
+    cell: Option<String>,
+    limit: Option<usize>,
+) -> Result<Vec<ChangeEntry>, String> {
+    let cell = cell.map(|c| parse_cell_ref(&c)).transpose()?;
+    Ok(state.change_journal.lock().unwrap().query(sheet, cell, limit))
+}
+
+/// Journal entries, oldest first, optionally for one sheet and/or one cell
+/// ("B3") and limited to the most recent `limit`.
+#[tauri::command]
+pub fn get_change_history(
+    state: State<AppState>,
+    sheet: Option<usize>,
+    cell: Option<String>,
+    limit: Option<usize>,
+) -> Result<Vec<ChangeEntry>, String> {
+    get_change_history_internal(&state, sheet, cell, limit)
+}
+
+const HISTORY_HEADERS: [&str; 9] =
+    ["Time", "Sheet", "Cell", "Old Value", "Old Formula", "New Value", "New Formula", "Command", "Description"];
+
+pub(crate) fn create_history_sheet_internal(state: &AppState) -> Result<SheetsResult, String> {
+    let entries = state.change_journal.lock().unwrap().query(None, None, None);
+    let name = {
+        let names = state.sheet_names.lock().unwrap();
+        let mut candidate = "Change History".to_string();
+        let mut n = 2;
+        while names.iter().any(|existing| existing.eq_ignore_ascii_case(&candidate)) {
+            candidate = format!("Change History ({})", n);
+            n += 1;
+        }
+        candidate
+    };
+    let result = crate::sheets::add_sheet_internal(state, Some(name))?;
+
+    // The new sheet is active. Its content is a report, not an edit: it goes
+    // in without an undo transaction and so never reaches the journal.
+    let mut grid = state.get_active_grid();
+    for (col, header) in HISTORY_HEADERS.iter().enumerate() {
+        grid.set_cell(0, col as u32, Cell::new_text(header.to_string()));
+    }
+    for (i, entry) in entries.iter().enumerate() {
+        let row = i as u32 + 1;
+        let fields = [
+            entry.timestamp.clone(),
+            entry.sheet_name.clone(),
+            entry.cell.clone(),
+            entry.old_value.clone(),
+            entry.old_formula.clone().unwrap_or_default(),
+            entry.new_value.clone(),
+            entry.new_formula.clone().unwrap_or_default(),
+            entry.command.clone().unwrap_or_default(),
+            entry.description.clone(),
+        ];
+        for (col, text) in fields.into_iter().enumerate() {
+            if !text.is_empty() {
+                grid.set_cell(row, col as u32, Cell::new_text(text));
+            }
+        }
+    }
+    Ok(result)
+}
+
+/// Write the journal to a new "Change History" sheet (which becomes active).
+#[tauri::command]
+pub fn create_history_sheet(state: State<AppState>) -> Result<SheetsResult, String> {
+    create_history_sheet_internal(&state)
+}
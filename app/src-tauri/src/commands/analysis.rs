@@ -0,0 +1,603 @@
+//! FILENAME: app/src-tauri/src/commands/analysis.rs
+// PURPOSE: Per-column profiling of a range for Quick Analysis suggestions, and
+// chart-ready series extraction from a selection.
+// CONTEXT: The frontend recommends charts, pivots and conditional formats from
+// a cheap summary of the selection: per column, the detected value type,
+// counts, numeric stats and a capped distinct count, plus whether the first
+// row looks like a header. Everything comes from one pass over the sparse
+// cells inside the rectangle (`for_each_cell_in`), the same walk the pivot
+// cache builder uses to find its data extent and autofilter uses for column
+// averages.
+
+use std::collections::HashSet;
+
+use engine::{Cell, CellValue, Grid, StyleRegistry};
+use serde::{Deserialize, Serialize};
+use tauri::State;
+
+use crate::AppState;
+use crate::commands::dimensions::get_axis_metadata_internal;
+use crate::sheet_access::sheet_at;
+use crate::structural_change::Axis;
+
+/// Distinct values tracked per column before the count is reported as capped.
+pub const DISTINCT_CAP: usize = 1000;
+
+// ============================================================================
+// SHARED SCAN
+// ============================================================================
+
+/// Visits the stored cells inside the inclusive rectangle (clamped to the
+/// grid's used extent), walking whichever is smaller: the rectangle's
+/// positions or the grid's cell map. Visit order is unspecified.
+pub(crate) fn for_each_cell_in<'a>(
+    grid: &'a Grid,
+    start_row: u32,
+    start_col: u32,
+    end_row: u32,
+    end_col: u32,
+    mut visit: impl FnMut(u32, u32, &'a Cell),
+) {
+    let end_row = end_row.min(grid.max_row);
+    let end_col = end_col.min(grid.max_col);
+    if end_row < start_row || end_col < start_col {
+        return;
+    }
+    let area = (end_row - start_row + 1) as u64 * (end_col - start_col + 1) as u64;
+    if area <= grid.cells.len() as u64 {
+        for row in start_row..=end_row {
+            for col in start_col..=end_col {
+                if let Some(cell) = grid.get_cell(row, col) {
+                    visit(row, col, cell);
+                }
+            }
+        }
+    } else {
+        for (&(row, col), cell) in grid.cells.iter() {
+            if (start_row..=end_row).contains(&row) && (start_col..=end_col).contains(&col) {
+                visit(row, col, cell);
+            }
+        }
+    }
+}
+
+/// The last row inside the rectangle holding a stored cell, if any.
+pub(crate) fn last_stored_row(grid: &Grid, start_row: u32, start_col: u32, end_row: u32, end_col: u32) -> Option<u32> {
+    let mut last = None;
+    for_each_cell_in(grid, start_row, start_col, end_row, end_col, |row, _, _| {
+        last = last.max(Some(row));
+    });
+    last
+}
+
+/// Running count, sum, min and max of numeric values.
+#[derive(Debug, Clone, Copy, Default)]
+pub(crate) struct NumericStats {
+    pub count: u32,
+    pub sum: f64,
+    pub min: Option<f64>,
+    pub max: Option<f64>,
+}
+
+impl NumericStats {
+    pub fn add(&mut self, n: f64) {
+        self.count += 1;
+        self.sum += n;
+        self.min = Some(self.min.map_or(n, |m| m.min(n)));
+        self.max = Some(self.max.map_or(n, |m| m.max(n)));
+    }
+
+    fn merge(&mut self, other: &NumericStats) {
+        self.count += other.count;
+        self.sum += other.sum;
+        self.min = match (self.min, other.min) {
+            (Some(a), Some(b)) => Some(a.min(b)),
+            (a, b) => a.or(b),
+        };
+        self.max = match (self.max, other.max) {
+            (Some(a), Some(b)) => Some(a.max(b)),
+            (a, b) => a.or(b),
+        };
+    }
+
+    pub fn average(&self) -> Option<f64> {
+        (self.count > 0).then(|| self.sum / self.count as f64)
+    }
+}
+
+// ============================================================================
+// TYPES
+// ============================================================================
+
+/// The kind of values a column holds. Errors do not affect the type;
+/// booleans count as text.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ColumnValueType {
+    /// No values at all.
+    Empty,
+    Numeric,
+    Text,
+    /// Numbers shown through a date or time format.
+    Date,
+    /// More than one of numeric, text and date.
+    Mixed,
+}
+
+/// Profile of one column of the analyzed range (header row excluded).
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ColumnProfile {
+    pub col: u32,
+    /// Header text, when the first row was detected as a header.
+    pub header: Option<String>,
+    pub value_type: ColumnValueType,
+    /// Non-blank cells, errors included.
+    pub count: u32,
+    pub blanks: u32,
+    pub errors: u32,
+    /// Set for numeric and date columns.
+    pub min: Option<f64>,
+    /// Set for numeric and date columns.
+    pub max: Option<f64>,
+    /// Set for numeric columns.
+    pub sum: Option<f64>,
+    /// Set for numeric columns.
+    pub average: Option<f64>,
+    /// Distinct non-blank values (text compared case-insensitively), at most
+    /// `DISTINCT_CAP`.
+    pub distinct_count: u32,
+    /// The column has more distinct values than `DISTINCT_CAP`.
+    pub distinct_capped: bool,
+}
+
+/// Result of `analyze_range`.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RangeAnalysis {
+    pub sheet_index: usize,
+    pub start_row: u32,
+    pub start_col: u32,
+    /// Last row holding data (the requested end row is trimmed to it).
+    pub end_row: u32,
+    pub end_col: u32,
+    pub has_header: bool,
+    /// Rows below the header (or all rows when there is none).
+    pub data_rows: u32,
+    pub columns: Vec<ColumnProfile>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AnalyzeRangeParams {
+    /// Sheet to analyze; None = the active sheet.
+    #[serde(default)]
+    pub sheet_index: Option<usize>,
+    pub start_row: u32,
+    pub start_col: u32,
+    pub end_row: u32,
+    pub end_col: u32,
+}
+
+// ============================================================================
+// COLUMN ACCUMULATION
+// ============================================================================
+
+#[derive(Debug, Default)]
+struct ColumnAccumulator {
+    count: u32,
+    errors: u32,
+    numbers: NumericStats,
+    dates: NumericStats,
+    texts: u32,
+    distinct: HashSet<String>,
+    distinct_capped: bool,
+}
+
+impl ColumnAccumulator {
+    fn add(&mut self, cell: &Cell, styles: &StyleRegistry) {
+        let key = match &cell.value {
+            CellValue::Empty => return,
+            CellValue::Error(_) => {
+                self.count += 1;
+                self.errors += 1;
+                format!("e:{}", cell.display_value())
+            }
+            CellValue::Number(n) => {
+                self.count += 1;
+                if engine::is_date_format(&styles.get(cell.style_index).number_format) {
+                    self.dates.add(*n);
+                } else {
+                    self.numbers.add(*n);
+                }
+                format!("n:{}", n)
+            }
+            _ => {
+                self.count += 1;
+                self.texts += 1;
+                format!("t:{}", cell.display_value().to_lowercase())
+            }
+        };
+        self.insert_distinct(key);
+    }
+
+    fn insert_distinct(&mut self, key: String) {
+        if self.distinct_capped || self.distinct.contains(&key) {
+            return;
+        }
+        if self.distinct.len() == DISTINCT_CAP {
+            self.distinct_capped = true;
+        } else {
+            self.distinct.insert(key);
+        }
+    }
+
+    fn merge(&mut self, other: ColumnAccumulator) {
+        self.count += other.count;
+        self.errors += other.errors;
+        self.numbers.merge(&other.numbers);
+        self.dates.merge(&other.dates);
+        self.texts += other.texts;
+        self.distinct_capped |= other.distinct_capped;
+        for key in other.distinct {
+            self.insert_distinct(key);
+        }
+    }
+
+    fn value_type(&self) -> ColumnValueType {
+        let kinds = [
+            (self.numbers.count > 0, ColumnValueType::Numeric),
+            (self.texts > 0, ColumnValueType::Text),
+            (self.dates.count > 0, ColumnValueType::Date),
+        ];
+        let mut present = kinds.iter().filter(|(present, _)| *present);
+        match (present.next(), present.next()) {
+            (None, _) => ColumnValueType::Empty,
+            (Some((_, kind)), None) => *kind,
+            _ => ColumnValueType::Mixed,
+        }
+    }
+
+    fn into_profile(self, col: u32, header: Option<String>, data_rows: u32) -> ColumnProfile {
+        let value_type = self.value_type();
+        let (min, max, sum, average) = match value_type {
+            ColumnValueType::Numeric => {
+                (self.numbers.min, self.numbers.max, Some(self.numbers.sum), self.numbers.average())
+            }
+            ColumnValueType::Date => (self.dates.min, self.dates.max, None, None),
+            _ => (None, None, None, None),
+        };
+        ColumnProfile {
+            col,
+            header,
+            value_type,
+            count: self.count,
+            blanks: data_rows.saturating_sub(self.count),
+            errors: self.errors,
+            min,
+            max,
+            sum,
+            average,
+            distinct_count: self.distinct.len() as u32,
+            distinct_capped: self.distinct_capped,
+        }
+    }
+}
+
+// ============================================================================
+// ANALYSIS
+// ============================================================================
+
+/// Profiles the columns of the inclusive rectangle on `grid`.
+pub(crate) fn analyze_grid_range(
+    grid: &Grid,
+    styles: &StyleRegistry,
+    sheet_index: usize,
+    start_row: u32,
+    start_col: u32,
+    end_row: u32,
+    end_col: u32,
+) -> RangeAnalysis {
+    let width = (end_col - start_col + 1) as usize;
+    let mut first_row: Vec<Option<&Cell>> = vec![None; width];
+    let mut first_row_acc: Vec<ColumnAccumulator> = (0..width).map(|_| ColumnAccumulator::default()).collect();
+    let mut body: Vec<ColumnAccumulator> = (0..width).map(|_| ColumnAccumulator::default()).collect();
+    let mut last_row = None;
+
+    for_each_cell_in(grid, start_row, start_col, end_row, end_col, |row, col, cell| {
+        if matches!(cell.value, CellValue::Empty) {
+            return;
+        }
+        last_row = last_row.max(Some(row));
+        let i = (col - start_col) as usize;
+        if row == start_row {
+            first_row[i] = Some(cell);
+            first_row_acc[i].add(cell, styles);
+        } else {
+            body[i].add(cell, styles);
+        }
+    });
+
+    let Some(last_row) = last_row else {
+        return RangeAnalysis {
+            sheet_index,
+            start_row,
+            start_col,
+            end_row: start_row,
+            end_col,
+            has_header: false,
+            data_rows: 0,
+            columns: (0..width)
+                .map(|i| ColumnAccumulator::default().into_profile(start_col + i as u32, None, 0))
+                .collect(),
+        };
+    };
+
+    let has_header = last_row > start_row && looks_like_header(&first_row, &body);
+    let data_rows = last_row - start_row + u32::from(!has_header);
+    let columns = first_row_acc
+        .into_iter()
+        .zip(body)
+        .enumerate()
+        .map(|(i, (first, mut acc))| {
+            let header = if has_header {
+                first_row[i].map(Cell::display_value)
+            } else {
+                acc.merge(first);
+                None
+            };
+            acc.into_profile(start_col + i as u32, header, data_rows)
+        })
+        .collect();
+
+    RangeAnalysis {
+        sheet_index,
+        start_row,
+        start_col,
+        end_row: last_row,
+        end_col,
+        has_header,
+        data_rows,
+        columns,
+    }
+}
+
+/// The first row is a header when every cell in it is non-blank text and the
+/// rows below differ from it: some column holds numbers or dates, or — for
+/// all-text data — no header text reappears in its own column.
+fn looks_like_header(first_row: &[Option<&Cell>], body: &[ColumnAccumulator]) -> bool {
+    let all_text = first_row.iter().all(|cell| {
+        matches!(cell, Some(Cell { value: CellValue::Text(t), .. }) if !t.trim().is_empty())
+    });
+    if !all_text {
+        return false;
+    }
+    let typed_body = body
+        .iter()
+        .any(|acc| matches!(acc.value_type(), ColumnValueType::Numeric | ColumnValueType::Date | ColumnValueType::Mixed));
+    if typed_body {
+        return true;
+    }
+    first_row.iter().zip(body).all(|(cell, acc)| {
+        let text = cell.map(|c| c.display_value().to_lowercase()).unwrap_or_default();
+        !acc.distinct.contains(&format!("t:{}", text))
+    })
+}
+
+// ============================================================================
+// COMMAND
+// ============================================================================
+
+/// Profile the columns of a range for Quick Analysis suggestions.
+#[tauri::command]
+pub fn analyze_range(state: State<AppState>, params: AnalyzeRangeParams) -> Result<RangeAnalysis, String> {
+    analyze_range_internal(&state, params)
+}
+
+pub(crate) fn analyze_range_internal(state: &AppState, params: AnalyzeRangeParams) -> Result<RangeAnalysis, String> {
+    let active_sheet = *state.active_sheet.lock().unwrap();
+    let sheet_index = params.sheet_index.unwrap_or(active_sheet);
+    let start_row = params.start_row.min(params.end_row);
+    let start_col = params.start_col.min(params.end_col);
+    let end_row = params.start_row.max(params.end_row);
+    let end_col = params.start_col.max(params.end_col);
+
+    let grids = state.grids.lock().unwrap();
+    let styles = state.style_registry.lock().unwrap();
+    let target = sheet_at(&grids, sheet_index)?;
+    Ok(analyze_grid_range(target, &styles, sheet_index, start_row, start_col, end_row, end_col))
+}
+
+// ============================================================================
+// SERIES EXTRACTION
+// ============================================================================
+
+/// Which axis of the selection holds the series.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum SeriesOrientation {
+    /// Series along the axis with fewer entries (columns on a tie).
+    #[default]
+    Auto,
+    /// One series per row.
+    ByRows,
+    /// One series per column.
+    ByColumns,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExtractSeriesParams {
+    /// Sheet to read; None = the active sheet.
+    #[serde(default)]
+    pub sheet_index: Option<usize>,
+    pub start_row: u32,
+    pub start_col: u32,
+    pub end_row: u32,
+    pub end_col: u32,
+    #[serde(default)]
+    pub orientation: SeriesOrientation,
+    /// Manually hidden rows (manual hides live in the frontend). Filter- and
+    /// group-hidden rows are added from the backend's own stores.
+    #[serde(default)]
+    pub hidden_rows: Vec<u32>,
+    /// Manually hidden columns.
+    #[serde(default)]
+    pub hidden_cols: Vec<u32>,
+}
+
+/// One plotted series.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SeriesData {
+    /// The header cell's text, or "Series N" without a header.
+    pub name: String,
+    /// One entry per category; None for blank, text, boolean and error cells.
+    pub values: Vec<Option<f64>>,
+    /// Positions in `values` whose cell holds an error.
+    pub errors: Vec<u32>,
+}
+
+/// Result of `extract_series_data`.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SeriesExtraction {
+    pub sheet_index: usize,
+    /// The orientation used (never `Auto`).
+    pub orientation: SeriesOrientation,
+    /// Whether `categories` came from a label row/column of the selection;
+    /// otherwise they are the positions "1", "2", ...
+    pub has_category_labels: bool,
+    pub categories: Vec<String>,
+    pub series: Vec<SeriesData>,
+}
+
+/// A label line holds no numbers and at least one text cell.
+fn is_label_line<'a>(cells: impl Iterator<Item = Option<&'a Cell>>) -> bool {
+    let mut any_text = false;
+    for cell in cells.flatten() {
+        match cell.value {
+            CellValue::Empty => {}
+            CellValue::Text(_) => any_text = true,
+            _ => return false,
+        }
+    }
+    any_text
+}
+
+/// Splits the visible cells of a selection (`cells[row][col]`) into category
+/// labels and series. The first row and column are labels when textual; with
+/// both, the top-left corner belongs to neither.
+pub(crate) fn extract_series(
+    cells: &[Vec<Option<&Cell>>],
+    sheet_index: usize,
+    orientation: SeriesOrientation,
+) -> SeriesExtraction {
+    let rows = cells.len();
+    let cols = cells.first().map_or(0, Vec::len);
+    let mut header_row = rows > 1 && cols > 0 && is_label_line(cells[0].iter().skip(1).copied());
+    let mut header_col = cols > 1 && rows > 0 && is_label_line(cells.iter().skip(1).map(|row| row[0]));
+    let corner_is_value = cells
+        .first()
+        .and_then(|row| row.first().copied().flatten())
+        .is_some_and(|c| !matches!(c.value, CellValue::Empty | CellValue::Text(_)));
+    if corner_is_value && !(header_row && header_col) {
+        header_row = false;
+        header_col = false;
+    }
+    let (row_skip, col_skip) = (usize::from(header_row), usize::from(header_col));
+
+    let orientation = match orientation {
+        SeriesOrientation::Auto if cols - col_skip > rows - row_skip => SeriesOrientation::ByRows,
+        SeriesOrientation::Auto => SeriesOrientation::ByColumns,
+        other => other,
+    };
+    // Read through (series, point) coordinates so both orientations share one
+    // walk. `names`: the first point holds series names; `labels`: the first
+    // series line holds category labels.
+    let by_columns = orientation == SeriesOrientation::ByColumns;
+    let at = |series: usize, point: usize| if by_columns { cells[point][series] } else { cells[series][point] };
+    let (series_len, point_len) = if by_columns { (cols, rows) } else { (rows, cols) };
+    let (names, labels) = if by_columns { (header_row, header_col) } else { (header_col, header_row) };
+    let (series_skip, point_skip) = (usize::from(labels), usize::from(names));
+
+    let categories: Vec<String> = (point_skip..point_len)
+        .enumerate()
+        .map(|(i, point)| {
+            if labels {
+                at(0, point).map(Cell::display_value).unwrap_or_default()
+            } else {
+                (i + 1).to_string()
+            }
+        })
+        .collect();
+    let series = (series_skip..series_len)
+        .enumerate()
+        .map(|(i, s)| {
+            let name = if names {
+                at(s, 0).map(Cell::display_value).filter(|n| !n.is_empty())
+            } else {
+                None
+            };
+            let mut errors = Vec::new();
+            let values = (point_skip..point_len)
+                .enumerate()
+                .map(|(p, point)| match at(s, point).map(|c| &c.value) {
+                    Some(CellValue::Number(n)) => Some(*n),
+                    Some(CellValue::Error(_)) => {
+                        errors.push(p as u32);
+                        None
+                    }
+                    _ => None,
+                })
+                .collect();
+            SeriesData { name: name.unwrap_or_else(|| format!("Series {}", i + 1)), values, errors }
+        })
+        .collect();
+    SeriesExtraction { sheet_index, orientation, has_category_labels: labels, categories, series }
+}
+
+/// Category labels and numeric series of a selection, ready for a chart
+/// component. Hidden rows and columns (manual, filter and group) are left out.
+#[tauri::command]
+pub fn extract_series_data(state: State<AppState>, params: ExtractSeriesParams) -> Result<SeriesExtraction, String> {
+    extract_series_data_internal(&state, params)
+}
+
+pub(crate) fn extract_series_data_internal(
+    state: &AppState,
+    params: ExtractSeriesParams,
+) -> Result<SeriesExtraction, String> {
+    let active_sheet = *state.active_sheet.lock().unwrap();
+    let sheet_index = params.sheet_index.unwrap_or(active_sheet);
+    let start_row = params.start_row.min(params.end_row);
+    let start_col = params.start_col.min(params.end_col);
+    // Trim full-row/column selections to the used extent.
+    let (end_row, end_col) = {
+        let grids = state.grids.lock().unwrap();
+        let grid = sheet_at(&grids, sheet_index)?;
+        (
+            params.start_row.max(params.end_row).min(grid.max_row.max(start_row)),
+            params.start_col.max(params.end_col).min(grid.max_col.max(start_col)),
+        )
+    };
+
+    // Feature stores before the grids (canonical lock order).
+    let visible = |axis, start, end, manual| -> Result<Vec<u32>, String> {
+        Ok(get_axis_metadata_internal(state, sheet_index, axis, start, end, manual)?
+            .into_iter()
+            .filter(|entry| !entry.hidden)
+            .map(|entry| entry.index)
+            .collect())
+    };
+    let rows = visible(Axis::Rows, start_row, end_row, params.hidden_rows)?;
+    let cols = visible(Axis::Columns, start_col, end_col, params.hidden_cols)?;
+
+    let grids = state.grids.lock().unwrap();
+    let grid = sheet_at(&grids, sheet_index)?;
+    let cells: Vec<Vec<Option<&Cell>>> = rows
+        .iter()
+        .map(|&row| cols.iter().map(|&col| grid.get_cell(row, col)).collect())
+        .collect();
+    Ok(extract_series(&cells, sheet_index, params.orientation))
+}
@@ -0,0 +1,487 @@
+//! FILENAME: app/src-tauri/src/commands/consistency.rs
+// PURPOSE: Workbook consistency checker - cross-store checks that find state
+// which drifted out of step (entries keyed to deleted sheets, ranges past the
+// sheet edge, stale dependency edges, regions owned by deleted pivots) and
+// the auto-repairs for them.
+// CONTEXT: Every check reads one store at a time and reports what it found as
+// an issue naming the store and, where one exists, the fix that resolves it.
+// Repairs are cleanups of state nothing else refers to, so they are not
+// recorded for undo. Sheet names are the authority on how many sheets exist.
+
+use std::collections::{BTreeSet, HashMap, HashSet};
+
+use serde::{Deserialize, Serialize};
+use tauri::State;
+
+use crate::api_types::{PageSetup, SheetDefaults, SheetViewOptions};
+use crate::pivot::types::PivotState;
+use crate::{AppState, FreezeConfig, SplitConfig};
+
+/// Last valid row / column index (Excel's 1,048,576 x 16,384 sheet).
+const LAST_ROW: u32 = 1_048_575;
+const LAST_COL: u32 = 16_383;
+
+// ============================================================================
+// TYPES
+// ============================================================================
+
+/// How much a consistency issue matters.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum IssueSeverity {
+    /// State that features read and can misbehave on.
+    Error,
+    /// Dead state: harmless today, but it bloats saves and can resurface.
+    Warning,
+}
+
+/// An automatic repair `repair_workbook` can apply.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum FixId {
+    /// Drop entries keyed to deleted sheets, table-name registrations of
+    /// missing tables and protected regions of deleted pivots.
+    DropOrphans,
+    /// Put inverted ranges in order and cut ranges back to the sheet edge.
+    ClampRanges,
+    /// Rebuild the dependency maps from the formulas in the grids.
+    RebuildDependencies,
+    /// Pad or truncate per-sheet lists to one entry per sheet.
+    ResizeSheetStores,
+}
+
+/// One finding of `validate_workbook`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConsistencyIssue {
+    pub severity: IssueSeverity,
+    /// The AppState store the issue was found in, e.g. "conditionalFormats".
+    pub store: String,
+    pub description: String,
+    /// None when the issue needs a human decision.
+    pub suggested_fix: Option<FixId>,
+}
+
+/// Result of `validate_workbook`. An empty list means the workbook is consistent.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WorkbookValidation {
+    pub issues: Vec<ConsistencyIssue>,
+}
+
+/// Result of `repair_workbook`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RepairWorkbookResult {
+    /// Issues the applied fixes resolved, as validated just before.
+    pub fixed: Vec<ConsistencyIssue>,
+    /// What a validation right after the repair still reports.
+    pub remaining: Vec<ConsistencyIssue>,
+}
+
+fn issue(severity: IssueSeverity, store: &str, description: String, suggested_fix: Option<FixId>) -> ConsistencyIssue {
+    ConsistencyIssue { severity, store: store.to_string(), description, suggested_fix }
+}
+
+// ============================================================================
+// STORE ACCESS
+// ============================================================================
+
+/// A store keyed by sheet index.
+trait SheetKeyed {
+    /// Keys at or past `sheet_count`, ascending.
+    fn orphan_sheets(&self, sheet_count: usize) -> Vec<usize>;
+    fn drop_orphan_sheets(&mut self, sheet_count: usize);
+}
+
+impl<V> SheetKeyed for HashMap<usize, V> {
+    fn orphan_sheets(&self, sheet_count: usize) -> Vec<usize> {
+        let orphans: BTreeSet<usize> = self.keys().copied().filter(|&s| s >= sheet_count).collect();
+        orphans.into_iter().collect()
+    }
+
+    fn drop_orphan_sheets(&mut self, sheet_count: usize) {
+        self.retain(|&sheet, _| sheet < sheet_count);
+    }
+}
+
+/// Run `visit` over every sheet-keyed store, one lock at a time.
+fn for_each_sheet_keyed(state: &AppState, mut visit: impl FnMut(&str, &mut dyn SheetKeyed)) {
+    visit("tables", &mut *state.tables.lock().unwrap());
+    visit("conditionalFormats", &mut *state.conditional_formats.lock().unwrap());
+    visit("dataValidations", &mut *state.data_validations.lock().unwrap());
+    visit("comments", &mut *state.comments.lock().unwrap());
+    visit("notes", &mut *state.notes.lock().unwrap());
+    visit("hyperlinks", &mut *state.hyperlinks.lock().unwrap());
+    visit("autoFilters", &mut *state.auto_filters.lock().unwrap());
+    visit("advancedFilterHiddenRows", &mut *state.advanced_filter_hidden_rows.lock().unwrap());
+    visit("outlines", &mut *state.outlines.lock().unwrap());
+    visit("sheetProtection", &mut *state.sheet_protection.lock().unwrap());
+    visit("cellProtection", &mut *state.cell_protection.lock().unwrap());
+    visit("rangeProtection", &mut *state.range_protection.lock().unwrap());
+    visit("computedProperties", &mut *state.computed_properties.lock().unwrap());
+    visit("scenarios", &mut *state.scenarios.lock().unwrap());
+}
+
+/// Lengths of the per-sheet lists `ResizeSheetStores` can fix.
+fn sheet_aligned_lengths(state: &AppState) -> [(&'static str, usize); 9] {
+    [
+        ("freezeConfigs", state.freeze_configs.lock().unwrap().len()),
+        ("splitConfigs", state.split_configs.lock().unwrap().len()),
+        ("sheetViewOptions", state.sheet_view_options.lock().unwrap().len()),
+        ("sheetDefaults", state.sheet_defaults.lock().unwrap().len()),
+        ("pageSetups", state.page_setups.lock().unwrap().len()),
+        ("tabColors", state.tab_colors.lock().unwrap().len()),
+        ("sheetVisibility", state.sheet_visibility.lock().unwrap().len()),
+        ("scrollAreas", state.scroll_areas.lock().unwrap().len()),
+        ("sheetIds", state.sheet_ids.lock().unwrap().len()),
+    ]
+}
+
+/// Whether a range is inverted or reaches past the sheet edge.
+fn range_invalid(start_row: u32, start_col: u32, end_row: u32, end_col: u32) -> bool {
+    start_row > end_row || start_col > end_col || end_row > LAST_ROW || end_col > LAST_COL
+}
+
+/// Order an inverted range and cut it back to the sheet edge.
+fn clamp_range(start_row: &mut u32, start_col: &mut u32, end_row: &mut u32, end_col: &mut u32) {
+    if *start_row > *end_row {
+        std::mem::swap(start_row, end_row);
+    }
+    if *start_col > *end_col {
+        std::mem::swap(start_col, end_col);
+    }
+    *start_row = (*start_row).min(LAST_ROW);
+    *start_col = (*start_col).min(LAST_COL);
+    *end_row = (*end_row).min(LAST_ROW);
+    *end_col = (*end_col).min(LAST_COL);
+}
+
+fn describe_range(start_row: u32, start_col: u32, end_row: u32, end_col: u32) -> String {
+    let one_based = |i: u32| u64::from(i) + 1;
+    format!("rows {}-{}, columns {}-{}", one_based(start_row), one_based(end_row), one_based(start_col), one_based(end_col))
+}
+
+// ============================================================================
+// CHECKS
+// ============================================================================
+
+fn check_sheet_keyed(state: &AppState, sheet_count: usize, issues: &mut Vec<ConsistencyIssue>) {
+    for_each_sheet_keyed(state, |store, map| {
+        let orphans = map.orphan_sheets(sheet_count);
+        if !orphans.is_empty() {
+            let list: Vec<String> = orphans.iter().map(usize::to_string).collect();
+            issues.push(issue(
+                IssueSeverity::Warning,
+                store,
+                format!("Entries for deleted sheet index {} (the workbook has {} sheets)", list.join(", "), sheet_count),
+                Some(FixId::DropOrphans),
+            ));
+        }
+    });
+}
+
+fn check_sheet_aligned(state: &AppState, sheet_count: usize, issues: &mut Vec<ConsistencyIssue>) {
+    let grid_count = state.grids.lock().unwrap().len();
+    if grid_count != sheet_count {
+        issues.push(issue(
+            IssueSeverity::Error,
+            "grids",
+            format!("{} grids for {} sheets", grid_count, sheet_count),
+            None,
+        ));
+    }
+    for (store, len) in sheet_aligned_lengths(state) {
+        if len != sheet_count {
+            issues.push(issue(
+                IssueSeverity::Error,
+                store,
+                format!("{} entries for {} sheets", len, sheet_count),
+                Some(FixId::ResizeSheetStores),
+            ));
+        }
+    }
+}
+
+fn check_tables(state: &AppState, issues: &mut Vec<ConsistencyIssue>) {
+    let tables = state.tables.lock().unwrap();
+    let mut ids = HashSet::new();
+    for (&sheet, by_id) in tables.iter() {
+        for table in by_id.values() {
+            ids.insert(table.id);
+            if table.sheet_index != sheet {
+                issues.push(issue(
+                    IssueSeverity::Error,
+                    "tables",
+                    format!("Table '{}' is stored under sheet {} but says it is on sheet {}", table.name, sheet, table.sheet_index),
+                    None,
+                ));
+            }
+            if range_invalid(table.start_row, table.start_col, table.end_row, table.end_col) {
+                issues.push(issue(
+                    IssueSeverity::Error,
+                    "tables",
+                    format!(
+                        "Table '{}' spans {}, outside the sheet",
+                        table.name,
+                        describe_range(table.start_row, table.start_col, table.end_row, table.end_col)
+                    ),
+                    Some(FixId::ClampRanges),
+                ));
+            }
+        }
+    }
+    drop(tables);
+    let names = state.table_names.lock().unwrap();
+    let mut stale: Vec<&str> = names.iter().filter(|(_, (_, id))| !ids.contains(id)).map(|(n, _)| n.as_str()).collect();
+    stale.sort_unstable();
+    if !stale.is_empty() {
+        issues.push(issue(
+            IssueSeverity::Warning,
+            "tableNames",
+            format!("Names registered for missing tables: {}", stale.join(", ")),
+            Some(FixId::DropOrphans),
+        ));
+    }
+}
+
+fn check_ranges(state: &AppState, issues: &mut Vec<ConsistencyIssue>) {
+    let conditional_formats = state.conditional_formats.lock().unwrap();
+    for (&sheet, rules) in conditional_formats.iter() {
+        for rule in rules {
+            for r in rule.ranges.iter().filter(|r| range_invalid(r.start_row, r.start_col, r.end_row, r.end_col)) {
+                issues.push(issue(
+                    IssueSeverity::Error,
+                    "conditionalFormats",
+                    format!(
+                        "Rule {} on sheet {} applies to {}, outside the sheet",
+                        rule.id,
+                        sheet,
+                        describe_range(r.start_row, r.start_col, r.end_row, r.end_col)
+                    ),
+                    Some(FixId::ClampRanges),
+                ));
+            }
+        }
+    }
+    drop(conditional_formats);
+    let validations = state.data_validations.lock().unwrap();
+    for (&sheet, ranges) in validations.iter() {
+        for r in ranges.iter().filter(|r| range_invalid(r.start_row, r.start_col, r.end_row, r.end_col)) {
+            issues.push(issue(
+                IssueSeverity::Error,
+                "dataValidations",
+                format!(
+                    "Validation on sheet {} covers {}, outside the sheet",
+                    sheet,
+                    describe_range(r.start_row, r.start_col, r.end_row, r.end_col)
+                ),
+                Some(FixId::ClampRanges),
+            ));
+        }
+    }
+}
+
+fn check_named_ranges(state: &AppState, sheet_count: usize, issues: &mut Vec<ConsistencyIssue>) {
+    let named_ranges = state.named_ranges.lock().unwrap();
+    let mut found: Vec<ConsistencyIssue> = Vec::new();
+    for nr in named_ranges.values() {
+        if nr.sheet_index.is_some_and(|s| s >= sheet_count) {
+            found.push(issue(
+                IssueSeverity::Warning,
+                "namedRanges",
+                format!("'{}' is scoped to deleted sheet index {}", nr.name, nr.sheet_index.unwrap_or_default()),
+                Some(FixId::DropOrphans),
+            ));
+        } else if parser::parse(&nr.refers_to).is_err() {
+            found.push(issue(
+                IssueSeverity::Warning,
+                "namedRanges",
+                format!("'{}' refers to '{}', which does not parse", nr.name, nr.refers_to),
+                None,
+            ));
+        }
+    }
+    found.sort_by(|a, b| a.description.cmp(&b.description));
+    issues.extend(found);
+}
+
+fn check_protected_regions(state: &AppState, pivot_state: &PivotState, sheet_count: usize, issues: &mut Vec<ConsistencyIssue>) {
+    let pivot_ids: HashSet<identity::EntityId> = pivot_state.pivot_tables.lock().unwrap().keys().copied().collect();
+    let regions = state.protected_regions.lock().unwrap();
+    for region in regions.iter() {
+        if region.sheet_index >= sheet_count {
+            issues.push(issue(
+                IssueSeverity::Warning,
+                "protectedRegions",
+                format!("Region '{}' is on deleted sheet index {}", region.id, region.sheet_index),
+                Some(FixId::DropOrphans),
+            ));
+        } else if region.region_type == "pivot" && !pivot_ids.contains(&region.owner_id) {
+            issues.push(issue(
+                IssueSeverity::Warning,
+                "protectedRegions",
+                format!("Region '{}' belongs to a pivot table that no longer exists", region.id),
+                Some(FixId::DropOrphans),
+            ));
+        } else if range_invalid(region.start_row, region.start_col, region.end_row, region.end_col) {
+            issues.push(issue(
+                IssueSeverity::Error,
+                "protectedRegions",
+                format!(
+                    "Region '{}' spans {}, outside the sheet",
+                    region.id,
+                    describe_range(region.start_row, region.start_col, region.end_row, region.end_col)
+                ),
+                Some(FixId::ClampRanges),
+            ));
+        }
+    }
+}
+
+/// Dependency edges whose formula cell has no formula any more. The
+/// single-sheet maps describe the active sheet only; the cross-sheet map
+/// covers every sheet.
+fn check_dependencies(state: &AppState, sheet_count: usize, issues: &mut Vec<ConsistencyIssue>) {
+    let grids = state.grids.lock().unwrap();
+    let active = *state.active_sheet.lock().unwrap();
+    let has_formula = |sheet: usize, row: u32, col: u32| {
+        grids.get(sheet).and_then(|g| g.get_cell(row, col)).is_some_and(|cell| cell.ast.is_some())
+    };
+    let mut report = |store: &str, stale: usize| {
+        if stale > 0 {
+            issues.push(issue(
+                IssueSeverity::Warning,
+                store,
+                format!("{} entries for cells that hold no formula", stale),
+                Some(FixId::RebuildDependencies),
+            ));
+        }
+    };
+
+    let dependencies = state.dependencies.lock().unwrap();
+    report("dependencies", dependencies.keys().filter(|&&(r, c)| !has_formula(active, r, c)).count());
+    drop(dependencies);
+    let dependents = state.dependents.lock().unwrap();
+    let stale = dependents.values().flatten().filter(|&&(r, c)| !has_formula(active, r, c)).count();
+    report("dependents", stale);
+    drop(dependents);
+    let cross_sheet = state.cross_sheet_dependencies.lock().unwrap();
+    let stale = cross_sheet
+        .keys()
+        .filter(|&&(s, r, c)| s >= sheet_count || !has_formula(s, r, c))
+        .count();
+    report("crossSheetDependencies", stale);
+}
+
+/// Run every check. Issues come grouped by check, in a stable order.
+fn collect_issues(state: &AppState, pivot_state: &PivotState) -> Vec<ConsistencyIssue> {
+    let sheet_count = state.sheet_names.lock().unwrap().len();
+    let mut issues = Vec::new();
+    check_sheet_aligned(state, sheet_count, &mut issues);
+    check_sheet_keyed(state, sheet_count, &mut issues);
+    check_tables(state, &mut issues);
+    check_ranges(state, &mut issues);
+    check_named_ranges(state, sheet_count, &mut issues);
+    check_protected_regions(state, pivot_state, sheet_count, &mut issues);
+    check_dependencies(state, sheet_count, &mut issues);
+    issues
+}
+
+/// Check every store against the others and report what drifted out of step.
+/// Changes nothing.
+#[tauri::command]
+pub fn validate_workbook(state: State<AppState>, pivot_state: State<'_, PivotState>) -> WorkbookValidation {
+    validate_workbook_internal(&state, &pivot_state)
+}
+
+pub(crate) fn validate_workbook_internal(state: &AppState, pivot_state: &PivotState) -> WorkbookValidation {
+    WorkbookValidation { issues: collect_issues(state, pivot_state) }
+}
+
+// ============================================================================
+// REPAIRS
+// ============================================================================
+
+fn drop_orphans(state: &AppState, pivot_state: &PivotState, sheet_count: usize) {
+    for_each_sheet_keyed(state, |_, map| map.drop_orphan_sheets(sheet_count));
+    let ids: HashSet<identity::EntityId> =
+        state.tables.lock().unwrap().values().flat_map(|by_id| by_id.keys().copied()).collect();
+    state.table_names.lock().unwrap().retain(|_, (_, id)| ids.contains(id));
+    state.named_ranges.lock().unwrap().retain(|_, nr| nr.sheet_index.is_none_or(|s| s < sheet_count));
+    let pivot_ids: HashSet<identity::EntityId> = pivot_state.pivot_tables.lock().unwrap().keys().copied().collect();
+    state.protected_regions.lock().unwrap().retain(|region| {
+        region.sheet_index < sheet_count && (region.region_type != "pivot" || pivot_ids.contains(&region.owner_id))
+    });
+}
+
+fn clamp_ranges(state: &AppState) {
+    for table in state.tables.lock().unwrap().values_mut().flat_map(|by_id| by_id.values_mut()) {
+        clamp_range(&mut table.start_row, &mut table.start_col, &mut table.end_row, &mut table.end_col);
+        table.columns.truncate((table.end_col - table.start_col + 1) as usize);
+    }
+    for rule in state.conditional_formats.lock().unwrap().values_mut().flatten() {
+        for r in rule.ranges.iter_mut() {
+            clamp_range(&mut r.start_row, &mut r.start_col, &mut r.end_row, &mut r.end_col);
+        }
+    }
+    for r in state.data_validations.lock().unwrap().values_mut().flatten() {
+        clamp_range(&mut r.start_row, &mut r.start_col, &mut r.end_row, &mut r.end_col);
+    }
+    let mut regions = state.protected_regions.lock().unwrap();
+    for region in regions.iter_mut() {
+        clamp_range(&mut region.start_row, &mut region.start_col, &mut region.end_row, &mut region.end_col);
+    }
+}
+
+fn resize_sheet_stores(state: &AppState, sheet_count: usize) {
+    state.freeze_configs.lock().unwrap().resize_with(sheet_count, FreezeConfig::default);
+    state.split_configs.lock().unwrap().resize_with(sheet_count, SplitConfig::default);
+    state.sheet_view_options.lock().unwrap().resize_with(sheet_count, SheetViewOptions::default);
+    state.sheet_defaults.lock().unwrap().resize_with(sheet_count, SheetDefaults::default);
+    state.page_setups.lock().unwrap().resize_with(sheet_count, PageSetup::default);
+    state.tab_colors.lock().unwrap().resize_with(sheet_count, String::new);
+    state.sheet_visibility.lock().unwrap().resize_with(sheet_count, || "visible".to_string());
+    state.scroll_areas.lock().unwrap().resize_with(sheet_count, || None);
+    state
+        .sheet_ids
+        .lock()
+        .unwrap()
+        .resize_with(sheet_count, || identity::SheetId::from_bytes(identity::generate_uuid_v7()));
+}
+
+/// Apply the selected auto-repairs, then validate again. Repairs only discard
+/// or trim state nothing refers to and are not recorded for undo; issues
+/// without a suggested fix are left for the user.
+#[tauri::command]
+pub fn repair_workbook(
+    state: State<AppState>,
+    pivot_state: State<'_, PivotState>,
+    fixes: Vec<FixId>,
+) -> RepairWorkbookResult {
+    repair_workbook_internal(&state, &pivot_state, &fixes)
+}
+
+pub(crate) fn repair_workbook_internal(state: &AppState, pivot_state: &PivotState, fixes: &[FixId]) -> RepairWorkbookResult {
+    let fixed: Vec<ConsistencyIssue> = collect_issues(state, pivot_state)
+        .into_iter()
+        .filter(|i| i.suggested_fix.is_some_and(|fix| fixes.contains(&fix)))
+        .collect();
+    let sheet_count = state.sheet_names.lock().unwrap().len();
+
+    if fixes.contains(&FixId::ResizeSheetStores) {
+        resize_sheet_stores(state, sheet_count);
+    }
+    if fixes.contains(&FixId::DropOrphans) {
+        drop_orphans(state, pivot_state, sheet_count);
+    }
+    if fixes.contains(&FixId::ClampRanges) {
+        clamp_ranges(state);
+    }
+    if fixes.contains(&FixId::RebuildDependencies) {
+        crate::undo_commands::rebuild_all_dependencies(state);
+        crate::undo_commands::rebuild_cross_sheet_dependencies(state);
+    }
+
+    RepairWorkbookResult { fixed, remaining: collect_issues(state, pivot_state) }
+}
@@ -13,12 +13,14 @@ use crate::{
     evaluate_formula_multi_sheet_with_files,
     evaluate_formula_raw_with_files_and_pivot,
     extract_all_references, format_cell_value, get_column_row_dependents,
-    get_recalculation_order, parse_cell_input, parse_cell_input_invariant,
+    parse_cell_input, parse_cell_input_invariant,
     update_column_dependencies, update_cross_sheet_dependencies,
     update_dependencies, update_row_dependencies, AppState, log_perf
 };
 use engine::{self, EvalResult, Grid, StyleRegistry};
 use crate::persistence::{FileState, UserFilesState};
+use crate::protection::HiddenFormulaFilter;
+use crate::sheet_access::{check_sheet_indices, sheet_at};
 use crate::slicer::SlicerState;
 use std::collections::HashSet;
 use tauri::State;
@@ -29,7 +31,14 @@ use tauri::State;
 /// Returns the formula display string with "=" prefix for the frontend.
 /// `Cell::formula_string()` renders the AST without the leading "=";
 /// this helper adds it so the formula bar shows "=A1+B1" not "A1+B1".
+/// Quote-prefixed text comes back with its apostrophe ("'00123") so that
+/// editing the cell reproduces the literal entry.
 fn formula_display(cell: &engine::Cell, locale: &engine::LocaleSettings) -> Option<String> {
+    if cell.quote_prefix {
+        if let engine::CellValue::Text(text) = &cell.value {
+            return Some(format!("'{}", text));
+        }
+    }
     cell.formula_string()
         .map(|f| format!("={}", engine::localize_formula(&f, locale)))
 }
@@ -219,16 +228,65 @@ pub fn get_viewport_cells(
     end_row: u32,
     end_col: u32,
 ) -> Vec<CellData> {
-    use std::collections::HashMap;
+    get_viewport_cells_impl(&state, start_row, start_col, end_row, end_col)
+}
+
+pub(crate) fn get_viewport_cells_impl(
+    state: &AppState,
+    start_row: u32,
+    start_col: u32,
+    end_row: u32,
+    end_col: u32,
+) -> Vec<CellData> {
     use std::time::Instant;
     let perf_t0 = Instant::now();
 
-    let grid = state.grid.lock().unwrap();
+    let active_sheet = *state.active_sheet.lock().unwrap();
+    let hidden_formulas = HiddenFormulaFilter::for_sheet(state, active_sheet);
+    let grid = state.get_active_grid();
     let styles = state.style_registry.lock().unwrap();
     let merged_regions = state.merged_regions.lock().unwrap();
     let locale = state.locale.lock().unwrap();
     let perf_t1_locks = Instant::now();
 
+    let mut cells = collect_viewport_cells(
+        &grid,
+        &styles,
+        &merged_regions,
+        &locale,
+        (start_row, start_col, end_row, end_col),
+    );
+    if let Some(filter) = &hidden_formulas {
+        filter.redact(&mut cells);
+    }
+
+    let perf_tend = Instant::now();
+    let lock_ms = perf_t1_locks.duration_since(perf_t0).as_secs_f64() * 1000.0;
+    let process_ms = perf_tend.duration_since(perf_t1_locks).as_secs_f64() * 1000.0;
+    let total_ms = perf_tend.duration_since(perf_t0).as_secs_f64() * 1000.0;
+    if total_ms > 5.0 {
+        log_perf!("VIEWPORT",
+            "get_viewport_cells({},{})..({},{}) => {} cells | lock_wait={:.2}ms process={:.2}ms TOTAL={:.2}ms",
+            start_row, start_col, end_row, end_col, cells.len(),
+            lock_ms, process_ms, total_ms
+        );
+    }
+
+    cells
+}
+
+/// Build the `CellData` list for a viewport rectangle: non-empty cells, empty
+/// cells in styled rows/columns, and merge masters (with spans); merge slaves
+/// are omitted. Shared by `get_viewport_cells` and `get_viewport_snapshot`.
+pub(crate) fn collect_viewport_cells(
+    grid: &Grid,
+    styles: &StyleRegistry,
+    merged_regions: &HashSet<MergedRegion>,
+    locale: &engine::LocaleSettings,
+    (start_row, start_col, end_row, end_col): (u32, u32, u32, u32),
+) -> Vec<CellData> {
+    use std::collections::HashMap;
+
     // Build O(1) merge lookup by master cell (same pattern as update_cells_batch)
     let merge_lookup: HashMap<(u32, u32), &MergedRegion> = merged_regions
         .iter()
@@ -280,14 +338,16 @@ pub fn get_viewport_cells(
             };
 
             let cell = grid.get_cell(row, col);
+            // Empty cells in a styled row/column still render that style.
+            let default_style = if cell.is_none() { grid.default_style_index(row, col) } else { 0 };
 
-            if cell.is_none() && row_span == 1 && col_span == 1 {
+            if cell.is_none() && default_style == 0 && row_span == 1 && col_span == 1 {
                 continue;
             }
 
             let (display, display_color, formula, style_index, rich_text, accounting_layout) = if let Some(c) = cell {
                 let style = styles.get(c.style_index);
-                let result = crate::format_cell_value_with_color(&c.value, style, &locale);
+                let result = crate::format_cell_value_with_color(&c.value, style, locale);
                 let rt = c.rich_text.as_ref().map(|runs| {
                     crate::api_types::rich_text_runs_to_data(runs)
                 });
@@ -296,9 +356,9 @@ pub fn get_viewport_cells(
                     symbol_before: a.symbol_before,
                     value: a.value,
                 });
-                (result.text, result.color, formula_display(&c, &locale), c.style_index, rt, acct)
+                (result.text, result.color, formula_display(&c, locale), c.style_index, rt, acct)
             } else {
-                (String::new(), None, None, 0, None, None)
+                (String::new(), None, None, default_style, None, None)
             };
 
             cells.push(CellData {
@@ -313,46 +373,316 @@ pub fn get_viewport_cells(
                 sheet_index: None,
                 rich_text,
                 accounting_layout,
+                error_detail: None,
             });
         }
     }
 
-    let perf_tend = Instant::now();
-    let lock_ms = perf_t1_locks.duration_since(perf_t0).as_secs_f64() * 1000.0;
-    let process_ms = perf_tend.duration_since(perf_t1_locks).as_secs_f64() * 1000.0;
-    let total_ms = perf_tend.duration_since(perf_t0).as_secs_f64() * 1000.0;
+    cells
+}
+
+/// Feature stores of one sheet that feed a viewport snapshot. Borrowed from
+/// the locked AppState by `get_viewport_snapshot`; built directly in tests.
+pub(crate) struct ViewportSources<'a> {
+    pub sheet_index: usize,
+    pub grid: &'a Grid,
+    pub grids: &'a [Grid],
+    pub sheet_names: &'a [String],
+    pub styles: &'a StyleRegistry,
+    pub locale: &'a engine::LocaleSettings,
+    pub merged_regions: &'a HashSet<MergedRegion>,
+    pub cf_rules: &'a [crate::ConditionalFormatDefinition],
+    pub comments: Option<&'a std::collections::HashMap<(u32, u32), crate::Comment>>,
+    pub hyperlinks: Option<&'a std::collections::HashMap<(u32, u32), crate::Hyperlink>>,
+    pub validations: &'a [crate::ValidationRange],
+    pub hidden_rows: &'a HashSet<u32>,
+    pub hidden_cols: &'a HashSet<u32>,
+}
+
+/// Assemble a `ViewportSnapshot` for a rectangle. Conditional formats are
+/// evaluated for the visible cells only; indicators are filtered to the rect.
+pub(crate) fn build_viewport_snapshot(
+    src: &ViewportSources,
+    rect: (u32, u32, u32, u32),
+) -> crate::api_types::ViewportSnapshot {
+    let (start_row, start_col, end_row, end_col) = rect;
+
+    let cells = collect_viewport_cells(src.grid, src.styles, src.merged_regions, src.locale, rect);
+    let conditional_formats = if src.cf_rules.is_empty() {
+        Vec::new()
+    } else {
+        crate::conditional_formatting::evaluate_cf_rect(
+            src.cf_rules,
+            src.grid,
+            src.grids,
+            src.sheet_names,
+            src.sheet_index,
+            rect,
+        )
+    };
+    let comment_indicators = src
+        .comments
+        .map(|c| crate::comments::comment_indicators_in_rect(c, rect))
+        .unwrap_or_default();
+    let hyperlink_indicators = src
+        .hyperlinks
+        .map(|h| crate::hyperlinks::hyperlink_indicators_in_rect(h, rect))
+        .unwrap_or_default();
+
+    // Dropdown arrows: walk the rules in order and let the first rule that
+    // claims a cell own it (the precedence get_validation_for_cell applies).
+    let mut dropdown_cells = Vec::new();
+    let mut claimed: HashSet<(u32, u32)> = HashSet::new();
+    for vr in src.validations {
+        let (r0, r1) = (vr.start_row.max(start_row), vr.end_row.min(end_row));
+        let (c0, c1) = (vr.start_col.max(start_col), vr.end_col.min(end_col));
+        if r0 > r1 || c0 > c1 {
+            continue;
+        }
+        let has_dropdown = matches!(&vr.validation.rule, crate::DataValidationRule::List(list_rule) if list_rule.in_cell_dropdown);
+        for row in r0..=r1 {
+            for col in c0..=c1 {
+                if claimed.insert((row, col)) && has_dropdown {
+                    dropdown_cells.push((row, col));
+                }
+            }
+        }
+    }
+
+    let merged_regions: Vec<MergedRegion> = src
+        .merged_regions
+        .iter()
+        .filter(|r| {
+            r.end_row >= start_row && r.start_row <= end_row && r.end_col >= start_col && r.start_col <= end_col
+        })
+        .cloned()
+        .collect();
+
+    let mut hidden_rows: Vec<u32> = src
+        .hidden_rows
+        .iter()
+        .copied()
+        .filter(|r| *r >= start_row && *r <= end_row)
+        .collect();
+    hidden_rows.sort_unstable();
+    let mut hidden_cols: Vec<u32> = src
+        .hidden_cols
+        .iter()
+        .copied()
+        .filter(|c| *c >= start_col && *c <= end_col)
+        .collect();
+    hidden_cols.sort_unstable();
+
+    crate::api_types::ViewportSnapshot {
+        sheet_index: src.sheet_index,
+        revision: 0,
+        kind: crate::api_types::SnapshotKind::Full,
+        cells,
+        cleared_cells: Vec::new(),
+        conditional_formats,
+        comment_indicators,
+        hyperlink_indicators,
+        dropdown_cells,
+        merged_regions,
+        hidden_rows,
+        hidden_cols,
+    }
+}
+
+/// Get everything needed to paint a viewport in one call: cell display data,
+/// conditional-format overrides, comment/hyperlink/dropdown indicators,
+/// merged regions and hidden rows/cols. Replaces the get_viewport_cells +
+/// evaluate_conditional_formats + indicator round trips on every scroll.
+/// Every store is locked once; feature stores before the grids (canonical
+/// lock order).
+///
+/// With `known_revision` (the `revision` of an earlier snapshot of this
+/// sheet) the answer is `NotModified` when no cell changed since, a `Delta`
+/// with just the changed cells when the sheet's change log still covers that
+/// revision, and a full snapshot otherwise.
+#[tauri::command]
+pub fn get_viewport_snapshot(
+    state: State<AppState>,
+    sheet_index: usize,
+    start_row: u32,
+    start_col: u32,
+    end_row: u32,
+    end_col: u32,
+    known_revision: Option<u64>,
+) -> Result<crate::api_types::ViewportSnapshot, String> {
+    get_viewport_snapshot_internal(&state, sheet_index, start_row, start_col, end_row, end_col, known_revision)
+}
+
+pub(crate) fn get_viewport_snapshot_internal(
+    state: &AppState,
+    sheet_index: usize,
+    start_row: u32,
+    start_col: u32,
+    end_row: u32,
+    end_col: u32,
+    known_revision: Option<u64>,
+) -> Result<crate::api_types::ViewportSnapshot, String> {
+    use crate::api_types::SnapshotKind;
+    use crate::viewport_revisions::RevisionDelta;
+    use std::time::Instant;
+    let perf_t0 = Instant::now();
+    let rect = (
+        start_row.min(end_row),
+        start_col.min(end_col),
+        start_row.max(end_row),
+        start_col.max(end_col),
+    );
+
+    // Seal writes made outside an IPC command before reading the revision.
+    crate::viewport_revisions::seal_viewport_changes(state);
+    let sheet_id = *sheet_at(&state.sheet_ids.lock().unwrap(), sheet_index)?;
+    let (revision, delta) = {
+        let mut revisions = state.viewport_revisions.lock().unwrap();
+        (revisions.revision(sheet_id), revisions.delta_since(sheet_id, known_revision))
+    };
+    if delta == RevisionDelta::NotModified {
+        return Ok(crate::api_types::ViewportSnapshot {
+            sheet_index,
+            revision,
+            kind: SnapshotKind::NotModified,
+            cells: Vec::new(),
+            cleared_cells: Vec::new(),
+            conditional_formats: Vec::new(),
+            comment_indicators: Vec::new(),
+            hyperlink_indicators: Vec::new(),
+            dropdown_cells: Vec::new(),
+            merged_regions: Vec::new(),
+            hidden_rows: Vec::new(),
+            hidden_cols: Vec::new(),
+        });
+    }
+
+    let active_sheet = *state.active_sheet.lock().unwrap();
+    let cf_storage = state.conditional_formats.lock().unwrap();
+    let comments = state.comments.lock().unwrap();
+    let hyperlinks = state.hyperlinks.lock().unwrap();
+    let validations = state.data_validations.lock().unwrap();
+    let auto_filters = state.auto_filters.lock().unwrap();
+    let adv_hidden = state.advanced_filter_hidden_rows.lock().unwrap();
+    let outlines = state.outlines.lock().unwrap();
+    let hidden_formulas = HiddenFormulaFilter::for_sheet(state, sheet_index);
+
+    let mut hidden_rows: HashSet<u32> = HashSet::new();
+    let mut hidden_cols: HashSet<u32> = HashSet::new();
+    if let Some(af) = auto_filters.get(&sheet_index) {
+        hidden_rows.extend(af.hidden_rows.iter());
+    }
+    if let Some(rows) = adv_hidden.get(&sheet_index) {
+        hidden_rows.extend(rows.iter());
+    }
+    if let Some(outline) = outlines.get(&sheet_index) {
+        hidden_rows.extend(outline.get_hidden_rows());
+        hidden_cols.extend(outline.get_hidden_cols());
+    }
+
+    let grids = state.grids.lock().unwrap();
+    let sheet_names = state.sheet_names.lock().unwrap();
+    let styles = state.style_registry.lock().unwrap();
+    let merged_regions = state.merged_regions.lock().unwrap();
+    let all_merged_regions = state.all_merged_regions.lock().unwrap();
+    let locale = state.locale.lock().unwrap();
+
+    // The active sheet's merges live in their active-sheet copy.
+    let no_merges = HashSet::new();
+    let sheet_grid = sheet_at(&grids, sheet_index)?;
+    let sheet_merges: &HashSet<MergedRegion> = if sheet_index == active_sheet {
+        &merged_regions
+    } else {
+        all_merged_regions.get(sheet_index).unwrap_or(&no_merges)
+    };
+    let no_cf_rules = Vec::new();
+    let no_validations = Vec::new();
+
+    let sources = ViewportSources {
+        sheet_index,
+        grid: sheet_grid,
+        grids: &grids,
+        sheet_names: &sheet_names,
+        styles: &styles,
+        locale: &locale,
+        merged_regions: sheet_merges,
+        cf_rules: cf_storage.get(&sheet_index).unwrap_or(&no_cf_rules),
+        comments: comments.get(&sheet_index),
+        hyperlinks: hyperlinks.get(&sheet_index),
+        validations: validations.get(&sheet_index).unwrap_or(&no_validations),
+        hidden_rows: &hidden_rows,
+        hidden_cols: &hidden_cols,
+    };
+    let mut snapshot = build_viewport_snapshot(&sources, rect);
+    snapshot.revision = revision;
+    if let RevisionDelta::Cells(changed) = &delta {
+        let (r0, c0, r1, c1) = rect;
+        let present: HashSet<(u32, u32)> = snapshot.cells.iter().map(|c| (c.row, c.col)).collect();
+        snapshot.cells.retain(|c| changed.contains(&(c.row, c.col)));
+        snapshot.cleared_cells = changed
+            .iter()
+            .copied()
+            .filter(|&(r, c)| r >= r0 && r <= r1 && c >= c0 && c <= c1 && !present.contains(&(r, c)))
+            .collect();
+        snapshot.cleared_cells.sort_unstable();
+        snapshot.kind = SnapshotKind::Delta;
+    }
+    let error_details = state.error_details.lock().unwrap();
+    for cell in snapshot.cells.iter_mut() {
+        cell.error_detail =
+            crate::error_detail_for(&error_details, (sheet_index, cell.row, cell.col), sheet_grid.get_cell(cell.row, cell.col));
+    }
+    drop(error_details);
+    if let Some(filter) = &hidden_formulas {
+        filter.redact(&mut snapshot.cells);
+    }
+
+    let total_ms = perf_t0.elapsed().as_secs_f64() * 1000.0;
     if total_ms > 5.0 {
         log_perf!("VIEWPORT",
-            "get_viewport_cells({},{})..({},{}) => {} cells | lock_wait={:.2}ms process={:.2}ms TOTAL={:.2}ms",
-            start_row, start_col, end_row, end_col, cells.len(),
-            lock_ms, process_ms, total_ms
+            "get_viewport_snapshot(sheet={}, {},{}..{},{}) => {} cells, {} cf | TOTAL={:.2}ms",
+            sheet_index, rect.0, rect.1, rect.2, rect.3,
+            snapshot.cells.len(), snapshot.conditional_formats.len(), total_ms
         );
     }
 
-    cells
+    Ok(snapshot)
 }
 
-/// Get a single cell's data.
+/// Get a single cell's data. A hidden formula on a protected sheet is
+/// omitted; the value still shows.
 #[tauri::command]
 pub fn get_cell(state: State<AppState>, row: u32, col: u32) -> Option<CellData> {
-    let grid = state.grid.lock().unwrap();
+    get_cell_impl(&state, row, col)
+}
+
+pub(crate) fn get_cell_impl(state: &AppState, row: u32, col: u32) -> Option<CellData> {
+    let active_sheet = *state.active_sheet.lock().unwrap();
+    let hidden_formulas = HiddenFormulaFilter::for_sheet(state, active_sheet);
+    let grid = state.get_active_grid();
     let styles = state.style_registry.lock().unwrap();
     let merged_regions = state.merged_regions.lock().unwrap();
     let locale = state.locale.lock().unwrap();
-    get_cell_internal_with_merge(&grid, &styles, &merged_regions, row, col, &locale)
+    let mut cell = get_cell_internal_with_merge(&grid, &styles, &merged_regions, row, col, &locale)?;
+    cell.error_detail = crate::error_detail_for(
+        &state.error_details.lock().unwrap(),
+        (active_sheet, row, col),
+        grid.get_cell(row, col),
+    );
+    if let Some(filter) = &hidden_formulas {
+        filter.redact(std::slice::from_mut(&mut cell));
+    }
+    Some(cell)
 }
 
 /// Batch-get cell display values from arbitrary sheets (for Watch Window).
 /// Takes a list of (sheetIndex, row, col) and returns parallel list of results.
-/// Note: grids[active_sheet] is stale; we use state.grid for the active sheet.
 #[tauri::command]
 pub fn get_watch_cells(
     state: State<AppState>,
     requests: Vec<(usize, u32, u32)>,
 ) -> Vec<Option<CellData>> {
     let grids = state.grids.lock().unwrap();
-    let active_grid = state.grid.lock().unwrap();
-    let active_sheet = *state.active_sheet.lock().unwrap();
     let styles = state.style_registry.lock().unwrap();
     let locale = state.locale.lock().unwrap();
 
@@ -379,6 +709,7 @@ pub fn get_watch_cells(
                 sheet_index: Some(sheet_index),
                 rich_text: None,
                 accounting_layout: None,
+                error_detail: None,
             }
         })
     }
@@ -386,13 +717,9 @@ pub fn get_watch_cells(
     requests
         .iter()
         .map(|&(sheet_index, row, col)| {
-            if sheet_index == active_sheet {
-                read_cell(&active_grid, &styles, sheet_index, row, col, &locale)
-            } else if sheet_index < grids.len() {
-                read_cell(&grids[sheet_index], &styles, sheet_index, row, col, &locale)
-            } else {
-                None
-            }
+            grids
+                .get(sheet_index)
+                .and_then(|grid| read_cell(grid, &styles, sheet_index, row, col, &locale))
         })
         .collect()
 }
@@ -407,7 +734,7 @@ pub fn get_cell_collection(
     use crate::api_types::{CollectionEntry, CollectionItem, CollectionPreviewResult};
     use engine::cell::{CellValue, DictKey};
 
-    let grid = state.grid.lock().unwrap();
+    let grid = state.get_active_grid();
 
     fn cell_value_to_item(val: &CellValue, depth: usize) -> CollectionItem {
         if depth > 32 {
@@ -527,7 +854,7 @@ pub fn get_collection_texts(
 ) -> Vec<String> {
     use engine::cell::{CellValue, DictKey};
 
-    let grid = state.grid.lock().unwrap();
+    let grid = state.get_active_grid();
 
     fn cell_value_to_json(val: &CellValue, depth: usize) -> serde_json::Value {
         if depth > 32 {
@@ -601,6 +928,7 @@ fn get_cell_internal(grid: &Grid, styles: &StyleRegistry, row: u32, col: u32, lo
             crate::api_types::rich_text_runs_to_data(runs)
         }),
         accounting_layout: None,
+        error_detail: None,
     })
 }
 
@@ -665,9 +993,33 @@ pub fn update_cell(
         result.cells.extend(extra);
     }
 
+    flush_edit_changes(&state, &result.cells);
+
+    // Pivots whose source range holds an updated cell go stale (and refresh
+    // when auto_refresh is set).
+    crate::pivot::source_watch::source_cells_changed(
+        &state,
+        &pivot_state,
+        Some((&*pane_control_state, &*ribbon_filter_state)),
+        &result.cells,
+    );
+
     Ok(result)
 }
 
+/// Change events for a finished edit. Cross-sheet dependents are in the
+/// response but outside the active sheet the frontend repaints from it, and
+/// dirty formulas left by manual mode make the calculation state pending.
+pub(crate) fn flush_edit_changes(state: &AppState, cells: &[CellData]) -> Vec<crate::change_events::ChangeEvent> {
+    let active_sheet = *state.active_sheet.lock().unwrap();
+    let mut changes = crate::change_events::ChangeBatch::new();
+    changes.push_off_sheet_cells(active_sheet, cells);
+    if !state.dirty_cells.lock().unwrap().is_empty() {
+        changes.set_calc_state("pending");
+    }
+    changes.flush()
+}
+
 /// One-probe hot-path lookup: the static, non-empty "name" of the on-grid
 /// control anchored at (active_sheet, row, col), or None (the overwhelmingly
 /// common case — a single HashMap probe under a brief lock).
@@ -683,7 +1035,7 @@ fn named_control_anchor_name(state: &AppState, row: u32, col: u32) -> Option<Str
 /// command wrapper can run the named-anchor control recalc afterwards without
 /// lock re-entry.
 #[allow(clippy::too_many_arguments)]
-fn update_cell_impl(
+pub(crate) fn update_cell_impl(
     state: &AppState,
     file_state: &FileState,
     user_files_state: &UserFilesState,
@@ -718,6 +1070,9 @@ fn update_cell_impl(
     let control_values = crate::control_values::build_control_values(
         &state, &pane_control_state, &ribbon_filter_state,
     );
+    // Filtered/collapsed rows for SUBTOTAL 101-111, likewise before the grids.
+    let hidden_rows =
+        crate::autofilter::sheet_hidden_rows(state, *state.active_sheet.lock().unwrap());
 
     // Lock user files for FILEREAD/FILELINES/FILEEXISTS support
     let user_files = user_files_state.files.lock().unwrap();
@@ -746,7 +1101,6 @@ fn update_cell_impl(
     }
 
     let sheet_names = state.sheet_names.lock().unwrap();
-    let mut grid = state.grid.lock().unwrap();
     let mut grids = state.grids.lock().unwrap();
     let active_sheet = *state.active_sheet.lock().unwrap();
     let mut styles = state.style_registry.lock().unwrap();
@@ -758,7 +1112,10 @@ fn update_cell_impl(
     let mut row_dependencies_map = state.row_dependencies.lock().unwrap();
     let mut cross_sheet_dependents_map = state.cross_sheet_dependents.lock().unwrap();
     let mut cross_sheet_dependencies_map = state.cross_sheet_dependencies.lock().unwrap();
-    let calc_mode = state.calculation_mode.lock().unwrap();
+    let (calc_mode, precision_as_displayed) = {
+        let settings = state.calculation_settings.lock().unwrap();
+        (settings.mode, settings.precision_as_displayed)
+    };
     let mut undo_stack = state.undo_stack.lock().unwrap();
     let merged_regions = state.merged_regions.lock().unwrap();
     let locale = state.locale.lock().unwrap();
@@ -791,7 +1148,7 @@ fn update_cell_impl(
     let mut needs_style_refresh = false;
 
     // Record previous state for undo BEFORE making any changes
-    let previous_cell = grid.get_cell(row, col).cloned();
+    let previous_cell = grids[active_sheet].get_cell(row, col).cloned();
 
     // Handle empty value - clear the cell
     if value.trim().is_empty() {
@@ -802,26 +1159,22 @@ fn update_cell_impl(
             if let Some(old_spill_cells) = spill_ranges.remove(&(active_sheet, row, col)) {
                 for (sr, sc) in &old_spill_cells {
                     spill_hosts.remove(&(active_sheet, *sr, *sc));
-                    grid.cells.remove(&(*sr, *sc));
-                    if active_sheet < grids.len() {
-                        grids[active_sheet].cells.remove(&(*sr, *sc));
-                    }
+                    grids[active_sheet].cells.remove(&(*sr, *sc));
+                    grids[active_sheet].mark_changed(*sr, *sc);
                     updated_cells.push(CellData {
                         row: *sr, col: *sc, display: String::new(),
                         display_color: None, formula: None, style_index: 0,
                         row_span: 1, col_span: 1, sheet_index: None,
                         rich_text: None,
                         accounting_layout: None,
+                        error_detail: None,
                     });
                 }
             }
         }
 
-        grid.clear_cell(row, col);
-        // Also update the grids vector
-        if active_sheet < grids.len() {
-            grids[active_sheet].clear_cell(row, col);
-        }
+        grids[active_sheet].clear_cell(row, col);
+        crate::clear_ui_effects_for_cell(state, active_sheet, row, col);
         // Clear cross-sheet dependencies for this cell
         update_cross_sheet_dependencies(
             (active_sheet, row, col),
@@ -873,13 +1226,25 @@ fn update_cell_impl(
             sheet_index: None,
             rich_text: None,
             accounting_layout: None,
+            error_detail: None,
         });
 
+        // Revert the formatting the cleared formula applied through SET.* functions
+        {
+            let mut rh = state.row_heights.lock().unwrap();
+            let mut cw = state.column_widths.lock().unwrap();
+            let (effect_dim_changes, effect_style_refresh) = crate::apply_pending_ui_effects(
+                state, &mut grids, &sheet_names, &mut styles, &mut rh, &mut cw, active_sheet,
+            );
+            dimension_changes.extend(effect_dim_changes);
+            needs_style_refresh = needs_style_refresh || effect_style_refresh;
+        }
+
         // Record subscriber override for the cleared cell (subscribed sheets only)
         crate::calp_commands::record_subscription_override_edits(
             &state,
             active_sheet,
-            &[(row, col, previous_cell.clone(), grid.get_cell(row, col).cloned())],
+            &[(row, col, previous_cell.clone(), grids[active_sheet].get_cell(row, col).cloned())],
         );
 
         // Record undo after successful change
@@ -893,10 +1258,21 @@ fn update_cell_impl(
 
     // Parse the input
     let mut cell = parse_cell_input(&value, &locale);
-
-    // Preserve existing style
-    if let Some(existing) = grid.get_cell(row, col) {
-        cell.style_index = existing.style_index;
+    // Typed form of a formula whose names/table refs get expanded below
+    let mut name_refs = None;
+
+    // Preserve existing style (a new cell takes its row/column style)
+    cell.style_index = grids[active_sheet].effective_style_index(row, col);
+    // A Text-formatted cell stores what was typed, verbatim
+    if let Some(text_cell) = crate::text_format_input(&value, styles.get(cell.style_index)) {
+        cell = engine::Cell { style_index: cell.style_index, ..text_cell };
+    }
+    // A typed date keeps its serial and gets a date format unless one is set
+    if matches!(cell.value, engine::CellValue::Number(_)) {
+        if let Some(index) = crate::style_for_date_input(&mut styles, cell.style_index, &value, &locale) {
+            cell.style_index = index;
+            needs_style_refresh = true;
+        }
     }
 
     // If it's a formula, evaluate it using multi-sheet context
@@ -904,6 +1280,7 @@ fn update_cell_impl(
         // Extract references for dependency tracking AND cache the AST
         match parser::parse(&formula) {
             Ok(parsed) => {
+                name_refs = crate::name_dependents::collect_refs(&formula, &parsed);
                 // Resolve named references (AST splicing) before extracting refs or evaluating.
                 let resolved = if crate::ast_has_named_refs(&parsed) {
                     let named_ranges_map = state.named_ranges.lock().unwrap();
@@ -952,7 +1329,10 @@ fn update_cell_impl(
                     resolved
                 };
 
-                let refs = extract_all_references(&resolved, &grid);
+                let refs = extract_all_references(&resolved, &grids[active_sheet]);
+                // Normalize cross-sheet references to the official sheet_names
+                // list and expand 3D references to the sheets they cover
+                let normalized_cross_sheet_refs = crate::resolve_cross_sheet_refs(&refs, &sheet_names);
 
                 log_debug!("DEPS", "update_cell({},{}) formula='{}' extracted_refs: cells={:?} cross_sheet={:?} columns={:?} rows={:?}",
                     row, col, formula, refs.cells, refs.cross_sheet_cells, refs.columns, refs.rows);
@@ -976,22 +1356,6 @@ fn update_cell_impl(
                     &mut row_dependents_map,
                 );
 
-                // Normalize cross-sheet references: match sheet names case-insensitively
-                // to the official sheet_names list
-                let normalized_cross_sheet_refs: rustc_hash::FxHashSet<(String, u32, u32)> = refs
-                    .cross_sheet_cells
-                    .iter()
-                    .filter_map(|(parsed_sheet_name, r, c)| {
-                        // Find the official sheet name (case-insensitive match)
-                        let normalized = sheet_names
-                            .iter()
-                            .find(|name| name.eq_ignore_ascii_case(parsed_sheet_name))
-                            .cloned()
-                            .unwrap_or_else(|| parsed_sheet_name.clone());
-                        Some((normalized, *r, *c))
-                    })
-                    .collect();
-
                 // Track cross-sheet dependencies
                 update_cross_sheet_dependencies(
                     (active_sheet, row, col),
@@ -1012,7 +1376,7 @@ fn update_cell_impl(
                     current_col: Some(col),
                     row_heights: Some(rh_map),
                     column_widths: Some(cw_map),
-                    hidden_rows: None,
+                    hidden_rows: (!hidden_rows.is_empty()).then(|| hidden_rows.clone()),
                     control_values: Some(control_values.clone()),
                 };
                 let raw_result = evaluate_formula_raw_with_files_and_pivot(
@@ -1035,16 +1399,15 @@ fn update_cell_impl(
                     if let Some(old_spill_cells) = spill_ranges.remove(&(active_sheet, row, col)) {
                         for (sr, sc) in &old_spill_cells {
                             spill_hosts.remove(&(active_sheet, *sr, *sc));
-                            grid.cells.remove(&(*sr, *sc));
-                            if active_sheet < grids.len() {
-                                grids[active_sheet].cells.remove(&(*sr, *sc));
-                            }
+                            grids[active_sheet].cells.remove(&(*sr, *sc));
+                            grids[active_sheet].mark_changed(*sr, *sc);
                             updated_cells.push(CellData {
                                 row: *sr, col: *sc, display: String::new(),
                                 display_color: None, formula: None, style_index: 0,
                                 row_span: 1, col_span: 1, sheet_index: None,
                                 rich_text: None,
                                 accounting_layout: None,
+                                error_detail: None,
                             });
                         }
                     }
@@ -1062,7 +1425,7 @@ fn update_cell_impl(
                         let target_r = row + dr;
                         let target_c = col + dc;
                         // Check if target is occupied by real data (not empty and not a spill from this origin)
-                        if let Some(existing) = grid.get_cell(target_r, target_c) {
+                        if let Some(existing) = grids[active_sheet].get_cell(target_r, target_c) {
                             if existing.value != engine::CellValue::Empty {
                                 spill_blocked = true;
                                 break;
@@ -1071,7 +1434,7 @@ fn update_cell_impl(
                     }
 
                     if spill_blocked {
-                        cell.value = engine::CellValue::Error(engine::CellError::Value);
+                        cell.value = engine::CellValue::Error(engine::CellError::Spill);
                     } else {
                         // Write the origin cell value (first element)
                         cell.value = raw_result.to_cell_value();
@@ -1091,11 +1454,9 @@ fn update_cell_impl(
                                 value: cv.clone(),
                                 style_index: 0,
                                 rich_text: None,
+                                quote_prefix: false,
                             };
-                            grid.set_cell(target_r, target_c, spill_cell.clone());
-                            if active_sheet < grids.len() {
-                                grids[active_sheet].set_cell(target_r, target_c, spill_cell);
-                            }
+                            grids[active_sheet].set_cell(target_r, target_c, spill_cell);
 
                             let style = styles.get(0);
                             let display = format_cell_value(&cv, style, &locale);
@@ -1105,6 +1466,7 @@ fn update_cell_impl(
                                 row_span: 1, col_span: 1, sheet_index: None,
                                 rich_text: None,
                                 accounting_layout: None,
+                                error_detail: None,
                             });
 
                             new_spill_cells.push((target_r, target_c));
@@ -1116,18 +1478,26 @@ fn update_cell_impl(
                         }
                     }
                 } else {
-                    cell.value = raw_result.to_cell_value();
+                    cell.value = crate::calculation::stored_result(
+                        raw_result.to_cell_value(),
+                        styles.get(cell.style_index),
+                        precision_as_displayed,
+                    );
                 }
+                crate::record_error_detail(&mut state.error_details.lock().unwrap(), (active_sheet, row, col), &cell.value);
+                crate::process_ui_effects(state, active_sheet, row, col, engine::take_ui_effects());
             }
             Err(_e) => {
                 // Formula parse error - dependencies won't be tracked
                 // Still try to evaluate (will return error)
                 let result =
-                    evaluate_formula_multi_sheet_with_files(&grids, &sheet_names, active_sheet, &formula, &user_files);
+                    evaluate_formula_multi_sheet_with_files(&grids, &sheet_names, active_sheet, &formula, engine::EvalContext::at(row, col), &user_files);
                 cell.value = result;
+                crate::clear_ui_effects_for_cell(state, active_sheet, row, col);
             }
         }
     } else {
+        crate::clear_ui_effects_for_cell(state, active_sheet, row, col);
         // Clear dependencies for non-formula cells
         update_dependencies(
             (row, col),
@@ -1159,11 +1529,13 @@ fn update_cell_impl(
     let perf_t2_parsed = Instant::now();
 
     // Store the cell
-    grid.set_cell(row, col, cell.clone());
-    // Also update the grids vector to keep them in sync
-    if active_sheet < grids.len() {
-        grids[active_sheet].set_cell(row, col, cell.clone());
-    }
+    grids[active_sheet].set_cell(row, col, cell.clone());
+    crate::name_dependents::track_cell(
+        &mut state.name_ref_sources.lock().unwrap(),
+        (active_sheet, row, col),
+        name_refs,
+        &cell,
+    );
 
     // Get the display value
     let style = styles.get(cell.style_index);
@@ -1195,20 +1567,26 @@ fn update_cell_impl(
         sheet_index: None, // Current active sheet
         rich_text: None,
         accounting_layout: None,
+        error_detail: None,
     });
 
     // Record subscriber override for the edited cell (subscribed sheets only)
     crate::calp_commands::record_subscription_override_edits(
         &state,
         active_sheet,
-        &[(row, col, previous_cell.clone(), grid.get_cell(row, col).cloned())],
+        &[(row, col, previous_cell.clone(), grids[active_sheet].get_cell(row, col).cloned())],
     );
 
     // Record undo after successful change
     undo_stack.record_cell_change(row, col, previous_cell);
+    crate::track_volatile_cell(
+        &mut state.volatile_cells.lock().unwrap(),
+        (row, col),
+        grids[active_sheet].get_cell(row, col),
+    );
 
     // Recalculate dependents if automatic mode
-    if *calc_mode == "automatic" {
+    if calc_mode.is_automatic() {
         // Build a HashMap for O(1) merge region lookup instead of O(n) linear search
         let merge_lookup: std::collections::HashMap<(u32, u32), &MergedRegion> = merged_regions
             .iter()
@@ -1220,8 +1598,12 @@ fn update_cell_impl(
         let cascade_table_names = state.table_names.lock().unwrap();
         let cascade_named_ranges = state.named_ranges.lock().unwrap();
 
-        // Get direct cell dependents
-        let mut recalc_order = get_recalculation_order((row, col), &dependents_map);
+        // Get direct cell dependents, plus the volatile formulas and theirs
+        let mut recalc_order = crate::get_recalculation_order_with_volatile(
+            (row, col),
+            &state.volatile_cells.lock().unwrap(),
+            &dependents_map,
+        );
 
         log_debug!("DEPS", "cascade for ({},{}) recalc_order={:?} dependents_entry={:?}",
             row, col, recalc_order, dependents_map.get(&(row, col)));
@@ -1245,9 +1627,9 @@ fn update_cell_impl(
         let include_cascade_formulas = recalc_order.len() <= CASCADE_FORMULA_LIMIT;
 
         for &(dep_row, dep_col) in &recalc_order {
-            // Clone dep_cell upfront to release the immutable borrow on grid,
+            // Clone dep_cell upfront to release the immutable borrow on grids,
             // allowing mutable access for spill cell writes below.
-            let dep_cell_opt = grid.get_cell(dep_row, dep_col).cloned();
+            let dep_cell_opt = grids[active_sheet].get_cell(dep_row, dep_col).cloned();
             if let Some(dep_cell) = dep_cell_opt {
                 if let Some(formula) = dep_cell.formula_string() {
                     let perf_eval_start = Instant::now();
@@ -1256,7 +1638,6 @@ fn update_cell_impl(
                     // own position so cube/UDF preserve semantics engage.
                     reevaluate_formula_cell(
                         &state,
-                        &mut grid,
                         &mut grids,
                         &sheet_names,
                         active_sheet,
@@ -1268,6 +1649,7 @@ fn update_cell_impl(
                         udf_resolver.as_ref().map(|r| r as &dyn Fn(&str, &[EvalResult]) -> Option<EvalResult>),
                         cube_arc.as_ref(),
                         Some(&control_values),
+                        &hidden_rows,
                         &styles,
                         &locale,
                         &merge_lookup,
@@ -1290,7 +1672,6 @@ fn update_cell_impl(
         // walk, also used by the targeted control recalc
         // (recalc_control_dependents in control_values.rs).
         cascade_cross_sheet_dependents(
-            &mut grid,
             &mut grids,
             &sheet_names,
             active_sheet,
@@ -1324,7 +1705,16 @@ fn update_cell_impl(
             perf_t6_cross_sheet.duration_since(perf_t0).as_secs_f64() * 1000.0
         );
     } else {
-        // Manual calc mode - just log basic timing
+        // Manual calc mode: dependents wait for calculate_now; remember them.
+        mark_dependents_dirty(
+            &mut state.dirty_cells.lock().unwrap(),
+            (active_sheet, sheet_names.get(active_sheet).map(String::as_str).unwrap_or_default()),
+            &[(row, col)],
+            &dependents_map,
+            &column_dependents_map,
+            &row_dependents_map,
+            &cross_sheet_dependents_map,
+        );
         let perf_tend = Instant::now();
         log_perf!("CELL",
             "update_cell({},{}) manual_mode | locks={:.2}ms parse+deps={:.2}ms store={:.2}ms TOTAL={:.2}ms",
@@ -1336,6 +1726,17 @@ fn update_cell_impl(
         );
     }
 
+    // Apply formatting requested by SET.* functions in the evaluated formulas
+    {
+        let mut rh = state.row_heights.lock().unwrap();
+        let mut cw = state.column_widths.lock().unwrap();
+        let (effect_dim_changes, effect_style_refresh) = crate::apply_pending_ui_effects(
+            state, &mut grids, &sheet_names, &mut styles, &mut rh, &mut cw, active_sheet,
+        );
+        dimension_changes.extend(effect_dim_changes);
+        needs_style_refresh = needs_style_refresh || effect_style_refresh;
+    }
+
     // Re-evaluate computed properties affected by changed cells
     {
         let cp_dependents = state.computed_prop_dependents.lock().unwrap();
@@ -1355,7 +1756,6 @@ fn update_cell_impl(
                     &mut cp_storage,
                     &cp_dependents,
                     &mut grids,
-                    &mut grid,
                     &sheet_names,
                     active_sheet,
                     &mut rh,
@@ -1418,18 +1818,18 @@ fn update_cell_impl(
 ///
 /// Steps: evaluate the cached AST (or, on a cache miss, parse + resolve
 /// names/tables/spill refs and cache the converted AST), clear the cell's
-/// previous spill range, spill new array results (or mark the origin #VALUE!
-/// when blocked), write the result to both `grid` (active-sheet mirror) and
-/// `grids[active_sheet]`, and append `CellData` for every touched cell
+/// previous spill range, spill new array results (or mark the origin #SPILL!
+/// when blocked), write the result to `grids[active_sheet]`, and append `CellData` for every touched cell
 /// (cleared spill cells, new spill cells, origin) to `updated_cells`.
 ///
-/// Locking: takes `state.spill_ranges` / `state.spill_hosts` briefly, AFTER
+/// Locking: takes `state.spill_ranges` / `state.spill_hosts` (and
+/// `state.ui_effects` for SET.* requests and `state.hyperlinks` for HYPERLINK
+/// links) briefly, AFTER
 /// the caller's grid locks — the same order `update_cell` uses. The caller
-/// holds grid/grids/styles/locale/tables/... and passes the guards' contents.
+/// holds grids/styles/locale/tables/... and passes the guards' contents.
 #[allow(clippy::too_many_arguments)]
 pub(crate) fn reevaluate_formula_cell(
     state: &AppState,
-    grid: &mut Grid,
     grids: &mut Vec<Grid>,
     sheet_names: &[String],
     active_sheet: usize,
@@ -1441,6 +1841,7 @@ pub(crate) fn reevaluate_formula_cell(
     udf_resolver: Option<&dyn Fn(&str, &[EvalResult]) -> Option<EvalResult>>,
     cube: Option<&std::sync::Arc<engine::CubePrefetch>>,
     control_values: Option<&std::sync::Arc<crate::control_values::ControlValuesMap>>,
+    hidden_rows: &HashSet<u32>,
     styles: &StyleRegistry,
     locale: &engine::LocaleSettings,
     merge_lookup: &std::collections::HashMap<(u32, u32), &MergedRegion>,
@@ -1457,14 +1858,15 @@ pub(crate) fn reevaluate_formula_cell(
     // fn doc). Mirrors the main-edit EvalContext in update_cell, except
     // row_heights/column_widths stay None: cloning those maps per dependent
     // is too expensive on this hot path, so GET.ROW.HEIGHT-style dependents
-    // keep their fallback behavior.
+    // keep their fallback behavior. Hidden rows are cloned only when a filter
+    // or outline actually hides something.
     let eval_ctx = engine::EvalContext {
         cube_prefetch: cube.cloned(),
         current_row: Some(dep_row),
         current_col: Some(dep_col),
         row_heights: None,
         column_widths: None,
-        hidden_rows: None,
+        hidden_rows: (!hidden_rows.is_empty()).then(|| hidden_rows.clone()),
         control_values: control_values.cloned(),
     };
 
@@ -1538,7 +1940,7 @@ pub(crate) fn reevaluate_formula_cell(
             // Fallback to string-based evaluation (no spill support)
             // (GET.CONTROLVALUE unavailable here (v1): string path)
             let cv = evaluate_formula_multi_sheet_with_files(
-                &*grids, sheet_names, active_sheet, formula, user_files,
+                &*grids, sheet_names, active_sheet, formula, engine::EvalContext::at(dep_row, dep_col), user_files,
             );
             let er = match cv {
                 engine::CellValue::Number(n) => engine::EvalResult::Number(n),
@@ -1558,15 +1960,13 @@ pub(crate) fn reevaluate_formula_cell(
         if let Some(old_spill_cells) = spill_ranges.remove(&(active_sheet, dep_row, dep_col)) {
             for (sr, sc) in &old_spill_cells {
                 spill_hosts.remove(&(active_sheet, *sr, *sc));
-                grid.cells.remove(&(*sr, *sc));
-                if active_sheet < grids.len() {
-                    grids[active_sheet].cells.remove(&(*sr, *sc));
-                }
+                grids[active_sheet].cells.remove(&(*sr, *sc));
+                grids[active_sheet].mark_changed(*sr, *sc);
                 updated_cells.push(CellData {
                     row: *sr, col: *sc, display: String::new(),
                     display_color: None, formula: None, style_index: 0,
                     row_span: 1, col_span: 1, sheet_index: None,
-                    rich_text: None, accounting_layout: None,
+                    rich_text: None, accounting_layout: None, error_detail: None,
                 });
             }
         }
@@ -1582,7 +1982,7 @@ pub(crate) fn reevaluate_formula_cell(
             if dr == 0 && dc == 0 { continue; }
             let target_r = dep_row + dr;
             let target_c = dep_col + dc;
-            if let Some(existing) = grid.get_cell(target_r, target_c) {
+            if let Some(existing) = grids[active_sheet].get_cell(target_r, target_c) {
                 if existing.value != engine::CellValue::Empty {
                     // Check if it's a spill cell from this same origin
                     let spill_hosts = state.spill_hosts.lock().unwrap();
@@ -1597,7 +1997,7 @@ pub(crate) fn reevaluate_formula_cell(
         }
 
         if spill_blocked {
-            engine::CellValue::Error(engine::CellError::Value)
+            engine::CellValue::Error(engine::CellError::Spill)
         } else {
             // Write spill cells
             let mut new_spill_cells = Vec::new();
@@ -1614,11 +2014,9 @@ pub(crate) fn reevaluate_formula_cell(
                     value: cv.clone(),
                     style_index: 0,
                     rich_text: None,
+                    quote_prefix: false,
                 };
-                grid.set_cell(target_r, target_c, spill_cell.clone());
-                if active_sheet < grids.len() {
-                    grids[active_sheet].set_cell(target_r, target_c, spill_cell);
-                }
+                grids[active_sheet].set_cell(target_r, target_c, spill_cell);
 
                 let style = styles.get(0);
                 let display = format_cell_value(cv, style, locale);
@@ -1626,7 +2024,7 @@ pub(crate) fn reevaluate_formula_cell(
                     row: target_r, col: target_c, display,
                     display_color: None, formula: None, style_index: 0,
                     row_span: 1, col_span: 1, sheet_index: None,
-                    rich_text: None, accounting_layout: None,
+                    rich_text: None, accounting_layout: None, error_detail: None,
                 });
 
                 new_spill_cells.push((target_r, target_c));
@@ -1643,16 +2041,18 @@ pub(crate) fn reevaluate_formula_cell(
         raw_result.to_cell_value()
     };
 
+    crate::process_ui_effects(state, active_sheet, dep_row, dep_col, engine::take_ui_effects());
+
     // Update the origin cell
+    let precision_as_displayed = state.calculation_settings.lock().unwrap().precision_as_displayed;
     let mut updated_dep = dep_cell.clone();
-    updated_dep.value = cell_value;
+    updated_dep.value =
+        crate::calculation::stored_result(cell_value, styles.get(dep_cell.style_index), precision_as_displayed);
+    crate::record_error_detail(&mut state.error_details.lock().unwrap(), (active_sheet, dep_row, dep_col), &updated_dep.value);
     if let Some(ast) = ast_to_cache {
         updated_dep.set_cached_ast(ast);
     }
-    grid.set_cell(dep_row, dep_col, updated_dep.clone());
-    if active_sheet < grids.len() {
-        grids[active_sheet].set_cell(dep_row, dep_col, updated_dep.clone());
-    }
+    grids[active_sheet].set_cell(dep_row, dep_col, updated_dep.clone());
 
     let dep_style = styles.get(updated_dep.style_index);
     let dep_display = format_cell_value(&updated_dep.value, dep_style, locale);
@@ -1683,6 +2083,7 @@ pub(crate) fn reevaluate_formula_cell(
         sheet_index: None,
         rich_text: None,
         accounting_layout: None,
+        error_detail: None,
     });
 }
 
@@ -1706,7 +2107,6 @@ pub(crate) fn reevaluate_formula_cell(
 /// merge spans, exactly like `update_cell` reports them.
 #[allow(clippy::too_many_arguments)]
 pub(crate) fn cascade_cross_sheet_dependents(
-    grid: &mut Grid,
     grids: &mut Vec<Grid>,
     sheet_names: &[String],
     active_sheet: usize,
@@ -1761,6 +2161,7 @@ pub(crate) fn cascade_cross_sheet_dependents(
                                     sheet_names,
                                     *dep_sheet_idx,
                                     cached_ast,
+                                    engine::EvalContext::at(*dep_row, *dep_col),
                                     user_files,
                                     None,
                                     None,
@@ -1774,6 +2175,7 @@ pub(crate) fn cascade_cross_sheet_dependents(
                                     sheet_names,
                                     *dep_sheet_idx,
                                     &formula,
+                                    engine::EvalContext::at(*dep_row, *dep_col),
                                     user_files,
                                 )
                             };
@@ -1786,14 +2188,10 @@ pub(crate) fn cascade_cross_sheet_dependents(
                                 updated_dep.clone(),
                             );
 
-                            // If the dependent is on the active sheet, also update the
-                            // active-sheet grid mutex so both stay in sync. This happens
-                            // when a named range's refers_to contains a sheet prefix
-                            // pointing to the same sheet (e.g., =Sheet1!$E$2*10).
+                            // A dependent on the active sheet happens when a named
+                            // range's refers_to carries a sheet prefix pointing to the
+                            // same sheet (e.g., =Sheet1!$E$2*10).
                             let is_same_sheet = *dep_sheet_idx == active_sheet;
-                            if is_same_sheet {
-                                grid.set_cell(*dep_row, *dep_col, updated_dep.clone());
-                            }
 
                             // Format the display value and add to updated_cells
                             let dep_style = styles.get(updated_dep.style_index);
@@ -1833,6 +2231,7 @@ pub(crate) fn cascade_cross_sheet_dependents(
                                 sheet_index: dep_sheet_index,
                                 rich_text: None,
                                 accounting_layout: None,
+                                error_detail: None,
                             });
 
                             // Add this updated cell to the work queue so its dependents also get recalculated
@@ -1878,6 +2277,7 @@ pub(crate) fn cascade_cross_sheet_dependents(
                                     sheet_names,
                                     source_sheet_idx,
                                     cached_ast,
+                                    engine::EvalContext::at(ss_dep_row, ss_dep_col),
                                     user_files,
                                     None,
                                     None,
@@ -1891,6 +2291,7 @@ pub(crate) fn cascade_cross_sheet_dependents(
                                     sheet_names,
                                     source_sheet_idx,
                                     &formula,
+                                    engine::EvalContext::at(ss_dep_row, ss_dep_col),
                                     user_files,
                                 )
                             };
@@ -1923,6 +2324,7 @@ pub(crate) fn cascade_cross_sheet_dependents(
                                 sheet_index: Some(source_sheet_idx),
                                 rich_text: None,
                                 accounting_layout: None,
+                                error_detail: None,
                             });
 
                             // Add this updated cell to the work queue so its dependents also get recalculated
@@ -2002,8 +2404,6 @@ pub fn update_cells_batch(
         }
     };
 
-    // Plain refs that outlive the State wrappers moved into the core below
-    // (tauri's State::inner returns &'r T) — needed for the post-batch recalc.
     let state_ref = state.inner();
     let user_files_ref = user_files_state.inner();
     let pivot_ref = pivot_state.inner();
@@ -2011,10 +2411,10 @@ pub fn update_cells_batch(
     let ribbon_ref = ribbon_filter_state.inner();
 
     let mut cells = update_cells_batch_with_controls(
-        state,
-        file_state,
-        user_files_state,
-        pivot_state,
+        state_ref,
+        file_state.inner(),
+        user_files_ref,
+        pivot_ref,
         updates,
         udf_results,
         Some(control_values),
@@ -2036,6 +2436,10 @@ pub fn update_cells_batch(
         cells.extend(extra);
     }
 
+    flush_edit_changes(state_ref, &cells);
+
+    crate::pivot::source_watch::source_cells_changed(state_ref, pivot_ref, Some((pane_ref, ribbon_ref)), &cells);
+
     Ok(cells)
 }
 
@@ -2044,10 +2448,10 @@ pub fn update_cells_batch(
 /// invoked from mcp/tools.rs, owned by a parallel workstream).
 /// `control_values: None` => GET.CONTROLVALUE evaluates to #N/A in this pass (v1).
 pub(crate) fn update_cells_batch_with_controls(
-    state: State<AppState>,
-    file_state: State<FileState>,
-    user_files_state: State<UserFilesState>,
-    pivot_state: State<'_, crate::pivot::PivotState>,
+    state: &AppState,
+    file_state: &FileState,
+    user_files_state: &UserFilesState,
+    pivot_state: &crate::pivot::PivotState,
     updates: Vec<crate::api_types::CellUpdateInput>,
     udf_results: Option<std::collections::HashMap<String, crate::scripting::udf::UdfValue>>,
     control_values: Option<std::sync::Arc<crate::control_values::ControlValuesMap>>,
@@ -2063,6 +2467,9 @@ pub(crate) fn update_cells_batch_with_controls(
     // Build the apply-time UDF resolver from the pre-fetched results table (if
     // any). Omitting udfResults -> None -> behavior identical to before.
     let udf_resolver = udf_results.as_ref().map(|t| crate::scripting::udf::make_udf_resolver(t));
+    // Filtered/collapsed rows for SUBTOTAL 101-111, before the grid locks below.
+    let hidden_rows =
+        crate::autofilter::sheet_hidden_rows(state, *state.active_sheet.lock().unwrap());
     let user_files = user_files_state.files.lock().unwrap();
     let perf_batch_size = updates.len();
 
@@ -2114,10 +2521,9 @@ pub(crate) fn update_cells_batch_with_controls(
 
     // Acquire all locks once
     let sheet_names = state.sheet_names.lock().unwrap();
-    let mut grid = state.grid.lock().unwrap();
     let mut grids = state.grids.lock().unwrap();
     let active_sheet = *state.active_sheet.lock().unwrap();
-    let styles = state.style_registry.lock().unwrap();
+    let mut styles = state.style_registry.lock().unwrap();
     let mut dependents_map = state.dependents.lock().unwrap();
     let mut dependencies_map = state.dependencies.lock().unwrap();
     let mut column_dependents_map = state.column_dependents.lock().unwrap();
@@ -2126,7 +2532,7 @@ pub(crate) fn update_cells_batch_with_controls(
     let mut row_dependencies_map = state.row_dependencies.lock().unwrap();
     let mut cross_sheet_dependents_map = state.cross_sheet_dependents.lock().unwrap();
     let mut cross_sheet_dependencies_map = state.cross_sheet_dependencies.lock().unwrap();
-    let calc_mode = state.calculation_mode.lock().unwrap();
+    let calc_mode = state.calculation_settings.lock().unwrap().mode;
     let mut undo_stack = state.undo_stack.lock().unwrap();
     let merged_regions = state.merged_regions.lock().unwrap();
     let locale = state.locale.lock().unwrap();
@@ -2147,7 +2553,7 @@ pub(crate) fn update_cells_batch_with_controls(
 
     // Pre-fetch writeback submissions so GATHER formulas see current data
     // (empty map, no registry I/O, when the workbook has no writeback regions).
-    let gather_data = crate::calp_commands::build_gather_data(&state);
+    let gather_data = crate::calp_commands::build_gather_data(state);
     let gather_fn = |region_id: &str| -> engine::GatherRegionData {
         gather_data.get(region_id).cloned().unwrap_or_default()
     };
@@ -2164,6 +2570,13 @@ pub(crate) fn update_cells_batch_with_controls(
 
     let mut updated_cells = Vec::new();
     let mut cells_needing_recalc: Vec<(u32, u32)> = Vec::new();
+    let mut volatile_cells = state.volatile_cells.lock().unwrap();
+    // Dependency-map updates are deferred until every cell of the batch is
+    // written: evaluation inside the loop reads the grids, never the maps, so
+    // the maps are rewritten once per cell below instead of being churned
+    // while the batch is still half-applied. Later entries win for a cell
+    // written twice (same order as the writes).
+    let mut deferred_refs: Vec<((u32, u32), crate::ExtractedRefs)> = Vec::with_capacity(updates.len());
     // Pre/post cell states collected for subscriber override capture.
     let mut override_edits: Vec<(u32, u32, Option<engine::Cell>, Option<engine::Cell>)> = Vec::new();
 
@@ -2184,39 +2597,13 @@ pub(crate) fn update_cells_batch_with_controls(
         // and checking 240 cells individually would be slow. The frontend should validate.
 
         // Record previous state for undo
-        let previous_cell = grid.get_cell(row, col).cloned();
+        let previous_cell = grids[active_sheet].get_cell(row, col).cloned();
 
         // Handle empty value - clear the cell
         if value.trim().is_empty() {
-            grid.clear_cell(row, col);
-            if active_sheet < grids.len() {
-                grids[active_sheet].clear_cell(row, col);
-            }
-            // Clear dependencies
-            update_cross_sheet_dependencies(
-                (active_sheet, row, col),
-                Default::default(),
-                &mut cross_sheet_dependencies_map,
-                &mut cross_sheet_dependents_map,
-            );
-            update_dependencies(
-                (row, col),
-                Default::default(),
-                &mut dependencies_map,
-                &mut dependents_map,
-            );
-            update_column_dependencies(
-                (row, col),
-                Default::default(),
-                &mut column_dependencies_map,
-                &mut column_dependents_map,
-            );
-            update_row_dependencies(
-                (row, col),
-                Default::default(),
-                &mut row_dependencies_map,
-                &mut row_dependents_map,
-            );
+            grids[active_sheet].clear_cell(row, col);
+            // Clear dependencies (applied after the loop)
+            deferred_refs.push(((row, col), crate::ExtractedRefs::new()));
 
             let (row_span, col_span) = if let Some(region) = merge_lookup.get(&(row, col)) {
                 (
@@ -2239,10 +2626,12 @@ pub(crate) fn update_cells_batch_with_controls(
                 sheet_index: None,
                 rich_text: None,
                 accounting_layout: None,
+                error_detail: None,
             });
 
-            override_edits.push((row, col, previous_cell.clone(), grid.get_cell(row, col).cloned()));
+            override_edits.push((row, col, previous_cell.clone(), grids[active_sheet].get_cell(row, col).cloned()));
             undo_stack.record_cell_change(row, col, previous_cell);
+            crate::track_volatile_cell(&mut volatile_cells, (row, col), None);
             cells_needing_recalc.push((row, col));
             continue;
         }
@@ -2253,18 +2642,27 @@ pub(crate) fn update_cells_batch_with_controls(
         } else {
             parse_cell_input(value, &locale)
         };
+        let mut name_refs = None;
 
         // Apply explicit style from input if provided, otherwise preserve existing
-        if let Some(explicit_style) = update.style_index {
-            cell.style_index = explicit_style;
-        } else if let Some(existing) = grid.get_cell(row, col) {
-            cell.style_index = existing.style_index;
+        // (or the row/column style for a new cell)
+        cell.style_index = update
+            .style_index
+            .unwrap_or_else(|| grids[active_sheet].effective_style_index(row, col));
+        if let Some(text_cell) = crate::text_format_input(value, styles.get(cell.style_index)) {
+            cell = engine::Cell { style_index: cell.style_index, ..text_cell };
+        }
+        if update.style_index.is_none() && matches!(cell.value, engine::CellValue::Number(_)) {
+            if let Some(index) = crate::style_for_date_input(&mut styles, cell.style_index, value, &locale) {
+                cell.style_index = index;
+            }
         }
 
         // If it's a formula, evaluate it
         if let Some(formula) = cell.formula_string() {
             match parser::parse(&formula) {
                 Ok(parsed) => {
+                    name_refs = crate::name_dependents::collect_refs(&formula, &parsed);
                     // Resolve named references (AST splicing)
                     let resolved = if crate::ast_has_named_refs(&parsed) {
                         let named_ranges_map = state.named_ranges.lock().unwrap();
@@ -2313,47 +2711,10 @@ pub(crate) fn update_cells_batch_with_controls(
                         resolved
                     };
 
-                    let refs = extract_all_references(&resolved, &grid);
-
-                    update_dependencies(
-                        (row, col),
-                        refs.cells,
-                        &mut dependencies_map,
-                        &mut dependents_map,
-                    );
-                    update_column_dependencies(
-                        (row, col),
-                        refs.columns,
-                        &mut column_dependencies_map,
-                        &mut column_dependents_map,
-                    );
-                    update_row_dependencies(
-                        (row, col),
-                        refs.rows,
-                        &mut row_dependencies_map,
-                        &mut row_dependents_map,
-                    );
-
-                    // Normalize cross-sheet references
-                    let normalized_cross_sheet_refs: rustc_hash::FxHashSet<(String, u32, u32)> = refs
-                        .cross_sheet_cells
-                        .iter()
-                        .filter_map(|(parsed_sheet_name, r, c)| {
-                            let normalized = sheet_names
-                                .iter()
-                                .find(|name| name.eq_ignore_ascii_case(parsed_sheet_name))
-                                .cloned()
-                                .unwrap_or_else(|| parsed_sheet_name.clone());
-                            Some((normalized, *r, *c))
-                        })
-                        .collect();
-
-                    update_cross_sheet_dependencies(
-                        (active_sheet, row, col),
-                        normalized_cross_sheet_refs,
-                        &mut cross_sheet_dependencies_map,
-                        &mut cross_sheet_dependents_map,
-                    );
+                    // Cross-sheet references are normalized (and 3D references
+                    // expanded) when the deferred updates are applied.
+                    let refs = extract_all_references(&resolved, &grids[active_sheet]);
+                    deferred_refs.push(((row, col), refs));
 
                     // PERF: Convert the already-parsed AST directly instead of re-parsing.
                     // This eliminates a redundant parse_formula() call per cell.
@@ -2367,7 +2728,7 @@ pub(crate) fn update_cells_batch_with_controls(
                         current_col: Some(col),
                         row_heights: None,
                         column_widths: None,
-                        hidden_rows: None,
+                        hidden_rows: (!hidden_rows.is_empty()).then(|| hidden_rows.clone()),
                         control_values: Some(control_values.clone()),
                     };
                     let raw_result = crate::evaluate_formula_raw_with_files_and_pivot(
@@ -2390,16 +2751,15 @@ pub(crate) fn update_cells_batch_with_controls(
                         if let Some(old_spill_cells) = spill_ranges.remove(&(active_sheet, row, col)) {
                             for (sr, sc) in &old_spill_cells {
                                 spill_hosts.remove(&(active_sheet, *sr, *sc));
-                                grid.cells.remove(&(*sr, *sc));
-                                if active_sheet < grids.len() {
-                                    grids[active_sheet].cells.remove(&(*sr, *sc));
-                                }
+                                grids[active_sheet].cells.remove(&(*sr, *sc));
+                                grids[active_sheet].mark_changed(*sr, *sc);
                                 updated_cells.push(CellData {
                                     row: *sr, col: *sc, display: String::new(),
                                     display_color: None, formula: None, style_index: 0,
                                     row_span: 1, col_span: 1, sheet_index: None,
                                     rich_text: None,
                                     accounting_layout: None,
+                                    error_detail: None,
                                 });
                             }
                         }
@@ -2415,7 +2775,7 @@ pub(crate) fn update_cells_batch_with_controls(
                             if dr == 0 && dc == 0 { continue; }
                             let target_r = row + dr;
                             let target_c = col + dc;
-                            if let Some(existing) = grid.get_cell(target_r, target_c) {
+                            if let Some(existing) = grids[active_sheet].get_cell(target_r, target_c) {
                                 if existing.value != engine::CellValue::Empty {
                                     spill_blocked = true;
                                     break;
@@ -2424,7 +2784,7 @@ pub(crate) fn update_cells_batch_with_controls(
                         }
 
                         if spill_blocked {
-                            cell.value = engine::CellValue::Error(engine::CellError::Value);
+                            cell.value = engine::CellValue::Error(engine::CellError::Spill);
                         } else {
                             cell.value = raw_result.to_cell_value();
 
@@ -2442,11 +2802,9 @@ pub(crate) fn update_cells_batch_with_controls(
                                     value: cv.clone(),
                                     style_index: 0,
                                     rich_text: None,
+                                    quote_prefix: false,
                                 };
-                                grid.set_cell(target_r, target_c, spill_cell.clone());
-                                if active_sheet < grids.len() {
-                                    grids[active_sheet].set_cell(target_r, target_c, spill_cell);
-                                }
+                                grids[active_sheet].set_cell(target_r, target_c, spill_cell);
 
                                 let spill_style = styles.get(0);
                                 let display = format_cell_value(&cv, spill_style, &locale);
@@ -2456,6 +2814,7 @@ pub(crate) fn update_cells_batch_with_controls(
                                     row_span: 1, col_span: 1, sheet_index: None,
                                     rich_text: None,
                                     accounting_layout: None,
+                                    error_detail: None,
                                 });
 
                                 new_spill_cells.push((target_r, target_c));
@@ -2472,43 +2831,23 @@ pub(crate) fn update_cells_batch_with_controls(
                 }
                 Err(_e) => {
                     let result =
-                        evaluate_formula_multi_sheet_with_files(&grids, &sheet_names, active_sheet, &formula, &user_files);
+                        evaluate_formula_multi_sheet_with_files(&grids, &sheet_names, active_sheet, &formula, engine::EvalContext::at(row, col), &user_files);
                     cell.value = result;
                 }
             }
         } else {
-            // Clear dependencies for non-formula cells
-            update_dependencies(
-                (row, col),
-                Default::default(),
-                &mut dependencies_map,
-                &mut dependents_map,
-            );
-            update_cross_sheet_dependencies(
-                (active_sheet, row, col),
-                Default::default(),
-                &mut cross_sheet_dependencies_map,
-                &mut cross_sheet_dependents_map,
-            );
-            update_column_dependencies(
-                (row, col),
-                Default::default(),
-                &mut column_dependencies_map,
-                &mut column_dependents_map,
-            );
-            update_row_dependencies(
-                (row, col),
-                Default::default(),
-                &mut row_dependencies_map,
-                &mut row_dependents_map,
-            );
+            // Clear dependencies for non-formula cells (applied after the loop)
+            deferred_refs.push(((row, col), crate::ExtractedRefs::new()));
         }
 
         // Store the cell
-        grid.set_cell(row, col, cell.clone());
-        if active_sheet < grids.len() {
-            grids[active_sheet].set_cell(row, col, cell.clone());
-        }
+        grids[active_sheet].set_cell(row, col, cell.clone());
+        crate::name_dependents::track_cell(
+            &mut state.name_ref_sources.lock().unwrap(),
+            (active_sheet, row, col),
+            name_refs,
+            &cell,
+        );
 
         // Get the display value
         let style = styles.get(cell.style_index);
@@ -2535,28 +2874,50 @@ pub(crate) fn update_cells_batch_with_controls(
             sheet_index: None,
             rich_text: None,
             accounting_layout: None,
+            error_detail: None,
         });
 
-        override_edits.push((row, col, previous_cell.clone(), grid.get_cell(row, col).cloned()));
+        override_edits.push((row, col, previous_cell.clone(), grids[active_sheet].get_cell(row, col).cloned()));
         undo_stack.record_cell_change(row, col, previous_cell);
+        crate::track_volatile_cell(&mut volatile_cells, (row, col), grids[active_sheet].get_cell(row, col));
         cells_needing_recalc.push((row, col));
     }
 
+    // Apply the deferred dependency-map updates in one pass.
+    for ((row, col), refs) in deferred_refs {
+        let cross_sheet_refs = crate::resolve_cross_sheet_refs(&refs, &sheet_names);
+        update_dependencies((row, col), refs.cells, &mut dependencies_map, &mut dependents_map);
+        update_column_dependencies(
+            (row, col),
+            refs.columns,
+            &mut column_dependencies_map,
+            &mut column_dependents_map,
+        );
+        update_row_dependencies((row, col), refs.rows, &mut row_dependencies_map, &mut row_dependents_map);
+        update_cross_sheet_dependencies(
+            (active_sheet, row, col),
+            cross_sheet_refs,
+            &mut cross_sheet_dependencies_map,
+            &mut cross_sheet_dependents_map,
+        );
+    }
+
     // Record subscriber overrides for all edited cells (subscribed sheets only)
-    crate::calp_commands::record_subscription_override_edits(&state, active_sheet, &override_edits);
+    crate::calp_commands::record_subscription_override_edits(state, active_sheet, &override_edits);
 
     let perf_t2_processed = Instant::now();
 
     // Recalculate dependents if automatic mode - do this ONCE after all updates
-    if *calc_mode == "automatic" {
+    if calc_mode.is_automatic() {
         // One multi-root traversal for the whole batch: a single BFS + Kahn
         // over the union of affected cells instead of one full pass per edited
         // cell. Batch cells are members of the ordering, so a formula written
         // by this batch is re-evaluated AFTER the batch cells it reads (fixes
         // in-batch stale values); value-only batch cells are skipped by the
         // formula check in the evaluation loop below.
+        let seeds = crate::with_volatile_seeds(&cells_needing_recalc, &volatile_cells);
         let mut all_recalc_order: Vec<(u32, u32)> =
-            crate::recalc_order_from_seeds(&cells_needing_recalc, &dependents_map, true);
+            crate::recalc_order_from_seeds(&seeds, &dependents_map, true);
         let mut recalc_set: crate::CoordSet = all_recalc_order.iter().copied().collect();
 
         // Also get column/row dependents (appended after the topological
@@ -2578,10 +2939,24 @@ pub(crate) fn update_cells_batch_with_controls(
 
         // PERF-20: skip per-dependent formula render + IPC payload for wide cascades.
         let include_cascade_formulas = all_recalc_order.len() <= CASCADE_FORMULA_LIMIT;
+        let batch_cells: crate::CoordSet = cells_needing_recalc.iter().copied().collect();
 
         // Recalculate all dependents
         for (dep_row, dep_col) in &all_recalc_order {
-            if let Some(dep_cell) = grid.get_cell(*dep_row, *dep_col) {
+            // A formula this batch wrote was evaluated when it was stored;
+            // only one that reads something the batch touched goes again.
+            if batch_cells.contains(&(*dep_row, *dep_col))
+                && !batch_formula_is_stale(
+                    (*dep_row, *dep_col),
+                    &recalc_set,
+                    &dependencies_map,
+                    &column_dependencies_map,
+                    &row_dependencies_map,
+                )
+            {
+                continue;
+            }
+            if let Some(dep_cell) = grids[active_sheet].get_cell(*dep_row, *dep_col) {
                 if let Some(formula) = dep_cell.formula_string() {
                     let result = if let Some(cached_ast) = dep_cell.get_cached_ast() {
                         crate::evaluate_formula_raw_with_ast_files_and_cube(
@@ -2589,6 +2964,7 @@ pub(crate) fn update_cells_batch_with_controls(
                             &sheet_names,
                             active_sheet,
                             cached_ast,
+                            engine::EvalContext::at(*dep_row, *dep_col),
                             &user_files,
                             None,
                             None,
@@ -2623,6 +2999,7 @@ pub(crate) fn update_cells_batch_with_controls(
                                 &sheet_names,
                                 active_sheet,
                                 &engine_ast,
+                                engine::EvalContext::at(*dep_row, *dep_col),
                                 &user_files,
                                 None,
                                 None,
@@ -2631,10 +3008,7 @@ pub(crate) fn update_cells_batch_with_controls(
                             let mut updated_with_ast = dep_cell.clone();
                             updated_with_ast.set_cached_ast(engine_ast);
                             updated_with_ast.value = result.clone();
-                            grid.set_cell(*dep_row, *dep_col, updated_with_ast.clone());
-                            if active_sheet < grids.len() {
-                                grids[active_sheet].set_cell(*dep_row, *dep_col, updated_with_ast.clone());
-                            }
+                            grids[active_sheet].set_cell(*dep_row, *dep_col, updated_with_ast.clone());
 
                             let dep_style = styles.get(updated_with_ast.style_index);
                             let dep_display = format_cell_value(&updated_with_ast.value, dep_style, &locale);
@@ -2661,19 +3035,16 @@ pub(crate) fn update_cells_batch_with_controls(
                                 sheet_index: None,
                                 rich_text: None,
                                 accounting_layout: None,
+                                error_detail: None,
                             });
                             continue;
                         }
-                        evaluate_formula_multi_sheet_with_files(&grids, &sheet_names, active_sheet, &formula, &user_files)
+                        evaluate_formula_multi_sheet_with_files(&grids, &sheet_names, active_sheet, &formula, engine::EvalContext::at(*dep_row, *dep_col), &user_files)
                     };
 
                     let mut updated_dep = dep_cell.clone();
                     updated_dep.value = result;
-                    grid.set_cell(*dep_row, *dep_col, updated_dep.clone());
-
-                    if active_sheet < grids.len() {
-                        grids[active_sheet].set_cell(*dep_row, *dep_col, updated_dep.clone());
-                    }
+                    grids[active_sheet].set_cell(*dep_row, *dep_col, updated_dep.clone());
 
                     let dep_style = styles.get(updated_dep.style_index);
                     let dep_display = format_cell_value(&updated_dep.value, dep_style, &locale);
@@ -2700,6 +3071,7 @@ pub(crate) fn update_cells_batch_with_controls(
                         sheet_index: None,
                         rich_text: None,
                         accounting_layout: None,
+                        error_detail: None,
                     });
                 }
             }
@@ -2741,6 +3113,7 @@ pub(crate) fn update_cells_batch_with_controls(
                                         &sheet_names,
                                         *dep_sheet_idx,
                                         cached_ast,
+                                        engine::EvalContext::at(*dep_row, *dep_col),
                                         &user_files,
                                         None,
                                         None,
@@ -2753,6 +3126,7 @@ pub(crate) fn update_cells_batch_with_controls(
                                         &sheet_names,
                                         *dep_sheet_idx,
                                         &formula,
+                                        engine::EvalContext::at(*dep_row, *dep_col),
                                         &user_files,
                                     )
                                 };
@@ -2780,6 +3154,7 @@ pub(crate) fn update_cells_batch_with_controls(
                                     sheet_index: Some(*dep_sheet_idx),
                                     rich_text: None,
                                     accounting_layout: None,
+                                    error_detail: None,
                                 });
 
                                 if let Some(dep_sheet_name) = sheet_names.get(*dep_sheet_idx) {
@@ -2807,6 +3182,18 @@ pub(crate) fn update_cells_batch_with_controls(
             perf_tend.duration_since(perf_t0).as_secs_f64() * 1000.0
         );
     } else {
+        // Manual mode: evaluate nothing, only remember which formulas the
+        // batch invalidated so calculate_now / get_calculation_state see them.
+        mark_dependents_dirty(
+            &mut state.dirty_cells.lock().unwrap(),
+            (active_sheet, &current_sheet_name),
+            &cells_needing_recalc,
+            &dependents_map,
+            &column_dependents_map,
+            &row_dependents_map,
+            &cross_sheet_dependents_map,
+        );
+
         let perf_tend = Instant::now();
         log_perf!("BATCH",
             "update_cells_batch(N={}) manual_mode | locks={:.2}ms process={:.2}ms TOTAL={:.2}ms",
@@ -2825,7 +3212,72 @@ pub(crate) fn update_cells_batch_with_controls(
     // Mark workbook as dirty
     if let Ok(mut modified) = file_state.is_modified.lock() { *modified = true; }
 
-    Ok(updated_cells)
+    Ok(consolidate_cell_updates(updated_cells))
+}
+
+/// Whether a formula written (and evaluated) by a batch must be evaluated
+/// again in the batch's recalc pass: it reads a cell in `touched` (written or
+/// recalculated by the batch), or whole columns/rows, which the per-cell
+/// dependency map does not cover.
+fn batch_formula_is_stale(
+    cell: (u32, u32),
+    touched: &crate::CoordSet,
+    dependencies: &crate::DependencyMap,
+    column_dependencies: &crate::StripeDependenciesMap,
+    row_dependencies: &crate::StripeDependenciesMap,
+) -> bool {
+    column_dependencies.contains_key(&cell)
+        || row_dependencies.contains_key(&cell)
+        || dependencies
+            .get(&cell)
+            .is_some_and(|precedents| precedents.iter().any(|p| touched.contains(p)))
+}
+
+/// Manual calculation mode: record the formula cells an edit of `edited` on
+/// `sheet` (index, name) invalidates — same-sheet dependents, whole
+/// column/row dependents and direct cross-sheet dependents — in `dirty`.
+pub(crate) fn mark_dependents_dirty(
+    dirty: &mut rustc_hash::FxHashSet<(usize, u32, u32)>,
+    sheet: (usize, &str),
+    edited: &[(u32, u32)],
+    dependents: &crate::DependencyMap,
+    column_dependents: &crate::StripeDependentsMap,
+    row_dependents: &crate::StripeDependentsMap,
+    cross_sheet_dependents: &crate::CrossSheetDependentsMap,
+) {
+    let (sheet_index, sheet_name) = sheet;
+    for (row, col) in crate::recalc_order_from_seeds(edited, dependents, false) {
+        dirty.insert((sheet_index, row, col));
+    }
+    for &(row, col) in edited {
+        for (dep_row, dep_col) in get_column_row_dependents((row, col), column_dependents, row_dependents) {
+            dirty.insert((sheet_index, dep_row, dep_col));
+        }
+        if let Some(cross_deps) = cross_sheet_dependents.get(&(sheet_name.to_string(), row, col)) {
+            dirty.extend(cross_deps.iter().copied());
+        }
+    }
+}
+
+/// Collapse a batch payload to one entry per cell. A cell can be pushed more
+/// than once during a batch (written by the batch and then recalculated as a
+/// dependent of a later batch cell, or cleared as a stale spill and rewritten);
+/// the LAST entry is the cell's final state. Order of first appearance is
+/// kept so the frontend still applies the edited cells first.
+pub(crate) fn consolidate_cell_updates(cells: Vec<CellData>) -> Vec<CellData> {
+    use std::collections::HashMap;
+    let mut slot: HashMap<(Option<usize>, u32, u32), usize> = HashMap::with_capacity(cells.len());
+    let mut out: Vec<CellData> = Vec::with_capacity(cells.len());
+    for cell in cells {
+        match slot.get(&(cell.sheet_index, cell.row, cell.col)) {
+            Some(&i) => out[i] = cell,
+            None => {
+                slot.insert((cell.sheet_index, cell.row, cell.col), out.len());
+                out.push(cell);
+            }
+        }
+    }
+    out
 }
 
 /// Clear a cell.
@@ -2842,8 +3294,7 @@ pub fn clear_cell(state: State<AppState>, file_state: State<FileState>, row: u32
     // Object-output protection (clearing a pivot/report cell).
     check_region_range_protection(&state, active_sheet, row, col, row, col)?;
 
-    let mut grid = state.grid.lock().unwrap();
-    let mut grids = state.grids.lock().unwrap();
+    let mut grid = state.get_active_grid();
     let mut dependents_map = state.dependents.lock().unwrap();
     let mut dependencies_map = state.dependencies.lock().unwrap();
     let mut column_dependents_map = state.column_dependents.lock().unwrap();
@@ -2858,10 +3309,6 @@ pub fn clear_cell(state: State<AppState>, file_state: State<FileState>, row: u32
     let previous_cell = grid.get_cell(row, col).cloned();
 
     grid.clear_cell(row, col);
-    // Also update the grids vector
-    if active_sheet < grids.len() {
-        grids[active_sheet].clear_cell(row, col);
-    }
 
     // Clear cross-sheet dependencies
     update_cross_sheet_dependencies(
@@ -2910,17 +3357,44 @@ pub fn clear_cell(state: State<AppState>, file_state: State<FileState>, row: u32
 }
 
 /// Clear a range of cells efficiently.
-/// Only clears cells that actually exist within the range.
+/// Only clears cells that actually exist within the range, as one undo step
+/// followed by one recalculation of everything that read them.
 /// Returns an error if any cell in the range is a spilled value (not the origin).
 #[tauri::command]
 pub fn clear_range(
     state: State<AppState>,
     file_state: State<FileState>,
+    user_files_state: State<UserFilesState>,
+    pivot_state: State<'_, crate::pivot::PivotState>,
+    pane_control_state: State<'_, crate::pane_control::PaneControlState>,
+    ribbon_filter_state: State<'_, crate::ribbon_filter::RibbonFilterState>,
     start_row: u32,
     start_col: u32,
     end_row: u32,
     end_col: u32,
 ) -> Result<u32, String> {
+    let cleared = clear_range_internal(&state, &file_state, start_row, start_col, end_row, end_col)?;
+    crate::calculation::recalc_dependents_of(
+        &state,
+        &user_files_state,
+        &pivot_state,
+        Some((&*pane_control_state, &*ribbon_filter_state)),
+        &cleared,
+    );
+    Ok(cleared.len() as u32)
+}
+
+/// Body of `clear_range`: clears the existing cells in the range and returns
+/// their coordinates. Every lock is dropped on return, so the caller can run
+/// the dependent recalculation.
+pub(crate) fn clear_range_internal(
+    state: &AppState,
+    file_state: &FileState,
+    start_row: u32,
+    start_col: u32,
+    end_row: u32,
+    end_col: u32,
+) -> Result<Vec<(u32, u32)>, String> {
     let active_sheet = *state.active_sheet.lock().unwrap();
 
     // Check if any cell in the range is a spill host (part of a spilled array, not the origin)
@@ -2930,10 +3404,9 @@ pub fn clear_range(
     }
 
     // Object-output protection (delete-key clear over a pivot/report region).
-    check_region_range_protection(&state, active_sheet, start_row, start_col, end_row, end_col)?;
+    check_region_range_protection(state, active_sheet, start_row, start_col, end_row, end_col)?;
 
-    let mut grid = state.grid.lock().unwrap();
-    let mut grids = state.grids.lock().unwrap();
+    let mut grid = state.get_active_grid();
     let mut dependents_map = state.dependents.lock().unwrap();
     let mut dependencies_map = state.dependencies.lock().unwrap();
     let mut column_dependents_map = state.column_dependents.lock().unwrap();
@@ -2944,24 +3417,12 @@ pub fn clear_range(
     let mut cross_sheet_dependencies_map = state.cross_sheet_dependencies.lock().unwrap();
     let mut undo_stack = state.undo_stack.lock().unwrap();
 
-    // Clamp to grid bounds to avoid iterating beyond used range
-    let effective_end_row = end_row.min(grid.max_row);
-    let effective_end_col = end_col.min(grid.max_col);
-
-    // Collect cells to clear (we need to collect first to avoid borrow issues)
-    let cells_to_clear: Vec<(u32, u32)> = grid
-        .cells
-        .keys()
-        .filter(|(r, c)| {
-            *r >= start_row && *r <= effective_end_row && *c >= start_col && *c <= effective_end_col
-        })
-        .cloned()
-        .collect();
-
-    let count = cells_to_clear.len() as u32;
+    let cells_to_clear = existing_cells_in_range(&grid, start_row, start_col, end_row, end_col);
 
-    // Begin undo transaction for batch operation
-    if count > 0 {
+    // One undo transaction for the batch, unless the caller already opened
+    // one (e.g. Clear All, which also clears comments and hyperlinks)
+    let opened_transaction = !cells_to_clear.is_empty() && !undo_stack.has_open_transaction();
+    if opened_transaction {
         undo_stack.begin_transaction(format!(
             "Clear range ({},{}) to ({},{})",
             start_row, start_col, end_row, end_col
@@ -2972,7 +3433,7 @@ pub fn clear_range(
     let mut override_edits: Vec<(u32, u32, Option<engine::Cell>, Option<engine::Cell>)> = Vec::new();
 
     // Clear each cell
-    for (row, col) in cells_to_clear {
+    for &(row, col) in &cells_to_clear {
         // Record previous state for undo
         let previous_cell = grid.get_cell(row, col).cloned();
         if previous_cell.is_some() {
@@ -2982,10 +3443,6 @@ pub fn clear_range(
 
         grid.clear_cell(row, col);
 
-        if active_sheet < grids.len() {
-            grids[active_sheet].clear_cell(row, col);
-        }
-
         // Clear dependencies
         update_cross_sheet_dependencies(
             (active_sheet, row, col),
@@ -3014,16 +3471,32 @@ pub fn clear_range(
     }
 
     // Record subscriber overrides for all cleared cells (subscribed sheets only)
-    crate::calp_commands::record_subscription_override_edits(&state, active_sheet, &override_edits);
+    crate::calp_commands::record_subscription_override_edits(state, active_sheet, &override_edits);
 
     // Commit undo transaction
-    if count > 0 {
+    if opened_transaction {
         undo_stack.commit_transaction();
+    }
+    if !cells_to_clear.is_empty() {
         // Mark workbook as dirty
         if let Ok(mut modified) = file_state.is_modified.lock() { *modified = true; }
     }
 
-    Ok(count)
+    Ok(cells_to_clear)
+}
+
+/// The cells that exist inside a rectangle, in row-major order. Walks the
+/// sparse cell map, so a whole-column or whole-row range costs as much as
+/// the cells it holds rather than every coordinate it spans.
+fn existing_cells_in_range(grid: &Grid, start_row: u32, start_col: u32, end_row: u32, end_col: u32) -> Vec<(u32, u32)> {
+    let mut cells: Vec<(u32, u32)> = grid
+        .cells
+        .keys()
+        .filter(|&&(r, c)| r >= start_row && r <= end_row && c >= start_col && c <= end_col)
+        .copied()
+        .collect();
+    cells.sort_unstable();
+    cells
 }
 
 /// Clear a range of cells with options for what to clear.
@@ -3031,15 +3504,57 @@ pub fn clear_range(
 /// - All: Clear both content and formatting (default)
 /// - Contents: Clear values only, keep formatting
 /// - Formats: Clear formatting only, keep values
-/// - Hyperlinks: Clear hyperlinks only (placeholder)
+/// - Hyperlinks: Clear hyperlinks only, keep content and formatting
 /// - RemoveHyperlinks: Remove hyperlinks and formatting, keep content
 /// - ResetContents: Reset to default state
 #[tauri::command]
 pub fn clear_range_with_options(
     state: State<AppState>,
     file_state: State<FileState>,
+    user_files_state: State<UserFilesState>,
+    pivot_state: State<'_, crate::pivot::PivotState>,
+    pane_control_state: State<'_, crate::pane_control::PaneControlState>,
+    ribbon_filter_state: State<'_, crate::ribbon_filter::RibbonFilterState>,
     params: ClearRangeParams,
 ) -> Result<ClearRangeResult, String> {
+    let clears_values = matches!(
+        params.apply_to,
+        ClearApplyTo::All | ClearApplyTo::Contents | ClearApplyTo::ResetContents
+    );
+    let mut result = clear_range_with_options_impl(&state, &file_state, params)?;
+
+    if clears_values {
+        // One recalculation pass for everything that read the cleared cells.
+        let cleared: Vec<(u32, u32)> = result.updated_cells.iter().map(|c| (c.row, c.col)).collect();
+        let recalculated = crate::calculation::recalc_dependents_of(
+            &state,
+            &user_files_state,
+            &pivot_state,
+            Some((&*pane_control_state, &*ribbon_filter_state)),
+            &cleared,
+        );
+        result.updated_cells.extend(recalculated);
+
+        // Cleared values inside a pivot's source range leave it stale.
+        crate::pivot::source_watch::source_cells_changed(
+            &state,
+            &pivot_state,
+            Some((&*pane_control_state, &*ribbon_filter_state)),
+            &result.updated_cells,
+        );
+    }
+
+    Ok(result)
+}
+
+/// Body of `clear_range_with_options` — every lock is acquired AND dropped
+/// inside, so the command wrapper can notify source-watched pivots afterwards.
+fn clear_range_with_options_impl(
+    state: &AppState,
+    file_state: &FileState,
+    params: ClearRangeParams,
+) -> Result<ClearRangeResult, String> {
+    use std::collections::HashMap;
     let active_sheet = *state.active_sheet.lock().unwrap();
 
     // Check if any cell in the range is a spill host (not the origin) — block content-clearing operations
@@ -3052,11 +3567,10 @@ pub fn clear_range_with_options(
         check_spill_protection(&spill_hosts, active_sheet, min_row, min_col, max_row, max_col)?;
         // Object-output protection: content clears cannot touch a pivot/report
         // region (format-only clears stay allowed, matching Excel).
-        check_region_range_protection(&state, active_sheet, min_row, min_col, max_row, max_col)?;
+        check_region_range_protection(state, active_sheet, min_row, min_col, max_row, max_col)?;
     }
 
-    let mut grid = state.grid.lock().unwrap();
-    let mut grids = state.grids.lock().unwrap();
+    let mut grid = state.get_active_grid();
     let style_registry = state.style_registry.lock().unwrap();
     let mut dependents_map = state.dependents.lock().unwrap();
     let mut dependencies_map = state.dependencies.lock().unwrap();
@@ -3084,35 +3598,40 @@ pub fn clear_range_with_options(
     let min_col = start_col.min(end_col);
     let max_col = start_col.max(end_col);
 
-    // Clamp to grid bounds
-    let effective_end_row = max_row.min(grid.max_row);
-    let effective_end_col = max_col.min(grid.max_col);
+    // Visit only what the option clears: the sheet's hyperlinks in the range,
+    // or the cells that exist in it (a cell without an entry has neither
+    // content nor formatting to clear).
+    let mut hyperlinks = state.hyperlinks.lock().unwrap();
+    let cells_in_range: Vec<(u32, u32)> = match apply_to {
+        ClearApplyTo::Hyperlinks | ClearApplyTo::RemoveHyperlinks => {
+            let mut linked: Vec<(u32, u32)> = hyperlinks
+                .get(&active_sheet)
+                .map(|links| {
+                    links
+                        .keys()
+                        .filter(|&&(r, c)| r >= min_row && r <= max_row && c >= min_col && c <= max_col)
+                        .copied()
+                        .collect()
+                })
+                .unwrap_or_default();
+            linked.sort_unstable();
+            linked
+        }
+        _ => existing_cells_in_range(&grid, min_row, min_col, max_row, max_col),
+    };
 
-    // Collect cells in the range (both existing and potential)
-    let mut cells_in_range: Vec<(u32, u32)> = grid
-        .cells
-        .keys()
-        .filter(|(r, c)| {
-            *r >= min_row && *r <= effective_end_row && *c >= min_col && *c <= effective_end_col
-        })
-        .cloned()
+    // Merge spans, looked up once per cleared cell
+    let merge_lookup: HashMap<(u32, u32), &MergedRegion> = merged_regions
+        .iter()
+        .map(|r| ((r.start_row, r.start_col), r))
         .collect();
 
-    // For "Formats" mode, we need to process all cells in the range, not just existing ones
-    if matches!(apply_to, ClearApplyTo::Formats) {
-        for r in min_row..=effective_end_row {
-            for c in min_col..=effective_end_col {
-                if !cells_in_range.contains(&(r, c)) {
-                    cells_in_range.push((r, c));
-                }
-            }
-        }
-    }
-
     let count = cells_in_range.len() as u32;
     let mut updated_cells = Vec::new();
 
-    if count > 0 {
+    // One undo transaction for the batch, unless the caller already opened one
+    let opened_transaction = count > 0 && !undo_stack.has_open_transaction();
+    if opened_transaction {
         let desc = match apply_to {
             ClearApplyTo::All => "Clear all",
             ClearApplyTo::Contents => "Clear contents",
@@ -3142,9 +3661,6 @@ pub fn clear_range_with_options(
                     undo_stack.record_cell_change(row, col, previous_cell);
                 }
                 grid.clear_cell(row, col);
-                if active_sheet < grids.len() {
-                    grids[active_sheet].clear_cell(row, col);
-                }
 
                 // Clear dependencies
                 update_cross_sheet_dependencies(
@@ -3173,9 +3689,7 @@ pub fn clear_range_with_options(
                 );
 
                 // Get merge span info
-                let merge_info = merged_regions
-                    .iter()
-                    .find(|r| r.start_row == row && r.start_col == col);
+                let merge_info = merge_lookup.get(&(row, col));
                 let (row_span, col_span) = if let Some(region) = merge_info {
                     (
                         region.end_row - region.start_row + 1,
@@ -3197,6 +3711,7 @@ pub fn clear_range_with_options(
                     sheet_index: None,
                     rich_text: None,
                     accounting_layout: None,
+                    error_detail: None,
                 });
             }
             ClearApplyTo::Contents => {
@@ -3210,10 +3725,7 @@ pub fn clear_range_with_options(
 
                     override_edits.push((row, col, previous_cell.clone(), Some(new_cell.clone())));
 
-                    grid.set_cell(row, col, new_cell.clone());
-                    if active_sheet < grids.len() {
-                        grids[active_sheet].set_cell(row, col, new_cell);
-                    }
+                    grid.set_cell(row, col, new_cell);
 
                     // Clear dependencies since formula is gone
                     update_cross_sheet_dependencies(
@@ -3242,9 +3754,7 @@ pub fn clear_range_with_options(
                     );
 
                     // Get merge span info
-                    let merge_info = merged_regions
-                        .iter()
-                        .find(|r| r.start_row == row && r.start_col == col);
+                    let merge_info = merge_lookup.get(&(row, col));
                     let (row_span, col_span) = if let Some(region) = merge_info {
                         (
                             region.end_row - region.start_row + 1,
@@ -3266,6 +3776,7 @@ pub fn clear_range_with_options(
                         sheet_index: None,
                         rich_text: None,
                         accounting_layout: None,
+                        error_detail: None,
                     });
                 }
             }
@@ -3277,18 +3788,13 @@ pub fn clear_range_with_options(
                     let mut new_cell = cell.clone();
                     new_cell.style_index = 0; // Reset to default style
 
-                    grid.set_cell(row, col, new_cell.clone());
-                    if active_sheet < grids.len() {
-                        grids[active_sheet].set_cell(row, col, new_cell);
-                    }
+                    grid.set_cell(row, col, new_cell);
 
                     let default_style = style_registry.get(0);
                     let display = format_cell_value(&cell.value, default_style, &locale);
 
                     // Get merge span info
-                    let merge_info = merged_regions
-                        .iter()
-                        .find(|r| r.start_row == row && r.start_col == col);
+                    let merge_info = merge_lookup.get(&(row, col));
                     let (row_span, col_span) = if let Some(region) = merge_info {
                         (
                             region.end_row - region.start_row + 1,
@@ -3310,12 +3816,16 @@ pub fn clear_range_with_options(
                         sheet_index: None,
                         rich_text: None,
                         accounting_layout: None,
+                        error_detail: None,
                     });
                 }
             }
             ClearApplyTo::Hyperlinks | ClearApplyTo::RemoveHyperlinks => {
-                // Placeholder - hyperlinks not yet implemented
-                // For now, treat RemoveHyperlinks as clear formats
+                // Drop the link; RemoveHyperlinks also resets the cell's formatting
+                let link = hyperlinks.get_mut(&active_sheet).and_then(|links| links.remove(&(row, col)));
+                crate::hyperlinks::record_hyperlink_undo_in(
+                    &mut undo_stack, active_sheet, row, col, link, "Clear hyperlinks",
+                );
                 if let Some(ref cell) = previous_cell {
                     if apply_to == ClearApplyTo::RemoveHyperlinks {
                         undo_stack.record_cell_change(row, col, previous_cell.clone());
@@ -3323,18 +3833,13 @@ pub fn clear_range_with_options(
                         let mut new_cell = cell.clone();
                         new_cell.style_index = 0; // Reset formatting
 
-                        grid.set_cell(row, col, new_cell.clone());
-                        if active_sheet < grids.len() {
-                            grids[active_sheet].set_cell(row, col, new_cell);
-                        }
+                        grid.set_cell(row, col, new_cell);
 
                         let default_style = style_registry.get(0);
                         let display = format_cell_value(&cell.value, default_style, &locale);
 
                         // Get merge span info
-                        let merge_info = merged_regions
-                            .iter()
-                            .find(|r| r.start_row == row && r.start_col == col);
+                        let merge_info = merge_lookup.get(&(row, col));
                         let (row_span, col_span) = if let Some(region) = merge_info {
                             (
                                 region.end_row - region.start_row + 1,
@@ -3356,6 +3861,7 @@ pub fn clear_range_with_options(
                             sheet_index: None,
                             rich_text: None,
                             accounting_layout: None,
+                            error_detail: None,
                         });
                     }
                 }
@@ -3364,10 +3870,12 @@ pub fn clear_range_with_options(
     }
 
     // Record subscriber overrides for all cleared cells (subscribed sheets only)
-    crate::calp_commands::record_subscription_override_edits(&state, active_sheet, &override_edits);
+    crate::calp_commands::record_subscription_override_edits(state, active_sheet, &override_edits);
 
-    if count > 0 {
+    if opened_transaction {
         undo_stack.commit_transaction();
+    }
+    if count > 0 {
         // Mark workbook as dirty
         if let Ok(mut modified) = file_state.is_modified.lock() { *modified = true; }
     }
@@ -3385,10 +3893,15 @@ pub fn clear_range_with_options(
 /// - Case sensitivity
 /// - Header row handling
 /// - Row or column orientation
+/// - Comments, notes, hyperlinks and validation travelling with their rows
 #[tauri::command]
 pub fn sort_range(state: State<AppState>, file_state: State<FileState>, params: SortRangeParams) -> Result<SortRangeResult, String> {
+    sort_range_internal(&state, &file_state, params)
+}
+
+pub(crate) fn sort_range_internal(state: &AppState, file_state: &FileState, params: SortRangeParams) -> Result<SortRangeResult, String> {
     // Check if any cell in the sort range is a spilled value
-    {
+    let preserve_annotations = {
         let active_sheet = *state.active_sheet.lock().unwrap();
         let spill_hosts = state.spill_hosts.lock().unwrap();
         check_spill_protection(
@@ -3396,10 +3909,18 @@ pub fn sort_range(state: State<AppState>, file_state: State<FileState>, params:
             params.start_row, params.start_col,
             params.end_row, params.end_col,
         )?;
-    }
+        drop(spill_hosts);
+        // Table rows are records: by default their annotations move with them.
+        params.sort_preserves_annotations.unwrap_or_else(|| {
+            state.tables.lock().unwrap().get(&active_sheet).is_some_and(|sheet_tables| {
+                sheet_tables.values().any(|t| {
+                    t.contains(params.start_row, params.start_col) && t.contains(params.end_row, params.end_col)
+                })
+            })
+        })
+    };
 
-    let mut grid = state.grid.lock().unwrap();
-    let mut grids = state.grids.lock().unwrap();
+    let mut grid = state.get_active_grid();
     let active_sheet = *state.active_sheet.lock().unwrap();
     let styles = state.style_registry.lock().unwrap();
     let mut undo_stack = state.undo_stack.lock().unwrap();
@@ -3415,6 +3936,7 @@ pub fn sort_range(state: State<AppState>, file_state: State<FileState>, params:
         match_case,
         has_headers,
         orientation,
+        ..
     } = params;
 
     // Validate sort fields
@@ -3496,10 +4018,12 @@ pub fn sort_range(state: State<AppState>, file_state: State<FileState>, params:
             // Apply the sorted order back to the grid
             let mut updated_cells = Vec::new();
             let sorted_count = rows.len() as u32;
+            let mut row_moves = std::collections::HashMap::new();
 
             for (new_row_idx, (original_row, row_data)) in rows.iter().enumerate() {
                 let target_row = data_start_row + new_row_idx as u32;
                 let row_delta = target_row as i32 - *original_row as i32;
+                row_moves.insert(*original_row, target_row);
 
                 for (col_offset, cell_opt) in row_data.iter().enumerate() {
                     let target_col = min_col + col_offset as u32;
@@ -3516,19 +4040,13 @@ pub fn sort_range(state: State<AppState>, file_state: State<FileState>, params:
                         // computes from the wrong rows (BUG-0010).
                         let mut cell = cell.clone();
                         if row_delta != 0 {
-                            if let Some(formula) = cell.formula_string() {
-                                let shifted = crate::commands::structure::shift_formula_internal(
-                                    &formula, row_delta, 0,
-                                );
-                                cell.ast = parser::parse(&shifted).ok().map(Box::new);
+                            if let Some(ast) = cell.ast.as_deref_mut() {
+                                engine::reference_shift::shift_for_fill(ast, row_delta, 0);
                             }
                         }
                         let cell = &cell;
 
                         grid.set_cell(target_row, target_col, cell.clone());
-                        if active_sheet < grids.len() {
-                            grids[active_sheet].set_cell(target_row, target_col, cell.clone());
-                        }
 
                         let style = styles.get(cell.style_index);
                         let display = format_cell_value(&cell.value, style, &locale);
@@ -3545,12 +4063,10 @@ pub fn sort_range(state: State<AppState>, file_state: State<FileState>, params:
                             sheet_index: None,
                             rich_text: None,
                             accounting_layout: None,
+                            error_detail: None,
                         });
                     } else {
                         grid.clear_cell(target_row, target_col);
-                        if active_sheet < grids.len() {
-                            grids[active_sheet].clear_cell(target_row, target_col);
-                        }
 
                         updated_cells.push(CellData {
                             row: target_row,
@@ -3564,12 +4080,21 @@ pub fn sort_range(state: State<AppState>, file_state: State<FileState>, params:
                             sheet_index: None,
                             rich_text: None,
                             accounting_layout: None,
+                            error_detail: None,
                         });
                     }
                 }
             }
 
-            undo_stack.commit_transaction();
+            if preserve_annotations {
+                // The annotation undo helpers lock the stack themselves and
+                // join the open transaction.
+                drop(undo_stack);
+                carry_row_annotations(state, active_sheet, min_col, max_col, &row_moves, "Sort range");
+                state.undo_stack.lock().unwrap().commit_transaction();
+            } else {
+                undo_stack.commit_transaction();
+            }
 
             // Formula cells moved (and their references were shifted) —
             // rebuild the dependency maps so incremental recalc keeps
@@ -3577,7 +4102,7 @@ pub fn sort_range(state: State<AppState>, file_state: State<FileState>, params:
             crate::undo_commands::rebuild_all_dependencies_from_grid(
                 &grid,
                 active_sheet,
-                &state,
+                state,
             );
 
             // Mark workbook as dirty
@@ -3645,19 +4170,13 @@ pub fn sort_range(state: State<AppState>, file_state: State<FileState>, params:
                         // BUG-0010).
                         let mut cell = cell.clone();
                         if col_delta != 0 {
-                            if let Some(formula) = cell.formula_string() {
-                                let shifted = crate::commands::structure::shift_formula_internal(
-                                    &formula, 0, col_delta,
-                                );
-                                cell.ast = parser::parse(&shifted).ok().map(Box::new);
+                            if let Some(ast) = cell.ast.as_deref_mut() {
+                                engine::reference_shift::shift_for_fill(ast, 0, col_delta);
                             }
                         }
                         let cell = &cell;
 
                         grid.set_cell(target_row, target_col, cell.clone());
-                        if active_sheet < grids.len() {
-                            grids[active_sheet].set_cell(target_row, target_col, cell.clone());
-                        }
 
                         let style = styles.get(cell.style_index);
                         let display = format_cell_value(&cell.value, style, &locale);
@@ -3674,12 +4193,10 @@ pub fn sort_range(state: State<AppState>, file_state: State<FileState>, params:
                             sheet_index: None,
                             rich_text: None,
                             accounting_layout: None,
+                            error_detail: None,
                         });
                     } else {
                         grid.clear_cell(target_row, target_col);
-                        if active_sheet < grids.len() {
-                            grids[active_sheet].clear_cell(target_row, target_col);
-                        }
 
                         updated_cells.push(CellData {
                             row: target_row,
@@ -3693,6 +4210,7 @@ pub fn sort_range(state: State<AppState>, file_state: State<FileState>, params:
                             sheet_index: None,
                             rich_text: None,
                             accounting_layout: None,
+                            error_detail: None,
                         });
                     }
                 }
@@ -3706,7 +4224,7 @@ pub fn sort_range(state: State<AppState>, file_state: State<FileState>, params:
             crate::undo_commands::rebuild_all_dependencies_from_grid(
                 &grid,
                 active_sheet,
-                &state,
+                state,
             );
 
             // Mark workbook as dirty
@@ -3722,6 +4240,85 @@ pub fn sort_range(state: State<AppState>, file_state: State<FileState>, params:
     }
 }
 
+/// Move the comments, notes, hyperlinks and data validation anchored in
+/// columns `min_col..=max_col` from each sorted row to where the row landed
+/// (`row_moves`: original row -> target row), recording undo for every cell
+/// touched. Must be called with the sort's undo transaction open.
+fn carry_row_annotations(
+    state: &AppState,
+    sheet_index: usize,
+    min_col: u32,
+    max_col: u32,
+    row_moves: &std::collections::HashMap<u32, u32>,
+    description: &str,
+) {
+    let previous = state.comments.lock().unwrap().get_mut(&sheet_index)
+        .map(|m| permute_cell_rows(m, min_col, max_col, row_moves, |c, row| c.row = row))
+        .unwrap_or_default();
+    for ((row, col), prev) in previous {
+        crate::comments::record_comment_undo(state, sheet_index, row, col, prev, description);
+    }
+
+    let previous = state.notes.lock().unwrap().get_mut(&sheet_index)
+        .map(|m| permute_cell_rows(m, min_col, max_col, row_moves, |n, row| n.row = row))
+        .unwrap_or_default();
+    for ((row, col), prev) in previous {
+        crate::notes::record_note_undo(state, sheet_index, row, col, prev, description);
+    }
+
+    let previous = state.hyperlinks.lock().unwrap().get_mut(&sheet_index)
+        .map(|m| permute_cell_rows(m, min_col, max_col, row_moves, |h, row| h.row = row))
+        .unwrap_or_default();
+    for ((row, col), prev) in previous {
+        crate::hyperlinks::record_hyperlink_undo(state, sheet_index, row, col, prev, description);
+    }
+
+    let mut validations = state.data_validations.lock().unwrap();
+    if let Some(sheet_validations) = validations.get_mut(&sheet_index) {
+        if let Some(permuted) = crate::data_validation::permute_validation_rows(
+            sheet_validations, min_col, max_col, row_moves,
+        ) {
+            let previous = std::mem::replace(sheet_validations, permuted);
+            drop(validations);
+            crate::undo_commands::record_validation_undo(state, sheet_index, previous, description);
+        }
+    }
+}
+
+/// Re-key the entries of a per-cell store whose row moved, returning the
+/// prior value of every position that changed (for undo).
+fn permute_cell_rows<T: Clone>(
+    map: &mut std::collections::HashMap<(u32, u32), T>,
+    min_col: u32,
+    max_col: u32,
+    row_moves: &std::collections::HashMap<u32, u32>,
+    set_row: impl Fn(&mut T, u32),
+) -> Vec<((u32, u32), Option<T>)> {
+    let mut keys: Vec<(u32, u32)> = map
+        .keys()
+        .filter(|(row, col)| {
+            (min_col..=max_col).contains(col) && row_moves.get(row).is_some_and(|t| t != row)
+        })
+        .copied()
+        .collect();
+    keys.sort_unstable();
+
+    let moved: Vec<((u32, u32), T)> = keys.iter().map(|k| (*k, map.remove(k).unwrap())).collect();
+    let mut previous: Vec<((u32, u32), Option<T>)> =
+        moved.iter().map(|(k, item)| (*k, Some(item.clone()))).collect();
+    for ((row, col), mut item) in moved {
+        let target = (row_moves[&row], col);
+        // Moved rows map onto moved rows, so a target is either a vacated
+        // source (already recorded) or was empty.
+        if keys.binary_search(&target).is_err() {
+            previous.push((target, None));
+        }
+        set_row(&mut item, target.0);
+        map.insert(target, item);
+    }
+    previous
+}
+
 /// Compare two rows by the given sort fields.
 fn compare_rows_by_fields(
     row_a: &[Option<engine::Cell>],
@@ -4007,21 +4604,21 @@ fn compare_cell_values(
 /// Get the grid bounds (max row and col with data).
 #[tauri::command]
 pub fn get_grid_bounds(state: State<AppState>) -> (u32, u32) {
-    let grid = state.grid.lock().unwrap();
+    let grid = state.get_active_grid();
     (grid.max_row, grid.max_col)
 }
 
 /// Get the total number of non-empty cells.
 #[tauri::command]
 pub fn get_cell_count(state: State<AppState>) -> usize {
-    let grid = state.grid.lock().unwrap();
+    let grid = state.get_active_grid();
     grid.cells.len()
 }
 
 /// Get the bounding box (used range) of all non-empty cells in the active sheet.
 #[tauri::command]
 pub fn get_used_range(state: State<AppState>) -> UsedRangeResult {
-    let grid = state.grid.lock().unwrap();
+    let grid = state.get_active_grid();
     if grid.cells.is_empty() {
         return UsedRangeResult {
             start_row: 0,
@@ -4059,7 +4656,7 @@ pub fn get_cells_in_rows(
     start_row: u32,
     end_row: u32,
 ) -> Vec<CellData> {
-    let grid = state.grid.lock().unwrap();
+    let grid = state.get_active_grid();
     let styles = state.style_registry.lock().unwrap();
     let merged_regions = state.merged_regions.lock().unwrap();
     let locale = state.locale.lock().unwrap();
@@ -4087,7 +4684,7 @@ pub fn get_cells_in_cols(
     start_col: u32,
     end_col: u32,
 ) -> Vec<CellData> {
-    let grid = state.grid.lock().unwrap();
+    let grid = state.get_active_grid();
     let styles = state.style_registry.lock().unwrap();
     let merged_regions = state.merged_regions.lock().unwrap();
     let locale = state.locale.lock().unwrap();
@@ -4117,7 +4714,7 @@ pub fn has_content_in_range(
     end_row: u32,
     end_col: u32,
 ) -> bool {
-    let grid = state.grid.lock().unwrap();
+    let grid = state.get_active_grid();
 
     grid.cells.iter().any(|(&(row, col), cell)| {
         row >= start_row
@@ -4140,9 +4737,7 @@ pub fn remove_duplicates(
     state: State<AppState>,
     params: RemoveDuplicatesParams,
 ) -> RemoveDuplicatesResult {
-    let mut grid = state.grid.lock().unwrap();
-    let mut grids = state.grids.lock().unwrap();
-    let active_sheet = *state.active_sheet.lock().unwrap();
+    let mut grid = state.get_active_grid();
     let styles = state.style_registry.lock().unwrap();
     let mut undo_stack = state.undo_stack.lock().unwrap();
     let merged_regions = state.merged_regions.lock().unwrap();
@@ -4290,9 +4885,6 @@ pub fn remove_duplicates(
 
             if let Some(cell) = cell_opt {
                 grid.set_cell(target_row, target_col, cell.clone());
-                if active_sheet < grids.len() {
-                    grids[active_sheet].set_cell(target_row, target_col, cell.clone());
-                }
 
                 let style = styles.get(cell.style_index);
                 let display = format_cell_value(&cell.value, style, &locale);
@@ -4309,12 +4901,10 @@ pub fn remove_duplicates(
                     sheet_index: None,
                     rich_text: None,
                     accounting_layout: None,
+                    error_detail: None,
                 });
             } else {
                 grid.clear_cell(target_row, target_col);
-                if active_sheet < grids.len() {
-                    grids[active_sheet].clear_cell(target_row, target_col);
-                }
 
                 updated_cells.push(CellData {
                     row: target_row,
@@ -4328,6 +4918,7 @@ pub fn remove_duplicates(
                     sheet_index: None,
                     rich_text: None,
                     accounting_layout: None,
+                    error_detail: None,
                 });
             }
         }
@@ -4341,9 +4932,6 @@ pub fn remove_duplicates(
             if prev_cell.is_some() {
                 undo_stack.record_cell_change(row, col, prev_cell);
                 grid.clear_cell(row, col);
-                if active_sheet < grids.len() {
-                    grids[active_sheet].clear_cell(row, col);
-                }
             }
 
             updated_cells.push(CellData {
@@ -4358,6 +4946,7 @@ pub fn remove_duplicates(
                 sheet_index: None,
                 rich_text: None,
                 accounting_layout: None,
+                error_detail: None,
             });
         }
     }
@@ -4392,11 +4981,12 @@ pub fn update_cell_on_sheets(
     let mut grids = state.grids.lock().unwrap();
     let active_sheet = *state.active_sheet.lock().unwrap();
     let mut undo_stack = state.undo_stack.lock().unwrap();
+    check_sheet_indices(grids.len(), &sheet_indices)?;
 
     // Handle empty value - clear the cell on each target sheet
     if value.trim().is_empty() {
         for &sheet_idx in &sheet_indices {
-            if sheet_idx == active_sheet || sheet_idx >= grids.len() {
+            if sheet_idx == active_sheet {
                 continue;
             }
             let previous_cell = grids[sheet_idx].get_cell(row, col).cloned();
@@ -4425,7 +5015,7 @@ pub fn update_cell_on_sheets(
     };
 
     for &sheet_idx in &sheet_indices {
-        if sheet_idx == active_sheet || sheet_idx >= grids.len() {
+        if sheet_idx == active_sheet {
             continue;
         }
 
@@ -4435,9 +5025,7 @@ pub fn update_cell_on_sheets(
         let mut cell = cell_template.clone();
 
         // Preserve existing style from target sheet
-        if let Some(existing) = grids[sheet_idx].get_cell(row, col) {
-            cell.style_index = existing.style_index;
-        }
+        cell.style_index = grids[sheet_idx].effective_style_index(row, col);
 
         // If formula, evaluate in the context of the target sheet
         if is_formula {
@@ -4447,6 +5035,7 @@ pub fn update_cell_on_sheets(
                     &sheet_names,
                     sheet_idx,
                     ast,
+                    engine::EvalContext::at(row, col),
                     &user_files,
                 );
                 cell.value = result_value;
@@ -4475,6 +5064,7 @@ pub fn clear_range_on_sheets(
 ) -> Result<(), String> {
     // Object-output protection on every targeted sheet (group clear must not
     // punch through a pivot/report region on a background sheet).
+    check_sheet_indices(state.grids.lock().unwrap().len(), &sheet_indices)?;
     for &sheet_idx in &sheet_indices {
         check_region_range_protection(&state, sheet_idx, start_row, start_col, end_row, end_col)?;
     }
@@ -4484,22 +5074,11 @@ pub fn clear_range_on_sheets(
     let mut undo_stack = state.undo_stack.lock().unwrap();
 
     for &sheet_idx in &sheet_indices {
-        if sheet_idx == active_sheet || sheet_idx >= grids.len() {
+        if sheet_idx == active_sheet {
             continue;
         }
 
-        let grid = &grids[sheet_idx];
-        let effective_end_row = end_row.min(grid.max_row);
-        let effective_end_col = end_col.min(grid.max_col);
-
-        let cells_to_clear: Vec<(u32, u32)> = grid
-            .cells
-            .keys()
-            .filter(|(r, c)| {
-                *r >= start_row && *r <= effective_end_row && *c >= start_col && *c <= effective_end_col
-            })
-            .cloned()
-            .collect();
+        let cells_to_clear = existing_cells_in_range(&grids[sheet_idx], start_row, start_col, end_row, end_col);
 
         if cells_to_clear.is_empty() {
             continue;
@@ -4557,6 +5136,9 @@ pub fn fill_range(
     let control_values = crate::control_values::build_control_values(
         &state, &pane_control_state, &ribbon_filter_state,
     );
+    // Filtered/collapsed rows for SUBTOTAL 101-111, likewise before the grids.
+    let hidden_rows =
+        crate::autofilter::sheet_hidden_rows(&state, *state.active_sheet.lock().unwrap());
     let user_files = user_files_state.files.lock().unwrap();
 
     // Check if target range overlaps any writeback region
@@ -4581,7 +5163,6 @@ pub fn fill_range(
 
     // Acquire all locks once
     let sheet_names = state.sheet_names.lock().unwrap();
-    let mut grid = state.grid.lock().unwrap();
     let mut grids = state.grids.lock().unwrap();
     let active_sheet = *state.active_sheet.lock().unwrap();
     let styles = state.style_registry.lock().unwrap();
@@ -4593,7 +5174,7 @@ pub fn fill_range(
     let mut row_dependencies_map = state.row_dependencies.lock().unwrap();
     let mut cross_sheet_dependents_map = state.cross_sheet_dependents.lock().unwrap();
     let mut cross_sheet_dependencies_map = state.cross_sheet_dependencies.lock().unwrap();
-    let calc_mode = state.calculation_mode.lock().unwrap();
+    let calc_mode = state.calculation_settings.lock().unwrap().mode;
     let mut undo_stack = state.undo_stack.lock().unwrap();
     let merged_regions = state.merged_regions.lock().unwrap();
     let locale = state.locale.lock().unwrap();
@@ -4629,7 +5210,7 @@ pub fn fill_range(
     let mut source_cells: HashMap<(u32, u32), engine::Cell> = HashMap::new();
     for r in source_start_row..=source_end_row {
         for c in source_start_col..=source_end_col {
-            if let Some(cell) = grid.get_cell(r, c) {
+            if let Some(cell) = grids[active_sheet].get_cell(r, c) {
                 let rel_r = r - source_start_row;
                 let rel_c = c - source_start_col;
                 source_cells.insert((rel_r, rel_c), cell.clone());
@@ -4654,6 +5235,7 @@ pub fn fill_range(
 
     let mut updated_cells: Vec<CellData> = Vec::new();
     let mut cells_needing_recalc: Vec<(u32, u32)> = Vec::new();
+    let mut volatile_cells = state.volatile_cells.lock().unwrap();
     // Pre/post cell states collected for subscriber override capture.
     let mut override_edits: Vec<(u32, u32, Option<engine::Cell>, Option<engine::Cell>)> = Vec::new();
 
@@ -4665,7 +5247,7 @@ pub fn fill_range(
             let rel_c = (tc - target_start_col) % src_cols;
 
             // Record previous state for undo
-            let previous_cell = grid.get_cell(tr, tc).cloned();
+            let previous_cell = grids[active_sheet].get_cell(tr, tc).cloned();
             let pre_for_override = previous_cell.clone();
             undo_stack.record_cell_change(tr, tc, previous_cell);
 
@@ -4745,7 +5327,8 @@ pub fn fill_range(
                                 resolved
                             };
 
-                            let refs = extract_all_references(&resolved, &grid);
+                            let refs = extract_all_references(&resolved, &grids[active_sheet]);
+                            let normalized_cross_sheet_refs = crate::resolve_cross_sheet_refs(&refs, &sheet_names);
 
                             update_dependencies(
                                 (tr, tc),
@@ -4767,18 +5350,6 @@ pub fn fill_range(
                             );
 
                             // Cross-sheet dependencies
-                            let normalized_cross_sheet_refs: rustc_hash::FxHashSet<(String, u32, u32)> = refs
-                                .cross_sheet_cells
-                                .iter()
-                                .filter_map(|(parsed_sheet_name, r, c)| {
-                                    let normalized = sheet_names
-                                        .iter()
-                                        .find(|name| name.eq_ignore_ascii_case(parsed_sheet_name))
-                                        .cloned()
-                                        .unwrap_or_else(|| parsed_sheet_name.clone());
-                                    Some((normalized, *r, *c))
-                                })
-                                .collect();
                             update_cross_sheet_dependencies(
                                 (active_sheet, tr, tc),
                                 normalized_cross_sheet_refs,
@@ -4796,7 +5367,7 @@ pub fn fill_range(
                                 current_col: Some(tc),
                                 row_heights: None,
                                 column_widths: None,
-                                hidden_rows: None,
+                                hidden_rows: (!hidden_rows.is_empty()).then(|| hidden_rows.clone()),
                                 control_values: Some(control_values.clone()),
                             };
                             let raw_result = evaluate_formula_raw_with_files_and_pivot(
@@ -4825,10 +5396,7 @@ pub fn fill_range(
                 // else: non-formula cell - value and style already cloned from source
 
                 // Write the cell
-                grid.set_cell(tr, tc, new_cell.clone());
-                if active_sheet < grids.len() {
-                    grids[active_sheet].set_cell(tr, tc, new_cell.clone());
-                }
+                grids[active_sheet].set_cell(tr, tc, new_cell.clone());
 
                 // Build CellData for the response
                 let style = styles.get(new_cell.style_index);
@@ -4855,13 +5423,11 @@ pub fn fill_range(
                     sheet_index: None,
                     rich_text: None,
                     accounting_layout: None,
+                    error_detail: None,
                 });
             } else {
                 // Source cell is empty - clear the target cell
-                grid.clear_cell(tr, tc);
-                if active_sheet < grids.len() {
-                    grids[active_sheet].clear_cell(tr, tc);
-                }
+                grids[active_sheet].clear_cell(tr, tc);
 
                 // Clear dependencies for this cell
                 update_cross_sheet_dependencies(
@@ -4910,10 +5476,12 @@ pub fn fill_range(
                     sheet_index: None,
                     rich_text: None,
                     accounting_layout: None,
+                    error_detail: None,
                 });
             }
 
-            override_edits.push((tr, tc, pre_for_override, grid.get_cell(tr, tc).cloned()));
+            override_edits.push((tr, tc, pre_for_override, grids[active_sheet].get_cell(tr, tc).cloned()));
+            crate::track_volatile_cell(&mut volatile_cells, (tr, tc), grids[active_sheet].get_cell(tr, tc));
             cells_needing_recalc.push((tr, tc));
         }
     }
@@ -4924,11 +5492,12 @@ pub fn fill_range(
     let perf_t2_processed = Instant::now();
 
     // Recalculate dependents if automatic mode
-    if *calc_mode == "automatic" {
+    if calc_mode.is_automatic() {
         // One multi-root traversal for the whole fill (see update_cells_batch):
         // fixes in-fill stale values and avoids one BFS + Kahn per filled cell.
+        let seeds = crate::with_volatile_seeds(&cells_needing_recalc, &volatile_cells);
         let mut all_recalc_order: Vec<(u32, u32)> =
-            crate::recalc_order_from_seeds(&cells_needing_recalc, &dependents_map, true);
+            crate::recalc_order_from_seeds(&seeds, &dependents_map, true);
         let mut recalc_set: crate::CoordSet = all_recalc_order.iter().copied().collect();
 
         for (row, col) in &cells_needing_recalc {
@@ -4950,7 +5519,7 @@ pub fn fill_range(
         let include_cascade_formulas = all_recalc_order.len() <= CASCADE_FORMULA_LIMIT;
 
         for (dep_row, dep_col) in &all_recalc_order {
-            if let Some(dep_cell) = grid.get_cell(*dep_row, *dep_col) {
+            if let Some(dep_cell) = grids[active_sheet].get_cell(*dep_row, *dep_col) {
                 if let Some(formula) = dep_cell.formula_string() {
                     let result = if let Some(cached_ast) = dep_cell.get_cached_ast() {
                         crate::evaluate_formula_raw_with_ast_files_and_cube(
@@ -4958,6 +5527,7 @@ pub fn fill_range(
                             &sheet_names,
                             active_sheet,
                             cached_ast,
+                            engine::EvalContext::at(*dep_row, *dep_col),
                             &user_files,
                             None,
                             None,
@@ -4991,6 +5561,7 @@ pub fn fill_range(
                                 &sheet_names,
                                 active_sheet,
                                 &engine_ast,
+                                engine::EvalContext::at(*dep_row, *dep_col),
                                 &user_files,
                                 None,
                                 None,
@@ -4999,10 +5570,7 @@ pub fn fill_range(
                             let mut updated_with_ast = dep_cell.clone();
                             updated_with_ast.set_cached_ast(engine_ast);
                             updated_with_ast.value = result.clone();
-                            grid.set_cell(*dep_row, *dep_col, updated_with_ast.clone());
-                            if active_sheet < grids.len() {
-                                grids[active_sheet].set_cell(*dep_row, *dep_col, updated_with_ast.clone());
-                            }
+                            grids[active_sheet].set_cell(*dep_row, *dep_col, updated_with_ast.clone());
                             let dep_style = styles.get(updated_with_ast.style_index);
                             let dep_display = format_cell_value(&updated_with_ast.value, dep_style, &locale);
                             let (drspan, dcspan) = if let Some(region) = merge_lookup.get(&(*dep_row, *dep_col)) {
@@ -5016,19 +5584,16 @@ pub fn fill_range(
                                 formula: if include_cascade_formulas { formula_display(&updated_with_ast, &locale) } else { None },
                                 style_index: updated_with_ast.style_index,
                                 row_span: drspan, col_span: dcspan,
-                                sheet_index: None, rich_text: None, accounting_layout: None,
+                                sheet_index: None, rich_text: None, accounting_layout: None, error_detail: None,
                             });
                             continue;
                         }
-                        evaluate_formula_multi_sheet_with_files(&grids, &sheet_names, active_sheet, &formula, &user_files)
+                        evaluate_formula_multi_sheet_with_files(&grids, &sheet_names, active_sheet, &formula, engine::EvalContext::at(*dep_row, *dep_col), &user_files)
                     };
 
                     let mut updated_dep = dep_cell.clone();
                     updated_dep.value = result;
-                    grid.set_cell(*dep_row, *dep_col, updated_dep.clone());
-                    if active_sheet < grids.len() {
-                        grids[active_sheet].set_cell(*dep_row, *dep_col, updated_dep.clone());
-                    }
+                    grids[active_sheet].set_cell(*dep_row, *dep_col, updated_dep.clone());
                     let dep_style = styles.get(updated_dep.style_index);
                     let dep_display = format_cell_value(&updated_dep.value, dep_style, &locale);
                     let (drspan, dcspan) = if let Some(region) = merge_lookup.get(&(*dep_row, *dep_col)) {
@@ -5042,7 +5607,7 @@ pub fn fill_range(
                         formula: if include_cascade_formulas { formula_display(&updated_dep, &locale) } else { None },
                         style_index: updated_dep.style_index,
                         row_span: drspan, col_span: dcspan,
-                        sheet_index: None, rich_text: None, accounting_layout: None,
+                        sheet_index: None, rich_text: None, accounting_layout: None, error_detail: None,
                     });
                 }
             }
@@ -5074,13 +5639,13 @@ pub fn fill_range(
                             if let Some(formula) = dep_cell.formula_string() {
                                 let result = if let Some(cached_ast) = dep_cell.get_cached_ast() {
                                     crate::evaluate_formula_raw_with_ast_files_and_cube(
-                                        &grids, &sheet_names, *dep_sheet_idx, cached_ast, &user_files,
+                                        &grids, &sheet_names, *dep_sheet_idx, cached_ast, engine::EvalContext::at(*dep_row, *dep_col), &user_files,
                                         None, None, Some(control_values.clone()),
                                     ).to_cell_value()
                                 } else {
                                     // (GET.CONTROLVALUE unavailable here (v1): string path)
                                     evaluate_formula_multi_sheet_with_files(
-                                        &grids, &sheet_names, *dep_sheet_idx, &formula, &user_files,
+                                        &grids, &sheet_names, *dep_sheet_idx, &formula, engine::EvalContext::at(*dep_row, *dep_col), &user_files,
                                     )
                                 };
                                 let mut updated_dep = dep_cell.clone();
@@ -5094,7 +5659,7 @@ pub fn fill_range(
                                     formula: if include_cascade_formulas { formula_display(&updated_dep, &locale) } else { None },
                                     style_index: updated_dep.style_index,
                                     row_span: 1, col_span: 1,
-                                    sheet_index: Some(*dep_sheet_idx), rich_text: None, accounting_layout: None,
+                                    sheet_index: Some(*dep_sheet_idx), rich_text: None, accounting_layout: None, error_detail: None,
                                 });
                                 if let Some(dep_sheet_name) = sheet_names.get(*dep_sheet_idx) {
                                     work_queue.push((*dep_sheet_idx, dep_sheet_name.clone(), *dep_row, *dep_col));
@@ -1,9 +1,13 @@
 //! FILENAME: app/src-tauri/src/commands/dimensions.rs
 // PURPOSE: Managing row heights and column widths.
 
-use crate::api_types::{DefaultDimensions, DimensionData};
+use crate::api_types::{AxisEntry, DefaultDimensions, DimensionData, HiddenReason, SheetDefaults};
+use crate::grouping::{SheetOutline, SummaryPosition};
 use crate::persistence::FileState;
+use crate::sheet_access::check_sheet_index;
+use crate::structural_change::Axis;
 use crate::AppState;
+use std::collections::{HashMap, HashSet};
 use tauri::State;
 
 /// Set a column width.
@@ -35,7 +39,9 @@ pub fn get_column_width(state: State<AppState>, col: u32) -> Option<f64> {
     widths.get(&col).copied()
 }
 
-/// Get all column widths.
+/// Get all explicit column widths of the active sheet. Columns without an
+/// entry are at the sheet default (`get_default_dimensions`); an explicit
+/// width always wins over it.
 #[tauri::command]
 pub fn get_all_column_widths(state: State<AppState>) -> Vec<DimensionData> {
     let widths = state.column_widths.lock().unwrap();
@@ -74,7 +80,9 @@ pub fn get_row_height(state: State<AppState>, row: u32) -> Option<f64> {
     heights.get(&row).copied()
 }
 
-/// Get all row heights.
+/// Get all explicit row heights of the active sheet. Rows without an entry
+/// are at the sheet default (`get_default_dimensions`); an explicit height
+/// always wins over it.
 #[tauri::command]
 pub fn get_all_row_heights(state: State<AppState>) -> Vec<DimensionData> {
     let heights = state.row_heights.lock().unwrap();
@@ -84,15 +92,74 @@ pub fn get_all_row_heights(state: State<AppState>) -> Vec<DimensionData> {
         .collect()
 }
 
-/// Get the default row height and column width.
+/// Get the default row height and column width of the active sheet: its own
+/// defaults where set, else the workbook's.
 #[tauri::command]
 pub fn get_default_dimensions(state: State<AppState>) -> DefaultDimensions {
-    let row_h = *state.default_row_height.lock().unwrap();
-    let col_w = *state.default_column_width.lock().unwrap();
+    let active_sheet = *state.active_sheet.lock().unwrap();
+    effective_default_dimensions(&state, active_sheet)
+}
+
+/// The default row height and column width in effect on `sheet`.
+pub(crate) fn effective_default_dimensions(state: &AppState, sheet: usize) -> DefaultDimensions {
+    let own = state.sheet_defaults.lock().unwrap().get(sheet).copied().unwrap_or_default();
     DefaultDimensions {
-        default_row_height: row_h,
-        default_column_width: col_w,
+        default_row_height: own.default_row_height.unwrap_or_else(|| *state.default_row_height.lock().unwrap()),
+        default_column_width: own.default_col_width.unwrap_or_else(|| *state.default_column_width.lock().unwrap()),
+    }
+}
+
+/// Get a sheet's own default column width and row height (`None` = the
+/// workbook default).
+#[tauri::command]
+pub fn get_sheet_defaults(state: State<AppState>, sheet: usize) -> Result<SheetDefaults, String> {
+    get_sheet_defaults_internal(&state, sheet)
+}
+
+pub(crate) fn get_sheet_defaults_internal(state: &AppState, sheet: usize) -> Result<SheetDefaults, String> {
+    check_sheet_index(state.sheet_names.lock().unwrap().len(), sheet)?;
+    Ok(state.sheet_defaults.lock().unwrap().get(sheet).copied().unwrap_or_default())
+}
+
+/// Set a sheet's default column width and row height. `None` (or a
+/// non-positive size) makes the sheet follow the workbook default again.
+/// Columns and rows with an explicit size keep it.
+#[tauri::command]
+pub fn set_sheet_defaults(
+    state: State<AppState>,
+    file_state: State<FileState>,
+    sheet: usize,
+    defaults: SheetDefaults,
+) -> Result<SheetDefaults, String> {
+    let result = set_sheet_defaults_internal(&state, sheet, defaults)?;
+    if let Ok(mut modified) = file_state.is_modified.lock() { *modified = true; }
+    Ok(result)
+}
+
+pub(crate) fn set_sheet_defaults_internal(
+    state: &AppState,
+    sheet: usize,
+    defaults: SheetDefaults,
+) -> Result<SheetDefaults, String> {
+    check_sheet_index(state.sheet_names.lock().unwrap().len(), sheet)?;
+    let size = |v: Option<f64>| v.filter(|v| v.is_finite() && *v > 0.0).map(|v| v.max(1.0));
+    let defaults = SheetDefaults {
+        default_col_width: size(defaults.default_col_width),
+        default_row_height: size(defaults.default_row_height),
+    };
+
+    let mut all = state.sheet_defaults.lock().unwrap();
+    while all.len() <= sheet {
+        all.push(SheetDefaults::default());
     }
+    let previous = std::mem::replace(&mut all[sheet], defaults);
+    drop(all);
+
+    // Record undo
+    let data = serde_json::to_vec(&(sheet, previous)).unwrap_or_default();
+    let mut undo_stack = state.undo_stack.lock().unwrap();
+    undo_stack.record_custom_restore("sheet_defaults".to_string(), data, "Change sheet default size");
+    Ok(defaults)
 }
 
 /// Set the default row height.
@@ -139,4 +206,151 @@ pub fn set_default_column_width(state: State<AppState>, file_state: State<FileSt
         default_row_height: row_h,
         default_column_width: clamped,
     }
-}
\ No newline at end of file
+}
+
+/// Inputs for `build_axis_metadata`, all for one sheet and one axis.
+pub(crate) struct AxisSources<'a> {
+    pub axis: Axis,
+    /// Custom sizes (row heights or column widths).
+    pub sizes: &'a HashMap<u32, f64>,
+    pub default_size: f64,
+    pub outline: Option<&'a SheetOutline>,
+    /// Rows hidden by AutoFilter / Advanced Filter (empty for columns).
+    pub filter_hidden: &'a HashSet<u32>,
+    pub manual_hidden: &'a HashSet<u32>,
+}
+
+/// Header metadata for every line in `start..=end`. Groups are narrowed to
+/// the window once, so the walk costs O(window * overlapping groups).
+pub(crate) fn build_axis_metadata(src: &AxisSources, start: u32, end: u32) -> Vec<AxisEntry> {
+    // (first, last, level, collapsed, summary line) of each group touching the window
+    let mut groups: Vec<(u32, u32, u8, bool, u32)> = Vec::new();
+    let mut group_hidden = HashSet::new();
+    if let Some(outline) = src.outline {
+        match src.axis {
+            Axis::Rows => {
+                let below = outline.settings.summary_row_position == SummaryPosition::BelowRight;
+                groups.extend(outline.row_groups.iter().map(|g| {
+                    (g.start_row, g.end_row, g.level, g.collapsed, if below { g.end_row } else { g.start_row })
+                }));
+                group_hidden = outline.get_hidden_rows();
+            }
+            Axis::Columns => {
+                let right = outline.settings.summary_col_position == SummaryPosition::BelowRight;
+                groups.extend(outline.column_groups.iter().map(|g| {
+                    (g.start_col, g.end_col, g.level, g.collapsed, if right { g.end_col } else { g.start_col })
+                }));
+                group_hidden = outline.get_hidden_cols();
+            }
+        }
+        groups.retain(|&(first, last, ..)| last >= start && first <= end);
+    }
+
+    (start..=end)
+        .map(|index| {
+            let custom = src.sizes.get(&index).copied();
+            let hidden_reason = if src.manual_hidden.contains(&index) {
+                Some(HiddenReason::Manual)
+            } else if src.filter_hidden.contains(&index) {
+                Some(HiddenReason::Filter)
+            } else if group_hidden.contains(&index) {
+                Some(HiddenReason::Group)
+            } else {
+                None
+            };
+            let mut entry = AxisEntry {
+                index,
+                size: custom.unwrap_or(src.default_size),
+                custom_size: custom.is_some(),
+                hidden: hidden_reason.is_some(),
+                hidden_reason,
+                outline_level: 0,
+                collapsed: false,
+                group_start: false,
+                group_end: false,
+                is_button: false,
+            };
+            for &(first, last, level, collapsed, summary) in &groups {
+                if index < first || index > last {
+                    continue;
+                }
+                entry.outline_level = entry.outline_level.max(level);
+                entry.collapsed |= collapsed;
+                entry.group_start |= index == first;
+                entry.group_end |= index == last;
+                entry.is_button |= index == summary;
+            }
+            entry
+        })
+        .collect()
+}
+
+/// Header metadata for rows or columns `start..=end` of `sheet`: effective
+/// size, visibility with the reason, outline level and group markers. The
+/// single source for the renderer's virtualized headers. Manual hides live in
+/// the frontend, so it passes its own set in `manually_hidden`.
+#[tauri::command]
+pub fn get_axis_metadata(
+    state: State<AppState>,
+    sheet: usize,
+    axis: Axis,
+    start: u32,
+    end: u32,
+    manually_hidden: Option<Vec<u32>>,
+) -> Result<Vec<AxisEntry>, String> {
+    get_axis_metadata_internal(&state, sheet, axis, start, end, manually_hidden.unwrap_or_default())
+}
+
+pub(crate) fn get_axis_metadata_internal(
+    state: &AppState,
+    sheet: usize,
+    axis: Axis,
+    start: u32,
+    end: u32,
+    manually_hidden: Vec<u32>,
+) -> Result<Vec<AxisEntry>, String> {
+    check_sheet_index(state.sheet_names.lock().unwrap().len(), sheet)?;
+    let (start, end) = (start.min(end), start.max(end));
+
+    // Feature stores before the dimension maps (canonical lock order).
+    let mut filter_hidden = HashSet::new();
+    if axis == Axis::Rows {
+        if let Some(af) = state.auto_filters.lock().unwrap().get(&sheet) {
+            filter_hidden.extend(af.hidden_rows.iter().copied());
+        }
+        if let Some(rows) = state.advanced_filter_hidden_rows.lock().unwrap().get(&sheet) {
+            filter_hidden.extend(rows.iter().copied());
+        }
+    }
+    let outlines = state.outlines.lock().unwrap();
+    let manual_hidden: HashSet<u32> = manually_hidden.into_iter().collect();
+
+    // The active sheet's sizes live in their active-sheet copy.
+    let active_sheet = *state.active_sheet.lock().unwrap();
+    let defaults = effective_default_dimensions(state, sheet);
+    let (active_sizes, all_sizes, default_size) = match axis {
+        Axis::Rows => (&state.row_heights, &state.all_row_heights, defaults.default_row_height),
+        Axis::Columns => (&state.column_widths, &state.all_column_widths, defaults.default_column_width),
+    };
+    let active_sizes = active_sizes.lock().unwrap();
+    let all_sizes = all_sizes.lock().unwrap();
+    let no_sizes = HashMap::new();
+    let sizes = if sheet == active_sheet {
+        &*active_sizes
+    } else {
+        all_sizes.get(sheet).unwrap_or(&no_sizes)
+    };
+
+    Ok(build_axis_metadata(
+        &AxisSources {
+            axis,
+            sizes,
+            default_size,
+            outline: outlines.get(&sheet),
+            filter_hidden: &filter_hidden,
+            manual_hidden: &manual_hidden,
+        },
+        start,
+        end,
+    ))
+}
@@ -0,0 +1,214 @@
+//! FILENAME: app/src-tauri/src/commands/export.rs
+// PURPOSE: Tauri commands for exporting sheet content as delimited text.
+// CONTEXT: Exports follow the same print settings as printing: the sheet's print
+// area (all of its areas, in order) replaces the used range when one is set, and
+// the print-title rows/columns repeat on every logical page.
+
+use crate::commands::print::sheet_page_setup;
+use crate::named_ranges::{print_area, print_titles, PrintRange};
+use crate::{format_cell_value, AppState};
+use serde::{Deserialize, Serialize};
+use tauri::State;
+
+fn default_delimiter() -> String {
+    ",".to_string()
+}
+
+fn default_text_qualifier() -> String {
+    "\"".to_string()
+}
+
+fn default_line_ending() -> String {
+    "\r\n".to_string()
+}
+
+fn default_true() -> bool {
+    true
+}
+
+/// Options for `export_csv`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CsvExportOptions {
+    #[serde(default = "default_delimiter")]
+    pub delimiter: String,
+    /// Empty string disables quoting.
+    #[serde(default = "default_text_qualifier")]
+    pub text_qualifier: String,
+    #[serde(default = "default_line_ending")]
+    pub line_ending: String,
+    /// Export the print area when the sheet has one (default). When false, or
+    /// when no print area is set, the used range is exported.
+    #[serde(default = "default_true")]
+    pub use_print_area: bool,
+    /// Rows per logical page, title rows included. `None` exports a single
+    /// page; otherwise manual row breaks also start new pages.
+    #[serde(default)]
+    pub rows_per_page: Option<u32>,
+}
+
+impl Default for CsvExportOptions {
+    fn default() -> Self {
+        CsvExportOptions {
+            delimiter: default_delimiter(),
+            text_qualifier: default_text_qualifier(),
+            line_ending: default_line_ending(),
+            use_print_area: true,
+            rows_per_page: None,
+        }
+    }
+}
+
+/// Result of `export_csv`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CsvExportResult {
+    pub content: String,
+    /// Number of lines written (repeated title rows included).
+    pub row_count: usize,
+}
+
+/// Split the body rows `first..=last` into logical pages. Every page starts
+/// with the title rows; title rows inside the range are not repeated as body
+/// rows. A page holds `rows_per_page` rows in total (at least one body row),
+/// and a manual break before a row starts a new page.
+pub fn paginate_rows(
+    first: u32,
+    last: u32,
+    titles: Option<(u32, u32)>,
+    rows_per_page: Option<u32>,
+    breaks: &[u32],
+) -> Vec<Vec<u32>> {
+    let title_rows: Vec<u32> = titles.map(|(start, end)| (start..=end).collect()).unwrap_or_default();
+    let capacity = rows_per_page
+        .map(|n| (n as usize).saturating_sub(title_rows.len()).max(1))
+        .unwrap_or(usize::MAX);
+
+    let mut pages = Vec::new();
+    let mut body: Vec<u32> = Vec::new();
+    for row in first..=last {
+        if title_rows.contains(&row) {
+            continue;
+        }
+        if !body.is_empty() && (body.len() >= capacity || breaks.contains(&row)) {
+            pages.push(std::mem::take(&mut body));
+        }
+        body.push(row);
+    }
+    if !body.is_empty() || pages.is_empty() {
+        pages.push(body);
+    }
+
+    pages
+        .into_iter()
+        .map(|rows| title_rows.iter().copied().chain(rows).collect::<Vec<u32>>())
+        .filter(|page| !page.is_empty())
+        .collect()
+}
+
+fn csv_field(value: &str, options: &CsvExportOptions) -> String {
+    let qualifier = options.text_qualifier.as_str();
+    if qualifier.is_empty() {
+        return value.to_string();
+    }
+    let needs_quoting = value.contains(options.delimiter.as_str())
+        || value.contains(qualifier)
+        || value.contains('\r')
+        || value.contains('\n');
+    if !needs_quoting {
+        return value.to_string();
+    }
+    let doubled = format!("{}{}", qualifier, qualifier);
+    format!("{}{}{}", qualifier, value.replace(qualifier, &doubled), qualifier)
+}
+
+pub(crate) fn export_csv_internal(state: &AppState, options: &CsvExportOptions) -> CsvExportResult {
+    let active_sheet = *state.active_sheet.lock().unwrap();
+    let setup = sheet_page_setup(state, active_sheet);
+    let (areas, titles) = {
+        let names = state.named_ranges.lock().unwrap();
+        let areas = if options.use_print_area { print_area(&names, active_sheet) } else { Vec::new() };
+        (areas, print_titles(&names, active_sheet))
+    };
+
+    let grid = state.get_active_grid();
+    let styles = state.style_registry.lock().unwrap();
+    let locale = state.locale.lock().unwrap();
+
+    if grid.cells.is_empty() {
+        return CsvExportResult { content: String::new(), row_count: 0 };
+    }
+
+    let areas = if areas.is_empty() {
+        vec![PrintRange { start_row: 0, start_col: 0, end_row: grid.max_row, end_col: grid.max_col }]
+    } else {
+        // Whole-row/column areas reach the sheet edge; only export up to the data.
+        areas
+            .into_iter()
+            .map(|area| PrintRange {
+                end_row: area.end_row.min(grid.max_row),
+                end_col: area.end_col.min(grid.max_col),
+                ..area
+            })
+            .filter(|area| area.start_row <= area.end_row && area.start_col <= area.end_col)
+            .collect()
+    };
+    let breaks: &[u32] = if options.rows_per_page.is_some() { &setup.manual_row_breaks } else { &[] };
+
+    let mut lines = Vec::new();
+    for area in &areas {
+        let mut cols: Vec<u32> = titles
+            .cols
+            .map(|(start, end)| (start..=end).filter(|c| *c < area.start_col).collect())
+            .unwrap_or_default();
+        cols.extend(area.start_col..=area.end_col);
+
+        for page in paginate_rows(area.start_row, area.end_row, titles.rows, options.rows_per_page, breaks) {
+            for row in page {
+                let fields: Vec<String> = cols
+                    .iter()
+                    .map(|&col| {
+                        let display = grid
+                            .get_cell(row, col)
+                            .map(|cell| format_cell_value(&cell.value, styles.get(cell.style_index), &locale))
+                            .unwrap_or_default();
+                        csv_field(&display, options)
+                    })
+                    .collect();
+                lines.push(fields.join(&options.delimiter));
+            }
+        }
+    }
+
+    CsvExportResult { row_count: lines.len(), content: lines.join(&options.line_ending) }
+}
+
+/// Export the active sheet as delimited text, honoring its print area and
+/// print titles. The caller writes the returned content to disk.
+#[tauri::command]
+pub fn export_csv(state: State<AppState>, options: Option<CsvExportOptions>) -> CsvExportResult {
+    export_csv_internal(&state, &options.unwrap_or_default())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_paginate_rows_single_page() {
+        assert_eq!(paginate_rows(0, 3, None, None, &[]), vec![vec![0, 1, 2, 3]]);
+        assert_eq!(paginate_rows(0, 3, Some((0, 0)), None, &[]), vec![vec![0, 1, 2, 3]]);
+    }
+
+    #[test]
+    fn test_paginate_rows_repeats_titles() {
+        let pages = paginate_rows(0, 6, Some((0, 0)), Some(3), &[]);
+        assert_eq!(pages, vec![vec![0, 1, 2], vec![0, 3, 4], vec![0, 5, 6]]);
+    }
+
+    #[test]
+    fn test_paginate_rows_manual_break() {
+        let pages = paginate_rows(5, 9, Some((0, 1)), Some(10), &[7]);
+        assert_eq!(pages, vec![vec![0, 1, 5, 6], vec![0, 1, 7, 8, 9]]);
+    }
+}
@@ -0,0 +1,205 @@
+//! FILENAME: app/src-tauri/src/commands/formula_extension.rs
+// PURPOSE: Table-less formula auto-extend ("extend data range formulas").
+// CONTEXT: When the user types a value in the row below a block whose
+// neighbouring columns hold formulas, Excel fills those formulas into the new
+// row. Outside tables (which grow their calculated columns themselves) there
+// is no structure to say which columns are formula columns, so the pattern is
+// inferred: a column qualifies when the MIN_PATTERN_ROWS cells directly above
+// the new row all hold the same formula modulo relative row offsets. Each
+// formula is shifted down to the new row (the same `shift_for_fill` the fill
+// handle uses) and the rendered results are compared. The upward scan is
+// fixed-size and the sideways scan stops at the first column that does not
+// qualify, so the check stays cheap enough to run after every entry.
+
+use engine::{Cell, CellValue, Grid};
+use serde::{Deserialize, Serialize};
+use tauri::State;
+
+use crate::api_types::{CellData, CellUpdateInput};
+use crate::persistence::{FileState, UserFilesState};
+use crate::AppState;
+
+/// Consistent formula rows required above the new row before extending.
+pub const MIN_PATTERN_ROWS: u32 = 3;
+
+/// A formula suggested for one cell of the newly entered row.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FormulaExtension {
+    pub row: u32,
+    pub col: u32,
+    /// The formula with its leading "=", in the user's locale.
+    pub formula: String,
+}
+
+// ============================================================================
+// DETECTION
+// ============================================================================
+
+fn is_blank(cell: Option<&Cell>) -> bool {
+    cell.is_none_or(|c| !c.has_formula() && matches!(c.value, CellValue::Empty))
+}
+
+/// The formula (invariant, without "=") that continues column `col`'s
+/// pattern into `row`, when the MIN_PATTERN_ROWS cells above agree and the
+/// target cell is still empty.
+fn extended_formula(grid: &Grid, row: u32, col: u32) -> Option<String> {
+    if row < MIN_PATTERN_ROWS || !is_blank(grid.get_cell(row, col)) {
+        return None;
+    }
+    let mut pattern: Option<String> = None;
+    for offset in 1..=MIN_PATTERN_ROWS {
+        let mut ast = grid.get_cell(row - offset, col)?.get_ast()?.clone();
+        engine::reference_shift::shift_for_fill(&mut ast, offset as i32, 0);
+        let rendered = engine::ast_render::render_formula(&ast);
+        match &pattern {
+            Some(expected) if *expected != rendered => return None,
+            Some(_) => {}
+            None => pattern = Some(rendered),
+        }
+    }
+    pattern
+}
+
+/// Candidate (col, formula) pairs for a value entered at (row, col): the
+/// contiguous qualifying columns on either side of the entry. The entry must
+/// continue a block, i.e. the cell above it holds something.
+fn scan_extensions(grid: &Grid, row: u32, col: u32) -> Vec<(u32, String)> {
+    if row == 0 || is_blank(grid.get_cell(row, col)) || is_blank(grid.get_cell(row - 1, col)) {
+        return Vec::new();
+    }
+    let mut found = Vec::new();
+    for c in (0..col).rev() {
+        match extended_formula(grid, row, c) {
+            Some(formula) => found.push((c, formula)),
+            None => break,
+        }
+    }
+    found.reverse();
+    for c in col + 1..=grid.max_col {
+        match extended_formula(grid, row, c) {
+            Some(formula) => found.push((c, formula)),
+            None => break,
+        }
+    }
+    found
+}
+
+/// Invariant-form suggestions for the active sheet, filtered to cells the
+/// user may write: never inside a table (tables extend their own calculated
+/// columns), a protected object region, or a locked cell of a protected
+/// sheet. Empty when `sheet` is no longer the active sheet.
+fn suggestions_for(state: &AppState, sheet: usize, row: u32, col: u32) -> Vec<(u32, String)> {
+    if *state.active_sheet.lock().unwrap() != sheet {
+        return Vec::new();
+    }
+    let candidates = {
+        let grids = state.grids.lock().unwrap();
+        match grids.get(sheet) {
+            Some(grid) => scan_extensions(grid, row, col),
+            None => return Vec::new(),
+        }
+    };
+    if candidates.is_empty() {
+        return candidates;
+    }
+
+    let candidates: Vec<(u32, String)> = {
+        let tables = state.tables.lock().unwrap();
+        let in_table = |r: u32, c: u32| {
+            tables.get(&sheet).is_some_and(|t| t.values().any(|t| t.contains(r, c)))
+        };
+        if in_table(row, col) {
+            return Vec::new();
+        }
+        candidates
+            .into_iter()
+            .filter(|(c, _)| !in_table(row, *c) && !in_table(row - 1, *c))
+            .collect()
+    };
+    candidates
+        .into_iter()
+        .filter(|(c, _)| state.get_region_at_cell(sheet, row, *c).is_none())
+        .filter(|(c, _)| crate::protection::can_edit_cell_internal(state, row, *c).can_edit)
+        .collect()
+}
+
+pub(crate) fn suggest_formula_extension_internal(
+    state: &AppState,
+    sheet: usize,
+    row: u32,
+    col: u32,
+) -> Vec<FormulaExtension> {
+    let suggestions = suggestions_for(state, sheet, row, col);
+    if suggestions.is_empty() {
+        return Vec::new();
+    }
+    let locale = state.locale.lock().unwrap();
+    suggestions
+        .into_iter()
+        .map(|(c, formula)| FormulaExtension {
+            row,
+            col: c,
+            formula: format!("={}", engine::localize_formula(&formula, &locale)),
+        })
+        .collect()
+}
+
+// ============================================================================
+// COMMANDS
+// ============================================================================
+
+/// After a value is entered at (row, col), the formulas that the adjacent
+/// formula columns would extend into that row. Empty when no column above
+/// holds a consistent pattern of at least MIN_PATTERN_ROWS formulas.
+#[tauri::command]
+pub fn suggest_formula_extension(
+    state: State<AppState>,
+    sheet: usize,
+    row: u32,
+    col: u32,
+) -> Vec<FormulaExtension> {
+    suggest_formula_extension_internal(&state, sheet, row, col)
+}
+
+/// Writes the suggested formulas for a value entered at (row, col) as one
+/// batch edit: dependencies are tracked and the whole extension is a single
+/// undo step. The pattern is re-detected here, so a stale suggestion never
+/// overwrites a cell that has since been filled.
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub fn apply_formula_extension(
+    state: State<AppState>,
+    file_state: State<FileState>,
+    user_files_state: State<UserFilesState>,
+    pivot_state: State<'_, crate::pivot::PivotState>,
+    pane_control_state: State<'_, crate::pane_control::PaneControlState>,
+    ribbon_filter_state: State<'_, crate::ribbon_filter::RibbonFilterState>,
+    sheet: usize,
+    row: u32,
+    col: u32,
+) -> Result<Vec<CellData>, String> {
+    let updates: Vec<CellUpdateInput> = suggestions_for(&state, sheet, row, col)
+        .into_iter()
+        .map(|(c, formula)| CellUpdateInput {
+            row,
+            col: c,
+            value: format!("={}", formula),
+            style_index: None,
+            invariant: Some(true),
+        })
+        .collect();
+    if updates.is_empty() {
+        return Ok(Vec::new());
+    }
+    super::data::update_cells_batch(
+        state,
+        file_state,
+        user_files_state,
+        pivot_state,
+        pane_control_state,
+        ribbon_filter_state,
+        updates,
+        None,
+    )
+}
@@ -0,0 +1,514 @@
+//! FILENAME: app/src-tauri/src/commands/inspect.rs
+// PURPOSE: Document inspector - find and strip content users may not want to
+// share (comments, hidden sheets/lines/formulas, names, external links, pivot
+// caches, personal and descriptive document metadata).
+// CONTEXT: Every category is read straight from its own store. Removal runs
+// the irreversible categories first (deleting hidden sheets renumbers every
+// sheet-keyed store) and then records the reversible ones - comment, note,
+// hyperlink, named-range and workbook-property restores - in a single undo
+// transaction. Irreversible categories are only removed when the caller
+// confirms the warning it got back.
+
+use std::collections::{BTreeSet, HashSet};
+
+use serde::{Deserialize, Serialize};
+use tauri::State;
+
+use crate::persistence::UserFilesState;
+use crate::pivot::types::PivotState;
+use crate::pivot::utils::col_index_to_letter;
+use crate::protection::effective_cell_protection;
+use crate::AppState;
+
+/// Undo description of a removal.
+const REMOVE_DESCRIPTION: &str = "Remove document inspector items";
+
+// ============================================================================
+// TYPES
+// ============================================================================
+
+/// A category of content the document inspector reports.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum InspectionKind {
+    /// Comment threads and notes.
+    Comments,
+    /// Sheets whose visibility is hidden or veryHidden.
+    HiddenSheets,
+    /// Rows hidden by filters or collapsed groups, columns in collapsed groups.
+    /// Manual hides live in the frontend and are not seen here.
+    HiddenRowsColumns,
+    /// Workbook- and sheet-scoped defined names.
+    DefinedNames,
+    /// Hyperlinks pointing outside the workbook (URLs, files, e-mail).
+    ExternalLinks,
+    /// Formula cells flagged formula-hidden.
+    HiddenFormulas,
+    /// Pivot tables whose cache holds a copy of their source records.
+    PivotCaches,
+    /// Author and other personal document properties.
+    PersonalInfo,
+    /// Descriptive document properties (title, subject, tags, ...) and
+    /// custom properties.
+    DocumentProperties,
+}
+
+impl InspectionKind {
+    pub const ALL: [InspectionKind; 9] = [
+        InspectionKind::Comments,
+        InspectionKind::HiddenSheets,
+        InspectionKind::HiddenRowsColumns,
+        InspectionKind::DefinedNames,
+        InspectionKind::ExternalLinks,
+        InspectionKind::HiddenFormulas,
+        InspectionKind::PivotCaches,
+        InspectionKind::PersonalInfo,
+        InspectionKind::DocumentProperties,
+    ];
+
+    /// Whether removing this category can be undone.
+    pub fn reversible(self) -> bool {
+        !matches!(
+            self,
+            InspectionKind::HiddenSheets
+                | InspectionKind::HiddenRowsColumns
+                | InspectionKind::HiddenFormulas
+                | InspectionKind::PivotCaches
+        )
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            InspectionKind::Comments => "comments and notes",
+            InspectionKind::HiddenSheets => "hidden sheets",
+            InspectionKind::HiddenRowsColumns => "the contents of hidden rows and columns",
+            InspectionKind::DefinedNames => "defined names",
+            InspectionKind::ExternalLinks => "external links",
+            InspectionKind::HiddenFormulas => "hidden formulas (replaced by their values)",
+            InspectionKind::PivotCaches => "pivot caches",
+            InspectionKind::PersonalInfo => "personal information",
+            InspectionKind::DocumentProperties => "document properties",
+        }
+    }
+}
+
+/// What the inspector found for one category.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InspectionItem {
+    pub kind: InspectionKind,
+    pub count: usize,
+    /// Where the items are, e.g. "Sheet1!B2", "Sheet2!4:6", "Budget".
+    pub locations: Vec<String>,
+    pub reversible: bool,
+}
+
+/// Result of `inspect_workbook`: one entry per category that has items.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WorkbookInspection {
+    pub items: Vec<InspectionItem>,
+}
+
+/// Result of `remove_inspection_items`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RemoveInspectionResult {
+    pub success: bool,
+    /// Irreversible categories were requested without confirmation; nothing
+    /// was removed. Call again with `confirm_irreversible` after showing
+    /// `warning`.
+    pub needs_confirmation: bool,
+    pub warning: Option<String>,
+    /// What was removed, as inspected just before.
+    pub removed: Vec<InspectionItem>,
+}
+
+// ============================================================================
+// INSPECTION
+// ============================================================================
+
+/// "Sheet1!B2"
+fn cell_location(sheet_name: &str, row: u32, col: u32) -> String {
+    format!("{}!{}{}", sheet_name, col_index_to_letter(col), row + 1)
+}
+
+/// Collapses sorted line indices into "Sheet1!4:6" / "Sheet1!B:D" runs.
+fn line_locations(sheet_name: &str, lines: &BTreeSet<u32>, columns: bool, out: &mut Vec<String>) {
+    let label = |i: u32| if columns { col_index_to_letter(i) } else { (i + 1).to_string() };
+    let mut iter = lines.iter().copied().peekable();
+    while let Some(first) = iter.next() {
+        let mut last = first;
+        while iter.peek() == Some(&(last + 1)) {
+            last = iter.next().unwrap();
+        }
+        out.push(format!("{}!{}:{}", sheet_name, label(first), label(last)));
+    }
+}
+
+/// Hidden rows and columns of one sheet: rows hidden by an AutoFilter,
+/// Advanced Filter or collapsed group, columns in a collapsed group.
+fn hidden_lines(state: &AppState, sheet: usize) -> (BTreeSet<u32>, BTreeSet<u32>) {
+    let mut rows = BTreeSet::new();
+    let mut cols = BTreeSet::new();
+    if let Some(af) = state.auto_filters.lock().unwrap().get(&sheet) {
+        rows.extend(af.hidden_rows.iter().copied());
+    }
+    if let Some(hidden) = state.advanced_filter_hidden_rows.lock().unwrap().get(&sheet) {
+        rows.extend(hidden.iter().copied());
+    }
+    if let Some(outline) = state.outlines.lock().unwrap().get(&sheet) {
+        rows.extend(outline.get_hidden_rows());
+        cols.extend(outline.get_hidden_cols());
+    }
+    (rows, cols)
+}
+
+/// Formula cells flagged formula-hidden on every sheet, as (sheet, row, col).
+/// The flag is reported whether or not the sheet is protected right now.
+fn hidden_formula_cells(state: &AppState) -> Vec<(usize, u32, u32)> {
+    let cell_protection = state.cell_protection.lock().unwrap();
+    let range_protection = state.range_protection.lock().unwrap();
+    let grids = state.grids.lock().unwrap();
+    let mut found = Vec::new();
+    for (sheet, grid) in grids.iter().enumerate() {
+        let cells = cell_protection.get(&sheet);
+        let ranges = range_protection.get(&sheet).map(Vec::as_slice).unwrap_or(&[]);
+        if cells.is_none_or(|c| c.values().all(|p| !p.formula_hidden))
+            && ranges.iter().all(|r| r.formula_hidden != Some(true))
+        {
+            continue;
+        }
+        for (&(row, col), cell) in grid.cells.iter() {
+            if cell.ast.is_some() && effective_cell_protection(cells, ranges, row, col).formula_hidden {
+                found.push((sheet, row, col));
+            }
+        }
+    }
+    found.sort_unstable();
+    found
+}
+
+fn inspect_kind(state: &AppState, pivot_state: &PivotState, kind: InspectionKind) -> InspectionItem {
+    let sheet_names = state.sheet_names.lock().unwrap().clone();
+    let sheet_name = |i: usize| sheet_names.get(i).cloned().unwrap_or_default();
+    let mut locations = Vec::new();
+    let mut count = 0;
+    match kind {
+        InspectionKind::Comments => {
+            let comments = state.comments.lock().unwrap();
+            let notes = state.notes.lock().unwrap();
+            let mut cells = BTreeSet::new();
+            for (&sheet, by_cell) in comments.iter() {
+                count += by_cell.len();
+                cells.extend(by_cell.keys().map(|&(row, col)| (sheet, row, col)));
+            }
+            for (&sheet, by_cell) in notes.iter() {
+                count += by_cell.len();
+                cells.extend(by_cell.keys().map(|&(row, col)| (sheet, row, col)));
+            }
+            locations.extend(cells.into_iter().map(|(s, r, c)| cell_location(&sheet_name(s), r, c)));
+        }
+        InspectionKind::HiddenSheets => {
+            let visibility = state.sheet_visibility.lock().unwrap();
+            for (i, name) in sheet_names.iter().enumerate() {
+                if visibility.get(i).is_some_and(|v| v != "visible") {
+                    count += 1;
+                    locations.push(name.clone());
+                }
+            }
+        }
+        InspectionKind::HiddenRowsColumns => {
+            for (sheet, name) in sheet_names.iter().enumerate() {
+                let (rows, cols) = hidden_lines(state, sheet);
+                count += rows.len() + cols.len();
+                line_locations(name, &rows, false, &mut locations);
+                line_locations(name, &cols, true, &mut locations);
+            }
+        }
+        InspectionKind::DefinedNames => {
+            let named_ranges = state.named_ranges.lock().unwrap();
+            let mut names: Vec<String> = named_ranges.values().map(|n| n.name.clone()).collect();
+            names.sort_by_key(|n| n.to_uppercase());
+            count = names.len();
+            locations = names;
+        }
+        InspectionKind::ExternalLinks => {
+            let hyperlinks = state.hyperlinks.lock().unwrap();
+            let mut cells = BTreeSet::new();
+            for (&sheet, by_cell) in hyperlinks.iter() {
+                for (&(row, col), link) in by_cell {
+                    if link.link_type != crate::hyperlinks::HyperlinkType::InternalReference {
+                        cells.insert((sheet, row, col));
+                    }
+                }
+            }
+            count = cells.len();
+            locations.extend(cells.into_iter().map(|(s, r, c)| cell_location(&sheet_name(s), r, c)));
+        }
+        InspectionKind::HiddenFormulas => {
+            let cells = hidden_formula_cells(state);
+            count = cells.len();
+            locations.extend(cells.into_iter().map(|(s, r, c)| cell_location(&sheet_name(s), r, c)));
+        }
+        InspectionKind::PivotCaches => {
+            let pivots = pivot_state.pivot_tables.lock().unwrap();
+            let mut cached: Vec<String> = pivots
+                .values()
+                .filter(|(_, cache)| !cache.records.is_empty())
+                .map(|(definition, cache)| {
+                    let name = definition.name.clone().unwrap_or_else(|| "PivotTable".to_string());
+                    format!("{} ({} records)", name, cache.records.len())
+                })
+                .collect();
+            cached.sort();
+            count = cached.len();
+            locations = cached;
+        }
+        InspectionKind::PersonalInfo => {
+            let properties = state.workbook_properties.lock().unwrap();
+            locations = [
+                ("Author", &properties.author),
+                ("Last modified by", &properties.last_modified_by),
+                ("Manager", &properties.manager),
+                ("Company", &properties.company),
+            ]
+            .into_iter()
+            .filter(|(_, value)| !value.is_empty())
+            .map(|(label, value)| format!("{}: {}", label, value))
+            .collect();
+            count = locations.len();
+        }
+        InspectionKind::DocumentProperties => {
+            let properties = state.workbook_properties.lock().unwrap();
+            locations = [
+                ("Title", &properties.title),
+                ("Subject", &properties.subject),
+                ("Comments", &properties.description),
+                ("Tags", &properties.keywords),
+                ("Categories", &properties.category),
+                ("Status", &properties.status),
+                ("Hyperlink base", &properties.hyperlink_base),
+            ]
+            .into_iter()
+            .filter(|(_, value)| !value.is_empty())
+            .map(|(label, value)| format!("{}: {}", label, value))
+            .chain(properties.custom.iter().map(|prop| format!("Custom: {}", prop.name)))
+            .collect();
+            count = locations.len();
+        }
+    }
+    InspectionItem { kind, count, locations, reversible: kind.reversible() }
+}
+
+/// Report everything the workbook holds that users may want to strip before
+/// sharing. Categories without items are left out.
+#[tauri::command]
+pub fn inspect_workbook(state: State<AppState>, pivot_state: State<'_, PivotState>) -> WorkbookInspection {
+    inspect_workbook_internal(&state, &pivot_state)
+}
+
+pub(crate) fn inspect_workbook_internal(state: &AppState, pivot_state: &PivotState) -> WorkbookInspection {
+    let items = InspectionKind::ALL
+        .into_iter()
+        .map(|kind| inspect_kind(state, pivot_state, kind))
+        .filter(|item| item.count > 0)
+        .collect();
+    WorkbookInspection { items }
+}
+
+// ============================================================================
+// REMOVAL
+// ============================================================================
+
+/// Delete every hidden sheet, highest index first so the rest keep theirs.
+fn remove_hidden_sheets(state: &AppState, pivot_state: &PivotState) -> Result<(), String> {
+    let hidden: Vec<usize> = {
+        let visibility = state.sheet_visibility.lock().unwrap();
+        visibility.iter().enumerate().filter(|(_, v)| v.as_str() != "visible").map(|(i, _)| i).collect()
+    };
+    for index in hidden.into_iter().rev() {
+        crate::sheets::delete_sheet_internal(state, pivot_state, index)?;
+    }
+    Ok(())
+}
+
+/// Clear the cells of hidden rows and columns; the lines themselves stay.
+fn clear_hidden_lines(state: &AppState) {
+    let sheet_count = state.sheet_names.lock().unwrap().len();
+    let lines: Vec<_> = (0..sheet_count).map(|sheet| hidden_lines(state, sheet)).collect();
+    let mut grids = state.grids.lock().unwrap();
+    for (grid, (rows, cols)) in grids.iter_mut().zip(&lines) {
+        let doomed: Vec<(u32, u32)> = grid
+            .cells
+            .keys()
+            .filter(|(row, col)| rows.contains(row) || cols.contains(col))
+            .copied()
+            .collect();
+        for (row, col) in doomed {
+            grid.clear_cell(row, col);
+        }
+    }
+}
+
+/// Replace each hidden formula with the value it last computed.
+fn freeze_hidden_formulas(state: &AppState) {
+    let cells = hidden_formula_cells(state);
+    let mut grids = state.grids.lock().unwrap();
+    for (sheet, row, col) in cells {
+        if let Some(cell) = grids[sheet].get_cell(row, col) {
+            let mut frozen = cell.clone();
+            frozen.ast = None;
+            grids[sheet].set_cell(row, col, frozen);
+        }
+    }
+}
+
+/// Drop the source records of every pivot cache; a refresh rebuilds them.
+fn clear_pivot_caches(pivot_state: &PivotState) {
+    let mut pivots = pivot_state.pivot_tables.lock().unwrap();
+    for (definition, cache) in pivots.values_mut() {
+        *cache = pivot_engine::PivotCache::new(definition.id, cache.fields.len());
+    }
+}
+
+/// Delete comments, notes and external hyperlinks, recording a restore for
+/// each into the open undo transaction.
+fn remove_cell_annotations(state: &AppState, comments: bool, links: bool) {
+    if comments {
+        let removed: Vec<_> = std::mem::take(&mut *state.comments.lock().unwrap())
+            .into_iter()
+            .flat_map(|(sheet, by_cell)| by_cell.into_iter().map(move |(pos, c)| (sheet, pos, c)))
+            .collect();
+        for (sheet, (row, col), comment) in removed {
+            crate::comments::record_comment_undo(state, sheet, row, col, Some(comment), REMOVE_DESCRIPTION);
+        }
+        let removed: Vec<_> = std::mem::take(&mut *state.notes.lock().unwrap())
+            .into_iter()
+            .flat_map(|(sheet, by_cell)| by_cell.into_iter().map(move |(pos, n)| (sheet, pos, n)))
+            .collect();
+        for (sheet, (row, col), note) in removed {
+            crate::notes::record_note_undo(state, sheet, row, col, Some(note), REMOVE_DESCRIPTION);
+        }
+    }
+    if links {
+        let mut removed = Vec::new();
+        {
+            let mut hyperlinks = state.hyperlinks.lock().unwrap();
+            for (&sheet, by_cell) in hyperlinks.iter_mut() {
+                by_cell.retain(|&pos, link| {
+                    let internal = link.link_type == crate::hyperlinks::HyperlinkType::InternalReference;
+                    if !internal {
+                        removed.push((sheet, pos, link.clone()));
+                    }
+                    internal
+                });
+            }
+        }
+        for (sheet, (row, col), link) in removed {
+            crate::hyperlinks::record_hyperlink_undo(state, sheet, row, col, Some(link), REMOVE_DESCRIPTION);
+        }
+    }
+}
+
+/// Strip the selected categories. Reversible ones are undone together by a
+/// single undo; irreversible ones (see `InspectionKind::reversible`) are only
+/// removed when `confirm_irreversible` is set, otherwise the call returns a
+/// warning naming them and changes nothing.
+#[tauri::command]
+pub fn remove_inspection_items(
+    state: State<AppState>,
+    pivot_state: State<'_, PivotState>,
+    user_files_state: State<UserFilesState>,
+    kinds: Vec<InspectionKind>,
+    confirm_irreversible: Option<bool>,
+) -> Result<RemoveInspectionResult, String> {
+    remove_inspection_items_internal(&state, &pivot_state, &user_files_state, kinds, confirm_irreversible.unwrap_or(false))
+}
+
+pub(crate) fn remove_inspection_items_internal(
+    state: &AppState,
+    pivot_state: &PivotState,
+    user_files_state: &UserFilesState,
+    kinds: Vec<InspectionKind>,
+    confirm_irreversible: bool,
+) -> Result<RemoveInspectionResult, String> {
+    let kinds: HashSet<InspectionKind> = kinds.into_iter().collect();
+    let removed: Vec<InspectionItem> = InspectionKind::ALL
+        .into_iter()
+        .filter(|kind| kinds.contains(kind))
+        .map(|kind| inspect_kind(state, pivot_state, kind))
+        .filter(|item| item.count > 0)
+        .collect();
+    let irreversible: Vec<&str> = removed.iter().filter(|i| !i.reversible).map(|i| i.kind.label()).collect();
+    if !irreversible.is_empty() && !confirm_irreversible {
+        return Ok(RemoveInspectionResult {
+            success: false,
+            needs_confirmation: true,
+            warning: Some(format!("Removing {} cannot be undone.", irreversible.join(", "))),
+            removed: Vec::new(),
+        });
+    }
+    let has = |kind: InspectionKind| removed.iter().any(|i| i.kind == kind);
+
+    // Irreversible first: deleting sheets renumbers the sheet-keyed stores the
+    // undo records below refer to.
+    if has(InspectionKind::HiddenSheets) {
+        remove_hidden_sheets(state, pivot_state)?;
+    }
+    let cells_changed = has(InspectionKind::HiddenRowsColumns) || has(InspectionKind::HiddenFormulas);
+    if has(InspectionKind::HiddenRowsColumns) {
+        clear_hidden_lines(state);
+    }
+    if has(InspectionKind::HiddenFormulas) {
+        freeze_hidden_formulas(state);
+    }
+    if has(InspectionKind::PivotCaches) {
+        clear_pivot_caches(pivot_state);
+    }
+
+    state.undo_stack.lock().unwrap().begin_transaction(REMOVE_DESCRIPTION);
+    remove_cell_annotations(state, has(InspectionKind::Comments), has(InspectionKind::ExternalLinks));
+    if has(InspectionKind::DefinedNames) {
+        let names: Vec<String> = state.named_ranges.lock().unwrap().values().map(|n| n.name.clone()).collect();
+        for name in names {
+            crate::named_ranges::delete_named_range_internal(state, user_files_state, pivot_state, name);
+        }
+    }
+    if has(InspectionKind::PersonalInfo) || has(InspectionKind::DocumentProperties) {
+        let previous = {
+            let mut properties = state.workbook_properties.lock().unwrap();
+            let previous = properties.clone();
+            if has(InspectionKind::PersonalInfo) {
+                properties.author.clear();
+                properties.last_modified_by.clear();
+                properties.manager.clear();
+                properties.company.clear();
+            }
+            if has(InspectionKind::DocumentProperties) {
+                properties.title.clear();
+                properties.subject.clear();
+                properties.description.clear();
+                properties.keywords.clear();
+                properties.category.clear();
+                properties.status.clear();
+                properties.hyperlink_base.clear();
+                properties.custom.clear();
+            }
+            previous
+        };
+        crate::undo_commands::record_workbook_properties_undo(state, previous, REMOVE_DESCRIPTION);
+    }
+    state.undo_stack.lock().unwrap().commit_transaction();
+
+    if cells_changed {
+        crate::undo_commands::rebuild_all_dependencies(state);
+        crate::undo_commands::rebuild_cross_sheet_dependencies(state);
+        let sheet_count = state.sheet_names.lock().unwrap().len();
+        for sheet in 0..sheet_count {
+            crate::calculation::recalculate_sheet_values(state, user_files_state, pivot_state, sheet, None);
+        }
+    }
+
+    Ok(RemoveInspectionResult { success: true, needs_confirmation: false, warning: None, removed })
+}
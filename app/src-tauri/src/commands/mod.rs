@@ -1,8 +1,13 @@
 //! FILENAME: app/src-tauri/src/commands/mod.rs
 // PURPOSE: Exposes all sub-modules to the rest of the app, maintaining the same API surface.
 
+pub mod analysis;
+pub mod consistency;
 pub mod data;
 pub mod dimensions;
+pub mod export;
+pub mod formula_extension;
+pub mod inspect;
 pub mod nav;
 pub mod print;
 pub mod search;
@@ -11,8 +16,13 @@ pub mod styles;
 pub mod utils;
 
 // Re-export commands so they are accessible via crate::commands::*
+pub use analysis::*;
+pub use consistency::*;
 pub use data::*;
 pub use dimensions::*;
+pub use export::*;
+pub use formula_extension::*;
+pub use inspect::*;
 pub use nav::*;
 pub use print::*;
 pub use search::*;
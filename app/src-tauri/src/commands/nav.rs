@@ -49,7 +49,7 @@ pub fn get_current_region(
 /// Internal implementation of data region detection.
 /// Shared by both `detect_data_region` and `get_current_region`.
 fn detect_data_region_impl(state: &AppState, row: u32, col: u32) -> Option<(u32, u32, u32, u32)> {
-    let grid = state.grid.lock().unwrap();
+    let grid = state.get_active_grid();
 
     // Helper: does this cell have content?
     let has_content = |r: u32, c: u32| -> bool {
@@ -144,7 +144,7 @@ pub fn find_ctrl_arrow_target(
     max_row: u32,
     max_col: u32,
 ) -> (u32, u32) {
-    let grid = state.grid.lock().unwrap();
+    let grid = state.get_active_grid();
     
     // Determine direction deltas
     let (d_row, d_col): (i32, i32) = match direction.as_str() {
@@ -237,7 +237,7 @@ pub fn find_ctrl_arrow_target(
 // ============================================================================
 
 /// A cell coordinate returned for Go To Special results.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct CellCoord {
     pub row: u32,
@@ -261,7 +261,7 @@ pub fn go_to_special(
     criteria: String,
     search_range: Option<(u32, u32, u32, u32)>,
 ) -> GoToSpecialResult {
-    let grid = state.grid.lock().unwrap();
+    let grid = state.get_active_grid();
     let active_sheet = *state.active_sheet.lock().unwrap();
 
     // Determine search bounds
@@ -383,4 +383,229 @@ pub fn go_to_special(
     cells.sort_by(|a, b| a.row.cmp(&b.row).then(a.col.cmp(&b.col)));
 
     GoToSpecialResult { cells }
-}
\ No newline at end of file
+}
+// ============================================================================
+// Find Next Cell
+// ============================================================================
+
+/// Which cells `find_next_cell` stops at.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum NavPredicate {
+    /// Cells editable on the protected sheet (Tab cycling)
+    Unlocked,
+    HasValidation,
+    HasComment,
+    HasError,
+    HasFormula,
+}
+
+/// Scan order of `find_next_cell`: `right`/`left` walk row by row (Tab and
+/// Shift+Tab), `down`/`up` walk column by column (Enter and Shift+Enter).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum NavDirection {
+    Right,
+    Left,
+    Down,
+    Up,
+}
+
+/// Parameters for `find_next_cell`.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FindNextCellParams {
+    pub from: CellCoord,
+    pub direction: NavDirection,
+    pub predicate: NavPredicate,
+    /// Continue from the other end of the sheet when nothing lies ahead
+    #[serde(default)]
+    pub wrap: bool,
+}
+
+/// Candidate block in scan order: `major` is the row for row-major
+/// directions and the column for column-major ones.
+#[derive(Debug, Clone, Copy)]
+struct ScanRect {
+    major: (u32, u32),
+    minor: (u32, u32),
+}
+
+impl ScanRect {
+    fn new(direction: NavDirection, (start_row, start_col): (u32, u32), (end_row, end_col): (u32, u32)) -> Self {
+        match direction {
+            NavDirection::Right | NavDirection::Left => ScanRect { major: (start_row, end_row), minor: (start_col, end_col) },
+            NavDirection::Down | NavDirection::Up => ScanRect { major: (start_col, end_col), minor: (start_row, end_row) },
+        }
+    }
+
+    /// First position of the block after `cursor` (or its first position when
+    /// there is no cursor yet).
+    fn next_after(&self, cursor: Option<(u32, u32)>) -> Option<(u32, u32)> {
+        let (a0, a1) = self.major;
+        let (b0, b1) = self.minor;
+        let Some((a, b)) = cursor else {
+            return Some((a0, b0));
+        };
+        if a < a0 {
+            Some((a0, b0))
+        } else if a > a1 {
+            None
+        } else if b < b0 {
+            Some((a, b0))
+        } else if b < b1 {
+            Some((a, b + 1))
+        } else if a < a1 {
+            Some((a + 1, b0))
+        } else {
+            None
+        }
+    }
+
+    /// Last position of the block before `cursor` (or its last position when
+    /// there is no cursor yet).
+    fn next_before(&self, cursor: Option<(u32, u32)>) -> Option<(u32, u32)> {
+        let (a0, a1) = self.major;
+        let (b0, b1) = self.minor;
+        let Some((a, b)) = cursor else {
+            return Some((a1, b1));
+        };
+        if a > a1 {
+            Some((a1, b1))
+        } else if a < a0 {
+            None
+        } else if b > b1 {
+            Some((a, b1))
+        } else if b > b0 {
+            Some((a, b - 1))
+        } else if a > a0 {
+            Some((a - 1, b1))
+        } else {
+            None
+        }
+    }
+}
+
+/// The cells a predicate may match, as blocks pulled from the store that
+/// owns them, plus a per-cell check for stores where a block is only a
+/// candidate (later protection records can lock cells inside an unlocked
+/// range).
+struct NavCandidates {
+    rects: Vec<ScanRect>,
+    accepts: Box<dyn Fn(u32, u32) -> bool>,
+}
+
+fn nav_candidates(state: &AppState, sheet: usize, predicate: NavPredicate, direction: NavDirection) -> NavCandidates {
+    let accept_all: Box<dyn Fn(u32, u32) -> bool> = Box::new(|_, _| true);
+    let cells = |points: Vec<(u32, u32)>| -> Vec<ScanRect> {
+        points.into_iter().map(|p| ScanRect::new(direction, p, p)).collect()
+    };
+
+    match predicate {
+        NavPredicate::Unlocked => {
+            let protected_ranges: Vec<crate::protection::AllowEditRange> = state
+                .sheet_protection
+                .lock()
+                .unwrap()
+                .get(&sheet)
+                .filter(|p| p.protected)
+                .map(|p| p.allow_edit_ranges.clone())
+                .unwrap_or_default();
+            let cell_flags = state.cell_protection.lock().unwrap().get(&sheet).cloned().unwrap_or_default();
+            let range_flags = state.range_protection.lock().unwrap().get(&sheet).cloned().unwrap_or_default();
+
+            let mut rects: Vec<ScanRect> = protected_ranges
+                .iter()
+                .map(|r| ScanRect::new(direction, (r.start_row, r.start_col), (r.end_row, r.end_col)))
+                .collect();
+            rects.extend(
+                range_flags
+                    .iter()
+                    .filter(|r| r.locked == Some(false))
+                    .map(|r| ScanRect::new(direction, (r.start_row, r.start_col), (r.end_row, r.end_col))),
+            );
+            rects.extend(cells(
+                cell_flags.iter().filter(|(_, cp)| !cp.locked).map(|(pos, _)| *pos).collect(),
+            ));
+
+            let accepts = move |row: u32, col: u32| {
+                protected_ranges.iter().any(|r| r.contains(row, col))
+                    || !crate::protection::effective_cell_protection(Some(&cell_flags), &range_flags, row, col).locked
+            };
+            NavCandidates { rects, accepts: Box::new(accepts) }
+        }
+        NavPredicate::HasValidation => {
+            let validations = state.data_validations.lock().unwrap();
+            let rects = validations
+                .get(&sheet)
+                .map(|ranges| {
+                    ranges
+                        .iter()
+                        .map(|r| ScanRect::new(direction, (r.start_row, r.start_col), (r.end_row, r.end_col)))
+                        .collect()
+                })
+                .unwrap_or_default();
+            NavCandidates { rects, accepts: accept_all }
+        }
+        NavPredicate::HasComment => {
+            let comments = state.comments.lock().unwrap();
+            let points = comments.get(&sheet).map(|c| c.keys().copied().collect()).unwrap_or_default();
+            NavCandidates { rects: cells(points), accepts: accept_all }
+        }
+        NavPredicate::HasError | NavPredicate::HasFormula => {
+            let grids = state.grids.lock().unwrap();
+            let points = grids
+                .get(sheet)
+                .map(|grid| {
+                    grid.cells
+                        .iter()
+                        .filter(|(_, cell)| match predicate {
+                            NavPredicate::HasError => matches!(cell.value, CellValue::Error(_)),
+                            _ => cell.has_formula(),
+                        })
+                        .map(|(pos, _)| *pos)
+                        .collect()
+                })
+                .unwrap_or_default();
+            NavCandidates { rects: cells(points), accepts: accept_all }
+        }
+    }
+}
+
+/// Closest accepted position after (or before) `cursor` in scan order.
+fn scan_candidates(candidates: &NavCandidates, mut cursor: Option<(u32, u32)>, forward: bool) -> Option<(u32, u32)> {
+    loop {
+        let steps = candidates.rects.iter().filter_map(|rect| {
+            if forward { rect.next_after(cursor) } else { rect.next_before(cursor) }
+        });
+        let next = if forward { steps.min() } else { steps.max() }?;
+        if (candidates.accepts)(next.0, next.1) {
+            return Some(next);
+        }
+        cursor = Some(next);
+    }
+}
+
+/// Find the next cell matching `predicate` from `from`, in the scan order
+/// of `direction`. The search jumps between the blocks recorded in the
+/// predicate's store instead of walking empty cells. With `wrap`, it
+/// continues from the start (or end) of the sheet and may come back to
+/// `from` itself. Returns None when no cell matches.
+#[tauri::command]
+pub fn find_next_cell(state: State<AppState>, params: FindNextCellParams) -> Option<CellCoord> {
+    find_next_cell_internal(&state, params)
+}
+
+pub(crate) fn find_next_cell_internal(state: &AppState, params: FindNextCellParams) -> Option<CellCoord> {
+    let sheet = *state.active_sheet.lock().unwrap();
+    let candidates = nav_candidates(state, sheet, params.predicate, params.direction);
+    let forward = matches!(params.direction, NavDirection::Right | NavDirection::Down);
+    let row_major = matches!(params.direction, NavDirection::Right | NavDirection::Left);
+
+    let from = if row_major { (params.from.row, params.from.col) } else { (params.from.col, params.from.row) };
+    let found = scan_candidates(&candidates, Some(from), forward)
+        .or_else(|| if params.wrap { scan_candidates(&candidates, None, forward) } else { None })?;
+
+    let (row, col) = if row_major { found } else { (found.1, found.0) };
+    Some(CellCoord { row, col })
+}
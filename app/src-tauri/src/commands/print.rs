@@ -1,20 +1,55 @@
 //! FILENAME: app/src-tauri/src/commands/print.rs
 // PURPOSE: Tauri commands for page setup and print functionality.
+// CONTEXT: The print area and print titles are the sheet's `_xlnm.Print_Area` /
+// `_xlnm.Print_Titles` built-in names (named_ranges.rs), so they follow row and
+// column changes like any other name. The page-setup text fields mirror them
+// for the frontend and the file formats.
 
 use crate::api_types::{PageSetup, PrintData, CellData, MergedRegion, StyleData};
+use crate::named_ranges::{
+    format_print_area, parse_print_area, parse_title_cols, parse_title_rows, print_area,
+    print_titles, set_print_area_name, set_print_titles_name, NamedRange, PrintRange,
+};
 use crate::{AppState, format_cell_value};
+use std::collections::HashMap;
 use tauri::State;
 use std::fs;
 
+/// The page setup of `sheet`, with its print area and titles read from the
+/// sheet's built-in print names.
+pub(crate) fn sheet_page_setup(state: &AppState, sheet: usize) -> PageSetup {
+    let (area, titles) = {
+        let names = state.named_ranges.lock().unwrap();
+        (print_area(&names, sheet), print_titles(&names, sheet))
+    };
+    let mut setup = state.page_setups.lock().unwrap().get(sheet).cloned().unwrap_or_default();
+    setup.print_area = format_print_area(&area);
+    setup.print_titles_rows = titles
+        .rows
+        .map(|(start, end)| format!("{}:{}", start + 1, end + 1))
+        .unwrap_or_default();
+    setup.print_titles_cols = titles
+        .cols
+        .map(|(start, end)| format!("{}:{}", col_index_to_letter(start), col_index_to_letter(end)))
+        .unwrap_or_default();
+    setup
+}
+
+/// Replace the print names of `sheet` with the print area and titles written
+/// in `setup` (a loaded file or the Page Setup dialog).
+pub(crate) fn adopt_page_setup_print_names(names: &mut HashMap<String, NamedRange>, sheet: usize, setup: &PageSetup) {
+    set_print_area_name(names, sheet, &parse_print_area(&setup.print_area));
+    let mut titles = print_titles(names, sheet);
+    titles.rows = parse_title_rows(&setup.print_titles_rows);
+    titles.cols = parse_title_cols(&setup.print_titles_cols);
+    set_print_titles_name(names, sheet, titles);
+}
+
 /// Get the page setup for the active sheet.
 #[tauri::command]
 pub fn get_page_setup(state: State<AppState>) -> PageSetup {
     let active_sheet = *state.active_sheet.lock().unwrap();
-    let page_setups = state.page_setups.lock().unwrap();
-    page_setups
-        .get(active_sheet)
-        .cloned()
-        .unwrap_or_default()
+    sheet_page_setup(&state, active_sheet)
 }
 
 /// Set the page setup for the active sheet.
@@ -24,6 +59,7 @@ pub fn set_page_setup(
     setup: PageSetup,
 ) -> Result<(), String> {
     let active_sheet = *state.active_sheet.lock().unwrap();
+    adopt_page_setup_print_names(&mut state.named_ranges.lock().unwrap(), active_sheet, &setup);
     let mut page_setups = state.page_setups.lock().unwrap();
 
     // Extend the vector if needed
@@ -40,12 +76,12 @@ pub fn set_page_setup(
 #[tauri::command]
 pub fn get_print_data(state: State<AppState>) -> Result<PrintData, String> {
     let active_sheet = *state.active_sheet.lock().unwrap();
-    let grid = state.grid.lock().unwrap();
+    let page_setup = sheet_page_setup(&state, active_sheet);
+    let grid = state.get_active_grid();
     let styles = state.style_registry.lock().unwrap();
     let merged_regions = state.merged_regions.lock().unwrap();
     let locale = state.locale.lock().unwrap();
     let sheet_names = state.sheet_names.lock().unwrap();
-    let page_setups = state.page_setups.lock().unwrap();
     let col_widths_map = state.column_widths.lock().unwrap();
     let row_heights_map = state.row_heights.lock().unwrap();
 
@@ -54,11 +90,6 @@ pub fn get_print_data(state: State<AppState>) -> Result<PrintData, String> {
         .cloned()
         .unwrap_or_else(|| format!("Sheet{}", active_sheet + 1));
 
-    let page_setup = page_setups
-        .get(active_sheet)
-        .cloned()
-        .unwrap_or_default();
-
     let max_row = grid.max_row;
     let max_col = grid.max_col;
 
@@ -96,6 +127,7 @@ pub fn get_print_data(state: State<AppState>) -> Result<PrintData, String> {
             sheet_index: None,
             rich_text: None,
                 accounting_layout: None,
+                error_detail: None,
         });
     }
 
@@ -234,40 +266,34 @@ pub fn set_print_area(
     end_row: u32,
     end_col: u32,
 ) -> Result<String, String> {
-    if start_row > end_row || start_col > end_col {
-        return Err("Invalid range: start must be <= end".to_string());
-    }
-
-    let range_str = format!(
-        "{}{}:{}{}",
-        col_index_to_letter(start_col),
-        start_row + 1,
-        col_index_to_letter(end_col),
-        end_row + 1,
-    );
+    let active_sheet = *state.active_sheet.lock().unwrap();
+    set_print_areas_internal(&state, active_sheet, &[PrintRange { start_row, start_col, end_row, end_col }])
+}
 
+/// Set a multi-area print area from a multi-rect selection. Returns the
+/// areas as text ("A1:F20,H1:J5").
+#[tauri::command]
+pub fn set_print_areas(state: State<AppState>, ranges: Vec<PrintRange>) -> Result<String, String> {
     let active_sheet = *state.active_sheet.lock().unwrap();
-    let mut page_setups = state.page_setups.lock().unwrap();
+    set_print_areas_internal(&state, active_sheet, &ranges)
+}
 
-    while page_setups.len() <= active_sheet {
-        page_setups.push(PageSetup::default());
+pub(crate) fn set_print_areas_internal(state: &AppState, sheet: usize, ranges: &[PrintRange]) -> Result<String, String> {
+    if ranges.is_empty() {
+        return Err("Select at least one range".to_string());
     }
-
-    page_setups[active_sheet].print_area = range_str.clone();
-    Ok(range_str)
+    if ranges.iter().any(|r| r.start_row > r.end_row || r.start_col > r.end_col) {
+        return Err("Invalid range: start must be <= end".to_string());
+    }
+    set_print_area_name(&mut state.named_ranges.lock().unwrap(), sheet, ranges);
+    Ok(format_print_area(ranges))
 }
 
 /// Clear the print area for the active sheet.
 #[tauri::command]
 pub fn clear_print_area(state: State<AppState>) -> Result<(), String> {
     let active_sheet = *state.active_sheet.lock().unwrap();
-    let mut page_setups = state.page_setups.lock().unwrap();
-
-    while page_setups.len() <= active_sheet {
-        page_setups.push(PageSetup::default());
-    }
-
-    page_setups[active_sheet].print_area = String::new();
+    set_print_area_name(&mut state.named_ranges.lock().unwrap(), active_sheet, &[]);
     Ok(())
 }
 
@@ -283,30 +309,22 @@ pub fn set_print_title_rows(
         return Err("Invalid range: start_row must be <= end_row".to_string());
     }
 
-    let title_str = format!("{}:{}", start_row + 1, end_row + 1);
-
     let active_sheet = *state.active_sheet.lock().unwrap();
-    let mut page_setups = state.page_setups.lock().unwrap();
-
-    while page_setups.len() <= active_sheet {
-        page_setups.push(PageSetup::default());
-    }
-
-    page_setups[active_sheet].print_titles_rows = title_str.clone();
-    Ok(title_str)
+    let mut names = state.named_ranges.lock().unwrap();
+    let mut titles = print_titles(&names, active_sheet);
+    titles.rows = Some((start_row, end_row));
+    set_print_titles_name(&mut names, active_sheet, titles);
+    Ok(format!("{}:{}", start_row + 1, end_row + 1))
 }
 
 /// Clear print title rows for the active sheet.
 #[tauri::command]
 pub fn clear_print_title_rows(state: State<AppState>) -> Result<(), String> {
     let active_sheet = *state.active_sheet.lock().unwrap();
-    let mut page_setups = state.page_setups.lock().unwrap();
-
-    while page_setups.len() <= active_sheet {
-        page_setups.push(PageSetup::default());
-    }
-
-    page_setups[active_sheet].print_titles_rows = String::new();
+    let mut names = state.named_ranges.lock().unwrap();
+    let mut titles = print_titles(&names, active_sheet);
+    titles.rows = None;
+    set_print_titles_name(&mut names, active_sheet, titles);
     Ok(())
 }
 
@@ -322,34 +340,22 @@ pub fn set_print_title_cols(
         return Err("Invalid range: start_col must be <= end_col".to_string());
     }
 
-    let title_str = format!(
-        "{}:{}",
-        col_index_to_letter(start_col),
-        col_index_to_letter(end_col),
-    );
-
     let active_sheet = *state.active_sheet.lock().unwrap();
-    let mut page_setups = state.page_setups.lock().unwrap();
-
-    while page_setups.len() <= active_sheet {
-        page_setups.push(PageSetup::default());
-    }
-
-    page_setups[active_sheet].print_titles_cols = title_str.clone();
-    Ok(title_str)
+    let mut names = state.named_ranges.lock().unwrap();
+    let mut titles = print_titles(&names, active_sheet);
+    titles.cols = Some((start_col, end_col));
+    set_print_titles_name(&mut names, active_sheet, titles);
+    Ok(format!("{}:{}", col_index_to_letter(start_col), col_index_to_letter(end_col)))
 }
 
 /// Clear print title columns for the active sheet.
 #[tauri::command]
 pub fn clear_print_title_cols(state: State<AppState>) -> Result<(), String> {
     let active_sheet = *state.active_sheet.lock().unwrap();
-    let mut page_setups = state.page_setups.lock().unwrap();
-
-    while page_setups.len() <= active_sheet {
-        page_setups.push(PageSetup::default());
-    }
-
-    page_setups[active_sheet].print_titles_cols = String::new();
+    let mut names = state.named_ranges.lock().unwrap();
+    let mut titles = print_titles(&names, active_sheet);
+    titles.cols = None;
+    set_print_titles_name(&mut names, active_sheet, titles);
     Ok(())
 }
 
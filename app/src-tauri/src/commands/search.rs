@@ -34,7 +34,7 @@ pub fn find_all(
     match_entire_cell: bool,
     search_formulas: bool,
 ) -> FindResult {
-    let grid = state.grid.lock().unwrap();
+    let grid = state.get_active_grid();
     let matches = grid.find_all(&query, case_sensitive, match_entire_cell, search_formulas);
     let total_count = matches.len();
     FindResult { matches, total_count }
@@ -49,7 +49,7 @@ pub fn count_matches(
     match_entire_cell: bool,
     search_formulas: bool,
 ) -> usize {
-    let grid = state.grid.lock().unwrap();
+    let grid = state.get_active_grid();
     grid.count_matches(&query, case_sensitive, match_entire_cell, search_formulas)
 }
 
@@ -64,8 +64,7 @@ pub fn replace_all(
     case_sensitive: bool,
     match_entire_cell: bool,
 ) -> Result<ReplaceResult, String> {
-    let mut grid = state.grid.lock().unwrap();
-    let mut grids = state.grids.lock().unwrap();
+    let mut grid = state.get_active_grid();
     let active_sheet = *state.active_sheet.lock().unwrap();
     let styles = state.style_registry.lock().unwrap();
     let mut undo_stack = state.undo_stack.lock().unwrap();
@@ -189,9 +188,6 @@ pub fn replace_all(
                 
                 // Update grid
                 grid.set_cell(row, col, new_cell.clone());
-                if active_sheet < grids.len() {
-                    grids[active_sheet].set_cell(row, col, new_cell.clone());
-                }
 
                 // Get display value for frontend
                 let style = styles.get(new_cell.style_index);
@@ -217,6 +213,7 @@ pub fn replace_all(
                     sheet_index: None,
                     rich_text: None,
                     accounting_layout: None,
+                    error_detail: None,
                 });
 
                 replacement_count += 1;
@@ -265,8 +262,7 @@ pub fn replace_single(
     replacement: String,
     case_sensitive: bool,
 ) -> Result<Option<CellData>, String> {
-    let mut grid = state.grid.lock().unwrap();
-    let mut grids = state.grids.lock().unwrap();
+    let mut grid = state.get_active_grid();
     let active_sheet = *state.active_sheet.lock().unwrap();
     let styles = state.style_registry.lock().unwrap();
     let mut undo_stack = state.undo_stack.lock().unwrap();
@@ -341,9 +337,6 @@ pub fn replace_single(
             
             // Update grid
             grid.set_cell(row, col, new_cell.clone());
-            if active_sheet < grids.len() {
-                grids[active_sheet].set_cell(row, col, new_cell.clone());
-            }
 
             let style = styles.get(new_cell.style_index);
             let display = format_cell_value(&new_cell.value, style, &locale);
@@ -368,6 +361,7 @@ pub fn replace_single(
                 sheet_index: None,
                 rich_text: None,
                 accounting_layout: None,
+                error_detail: None,
             }));
         }
     }
@@ -1,31 +1,30 @@
 //! FILENAME: app/src-tauri/src/commands/structure.rs
 // PURPOSE: Complex logic for inserting and deleting rows/columns and updating references.
 
-use crate::api_types::CellData;
+use crate::api_types::{CellData, DeleteLinesResult, RefErrorCell};
 use crate::commands::utils::get_cell_internal_with_merge;
 use crate::AppState;
-use crate::persistence::FileState;
+use crate::persistence::{FileState, UserFilesState};
 use crate::pivot::types::PivotState;
-use engine::{Cell, GridSnapshot, UndoMergeRegion};
+use crate::structural_change::{
+    shift_formula_refs, shift_sheet_formula_refs, ApplyStructuralChange, Axis, StructuralChange,
+};
+use engine::{Cell, Grid, GridSnapshot, UndoMergeRegion, UndoStack};
 use once_cell::sync::Lazy;
 
 use regex::Regex;
 use std::collections::HashMap;
 use tauri::State;
 
-// Pre-compiled regexes for formula reference shifting (avoids ~2.6ms per Regex::new call)
+// Pre-compiled regexes for drag-move reference relocation (avoids ~2.6ms per Regex::new call)
 static CELL_REF_RE: Lazy<Regex> =
     Lazy::new(|| Regex::new(r"(\$?)([A-Za-z]+)(\$?)(\d+)").unwrap());
-static ROW_RANGE_RE: Lazy<Regex> =
-    Lazy::new(|| Regex::new(r"(\$?)(\d+):(\$?)(\d+)").unwrap());
-static COL_RANGE_RE: Lazy<Regex> =
-    Lazy::new(|| Regex::new(r"(\$?)([A-Za-z]+):(\$?)([A-Za-z]+)").unwrap());
 static CELL_RANGE_RE: Lazy<Regex> =
     Lazy::new(|| Regex::new(r"(\$?)([A-Za-z]+)(\$?)(\d+):(\$?)([A-Za-z]+)(\$?)(\d+)").unwrap());
 
 /// Capture a snapshot of the current grid state for undo.
 fn capture_grid_snapshot(state: &AppState) -> GridSnapshot {
-    let grid = state.grid.lock().unwrap();
+    let grid = state.get_active_grid();
     let row_heights = state.row_heights.lock().unwrap();
     let column_widths = state.column_widths.lock().unwrap();
     let merged_regions = state.merged_regions.lock().unwrap();
@@ -48,473 +47,6 @@ fn capture_grid_snapshot(state: &AppState) -> GridSnapshot {
     }
 }
 
-/// ============================================================================
-// PROTECTED REGION SHIFT HELPERS
-// ============================================================================
-
-/// Realign report definitions with their (already coordinate-shifted) protected
-/// regions and drop definitions whose region was removed, then persist. A report
-/// definition mirrors its region exactly (anchor = region start, bounds = region
-/// end), so after a generic region shift this brings the definition store — the
-/// source of truth for the NEXT refresh's destination — back in sync. Without it
-/// a refresh would re-materialize the report at its pre-shift coordinates.
-fn sync_report_definitions_to_regions(state: &AppState) {
-    let report_regions: Vec<_> = {
-        let regions = state.protected_regions.lock().unwrap();
-        regions
-            .iter()
-            .filter(|r| r.region_type == "report")
-            .map(|r| (r.owner_id, r.sheet_index, r.start_row, r.start_col, r.end_row, r.end_col))
-            .collect()
-    };
-    {
-        let mut defs = state.report_definitions.lock().unwrap();
-        defs.retain(|d| report_regions.iter().any(|(id, ..)| *id == d.id));
-        for d in defs.iter_mut() {
-            if let Some((_, sheet, sr, sc, er, ec)) =
-                report_regions.iter().find(|(id, ..)| *id == d.id)
-            {
-                d.sheet_index = *sheet;
-                d.anchor_row = *sr;
-                d.anchor_col = *sc;
-                d.end_row = *er;
-                d.end_col = *ec;
-            }
-        }
-    }
-    crate::report::sync_reports_to_extension_data(state);
-}
-
-/// Shift protected regions when rows are inserted.
-/// Coordinate shifts apply to ALL regions; pivot definition updates apply only to pivot regions.
-fn shift_pivot_regions_for_row_insert(state: &AppState, pivot_state: &PivotState, from_row: u32, count: u32, sheet_index: usize) {
-    let mut regions = state.protected_regions.lock().unwrap();
-    let mut pivot_tables = pivot_state.pivot_tables.lock().unwrap();
-
-    for region in regions.iter_mut() {
-        if region.sheet_index != sheet_index {
-            continue;
-        }
-
-        // Shift region coordinates if at or below insertion point (generic for all regions)
-        if region.start_row >= from_row {
-            region.start_row += count;
-            region.end_row += count;
-        } else if region.end_row >= from_row {
-            // Region spans the insertion point - expand it
-            region.end_row += count;
-        }
-
-        // Pivot-specific: also update the pivot definition's destination
-        if region.region_type == "pivot" {
-            let pid = region.owner_id;
-            if let Some((definition, _)) = pivot_tables.get_mut(&pid) {
-                let (dest_row, dest_col) = definition.destination;
-                if dest_row >= from_row {
-                    definition.destination = (dest_row + count, dest_col);
-                }
-
-                let (src_start_row, src_start_col) = definition.source_start;
-                let (src_end_row, src_end_col) = definition.source_end;
-
-                if src_start_row >= from_row {
-                    definition.source_start = (src_start_row + count, src_start_col);
-                }
-                if src_end_row >= from_row {
-                    definition.source_end = (src_end_row + count, src_end_col);
-                } else if src_end_row >= from_row {
-                    definition.source_end = (src_end_row + count, src_end_col);
-                }
-            }
-        }
-    }
-
-    // Report-specific: realign report definitions with their shifted regions.
-    drop(pivot_tables);
-    drop(regions);
-    sync_report_definitions_to_regions(state);
-}
-
-/// Shift protected regions when columns are inserted.
-fn shift_pivot_regions_for_col_insert(state: &AppState, pivot_state: &PivotState, from_col: u32, count: u32, sheet_index: usize) {
-    let mut regions = state.protected_regions.lock().unwrap();
-    let mut pivot_tables = pivot_state.pivot_tables.lock().unwrap();
-
-    for region in regions.iter_mut() {
-        if region.sheet_index != sheet_index {
-            continue;
-        }
-
-        // Shift region coordinates (generic for all regions)
-        if region.start_col >= from_col {
-            region.start_col += count;
-            region.end_col += count;
-        } else if region.end_col >= from_col {
-            region.end_col += count;
-        }
-
-        // Pivot-specific: update the pivot definition's destination
-        if region.region_type == "pivot" {
-            let pid = region.owner_id;
-            if let Some((definition, _)) = pivot_tables.get_mut(&pid) {
-                let (dest_row, dest_col) = definition.destination;
-                if dest_col >= from_col {
-                    definition.destination = (dest_row, dest_col + count);
-                }
-
-                let (src_start_row, src_start_col) = definition.source_start;
-                let (src_end_row, src_end_col) = definition.source_end;
-
-                if src_start_col >= from_col {
-                    definition.source_start = (src_start_row, src_start_col + count);
-                }
-                if src_end_col >= from_col {
-                    definition.source_end = (src_end_row, src_end_col + count);
-                } else if src_end_col >= from_col {
-                    definition.source_end = (src_end_row, src_end_col + count);
-                }
-            }
-        }
-    }
-
-    // Report-specific: realign report definitions with their shifted regions.
-    drop(pivot_tables);
-    drop(regions);
-    sync_report_definitions_to_regions(state);
-}
-
-/// Shift protected regions when rows are deleted.
-fn shift_pivot_regions_for_row_delete(state: &AppState, pivot_state: &PivotState, from_row: u32, count: u32, sheet_index: usize) {
-    let mut regions = state.protected_regions.lock().unwrap();
-    let mut pivot_tables = pivot_state.pivot_tables.lock().unwrap();
-
-    // Collect IDs of regions fully within the deleted range
-    let mut regions_to_remove: Vec<String> = Vec::new();
-
-    for region in regions.iter_mut() {
-        if region.sheet_index != sheet_index {
-            continue;
-        }
-
-        let delete_end = from_row + count;
-
-        // Check if region is fully within deleted range
-        if region.start_row >= from_row && region.end_row < delete_end {
-            regions_to_remove.push(region.id.clone());
-            continue;
-        }
-
-        // Shift region coordinates (generic for all regions)
-        if region.start_row >= delete_end {
-            region.start_row -= count;
-            region.end_row -= count;
-        } else if region.start_row >= from_row {
-            region.start_row = from_row;
-            region.end_row -= count;
-        } else if region.end_row >= delete_end {
-            region.end_row -= count;
-        } else if region.end_row >= from_row {
-            region.end_row = from_row.saturating_sub(1);
-        }
-
-        // Pivot-specific: update definition
-        if region.region_type == "pivot" {
-            let pid = region.owner_id;
-            if let Some((definition, _)) = pivot_tables.get_mut(&pid) {
-                let (dest_row, dest_col) = definition.destination;
-                if dest_row >= delete_end {
-                    definition.destination = (dest_row - count, dest_col);
-                } else if dest_row >= from_row {
-                    definition.destination = (from_row, dest_col);
-                }
-
-                let (src_start_row, src_start_col) = definition.source_start;
-                let (src_end_row, src_end_col) = definition.source_end;
-
-                let new_start_row = if src_start_row >= delete_end {
-                    src_start_row - count
-                } else if src_start_row >= from_row {
-                    from_row
-                } else {
-                    src_start_row
-                };
-
-                let new_end_row = if src_end_row >= delete_end {
-                    src_end_row - count
-                } else if src_end_row >= from_row {
-                    from_row.saturating_sub(1).max(new_start_row)
-                } else {
-                    src_end_row
-                };
-
-                definition.source_start = (new_start_row, src_start_col);
-                definition.source_end = (new_end_row, src_end_col);
-            }
-        }
-    }
-
-    // Remove fully deleted regions and their associated pivot data
-    for region_id in &regions_to_remove {
-        if let Some(region) = regions.iter().find(|r| &r.id == region_id) {
-            if region.region_type == "pivot" {
-                let pid = region.owner_id;
-                pivot_tables.remove(&pid);
-            }
-        }
-    }
-    regions.retain(|r| !regions_to_remove.contains(&r.id));
-
-    // Report-specific: realign report definitions with their shifted regions
-    // (definitions whose region was fully deleted are dropped).
-    drop(pivot_tables);
-    drop(regions);
-    sync_report_definitions_to_regions(state);
-}
-
-/// ============================================================================
-// TABLE BOUNDARY SHIFT HELPERS
-// ============================================================================
-
-/// Shift table boundaries when rows are inserted.
-/// Tables entirely below the insertion point are shifted down.
-/// Tables spanning the insertion point (including at start_row) expand.
-fn shift_table_boundaries_for_row_insert(state: &AppState, from_row: u32, count: u32, sheet_index: usize) {
-    let mut tables = state.tables.lock().unwrap();
-
-    if let Some(sheet_tables) = tables.get_mut(&sheet_index) {
-        for table in sheet_tables.values_mut() {
-            if table.start_row > from_row {
-                // Insertion is strictly before the table - shift entire table down
-                table.start_row += count;
-                table.end_row += count;
-            } else if table.end_row >= from_row {
-                // Insertion is inside the table (including at start_row) - expand
-                table.end_row += count;
-            }
-        }
-    }
-}
-
-/// Shift table boundaries when columns are inserted.
-/// Tables entirely to the right of the insertion point are shifted right.
-/// Tables spanning the insertion point (including at start_col) expand.
-fn shift_table_boundaries_for_col_insert(state: &AppState, from_col: u32, count: u32, sheet_index: usize) {
-    let mut tables = state.tables.lock().unwrap();
-
-    if let Some(sheet_tables) = tables.get_mut(&sheet_index) {
-        for table in sheet_tables.values_mut() {
-            if table.start_col > from_col {
-                // Insertion is strictly before the table - shift entire table right
-                table.start_col += count;
-                table.end_col += count;
-            } else if table.end_col >= from_col {
-                // Insertion is inside the table (including at start_col) - expand
-                table.end_col += count;
-            }
-        }
-    }
-}
-
-/// Shift table boundaries when rows are deleted.
-/// Tables fully within the deleted range are removed.
-fn shift_table_boundaries_for_row_delete(state: &AppState, from_row: u32, count: u32, sheet_index: usize) {
-    let mut tables = state.tables.lock().unwrap();
-    let mut table_names = state.table_names.lock().unwrap();
-
-    let delete_end = from_row + count;
-
-    if let Some(sheet_tables) = tables.get_mut(&sheet_index) {
-        // Collect IDs of tables to remove (fully within deleted range)
-        let to_remove: Vec<identity::EntityId> = sheet_tables
-            .values()
-            .filter(|t| t.start_row >= from_row && t.end_row < delete_end)
-            .map(|t| t.id)
-            .collect();
-
-        // Remove from name registry
-        for id in &to_remove {
-            if let Some(table) = sheet_tables.get(id) {
-                table_names.remove(&table.name.to_uppercase());
-            }
-        }
-
-        // Remove fully deleted tables
-        for id in &to_remove {
-            sheet_tables.remove(id);
-        }
-
-        // Shift remaining table boundaries
-        for table in sheet_tables.values_mut() {
-            if table.start_row >= delete_end {
-                // Entire table is below deleted range - shift up
-                table.start_row -= count;
-                table.end_row -= count;
-            } else if table.start_row >= from_row {
-                // Table starts within deleted range but extends beyond - shrink from top
-                table.start_row = from_row;
-                table.end_row -= count;
-            } else if table.end_row >= delete_end {
-                // Table spans entire deleted range - shrink
-                table.end_row -= count;
-            } else if table.end_row >= from_row {
-                // Table end is within deleted range - shrink from bottom
-                table.end_row = from_row.saturating_sub(1);
-            }
-        }
-    }
-}
-
-/// Shift table boundaries when columns are deleted.
-/// Tables fully within the deleted range are removed.
-fn shift_table_boundaries_for_col_delete(state: &AppState, from_col: u32, count: u32, sheet_index: usize) {
-    let mut tables = state.tables.lock().unwrap();
-    let mut table_names = state.table_names.lock().unwrap();
-
-    let delete_end = from_col + count;
-
-    if let Some(sheet_tables) = tables.get_mut(&sheet_index) {
-        // Collect IDs of tables to remove (fully within deleted range)
-        let to_remove: Vec<identity::EntityId> = sheet_tables
-            .values()
-            .filter(|t| t.start_col >= from_col && t.end_col < delete_end)
-            .map(|t| t.id)
-            .collect();
-
-        // Remove from name registry
-        for id in &to_remove {
-            if let Some(table) = sheet_tables.get(id) {
-                table_names.remove(&table.name.to_uppercase());
-            }
-        }
-
-        // Remove fully deleted tables
-        for id in &to_remove {
-            sheet_tables.remove(id);
-        }
-
-        // Shift remaining table boundaries and truncate columns
-        for table in sheet_tables.values_mut() {
-            if table.start_col >= delete_end {
-                // Entire table is right of deleted range - shift left
-                table.start_col -= count;
-                table.end_col -= count;
-            } else if table.start_col >= from_col {
-                // Table starts within deleted range but extends beyond - shrink from left
-                let cols_removed = (delete_end - table.start_col) as usize;
-                // Remove columns from the beginning
-                for _ in 0..cols_removed.min(table.columns.len()) {
-                    table.columns.remove(0);
-                }
-                table.start_col = from_col;
-                table.end_col -= count;
-            } else if table.end_col >= delete_end {
-                // Table spans entire deleted range - shrink and remove middle columns
-                let first_col_idx = (from_col - table.start_col) as usize;
-                let cols_to_remove = count as usize;
-                for _ in 0..cols_to_remove.min(table.columns.len().saturating_sub(first_col_idx)) {
-                    if first_col_idx < table.columns.len() {
-                        table.columns.remove(first_col_idx);
-                    }
-                }
-                table.end_col -= count;
-            } else if table.end_col >= from_col {
-                // Table end is within deleted range - shrink from right
-                let cols_to_remove = (table.end_col - from_col + 1) as usize;
-                let keep_count = table.columns.len().saturating_sub(cols_to_remove);
-                table.columns.truncate(keep_count);
-                table.end_col = from_col.saturating_sub(1);
-            }
-        }
-    }
-}
-
-/// Shift protected regions when columns are deleted.
-fn shift_pivot_regions_for_col_delete(state: &AppState, pivot_state: &PivotState, from_col: u32, count: u32, sheet_index: usize) {
-    let mut regions = state.protected_regions.lock().unwrap();
-    let mut pivot_tables = pivot_state.pivot_tables.lock().unwrap();
-
-    let mut regions_to_remove: Vec<String> = Vec::new();
-
-    for region in regions.iter_mut() {
-        if region.sheet_index != sheet_index {
-            continue;
-        }
-
-        let delete_end = from_col + count;
-
-        // Check if region is fully within deleted range
-        if region.start_col >= from_col && region.end_col < delete_end {
-            regions_to_remove.push(region.id.clone());
-            continue;
-        }
-
-        // Shift region coordinates (generic for all regions)
-        if region.start_col >= delete_end {
-            region.start_col -= count;
-            region.end_col -= count;
-        } else if region.start_col >= from_col {
-            region.start_col = from_col;
-            region.end_col -= count;
-        } else if region.end_col >= delete_end {
-            region.end_col -= count;
-        } else if region.end_col >= from_col {
-            region.end_col = from_col.saturating_sub(1);
-        }
-
-        // Pivot-specific: update definition
-        if region.region_type == "pivot" {
-            let pid = region.owner_id;
-            if let Some((definition, _)) = pivot_tables.get_mut(&pid) {
-                let (dest_row, dest_col) = definition.destination;
-                if dest_col >= delete_end {
-                    definition.destination = (dest_row, dest_col - count);
-                } else if dest_col >= from_col {
-                    definition.destination = (dest_row, from_col);
-                }
-
-                let (src_start_row, src_start_col) = definition.source_start;
-                let (src_end_row, src_end_col) = definition.source_end;
-
-                let new_start_col = if src_start_col >= delete_end {
-                    src_start_col - count
-                } else if src_start_col >= from_col {
-                    from_col
-                } else {
-                    src_start_col
-                };
-
-                let new_end_col = if src_end_col >= delete_end {
-                    src_end_col - count
-                } else if src_end_col >= from_col {
-                    from_col.saturating_sub(1).max(new_start_col)
-                } else {
-                    src_end_col
-                };
-
-                definition.source_start = (src_start_row, new_start_col);
-                definition.source_end = (src_end_row, new_end_col);
-            }
-        }
-    }
-
-    // Remove fully deleted regions and their associated pivot data
-    for region_id in &regions_to_remove {
-        if let Some(region) = regions.iter().find(|r| &r.id == region_id) {
-            if region.region_type == "pivot" {
-                let pid = region.owner_id;
-                pivot_tables.remove(&pid);
-            }
-        }
-    }
-    regions.retain(|r| !regions_to_remove.contains(&r.id));
-
-    // Report-specific: realign report definitions with their shifted regions
-    // (definitions whose region was fully deleted are dropped).
-    drop(pivot_tables);
-    drop(regions);
-    sync_report_definitions_to_regions(state);
-}
-
 // ============================================================================
 // ROW/COLUMN INSERTION WITH DEPENDENCY MAP UPDATES
 // ============================================================================
@@ -622,16 +154,58 @@ pub fn insert_rows(
     row: u32,
     count: u32,
 ) -> Result<Vec<CellData>, String> {
+    insert_rows_internal(&state, &pivot_state, row, count)?;
+
+    // Re-acquire locks for result building
+    let grid = state.get_active_grid();
+    let styles = state.style_registry.lock().map_err(|e| e.to_string())?;
+    let merged_regions = state.merged_regions.lock().map_err(|e| e.to_string())?;
+    let locale = state.locale.lock().map_err(|e| e.to_string())?;
+
+    // Return updated cells with merge info
+    let mut result: Vec<CellData> = Vec::new();
+    for r in 0..=grid.max_row {
+        for c in 0..=grid.max_col {
+            if let Some(cell_data) = get_cell_internal_with_merge(&grid, &styles, &merged_regions, r, c, &locale) {
+                result.push(cell_data);
+            }
+        }
+    }
+
+    // Mark workbook as dirty
+    if let Ok(mut modified) = file_state.is_modified.lock() { *modified = true; }
+
+    Ok(result)
+}
+
+/// Shifts rows at and below `row` down by `count` on the active sheet,
+/// with every feature store following; records one undo transaction.
+/// Callers build their own cell results.
+pub(crate) fn insert_rows_internal(
+    state: &AppState,
+    pivot_state: &PivotState,
+    row: u32,
+    count: u32,
+) -> Result<(), String> {
     // Capture snapshot BEFORE acquiring other locks (helper acquires its own locks)
-    let snapshot = capture_grid_snapshot(&state);
+    let snapshot = capture_grid_snapshot(state);
+
+    // Every feature store with coordinates of its own follows the shift;
+    // feature stores are locked (and released) before the grid locks below.
+    let change = {
+        let active_sheet = *state.active_sheet.lock().map_err(|e| e.to_string())?;
+        let sheet_name = state.sheet_names.lock().map_err(|e| e.to_string())?
+            .get(active_sheet).cloned().unwrap_or_default();
+        StructuralChange::insert(active_sheet, &sheet_name, Axis::Rows, row, count)
+    };
+    let features_snapshot = crate::structural_change::dispatch_structural_change(state, pivot_state, &change);
 
-    let mut grid = state.grid.lock().map_err(|e| e.to_string())?;
     let mut grids = state.grids.lock().map_err(|e| e.to_string())?;
     let styles = state.style_registry.lock().map_err(|e| e.to_string())?;
     let mut row_heights = state.row_heights.lock().map_err(|e| e.to_string())?;
     let active_sheet = *state.active_sheet.lock().map_err(|e| e.to_string())?;
     let mut undo_stack = state.undo_stack.lock().map_err(|e| e.to_string())?;
-    let merged_regions = state.merged_regions.lock().map_err(|e| e.to_string())?;
+    let mut merged_regions = state.merged_regions.lock().map_err(|e| e.to_string())?;
 
     // Lock all dependency maps
     let mut dependents_map = state.dependents.lock().map_err(|e| e.to_string())?;
@@ -644,6 +218,11 @@ pub fn insert_rows(
     // Record snapshot for undo
     undo_stack.begin_transaction(format!("Insert {} row(s)", count));
     undo_stack.record_snapshot(snapshot);
+    undo_stack.record_custom_restore(
+        "obj_structural_features".to_string(),
+        features_snapshot,
+        "Shift sheet features",
+    );
     // Cell-type assignments move with their rows; their pre-shift state is
     // recorded in the SAME transaction so one undo restores grid + assignments
     // atomically.
@@ -671,26 +250,28 @@ pub fn insert_rows(
         }
     }
     undo_stack.commit_transaction();
+    // Merges are part of the grid snapshot recorded above.
+    merged_regions.apply_structural_change(&change);
 
     // First, update formula references in ALL cells that reference rows at or after the insertion point
-    let all_cells: Vec<((u32, u32), Cell)> = grid.cells.iter()
+    let all_cells: Vec<((u32, u32), Cell)> = grids[active_sheet].cells.iter()
         .map(|(&pos, cell)| (pos, cell.clone()))
         .collect();
     
     for ((r, c), cell) in &all_cells {
-        if let Some(formula) = cell.formula_string() {
-            let updated_formula = shift_formula_row_references(&formula, row, count as i32);
-            if updated_formula != formula {
+        if let Some(ast) = cell.ast.as_deref() {
+            let mut ast = ast.clone();
+            if shift_formula_refs(&mut ast, &change) {
                 let mut updated_cell = cell.clone();
-                updated_cell.ast = parser::parse(&updated_formula).ok().map(Box::new);
-                grid.cells.insert((*r, *c), updated_cell);
+                updated_cell.ast = Some(Box::new(ast));
+                grids[active_sheet].cells.insert((*r, *c), updated_cell);
             }
         }
     }
 
     // Collect all cells that need to be moved (from row onwards)
     let mut cells_to_move: Vec<((u32, u32), Cell)> = Vec::new();
-    for (&(r, c), cell) in grid.cells.iter() {
+    for (&(r, c), cell) in grids[active_sheet].cells.iter() {
         if r >= row {
             cells_to_move.push(((r, c), cell.clone()));
         }
@@ -701,8 +282,8 @@ pub fn insert_rows(
     
     // Remove old cells and insert at new positions
     for ((r, c), cell) in cells_to_move {
-        grid.cells.remove(&(r, c));
-        grid.cells.insert((r + count, c), cell);
+        grids[active_sheet].cells.remove(&(r, c));
+        grids[active_sheet].cells.insert((r + count, c), cell);
     }
     
     // Update row heights
@@ -749,16 +330,12 @@ pub fn insert_rows(
     shift_row_dependencies_map(&mut row_dependencies_map, row, count);
     
     // Recalculate grid bounds
-    grid.recalculate_bounds();
+    grids[active_sheet].recalculate_bounds();
+    // Cells past the affected lines moved; viewports repaint in full.
+    grids[active_sheet].mark_all_changed();
     
-    // Sync grids vector
-    if active_sheet < grids.len() {
-        grids[active_sheet].cells = grid.cells.clone();
-        grids[active_sheet].max_row = grid.max_row;
-        grids[active_sheet].max_col = grid.max_col;
-    }
     
-    // Drop locks before calling pivot region shift (which needs its own locks)
+    // Drop locks before re-acquiring for result building
     drop(dependents_map);
     drop(dependencies_map);
     drop(column_dependents_map);
@@ -770,16 +347,34 @@ pub fn insert_rows(
     drop(merged_regions);
     drop(styles);
     drop(grids);
-    drop(grid);
-    
-    // === UPDATE PIVOT REGIONS ===
-    shift_pivot_regions_for_row_insert(&state, &pivot_state, row, count, active_sheet);
 
-    // === UPDATE TABLE BOUNDARIES ===
-    shift_table_boundaries_for_row_insert(&state, row, count, active_sheet);
+    // Update IdRegistry for the structural shift
+    {
+        let active = *state.active_sheet.lock().map_err(|e| e.to_string())?;
+        let sheet_ids = state.sheet_ids.lock().map_err(|e| e.to_string())?;
+        if let Some(&sid) = sheet_ids.get(active) {
+            let mut id_reg = state.id_registry.lock().map_err(|e| e.to_string())?;
+            id_reg.shift_rows_down(sid, row, count);
+        }
+    }
+
+    Ok(())
+}
+
+/// Insert columns at the specified position, shifting existing columns right.
+/// Uses snapshot-based undo to restore the full grid state on undo.
+#[tauri::command]
+pub fn insert_columns(
+    state: State<AppState>,
+    file_state: State<FileState>,
+    pivot_state: State<'_, PivotState>,
+    col: u32,
+    count: u32,
+) -> Result<Vec<CellData>, String> {
+    insert_columns_internal(&state, &pivot_state, col, count)?;
 
     // Re-acquire locks for result building
-    let grid = state.grid.lock().map_err(|e| e.to_string())?;
+    let grid = state.get_active_grid();
     let styles = state.style_registry.lock().map_err(|e| e.to_string())?;
     let merged_regions = state.merged_regions.lock().map_err(|e| e.to_string())?;
     let locale = state.locale.lock().map_err(|e| e.to_string())?;
@@ -793,16 +388,6 @@ pub fn insert_rows(
             }
         }
     }
-    
-    // Update IdRegistry for the structural shift
-    {
-        let active = *state.active_sheet.lock().map_err(|e| e.to_string())?;
-        let sheet_ids = state.sheet_ids.lock().map_err(|e| e.to_string())?;
-        if let Some(&sid) = sheet_ids.get(active) {
-            let mut id_reg = state.id_registry.lock().map_err(|e| e.to_string())?;
-            id_reg.shift_rows_down(sid, row, count);
-        }
-    }
 
     // Mark workbook as dirty
     if let Ok(mut modified) = file_state.is_modified.lock() { *modified = true; }
@@ -810,26 +395,34 @@ pub fn insert_rows(
     Ok(result)
 }
 
-/// Insert columns at the specified position, shifting existing columns right.
-/// Uses snapshot-based undo to restore the full grid state on undo.
-#[tauri::command]
-pub fn insert_columns(
-    state: State<AppState>,
-    file_state: State<FileState>,
-    pivot_state: State<'_, PivotState>,
+/// Shifts columns at and right of `col` right by `count` on the active
+/// sheet, with every feature store following; records one undo transaction.
+/// Callers build their own cell results.
+pub(crate) fn insert_columns_internal(
+    state: &AppState,
+    pivot_state: &PivotState,
     col: u32,
     count: u32,
-) -> Result<Vec<CellData>, String> {
+) -> Result<(), String> {
     // Capture snapshot BEFORE acquiring other locks
-    let snapshot = capture_grid_snapshot(&state);
+    let snapshot = capture_grid_snapshot(state);
+
+    // Every feature store with coordinates of its own follows the shift;
+    // feature stores are locked (and released) before the grid locks below.
+    let change = {
+        let active_sheet = *state.active_sheet.lock().map_err(|e| e.to_string())?;
+        let sheet_name = state.sheet_names.lock().map_err(|e| e.to_string())?
+            .get(active_sheet).cloned().unwrap_or_default();
+        StructuralChange::insert(active_sheet, &sheet_name, Axis::Columns, col, count)
+    };
+    let features_snapshot = crate::structural_change::dispatch_structural_change(state, pivot_state, &change);
 
-    let mut grid = state.grid.lock().map_err(|e| e.to_string())?;
     let mut grids = state.grids.lock().map_err(|e| e.to_string())?;
     let styles = state.style_registry.lock().map_err(|e| e.to_string())?;
     let mut column_widths = state.column_widths.lock().map_err(|e| e.to_string())?;
     let active_sheet = *state.active_sheet.lock().map_err(|e| e.to_string())?;
     let mut undo_stack = state.undo_stack.lock().map_err(|e| e.to_string())?;
-    let merged_regions = state.merged_regions.lock().map_err(|e| e.to_string())?;
+    let mut merged_regions = state.merged_regions.lock().map_err(|e| e.to_string())?;
 
     // Lock all dependency maps
     let mut dependents_map = state.dependents.lock().map_err(|e| e.to_string())?;
@@ -842,6 +435,11 @@ pub fn insert_columns(
     // Record snapshot for undo
     undo_stack.begin_transaction(format!("Insert {} column(s)", count));
     undo_stack.record_snapshot(snapshot);
+    undo_stack.record_custom_restore(
+        "obj_structural_features".to_string(),
+        features_snapshot,
+        "Shift sheet features",
+    );
     // Cell-type assignments move with their columns (same transaction; see insert_rows).
     {
         let mut cell_types = state.cell_types.lock().map_err(|e| e.to_string())?;
@@ -866,26 +464,28 @@ pub fn insert_columns(
         }
     }
     undo_stack.commit_transaction();
+    // Merges are part of the grid snapshot recorded above.
+    merged_regions.apply_structural_change(&change);
     
     // First, update formula references in ALL cells
-    let all_cells: Vec<((u32, u32), Cell)> = grid.cells.iter()
+    let all_cells: Vec<((u32, u32), Cell)> = grids[active_sheet].cells.iter()
         .map(|(&pos, cell)| (pos, cell.clone()))
         .collect();
     
     for ((r, c), cell) in &all_cells {
-        if let Some(formula) = cell.formula_string() {
-            let updated_formula = shift_formula_col_references(&formula, col, count as i32);
-            if updated_formula != formula {
+        if let Some(ast) = cell.ast.as_deref() {
+            let mut ast = ast.clone();
+            if shift_formula_refs(&mut ast, &change) {
                 let mut updated_cell = cell.clone();
-                updated_cell.ast = parser::parse(&updated_formula).ok().map(Box::new);
-                grid.cells.insert((*r, *c), updated_cell);
+                updated_cell.ast = Some(Box::new(ast));
+                grids[active_sheet].cells.insert((*r, *c), updated_cell);
             }
         }
     }
 
     // Collect all cells that need to be moved (from col onwards)
     let mut cells_to_move: Vec<((u32, u32), Cell)> = Vec::new();
-    for (&(r, c), cell) in grid.cells.iter() {
+    for (&(r, c), cell) in grids[active_sheet].cells.iter() {
         if c >= col {
             cells_to_move.push(((r, c), cell.clone()));
         }
@@ -896,8 +496,8 @@ pub fn insert_columns(
     
     // Remove old cells and insert at new positions
     for ((r, c), cell) in cells_to_move {
-        grid.cells.remove(&(r, c));
-        grid.cells.insert((r, c + count), cell);
+        grids[active_sheet].cells.remove(&(r, c));
+        grids[active_sheet].cells.insert((r, c + count), cell);
     }
     
     // Update column widths
@@ -944,16 +544,12 @@ pub fn insert_columns(
     shift_cell_positions_for_col_insert(&mut row_dependencies_map, col, count);
     
     // Recalculate grid bounds
-    grid.recalculate_bounds();
+    grids[active_sheet].recalculate_bounds();
+    // Cells past the affected lines moved; viewports repaint in full.
+    grids[active_sheet].mark_all_changed();
     
-    // Sync grids vector
-    if active_sheet < grids.len() {
-        grids[active_sheet].cells = grid.cells.clone();
-        grids[active_sheet].max_row = grid.max_row;
-        grids[active_sheet].max_col = grid.max_col;
-    }
     
-    // Drop locks before calling pivot region shift
+    // Drop locks before re-acquiring for result building
     drop(dependents_map);
     drop(dependencies_map);
     drop(column_dependents_map);
@@ -965,29 +561,6 @@ pub fn insert_columns(
     drop(merged_regions);
     drop(styles); 
     drop(grids);
-    drop(grid);
-    
-    // === UPDATE PIVOT REGIONS ===
-    shift_pivot_regions_for_col_insert(&state, &pivot_state, col, count, active_sheet);
-
-    // === UPDATE TABLE BOUNDARIES ===
-    shift_table_boundaries_for_col_insert(&state, col, count, active_sheet);
-
-    // Re-acquire locks for result building
-    let grid = state.grid.lock().map_err(|e| e.to_string())?;
-    let styles = state.style_registry.lock().map_err(|e| e.to_string())?;
-    let merged_regions = state.merged_regions.lock().map_err(|e| e.to_string())?;
-    let locale = state.locale.lock().map_err(|e| e.to_string())?;
-
-    // Return updated cells with merge info
-    let mut result: Vec<CellData> = Vec::new();
-    for r in 0..=grid.max_row {
-        for c in 0..=grid.max_col {
-            if let Some(cell_data) = get_cell_internal_with_merge(&grid, &styles, &merged_regions, r, c, &locale) {
-                result.push(cell_data);
-            }
-        }
-    }
 
     // Update IdRegistry for the structural shift
     {
@@ -999,133 +572,13 @@ pub fn insert_columns(
         }
     }
 
-    // Mark workbook as dirty
-    if let Ok(mut modified) = file_state.is_modified.lock() { *modified = true; }
-
-    Ok(result)
+    Ok(())
 }
 
 // ============================================================================
-// FORMULA REFERENCE SHIFTING (respects $ absolute markers)
+// FORMULA REFERENCE SHIFTING FOR FILL/COPY (respects $ absolute markers)
 // ============================================================================
 
-/// Shift row references in a formula by a given amount.
-/// Respects $ absolute markers - $5 won't be shifted, but 5 will.
-pub fn shift_formula_row_references(formula: &str, from_row: u32, delta: i32) -> String {
-    // Handle cell references (e.g., A5, $A$5, A$5, $A5)
-    let result = CELL_REF_RE.replace_all(formula, |caps: &regex::Captures| {
-        let col_abs = &caps[1];
-        let col_letters = &caps[2];
-        let row_abs = &caps[3];
-        let row_num: u32 = caps[4].parse().unwrap_or(0);
-        
-        // Only shift if row is NOT absolute (no $) and row is at or after from_row
-        // from_row is 0-indexed, row_num is 1-indexed
-        let new_row = if row_abs.is_empty() && row_num > from_row {
-            ((row_num as i32) + delta).max(1) as u32
-        } else {
-            row_num
-        };
-        
-        format!("{}{}{}{}", col_abs, col_letters, row_abs, new_row)
-    }).to_string();
-    
-    // Handle row-only references (e.g., 5:5, $2:$10, 2:$10)
-    ROW_RANGE_RE.replace_all(&result, |caps: &regex::Captures| {
-        let start_abs = &caps[1];
-        let start_row: u32 = caps[2].parse().unwrap_or(0);
-        let end_abs = &caps[3];
-        let end_row: u32 = caps[4].parse().unwrap_or(0);
-        
-        let new_start = if start_abs.is_empty() && start_row > from_row {
-            ((start_row as i32) + delta).max(1) as u32
-        } else {
-            start_row
-        };
-        
-        let new_end = if end_abs.is_empty() && end_row > from_row {
-            ((end_row as i32) + delta).max(1) as u32
-        } else {
-            end_row
-        };
-        
-        format!("{}{}:{}{}", start_abs, new_start, end_abs, new_end)
-    }).to_string()
-}
-
-/// Shift column references in a formula by a given amount.
-/// Respects $ absolute markers - $A won't be shifted, but A will.
-pub fn shift_formula_col_references(formula: &str, from_col: u32, delta: i32) -> String {
-    fn col_to_index(col: &str) -> u32 {
-        let mut index: u32 = 0;
-        for ch in col.to_uppercase().chars() {
-            index = index * 26 + (ch as u32 - 'A' as u32 + 1);
-        }
-        index - 1
-    }
-    
-    fn index_to_col(mut idx: u32) -> String {
-        let mut result = String::new();
-        loop {
-            result.insert(0, (b'A' + (idx % 26) as u8) as char);
-            if idx < 26 {
-                break;
-            }
-            idx = idx / 26 - 1;
-        }
-        result
-    }
-    
-    // Handle cell references (e.g., C5, $C$5, C$5, $C5)
-    let result = CELL_REF_RE.replace_all(formula, |caps: &regex::Captures| {
-        let col_abs = &caps[1];
-        let col_letters = &caps[2];
-        let row_abs = &caps[3];
-        let row_num = &caps[4];
-        
-        let col_index = col_to_index(col_letters);
-        
-        // Only shift if column is NOT absolute (no $) and col is at or after from_col
-        let new_col_index = if col_abs.is_empty() && col_index >= from_col {
-            ((col_index as i32) + delta).max(0) as u32
-        } else {
-            col_index
-        };
-        
-        let new_col_letters = index_to_col(new_col_index);
-        
-        format!("{}{}{}{}", col_abs, new_col_letters, row_abs, row_num)
-    }).to_string();
-    
-    // Handle column-only references (e.g., B:B, $A:$C, A:$C)
-    COL_RANGE_RE.replace_all(&result, |caps: &regex::Captures| {
-        let start_abs = &caps[1];
-        let start_col = &caps[2];
-        let end_abs = &caps[3];
-        let end_col = &caps[4];
-        
-        let start_index = col_to_index(start_col);
-        let end_index = col_to_index(end_col);
-        
-        let new_start_index = if start_abs.is_empty() && start_index >= from_col {
-            ((start_index as i32) + delta).max(0) as u32
-        } else {
-            start_index
-        };
-        
-        let new_end_index = if end_abs.is_empty() && end_index >= from_col {
-            ((end_index as i32) + delta).max(0) as u32
-        } else {
-            end_index
-        };
-        
-        let new_start_col = index_to_col(new_start_index);
-        let new_end_col = index_to_col(new_end_index);
-        
-        format!("{}{}:{}{}", start_abs, new_start_col, end_abs, new_end_col)
-    }).to_string()
-}
-
 /// Convert a column letter string (e.g., "A", "AA", "AZ") to a 0-based index.
 /// Extracted as a shared helper for formula manipulation functions.
 fn col_letters_to_index(col: &str) -> u32 {
@@ -1136,63 +589,10 @@ fn col_letters_to_index(col: &str) -> u32 {
     index - 1
 }
 
-/// Normalize inverted ranges in a formula after reference shifting.
-///
-/// During fill operations, a relative reference can shift past an absolute
-/// anchor, producing an inverted range where start > end. For example:
-///   =SUM(I10:$I$11)  filled down by 3  -->  =SUM(I13:$I$11)   [row 13 > 11]
-///
-/// This function detects such inversions and swaps the two cell references
-/// so the range is valid:
-///   =SUM(I13:$I$11)  -->  =SUM($I$11:I13)
-///
-/// The $ (absolute) markers travel with their original reference, preserving
-/// fill semantics for any future operations on the result.
-fn normalize_inverted_ranges(formula: &str) -> String {
-    CELL_RANGE_RE.replace_all(formula, |caps: &regex::Captures| {
-        let s_col_abs = &caps[1];
-        let s_col     = &caps[2];
-        let s_row_abs = &caps[3];
-        let s_row: u32 = caps[4].parse().unwrap_or(0);
-
-        let e_col_abs = &caps[5];
-        let e_col     = &caps[6];
-        let e_row_abs = &caps[7];
-        let e_row: u32 = caps[8].parse().unwrap_or(0);
-
-        let s_col_idx = col_letters_to_index(s_col);
-        let e_col_idx = col_letters_to_index(e_col);
-
-        let row_inverted = s_row > e_row;
-        let col_inverted = s_col_idx > e_col_idx;
-
-        if row_inverted || col_inverted {
-            // Swap the entire start and end cell references.
-            //
-            // During fill, only one axis shifts at a time (rows for vertical,
-            // cols for horizontal), so inversion only occurs on one axis while
-            // the other stays equal or correctly ordered.  A full swap is safe
-            // because the non-inverted axis either:
-            //   (a) has identical start/end values (e.g., I:I), or
-            //   (b) was already correctly ordered and stays that way.
-            //
-            // The $ markers travel with their original reference, which is
-            // correct: the fixed (absolute) part becomes the new start, and
-            // the moving (relative) part becomes the new end.
-            format!("{}{}{}{}:{}{}{}{}",
-                e_col_abs, e_col, e_row_abs, e_row,
-                s_col_abs, s_col, s_row_abs, s_row)
-        } else {
-            // Range is correctly ordered -- keep as-is
-            caps[0].to_string()
-        }
-    }).to_string()
-}
-
 /// Shift formula references for fill handle operation.
-/// This shifts references based on the fill direction and offset.
-/// After shifting, inverted ranges (where start > end due to a relative
-/// reference crossing past an absolute anchor) are normalized.
+/// Relative row/column components move by the fill offset, `$` components
+/// stay. A range whose relative endpoint crosses an absolute anchor is
+/// normalized, and a reference pushed off the sheet becomes `#REF!`.
 /// Exported for use by fill handle command.
 #[tauri::command]
 pub fn shift_formula_for_fill(
@@ -1204,108 +604,64 @@ pub fn shift_formula_for_fill(
 }
 
 /// Internal function to shift a single formula (no Result wrapper).
+/// Accepts the formula with or without its leading `=`; text that does not
+/// parse is returned unchanged.
 pub(crate) fn shift_formula_internal(formula: &str, row_delta: i32, col_delta: i32) -> String {
-    let mut result = formula.to_string();
-
-    // Shift rows if there's a row delta
-    if row_delta != 0 {
-        result = shift_formula_row_references_for_fill(&result, row_delta);
+    let body = formula.strip_prefix('=').unwrap_or(formula);
+    match parser::parse(body) {
+        Ok(ast) => render_shifted_formula(formula, &ast, row_delta, col_delta),
+        Err(_) => formula.to_string(),
     }
+}
 
-    // Shift columns if there's a column delta
-    if col_delta != 0 {
-        result = shift_formula_col_references_for_fill(&result, col_delta);
+/// Shift an already-parsed formula and render it back in the author's
+/// spelling. `formula` is the original text (with its `=`, if any).
+fn render_shifted_formula(formula: &str, ast: &parser::Expression, row_delta: i32, col_delta: i32) -> String {
+    let mut ast = ast.clone();
+    if !engine::reference_shift::shift_for_fill(&mut ast, row_delta, col_delta) {
+        return formula.to_string();
     }
-
-    // Normalize any ranges that became inverted after shifting.
-    // Example: I10:$I$11 shifted by +3 rows --> I13:$I$11 --> $I$11:I13
-    normalize_inverted_ranges(&result)
+    let (prefix, body) = match formula.strip_prefix('=') {
+        Some(body) => ("=", body),
+        None => ("", formula),
+    };
+    let rendered = engine::ast_render::render_formula(&ast);
+    format!("{}{}", prefix, engine::reference_shift::restore_identifier_case(&rendered, body))
 }
 
 /// Batch shift multiple formulas at once for fill operations.
 /// This is significantly faster than calling shift_formula_for_fill multiple times
-/// because it processes all formulas in a single IPC call.
+/// because it processes all formulas in a single IPC call, and each distinct
+/// formula text is parsed only once (a fill repeats the same source formulas).
 #[tauri::command]
 pub fn shift_formulas_batch(
     inputs: Vec<crate::api_types::FormulaShiftInput>,
 ) -> crate::api_types::FormulaShiftResult {
     let t0 = std::time::Instant::now();
+    let mut parsed: HashMap<&str, Option<parser::Expression>> = HashMap::new();
     let formulas: Vec<String> = inputs
         .iter()
-        .map(|input| shift_formula_internal(&input.formula, input.row_delta, input.col_delta))
+        .map(|input| {
+            let formula = input.formula.as_str();
+            let ast = parsed
+                .entry(formula)
+                .or_insert_with(|| parser::parse(formula.strip_prefix('=').unwrap_or(formula)).ok());
+            match ast {
+                Some(ast) => render_shifted_formula(formula, ast, input.row_delta, input.col_delta),
+                None => formula.to_string(),
+            }
+        })
         .collect();
     let dt = t0.elapsed();
 
     crate::logging::log_perf!("SHIFT",
-        "shift_formulas_batch(N={}) | process={:.2}ms",
-        inputs.len(), dt.as_secs_f64() * 1000.0
+        "shift_formulas_batch(N={}, distinct={}) | process={:.2}ms",
+        inputs.len(), parsed.len(), dt.as_secs_f64() * 1000.0
     );
 
     crate::api_types::FormulaShiftResult { formulas }
 }
 
-/// Shift row references for fill operation (all non-absolute refs shift).
-fn shift_formula_row_references_for_fill(formula: &str, delta: i32) -> String {
-    CELL_REF_RE.replace_all(formula, |caps: &regex::Captures| {
-        let col_abs = &caps[1];
-        let col_letters = &caps[2];
-        let row_abs = &caps[3];
-        let row_num: u32 = caps[4].parse().unwrap_or(0);
-        
-        // Only shift if row is NOT absolute (no $)
-        let new_row = if row_abs.is_empty() {
-            ((row_num as i32) + delta).max(1) as u32
-        } else {
-            row_num
-        };
-        
-        format!("{}{}{}{}", col_abs, col_letters, row_abs, new_row)
-    }).to_string()
-}
-
-/// Shift column references for fill operation (all non-absolute refs shift).
-fn shift_formula_col_references_for_fill(formula: &str, delta: i32) -> String {
-    fn col_to_index(col: &str) -> u32 {
-        let mut index: u32 = 0;
-        for ch in col.to_uppercase().chars() {
-            index = index * 26 + (ch as u32 - 'A' as u32 + 1);
-        }
-        index - 1
-    }
-    
-    fn index_to_col(mut idx: u32) -> String {
-        let mut result = String::new();
-        loop {
-            result.insert(0, (b'A' + (idx % 26) as u8) as char);
-            if idx < 26 {
-                break;
-            }
-            idx = idx / 26 - 1;
-        }
-        result
-    }
-    
-    CELL_REF_RE.replace_all(formula, |caps: &regex::Captures| {
-        let col_abs = &caps[1];
-        let col_letters = &caps[2];
-        let row_abs = &caps[3];
-        let row_num = &caps[4];
-
-        let col_index = col_to_index(col_letters);
-
-        // Only shift if column is NOT absolute (no $)
-        let new_col_index = if col_abs.is_empty() {
-            ((col_index as i32) + delta).max(0) as u32
-        } else {
-            col_index
-        };
-        
-        let new_col_letters = index_to_col(new_col_index);
-        
-        format!("{}{}{}{}", col_abs, new_col_letters, row_abs, row_num)
-    }).to_string()
-}
-
 // ============================================================================
 // ROW/COLUMN DELETION WITH DEPENDENCY MAP UPDATES
 // ============================================================================
@@ -1438,6 +794,65 @@ fn shift_col_dependencies_map_for_delete(map: &mut crate::StripeDependenciesMap,
     }
 }
 
+/// Report entry for the formula cell at pre-change (`row`, `col`) of `sheet`,
+/// whose rewritten form `cell` holds a #REF!.
+fn ref_error_cell(sheet: usize, change: &StructuralChange, row: u32, col: u32, cell: &Cell) -> RefErrorCell {
+    let (row, col) = if sheet == change.sheet { change.map_cell(row, col).unwrap_or((row, col)) } else { (row, col) };
+    RefErrorCell {
+        sheet_index: sheet,
+        row,
+        col,
+        formula: format!("={}", cell.formula_string().unwrap_or_default()),
+    }
+}
+
+/// Shift the references into the changed sheet held by formulas on every
+/// OTHER sheet. The prior cells of each touched sheet are recorded as a
+/// `"script_grid_cells"` restore (the generic off-active-sheet cell restore)
+/// in the open undo transaction. Returns the cells left with a #REF! and the
+/// touched sheets.
+fn shift_cross_sheet_referrers(
+    grids: &mut [Grid],
+    change: &StructuralChange,
+    undo_stack: &mut UndoStack,
+) -> (Vec<RefErrorCell>, Vec<usize>) {
+    let mut ref_errors = Vec::new();
+    let mut touched = Vec::new();
+    for (sheet, grid) in grids.iter_mut().enumerate() {
+        if sheet == change.sheet {
+            continue;
+        }
+        let mut before_cells = Vec::new();
+        for (&(r, c), cell) in grid.cells.iter_mut() {
+            let Some(ast) = cell.ast.as_deref() else { continue };
+            let mut ast = ast.clone();
+            let shifted = shift_sheet_formula_refs(&mut ast, sheet, change);
+            if !shifted.changed {
+                continue;
+            }
+            before_cells.push((r, c, Some(cell.clone())));
+            cell.ast = Some(Box::new(ast));
+            if shifted.invalidated {
+                ref_errors.push(ref_error_cell(sheet, change, r, c, cell));
+            }
+        }
+        if before_cells.is_empty() {
+            continue;
+        }
+        for &(r, c, _) in &before_cells {
+            grid.mark_changed(r, c);
+        }
+        let snapshot = crate::undo_commands::ScriptGridCellsSnapshot { sheet_index: sheet, cells: before_cells };
+        undo_stack.record_custom_restore(
+            "script_grid_cells".to_string(),
+            serde_json::to_vec(&snapshot).unwrap_or_default(),
+            "Shift cross-sheet references",
+        );
+        touched.push(sheet);
+    }
+    (ref_errors, touched)
+}
+
 /// Delete rows at the specified position, shifting remaining rows up.
 /// Uses snapshot-based undo to restore the full grid state on undo.
 #[tauri::command]
@@ -1445,9 +860,21 @@ pub fn delete_rows(
     state: State<AppState>,
     file_state: State<FileState>,
     pivot_state: State<'_, PivotState>,
+    user_files_state: State<UserFilesState>,
     row: u32,
     count: u32,
-) -> Result<Vec<CellData>, String> {
+) -> Result<DeleteLinesResult, String> {
+    delete_rows_internal(&state, &file_state, &pivot_state, &user_files_state, row, count)
+}
+
+pub(crate) fn delete_rows_internal(
+    state: &AppState,
+    file_state: &FileState,
+    pivot_state: &PivotState,
+    user_files_state: &UserFilesState,
+    row: u32,
+    count: u32,
+) -> Result<DeleteLinesResult, String> {
     // Check if any spill range would be broken by this row deletion.
     // Block if any spill range has cells both inside and outside the deleted rows.
     {
@@ -1479,15 +906,24 @@ pub fn delete_rows(
     }
 
     // Capture snapshot BEFORE acquiring other locks
-    let snapshot = capture_grid_snapshot(&state);
+    let snapshot = capture_grid_snapshot(state);
+
+    // Every feature store with coordinates of its own follows the shift;
+    // feature stores are locked (and released) before the grid locks below.
+    let change = {
+        let active_sheet = *state.active_sheet.lock().map_err(|e| e.to_string())?;
+        let sheet_name = state.sheet_names.lock().map_err(|e| e.to_string())?
+            .get(active_sheet).cloned().unwrap_or_default();
+        StructuralChange::delete(active_sheet, &sheet_name, Axis::Rows, row, count)
+    };
+    let features_snapshot = crate::structural_change::dispatch_structural_change(state, pivot_state, &change);
 
-    let mut grid = state.grid.lock().map_err(|e| e.to_string())?;
     let mut grids = state.grids.lock().map_err(|e| e.to_string())?;
     let styles = state.style_registry.lock().map_err(|e| e.to_string())?;
     let mut row_heights = state.row_heights.lock().map_err(|e| e.to_string())?;
     let active_sheet = *state.active_sheet.lock().map_err(|e| e.to_string())?;
     let mut undo_stack = state.undo_stack.lock().map_err(|e| e.to_string())?;
-    let merged_regions = state.merged_regions.lock().map_err(|e| e.to_string())?;
+    let mut merged_regions = state.merged_regions.lock().map_err(|e| e.to_string())?;
 
     // Lock all dependency maps
     let mut dependents_map = state.dependents.lock().map_err(|e| e.to_string())?;
@@ -1496,10 +932,17 @@ pub fn delete_rows(
     let mut column_dependencies_map = state.column_dependencies.lock().map_err(|e| e.to_string())?;
     let mut row_dependents_map = state.row_dependents.lock().map_err(|e| e.to_string())?;
     let mut row_dependencies_map = state.row_dependencies.lock().map_err(|e| e.to_string())?;
+    let mut name_ref_sources = state.name_ref_sources.lock().map_err(|e| e.to_string())?;
+    let fresh_name_refs = crate::name_dependents::fresh_entries(&name_ref_sources, &grids);
 
     // Record snapshot for undo
     undo_stack.begin_transaction(format!("Delete {} row(s)", count));
     undo_stack.record_snapshot(snapshot);
+    undo_stack.record_custom_restore(
+        "obj_structural_features".to_string(),
+        features_snapshot,
+        "Shift sheet features",
+    );
     // Assignments on deleted rows drop; those below shift up (same transaction;
     // see insert_rows).
     {
@@ -1525,37 +968,45 @@ pub fn delete_rows(
             );
         }
     }
+    // Formulas on other sheets that point into the deleted band.
+    let (mut ref_errors, mut recalc_sheets) = shift_cross_sheet_referrers(&mut grids, &change, &mut undo_stack);
     undo_stack.commit_transaction();
+    // Merges are part of the grid snapshot recorded above.
+    merged_regions.apply_structural_change(&change);
     
     // First, remove cells in the deleted rows
-    let cells_to_delete: Vec<(u32, u32)> = grid.cells.keys()
+    let cells_to_delete: Vec<(u32, u32)> = grids[active_sheet].cells.keys()
         .filter(|(r, _)| *r >= row && *r < row + count)
         .cloned()
         .collect();
     
     for pos in cells_to_delete {
-        grid.cells.remove(&pos);
+        grids[active_sheet].cells.remove(&pos);
     }
     
     // Update formula references in remaining cells (shift up = negative delta)
-    let all_cells: Vec<((u32, u32), Cell)> = grid.cells.iter()
+    let all_cells: Vec<((u32, u32), Cell)> = grids[active_sheet].cells.iter()
         .map(|(&pos, cell)| (pos, cell.clone()))
         .collect();
     
     for ((r, c), cell) in &all_cells {
-        if let Some(formula) = cell.formula_string() {
-            let updated_formula = shift_formula_row_references(&formula, row, -(count as i32));
-            if updated_formula != formula {
+        if let Some(ast) = cell.ast.as_deref() {
+            let mut ast = ast.clone();
+            let shifted = shift_sheet_formula_refs(&mut ast, active_sheet, &change);
+            if shifted.changed {
                 let mut updated_cell = cell.clone();
-                updated_cell.ast = parser::parse(&updated_formula).ok().map(Box::new);
-                grid.cells.insert((*r, *c), updated_cell);
+                updated_cell.ast = Some(Box::new(ast));
+                if shifted.invalidated {
+                    ref_errors.push(ref_error_cell(active_sheet, &change, *r, *c, &updated_cell));
+                }
+                grids[active_sheet].cells.insert((*r, *c), updated_cell);
             }
         }
     }
 
     // Move remaining cells up
     let mut cells_to_move: Vec<((u32, u32), Cell)> = Vec::new();
-    for (&(r, c), cell) in grid.cells.iter() {
+    for (&(r, c), cell) in grids[active_sheet].cells.iter() {
         if r >= row + count {
             cells_to_move.push(((r, c), cell.clone()));
         }
@@ -1566,8 +1017,8 @@ pub fn delete_rows(
     
     // Remove old cells and insert at new positions
     for ((r, c), cell) in cells_to_move {
-        grid.cells.remove(&(r, c));
-        grid.cells.insert((r - count, c), cell);
+        grids[active_sheet].cells.remove(&(r, c));
+        grids[active_sheet].cells.insert((r - count, c), cell);
     }
     
     // Update row heights
@@ -1628,16 +1079,16 @@ pub fn delete_rows(
     shift_row_dependencies_map_for_delete(&mut row_dependencies_map, row, count);
     
     // Recalculate grid bounds
-    grid.recalculate_bounds();
+    grids[active_sheet].recalculate_bounds();
+    // Cells past the affected lines moved; viewports repaint in full.
+    grids[active_sheet].mark_all_changed();
     
-    // Sync grids vector
-    if active_sheet < grids.len() {
-        grids[active_sheet].cells = grid.cells.clone();
-        grids[active_sheet].max_row = grid.max_row;
-        grids[active_sheet].max_col = grid.max_col;
-    }
+    crate::name_dependents::follow_structural_change(
+        &mut name_ref_sources, &fresh_name_refs, &change, &grids,
+    );
     
-    // Drop locks before calling pivot region shift
+    // Drop locks before re-acquiring for result building
+    drop(name_ref_sources);
     drop(dependents_map);
     drop(dependencies_map);
     drop(column_dependents_map);
@@ -1649,16 +1100,20 @@ pub fn delete_rows(
     drop(merged_regions);
     drop(styles);
     drop(grids);
-    drop(grid);
-    
-    // === UPDATE PIVOT REGIONS ===
-    shift_pivot_regions_for_row_delete(&state, &pivot_state, row, count, active_sheet);
-
-    // === UPDATE TABLE BOUNDARIES ===
-    shift_table_boundaries_for_row_delete(&state, row, count, active_sheet);
 
+    // Dependents (here and on other sheets) of the shifted or deleted cells
+    // need new values: a reference into the deleted band now reads #REF!.
+    crate::undo_commands::rebuild_cross_sheet_dependencies(state);
+    if state.calculation_settings.lock().map_err(|e| e.to_string())?.mode.is_automatic() {
+        recalc_sheets.push(active_sheet);
+        recalc_sheets.sort_unstable();
+        for sheet in recalc_sheets {
+            crate::calculation::recalculate_sheet_values(state, user_files_state, pivot_state, sheet, None);
+        }
+    }
+    
     // Re-acquire locks for result building
-    let grid = state.grid.lock().map_err(|e| e.to_string())?;
+    let grid = state.get_active_grid();
     let styles = state.style_registry.lock().map_err(|e| e.to_string())?;
     let merged_regions = state.merged_regions.lock().map_err(|e| e.to_string())?;
     let locale = state.locale.lock().map_err(|e| e.to_string())?;
@@ -1686,7 +1141,7 @@ pub fn delete_rows(
     // Mark workbook as dirty
     if let Ok(mut modified) = file_state.is_modified.lock() { *modified = true; }
 
-    Ok(result)
+    Ok(DeleteLinesResult { updated_cells: result, ref_errors })
 }
 
 /// Delete columns at the specified position, shifting remaining columns left.
@@ -1696,9 +1151,21 @@ pub fn delete_columns(
     state: State<AppState>,
     file_state: State<FileState>,
     pivot_state: State<'_, PivotState>,
+    user_files_state: State<UserFilesState>,
     col: u32,
     count: u32,
-) -> Result<Vec<CellData>, String> {
+) -> Result<DeleteLinesResult, String> {
+    delete_columns_internal(&state, &file_state, &pivot_state, &user_files_state, col, count)
+}
+
+pub(crate) fn delete_columns_internal(
+    state: &AppState,
+    file_state: &FileState,
+    pivot_state: &PivotState,
+    user_files_state: &UserFilesState,
+    col: u32,
+    count: u32,
+) -> Result<DeleteLinesResult, String> {
     // Check if any spill range would be broken by this column deletion.
     {
         let active_sheet = *state.active_sheet.lock().unwrap();
@@ -1727,15 +1194,24 @@ pub fn delete_columns(
     }
 
     // Capture snapshot BEFORE acquiring other locks
-    let snapshot = capture_grid_snapshot(&state);
+    let snapshot = capture_grid_snapshot(state);
+
+    // Every feature store with coordinates of its own follows the shift;
+    // feature stores are locked (and released) before the grid locks below.
+    let change = {
+        let active_sheet = *state.active_sheet.lock().map_err(|e| e.to_string())?;
+        let sheet_name = state.sheet_names.lock().map_err(|e| e.to_string())?
+            .get(active_sheet).cloned().unwrap_or_default();
+        StructuralChange::delete(active_sheet, &sheet_name, Axis::Columns, col, count)
+    };
+    let features_snapshot = crate::structural_change::dispatch_structural_change(state, pivot_state, &change);
 
-    let mut grid = state.grid.lock().map_err(|e| e.to_string())?;
     let mut grids = state.grids.lock().map_err(|e| e.to_string())?;
     let styles = state.style_registry.lock().map_err(|e| e.to_string())?;
     let mut column_widths = state.column_widths.lock().map_err(|e| e.to_string())?;
     let active_sheet = *state.active_sheet.lock().map_err(|e| e.to_string())?;
     let mut undo_stack = state.undo_stack.lock().map_err(|e| e.to_string())?;
-    let merged_regions = state.merged_regions.lock().map_err(|e| e.to_string())?;
+    let mut merged_regions = state.merged_regions.lock().map_err(|e| e.to_string())?;
 
     // Lock all dependency maps
     let mut dependents_map = state.dependents.lock().map_err(|e| e.to_string())?;
@@ -1744,10 +1220,17 @@ pub fn delete_columns(
     let mut column_dependencies_map = state.column_dependencies.lock().map_err(|e| e.to_string())?;
     let mut row_dependents_map = state.row_dependents.lock().map_err(|e| e.to_string())?;
     let mut row_dependencies_map = state.row_dependencies.lock().map_err(|e| e.to_string())?;
+    let mut name_ref_sources = state.name_ref_sources.lock().map_err(|e| e.to_string())?;
+    let fresh_name_refs = crate::name_dependents::fresh_entries(&name_ref_sources, &grids);
 
     // Record snapshot for undo
     undo_stack.begin_transaction(format!("Delete {} column(s)", count));
     undo_stack.record_snapshot(snapshot);
+    undo_stack.record_custom_restore(
+        "obj_structural_features".to_string(),
+        features_snapshot,
+        "Shift sheet features",
+    );
     // Assignments on deleted columns drop; those to the right shift left (same
     // transaction; see insert_rows).
     {
@@ -1772,37 +1255,45 @@ pub fn delete_columns(
             );
         }
     }
+    // Formulas on other sheets that point into the deleted band.
+    let (mut ref_errors, mut recalc_sheets) = shift_cross_sheet_referrers(&mut grids, &change, &mut undo_stack);
     undo_stack.commit_transaction();
+    // Merges are part of the grid snapshot recorded above.
+    merged_regions.apply_structural_change(&change);
     
     // First, remove cells in the deleted columns
-    let cells_to_delete: Vec<(u32, u32)> = grid.cells.keys()
+    let cells_to_delete: Vec<(u32, u32)> = grids[active_sheet].cells.keys()
         .filter(|(_, c)| *c >= col && *c < col + count)
         .cloned()
         .collect();
     
     for pos in cells_to_delete {
-        grid.cells.remove(&pos);
+        grids[active_sheet].cells.remove(&pos);
     }
     
     // Update formula references in remaining cells (shift left = negative delta)
-    let all_cells: Vec<((u32, u32), Cell)> = grid.cells.iter()
+    let all_cells: Vec<((u32, u32), Cell)> = grids[active_sheet].cells.iter()
         .map(|(&pos, cell)| (pos, cell.clone()))
         .collect();
     
     for ((r, c), cell) in &all_cells {
-        if let Some(formula) = cell.formula_string() {
-            let updated_formula = shift_formula_col_references(&formula, col, -(count as i32));
-            if updated_formula != formula {
+        if let Some(ast) = cell.ast.as_deref() {
+            let mut ast = ast.clone();
+            let shifted = shift_sheet_formula_refs(&mut ast, active_sheet, &change);
+            if shifted.changed {
                 let mut updated_cell = cell.clone();
-                updated_cell.ast = parser::parse(&updated_formula).ok().map(Box::new);
-                grid.cells.insert((*r, *c), updated_cell);
+                updated_cell.ast = Some(Box::new(ast));
+                if shifted.invalidated {
+                    ref_errors.push(ref_error_cell(active_sheet, &change, *r, *c, &updated_cell));
+                }
+                grids[active_sheet].cells.insert((*r, *c), updated_cell);
             }
         }
     }
 
     // Move remaining cells left
     let mut cells_to_move: Vec<((u32, u32), Cell)> = Vec::new();
-    for (&(r, c), cell) in grid.cells.iter() {
+    for (&(r, c), cell) in grids[active_sheet].cells.iter() {
         if c >= col + count {
             cells_to_move.push(((r, c), cell.clone()));
         }
@@ -1813,8 +1304,8 @@ pub fn delete_columns(
     
     // Remove old cells and insert at new positions
     for ((r, c), cell) in cells_to_move {
-        grid.cells.remove(&(r, c));
-        grid.cells.insert((r, c - count), cell);
+        grids[active_sheet].cells.remove(&(r, c));
+        grids[active_sheet].cells.insert((r, c - count), cell);
     }
     
     // Update column widths
@@ -1875,16 +1366,16 @@ pub fn delete_columns(
     shift_cell_positions_for_col_delete(&mut row_dependencies_map, col, count);
     
     // Recalculate grid bounds
-    grid.recalculate_bounds();
+    grids[active_sheet].recalculate_bounds();
+    // Cells past the affected lines moved; viewports repaint in full.
+    grids[active_sheet].mark_all_changed();
     
-    // Sync grids vector
-    if active_sheet < grids.len() {
-        grids[active_sheet].cells = grid.cells.clone();
-        grids[active_sheet].max_row = grid.max_row;
-        grids[active_sheet].max_col = grid.max_col;
-    }
+    crate::name_dependents::follow_structural_change(
+        &mut name_ref_sources, &fresh_name_refs, &change, &grids,
+    );
     
-    // Drop locks before calling pivot region shift
+    // Drop locks before re-acquiring for result building
+    drop(name_ref_sources);
     drop(dependents_map);
     drop(dependencies_map);
     drop(column_dependents_map);
@@ -1896,16 +1387,20 @@ pub fn delete_columns(
     drop(merged_regions);
     drop(styles);
     drop(grids);
-    drop(grid);
-    
-    // === UPDATE PIVOT REGIONS ===
-    shift_pivot_regions_for_col_delete(&state, &pivot_state, col, count, active_sheet);
-
-    // === UPDATE TABLE BOUNDARIES ===
-    shift_table_boundaries_for_col_delete(&state, col, count, active_sheet);
 
+    // Dependents (here and on other sheets) of the shifted or deleted cells
+    // need new values: a reference into the deleted band now reads #REF!.
+    crate::undo_commands::rebuild_cross_sheet_dependencies(state);
+    if state.calculation_settings.lock().map_err(|e| e.to_string())?.mode.is_automatic() {
+        recalc_sheets.push(active_sheet);
+        recalc_sheets.sort_unstable();
+        for sheet in recalc_sheets {
+            crate::calculation::recalculate_sheet_values(state, user_files_state, pivot_state, sheet, None);
+        }
+    }
+    
     // Re-acquire locks for result building
-    let grid = state.grid.lock().map_err(|e| e.to_string())?;
+    let grid = state.get_active_grid();
     let styles = state.style_registry.lock().map_err(|e| e.to_string())?;
     let merged_regions = state.merged_regions.lock().map_err(|e| e.to_string())?;
     let locale = state.locale.lock().map_err(|e| e.to_string())?;
@@ -1933,7 +1428,7 @@ pub fn delete_columns(
     // Mark workbook as dirty
     if let Ok(mut modified) = file_state.is_modified.lock() { *modified = true; }
 
-    Ok(result)
+    Ok(DeleteLinesResult { updated_cells: result, ref_errors })
 }
 
 // ============================================================================
@@ -2059,7 +1554,6 @@ pub fn relocate_cell_references(
     }
 
     let sheet_names = state.sheet_names.lock().unwrap();
-    let mut grid = state.grid.lock().unwrap();
     let mut grids = state.grids.lock().unwrap();
     let active_sheet = *state.active_sheet.lock().unwrap();
     let styles = state.style_registry.lock().unwrap();
@@ -2081,8 +1575,8 @@ pub fn relocate_cell_references(
     let dest_max_col = dest_start_col + (src_max_col - src_min_col);
     let mut rewrites: Vec<(u32, u32, String)> = Vec::new();
 
-    for r in 0..=grid.max_row {
-        for c in 0..=grid.max_col {
+    for r in 0..=grids[active_sheet].max_row {
+        for c in 0..=grids[active_sheet].max_col {
             // Skip cells that are IN the destination range (they were just written)
             if r >= dest_start_row && r <= dest_max_row
                 && c >= dest_start_col && c <= dest_max_col
@@ -2090,7 +1584,7 @@ pub fn relocate_cell_references(
                 continue;
             }
 
-            if let Some(cell) = grid.get_cell(r, c) {
+            if let Some(cell) = grids[active_sheet].get_cell(r, c) {
                 if let Some(formula) = cell.formula_string() {
                     let new_formula = relocate_references_in_formula(
                         &formula,
@@ -2114,7 +1608,7 @@ pub fn relocate_cell_references(
 
     for (r, c, new_formula) in &rewrites {
         // Record undo
-        let prev = grid.get_cell(*r, *c).cloned();
+        let prev = grids[active_sheet].get_cell(*r, *c).cloned();
         undo_stack.record_cell_change(*r, *c, prev.clone());
 
         // Preserve existing style
@@ -2126,6 +1620,7 @@ pub fn relocate_cell_references(
             &sheet_names,
             active_sheet,
             new_formula,
+            engine::EvalContext::at(*r, *c),
             &user_files,
         );
 
@@ -2135,29 +1630,19 @@ pub fn relocate_cell_references(
             value: cell_value,
             style_index: existing_style_index,
             rich_text: prev.as_ref().and_then(|c| c.rich_text.clone()),
+            quote_prefix: false,
         };
 
         // Parse the formula to extract references for dependency tracking
         if let Ok(parsed) = parser::parse(new_formula) {
-            let refs = crate::extract_all_references(&parsed, &grid);
+            let refs = crate::extract_all_references(&parsed, &grids[active_sheet]);
+            // Normalize cross-sheet refs
+            let normalized_cross = crate::resolve_cross_sheet_refs(&refs, &sheet_names);
 
             crate::update_dependencies((*r, *c), refs.cells, &mut dependencies_map, &mut dependents_map);
             crate::update_column_dependencies((*r, *c), refs.columns, &mut column_dependencies_map, &mut column_dependents_map);
             crate::update_row_dependencies((*r, *c), refs.rows, &mut row_dependencies_map, &mut row_dependents_map);
 
-            // Normalize cross-sheet refs
-            let normalized_cross: rustc_hash::FxHashSet<(String, u32, u32)> = refs
-                .cross_sheet_cells
-                .iter()
-                .filter_map(|(parsed_name, cr, cc)| {
-                    let normalized = sheet_names
-                        .iter()
-                        .find(|name| name.eq_ignore_ascii_case(parsed_name))
-                        .cloned()
-                        .unwrap_or_else(|| parsed_name.clone());
-                    Some((normalized, *cr, *cc))
-                })
-                .collect();
             crate::update_cross_sheet_dependencies(
                 (active_sheet, *r, *c),
                 normalized_cross,
@@ -2170,13 +1655,10 @@ pub fn relocate_cell_references(
             new_cell.set_cached_ast(engine_ast);
         }
 
-        grid.set_cell(*r, *c, new_cell.clone());
-        if active_sheet < grids.len() {
-            grids[active_sheet].set_cell(*r, *c, new_cell);
-        }
+        grids[active_sheet].set_cell(*r, *c, new_cell);
 
         // Build CellData for result
-        if let Some(cd) = get_cell_internal_with_merge(&grid, &styles, &merged_regions, *r, *c, &locale) {
+        if let Some(cd) = get_cell_internal_with_merge(&grids[active_sheet], &styles, &merged_regions, *r, *c, &locale) {
             result.push(cd);
         }
     }
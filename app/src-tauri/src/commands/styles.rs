@@ -3,10 +3,12 @@
 
 use crate::api_types::{CellData, FillParam, FormattingParams, FormattingResult, PreviewResult, StyleData, StyleEntry};
 use crate::persistence::FileState;
+use crate::sheet_access::check_sheet_indices;
 use crate::{format_cell_value_with_color, AppState};
 use engine::{
     BorderLineStyle, BorderStyle, Cell, CellStyle, CellValue, Color, CurrencyPosition, Fill,
-    GradientDirection, NumberFormat, PatternType, TextAlign, TextRotation, ThemeColor, VerticalAlign,
+    GradientDirection, Grid, NumberFormat, PatternType, StyleRegistry, TextAlign, TextRotation,
+    ThemeColor, UndoStack, VerticalAlign,
 };
 use tauri::State;
 
@@ -35,9 +37,7 @@ pub fn set_cell_style(
     col: u32,
     style_index: usize,
 ) -> Option<CellData> {
-    let mut grid = state.grid.lock().unwrap();
-    let mut grids = state.grids.lock().unwrap();
-    let active_sheet = *state.active_sheet.lock().unwrap();
+    let mut grid = state.get_active_grid();
     let styles = state.style_registry.lock().unwrap();
     let mut undo_stack = state.undo_stack.lock().unwrap();
     let merged_regions = state.merged_regions.lock().unwrap();
@@ -59,10 +59,6 @@ pub fn set_cell_style(
         updated_cell.style_index = style_index;
         grid.set_cell(row, col, updated_cell.clone());
 
-        if active_sheet < grids.len() {
-            grids[active_sheet].set_cell(row, col, updated_cell.clone());
-        }
-
         // Record undo
         undo_stack.record_cell_change(row, col, previous_cell);
 
@@ -90,6 +86,7 @@ pub fn set_cell_style(
             sheet_index: None,
             rich_text: None,
             accounting_layout,
+            error_detail: None,
         })
     } else {
         // Create a new empty cell with the style
@@ -98,12 +95,9 @@ pub fn set_cell_style(
             ast: None,
             style_index,
             rich_text: None,
+            quote_prefix: false,
         };
-        grid.set_cell(row, col, cell.clone());
-
-        if active_sheet < grids.len() {
-            grids[active_sheet].set_cell(row, col, cell);
-        }
+        grid.set_cell(row, col, cell);
 
         // Record undo (previous was None since cell didn't exist)
         undo_stack.record_cell_change(row, col, previous_cell);
@@ -123,6 +117,7 @@ pub fn set_cell_style(
             sheet_index: None,
             rich_text: None,
             accounting_layout: None,
+            error_detail: None,
         })
     }
 }
@@ -134,9 +129,20 @@ pub fn apply_formatting(
     file_state: State<FileState>,
     params: FormattingParams,
 ) -> Result<FormattingResult, String> {
-    let mut grid = state.grid.lock().unwrap();
-    let mut grids = state.grids.lock().unwrap();
-    let active_sheet = *state.active_sheet.lock().unwrap();
+    let result = apply_formatting_internal(&state, &params);
+
+    // Mark workbook as dirty
+    if !result.cells.is_empty() || params.full_columns || params.full_rows {
+        if let Ok(mut modified) = file_state.is_modified.lock() { *modified = true; }
+    }
+
+    Ok(result)
+}
+
+/// Core of `apply_formatting`. Whole columns/rows (`full_columns`/`full_rows`)
+/// set the line styles; only their existing cells come back in `cells`.
+pub(crate) fn apply_formatting_internal(state: &AppState, params: &FormattingParams) -> FormattingResult {
+    let mut grid = state.get_active_grid();
     let mut styles = state.style_registry.lock().unwrap();
     let mut undo_stack = state.undo_stack.lock().unwrap();
     let merged_regions = state.merged_regions.lock().unwrap();
@@ -147,239 +153,69 @@ pub fn apply_formatting(
     let mut used_style_indices = std::collections::HashSet::new();
 
     // Begin undo transaction for batch formatting
-    let cell_count = params.rows.len() * params.cols.len();
-    undo_stack.begin_transaction(format!("Format {} cells", cell_count));
+    undo_stack.begin_transaction(formatting_description(params));
 
     // Optimization: cache computed style index per base style index.
     // When many cells share the same base style (common case: formatting a selection),
     // we only compute the new style once per unique base style.
     let mut style_cache: std::collections::HashMap<usize, usize> = std::collections::HashMap::new();
 
-    // Iterate over all row/col combinations from the params
-    for row in &params.rows {
-        for col in &params.cols {
-            let row = *row;
-            let col = *col;
+    let targets = formatting_targets(&mut grid, &mut styles, &mut undo_stack, params, &mut style_cache);
+    used_style_indices.extend(style_cache.values().copied());
 
-            // Record previous state for undo
-            let previous_cell = grid.get_cell(row, col).cloned();
+    for (row, col, default_style) in targets {
+        // Record previous state for undo
+        let previous_cell = grid.get_cell(row, col).cloned();
 
-            // Get or create cell
-            let (cell, old_style_index) = if let Some(existing) = grid.get_cell(row, col) {
-                (existing.clone(), existing.style_index)
-            } else {
-                (
-                    Cell {
-                        value: CellValue::Empty,
-                        ast: None,
-                        style_index: 0,
-                        rich_text: None,
-                    },
-                    0,
-                )
-            };
-
-            // Check style cache: if we've already computed the new style for this base, reuse it
-            if let Some(&cached_new_index) = style_cache.get(&old_style_index) {
-                // Fast path: reuse cached style
-                let mut updated_cell = cell;
-                updated_cell.style_index = cached_new_index;
-                grid.set_cell(row, col, updated_cell.clone());
-                if active_sheet < grids.len() {
-                    grids[active_sheet].set_cell(row, col, updated_cell.clone());
-                }
-                undo_stack.record_cell_change(row, col, previous_cell);
-                let new_style = styles.get(cached_new_index);
-                let fmt_result = format_cell_value_with_color(&updated_cell.value, new_style, &locale);
-                let acct_layout = fmt_result.accounting.map(|a| crate::api_types::AccountingLayout {
-                    symbol: a.symbol,
-                    symbol_before: a.symbol_before,
-                    value: a.value,
-                });
-                let merge_info = merged_regions.iter().find(|r| r.start_row == row && r.start_col == col);
-                let (row_span, col_span) = if let Some(region) = merge_info {
-                    (region.end_row - region.start_row + 1, region.end_col - region.start_col + 1)
-                } else {
-                    (1, 1)
-                };
-                updated_cells.push(CellData {
-                    row,
-                    col,
-                    display: fmt_result.text,
-                    display_color: fmt_result.color,
-                    formula: updated_cell.formula_string().map(|f| format!("={}", f)),
-                    style_index: cached_new_index,
-                    row_span,
-                    col_span,
-                    sheet_index: None,
-                    rich_text: None,
-                    accounting_layout: acct_layout,
-                });
-                continue;
-            }
-
-            // Slow path: compute new style from base
-            let mut new_style = styles.get(old_style_index).clone();
-
-            // Apply formatting changes
-            if let Some(bold) = params.bold {
-                new_style.font.bold = bold;
-            }
-            if let Some(italic) = params.italic {
-                new_style.font.italic = italic;
-            }
-            if let Some(underline) = params.underline {
-                new_style.font.underline = underline.into();
-            }
-            if let Some(strikethrough) = params.strikethrough {
-                new_style.font.strikethrough = strikethrough;
-            }
-            if let Some(font_size) = params.font_size {
-                new_style.font.size = font_size;
-            }
-            if let Some(ref font_family) = params.font_family {
-                new_style.font.family = font_family.clone();
-            }
-            if let Some(ref text_color) = params.text_color {
-                if let Some(color) = Color::from_hex(text_color) {
-                    new_style.font.color = ThemeColor::Absolute(color);
-                }
-            }
-            if let Some(ref text_color_theme) = params.text_color_theme {
-                if let Some(slot) = engine::ThemeColorSlot::from_key(text_color_theme) {
-                    let tint = engine::Tint(params.text_color_tint.unwrap_or(0));
-                    new_style.font.color = ThemeColor::Theme { slot, tint };
-                }
-            }
-            if let Some(ref bg_color) = params.background_color {
-                if let Some(color) = Color::from_hex(bg_color) {
-                    new_style.fill = Fill::Solid { color: ThemeColor::Absolute(color) };
-                }
-            }
-            if let Some(ref bg_color_theme) = params.bg_color_theme {
-                if let Some(slot) = engine::ThemeColorSlot::from_key(bg_color_theme) {
-                    let tint = engine::Tint(params.bg_color_tint.unwrap_or(0));
-                    new_style.fill = Fill::Solid { color: ThemeColor::Theme { slot, tint } };
-                }
-            }
-            if let Some(ref align) = params.text_align {
-                new_style.text_align = match align.as_str() {
-                    "left" => TextAlign::Left,
-                    "center" => TextAlign::Center,
-                    "right" => TextAlign::Right,
-                    _ => TextAlign::General,
-                };
-            }
-            if let Some(ref valign) = params.vertical_align {
-                new_style.vertical_align = match valign.as_str() {
-                    "top" => VerticalAlign::Top,
-                    "middle" => VerticalAlign::Middle,
-                    "bottom" => VerticalAlign::Bottom,
-                    _ => VerticalAlign::Middle,
-                };
-            }
-            if let Some(wrap) = params.wrap_text {
-                new_style.wrap_text = wrap;
-            }
-            if let Some(ref rotation) = params.text_rotation {
-                new_style.text_rotation = parse_text_rotation(rotation);
-            }
-            if let Some(ref format) = params.number_format {
-                new_style.number_format = parse_number_format(format);
-            }
-
-            if let Some(checkbox) = params.checkbox {
-                new_style.checkbox = checkbox;
-            }
-            if let Some(button) = params.button {
-                new_style.button = button;
-            }
-            if let Some(indent) = params.indent {
-                new_style.indent = indent;
-            }
-            if let Some(shrink_to_fit) = params.shrink_to_fit {
-                new_style.shrink_to_fit = shrink_to_fit;
-            }
-
-            // Apply border formatting
-            if let Some(ref border) = params.border_top {
-                new_style.borders.top = parse_border_side(border);
-            }
-            if let Some(ref border) = params.border_right {
-                new_style.borders.right = parse_border_side(border);
-            }
-            if let Some(ref border) = params.border_bottom {
-                new_style.borders.bottom = parse_border_side(border);
-            }
-            if let Some(ref border) = params.border_left {
-                new_style.borders.left = parse_border_side(border);
-            }
-            if let Some(ref border) = params.border_diagonal_down {
-                new_style.borders.diagonal_down = parse_border_side(border);
-            }
-            if let Some(ref border) = params.border_diagonal_up {
-                new_style.borders.diagonal_up = parse_border_side(border);
-            }
-
-            // Apply fill
-            if let Some(ref fill_param) = params.fill {
-                new_style.fill = parse_fill_param(fill_param);
-            }
-
-            // Apply protection
-            if let Some(locked) = params.locked {
-                new_style.locked = locked;
-            }
-            if let Some(formula_hidden) = params.formula_hidden {
-                new_style.formula_hidden = formula_hidden;
-            }
-
-            // Get or create style index
-            let new_style_index = styles.get_or_create(new_style.clone());
-            used_style_indices.insert(new_style_index);
-            style_cache.insert(old_style_index, new_style_index);
+        // Get or create cell; a new cell starts from its row/column style
+        let cell = previous_cell.clone().unwrap_or(Cell {
+            value: CellValue::Empty,
+            ast: None,
+            style_index: default_style,
+            rich_text: None,
+            quote_prefix: false,
+        });
 
-            // Update cell
-            let mut updated_cell = cell;
-            updated_cell.style_index = new_style_index;
-            grid.set_cell(row, col, updated_cell.clone());
+        let new_style_index = restyle(&mut styles, params, &mut style_cache, cell.style_index);
+        used_style_indices.insert(new_style_index);
 
-            if active_sheet < grids.len() {
-                grids[active_sheet].set_cell(row, col, updated_cell.clone());
-            }
+        // Update cell
+        let mut updated_cell = cell;
+        updated_cell.style_index = new_style_index;
+        grid.set_cell(row, col, updated_cell.clone());
 
-            // Record undo
-            undo_stack.record_cell_change(row, col, previous_cell);
+        // Record undo
+        undo_stack.record_cell_change(row, col, previous_cell);
 
-            let fmt_result = format_cell_value_with_color(&updated_cell.value, &new_style, &locale);
-            let acct_layout = fmt_result.accounting.map(|a| crate::api_types::AccountingLayout {
-                symbol: a.symbol,
-                symbol_before: a.symbol_before,
-                value: a.value,
-            });
+        let fmt_result = format_cell_value_with_color(&updated_cell.value, styles.get(new_style_index), &locale);
+        let acct_layout = fmt_result.accounting.map(|a| crate::api_types::AccountingLayout {
+            symbol: a.symbol,
+            symbol_before: a.symbol_before,
+            value: a.value,
+        });
 
-            // Get merge span info
-            let merge_info = merged_regions.iter().find(|r| r.start_row == row && r.start_col == col);
-            let (row_span, col_span) = if let Some(region) = merge_info {
-                (region.end_row - region.start_row + 1, region.end_col - region.start_col + 1)
-            } else {
-                (1, 1)
-            };
+        // Get merge span info
+        let merge_info = merged_regions.iter().find(|r| r.start_row == row && r.start_col == col);
+        let (row_span, col_span) = if let Some(region) = merge_info {
+            (region.end_row - region.start_row + 1, region.end_col - region.start_col + 1)
+        } else {
+            (1, 1)
+        };
 
-            updated_cells.push(CellData {
-                row,
-                col,
-                display: fmt_result.text,
-                display_color: fmt_result.color,
-                formula: updated_cell.formula_string().map(|f| format!("={}", f)),
-                style_index: new_style_index,
-                row_span,
-                col_span,
-                sheet_index: None,
-                rich_text: None,
-                accounting_layout: acct_layout,
-            });
-        }
+        updated_cells.push(CellData {
+            row,
+            col,
+            display: fmt_result.text,
+            display_color: fmt_result.color,
+            formula: updated_cell.formula_string().map(|f| format!("={}", f)),
+            style_index: new_style_index,
+            row_span,
+            col_span,
+            sheet_index: None,
+            rich_text: None,
+            accounting_layout: acct_layout,
+            error_detail: None,
+        });
     }
 
     // Commit undo transaction
@@ -396,15 +232,10 @@ pub fn apply_formatting(
         }
     }
 
-    // Mark workbook as dirty
-    if !updated_cells.is_empty() {
-        if let Ok(mut modified) = file_state.is_modified.lock() { *modified = true; }
-    }
-
-    Ok(FormattingResult {
+    FormattingResult {
         cells: updated_cells,
         styles: updated_styles,
-    })
+    }
 }
 
 /// Apply formatting to a range of cells on multiple non-active sheets.
@@ -422,131 +253,261 @@ pub fn apply_formatting_to_sheets(
     let mut styles = state.style_registry.lock().unwrap();
     let mut undo_stack = state.undo_stack.lock().unwrap();
 
-    let cell_count = params.rows.len() * params.cols.len();
+    let description = formatting_description(&params);
+    check_sheet_indices(grids.len(), &sheet_indices)?;
 
     for &sheet_idx in &sheet_indices {
         // Skip the active sheet (already formatted by normal apply_formatting)
-        if sheet_idx == active_sheet || sheet_idx >= grids.len() {
+        if sheet_idx == active_sheet {
             continue;
         }
 
-        undo_stack.begin_transaction(format!(
-            "Format {} cells on sheet {}",
-            cell_count, sheet_idx
-        ));
+        undo_stack.begin_transaction(format!("{} on sheet {}", description, sheet_idx));
 
         let grid = &mut grids[sheet_idx];
+        let mut style_cache = std::collections::HashMap::new();
 
-        for row in &params.rows {
-            for col in &params.cols {
-                let row = *row;
-                let col = *col;
+        let targets = formatting_targets(grid, &mut styles, &mut undo_stack, &params, &mut style_cache);
+        for (row, col, default_style) in targets {
+            let previous_cell = grid.get_cell(row, col).cloned();
 
-                let previous_cell = grid.get_cell(row, col).cloned();
+            let mut cell = previous_cell.clone().unwrap_or(Cell {
+                value: CellValue::Empty,
+                ast: None,
+                style_index: default_style,
+                rich_text: None,
+                quote_prefix: false,
+            });
+            cell.style_index = restyle(&mut styles, &params, &mut style_cache, cell.style_index);
+            grid.set_cell(row, col, cell);
 
-                let (cell, old_style_index) = if let Some(existing) = grid.get_cell(row, col) {
-                    (existing.clone(), existing.style_index)
-                } else {
-                    (
-                        Cell {
-                            value: CellValue::Empty,
-                            ast: None,
-                            style_index: 0,
-                            rich_text: None,
-                        },
-                        0,
-                    )
-                };
-
-                let mut new_style = styles.get(old_style_index).clone();
-
-                // Apply all formatting fields (same logic as apply_formatting)
-                if let Some(bold) = params.bold { new_style.font.bold = bold; }
-                if let Some(italic) = params.italic { new_style.font.italic = italic; }
-                if let Some(underline) = params.underline { new_style.font.underline = underline.into(); }
-                if let Some(strikethrough) = params.strikethrough { new_style.font.strikethrough = strikethrough; }
-                if let Some(font_size) = params.font_size { new_style.font.size = font_size; }
-                if let Some(ref font_family) = params.font_family { new_style.font.family = font_family.clone(); }
-                if let Some(ref text_color) = params.text_color {
-                    if let Some(color) = Color::from_hex(text_color) { new_style.font.color = ThemeColor::Absolute(color); }
-                }
-                if let Some(ref text_color_theme) = params.text_color_theme {
-                    if let Some(slot) = engine::ThemeColorSlot::from_key(text_color_theme) {
-                        let tint = engine::Tint(params.text_color_tint.unwrap_or(0));
-                        new_style.font.color = ThemeColor::Theme { slot, tint };
-                    }
-                }
-                if let Some(ref bg_color) = params.background_color {
-                    if let Some(color) = Color::from_hex(bg_color) { new_style.fill = Fill::Solid { color: ThemeColor::Absolute(color) }; }
-                }
-                if let Some(ref bg_color_theme) = params.bg_color_theme {
-                    if let Some(slot) = engine::ThemeColorSlot::from_key(bg_color_theme) {
-                        let tint = engine::Tint(params.bg_color_tint.unwrap_or(0));
-                        new_style.fill = Fill::Solid { color: ThemeColor::Theme { slot, tint } };
-                    }
-                }
-                if let Some(ref align) = params.text_align {
-                    new_style.text_align = match align.as_str() {
-                        "left" => TextAlign::Left,
-                        "center" => TextAlign::Center,
-                        "right" => TextAlign::Right,
-                        _ => TextAlign::General,
-                    };
-                }
-                if let Some(ref valign) = params.vertical_align {
-                    new_style.vertical_align = match valign.as_str() {
-                        "top" => VerticalAlign::Top,
-                        "middle" => VerticalAlign::Middle,
-                        "bottom" => VerticalAlign::Bottom,
-                        _ => VerticalAlign::Middle,
-                    };
-                }
-                if let Some(wrap) = params.wrap_text { new_style.wrap_text = wrap; }
-                if let Some(ref rotation) = params.text_rotation {
-                    new_style.text_rotation = parse_text_rotation(rotation);
-                }
-                if let Some(ref format) = params.number_format {
-                    new_style.number_format = parse_number_format(format);
-                }
-                if let Some(checkbox) = params.checkbox { new_style.checkbox = checkbox; }
-                if let Some(button) = params.button { new_style.button = button; }
-                if let Some(indent) = params.indent { new_style.indent = indent; }
-                if let Some(shrink_to_fit) = params.shrink_to_fit { new_style.shrink_to_fit = shrink_to_fit; }
+            undo_stack.record_cell_change(row, col, previous_cell);
+        }
 
-                // Apply border formatting
-                if let Some(ref border) = params.border_top { new_style.borders.top = parse_border_side(border); }
-                if let Some(ref border) = params.border_right { new_style.borders.right = parse_border_side(border); }
-                if let Some(ref border) = params.border_bottom { new_style.borders.bottom = parse_border_side(border); }
-                if let Some(ref border) = params.border_left { new_style.borders.left = parse_border_side(border); }
-                if let Some(ref border) = params.border_diagonal_down { new_style.borders.diagonal_down = parse_border_side(border); }
-                if let Some(ref border) = params.border_diagonal_up { new_style.borders.diagonal_up = parse_border_side(border); }
+        undo_stack.commit_transaction();
+    }
 
-                // Apply fill
-                if let Some(ref fill_param) = params.fill { new_style.fill = parse_fill_param(fill_param); }
+    // Mark workbook as dirty
+    if !sheet_indices.is_empty() {
+        if let Ok(mut modified) = file_state.is_modified.lock() { *modified = true; }
+    }
 
-                // Apply protection
-                if let Some(locked) = params.locked { new_style.locked = locked; }
-                if let Some(formula_hidden) = params.formula_hidden { new_style.formula_hidden = formula_hidden; }
+    Ok(())
+}
 
-                let new_style_index = styles.get_or_create(new_style);
+fn formatting_description(params: &FormattingParams) -> String {
+    if params.full_columns {
+        format!("Format {} columns", params.cols.len())
+    } else if params.full_rows {
+        format!("Format {} rows", params.rows.len())
+    } else {
+        format!("Format {} cells", params.rows.len() * params.cols.len())
+    }
+}
 
-                let mut updated_cell = cell;
-                updated_cell.style_index = new_style_index;
-                grid.set_cell(row, col, updated_cell);
+/// The cells to restyle, each with the style it starts from if it does not
+/// exist yet. For whole columns/rows this also updates the line styles
+/// (recording undo), so only existing cells and cells where the line crosses
+/// a styled row/column are touched, not every cell in the line.
+fn formatting_targets(
+    grid: &mut Grid,
+    styles: &mut StyleRegistry,
+    undo_stack: &mut UndoStack,
+    params: &FormattingParams,
+    style_cache: &mut std::collections::HashMap<usize, usize>,
+) -> Vec<(u32, u32, usize)> {
+    if !params.full_columns && !params.full_rows {
+        let mut targets = Vec::with_capacity(params.rows.len() * params.cols.len());
+        for &row in &params.rows {
+            for &col in &params.cols {
+                targets.push((row, col, grid.default_style_index(row, col)));
+            }
+        }
+        return targets;
+    }
 
-                undo_stack.record_cell_change(row, col, previous_cell);
+    let (lines, by_col): (std::collections::HashSet<u32>, bool) = if params.full_columns {
+        (params.cols.iter().copied().collect(), true)
+    } else {
+        (params.rows.iter().copied().collect(), false)
+    };
+    let mut targets: Vec<(u32, u32, usize)> = grid
+        .cells
+        .keys()
+        .filter(|&&(row, col)| lines.contains(if by_col { &col } else { &row }))
+        .map(|&(row, col)| (row, col, 0))
+        .collect();
+
+    // Where a line crosses a styled line of the other axis, the empty cells
+    // show the row style (which outranks the column style), so they need
+    // their own cell to carry both formats.
+    if by_col {
+        for &row in grid.row_styles.keys() {
+            for &col in &lines {
+                if grid.get_cell(row, col).is_none() {
+                    targets.push((row, col, grid.default_style_index(row, col)));
+                }
             }
         }
+    } else {
+        for &col in grid.col_styles.keys() {
+            for &row in &lines {
+                if !grid.row_styles.contains_key(&row) && grid.get_cell(row, col).is_none() {
+                    targets.push((row, col, grid.default_style_index(row, col)));
+                }
+            }
+        }
+    }
+    targets.sort_unstable();
 
-        undo_stack.commit_transaction();
+    let line_styles = if by_col { &mut grid.col_styles } else { &mut grid.row_styles };
+    for &line in &lines {
+        let previous = line_styles.get(&line).copied();
+        if by_col {
+            undo_stack.record_column_style_change(line, previous);
+        } else {
+            undo_stack.record_row_style_change(line, previous);
+        }
+        match restyle(styles, params, style_cache, previous.unwrap_or(0)) {
+            0 => line_styles.remove(&line),
+            index => line_styles.insert(line, index),
+        };
     }
+    // Empty cells along the lines change look; viewports repaint in full.
+    grid.mark_all_changed();
+    targets
+}
 
-    // Mark workbook as dirty
-    if !sheet_indices.is_empty() {
-        if let Ok(mut modified) = file_state.is_modified.lock() { *modified = true; }
+/// New style index for something currently styled `base`, memoized per base.
+fn restyle(
+    styles: &mut StyleRegistry,
+    params: &FormattingParams,
+    style_cache: &mut std::collections::HashMap<usize, usize>,
+    base: usize,
+) -> usize {
+    if let Some(&index) = style_cache.get(&base) {
+        return index;
     }
+    let mut style = styles.get(base).clone();
+    apply_params_to_style(&mut style, params);
+    let index = styles.get_or_create(style);
+    style_cache.insert(base, index);
+    index
+}
 
-    Ok(())
+/// Apply the formatting fields set in `params` to `style`.
+fn apply_params_to_style(style: &mut CellStyle, params: &FormattingParams) {
+    if let Some(bold) = params.bold {
+        style.font.bold = bold;
+    }
+    if let Some(italic) = params.italic {
+        style.font.italic = italic;
+    }
+    if let Some(underline) = params.underline {
+        style.font.underline = underline.into();
+    }
+    if let Some(strikethrough) = params.strikethrough {
+        style.font.strikethrough = strikethrough;
+    }
+    if let Some(font_size) = params.font_size {
+        style.font.size = font_size;
+    }
+    if let Some(ref font_family) = params.font_family {
+        style.font.family = font_family.clone();
+    }
+    if let Some(ref text_color) = params.text_color {
+        if let Some(color) = Color::from_hex(text_color) {
+            style.font.color = ThemeColor::Absolute(color);
+        }
+    }
+    if let Some(ref text_color_theme) = params.text_color_theme {
+        if let Some(slot) = engine::ThemeColorSlot::from_key(text_color_theme) {
+            let tint = engine::Tint(params.text_color_tint.unwrap_or(0));
+            style.font.color = ThemeColor::Theme { slot, tint };
+        }
+    }
+    if let Some(ref bg_color) = params.background_color {
+        if let Some(color) = Color::from_hex(bg_color) {
+            style.fill = Fill::Solid { color: ThemeColor::Absolute(color) };
+        }
+    }
+    if let Some(ref bg_color_theme) = params.bg_color_theme {
+        if let Some(slot) = engine::ThemeColorSlot::from_key(bg_color_theme) {
+            let tint = engine::Tint(params.bg_color_tint.unwrap_or(0));
+            style.fill = Fill::Solid { color: ThemeColor::Theme { slot, tint } };
+        }
+    }
+    if let Some(ref align) = params.text_align {
+        style.text_align = match align.as_str() {
+            "left" => TextAlign::Left,
+            "center" => TextAlign::Center,
+            "right" => TextAlign::Right,
+            _ => TextAlign::General,
+        };
+    }
+    if let Some(ref valign) = params.vertical_align {
+        style.vertical_align = match valign.as_str() {
+            "top" => VerticalAlign::Top,
+            "middle" => VerticalAlign::Middle,
+            "bottom" => VerticalAlign::Bottom,
+            _ => VerticalAlign::Middle,
+        };
+    }
+    if let Some(wrap) = params.wrap_text {
+        style.wrap_text = wrap;
+    }
+    if let Some(ref rotation) = params.text_rotation {
+        style.text_rotation = parse_text_rotation(rotation);
+    }
+    if let Some(ref format) = params.number_format {
+        style.number_format = parse_number_format(format);
+    }
+
+    if let Some(checkbox) = params.checkbox {
+        style.checkbox = checkbox;
+    }
+    if let Some(button) = params.button {
+        style.button = button;
+    }
+    if let Some(indent) = params.indent {
+        style.indent = indent;
+    }
+    if let Some(shrink_to_fit) = params.shrink_to_fit {
+        style.shrink_to_fit = shrink_to_fit;
+    }
+
+    // Apply border formatting
+    if let Some(ref border) = params.border_top {
+        style.borders.top = parse_border_side(border);
+    }
+    if let Some(ref border) = params.border_right {
+        style.borders.right = parse_border_side(border);
+    }
+    if let Some(ref border) = params.border_bottom {
+        style.borders.bottom = parse_border_side(border);
+    }
+    if let Some(ref border) = params.border_left {
+        style.borders.left = parse_border_side(border);
+    }
+    if let Some(ref border) = params.border_diagonal_down {
+        style.borders.diagonal_down = parse_border_side(border);
+    }
+    if let Some(ref border) = params.border_diagonal_up {
+        style.borders.diagonal_up = parse_border_side(border);
+    }
+
+    // Apply fill
+    if let Some(ref fill_param) = params.fill {
+        style.fill = parse_fill_param(fill_param);
+    }
+
+    // Apply protection
+    if let Some(locked) = params.locked {
+        style.locked = locked;
+    }
+    if let Some(formula_hidden) = params.formula_hidden {
+        style.formula_hidden = formula_hidden;
+    }
 }
 
 /// Preview a custom number format string against a sample value.
@@ -638,6 +599,9 @@ pub(crate) fn parse_number_format(format: &str) -> NumberFormat {
         "time_12h" => NumberFormat::Time {
             format: "hh:mm:ss AM/PM".to_string(),
         },
+        "datetime" => NumberFormat::DateTime {
+            format: "yyyy-mm-dd hh:mm".to_string(),
+        },
         _ => {
             // Try to recognize common Excel-style format codes before falling
             // through to the custom format engine (which has known issues with
@@ -907,9 +871,7 @@ pub fn set_cell_rich_text(
     col: u32,
     runs: Option<Vec<crate::api_types::RichTextRunData>>,
 ) -> Option<CellData> {
-    let mut grid = state.grid.lock().unwrap();
-    let mut grids = state.grids.lock().unwrap();
-    let active_sheet = *state.active_sheet.lock().unwrap();
+    let mut grid = state.get_active_grid();
     let styles = state.style_registry.lock().unwrap();
     let mut undo_stack = state.undo_stack.lock().unwrap();
     let merged_regions = state.merged_regions.lock().unwrap();
@@ -925,13 +887,6 @@ pub fn set_cell_rich_text(
     cell.rich_text = engine_runs;
     grid.set_cell(row, col, cell);
 
-    // Sync to grids vector
-    if active_sheet < grids.len() {
-        if let Some(c) = grid.get_cell(row, col) {
-            grids[active_sheet].set_cell(row, col, c.clone());
-        }
-    }
-
     // Build response
     let cell = grid.get_cell(row, col)?;
     let style = styles.get(cell.style_index);
@@ -964,6 +919,7 @@ pub fn set_cell_rich_text(
         sheet_index: None,
         rich_text: cell.rich_text.as_ref().map(|r| crate::api_types::rich_text_runs_to_data(r)),
         accounting_layout,
+        error_detail: None,
     })
 }
 
@@ -989,9 +945,7 @@ pub fn apply_border_preset(
     color: String,
     width: u8,
 ) -> Result<FormattingResult, String> {
-    let mut grid = state.grid.lock().unwrap();
-    let mut grids = state.grids.lock().unwrap();
-    let active_sheet = *state.active_sheet.lock().unwrap();
+    let mut grid = state.get_active_grid();
     let mut styles = state.style_registry.lock().unwrap();
     let mut undo_stack = state.undo_stack.lock().unwrap();
     let merged_regions = state.merged_regions.lock().unwrap();
@@ -1035,6 +989,7 @@ pub fn apply_border_preset(
                         ast: None,
                         style_index: 0,
                         rich_text: None,
+                        quote_prefix: false,
                     },
                     0,
                 )
@@ -1116,10 +1071,6 @@ pub fn apply_border_preset(
             updated_cell.style_index = new_style_index;
             grid.set_cell(row, col, updated_cell.clone());
 
-            if active_sheet < grids.len() {
-                grids[active_sheet].set_cell(row, col, updated_cell.clone());
-            }
-
             undo_stack.record_cell_change(row, col, previous_cell);
 
             let fmt_result = format_cell_value_with_color(&updated_cell.value, &new_style, &locale);
@@ -1148,6 +1099,7 @@ pub fn apply_border_preset(
                 sheet_index: None,
                 rich_text: None,
                 accounting_layout: acct_layout,
+                error_detail: None,
             });
         }
     }
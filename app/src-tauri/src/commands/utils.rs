@@ -68,5 +68,6 @@ pub(crate) fn get_cell_internal_with_merge(
         sheet_index: None,
         rich_text,
         accounting_layout,
+        error_detail: None,
     })
 }
@@ -11,7 +11,7 @@ use chrono::Utc;
 use uuid::Uuid;
 
 /// Record a comment change to the undo stack.
-fn record_comment_undo(state: &AppState, sheet_index: usize, row: u32, col: u32, previous: Option<Comment>, description: &str) {
+pub(crate) fn record_comment_undo(state: &AppState, sheet_index: usize, row: u32, col: u32, previous: Option<Comment>, description: &str) {
     #[derive(Serialize)]
     struct CommentSnapshot {
         sheet_index: usize,
@@ -606,23 +606,31 @@ pub fn get_comment_indicators_in_range(
     comments
         .get(&active_sheet)
         .map(|sheet_comments| {
-            sheet_comments
-                .values()
-                .filter(|c| {
-                    c.row >= start_row && c.row <= end_row &&
-                    c.col >= start_col && c.col <= end_col
-                })
-                .map(|c| CommentIndicator {
-                    row: c.row,
-                    col: c.col,
-                    resolved: c.resolved,
-                    reply_count: c.replies.len(),
-                })
-                .collect()
+            comment_indicators_in_rect(sheet_comments, (start_row, start_col, end_row, end_col))
         })
         .unwrap_or_default()
 }
 
+/// Comment indicators of one sheet that fall inside a rectangle.
+pub(crate) fn comment_indicators_in_rect(
+    sheet_comments: &HashMap<(u32, u32), Comment>,
+    (start_row, start_col, end_row, end_col): (u32, u32, u32, u32),
+) -> Vec<CommentIndicator> {
+    sheet_comments
+        .values()
+        .filter(|c| {
+            c.row >= start_row && c.row <= end_row &&
+            c.col >= start_col && c.col <= end_col
+        })
+        .map(|c| CommentIndicator {
+            row: c.row,
+            col: c.col,
+            resolved: c.resolved,
+            reply_count: c.replies.len(),
+        })
+        .collect()
+}
+
 /// Set the resolved status of a comment.
 #[tauri::command]
 pub fn resolve_comment(
@@ -294,8 +294,6 @@ pub fn apply_property_value(
     row_heights: &mut HashMap<u32, f64>,
     column_widths: &mut HashMap<u32, f64>,
     grid: &mut Grid,
-    grids: &mut [Grid],
-    sheet_index: usize,
     style_registry: &mut StyleRegistry,
 ) -> (Vec<DimensionData>, bool) {
     let mut dimension_changes = Vec::new();
@@ -337,7 +335,7 @@ pub fn apply_property_value(
                     "cell" => {
                         let row = target_index;
                         let col = target_index2.unwrap_or(0);
-                        apply_fill_color(grid, grids, sheet_index, style_registry, row, col, color);
+                        apply_fill_color(grid, style_registry, row, col, color);
                         needs_style_refresh = true;
                     }
                     "column" => {
@@ -347,7 +345,7 @@ pub fn apply_property_value(
                             .filter(|&(_, c)| c == col)
                             .collect();
                         for (r, c) in cell_keys {
-                            apply_fill_color(grid, grids, sheet_index, style_registry, r, c, color.clone());
+                            apply_fill_color(grid, style_registry, r, c, color.clone());
                         }
                         needs_style_refresh = true;
                     }
@@ -357,7 +355,7 @@ pub fn apply_property_value(
                             .filter(|&(r, _)| r == row)
                             .collect();
                         for (r, c) in cell_keys {
-                            apply_fill_color(grid, grids, sheet_index, style_registry, r, c, color.clone());
+                            apply_fill_color(grid, style_registry, r, c, color.clone());
                         }
                         needs_style_refresh = true;
                     }
@@ -367,14 +365,14 @@ pub fn apply_property_value(
         }
         "fontBold" => {
             let bold = value_as_bool(value);
-            apply_style_change(target_type, target_index, target_index2, grid, grids, sheet_index, style_registry, |style| {
+            apply_style_change(target_type, target_index, target_index2, grid, style_registry, |style| {
                 style.font.bold = bold;
             });
             needs_style_refresh = true;
         }
         "fontItalic" => {
             let italic = value_as_bool(value);
-            apply_style_change(target_type, target_index, target_index2, grid, grids, sheet_index, style_registry, |style| {
+            apply_style_change(target_type, target_index, target_index2, grid, style_registry, |style| {
                 style.font.italic = italic;
             });
             needs_style_refresh = true;
@@ -382,7 +380,7 @@ pub fn apply_property_value(
         "fontSize" => {
             if let Some(size) = value_as_f64(value) {
                 let size_u8 = (size.round() as u8).max(1);
-                apply_style_change(target_type, target_index, target_index2, grid, grids, sheet_index, style_registry, |style| {
+                apply_style_change(target_type, target_index, target_index2, grid, style_registry, |style| {
                     style.font.size = size_u8;
                 });
                 needs_style_refresh = true;
@@ -391,7 +389,7 @@ pub fn apply_property_value(
         "fontFamily" => {
             let family = value_as_string(value);
             if !family.is_empty() {
-                apply_style_change(target_type, target_index, target_index2, grid, grids, sheet_index, style_registry, |style| {
+                apply_style_change(target_type, target_index, target_index2, grid, style_registry, |style| {
                     style.font.family = family.clone();
                 });
                 needs_style_refresh = true;
@@ -400,7 +398,7 @@ pub fn apply_property_value(
         "fontColor" => {
             let color_str = value_as_string(value);
             if let Some(color) = parse_color(&color_str) {
-                apply_style_change(target_type, target_index, target_index2, grid, grids, sheet_index, style_registry, |style| {
+                apply_style_change(target_type, target_index, target_index2, grid, style_registry, |style| {
                     style.font.color = color.clone();
                 });
                 needs_style_refresh = true;
@@ -409,7 +407,7 @@ pub fn apply_property_value(
         "numberFormat" => {
             let fmt_str = value_as_string(value);
             if !fmt_str.is_empty() {
-                apply_style_change(target_type, target_index, target_index2, grid, grids, sheet_index, style_registry, |style| {
+                apply_style_change(target_type, target_index, target_index2, grid, style_registry, |style| {
                     style.number_format = engine::NumberFormat::Custom { format: fmt_str.clone() };
                 });
                 needs_style_refresh = true;
@@ -418,7 +416,7 @@ pub fn apply_property_value(
         "textAlign" => {
             let align_str = value_as_string(value);
             if let Some(align) = parse_text_align(&align_str) {
-                apply_style_change(target_type, target_index, target_index2, grid, grids, sheet_index, style_registry, |style| {
+                apply_style_change(target_type, target_index, target_index2, grid, style_registry, |style| {
                     style.text_align = align;
                 });
                 needs_style_refresh = true;
@@ -436,8 +434,6 @@ pub fn apply_property_value(
 
 fn apply_fill_color(
     grid: &mut Grid,
-    grids: &mut [Grid],
-    sheet_index: usize,
     style_registry: &mut StyleRegistry,
     row: u32,
     col: u32,
@@ -459,18 +455,6 @@ fn apply_fill_color(
         new_cell.style_index = new_style_index;
         grid.set_cell(row, col, new_cell);
     }
-
-    if let Some(g) = grids.get_mut(sheet_index) {
-        if let Some(existing) = g.get_cell(row, col) {
-            let mut updated = existing.clone();
-            updated.style_index = new_style_index;
-            g.set_cell(row, col, updated);
-        } else {
-            let mut new_cell = engine::Cell::default();
-            new_cell.style_index = new_style_index;
-            g.set_cell(row, col, new_cell);
-        }
-    }
 }
 
 /// Apply a style mutation to the target cells (cell, all cells in a column, or all cells in a row).
@@ -479,8 +463,6 @@ fn apply_style_change<F>(
     target_index: u32,
     target_index2: Option<u32>,
     grid: &mut Grid,
-    grids: &mut [Grid],
-    sheet_index: usize,
     style_registry: &mut StyleRegistry,
     mutate: F,
 ) where
@@ -518,17 +500,11 @@ fn apply_style_change<F>(
         if let Some(existing) = grid.get_cell(row, col) {
             let mut updated = existing.clone();
             updated.style_index = new_style_index;
-            grid.set_cell(row, col, updated.clone());
-            if let Some(g) = grids.get_mut(sheet_index) {
-                g.set_cell(row, col, updated);
-            }
+            grid.set_cell(row, col, updated);
         } else {
             let mut new_cell = engine::Cell::default();
             new_cell.style_index = new_style_index;
-            grid.set_cell(row, col, new_cell.clone());
-            if let Some(g) = grids.get_mut(sheet_index) {
-                g.set_cell(row, col, new_cell);
-            }
+            grid.set_cell(row, col, new_cell);
         }
     }
 }
@@ -753,7 +729,6 @@ pub fn add_computed_property(
     );
     let active_sheet = *state.active_sheet.lock().unwrap();
     let grids = state.grids.lock().unwrap();
-    let grid = state.grid.lock().unwrap();
     let sheet_names = state.sheet_names.lock().unwrap();
     let styles = state.style_registry.lock().unwrap();
     let row_heights_snapshot = state.row_heights.lock().unwrap().clone();
@@ -824,12 +799,11 @@ pub fn add_computed_property(
     // Update dependency tracking
     let mut deps = state.computed_prop_dependencies.lock().unwrap();
     let mut rev_deps = state.computed_prop_dependents.lock().unwrap();
-    update_prop_dependencies(prop_id, &formula, active_sheet, &grid, &mut deps, &mut rev_deps);
+    update_prop_dependencies(prop_id, &formula, active_sheet, &grids[active_sheet], &mut deps, &mut rev_deps);
 
     // Drop locks we no longer need before applying effects
     drop(props_storage);
     drop(grids);
-    drop(grid);
     drop(sheet_names);
     drop(styles);
     drop(deps);
@@ -838,7 +812,6 @@ pub fn add_computed_property(
     // Apply the computed value to the target
     let mut rh = state.row_heights.lock().unwrap();
     let mut cw = state.column_widths.lock().unwrap();
-    let mut grid = state.grid.lock().unwrap();
     let mut grids = state.grids.lock().unwrap();
     let mut style_reg = state.style_registry.lock().unwrap();
 
@@ -850,15 +823,12 @@ pub fn add_computed_property(
         index2,
         &mut rh,
         &mut cw,
-        &mut grid,
-        &mut grids,
-        active_sheet,
+        &mut grids[active_sheet],
         &mut style_reg,
     );
 
     drop(rh);
     drop(cw);
-    drop(grid);
     drop(grids);
     drop(style_reg);
 
@@ -890,7 +860,6 @@ pub fn update_computed_property(
     );
     let active_sheet = *state.active_sheet.lock().unwrap();
     let grids = state.grids.lock().unwrap();
-    let grid = state.grid.lock().unwrap();
     let sheet_names = state.sheet_names.lock().unwrap();
     let styles = state.style_registry.lock().unwrap();
     let row_heights_snapshot = state.row_heights.lock().unwrap().clone();
@@ -961,11 +930,10 @@ pub fn update_computed_property(
     // Update dependencies
     let mut deps = state.computed_prop_dependencies.lock().unwrap();
     let mut rev_deps = state.computed_prop_dependents.lock().unwrap();
-    update_prop_dependencies(prop_id, &formula, active_sheet, &grid, &mut deps, &mut rev_deps);
+    update_prop_dependencies(prop_id, &formula, active_sheet, &grids[active_sheet], &mut deps, &mut rev_deps);
 
     drop(props_storage);
     drop(grids);
-    drop(grid);
     drop(sheet_names);
     drop(styles);
     drop(deps);
@@ -974,7 +942,6 @@ pub fn update_computed_property(
     // Apply effect
     let mut rh = state.row_heights.lock().unwrap();
     let mut cw = state.column_widths.lock().unwrap();
-    let mut grid = state.grid.lock().unwrap();
     let mut grids = state.grids.lock().unwrap();
     let mut style_reg = state.style_registry.lock().unwrap();
 
@@ -986,15 +953,12 @@ pub fn update_computed_property(
         index2,
         &mut rh,
         &mut cw,
-        &mut grid,
-        &mut grids,
-        active_sheet,
+        &mut grids[active_sheet],
         &mut style_reg,
     );
 
     drop(rh);
     drop(cw);
-    drop(grid);
     drop(grids);
     drop(style_reg);
 
@@ -1099,7 +1063,6 @@ pub fn re_evaluate_for_changed_cells(
     cp_storage: &mut ComputedPropertiesStorage,
     cp_dependents: &ComputedPropDependents,
     grids: &mut [Grid],
-    grid: &mut Grid,
     sheet_names: &[String],
     active_sheet: usize,
     row_heights: &mut HashMap<u32, f64>,
@@ -1193,7 +1156,7 @@ pub fn re_evaluate_for_changed_cells(
     for (_prop_id, attribute, target_type, index, index2, value) in &eval_results {
         let (dim_changes, style_refresh) = apply_property_value(
             attribute, value, target_type, *index, *index2,
-            row_heights, column_widths, grid, grids, active_sheet, style_registry,
+            row_heights, column_widths, &mut grids[active_sheet], style_registry,
         );
         all_dimension_changes.extend(dim_changes);
         any_style_refresh = any_style_refresh || style_refresh;
@@ -1207,7 +1170,6 @@ pub fn re_evaluate_for_changed_cells(
 pub fn re_evaluate_all_properties(
     cp_storage: &mut ComputedPropertiesStorage,
     grids: &mut [Grid],
-    grid: &mut Grid,
     sheet_names: &[String],
     sheet_index: usize,
     row_heights: &mut HashMap<u32, f64>,
@@ -1279,7 +1241,7 @@ pub fn re_evaluate_all_properties(
     for (_prop_id, attribute, target_type, index, index2, value) in &eval_results {
         let (dim_changes, style_refresh) = apply_property_value(
             attribute, value, target_type, *index, *index2,
-            row_heights, column_widths, grid, grids, sheet_index, style_registry,
+            row_heights, column_widths, &mut grids[sheet_index], style_registry,
         );
         all_dimension_changes.extend(dim_changes);
         any_style_refresh = any_style_refresh || style_refresh;
@@ -742,6 +742,17 @@ pub fn evaluate_conditional_formats(
     start_col: u32,
     end_row: u32,
     end_col: u32,
+) -> EvaluateCFResult {
+    evaluate_conditional_formats_internal(&state, start_row, start_col, end_row, end_col)
+}
+
+/// Body of `evaluate_conditional_formats` for the active sheet.
+pub(crate) fn evaluate_conditional_formats_internal(
+    state: &AppState,
+    start_row: u32,
+    start_col: u32,
+    end_row: u32,
+    end_col: u32,
 ) -> EvaluateCFResult {
     let active_sheet = *state.active_sheet.lock().unwrap();
     let cf_storage = state.conditional_formats.lock().unwrap();
@@ -758,6 +769,29 @@ pub fn evaluate_conditional_formats(
         None => return EvaluateCFResult { cells: Vec::new() },
     };
 
+    EvaluateCFResult {
+        cells: evaluate_cf_rect(
+            rules,
+            grid,
+            &grids,
+            &sheet_names,
+            active_sheet,
+            (start_row, start_col, end_row, end_col),
+        ),
+    }
+}
+
+/// Evaluate a sheet's rules for every cell of a rectangle (corners in any
+/// order). Shared by `evaluate_conditional_formats` and the viewport snapshot
+/// so both resolve overrides identically.
+pub(crate) fn evaluate_cf_rect(
+    rules: &[ConditionalFormatDefinition],
+    grid: &Grid,
+    grids: &[Grid],
+    sheet_names: &[String],
+    sheet_index: usize,
+    (start_row, start_col, end_row, end_col): (u32, u32, u32, u32),
+) -> Vec<CellConditionalFormat> {
     let min_row = start_row.min(end_row);
     let max_row = start_row.max(end_row);
     let min_col = start_col.min(end_col);
@@ -793,9 +827,9 @@ pub fn evaluate_conditional_formats(
 
                 if let Some(cf) = evaluate_rule(
                     grid,
-                    &grids,
-                    &sheet_names,
-                    active_sheet,
+                    grids,
+                    sheet_names,
+                    sheet_index,
                     &rule_def.rule,
                     &rule_def.format,
                     row,
@@ -812,7 +846,7 @@ pub fn evaluate_conditional_formats(
         }
     }
 
-    EvaluateCFResult { cells: result }
+    result
 }
 
 /// Clear conditional formats in a range
@@ -1272,6 +1306,7 @@ fn evaluate_rule(
                 sheet_names,
                 active_sheet,
                 &expr_rule.formula,
+                engine::EvalContext::at(row, col),
             );
             let truthy = match result {
                 CellValue::Number(n) => n != 0.0,
@@ -1476,7 +1511,8 @@ struct CFFormulaContext<'a> {
     active_sheet: usize,
 }
 
-/// Evaluate a formula string and return its numeric result, or a fallback value
+/// Evaluate a formula string and return its numeric result, or a fallback value.
+/// Thresholds belong to the whole range, so no single cell is current.
 fn evaluate_cf_formula(
     formula: &str,
     ctx: &CFFormulaContext,
@@ -1487,6 +1523,7 @@ fn evaluate_cf_formula(
         ctx.sheet_names,
         ctx.active_sheet,
         formula,
+        engine::EvalContext::default(),
     );
     match result {
         CellValue::Number(n) => n,
@@ -166,6 +166,7 @@ fn build_cell_data(
             crate::api_types::rich_text_runs_to_data(runs)
         }),
         accounting_layout: None,
+        error_detail: None,
     })
 }
 
@@ -415,7 +416,6 @@ pub fn consolidate_data(state: State<AppState>, params: ConsolidateParams) -> Co
     }
 
     // Acquire locks (same order as goal_seek.rs to avoid deadlocks)
-    let mut grid = state.grid.lock().unwrap();
     let mut grids = state.grids.lock().unwrap();
     let active_sheet = *state.active_sheet.lock().unwrap();
     let styles = state.style_registry.lock().unwrap();
@@ -481,10 +481,7 @@ pub fn consolidate_data(state: State<AppState>, params: ConsolidateParams) -> Co
                 let cell = Cell::new_text(header.clone());
                 let dest_r = params.dest_row;
                 let dest_c = params.dest_col + header_col_offset + c_idx as u32;
-                grids[dest_sheet].set_cell(dest_r, dest_c, cell.clone());
-                if dest_sheet == active_sheet {
-                    grid.set_cell(dest_r, dest_c, cell);
-                }
+                grids[dest_sheet].set_cell(dest_r, dest_c, cell);
             }
         }
 
@@ -494,10 +491,7 @@ pub fn consolidate_data(state: State<AppState>, params: ConsolidateParams) -> Co
                 let cell = Cell::new_text(header.clone());
                 let dest_r = params.dest_row + header_row_offset + r_idx as u32;
                 let dest_c = params.dest_col;
-                grids[dest_sheet].set_cell(dest_r, dest_c, cell.clone());
-                if dest_sheet == active_sheet {
-                    grid.set_cell(dest_r, dest_c, cell);
-                }
+                grids[dest_sheet].set_cell(dest_r, dest_c, cell);
             }
         }
 
@@ -507,10 +501,7 @@ pub fn consolidate_data(state: State<AppState>, params: ConsolidateParams) -> Co
                 let cell = Cell::new_number(value);
                 let dest_r = params.dest_row + header_row_offset + r_idx as u32;
                 let dest_c = params.dest_col + header_col_offset + c_idx as u32;
-                grids[dest_sheet].set_cell(dest_r, dest_c, cell.clone());
-                if dest_sheet == active_sheet {
-                    grid.set_cell(dest_r, dest_c, cell);
-                }
+                grids[dest_sheet].set_cell(dest_r, dest_c, cell);
             }
         }
 
@@ -574,10 +565,7 @@ pub fn consolidate_data(state: State<AppState>, params: ConsolidateParams) -> Co
             let cell = Cell::new_number(value);
             let dest_r = params.dest_row + rel_r;
             let dest_c = params.dest_col + rel_c;
-            grids[dest_sheet].set_cell(dest_r, dest_c, cell.clone());
-            if dest_sheet == active_sheet {
-                grid.set_cell(dest_r, dest_c, cell);
-            }
+            grids[dest_sheet].set_cell(dest_r, dest_c, cell);
         }
 
         // Build updated_cells
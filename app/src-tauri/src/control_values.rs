@@ -215,6 +215,7 @@ fn walk_control_names(expr: &Expression, scan: &mut ControlNameScan) {
             }
         }
         Expression::Literal(_)
+        | Expression::ArrayLiteral { .. }
         | Expression::CellRef { .. }
         | Expression::ColumnRef { .. }
         | Expression::RowRef { .. }
@@ -407,33 +408,26 @@ pub(crate) fn recalc_control_dependents_core(
     let _lookup_pass = engine::begin_lookup_pass();
     // Respect manual calculation mode — this is the dependent cascade of a
     // control mutation, and update_cell gates its cascade the same way.
-    {
-        let calc_mode = state.calculation_mode.lock().unwrap();
-        if *calc_mode != "automatic" {
-            return Ok(Vec::new());
-        }
+    if !state.calculation_settings.lock().unwrap().mode.is_automatic() {
+        return Ok(Vec::new());
     }
 
     // GET.CONTROLVALUE snapshot: built ONCE, BEFORE any grid locks (canonical
     // lock order: control stores first, grids last).
     let control_values =
         build_control_values(state, pane_control_state, ribbon_filter_state);
+    let hidden_rows =
+        crate::autofilter::sheet_hidden_rows(state, *state.active_sheet.lock().unwrap());
 
     let changed_upper: Option<HashSet<String>> = changed_names
         .map(|names| names.iter().map(|n| n.trim().to_uppercase()).collect());
 
-    // Pre-pass: sync the active-sheet mirror into grids (BUG-0016 discipline —
-    // the other-sheet pass below evaluates THROUGH grids, and other sheets may
-    // reference active-sheet cells) and detect the non-active sheets that
-    // contain GET.CONTROLVALUE formulas. Name-agnostic prefilter, like the
-    // active-sheet scan's string prefilter (conservative).
+    // Pre-pass: detect the non-active sheets that contain GET.CONTROLVALUE
+    // formulas. Name-agnostic prefilter, like the active-sheet scan's string
+    // prefilter (conservative).
     let (control_sheets, prepass_active_sheet) = {
-        let grid = state.grid.lock().unwrap();
-        let mut grids = state.grids.lock().unwrap();
+        let grids = state.grids.lock().unwrap();
         let active_sheet = *state.active_sheet.lock().unwrap();
-        if active_sheet < grids.len() {
-            grids[active_sheet] = grid.clone();
-        }
         let list: Vec<usize> = grids
             .iter()
             .enumerate()
@@ -520,17 +514,9 @@ pub(crate) fn recalc_control_dependents_core(
     let updated_cells = {
         let user_files = user_files_state.files.lock().unwrap();
         let sheet_names = state.sheet_names.lock().unwrap();
-        let mut grid = state.grid.lock().unwrap();
         let mut grids = state.grids.lock().unwrap();
         let active_sheet = *state.active_sheet.lock().unwrap();
 
-        // The active-sheet mirror (state.grid) is the source of truth; grids[i]
-        // can lag behind it (BUG-0016, see calculate_now). Sync before scanning
-        // and evaluating.
-        if active_sheet < grids.len() {
-            grids[active_sheet] = grid.clone();
-        }
-
         let styles = state.style_registry.lock().unwrap();
         let dependents_map = state.dependents.lock().unwrap();
         let column_dependents_map = state.column_dependents.lock().unwrap();
@@ -546,7 +532,7 @@ pub(crate) fn recalc_control_dependents_core(
 
         // Scan the active sheet: string prefilter, then AST walk. Sorted for a
         // deterministic seed order (HashMap iteration is not).
-        let mut scan_hits: Vec<((u32, u32), ControlNameScan)> = grid
+        let mut scan_hits: Vec<((u32, u32), ControlNameScan)> = grids[active_sheet]
             .cells
             .iter()
             .filter_map(|(&(row, col), cell)| {
@@ -621,12 +607,11 @@ pub(crate) fn recalc_control_dependents_core(
             affected.len() <= crate::commands::data::CASCADE_FORMULA_LIMIT;
 
         for &(row, col) in &affected {
-            let cell_opt = grid.get_cell(row, col).cloned();
+            let cell_opt = grids[active_sheet].get_cell(row, col).cloned();
             if let Some(cell) = cell_opt {
                 if let Some(formula) = cell.formula_string() {
                     crate::commands::data::reevaluate_formula_cell(
                         state,
-                        &mut grid,
                         &mut grids,
                         &sheet_names,
                         active_sheet,
@@ -646,6 +631,7 @@ pub(crate) fn recalc_control_dependents_core(
                         // prefetch invariant via preserved_cube_value).
                         None,
                         Some(&control_values),
+                        &hidden_rows,
                         &styles,
                         &locale,
                         &merge_lookup,
@@ -674,7 +660,6 @@ pub(crate) fn recalc_control_dependents_core(
                 .collect()
         };
         crate::commands::data::cascade_cross_sheet_dependents(
-            &mut grid,
             &mut grids,
             &sheet_names,
             active_sheet,
@@ -711,6 +696,7 @@ mod tests {
             value: engine::CellValue::Empty,
             style_index: 0,
             rich_text: None,
+            quote_prefix: false,
         }
     }
 
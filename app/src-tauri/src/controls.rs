@@ -348,12 +348,12 @@ pub fn resolve_control_properties(
     let grids = state.grids.lock().unwrap();
     let sheet_names = state.sheet_names.lock().unwrap();
 
-    // Build evaluator once for all formulas
+    // Build evaluator once for all formulas, positioned at the control's anchor cell
     let evaluator = if sheet_index < grids.len() && sheet_index < sheet_names.len() {
         let current_grid = &grids[sheet_index];
         let current_sheet_name = &sheet_names[sheet_index];
         let context = create_multi_sheet_context(&grids, &sheet_names, current_sheet_name);
-        Some(Evaluator::with_multi_sheet(current_grid, context))
+        Some(Evaluator::with_context(current_grid, context, engine::EvalContext::at(row, col)))
     } else {
         None
     };
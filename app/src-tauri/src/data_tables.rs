@@ -10,6 +10,7 @@ use crate::api_types::{
     CellData, DataTableCell, DataTableOneVarParams, DataTableResult, DataTableTwoVarParams,
     MergedRegion,
 };
+use crate::sheet_access::check_sheet_index;
 use crate::{evaluate_formula_multi_sheet, format_cell_value, AppState};
 use engine::{Cell, CellValue, Grid, StyleRegistry};
 
@@ -49,6 +50,7 @@ fn build_cell_data(
         sheet_index: None,
         rich_text: None,
         accounting_layout: None,
+        error_detail: None,
     })
 }
 
@@ -100,6 +102,10 @@ pub fn data_table_one_var(
     state: State<AppState>,
     params: DataTableOneVarParams,
 ) -> DataTableResult {
+    data_table_one_var_internal(&state, params)
+}
+
+pub(crate) fn data_table_one_var_internal(state: &AppState, params: DataTableOneVarParams) -> DataTableResult {
     crate::log_info!(
         "DATATABLE",
         "One-var: range=({},{})..({},{}) sheet={}",
@@ -110,15 +116,20 @@ pub fn data_table_one_var(
         params.sheet_index
     );
 
-    let mut grid = state.grid.lock().unwrap();
     let mut grids = state.grids.lock().unwrap();
-    let active_sheet = *state.active_sheet.lock().unwrap();
     let sheet_names = state.sheet_names.lock().unwrap();
     let styles = state.style_registry.lock().unwrap();
     let merged_regions = state.merged_regions.lock().unwrap();
     let locale = state.locale.lock().unwrap();
 
     let sheet_idx = params.sheet_index;
+    if let Err(e) = check_sheet_index(grids.len(), sheet_idx) {
+        return DataTableResult {
+            cells: Vec::new(),
+            updated_cells: Vec::new(),
+            error: Some(e.to_string()),
+        };
+    }
 
     // Determine orientation
     let is_col_input = params.col_input_row.is_some() && params.col_input_col.is_some();
@@ -168,8 +179,10 @@ pub fn data_table_one_var(
             for (fi, formula_opt) in formulas.iter().enumerate() {
                 let c = params.start_col + 1 + fi as u32;
                 if let Some(formula) = formula_opt {
+                    // Evaluated at its own header cell, as the table substitutes inputs
                     let eval_result = evaluate_formula_multi_sheet(
                         &grids, &sheet_names, sheet_idx, formula,
+                        engine::EvalContext::at(params.start_row, c),
                     );
                     let display = cell_value_to_string(&eval_result);
                     let numeric = cell_value_to_f64(&eval_result);
@@ -190,19 +203,13 @@ pub fn data_table_one_var(
                         _ => Cell::new_text(display.clone()),
                     };
                     new_cell.style_index = style_index;
-                    grids[sheet_idx].set_cell(r, c, new_cell.clone());
-                    if sheet_idx == active_sheet {
-                        grid.set_cell(r, c, new_cell);
-                    }
+                    grids[sheet_idx].set_cell(r, c, new_cell);
                 }
             }
         }
 
         // Restore original input cell
         restore_cell(&mut grids[sheet_idx], input_row, input_col, &original_input);
-        if sheet_idx == active_sheet {
-            restore_cell(&mut grid, input_row, input_col, &original_input);
-        }
 
     } else {
         // Row-oriented: input values across the top row
@@ -238,6 +245,7 @@ pub fn data_table_one_var(
                 if let Some(formula) = formula_opt {
                     let eval_result = evaluate_formula_multi_sheet(
                         &grids, &sheet_names, sheet_idx, formula,
+                        engine::EvalContext::at(r, params.start_col),
                     );
                     let display = cell_value_to_string(&eval_result);
                     let numeric = cell_value_to_f64(&eval_result);
@@ -257,19 +265,13 @@ pub fn data_table_one_var(
                         _ => Cell::new_text(display.clone()),
                     };
                     new_cell.style_index = style_index;
-                    grids[sheet_idx].set_cell(r, c, new_cell.clone());
-                    if sheet_idx == active_sheet {
-                        grid.set_cell(r, c, new_cell);
-                    }
+                    grids[sheet_idx].set_cell(r, c, new_cell);
                 }
             }
         }
 
         // Restore original input cell
         restore_cell(&mut grids[sheet_idx], input_row, input_col, &original_input);
-        if sheet_idx == active_sheet {
-            restore_cell(&mut grid, input_row, input_col, &original_input);
-        }
     }
 
     // Build updated cells for grid refresh
@@ -282,13 +284,7 @@ pub fn data_table_one_var(
     }
 
     // Re-evaluate formulas back with restored input
-    re_evaluate_formulas(
-        &mut grid,
-        &mut grids,
-        &sheet_names,
-        sheet_idx,
-        active_sheet,
-    );
+    re_evaluate_formulas(&mut grids, &sheet_names, sheet_idx);
 
     DataTableResult {
         cells: result_cells,
@@ -313,6 +309,10 @@ pub fn data_table_two_var(
     state: State<AppState>,
     params: DataTableTwoVarParams,
 ) -> DataTableResult {
+    data_table_two_var_internal(&state, params)
+}
+
+pub(crate) fn data_table_two_var_internal(state: &AppState, params: DataTableTwoVarParams) -> DataTableResult {
     crate::log_info!(
         "DATATABLE",
         "Two-var: range=({},{})..({},{}) sheet={}",
@@ -323,15 +323,20 @@ pub fn data_table_two_var(
         params.sheet_index
     );
 
-    let mut grid = state.grid.lock().unwrap();
     let mut grids = state.grids.lock().unwrap();
-    let active_sheet = *state.active_sheet.lock().unwrap();
     let sheet_names = state.sheet_names.lock().unwrap();
     let styles = state.style_registry.lock().unwrap();
     let merged_regions = state.merged_regions.lock().unwrap();
     let locale = state.locale.lock().unwrap();
 
     let sheet_idx = params.sheet_index;
+    if let Err(e) = check_sheet_index(grids.len(), sheet_idx) {
+        return DataTableResult {
+            cells: Vec::new(),
+            updated_cells: Vec::new(),
+            error: Some(e.to_string()),
+        };
+    }
 
     // The formula is in the top-left cell
     let formula = match grids[sheet_idx]
@@ -387,9 +392,14 @@ pub fn data_table_two_var(
                 &row_input_val,
             );
 
-            // Evaluate the formula
-            let eval_result =
-                evaluate_formula_multi_sheet(&grids, &sheet_names, sheet_idx, &formula);
+            // Evaluate the formula at its corner cell
+            let eval_result = evaluate_formula_multi_sheet(
+                &grids,
+                &sheet_names,
+                sheet_idx,
+                &formula,
+                engine::EvalContext::at(params.start_row, params.start_col),
+            );
             let display = cell_value_to_string(&eval_result);
             let numeric = cell_value_to_f64(&eval_result);
 
@@ -409,10 +419,7 @@ pub fn data_table_two_var(
                 _ => Cell::new_text(display.clone()),
             };
             new_cell.style_index = style_index;
-            grids[sheet_idx].set_cell(r, c, new_cell.clone());
-            if sheet_idx == active_sheet {
-                grid.set_cell(r, c, new_cell);
-            }
+            grids[sheet_idx].set_cell(r, c, new_cell);
         }
     }
 
@@ -429,10 +436,6 @@ pub fn data_table_two_var(
         params.col_input_col,
         &original_col_input,
     );
-    if sheet_idx == active_sheet {
-        restore_cell(&mut grid, params.row_input_row, params.row_input_col, &original_row_input);
-        restore_cell(&mut grid, params.col_input_row, params.col_input_col, &original_col_input);
-    }
 
     // Build updated cells
     let mut updated_cells = Vec::new();
@@ -445,13 +448,7 @@ pub fn data_table_two_var(
     }
 
     // Re-evaluate with restored input values
-    re_evaluate_formulas(
-        &mut grid,
-        &mut grids,
-        &sheet_names,
-        sheet_idx,
-        active_sheet,
-    );
+    re_evaluate_formulas(&mut grids, &sheet_names, sheet_idx);
 
     DataTableResult {
         cells: result_cells,
@@ -489,13 +486,7 @@ fn restore_cell(grid: &mut Grid, row: u32, col: u32, original: &Option<Cell>) {
 /// Re-evaluate formula cells after data table computation.
 /// This is a simplified recalc for the formula cells that may have been
 /// affected by the temporary input value changes.
-fn re_evaluate_formulas(
-    grid: &mut Grid,
-    grids: &mut [Grid],
-    sheet_names: &[String],
-    sheet_idx: usize,
-    active_sheet: usize,
-) {
+fn re_evaluate_formulas(grids: &mut [Grid], sheet_names: &[String], sheet_idx: usize) {
     // Walk through all cells in the sheet and re-evaluate any formula cells
     // This is a simple approach; a production system would use the dependency graph
     let max_row = grids[sheet_idx].max_row;
@@ -507,13 +498,11 @@ fn re_evaluate_formulas(
                     if let Some(formula) = cell.formula_string() {
                         let new_value = evaluate_formula_multi_sheet(
                             grids, sheet_names, sheet_idx, &formula,
+                            engine::EvalContext::at(r, c),
                         );
                         let mut updated = cell;
                         updated.value = new_value;
-                        grids[sheet_idx].set_cell(r, c, updated.clone());
-                        if sheet_idx == active_sheet {
-                            grid.set_cell(r, c, updated);
-                        }
+                        grids[sheet_idx].set_cell(r, c, updated);
                     }
                 }
             }
@@ -4,6 +4,7 @@
 //! TextLength, Custom), operators, error alerts, and input prompts.
 
 use crate::AppState;
+use crate::tables::{ColumnDataType, TypeCheck};
 use engine::{CellValue, Grid};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
@@ -311,6 +312,10 @@ pub struct InvalidCellsResult {
 pub struct CellValidationResult {
     pub is_valid: bool,
     pub error_alert: Option<DataValidationErrorAlert>,
+    /// Replacement input produced by a table column's Coerce data type
+    /// policy; the caller commits this instead of what was typed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub coerced_value: Option<String>,
 }
 
 /// Parameters for setting validation on a range.
@@ -546,6 +551,96 @@ pub fn resolve_list_source(
     }
 }
 
+/// Rewrite `ranges` so the rule on every cell of columns `min_col..=max_col`
+/// follows its row through `row_moves` (original row -> target row, covering
+/// a contiguous block of rows). Ranges are clipped around the block and the
+/// block is re-covered row by row, so rules that only apply to some rows end
+/// up on the right ones. Returns `None` when no cell's rule would change
+/// (e.g. a rule spanning the whole block).
+pub(crate) fn permute_validation_rows(
+    ranges: &[ValidationRange],
+    min_col: u32,
+    max_col: u32,
+    row_moves: &HashMap<u32, u32>,
+) -> Option<Vec<ValidationRange>> {
+    let lo = *row_moves.keys().min()?;
+    let hi = *row_moves.keys().max()?;
+    let origin_of: HashMap<u32, u32> = row_moves.iter().map(|(from, to)| (*to, *from)).collect();
+
+    // Index of the first range covering a cell (first match wins on lookup)
+    let rule_at = |row: u32, col: u32| {
+        ranges.iter().position(|vr| {
+            row >= vr.start_row && row <= vr.end_row && col >= vr.start_col && col <= vr.end_col
+        })
+    };
+    let changed = (lo..=hi).any(|row| {
+        let origin = origin_of.get(&row).copied().unwrap_or(row);
+        origin != row && (min_col..=max_col).any(|col| rule_at(origin, col) != rule_at(row, col))
+    });
+    if !changed {
+        return None;
+    }
+
+    let piece = |vr: &ValidationRange, start_row, end_row, start_col, end_col| ValidationRange {
+        start_row,
+        start_col,
+        end_row,
+        end_col,
+        validation: vr.validation.clone(),
+    };
+    let mut result = Vec::new();
+    for (index, vr) in ranges.iter().enumerate() {
+        let overlaps = vr.start_row <= hi && vr.end_row >= lo
+            && vr.start_col <= max_col && vr.end_col >= min_col;
+        if !overlaps {
+            result.push(vr.clone());
+            continue;
+        }
+
+        // The parts outside the block stay where they are
+        if vr.start_row < lo {
+            result.push(piece(vr, vr.start_row, lo - 1, vr.start_col, vr.end_col));
+        }
+        if vr.end_row > hi {
+            result.push(piece(vr, hi + 1, vr.end_row, vr.start_col, vr.end_col));
+        }
+        let (band_start, band_end) = (vr.start_row.max(lo), vr.end_row.min(hi));
+        if vr.start_col < min_col {
+            result.push(piece(vr, band_start, band_end, vr.start_col, min_col - 1));
+        }
+        if vr.end_col > max_col {
+            result.push(piece(vr, band_start, band_end, max_col + 1, vr.end_col));
+        }
+
+        // Inside the block, cover the cells whose original row carried this
+        // rule, merging identical column runs on consecutive rows.
+        let mut runs: Vec<ValidationRange> = Vec::new();
+        for row in lo..=hi {
+            let origin = origin_of.get(&row).copied().unwrap_or(row);
+            let mut col = min_col;
+            while col <= max_col {
+                if rule_at(origin, col) != Some(index) {
+                    col += 1;
+                    continue;
+                }
+                let run_start = col;
+                while col < max_col && rule_at(origin, col + 1) == Some(index) {
+                    col += 1;
+                }
+                match runs.iter_mut().find(|r| {
+                    r.end_row + 1 == row && r.start_col == run_start && r.end_col == col
+                }) {
+                    Some(run) => run.end_row = row,
+                    None => runs.push(piece(vr, row, row, run_start, col)),
+                }
+                col += 1;
+            }
+        }
+        result.extend(runs);
+    }
+    Some(result)
+}
+
 // ============================================================================
 // TAURI COMMANDS
 // ============================================================================
@@ -707,6 +802,7 @@ pub fn validate_cell(
             return CellValidationResult {
                 is_valid: true,
                 error_alert: None,
+                coerced_value: None,
             };
         }
     };
@@ -729,9 +825,15 @@ pub fn validate_cell(
         resolve_list_source(source, grids_ref, sheet_names_ref, active_sheet)
     };
 
-    // Create a formula evaluator for custom validation
+    // Create a formula evaluator for custom validation, positioned at the cell
     let formula_eval = |formula: &str| -> CellValue {
-        crate::evaluate_formula_multi_sheet(grids_ref, sheet_names_ref, active_sheet, formula)
+        crate::evaluate_formula_multi_sheet(
+            grids_ref,
+            sheet_names_ref,
+            active_sheet,
+            formula,
+            engine::EvalContext::at(row, col),
+        )
     };
 
     let is_valid = validate_cell_value(&cell_value, &validation, Some(&resolver), Some(&formula_eval));
@@ -743,6 +845,7 @@ pub fn validate_cell(
         } else {
             None
         },
+        coerced_value: None,
     }
 }
 
@@ -791,11 +894,6 @@ pub fn get_invalid_cells(
                 resolve_list_source(source, grids_ref, sheet_names_ref, active_sheet)
             };
 
-            // Create a formula evaluator for custom validation
-            let formula_eval = |formula: &str| -> CellValue {
-                crate::evaluate_formula_multi_sheet(grids_ref, sheet_names_ref, active_sheet, formula)
-            };
-
             // Check each validation range
             for vr in sheet_validations {
                 for row in vr.start_row..=vr.end_row {
@@ -806,6 +904,17 @@ pub fn get_invalid_cells(
                             .map(|c| c.value.clone())
                             .unwrap_or(CellValue::Empty);
 
+                        // Custom validation formulas evaluate at the cell being checked
+                        let formula_eval = |formula: &str| -> CellValue {
+                            crate::evaluate_formula_multi_sheet(
+                                grids_ref,
+                                sheet_names_ref,
+                                active_sheet,
+                                formula,
+                                engine::EvalContext::at(row, col),
+                            )
+                        };
+
                         if !validate_cell_value(&cell_value, &vr.validation, Some(&resolver), Some(&formula_eval)) {
                             invalid_cells.push((row, col));
                         }
@@ -872,16 +981,56 @@ pub fn has_in_cell_dropdown(
     false
 }
 
+/// Stop alert for input that does not match a table column's data type.
+fn column_type_alert(data_type: ColumnDataType) -> DataValidationErrorAlert {
+    let accepted = match data_type {
+        ColumnDataType::Number => "numbers",
+        ColumnDataType::Date => "dates",
+        ColumnDataType::Text => "text",
+        ColumnDataType::Boolean => "TRUE or FALSE",
+    };
+    DataValidationErrorAlert {
+        title: "Column data type".to_string(),
+        message: format!("This table column only accepts {}.", accepted),
+        ..Default::default()
+    }
+}
+
 /// Validate a pending (not yet committed) value against a cell's validation rule.
 /// This is used by the commit guard system to validate before writing to the grid.
+/// Table column data types are enforced first: a rejected value fails with a
+/// Stop alert, and a coerced value is validated and reported in `coerced_value`.
 #[tauri::command]
 pub fn validate_pending_value(
     state: State<AppState>,
     row: u32,
     col: u32,
     pending_value: String,
+) -> CellValidationResult {
+    validate_pending_value_internal(&state, row, col, pending_value)
+}
+
+pub(crate) fn validate_pending_value_internal(
+    state: &AppState,
+    row: u32,
+    col: u32,
+    pending_value: String,
 ) -> CellValidationResult {
     let active_sheet = *state.active_sheet.lock().unwrap();
+
+    let (pending_value, coerced_value) =
+        match crate::tables::check_table_column_type(state, active_sheet, row, col, &pending_value) {
+            TypeCheck::Accept => (pending_value, None),
+            TypeCheck::Coerce(value) => (value.clone(), Some(value)),
+            TypeCheck::Reject(data_type) => {
+                return CellValidationResult {
+                    is_valid: false,
+                    error_alert: Some(column_type_alert(data_type)),
+                    coerced_value: None,
+                };
+            }
+        };
+
     let validations = state.data_validations.lock().unwrap();
     let grids = state.grids.lock().unwrap();
     let sheet_names = state.sheet_names.lock().unwrap();
@@ -899,6 +1048,7 @@ pub fn validate_pending_value(
             return CellValidationResult {
                 is_valid: true,
                 error_alert: None,
+                coerced_value,
             };
         }
     };
@@ -911,7 +1061,10 @@ pub fn validate_pending_value(
         return CellValidationResult {
             is_valid: true,
             error_alert: None,
+            coerced_value,
         };
+    } else if let Some(literal) = pending_value.strip_prefix('\'') {
+        CellValue::Text(literal.to_string())
     } else if let Ok(n) = pending_value.parse::<f64>() {
         CellValue::Number(n)
     } else if pending_value.eq_ignore_ascii_case("true") {
@@ -929,9 +1082,15 @@ pub fn validate_pending_value(
         resolve_list_source(source, grids_ref, sheet_names_ref, active_sheet)
     };
 
-    // Create a formula evaluator for custom validation
+    // Create a formula evaluator for custom validation, positioned at the cell
     let formula_eval = |formula: &str| -> CellValue {
-        crate::evaluate_formula_multi_sheet(grids_ref, sheet_names_ref, active_sheet, formula)
+        crate::evaluate_formula_multi_sheet(
+            grids_ref,
+            sheet_names_ref,
+            active_sheet,
+            formula,
+            engine::EvalContext::at(row, col),
+        )
     };
 
     let is_valid = validate_cell_value(&cell_value, &validation, Some(&resolver), Some(&formula_eval));
@@ -943,5 +1102,6 @@ pub fn validate_pending_value(
         } else {
             None
         },
+        coerced_value: if is_valid { coerced_value } else { None },
     }
 }
@@ -40,7 +40,7 @@ pub fn get_error_indicators(
     end_row: u32,
     end_col: u32,
 ) -> Vec<CellErrorIndicator> {
-    let grid = state.grid.lock().unwrap();
+    let grid = state.get_active_grid();
     let mut indicators = Vec::new();
 
     for row in start_row..=end_row {
@@ -72,6 +72,8 @@ pub fn get_error_indicators(
                             engine::CellError::NA => "#N/A".to_string(),
                             engine::CellError::Conflict => "#CONFLICT".to_string(),
                             engine::CellError::Blocked => "#BLOCKED!".to_string(),
+                            engine::CellError::Spill => "#SPILL!".to_string(),
+                            engine::CellError::Num => "#NUM!".to_string(),
                             other => format!("#{:?}", other).to_uppercase(),
                         };
                         indicators.push(CellErrorIndicator {
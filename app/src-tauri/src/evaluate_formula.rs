@@ -8,14 +8,15 @@ use std::collections::HashMap;
 use std::sync::Mutex;
 
 use engine::{
-    BinaryOperator, BuiltinFunction, Evaluator, Expression,
+    BinaryOperator, BuiltinFunction, CellValue, EvalResult, Evaluator, Expression,
     UnaryOperator, Value,
 };
 use engine::coord::{col_to_index, index_to_col};
 use parser::parse as parse_formula;
 use tauri::State;
 
-use crate::api_types::EvalStepState;
+use crate::api_types::{EvalNodeExpansion, EvalNodeInfo, EvalStepState, EvalValuePreview};
+use crate::sheet_access::{check_sheet_index, ApiError};
 use crate::{convert_expr, AppState};
 
 // ============================================================================
@@ -48,6 +49,27 @@ impl EvalFormulaState {
 // Session Data Structures
 // ============================================================================
 
+/// Values shown in a preview before the dialog has to ask for more.
+const PREVIEW_LEN: usize = 10;
+/// Largest page `eval_formula_expand_node` hands out at once.
+const MAX_EXPAND_PAGE: usize = 10_000;
+
+/// Limits that keep a session on a volatile or enormous formula from hanging
+/// the app. Exhausting either one stops the session until Restart.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct EvalBudget {
+    /// Evaluate / Step Out actions per session.
+    pub(crate) max_steps: usize,
+    /// Referenced cells the evaluated nodes may cover in total.
+    pub(crate) max_cells: u64,
+}
+
+impl Default for EvalBudget {
+    fn default() -> Self {
+        EvalBudget { max_steps: 10_000, max_cells: 50_000_000 }
+    }
+}
+
 /// A single frame in the evaluation stack (for Step In / Step Out).
 struct StepFrame {
     /// Cell reference for display (e.g., "$A$1")
@@ -61,12 +83,30 @@ struct StepFrame {
     original_formula: String,
     /// The working AST - nodes get replaced with Literals as they are resolved
     ast: Expression,
+    /// Evaluated nodes whose result is an array. A Literal only holds a
+    /// scalar, so these stay in `ast` and the stepper skips over them.
+    arrays: HashMap<Vec<usize>, EvalResult>,
+}
+
+impl StepFrame {
+    fn new(cell_ref: String, row: u32, col: u32, sheet_index: usize, original_formula: String, ast: Expression) -> Self {
+        StepFrame { cell_ref, row, col, sheet_index, original_formula, ast, arrays: HashMap::new() }
+    }
+
+    fn next_node(&self) -> Option<NextNode> {
+        find_next_unresolved(&self.ast, &|path| self.arrays.contains_key(path))
+    }
 }
 
 /// A step-evaluation session.
-struct EvalSession {
+pub(crate) struct EvalSession {
     /// Stack of frames; last element is the current frame
     frames: Vec<StepFrame>,
+    budget: EvalBudget,
+    steps_used: usize,
+    cells_used: u64,
+    /// Set once the budget ran out; only Restart clears it.
+    exhausted: bool,
 }
 
 // ============================================================================
@@ -87,13 +127,27 @@ pub(crate) struct NextNode {
 /// Returns None if the entire AST is a single Literal (evaluation complete).
 pub(crate) fn find_next_eval_node(expr: &Expression) -> Option<NextNode> {
     let mut path = Vec::new();
-    find_next_recursive(expr, &mut path)
+    find_next_recursive(expr, &mut path, &|_| false)
+}
+
+/// Like `find_next_eval_node`, but nodes for which `resolved` holds (array
+/// results kept in place) count as evaluated.
+fn find_next_unresolved(expr: &Expression, resolved: &dyn Fn(&[usize]) -> bool) -> Option<NextNode> {
+    let mut path = Vec::new();
+    find_next_recursive(expr, &mut path, resolved)
 }
 
-fn find_next_recursive(expr: &Expression, path: &mut Vec<usize>) -> Option<NextNode> {
+fn find_next_recursive(
+    expr: &Expression,
+    path: &mut Vec<usize>,
+    resolved: &dyn Fn(&[usize]) -> bool,
+) -> Option<NextNode> {
+    if resolved(path) {
+        return None;
+    }
     match expr {
         // Already resolved - nothing to evaluate
-        Expression::Literal(_) => None,
+        Expression::Literal(_) | Expression::ArrayLiteral { .. } => None,
 
         // Cell references need resolving
         Expression::CellRef { sheet, col, row, .. } => Some(NextNode {
@@ -114,13 +168,13 @@ fn find_next_recursive(expr: &Expression, path: &mut Vec<usize>) -> Option<NextN
         // Binary operations: check left, then right, then this node
         Expression::BinaryOp { left, right, .. } => {
             path.push(0);
-            if let Some(result) = find_next_recursive(left, path) {
+            if let Some(result) = find_next_recursive(left, path, resolved) {
                 return Some(result);
             }
             path.pop();
 
             path.push(1);
-            if let Some(result) = find_next_recursive(right, path) {
+            if let Some(result) = find_next_recursive(right, path, resolved) {
                 return Some(result);
             }
             path.pop();
@@ -136,7 +190,7 @@ fn find_next_recursive(expr: &Expression, path: &mut Vec<usize>) -> Option<NextN
         // Unary operations: check operand, then this node
         Expression::UnaryOp { operand, .. } => {
             path.push(0);
-            if let Some(result) = find_next_recursive(operand, path) {
+            if let Some(result) = find_next_recursive(operand, path, resolved) {
                 return Some(result);
             }
             path.pop();
@@ -166,13 +220,13 @@ fn find_next_recursive(expr: &Expression, path: &mut Vec<usize>) -> Option<NextN
         // IndexAccess: recurse into target first, then index
         Expression::IndexAccess { target, index } => {
             path.push(0);
-            if let Some(result) = find_next_recursive(target, path) {
+            if let Some(result) = find_next_recursive(target, path, resolved) {
                 return Some(result);
             }
             path.pop();
 
             path.push(1);
-            if let Some(result) = find_next_recursive(index, path) {
+            if let Some(result) = find_next_recursive(index, path, resolved) {
                 return Some(result);
             }
             path.pop();
@@ -188,7 +242,7 @@ fn find_next_recursive(expr: &Expression, path: &mut Vec<usize>) -> Option<NextN
         Expression::ListLiteral { elements } => {
             for (i, elem) in elements.iter().enumerate() {
                 path.push(i);
-                if let Some(result) = find_next_recursive(elem, path) {
+                if let Some(result) = find_next_recursive(elem, path, resolved) {
                     return Some(result);
                 }
                 path.pop();
@@ -204,13 +258,13 @@ fn find_next_recursive(expr: &Expression, path: &mut Vec<usize>) -> Option<NextN
         Expression::DictLiteral { entries } => {
             for (i, (key, value)) in entries.iter().enumerate() {
                 path.push(i * 2);
-                if let Some(result) = find_next_recursive(key, path) {
+                if let Some(result) = find_next_recursive(key, path, resolved) {
                     return Some(result);
                 }
                 path.pop();
 
                 path.push(i * 2 + 1);
-                if let Some(result) = find_next_recursive(value, path) {
+                if let Some(result) = find_next_recursive(value, path, resolved) {
                     return Some(result);
                 }
                 path.pop();
@@ -235,7 +289,7 @@ fn find_next_recursive(expr: &Expression, path: &mut Vec<usize>) -> Option<NextN
                 // IF: evaluate condition first (skip range-like conditions)
                 if !matches!(&args[0], Expression::Range { .. } | Expression::ColumnRef { .. } | Expression::RowRef { .. }) {
                     path.push(0);
-                    if let Some(result) = find_next_recursive(&args[0], path) {
+                    if let Some(result) = find_next_recursive(&args[0], path, resolved) {
                         return Some(result);
                     }
                     path.pop();
@@ -254,7 +308,7 @@ fn find_next_recursive(expr: &Expression, path: &mut Vec<usize>) -> Option<NextN
                         // Skip range-like branch args
                         if !matches!(&args[branch_idx], Expression::Range { .. } | Expression::ColumnRef { .. } | Expression::RowRef { .. }) {
                             path.push(branch_idx);
-                            if let Some(result) = find_next_recursive(&args[branch_idx], path) {
+                            if let Some(result) = find_next_recursive(&args[branch_idx], path, resolved) {
                                 return Some(result);
                             }
                             path.pop();
@@ -281,7 +335,7 @@ fn find_next_recursive(expr: &Expression, path: &mut Vec<usize>) -> Option<NextN
                         _ => {}
                     }
                     path.push(i);
-                    if let Some(result) = find_next_recursive(arg, path) {
+                    if let Some(result) = find_next_recursive(arg, path, resolved) {
                         return Some(result);
                     }
                     path.pop();
@@ -349,9 +403,19 @@ pub(crate) fn get_node<'a>(ast: &'a Expression, path: &[usize]) -> &'a Expressio
 /// Convert the AST to display with proper underline tracking.
 /// Returns (display_string, underline_start, underline_end).
 pub(crate) fn build_display(ast: &Expression, target_path: &[usize]) -> (String, usize, usize) {
+    build_display_with(ast, target_path, &HashMap::new())
+}
+
+/// `build_display`, with the nodes in `overrides` shown as the given text
+/// (e.g. an evaluated array as its preview).
+fn build_display_with(
+    ast: &Expression,
+    target_path: &[usize],
+    overrides: &HashMap<Vec<usize>, String>,
+) -> (String, usize, usize) {
     let mut result = String::new();
     let mut underline = (0_usize, 0_usize);
-    build_display_recursive(ast, target_path, &[], &mut result, &mut underline);
+    build_display_recursive(ast, target_path, &[], &mut result, &mut underline, overrides);
     (result, underline.0, underline.1)
 }
 
@@ -361,15 +425,28 @@ pub(crate) fn build_display_recursive(
     current_path: &[usize],
     output: &mut String,
     underline: &mut (usize, usize),
+    overrides: &HashMap<Vec<usize>, String>,
 ) {
     let is_target = current_path == target_path;
     let start_pos = output.len();
 
+    if let Some(text) = overrides.get(current_path) {
+        output.push_str(text);
+        if is_target {
+            *underline = (start_pos, output.len());
+        }
+        return;
+    }
+
     match expr {
         Expression::Literal(val) => {
             output.push_str(&value_to_display(val));
         }
 
+        Expression::ArrayLiteral { rows } => {
+            output.push_str(&array_literal_display(rows));
+        }
+
         Expression::CellRef { sheet, col, row, .. } => {
             if let Some(sheet_name) = sheet {
                 if sheet_name.contains(' ') {
@@ -392,11 +469,11 @@ pub(crate) fn build_display_recursive(
             }
             let mut child_path = current_path.to_vec();
             child_path.push(0);
-            build_display_recursive(start, target_path, &child_path, output, underline);
+            build_display_recursive(start, target_path, &child_path, output, underline, overrides);
             output.push(':');
             child_path.pop();
             child_path.push(1);
-            build_display_recursive(end, target_path, &child_path, output, underline);
+            build_display_recursive(end, target_path, &child_path, output, underline, overrides);
         }
 
         Expression::ColumnRef { sheet, start_col, end_col, .. } => {
@@ -420,7 +497,7 @@ pub(crate) fn build_display_recursive(
         Expression::BinaryOp { left, op, right } => {
             let mut child_path = current_path.to_vec();
             child_path.push(0);
-            build_display_recursive(left, target_path, &child_path, output, underline);
+            build_display_recursive(left, target_path, &child_path, output, underline, overrides);
 
             let op_str = match op {
                 BinaryOperator::Add => "+",
@@ -440,18 +517,26 @@ pub(crate) fn build_display_recursive(
 
             child_path.pop();
             child_path.push(1);
-            build_display_recursive(right, target_path, &child_path, output, underline);
+            build_display_recursive(right, target_path, &child_path, output, underline, overrides);
         }
 
         Expression::UnaryOp { op, operand } => {
             let op_str = match op {
                 UnaryOperator::Negate => "-",
+                UnaryOperator::Plus => "+",
+                UnaryOperator::Percent => "%",
             };
-            output.push_str(op_str);
+            if !op.is_postfix() {
+                output.push_str(op_str);
+            }
 
             let mut child_path = current_path.to_vec();
             child_path.push(0);
-            build_display_recursive(operand, target_path, &child_path, output, underline);
+            build_display_recursive(operand, target_path, &child_path, output, underline, overrides);
+
+            if op.is_postfix() {
+                output.push_str(op_str);
+            }
         }
 
         Expression::FunctionCall { func, args, .. } => {
@@ -463,7 +548,7 @@ pub(crate) fn build_display_recursive(
                 }
                 let mut child_path = current_path.to_vec();
                 child_path.push(i);
-                build_display_recursive(arg, target_path, &child_path, output, underline);
+                build_display_recursive(arg, target_path, &child_path, output, underline, overrides);
             }
             output.push(')');
         }
@@ -484,17 +569,17 @@ pub(crate) fn build_display_recursive(
             }
             let mut child_path = current_path.to_vec();
             child_path.push(0);
-            build_display_recursive(reference, target_path, &child_path, output, underline);
+            build_display_recursive(reference, target_path, &child_path, output, underline, overrides);
         }
 
         Expression::IndexAccess { target, index } => {
             let mut child_path = current_path.to_vec();
             child_path.push(0);
-            build_display_recursive(target, target_path, &child_path, output, underline);
+            build_display_recursive(target, target_path, &child_path, output, underline, overrides);
             output.push('[');
             let mut idx_path = current_path.to_vec();
             idx_path.push(1);
-            build_display_recursive(index, target_path, &idx_path, output, underline);
+            build_display_recursive(index, target_path, &idx_path, output, underline, overrides);
             output.push(']');
         }
 
@@ -506,7 +591,7 @@ pub(crate) fn build_display_recursive(
                 }
                 let mut child_path = current_path.to_vec();
                 child_path.push(i);
-                build_display_recursive(elem, target_path, &child_path, output, underline);
+                build_display_recursive(elem, target_path, &child_path, output, underline, overrides);
             }
             output.push('}');
         }
@@ -519,11 +604,11 @@ pub(crate) fn build_display_recursive(
                 }
                 let mut key_path = current_path.to_vec();
                 key_path.push(i * 2);
-                build_display_recursive(key, target_path, &key_path, output, underline);
+                build_display_recursive(key, target_path, &key_path, output, underline, overrides);
                 output.push_str(": ");
                 let mut val_path = current_path.to_vec();
                 val_path.push(i * 2 + 1);
-                build_display_recursive(value, target_path, &val_path, output, underline);
+                build_display_recursive(value, target_path, &val_path, output, underline, overrides);
             }
             output.push('}');
         }
@@ -533,13 +618,13 @@ pub(crate) fn build_display_recursive(
         }
 
         Expression::SpillRef { cell, .. } => {
-            build_display_recursive(cell, target_path, current_path, output, underline);
+            build_display_recursive(cell, target_path, current_path, output, underline, overrides);
             output.push('#');
         }
 
         Expression::ImplicitIntersection { operand } => {
             output.push('@');
-            build_display_recursive(operand, target_path, current_path, output, underline);
+            build_display_recursive(operand, target_path, current_path, output, underline, overrides);
         }
     }
 
@@ -563,6 +648,15 @@ pub(crate) fn value_to_display(val: &Value) -> String {
     }
 }
 
+/// Display an array constant the way it is written: {1,2;3,4}
+pub(crate) fn array_literal_display(rows: &[Vec<Value>]) -> String {
+    let rows: Vec<String> = rows
+        .iter()
+        .map(|row| row.iter().map(value_to_display).collect::<Vec<_>>().join(","))
+        .collect();
+    format!("{{{}}}", rows.join(";"))
+}
+
 pub(crate) fn table_specifier_to_display(spec: &engine::TableSpecifier) -> String {
     match spec {
         engine::TableSpecifier::Column(col) => col.clone(),
@@ -622,6 +716,9 @@ pub(crate) fn builtin_fn_name(func: &BuiltinFunction) -> String {
         BuiltinFunction::GetRowHeight => "GET.ROW.HEIGHT".to_string(),
         BuiltinFunction::GetColumnWidth => "GET.COLUMN.WIDTH".to_string(),
         BuiltinFunction::GetCellFillColor => "GET.CELL.FILLCOLOR".to_string(),
+        BuiltinFunction::SetRowHeight => "SET.ROW.HEIGHT".to_string(),
+        BuiltinFunction::SetColumnWidth => "SET.COLUMN.WIDTH".to_string(),
+        BuiltinFunction::SetCellFillColor => "SET.CELL.FILLCOLOR".to_string(),
         BuiltinFunction::Row => "ROW".to_string(),
         BuiltinFunction::Column => "COLUMN".to_string(),
         // New functions - use format!("{:?}") for debug name, then uppercase
@@ -799,6 +896,7 @@ pub(crate) fn builtin_fn_name(func: &BuiltinFunction) -> String {
         BuiltinFunction::Aggregate => "AGGREGATE".to_string(),
         // Web
         BuiltinFunction::EncodeUrl => "ENCODEURL".to_string(),
+        BuiltinFunction::Hyperlink => "HYPERLINK".to_string(),
         // Database functions
         BuiltinFunction::DAverage => "DAVERAGE".to_string(),
         BuiltinFunction::DCount => "DCOUNT".to_string(),
@@ -822,22 +920,32 @@ pub(crate) fn builtin_fn_name(func: &BuiltinFunction) -> String {
 // ============================================================================
 
 /// Evaluate a single node in the AST and return its result as a Value literal.
+/// `cell` is the (row, col) whose formula the node belongs to.
 pub(crate) fn evaluate_single_node(
     expr: &Expression,
     grids: &[engine::Grid],
     sheet_names: &[String],
     sheet_index: usize,
+    cell: (u32, u32),
 ) -> Value {
-    let engine_ast = expr.clone();
+    eval_result_to_value(&evaluate_node_result(expr, grids, sheet_names, sheet_index, cell))
+}
+
+/// Evaluate a node, keeping array results intact.
+fn evaluate_node_result(
+    expr: &Expression,
+    grids: &[engine::Grid],
+    sheet_names: &[String],
+    sheet_index: usize,
+    cell: (u32, u32),
+) -> EvalResult {
     let current_grid = &grids[sheet_index];
     let current_sheet_name = &sheet_names[sheet_index];
 
     let context = crate::create_multi_sheet_context(grids, sheet_names, current_sheet_name);
 
-    let evaluator = Evaluator::with_multi_sheet(current_grid, context);
-    let result = evaluator.evaluate(&engine_ast);
-
-    eval_result_to_value(&result)
+    let evaluator = Evaluator::with_context(current_grid, context, engine::EvalContext::at(cell.0, cell.1));
+    evaluator.evaluate(expr)
 }
 
 pub(crate) fn eval_result_to_value(result: &engine::EvalResult) -> Value {
@@ -894,6 +1002,15 @@ fn cell_has_formula(
     }
 }
 
+/// Sheet a reference's optional sheet name points to (the current sheet when
+/// absent or unknown).
+fn sheet_index_of(sheet: &Option<String>, sheet_names: &[String], current_sheet_index: usize) -> usize {
+    sheet
+        .as_ref()
+        .and_then(|name| sheet_names.iter().position(|n| n.to_uppercase() == name.to_uppercase()))
+        .unwrap_or(current_sheet_index)
+}
+
 /// Check if a cell reference points to a formula cell.
 /// Returns (sheet_index, row_0based, col_0based, has_formula).
 fn check_step_in_target(
@@ -906,40 +1023,416 @@ fn check_step_in_target(
     let col_idx = col_to_index(col_str);
     let row_idx = row_1based - 1; // Convert to 0-based
 
-    let target_sheet = if let Some(sheet_name) = sheet {
-        sheet_names
-            .iter()
-            .position(|n| n.to_uppercase() == sheet_name.to_uppercase())
-            .unwrap_or(current_sheet_index)
-    } else {
-        current_sheet_index
-    };
+    let target_sheet = sheet_index_of(sheet, sheet_names, current_sheet_index);
 
     let has_formula = cell_has_formula(grids, target_sheet, row_idx, col_idx);
     (target_sheet, row_idx, col_idx, has_formula)
 }
 
+// ============================================================================
+// Node Identity, References and Previews
+// ============================================================================
+
+/// Stable id of the node at `path` in frame `depth` (e.g. "0/2.1"). Nodes
+/// are replaced in place, so a path keeps naming the same node across steps.
+pub(crate) fn node_id(depth: usize, path: &[usize]) -> String {
+    let path: Vec<String> = path.iter().map(|i| i.to_string()).collect();
+    format!("{}/{}", depth, path.join("."))
+}
+
+fn parse_node_id(id: &str) -> Option<(usize, Vec<usize>)> {
+    let (depth, path) = id.split_once('/')?;
+    let path = if path.is_empty() {
+        Vec::new()
+    } else {
+        path.split('.').map(|i| i.parse().ok()).collect::<Option<Vec<usize>>>()?
+    };
+    Some((depth.parse().ok()?, path))
+}
+
+/// Calls `f` with each child of `expr` and its index, using the same
+/// numbering as `get_node`. References are leaves.
+fn for_each_child(expr: &Expression, f: &mut dyn FnMut(usize, &Expression)) {
+    match expr {
+        Expression::BinaryOp { left, right, .. } => {
+            f(0, left);
+            f(1, right);
+        }
+        Expression::UnaryOp { operand, .. } => f(0, operand),
+        Expression::IndexAccess { target, index } => {
+            f(0, target);
+            f(1, index);
+        }
+        Expression::FunctionCall { args, .. } => {
+            for (i, arg) in args.iter().enumerate() {
+                f(i, arg);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// A reference node resolved to a 0-based inclusive rectangle.
+struct ResolvedRef {
+    sheet_index: usize,
+    start_row: u32,
+    start_col: u32,
+    end_row: u32,
+    end_col: u32,
+}
+
+impl ResolvedRef {
+    fn rows(&self) -> usize {
+        (self.end_row - self.start_row) as usize + 1
+    }
+
+    fn cols(&self) -> usize {
+        (self.end_col - self.start_col) as usize + 1
+    }
+
+    fn address(&self, sheet_names: &[String]) -> String {
+        let sheet = sheet_names.get(self.sheet_index).map(String::as_str).unwrap_or("");
+        let sheet = if sheet.contains(' ') { format!("'{}'", sheet) } else { sheet.to_string() };
+        let start = format_cell_ref(self.start_row, self.start_col);
+        if self.rows() == 1 && self.cols() == 1 {
+            format!("{}!{}", sheet, start)
+        } else {
+            format!("{}!{}:{}", sheet, start, format_cell_ref(self.end_row, self.end_col))
+        }
+    }
+
+    /// Display values of the cells at row-major positions
+    /// `offset..offset + limit`, read straight from the grid so a huge range
+    /// is never materialized.
+    fn values(&self, grids: &[engine::Grid], offset: usize, limit: usize) -> Vec<String> {
+        let Some(grid) = grids.get(self.sheet_index) else { return Vec::new() };
+        let cols = self.cols();
+        let end = (self.rows() * cols).min(offset.saturating_add(limit));
+        (offset..end)
+            .map(|i| {
+                let row = self.start_row + (i / cols) as u32;
+                let col = self.start_col + (i % cols) as u32;
+                grid.get_cell(row, col).map(|c| cell_value_display(&c.value)).unwrap_or_default()
+            })
+            .collect()
+    }
+}
+
+/// Resolve a reference node; None for anything else (and for 3D references,
+/// which span several sheets).
+fn resolve_reference(
+    expr: &Expression,
+    grids: &[engine::Grid],
+    sheet_names: &[String],
+    current_sheet_index: usize,
+) -> Option<ResolvedRef> {
+    let rect = |sheet: &Option<String>, r1: u32, c1: u32, r2: u32, c2: u32| {
+        let sheet_index = sheet_index_of(sheet, sheet_names, current_sheet_index);
+        ResolvedRef { sheet_index, start_row: r1.min(r2), start_col: c1.min(c2), end_row: r1.max(r2), end_col: c1.max(c2) }
+    };
+    match expr {
+        Expression::CellRef { sheet, col, row, .. } => {
+            let (r, c) = (row.checked_sub(1)?, col_to_index(col));
+            Some(rect(sheet, r, c, r, c))
+        }
+        Expression::Range { sheet, start, end, .. } => match (start.as_ref(), end.as_ref()) {
+            (
+                Expression::CellRef { col: sc, row: sr, .. },
+                Expression::CellRef { col: ec, row: er, .. },
+            ) => Some(rect(sheet, sr.checked_sub(1)?, col_to_index(sc), er.checked_sub(1)?, col_to_index(ec))),
+            _ => None,
+        },
+        Expression::ColumnRef { sheet, start_col, end_col, .. } => {
+            let max_row = grids.get(sheet_index_of(sheet, sheet_names, current_sheet_index))?.max_row;
+            Some(rect(sheet, 0, col_to_index(start_col), max_row, col_to_index(end_col)))
+        }
+        Expression::RowRef { sheet, start_row, end_row, .. } => {
+            let max_col = grids.get(sheet_index_of(sheet, sheet_names, current_sheet_index))?.max_col;
+            Some(rect(sheet, start_row.checked_sub(1)?, 0, end_row.checked_sub(1)?, max_col))
+        }
+        _ => None,
+    }
+}
+
+fn is_reference(expr: &Expression) -> bool {
+    matches!(
+        expr,
+        Expression::CellRef { .. }
+            | Expression::Range { .. }
+            | Expression::ColumnRef { .. }
+            | Expression::RowRef { .. }
+            | Expression::Sheet3DRef { .. }
+    )
+}
+
+/// Cells the references under `expr` cover, charged against the budget.
+fn reference_cost(
+    expr: &Expression,
+    grids: &[engine::Grid],
+    sheet_names: &[String],
+    sheet_index: usize,
+) -> u64 {
+    match expr {
+        Expression::Sheet3DRef { reference, .. } => {
+            reference_cost(reference, grids, sheet_names, sheet_index).saturating_mul(grids.len() as u64)
+        }
+        e if is_reference(e) => resolve_reference(e, grids, sheet_names, sheet_index)
+            .map_or(1, |r| r.rows() as u64 * r.cols() as u64),
+        e => {
+            let mut total = 0u64;
+            for_each_child(e, &mut |_, child| {
+                total = total.saturating_add(reference_cost(child, grids, sheet_names, sheet_index));
+            });
+            total
+        }
+    }
+}
+
+fn cell_value_display(value: &CellValue) -> String {
+    match value {
+        CellValue::Empty => String::new(),
+        CellValue::Number(n) => value_to_display(&Value::Number(*n)),
+        CellValue::Text(t) => value_to_display(&Value::String(t.clone())),
+        CellValue::Boolean(b) => value_to_display(&Value::Boolean(*b)),
+        CellValue::Error(e) => format!("#{}", format!("{:?}", e).to_uppercase()),
+        CellValue::List(items) => format!("[List({})]", items.len()),
+        CellValue::Dict(entries) => format!("[Dict({})]", entries.len()),
+    }
+}
+
+/// (rows, cols, row-major values) of an evaluated array.
+fn array_shape(result: &EvalResult) -> (usize, usize, Vec<EvalResult>) {
+    let (rows, cols) = result.spill_dimensions();
+    (rows, cols, result.flatten())
+}
+
+fn array_preview(result: &EvalResult) -> EvalValuePreview {
+    let (rows, cols, values) = array_shape(result);
+    let total = values.len();
+    EvalValuePreview {
+        rows,
+        cols,
+        total,
+        values: values.iter().take(PREVIEW_LEN).map(|v| value_to_display(&eval_result_to_value(v))).collect(),
+        truncated: total > PREVIEW_LEN,
+    }
+}
+
+/// Inline text for an evaluated array, e.g. "{1, 2, 3, … (500 values)}".
+fn array_display(preview: &EvalValuePreview) -> String {
+    let shown = preview.values.iter().take(3).cloned().collect::<Vec<_>>().join(", ");
+    if preview.total > 3 {
+        format!("{{{}, … ({} values)}}", shown, preview.total)
+    } else {
+        format!("{{{}}}", shown)
+    }
+}
+
+fn reference_info(
+    id: String,
+    expr: &Expression,
+    grids: &[engine::Grid],
+    sheet_names: &[String],
+    sheet_index: usize,
+) -> EvalNodeInfo {
+    let (label, _, _) = build_display(expr, &[usize::MAX]);
+    let resolved = resolve_reference(expr, grids, sheet_names, sheet_index);
+    EvalNodeInfo {
+        node_id: id,
+        label,
+        address: resolved.as_ref().map(|r| r.address(sheet_names)),
+        preview: resolved.map(|r| {
+            let total = r.rows() * r.cols();
+            EvalValuePreview {
+                rows: r.rows(),
+                cols: r.cols(),
+                total,
+                values: r.values(grids, 0, PREVIEW_LEN),
+                truncated: total > PREVIEW_LEN,
+            }
+        }),
+    }
+}
+
+// ============================================================================
+// Session Stepping
+// ============================================================================
+
+impl EvalSession {
+    pub(crate) fn new(
+        cell_ref: String,
+        row: u32,
+        col: u32,
+        sheet_index: usize,
+        formula: String,
+        ast: Expression,
+        budget: EvalBudget,
+    ) -> Self {
+        EvalSession {
+            frames: vec![StepFrame::new(cell_ref, row, col, sheet_index, formula, ast)],
+            budget,
+            steps_used: 0,
+            cells_used: 0,
+            exhausted: false,
+        }
+    }
+
+    /// Back to the bottom frame with a freshly parsed AST and a full budget.
+    fn restart(&mut self, ast: Expression) {
+        let bottom = self.frames.swap_remove(0);
+        self.frames = vec![StepFrame::new(
+            bottom.cell_ref,
+            bottom.row,
+            bottom.col,
+            bottom.sheet_index,
+            bottom.original_formula,
+            ast,
+        )];
+        self.steps_used = 0;
+        self.cells_used = 0;
+        self.exhausted = false;
+    }
+
+    /// Take one step covering `cells` referenced cells out of the budget.
+    fn charge(&mut self, cells: u64) -> Result<(), String> {
+        if self.exhausted {
+            return Err("Evaluation budget exhausted. Restart to evaluate again.".to_string());
+        }
+        if self.steps_used >= self.budget.max_steps {
+            self.exhausted = true;
+            return Err(format!("Step limit of {} reached. Restart to evaluate again.", self.budget.max_steps));
+        }
+        if self.cells_used.saturating_add(cells) > self.budget.max_cells {
+            self.exhausted = true;
+            return Err(format!(
+                "Evaluation budget exceeded: this step would read {} cells (limit {}).",
+                cells, self.budget.max_cells
+            ));
+        }
+        self.steps_used += 1;
+        self.cells_used += cells;
+        Ok(())
+    }
+
+    /// Evaluate the underlined node. Scalars replace the node with a Literal;
+    /// arrays stay in place with their value kept for previews.
+    pub(crate) fn evaluate_next(&mut self, grids: &[engine::Grid], sheet_names: &[String]) -> Result<(), String> {
+        let frame = self.frames.last().ok_or("No active frame.")?;
+        let Some(next) = frame.next_node() else { return Ok(()) };
+        let cost = reference_cost(get_node(&frame.ast, &next.path), grids, sheet_names, frame.sheet_index);
+        self.charge(cost)?;
+
+        let frame = self.frames.last_mut().ok_or("No active frame.")?;
+        let result = evaluate_node_result(
+            get_node(&frame.ast, &next.path), grids, sheet_names, frame.sheet_index, (frame.row, frame.col),
+        );
+        frame.arrays.retain(|path, _| !path.starts_with(&next.path));
+        match result {
+            EvalResult::Array(ref items) if !items.is_empty() => {
+                frame.arrays.insert(next.path, result);
+            }
+            _ => *get_node_mut(&mut frame.ast, &next.path) = Expression::Literal(eval_result_to_value(&result)),
+        }
+        Ok(())
+    }
+
+    /// Fully evaluate the current frame and substitute its value for the
+    /// cell reference the parent stepped into.
+    pub(crate) fn step_out(&mut self, grids: &[engine::Grid], sheet_names: &[String]) -> Result<(), String> {
+        if self.frames.len() <= 1 {
+            return Err("Cannot step out: already at top level.".to_string());
+        }
+        let child = self.frames.last().ok_or("No active frame.")?;
+        let cost = reference_cost(&child.ast, grids, sheet_names, child.sheet_index);
+        self.charge(cost)?;
+
+        let child = self.frames.pop().ok_or("No active frame.")?;
+        let final_value = evaluate_single_node(
+            &child.ast, grids, sheet_names, child.sheet_index, (child.row, child.col),
+        );
+        let parent = self.frames.last_mut().ok_or("No active frame.")?;
+        if let Some(next) = parent.next_node() {
+            *get_node_mut(&mut parent.ast, &next.path) = Expression::Literal(final_value);
+        }
+        Ok(())
+    }
+
+    /// One page of the values of a reference node or an evaluated array node.
+    pub(crate) fn expand_node(
+        &self,
+        id: &str,
+        offset: usize,
+        limit: usize,
+        grids: &[engine::Grid],
+        sheet_names: &[String],
+    ) -> Result<EvalNodeExpansion, String> {
+        let (depth, path) = parse_node_id(id).ok_or_else(|| format!("Invalid node id '{}'", id))?;
+        let frame = self.frames.get(depth).ok_or_else(|| format!("Node '{}' is not on the stack", id))?;
+        let limit = limit.min(MAX_EXPAND_PAGE);
+
+        if let Some(result) = frame.arrays.get(&path) {
+            let (rows, cols, values) = array_shape(result);
+            let total = values.len();
+            let values = values
+                .iter()
+                .skip(offset)
+                .take(limit)
+                .map(|v| value_to_display(&eval_result_to_value(v)))
+                .collect();
+            return Ok(EvalNodeExpansion { node_id: id.to_string(), rows, cols, total, offset, values });
+        }
+
+        let node = get_node(&frame.ast, &path);
+        let resolved = resolve_reference(node, grids, sheet_names, frame.sheet_index)
+            .ok_or_else(|| format!("Node '{}' has no values to expand yet", id))?;
+        Ok(EvalNodeExpansion {
+            node_id: id.to_string(),
+            rows: resolved.rows(),
+            cols: resolved.cols(),
+            total: resolved.rows() * resolved.cols(),
+            offset,
+            values: resolved.values(grids, offset, limit),
+        })
+    }
+}
+
 // ============================================================================
 // Build State Response
 // ============================================================================
 
-fn build_step_state(
+pub(crate) fn build_step_state(
     session_id: &str,
     session: &EvalSession,
     grids: &[engine::Grid],
     sheet_names: &[String],
 ) -> EvalStepState {
-    let frame = session.frames.last().unwrap();
+    let depth = session.frames.len() - 1;
+    let frame = &session.frames[depth];
+
+    // Evaluated arrays: previewed in the side list and inline in the formula
+    let mut array_paths: Vec<&Vec<usize>> = frame.arrays.keys().collect();
+    array_paths.sort();
+    let mut overrides = HashMap::new();
+    let array_results: Vec<EvalNodeInfo> = array_paths
+        .into_iter()
+        .map(|path| {
+            let preview = array_preview(&frame.arrays[path]);
+            overrides.insert(path.clone(), array_display(&preview));
+            let (label, _, _) = build_display(get_node(&frame.ast, path), &[usize::MAX]);
+            EvalNodeInfo { node_id: node_id(depth, path), label, address: None, preview: Some(preview) }
+        })
+        .collect();
 
     // Find next node to evaluate
-    let next = find_next_eval_node(&frame.ast);
+    let next = frame.next_node();
 
-    match next {
+    let mut state = match next {
         None => {
-            // Evaluation is complete - the entire AST is a single Literal
+            // Evaluation is complete - the AST is a single Literal or array
             let result_str = match &frame.ast {
                 Expression::Literal(val) => value_to_display(val),
-                _ => "?".to_string(),
+                Expression::ArrayLiteral { rows } => array_literal_display(rows),
+                _ => overrides.get(&Vec::new()).cloned().unwrap_or_else(|| "?".to_string()),
             };
 
             EvalStepState {
@@ -955,11 +1448,16 @@ fn build_step_state(
                 step_in_target: None,
                 evaluation_result: Some(result_str),
                 error: None,
+                node_id: None,
+                references: Vec::new(),
+                array_results: Vec::new(),
+                steps_used: 0,
+                step_limit: 0,
             }
         }
         Some(next_node) => {
             // Build display string with underline
-            let (display, ul_start, ul_end) = build_display(&frame.ast, &next_node.path);
+            let (display, ul_start, ul_end) = build_display_with(&frame.ast, &next_node.path, &overrides);
 
             // Check if Step In is available
             let (can_step_in, step_in_target) = if next_node.is_cell_ref {
@@ -978,12 +1476,28 @@ fn build_step_state(
                 (false, None)
             };
 
+            // The references this step reads, resolved up front: the node
+            // itself or its direct (not yet evaluated) reference arguments
+            let node = get_node(&frame.ast, &next_node.path);
+            let mut references = Vec::new();
+            if is_reference(node) {
+                references.push(reference_info(node_id(depth, &next_node.path), node, grids, sheet_names, frame.sheet_index));
+            } else {
+                for_each_child(node, &mut |i, child| {
+                    let mut path = next_node.path.clone();
+                    path.push(i);
+                    if is_reference(child) && !frame.arrays.contains_key(&path) {
+                        references.push(reference_info(node_id(depth, &path), child, grids, sheet_names, frame.sheet_index));
+                    }
+                });
+            }
+
             EvalStepState {
                 session_id: session_id.to_string(),
                 formula_display: format!("={}", display),
                 underline_start: ul_start + 1, // +1 for the "=" prefix
                 underline_end: ul_end + 1,
-                can_evaluate: true,
+                can_evaluate: !session.exhausted,
                 can_step_in,
                 can_step_out: session.frames.len() > 1,
                 is_complete: false,
@@ -991,9 +1505,32 @@ fn build_step_state(
                 step_in_target,
                 evaluation_result: None,
                 error: None,
+                node_id: Some(node_id(depth, &next_node.path)),
+                references,
+                array_results: Vec::new(),
+                steps_used: 0,
+                step_limit: 0,
             }
         }
-    }
+    };
+    state.array_results = array_results;
+    state.steps_used = session.steps_used;
+    state.step_limit = session.budget.max_steps;
+    state
+}
+
+/// The current state with `msg` attached, for failures that leave the
+/// session usable (e.g. an exhausted budget).
+fn state_with_error(
+    session_id: &str,
+    session: &EvalSession,
+    grids: &[engine::Grid],
+    sheet_names: &[String],
+    msg: &str,
+) -> EvalStepState {
+    let mut state = build_step_state(session_id, session, grids, sheet_names);
+    state.error = Some(msg.to_string());
+    state
 }
 
 fn error_state(session_id: &str, msg: &str) -> EvalStepState {
@@ -1010,6 +1547,11 @@ fn error_state(session_id: &str, msg: &str) -> EvalStepState {
         step_in_target: None,
         evaluation_result: None,
         error: Some(msg.to_string()),
+        node_id: None,
+        references: Vec::new(),
+        array_results: Vec::new(),
+        steps_used: 0,
+        step_limit: 0,
     }
 }
 
@@ -1017,6 +1559,12 @@ fn error_state(session_id: &str, msg: &str) -> EvalStepState {
 // Tauri Commands
 // ============================================================================
 
+/// Frames address sheets by index; a sheet deleted while the session was open
+/// leaves them pointing past the end of `grids`.
+fn check_session_sheets(session: &EvalSession, sheet_count: usize) -> Result<(), ApiError> {
+    session.frames.iter().try_for_each(|frame| check_sheet_index(sheet_count, frame.sheet_index))
+}
+
 /// Initialize a formula evaluation session for the given cell.
 #[tauri::command]
 pub fn eval_formula_init(
@@ -1081,16 +1629,7 @@ pub fn eval_formula_init(
 
     let cell_ref = format_cell_ref(row, col);
 
-    let session = EvalSession {
-        frames: vec![StepFrame {
-            cell_ref: cell_ref.clone(),
-            row,
-            col,
-            sheet_index: active_sheet,
-            original_formula: formula,
-            ast,
-        }],
-    };
+    let session = EvalSession::new(cell_ref, row, col, active_sheet, formula, ast, EvalBudget::default());
 
     // Build initial state before storing session
     let result = build_step_state(&session_id, &session, &grids, &sheet_names);
@@ -1120,29 +1659,14 @@ pub fn eval_formula_evaluate(
         Some(s) => s,
         None => return error_state(&session_id, "Session not found."),
     };
+    if let Err(e) = check_session_sheets(session, grids.len()) {
+        return error_state(&session_id, &e.to_string());
+    }
 
-    let frame = match session.frames.last_mut() {
-        Some(f) => f,
-        None => return error_state(&session_id, "No active frame."),
-    };
-
-    // Find the next node
-    let next = match find_next_eval_node(&frame.ast) {
-        Some(n) => n,
-        None => {
-            return build_step_state(&session_id, session, &grids, &sheet_names);
-        }
-    };
-
-    // Evaluate the node
-    let node = get_node(&frame.ast, &next.path);
-    let result_value = evaluate_single_node(node, &grids, &sheet_names, frame.sheet_index);
-
-    // Replace the node with a Literal
-    let target = get_node_mut(&mut frame.ast, &next.path);
-    *target = Expression::Literal(result_value);
-
-    build_step_state(&session_id, session, &grids, &sheet_names)
+    match session.evaluate_next(&grids, &sheet_names) {
+        Ok(()) => build_step_state(&session_id, session, &grids, &sheet_names),
+        Err(e) => state_with_error(&session_id, session, &grids, &sheet_names, &e),
+    }
 }
 
 /// Step into a cell reference (push current frame, load referenced cell's formula).
@@ -1160,6 +1684,9 @@ pub fn eval_formula_step_in(
         Some(s) => s,
         None => return error_state(&session_id, "Session not found."),
     };
+    if let Err(e) = check_session_sheets(session, grids.len()) {
+        return error_state(&session_id, &e.to_string());
+    }
 
     let frame = match session.frames.last() {
         Some(f) => f,
@@ -1167,7 +1694,7 @@ pub fn eval_formula_step_in(
     };
 
     // Find the next node - must be a cell ref
-    let next = match find_next_eval_node(&frame.ast) {
+    let next = match frame.next_node() {
         Some(n) if n.is_cell_ref => n,
         _ => return error_state(&session_id, "Cannot step in: not a cell reference."),
     };
@@ -1220,14 +1747,9 @@ pub fn eval_formula_step_in(
     let cell_ref = format_cell_ref(row_0, col_0);
 
     // Push new frame
-    session.frames.push(StepFrame {
-        cell_ref,
-        row: row_0,
-        col: col_0,
-        sheet_index: target_sheet,
-        original_formula: target_formula,
-        ast: target_ast,
-    });
+    session
+        .frames
+        .push(StepFrame::new(cell_ref, row_0, col_0, target_sheet, target_formula, target_ast));
 
     // Enforce max depth
     if session.frames.len() > 32 {
@@ -1253,29 +1775,18 @@ pub fn eval_formula_step_out(
         Some(s) => s,
         None => return error_state(&session_id, "Session not found."),
     };
+    if let Err(e) = check_session_sheets(session, grids.len()) {
+        return error_state(&session_id, &e.to_string());
+    }
 
     if session.frames.len() <= 1 {
         return error_state(&session_id, "Cannot step out: already at top level.");
     }
 
-    // Fully evaluate the current frame's AST
-    let child_frame = session.frames.pop().unwrap();
-    let final_value = evaluate_single_node(
-        &child_frame.ast,
-        &grids,
-        &sheet_names,
-        child_frame.sheet_index,
-    );
-
-    // In the parent frame, find the cell ref that was stepped into and replace it
-    let parent_frame = session.frames.last_mut().unwrap();
-    let next = find_next_eval_node(&parent_frame.ast);
-    if let Some(next_node) = next {
-        let target = get_node_mut(&mut parent_frame.ast, &next_node.path);
-        *target = Expression::Literal(final_value);
+    match session.step_out(&grids, &sheet_names) {
+        Ok(()) => build_step_state(&session_id, session, &grids, &sheet_names),
+        Err(e) => state_with_error(&session_id, session, &grids, &sheet_names, &e),
     }
-
-    build_step_state(&session_id, session, &grids, &sheet_names)
 }
 
 /// Restart the evaluation from the beginning.
@@ -1293,13 +1804,14 @@ pub fn eval_formula_restart(
         Some(s) => s,
         None => return error_state(&session_id, "Session not found."),
     };
+    if let Err(e) = check_session_sheets(session, grids.len()) {
+        return error_state(&session_id, &e.to_string());
+    }
 
     // Get the bottom frame's info
     let bottom = &session.frames[0];
     let formula = bottom.original_formula.clone();
-    let cell_ref = bottom.cell_ref.clone();
     let row = bottom.row;
-    let col = bottom.col;
     let sheet_index = bottom.sheet_index;
 
     // Re-parse the formula (resolve table refs)
@@ -1326,20 +1838,32 @@ pub fn eval_formula_restart(
         Err(e) => return error_state(&session_id, &format!("Parse error: {}", e)),
     };
 
-    // Reset to a single frame
-    session.frames.clear();
-    session.frames.push(StepFrame {
-        cell_ref,
-        row,
-        col,
-        sheet_index,
-        original_formula: formula,
-        ast,
-    });
+    // Reset to a single frame (and a fresh budget)
+    session.restart(ast);
 
     build_step_state(&session_id, session, &grids, &sheet_names)
 }
 
+/// Fetch `limit` values of a reference or evaluated array node, starting at
+/// row-major position `offset`, so large ranges are expanded on demand.
+#[tauri::command]
+pub fn eval_formula_expand_node(
+    state: State<AppState>,
+    eval_state: State<EvalFormulaState>,
+    session_id: String,
+    node_id: String,
+    offset: usize,
+    limit: usize,
+) -> Result<EvalNodeExpansion, String> {
+    let grids = state.grids.lock().map_err(|e| e.to_string())?;
+    let sheet_names = state.sheet_names.lock().map_err(|e| e.to_string())?;
+
+    let sessions = eval_state.sessions.lock().map_err(|e| e.to_string())?;
+    let session = sessions.get(&session_id).ok_or("Session not found.")?;
+    check_session_sheets(session, grids.len())?;
+    session.expand_node(&node_id, offset, limit, &grids, &sheet_names)
+}
+
 /// Close and clean up a session.
 #[tauri::command]
 pub fn eval_formula_close(
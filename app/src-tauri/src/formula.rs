@@ -2,15 +2,21 @@
 // PURPOSE: Formula library commands - function catalog, templates, and expression evaluation
 // FORMAT: seq|level|category|message
 
-use crate::api_types::{FunctionInfo, FunctionListResult};
+use crate::api_types::{
+    CompletionItem, CompletionKind, CompletionResult, FormulaIssue, FormulaIssueKind, FormulaValidation,
+    FunctionInfo, FunctionListResult,
+};
 use crate::logging::{log_enter, log_exit};
 use crate::AppState;
 use crate::persistence::UserFilesState;
 use tauri::State;
 use parser::BuiltinFunction;
 use parser::FunctionMeta;
+use parser::FunctionSignature;
 use parser::parse as parse_formula;
+use parser::{Expression, Lexer, Token};
 use engine::{Evaluator, EvalResult};
+use std::collections::HashSet;
 
 /// Build the complete function catalog from the parser's single source of truth.
 /// Aliases (e.g. AVG, CEIL) are excluded from the user-facing catalog.
@@ -120,6 +126,556 @@ pub fn get_function_template(function_name: String) -> String {
     template
 }
 
+/// Structured signature (argument names, optional/variadic flags, type hints,
+/// example) for the formula bar's argument tooltips. None for unknown names.
+#[tauri::command]
+pub fn get_function_signature(function_name: String) -> Option<FunctionSignature> {
+    log_enter!("CMD", "get_function_signature", "name={}", function_name);
+    let signature = parser::function_signature(&function_name);
+    log_exit!("CMD", "get_function_signature", "found={}", signature.is_some());
+    signature
+}
+
+// ============================================================================
+// Formula completions
+// ============================================================================
+// The real lexer rejects incomplete input, so completions use a small
+// tolerant scan of the text before the cursor: it tracks string literals,
+// quoted sheet names and structured-reference brackets, and classifies the
+// token being typed. Positions are character offsets.
+
+const MAX_COMPLETIONS: usize = 50;
+const TABLE_SPECIAL_ITEMS: [&str; 5] = ["#All", "#Data", "#Headers", "#Totals", "#This Row"];
+
+/// What the cursor is in the middle of typing.
+#[derive(Debug, PartialEq)]
+enum CompletionContext {
+    None,
+    /// A bare identifier; `sheet` is set when it follows `Sheet!`.
+    Identifier { start: usize, prefix: String, sheet: Option<String> },
+    /// A sheet name after an opening `'`; `start` is the quote.
+    QuotedSheet { start: usize, prefix: String },
+    /// Inside `Table[...]`; no table name means the table at the edited cell.
+    StructuredRef { table: Option<String>, start: usize, prefix: String },
+}
+
+fn is_name_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_' || c == '.' || c == '\\'
+}
+
+/// Start of the run of name characters ending at `end`.
+fn name_start(chars: &[char], end: usize) -> usize {
+    let mut start = end;
+    while start > 0 && is_name_char(chars[start - 1]) {
+        start -= 1;
+    }
+    start
+}
+
+/// Classify the token at `cursor` in a possibly incomplete formula.
+fn completion_context(formula: &str, cursor: usize) -> CompletionContext {
+    let chars: Vec<char> = formula.chars().collect();
+    let cursor = cursor.min(chars.len());
+
+    let mut in_string = false;
+    let mut quote_start: Option<usize> = None;
+    let mut brackets: Vec<usize> = Vec::new();
+    let mut i = 0;
+    while i < cursor {
+        let c = chars[i];
+        if in_string {
+            in_string = c != '"';
+        } else if quote_start.is_some() {
+            if c == '\'' {
+                // '' is an escaped quote inside a sheet name.
+                if i + 1 < cursor && chars[i + 1] == '\'' {
+                    i += 1;
+                } else {
+                    quote_start = None;
+                }
+            }
+        } else if !brackets.is_empty() {
+            match c {
+                '[' => brackets.push(i),
+                ']' => {
+                    brackets.pop();
+                }
+                // ' escapes the next character in a column name.
+                '\'' => i += 1,
+                _ => {}
+            }
+        } else {
+            match c {
+                '"' => in_string = true,
+                '\'' => quote_start = Some(i),
+                '[' => brackets.push(i),
+                _ => {}
+            }
+        }
+        i += 1;
+    }
+
+    if in_string {
+        return CompletionContext::None;
+    }
+    if let Some(q) = quote_start {
+        let prefix: String = chars[q + 1..cursor].iter().collect::<String>().replace("''", "'");
+        return CompletionContext::QuotedSheet { start: q, prefix };
+    }
+    if let (Some(&outer), Some(&inner)) = (brackets.first(), brackets.last()) {
+        let name_begin = name_start(&chars, outer);
+        let table = (name_begin < outer).then(|| chars[name_begin..outer].iter().collect());
+        let mut start = inner + 1;
+        if chars.get(start) == Some(&'@') && start < cursor {
+            start += 1;
+        }
+        let prefix = chars[start.min(cursor)..cursor].iter().collect();
+        return CompletionContext::StructuredRef { table, start: start.min(cursor), prefix };
+    }
+
+    let start = name_start(&chars, cursor);
+    if start == cursor || chars[start].is_ascii_digit() {
+        return CompletionContext::None;
+    }
+    let prefix: String = chars[start..cursor].iter().collect();
+    let sheet = match start.checked_sub(1).map(|p| chars[p]) {
+        // The end of a range (A1:B) is a reference, not a name.
+        Some(':') => return CompletionContext::None,
+        Some('!') => {
+            let bang = start - 1;
+            if bang > 0 && chars[bang - 1] == '\'' {
+                let open = chars[..bang - 1].iter().rposition(|&c| c == '\'');
+                open.map(|o| chars[o + 1..bang - 1].iter().collect::<String>().replace("''", "'"))
+            } else {
+                let begin = name_start(&chars, bang);
+                Some(chars[begin..bang].iter().collect())
+            }
+        }
+        _ => None,
+    };
+    CompletionContext::Identifier { start, prefix, sheet }
+}
+
+/// Sort key for `candidate` against the typed `prefix` (case-insensitive):
+/// tier 0 for a prefix match, 1 for a subsequence match, None otherwise.
+fn completion_rank(candidate: &str, prefix: &str) -> Option<u8> {
+    let candidate = candidate.to_uppercase();
+    let prefix = prefix.to_uppercase();
+    if candidate.starts_with(&prefix) {
+        return Some(0);
+    }
+    let mut rest = candidate.chars();
+    prefix.chars().all(|p| rest.any(|c| c == p)).then_some(1)
+}
+
+/// Sheet name as it must appear before `!`.
+fn sheet_reference(name: &str) -> String {
+    let plain = name.chars().next().is_some_and(|c| c.is_alphabetic() || c == '_')
+        && name.chars().all(is_name_char);
+    if plain {
+        format!("{}!", name)
+    } else {
+        format!("'{}'!", name.replace('\'', "''"))
+    }
+}
+
+/// Column name as it must appear inside `[...]`.
+fn escape_column_name(name: &str) -> String {
+    let mut out = String::with_capacity(name.len());
+    for c in name.chars() {
+        if matches!(c, '[' | ']' | '#' | '\'') {
+            out.push('\'');
+        }
+        out.push(c);
+    }
+    out
+}
+
+/// Ranked completions for the token at `cursor_pos` in `partial_formula`,
+/// typed into cell (row, col) of `sheet`.
+pub fn compute_completions(
+    state: &AppState,
+    sheet: usize,
+    row: u32,
+    col: u32,
+    partial_formula: &str,
+    cursor_pos: usize,
+) -> Result<CompletionResult, String> {
+    let cursor = cursor_pos.min(partial_formula.chars().count());
+    let context = completion_context(partial_formula, cursor);
+    let mut ranked: Vec<(u8, CompletionItem)> = Vec::new();
+    let mut offer = |label: &str, kind: CompletionKind, insert_text: String, detail: Option<String>, prefix: &str| {
+        if let Some(rank) = completion_rank(label, prefix) {
+            ranked.push((rank, CompletionItem { label: label.to_string(), kind, insert_text, detail }));
+        }
+    };
+
+    let replace_start = match &context {
+        CompletionContext::None => cursor,
+        CompletionContext::Identifier { start, prefix, sheet: qualifier } => {
+            let named_ranges = state.named_ranges.lock().map_err(|e| e.to_string())?;
+            let sheet_names = state.sheet_names.lock().map_err(|e| e.to_string())?;
+            // After `Sheet!` only that sheet's own names make sense.
+            let scope = qualifier
+                .as_ref()
+                .map(|q| sheet_names.iter().position(|n| n.eq_ignore_ascii_case(q)));
+            for nr in named_ranges.values() {
+                let visible = match scope {
+                    Some(target) => target.is_some() && nr.sheet_index == target,
+                    None => nr.sheet_index.is_none() || nr.sheet_index == Some(sheet),
+                };
+                if visible {
+                    offer(&nr.name, CompletionKind::DefinedName, nr.name.clone(), Some(nr.refers_to.clone()), prefix);
+                }
+            }
+            if qualifier.is_none() {
+                for meta in BuiltinFunction::all_catalog_entries().iter().filter(|m| !m.is_alias) {
+                    offer(meta.name, CompletionKind::Function, format!("{}(", meta.name), Some(meta.syntax.to_string()), prefix);
+                }
+                let tables = state.tables.lock().map_err(|e| e.to_string())?;
+                for table in tables.values().flat_map(|t| t.values()) {
+                    let detail = sheet_names.get(table.sheet_index).cloned();
+                    offer(&table.name, CompletionKind::Table, table.name.clone(), detail, prefix);
+                }
+                for name in sheet_names.iter() {
+                    offer(name, CompletionKind::Sheet, sheet_reference(name), None, prefix);
+                }
+            }
+            *start
+        }
+        CompletionContext::QuotedSheet { start, prefix } => {
+            let sheet_names = state.sheet_names.lock().map_err(|e| e.to_string())?;
+            for name in sheet_names.iter() {
+                let insert = format!("'{}'!", name.replace('\'', "''"));
+                offer(name, CompletionKind::Sheet, insert, None, prefix);
+            }
+            *start
+        }
+        CompletionContext::StructuredRef { table, start, prefix } => {
+            let tables = state.tables.lock().map_err(|e| e.to_string())?;
+            let target = match table {
+                Some(name) => {
+                    let registry = state.table_names.lock().map_err(|e| e.to_string())?;
+                    registry
+                        .get(&name.to_uppercase())
+                        .and_then(|(s, id)| tables.get(s).and_then(|t| t.get(id)))
+                }
+                None => tables.get(&sheet).and_then(|t| t.values().find(|t| t.contains(row, col))),
+            };
+            if let Some(target) = target {
+                for column in &target.columns {
+                    let insert = format!("{}]", escape_column_name(&column.name));
+                    offer(&column.name, CompletionKind::Column, insert, Some(target.name.clone()), prefix);
+                }
+                for item in TABLE_SPECIAL_ITEMS {
+                    offer(item, CompletionKind::Column, format!("{}]", item), Some(target.name.clone()), prefix);
+                }
+            }
+            *start
+        }
+    };
+
+    ranked.sort_by(|(ra, a), (rb, b)| {
+        ra.cmp(rb)
+            .then(a.label.len().cmp(&b.label.len()))
+            .then_with(|| a.label.to_uppercase().cmp(&b.label.to_uppercase()))
+    });
+    let items = ranked.into_iter().take(MAX_COMPLETIONS).map(|(_, item)| item).collect();
+    Ok(CompletionResult { items, replace_start, replace_end: cursor })
+}
+
+/// Context-aware completions for the formula editor: functions, defined
+/// names, tables, table columns inside `Table[`, and sheet names.
+#[tauri::command]
+pub fn get_completions(
+    state: State<AppState>,
+    sheet: usize,
+    row: u32,
+    col: u32,
+    partial_formula: String,
+    cursor_pos: usize,
+) -> Result<CompletionResult, String> {
+    log_enter!("CMD", "get_completions", "sheet={} cursor={}", sheet, cursor_pos);
+    let result = compute_completions(&state, sheet, row, col, &partial_formula, cursor_pos)?;
+    log_exit!("CMD", "get_completions", "count={}", result.items.len());
+    Ok(result)
+}
+
+// ============================================================================
+// Formula Validation
+// ============================================================================
+
+/// A name or structured reference in a parsed formula.
+enum RefSite<'a> {
+    /// `local` when an enclosing LET or LAMBDA binds the name.
+    Name { expr: &'a Expression, name: &'a str, local: bool },
+    Table { expr: &'a Expression, table_name: &'a str },
+}
+
+/// Every NamedRef and TableRef in `expr`, in source order. LAMBDA parameters
+/// and LET names count as sites too (always local), so the k-th site with a
+/// given name is the k-th time that name is written.
+fn collect_ref_sites<'a>(expr: &'a Expression, locals: &mut Vec<String>, out: &mut Vec<RefSite<'a>>) {
+    match expr {
+        Expression::NamedRef { name, .. } => {
+            let local = locals.contains(&name.to_uppercase());
+            out.push(RefSite::Name { expr, name, local });
+        }
+        Expression::TableRef { table_name, .. } => out.push(RefSite::Table { expr, table_name }),
+        Expression::FunctionCall { func: BuiltinFunction::Lambda, args, .. } if args.len() >= 2 => {
+            let depth = locals.len();
+            for param in &args[..args.len() - 1] {
+                match param {
+                    Expression::NamedRef { name, .. } => {
+                        out.push(RefSite::Name { expr: param, name, local: true });
+                        locals.push(name.to_uppercase());
+                    }
+                    other => collect_ref_sites(other, locals, out),
+                }
+            }
+            collect_ref_sites(&args[args.len() - 1], locals, out);
+            locals.truncate(depth);
+        }
+        Expression::FunctionCall { func: BuiltinFunction::Let, args, .. } if args.len() >= 3 && args.len() % 2 == 1 => {
+            let depth = locals.len();
+            for pair in args[..args.len() - 1].chunks(2) {
+                let bound = match &pair[0] {
+                    Expression::NamedRef { name, .. } => {
+                        out.push(RefSite::Name { expr: &pair[0], name, local: true });
+                        Some(name.to_uppercase())
+                    }
+                    other => {
+                        collect_ref_sites(other, locals, out);
+                        None
+                    }
+                };
+                collect_ref_sites(&pair[1], locals, out);
+                locals.extend(bound);
+            }
+            collect_ref_sites(&args[args.len() - 1], locals, out);
+            locals.truncate(depth);
+        }
+        Expression::FunctionCall { args, .. } => {
+            for arg in args {
+                collect_ref_sites(arg, locals, out);
+            }
+        }
+        Expression::BinaryOp { left: a, right: b, .. }
+        | Expression::Range { start: a, end: b, .. }
+        | Expression::IndexAccess { target: a, index: b } => {
+            collect_ref_sites(a, locals, out);
+            collect_ref_sites(b, locals, out);
+        }
+        Expression::UnaryOp { operand, .. } | Expression::ImplicitIntersection { operand } => {
+            collect_ref_sites(operand, locals, out);
+        }
+        Expression::Sheet3DRef { reference: inner, .. } | Expression::SpillRef { cell: inner, .. } => {
+            collect_ref_sites(inner, locals, out);
+        }
+        Expression::ListLiteral { elements } => {
+            for element in elements {
+                collect_ref_sites(element, locals, out);
+            }
+        }
+        Expression::DictLiteral { entries } => {
+            for (key, value) in entries {
+                collect_ref_sites(key, locals, out);
+                collect_ref_sites(value, locals, out);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Tokens of `formula` with their character spans (end exclusive).
+fn spanned_tokens(formula: &str) -> Vec<(Token, (usize, usize))> {
+    let mut lexer = Lexer::new(formula);
+    let mut tokens = Vec::new();
+    loop {
+        let token = lexer.next_token();
+        if token == Token::EOF {
+            return tokens;
+        }
+        tokens.push((token, lexer.token_span()));
+    }
+}
+
+/// Spans where `name` is written as a name: not as a function, sheet or
+/// table name.
+fn name_spans(tokens: &[(Token, (usize, usize))], name: &str) -> Vec<(usize, usize)> {
+    tokens
+        .iter()
+        .enumerate()
+        .filter(|(i, (token, _))| {
+            matches!(token, Token::Identifier(id) if id.eq_ignore_ascii_case(name))
+                && !matches!(
+                    tokens.get(i + 1).map(|(next, _)| next),
+                    Some(Token::LParen | Token::Exclamation | Token::LBracket)
+                )
+        })
+        .map(|(_, (_, span))| *span)
+        .collect()
+}
+
+/// Spans of the structured references to `table_name`, from the table name
+/// through the closing ']'. An empty name matches the table-less `[Col]` and
+/// `[@Col]` forms.
+fn table_ref_spans(tokens: &[(Token, (usize, usize))], table_name: &str) -> Vec<(usize, usize)> {
+    let mut spans = Vec::new();
+    let mut depth = 0usize;
+    let mut open: Option<usize> = None;
+    for (i, (token, span)) in tokens.iter().enumerate() {
+        match token {
+            Token::LBracket => {
+                if depth == 0 {
+                    let prev = i.checked_sub(1).map(|p| &tokens[p]);
+                    open = match prev {
+                        Some((Token::Identifier(id), prev_span)) if id.eq_ignore_ascii_case(table_name) => {
+                            Some(prev_span.0)
+                        }
+                        Some((Token::Identifier(_) | Token::RParen | Token::RBracket | Token::RBrace, _)) => None,
+                        _ if table_name.is_empty() => Some(span.0),
+                        _ => None,
+                    };
+                }
+                depth += 1;
+            }
+            Token::RBracket => {
+                depth = depth.saturating_sub(1);
+                if depth == 0 {
+                    if let Some(start) = open.take() {
+                        spans.push((start, span.1));
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    spans
+}
+
+fn char_slice(text: &str, start: usize, end: usize) -> String {
+    text.chars().skip(start).take(end.saturating_sub(start)).collect()
+}
+
+/// Diagnostics for `formula` typed into `row` of `sheet`: the parse error if
+/// it does not parse, otherwise every name, table and table column the
+/// name and structured-reference resolution passes leave unresolved. Text
+/// that does not start with "=" is a value and always valid.
+pub(crate) fn validate_formula_internal(
+    state: &AppState,
+    sheet: usize,
+    row: u32,
+    formula: &str,
+) -> Result<FormulaValidation, String> {
+    let mut issues = Vec::new();
+    if !formula.starts_with('=') {
+        return Ok(FormulaValidation { issues });
+    }
+    let parsed = match parse_formula(formula) {
+        Ok(parsed) => parsed,
+        Err(e) => {
+            let start = e.offset.unwrap_or_else(|| formula.chars().count());
+            let end = start + e.token.as_ref().map_or(0, |t| t.chars().count());
+            issues.push(FormulaIssue {
+                kind: FormulaIssueKind::Syntax,
+                message: e.message,
+                start,
+                end,
+                token: e.token,
+                expected: e.expected,
+            });
+            return Ok(FormulaValidation { issues });
+        }
+    };
+
+    let mut sites = Vec::new();
+    collect_ref_sites(&parsed, &mut Vec::new(), &mut sites);
+    let tokens = spanned_tokens(formula);
+    let whole = (0, formula.chars().count());
+    let named_ranges = state.named_ranges.lock().map_err(|e| e.to_string())?;
+    let tables = state.tables.lock().map_err(|e| e.to_string())?;
+    let table_names = state.table_names.lock().map_err(|e| e.to_string())?;
+    let table_ctx = crate::TableRefContext {
+        tables: &tables,
+        table_names: &table_names,
+        current_sheet_index: sheet,
+        current_row: row,
+    };
+
+    // The k-th site for a name (or table) is its k-th written occurrence.
+    let mut seen: Vec<(bool, String)> = Vec::new();
+    let mut nth = |is_table: bool, key: &str| {
+        let key = (is_table, key.to_uppercase());
+        let n = seen.iter().filter(|k| **k == key).count();
+        seen.push(key);
+        n
+    };
+    for site in sites {
+        match site {
+            RefSite::Name { expr, name, local } => {
+                let n = nth(false, name);
+                if local || name == "#REF!" {
+                    continue;
+                }
+                let resolved = crate::resolve_names_in_ast(expr, &named_ranges, sheet, &mut HashSet::new());
+                if !matches!(&resolved, Expression::NamedRef { name: left, .. } if left == name) {
+                    continue;
+                }
+                let ((start, end), text) = match name_spans(&tokens, name).get(n) {
+                    Some(&(start, end)) => ((start, end), char_slice(formula, start, end)),
+                    None => (whole, name.to_string()),
+                };
+                issues.push(FormulaIssue {
+                    kind: FormulaIssueKind::UnknownName,
+                    message: format!("Unknown name '{}'", text),
+                    start,
+                    end,
+                    token: Some(text),
+                    expected: Vec::new(),
+                });
+            }
+            RefSite::Table { expr, table_name } => {
+                let n = nth(true, table_name);
+                let Expression::NamedRef { name: left, .. } = crate::resolve_table_refs_in_ast(expr, &table_ctx) else {
+                    continue;
+                };
+                let (start, end) = table_ref_spans(&tokens, table_name).get(n).copied().unwrap_or(whole);
+                let text = char_slice(formula, start, end);
+                let (kind, message) = if left.starts_with("_UNRESOLVED_") {
+                    (
+                        FormulaIssueKind::UnknownColumn,
+                        format!("{} names a column or section its table does not have", text),
+                    )
+                } else if table_name.is_empty() {
+                    (FormulaIssueKind::UnknownTable, format!("{} is used outside a table", text))
+                } else {
+                    let written = text.split('[').next().unwrap_or(table_name);
+                    (FormulaIssueKind::UnknownTable, format!("Unknown table '{}'", written))
+                };
+                issues.push(FormulaIssue { kind, message, start, end, token: Some(text), expected: Vec::new() });
+            }
+        }
+    }
+    Ok(FormulaValidation { issues })
+}
+
+/// Check a formula without entering it: a syntax error with its position,
+/// offending token and expected tokens, or the names, tables and table
+/// columns that do not resolve. For squiggles in the formula editor.
+#[tauri::command]
+pub fn validate_formula(
+    state: State<AppState>,
+    sheet: usize,
+    row: u32,
+    formula: String,
+) -> Result<FormulaValidation, String> {
+    log_enter!("CMD", "validate_formula", "sheet={} row={}", sheet, row);
+    let result = validate_formula_internal(&state, sheet, row, &formula)?;
+    log_exit!("CMD", "validate_formula", "issues={}", result.issues.len());
+    Ok(result)
+}
+
 // ============================================================================
 // Expression Evaluation (for file template resolution)
 // ============================================================================
@@ -160,7 +716,8 @@ pub fn evaluate_expressions(
         gather_data.get(region_id).cloned().unwrap_or_default()
     };
 
-    let mut evaluator = Evaluator::with_multi_sheet(current_grid, context);
+    // Template expressions belong to no cell, so ROW()/COLUMN() have no position
+    let mut evaluator = Evaluator::with_context(current_grid, context, engine::EvalContext::default());
     evaluator.set_file_reader(&reader);
     evaluator.set_gather_fn(&gather_fn);
 
@@ -264,10 +821,13 @@ fn evaluate_scoped_impl(
     };
 
     let grid = engine::Grid::new();
+    let grids = std::slice::from_ref(&grid);
+    let sheet_names = ["Sheet1".to_string()];
     let results = scopes
         .iter()
         .map(|scope| {
-            let evaluator = Evaluator::new(&grid);
+            let context = crate::create_multi_sheet_context(grids, &sheet_names, &sheet_names[0]);
+            let evaluator = Evaluator::with_context(&grid, context, engine::EvalContext::default());
             for (name, value) in scope {
                 evaluator.bind_name(name, scope_value_to_eval(value));
             }
@@ -13,7 +13,7 @@ use tauri::State;
 
 use crate::api_types::{EvalPlanNode, EvalReductionStep, FormulaEvalPlan};
 use crate::evaluate_formula::{
-    build_display, builtin_fn_name, evaluate_single_node,
+    array_literal_display, build_display, builtin_fn_name, evaluate_single_node,
     find_next_eval_node, get_node_mut, table_specifier_to_display, value_to_display,
 };
 use crate::{convert_expr, AppState};
@@ -69,6 +69,18 @@ fn assign_ids_recursive(
             });
         }
 
+        Expression::ArrayLiteral { rows } => {
+            nodes.push(NodeInfo {
+                id: id.clone(),
+                node_type: "literal".to_string(),
+                label: array_literal_display(rows),
+                subtitle: "array constant".to_string(),
+                children: vec![],
+                path: current_path.to_vec(),
+                is_leaf: true,
+            });
+        }
+
         Expression::CellRef { sheet, col, row, .. } => {
             let label = if let Some(s) = sheet {
                 format!("{}!{}{}", s, col, row)
@@ -177,7 +189,12 @@ fn assign_ids_recursive(
                 id: id.clone(),
                 node_type: "unary".to_string(),
                 label: op_str.to_string(),
-                subtitle: "negate".to_string(),
+                subtitle: match op {
+                    engine::UnaryOperator::Negate => "negate",
+                    engine::UnaryOperator::Plus => "plus",
+                    engine::UnaryOperator::Percent => "percent",
+                }
+                .to_string(),
                 children: vec![child_id],
                 path: current_path.to_vec(),
                 is_leaf: false,
@@ -398,6 +415,8 @@ fn binary_op_str(op: &BinaryOperator) -> &'static str {
 fn unary_op_str(op: &engine::UnaryOperator) -> &'static str {
     match op {
         engine::UnaryOperator::Negate => "-",
+        engine::UnaryOperator::Plus => "+",
+        engine::UnaryOperator::Percent => "%",
     }
 }
 
@@ -438,6 +457,10 @@ fn build_spans_recursive(
             output.push_str(&value_to_display(val));
         }
 
+        Expression::ArrayLiteral { rows } => {
+            output.push_str(&array_literal_display(rows));
+        }
+
         Expression::CellRef { sheet, col, row, .. } => {
             if let Some(sheet_name) = sheet {
                 if sheet_name.contains(' ') {
@@ -498,11 +521,17 @@ fn build_spans_recursive(
         }
 
         Expression::UnaryOp { op, operand } => {
-            output.push_str(unary_op_str(op));
+            if !op.is_postfix() {
+                output.push_str(unary_op_str(op));
+            }
 
             let mut child_path = current_path.to_vec();
             child_path.push(0);
             build_spans_recursive(operand, &child_path, output, spans);
+
+            if op.is_postfix() {
+                output.push_str(unary_op_str(op));
+            }
         }
 
         Expression::FunctionCall { func, args, .. } => {
@@ -754,7 +783,7 @@ fn format_child_all(
 /// Simple heuristic cost estimation for a node.
 fn estimate_cost(expr: &Expression) -> f64 {
     match expr {
-        Expression::Literal(_) => 0.0,
+        Expression::Literal(_) | Expression::ArrayLiteral { .. } => 0.0,
         Expression::CellRef { .. } => 1.0,
         Expression::Range { .. } | Expression::ColumnRef { .. } | Expression::RowRef { .. } => 5.0,
         Expression::BinaryOp { .. } => 2.0,
@@ -799,6 +828,7 @@ fn build_eval_plan(
     grids: &[engine::Grid],
     sheet_names: &[String],
     sheet_index: usize,
+    cell: (u32, u32),
 ) -> FormulaEvalPlan {
     // 1. Assign node IDs
     let (node_infos, path_to_id) = assign_node_ids(ast);
@@ -872,7 +902,7 @@ fn build_eval_plan(
 
                 // Evaluate this node
                 let node_expr = get_node_by_path(&working_ast, &next_node.path);
-                let result_value = evaluate_single_node(node_expr, grids, sheet_names, sheet_index);
+                let result_value = evaluate_single_node(node_expr, grids, sheet_names, sheet_index, cell);
                 let display_val = value_to_display(&result_value);
                 let raw_val = match &result_value {
                     Value::Number(n) => Some(*n),
@@ -1078,6 +1108,6 @@ pub fn get_formula_eval_plan(
         Err(e) => return Err(format!("Parse error: {}", e)),
     };
 
-    let plan = build_eval_plan(&ast, &grids, &sheet_names, active_sheet);
+    let plan = build_eval_plan(&ast, &grids, &sheet_names, active_sheet, (row, col));
     Ok(plan)
 }
@@ -1,17 +1,19 @@
 //! FILENAME: app/src-tauri/src/goal_seek.rs
 // PURPOSE: Goal Seek solver - iterative single-variable numerical solver.
 // CONTEXT: Uses the secant method to find a variable cell value that makes
-//          a target formula evaluate to a desired result.
+//          a target formula evaluate to a desired result. Trial values are
+//          evaluated through engine overlays, so no grid is mutated or cloned
+//          until the answer is applied.
 
 use std::collections::HashSet;
 use tauri::State;
 
 use crate::api_types::{CellData, GoalSeekParams, GoalSeekResult};
 use crate::{
-    evaluate_formula_multi_sheet,
+    evaluate_formula_multi_sheet, evaluate_formula_with_overlays,
     format_cell_value, get_column_row_dependents, get_recalculation_order, AppState,
 };
-use engine::{Cell, CellValue, Grid, StyleRegistry};
+use engine::{Cell, CellValue, Grid, OverlayGrid, StyleRegistry};
 
 // ============================================================================
 // Dependency verification
@@ -69,14 +71,16 @@ fn variable_affects_target(
 // Solver helper
 // ============================================================================
 
-/// Set the variable cell to a numeric value in the grids array and evaluate
-/// the target formula. Returns the numeric result, or None if non-numeric.
+/// Override the variable cell with a numeric value in the active sheet's
+/// overlay and evaluate the target formula against the overlays. Returns the
+/// numeric result, or None if non-numeric. The grids are never touched.
 fn evaluate_target(
-    grids: &mut [Grid],
+    overlays: &mut [OverlayGrid<'_>],
     sheet_names: &[String],
     active_sheet: usize,
     variable_pos: (u32, u32),
     variable_style_index: usize,
+    target_pos: (u32, u32),
     target_formula: &str,
     value: f64,
 ) -> Option<f64> {
@@ -84,15 +88,16 @@ fn evaluate_target(
     let mut var_cell = Cell::new_number(value);
     var_cell.style_index = variable_style_index;
 
-    // Set in the active sheet's grid
-    grids[active_sheet].set_cell(variable_pos.0, variable_pos.1, var_cell);
+    // Override in the active sheet's overlay
+    overlays[active_sheet].set_cell(variable_pos.0, variable_pos.1, var_cell);
 
     // Evaluate the target formula in multi-sheet context
-    let result = evaluate_formula_multi_sheet(
-        grids,
+    let result = evaluate_formula_with_overlays(
+        overlays,
         sheet_names,
         active_sheet,
         target_formula,
+        engine::EvalContext::at(target_pos.0, target_pos.1),
     );
 
     match result {
@@ -101,6 +106,119 @@ fn evaluate_target(
     }
 }
 
+/// Run the secant method against what-if overlays of `grids`.
+/// Returns (value, iterations, found) or the error message to report.
+#[allow(clippy::too_many_arguments)]
+fn solve(
+    grids: &[Grid],
+    sheet_names: &[String],
+    active_sheet: usize,
+    variable_pos: (u32, u32),
+    variable_style_index: usize,
+    target_pos: (u32, u32),
+    target_formula: &str,
+    original_value: f64,
+    goal: f64,
+    max_iter: u32,
+    tol: f64,
+) -> Result<(f64, u32, bool), &'static str> {
+    let mut overlays: Vec<OverlayGrid> = grids.iter().map(OverlayGrid::new).collect();
+    let mut eval = |x: f64| evaluate_target(
+        &mut overlays, sheet_names, active_sheet,
+        variable_pos, variable_style_index, target_pos, target_formula, x,
+    );
+
+    // Initial point: current variable value
+    let mut x0 = original_value;
+    let mut f0 = match eval(x0) {
+        Some(v) => v - goal,
+        None => return Err("Target formula does not evaluate to a number"),
+    };
+
+    // Check if already at the solution
+    if f0.abs() < tol {
+        return Ok((x0, 0, true));
+    }
+
+    // Second point: perturb slightly for secant method
+    let mut x1 = if x0.abs() < 1e-10 { 0.001 } else { x0 * 1.001 };
+    let mut f1 = match eval(x1) {
+        Some(v) => v - goal,
+        None => return Err("Target formula does not evaluate to a number"),
+    };
+
+    if f1.abs() < tol {
+        return Ok((x1, 1, true));
+    }
+
+    let mut iterations: u32 = 0;
+    let mut best_x = if f0.abs() < f1.abs() { x0 } else { x1 };
+    let mut best_f = f0.abs().min(f1.abs());
+
+    for _ in 0..max_iter {
+        iterations += 1;
+
+        let denominator = f1 - f0;
+        if denominator.abs() < 1e-15 {
+            // Derivative effectively zero - try a bigger perturbation
+            x1 = x1 + if x1.abs() < 1e-10 { 1.0 } else { x1 * 0.1 };
+            f1 = match eval(x1) {
+                Some(v) => v - goal,
+                None => break,
+            };
+            if f1.abs() < tol {
+                best_x = x1;
+                best_f = f1.abs();
+                break;
+            }
+            if f1.abs() < best_f {
+                best_f = f1.abs();
+                best_x = x1;
+            }
+            continue;
+        }
+
+        // Secant step
+        let x_new = x1 - f1 * (x1 - x0) / denominator;
+
+        // Clamp step size to prevent wild divergence
+        let step = x_new - x1;
+        let max_step = (x1 - x0).abs() * 10.0 + 1.0;
+        let x_new = if step.abs() > max_step {
+            x1 + step.signum() * max_step
+        } else {
+            x_new
+        };
+
+        let f_new = match eval(x_new) {
+            Some(v) => v - goal,
+            None => break,
+        };
+
+        if f_new.abs() < best_f {
+            best_f = f_new.abs();
+            best_x = x_new;
+        }
+
+        if f_new.abs() < tol {
+            break;
+        }
+
+        // Advance for next iteration
+        x0 = x1;
+        f0 = f1;
+        x1 = x_new;
+        f1 = f_new;
+    }
+
+    let found = best_f < tol;
+
+    crate::log_info!("GOALSEEK", "Done: found={} value={} residual={} iters={}",
+        found, best_x, best_f, iterations);
+
+    Ok((best_x, iterations, found))
+}
+
 /// Build an error GoalSeekResult with the given message.
 fn error_result(msg: &str) -> GoalSeekResult {
     GoalSeekResult {
@@ -145,7 +263,6 @@ pub fn goal_seek(
     }
 
     // Acquire locks (same order as update_cell to avoid deadlocks)
-    let mut grid = state.grid.lock().unwrap();
     let mut grids = state.grids.lock().unwrap();
     let active_sheet = *state.active_sheet.lock().unwrap();
     let sheet_names = state.sheet_names.lock().unwrap();
@@ -162,7 +279,7 @@ pub fn goal_seek(
     // --- Validation ---
 
     // 1. Target cell must contain a formula
-    let target_formula = match grid.get_cell(params.target_row, params.target_col) {
+    let target_formula = match grids[active_sheet].get_cell(params.target_row, params.target_col) {
         Some(cell) => match cell.formula_string() {
             Some(f) => f,
             None => return error_result("Cell must contain a formula"),
@@ -171,7 +288,7 @@ pub fn goal_seek(
     };
 
     // 2. Variable cell must NOT contain a formula
-    let variable_cell = grid.get_cell(params.variable_row, params.variable_col);
+    let variable_cell = grids[active_sheet].get_cell(params.variable_row, params.variable_col);
     if variable_cell.map_or(false, |c| c.has_formula()) {
         return error_result("Changing cell must not contain a formula");
     }
@@ -200,145 +317,24 @@ pub fn goal_seek(
     let tol = params.tolerance;
 
     // --- Secant Method Solver ---
+    // Trial values live in overlays; only the final value is written back.
 
-    // Initial point: current variable value
-    let mut x0 = original_value;
-    let f0_eval = evaluate_target(
-        &mut grids, &sheet_names, active_sheet,
-        variable_pos, variable_style_index, &target_formula, x0,
-    );
-    let mut f0 = match f0_eval {
-        Some(v) => v - goal,
-        None => {
-            // Restore original value
-            let mut restore_cell = Cell::new_number(original_value);
-            restore_cell.style_index = variable_style_index;
-            grids[active_sheet].set_cell(variable_pos.0, variable_pos.1, restore_cell);
-            return error_result("Target formula does not evaluate to a number");
-        }
-    };
-
-    // Check if already at the solution
-    if f0.abs() < tol {
-        // Already solved - still need to build updated_cells
-        // Fall through to finalization with x0 as the answer
-        return finalize_result(
-            &mut grid, &mut grids, &styles, &merged_regions,
-            &dependents_map, &column_dependents_map, &row_dependents_map,
-            &sheet_names, active_sheet,
-            variable_pos, variable_style_index,
-            target_pos, &target_formula,
-            x0, original_value, 0, true,
-            &locale,
-        );
-    }
-
-    // Second point: perturb slightly for secant method
-    let mut x1 = if x0.abs() < 1e-10 { 0.001 } else { x0 * 1.001 };
-    let f1_eval = evaluate_target(
-        &mut grids, &sheet_names, active_sheet,
-        variable_pos, variable_style_index, &target_formula, x1,
-    );
-    let mut f1 = match f1_eval {
-        Some(v) => v - goal,
-        None => {
-            let mut restore_cell = Cell::new_number(original_value);
-            restore_cell.style_index = variable_style_index;
-            grids[active_sheet].set_cell(variable_pos.0, variable_pos.1, restore_cell);
-            return error_result("Target formula does not evaluate to a number");
-        }
+    let (final_value, iterations, found) = match solve(
+        &grids, &sheet_names, active_sheet,
+        variable_pos, variable_style_index, target_pos, &target_formula,
+        original_value, goal, max_iter, tol,
+    ) {
+        Ok(outcome) => outcome,
+        Err(msg) => return error_result(msg),
     };
 
-    if f1.abs() < tol {
-        return finalize_result(
-            &mut grid, &mut grids, &styles, &merged_regions,
-            &dependents_map, &column_dependents_map, &row_dependents_map,
-            &sheet_names, active_sheet,
-            variable_pos, variable_style_index,
-            target_pos, &target_formula,
-            x1, original_value, 1, true,
-            &locale,
-        );
-    }
-
-    let mut iterations: u32 = 0;
-    let mut best_x = if f0.abs() < f1.abs() { x0 } else { x1 };
-    let mut best_f = f0.abs().min(f1.abs());
-
-    for _ in 0..max_iter {
-        iterations += 1;
-
-        let denominator = f1 - f0;
-        if denominator.abs() < 1e-15 {
-            // Derivative effectively zero - try a bigger perturbation
-            x1 = x1 + if x1.abs() < 1e-10 { 1.0 } else { x1 * 0.1 };
-            f1 = match evaluate_target(
-                &mut grids, &sheet_names, active_sheet,
-                variable_pos, variable_style_index, &target_formula, x1,
-            ) {
-                Some(v) => v - goal,
-                None => break,
-            };
-            if f1.abs() < tol {
-                best_x = x1;
-                best_f = f1.abs();
-                break;
-            }
-            if f1.abs() < best_f {
-                best_f = f1.abs();
-                best_x = x1;
-            }
-            continue;
-        }
-
-        // Secant step
-        let x_new = x1 - f1 * (x1 - x0) / denominator;
-
-        // Clamp step size to prevent wild divergence
-        let step = x_new - x1;
-        let max_step = (x1 - x0).abs() * 10.0 + 1.0;
-        let x_new = if step.abs() > max_step {
-            x1 + step.signum() * max_step
-        } else {
-            x_new
-        };
-
-        let f_new = match evaluate_target(
-            &mut grids, &sheet_names, active_sheet,
-            variable_pos, variable_style_index, &target_formula, x_new,
-        ) {
-            Some(v) => v - goal,
-            None => break,
-        };
-
-        if f_new.abs() < best_f {
-            best_f = f_new.abs();
-            best_x = x_new;
-        }
-
-        if f_new.abs() < tol {
-            break;
-        }
-
-        // Advance for next iteration
-        x0 = x1;
-        f0 = f1;
-        x1 = x_new;
-        f1 = f_new;
-    }
-
-    let found = best_f < tol;
-
-    crate::log_info!("GOALSEEK", "Done: found={} value={} residual={} iters={}",
-        found, best_x, best_f, iterations);
-
     finalize_result(
-        &mut grid, &mut grids, &styles, &merged_regions,
+        &mut grids, &styles, &merged_regions,
         &dependents_map, &column_dependents_map, &row_dependents_map,
         &sheet_names, active_sheet,
         variable_pos, variable_style_index,
         target_pos, &target_formula,
-        best_x, original_value, iterations, found,
+        final_value, original_value, iterations, found,
         &locale,
     )
 }
@@ -349,7 +345,6 @@ pub fn goal_seek(
 
 #[allow(clippy::too_many_arguments)]
 fn finalize_result(
-    grid: &mut Grid,
     grids: &mut [Grid],
     styles: &StyleRegistry,
     merged_regions: &HashSet<crate::api_types::MergedRegion>,
@@ -371,8 +366,7 @@ fn finalize_result(
     // 1. Set the final value in the variable cell
     let mut final_cell = Cell::new_number(final_value);
     final_cell.style_index = variable_style_index;
-    grids[active_sheet].set_cell(variable_pos.0, variable_pos.1, final_cell.clone());
-    grid.set_cell(variable_pos.0, variable_pos.1, final_cell);
+    grids[active_sheet].set_cell(variable_pos.0, variable_pos.1, final_cell);
 
     // 2. Re-evaluate the target cell to get its final display value
     let target_result_value = evaluate_formula_multi_sheet(
@@ -380,6 +374,7 @@ fn finalize_result(
         sheet_names,
         active_sheet,
         target_formula,
+        engine::EvalContext::at(target_pos.0, target_pos.1),
     );
     let target_result_num = match &target_result_value {
         CellValue::Number(n) => *n,
@@ -390,8 +385,7 @@ fn finalize_result(
     if let Some(target_cell) = grids[active_sheet].get_cell(target_pos.0, target_pos.1).cloned() {
         let mut updated_target = target_cell;
         updated_target.value = target_result_value.clone();
-        grids[active_sheet].set_cell(target_pos.0, target_pos.1, updated_target.clone());
-        grid.set_cell(target_pos.0, target_pos.1, updated_target);
+        grids[active_sheet].set_cell(target_pos.0, target_pos.1, updated_target);
     }
 
     // 3. Re-evaluate all dependents of the variable cell
@@ -412,11 +406,11 @@ fn finalize_result(
                     sheet_names,
                     active_sheet,
                     &formula,
+                    engine::EvalContext::at(r, c),
                 );
                 let mut updated = cell;
                 updated.value = new_value;
-                grids[active_sheet].set_cell(r, c, updated.clone());
-                grid.set_cell(r, c, updated);
+                grids[active_sheet].set_cell(r, c, updated);
             }
         }
     }
@@ -448,6 +442,7 @@ fn finalize_result(
             sheet_index: None,
             rich_text: None,
                 accounting_layout: None,
+                error_detail: None,
         })
     };
 
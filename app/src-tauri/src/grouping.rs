@@ -227,7 +227,7 @@ impl SheetOutline {
     }
 
     /// Recalculate max levels
-    fn recalculate_max_levels(&mut self) {
+    pub(crate) fn recalculate_max_levels(&mut self) {
         self.max_row_level = self.row_groups.iter().map(|g| g.level).max().unwrap_or(0);
         self.max_col_level = self.column_groups.iter().map(|g| g.level).max().unwrap_or(0);
     }
@@ -361,6 +361,17 @@ pub struct GroupColumnsParams {
 // COMMANDS
 // ============================================================================
 
+/// Pass a command's result through, re-evaluating the active sheet's table
+/// totals and SUBTOTAL/AGGREGATE cells once the command has released the
+/// outline lock - showing or hiding grouped rows changes what the 101-series
+/// codes see.
+fn with_refreshed_subtotals(state: &AppState, result: GroupResult) -> GroupResult {
+    if result.success {
+        crate::autofilter::refresh_filtered_totals(state, *state.active_sheet.lock().unwrap());
+    }
+    result
+}
+
 /// Group rows (create or increment outline level)
 #[tauri::command]
 pub fn group_rows(
@@ -404,6 +415,14 @@ pub fn ungroup_rows(
     state: State<AppState>,
     start_row: u32,
     end_row: u32,
+) -> GroupResult {
+    with_refreshed_subtotals(&state, ungroup_rows_internal(&state, start_row, end_row))
+}
+
+pub(crate) fn ungroup_rows_internal(
+    state: &AppState,
+    start_row: u32,
+    end_row: u32,
 ) -> GroupResult {
     let active_sheet = *state.active_sheet.lock().unwrap();
     let mut outlines = state.outlines.lock().unwrap();
@@ -568,6 +587,13 @@ pub fn ungroup_columns(
 pub fn collapse_row_group(
     state: State<AppState>,
     row: u32,
+) -> GroupResult {
+    with_refreshed_subtotals(&state, collapse_row_group_internal(&state, row))
+}
+
+pub(crate) fn collapse_row_group_internal(
+    state: &AppState,
+    row: u32,
 ) -> GroupResult {
     let active_sheet = *state.active_sheet.lock().unwrap();
     let mut outlines = state.outlines.lock().unwrap();
@@ -609,6 +635,13 @@ pub fn collapse_row_group(
 pub fn expand_row_group(
     state: State<AppState>,
     row: u32,
+) -> GroupResult {
+    with_refreshed_subtotals(&state, expand_row_group_internal(&state, row))
+}
+
+pub(crate) fn expand_row_group_internal(
+    state: &AppState,
+    row: u32,
 ) -> GroupResult {
     let active_sheet = *state.active_sheet.lock().unwrap();
     let mut outlines = state.outlines.lock().unwrap();
@@ -726,6 +759,14 @@ pub fn show_outline_level(
     state: State<AppState>,
     row_level: Option<u8>,
     col_level: Option<u8>,
+) -> GroupResult {
+    with_refreshed_subtotals(&state, show_outline_level_internal(&state, row_level, col_level))
+}
+
+pub(crate) fn show_outline_level_internal(
+    state: &AppState,
+    row_level: Option<u8>,
+    col_level: Option<u8>,
 ) -> GroupResult {
     let active_sheet = *state.active_sheet.lock().unwrap();
     let mut outlines = state.outlines.lock().unwrap();
@@ -878,6 +919,10 @@ pub fn set_outline_settings(
 /// Clear all outline/grouping for the current sheet
 #[tauri::command]
 pub fn clear_outline(state: State<AppState>) -> GroupResult {
+    with_refreshed_subtotals(&state, clear_outline_internal(&state))
+}
+
+pub(crate) fn clear_outline_internal(state: &AppState) -> GroupResult {
     let active_sheet = *state.active_sheet.lock().unwrap();
     let mut outlines = state.outlines.lock().unwrap();
 
@@ -8,7 +8,13 @@ use tauri::State;
 use crate::AppState;
 
 /// Record a hyperlink change to the undo stack.
-fn record_hyperlink_undo(state: &AppState, sheet_index: usize, row: u32, col: u32, previous: Option<Hyperlink>, description: &str) {
+pub(crate) fn record_hyperlink_undo(state: &AppState, sheet_index: usize, row: u32, col: u32, previous: Option<Hyperlink>, description: &str) {
+    let mut undo_stack = state.undo_stack.lock().unwrap();
+    record_hyperlink_undo_in(&mut undo_stack, sheet_index, row, col, previous, description);
+}
+
+/// `record_hyperlink_undo` for callers already holding the undo stack.
+pub(crate) fn record_hyperlink_undo_in(undo_stack: &mut engine::UndoStack, sheet_index: usize, row: u32, col: u32, previous: Option<Hyperlink>, description: &str) {
     #[derive(Serialize)]
     struct HyperlinkSnapshot {
         sheet_index: usize,
@@ -17,7 +23,6 @@ fn record_hyperlink_undo(state: &AppState, sheet_index: usize, row: u32, col: u3
         previous: Option<Hyperlink>,
     }
     let data = serde_json::to_vec(&HyperlinkSnapshot { sheet_index, row, col, previous }).unwrap_or_default();
-    let mut undo_stack = state.undo_stack.lock().unwrap();
     undo_stack.record_custom_restore("hyperlink".to_string(), data, description);
 }
 
@@ -77,6 +82,13 @@ pub struct Hyperlink {
     /// Tooltip/screen tip text
     #[serde(skip_serializing_if = "Option::is_none")]
     pub tooltip: Option<String>,
+    /// Internal target whose cells were deleted; the link no longer navigates
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub broken: bool,
+    /// Registered by a HYPERLINK formula in the cell; replaced or removed
+    /// when the cell is re-entered
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub from_formula: bool,
 }
 
 impl Hyperlink {
@@ -91,6 +103,8 @@ impl Hyperlink {
             internal_ref: None,
             display_text: None,
             tooltip: None,
+            broken: false,
+            from_formula: false,
         }
     }
 
@@ -105,6 +119,8 @@ impl Hyperlink {
             internal_ref: None,
             display_text: None,
             tooltip: None,
+            broken: false,
+            from_formula: false,
         }
     }
 
@@ -132,9 +148,24 @@ impl Hyperlink {
             }),
             display_text: None,
             tooltip: None,
+            broken: false,
+            from_formula: false,
         }
     }
 
+    /// Point an internal link at a new cell reference on the same target
+    /// sheet, keeping `target` in step with `internal_ref`.
+    pub(crate) fn set_internal_target(&mut self, target_sheet: Option<String>, cell_reference: String) {
+        self.target = match &target_sheet {
+            Some(name) => format!("'{}'!{}", name, cell_reference),
+            None => cell_reference.clone(),
+        };
+        self.internal_ref = Some(InternalReference {
+            sheet_name: target_sheet,
+            cell_reference,
+        });
+    }
+
     /// Create an email hyperlink
     pub fn new_email(
         row: u32,
@@ -156,6 +187,8 @@ impl Hyperlink {
             internal_ref: None,
             display_text: None,
             tooltip: None,
+            broken: false,
+            from_formula: false,
         }
     }
 }
@@ -454,23 +487,31 @@ pub fn get_hyperlinks_in_range(
     let active_sheet = *state.active_sheet.lock().unwrap();
     let hyperlinks = state.hyperlinks.lock().unwrap();
 
+    hyperlinks
+        .get(&active_sheet)
+        .map(|sheet_hyperlinks| {
+            hyperlink_indicators_in_rect(sheet_hyperlinks, (start_row, start_col, end_row, end_col))
+        })
+        .unwrap_or_default()
+}
+
+/// Hyperlink indicators of one sheet inside a rectangle (corners in any order).
+pub(crate) fn hyperlink_indicators_in_rect(
+    sheet_hyperlinks: &HashMap<(u32, u32), Hyperlink>,
+    (start_row, start_col, end_row, end_col): (u32, u32, u32, u32),
+) -> Vec<HyperlinkIndicator> {
     let min_row = start_row.min(end_row);
     let max_row = start_row.max(end_row);
     let min_col = start_col.min(end_col);
     let max_col = start_col.max(end_col);
 
-    hyperlinks
-        .get(&active_sheet)
-        .map(|sheet_hyperlinks| {
-            sheet_hyperlinks
-                .values()
-                .filter(|h| {
-                    h.row >= min_row && h.row <= max_row && h.col >= min_col && h.col <= max_col
-                })
-                .map(HyperlinkIndicator::from)
-                .collect()
+    sheet_hyperlinks
+        .values()
+        .filter(|h| {
+            h.row >= min_row && h.row <= max_row && h.col >= min_col && h.col <= max_col
         })
-        .unwrap_or_default()
+        .map(HyperlinkIndicator::from)
+        .collect()
 }
 
 /// Check if a cell has a hyperlink
@@ -561,6 +602,21 @@ pub fn move_hyperlink(
     }
 }
 
+/// Point internal links at `old_name` to `new_name` after a sheet rename.
+/// Returns true when any link changed.
+pub(crate) fn rename_target_sheet(hyperlinks: &mut HyperlinkStorage, old_name: &str, new_name: &str) -> bool {
+    let mut changed = false;
+    for link in hyperlinks.values_mut().flat_map(|links| links.values_mut()) {
+        let Some(internal) = &link.internal_ref else { continue };
+        if internal.sheet_name.as_deref() == Some(old_name) {
+            let cell_reference = internal.cell_reference.clone();
+            link.set_internal_target(Some(new_name.to_string()), cell_reference);
+            changed = true;
+        }
+    }
+    changed
+}
+
 /// Get hyperlinks for a specific sheet (internal use)
 pub fn get_hyperlinks_for_sheet(
     hyperlinks: &HyperlinkStorage,
@@ -572,6 +628,51 @@ pub fn get_hyperlinks_for_sheet(
         .unwrap_or_default()
 }
 
+// ============================================================================
+// FORMULA LINKS (HYPERLINK)
+// ============================================================================
+
+/// The link a HYPERLINK formula at (row, col) asks for. Cell and range
+/// references, with or without a sheet and Excel's leading "#" ("B3",
+/// "Sheet2!A1", "#'My Sheet'!A1:C4"), are internal references; "mailto:"
+/// targets are email links, targets with a scheme or "www." are URLs and
+/// anything else is a file path.
+pub(crate) fn formula_hyperlink(row: u32, col: u32, sheet_index: usize, target: &str) -> Hyperlink {
+    let target = target.trim();
+    let mut link = if let Some((sheet_name, cell_reference)) = internal_target(target) {
+        Hyperlink::new_internal(row, col, sheet_index, sheet_name, cell_reference)
+    } else if target.to_ascii_lowercase().starts_with("mailto:") {
+        Hyperlink {
+            link_type: HyperlinkType::Email,
+            ..Hyperlink::new_url(row, col, sheet_index, target.to_string())
+        }
+    } else if target.contains("://") || target.to_ascii_lowercase().starts_with("www.") {
+        Hyperlink::new_url(row, col, sheet_index, target.to_string())
+    } else {
+        Hyperlink::new_file(row, col, sheet_index, target.to_string())
+    };
+    link.from_formula = true;
+    link
+}
+
+/// Split an in-workbook link target into its sheet (unquoted) and A1 cell or
+/// range; None when `target` is not one.
+fn internal_target(target: &str) -> Option<(Option<String>, String)> {
+    let reference = target.strip_prefix('#').unwrap_or(target);
+    let (sheet_name, cell_reference) = match reference.rsplit_once('!') {
+        Some((sheet, cell)) => {
+            let sheet = match sheet.strip_prefix('\'').and_then(|s| s.strip_suffix('\'')) {
+                Some(quoted) => quoted.replace("''", "'"),
+                None => sheet.to_string(),
+            };
+            (Some(sheet).filter(|s| !s.is_empty()), cell)
+        }
+        None => (None, reference),
+    };
+    TargetRange::parse(cell_reference)?;
+    Some((sheet_name, cell_reference.trim().to_string()))
+}
+
 // ============================================================================
 // HELPER FUNCTIONS
 // ============================================================================
@@ -644,6 +745,79 @@ pub fn parse_cell_reference(cell_ref: &str) -> Option<(u32, u32)> {
     Some((row, col))
 }
 
+/// An internal link's cell or range target ("B5", "$B$5", "A1:C10"), 0-based
+/// with its corners ordered. The `$` markers of each corner are kept so the
+/// shifted reference renders the way the user wrote it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct TargetRange {
+    pub start_row: u32,
+    pub start_col: u32,
+    pub end_row: u32,
+    pub end_col: u32,
+    /// (absolute column, absolute row) of the start and end corners.
+    absolute: [(bool, bool); 2],
+    single_cell: bool,
+}
+
+impl TargetRange {
+    /// Parse an A1 cell or range; None for anything else (e.g. a defined name).
+    pub(crate) fn parse(reference: &str) -> Option<Self> {
+        fn corner(text: &str) -> Option<(u32, u32, bool, bool)> {
+            let (abs_col, rest) = match text.strip_prefix('$') {
+                Some(rest) => (true, rest),
+                None => (false, text),
+            };
+            let letters = rest.chars().take_while(|c| c.is_ascii_alphabetic()).count();
+            if letters == 0 || letters > 3 {
+                return None;
+            }
+            let (col_str, rest) = rest.split_at(letters);
+            let (abs_row, digits) = match rest.strip_prefix('$') {
+                Some(digits) => (true, digits),
+                None => (false, rest),
+            };
+            if digits.is_empty() || !digits.chars().all(|c| c.is_ascii_digit()) {
+                return None;
+            }
+            let row: u32 = digits.parse().ok().filter(|&r| r > 0)?;
+            Some((row - 1, engine::coord::col_to_index(col_str), abs_col, abs_row))
+        }
+
+        let reference = reference.trim();
+        let (first, second) = match reference.split_once(':') {
+            Some((a, b)) => (corner(a)?, Some(corner(b)?)),
+            None => (corner(reference)?, None),
+        };
+        let last = second.unwrap_or(first);
+        Some(TargetRange {
+            start_row: first.0.min(last.0),
+            start_col: first.1.min(last.1),
+            end_row: first.0.max(last.0),
+            end_col: first.1.max(last.1),
+            absolute: [(first.2, first.3), (last.2, last.3)],
+            single_cell: second.is_none(),
+        })
+    }
+
+    pub(crate) fn render(&self) -> String {
+        let corner = |row: u32, col: u32, (abs_col, abs_row): (bool, bool)| {
+            format!(
+                "{}{}{}{}",
+                if abs_col { "$" } else { "" },
+                engine::coord::index_to_col(col),
+                if abs_row { "$" } else { "" },
+                row + 1
+            )
+        };
+        let start = corner(self.start_row, self.start_col, self.absolute[0]);
+        if self.single_cell {
+            start
+        } else {
+            format!("{}:{}", start, corner(self.end_row, self.end_col, self.absolute[1]))
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -670,6 +844,25 @@ mod tests {
         assert!(h.target.starts_with("mailto:test@example.com"));
     }
 
+    #[test]
+    fn test_formula_hyperlink_classifies_targets() {
+        let internal = formula_hyperlink(2, 1, 0, "#'My Sheet'!B3");
+        assert_eq!(internal.link_type, HyperlinkType::InternalReference);
+        let reference = internal.internal_ref.as_ref().unwrap();
+        assert_eq!(reference.sheet_name.as_deref(), Some("My Sheet"));
+        assert_eq!(reference.cell_reference, "B3");
+        assert!(internal.from_formula);
+
+        let same_sheet = formula_hyperlink(0, 0, 0, "A1:C4");
+        assert_eq!(same_sheet.link_type, HyperlinkType::InternalReference);
+        assert_eq!(same_sheet.internal_ref.unwrap().sheet_name, None);
+
+        assert_eq!(formula_hyperlink(0, 0, 0, "https://example.com").link_type, HyperlinkType::Url);
+        assert_eq!(formula_hyperlink(0, 0, 0, "www.example.com").link_type, HyperlinkType::Url);
+        assert_eq!(formula_hyperlink(0, 0, 0, "mailto:a@b.com").link_type, HyperlinkType::Email);
+        assert_eq!(formula_hyperlink(0, 0, 0, "C:\\Reports\\q1.xlsx").link_type, HyperlinkType::File);
+    }
+
     #[test]
     fn test_parse_cell_reference() {
         assert_eq!(parse_cell_reference("A1"), Some((0, 0)));
@@ -678,6 +871,16 @@ mod tests {
         assert_eq!(parse_cell_reference("Z100"), Some((99, 25)));
     }
 
+    #[test]
+    fn test_target_range_round_trip() {
+        let range = TargetRange::parse("$B5:c$10").unwrap();
+        assert_eq!((range.start_row, range.start_col, range.end_row, range.end_col), (4, 1, 9, 2));
+        assert_eq!(range.render(), "$B5:C$10");
+        assert_eq!(TargetRange::parse("AA100").unwrap().render(), "AA100");
+        assert!(TargetRange::parse("MyName").is_none());
+        assert!(TargetRange::parse("A0").is_none());
+    }
+
     #[test]
     fn test_is_valid_url() {
         assert!(is_valid_url("https://example.com"));
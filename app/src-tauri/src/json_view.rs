@@ -141,28 +141,18 @@ pub fn get_object_json(
             let idx: usize = object_id.parse().map_err(|_| "Invalid sheet index".to_string())?;
             // Cell values are the sparse, A1-keyed grid contents — a structure
             // entirely separate from the layout above (which is only column/row
-            // sizes). The active sheet's live cells are in the primary grid;
-            // other sheets are held in the per-sheet grids vector (the active
-            // slot there is stale). Read the freshest source, then reuse the
-            // .cala serializer so the shape matches the on-disk data.json.
-            let active = *state.active_sheet.lock().unwrap();
-            let cells: std::collections::HashMap<(u32, u32), ::persistence::SavedCell> =
-                if idx == active {
-                    let grid = state.grid.lock().unwrap();
-                    grid.cells
-                        .iter()
-                        .map(|(&rc, c)| (rc, ::persistence::SavedCell::from_cell(c)))
-                        .collect()
-                } else {
-                    let grids = state.grids.lock().unwrap();
-                    let grid = grids
-                        .get(idx)
-                        .ok_or_else(|| format!("Sheet {} not found", idx))?;
-                    grid.cells
-                        .iter()
-                        .map(|(&rc, c)| (rc, ::persistence::SavedCell::from_cell(c)))
-                        .collect()
-                };
+            // sizes). Reuse the .cala serializer so the shape matches the
+            // on-disk data.json.
+            let cells: std::collections::HashMap<(u32, u32), ::persistence::SavedCell> = {
+                let grids = state.grids.lock().unwrap();
+                let grid = grids
+                    .get(idx)
+                    .ok_or_else(|| format!("Sheet {} not found", idx))?;
+                grid.cells
+                    .iter()
+                    .map(|(&rc, c)| (rc, ::persistence::SavedCell::from_cell(c)))
+                    .collect()
+            };
             let data = calcula_format::sheet_data::cells_to_sheet_data(&cells);
             serde_json::to_string_pretty(&data).map_err(|e| e.to_string())
         }
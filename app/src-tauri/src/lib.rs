@@ -47,6 +47,8 @@ pub type DependencyMap = FxHashMap<(u32, u32), CoordSet>;
 pub type StripeDependentsMap = FxHashMap<u32, CoordSet>;
 /// formula cell -> column/row indices it depends on (for cleanup).
 pub type StripeDependenciesMap = FxHashMap<(u32, u32), FxHashSet<u32>>;
+/// (sheet, row, col) -> the error a formula produced there and why.
+pub type ErrorDetailMap = FxHashMap<(usize, u32, u32), (CellError, String)>;
 /// (sheet_name, row, col) -> dependent formula cells on other sheets.
 pub type CrossSheetDependentsMap =
     FxHashMap<(String, u32, u32), FxHashSet<(usize, u32, u32)>>;
@@ -60,16 +62,22 @@ pub use identity;
 pub mod persistence;
 pub mod api_types;
 pub mod calculation;
+pub mod calc_worker;
+pub mod change_events;
+pub mod structural_change;
+pub mod protected_regions;
 pub mod commands;
 pub mod formula;
 pub mod logging;
 pub mod sheets;
 pub mod undo_commands;
+pub mod change_journal;
 pub mod merge_commands;
 pub mod pivot;
 pub mod bi;
 pub mod scripting;
 pub mod named_ranges;
+pub mod name_dependents;
 pub mod data_validation;
 pub mod comments;
 pub mod notes;
@@ -92,6 +100,7 @@ pub mod formula_eval_plan;
 pub mod consolidate;
 pub mod status_bar;
 pub mod computed_properties;
+pub mod ui_effects;
 pub mod controls;
 pub mod cell_types;
 pub mod cell_behaviors;
@@ -116,13 +125,20 @@ pub mod state_digest;
 pub mod security;
 pub mod net_commands;
 pub mod file_keychain;
+pub mod read_only;
+pub mod sheet_access;
+pub mod viewport_revisions;
 pub mod ai_chat;
+#[cfg(feature = "headless")]
+pub mod session;
 
 pub use api_types::{CellData, StyleData, DimensionData, FormattingParams, MergedRegion};
 pub use logging::{init_log_file, get_log_path, next_seq, write_log, write_log_raw};
 pub use engine::{Transaction, CellChange};
 pub use sheets::FreezeConfig;
 pub use sheets::SplitConfig;
+#[cfg(feature = "headless")]
+pub use session::WorkbookSession;
 pub use named_ranges::{NamedRange, NamedRangeResult, ApplyNamesResult};
 pub use data_validation::{
     DataValidation, DataValidationType, DataValidationOperator, DataValidationAlertStyle,
@@ -150,8 +166,8 @@ pub use hyperlinks::{
 pub use protection::{
     SheetProtection, SheetProtectionOptions, AllowEditRange, CellProtection,
     ProtectionResult, ProtectionCheckResult, ProtectionStatus,
-    ProtectionStorage, CellProtectionStorage,
-    ProtectSheetParams, AddAllowEditRangeParams, SetCellProtectionParams,
+    ProtectionStorage, CellProtectionStorage, RangeProtection, RangeProtectionStorage,
+    ProtectSheetParams, AddAllowEditRangeParams, SetCellProtectionParams, SetRangeProtectionParams,
     WorkbookProtection, WorkbookProtectionResult, WorkbookProtectionStatus,
 };
 pub use grouping::{
@@ -186,7 +202,8 @@ mod tests;
 
 /// A generic protected region on a sheet, registered by any extension (e.g., pivot, chart).
 /// Extensions register regions through this struct; the kernel remains feature-agnostic.
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
 pub struct ProtectedRegion {
     /// Unique identifier (e.g., "pivot-1", "chart-3")
     pub id: String,
@@ -208,9 +225,6 @@ pub struct AppState {
     pub sheet_names: Mutex<Vec<String>>,
     /// Currently active sheet index
     pub active_sheet: Mutex<usize>,
-    /// The currently active grid (synced with grids[active_sheet])
-    /// Commands use this for all cell operations
-    pub grid: Mutex<Grid>,
     pub style_registry: Mutex<StyleRegistry>,
     /// Column widths for the currently active sheet (swapped on sheet switch)
     pub column_widths: Mutex<HashMap<u32, f64>>,
@@ -224,16 +238,25 @@ pub struct AppState {
     pub default_row_height: Mutex<f64>,
     /// Default column width for columns without custom widths (pixels)
     pub default_column_width: Mutex<f64>,
+    /// Per-sheet default sizes overriding the two workbook defaults above
+    /// (indexed by sheet index)
+    pub sheet_defaults: Mutex<Vec<crate::api_types::SheetDefaults>>,
     pub dependents: Mutex<DependencyMap>,
     pub dependencies: Mutex<DependencyMap>,
-    /// Calculation mode: "automatic" or "manual"
-    pub calculation_mode: Mutex<String>,
-    /// Iterative calculation: allow circular references to converge
-    pub iteration_enabled: Mutex<bool>,
-    /// Maximum number of iterations for circular reference resolution
-    pub max_iterations: Mutex<u32>,
-    /// Maximum change threshold for convergence (stop when delta < this value)
-    pub max_change: Mutex<f64>,
+    /// Workbook calculation settings: mode, iteration, precision as
+    /// displayed, recalculate before save. Leaf lock.
+    pub calculation_settings: Mutex<calculation::CalculationSettings>,
+    /// Formula cells (sheet_index, row, col) invalidated by edits made while
+    /// in manual calculation mode. Cleared by a full recalculation.
+    pub dirty_cells: Mutex<FxHashSet<(usize, u32, u32)>>,
+    /// Formula cells of the ACTIVE sheet that call a volatile function (RAND,
+    /// NOW, OFFSET, ...). Every recalculation includes them and their
+    /// dependents. Rebuilt with the dependency maps and by a full recalc.
+    pub volatile_cells: Mutex<CoordSet>,
+    /// Explanations of formula errors ("SQRT argument was negative"), kept
+    /// beside the cell values rather than in them. An entry only applies while
+    /// its cell still holds the same error; every evaluation replaces it.
+    pub error_details: Mutex<ErrorDetailMap>,
     /// Column-level dependencies: column index -> set of formula cells that depend on entire column
     pub column_dependents: Mutex<StripeDependentsMap>,
     /// Row-level dependencies: row index -> set of formula cells that depend on entire row
@@ -247,21 +270,26 @@ pub struct AppState {
     /// Track which cross-sheet cells each formula depends on (for cleanup)
     pub cross_sheet_dependencies: Mutex<CrossSheetDependenciesMap>,
     pub undo_stack: Mutex<UndoStack>,
+    /// Opt-in session change journal fed by committed undo transactions
+    pub change_journal: Mutex<change_journal::ChangeJournal>,
     /// Freeze pane configurations per sheet
     pub freeze_configs: Mutex<Vec<FreezeConfig>>,
     /// Split window configurations per sheet
     pub split_configs: Mutex<Vec<SplitConfig>>,
-    /// Per-sheet gridlines visibility (default true)
-    pub show_gridlines: Mutex<Vec<bool>>,
+    /// Per-sheet view options: zoom, gridlines, headings, direction
+    pub sheet_view_options: Mutex<Vec<crate::api_types::SheetViewOptions>>,
     /// Merged cell regions for the current (active) sheet
     pub merged_regions: Mutex<HashSet<MergedRegion>>,
     /// Merged cell regions for ALL sheets (swapped on sheet switch)
     pub all_merged_regions: Mutex<Vec<HashSet<MergedRegion>>>,
     /// Protected regions - cells in these regions cannot be edited directly.
     /// Registered by extensions (e.g., pivot tables, charts).
-    pub protected_regions: Mutex<Vec<ProtectedRegion>>,
+    pub protected_regions: Mutex<protected_regions::ProtectedRegionStore>,
     /// Named ranges for formula references (key is uppercase name)
     pub named_ranges: Mutex<HashMap<String, named_ranges::NamedRange>>,
+    /// Typed formulas of cells whose names/table references were expanded at
+    /// edit time, so definition changes can re-expand them
+    pub name_ref_sources: Mutex<name_dependents::NameRefSources>,
     /// Data validation rules per sheet
     pub data_validations: Mutex<data_validation::ValidationStorage>,
     /// Comments per sheet: sheet_index -> (row, col) -> Comment
@@ -272,10 +300,15 @@ pub struct AppState {
     pub auto_filters: Mutex<autofilter::AutoFilterStorage>,
     /// Hyperlinks per sheet: sheet_index -> (row, col) -> Hyperlink
     pub hyperlinks: Mutex<hyperlinks::HyperlinkStorage>,
+    /// Formatting requested by SET.* formulas and the values it replaced;
+    /// applied by `apply_pending_ui_effects`
+    pub ui_effects: Mutex<ui_effects::UiEffectRegistry>,
     /// Sheet protection settings per sheet
     pub sheet_protection: Mutex<protection::ProtectionStorage>,
     /// Cell-level protection per sheet: sheet_index -> (row, col) -> CellProtection
     pub cell_protection: Mutex<protection::CellProtectionStorage>,
+    /// Range-level protection records per sheet: sheet_index -> records, oldest first
+    pub range_protection: Mutex<protection::RangeProtectionStorage>,
     /// Workbook-level structural protection (prevents add/delete/rename/move sheets)
     pub workbook_protection: Mutex<protection::WorkbookProtection>,
     /// Row/column grouping (outlines) per sheet
@@ -335,10 +368,6 @@ pub struct AppState {
     pub named_styles: Mutex<HashMap<String, api_types::NamedCellStyle>>,
     /// Workbook document properties (author, title, subject, etc.)
     pub workbook_properties: Mutex<api_types::WorkbookProperties>,
-    /// Use displayed precision for calculations (default: false)
-    pub precision_as_displayed: Mutex<bool>,
-    /// Recalculate before saving (default: true)
-    pub calculate_before_save: Mutex<bool>,
     /// Chart entries: persisted chart definitions (opaque JSON)
     pub charts: Mutex<Vec<api_types::ChartEntry>>,
     /// Sparkline entries: persisted sparkline groups per sheet (opaque JSON)
@@ -394,40 +423,63 @@ pub struct AppState {
     /// entries before it are hidden ("blank on reload"). Reset at workbook
     /// open/new.
     pub model_writeback_floor: Mutex<String>,
+    /// Workbook revision, advanced by every edit command. See sheet_access.
+    pub workbook_revision: Mutex<u64>,
+    /// Per-sheet cell revisions for differential viewport snapshots. See
+    /// viewport_revisions.
+    pub viewport_revisions: Mutex<crate::viewport_revisions::ViewportRevisions>,
+}
+
+/// The active sheet's grid, borrowed in place from `AppState::grids`.
+/// Holds the `grids` lock for its lifetime, so a caller that also needs
+/// other sheets should lock `grids` directly instead.
+pub struct ActiveGrid<'a> {
+    grids: std::sync::MutexGuard<'a, Vec<Grid>>,
+    index: usize,
+}
+
+impl ActiveGrid<'_> {
+    /// Index of the active sheet.
+    pub fn index(&self) -> usize {
+        self.index
+    }
+}
+
+impl std::ops::Deref for ActiveGrid<'_> {
+    type Target = Grid;
+    fn deref(&self) -> &Grid {
+        &self.grids[self.index]
+    }
+}
+
+impl std::ops::DerefMut for ActiveGrid<'_> {
+    fn deref_mut(&mut self) -> &mut Grid {
+        &mut self.grids[self.index]
+    }
 }
 
 impl AppState {
-    /// Get the active grid (convenience method)
-    pub fn get_active_grid(&self) -> std::sync::MutexGuard<'_, Grid> {
-        self.grid.lock().unwrap()
+    /// Get the active grid. Reads `active_sheet` and releases it before
+    /// locking `grids`; callers must not hold either lock.
+    pub fn get_active_grid(&self) -> ActiveGrid<'_> {
+        let index = *self.active_sheet.lock().unwrap();
+        ActiveGrid { grids: self.grids.lock().unwrap(), index }
     }
     
     /// Check if a cell is within any protected region.
     /// Returns the first matching region, or None.
     pub fn get_region_at_cell(&self, sheet_index: usize, row: u32, col: u32) -> Option<ProtectedRegion> {
-        let regions = self.protected_regions.lock().unwrap();
-        for region in regions.iter() {
-            if region.sheet_index == sheet_index
-                && row >= region.start_row
-                && row <= region.end_row
-                && col >= region.start_col
-                && col <= region.end_col
-            {
-                return Some(region.clone());
-            }
-        }
-        None
+        let mut regions = self.protected_regions.lock().unwrap();
+        regions.region_at(sheet_index, row, col).cloned()
     }
 }
 
 pub fn create_app_state() -> AppState {
     log_info!("SYS", "Creating AppState");
-    let initial_grid = Grid::new();
     let app_state = AppState {
-        grids: Mutex::new(vec![initial_grid.clone()]),
+        grids: Mutex::new(vec![Grid::new()]),
         sheet_names: Mutex::new(vec!["Sheet1".to_string()]),
         active_sheet: Mutex::new(0),
-        grid: Mutex::new(initial_grid),
         style_registry: Mutex::new(StyleRegistry::new()),
         column_widths: Mutex::new(HashMap::new()),
         row_heights: Mutex::new(HashMap::new()),
@@ -435,12 +487,13 @@ pub fn create_app_state() -> AppState {
         all_row_heights: Mutex::new(vec![HashMap::new()]),
         default_row_height: Mutex::new(20.0), // Excel default: Calibri 11 => 15pt = 20px
         default_column_width: Mutex::new(64.29), // Excel default: 8.47 chars => 8.47*7+5 = 64.29px
+        sheet_defaults: Mutex::new(vec![crate::api_types::SheetDefaults::default()]),
         dependents: Mutex::new(DependencyMap::default()),
         dependencies: Mutex::new(DependencyMap::default()),
-        calculation_mode: Mutex::new("automatic".to_string()),
-        iteration_enabled: Mutex::new(false),
-        max_iterations: Mutex::new(100),
-        max_change: Mutex::new(0.001),
+        calculation_settings: Mutex::new(calculation::CalculationSettings::default()),
+        dirty_cells: Mutex::new(FxHashSet::default()),
+        volatile_cells: Mutex::new(CoordSet::default()),
+        error_details: Mutex::new(ErrorDetailMap::default()),
         column_dependents: Mutex::new(StripeDependentsMap::default()),
         row_dependents: Mutex::new(StripeDependentsMap::default()),
         column_dependencies: Mutex::new(StripeDependenciesMap::default()),
@@ -448,20 +501,24 @@ pub fn create_app_state() -> AppState {
         cross_sheet_dependents: Mutex::new(CrossSheetDependentsMap::default()),
         cross_sheet_dependencies: Mutex::new(CrossSheetDependenciesMap::default()),
         undo_stack: Mutex::new(UndoStack::new()),
+        change_journal: Mutex::new(change_journal::ChangeJournal::default()),
         freeze_configs: Mutex::new(vec![FreezeConfig::default()]),
         split_configs: Mutex::new(vec![SplitConfig::default()]),
-        show_gridlines: Mutex::new(vec![true]),
+        sheet_view_options: Mutex::new(vec![crate::api_types::SheetViewOptions::default()]),
         merged_regions: Mutex::new(HashSet::new()),
         all_merged_regions: Mutex::new(Vec::new()),
-        protected_regions: Mutex::new(Vec::new()),
+        protected_regions: Mutex::new(protected_regions::ProtectedRegionStore::new()),
         named_ranges: Mutex::new(HashMap::new()),
+        name_ref_sources: Mutex::new(HashMap::new()),
         data_validations: Mutex::new(HashMap::new()),
         comments: Mutex::new(HashMap::new()),
         notes: Mutex::new(HashMap::new()),
         auto_filters: Mutex::new(HashMap::new()),
         hyperlinks: Mutex::new(HashMap::new()),
+        ui_effects: Mutex::new(ui_effects::UiEffectRegistry::default()),
         sheet_protection: Mutex::new(HashMap::new()),
         cell_protection: Mutex::new(HashMap::new()),
+        range_protection: Mutex::new(HashMap::new()),
         workbook_protection: Mutex::new(protection::WorkbookProtection::default()),
         outlines: Mutex::new(HashMap::new()),
         conditional_formats: Mutex::new(HashMap::new()),
@@ -506,8 +563,6 @@ pub fn create_app_state() -> AppState {
                 ..Default::default()
             }
         }),
-        precision_as_displayed: Mutex::new(false),
-        calculate_before_save: Mutex::new(true),
         charts: Mutex::new(Vec::new()),
         sparklines: Mutex::new(Vec::new()),
         scroll_areas: Mutex::new(vec![None]),
@@ -529,6 +584,8 @@ pub fn create_app_state() -> AppState {
         writeback_layer: Mutex::new(calp::writeback::WritebackLayer::new()),
         model_writeback: Mutex::new(crate::bi::writeback::ModelWritebackStore::default()),
         model_writeback_floor: Mutex::new(chrono::Utc::now().to_rfc3339()),
+        workbook_revision: Mutex::new(0),
+        viewport_revisions: Mutex::new(crate::viewport_revisions::ViewportRevisions::default()),
     };
 
     // Register the initial sheet in the IdRegistry
@@ -734,6 +791,7 @@ pub fn expand_wildcard_sheets(expr: &mut engine::Expression) {
         }
         // Leaf nodes — nothing to expand
         engine::Expression::Literal(_)
+        | engine::Expression::ArrayLiteral { .. }
         | engine::Expression::NamedRef { .. }
         | engine::Expression::TableRef { .. } => {}
     }
@@ -914,6 +972,9 @@ pub struct ExtractedRefs {
     pub rows: FxHashSet<u32>,
     /// Cross-sheet cell references (sheet_name, row, col) - row is 0-indexed
     pub cross_sheet_cells: FxHashSet<(String, u32, u32)>,
+    /// 3D references (start_sheet, end_sheet, row, col); `*` bookends cover
+    /// every sheet. Expanded to concrete sheets by `resolve_cross_sheet_refs`.
+    pub sheet_3d_cells: FxHashSet<(String, String, u32, u32)>,
 }
 
 impl ExtractedRefs {
@@ -923,6 +984,7 @@ impl ExtractedRefs {
             columns: FxHashSet::default(),
             rows: FxHashSet::default(),
             cross_sheet_cells: FxHashSet::default(),
+            sheet_3d_cells: FxHashSet::default(),
         }
     }
 }
@@ -940,11 +1002,13 @@ pub fn extract_all_references(expr: &ParserExpr, grid: &Grid) -> ExtractedRefs {
 
 fn extract_references_recursive(expr: &ParserExpr, grid: &Grid, refs: &mut ExtractedRefs) {
     match expr {
-        ParserExpr::Literal(_) => {}
+        ParserExpr::Literal(_) | ParserExpr::ArrayLiteral { .. } => {}
         ParserExpr::CellRef { sheet, col, row, .. } => {
             let col_idx = col_letter_to_index(col);
             let row_idx = row.saturating_sub(1);
-            if let Some(sheet_name) = sheet {
+            if sheet.as_deref() == Some("*") {
+                refs.sheet_3d_cells.insert(("*".to_string(), "*".to_string(), row_idx, col_idx));
+            } else if let Some(sheet_name) = sheet {
                 refs.cross_sheet_cells.insert((sheet_name.clone(), row_idx, col_idx));
             } else {
                 refs.cells.insert((row_idx, col_idx));
@@ -961,7 +1025,13 @@ fn extract_references_recursive(expr: &ParserExpr, grid: &Grid, refs: &mut Extra
                 let sr = start_row.saturating_sub(1);
                 let er = end_row.saturating_sub(1);
                 
-                if let Some(sheet_name) = sheet {
+                if sheet.as_deref() == Some("*") {
+                    for r in sr.min(er)..=sr.max(er) {
+                        for c in sc.min(ec)..=sc.max(ec) {
+                            refs.sheet_3d_cells.insert(("*".to_string(), "*".to_string(), r, c));
+                        }
+                    }
+                } else if let Some(sheet_name) = sheet {
                     for r in sr.min(er)..=sr.max(er) {
                         for c in sc.min(ec)..=sc.max(ec) {
                             refs.cross_sheet_cells.insert((sheet_name.clone(), r, c));
@@ -1016,20 +1086,24 @@ fn extract_references_recursive(expr: &ParserExpr, grid: &Grid, refs: &mut Extra
         ParserExpr::UnaryOp { operand, .. } => {
             extract_references_recursive(operand, grid, refs);
         }
-        ParserExpr::FunctionCall { args, .. } => {
+        ParserExpr::FunctionCall { func, args, .. } => {
             for arg in args {
                 extract_references_recursive(arg, grid, refs);
             }
+            if matches!(func, ParserBuiltinFn::Offset) {
+                if let Some(anchor) = args.first() {
+                    add_offset_anchor_stripes(anchor, refs);
+                }
+            }
         }
-        // 3D cross-sheet reference: tag inner cells with each bookend sheet
+        // 3D cross-sheet reference: keep the bookends, the covered sheets
+        // depend on the sheet order at registration time
         ParserExpr::Sheet3DRef { start_sheet, end_sheet, reference, .. } => {
             // Extract the inner reference's cells (without sheet context)
             let mut inner_refs = ExtractedRefs::new();
             extract_references_recursive(reference, grid, &mut inner_refs);
-            // Tag each cell with both bookend sheets as cross-sheet dependencies
             for (row, col) in &inner_refs.cells {
-                refs.cross_sheet_cells.insert((start_sheet.clone(), *row, *col));
-                refs.cross_sheet_cells.insert((end_sheet.clone(), *row, *col));
+                refs.sheet_3d_cells.insert((start_sheet.clone(), end_sheet.clone(), *row, *col));
             }
         }
         // NamedRef nodes should be resolved before reference extraction.
@@ -1065,6 +1139,35 @@ fn extract_references_recursive(expr: &ParserExpr, grid: &Grid, refs: &mut Extra
     }
 }
 
+/// OFFSET reads cells its formula never names, so its anchor alone is not a
+/// sufficient dependency. As a conservative fallback the formula depends on
+/// the anchor's whole columns and rows: that covers the usual window sliding
+/// down a column or along a row. Cells further afield, and anchors on other
+/// sheets, rely on OFFSET being volatile (recalculated on every pass).
+fn add_offset_anchor_stripes(anchor: &ParserExpr, refs: &mut ExtractedRefs) {
+    let (cols, rows) = match anchor {
+        ParserExpr::CellRef { sheet: None, col, row, .. } => {
+            let col = col_letter_to_index(col);
+            let row = row.saturating_sub(1);
+            ((col, col), (row, row))
+        }
+        ParserExpr::Range { sheet: None, start, end, .. } => match (start.as_ref(), end.as_ref()) {
+            (
+                ParserExpr::CellRef { col: start_col, row: start_row, .. },
+                ParserExpr::CellRef { col: end_col, row: end_row, .. },
+            ) => {
+                let (sc, ec) = (col_letter_to_index(start_col), col_letter_to_index(end_col));
+                let (sr, er) = (start_row.saturating_sub(1), end_row.saturating_sub(1));
+                ((sc.min(ec), sc.max(ec)), (sr.min(er), sr.max(er)))
+            }
+            _ => return,
+        },
+        _ => return,
+    };
+    refs.columns.extend(cols.0..=cols.1);
+    refs.rows.extend(rows.0..=rows.1);
+}
+
 // ============================================================================
 // NAMED REFERENCE RESOLUTION (AST SPLICING)
 // ============================================================================
@@ -1139,6 +1242,7 @@ pub fn resolve_names_in_ast(
             }
         }
         ParserExpr::Literal(_) => ast.clone(),
+        ParserExpr::ArrayLiteral { .. } => ast.clone(),
         ParserExpr::CellRef { .. } => ast.clone(),
         ParserExpr::ColumnRef { .. } => ast.clone(),
         ParserExpr::RowRef { .. } => ast.clone(),
@@ -1182,29 +1286,9 @@ pub fn resolve_names_in_ast(
                 }
                 ParserBuiltinFn::Let if args.len() >= 3 && args.len() % 2 == 1 => {
                     // LET(name1, value1, name2, value2, ..., calculation)
-                    // Collect all LET-bound names to shadow in value exprs and body
-                    let pair_count = (args.len() - 1) / 2;
-                    let mut let_names: Vec<String> = Vec::new();
-                    for i in 0..pair_count {
-                        if let ParserExpr::NamedRef { name, .. } = &args[i * 2] {
-                            let_names.push(name.to_uppercase());
-                        }
-                    }
-                    let mut resolved_args: Vec<ParserExpr> = Vec::with_capacity(args.len());
-                    for (idx, arg) in args.iter().enumerate() {
-                        if idx % 2 == 0 && idx < args.len() - 1 {
-                            // Name positions: keep as-is
-                            resolved_args.push(arg.clone());
-                        } else {
-                            // Value and body positions: resolve with shadows
-                            resolved_args.push(resolve_names_in_ast_with_shadows(
-                                arg, named_ranges, current_sheet_index, visited, &let_names,
-                            ));
-                        }
-                    }
                     ParserExpr::FunctionCall {
                         func: func.clone(),
-                        args: resolved_args,
+                        args: resolve_let_args(args, named_ranges, current_sheet_index, visited, &[]),
                         ref_site_id: Default::default(),
                     }
                 }
@@ -1305,6 +1389,35 @@ pub fn resolve_names_in_ast(
     }
 }
 
+/// Resolve the arguments of `LET(name1, value1, ..., calculation)`. Name
+/// positions stay as-is; each value is resolved with the names bound before
+/// it shadowing workbook names, and the calculation with all of them. A
+/// value can therefore still use a workbook name that a later binding reuses.
+fn resolve_let_args(
+    args: &[ParserExpr],
+    named_ranges: &HashMap<String, named_ranges::NamedRange>,
+    current_sheet_index: usize,
+    visited: &mut HashSet<String>,
+    shadows: &[String],
+) -> Vec<ParserExpr> {
+    let mut scope: Vec<String> = shadows.to_vec();
+    let mut resolved_args: Vec<ParserExpr> = Vec::with_capacity(args.len());
+    for (idx, arg) in args.iter().enumerate() {
+        if idx % 2 == 0 && idx < args.len() - 1 {
+            resolved_args.push(arg.clone());
+        } else {
+            resolved_args.push(resolve_names_in_ast_with_shadows(
+                arg, named_ranges, current_sheet_index, visited, &scope,
+            ));
+            // The name bound by this value is visible from here on
+            if let (1, ParserExpr::NamedRef { name, .. }) = (idx % 2, &args[idx - 1]) {
+                scope.push(name.to_uppercase());
+            }
+        }
+    }
+    resolved_args
+}
+
 /// Like `resolve_names_in_ast`, but skips resolution for NamedRef nodes
 /// whose uppercased name is in the `shadows` set. Used for LAMBDA/LET parameters
 /// which should NOT be resolved as global named ranges.
@@ -1325,7 +1438,7 @@ fn resolve_names_in_ast_with_shadows(
             // Otherwise, delegate to the normal resolver
             resolve_names_in_ast(ast, named_ranges, current_sheet_index, visited)
         }
-        ParserExpr::Literal(_) | ParserExpr::CellRef { .. }
+        ParserExpr::Literal(_) | ParserExpr::ArrayLiteral { .. } | ParserExpr::CellRef { .. }
         | ParserExpr::ColumnRef { .. } | ParserExpr::RowRef { .. }
         | ParserExpr::TableRef { .. } => ast.clone(),
         ParserExpr::BinaryOp { left, op, right } => ParserExpr::BinaryOp {
@@ -1357,23 +1470,7 @@ fn resolve_names_in_ast_with_shadows(
                     ParserExpr::FunctionCall { func: func.clone(), args: resolved_args, ref_site_id: Default::default() }
                 }
                 ParserBuiltinFn::Let if args.len() >= 3 && args.len() % 2 == 1 => {
-                    let pair_count = (args.len() - 1) / 2;
-                    let mut inner_shadows: Vec<String> = shadows.to_vec();
-                    for i in 0..pair_count {
-                        if let ParserExpr::NamedRef { name, .. } = &args[i * 2] {
-                            inner_shadows.push(name.to_uppercase());
-                        }
-                    }
-                    let mut resolved_args: Vec<ParserExpr> = Vec::with_capacity(args.len());
-                    for (idx, arg) in args.iter().enumerate() {
-                        if idx % 2 == 0 && idx < args.len() - 1 {
-                            resolved_args.push(arg.clone());
-                        } else {
-                            resolved_args.push(resolve_names_in_ast_with_shadows(
-                                arg, named_ranges, current_sheet_index, visited, &inner_shadows,
-                            ));
-                        }
-                    }
+                    let resolved_args = resolve_let_args(args, named_ranges, current_sheet_index, visited, shadows);
                     ParserExpr::FunctionCall { func: func.clone(), args: resolved_args, ref_site_id: Default::default() }
                 }
                 _ => {
@@ -1469,7 +1566,7 @@ fn resolve_names_in_ast_with_shadows(
 pub fn ast_has_named_refs(ast: &ParserExpr) -> bool {
     match ast {
         ParserExpr::NamedRef { .. } => true,
-        ParserExpr::Literal(_) | ParserExpr::CellRef { .. }
+        ParserExpr::Literal(_) | ParserExpr::ArrayLiteral { .. } | ParserExpr::CellRef { .. }
         | ParserExpr::ColumnRef { .. } | ParserExpr::RowRef { .. }
         | ParserExpr::TableRef { .. } => false,
         ParserExpr::BinaryOp { left, right, .. } => {
@@ -1501,7 +1598,7 @@ pub fn ast_has_named_refs(ast: &ParserExpr) -> bool {
 pub fn ast_has_table_refs(ast: &ParserExpr) -> bool {
     match ast {
         ParserExpr::TableRef { .. } => true,
-        ParserExpr::Literal(_) | ParserExpr::CellRef { .. }
+        ParserExpr::Literal(_) | ParserExpr::ArrayLiteral { .. } | ParserExpr::CellRef { .. }
         | ParserExpr::ColumnRef { .. } | ParserExpr::RowRef { .. }
         | ParserExpr::NamedRef { .. } => false,
         ParserExpr::IndexAccess { target, index } => {
@@ -1553,6 +1650,7 @@ pub fn resolve_table_refs_in_ast(
             resolve_single_table_ref(table_name, specifier, ctx)
         }
         ParserExpr::Literal(_) => ast.clone(),
+        ParserExpr::ArrayLiteral { .. } => ast.clone(),
         ParserExpr::CellRef { .. } => ast.clone(),
         ParserExpr::ColumnRef { .. } => ast.clone(),
         ParserExpr::RowRef { .. } => ast.clone(),
@@ -2003,6 +2101,9 @@ pub fn expression_to_formula(expr: &ParserExpr) -> String {
         ParserExpr::BinaryOp { left, op, right } => {
             format!("{}{}{}", expression_to_formula(left), op, expression_to_formula(right))
         }
+        ParserExpr::UnaryOp { op, operand } if op.is_postfix() => {
+            format!("{}{}", expression_to_formula(operand), op)
+        }
         ParserExpr::UnaryOp { op, operand } => {
             format!("{}{}", op, expression_to_formula(operand))
         }
@@ -2058,6 +2159,12 @@ pub fn expression_to_formula(expr: &ParserExpr) -> String {
     }
 }
 
+/// `expression_to_formula` for display: function names and separators follow
+/// `locale`. Never store the result - stored formulas use canonical names.
+pub fn expression_to_localized_formula(expr: &ParserExpr, locale: &engine::LocaleSettings) -> String {
+    engine::localize_formula(&expression_to_formula(expr), locale)
+}
+
 /// Helper: serializes a CellRef without its sheet prefix (for Range start/end).
 fn expression_to_formula_no_sheet(expr: &ParserExpr) -> String {
     match expr {
@@ -2073,6 +2180,13 @@ fn expression_to_formula_no_sheet(expr: &ParserExpr) -> String {
     }
 }
 
+/// The name of `func` in function language `language` ("de" -> "SUMME");
+/// the canonical name when the language keeps it.
+pub fn builtin_function_to_localized_name(func: &ParserBuiltinFn, language: &str) -> String {
+    let name = builtin_function_to_name(func);
+    engine::localized_function_name(&name, language).map(String::from).unwrap_or(name)
+}
+
 /// Converts a BuiltinFunction enum variant back to its canonical name string.
 fn builtin_function_to_name(func: &ParserBuiltinFn) -> String {
     match func {
@@ -2230,6 +2344,9 @@ fn builtin_function_to_name(func: &ParserBuiltinFn) -> String {
         ParserBuiltinFn::GetRowHeight => "GET.ROW.HEIGHT".to_string(),
         ParserBuiltinFn::GetColumnWidth => "GET.COLUMN.WIDTH".to_string(),
         ParserBuiltinFn::GetCellFillColor => "GET.CELL.FILLCOLOR".to_string(),
+        ParserBuiltinFn::SetRowHeight => "SET.ROW.HEIGHT".to_string(),
+        ParserBuiltinFn::SetColumnWidth => "SET.COLUMN.WIDTH".to_string(),
+        ParserBuiltinFn::SetCellFillColor => "SET.CELL.FILLCOLOR".to_string(),
         ParserBuiltinFn::Row => "ROW".to_string(),
         ParserBuiltinFn::Column => "COLUMN".to_string(),
         ParserBuiltinFn::Let => "LET".to_string(),
@@ -2293,6 +2410,7 @@ fn builtin_function_to_name(func: &ParserBuiltinFn) -> String {
         ParserBuiltinFn::Aggregate => "AGGREGATE".to_string(),
         // Web
         ParserBuiltinFn::EncodeUrl => "ENCODEURL".to_string(),
+        ParserBuiltinFn::Hyperlink => "HYPERLINK".to_string(),
         // Database functions
         ParserBuiltinFn::DAverage => "DAVERAGE".to_string(),
         ParserBuiltinFn::DCount => "DCOUNT".to_string(),
@@ -2307,7 +2425,7 @@ fn builtin_function_to_name(func: &ParserBuiltinFn) -> String {
         ParserBuiltinFn::DVar => "DVAR".to_string(),
         ParserBuiltinFn::DVarP => "DVARP".to_string(),
         ParserBuiltinFn::Custom(name) => name.clone(),
-        other => format!("{:?}", other),
+        other => other.to_canonical_name().to_string(),
     }
 }
 
@@ -2333,7 +2451,10 @@ fn table_specifier_to_string(spec: &ParserTableSpecifier) -> String {
 // ============================================================================
 
 /// Repairs 3D reference bookends in a formula after a sheet is deleted.
-/// - If the deleted sheet is a bookend, shrink to the adjacent sheet.
+/// `sheet_names_before` is the tab order BEFORE the deletion.
+/// - If the deleted sheet is a bookend, shrink to the sheet that was next to it
+///   inside the span (Feb:Dec with Feb deleted becomes Mar:Dec).
+/// - Interior deletions leave the bookends untouched.
 /// - If both bookends become invalid (single-sheet 3D ref deleted), returns None
 ///   to indicate the formula should show #REF!.
 /// Returns Some(new_formula) if the formula was modified, None if it should become #REF!,
@@ -2341,14 +2462,14 @@ fn table_specifier_to_string(spec: &ParserTableSpecifier) -> String {
 pub fn repair_3d_refs_on_delete(
     formula: &str,
     deleted_name: &str,
-    sheet_names_after: &[String],
+    sheet_names_before: &[String],
 ) -> Option<String> {
     let ast = match parse_formula(formula) {
         Ok(ast) => ast,
         Err(_) => return Some(formula.to_string()),
     };
 
-    let (new_ast, had_ref_error) = repair_3d_delete_recursive(&ast, deleted_name, sheet_names_after);
+    let (new_ast, had_ref_error) = repair_3d_delete_recursive(&ast, deleted_name, sheet_names_before);
     if had_ref_error {
         return None; // Entire formula becomes #REF!
     }
@@ -2362,7 +2483,7 @@ pub fn repair_3d_refs_on_delete(
 fn repair_3d_delete_recursive(
     ast: &ParserExpr,
     deleted_name: &str,
-    sheet_names_after: &[String],
+    sheet_names_before: &[String],
 ) -> (ParserExpr, bool) {
     match ast {
         ParserExpr::Sheet3DRef { start_sheet, end_sheet, reference, .. } => {
@@ -2376,24 +2497,20 @@ fn repair_3d_delete_recursive(
             }
 
             let new_start = if start_is_deleted {
-                // Find the next sheet after the deleted one in the post-delete order
-                // The "next" sheet is the one that was immediately after the deleted sheet
-                // in the original order. In the post-delete list, we look for the sheet
-                // that's now adjacent to where the deleted sheet was.
-                find_adjacent_sheet(deleted_name, end_sheet, sheet_names_after, true)
+                find_adjacent_sheet(deleted_name, end_sheet, sheet_names_before)
             } else {
                 Some(start_sheet.clone())
             };
 
             let new_end = if end_is_deleted {
-                find_adjacent_sheet(deleted_name, start_sheet, sheet_names_after, false)
+                find_adjacent_sheet(deleted_name, start_sheet, sheet_names_before)
             } else {
                 Some(end_sheet.clone())
             };
 
             match (new_start, new_end) {
                 (Some(s), Some(e)) => {
-                    let (new_ref, err) = repair_3d_delete_recursive(reference, deleted_name, sheet_names_after);
+                    let (new_ref, err) = repair_3d_delete_recursive(reference, deleted_name, sheet_names_before);
                     if err { return (ast.clone(), true); }
                     (ParserExpr::Sheet3DRef {
                         start_sheet: s,
@@ -2406,8 +2523,8 @@ fn repair_3d_delete_recursive(
             }
         }
         ParserExpr::BinaryOp { left, op, right } => {
-            let (new_left, l_err) = repair_3d_delete_recursive(left, deleted_name, sheet_names_after);
-            let (new_right, r_err) = repair_3d_delete_recursive(right, deleted_name, sheet_names_after);
+            let (new_left, l_err) = repair_3d_delete_recursive(left, deleted_name, sheet_names_before);
+            let (new_right, r_err) = repair_3d_delete_recursive(right, deleted_name, sheet_names_before);
             (ParserExpr::BinaryOp {
                 left: Box::new(new_left),
                 op: *op,
@@ -2415,22 +2532,22 @@ fn repair_3d_delete_recursive(
             }, l_err || r_err)
         }
         ParserExpr::UnaryOp { op, operand } => {
-            let (new_op, err) = repair_3d_delete_recursive(operand, deleted_name, sheet_names_after);
+            let (new_op, err) = repair_3d_delete_recursive(operand, deleted_name, sheet_names_before);
             (ParserExpr::UnaryOp { op: *op, operand: Box::new(new_op) }, err)
         }
         ParserExpr::FunctionCall { func, args, .. } => {
             let mut new_args = Vec::new();
             let mut any_err = false;
             for arg in args {
-                let (new_arg, err) = repair_3d_delete_recursive(arg, deleted_name, sheet_names_after);
+                let (new_arg, err) = repair_3d_delete_recursive(arg, deleted_name, sheet_names_before);
                 any_err = any_err || err;
                 new_args.push(new_arg);
             }
             (ParserExpr::FunctionCall { func: func.clone(), args: new_args, ref_site_id: Default::default() }, any_err)
         }
         ParserExpr::Range { sheet, start, end, .. } => {
-            let (new_start, s_err) = repair_3d_delete_recursive(start, deleted_name, sheet_names_after);
-            let (new_end, e_err) = repair_3d_delete_recursive(end, deleted_name, sheet_names_after);
+            let (new_start, s_err) = repair_3d_delete_recursive(start, deleted_name, sheet_names_before);
+            let (new_end, e_err) = repair_3d_delete_recursive(end, deleted_name, sheet_names_before);
             (ParserExpr::Range {
                 sheet: sheet.clone(),
                 start: Box::new(new_start),
@@ -2443,41 +2560,22 @@ fn repair_3d_delete_recursive(
     }
 }
 
-/// Finds the sheet adjacent to the deleted one, constrained by the other bookend.
-/// If `toward_start` is true, finds the first sheet in post-delete order.
-/// If `toward_start` is false, finds the last sheet before the other bookend.
+/// Finds the sheet that was immediately next to the deleted bookend on the
+/// side of `other_bookend`, in the pre-delete tab order. None when either
+/// bookend is not in the workbook.
 fn find_adjacent_sheet(
-    _deleted_name: &str,
+    deleted_name: &str,
     other_bookend: &str,
-    sheet_names_after: &[String],
-    toward_start: bool,
+    sheet_names_before: &[String],
 ) -> Option<String> {
-    // The remaining bookend is `other_bookend`. We just need the first (or last)
-    // sheet in the post-delete order. Since the deleted sheet is already removed
-    // from sheet_names_after, the adjacent sheet is the first/last in the range
-    // that includes the other bookend.
-    let other_upper = other_bookend.to_uppercase();
-    let other_idx = sheet_names_after.iter()
-        .position(|s| s.to_uppercase() == other_upper)?;
-
-    if toward_start {
-        // The start bookend was deleted — new start is the sheet at the same position
-        // (which was the next sheet after the deleted one in the original order)
-        // In the simplest case, the first sheet in the remaining names works
-        if other_idx > 0 {
-            Some(sheet_names_after[0].clone())
-        } else {
-            Some(sheet_names_after[0].clone())
-        }
-    } else {
-        // The end bookend was deleted — new end is the sheet just before other_bookend's position
-        // in the post-delete order, or the last sheet
-        if other_idx < sheet_names_after.len() - 1 {
-            Some(sheet_names_after[sheet_names_after.len() - 1].clone())
-        } else {
-            Some(sheet_names_after[sheet_names_after.len() - 1].clone())
-        }
-    }
+    let position = |name: &str| {
+        let upper = name.to_uppercase();
+        sheet_names_before.iter().position(|s| s.to_uppercase() == upper)
+    };
+    let deleted_idx = position(deleted_name)?;
+    let other_idx = position(other_bookend)?;
+    let adjacent_idx = if other_idx > deleted_idx { deleted_idx + 1 } else { deleted_idx.checked_sub(1)? };
+    sheet_names_before.get(adjacent_idx).cloned()
 }
 
 /// Repairs 3D reference bookends in a formula after a sheet is renamed.
@@ -2617,6 +2715,7 @@ pub fn repair_all_formulas(
                     if new_formula != formula {
                         if let Some(cell) = grid.cells.get_mut(&(row, col)) {
                             cell.ast = parser::parse(&new_formula).ok().map(Box::new);
+                            grid.mark_changed(row, col);
                         }
                     }
                 }
@@ -2625,6 +2724,7 @@ pub fn repair_all_formulas(
                     if let Some(cell) = grid.cells.get_mut(&(row, col)) {
                         cell.value = CellValue::Error(CellError::Ref);
                         cell.ast = None;
+                        grid.mark_changed(row, col);
                     }
                 }
             }
@@ -2632,14 +2732,12 @@ pub fn repair_all_formulas(
     }
 }
 
+/// Evaluates a formula against a lone grid, as "Sheet1" and for no
+/// particular cell. A formula stored in a cell goes through
+/// `evaluate_formula_multi_sheet` with that cell's `EvalContext`.
 pub fn evaluate_formula(grid: &Grid, formula: &str) -> CellValue {
     match parse_formula(formula) {
-        Ok(parser_ast) => {
-            let engine_ast = convert_expr(&parser_ast);
-            let evaluator = Evaluator::new(grid);
-            let result = evaluator.evaluate(&engine_ast);
-            result.to_cell_value()
-        }
+        Ok(parser_ast) => evaluate_formula_with_ast(grid, &convert_expr(&parser_ast)),
         Err(e) => {
             log_error!("EVAL", "parse_err formula={} err={}", formula, e);
             CellValue::Error(CellError::Value)
@@ -2650,16 +2748,22 @@ pub fn evaluate_formula(grid: &Grid, formula: &str) -> CellValue {
 /// Evaluates a formula using a pre-parsed AST. More efficient than evaluate_formula
 /// when the AST is already available (e.g., from cell's cached_ast).
 pub fn evaluate_formula_with_ast(grid: &Grid, ast: &EngineExpr) -> CellValue {
-    let evaluator = Evaluator::new(grid);
-    let result = evaluator.evaluate(ast);
-    result.to_cell_value()
+    let sheet_names = ["Sheet1".to_string()];
+    let context = create_multi_sheet_context(std::slice::from_ref(grid), &sheet_names, &sheet_names[0]);
+    let evaluator = Evaluator::with_context(grid, context, engine::EvalContext::default());
+    evaluator.evaluate(ast).to_cell_value()
 }
 
+/// Evaluates `formula` on sheet `current_sheet_index`. `eval_ctx` names the
+/// cell being computed (`EvalContext::at`) so ROW(), COLUMN() and CELL()
+/// resolve per cell; pass `EvalContext::default()` only for a formula that
+/// belongs to no cell.
 pub fn evaluate_formula_multi_sheet(
     grids: &[Grid],
     sheet_names: &[String],
     current_sheet_index: usize,
     formula: &str,
+    eval_ctx: engine::EvalContext,
 ) -> CellValue {
     if current_sheet_index >= grids.len() || current_sheet_index >= sheet_names.len() {
         return CellValue::Error(CellError::Ref);
@@ -2674,7 +2778,7 @@ pub fn evaluate_formula_multi_sheet(
 
             let context = create_multi_sheet_context(grids, sheet_names, current_sheet_name);
 
-            let evaluator = Evaluator::with_multi_sheet(current_grid, context);
+            let evaluator = Evaluator::with_context(current_grid, context, eval_ctx);
             evaluator.evaluate(&engine_ast).to_cell_value()
         }
         Err(e) => {
@@ -2691,6 +2795,7 @@ pub fn evaluate_formula_multi_sheet_with_ast(
     sheet_names: &[String],
     current_sheet_index: usize,
     ast: &EngineExpr,
+    eval_ctx: engine::EvalContext,
 ) -> CellValue {
     if current_sheet_index >= grids.len() || current_sheet_index >= sheet_names.len() {
         return CellValue::Error(CellError::Ref);
@@ -2701,10 +2806,44 @@ pub fn evaluate_formula_multi_sheet_with_ast(
 
     let context = create_multi_sheet_context(grids, sheet_names, current_sheet_name);
 
-    let evaluator = Evaluator::with_multi_sheet(current_grid, context);
+    let evaluator = Evaluator::with_context(current_grid, context, eval_ctx);
     evaluator.evaluate(ast).to_cell_value()
 }
 
+/// Evaluates a formula against the workbook as seen through `overlays` (one
+/// per sheet, in tab order), i.e. with their overridden cells in place of the
+/// stored ones. The grids themselves are neither modified nor copied, so
+/// what-if features (goal seek, data tables, scenarios) pay per override, not
+/// per cell.
+pub fn evaluate_formula_with_overlays(
+    overlays: &[engine::OverlayGrid<'_>],
+    sheet_names: &[String],
+    current_sheet_index: usize,
+    formula: &str,
+    eval_ctx: engine::EvalContext,
+) -> CellValue {
+    if current_sheet_index >= overlays.len() || current_sheet_index >= sheet_names.len() {
+        return CellValue::Error(CellError::Ref);
+    }
+
+    match parse_formula(formula) {
+        Ok(parser_ast) => {
+            let engine_ast = convert_expr(&parser_ast);
+            let context = MultiSheetContext::with_overlays(
+                sheet_names[current_sheet_index].clone(),
+                sheet_names,
+                overlays,
+            );
+            let evaluator = Evaluator::with_context(overlays[current_sheet_index].base, context, eval_ctx);
+            evaluator.evaluate(&engine_ast).to_cell_value()
+        }
+        Err(e) => {
+            log_error!("EVAL", "parse_err formula={} err={}", formula, e);
+            CellValue::Error(CellError::Value)
+        }
+    }
+}
+
 /// Evaluates a formula AST with EvalContext and optional StyleRegistry.
 /// Used when the formula may need current cell position or style access (for GET.* functions).
 pub fn evaluate_formula_with_context(
@@ -2864,7 +3003,8 @@ pub fn evaluate_ast_with_evaluator(evaluator: &Evaluator, ast: &EngineExpr) -> C
 }
 
 /// Creates an Evaluator with multi-sheet context. Reuse this for batch evaluations
-/// to avoid recreating the context (HashMap + string cloning) for each formula.
+/// to avoid recreating the context (HashMap + string cloning) for each formula;
+/// move it to each formula's cell with `set_current_cell` first.
 pub fn create_evaluator_for_sheet<'a>(
     grids: &'a [Grid],
     sheet_names: &[String],
@@ -2877,10 +3017,11 @@ pub fn create_evaluator_for_sheet<'a>(
     let current_grid = &grids[current_sheet_index];
     let current_sheet_name = &sheet_names[current_sheet_index];
     let context = create_multi_sheet_context(grids, sheet_names, current_sheet_name);
-    Some(Evaluator::with_multi_sheet(current_grid, context))
+    Some(Evaluator::with_context(current_grid, context, engine::EvalContext::default()))
 }
 
 /// Creates an Evaluator with multi-sheet context and file reader support.
+/// Like `create_evaluator_for_sheet`, position it with `set_current_cell`.
 pub fn create_evaluator_with_files<'a>(
     grids: &'a [Grid],
     sheet_names: &[String],
@@ -2894,7 +3035,7 @@ pub fn create_evaluator_with_files<'a>(
     let current_grid = &grids[current_sheet_index];
     let current_sheet_name = &sheet_names[current_sheet_index];
     let context = create_multi_sheet_context(grids, sheet_names, current_sheet_name);
-    let mut evaluator = Evaluator::with_multi_sheet(current_grid, context);
+    let mut evaluator = Evaluator::with_context(current_grid, context, engine::EvalContext::default());
     if let Some(reader) = file_reader {
         evaluator.set_file_reader(reader);
     }
@@ -2907,6 +3048,7 @@ pub fn evaluate_formula_multi_sheet_with_files(
     sheet_names: &[String],
     current_sheet_index: usize,
     formula: &str,
+    eval_ctx: engine::EvalContext,
     user_files: &HashMap<String, Vec<u8>>,
 ) -> CellValue {
     if current_sheet_index >= grids.len() || current_sheet_index >= sheet_names.len() {
@@ -2922,7 +3064,7 @@ pub fn evaluate_formula_multi_sheet_with_files(
             let reader = |path: &str| -> Option<String> {
                 user_files.get(path).and_then(|bytes| String::from_utf8(bytes.clone()).ok())
             };
-            let mut evaluator = Evaluator::with_multi_sheet(current_grid, context);
+            let mut evaluator = Evaluator::with_context(current_grid, context, eval_ctx);
             evaluator.set_file_reader(&reader);
             evaluator.evaluate(&engine_ast).to_cell_value()
         }
@@ -2939,9 +3081,10 @@ pub fn evaluate_formula_multi_sheet_with_ast_and_files(
     sheet_names: &[String],
     current_sheet_index: usize,
     ast: &EngineExpr,
+    eval_ctx: engine::EvalContext,
     user_files: &HashMap<String, Vec<u8>>,
 ) -> CellValue {
-    evaluate_formula_raw_with_ast_and_files(grids, sheet_names, current_sheet_index, ast, user_files, None)
+    evaluate_formula_raw_with_ast_and_files(grids, sheet_names, current_sheet_index, ast, eval_ctx, user_files, None)
         .to_cell_value()
 }
 
@@ -2957,11 +3100,12 @@ pub fn evaluate_formula_raw_with_ast_and_files(
     sheet_names: &[String],
     current_sheet_index: usize,
     ast: &EngineExpr,
+    eval_ctx: engine::EvalContext,
     user_files: &HashMap<String, Vec<u8>>,
     udf_fn: Option<&dyn Fn(&str, &[EvalResult]) -> Option<EvalResult>>,
 ) -> EvalResult {
     evaluate_formula_raw_with_ast_files_and_cube(
-        grids, sheet_names, current_sheet_index, ast, user_files, udf_fn, None,
+        grids, sheet_names, current_sheet_index, ast, eval_ctx, user_files, udf_fn, None,
         None, // GET.CONTROLVALUE unavailable here (v1)
     )
 }
@@ -2974,6 +3118,7 @@ pub fn evaluate_formula_raw_with_ast_files_and_cube(
     sheet_names: &[String],
     current_sheet_index: usize,
     ast: &EngineExpr,
+    eval_ctx: engine::EvalContext,
     user_files: &HashMap<String, Vec<u8>>,
     udf_fn: Option<&dyn Fn(&str, &[EvalResult]) -> Option<EvalResult>>,
     cube: Option<std::sync::Arc<engine::CubePrefetch>>,
@@ -2989,7 +3134,7 @@ pub fn evaluate_formula_raw_with_ast_files_and_cube(
     let reader = |path: &str| -> Option<String> {
         user_files.get(path).and_then(|bytes| String::from_utf8(bytes.clone()).ok())
     };
-    let mut evaluator = Evaluator::with_multi_sheet(current_grid, context);
+    let mut evaluator = Evaluator::with_context(current_grid, context, eval_ctx);
     evaluator.set_file_reader(&reader);
     if let Some(uf) = udf_fn {
         evaluator.set_udf_fn(uf);
@@ -3029,6 +3174,7 @@ pub fn create_multi_sheet_context<'a>(
 }
 
 /// Batch evaluates multiple formulas efficiently by reusing the context.
+/// Each formula is evaluated as the formula of its own (row, col) cell.
 /// Returns a vector of (row, col, result) tuples.
 pub fn batch_evaluate_formulas(
     grids: &[Grid],
@@ -3048,11 +3194,12 @@ pub fn batch_evaluate_formulas(
 
     // Build context once for all formulas
     let context = create_multi_sheet_context(grids, sheet_names, current_sheet_name);
-    let evaluator = Evaluator::with_multi_sheet(current_grid, context);
+    let mut evaluator = Evaluator::with_context(current_grid, context, engine::EvalContext::default());
 
     formulas
         .iter()
         .map(|((row, col), formula)| {
+            evaluator.set_current_cell(*row, *col);
             let result = match parse_formula(formula) {
                 Ok(parser_ast) => {
                     let engine_ast = convert_expr(&parser_ast);
@@ -3070,6 +3217,11 @@ pub fn parse_cell_input(input: &str, locale: &engine::LocaleSettings) -> Cell {
     if trimmed.is_empty() {
         return Cell::new();
     }
+    // A leading apostrophe forces literal text: '00123 keeps its zeros and
+    // '=SUM(A1) is not a formula. The apostrophe itself is not stored.
+    if let Some(literal) = trimmed.strip_prefix('\'') {
+        return Cell::new_quoted_text(literal.to_string());
+    }
     if trimmed.starts_with('=') {
         // Delocalize the formula: convert locale separators to invariant format for storage
         let invariant = engine::delocalize_formula(trimmed, locale);
@@ -3082,12 +3234,73 @@ pub fn parse_cell_input(input: &str, locale: &engine::LocaleSettings) -> Cell {
     if upper == "FALSE" {
         return Cell::new_boolean(false);
     }
+    // Before numbers: in dot-grouping locales "15.01.2024" would otherwise
+    // read as the number 15012024.
+    if let Some((serial, _)) = parse_date_input(trimmed, locale) {
+        return Cell::new_number(serial);
+    }
     if let Some(num) = parse_number(trimmed, locale) {
         return Cell::new_number(num);
     }
     Cell::new_text(trimmed.to_string())
 }
 
+/// Input typed into a Text-formatted (`@`) cell is kept verbatim as text:
+/// no number, date, boolean or formula recognition. A leading apostrophe is
+/// still consumed as a quote prefix. None when the style is not Text.
+pub fn text_format_input(input: &str, style: &CellStyle) -> Option<Cell> {
+    if !style.number_format.is_text() || input.trim().is_empty() {
+        return None;
+    }
+    let trimmed = input.trim();
+    Some(match trimmed.strip_prefix('\'') {
+        Some(literal) => Cell::new_quoted_text(literal.to_string()),
+        None => Cell::new_text(trimmed.to_string()),
+    })
+}
+
+/// Recognizes a typed date or date-time ("2024-01-15", "1/15/2024",
+/// "15 Jan 2024", "2024-01-15 14:30") as its Excel serial, plus whether it
+/// carried a time. Slash and dot dates follow the locale's day/month order.
+fn parse_date_input(input: &str, locale: &engine::LocaleSettings) -> Option<(f64, bool)> {
+    let day_first = locale.date_format.to_uppercase().starts_with('D');
+    engine::date_serial::parse_date_input(input.trim(), day_first)
+}
+
+/// The number format a typed date should display with: the locale's date
+/// pattern, with hours and minutes when a time was given. None when `input`
+/// is not a date.
+pub fn date_input_number_format(input: &str, locale: &engine::LocaleSettings) -> Option<NumberFormat> {
+    if input.trim().starts_with('=') {
+        return None;
+    }
+    let (_, has_time) = parse_date_input(input, locale)?;
+    Some(if has_time {
+        NumberFormat::DateTime { format: format!("{} HH:MM", locale.date_format) }
+    } else {
+        NumberFormat::Date { format: locale.date_format.clone() }
+    })
+}
+
+/// Applies the date format of a typed date to a cell style that has no
+/// number format of its own (General), as Excel does on entry. Returns the
+/// new style index, or None when the style is left alone.
+pub fn style_for_date_input(
+    styles: &mut StyleRegistry,
+    style_index: usize,
+    input: &str,
+    locale: &engine::LocaleSettings,
+) -> Option<usize> {
+    let format = date_input_number_format(input, locale)?;
+    let style = styles.get(style_index);
+    if !matches!(style.number_format, NumberFormat::General) {
+        return None;
+    }
+    let mut style = style.clone();
+    style.number_format = format;
+    Some(styles.get_or_create(style))
+}
+
 /// Parse cell input that is already in invariant (US) format.
 /// Formulas are stored as-is without delocalization; numbers use '.' as decimal separator.
 pub fn parse_cell_input_invariant(input: &str, locale: &engine::LocaleSettings) -> Cell {
@@ -3095,6 +3308,11 @@ pub fn parse_cell_input_invariant(input: &str, locale: &engine::LocaleSettings)
     if trimmed.is_empty() {
         return Cell::new();
     }
+    // A leading apostrophe forces literal text: '00123 keeps its zeros and
+    // '=SUM(A1) is not a formula. The apostrophe itself is not stored.
+    if let Some(literal) = trimmed.strip_prefix('\'') {
+        return Cell::new_quoted_text(literal.to_string());
+    }
     if trimmed.starts_with('=') {
         // Formula is already in invariant format — store directly
         return Cell::new_formula(trimmed.to_string());
@@ -3106,6 +3324,9 @@ pub fn parse_cell_input_invariant(input: &str, locale: &engine::LocaleSettings)
     if upper == "FALSE" {
         return Cell::new_boolean(false);
     }
+    if let Some((serial, _)) = parse_date_input(trimmed, &engine::LocaleSettings::invariant()) {
+        return Cell::new_number(serial);
+    }
     // Try invariant number parsing first (dot decimal), then locale-aware
     if let Ok(n) = trimmed.parse::<f64>() {
         if n.is_finite() {
@@ -3118,45 +3339,17 @@ pub fn parse_cell_input_invariant(input: &str, locale: &engine::LocaleSettings)
     Cell::new_text(trimmed.to_string())
 }
 
-/// Parse a string as a number, respecting locale separators.
-/// - Strips the locale's thousands separator
-/// - Replaces the locale's decimal separator with '.' for f64 parsing
+/// Parse a string as a number, respecting locale separators: the locale's
+/// thousands separator, its decimal separator, its currency symbol (or a
+/// common one), accounting parentheses and a percent suffix. Shares the
+/// reader behind VALUE, so typed input and formulas agree on what a number is.
 fn parse_number(s: &str, locale: &engine::LocaleSettings) -> Option<f64> {
-    let trimmed = s.trim();
-    if trimmed.ends_with('%') {
-        let num_part = trimmed.trim_end_matches('%').trim();
-        // For percentage parsing, also apply locale decimal separator
-        let cleaned = if locale.decimal_separator == ',' {
-            num_part
-                .replace('.', "") // strip thousands (dot in comma-decimal locales)
-                .replace(',', ".") // convert decimal comma to dot
-        } else {
-            num_part.replace(',', "") // strip thousands
-        };
-        if let Ok(n) = cleaned.parse::<f64>() {
-            return Some(n / 100.0);
-        }
-        return None;
-    }
-
-    // Strip thousands separator, then convert decimal separator to '.'
-    let cleaned = if locale.decimal_separator == ',' {
-        // Comma-decimal locale: thousands separator is '.', ' ', or '\u{00A0}'
-        let s = trimmed
-            .replace(&locale.thousands_separator.to_string(), "")
-            .replace(',', ".");
-        s
-    } else {
-        // Dot-decimal locale: thousands separator is ','
-        trimmed.replace(&locale.thousands_separator.to_string(), "")
-    };
-
-    if let Ok(n) = cleaned.parse::<f64>() {
-        if n.is_finite() {
-            return Some(n);
-        }
-    }
-    None
+    engine::parse_number_text(
+        s,
+        locale.decimal_separator,
+        Some(locale.thousands_separator),
+        Some(&locale.currency_symbol),
+    )
 }
 
 // ============================================================================
@@ -3248,6 +3441,44 @@ pub fn update_row_dependencies(
     }
 }
 
+/// The cross-sheet cells a formula depends on, keyed by the workbook's own
+/// sheet names: parsed names are matched case-insensitively, and each 3D
+/// reference is expanded to every sheet between its bookends in the current
+/// sheet order (`*` = all sheets). A bookend that no longer exists falls back
+/// to tagging the bookend names themselves.
+pub fn resolve_cross_sheet_refs(
+    refs: &ExtractedRefs,
+    sheet_names: &[String],
+) -> FxHashSet<(String, u32, u32)> {
+    let normalize = |parsed: &str| {
+        sheet_names
+            .iter()
+            .find(|name| name.eq_ignore_ascii_case(parsed))
+            .cloned()
+            .unwrap_or_else(|| parsed.to_string())
+    };
+    let mut resolved: FxHashSet<(String, u32, u32)> = refs
+        .cross_sheet_cells
+        .iter()
+        .map(|(sheet, r, c)| (normalize(sheet), *r, *c))
+        .collect();
+    for (start, end, r, c) in &refs.sheet_3d_cells {
+        let covered = if start == "*" || end == "*" {
+            sheet_names.to_vec()
+        } else {
+            engine::dependency_extractor::get_sheets_in_range(start, end, sheet_names)
+        };
+        if covered.is_empty() {
+            resolved.insert((normalize(start), *r, *c));
+            resolved.insert((normalize(end), *r, *c));
+        }
+        for sheet in covered {
+            resolved.insert((sheet, *r, *c));
+        }
+    }
+    resolved
+}
+
 pub fn update_cross_sheet_dependencies(
     formula_cell: (usize, u32, u32),
     new_refs: FxHashSet<(String, u32, u32)>,
@@ -3287,6 +3518,68 @@ pub fn get_recalculation_order(
     recalc_order_from_seeds(&[changed_cell], dependents, false)
 }
 
+/// Like `get_recalculation_order`, but volatile formula cells (and everything
+/// downstream of them) are recalculated too, in one topological order with the
+/// edited cell's dependents. The changed cell itself is not included.
+pub fn get_recalculation_order_with_volatile(
+    changed_cell: (u32, u32),
+    volatile_cells: &CoordSet,
+    dependents: &DependencyMap,
+) -> Vec<(u32, u32)> {
+    if volatile_cells.iter().all(|&cell| cell == changed_cell) {
+        return get_recalculation_order(changed_cell, dependents);
+    }
+    let seeds = with_volatile_seeds(&[changed_cell], volatile_cells);
+    recalc_order_from_seeds(&seeds, dependents, true)
+        .into_iter()
+        .filter(|&cell| cell != changed_cell)
+        .collect()
+}
+
+/// `seeds` followed by the volatile formula cells not already among them,
+/// in coordinate order, for a recalc pass that must also refresh volatiles.
+pub fn with_volatile_seeds(seeds: &[(u32, u32)], volatile_cells: &CoordSet) -> Vec<(u32, u32)> {
+    let mut volatile: Vec<(u32, u32)> =
+        volatile_cells.iter().copied().filter(|cell| !seeds.contains(cell)).collect();
+    volatile.sort_unstable();
+    let mut all = seeds.to_vec();
+    all.extend(volatile);
+    all
+}
+
+/// Record whether `cell`, now at `pos` on the active sheet, calls a volatile
+/// function (see `AppState::volatile_cells`).
+pub fn track_volatile_cell(volatile_cells: &mut CoordSet, pos: (u32, u32), cell: Option<&Cell>) {
+    if cell.and_then(|c| c.get_ast()).is_some_and(engine::is_volatile) {
+        volatile_cells.insert(pos);
+    } else {
+        volatile_cells.remove(&pos);
+    }
+}
+
+/// Store the engine's explanation for the value just computed at `pos`
+/// (see `engine::take_error_detail`), dropping any older one. Call right
+/// after the evaluation that produced `value`.
+pub fn record_error_detail(details: &mut ErrorDetailMap, pos: (usize, u32, u32), value: &CellValue) {
+    match (value, engine::take_error_detail()) {
+        (CellValue::Error(e), Some(detail)) => {
+            details.insert(pos, (e.clone(), detail));
+        }
+        _ => {
+            details.remove(&pos);
+        }
+    }
+}
+
+/// The recorded explanation for `cell` at `pos`, if it still holds the error
+/// the explanation was recorded for.
+pub fn error_detail_for(details: &ErrorDetailMap, pos: (usize, u32, u32), cell: Option<&Cell>) -> Option<String> {
+    match (details.get(&pos), cell.map(|c| &c.value)) {
+        (Some((kind, detail)), Some(CellValue::Error(e))) if kind == e => Some(detail.clone()),
+        _ => None,
+    }
+}
+
 /// Multi-root topological recalc ordering over the dependents graph (Kahn's
 /// algorithm on the induced subgraph). Shared by the single-cell edit cascade,
 /// batch edits (paste/fill) and the control-value recalc.
@@ -3436,6 +3729,293 @@ pub fn get_column_row_dependents(
     result
 }
 
+/// Record the UI side-effects of the formula just evaluated at (row, col) on
+/// `sheet_index` (see `engine::take_ui_effects`); call right after the
+/// evaluation, like `record_error_detail`. Whatever an earlier evaluation of
+/// the cell requested is replaced; a link added by hand is never touched.
+/// Callers hold the grid and style locks here, so the formatting
+/// (SET.ROW.HEIGHT, SET.COLUMN.WIDTH, SET.CELL.FILLCOLOR) is only recorded in
+/// the `ui_effects` registry and applied later by `apply_pending_ui_effects`,
+/// which also settles requests that compete for one target under the
+/// workbook's conflict policy.
+pub fn process_ui_effects(state: &AppState, sheet_index: usize, row: u32, col: u32, effects: Vec<engine::UiEffect>) {
+    clear_ui_effects_for_cell(state, sheet_index, row, col);
+    let mut registry = state.ui_effects.lock().unwrap();
+    registry.mark_evaluated((sheet_index, row, col));
+    if effects.is_empty() {
+        return;
+    }
+    registry.set_cell_effects((sheet_index, row, col), &effects);
+    drop(registry);
+    let mut hyperlinks = state.hyperlinks.lock().unwrap();
+    let sheet_links = hyperlinks.entry(sheet_index).or_default();
+    for effect in effects {
+        if let engine::UiEffect::SetHyperlink { row, col, target } = effect {
+            if sheet_links.get(&(row, col)).is_none_or(|link| link.from_formula) {
+                let link = hyperlinks::formula_hyperlink(row, col, sheet_index, &target);
+                sheet_links.insert((row, col), link);
+            }
+        }
+    }
+}
+
+/// Remove what `process_ui_effects` registered for (row, col) once the cell
+/// no longer holds the formula that requested it. Its formatting is reverted
+/// by the next `apply_pending_ui_effects` unless another formula still
+/// requests the same target.
+pub fn clear_ui_effects_for_cell(state: &AppState, sheet_index: usize, row: u32, col: u32) {
+    state.ui_effects.lock().unwrap().clear_cell((sheet_index, row, col));
+    let mut hyperlinks = state.hyperlinks.lock().unwrap();
+    if let Some(sheet_links) = hyperlinks.get_mut(&sheet_index) {
+        if sheet_links.get(&(row, col)).is_some_and(|link| link.from_formula) {
+            sheet_links.remove(&(row, col));
+        }
+    }
+}
+
+/// Whether `formula` calls HYPERLINK, i.e. whether a link saved on its cell
+/// was registered by the formula.
+pub fn formula_has_hyperlink(formula: &str) -> bool {
+    formula.to_ascii_uppercase().contains("HYPERLINK(")
+}
+
+/// Whether `formula` calls a function with UI side-effects (HYPERLINK or one
+/// of the SET.* formatting functions), i.e. whether what its cell registered
+/// through `process_ui_effects` may still apply.
+pub fn formula_has_ui_effects(formula: &str) -> bool {
+    let formula = formula.to_ascii_uppercase();
+    ["HYPERLINK(", "SET.ROW.HEIGHT(", "SET.COLUMN.WIDTH(", "SET.CELL.FILLCOLOR("]
+        .iter()
+        .any(|name| formula.contains(name))
+}
+
+/// Apply the formatting recorded by `process_ui_effects` and revert what
+/// `clear_ui_effects_for_cell` released: a target no formula requests any
+/// more gets back the value it had before the first one changed it.
+/// Formulas requesting different values for one target are settled by the
+/// workbook's conflict policy (`ui_effects::conflict_policy`); under `Error`
+/// the target keeps its original value and the requesting cells show
+/// #CONFLICT until the conflict ends, when the surviving request applies and
+/// its cell is evaluated again.
+/// `row_heights` and `column_widths` are the active sheet's maps; sizes for
+/// other sheets go to the per-sheet storage. Returns the active sheet's
+/// dimension changes and whether any fill changed (see `UpdateCellResult`).
+pub fn apply_pending_ui_effects(
+    state: &AppState,
+    grids: &mut [Grid],
+    sheet_names: &[String],
+    styles: &mut StyleRegistry,
+    row_heights: &mut HashMap<u32, f64>,
+    column_widths: &mut HashMap<u32, f64>,
+    active_sheet: usize,
+) -> (Vec<DimensionData>, bool) {
+    use ui_effects::{UiEffectConflictPolicy, UiEffectOriginal, UiEffectTarget, UiEffectValue};
+    let policy = ui_effects::conflict_policy(state);
+    let mut registry = state.ui_effects.lock().unwrap();
+    let mut dimension_changes = Vec::new();
+    let mut needs_style_refresh = false;
+    let dirty = registry.take_dirty();
+    if dirty.is_empty() {
+        return (dimension_changes, needs_style_refresh);
+    }
+    for target in dirty {
+        let sheet_index = target.sheet();
+        let requested = registry.resolve(&target, policy);
+        match target {
+            UiEffectTarget::RowHeight { row: index, .. } | UiEffectTarget::ColumnWidth { col: index, .. } => {
+                let is_row = matches!(target, UiEffectTarget::RowHeight { .. });
+                let mut all_sizes;
+                let sizes: &mut HashMap<u32, f64> = match (sheet_index == active_sheet, is_row) {
+                    (true, true) => &mut *row_heights,
+                    (true, false) => &mut *column_widths,
+                    (false, true) => {
+                        all_sizes = state.all_row_heights.lock().unwrap();
+                        let Some(sizes) = all_sizes.get_mut(sheet_index) else { continue };
+                        sizes
+                    }
+                    (false, false) => {
+                        all_sizes = state.all_column_widths.lock().unwrap();
+                        let Some(sizes) = all_sizes.get_mut(sheet_index) else { continue };
+                        sizes
+                    }
+                };
+                let size = match requested {
+                    Some(UiEffectValue::Size(size)) => {
+                        let current = sizes.get(&index).copied();
+                        registry.record_original(target, || UiEffectOriginal::Size(current));
+                        Some(size)
+                    }
+                    _ => match registry.take_original(&target) {
+                        Some(UiEffectOriginal::Size(original)) => original,
+                        _ => continue,
+                    },
+                };
+                if sizes.get(&index).copied() == size {
+                    continue;
+                }
+                match size {
+                    Some(size) => sizes.insert(index, size),
+                    None => sizes.remove(&index),
+                };
+                if sheet_index == active_sheet {
+                    dimension_changes.push(DimensionData {
+                        index,
+                        // 0 asks the frontend for the default size.
+                        size: size.unwrap_or(0.0),
+                        dimension_type: if is_row { "row" } else { "column" }.to_string(),
+                    });
+                }
+            }
+            UiEffectTarget::CellFill { row, col, .. } => {
+                let Some(sheet_grid) = grids.get_mut(sheet_index) else { continue };
+                let existing = sheet_grid.get_cell(row, col).cloned();
+                let style_index = existing.as_ref().map_or(0, |c| c.style_index);
+                let current = styles.get(style_index).fill.clone();
+                let fill = match requested {
+                    Some(UiEffectValue::Fill(color)) => {
+                        registry.record_original(target, || UiEffectOriginal::Fill(current.clone()));
+                        match color {
+                            Some(color) => engine::Fill::Solid { color: engine::ThemeColor::Absolute(color) },
+                            None => engine::Fill::None,
+                        }
+                    }
+                    _ => match registry.take_original(&target) {
+                        Some(UiEffectOriginal::Fill(original)) => original,
+                        _ => continue,
+                    },
+                };
+                if fill == current {
+                    continue;
+                }
+                let mut style = styles.get(style_index).clone();
+                style.fill = fill;
+                let mut cell = existing.unwrap_or_default();
+                cell.style_index = styles.get_or_create(style);
+                sheet_grid.set_cell(row, col, cell);
+                needs_style_refresh = true;
+            }
+        }
+    }
+
+    let conflicting: std::collections::BTreeSet<ui_effects::SourceCell> = if policy == UiEffectConflictPolicy::Error {
+        registry.conflicts().into_iter().flat_map(|(_, cells)| cells).collect()
+    } else {
+        Default::default()
+    };
+    let released = registry.set_flagged(conflicting.clone());
+    drop(registry);
+    let conflict = CellValue::Error(CellError::Conflict);
+    let mut rewritten = Vec::new();
+    for (sheet, row, col) in released {
+        // A cell rewritten since it was flagged (edited, recalculated) already
+        // holds its own value.
+        let Some(cell) = grids.get(sheet).and_then(|g| g.get_cell(row, col)) else { continue };
+        let Some(ast) = cell.get_ast().filter(|_| cell.value == conflict) else { continue };
+        let value = evaluate_formula_multi_sheet_with_ast(grids, sheet_names, sheet, ast, engine::EvalContext::at(row, col));
+        // The effects are the ones already registered for the cell.
+        engine::take_ui_effects();
+        let mut cell = cell.clone();
+        cell.value = value;
+        rewritten.push((sheet, row, col, cell));
+    }
+    for (sheet, row, col) in conflicting {
+        let Some(cell) = grids.get(sheet).and_then(|g| g.get_cell(row, col)) else { continue };
+        if cell.value == conflict {
+            continue;
+        }
+        let mut cell = cell.clone();
+        cell.value = conflict.clone();
+        rewritten.push((sheet, row, col, cell));
+    }
+    for (sheet, row, col, cell) in rewritten {
+        grids[sheet].set_cell(row, col, cell);
+    }
+    (dimension_changes, needs_style_refresh)
+}
+
+/// Commit point of the UI side-effects, run by `seal_viewport_changes` before
+/// the cell writes of a command become revisions. Every written cell is
+/// reconciled with what it registered: a cell that no longer calls HYPERLINK
+/// or a SET.* function drops its effects (reverting their formatting), and
+/// one that does but was not evaluated on the way (pasted, restored by undo,
+/// loaded, moved by a structural change) is evaluated for its effects. The
+/// pending formatting is then applied; dimension changes on the active sheet
+/// go out as a `dimensions-changed` event, fills with the cells' revisions.
+pub fn commit_ui_effects(state: &AppState) {
+    let active_sheet = *state.active_sheet.lock().unwrap();
+    let sheet_names = state.sheet_names.lock().unwrap().clone();
+    let mut grids = state.grids.lock().unwrap();
+    let (evaluated, mut registered) = {
+        let mut registry = state.ui_effects.lock().unwrap();
+        let registered: FxHashSet<(usize, u32, u32)> = registry.cells().copied().collect();
+        (registry.take_evaluated(), registered)
+    };
+    for (&sheet, links) in state.hyperlinks.lock().unwrap().iter() {
+        registered.extend(
+            links.iter().filter(|(_, link)| link.from_formula).map(|(&(row, col), _)| (sheet, row, col)),
+        );
+    }
+
+    let mut to_evaluate = Vec::new();
+    for (sheet, grid) in grids.iter().enumerate() {
+        let changed = grid.changed_cells();
+        if changed.is_empty() {
+            continue;
+        }
+        let candidates: Vec<(u32, u32)> = if changed.overflowed {
+            let mut cells: Vec<(u32, u32)> = grid.cells
+                .iter()
+                .filter(|(_, cell)| cell.ast.is_some())
+                .map(|(&pos, _)| pos)
+                .chain(registered.iter().filter(|cell| cell.0 == sheet).map(|&(_, row, col)| (row, col)))
+                .collect();
+            cells.sort_unstable();
+            cells.dedup();
+            cells
+        } else {
+            changed.cells.iter().copied().collect()
+        };
+        for (row, col) in candidates {
+            if evaluated.contains(&(sheet, row, col)) {
+                continue;
+            }
+            let cell = grid.get_cell(row, col);
+            let has_effects = cell
+                .and_then(|cell| cell.formula_string())
+                .is_some_and(|formula| formula_has_ui_effects(&formula));
+            match cell.and_then(|cell| cell.get_ast()) {
+                Some(ast) if has_effects => to_evaluate.push((sheet, row, col, ast.clone())),
+                _ if registered.contains(&(sheet, row, col)) => clear_ui_effects_for_cell(state, sheet, row, col),
+                _ => {}
+            }
+        }
+    }
+    for (sheet, row, col, ast) in to_evaluate {
+        evaluate_formula_multi_sheet_with_ast(&grids, &sheet_names, sheet, &ast, engine::EvalContext::at(row, col));
+        process_ui_effects(state, sheet, row, col, engine::take_ui_effects());
+    }
+    {
+        let mut registry = state.ui_effects.lock().unwrap();
+        registry.take_evaluated();
+        if !registry.has_dirty() {
+            return;
+        }
+    }
+
+    let dimension_changes = {
+        let mut styles = state.style_registry.lock().unwrap();
+        let mut row_heights = state.row_heights.lock().unwrap();
+        let mut column_widths = state.column_widths.lock().unwrap();
+        apply_pending_ui_effects(
+            state, &mut grids, &sheet_names, &mut styles, &mut row_heights, &mut column_widths, active_sheet,
+        ).0
+    };
+    drop(grids);
+    let mut changes = change_events::ChangeBatch::new();
+    changes.push_dimensions(active_sheet, dimension_changes);
+    changes.flush();
+}
+
 // ============================================================================
 // Third-Party Extension Loading
 // ============================================================================
@@ -3869,6 +4449,7 @@ pub fn run() {
         .manage(create_app_state())
         .manage(FileState::default())
         .manage(UserFilesState::default())
+        .manage(calc_worker::CalcWorkerState::new())
         .manage(pivot::PivotState::new())
         .manage(bi::BiState::new())
         .manage(evaluate_formula::EvalFormulaState::new())
@@ -3880,9 +4461,10 @@ pub fn run() {
         .manage(timeline_slicer::TimelineSlicerState::new())
         .manage(mcp::McpState::new())
         .manage(managed_policy::ManagedAppearanceState(std::sync::Mutex::new(appearance_policy)))
-        .invoke_handler(tauri::generate_handler![
+        .invoke_handler(change_journal::journal_invokes(read_only::guard_invokes(sheet_access::revision_invokes(tauri::generate_handler![
             // Grid commands
             commands::get_viewport_cells,
+            commands::get_viewport_snapshot,
             commands::get_spill_ranges,
             commands::get_cell,
             commands::get_watch_cells,
@@ -3910,6 +4492,7 @@ pub fn run() {
             commands::detect_data_region,
             commands::get_current_region,
             commands::go_to_special,
+            commands::find_next_cell,
             // Dimension commands
             commands::set_column_width,
             commands::get_column_width,
@@ -3917,9 +4500,16 @@ pub fn run() {
             commands::set_row_height,
             commands::get_row_height,
             commands::get_all_row_heights,
+            commands::get_axis_metadata,
+            commands::inspect_workbook,
+            commands::remove_inspection_items,
+            commands::validate_workbook,
+            commands::repair_workbook,
             commands::get_default_dimensions,
             commands::set_default_row_height,
             commands::set_default_column_width,
+            commands::get_sheet_defaults,
+            commands::set_sheet_defaults,
             // Style commands
             commands::get_style,
             commands::get_all_styles,
@@ -3945,6 +4535,10 @@ pub fn run() {
             undo_commands::undo,
             undo_commands::redo,
             undo_commands::clear_undo_history,
+            // Change journal commands
+            change_journal::set_change_tracking,
+            change_journal::get_change_history,
+            change_journal::create_history_sheet,
             // Testing oracle commands
             state_digest::get_workbook_state_digest,
             // Logging commands
@@ -3955,11 +4549,16 @@ pub fn run() {
             logging::get_log_filter_config,
             logging::set_log_filter,
             logging::set_debug_logging,
-            // Calculation mode commands
+            // Calculation settings commands
+            calculation::get_calculation_settings,
+            calculation::update_calculation_settings,
             calculation::set_calculation_mode,
             calculation::get_calculation_mode,
+            calculation::calculate_range,
+            change_events::get_change_sequence,
             calculation::calculate_now,
             calculation::calculate_sheet,
+            calculation::cancel_calculation,
             calculation::get_iteration_settings,
             calculation::set_iteration_settings,
             calculation::get_calculation_state,
@@ -3967,14 +4566,21 @@ pub fn run() {
             calculation::set_precision_as_displayed,
             calculation::get_calculate_before_save,
             calculation::set_calculate_before_save,
+            ui_effects::get_ui_effect_conflicts,
+            ui_effects::get_ui_effect_conflict_policy,
+            ui_effects::set_ui_effect_conflict_policy,
             // Formula library commands
             formula::get_functions_by_category,
             formula::get_all_functions,
             formula::get_function_template,
+            formula::get_function_signature,
+            formula::get_completions,
+            formula::validate_formula,
             formula::evaluate_expressions,
             formula::evaluate_scoped,
             // File commands
             persistence::save_file,
+            persistence::export_values_only,
             persistence::get_extension_data,
             persistence::set_extension_data,
             persistence::set_extension_data_undoable,
@@ -3982,8 +4588,11 @@ pub fn run() {
             persistence::new_file,
             persistence::get_current_file_path,
             persistence::is_file_modified,
+            persistence::check_file_external_changes,
+            persistence::reload_file,
             persistence::mark_file_modified,
             persistence::is_document_encrypted,
+            persistence::get_file_access_state,
             persistence::set_session_password,
             persistence::clear_session_password,
             file_keychain::keychain_set_password,
@@ -4014,8 +4623,11 @@ pub fn run() {
             sheets::get_sheets,
             sheets::get_active_sheet,
             sheets::get_sheet_ids,
+            sheet_access::get_workbook_revision,
             sheets::get_show_gridlines,
             sheets::set_show_gridlines,
+            sheets::get_sheet_view_options,
+            sheets::set_sheet_view_options,
             sheets::set_active_sheet,
             sheets::add_sheet,
             sheets::delete_sheet,
@@ -4043,6 +4655,9 @@ pub fn run() {
             merge_commands::unmerge_cells,
             merge_commands::get_merged_regions,
             merge_commands::get_merge_info,
+            protected_regions::check_region_conflicts,
+            protected_regions::update_protected_region,
+            protected_regions::move_protected_region,
             // Pivot table commands - Core operations
             pivot::create_pivot_table,
             pivot::update_pivot_fields,
@@ -4067,6 +4682,7 @@ pub fn run() {
             pivot::get_pivot_layout_ranges,
             pivot::update_pivot_layout,
             pivot::get_pivot_hierarchies,
+            pivot::get_pivot_source_fields,
             pivot::add_pivot_hierarchy,
             pivot::remove_pivot_hierarchy,
             pivot::move_pivot_field,
@@ -4081,6 +4697,7 @@ pub fn run() {
             pivot::get_pivot_bi_metadata,
             pivot::get_pivots_for_bi_connection,
             pivot::refresh_all_pivot_tables,
+            pivot::source_watch::get_stale_objects,
             pivot::set_pivot_item_expanded,
             pivot::expand_collapse_level,
             pivot::expand_collapse_all,
@@ -4121,6 +4738,9 @@ pub fn run() {
             named_ranges::resolve_named_range_coords,
             named_ranges::rename_named_range,
             named_ranges::apply_names_to_formulas,
+            named_ranges::create_names_from_selection,
+            named_ranges::export_named_ranges,
+            named_ranges::import_named_ranges,
             // BI (Business Intelligence) commands
             bi::bi_create_connection,
             bi::bi_delete_connection,
@@ -4324,6 +4944,7 @@ pub fn run() {
             protection::can_edit_cell,
             protection::can_perform_action,
             protection::set_cell_protection,
+            protection::set_range_protection,
             protection::get_cell_protection,
             protection::verify_edit_range_password,
             // Workbook protection commands
@@ -4367,6 +4988,8 @@ pub fn run() {
             tables::remove_table_column,
             tables::rename_table_column,
             tables::set_totals_row_function,
+            tables::set_table_column_data_type,
+            tables::set_table_data_type_policy,
             tables::toggle_totals_row,
             tables::resize_table,
             tables::convert_to_range,
@@ -4413,11 +5036,18 @@ pub fn run() {
             evaluate_formula::eval_formula_step_in,
             evaluate_formula::eval_formula_step_out,
             evaluate_formula::eval_formula_restart,
+            evaluate_formula::eval_formula_expand_node,
             evaluate_formula::eval_formula_close,
             // Formula evaluation plan (visual formula debugger)
             formula_eval_plan::get_formula_eval_plan,
             // Status bar aggregation command
             status_bar::get_selection_aggregations,
+            // Quick Analysis range profiling
+            commands::analyze_range,
+            commands::extract_series_data,
+            // Table-less formula auto-extend
+            commands::suggest_formula_extension,
+            commands::apply_formula_extension,
             // Computed Properties commands
             computed_properties::get_computed_properties,
             computed_properties::get_available_attributes,
@@ -4497,12 +5127,14 @@ pub fn run() {
             commands::remove_col_page_break,
             commands::reset_all_page_breaks,
             commands::set_print_area,
+            commands::set_print_areas,
             commands::clear_print_area,
             commands::set_print_title_rows,
             commands::clear_print_title_rows,
             commands::set_print_title_cols,
             commands::clear_print_title_cols,
             commands::move_page_break,
+            commands::export_csv,
             // MCP server commands
             mcp::mcp_start,
             mcp::mcp_stop,
@@ -4567,6 +5199,8 @@ pub fn run() {
             locale_commands::get_locale_settings,
             locale_commands::set_locale,
             locale_commands::get_supported_locales,
+            locale_commands::set_function_language,
+            locale_commands::get_function_languages,
             // Named cell styles commands
             named_styles_cmd::get_named_styles,
             named_styles_cmd::create_named_style,
@@ -4657,7 +5291,7 @@ pub fn run() {
             managed_policy::get_effective_appearance_policy,
             managed_policy::refresh_managed_appearance,
             managed_policy::publish_skin_pack,
-        ])
+        ]))))
         .build(tauri::generate_context!())
         .expect("error while building tauri application");
 
@@ -4669,6 +5303,12 @@ pub fn run() {
     // writeback mutation paths (which only see &AppState) can re-provision the
     // BI writeback source without threading an AppHandle everywhere.
     bi::writeback_source::set_app_handle(app.handle().clone());
+    // Same for push-based change events (cells-changed, calc-state-changed, ...)
+    // raised from sync command/recalc paths.
+    change_events::set_app_handle(app.handle().clone());
+    // Background comparison of the open document with the disk, so edits by
+    // another program (or a sync client) are reported before a save.
+    persistence::spawn_external_change_watcher(app.handle().clone());
 
     app.run(|app_handle, event| {
             if let tauri::RunEvent::Exit = event {
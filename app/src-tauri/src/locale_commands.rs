@@ -1,7 +1,7 @@
 //! FILENAME: app/src-tauri/src/locale_commands.rs
 //! PURPOSE: Tauri commands for locale/regional settings management.
 
-use crate::api_types::{LocaleSettingsData, SupportedLocaleEntry};
+use crate::api_types::{FunctionLanguageEntry, LocaleSettingsData, SupportedLocaleEntry};
 use crate::AppState;
 use engine::LocaleSettings;
 use tauri::State;
@@ -14,14 +14,44 @@ pub fn get_locale_settings(state: State<AppState>) -> LocaleSettingsData {
 }
 
 /// Set the locale by ID. Returns the new locale settings.
+/// The function language is chosen separately and survives a locale change.
 #[tauri::command]
 pub fn set_locale(state: State<AppState>, locale_id: String) -> LocaleSettingsData {
-    let new_locale = LocaleSettings::from_locale_id(&locale_id);
+    let mut locale = state.locale.lock().unwrap();
+    let mut new_locale = LocaleSettings::from_locale_id(&locale_id);
+    new_locale.function_language = std::mem::take(&mut locale.function_language);
     let data = LocaleSettingsData::from(&new_locale);
-    *state.locale.lock().unwrap() = new_locale;
+    *locale = new_locale;
     data
 }
 
+/// Set the language function names are typed and displayed in ("en", "de").
+/// Stored formulas always keep canonical names.
+#[tauri::command]
+pub fn set_function_language(
+    state: State<AppState>,
+    language: String,
+) -> Result<LocaleSettingsData, String> {
+    if !engine::FUNCTION_LANGUAGES.iter().any(|(code, _)| *code == language) {
+        return Err(format!("Unsupported function language: {}", language));
+    }
+    let mut locale = state.locale.lock().unwrap();
+    locale.function_language = language;
+    Ok(LocaleSettingsData::from(&*locale))
+}
+
+/// List the function languages for the settings UI dropdown.
+#[tauri::command]
+pub fn get_function_languages() -> Vec<FunctionLanguageEntry> {
+    engine::FUNCTION_LANGUAGES
+        .iter()
+        .map(|(code, name)| FunctionLanguageEntry {
+            language: code.to_string(),
+            display_name: name.to_string(),
+        })
+        .collect()
+}
+
 /// List all supported locales for the settings UI dropdown.
 #[tauri::command]
 pub fn get_supported_locales() -> Vec<SupportedLocaleEntry> {
@@ -46,7 +46,7 @@ pub fn read_cell_range(
     end_col: u32,
 ) -> Result<String, String> {
     let state = handle.state::<AppState>();
-    let grid = state.grid.lock().map_err(|e| e.to_string())?;
+    let grid = state.get_active_grid();
     let styles = state.style_registry.lock().map_err(|e| e.to_string())?;
     let locale = state.locale.lock().map_err(|e| e.to_string())?;
 
@@ -152,8 +152,6 @@ pub fn get_sheet_summary(
     let grids = state.grids.lock().map_err(|e| e.to_string())?;
     let sheet_names = state.sheet_names.lock().map_err(|e| e.to_string())?;
     let styles = state.style_registry.lock().map_err(|e| e.to_string())?;
-    let active_grid = state.grid.lock().map_err(|e| e.to_string())?;
-    let active_sheet = *state.active_sheet.lock().map_err(|e| e.to_string())?;
 
     let options = AiSerializeOptions {
         max_chars: max_chars as usize,
@@ -165,26 +163,17 @@ pub fn get_sheet_summary(
     };
 
     let mut sheet_inputs: Vec<SheetInput> = Vec::new();
-    for (i, name) in sheet_names.iter().enumerate() {
-        if i == active_sheet {
-            sheet_inputs.push(SheetInput {
-                name,
-                grid: &active_grid,
-                styles: &styles,
-            });
-        } else if let Some(grid) = grids.get(i) {
-            sheet_inputs.push(SheetInput {
-                name,
-                grid,
-                styles: &styles,
-            });
-        }
+    for (name, grid) in sheet_names.iter().zip(grids.iter()) {
+        sheet_inputs.push(SheetInput {
+            name,
+            grid,
+            styles: &styles,
+        });
     }
 
     let mut summary = serialize_for_ai(&sheet_inputs, &options);
     // Release the sheet-data locks before touching the (unrelated) charts lock.
     drop(sheet_inputs);
-    drop(active_grid);
     drop(grids);
     drop(styles);
     drop(sheet_names);
@@ -265,8 +254,7 @@ pub fn apply_cell_formatting(
     )?;
 
     let state = handle.state::<AppState>();
-    let mut grid = state.grid.lock().map_err(|e| e.to_string())?;
-    let mut grids = state.grids.lock().map_err(|e| e.to_string())?;
+    let mut grid = state.get_active_grid();
     let active_sheet = *state.active_sheet.lock().map_err(|e| e.to_string())?;
     let mut styles = state.style_registry.lock().map_err(|e| e.to_string())?;
     let mut undo_stack = state.undo_stack.lock().map_err(|e| e.to_string())?;
@@ -326,21 +314,16 @@ pub fn apply_cell_formatting(
             if let Some(cell) = grid.get_cell(row, col) {
                 let mut updated = cell.clone();
                 updated.style_index = new_index;
-                grid.set_cell(row, col, updated.clone());
-                if active_sheet < grids.len() {
-                    grids[active_sheet].set_cell(row, col, updated);
-                }
+                grid.set_cell(row, col, updated);
             } else {
                 let cell = engine::Cell {
                     value: engine::CellValue::Empty,
                     ast: None,
                     style_index: new_index,
                     rich_text: None,
+                    quote_prefix: false,
                 };
-                grid.set_cell(row, col, cell.clone());
-                if active_sheet < grids.len() {
-                    grids[active_sheet].set_cell(row, col, cell);
-                }
+                grid.set_cell(row, col, cell);
             }
             undo_stack.record_cell_change(row, col, previous_cell);
             count += 1;
@@ -744,6 +727,8 @@ pub fn create_named_range(
     // The command validates the name + range, inserts, and records an undo entry.
     let result = crate::named_ranges::create_named_range(
         handle.state::<AppState>(),
+        handle.state::<crate::persistence::UserFilesState>(),
+        handle.state::<crate::pivot::PivotState>(),
         name.to_string(),
         sheet_index,
         refers_to.to_string(),
@@ -899,8 +884,8 @@ pub fn create_pivot(
     };
 
     let response = crate::pivot::commands::create_pivot_inner(
-        handle.state::<AppState>(),
-        handle.state::<crate::pivot::PivotState>(),
+        &handle.state::<AppState>(),
+        &handle.state::<crate::pivot::PivotState>(),
         request,
         row_fields,
         value_specs,
@@ -1473,6 +1458,7 @@ mod tests {
             },
             style_name: "TableStyleMedium2".to_string(),
             auto_filter_id: None,
+            data_type_policy: Default::default(),
         }
     }
 
@@ -1514,6 +1500,8 @@ mod tests {
             allow_multiple_filters_per_field: false,
             enable_data_value_editing: false,
             refresh_on_open: false,
+            auto_refresh: false,
+            on_grow: pivot_engine::GrowPolicy::Overwrite,
             use_custom_sort_lists: false,
             has_headers: true,
             source_table_name: table.map(|t| t.to_string()),
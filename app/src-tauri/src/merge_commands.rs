@@ -2,10 +2,12 @@
 // PURPOSE: Tauri commands for cell merge operations.
 // CONTEXT: Handles merging and unmerging cells in the spreadsheet.
 
-use crate::api_types::{CellData, MergedRegion, MergeResult};
+use crate::api_types::{
+    CellData, DiscardedCell, MergeContentPolicy, MergeMode, MergeOptions, MergedRegion, MergeResult,
+};
 use crate::persistence::FileState;
 use crate::{format_cell_value, AppState};
-use engine::UndoMergeRegion;
+use engine::{Cell, CellValue, TextAlign, UndoMergeRegion};
 use tauri::State;
 
 /// Convert an api_types::MergedRegion to an engine::UndoMergeRegion.
@@ -18,9 +20,95 @@ fn to_undo_region(r: &MergedRegion) -> UndoMergeRegion {
     }
 }
 
+/// Reject merges Excel refuses: a region that spans a table's header row and
+/// any other row, or one that touches a pivot table's protected region.
+fn check_merge_allowed(state: &AppState, sheet: usize, regions: &[MergedRegion]) -> Result<(), String> {
+    let overlaps = |r: &MergedRegion, sr: u32, sc: u32, er: u32, ec: u32| {
+        !(r.end_row < sr || r.start_row > er || r.end_col < sc || r.start_col > ec)
+    };
+
+    let tables = state.tables.lock().map_err(|e| e.to_string())?;
+    for table in tables.get(&sheet).into_iter().flat_map(|t| t.values()) {
+        if !table.style_options.header_row {
+            continue;
+        }
+        let header = table.start_row;
+        for r in regions {
+            if r.start_row != r.end_row
+                && overlaps(r, header, table.start_col, header, table.end_col)
+            {
+                return Err(format!("Cannot merge across the header row of table '{}'", table.name));
+            }
+        }
+    }
+    drop(tables);
+
+    let protected = state.protected_regions.lock().map_err(|e| e.to_string())?;
+    for p in protected.iter().filter(|p| p.region_type == "pivot" && p.sheet_index == sheet) {
+        if regions.iter().any(|r| overlaps(r, p.start_row, p.start_col, p.end_row, p.end_col)) {
+            return Err("Cannot merge cells inside a PivotTable".to_string());
+        }
+    }
+    Ok(())
+}
+
+/// Master cell of a region as CellData with the given spans.
+fn master_cell_data(
+    grid: &engine::Grid,
+    styles: &engine::StyleRegistry,
+    locale: &engine::LocaleSettings,
+    row: u32,
+    col: u32,
+    row_span: u32,
+    col_span: u32,
+) -> CellData {
+    let master_cell = grid.get_cell(row, col);
+    let style_index = master_cell.map(|c| c.style_index).unwrap_or(0);
+    let display = master_cell
+        .map(|c| format_cell_value(&c.value, styles.get(style_index), locale))
+        .unwrap_or_default();
+    CellData {
+        row,
+        col,
+        display,
+        display_color: None,
+        formula: master_cell.and_then(|c| c.formula_string()).map(|f| format!("={}", f)),
+        style_index,
+        row_span,
+        col_span,
+        sheet_index: None,
+        rich_text: None,
+        accounting_layout: None,
+        error_detail: None,
+    }
+}
+
+/// Set the master cell's horizontal alignment (Merge & Center, and its
+/// removal on unmerge). Returns the previous cell state when it changed.
+fn set_master_alignment(
+    grid: &mut engine::Grid,
+    styles: &mut engine::StyleRegistry,
+    row: u32,
+    col: u32,
+    align: TextAlign,
+) -> Option<Option<Cell>> {
+    let previous = grid.get_cell(row, col).cloned();
+    let mut cell = previous.clone().unwrap_or_else(Cell::new);
+    let mut style = styles.get(cell.style_index).clone();
+    if style.text_align == align {
+        return None;
+    }
+    style.text_align = align;
+    cell.style_index = styles.get_or_create(style);
+    grid.set_cell(row, col, cell);
+    Some(previous)
+}
+
 /// Merge cells in the specified range.
-/// The top-left cell becomes the "master" cell containing the merged content.
-/// All other cells in the range are cleared.
+/// The top-left cell of each region becomes the "master" cell containing the
+/// merged content. All other cells in the region are cleared; their values
+/// are returned in `discarded_cells` so the UI can warn.
+/// `options` selects Merge Across, Merge & Center and the content policy.
 #[tauri::command]
 pub fn merge_cells(
     state: State<AppState>,
@@ -29,14 +117,34 @@ pub fn merge_cells(
     start_col: u32,
     end_row: u32,
     end_col: u32,
+    options: Option<MergeOptions>,
+) -> Result<MergeResult, String> {
+    let result = merge_cells_internal(
+        &state,
+        start_row,
+        start_col,
+        end_row,
+        end_col,
+        &options.unwrap_or_default(),
+    )?;
+
+    // Mark workbook as dirty
+    if result.success {
+        if let Ok(mut modified) = file_state.is_modified.lock() { *modified = true; }
+    }
+
+    Ok(result)
+}
+
+pub(crate) fn merge_cells_internal(
+    state: &AppState,
+    start_row: u32,
+    start_col: u32,
+    end_row: u32,
+    end_col: u32,
+    options: &MergeOptions,
 ) -> Result<MergeResult, String> {
-    let mut grid = state.grid.lock().map_err(|e| e.to_string())?;
-    let mut grids = state.grids.lock().map_err(|e| e.to_string())?;
     let active_sheet = *state.active_sheet.lock().map_err(|e| e.to_string())?;
-    let styles = state.style_registry.lock().map_err(|e| e.to_string())?;
-    let mut merged_regions = state.merged_regions.lock().map_err(|e| e.to_string())?;
-    let mut undo_stack = state.undo_stack.lock().map_err(|e| e.to_string())?;
-    let locale = state.locale.lock().map_err(|e| e.to_string())?;
 
     // Normalize coordinates (ensure start <= end)
     let min_row = start_row.min(end_row);
@@ -44,12 +152,36 @@ pub fn merge_cells(
     let min_col = start_col.min(end_col);
     let max_col = start_col.max(end_col);
 
-    // Check if single cell - nothing to merge
-    if min_row == max_row && min_col == max_col {
+    // Regions to create; single cells have nothing to merge.
+    let regions: Vec<MergedRegion> = match options.mode {
+        MergeMode::MergeAll => vec![MergedRegion {
+            start_row: min_row,
+            start_col: min_col,
+            end_row: max_row,
+            end_col: max_col,
+        }],
+        MergeMode::MergeAcross => (min_row..=max_row)
+            .map(|row| MergedRegion { start_row: row, start_col: min_col, end_row: row, end_col: max_col })
+            .collect(),
+    }
+    .into_iter()
+    .filter(|r| r.start_row != r.end_row || r.start_col != r.end_col)
+    .collect();
+
+    check_merge_allowed(state, active_sheet, &regions)?;
+
+    let mut grid = state.get_active_grid();
+    let mut styles = state.style_registry.lock().map_err(|e| e.to_string())?;
+    let mut merged_regions = state.merged_regions.lock().map_err(|e| e.to_string())?;
+    let mut undo_stack = state.undo_stack.lock().map_err(|e| e.to_string())?;
+    let locale = state.locale.lock().map_err(|e| e.to_string())?;
+
+    if regions.is_empty() {
         return Ok(MergeResult {
             success: false,
             merged_regions: merged_regions.iter().cloned().collect(),
             updated_cells: Vec::new(),
+            discarded_cells: Vec::new(),
         });
     }
 
@@ -64,96 +196,100 @@ pub fn merge_cells(
         }
     }
 
-    // Create the new merged region
-    let new_region = MergedRegion {
-        start_row: min_row,
-        start_col: min_col,
-        end_row: max_row,
-        end_col: max_col,
-    };
-
-    // Get the master cell content (top-left)
-    let master_cell = grid.get_cell(min_row, min_col).cloned();
-    let master_style_index = master_cell.as_ref().map(|c| c.style_index).unwrap_or(0);
+    // Values outside each region's master cell are lost by the merge.
+    let mut discarded_cells = Vec::new();
+    for region in &regions {
+        for row in region.start_row..=region.end_row {
+            for col in region.start_col..=region.end_col {
+                if row == region.start_row && col == region.start_col {
+                    continue;
+                }
+                if let Some(cell) = grid.get_cell(row, col) {
+                    if cell.ast.is_some() || !matches!(cell.value, CellValue::Empty) {
+                        discarded_cells.push(DiscardedCell {
+                            row,
+                            col,
+                            display: format_cell_value(&cell.value, styles.get(cell.style_index), &locale),
+                            formula: cell.formula_string().map(|f| format!("={}", f)),
+                        });
+                    }
+                }
+            }
+        }
+    }
+    if options.content_policy == MergeContentPolicy::RefuseIfMultiple
+        && !options.force
+        && !discarded_cells.is_empty()
+    {
+        return Ok(MergeResult {
+            success: false,
+            merged_regions: merged_regions.iter().cloned().collect(),
+            updated_cells: Vec::new(),
+            discarded_cells,
+        });
+    }
 
-    // Record undo: save slave cells that will be cleared + the merge region being added
+    // Record undo: save slave cells that will be cleared + the merge regions being added
     let opened_transaction = !undo_stack.has_open_transaction();
     if opened_transaction {
         undo_stack.begin_transaction("Merge cells".to_string());
     }
 
-    // Record each slave cell's previous state for undo
-    for row in min_row..=max_row {
-        for col in min_col..=max_col {
-            if row == min_row && col == min_col {
-                continue; // Master cell is not cleared
+    let mut updated_cells = Vec::new();
+    for region in &regions {
+        // Record each slave cell's previous state for undo, then clear it
+        for row in region.start_row..=region.end_row {
+            for col in region.start_col..=region.end_col {
+                if row == region.start_row && col == region.start_col {
+                    continue; // Master cell is not cleared
+                }
+                let previous = grid.get_cell(row, col).cloned();
+                if previous.is_some() {
+                    undo_stack.record_cell_change(row, col, previous);
+                }
+                grid.clear_cell(row, col);
             }
-            let previous = grid.get_cell(row, col).cloned();
-            if previous.is_some() {
+        }
+
+        if options.center {
+            let (row, col) = (region.start_row, region.start_col);
+            if let Some(previous) = set_master_alignment(&mut grid, &mut styles, row, col, TextAlign::Center) {
                 undo_stack.record_cell_change(row, col, previous);
             }
         }
-    }
 
-    // Record the merge region addition
-    undo_stack.record_merge_region_added(to_undo_region(&new_region));
+        // Record the merge region addition
+        undo_stack.record_merge_region_added(to_undo_region(region));
+        merged_regions.insert(region.clone());
 
-    if opened_transaction {
-        undo_stack.commit_transaction();
+        // Return the master cell with span info
+        updated_cells.push(master_cell_data(
+            &grid,
+            &styles,
+            &locale,
+            region.start_row,
+            region.start_col,
+            region.end_row - region.start_row + 1,
+            region.end_col - region.start_col + 1,
+        ));
     }
 
-    // Clear all cells in the range except the master
-    let mut updated_cells = Vec::new();
-    for row in min_row..=max_row {
-        for col in min_col..=max_col {
-            if row == min_row && col == min_col {
-                // Master cell - keep content, will be returned with spans
-                continue;
-            }
-            // Clear slave cells
-            grid.clear_cell(row, col);
-            if active_sheet < grids.len() {
-                grids[active_sheet].clear_cell(row, col);
-            }
-        }
+    if opened_transaction {
+        undo_stack.commit_transaction();
     }
 
-    // Add the merged region
-    merged_regions.insert(new_region.clone());
-
-    // Return the master cell with span info
-    let style = styles.get(master_style_index);
-    let display = master_cell
-        .as_ref()
-        .map(|c| format_cell_value(&c.value, style, &locale))
-        .unwrap_or_default();
-
-    updated_cells.push(CellData {
-        row: min_row,
-        col: min_col,
-        display,
-        display_color: None,
-        formula: master_cell.as_ref().and_then(|c| c.formula_string()).map(|f| format!("={}", f)),
-        style_index: master_style_index,
-        row_span: max_row - min_row + 1,
-        col_span: max_col - min_col + 1,
-        sheet_index: None,
-        rich_text: None,
-                accounting_layout: None,
-    });
-
-    // Mark workbook as dirty
-    if let Ok(mut modified) = file_state.is_modified.lock() { *modified = true; }
-
     Ok(MergeResult {
         success: true,
         merged_regions: merged_regions.iter().cloned().collect(),
         updated_cells,
+        discarded_cells,
     })
 }
 
 /// Unmerge cells at the specified position.
-/// If the cell is part of a merged region, the region is dissolved.
+/// If the cell is part of a merged region, the region is dissolved. Cleared
+/// cells stay empty; the centering Merge & Center applied to the master cell
+/// is removed.
 #[tauri::command]
 pub fn unmerge_cells(
     state: State<AppState>,
@@ -161,8 +297,19 @@ pub fn unmerge_cells(
     row: u32,
     col: u32,
 ) -> Result<MergeResult, String> {
-    let grid = state.grid.lock().map_err(|e| e.to_string())?;
-    let styles = state.style_registry.lock().map_err(|e| e.to_string())?;
+    let result = unmerge_cells_internal(&state, row, col)?;
+
+    // Mark workbook as dirty
+    if result.success {
+        if let Ok(mut modified) = file_state.is_modified.lock() { *modified = true; }
+    }
+
+    Ok(result)
+}
+
+pub(crate) fn unmerge_cells_internal(state: &AppState, row: u32, col: u32) -> Result<MergeResult, String> {
+    let mut grid = state.get_active_grid();
+    let mut styles = state.style_registry.lock().map_err(|e| e.to_string())?;
     let mut merged_regions = state.merged_regions.lock().map_err(|e| e.to_string())?;
     let mut undo_stack = state.undo_stack.lock().map_err(|e| e.to_string())?;
     let locale = state.locale.lock().map_err(|e| e.to_string())?;
@@ -173,57 +320,47 @@ pub fn unmerge_cells(
         .find(|r| row >= r.start_row && row <= r.end_row && col >= r.start_col && col <= r.end_col)
         .cloned();
 
-    if let Some(region) = region_to_remove {
-        // Record undo: the merge region being removed
-        let opened_transaction = !undo_stack.has_open_transaction();
-        if opened_transaction {
-            undo_stack.begin_transaction("Unmerge cells".to_string());
-        }
-        undo_stack.record_merge_region_removed(to_undo_region(&region));
-        if opened_transaction {
-            undo_stack.commit_transaction();
-        }
-
-        merged_regions.remove(&region);
-
-        // Return the master cell with span reset to 1
-        let master_cell = grid.get_cell(region.start_row, region.start_col).cloned();
-        let master_style_index = master_cell.as_ref().map(|c| c.style_index).unwrap_or(0);
-        let style = styles.get(master_style_index);
-        let display = master_cell
-            .as_ref()
-            .map(|c| format_cell_value(&c.value, style, &locale))
-            .unwrap_or_default();
-
-        let updated_cells = vec![CellData {
-            row: region.start_row,
-            col: region.start_col,
-            display,
-            display_color: None,
-            formula: master_cell.as_ref().and_then(|c| c.formula_string()).map(|f| format!("={}", f)),
-            style_index: master_style_index,
-            row_span: 1,
-            col_span: 1,
-            sheet_index: None,
-            rich_text: None,
-                accounting_layout: None,
-        }];
-
-        // Mark workbook as dirty
-        if let Ok(mut modified) = file_state.is_modified.lock() { *modified = true; }
-
-        Ok(MergeResult {
-            success: true,
-            merged_regions: merged_regions.iter().cloned().collect(),
-            updated_cells,
-        })
-    } else {
-        Ok(MergeResult {
+    let Some(region) = region_to_remove else {
+        return Ok(MergeResult {
             success: false,
             merged_regions: merged_regions.iter().cloned().collect(),
             updated_cells: Vec::new(),
-        })
+            discarded_cells: Vec::new(),
+        });
+    };
+
+    // Record undo: the merge region being removed (and the master's alignment)
+    let opened_transaction = !undo_stack.has_open_transaction();
+    if opened_transaction {
+        undo_stack.begin_transaction("Unmerge cells".to_string());
+    }
+    undo_stack.record_merge_region_removed(to_undo_region(&region));
+    let (master_row, master_col) = (region.start_row, region.start_col);
+    let centered = grid
+        .get_cell(master_row, master_col)
+        .is_some_and(|c| styles.get(c.style_index).text_align == TextAlign::Center);
+    if centered {
+        if let Some(previous) =
+            set_master_alignment(&mut grid, &mut styles, master_row, master_col, TextAlign::General)
+        {
+            undo_stack.record_cell_change(master_row, master_col, previous);
+        }
     }
+    if opened_transaction {
+        undo_stack.commit_transaction();
+    }
+
+    merged_regions.remove(&region);
+
+    // Return the master cell with span reset to 1
+    let updated_cells = vec![master_cell_data(&grid, &styles, &locale, master_row, master_col, 1, 1)];
+
+    Ok(MergeResult {
+        success: true,
+        merged_regions: merged_regions.iter().cloned().collect(),
+        updated_cells,
+        discarded_cells: Vec::new(),
+    })
 }
 
 /// Get all merged regions for the current sheet.
@@ -0,0 +1,319 @@
+//! FILENAME: app/src-tauri/src/name_dependents.rs
+//! PURPOSE: Tracks formula cells that reference named ranges or tables and
+//! re-resolves them when a definition changes.
+//! CONTEXT: update_cell splices names (`resolve_names_in_ast`) and structured
+//! references (`resolve_table_refs_in_ast`) into the cell's AST at edit time,
+//! so the grid only holds the expansion. The typed formula is kept here so
+//! `update_named_range`, `resize_table`, etc. can expand it again, rebuild the
+//! dependency maps and recalculate the affected sheets.
+
+use std::collections::{HashMap, HashSet};
+
+use engine::{BuiltinFunction, Cell, Expression, Grid};
+
+use crate::persistence::UserFilesState;
+use crate::pivot::PivotState;
+use crate::structural_change::StructuralChange;
+use crate::AppState;
+
+/// A formula cell whose names and/or table references were expanded at edit time.
+#[derive(Debug, Clone)]
+pub struct NameRefSource {
+    /// The formula as typed, names and structured references intact.
+    pub formula: String,
+    /// Uppercase names it mentions (named ranges and LAMBDA-name calls).
+    pub names: HashSet<String>,
+    /// Uppercase table names it mentions; "" for implicit `[@Col]` refs.
+    pub tables: HashSet<String>,
+    /// `Cell::formula_string()` of the expansion stored in the grid. A cell
+    /// that no longer renders this was overwritten and its entry is stale.
+    pub expanded: String,
+}
+
+/// (sheet_index, row, col) -> typed formula of that cell.
+pub type NameRefSources = HashMap<(usize, u32, u32), NameRefSource>;
+
+/// Visits every node of `ast`, parents before children.
+fn visit_mut(ast: &mut Expression, f: &mut dyn FnMut(&mut Expression)) {
+    f(ast);
+    match ast {
+        Expression::Literal(_)
+        | Expression::ArrayLiteral { .. }
+        | Expression::CellRef { .. }
+        | Expression::ColumnRef { .. }
+        | Expression::RowRef { .. }
+        | Expression::NamedRef { .. }
+        | Expression::TableRef { .. } => {}
+        Expression::Range { start, end, .. } => {
+            visit_mut(start, f);
+            visit_mut(end, f);
+        }
+        Expression::BinaryOp { left, right, .. } => {
+            visit_mut(left, f);
+            visit_mut(right, f);
+        }
+        Expression::UnaryOp { operand, .. } => visit_mut(operand, f),
+        Expression::FunctionCall { args, .. } => args.iter_mut().for_each(|a| visit_mut(a, f)),
+        Expression::Sheet3DRef { reference, .. } => visit_mut(reference, f),
+        Expression::IndexAccess { target, index } => {
+            visit_mut(target, f);
+            visit_mut(index, f);
+        }
+        Expression::ListLiteral { elements } => elements.iter_mut().for_each(|e| visit_mut(e, f)),
+        Expression::DictLiteral { entries } => {
+            for (k, v) in entries.iter_mut() {
+                visit_mut(k, f);
+                visit_mut(v, f);
+            }
+        }
+        Expression::SpillRef { cell, .. } => visit_mut(cell, f),
+        Expression::ImplicitIntersection { operand } => visit_mut(operand, f),
+    }
+}
+
+/// Uppercase (names, tables) an unresolved AST mentions.
+fn referenced_names_and_tables(ast: &Expression) -> (HashSet<String>, HashSet<String>) {
+    let mut names = HashSet::new();
+    let mut tables = HashSet::new();
+    visit_mut(&mut ast.clone(), &mut |node| match node {
+        Expression::NamedRef { name, .. } => {
+            names.insert(name.to_uppercase());
+        }
+        Expression::FunctionCall { func: BuiltinFunction::Custom(name), .. } => {
+            names.insert(name.to_uppercase());
+        }
+        Expression::TableRef { table_name, .. } => {
+            tables.insert(table_name.to_uppercase());
+        }
+        _ => {}
+    });
+    (names, tables)
+}
+
+/// Tracking entry for a typed `formula` parsed to `ast` (before resolution),
+/// or None when it mentions no names or tables.
+pub fn collect_refs(formula: &str, ast: &Expression) -> Option<NameRefSource> {
+    let (names, tables) = referenced_names_and_tables(ast);
+    if names.is_empty() && tables.is_empty() {
+        return None;
+    }
+    Some(NameRefSource { formula: formula.to_string(), names, tables, expanded: String::new() })
+}
+
+/// Record (or forget) the typed formula of a cell that was just stored in the
+/// grid as `cell`. `source` comes from `collect_refs`; None clears the entry.
+pub(crate) fn track_cell(
+    sources: &mut NameRefSources,
+    key: (usize, u32, u32),
+    source: Option<NameRefSource>,
+    cell: &Cell,
+) {
+    match source {
+        Some(mut source) => {
+            source.expanded = cell.formula_string().unwrap_or_default();
+            sources.insert(key, source);
+        }
+        None => {
+            sources.remove(&key);
+        }
+    }
+}
+
+/// The cell behind `key`.
+fn tracked_cell(key: (usize, u32, u32), grids: &[Grid]) -> Option<&Cell> {
+    let (sheet, row, col) = key;
+    grids.get(sheet).and_then(|g| g.get_cell(row, col))
+}
+
+/// Entries whose cell still renders the tracked expansion. Taken before a
+/// row/column insertion or deletion and handed to `follow_structural_change`.
+pub(crate) fn fresh_entries(
+    sources: &NameRefSources,
+    grids: &[Grid],
+) -> HashSet<(usize, u32, u32)> {
+    sources
+        .iter()
+        .filter(|(key, source)| {
+            tracked_cell(**key, grids).and_then(|c| c.formula_string()).as_deref()
+                == Some(source.expanded.as_str())
+        })
+        .map(|(key, _)| *key)
+        .collect()
+}
+
+/// Carry the entries across a row/column insertion or deletion: entries on the
+/// changed sheet move with their cells, deleted or already-stale ones drop,
+/// and every survivor re-reads its (shifted) expansion.
+pub(crate) fn follow_structural_change(
+    sources: &mut NameRefSources,
+    fresh: &HashSet<(usize, u32, u32)>,
+    change: &StructuralChange,
+    grids: &[Grid],
+) {
+    let old = std::mem::take(sources);
+    for ((sheet, row, col), mut source) in old {
+        if !fresh.contains(&(sheet, row, col)) {
+            continue;
+        }
+        let key = if sheet == change.sheet {
+            match change.map_cell(row, col) {
+                Some((r, c)) => (sheet, r, c),
+                None => continue,
+            }
+        } else {
+            (sheet, row, col)
+        };
+        if let Some(expanded) = tracked_cell(key, grids).and_then(|c| c.formula_string()) {
+            source.expanded = expanded;
+            sources.insert(key, source);
+        }
+    }
+}
+
+/// Rewrite the tracked formulas that mention `old_name` to use `new_name`.
+pub(crate) fn rename_name(state: &AppState, old_name: &str, new_name: &str) {
+    let old_upper = old_name.to_uppercase();
+    let mut sources = state.name_ref_sources.lock().unwrap();
+    for source in sources.values_mut().filter(|s| s.names.contains(&old_upper)) {
+        let Ok(mut ast) = parser::parse(&source.formula) else { continue };
+        visit_mut(&mut ast, &mut |node| match node {
+            Expression::NamedRef { name, .. } if name.to_uppercase() == old_upper => {
+                *name = new_name.to_string();
+            }
+            Expression::FunctionCall { func: BuiltinFunction::Custom(name), .. }
+                if name.to_uppercase() == old_upper =>
+            {
+                *name = new_name.to_string();
+            }
+            _ => {}
+        });
+        source.formula = crate::expression_to_formula(&ast);
+        source.names.remove(&old_upper);
+        source.names.insert(new_name.to_uppercase());
+    }
+}
+
+/// Re-expand every tracked formula that depends on one of `names` or `tables`
+/// (directly, or through a name defined in terms of them), rebuild the
+/// dependency maps, and — in automatic mode — recalculate the sheets those
+/// cells live on. Acquires every lock itself: callers must hold none.
+pub(crate) fn refresh_dependents(
+    state: &AppState,
+    user_files_state: &UserFilesState,
+    pivot_state: &PivotState,
+    names: &[&str],
+    tables: &[&str],
+) {
+    let mut names: HashSet<String> = names.iter().map(|n| n.to_uppercase()).collect();
+    let tables: HashSet<String> = tables.iter().map(|t| t.to_uppercase()).collect();
+
+    // Names defined in terms of a changed name or table change with it.
+    {
+        let named_ranges = state.named_ranges.lock().unwrap();
+        let definitions: Vec<(String, HashSet<String>, HashSet<String>)> = named_ranges
+            .values()
+            .filter_map(|nr| {
+                let ast = parser::parse(&nr.refers_to).ok()?;
+                let (n, t) = referenced_names_and_tables(&ast);
+                Some((nr.name.to_uppercase(), n, t))
+            })
+            .collect();
+        loop {
+            let before = names.len();
+            for (name, uses_names, uses_tables) in &definitions {
+                if !names.contains(name)
+                    && (!uses_names.is_disjoint(&names) || !uses_tables.is_disjoint(&tables))
+                {
+                    names.insert(name.clone());
+                }
+            }
+            if names.len() == before {
+                break;
+            }
+        }
+    }
+
+    let affected: Vec<((usize, u32, u32), NameRefSource)> = {
+        let sources = state.name_ref_sources.lock().unwrap();
+        sources
+            .iter()
+            .filter(|(_, s)| {
+                !s.names.is_disjoint(&names)
+                    || !s.tables.is_disjoint(&tables)
+                    || (!tables.is_empty() && s.tables.contains(""))
+            })
+            .map(|(k, s)| (*k, s.clone()))
+            .collect()
+    };
+    if affected.is_empty() {
+        return;
+    }
+
+    let mut stale = Vec::new();
+    let mut expanded = Vec::new();
+    let mut sheets: Vec<usize> = Vec::new();
+    {
+        let mut grids = state.grids.lock().unwrap();
+        let named_ranges = state.named_ranges.lock().unwrap();
+        let tables_map = state.tables.lock().unwrap();
+        let table_names_map = state.table_names.lock().unwrap();
+        let spill_ranges = state.spill_ranges.lock().unwrap();
+
+        for ((sheet, row, col), source) in affected {
+            let Some(cell) = grids.get(sheet).and_then(|g| g.get_cell(row, col)) else {
+                stale.push((sheet, row, col));
+                continue;
+            };
+            if cell.formula_string().as_deref() != Some(source.expanded.as_str()) {
+                stale.push((sheet, row, col));
+                continue;
+            }
+            let Ok(parsed) = parser::parse(&source.formula) else { continue };
+            let mut visited = HashSet::new();
+            let resolved = crate::resolve_names_in_ast(&parsed, &named_ranges, sheet, &mut visited);
+            let ctx = crate::TableRefContext {
+                tables: &tables_map,
+                table_names: &table_names_map,
+                current_sheet_index: sheet,
+                current_row: row,
+            };
+            let resolved = crate::resolve_table_refs_in_ast(&resolved, &ctx);
+            let resolved = if crate::ast_has_spill_refs(&resolved) {
+                crate::resolve_spill_refs_in_ast(&resolved, &spill_ranges, sheet)
+            } else {
+                resolved
+            };
+
+            let mut updated = cell.clone();
+            updated.set_cached_ast(crate::convert_expr(&resolved));
+            expanded.push(((sheet, row, col), updated.formula_string().unwrap_or_default()));
+            grids[sheet].set_cell(row, col, updated);
+            if !sheets.contains(&sheet) {
+                sheets.push(sheet);
+            }
+        }
+    }
+
+    {
+        let mut sources = state.name_ref_sources.lock().unwrap();
+        for key in stale {
+            sources.remove(&key);
+        }
+        for (key, text) in expanded {
+            if let Some(source) = sources.get_mut(&key) {
+                source.expanded = text;
+            }
+        }
+    }
+
+    crate::undo_commands::rebuild_all_dependencies(state);
+    crate::undo_commands::rebuild_cross_sheet_dependencies(state);
+
+    if !state.calculation_settings.lock().unwrap().mode.is_automatic() {
+        return;
+    }
+    sheets.sort_unstable();
+    for sheet in sheets {
+        crate::calculation::recalculate_sheet_values(state, user_files_state, pivot_state, sheet, None);
+    }
+}
@@ -3,11 +3,13 @@
 //! CONTEXT: Allows users to define names for cell ranges that can be used in formulas.
 
 use serde::{Deserialize, Serialize};
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use tauri::State;
 
 use crate::api_types::CellData;
 use crate::commands::utils::get_cell_internal_with_merge;
+use crate::persistence::UserFilesState;
+use crate::pivot::PivotState;
 use crate::AppState;
 
 /// A named range definition.
@@ -147,6 +149,8 @@ impl NamedRange {
 #[tauri::command]
 pub fn create_named_range(
     state: State<AppState>,
+    user_files_state: State<UserFilesState>,
+    pivot_state: State<'_, PivotState>,
     name: String,
     sheet_index: Option<usize>,
     refers_to: String,
@@ -161,6 +165,13 @@ pub fn create_named_range(
             error: Some(format!("Invalid name '{}'. Names must start with a letter or underscore, contain only letters, numbers, underscores, and periods, and cannot be cell references.", name)),
         };
     }
+    if is_builtin_name(&name) {
+        return NamedRangeResult {
+            success: false,
+            named_range: None,
+            error: Some(format!("'{}' is reserved for built-in names.", name)),
+        };
+    }
 
     let mut named_ranges = state.named_ranges.lock().unwrap();
 
@@ -187,6 +198,8 @@ pub fn create_named_range(
 
     // BUG-0007 (user decision: undo-everything): name creation is undoable.
     crate::undo_commands::record_named_range_undo(&state, &key, None, "Define name");
+    // Formulas typed before the name existed show #NAME? until now.
+    crate::name_dependents::refresh_dependents(&state, &user_files_state, &pivot_state, &[&name], &[]);
 
     NamedRangeResult {
         success: true,
@@ -199,6 +212,22 @@ pub fn create_named_range(
 #[tauri::command]
 pub fn update_named_range(
     state: State<AppState>,
+    user_files_state: State<UserFilesState>,
+    pivot_state: State<'_, PivotState>,
+    name: String,
+    sheet_index: Option<usize>,
+    refers_to: String,
+    comment: Option<String>,
+    folder: Option<String>,
+) -> NamedRangeResult {
+    update_named_range_internal(&state, &user_files_state, &pivot_state, name, sheet_index, refers_to, comment, folder)
+}
+
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn update_named_range_internal(
+    state: &AppState,
+    user_files_state: &UserFilesState,
+    pivot_state: &PivotState,
     name: String,
     sheet_index: Option<usize>,
     refers_to: String,
@@ -227,7 +256,8 @@ pub fn update_named_range(
     let previous = named_ranges.insert(key.clone(), named_range.clone());
     drop(named_ranges);
 
-    crate::undo_commands::record_named_range_undo(&state, &key, previous, "Edit name");
+    crate::undo_commands::record_named_range_undo(state, &key, previous, "Edit name");
+    crate::name_dependents::refresh_dependents(state, user_files_state, pivot_state, &[&name], &[]);
 
     NamedRangeResult {
         success: true,
@@ -240,6 +270,17 @@ pub fn update_named_range(
 #[tauri::command]
 pub fn delete_named_range(
     state: State<AppState>,
+    user_files_state: State<UserFilesState>,
+    pivot_state: State<'_, PivotState>,
+    name: String,
+) -> NamedRangeResult {
+    delete_named_range_internal(&state, &user_files_state, &pivot_state, name)
+}
+
+pub(crate) fn delete_named_range_internal(
+    state: &AppState,
+    user_files_state: &UserFilesState,
+    pivot_state: &PivotState,
     name: String,
 ) -> NamedRangeResult {
     let mut named_ranges = state.named_ranges.lock().unwrap();
@@ -249,11 +290,13 @@ pub fn delete_named_range(
         Some(removed) => {
             drop(named_ranges);
             crate::undo_commands::record_named_range_undo(
-                &state,
+                state,
                 &key,
                 Some(removed.clone()),
                 "Delete name",
             );
+            // Dependents fall back to #NAME?.
+            crate::name_dependents::refresh_dependents(state, user_files_state, pivot_state, &[&name], &[]);
 
             // C10 cleanup: prune any object scripts attached to this name so a
             // deleted name leaves no dangling scripts behind. instanceId == the
@@ -293,13 +336,17 @@ pub fn get_named_range(
     named_ranges.get(&key).cloned()
 }
 
-/// Get all named ranges.
+/// Get all named ranges (built-in print names excluded).
 #[tauri::command]
 pub fn get_all_named_ranges(
     state: State<AppState>,
 ) -> Vec<NamedRange> {
+    get_all_named_ranges_internal(&state)
+}
+
+pub(crate) fn get_all_named_ranges_internal(state: &AppState) -> Vec<NamedRange> {
     let named_ranges = state.named_ranges.lock().unwrap();
-    named_ranges.values().cloned().collect()
+    named_ranges.values().filter(|nr| !is_builtin_name(&nr.name)).cloned().collect()
 }
 
 /// Find a named range that matches the given selection coordinates.
@@ -322,7 +369,7 @@ pub fn get_named_range_for_selection(
     // We try to match by parsing each name's refers_to formula.
     let mut best_match: Option<&NamedRange> = None;
 
-    for nr in named_ranges.values() {
+    for nr in named_ranges.values().filter(|nr| !is_builtin_name(&nr.name)) {
         // Skip sheet-scoped names that don't match the current sheet
         if let Some(scope_sheet) = nr.sheet_index {
             if scope_sheet != sheet_index {
@@ -502,6 +549,8 @@ pub fn resolve_named_range_coords(
 #[tauri::command]
 pub fn rename_named_range(
     state: State<AppState>,
+    user_files_state: State<UserFilesState>,
+    pivot_state: State<'_, PivotState>,
     old_name: String,
     new_name: String,
 ) -> NamedRangeResult {
@@ -513,6 +562,13 @@ pub fn rename_named_range(
             error: Some(format!("Invalid name '{}'. Names must start with a letter or underscore, contain only letters, numbers, underscores, and periods, and cannot be cell references.", new_name)),
         };
     }
+    if is_builtin_name(&new_name) {
+        return NamedRangeResult {
+            success: false,
+            named_range: None,
+            error: Some(format!("'{}' is reserved for built-in names.", new_name)),
+        };
+    }
 
     let mut named_ranges = state.named_ranges.lock().unwrap();
 
@@ -541,6 +597,11 @@ pub fn rename_named_range(
     if let Some(mut nr) = named_ranges.remove(&old_key) {
         nr.name = new_name.clone();
         named_ranges.insert(new_key, nr.clone());
+        drop(named_ranges);
+
+        // Formulas typed with the old name follow the rename.
+        crate::name_dependents::rename_name(&state, &old_name, &new_name);
+        crate::name_dependents::refresh_dependents(&state, &user_files_state, &pivot_state, &[&new_name], &[]);
 
         NamedRangeResult {
             success: true,
@@ -696,6 +757,255 @@ fn replace_ref_in_formula(formula: &str, patterns: &[String], name: &str) -> Str
     result
 }
 
+// ============================================================================
+// BUILT-IN PRINT NAMES
+// ============================================================================
+
+/// Excel's built-in name holding a sheet's print area (one or more ranges).
+pub const PRINT_AREA_NAME: &str = "_xlnm.Print_Area";
+/// Excel's built-in name holding a sheet's print titles (rows and/or columns).
+pub const PRINT_TITLES_NAME: &str = "_xlnm.Print_Titles";
+
+/// Whether `name` is an Excel built-in (`_xlnm.`) name. Built-ins are managed
+/// by their features and hidden from the names list.
+pub fn is_builtin_name(name: &str) -> bool {
+    name.get(..6).is_some_and(|prefix| prefix.eq_ignore_ascii_case("_xlnm."))
+}
+
+/// Map key of a built-in name. A built-in exists once per sheet, so unlike
+/// user names (keyed by the uppercased name alone) the key carries the sheet;
+/// the `#` keeps it out of reach of formula lookups.
+fn builtin_key(name: &str, sheet_index: usize) -> String {
+    format!("{}#{}", name.to_uppercase(), sheet_index)
+}
+
+/// One range of a print name, 0-based and inclusive.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PrintRange {
+    pub start_row: u32,
+    pub start_col: u32,
+    pub end_row: u32,
+    pub end_col: u32,
+}
+
+/// A sheet's print titles: rows repeated at the top and columns repeated at
+/// the left of every page.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PrintTitles {
+    pub rows: Option<(u32, u32)>,
+    pub cols: Option<(u32, u32)>,
+}
+
+/// One comma-separated part of a print name's `refers_to`.
+enum PrintRef {
+    Cells(PrintRange),
+    Rows(u32, u32),
+    Columns(u32, u32),
+}
+
+/// Split `refers_to` at top-level commas (not inside quoted sheet names).
+fn split_areas(refers_to: &str) -> Vec<&str> {
+    let text = refers_to.trim().trim_start_matches('=');
+    let mut parts = Vec::new();
+    let mut in_quotes = false;
+    let mut start = 0;
+    for (i, ch) in text.char_indices() {
+        match ch {
+            '\'' => in_quotes = !in_quotes,
+            ',' if !in_quotes => {
+                parts.push(text[start..i].trim());
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    parts.push(text[start..].trim());
+    parts.retain(|p| !p.is_empty());
+    parts
+}
+
+/// Parse one part: "Sheet1!$A$1:$F$20", "$A$1", "$1:$2" or "$A:$C". The sheet
+/// prefix is ignored (print names are sheet-scoped); `#REF!` parts are None.
+fn parse_print_ref(part: &str) -> Option<PrintRef> {
+    use crate::pivot::utils::col_letter_to_index;
+    let range = part.rsplit_once('!').map(|(_, r)| r).unwrap_or(part).replace('$', "");
+    let (first, last) = range.split_once(':').unwrap_or((&range, &range));
+    let split = |a1: &str| -> Option<(Option<u32>, Option<u32>)> {
+        let letters: String = a1.chars().take_while(|c| c.is_ascii_alphabetic()).collect();
+        let digits = &a1[letters.len()..];
+        if letters.len() > 3 || (letters.is_empty() && digits.is_empty()) || !digits.chars().all(|c| c.is_ascii_digit()) {
+            return None;
+        }
+        let col = (!letters.is_empty()).then(|| col_letter_to_index(&letters));
+        let row = match digits {
+            "" => None,
+            d => Some(d.parse::<u32>().ok()?.checked_sub(1)?),
+        };
+        Some((col, row))
+    };
+    match (split(first)?, split(last)?) {
+        ((Some(sc), Some(sr)), (Some(ec), Some(er))) => Some(PrintRef::Cells(PrintRange {
+            start_row: sr.min(er),
+            start_col: sc.min(ec),
+            end_row: sr.max(er),
+            end_col: sc.max(ec),
+        })),
+        ((None, Some(sr)), (None, Some(er))) => Some(PrintRef::Rows(sr.min(er), sr.max(er))),
+        ((Some(sc), None), (Some(ec), None)) => Some(PrintRef::Columns(sc.min(ec), sc.max(ec))),
+        _ => None,
+    }
+}
+
+/// A sheet's print area from its `_xlnm.Print_Area` name; empty when none is
+/// set. Whole-row or whole-column areas span the full other axis.
+pub fn print_area(names: &HashMap<String, NamedRange>, sheet_index: usize) -> Vec<PrintRange> {
+    use engine::reference_shift::{MAX_COL, MAX_ROW};
+    let Some(name) = names.get(&builtin_key(PRINT_AREA_NAME, sheet_index)) else {
+        return Vec::new();
+    };
+    split_areas(&name.refers_to)
+        .into_iter()
+        .filter_map(parse_print_ref)
+        .map(|r| match r {
+            PrintRef::Cells(range) => range,
+            PrintRef::Rows(start_row, end_row) => PrintRange { start_row, start_col: 0, end_row, end_col: MAX_COL },
+            PrintRef::Columns(start_col, end_col) => PrintRange { start_row: 0, start_col, end_row: MAX_ROW - 1, end_col },
+        })
+        .collect()
+}
+
+/// A sheet's print titles from its `_xlnm.Print_Titles` name.
+pub fn print_titles(names: &HashMap<String, NamedRange>, sheet_index: usize) -> PrintTitles {
+    let mut titles = PrintTitles::default();
+    if let Some(name) = names.get(&builtin_key(PRINT_TITLES_NAME, sheet_index)) {
+        for part in split_areas(&name.refers_to) {
+            match parse_print_ref(part) {
+                Some(PrintRef::Rows(start, end)) => titles.rows = Some((start, end)),
+                Some(PrintRef::Columns(start, end)) => titles.cols = Some((start, end)),
+                _ => {}
+            }
+        }
+    }
+    titles
+}
+
+/// Store `refers_to` as built-in `name` of `sheet_index`; None removes it.
+fn set_builtin_name(
+    names: &mut HashMap<String, NamedRange>,
+    name: &str,
+    sheet_index: usize,
+    refers_to: Option<String>,
+) {
+    let key = builtin_key(name, sheet_index);
+    match refers_to {
+        Some(refers_to) => {
+            names.insert(
+                key,
+                NamedRange { name: name.to_string(), sheet_index: Some(sheet_index), refers_to, comment: None, folder: None },
+            );
+        }
+        None => {
+            names.remove(&key);
+        }
+    }
+}
+
+/// Absolute A1 text of a range (bare: the name is scoped to its sheet, so its
+/// references follow row/column changes on that sheet).
+fn absolute_range(range: &PrintRange) -> String {
+    use crate::pivot::utils::col_index_to_letter;
+    let start = format!("${}${}", col_index_to_letter(range.start_col), range.start_row + 1);
+    if range.start_row == range.end_row && range.start_col == range.end_col {
+        return start;
+    }
+    format!("{}:${}${}", start, col_index_to_letter(range.end_col), range.end_row + 1)
+}
+
+/// Set (or, with no ranges, clear) a sheet's print area. Several ranges make
+/// a multi-area name, as a multi-rect selection does in Excel.
+pub(crate) fn set_print_area_name(names: &mut HashMap<String, NamedRange>, sheet_index: usize, ranges: &[PrintRange]) {
+    let refers_to = (!ranges.is_empty())
+        .then(|| format!("={}", ranges.iter().map(absolute_range).collect::<Vec<_>>().join(",")));
+    set_builtin_name(names, PRINT_AREA_NAME, sheet_index, refers_to);
+}
+
+/// Set (or, with neither rows nor columns, clear) a sheet's print titles.
+pub(crate) fn set_print_titles_name(names: &mut HashMap<String, NamedRange>, sheet_index: usize, titles: PrintTitles) {
+    use crate::pivot::utils::col_index_to_letter;
+    let mut parts = Vec::new();
+    if let Some((start, end)) = titles.rows {
+        parts.push(format!("${}:${}", start + 1, end + 1));
+    }
+    if let Some((start, end)) = titles.cols {
+        parts.push(format!("${}:${}", col_index_to_letter(start), col_index_to_letter(end)));
+    }
+    let refers_to = (!parts.is_empty()).then(|| format!("={}", parts.join(",")));
+    set_builtin_name(names, PRINT_TITLES_NAME, sheet_index, refers_to);
+}
+
+/// Page-setup text of a print area ("A1:F20,H1:J5").
+pub fn format_print_area(ranges: &[PrintRange]) -> String {
+    ranges.iter().map(|r| absolute_range(r).replace('$', "")).collect::<Vec<_>>().join(",")
+}
+
+/// Parse page-setup print-area text ("A1:F20" or "A1:F20,H1:J5"); parts that
+/// are not cell ranges are skipped.
+pub fn parse_print_area(text: &str) -> Vec<PrintRange> {
+    split_areas(text)
+        .into_iter()
+        .filter_map(|part| match parse_print_ref(part) {
+            Some(PrintRef::Cells(range)) => Some(range),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Parse page-setup title-rows text ("1:3").
+pub fn parse_title_rows(text: &str) -> Option<(u32, u32)> {
+    match parse_print_ref(text.trim()) {
+        Some(PrintRef::Rows(start, end)) => Some((start, end)),
+        _ => None,
+    }
+}
+
+/// Parse page-setup title-columns text ("A:C").
+pub fn parse_title_cols(text: &str) -> Option<(u32, u32)> {
+    match parse_print_ref(text.trim()) {
+        Some(PrintRef::Columns(start, end)) => Some((start, end)),
+        _ => None,
+    }
+}
+
+/// Re-point the built-in names after the sheet list changed: `remap` gives
+/// each old sheet index its new one (None drops the sheet's names).
+pub(crate) fn remap_builtin_name_sheets(names: &mut HashMap<String, NamedRange>, remap: &impl Fn(usize) -> Option<usize>) {
+    let builtins: Vec<String> = names
+        .iter()
+        .filter(|(_, nr)| is_builtin_name(&nr.name))
+        .map(|(key, _)| key.clone())
+        .collect();
+    let moved: Vec<NamedRange> = builtins.iter().filter_map(|key| names.remove(key)).collect();
+    for mut nr in moved {
+        let Some(new_index) = nr.sheet_index.and_then(remap) else { continue };
+        nr.sheet_index = Some(new_index);
+        names.insert(builtin_key(&nr.name, new_index), nr);
+    }
+}
+
+/// Give sheet `to` (a fresh copy of `from`) the built-in names of `from`.
+pub(crate) fn copy_builtin_names(names: &mut HashMap<String, NamedRange>, from: usize, to: usize) {
+    let copies: Vec<NamedRange> = names
+        .values()
+        .filter(|nr| is_builtin_name(&nr.name) && nr.sheet_index == Some(from))
+        .cloned()
+        .collect();
+    for mut nr in copies {
+        nr.sheet_index = Some(to);
+        names.insert(builtin_key(&nr.name, to), nr);
+    }
+}
+
 /// Apply named range names to formulas, replacing cell references with names.
 /// This is Excel's "Apply Names" feature.
 ///
@@ -711,7 +1021,7 @@ pub fn apply_names_to_formulas(
     end_col: Option<u32>,
 ) -> Result<ApplyNamesResult, String> {
     let named_ranges = state.named_ranges.lock().unwrap();
-    let mut grid = state.grid.lock().unwrap();
+    let mut grid = state.get_active_grid();
     let styles = state.style_registry.lock().unwrap();
     let merged_regions = state.merged_regions.lock().unwrap();
     let locale = state.locale.lock().unwrap();
@@ -722,7 +1032,7 @@ pub fn apply_names_to_formulas(
 
     let mut replacements: Vec<(String, Vec<String>)> = Vec::new();
 
-    for nr in named_ranges.values() {
+    for nr in named_ranges.values().filter(|nr| !is_builtin_name(&nr.name)) {
         if !apply_all && !names_filter.contains(&nr.name.to_uppercase()) {
             continue;
         }
@@ -775,6 +1085,7 @@ pub fn apply_names_to_formulas(
     for (row, col, new_formula) in &modifications {
         if let Some(cell) = grid.cells.get_mut(&(*row, *col)) {
             cell.ast = parser::parse(new_formula).ok().map(Box::new);
+            grid.mark_changed(*row, *col);
         }
     }
 
@@ -793,6 +1104,343 @@ pub fn apply_names_to_formulas(
     })
 }
 
+// ============================================================================
+// BULK NAMES: CREATE FROM SELECTION, IMPORT / EXPORT
+// ============================================================================
+
+/// Which header cells of a selection name its columns and rows.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum NameHeaders {
+    /// One name per column, from the top row.
+    TopRow,
+    /// One name per row, from the left column.
+    LeftColumn,
+    /// Both; the corner cell names nothing.
+    Both,
+}
+
+/// What an import does with a name the workbook already has.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum NameCollisionPolicy {
+    /// Keep the workbook's definition.
+    Skip,
+    /// Replace it with the imported one.
+    Overwrite,
+    /// Store the imported name under a free name ("Rate_2").
+    Rename,
+}
+
+/// A name a bulk operation did not define, and why.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SkippedName {
+    pub name: String,
+    pub reason: String,
+}
+
+/// An imported name stored under a different name to avoid a collision.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RenamedName {
+    pub from: String,
+    pub to: String,
+}
+
+/// Result of creating or importing several names at once.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BulkNamesResult {
+    /// Every name stored, as stored (including overwrites and renames).
+    pub created: Vec<NamedRange>,
+    /// Names whose previous definition was replaced.
+    pub overwritten: Vec<String>,
+    pub renamed: Vec<RenamedName>,
+    pub skipped: Vec<SkippedName>,
+}
+
+/// Turn header text into a valid name the way Excel's "Create from Selection"
+/// does: invalid characters become underscores, and a name that would start
+/// with a digit or read as a cell reference or keyword gets an underscore
+/// added. None for a blank header.
+pub(crate) fn sanitize_name(text: &str) -> Option<String> {
+    let text = text.trim();
+    if text.is_empty() {
+        return None;
+    }
+    let mut name: String = text
+        .chars()
+        .map(|c| if c.is_alphanumeric() || c == '_' || c == '.' { c } else { '_' })
+        .collect();
+    if !name.starts_with(|c: char| c.is_alphabetic() || c == '_') {
+        name.insert(0, '_');
+    }
+    if !NamedRange::is_valid_name(&name) {
+        name.push('_');
+    }
+    Some(name)
+}
+
+/// `base`, or the first of "base_2", "base_3", ... that `taken` rejects.
+fn unique_name(base: &str, taken: impl Fn(&str) -> bool) -> String {
+    if !taken(base) {
+        return base.to_string();
+    }
+    (2..)
+        .map(|n| format!("{}_{}", base, n))
+        .find(|candidate| !taken(candidate))
+        .unwrap_or_else(|| base.to_string())
+}
+
+/// Text of a header cell; errors and empty cells have none.
+fn header_text(value: &engine::CellValue) -> String {
+    match value {
+        engine::CellValue::Text(s) => s.clone(),
+        engine::CellValue::Number(n) => n.to_string(),
+        engine::CellValue::Boolean(b) => if *b { "TRUE" } else { "FALSE" }.to_string(),
+        _ => String::new(),
+    }
+}
+
+/// "Sheet1!" or "'Q1 Sales'!" for a reference into `sheet_name`.
+fn sheet_ref_prefix(sheet_name: &str) -> String {
+    let plain = sheet_name.starts_with(|c: char| c.is_alphabetic() || c == '_')
+        && sheet_name.chars().all(|c| c.is_alphanumeric() || c == '_');
+    if plain {
+        format!("{}!", sheet_name)
+    } else {
+        format!("'{}'!", sheet_name.replace('\'', "''"))
+    }
+}
+
+/// Store `names` (replacing same-named entries) as one undo step and
+/// recalculate the formulas that use them.
+fn store_names(
+    state: &AppState,
+    user_files_state: &UserFilesState,
+    pivot_state: &PivotState,
+    names: &[NamedRange],
+    description: &str,
+) {
+    if names.is_empty() {
+        return;
+    }
+    let previous: Vec<(String, Option<NamedRange>)> = {
+        let mut named_ranges = state.named_ranges.lock().unwrap();
+        names
+            .iter()
+            .map(|nr| {
+                let key = nr.name.to_uppercase();
+                let previous = named_ranges.insert(key.clone(), nr.clone());
+                (key, previous)
+            })
+            .collect()
+    };
+
+    state.undo_stack.lock().unwrap().begin_transaction(description);
+    for (key, previous) in previous {
+        crate::undo_commands::record_named_range_undo(state, &key, previous, description);
+    }
+    state.undo_stack.lock().unwrap().commit_transaction();
+
+    let changed: Vec<&str> = names.iter().map(|nr| nr.name.as_str()).collect();
+    crate::name_dependents::refresh_dependents(state, user_files_state, pivot_state, &changed, &[]);
+}
+
+/// Define one workbook name per column and/or row of a selection, named by
+/// its header cell (Excel's "Create from Selection"). Headers are sanitized
+/// and deduplicated; names that already exist are reported, not replaced.
+#[tauri::command]
+pub fn create_names_from_selection(
+    state: State<AppState>,
+    user_files_state: State<UserFilesState>,
+    pivot_state: State<'_, PivotState>,
+    start_row: u32,
+    start_col: u32,
+    end_row: u32,
+    end_col: u32,
+    headers: NameHeaders,
+) -> Result<BulkNamesResult, String> {
+    create_names_from_selection_internal(
+        &state, &user_files_state, &pivot_state, start_row, start_col, end_row, end_col, headers,
+    )
+}
+
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn create_names_from_selection_internal(
+    state: &AppState,
+    user_files_state: &UserFilesState,
+    pivot_state: &PivotState,
+    start_row: u32,
+    start_col: u32,
+    end_row: u32,
+    end_col: u32,
+    headers: NameHeaders,
+) -> Result<BulkNamesResult, String> {
+    use crate::pivot::utils::col_index_to_letter;
+
+    let (start_row, end_row) = (start_row.min(end_row), start_row.max(end_row));
+    let (start_col, end_col) = (start_col.min(end_col), start_col.max(end_col));
+    let top = headers != NameHeaders::LeftColumn;
+    let left = headers != NameHeaders::TopRow;
+    if (top && start_row == end_row) || (left && start_col == end_col) {
+        return Err("The selection must extend past its header row or column.".to_string());
+    }
+    let first_row = if top { start_row + 1 } else { start_row };
+    let first_col = if left { start_col + 1 } else { start_col };
+
+    let active_sheet = *state.active_sheet.lock().unwrap();
+    let sheet_name = state
+        .sheet_names
+        .lock()
+        .unwrap()
+        .get(active_sheet)
+        .cloned()
+        .ok_or_else(|| "Invalid active sheet.".to_string())?;
+
+    // (header cell, range it names)
+    let mut candidates: Vec<((u32, u32), PrintRange)> = Vec::new();
+    if top {
+        for col in first_col..=end_col {
+            candidates.push(((start_row, col), PrintRange { start_row: first_row, start_col: col, end_row, end_col: col }));
+        }
+    }
+    if left {
+        for row in first_row..=end_row {
+            candidates.push(((row, start_col), PrintRange { start_row: row, start_col: first_col, end_row: row, end_col }));
+        }
+    }
+
+    let mut result = BulkNamesResult::default();
+    {
+        let grids = state.grids.lock().unwrap();
+        let named_ranges = state.named_ranges.lock().unwrap();
+        let grid = grids.get(active_sheet).ok_or_else(|| "Invalid active sheet.".to_string())?;
+        let mut taken: HashSet<String> = HashSet::new();
+
+        for ((row, col), range) in candidates {
+            let text = grid.get_cell(row, col).map(|cell| header_text(&cell.value)).unwrap_or_default();
+            let Some(base) = sanitize_name(&text) else {
+                result.skipped.push(SkippedName {
+                    name: String::new(),
+                    reason: format!("Header cell {}{} is blank.", col_index_to_letter(col), row + 1),
+                });
+                continue;
+            };
+            // Repeated headers in the selection get numbered ("Region_2")
+            let name = unique_name(&base, |candidate| taken.contains(&candidate.to_uppercase()));
+            let key = name.to_uppercase();
+            if named_ranges.contains_key(&key) || is_builtin_name(&name) {
+                result.skipped.push(SkippedName {
+                    reason: format!("A named range '{}' already exists.", name),
+                    name,
+                });
+                continue;
+            }
+            taken.insert(key);
+            result.created.push(NamedRange {
+                name,
+                sheet_index: None,
+                refers_to: format!("={}{}", sheet_ref_prefix(&sheet_name), absolute_range(&range)),
+                comment: None,
+                folder: None,
+            });
+        }
+    }
+
+    store_names(state, user_files_state, pivot_state, &result.created, "Create names from selection");
+    Ok(result)
+}
+
+/// Export the workbook's names (built-in print names excluded) as JSON, for
+/// `import_named_ranges` in another workbook.
+#[tauri::command]
+pub fn export_named_ranges(state: State<AppState>) -> Result<String, String> {
+    export_named_ranges_internal(&state)
+}
+
+pub(crate) fn export_named_ranges_internal(state: &AppState) -> Result<String, String> {
+    let mut names = get_all_named_ranges_internal(state);
+    names.sort_by_key(|nr| nr.name.to_uppercase());
+    serde_json::to_string_pretty(&names).map_err(|e| e.to_string())
+}
+
+/// Import names exported by `export_named_ranges`. Names the workbook already
+/// has follow `policy`; invalid names and names scoped to a sheet this
+/// workbook lacks are skipped. All imported names are one undo step.
+#[tauri::command]
+pub fn import_named_ranges(
+    state: State<AppState>,
+    user_files_state: State<UserFilesState>,
+    pivot_state: State<'_, PivotState>,
+    json: String,
+    policy: NameCollisionPolicy,
+) -> Result<BulkNamesResult, String> {
+    import_named_ranges_internal(&state, &user_files_state, &pivot_state, &json, policy)
+}
+
+pub(crate) fn import_named_ranges_internal(
+    state: &AppState,
+    user_files_state: &UserFilesState,
+    pivot_state: &PivotState,
+    json: &str,
+    policy: NameCollisionPolicy,
+) -> Result<BulkNamesResult, String> {
+    let incoming: Vec<NamedRange> =
+        serde_json::from_str(json).map_err(|e| format!("Invalid named range data: {}", e))?;
+    let sheet_count = state.grids.lock().unwrap().len();
+
+    let mut result = BulkNamesResult::default();
+    {
+        let named_ranges = state.named_ranges.lock().unwrap();
+        let mut imported: HashSet<String> = HashSet::new();
+
+        for mut nr in incoming {
+            if !NamedRange::is_valid_name(&nr.name) || is_builtin_name(&nr.name) {
+                result.skipped.push(SkippedName { reason: format!("Invalid name '{}'.", nr.name), name: nr.name });
+                continue;
+            }
+            if let Some(index) = nr.sheet_index.filter(|&i| i >= sheet_count) {
+                result.skipped.push(SkippedName {
+                    reason: format!("Sheet {} does not exist in this workbook.", index + 1),
+                    name: nr.name,
+                });
+                continue;
+            }
+            let key = nr.name.to_uppercase();
+            if imported.contains(&key) {
+                result.skipped.push(SkippedName { reason: "Listed more than once.".to_string(), name: nr.name });
+                continue;
+            }
+            if named_ranges.contains_key(&key) {
+                match policy {
+                    NameCollisionPolicy::Skip => {
+                        result.skipped.push(SkippedName {
+                            reason: format!("A named range '{}' already exists.", nr.name),
+                            name: nr.name,
+                        });
+                        continue;
+                    }
+                    NameCollisionPolicy::Overwrite => result.overwritten.push(nr.name.clone()),
+                    NameCollisionPolicy::Rename => {
+                        let to = unique_name(&nr.name, |candidate| {
+                            let key = candidate.to_uppercase();
+                            named_ranges.contains_key(&key) || imported.contains(&key)
+                        });
+                        result.renamed.push(RenamedName { from: std::mem::replace(&mut nr.name, to.clone()), to });
+                    }
+                }
+            }
+            imported.insert(nr.name.to_uppercase());
+            result.created.push(nr);
+        }
+    }
+
+    store_names(state, user_files_state, pivot_state, &result.created, "Import names");
+    Ok(result)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -826,4 +1474,17 @@ mod tests {
         assert!(!NamedRange::looks_like_cell_reference("A"));
         assert!(!NamedRange::looks_like_cell_reference("1"));
     }
+
+    #[test]
+    fn test_sanitize_header_names() {
+        assert_eq!(sanitize_name("Unit Price").as_deref(), Some("Unit_Price"));
+        assert_eq!(sanitize_name(" 2024 ").as_deref(), Some("_2024"));
+        assert_eq!(sanitize_name("Q1").as_deref(), Some("Q1_"));
+        assert_eq!(sanitize_name("true").as_deref(), Some("true_"));
+        assert_eq!(sanitize_name("Cost/Unit (EUR)").as_deref(), Some("Cost_Unit__EUR_"));
+        assert_eq!(sanitize_name("   "), None);
+        for text in ["Unit Price", "2024", "Q1", "true", "#x"] {
+            assert!(NamedRange::is_valid_name(&sanitize_name(text).unwrap()), "{}", text);
+        }
+    }
 }
@@ -90,9 +90,7 @@ pub fn apply_named_style(
         }
     };
 
-    let mut grid = state.grid.lock().unwrap();
-    let mut grids = state.grids.lock().unwrap();
-    let active_sheet = *state.active_sheet.lock().unwrap();
+    let mut grid = state.get_active_grid();
     let styles = state.style_registry.lock().unwrap();
     let mut undo_stack = state.undo_stack.lock().unwrap();
     let merged_regions = state.merged_regions.lock().unwrap();
@@ -117,6 +115,7 @@ pub fn apply_named_style(
                     ast: None,
                     style_index: 0,
                     rich_text: None,
+                    quote_prefix: false,
                 }
             };
 
@@ -125,10 +124,6 @@ pub fn apply_named_style(
             updated_cell.style_index = style_index;
             grid.set_cell(row, col, updated_cell.clone());
 
-            if active_sheet < grids.len() {
-                grids[active_sheet].set_cell(row, col, updated_cell.clone());
-            }
-
             // Record undo
             undo_stack.record_cell_change(row, col, previous_cell);
 
@@ -160,6 +155,7 @@ pub fn apply_named_style(
                 sheet_index: None,
                 rich_text: None,
                 accounting_layout: acct_layout,
+                error_detail: None,
             });
         }
     }
@@ -11,7 +11,7 @@ use chrono::Utc;
 use uuid::Uuid;
 
 /// Record a note change to the undo stack.
-fn record_note_undo(state: &AppState, sheet_index: usize, row: u32, col: u32, previous: Option<Note>, description: &str) {
+pub(crate) fn record_note_undo(state: &AppState, sheet_index: usize, row: u32, col: u32, previous: Option<Note>, description: &str) {
     #[derive(Serialize)]
     struct NoteSnapshot {
         sheet_index: usize,
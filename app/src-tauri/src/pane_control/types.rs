@@ -187,7 +187,7 @@ pub struct UpdatePaneControlParams {
 ///
 /// LOCK ORDER: when both families are needed, take `PaneControlState.controls`
 /// BEFORE `RibbonFilterState.filters`, and NEVER hold either while acquiring
-/// the grid locks (`AppState.grids` / `AppState.grid`) — extract plain data
+/// the grids lock (`AppState.grids`) — extract plain data
 /// first, drop, then read grids (convention per resolve_control_properties).
 pub struct PaneControlState {
     /// All pane controls: id -> PaneControl
@@ -3,13 +3,14 @@
 use identity::SheetId;
 use crate::api_types::CellData;
 use crate::tables::{
-    Table, TableColumn, TableStyleOptions, TotalsRowFunction, TableStorage, TableNameRegistry,
+    ColumnDataType, DataTypePolicy, Table, TableColumn, TableStyleOptions, TotalsRowFunction,
+    TableStorage, TableNameRegistry,
 };
 use crate::{format_cell_value, AppState};
 use persistence::{
     load_xlsx, save_xlsx, DimensionData, SavedTable, SavedTableColumn, SavedTableStyleOptions,
     SavedMergedRegion, SavedNamedRange, SavedNote, SavedHyperlink, SavedPageSetup,
-    Workbook,
+    ValuesOnlyReport, ValuesOnlyStrip, Workbook,
 };
 use calcula_format::{save_calcula_opt, load_calcula_opt};
 use zeroize::Zeroizing;
@@ -31,6 +32,187 @@ pub struct FileState {
     /// Whether the currently-open document is encrypted. Drives the File-menu
     /// label ("Encrypt with Password…" vs "Remove Password").
     pub is_encrypted: Mutex<bool>,
+    /// The current file as it was on disk after the last open/save. `None`
+    /// for a new, never-saved document.
+    pub disk_state: Mutex<Option<DiskState>>,
+    /// Disk state last announced by the external-change watcher, so one
+    /// external edit raises one event (`Some(None)`: a deletion was reported).
+    pub reported_disk_state: Mutex<Option<Option<DiskState>>>,
+    /// Set while the open workbook is read-only; edit commands are then
+    /// rejected (see `read_only`).
+    pub read_only: Mutex<Option<crate::read_only::ReadOnlyReason>>,
+}
+
+/// Read-only state of the open workbook, for the title-bar badge.
+#[derive(Debug, Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FileAccessState {
+    pub read_only: bool,
+    pub reason: Option<crate::read_only::ReadOnlyReason>,
+}
+
+/// Sentinel error from `save_file` when the target is the open document and
+/// another program changed it since it was opened or last saved. The frontend
+/// offers `save_file { overwrite: true }` or Save As.
+pub const FILE_CHANGED_EXTERNALLY: &str = "FILE_CHANGED_EXTERNALLY";
+
+/// Sentinel error from `reload_file` when it would discard unsaved changes
+/// and `force` was not set.
+pub const RELOAD_DISCARDS_CHANGES: &str = "RELOAD_DISCARDS_CHANGES";
+
+/// Event emitted by the external-change watcher with an `ExternalFileChange`.
+pub const FILE_CHANGED_EXTERNALLY_EVENT: &str = "file-changed-externally";
+
+/// How often the watcher compares the open document with the disk.
+const EXTERNAL_CHANGE_POLL_INTERVAL: std::time::Duration = std::time::Duration::from_secs(5);
+
+/// Fingerprint of a file on disk: the cheap mtime/length pair, plus a content
+/// hash so a file that was merely touched (or rewritten byte-for-byte by a
+/// sync client) is not reported as changed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DiskState {
+    modified: Option<std::time::SystemTime>,
+    len: u64,
+    hash: u64,
+}
+
+impl DiskState {
+    pub fn read(path: &std::path::Path) -> std::io::Result<Self> {
+        use std::hash::Hasher;
+        let metadata = std::fs::metadata(path)?;
+        let mut hasher = std::collections::hash_map::DefaultHasher::new();
+        hasher.write(&std::fs::read(path)?);
+        Ok(DiskState {
+            modified: metadata.modified().ok(),
+            len: metadata.len(),
+            hash: hasher.finish(),
+        })
+    }
+
+    fn same_metadata(&self, metadata: &std::fs::Metadata) -> bool {
+        self.len == metadata.len() && self.modified == metadata.modified().ok()
+    }
+}
+
+/// The open document was changed (or removed) on disk by someone else.
+#[derive(Debug, Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExternalFileChange {
+    pub path: String,
+    /// The file no longer exists at `path`.
+    pub deleted: bool,
+    /// Disk modification time (RFC 3339), when the file still exists.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub modified_at: Option<String>,
+    /// Saving over it or reloading would also lose in-memory edits.
+    pub has_unsaved_changes: bool,
+}
+
+impl FileState {
+    /// Why the open workbook is read-only, if it is.
+    pub fn read_only_reason(&self) -> Option<crate::read_only::ReadOnlyReason> {
+        self.read_only.lock().map(|r| *r).unwrap_or(None)
+    }
+
+    pub fn set_read_only(&self, reason: Option<crate::read_only::ReadOnlyReason>) -> Result<(), String> {
+        *self.read_only.lock().map_err(|e| e.to_string())? = reason;
+        Ok(())
+    }
+
+    /// Refuse to save a read-only workbook over its own file. Any other path
+    /// is a Save As, which is how the user leaves read-only mode.
+    pub fn check_save_target(&self, path: &std::path::Path) -> Result<(), String> {
+        if self.read_only_reason().is_none() {
+            return Ok(());
+        }
+        let is_current = self.current_path.lock().map_err(|e| e.to_string())?.as_deref() == Some(path);
+        if is_current {
+            return Err(crate::read_only::WORKBOOK_READ_ONLY.to_string());
+        }
+        Ok(())
+    }
+
+    /// Adopt `path` as the open document after a successful save: it is
+    /// unmodified, writable, and its disk state is the new baseline.
+    pub fn record_save(&self, path: PathBuf) -> Result<(), String> {
+        self.record_disk_state(&path);
+        *self.current_path.lock().map_err(|e| e.to_string())? = Some(path);
+        *self.is_modified.lock().map_err(|e| e.to_string())? = false;
+        self.set_read_only(None)
+    }
+
+    /// Take `path`'s current disk state as the baseline for external-change
+    /// detection (after a successful open or save).
+    pub fn record_disk_state(&self, path: &std::path::Path) {
+        let state = DiskState::read(path).ok();
+        if let Ok(mut reported) = self.reported_disk_state.lock() {
+            *reported = None;
+        }
+        if let Ok(mut disk) = self.disk_state.lock() {
+            *disk = state;
+        }
+    }
+
+    /// Compare the open document with the disk. `None` when nothing changed
+    /// or no file is open.
+    pub fn external_change(&self) -> Result<Option<ExternalFileChange>, String> {
+        self.external_change_state().map(|found| found.map(|(change, _)| change))
+    }
+
+    /// `external_change` plus the disk state it saw (`None` when deleted).
+    fn external_change_state(&self) -> Result<Option<(ExternalFileChange, Option<DiskState>)>, String> {
+        let Some(path) = self.current_path.lock().map_err(|e| e.to_string())?.clone() else {
+            return Ok(None);
+        };
+        let Some(baseline) = self.disk_state.lock().map_err(|e| e.to_string())?.clone() else {
+            return Ok(None);
+        };
+        let has_unsaved_changes = *self.is_modified.lock().map_err(|e| e.to_string())?;
+        let change = |deleted: bool, modified: Option<std::time::SystemTime>| ExternalFileChange {
+            path: path.to_string_lossy().to_string(),
+            deleted,
+            modified_at: modified.map(|t| chrono::DateTime::<chrono::Utc>::from(t).to_rfc3339()),
+            has_unsaved_changes,
+        };
+
+        let Ok(metadata) = std::fs::metadata(&path) else {
+            return Ok(Some((change(true, None), None)));
+        };
+        if baseline.same_metadata(&metadata) {
+            return Ok(None);
+        }
+        let Ok(current) = DiskState::read(&path) else {
+            return Ok(Some((change(true, None), None)));
+        };
+        if current.len == baseline.len && current.hash == baseline.hash {
+            return Ok(None);
+        }
+        Ok(Some((change(false, current.modified), Some(current))))
+    }
+
+    /// The external change to announce, once per distinct disk state.
+    pub(crate) fn unreported_external_change(&self) -> Option<ExternalFileChange> {
+        let (change, seen) = self.external_change_state().ok()??;
+        let mut reported = self.reported_disk_state.lock().ok()?;
+        if reported.as_ref() == Some(&seen) {
+            return None;
+        }
+        *reported = Some(seen);
+        Some(change)
+    }
+}
+
+/// Poll the open document for external changes in the background and emit
+/// `FILE_CHANGED_EXTERNALLY_EVENT` once per change. Started from `run()`.
+pub fn spawn_external_change_watcher(app: tauri::AppHandle) {
+    std::thread::spawn(move || loop {
+        std::thread::sleep(EXTERNAL_CHANGE_POLL_INTERVAL);
+        use tauri::Manager;
+        let Some(file_state) = app.try_state::<FileState>() else { continue };
+        if let Some(change) = file_state.unreported_external_change() {
+            let _ = app.emit(FILE_CHANGED_EXTERNALLY_EVENT, &change);
+        }
+    });
 }
 
 /// Virtual filesystem for user files stored inside the .cala archive.
@@ -75,6 +257,7 @@ fn table_to_saved(table: &Table, sheet_ids: &[SheetId]) -> SavedTable {
                 totals_row_function: totals_fn_to_string(&c.totals_row_function),
                 totals_row_formula: c.totals_row_formula.clone(),
                 calculated_formula: c.calculated_formula.clone(),
+                data_type: c.data_type.map(|t| t.as_str().to_string()),
             })
             .collect(),
         style_options: SavedTableStyleOptions {
@@ -87,6 +270,10 @@ fn table_to_saved(table: &Table, sheet_ids: &[SheetId]) -> SavedTable {
             show_filter_button: table.style_options.show_filter_button,
         },
         style_name: table.style_name.clone(),
+        data_type_policy: match table.data_type_policy {
+            DataTypePolicy::Reject => None,
+            DataTypePolicy::Coerce => Some("coerce".to_string()),
+        },
     }
 }
 
@@ -115,6 +302,7 @@ pub fn saved_table_to_table_at(saved: &SavedTable, sheet_index: usize) -> Table
                 totals_row_function: string_to_totals_fn(&c.totals_row_function),
                 totals_row_formula: c.totals_row_formula.clone(),
                 calculated_formula: c.calculated_formula.clone(),
+                data_type: c.data_type.as_deref().and_then(ColumnDataType::from_name),
             })
             .collect(),
         style_options: TableStyleOptions {
@@ -128,6 +316,10 @@ pub fn saved_table_to_table_at(saved: &SavedTable, sheet_index: usize) -> Table
         },
         style_name: saved.style_name.clone(),
         auto_filter_id: None,
+        data_type_policy: match saved.data_type_policy.as_deref() {
+            Some("coerce") => DataTypePolicy::Coerce,
+            _ => DataTypePolicy::Reject,
+        },
     }
 }
 
@@ -203,16 +395,15 @@ fn restore_tables(
 ///
 /// Captures ALL sheets, not just the active one (BUG-0011: the old
 /// single-sheet `Workbook::from_grid` build silently dropped every other
-/// sheet on save). The active sheet is read from the `state.grid` mirror and
-/// the active-sheet dimension/merge mirrors, which are the source of truth
-/// while a sheet is active (the `all_*` slots for the active sheet are
-/// empty — they were std::mem::take'n on switch).
+/// sheet on save). The active sheet's dimensions and merges are read from the
+/// active-sheet mirrors, which are the source of truth while a sheet is
+/// active (the `all_*` slots for the active sheet are empty — they were
+/// std::mem::take'n on switch).
 pub fn build_workbook_for_save(
-    state: &State<AppState>,
-    user_files_state: &State<UserFilesState>,
+    state: &AppState,
+    user_files_state: &UserFilesState,
 ) -> Result<Workbook, String> {
     let grids = state.grids.lock().map_err(|e| e.to_string())?;
-    let active_grid = state.grid.lock().map_err(|e| e.to_string())?;
     let sheet_names = state.sheet_names.lock().map_err(|e| e.to_string())?;
     let active_sheet = *state.active_sheet.lock().map_err(|e| e.to_string())?;
     let styles = state.style_registry.lock().map_err(|e| e.to_string())?;
@@ -229,11 +420,7 @@ pub fn build_workbook_for_save(
 
     let empty_grid = engine::grid::Grid::new();
     for i in 0..sheet_names.len() {
-        let grid_ref: &engine::Grid = if i == active_sheet {
-            &active_grid
-        } else {
-            grids.get(i).unwrap_or(&empty_grid)
-        };
+        let grid_ref: &engine::Grid = grids.get(i).unwrap_or(&empty_grid);
         let dimensions = DimensionData {
             column_widths: if i == active_sheet {
                 col_widths.clone()
@@ -259,7 +446,6 @@ pub fn build_workbook_for_save(
     }
 
     drop(grids);
-    drop(active_grid);
     drop(sheet_names);
     drop(styles);
     drop(col_widths);
@@ -274,20 +460,13 @@ pub fn build_workbook_for_save(
     workbook.theme = state.theme.lock().unwrap().clone();
     workbook.default_row_height = *state.default_row_height.lock().unwrap();
     workbook.default_column_width = *state.default_column_width.lock().unwrap();
+    workbook.calc_properties = state.calculation_settings.lock().unwrap().to_saved();
 
     // Include workbook properties
     {
         let props = state.workbook_properties.lock().unwrap();
-        workbook.properties = persistence::WorkbookProperties {
-            title: props.title.clone(),
-            author: props.author.clone(),
-            subject: props.subject.clone(),
-            description: props.description.clone(),
-            keywords: props.keywords.clone(),
-            category: props.category.clone(),
-            created: props.created.clone(),
-            last_modified: chrono::Utc::now().to_rfc3339(),
-        };
+        workbook.properties = persistence::WorkbookProperties::from(&*props);
+        workbook.properties.last_modified = chrono::Utc::now().to_rfc3339();
     }
 
     // Enrich with sheet-level metadata (merged regions, freeze panes, etc.)
@@ -298,10 +477,10 @@ pub fn build_workbook_for_save(
 
 /// Build a Workbook from the current AppState including slicer and ribbon filter state.
 pub fn build_workbook_for_save_with_slicers(
-    state: &State<AppState>,
-    user_files_state: &State<UserFilesState>,
-    slicer_state: &State<crate::slicer::SlicerState>,
-    ribbon_filter_state: &State<crate::ribbon_filter::RibbonFilterState>,
+    state: &AppState,
+    user_files_state: &UserFilesState,
+    slicer_state: &crate::slicer::SlicerState,
+    ribbon_filter_state: &crate::ribbon_filter::RibbonFilterState,
 ) -> Result<Workbook, String> {
     let mut workbook = build_workbook_for_save(state, user_files_state)?;
     let sheet_ids_bwfs = state.sheet_ids.lock().map_err(|e| e.to_string())?;
@@ -560,6 +739,9 @@ fn enrich_workbook_metadata(workbook: &mut Workbook, state: &AppState, sheet_ids
     }
 
     // ---- Page setup ----
+    // Print area / titles live in the sheet's built-in names; the saved text
+    // fields are filled from them.
+    let print_setup = crate::commands::print::sheet_page_setup(state, i);
     if let Ok(page_setups) = state.page_setups.lock() {
         if let Some(ps) = page_setups.get(i) {
             workbook.sheets[i].page_setup = Some(SavedPageSetup {
@@ -573,8 +755,8 @@ fn enrich_workbook_metadata(workbook: &mut Workbook, state: &AppState, sheet_ids
                 margin_footer: ps.margin_footer,
                 header: ps.header.clone(),
                 footer: ps.footer.clone(),
-                print_area: ps.print_area.clone(),
-                print_titles_rows: ps.print_titles_rows.clone(),
+                print_area: print_setup.print_area.clone(),
+                print_titles_rows: print_setup.print_titles_rows.clone(),
                 manual_row_breaks: ps.manual_row_breaks.clone(),
                 print_gridlines: ps.print_gridlines,
                 center_horizontally: ps.center_horizontally,
@@ -588,18 +770,33 @@ fn enrich_workbook_metadata(workbook: &mut Workbook, state: &AppState, sheet_ids
         }
     }
 
-    // ---- Gridlines visibility ----
-    if let Ok(gridlines) = state.show_gridlines.lock() {
-        if let Some(&visible) = gridlines.get(i) {
-            workbook.sheets[i].show_gridlines = visible;
+    // ---- View options: zoom, gridlines, headings, direction ----
+    if let Ok(views) = state.sheet_view_options.lock() {
+        if let Some(view) = views.get(i) {
+            let sheet = &mut workbook.sheets[i];
+            sheet.zoom = view.zoom;
+            sheet.show_gridlines = view.show_gridlines;
+            sheet.show_headings = view.show_headings;
+            sheet.right_to_left = view.right_to_left;
+        }
+    }
+
+    // ---- Sheet default sizes ----
+    if let Ok(defaults) = state.sheet_defaults.lock() {
+        if let Some(d) = defaults.get(i) {
+            let sheet = &mut workbook.sheets[i];
+            sheet.default_column_width = d.default_col_width;
+            sheet.default_row_height = d.default_row_height;
         }
     }
     } // end per-sheet loop
 
     // ---- Named ranges (workbook-level) ----
     if let Ok(named_ranges) = state.named_ranges.lock() {
+        // Built-in print names are saved through the page setup instead.
         workbook.named_ranges = named_ranges
             .values()
+            .filter(|nr| !crate::named_ranges::is_builtin_name(&nr.name))
             .map(|nr| SavedNamedRange {
                 name: nr.name.clone(),
                 refers_to: nr.refers_to.clone(),
@@ -656,7 +853,8 @@ fn enrich_workbook_metadata(workbook: &mut Workbook, state: &AppState, sheet_ids
 /// Collect sheet/cell/workbook protection into the persisted SheetId-keyed
 /// opaque carriers. Cell-protection maps are keyed by (row, col) tuples in
 /// AppState, which JSON cannot represent as object keys, so they serialize as
-/// a `[{ row, col, locked, formulaHidden }]` entry list.
+/// a `[{ row, col, locked, formulaHidden }]` entry list, followed by the
+/// sheet's range records.
 fn collect_protection_for_save(
     state: &AppState,
     sheet_ids: &[SheetId],
@@ -709,6 +907,23 @@ fn collect_protection_for_save(
             per_sheet.entry(*idx).or_default().1 = Some(serde_json::Value::Array(entries));
         }
     }
+    // Range records ride in the same list after the cell entries, in apply
+    // order; they carry startRow/startCol/endRow/endCol instead of row/col.
+    if let Ok(store) = state.range_protection.lock() {
+        for (idx, ranges) in store.iter() {
+            if ranges.is_empty() {
+                continue;
+            }
+            let slot = per_sheet
+                .entry(*idx)
+                .or_default()
+                .1
+                .get_or_insert_with(|| serde_json::Value::Array(Vec::new()));
+            if let Some(entries) = slot.as_array_mut() {
+                entries.extend(ranges.iter().filter_map(|r| serde_json::to_value(r).ok()));
+            }
+        }
+    }
 
     let sheet_protections = per_sheet
         .into_iter()
@@ -733,7 +948,7 @@ fn collect_protection_for_save(
 
 /// Collect slicers from SlicerState into SavedSlicer format.
 fn collect_slicers_for_save(
-    slicer_state: &State<crate::slicer::SlicerState>,
+    slicer_state: &crate::slicer::SlicerState,
     sheet_ids: &[SheetId],
 ) -> Vec<persistence::SavedSlicer> {
     let slicers = slicer_state.slicers.lock().unwrap();
@@ -908,7 +1123,7 @@ pub(crate) fn slicer_computed_props_from_saved(
 /// Restore slicers from SavedSlicer format into SlicerState.
 fn restore_slicers(
     saved_slicers: &[persistence::SavedSlicer],
-    slicer_state: &State<crate::slicer::SlicerState>,
+    slicer_state: &crate::slicer::SlicerState,
     workbook: &persistence::Workbook,
 ) {
     let mut slicers = slicer_state.slicers.lock().unwrap();
@@ -935,7 +1150,7 @@ fn restore_slicers(
 
 /// Collect ribbon filters from RibbonFilterState into SavedRibbonFilter format.
 fn collect_ribbon_filters_for_save(
-    ribbon_filter_state: &State<crate::ribbon_filter::RibbonFilterState>,
+    ribbon_filter_state: &crate::ribbon_filter::RibbonFilterState,
 ) -> Vec<persistence::SavedRibbonFilter> {
     let filters = ribbon_filter_state.filters.lock().unwrap();
     filters
@@ -1076,7 +1291,7 @@ fn parse_advanced_operator(s: &str) -> crate::ribbon_filter::AdvancedFilterOpera
 /// Restore ribbon filters from SavedRibbonFilter format into RibbonFilterState.
 fn restore_ribbon_filters(
     saved_filters: &[persistence::SavedRibbonFilter],
-    ribbon_filter_state: &State<crate::ribbon_filter::RibbonFilterState>,
+    ribbon_filter_state: &crate::ribbon_filter::RibbonFilterState,
 ) {
     let mut filters = ribbon_filter_state.filters.lock().unwrap();
 
@@ -1095,7 +1310,7 @@ fn restore_ribbon_filters(
 /// Collect pane controls from PaneControlState into SavedPaneControl format.
 /// Sorted by (order, id) for deterministic artifact bytes across saves.
 pub(crate) fn collect_pane_controls_for_save(
-    pane_control_state: &State<crate::pane_control::PaneControlState>,
+    pane_control_state: &crate::pane_control::PaneControlState,
 ) -> Vec<persistence::SavedPaneControl> {
     let controls = pane_control_state.controls.lock().unwrap();
     let mut saved: Vec<persistence::SavedPaneControl> =
@@ -1169,7 +1384,7 @@ pub(crate) fn saved_to_pane_control(
 /// Unknown/bad entries are skipped with a warning (see saved_to_pane_control).
 fn restore_pane_controls(
     saved_controls: &[persistence::SavedPaneControl],
-    pane_control_state: &State<crate::pane_control::PaneControlState>,
+    pane_control_state: &crate::pane_control::PaneControlState,
 ) {
     let mut controls = pane_control_state.controls.lock().unwrap();
 
@@ -1187,7 +1402,7 @@ fn restore_pane_controls(
 // ============================================================================
 
 /// Collect charts from AppState into SavedChart format for persistence.
-pub(crate) fn collect_charts_for_save(state: &State<AppState>, sheet_ids: &[SheetId]) -> Vec<persistence::SavedChart> {
+pub(crate) fn collect_charts_for_save(state: &AppState, sheet_ids: &[SheetId]) -> Vec<persistence::SavedChart> {
     let charts = state.charts.lock().unwrap();
     charts
         .iter()
@@ -1200,7 +1415,7 @@ pub(crate) fn collect_charts_for_save(state: &State<AppState>, sheet_ids: &[Shee
 }
 
 /// Restore charts from SavedChart format into AppState.
-fn restore_charts(saved: &[persistence::SavedChart], state: &State<AppState>, workbook: &persistence::Workbook) {
+fn restore_charts(saved: &[persistence::SavedChart], state: &AppState, workbook: &persistence::Workbook) {
     let mut charts = state.charts.lock().unwrap();
     charts.clear();
     for s in saved {
@@ -1213,7 +1428,7 @@ fn restore_charts(saved: &[persistence::SavedChart], state: &State<AppState>, wo
 }
 
 /// Collect sparkline entries from AppState for saving to .cala.
-pub(crate) fn collect_sparklines_for_save(state: &State<AppState>, sheet_ids: &[SheetId]) -> Vec<persistence::SavedSparkline> {
+pub(crate) fn collect_sparklines_for_save(state: &AppState, sheet_ids: &[SheetId]) -> Vec<persistence::SavedSparkline> {
     let sparklines = state.sparklines.lock().unwrap();
     sparklines
         .iter()
@@ -1225,7 +1440,7 @@ pub(crate) fn collect_sparklines_for_save(state: &State<AppState>, sheet_ids: &[
 }
 
 /// Restore sparklines from SavedSparkline format into AppState.
-fn restore_sparklines(saved: &[persistence::SavedSparkline], state: &State<AppState>, workbook: &persistence::Workbook) {
+fn restore_sparklines(saved: &[persistence::SavedSparkline], state: &AppState, workbook: &persistence::Workbook) {
     let mut sparklines = state.sparklines.lock().unwrap();
     sparklines.clear();
     for s in saved {
@@ -1266,9 +1481,9 @@ pub(crate) fn collect_pivot_definitions(
     for (pivot_id, (def, _cache)) in pivot_tables.iter() {
         let is_bi = bi_metadata.contains_key(pivot_id);
         let source_sheet_index = if !is_bi {
-            // For grid pivots, find the source sheet by the destination_sheet name
-            // (source data is typically on the same or a known sheet)
-            def.destination_sheet.as_ref().and_then(|name|
+            // For grid pivots, find the source sheet by name; pivots created
+            // before it was recorded fall back to the destination sheet
+            def.source_sheet.as_ref().or(def.destination_sheet.as_ref()).and_then(|name|
                 sheet_names.iter().position(|n| n == name)
             )
         } else {
@@ -1453,14 +1668,14 @@ fn restore_pivot_definitions(
 /// can never silently drift behind the real save again.
 #[allow(clippy::too_many_arguments)]
 fn assemble_workbook_for_save(
-    state: &State<AppState>,
-    user_files_state: &State<UserFilesState>,
-    slicer_state: &State<crate::slicer::SlicerState>,
-    ribbon_filter_state: &State<crate::ribbon_filter::RibbonFilterState>,
-    pane_control_state: &State<crate::pane_control::PaneControlState>,
-    script_state: &State<crate::scripting::types::ScriptState>,
-    pivot_state: &State<crate::pivot::types::PivotState>,
-    bi_state: &State<crate::bi::types::BiState>,
+    state: &AppState,
+    user_files_state: &UserFilesState,
+    slicer_state: &crate::slicer::SlicerState,
+    ribbon_filter_state: &crate::ribbon_filter::RibbonFilterState,
+    pane_control_state: &crate::pane_control::PaneControlState,
+    script_state: &crate::scripting::types::ScriptState,
+    pivot_state: &crate::pivot::types::PivotState,
+    bi_state: &crate::bi::types::BiState,
 ) -> Result<Workbook, String> {
     // Multi-sheet workbook build (BUG-0011: the old inline single-sheet
     // Workbook::from_grid build dropped every sheet but the active one).
@@ -1585,16 +1800,7 @@ fn assemble_workbook_for_save(
     // caller's decision — save_file stamps, auto-recover does not).
     {
         let props = state.workbook_properties.lock().unwrap();
-        workbook.properties = persistence::WorkbookProperties {
-            title: props.title.clone(),
-            author: props.author.clone(),
-            subject: props.subject.clone(),
-            description: props.description.clone(),
-            keywords: props.keywords.clone(),
-            category: props.category.clone(),
-            created: props.created.clone(),
-            last_modified: props.last_modified.clone(),
-        };
+        workbook.properties = persistence::WorkbookProperties::from(&*props);
     }
 
     // Sheet-level metadata was already enriched by build_workbook_for_save.
@@ -1618,29 +1824,85 @@ pub fn save_file(
     // `None` falls back to the session password so a plain Ctrl+S keeps an
     // already-encrypted document encrypted. Ignored for non-`.cala` formats.
     password: Option<String>,
+    // Save over the open document even though another program changed it on
+    // disk (the user chose to discard those edits).
+    overwrite: Option<bool>,
     window: tauri::Window,
 ) -> Result<(), String> {
     crate::security::window_guard::require_label(&window, crate::security::window_guard::MAIN)?;
-    // If calculate_before_save is enabled, recalculate all formulas first
+    save_file_internal(
+        &state,
+        &file_state,
+        &user_files_state,
+        &slicer_state,
+        &ribbon_filter_state,
+        &pane_control_state,
+        &script_state,
+        &pivot_state,
+        &bi_state,
+        path,
+        password,
+        overwrite,
+    )
+}
+
+/// Body of `save_file`, callable without a window (the headless session).
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn save_file_internal(
+    state: &AppState,
+    file_state: &FileState,
+    user_files_state: &UserFilesState,
+    slicer_state: &crate::slicer::SlicerState,
+    ribbon_filter_state: &crate::ribbon_filter::RibbonFilterState,
+    pane_control_state: &crate::pane_control::PaneControlState,
+    script_state: &crate::scripting::types::ScriptState,
+    pivot_state: &crate::pivot::types::PivotState,
+    bi_state: &crate::bi::types::BiState,
+    path: String,
+    password: Option<String>,
+    overwrite: Option<bool>,
+) -> Result<(), String> {
+    file_state.check_save_target(std::path::Path::new(&path))?;
+    // Never silently overwrite another program's edits to the open file. A
+    // deleted file is simply recreated; Save As to another path is unaffected.
+    if !overwrite.unwrap_or(false) {
+        let is_current = file_state
+            .current_path
+            .lock()
+            .map_err(|e| e.to_string())?
+            .as_deref()
+            == Some(std::path::Path::new(&path));
+        if is_current && file_state.external_change()?.is_some_and(|c| !c.deleted) {
+            return Err(FILE_CHANGED_EXTERNALLY.to_string());
+        }
+    }
+    // If recalc_before_save is enabled, recalculate all formulas first
     {
-        let calc_before_save = *state.calculate_before_save.lock().unwrap();
-        if calc_before_save {
-            let _ = crate::calculation::calculate_now(
-                state.clone(),
-                user_files_state.clone(),
-                pivot_state.clone(),
-                pane_control_state.clone(),
-                ribbon_filter_state.clone(),
+        let recalc_before_save = state.calculation_settings.lock().unwrap().recalc_before_save;
+        if recalc_before_save {
+            let _ = crate::calculation::calculate_and_refresh_pivots(
+                &state,
+                &user_files_state,
+                &pivot_state,
+                &pane_control_state,
+                &ribbon_filter_state,
                 None,
+                &std::sync::atomic::AtomicBool::new(false),
+                &mut |_| {},
             );
         }
     }
 
-    // Stamp last_modified BEFORE assembly so the snapshot carries it (the
-    // background auto-recover path deliberately does NOT stamp).
+    // Stamp last_modified (and created, for a workbook that never had one)
+    // BEFORE assembly so the snapshot carries it (the background
+    // auto-recover path deliberately does NOT stamp).
     {
         let mut props = state.workbook_properties.lock().unwrap();
-        props.last_modified = chrono::Utc::now().to_rfc3339();
+        let now = chrono::Utc::now().to_rfc3339();
+        if props.created.is_empty() {
+            props.created = now.clone();
+        }
+        props.last_modified = now;
     }
 
     // Full-fidelity workbook assembly, shared with auto_recover_save (the
@@ -1702,10 +1964,25 @@ pub fn save_file(
         }
     }
 
-    *file_state.current_path.lock().map_err(|e| e.to_string())? = Some(path_buf);
-    *file_state.is_modified.lock().map_err(|e| e.to_string())? = false;
+    file_state.record_save(path_buf)
+}
 
-    Ok(())
+/// Export a values-only xlsx copy of the workbook to `path`: every formula
+/// cell keeps just its computed value, and the categories selected in `strip`
+/// are left out. The open workbook, its file path and dirty state are not
+/// touched.
+#[tauri::command]
+pub fn export_values_only(
+    state: State<AppState>,
+    user_files_state: State<UserFilesState>,
+    path: String,
+    strip: Option<ValuesOnlyStrip>,
+    window: tauri::Window,
+) -> Result<ValuesOnlyReport, String> {
+    crate::security::window_guard::require_label(&window, crate::security::window_guard::MAIN)?;
+    let workbook = build_workbook_for_save(&state, &user_files_state)?;
+    persistence::export_values_only(&workbook, std::path::Path::new(&path), &strip.unwrap_or_default())
+        .map_err(|e| e.to_string())
 }
 
 #[tauri::command]
@@ -1725,9 +2002,44 @@ pub fn open_file(
     // (ENC_NEEDS_PASSWORD / ENC_WRONG_PASSWORD / ENC_CORRUPT) the frontend
     // branches on to prompt and retry.
     password: Option<String>,
+    // Open in read-only mode. Without it the file is still opened read-only
+    // when the OS marks it so or another program holds it.
+    read_only: Option<bool>,
     window: tauri::Window,
 ) -> Result<Vec<CellData>, String> {
     crate::security::window_guard::require_label(&window, crate::security::window_guard::MAIN)?;
+    open_file_internal(
+        &state,
+        &file_state,
+        &user_files_state,
+        &slicer_state,
+        &ribbon_filter_state,
+        &pane_control_state,
+        &script_state,
+        &pivot_state,
+        &bi_state,
+        path,
+        password,
+        read_only,
+    )
+}
+
+/// Body of `open_file`, callable without a window (the headless session).
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn open_file_internal(
+    state: &AppState,
+    file_state: &FileState,
+    user_files_state: &UserFilesState,
+    slicer_state: &crate::slicer::SlicerState,
+    ribbon_filter_state: &crate::ribbon_filter::RibbonFilterState,
+    pane_control_state: &crate::pane_control::PaneControlState,
+    script_state: &crate::scripting::types::ScriptState,
+    pivot_state: &crate::pivot::types::PivotState,
+    bi_state: &crate::bi::types::BiState,
+    path: String,
+    password: Option<String>,
+    read_only: Option<bool>,
+) -> Result<Vec<CellData>, String> {
     let path_buf = PathBuf::from(&path);
 
     // Route by file extension
@@ -1791,6 +2103,11 @@ pub fn open_file(
                     cell.style_index = remap[cell.style_index];
                 }
             }
+            for style_index in grid.col_styles.values_mut().chain(grid.row_styles.values_mut()) {
+                if *style_index < remap.len() {
+                    *style_index = remap[*style_index];
+                }
+            }
 
             all_grids.push(grid);
             all_cw_vec.push(sheet.column_widths.clone());
@@ -1808,10 +2125,6 @@ pub fn open_file(
         // Set active sheet index
         *state.active_sheet.lock().map_err(|e| e.to_string())? = active_idx;
 
-        // Set the active grid (clone from the all_grids vec)
-        let mut grid = state.grid.lock().map_err(|e| e.to_string())?;
-        *grid = all_grids[active_idx].clone();
-
         // Set active sheet dimensions
         let mut col_widths = state.column_widths.lock().map_err(|e| e.to_string())?;
         let mut row_heights = state.row_heights.lock().map_err(|e| e.to_string())?;
@@ -1819,8 +2132,6 @@ pub fn open_file(
         *row_heights = all_rh_vec[active_idx].clone();
 
         // Store per-sheet grids and dimensions
-        // Note: set_active_sheet swaps between grids[i] and state.grid,
-        // so the active sheet slot in grids holds a copy too.
         let mut grids = state.grids.lock().map_err(|e| e.to_string())?;
         *grids = all_grids;
 
@@ -1837,6 +2148,8 @@ pub fn open_file(
         // Clear dependency maps (will be rebuilt on recalculation)
         let mut deps = state.dependents.lock().map_err(|e| e.to_string())?;
         deps.clear();
+        state.volatile_cells.lock().map_err(|e| e.to_string())?.clear();
+        state.error_details.lock().map_err(|e| e.to_string())?.clear();
 
         // Restore table state
         let mut tables = state.tables.lock().map_err(|e| e.to_string())?;
@@ -1907,11 +2220,26 @@ pub fn open_file(
             all_merged.push(sheet_merges);
         }
 
-        // ---- Per-sheet gridlines visibility ----
-        let mut show_gridlines = state.show_gridlines.lock().map_err(|e| e.to_string())?;
-        show_gridlines.clear();
+        // ---- Per-sheet view options ----
+        let mut views = state.sheet_view_options.lock().map_err(|e| e.to_string())?;
+        views.clear();
         for sheet in &workbook.sheets {
-            show_gridlines.push(sheet.show_gridlines);
+            views.push(crate::api_types::SheetViewOptions {
+                zoom: sheet.zoom,
+                show_gridlines: sheet.show_gridlines,
+                show_headings: sheet.show_headings,
+                right_to_left: sheet.right_to_left,
+            });
+        }
+
+        // ---- Per-sheet default sizes ----
+        let mut defaults = state.sheet_defaults.lock().map_err(|e| e.to_string())?;
+        defaults.clear();
+        for sheet in &workbook.sheets {
+            defaults.push(crate::api_types::SheetDefaults {
+                default_col_width: sheet.default_column_width,
+                default_row_height: sheet.default_row_height,
+            });
         }
 
         // ---- Page setups for all sheets ----
@@ -1991,16 +2319,29 @@ pub fn open_file(
             if !sheet.hyperlinks.is_empty() {
                 let mut sheet_links = std::collections::HashMap::new();
                 for h in &sheet.hyperlinks {
-                    sheet_links.insert((h.row, h.col), crate::hyperlinks::Hyperlink {
-                        row: h.row,
-                        col: h.col,
-                        sheet_index: sheet_idx,
-                        link_type: crate::hyperlinks::HyperlinkType::Url,
-                        target: h.target.clone(),
-                        internal_ref: None,
-                        display_text: h.display_text.clone(),
-                        tooltip: h.tooltip.clone(),
-                    });
+                    // A link saved from a HYPERLINK formula stays owned by it
+                    let from_formula = sheet
+                        .cells
+                        .get(&(h.row, h.col))
+                        .and_then(|c| c.formula.as_deref())
+                        .is_some_and(crate::formula_has_hyperlink);
+                    let link = if from_formula {
+                        crate::hyperlinks::formula_hyperlink(h.row, h.col, sheet_idx, &h.target)
+                    } else {
+                        crate::hyperlinks::Hyperlink {
+                            row: h.row,
+                            col: h.col,
+                            sheet_index: sheet_idx,
+                            link_type: crate::hyperlinks::HyperlinkType::Url,
+                            target: h.target.clone(),
+                            internal_ref: None,
+                            display_text: h.display_text.clone(),
+                            tooltip: h.tooltip.clone(),
+                            broken: false,
+                            from_formula: false,
+                        }
+                    };
+                    sheet_links.insert((h.row, h.col), link);
                 }
                 hyperlinks_storage.insert(sheet_idx, sheet_links);
             }
@@ -2076,6 +2417,11 @@ pub fn open_file(
     // the parsed names never reach runtime state — so defined names silently
     // vanished on every reload. Map the persisted SheetId back to this session's
     // sheet index (workbook-scoped names carry no sheet_id).
+    // Saved formulas carry their expansions, so no typed formula survives a load.
+    state.name_ref_sources.lock().unwrap().clear();
+    // The change journal covers one workbook session.
+    state.change_journal.lock().unwrap().clear();
+    let restored_page_setups = state.page_setups.lock().map(|p| p.clone()).unwrap_or_default();
     if let Ok(mut named_ranges) = state.named_ranges.lock() {
         named_ranges.clear();
         for nr in &workbook.named_ranges {
@@ -2093,6 +2439,10 @@ pub fn open_file(
                 },
             );
         }
+        // Rebuild the built-in print names from the restored page setups.
+        for (sheet, setup) in restored_page_setups.iter().enumerate() {
+            crate::commands::print::adopt_page_setup_print_names(&mut named_ranges, sheet, setup);
+        }
     }
 
     // Restore conditional formatting + data validation (per-sheet). Map the
@@ -2151,14 +2501,25 @@ pub fn open_file(
             }
         }
     }
-    if let Ok(mut cell_prot) = state.cell_protection.lock() {
+    if let (Ok(mut cell_prot), Ok(mut range_prot)) =
+        (state.cell_protection.lock(), state.range_protection.lock())
+    {
         cell_prot.clear();
+        range_prot.clear();
         for entry in &workbook.sheet_protections {
             let idx = sheet_id_to_index(&workbook, entry.sheet_id);
             let Some(ref v) = entry.cell_protection else { continue };
             let Some(entries) = v.as_array() else { continue };
             let sheet_map = cell_prot.entry(idx).or_default();
             for e in entries {
+                if e.get("startRow").is_some() {
+                    if let Ok(range) =
+                        serde_json::from_value::<crate::protection::RangeProtection>(e.clone())
+                    {
+                        range_prot.entry(idx).or_default().push(range);
+                    }
+                    continue;
+                }
                 let (Some(row), Some(col)) = (
                     e.get("row").and_then(|v| v.as_u64()),
                     e.get("col").and_then(|v| v.as_u64()),
@@ -2432,19 +2793,14 @@ pub fn open_file(
     // Restore document theme
     *state.theme.lock().map_err(|e| e.to_string())? = workbook.theme;
 
+    // Restore calculation settings
+    *state.calculation_settings.lock().map_err(|e| e.to_string())? =
+        crate::calculation::CalculationSettings::from_saved(&workbook.calc_properties);
+
     // Restore workbook properties
     {
         let mut props = state.workbook_properties.lock().unwrap();
-        *props = crate::api_types::WorkbookProperties {
-            title: workbook.properties.title,
-            author: workbook.properties.author,
-            subject: workbook.properties.subject,
-            description: workbook.properties.description,
-            keywords: workbook.properties.keywords,
-            category: workbook.properties.category,
-            created: workbook.properties.created,
-            last_modified: workbook.properties.last_modified,
-        };
+        *props = crate::api_types::WorkbookProperties::from(workbook.properties);
     }
 
     // Adopt the session encryption state from the file we just opened: an
@@ -2464,10 +2820,17 @@ pub fn open_file(
         }
     }
 
+    let read_only_reason = if read_only.unwrap_or(false) {
+        Some(crate::read_only::ReadOnlyReason::Requested)
+    } else {
+        crate::read_only::detect_read_only(&path_buf)
+    };
+    file_state.set_read_only(read_only_reason)?;
+    file_state.record_disk_state(&path_buf);
     *file_state.current_path.lock().map_err(|e| e.to_string())? = Some(path_buf);
     *file_state.is_modified.lock().map_err(|e| e.to_string())? = false;
 
-    let grid = state.grid.lock().map_err(|e| e.to_string())?;
+    let grid = state.get_active_grid();
     let styles = state.style_registry.lock().map_err(|e| e.to_string())?;
     let locale = state.locale.lock().map_err(|e| e.to_string())?;
     let merged = state.merged_regions.lock().map_err(|e| e.to_string())?;
@@ -2495,6 +2858,7 @@ pub fn open_file(
                 sheet_index: None,
                 rich_text: None,
                 accounting_layout: None,
+                error_detail: None,
             }
         })
         .collect();
@@ -2514,7 +2878,6 @@ pub fn new_file(
 ) -> Result<(), String> {
     crate::security::window_guard::require_label(&window, crate::security::window_guard::MAIN)?;
     {
-        let mut grid = state.grid.lock().map_err(|e| e.to_string())?;
         let mut styles = state.style_registry.lock().map_err(|e| e.to_string())?;
         let mut col_widths = state.column_widths.lock().map_err(|e| e.to_string())?;
         let mut row_heights = state.row_heights.lock().map_err(|e| e.to_string())?;
@@ -2522,11 +2885,11 @@ pub fn new_file(
         let mut tables = state.tables.lock().map_err(|e| e.to_string())?;
         let mut table_names = state.table_names.lock().map_err(|e| e.to_string())?;
 
-        *grid = engine::grid::Grid::new();
         *styles = engine::style::StyleRegistry::new();
         col_widths.clear();
         row_heights.clear();
         deps.clear();
+        state.name_ref_sources.lock().map_err(|e| e.to_string())?.clear();
 
         // Reset per-sheet grids to a single empty sheet
         let mut grids = state.grids.lock().map_err(|e| e.to_string())?;
@@ -2584,10 +2947,15 @@ pub fn new_file(
         all_merged.clear();
         all_merged.push(std::collections::HashSet::new());
 
-        // Reset gridlines visibility
-        let mut show_gridlines = state.show_gridlines.lock().map_err(|e| e.to_string())?;
-        show_gridlines.clear();
-        show_gridlines.push(true);
+        // Reset view options
+        let mut views = state.sheet_view_options.lock().map_err(|e| e.to_string())?;
+        views.clear();
+        views.push(crate::api_types::SheetViewOptions::default());
+
+        // Reset sheet default sizes
+        let mut defaults = state.sheet_defaults.lock().map_err(|e| e.to_string())?;
+        defaults.clear();
+        defaults.push(crate::api_types::SheetDefaults::default());
 
         // Reset page setups
         let mut page_setups = state.page_setups.lock().map_err(|e| e.to_string())?;
@@ -2613,12 +2981,21 @@ pub fn new_file(
     state.cross_sheet_dependents.lock().map_err(|e| e.to_string())?.clear();
     state.cross_sheet_dependencies.lock().map_err(|e| e.to_string())?.clear();
 
-    // Reset undo stack
-    *state.undo_stack.lock().map_err(|e| e.to_string())? = engine::UndoStack::new();
+    // Reset undo stack. The change journal starts over with the new workbook
+    // but stays on if it was on.
+    let tracking = {
+        let mut journal = state.change_journal.lock().map_err(|e| e.to_string())?;
+        journal.clear();
+        journal.is_enabled()
+    };
+    let mut undo_stack = engine::UndoStack::new();
+    undo_stack.set_commit_log(tracking);
+    *state.undo_stack.lock().map_err(|e| e.to_string())? = undo_stack;
 
     // Clear sheet protection and cell protection
     state.sheet_protection.lock().map_err(|e| e.to_string())?.clear();
     state.cell_protection.lock().map_err(|e| e.to_string())?.clear();
+    state.range_protection.lock().map_err(|e| e.to_string())?.clear();
     // Workbook structure protection must reset too — without this a File>New
     // after opening a structure-protected workbook inherits the old password
     // (and, now that protection persists, would even SAVE the old hash into
@@ -2663,6 +3040,8 @@ pub fn new_file(
     state.row_dependents.lock().map_err(|e| e.to_string())?.clear();
     state.column_dependencies.lock().map_err(|e| e.to_string())?.clear();
     state.row_dependencies.lock().map_err(|e| e.to_string())?.clear();
+    state.volatile_cells.lock().map_err(|e| e.to_string())?.clear();
+    state.error_details.lock().map_err(|e| e.to_string())?.clear();
 
     // Reset conditional format ID counter
     *state.next_cf_rule_id.lock().map_err(|e| e.to_string())? = 1;
@@ -2732,6 +3111,10 @@ pub fn new_file(
     // Clear user files
     user_files_state.files.lock().map_err(|e| e.to_string())?.clear();
 
+    // Reset calculation settings
+    *state.calculation_settings.lock().map_err(|e| e.to_string())? =
+        crate::calculation::CalculationSettings::default();
+
     // Reset workbook properties with defaults
     {
         let mut props = state.workbook_properties.lock().unwrap();
@@ -2748,10 +3131,12 @@ pub fn new_file(
     }
 
     *file_state.current_path.lock().map_err(|e| e.to_string())? = None;
+    *file_state.disk_state.lock().map_err(|e| e.to_string())? = None;
     *file_state.is_modified.lock().map_err(|e| e.to_string())? = false;
     // A new (blank) document is never encrypted; drop any session passphrase.
     *file_state.session_password.lock().map_err(|e| e.to_string())? = None;
     *file_state.is_encrypted.lock().map_err(|e| e.to_string())? = false;
+    file_state.set_read_only(None)?;
 
     Ok(())
 }
@@ -2770,6 +3155,100 @@ pub fn is_file_modified(file_state: State<FileState>) -> bool {
     file_state.is_modified.lock().map(|m| *m).unwrap_or(false)
 }
 
+/// Whether another program changed (or removed) the open document on disk
+/// since it was opened or last saved.
+#[tauri::command]
+pub fn check_file_external_changes(file_state: State<FileState>) -> Result<Option<ExternalFileChange>, String> {
+    file_state.external_change()
+}
+
+/// Re-open the current file from disk, discarding the in-memory workbook.
+/// Refuses with `RELOAD_DISCARDS_CHANGES` when there are unsaved edits and
+/// `force` is not set. An encrypted document reuses the session passphrase.
+#[tauri::command]
+pub fn reload_file(
+    state: State<AppState>,
+    file_state: State<FileState>,
+    user_files_state: State<UserFilesState>,
+    slicer_state: State<crate::slicer::SlicerState>,
+    ribbon_filter_state: State<crate::ribbon_filter::RibbonFilterState>,
+    pane_control_state: State<crate::pane_control::PaneControlState>,
+    script_state: State<crate::scripting::types::ScriptState>,
+    pivot_state: State<'_, crate::pivot::types::PivotState>,
+    bi_state: State<'_, crate::bi::types::BiState>,
+    force: Option<bool>,
+    window: tauri::Window,
+) -> Result<Vec<CellData>, String> {
+    crate::security::window_guard::require_label(&window, crate::security::window_guard::MAIN)?;
+    reload_file_internal(
+        &state,
+        &file_state,
+        &user_files_state,
+        &slicer_state,
+        &ribbon_filter_state,
+        &pane_control_state,
+        &script_state,
+        &pivot_state,
+        &bi_state,
+        force,
+    )
+}
+
+/// Body of `reload_file`, callable without a window.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn reload_file_internal(
+    state: &AppState,
+    file_state: &FileState,
+    user_files_state: &UserFilesState,
+    slicer_state: &crate::slicer::SlicerState,
+    ribbon_filter_state: &crate::ribbon_filter::RibbonFilterState,
+    pane_control_state: &crate::pane_control::PaneControlState,
+    script_state: &crate::scripting::types::ScriptState,
+    pivot_state: &crate::pivot::types::PivotState,
+    bi_state: &crate::bi::types::BiState,
+    force: Option<bool>,
+) -> Result<Vec<CellData>, String> {
+    let path = file_state
+        .current_path
+        .lock()
+        .map_err(|e| e.to_string())?
+        .clone()
+        .ok_or_else(|| "No file is open".to_string())?;
+    if *file_state.is_modified.lock().map_err(|e| e.to_string())? && !force.unwrap_or(false) {
+        return Err(RELOAD_DISCARDS_CHANGES.to_string());
+    }
+    let password = file_state
+        .session_password
+        .lock()
+        .map_err(|e| e.to_string())?
+        .as_ref()
+        .map(|pw| pw.as_str().to_string());
+    // A requested read-only mode survives the reload; an attribute or lock is
+    // detected afresh.
+    let read_only = file_state.read_only_reason() == Some(crate::read_only::ReadOnlyReason::Requested);
+    open_file_internal(
+        state,
+        file_state,
+        user_files_state,
+        slicer_state,
+        ribbon_filter_state,
+        pane_control_state,
+        script_state,
+        pivot_state,
+        bi_state,
+        path.to_string_lossy().to_string(),
+        password,
+        Some(read_only),
+    )
+}
+
+/// Whether the open workbook is read-only, and why.
+#[tauri::command]
+pub fn get_file_access_state(file_state: State<FileState>) -> FileAccessState {
+    let reason = file_state.read_only_reason();
+    FileAccessState { read_only: reason.is_some(), reason }
+}
+
 /// Whether the currently-open document is encrypted. Used by the frontend to
 /// toggle the File-menu label between "Encrypt with Password…" and "Remove
 /// Password". Never exposes the passphrase itself.
@@ -2819,11 +3298,20 @@ pub fn get_workbook_properties(
 pub fn set_workbook_properties(
     state: State<AppState>,
     props: crate::api_types::WorkbookProperties,
+) -> crate::api_types::WorkbookProperties {
+    set_workbook_properties_internal(&state, props)
+}
+
+/// Replace the editable document properties. `created` and `last_modified`
+/// keep their stored values: they are maintained by save, not by the caller.
+pub(crate) fn set_workbook_properties_internal(
+    state: &AppState,
+    props: crate::api_types::WorkbookProperties,
 ) -> crate::api_types::WorkbookProperties {
     let mut stored = state.workbook_properties.lock().unwrap();
-    *stored = props;
-    // Update last_modified timestamp
-    stored.last_modified = chrono::Utc::now().to_rfc3339();
+    let created = std::mem::take(&mut stored.created);
+    let last_modified = std::mem::take(&mut stored.last_modified);
+    *stored = crate::api_types::WorkbookProperties { created, last_modified, ..props };
     stored.clone()
 }
 
@@ -3072,25 +3560,14 @@ pub fn get_ai_context(
     let grids = state.grids.lock().map_err(|e| e.to_string())?;
     let sheet_names = state.sheet_names.lock().map_err(|e| e.to_string())?;
     let styles = state.style_registry.lock().map_err(|e| e.to_string())?;
-    let active_grid = state.grid.lock().map_err(|e| e.to_string())?;
-    let active_sheet = *state.active_sheet.lock().map_err(|e| e.to_string())?;
 
-    // Build sheet inputs — use stored grids for non-active sheets, active grid for current
     let mut sheet_inputs: Vec<SheetInput> = Vec::new();
-    for (i, name) in sheet_names.iter().enumerate() {
-        if i == active_sheet {
-            sheet_inputs.push(SheetInput {
-                name,
-                grid: &active_grid,
-                styles: &styles,
-            });
-        } else if let Some(grid) = grids.get(i) {
-            sheet_inputs.push(SheetInput {
-                name,
-                grid,
-                styles: &styles,
-            });
-        }
+    for (name, grid) in sheet_names.iter().zip(grids.iter()) {
+        sheet_inputs.push(SheetInput {
+            name,
+            grid,
+            styles: &styles,
+        });
     }
 
     Ok(serialize_for_ai(&sheet_inputs, &options))
@@ -3167,7 +3644,7 @@ pub fn write_text_file(path: String, content: String, encoding: Option<String>,
 
 /// Collect scripts from ScriptState into SavedScript format for persistence.
 pub(crate) fn collect_scripts_for_save(
-    script_state: &State<crate::scripting::types::ScriptState>,
+    script_state: &crate::scripting::types::ScriptState,
 ) -> Vec<persistence::SavedScript> {
     use crate::scripting::types::ScriptScope;
     let scripts = script_state.workbook_scripts.lock().unwrap();
@@ -3243,7 +3720,7 @@ pub(crate) fn saved_output_to_item(
 
 /// Collect notebooks from ScriptState into SavedNotebook format for persistence.
 pub(crate) fn collect_notebooks_for_save(
-    script_state: &State<crate::scripting::types::ScriptState>,
+    script_state: &crate::scripting::types::ScriptState,
 ) -> Vec<persistence::SavedNotebook> {
     let notebooks = script_state.workbook_notebooks.lock().unwrap();
     notebooks
@@ -3272,7 +3749,7 @@ pub(crate) fn collect_notebooks_for_save(
 /// Restore scripts from saved data into ScriptState.
 fn restore_scripts(
     saved: &[persistence::SavedScript],
-    script_state: &State<crate::scripting::types::ScriptState>,
+    script_state: &crate::scripting::types::ScriptState,
 ) {
     use crate::scripting::types::ScriptScope;
     let mut scripts = script_state.workbook_scripts.lock().unwrap();
@@ -3515,7 +3992,7 @@ pub fn auto_recover_save(
 
 fn restore_notebooks(
     saved: &[persistence::SavedNotebook],
-    script_state: &State<crate::scripting::types::ScriptState>,
+    script_state: &crate::scripting::types::ScriptState,
 ) {
     let mut notebooks = script_state.workbook_notebooks.lock().unwrap();
     notebooks.clear();
@@ -16,6 +16,7 @@ use pivot_engine::{
     drill_down, AggregationType, PivotCache, PivotDefinition, PivotField, PivotId,
     PivotView, ValueField, VALUE_ID_EMPTY,
 };
+use crate::sheet_access::{check_sheet_index, sheet_at};
 use crate::sheets::FreezeConfig;
 use std::time::Instant;
 use tauri::{Emitter, State};
@@ -25,7 +26,7 @@ use tauri::{Emitter, State};
 // ============================================================================
 
 /// Store a computed PivotView for later windowed cell fetching.
-fn store_view(pivot_state: &PivotState, pivot_id: PivotId, view: &PivotView) {
+pub(crate) fn store_view(pivot_state: &PivotState, pivot_id: PivotId, view: &PivotView) {
     pivot_state.views.lock().unwrap().insert(pivot_id, view.clone());
 }
 
@@ -164,7 +165,7 @@ pub fn create_pivot_table(
     pivot_state: State<'_, PivotState>,
     request: CreatePivotRequest,
 ) -> Result<PivotViewResponse, String> {
-    create_pivot_inner(state, pivot_state, request, Vec::new(), Vec::new())
+    create_pivot_inner(&state, &pivot_state, request, Vec::new(), Vec::new())
 }
 
 /// Core pivot creation, optionally with row/value fields configured UP FRONT so
@@ -172,8 +173,8 @@ pub fn create_pivot_table(
 /// tool; create_pivot_table passes empty field lists). Field NAMES are resolved
 /// to source-column indices against the freshly built cache.
 pub fn create_pivot_inner(
-    state: State<AppState>,
-    pivot_state: State<'_, PivotState>,
+    state: &AppState,
+    pivot_state: &PivotState,
     request: CreatePivotRequest,
     row_field_names: Vec<String>,
     value_specs: Vec<(String, AggregationType)>,
@@ -207,14 +208,15 @@ pub fn create_pivot_inner(
         dest_sheet_idx
     );
 
+    // A stale destination index would create the pivot with no sheet to render on.
+    check_sheet_index(state.grids.lock().unwrap().len(), dest_sheet_idx)?;
+
     // Check that destination doesn't overlap an existing pivot table
     check_pivot_overlap(&state, dest_sheet_idx, destination)?;
 
     // Get grid data for source
     let grids = state.grids.lock().unwrap();
-    let grid = grids
-        .get(source_sheet_idx)
-        .ok_or_else(|| format!("Sheet index {} not found", source_sheet_idx))?;
+    let grid = sheet_at(&grids, source_sheet_idx)?;
 
     // Clamp source_end row to the grid's actual data extent.
     // This handles full-column selections (e.g. A:D -> A1:D1048576) by
@@ -249,12 +251,13 @@ pub fn create_pivot_inner(
     );
     definition.source_table_name = request.source_table_name.clone();
 
-    // Store destination sheet in definition
+    // Store destination and source sheets in definition
     {
         let sheet_names = state.sheet_names.lock().unwrap();
         if dest_sheet_idx < sheet_names.len() {
             definition.destination_sheet = Some(sheet_names[dest_sheet_idx].clone());
         }
+        definition.source_sheet = sheet_names.get(source_sheet_idx).cloned();
     }
 
     // C1: resolve requested field NAMES -> source indices and configure the
@@ -309,7 +312,7 @@ pub fn create_pivot_inner(
         }
 
         if let Some(dest_grid) = grids.get_mut(dest_sheet_idx) {
-            let pivot_merges = write_pivot_to_grid(dest_grid, None, &view, destination, &mut styles);
+            let pivot_merges = write_pivot_to_grid(dest_grid, &view, destination, &mut styles);
             log_info!(
                 "PIVOT",
                 "wrote pivot output to grids[{}] at ({},{}) size {}x{}",
@@ -327,18 +330,6 @@ pub fn create_pivot_inner(
                     merged.insert(mr);
                 }
             }
-
-            // IMPORTANT: If dest_sheet is the currently active sheet, sync state.grid
-            let active_sheet = *state.active_sheet.lock().unwrap();
-            if dest_sheet_idx == active_sheet {
-                let mut grid = state.grid.lock().unwrap();
-                // Copy the cells we just wrote to state.grid as well
-                for ((r, c), cell) in dest_grid.cells.iter() {
-                    grid.set_cell(*r, *c, cell.clone());
-                }
-                grid.recalculate_bounds();
-                log_info!("PIVOT", "synced pivot cells to state.grid (active sheet)");
-            }
         } else {
             log_info!("PIVOT", "WARNING: destination sheet {} not found", dest_sheet_idx);
         }
@@ -510,13 +501,6 @@ pub fn undo_pivot_overwrite(
                                             dest_grid.set_cell(sc.row, sc.col, sc.cell.clone());
                                         }
                                     }
-                                    let active_sheet = *state.active_sheet.lock().unwrap();
-                                    if snapshot.dest_sheet_idx == active_sheet {
-                                        let mut grid = state.grid.lock().unwrap();
-                                        for sc in &snapshot.overwritten_cells {
-                                            grid.set_cell(sc.row, sc.col, sc.cell.clone());
-                                        }
-                                    }
                                 }
 
                                 return Ok(());
@@ -1199,18 +1183,7 @@ pub fn delete_pivot_table(state: State<AppState>, pivot_state: State<'_, PivotSt
                 region.end_row,
                 region.end_col,
             );
-            
-            // Sync to state.grid if this is the active sheet
-            let active_sheet = *state.active_sheet.lock().unwrap();
-            if dest_sheet_idx == active_sheet {
-                let mut grid = state.grid.lock().unwrap();
-                for row in region.start_row..=region.end_row {
-                    for col in region.start_col..=region.end_col {
-                        grid.clear_cell(row, col);
-                    }
-                }
-                grid.recalculate_bounds();
-            }
+            dest_grid.recalculate_bounds();
         }
     }
 
@@ -1218,8 +1191,9 @@ pub fn delete_pivot_table(state: State<AppState>, pivot_state: State<'_, PivotSt
     let mut pivot_tables = pivot_state.pivot_tables.lock().unwrap();
     pivot_tables.remove(&pivot_id);
 
-    // Remove cached view
+    // Remove cached view and staleness mark
     pivot_state.views.lock().unwrap().remove(&pivot_id);
+    pivot_state.stale.lock().unwrap().remove(&pivot_id);
 
     // Clear active if this was the active pivot
     let mut active = pivot_state.active_pivot_id.lock().unwrap();
@@ -1589,33 +1563,15 @@ pub async fn refresh_pivot_cache(
             .insert(pivot_id, (old_definition.clone(), old_cache.clone()));
 
         {
-            // Grid pivot: rebuild cache from source grid data
-            let mut source_start = definition.source_start;
-            let mut source_end = definition.source_end;
+            // Grid pivot: rebuild cache from source grid data. A pivot linked
+            // to a table follows the table's current range.
             let has_headers = definition.source_has_headers;
-            let source_table_name = definition.source_table_name.clone();
-
-            // If the pivot is linked to a table, resolve its current range
-            let mut source_sheet_idx: usize = 0; // TODO: resolve from definition.source_sheet
-            if let Some(ref table_name) = source_table_name {
-                let table_names = state.table_names.lock().unwrap();
-                if let Some((sheet_index, table_id)) = table_names.get(&table_name.to_uppercase()) {
-                    let tables = state.tables.lock().unwrap();
-                    if let Some(sheet_tables) = tables.get(sheet_index) {
-                        if let Some(table) = sheet_tables.get(table_id) {
-                            source_start = (table.start_row, table.start_col);
-                            source_end = (table.end_row, table.end_col);
-                            source_sheet_idx = table.sheet_index;
-                            log_info!(
-                                "PIVOT",
-                                "resolved table '{}' -> ({},{})..({},{}) on sheet {}",
-                                table_name, source_start.0, source_start.1,
-                                source_end.0, source_end.1, source_sheet_idx
-                            );
-                        }
-                    }
-                }
-            }
+            let (source_sheet_idx, source_start, mut source_end) = resolve_grid_source(&state, definition);
+            log_info!(
+                "PIVOT",
+                "resolved source -> ({},{})..({},{}) on sheet {}",
+                source_start.0, source_start.1, source_end.0, source_end.1, source_sheet_idx
+            );
 
             drop(pivot_tables);
 
@@ -1666,7 +1622,7 @@ pub async fn refresh_pivot_cache(
     .await
     .map_err(|e| format!("Pivot computation failed: {}", e))?;
 
-    let (view, definition, mut cache, calc_ms) = calc_result;
+    let (view, mut definition, mut cache, calc_ms) = calc_result;
 
     // Check cancellation after computation
     if token.is_cancelled() {
@@ -1689,6 +1645,32 @@ pub async fn refresh_pivot_cache(
     let mut response = view_to_response(&view, &definition, &mut cache);
     let serialize_ms = t1.elapsed().as_secs_f64() * 1000.0;
 
+    // Apply the pivot's on_grow policy before the new output is committed
+    match resolve_pivot_growth(&state, &pivot_state, pivot_id, definition.on_grow, dest_sheet_idx, destination, &view) {
+        Ok(true) => {
+            // Inserted rows/columns may have moved the source; keep the
+            // stored coordinates.
+            let pivot_tables = pivot_state.pivot_tables.lock().unwrap();
+            if let Some((def, _)) = pivot_tables.get(&pivot_id) {
+                definition.source_start = def.source_start;
+                definition.source_end = def.source_end;
+            }
+        }
+        Ok(false) => {}
+        Err(blocked) => {
+            log_info!("PIVOT", "refresh_pivot_cache pivot_id={} blocked: {}", pivot_id, blocked);
+            {
+                let mut pivot_tables = pivot_state.pivot_tables.lock().unwrap();
+                if let Some((def, c)) = pivot_tables.get_mut(&pivot_id) {
+                    *def = old_definition;
+                    *c = old_cache;
+                }
+            }
+            pivot_state.cancellation_tokens.lock().unwrap().remove(&pivot_id);
+            return Err(blocked.to_string());
+        }
+    }
+
     // 5. Put updated definition + cache back
     {
         let mut pivot_tables = pivot_state.pivot_tables.lock().unwrap();
@@ -1730,8 +1712,9 @@ pub async fn refresh_pivot_cache(
     // Recalculate formulas referencing pivot cells
     recalculate_sheet_formulas(&state, &pivot_state, Some((&*pane_control_state, &*ribbon_filter_state)));
 
-    // Clean up cancellation token
+    // Clean up cancellation token; the output now matches its source
     pivot_state.cancellation_tokens.lock().unwrap().remove(&pivot_id);
+    pivot_state.stale.lock().unwrap().remove(&pivot_id);
 
     let total_ms = t_total.elapsed().as_secs_f64() * 1000.0;
 
@@ -2147,6 +2130,8 @@ pub fn get_pivot_table_info(
         allow_multiple_filters_per_field: definition.allow_multiple_filters_per_field,
         enable_data_value_editing: definition.enable_data_value_editing,
         refresh_on_open: definition.refresh_on_open,
+        auto_refresh: definition.auto_refresh,
+        on_grow: definition.on_grow,
         use_custom_sort_lists: definition.use_custom_sort_lists,
         has_headers: definition.source_has_headers,
         source_table_name: definition.source_table_name.clone(),
@@ -2180,6 +2165,12 @@ pub fn update_pivot_properties(
     if let Some(v) = request.refresh_on_open {
         definition.refresh_on_open = v;
     }
+    if let Some(v) = request.auto_refresh {
+        definition.auto_refresh = v;
+    }
+    if let Some(v) = request.on_grow {
+        definition.on_grow = v;
+    }
     if let Some(v) = request.use_custom_sort_lists {
         definition.use_custom_sort_lists = v;
     }
@@ -2196,6 +2187,8 @@ pub fn update_pivot_properties(
         allow_multiple_filters_per_field: definition.allow_multiple_filters_per_field,
         enable_data_value_editing: definition.enable_data_value_editing,
         refresh_on_open: definition.refresh_on_open,
+        auto_refresh: definition.auto_refresh,
+        on_grow: definition.on_grow,
         use_custom_sort_lists: definition.use_custom_sort_lists,
         has_headers: definition.source_has_headers,
         source_table_name: definition.source_table_name.clone(),
@@ -2258,6 +2251,7 @@ pub async fn change_pivot_data_source(
         definition.source_start = source_start;
         definition.source_end = source_end;
         definition.source_range_display = Some(request.source_range.clone());
+        definition.source_sheet = state.sheet_names.lock().unwrap().get(source_sheet_idx).cloned();
         definition.bump_version();
 
         let has_headers = definition.source_has_headers;
@@ -2668,6 +2662,95 @@ pub fn get_pivot_hierarchies(
     })
 }
 
+/// Number of sample values reported per source field.
+const SOURCE_FIELD_SAMPLE_LIMIT: usize = 5;
+
+/// Data rows inspected per column when deciding whether numbers are dates.
+const DATE_FORMAT_PROBE_ROWS: u32 = 1000;
+
+/// Gets field-list metadata for a pivot's source fields: inferred data type,
+/// sample values, distinct/blank counts, and any renamed header cells.
+#[tauri::command]
+pub fn get_pivot_source_fields(
+    state: State<AppState>,
+    pivot_state: State<'_, PivotState>,
+    pivot_id: PivotId,
+) -> Result<PivotSourceFieldsInfo, String> {
+    log_debug!("PIVOT", "get_pivot_source_fields pivot_id={}", pivot_id);
+    get_pivot_source_fields_internal(&state, &pivot_state, pivot_id)
+}
+
+pub(crate) fn get_pivot_source_fields_internal(
+    state: &AppState,
+    pivot_state: &PivotState,
+    pivot_id: PivotId,
+) -> Result<PivotSourceFieldsInfo, String> {
+    let definition = pivot_state
+        .pivot_tables
+        .lock()
+        .unwrap()
+        .get(&pivot_id)
+        .map(|(definition, _)| definition.clone())
+        .ok_or_else(|| format!("Pivot table {} not found", pivot_id))?;
+
+    // The cache only holds raw values, so date columns are recognised from the
+    // number format of the first numeric cell in each source column.
+    let is_bi = pivot_state.bi_metadata.lock().unwrap().contains_key(&pivot_id);
+    let date_columns: Vec<bool> = if is_bi {
+        Vec::new()
+    } else {
+        let (sheet_index, start, end) = resolve_grid_source(state, &definition);
+        let grids = state.grids.lock().unwrap();
+        let styles = state.style_registry.lock().unwrap();
+        match grids.get(sheet_index) {
+            Some(grid) => {
+                let data_start = if definition.source_has_headers { start.0 + 1 } else { start.0 };
+                let probe_end = end.0.min(grid.max_row).min(data_start.saturating_add(DATE_FORMAT_PROBE_ROWS - 1));
+                (start.1..=end.1)
+                    .map(|col| {
+                        (data_start..=probe_end)
+                            .filter_map(|row| grid.get_cell(row, col))
+                            .find(|cell| matches!(cell.value, engine::CellValue::Number(_)))
+                            .map(|cell| engine::is_date_format(&styles.get(cell.style_index).number_format))
+                            .unwrap_or(false)
+                    })
+                    .collect()
+            }
+            None => Vec::new(),
+        }
+    };
+
+    let pivot_tables = pivot_state.pivot_tables.lock().unwrap();
+    let (_, cache) = pivot_tables
+        .get(&pivot_id)
+        .ok_or_else(|| format!("Pivot table {} not found", pivot_id))?;
+
+    let fields = (0..cache.field_count())
+        .filter_map(|i| {
+            let date_formatted = date_columns.get(i).copied().unwrap_or(false);
+            let profile = cache.field_profile(i, SOURCE_FIELD_SAMPLE_LIMIT, date_formatted)?;
+            Some(SourceFieldDetails {
+                index: i,
+                name: cache.field_name(i).unwrap_or_else(|| format!("Field{}", i + 1)),
+                data_type: profile.data_type,
+                samples: profile.samples,
+                distinct_count: profile.distinct_count,
+                blank_count: profile.blank_count,
+                renamed_from: cache
+                    .header_renames
+                    .iter()
+                    .find(|r| r.index == i)
+                    .map(|r| r.original.clone()),
+            })
+        })
+        .collect();
+
+    Ok(PivotSourceFieldsInfo {
+        fields,
+        header_renames: cache.header_renames.clone(),
+    })
+}
+
 /// Adds a field to a hierarchy (row, column, data, or filter).
 #[tauri::command]
 pub fn add_pivot_hierarchy(
@@ -3557,6 +3640,8 @@ pub fn get_all_pivot_tables(
                 allow_multiple_filters_per_field: definition.allow_multiple_filters_per_field,
                 enable_data_value_editing: definition.enable_data_value_editing,
                 refresh_on_open: definition.refresh_on_open,
+                auto_refresh: definition.auto_refresh,
+                on_grow: definition.on_grow,
                 use_custom_sort_lists: definition.use_custom_sort_lists,
                 has_headers: definition.source_has_headers,
                 source_table_name: definition.source_table_name.clone(),
@@ -4518,7 +4603,6 @@ pub async fn drill_through_to_sheet(
     let mut sheet_names = state.sheet_names.lock().unwrap();
     let mut grids = state.grids.lock().unwrap();
     let mut active_sheet = state.active_sheet.lock().unwrap();
-    let mut current_grid = state.grid.lock().unwrap();
     let mut freeze_configs = state.freeze_configs.lock().unwrap();
 
     // Generate a unique sheet name
@@ -4538,12 +4622,6 @@ pub async fn drill_through_to_sheet(
         }
     };
 
-    // Save current active grid
-    let old_index = *active_sheet;
-    if old_index < grids.len() {
-        grids[old_index] = current_grid.clone();
-    }
-
     // Create and populate the new grid
     let mut new_grid = engine::grid::Grid::new();
 
@@ -4555,17 +4633,16 @@ pub async fn drill_through_to_sheet(
     // Write data rows
     for (r, row) in row_data.iter().enumerate() {
         for (c, cv) in row.iter().enumerate() {
-            new_grid.set_cell((r + 1) as u32, c as u32, engine::Cell { ast: None, value: cv.clone(), style_index: 0, rich_text: None });
+            new_grid.set_cell((r + 1) as u32, c as u32, engine::Cell { ast: None, value: cv.clone(), style_index: 0, rich_text: None, quote_prefix: false });
         }
     }
 
     sheet_names.push(sheet_name.clone());
-    grids.push(new_grid.clone());
+    grids.push(new_grid);
     freeze_configs.push(FreezeConfig::default());
 
     let new_index = sheet_names.len() - 1;
     *active_sheet = new_index;
-    *current_grid = new_grid;
 
     Ok(DrillThroughResponse {
         sheet_name,
@@ -5168,6 +5245,8 @@ pub async fn create_pivot_from_bi_model(
         *state.active_sheet.lock().unwrap()
     });
 
+    check_sheet_index(state.grids.lock().unwrap().len(), dest_sheet_idx)?;
+
     // Check that destination doesn't overlap an existing pivot table
     check_pivot_overlap(&state, dest_sheet_idx, destination)?;
 
@@ -5202,15 +5281,8 @@ pub async fn create_pivot_from_bi_model(
         let mut styles = state.style_registry.lock().unwrap();
         let mut grids = state.grids.lock().unwrap();
         if let Some(dest_grid) = grids.get_mut(dest_sheet_idx) {
-            let active_sheet = *state.active_sheet.lock().unwrap();
-            let pivot_merges = if dest_sheet_idx == active_sheet {
-                let mut grid = state.grid.lock().unwrap();
-                let merges = write_pivot_to_grid(dest_grid, Some(&mut grid), &view, destination, &mut styles);
-                grid.recalculate_bounds();
-                merges
-            } else {
-                write_pivot_to_grid(dest_grid, None, &view, destination, &mut styles)
-            };
+            let pivot_merges = write_pivot_to_grid(dest_grid, &view, destination, &mut styles);
+            dest_grid.recalculate_bounds();
 
             // Update merge regions
             if !pivot_merges.is_empty() {
@@ -6821,7 +6893,6 @@ pub fn show_report_filter_pages(
         if let Some(grid) = grids.get_mut(sheet_idx) {
             let _ = crate::pivot::operations::write_pivot_to_grid(
                 grid,
-                None,
                 &view,
                 (0, 0),
                 &mut styles,
@@ -6,6 +6,7 @@ pub mod commands;
 pub mod layout_commands;
 pub mod headless;
 pub mod totals;
+pub mod source_watch;
 
 // Re-export commands so they are easy to access from main.rs
 pub use commands::*;
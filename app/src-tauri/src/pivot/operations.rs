@@ -3,9 +3,10 @@ use std::collections::HashMap;
 use crate::api_types::MergedRegion;
 use crate::commands::styles::parse_number_format;
 use crate::pivot::utils::col_index_to_letter;
+use crate::protected_regions::RegionRect;
 use crate::{log_debug, AppState, ProtectedRegion};
 use crate::pivot::types::PivotState;
-use pivot_engine::{calculate_pivot, PivotCache, PivotDefinition, PivotId, PivotView};
+use pivot_engine::{calculate_pivot, GrowPolicy, PivotCache, PivotDefinition, PivotId, PivotView};
 use engine::{
     Cell, CellStyle, CellValue, StyleRegistry,
     Borders, BorderStyle, BorderLineStyle, Color, Fill, TextAlign, ThemeColor,
@@ -117,33 +118,29 @@ pub(crate) fn build_cache_from_grid(
 
     let data_start_row = if has_headers { start_row + 1 } else { start_row };
 
-    // Extract headers
-    let headers: Vec<String> = if has_headers {
-        (start_col..=end_col)
+    // Extract headers. Blank and duplicate header cells are renamed so every
+    // field name is unique; the renames are kept on the cache for the field list.
+    let (headers, header_renames) = if has_headers {
+        let raw: Vec<String> = (start_col..=end_col)
             .map(|c| {
                 grid.get_cell(start_row, c)
                     .map(|cell| cell.display_value())
-                    .unwrap_or_else(|| col_index_to_letter(c - start_col))
+                    .unwrap_or_default()
             })
-            .collect()
+            .collect();
+        pivot_engine::disambiguate_headers(&raw)
     } else {
-        (0..col_count)
+        let letters = (0..col_count)
             .map(|i| col_index_to_letter(i as u32))
-            .collect()
+            .collect();
+        (letters, Vec::new())
     };
 
     // Find the actual last row with data within this column range.
     // grid.max_row is a global bound — data in other columns may push it
     // beyond what these specific columns contain.
-    let mut effective_end_row = data_start_row.saturating_sub(1);
-    for row in (data_start_row..=end_row).rev() {
-        let has_data = (start_col..=end_col)
-            .any(|col| grid.get_cell(row, col).is_some());
-        if has_data {
-            effective_end_row = row;
-            break;
-        }
-    }
+    let effective_end_row = crate::commands::analysis::last_stored_row(grid, data_start_row, start_col, end_row, end_col)
+        .unwrap_or(data_start_row.saturating_sub(1));
 
     // Create cache
     let mut cache = PivotCache::new(identity::EntityId::ZERO, col_count);
@@ -152,6 +149,7 @@ pub(crate) fn build_cache_from_grid(
     for (i, name) in headers.iter().enumerate() {
         cache.set_field_name(i, name.clone());
     }
+    cache.header_renames = header_renames;
 
     // Add records up to the last row with data
     for row in data_start_row..=effective_end_row {
@@ -484,6 +482,44 @@ pub(crate) fn resolve_dest_sheet_index(state: &AppState, definition: &PivotDefin
     *state.active_sheet.lock().unwrap()
 }
 
+/// Resolves the sheet holding a grid pivot's source range.
+/// Pivots saved before source_sheet was recorded fall back to the destination sheet.
+pub(crate) fn resolve_source_sheet_index(state: &AppState, definition: &PivotDefinition) -> usize {
+    if let Some(ref sheet_name) = definition.source_sheet {
+        let sheet_names = state.sheet_names.lock().unwrap();
+        if let Some(idx) = sheet_names.iter().position(|name| name == sheet_name) {
+            return idx;
+        }
+    }
+    resolve_dest_sheet_index(state, definition)
+}
+
+/// Resolves a grid pivot's current source as (sheet, start, end).
+/// A pivot linked to a table follows the table's current range.
+pub(crate) fn resolve_grid_source(
+    state: &AppState,
+    definition: &PivotDefinition,
+) -> (usize, (u32, u32), (u32, u32)) {
+    if let Some(ref table_name) = definition.source_table_name {
+        let table_names = state.table_names.lock().unwrap();
+        if let Some((sheet_index, table_id)) = table_names.get(&table_name.to_uppercase()) {
+            let tables = state.tables.lock().unwrap();
+            if let Some(table) = tables.get(sheet_index).and_then(|t| t.get(table_id)) {
+                return (
+                    table.sheet_index,
+                    (table.start_row, table.start_col),
+                    (table.end_row, table.end_col),
+                );
+            }
+        }
+    }
+    (
+        resolve_source_sheet_index(state, definition),
+        definition.source_start,
+        definition.source_end,
+    )
+}
+
 /// Clears cells in a pivot region from the grid.
 pub(crate) fn clear_pivot_region_from_grid(
     grid: &mut engine::Grid,
@@ -664,7 +700,6 @@ fn build_pivot_cell_style(
 /// Returns a list of merge regions for cells with col_span/row_span > 1.
 pub(crate) fn write_pivot_to_grid(
     grid: &mut engine::Grid,
-    mut active_grid: Option<&mut engine::Grid>,
     view: &PivotView,
     destination: (u32, u32),
     styles: &mut StyleRegistry,
@@ -673,12 +708,11 @@ pub(crate) fn write_pivot_to_grid(
 
     log_debug!(
         "PIVOT",
-        "write_pivot_to_grid: dest=({},{}) view_size={}x{} dual_write={}",
+        "write_pivot_to_grid: dest=({},{}) view_size={}x{}",
         dest_row,
         dest_col,
         view.row_count,
-        view.col_count,
-        active_grid.is_some()
+        view.col_count
     );
 
     // If view is empty, nothing to write
@@ -696,9 +730,6 @@ pub(crate) fn write_pivot_to_grid(
     // Pre-allocate grid capacity to avoid HashMap resizing during bulk insert.
     let cell_count = view.row_count * view.col_count;
     grid.cells.reserve(cell_count);
-    if let Some(ref mut ag) = active_grid {
-        ag.cells.reserve(cell_count);
-    }
 
     // Iterate through all rows, skipping hidden ones.
     // Use view_row (sequential visible index) for grid positioning so that
@@ -718,7 +749,7 @@ pub(crate) fn write_pivot_to_grid(
             let grid_row = dest_row + row_descriptor.view_row as u32;
             let grid_col = dest_col + col_idx as u32;
 
-            // Determine CellValue and style_index (shared between both grid writes)
+            // Determine CellValue and style_index
             let cell_value = match &pivot_cell.value {
                 pivot_engine::PivotCellValue::Empty => CellValue::Empty,
                 pivot_engine::PivotCellValue::Number(n) => CellValue::Number(*n),
@@ -736,20 +767,13 @@ pub(crate) fn write_pivot_to_grid(
             // Build full cell style (fill, bold, borders, alignment, indent, number format)
             let style_idx = build_pivot_cell_style(pivot_cell, styles, &mut style_cache);
 
-            // Write to both grids using unchecked insert (bounds set once after loop)
-            if let Some(ag) = active_grid.as_deref_mut() {
-                ag.set_cell_unchecked(grid_row, grid_col, Cell {
-                    ast: None,
-                    value: cell_value.clone(),
-                    style_index: style_idx,
-                    rich_text: None,
-                });
-            }
+            // Unchecked insert (bounds set once after loop)
             grid.set_cell_unchecked(grid_row, grid_col, Cell {
                 ast: None,
                 value: cell_value,
                 style_index: style_idx,
                 rich_text: None,
+                quote_prefix: false,
             });
 
             // Collect merge regions for spanned cells
@@ -769,9 +793,6 @@ pub(crate) fn write_pivot_to_grid(
         let end_row = dest_row + view.row_count as u32 - 1;
         let end_col = dest_col + view.col_count as u32 - 1;
         grid.update_bounds(end_row, end_col);
-        if let Some(ag) = active_grid.as_deref_mut() {
-            ag.update_bounds(end_row, end_col);
-        }
     }
 
     log_debug!(
@@ -810,6 +831,23 @@ pub(crate) fn check_pivot_overlap(
     Ok(())
 }
 
+/// The rectangle a view occupies at `destination`: the full view, or the
+/// reserved placeholder area for an empty pivot.
+pub(crate) fn pivot_output_rect(destination: (u32, u32), view: &PivotView) -> RegionRect {
+    let (dest_row, dest_col) = destination;
+    let (rows, cols) = if view.row_count > 0 && view.col_count > 0 {
+        (view.row_count as u32, view.col_count as u32)
+    } else {
+        (EMPTY_PIVOT_ROWS, EMPTY_PIVOT_COLS)
+    };
+    RegionRect {
+        start_row: dest_row,
+        start_col: dest_col,
+        end_row: dest_row + rows - 1,
+        end_col: dest_col + cols - 1,
+    }
+}
+
 /// Updates the pivot region tracking for a pivot table.
 pub(crate) fn update_pivot_region(
     state: &AppState,
@@ -820,54 +858,211 @@ pub(crate) fn update_pivot_region(
 ) {
     let mut regions = state.protected_regions.lock().unwrap();
 
-    // Remove any existing region for this pivot
-    regions.retain(|r| !(r.region_type == "pivot" && r.owner_id == pivot_id));
+    let id = format!("pivot-{}", pivot_id);
+    let rect = pivot_output_rect(destination, view);
+
+    // Resize the existing region in place so its id stays stable for the
+    // frontend across refreshes; register it on first render or when the
+    // pivot moved to another sheet.
+    let resized = regions.iter().any(|r| r.id == id && r.sheet_index == sheet_index)
+        && regions.resize(&id, rect).is_ok();
+    if !resized {
+        regions.upsert(ProtectedRegion {
+            id,
+            region_type: "pivot".to_string(),
+            owner_id: pivot_id,
+            sheet_index,
+            start_row: rect.start_row,
+            start_col: rect.start_col,
+            end_row: rect.end_row,
+            end_col: rect.end_col,
+        });
+    }
 
-    let (dest_row, dest_col) = destination;
-    
-    // Calculate region size - use actual view size or minimum reserved size for empty pivots
-    let (end_row, end_col) = if view.row_count > 0 && view.col_count > 0 {
-        // Count all rows in the view (headers + data)
-        let total_rows = view.row_count as u32;
-        let total_cols = view.col_count as u32;
-        (
-            dest_row + total_rows.saturating_sub(1),
-            dest_col + total_cols.saturating_sub(1),
-        )
-    } else {
-        // Empty pivot - reserve minimum space for placeholder
-        (
-            dest_row + EMPTY_PIVOT_ROWS - 1,
-            dest_col + EMPTY_PIVOT_COLS - 1,
-        )
-    };
-    
-    regions.push(ProtectedRegion {
-        id: format!("pivot-{}", pivot_id),
-        region_type: "pivot".to_string(),
-        owner_id: pivot_id,
-        sheet_index,
-        start_row: dest_row,
-        start_col: dest_col,
-        end_row,
-        end_col,
-    });
-    
     log_debug!(
         "PIVOT",
         "updated pivot region: id={} sheet={} ({},{}) to ({},{}) empty={}",
         pivot_id,
         sheet_index,
-        dest_row,
-        dest_col,
-        end_row,
-        end_col,
+        rect.start_row,
+        rect.start_col,
+        rect.end_row,
+        rect.end_col,
         view.row_count == 0
     );
+    drop(regions);
+
+    let mut changes = crate::change_events::ChangeBatch::new();
+    changes.push_region(sheet_index, "pivot");
+    changes.flush();
+}
+
+// ============================================================================
+// OUTPUT GROWTH
+// ============================================================================
+
+/// Cells and protected regions that stand in the way of a pivot whose output
+/// grew past its previous extent.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct PivotGrowthBlocked {
+    pub sheet_index: usize,
+    /// Non-empty cells in the grown area, (row, col), sorted.
+    pub cells: Vec<(u32, u32)>,
+    /// Ids of other protected regions overlapping the grown area.
+    pub regions: Vec<String>,
+}
+
+/// How many blocking cells the error message spells out.
+const GROWTH_BLOCKED_CELLS_SHOWN: usize = 5;
+
+impl std::fmt::Display for PivotGrowthBlocked {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut parts = Vec::new();
+        if !self.cells.is_empty() {
+            let mut cells: Vec<String> = self
+                .cells
+                .iter()
+                .take(GROWTH_BLOCKED_CELLS_SHOWN)
+                .map(|&(row, col)| format!("{}{}", col_index_to_letter(col), row + 1))
+                .collect();
+            if self.cells.len() > GROWTH_BLOCKED_CELLS_SHOWN {
+                cells.push(format!("and {} more", self.cells.len() - GROWTH_BLOCKED_CELLS_SHOWN));
+            }
+            parts.push(format!("non-empty cells {}", cells.join(", ")));
+        }
+        if !self.regions.is_empty() {
+            parts.push(format!("regions {}", self.regions.join(", ")));
+        }
+        write!(f, "Pivot table output would grow into {}", parts.join(" and "))
+    }
+}
+
+/// Rectangles the output covers at `new` but did not at `old`: the rows
+/// below the old output, then the columns right of it (over the old rows).
+fn growth_bands(old: RegionRect, new: RegionRect) -> Vec<RegionRect> {
+    let mut bands = Vec::new();
+    if new.end_row > old.end_row {
+        bands.push(RegionRect {
+            start_row: old.end_row + 1,
+            start_col: new.start_col,
+            end_row: new.end_row,
+            end_col: new.end_col,
+        });
+    }
+    if new.end_col > old.end_col {
+        bands.push(RegionRect {
+            start_row: new.start_row,
+            start_col: old.end_col + 1,
+            end_row: new.end_row.min(old.end_row),
+            end_col: new.end_col,
+        });
+    }
+    bands
+}
+
+/// Collects what occupies the grown area of a pivot's output.
+fn find_growth_obstructions(
+    state: &AppState,
+    region_id: &str,
+    sheet_index: usize,
+    bands: &[RegionRect],
+) -> PivotGrowthBlocked {
+    let mut blocked = PivotGrowthBlocked { sheet_index, cells: Vec::new(), regions: Vec::new() };
+    for band in bands {
+        // A lookup failure leaves the band unchecked, like the overwrite path.
+        let Ok(conflicts) = state.check_region_conflicts(sheet_index, *band, Some(region_id)) else {
+            continue;
+        };
+        blocked.cells.extend(conflicts.non_empty_cells);
+        for region in conflicts.overlapping_regions {
+            if !blocked.regions.contains(&region.id) {
+                blocked.regions.push(region.id);
+            }
+        }
+    }
+    blocked.cells.sort_unstable();
+    blocked.cells.dedup();
+    blocked
+}
+
+/// Applies the pivot's `on_grow` policy before a view is written. When the
+/// new output extends below or right of the current region into non-empty
+/// cells or other protected regions:
+/// - `Overwrite` lets the write go ahead;
+/// - `InsertCells` inserts whole rows below / columns right of the old output
+///   so the obstructions (and every reference to them) move out of the way;
+/// - `Fail` refuses with the blocking cells.
+///
+/// Returns whether rows or columns were inserted (which may have shifted the
+/// stored definition's source). Acquires every lock itself: callers must hold
+/// none. Shrinking needs nothing here; `update_pivot_in_grid` clears the
+/// whole old region before writing.
+pub(crate) fn resolve_pivot_growth(
+    state: &AppState,
+    pivot_state: &PivotState,
+    pivot_id: PivotId,
+    policy: GrowPolicy,
+    dest_sheet_idx: usize,
+    destination: (u32, u32),
+    view: &PivotView,
+) -> Result<bool, PivotGrowthBlocked> {
+    if policy == GrowPolicy::Overwrite {
+        return Ok(false);
+    }
+    // First render or a move: there is no previous extent to grow from.
+    let Some(old_region) = get_pivot_region(state, pivot_id) else { return Ok(false) };
+    let old = RegionRect::of(&old_region);
+    if old_region.sheet_index != dest_sheet_idx || (old.start_row, old.start_col) != destination {
+        return Ok(false);
+    }
+    let new = pivot_output_rect(destination, view);
+    let bands = growth_bands(old, new);
+    if bands.is_empty() {
+        return Ok(false);
+    }
+
+    let blocked = find_growth_obstructions(state, &old_region.id, dest_sheet_idx, &bands);
+    if blocked.cells.is_empty() && blocked.regions.is_empty() {
+        return Ok(false);
+    }
+    if policy == GrowPolicy::Fail {
+        return Err(blocked);
+    }
+
+    // Row/column insertion works on the active sheet only.
+    if *state.active_sheet.lock().unwrap() != dest_sheet_idx {
+        return Err(blocked);
+    }
+    if new.end_row > old.end_row {
+        crate::commands::structure::insert_rows_internal(
+            state,
+            pivot_state,
+            old.end_row + 1,
+            new.end_row - old.end_row,
+        )
+        .map_err(|_| blocked.clone())?;
+    }
+    if new.end_col > old.end_col {
+        crate::commands::structure::insert_columns_internal(
+            state,
+            pivot_state,
+            old.end_col + 1,
+            new.end_col - old.end_col,
+        )
+        .map_err(|_| blocked.clone())?;
+    }
+
+    // Anything still in the way (e.g. a merge or region spanning the
+    // insertion line) blocks the refresh.
+    let remaining = find_growth_obstructions(state, &old_region.id, dest_sheet_idx, &bands);
+    if remaining.cells.is_empty() && remaining.regions.is_empty() {
+        Ok(true)
+    } else {
+        Err(remaining)
+    }
 }
 
 /// Clears the old pivot region and writes the new view to the grid.
-/// Also syncs to state.grid if needed.
 pub(crate) fn update_pivot_in_grid(
     state: &AppState,
     pivot_id: PivotId,
@@ -894,34 +1089,8 @@ pub(crate) fn update_pivot_in_grid(
             }
         }
 
-        // Check if this is the active sheet — if so, write to both grids in one pass
-        let active_sheet = *state.active_sheet.lock().unwrap();
-        let is_active = dest_sheet_idx == active_sheet;
-
-        let pivot_merges = if is_active {
-            let mut active_grid = state.grid.lock().unwrap();
-
-            // Clear old region from active grid too
-            if let Some(ref region) = old_region {
-                if region.sheet_index == dest_sheet_idx {
-                    active_grid.clear_region(
-                        region.start_row,
-                        region.start_col,
-                        region.end_row,
-                        region.end_col,
-                    );
-                }
-            }
-
-            // Single-pass write to both grids (eliminates second iteration + clones)
-            let merges = write_pivot_to_grid(dest_grid, Some(&mut active_grid), view, destination, &mut styles);
-            active_grid.recalculate_bounds();
-            log_debug!("PIVOT", "wrote pivot to both grids in single pass (active sheet)");
-            merges
-        } else {
-            // Not the active sheet — write to sheet grid only
-            write_pivot_to_grid(dest_grid, None, view, destination, &mut styles)
-        };
+        let pivot_merges = write_pivot_to_grid(dest_grid, view, destination, &mut styles);
+        dest_grid.recalculate_bounds();
         let (dest_row, dest_col) = destination;
         let new_end_row = dest_row + view.row_count.max(1) as u32 - 1;
         let new_end_col = dest_col + view.col_count.max(1) as u32 - 1;
@@ -1416,11 +1585,8 @@ pub(crate) fn recalculate_sheet_formulas(
     control_states: Option<(&crate::pane_control::PaneControlState, &crate::ribbon_filter::RibbonFilterState)>,
 ) {
     // Only recalculate in automatic mode
-    {
-        let calc_mode = state.calculation_mode.lock().unwrap();
-        if *calc_mode != "automatic" {
-            return;
-        }
+    if !state.calculation_settings.lock().unwrap().mode.is_automatic() {
+        return;
     }
 
     // GET.CONTROLVALUE snapshot: built BEFORE the grid locks below (canonical
@@ -1429,7 +1595,6 @@ pub(crate) fn recalculate_sheet_formulas(
     let control_values =
         crate::control_values::build_control_values_from_states(state, control_states);
 
-    let mut grid = state.grid.lock().unwrap();
     let mut grids = state.grids.lock().unwrap();
     let sheet_names = state.sheet_names.lock().unwrap();
     let active_sheet = *state.active_sheet.lock().unwrap();
@@ -1442,7 +1607,7 @@ pub(crate) fn recalculate_sheet_formulas(
     };
 
     // Collect all cells with formulas on the active sheet
-    let formula_cells: Vec<_> = grid
+    let formula_cells: Vec<_> = grids[active_sheet]
         .cells
         .iter()
         .filter_map(|(&(row, col), cell)| {
@@ -1513,15 +1678,10 @@ pub(crate) fn recalculate_sheet_formulas(
             Err(_) => CellValue::Error(engine::CellError::Value),
         };
 
-        if let Some(cell) = grid.get_cell(row, col) {
+        if let Some(cell) = grids[active_sheet].get_cell(row, col) {
             let mut updated = cell.clone();
             updated.value = result;
-            grid.set_cell(row, col, updated.clone());
-
-            // Keep grids vector in sync
-            if active_sheet < grids.len() {
-                grids[active_sheet].set_cell(row, col, updated);
-            }
+            grids[active_sheet].set_cell(row, col, updated);
         }
     }
 }
\ No newline at end of file
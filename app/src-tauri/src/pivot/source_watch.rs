@@ -0,0 +1,257 @@
+//! FILENAME: app/src-tauri/src/pivot/source_watch.rs
+//! PURPOSE: Keeps grid pivots in step with their source cells.
+//! CONTEXT: A pivot's cache is a snapshot of its source range, so edits inside
+//! the range leave the output stale until an explicit refresh. Commands that
+//! change cells hand their updated-cell list to `source_cells_changed`, which
+//! looks the coordinates up in a per-sheet interval index of pivot sources,
+//! marks the hit pivots stale and — in automatic calculation mode — refreshes
+//! the ones with `auto_refresh` set. The UI badges the rest via
+//! `get_stale_objects`. BI pivots are fed by a model, not grid cells, and are
+//! never registered.
+
+use std::collections::{HashMap, HashSet};
+
+use pivot_engine::PivotId;
+use tauri::State;
+
+use crate::api_types::CellData;
+use crate::pivot::commands::store_view;
+use crate::pivot::operations::{
+    build_cache_from_grid, finalize_pivot_update, resolve_dest_sheet_index, resolve_grid_source,
+    resolve_pivot_growth, safe_calculate_pivot,
+};
+use crate::pivot::types::PivotState;
+use crate::{log_debug, log_info, AppState};
+
+type ControlStates<'a> = Option<(&'a crate::pane_control::PaneControlState, &'a crate::ribbon_filter::RibbonFilterState)>;
+
+/// A registered source rectangle (inclusive bounds).
+#[derive(Debug, Clone, Copy)]
+struct WatchedSource {
+    start_row: u32,
+    start_col: u32,
+    end_row: u32,
+    end_col: u32,
+    pivot_id: PivotId,
+}
+
+/// The sources on one sheet, sorted by start row. `reach[i]` is the largest
+/// end row among `entries[..=i]`, so a lookup walks back from the last entry
+/// starting at or above the row only while an earlier one can still cover it.
+#[derive(Debug, Default)]
+struct SheetSources {
+    entries: Vec<WatchedSource>,
+    reach: Vec<u32>,
+}
+
+/// Per-sheet interval index over the source ranges of the grid pivots.
+#[derive(Debug, Default)]
+pub(crate) struct SourceIndex {
+    sheets: HashMap<usize, SheetSources>,
+}
+
+impl SourceIndex {
+    /// Registers the current source of every grid pivot. Table-linked
+    /// sources are resolved to the table's current range.
+    pub(crate) fn build(state: &AppState, pivot_state: &PivotState) -> Self {
+        let definitions: Vec<_> = {
+            let pivot_tables = pivot_state.pivot_tables.lock().unwrap();
+            let bi_metadata = pivot_state.bi_metadata.lock().unwrap();
+            pivot_tables
+                .iter()
+                .filter(|(id, _)| !bi_metadata.contains_key(id))
+                .map(|(_, (definition, _))| definition.clone())
+                .collect()
+        };
+
+        let mut sheets: HashMap<usize, SheetSources> = HashMap::new();
+        for definition in &definitions {
+            let (sheet, start, end) = resolve_grid_source(state, definition);
+            sheets.entry(sheet).or_default().entries.push(WatchedSource {
+                start_row: start.0,
+                start_col: start.1,
+                end_row: end.0,
+                end_col: end.1,
+                pivot_id: definition.id,
+            });
+        }
+        for sources in sheets.values_mut() {
+            sources.entries.sort_by_key(|e| e.start_row);
+            let mut reach = 0;
+            sources.reach = sources
+                .entries
+                .iter()
+                .map(|e| {
+                    reach = reach.max(e.end_row);
+                    reach
+                })
+                .collect();
+        }
+        SourceIndex { sheets }
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.sheets.is_empty()
+    }
+
+    /// Adds the pivots whose source contains (sheet, row, col) to `hits`.
+    pub(crate) fn collect_hits(&self, sheet: usize, row: u32, col: u32, hits: &mut HashSet<PivotId>) {
+        let Some(sources) = self.sheets.get(&sheet) else { return };
+        let upto = sources.entries.partition_point(|e| e.start_row <= row);
+        for i in (0..upto).rev() {
+            if sources.reach[i] < row {
+                break;
+            }
+            let e = &sources.entries[i];
+            if e.end_row >= row && e.start_col <= col && col <= e.end_col {
+                hits.insert(e.pivot_id);
+            }
+        }
+    }
+}
+
+/// Rebuilds a grid pivot's cache from its source and rewrites its output
+/// (the synchronous counterpart of `refresh_pivot_cache`). Acquires every
+/// lock itself: callers must hold none.
+pub(crate) fn refresh_grid_pivot(
+    state: &AppState,
+    pivot_state: &PivotState,
+    pivot_id: PivotId,
+    control_states: ControlStates<'_>,
+) -> Result<(), String> {
+    let (definition, dest_sheet_idx) = {
+        let pivot_tables = pivot_state.pivot_tables.lock().unwrap();
+        let (definition, _) = pivot_tables
+            .get(&pivot_id)
+            .ok_or_else(|| format!("Pivot table {} not found", pivot_id))?;
+        (definition.clone(), resolve_dest_sheet_index(state, definition))
+    };
+
+    let (source_sheet_idx, source_start, mut source_end) = resolve_grid_source(state, &definition);
+    let mut fresh_cache = {
+        let grids = state.grids.lock().unwrap();
+        let grid = grids
+            .get(source_sheet_idx)
+            .ok_or_else(|| "Source sheet not found".to_string())?;
+        // Clamp source_end row to grid's actual data extent (handles full-column refs)
+        if source_end.0 > grid.max_row {
+            source_end.0 = grid.max_row;
+        }
+        build_cache_from_grid(grid, source_start, source_end, definition.source_has_headers)?.0
+    };
+
+    // Lay the new output out before committing anything, so a refusal leaves
+    // the pivot (and its stale mark) as it was.
+    let mut candidate = definition;
+    candidate.source_start = source_start;
+    candidate.source_end = source_end;
+    let view = safe_calculate_pivot(&candidate, &mut fresh_cache);
+    let destination = candidate.destination;
+    let inserted = resolve_pivot_growth(
+        state,
+        pivot_state,
+        pivot_id,
+        candidate.on_grow,
+        dest_sheet_idx,
+        destination,
+        &view,
+    )
+    .map_err(|blocked| blocked.to_string())?;
+
+    {
+        let mut pivot_tables = pivot_state.pivot_tables.lock().unwrap();
+        let (definition, cache) = pivot_tables
+            .get_mut(&pivot_id)
+            .ok_or_else(|| format!("Pivot table {} not found", pivot_id))?;
+        *cache = fresh_cache;
+        // Inserted rows/columns already moved the stored source where needed.
+        if !inserted {
+            definition.source_start = source_start;
+            definition.source_end = source_end;
+        }
+        definition.bump_version();
+    }
+
+    store_view(pivot_state, pivot_id, &view);
+    finalize_pivot_update(state, pivot_state, pivot_id, dest_sheet_idx, destination, &view, control_states);
+    pivot_state.stale.lock().unwrap().remove(&pivot_id);
+    Ok(())
+}
+
+/// Refreshes every stale pivot that has `auto_refresh` set, regardless of
+/// the calculation mode (Calculate Now calls this directly). Returns the ids
+/// refreshed, in id order. Acquires every lock itself: callers must hold none.
+pub(crate) fn refresh_stale_auto_pivots(
+    state: &AppState,
+    pivot_state: &PivotState,
+    control_states: ControlStates<'_>,
+) -> Vec<PivotId> {
+    let stale: Vec<PivotId> = pivot_state.stale.lock().unwrap().iter().copied().collect();
+    let mut due: Vec<PivotId> = {
+        let pivot_tables = pivot_state.pivot_tables.lock().unwrap();
+        stale
+            .into_iter()
+            .filter(|id| pivot_tables.get(id).is_some_and(|(d, _)| d.auto_refresh))
+            .collect()
+    };
+    due.sort();
+
+    due.retain(|&pivot_id| match refresh_grid_pivot(state, pivot_state, pivot_id, control_states) {
+        Ok(()) => true,
+        Err(e) => {
+            log_debug!("PIVOT", "auto-refresh of pivot {} failed: {}", pivot_id, e);
+            false
+        }
+    });
+    due
+}
+
+/// Marks the grid pivots whose source contains one of `cells` stale and, in
+/// automatic calculation mode, refreshes the ones with `auto_refresh` set.
+/// `cells` is a command's updated-cell list (no sheet index = active sheet).
+/// Returns the ids refreshed. Acquires every lock itself: callers must hold none.
+pub(crate) fn source_cells_changed(
+    state: &AppState,
+    pivot_state: &PivotState,
+    control_states: ControlStates<'_>,
+    cells: &[CellData],
+) -> Vec<PivotId> {
+    if cells.is_empty() {
+        return Vec::new();
+    }
+    let index = SourceIndex::build(state, pivot_state);
+    if index.is_empty() {
+        return Vec::new();
+    }
+
+    let active_sheet = *state.active_sheet.lock().unwrap();
+    let mut hits = HashSet::new();
+    for cell in cells {
+        index.collect_hits(cell.sheet_index.unwrap_or(active_sheet), cell.row, cell.col, &mut hits);
+    }
+    if hits.is_empty() {
+        return Vec::new();
+    }
+    log_info!("PIVOT", "source cells changed: {} pivot(s) now stale", hits.len());
+    pivot_state.stale.lock().unwrap().extend(hits);
+
+    if !state.calculation_settings.lock().unwrap().mode.is_automatic() {
+        return Vec::new();
+    }
+    refresh_stale_auto_pivots(state, pivot_state, control_states)
+}
+
+/// Returns the pivots whose source changed since their last refresh, so the
+/// UI can badge them.
+#[tauri::command]
+pub fn get_stale_objects(pivot_state: State<'_, PivotState>) -> Vec<PivotId> {
+    get_stale_objects_internal(&pivot_state)
+}
+
+pub(crate) fn get_stale_objects_internal(pivot_state: &PivotState) -> Vec<PivotId> {
+    let stale: Vec<PivotId> = pivot_state.stale.lock().unwrap().iter().copied().collect();
+    let pivot_tables = pivot_state.pivot_tables.lock().unwrap();
+    let mut ids: Vec<PivotId> = stale.into_iter().filter(|id| pivot_tables.contains_key(id)).collect();
+    ids.sort();
+    ids
+}
@@ -772,6 +772,10 @@ pub struct UpdatePivotPropertiesRequest {
     pub enable_data_value_editing: Option<bool>,
     /// Refresh when workbook opens
     pub refresh_on_open: Option<bool>,
+    /// Refresh when source cells change
+    pub auto_refresh: Option<bool>,
+    /// What a refresh does when the output grows into occupied cells
+    pub on_grow: Option<pivot_engine::GrowPolicy>,
     /// Use custom sort lists
     pub use_custom_sort_lists: Option<bool>,
 }
@@ -979,6 +983,10 @@ pub struct PivotTableInfo {
     pub enable_data_value_editing: bool,
     /// Refresh on workbook open
     pub refresh_on_open: bool,
+    /// Refresh when source cells change
+    pub auto_refresh: bool,
+    /// What a refresh does when the output grows into occupied cells
+    pub on_grow: pivot_engine::GrowPolicy,
     /// Use custom sort lists
     pub use_custom_sort_lists: bool,
     /// Source has headers
@@ -1212,6 +1220,32 @@ pub struct SourceFieldInfo {
     pub table_name: Option<String>,
 }
 
+/// Field-list metadata for one source field: inferred type and cheap column stats.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SourceFieldDetails {
+    pub index: usize,
+    pub name: String,
+    pub data_type: pivot_engine::SourceFieldType,
+    /// The first few distinct values, in source order.
+    pub samples: Vec<String>,
+    pub distinct_count: usize,
+    pub blank_count: usize,
+    /// Original header text when the field was renamed (blank or duplicate header).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub renamed_from: Option<String>,
+}
+
+/// Response for `get_pivot_source_fields`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PivotSourceFieldsInfo {
+    pub fields: Vec<SourceFieldDetails>,
+    /// Header cells that were blank or duplicated and had to be renamed.
+    /// Empty when the source header row is healthy.
+    pub header_renames: Vec<pivot_engine::HeaderRename>,
+}
+
 /// Pivot region data for rendering
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -1366,7 +1400,7 @@ pub struct DrillThroughResponse {
     pub col_count: usize,
 }
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::sync::Mutex;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
@@ -1423,6 +1457,8 @@ pub struct PivotState {
     pub cancellation_tokens: Mutex<HashMap<PivotId, CancellationToken>>,
     /// Previous states for revert after user-cancel (saved before async operations)
     pub previous_states: Mutex<HashMap<PivotId, (PivotDefinition, PivotCache)>>,
+    /// Grid pivots whose source cells changed since their last refresh
+    pub stale: Mutex<HashSet<PivotId>>,
 }
 
 impl PivotState {
@@ -1434,6 +1470,7 @@ impl PivotState {
             views: Mutex::new(HashMap::new()),
             cancellation_tokens: Mutex::new(HashMap::new()),
             previous_states: Mutex::new(HashMap::new()),
+            stale: Mutex::new(HashSet::new()),
         }
     }
 }
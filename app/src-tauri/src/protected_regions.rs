@@ -0,0 +1,304 @@
+//! FILENAME: app/src-tauri/src/protected_regions.rs
+//! PURPOSE: Storage and management API for extension-owned protected regions
+//! (pivot, report, bi, ...).
+//! CONTEXT: `ProtectedRegionStore` keeps the regions in registration order and
+//! derefs to that `Vec`, so extensions keep pushing/retaining as before. A
+//! per-sheet index (sorted by start row, with a running max of end rows) is
+//! rebuilt lazily after any mutable access, so cell lookups and overlap
+//! queries stay cheap with thousands of regions. Owners whose output grows
+//! call `resize`/`upsert` instead of delete + re-add, which keeps the region's
+//! id and position stable for the frontend.
+
+use std::collections::HashMap;
+use std::ops::{Deref, DerefMut};
+
+use serde::{Deserialize, Serialize};
+use tauri::State;
+
+use crate::{AppState, ProtectedRegion};
+use crate::sheet_access::sheet_at;
+
+/// Inclusive cell rectangle, 0-based.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RegionRect {
+    pub start_row: u32,
+    pub start_col: u32,
+    pub end_row: u32,
+    pub end_col: u32,
+}
+
+impl RegionRect {
+    /// Rectangle with its corners ordered (start <= end).
+    pub fn normalized(self) -> Self {
+        RegionRect {
+            start_row: self.start_row.min(self.end_row),
+            start_col: self.start_col.min(self.end_col),
+            end_row: self.start_row.max(self.end_row),
+            end_col: self.start_col.max(self.end_col),
+        }
+    }
+
+    pub fn of(region: &ProtectedRegion) -> Self {
+        RegionRect {
+            start_row: region.start_row,
+            start_col: region.start_col,
+            end_row: region.end_row,
+            end_col: region.end_col,
+        }
+    }
+
+    pub fn contains(&self, row: u32, col: u32) -> bool {
+        row >= self.start_row && row <= self.end_row && col >= self.start_col && col <= self.end_col
+    }
+
+    pub fn intersects(&self, other: &RegionRect) -> bool {
+        !(self.end_row < other.start_row
+            || self.start_row > other.end_row
+            || self.end_col < other.start_col
+            || self.start_col > other.end_col)
+    }
+}
+
+/// Result of a pre-flight `check_region_conflicts`.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RegionConflicts {
+    pub overlapping_regions: Vec<ProtectedRegion>,
+    /// Non-empty cells inside the rectangle, (row, col), sorted.
+    pub non_empty_cells: Vec<(u32, u32)>,
+}
+
+/// Regions of one sheet, as positions into the store's `Vec`.
+#[derive(Debug, Default)]
+struct SheetIndex {
+    /// Sorted by start_row.
+    order: Vec<usize>,
+    /// max_end[i] = max end_row over order[..=i]; bounds the backward scan.
+    max_end: Vec<u32>,
+}
+
+#[derive(Debug, Default)]
+pub struct ProtectedRegionStore {
+    regions: Vec<ProtectedRegion>,
+    /// None when stale (any mutable access invalidates it).
+    index: Option<HashMap<usize, SheetIndex>>,
+}
+
+impl Deref for ProtectedRegionStore {
+    type Target = Vec<ProtectedRegion>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.regions
+    }
+}
+
+impl DerefMut for ProtectedRegionStore {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.index = None;
+        &mut self.regions
+    }
+}
+
+impl ProtectedRegionStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn index(&mut self) -> &HashMap<usize, SheetIndex> {
+        let regions = &self.regions;
+        self.index.get_or_insert_with(|| {
+            let mut by_sheet: HashMap<usize, SheetIndex> = HashMap::new();
+            for (i, r) in regions.iter().enumerate() {
+                by_sheet.entry(r.sheet_index).or_default().order.push(i);
+            }
+            for sheet in by_sheet.values_mut() {
+                sheet.order.sort_by_key(|&i| (regions[i].start_row, i));
+                let mut max = 0;
+                sheet.max_end = sheet
+                    .order
+                    .iter()
+                    .map(|&i| {
+                        max = max.max(regions[i].end_row);
+                        max
+                    })
+                    .collect();
+            }
+            by_sheet
+        })
+    }
+
+    /// Positions of the regions on `sheet` intersecting `rect`, in
+    /// registration order.
+    fn positions_intersecting(&mut self, sheet: usize, rect: &RegionRect) -> Vec<usize> {
+        self.index();
+        let (Some(index), regions) = (&self.index, &self.regions) else {
+            return Vec::new();
+        };
+        let Some(sheet_index) = index.get(&sheet) else {
+            return Vec::new();
+        };
+        // Candidates start at or above the rect's last row; walk back while
+        // some earlier region still reaches down to its first row.
+        let end = sheet_index.order.partition_point(|&i| regions[i].start_row <= rect.end_row);
+        let mut hits = Vec::new();
+        for k in (0..end).rev() {
+            if sheet_index.max_end[k] < rect.start_row {
+                break;
+            }
+            let i = sheet_index.order[k];
+            if RegionRect::of(&regions[i]).intersects(rect) {
+                hits.push(i);
+            }
+        }
+        hits.sort_unstable();
+        hits
+    }
+
+    /// First registered region on `sheet` containing the cell.
+    pub fn region_at(&mut self, sheet: usize, row: u32, col: u32) -> Option<&ProtectedRegion> {
+        let cell = RegionRect { start_row: row, start_col: col, end_row: row, end_col: col };
+        let first = self.positions_intersecting(sheet, &cell).into_iter().next()?;
+        self.regions.get(first)
+    }
+
+    /// Regions on `sheet` intersecting `rect`, in registration order.
+    pub fn overlapping(&mut self, sheet: usize, rect: RegionRect) -> Vec<&ProtectedRegion> {
+        let positions = self.positions_intersecting(sheet, &rect.normalized());
+        positions.into_iter().map(|i| &self.regions[i]).collect()
+    }
+
+    /// Give the region `id` new bounds; its id and position are unchanged.
+    pub fn resize(&mut self, id: &str, rect: RegionRect) -> Result<ProtectedRegion, String> {
+        let rect = rect.normalized();
+        let region = self
+            .iter_mut()
+            .find(|r| r.id == id)
+            .ok_or_else(|| format!("Protected region '{}' not found", id))?;
+        region.start_row = rect.start_row;
+        region.start_col = rect.start_col;
+        region.end_row = rect.end_row;
+        region.end_col = rect.end_col;
+        Ok(region.clone())
+    }
+
+    /// Shift the region `id` by whole rows/columns.
+    pub fn translate(&mut self, id: &str, delta_rows: i32, delta_cols: i32) -> Result<ProtectedRegion, String> {
+        let current = self
+            .regions
+            .iter()
+            .find(|r| r.id == id)
+            .map(RegionRect::of)
+            .ok_or_else(|| format!("Protected region '{}' not found", id))?;
+        let shift = |v: u32, d: i32| u32::try_from(v as i64 + d as i64).ok();
+        let moved = match (
+            shift(current.start_row, delta_rows),
+            shift(current.start_col, delta_cols),
+            shift(current.end_row, delta_rows),
+            shift(current.end_col, delta_cols),
+        ) {
+            (Some(start_row), Some(start_col), Some(end_row), Some(end_col)) => {
+                RegionRect { start_row, start_col, end_row, end_col }
+            }
+            _ => return Err(format!("Cannot move protected region '{}' off the sheet", id)),
+        };
+        self.resize(id, moved)
+    }
+
+    /// Register `region`, or resize the existing region with the same id in
+    /// place (moving it to `region.sheet_index` if that changed).
+    pub fn upsert(&mut self, region: ProtectedRegion) {
+        match self.iter_mut().find(|r| r.id == region.id) {
+            Some(existing) => *existing = region,
+            None => self.push(region),
+        }
+    }
+}
+
+impl AppState {
+    /// Would `rect` on `sheet` overlap another protected region or existing
+    /// content? `exclude_id` skips a region (and the cells it currently
+    /// covers), for owners checking their own resize.
+    pub fn check_region_conflicts(
+        &self,
+        sheet: usize,
+        rect: RegionRect,
+        exclude_id: Option<&str>,
+    ) -> Result<RegionConflicts, String> {
+        let rect = rect.normalized();
+        let (overlapping_regions, own_rect) = {
+            let mut regions = self.protected_regions.lock().map_err(|e| e.to_string())?;
+            let own = exclude_id
+                .and_then(|id| regions.iter().find(|r| r.id == id))
+                .map(RegionRect::of);
+            let overlapping: Vec<ProtectedRegion> = regions
+                .overlapping(sheet, rect)
+                .into_iter()
+                .filter(|r| Some(r.id.as_str()) != exclude_id)
+                .cloned()
+                .collect();
+            (overlapping, own)
+        };
+
+        let grids = self.grids.lock().map_err(|e| e.to_string())?;
+        let grid = sheet_at(&grids, sheet)?;
+        let mut non_empty_cells: Vec<(u32, u32)> = grid
+            .cells
+            .iter()
+            .filter(|(&(row, col), cell)| {
+                rect.contains(row, col)
+                    && !own_rect.is_some_and(|own| own.contains(row, col))
+                    && (cell.ast.is_some() || !matches!(cell.value, engine::CellValue::Empty))
+            })
+            .map(|(&pos, _)| pos)
+            .collect();
+        non_empty_cells.sort_unstable();
+
+        Ok(RegionConflicts { overlapping_regions, non_empty_cells })
+    }
+
+    /// Resize the protected region `id`, keeping its id.
+    pub fn update_protected_region(&self, id: &str, rect: RegionRect) -> Result<ProtectedRegion, String> {
+        self.protected_regions.lock().map_err(|e| e.to_string())?.resize(id, rect)
+    }
+
+    /// Move the protected region `id` by whole rows/columns.
+    pub fn move_protected_region(&self, id: &str, delta_rows: i32, delta_cols: i32) -> Result<ProtectedRegion, String> {
+        self.protected_regions
+            .lock()
+            .map_err(|e| e.to_string())?
+            .translate(id, delta_rows, delta_cols)
+    }
+}
+
+/// Pre-flight check before an extension renders into `rect`.
+#[tauri::command]
+pub fn check_region_conflicts(
+    state: State<AppState>,
+    sheet_index: usize,
+    rect: RegionRect,
+    exclude_id: Option<String>,
+) -> Result<RegionConflicts, String> {
+    state.check_region_conflicts(sheet_index, rect, exclude_id.as_deref())
+}
+
+/// Resize a protected region in place (id stays stable).
+#[tauri::command]
+pub fn update_protected_region(
+    state: State<AppState>,
+    id: String,
+    new_rect: RegionRect,
+) -> Result<ProtectedRegion, String> {
+    state.update_protected_region(&id, new_rect)
+}
+
+/// Move a protected region by whole rows/columns.
+#[tauri::command]
+pub fn move_protected_region(
+    state: State<AppState>,
+    id: String,
+    delta_rows: i32,
+    delta_cols: i32,
+) -> Result<ProtectedRegion, String> {
+    state.move_protected_region(&id, delta_rows, delta_cols)
+}
@@ -1,10 +1,16 @@
 //! FILENAME: app/src-tauri/src/protection.rs
 //! PURPOSE: Sheet and cell protection feature - password protection, locked cells, allow-edit ranges.
+//! CONTEXT: Cell locked/formula-hidden flags live apart from the sheet's
+//! protected state: per-cell entries plus range records, resolved cell entry
+//! -> range records -> default (locked). They only take effect while the
+//! sheet is protected.
 
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use tauri::State;
 
+use crate::api_types::CellData;
+use crate::sheet_access::check_sheet_index;
 use crate::AppState;
 
 // ============================================================================
@@ -229,6 +235,173 @@ pub type ProtectionStorage = HashMap<usize, SheetProtection>;
 /// Only stores non-default values (cells that differ from default locked state)
 pub type CellProtectionStorage = HashMap<usize, HashMap<(u32, u32), CellProtection>>;
 
+/// Locked/hidden flags applied to a block of cells in one record. `None`
+/// leaves that flag to earlier records or the default.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RangeProtection {
+    pub start_row: u32,
+    pub start_col: u32,
+    pub end_row: u32,
+    pub end_col: u32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub locked: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub formula_hidden: Option<bool>,
+}
+
+impl RangeProtection {
+    /// Check if a cell is within this range
+    pub fn contains(&self, row: u32, col: u32) -> bool {
+        row >= self.start_row
+            && row <= self.end_row
+            && col >= self.start_col
+            && col <= self.end_col
+    }
+
+    fn within(&self, start_row: u32, start_col: u32, end_row: u32, end_col: u32) -> bool {
+        self.start_row >= start_row
+            && self.end_row <= end_row
+            && self.start_col >= start_col
+            && self.end_col <= end_col
+    }
+}
+
+/// Storage for range-level protection: sheet_index -> records, oldest first.
+pub type RangeProtectionStorage = HashMap<usize, Vec<RangeProtection>>;
+
+/// Effective protection of a cell: its own entry, else the range records
+/// covering it (later records win per flag), else the default (locked).
+pub fn effective_cell_protection(
+    cells: Option<&HashMap<(u32, u32), CellProtection>>,
+    ranges: &[RangeProtection],
+    row: u32,
+    col: u32,
+) -> CellProtection {
+    if let Some(cp) = cells.and_then(|c| c.get(&(row, col))) {
+        return *cp;
+    }
+    let mut cp = CellProtection::default_locked();
+    for range in ranges.iter().filter(|r| r.contains(row, col)) {
+        if let Some(locked) = range.locked {
+            cp.locked = locked;
+        }
+        if let Some(hidden) = range.formula_hidden {
+            cp.formula_hidden = hidden;
+        }
+    }
+    cp
+}
+
+/// Apply locked/hidden flags to a rectangle of one sheet. A single cell gets
+/// its own entry; a block is stored as one range record. Cell entries inside
+/// the block are updated too, and superseded flags of older records inside it
+/// are dropped, so the newest setting always wins and records don't pile up.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn apply_protection(
+    cells: &mut CellProtectionStorage,
+    ranges: &mut RangeProtectionStorage,
+    sheet: usize,
+    (start_row, start_col): (u32, u32),
+    (end_row, end_col): (u32, u32),
+    locked: Option<bool>,
+    formula_hidden: Option<bool>,
+) {
+    let (min_row, max_row) = (start_row.min(end_row), start_row.max(end_row));
+    let (min_col, max_col) = (start_col.min(end_col), start_col.max(end_col));
+    let sheet_cells = cells.entry(sheet).or_default();
+    let sheet_ranges = ranges.entry(sheet).or_default();
+
+    let patch = |cp: &mut CellProtection| {
+        if let Some(locked) = locked {
+            cp.locked = locked;
+        }
+        if let Some(hidden) = formula_hidden {
+            cp.formula_hidden = hidden;
+        }
+    };
+
+    if min_row == max_row && min_col == max_col {
+        let mut cp = effective_cell_protection(Some(&*sheet_cells), sheet_ranges, min_row, min_col);
+        patch(&mut cp);
+        sheet_cells.insert((min_row, min_col), cp);
+        return;
+    }
+
+    for ((row, col), cp) in sheet_cells.iter_mut() {
+        if (min_row..=max_row).contains(row) && (min_col..=max_col).contains(col) {
+            patch(cp);
+        }
+    }
+    for range in sheet_ranges.iter_mut() {
+        if range.within(min_row, min_col, max_row, max_col) {
+            if locked.is_some() {
+                range.locked = None;
+            }
+            if formula_hidden.is_some() {
+                range.formula_hidden = None;
+            }
+        }
+    }
+    sheet_ranges.retain(|r| r.locked.is_some() || r.formula_hidden.is_some());
+    if locked.is_some() || formula_hidden.is_some() {
+        sheet_ranges.push(RangeProtection {
+            start_row: min_row,
+            start_col: min_col,
+            end_row: max_row,
+            end_col: max_col,
+            locked,
+            formula_hidden,
+        });
+    }
+}
+
+/// Read access to which formulas are hidden on one sheet. Exists only while
+/// the sheet is protected: hidden formulas are visible otherwise.
+pub(crate) struct HiddenFormulaFilter<'a> {
+    cells: std::sync::MutexGuard<'a, CellProtectionStorage>,
+    ranges: std::sync::MutexGuard<'a, RangeProtectionStorage>,
+    sheet: usize,
+}
+
+impl<'a> HiddenFormulaFilter<'a> {
+    /// Locks the protection stores for `sheet`, or returns None if the sheet
+    /// is unprotected. Take it before the grid locks (feature stores first).
+    pub(crate) fn for_sheet(state: &'a AppState, sheet: usize) -> Option<Self> {
+        let protected = state
+            .sheet_protection
+            .lock()
+            .unwrap()
+            .get(&sheet)
+            .is_some_and(|p| p.protected);
+        if !protected {
+            return None;
+        }
+        Some(Self {
+            cells: state.cell_protection.lock().unwrap(),
+            ranges: state.range_protection.lock().unwrap(),
+            sheet,
+        })
+    }
+
+    pub(crate) fn is_hidden(&self, row: u32, col: u32) -> bool {
+        let ranges = self.ranges.get(&self.sheet).map(Vec::as_slice).unwrap_or(&[]);
+        effective_cell_protection(self.cells.get(&self.sheet), ranges, row, col).formula_hidden
+    }
+
+    /// Drops the formula text (and error detail) of hidden-formula cells;
+    /// values stay.
+    pub(crate) fn redact(&self, cells: &mut [CellData]) {
+        for cell in cells.iter_mut() {
+            // The error detail can quote the formula's names and arguments.
+            if (cell.formula.is_some() || cell.error_detail.is_some()) && self.is_hidden(cell.row, cell.col) {
+                cell.formula = None;
+                cell.error_detail = None;
+            }
+        }
+    }
+}
+
 // ============================================================================
 // RESULT TYPES
 // ============================================================================
@@ -330,6 +503,23 @@ pub struct SetCellProtectionParams {
     pub formula_hidden: Option<bool>,
 }
 
+/// Parameters for setting range protection on any sheet
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SetRangeProtectionParams {
+    /// Target sheet (default: the active sheet)
+    #[serde(default)]
+    pub sheet_index: Option<usize>,
+    pub start_row: u32,
+    pub start_col: u32,
+    pub end_row: u32,
+    pub end_col: u32,
+    #[serde(default)]
+    pub locked: Option<bool>,
+    #[serde(default, alias = "hidden")]
+    pub formula_hidden: Option<bool>,
+}
+
 // ============================================================================
 // PASSWORD HASHING
 // ============================================================================
@@ -575,9 +765,14 @@ pub fn can_edit_cell(
     row: u32,
     col: u32,
 ) -> ProtectionCheckResult {
+    can_edit_cell_internal(&state, row, col)
+}
+
+pub(crate) fn can_edit_cell_internal(state: &AppState, row: u32, col: u32) -> ProtectionCheckResult {
     let active_sheet = *state.active_sheet.lock().unwrap();
     let protection_storage = state.sheet_protection.lock().unwrap();
     let cell_protection_storage = state.cell_protection.lock().unwrap();
+    let range_protection_storage = state.range_protection.lock().unwrap();
 
     let protection = match protection_storage.get(&active_sheet) {
         Some(p) => p,
@@ -606,12 +801,14 @@ pub fn can_edit_cell(
         }
     }
 
-    // Check cell lock status (default is locked)
-    let is_locked = cell_protection_storage
-        .get(&active_sheet)
-        .and_then(|sheet| sheet.get(&(row, col)))
-        .map(|cp| cp.locked)
-        .unwrap_or(true); // Default is locked
+    // Check cell lock status: cell entry, then range records, then default (locked)
+    let is_locked = effective_cell_protection(
+        cell_protection_storage.get(&active_sheet),
+        range_protection_storage.get(&active_sheet).map(Vec::as_slice).unwrap_or(&[]),
+        row,
+        col,
+    )
+    .locked;
 
     if is_locked {
         ProtectionCheckResult {
@@ -658,43 +855,65 @@ pub fn can_perform_action(
     }
 }
 
-/// Set cell protection for a range
+/// Set cell protection for a range of the current sheet
 #[tauri::command]
 pub fn set_cell_protection(
     state: State<AppState>,
     params: SetCellProtectionParams,
 ) -> ProtectionResult {
     let active_sheet = *state.active_sheet.lock().unwrap();
-    let mut cell_protection_storage = state.cell_protection.lock().unwrap();
-
-    let sheet_protection = cell_protection_storage
-        .entry(active_sheet)
-        .or_insert_with(HashMap::new);
-
-    let min_row = params.start_row.min(params.end_row);
-    let max_row = params.start_row.max(params.end_row);
-    let min_col = params.start_col.min(params.end_col);
-    let max_col = params.start_col.max(params.end_col);
+    set_range_protection_internal(
+        &state,
+        SetRangeProtectionParams {
+            sheet_index: Some(active_sheet),
+            start_row: params.start_row,
+            start_col: params.start_col,
+            end_row: params.end_row,
+            end_col: params.end_col,
+            locked: params.locked,
+            formula_hidden: params.formula_hidden,
+        },
+    )
+}
 
-    for row in min_row..=max_row {
-        for col in min_col..=max_col {
-            let current = sheet_protection
-                .entry((row, col))
-                .or_insert(CellProtection::default_locked());
+/// Mark a block of cells locked/unlocked and/or formula-hidden in one call,
+/// independent of whether the sheet is currently protected.
+#[tauri::command]
+pub fn set_range_protection(
+    state: State<AppState>,
+    params: SetRangeProtectionParams,
+) -> ProtectionResult {
+    set_range_protection_internal(&state, params)
+}
 
-            if let Some(locked) = params.locked {
-                current.locked = locked;
-            }
-            if let Some(hidden) = params.formula_hidden {
-                current.formula_hidden = hidden;
-            }
-        }
+pub(crate) fn set_range_protection_internal(
+    state: &AppState,
+    params: SetRangeProtectionParams,
+) -> ProtectionResult {
+    let sheet = match params.sheet_index {
+        Some(idx) => idx,
+        None => *state.active_sheet.lock().unwrap(),
+    };
+    if let Err(e) = check_sheet_index(state.sheet_names.lock().unwrap().len(), sheet) {
+        return ProtectionResult::err(e.to_string());
     }
 
+    let mut cell_protection_storage = state.cell_protection.lock().unwrap();
+    let mut range_protection_storage = state.range_protection.lock().unwrap();
+    apply_protection(
+        &mut cell_protection_storage,
+        &mut range_protection_storage,
+        sheet,
+        (params.start_row, params.start_col),
+        (params.end_row, params.end_col),
+        params.locked,
+        params.formula_hidden,
+    );
+
     ProtectionResult::ok_empty()
 }
 
-/// Get cell protection for a specific cell
+/// Get the effective cell protection for a specific cell
 #[tauri::command]
 pub fn get_cell_protection(
     state: State<AppState>,
@@ -703,12 +922,14 @@ pub fn get_cell_protection(
 ) -> CellProtection {
     let active_sheet = *state.active_sheet.lock().unwrap();
     let cell_protection_storage = state.cell_protection.lock().unwrap();
-
-    cell_protection_storage
-        .get(&active_sheet)
-        .and_then(|sheet| sheet.get(&(row, col)))
-        .cloned()
-        .unwrap_or_else(CellProtection::default_locked)
+    let range_protection_storage = state.range_protection.lock().unwrap();
+
+    effective_cell_protection(
+        cell_protection_storage.get(&active_sheet),
+        range_protection_storage.get(&active_sheet).map(Vec::as_slice).unwrap_or(&[]),
+        row,
+        col,
+    )
 }
 
 /// Verify password for an allow-edit range
@@ -1012,4 +1233,38 @@ mod tests {
         assert!(wb.password_hash.is_none());
         assert!(wb.password_salt.is_none());
     }
+
+    #[test]
+    fn test_range_protection_precedence() {
+        let mut cells = CellProtectionStorage::new();
+        let mut ranges = RangeProtectionStorage::new();
+        apply_protection(&mut cells, &mut ranges, 0, (0, 0), (999, 9), Some(false), None);
+        apply_protection(&mut cells, &mut ranges, 0, (5, 5), (5, 5), Some(true), None);
+        apply_protection(&mut cells, &mut ranges, 0, (0, 0), (9, 0), None, Some(true));
+
+        let at = |row, col| effective_cell_protection(cells.get(&0), &ranges[&0], row, col);
+        // Cell override beats the range; ranges stack flag by flag.
+        assert!(at(5, 5).locked);
+        assert_eq!(at(3, 0), CellProtection { locked: false, formula_hidden: true });
+        assert_eq!(at(500, 9), CellProtection { locked: false, formula_hidden: false });
+        // Outside every record: Excel's default.
+        assert_eq!(at(1000, 0), CellProtection::default_locked());
+        // A block is one record, not a cell per address.
+        assert_eq!(cells[&0].len(), 1);
+        assert_eq!(ranges[&0].len(), 2);
+    }
+
+    #[test]
+    fn test_range_protection_supersedes_contained_ranges() {
+        let mut cells = CellProtectionStorage::new();
+        let mut ranges = RangeProtectionStorage::new();
+        apply_protection(&mut cells, &mut ranges, 0, (2, 2), (4, 4), Some(false), None);
+        apply_protection(&mut cells, &mut ranges, 0, (3, 3), (3, 3), Some(false), None);
+        apply_protection(&mut cells, &mut ranges, 0, (0, 0), (9, 9), Some(true), None);
+
+        // The inner range is dropped and the cell entry patched in place.
+        assert_eq!(ranges[&0].len(), 1);
+        assert!(cells[&0][&(3, 3)].locked);
+        assert!(effective_cell_protection(cells.get(&0), &ranges[&0], 2, 2).locked);
+    }
 }
@@ -0,0 +1,528 @@
+//! FILENAME: app/src-tauri/src/read_only.rs
+//! PURPOSE: Read-only workbook mode. A workbook opened with
+//! `open_file { readOnly: true }`, marked read-only by the OS, or locked by
+//! another process may be looked at, filtered and recalculated, but not edited
+//! or saved over. Every IPC command is classified here; the invoke handler
+//! rejects `Edit` commands with the `WORKBOOK_READ_ONLY` sentinel while the
+//! mode is on.
+//! CONTEXT: Policy —
+//!   * `View`: filters (AutoFilter, advanced filter, slicer/timeline/ribbon
+//!     selections, pivot filters, sorting and expand/collapse), outline
+//!     expand/collapse, freeze/split panes, gridlines and sheet navigation.
+//!     These change what is shown, not what the file holds, so they stay
+//!     available (the same latitude Excel gives a read-only workbook).
+//!   * `Calculation`: recalculation and data refresh (pivot caches, BI
+//!     connections, control dependents) plus calculation settings. Results
+//!     are derived from the file's own contents.
+//!   * `Edit`: anything that changes cells, formatting, structure or workbook
+//!     objects (sheets, tables, names, comments, notes, charts, pivots'
+//!     layout, BI model, scripts, protection, page setup, ...). Blocked.
+//!   * Everything else (getters, app-level settings, logging, export,
+//!     publishing, registries, MCP) is `Read` and unaffected.
+//! `save_file` is not listed: it refuses to overwrite the open path itself,
+//! while Save As to a new path succeeds and leaves read-only mode.
+
+use std::path::Path;
+
+use serde::Serialize;
+use tauri::Manager;
+
+use crate::persistence::FileState;
+
+/// Sentinel error returned by every `Edit` command (and by `save_file` onto
+/// the open path) while the workbook is read-only. The frontend offers Save As.
+pub const WORKBOOK_READ_ONLY: &str = "WORKBOOK_READ_ONLY";
+
+/// Why the open workbook is read-only.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ReadOnlyReason {
+    /// The user asked for it (`open_file { readOnly: true }`).
+    Requested,
+    /// The file carries the OS read-only attribute.
+    ReadOnlyAttribute,
+    /// The file could not be opened for writing (another program holds it).
+    LockedByAnotherProcess,
+}
+
+/// What a command may do to the open workbook.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CommandAccess {
+    /// Reads state or touches only app-level settings.
+    Read,
+    /// Changes what is displayed (filters, panes, outline state).
+    View,
+    /// Recalculates or refreshes derived results.
+    Calculation,
+    /// Changes workbook content. Rejected while read-only.
+    Edit,
+}
+
+/// Commands that change workbook content.
+pub const EDIT_COMMANDS: &[&str] = &[
+    // Cells
+    "update_cell",
+    "update_cells_batch",
+    "update_cell_on_sheets",
+    "clear_cell",
+    "clear_range",
+    "clear_range_with_options",
+    "clear_range_on_sheets",
+    "sort_range",
+    "fill_range",
+    "remove_duplicates",
+    "replace_all",
+    "replace_single",
+    "relocate_cell_references",
+    "remove_inspection_items",
+    "repair_workbook",
+    "apply_formula_extension",
+    "set_precision_as_displayed",
+    "undo",
+    "redo",
+    // Formatting and dimensions
+    "set_column_width",
+    "set_row_height",
+    "set_default_row_height",
+    "set_default_column_width",
+    "set_sheet_defaults",
+    "set_cell_style",
+    "set_cell_rich_text",
+    "apply_formatting",
+    "apply_formatting_to_sheets",
+    "apply_border_preset",
+    "merge_cells",
+    "unmerge_cells",
+    "set_document_theme",
+    "create_named_style",
+    "delete_named_style",
+    "apply_named_style",
+    // Structure and sheets
+    "insert_rows",
+    "insert_columns",
+    "delete_rows",
+    "delete_columns",
+    "add_sheet",
+    "delete_sheet",
+    "rename_sheet",
+    "move_sheet",
+    "copy_sheet",
+    "create_history_sheet",
+    "hide_sheet",
+    "unhide_sheet",
+    "set_tab_color",
+    "update_protected_region",
+    "move_protected_region",
+    // Workbook metadata
+    "set_workbook_properties",
+    "set_extension_data",
+    "set_extension_data_undoable",
+    "create_virtual_file",
+    "create_virtual_folder",
+    "delete_virtual_file",
+    "rename_virtual_file",
+    "set_object_json",
+    // Pivot tables and reports
+    "create_pivot_table",
+    "update_pivot_fields",
+    "revert_pivot_operation",
+    "undo_pivot_overwrite",
+    "delete_pivot_table",
+    "relocate_pivot",
+    "update_pivot_properties",
+    "change_pivot_data_source",
+    "update_pivot_layout",
+    "add_pivot_hierarchy",
+    "remove_pivot_hierarchy",
+    "move_pivot_field",
+    "set_pivot_aggregation",
+    "set_pivot_number_format",
+    "group_pivot_field",
+    "create_manual_group",
+    "ungroup_pivot_field",
+    "drill_through_to_sheet",
+    "set_pivot_drill_behavior",
+    "set_pivot_perspective",
+    "create_pivot_from_bi_model",
+    "update_bi_pivot_fields",
+    "set_bi_lookup_columns",
+    "show_report_filter_pages",
+    "add_calculated_field",
+    "update_calculated_field",
+    "remove_calculated_field",
+    "add_calculated_item",
+    "remove_calculated_item",
+    "save_pivot_layout",
+    "delete_pivot_layout",
+    "create_report",
+    "delete_report",
+    "restore_report",
+    // Names
+    "create_named_range",
+    "update_named_range",
+    "delete_named_range",
+    "rename_named_range",
+    "apply_names_to_formulas",
+    // BI connections and model
+    "bi_create_connection",
+    "bi_delete_connection",
+    "bi_update_connection",
+    "bi_bind_table",
+    "bi_insert_result",
+    "bi_set_calculated_measures",
+    "bi_model_upsert_measure",
+    "bi_model_delete_measure",
+    "bi_model_update_table",
+    "bi_model_update_column",
+    "bi_model_upsert_model_column",
+    "bi_model_delete_calc_column",
+    "bi_model_upsert_relationship",
+    "bi_model_delete_relationship",
+    "bi_model_upsert_hierarchy",
+    "bi_model_delete_hierarchy",
+    "bi_model_upsert_kpi",
+    "bi_model_delete_kpi",
+    "bi_model_upsert_role",
+    "bi_model_delete_role",
+    "bi_model_upsert_perspective",
+    "bi_model_delete_perspective",
+    "bi_model_upsert_culture",
+    "bi_model_delete_culture",
+    "bi_model_upsert_calc_group",
+    "bi_model_delete_calc_group",
+    "bi_model_upsert_global_variable",
+    "bi_model_delete_global_variable",
+    "bi_model_materialize_calculated_table",
+    "bi_model_upsert_table_variable",
+    "bi_model_delete_table_variable",
+    "bi_model_upsert_script_function",
+    "bi_model_delete_script_function",
+    "bi_model_upsert_context",
+    "bi_model_delete_context",
+    "bi_model_upsert_context_column",
+    "bi_model_delete_context_column",
+    "bi_model_set_date_table",
+    "bi_model_set_default_lookup_resolution",
+    "bi_model_set_metadata",
+    "bi_model_set_table_storage_mode",
+    "bi_model_set_table_refresh",
+    "bi_model_undo",
+    "bi_model_redo",
+    "bi_model_import_tables",
+    "bi_model_create_blank",
+    "bi_model_import_from_file",
+    "bi_model_import_sql_source",
+    "bi_model_delete_table",
+    "bi_model_upsert_source",
+    "bi_model_delete_source",
+    "bi_model_set_table_source_binding",
+    "bi_model_upsert_writeback_column",
+    "bi_model_delete_writeback_column",
+    "bi_import_writeback_tables",
+    "bi_writeback_set_value",
+    // Validation, comments, notes, hyperlinks
+    "set_data_validation",
+    "clear_data_validation",
+    "add_comment",
+    "update_comment",
+    "delete_comment",
+    "add_reply",
+    "update_reply",
+    "delete_reply",
+    "move_comment",
+    "resolve_comment",
+    "clear_all_comments",
+    "clear_comments_in_range",
+    "add_note",
+    "update_note",
+    "delete_note",
+    "resize_note",
+    "toggle_note_visibility",
+    "move_note",
+    "clear_all_notes",
+    "clear_notes_in_range",
+    "convert_note_to_comment",
+    "add_hyperlink",
+    "update_hyperlink",
+    "remove_hyperlink",
+    "clear_hyperlinks_in_range",
+    "move_hyperlink",
+    // Protection
+    "protect_sheet",
+    "unprotect_sheet",
+    "update_protection_options",
+    "add_allow_edit_range",
+    "remove_allow_edit_range",
+    "set_cell_protection",
+    "set_range_protection",
+    "protect_workbook",
+    "unprotect_workbook",
+    // Outline
+    "group_rows",
+    "ungroup_rows",
+    "group_columns",
+    "ungroup_columns",
+    "set_outline_settings",
+    "clear_outline",
+    // Conditional formats
+    "add_conditional_format",
+    "update_conditional_format",
+    "delete_conditional_format",
+    "reorder_conditional_formats",
+    "clear_conditional_formats_in_range",
+    // Tables
+    "create_table",
+    "delete_table",
+    "rename_table",
+    "update_table_style",
+    "add_table_column",
+    "remove_table_column",
+    "rename_table_column",
+    "set_totals_row_function",
+    "set_table_column_data_type",
+    "set_table_data_type_policy",
+    "toggle_totals_row",
+    "resize_table",
+    "convert_to_range",
+    "enforce_table_header",
+    "set_calculated_column",
+    "add_table_row",
+    "check_table_auto_expand",
+    // What-if analysis
+    "goal_seek",
+    "scenario_add",
+    "scenario_delete",
+    "scenario_show",
+    "scenario_summary",
+    "scenario_merge",
+    "data_table_one_var",
+    "data_table_two_var",
+    "solver_solve",
+    "solver_revert",
+    "consolidate_data",
+    // Computed properties, controls, cell types and behaviors
+    "add_computed_property",
+    "update_computed_property",
+    "remove_computed_property",
+    "set_control_property",
+    "set_control_metadata",
+    "remove_control_metadata",
+    "set_cell_type",
+    "set_cell_type_range",
+    "clear_cell_type",
+    "clear_cell_type_range",
+    "set_cell_behavior",
+    "remove_cell_behavior",
+    "set_cell_behavior_enabled",
+    // Scripts and notebooks (scripts write to the grid directly)
+    "run_script",
+    "save_script",
+    "delete_script",
+    "rename_script",
+    "notebook_create",
+    "notebook_save",
+    "notebook_delete",
+    "notebook_run_cell",
+    "notebook_run_all",
+    "notebook_rewind",
+    "notebook_run_from",
+    "save_object_script",
+    "delete_object_script",
+    "delete_object_scripts_for_instance",
+    "save_object_template",
+    "delete_object_template",
+    // Page layout
+    "set_page_setup",
+    "insert_row_page_break",
+    "remove_row_page_break",
+    "insert_col_page_break",
+    "remove_col_page_break",
+    "reset_all_page_breaks",
+    "move_page_break",
+    "set_print_area",
+    "set_print_areas",
+    "clear_print_area",
+    "set_print_title_rows",
+    "clear_print_title_rows",
+    "set_print_title_cols",
+    "clear_print_title_cols",
+    // Slicers, ribbon filters, pane controls, timelines (definitions only;
+    // their selections are `View`)
+    "create_slicer",
+    "delete_slicer",
+    "update_slicer",
+    "update_slicer_position",
+    "add_slicer_computed_property",
+    "update_slicer_computed_property",
+    "remove_slicer_computed_property",
+    "create_ribbon_filter",
+    "delete_ribbon_filter",
+    "update_ribbon_filter",
+    "create_pane_control",
+    "delete_pane_control",
+    "update_pane_control",
+    "create_timeline_slicer",
+    "delete_timeline_slicer",
+    "update_timeline_slicer",
+    "update_timeline_position",
+    "update_timeline_connections",
+    // Charts and sparklines
+    "save_chart",
+    "update_chart",
+    "delete_chart",
+    "save_sparklines",
+    "delete_sparklines",
+    "clear_all_sparklines",
+    // Package subscriptions and writeback regions stored in the workbook
+    "calp_pull",
+    "calp_revert_override",
+    "calp_accept_upstream",
+    "calp_keep_override",
+    "calp_import_overrides",
+    "calp_refresh_apply",
+    "calp_reset_subscription",
+    "calp_detach",
+    "calp_dev_subscribe",
+    "calp_dev_refresh",
+    "calp_rename_cell_id",
+    "calp_merge_cell_ids",
+    "calp_add_writeback_region",
+    "calp_remove_writeback_region",
+    "calp_update_writeback_region",
+    "calp_save_writeback_draft",
+    "calp_set_writeback_rollup",
+    "calp_save_data_source_config",
+];
+
+/// Commands that only change what is displayed.
+pub const VIEW_COMMANDS: &[&str] = &[
+    // Panes, gridlines and navigation
+    "set_show_gridlines",
+    "set_sheet_view_options",
+    "set_active_sheet",
+    "set_freeze_panes",
+    "set_split_window",
+    "set_scroll_area",
+    "next_sheet",
+    "previous_sheet",
+    "set_reference_style",
+    "show_all_notes",
+    // AutoFilter and advanced filter (copy-to output is written by the
+    // frontend through `update_cells_batch`, which stays blocked)
+    "apply_auto_filter",
+    "clear_column_criteria",
+    "clear_auto_filter_criteria",
+    "reapply_auto_filter",
+    "remove_auto_filter",
+    "set_advanced_filter_hidden_rows",
+    "clear_advanced_filter_hidden_rows",
+    "run_advanced_filter",
+    "set_column_filter_values",
+    "set_column_custom_filter",
+    "set_column_top_bottom_filter",
+    "set_column_dynamic_filter",
+    // Outline state
+    "collapse_row_group",
+    "expand_row_group",
+    "collapse_column_group",
+    "expand_column_group",
+    "show_outline_level",
+    // Pivot filtering, sorting and drill state
+    "toggle_pivot_group",
+    "apply_pivot_filter",
+    "clear_pivot_filter",
+    "sort_pivot_field",
+    "set_pivot_item_visibility",
+    "set_pivot_item_expanded",
+    "expand_collapse_level",
+    "expand_collapse_all",
+    "bi_set_active_role",
+    // Slicer, ribbon filter, pane control and timeline selections
+    "clear_slicer_filter",
+    "set_slicer_item_selected",
+    "update_slicer_selection",
+    "update_ribbon_filter_selection",
+    "clear_ribbon_filter",
+    "set_ribbon_filter_item_selected",
+    "set_pane_control_value",
+    "update_timeline_selection",
+    "update_timeline_scroll",
+];
+
+/// Commands that recalculate or refresh derived results.
+pub const CALCULATION_COMMANDS: &[&str] = &[
+    "calculate_now",
+    "calculate_sheet",
+    "calculate_range",
+    "cancel_calculation",
+    "set_calculation_mode",
+    "update_calculation_settings",
+    "set_iteration_settings",
+    "set_calculate_before_save",
+    "recalc_control_dependents",
+    "refresh_pivot_cache",
+    "refresh_all_pivot_tables",
+    "refresh_report",
+    "bi_refresh_connection",
+    "bi_refresh_all_in_memory",
+    "bi_model_refresh_table",
+    "bi_refresh_writeback_data",
+    "calp_refresh_data",
+    // Animation playback writes frames transiently and always restores the
+    // snapshot it took.
+    "anim_snapshot",
+    "anim_apply_frame",
+    "anim_restore",
+    "anim_reroll_and_read",
+];
+
+/// Classify an IPC command. Unlisted commands are `Read`.
+pub fn command_access(command: &str) -> CommandAccess {
+    if EDIT_COMMANDS.contains(&command) {
+        CommandAccess::Edit
+    } else if VIEW_COMMANDS.contains(&command) {
+        CommandAccess::View
+    } else if CALCULATION_COMMANDS.contains(&command) {
+        CommandAccess::Calculation
+    } else {
+        CommandAccess::Read
+    }
+}
+
+/// Reject `command` with `WORKBOOK_READ_ONLY` when it edits a read-only workbook.
+pub fn guard_command(command: &str, file_state: &FileState) -> Result<(), String> {
+    if command_access(command) == CommandAccess::Edit && file_state.read_only_reason().is_some() {
+        return Err(WORKBOOK_READ_ONLY.to_string());
+    }
+    Ok(())
+}
+
+/// Whether the OS keeps us from writing `path`: the read-only attribute, or a
+/// failed open for writing (another process holds it exclusively).
+pub fn detect_read_only(path: &Path) -> Option<ReadOnlyReason> {
+    let metadata = std::fs::metadata(path).ok()?;
+    if metadata.permissions().readonly() {
+        return Some(ReadOnlyReason::ReadOnlyAttribute);
+    }
+    match std::fs::OpenOptions::new().read(true).write(true).open(path) {
+        Ok(_) => None,
+        Err(_) => Some(ReadOnlyReason::LockedByAnotherProcess),
+    }
+}
+
+/// Wrap the app's invoke handler so edit commands are rejected before they
+/// reach their handlers. Every mutation path (UI, scripts, MCP) goes through
+/// IPC, so all of them get the same `WORKBOOK_READ_ONLY` error.
+pub fn guard_invokes<R: tauri::Runtime>(
+    handler: impl Fn(tauri::ipc::Invoke<R>) -> bool + Send + Sync + 'static,
+) -> impl Fn(tauri::ipc::Invoke<R>) -> bool + Send + Sync + 'static {
+    move |invoke| {
+        let webview = invoke.message.webview();
+        let guard = guard_command(invoke.message.command(), &webview.state::<FileState>());
+        if let Err(e) = guard {
+            invoke.resolver.reject(e);
+            return true;
+        }
+        handler(invoke)
+    }
+}
@@ -192,8 +192,7 @@ fn union_bounds(a: (u32, u32, u32, u32), b: (u32, u32, u32, u32)) -> (u32, u32,
 /// (the cells themselves are restored as ordinary grid content).
 pub fn reregister_report_region(state: &AppState, r: &SavedReport) {
     let mut regions = state.protected_regions.lock().unwrap();
-    regions.retain(|reg| !(reg.region_type == "report" && reg.owner_id == r.id));
-    regions.push(ProtectedRegion {
+    regions.upsert(ProtectedRegion {
         id: format!("report-{}", r.id),
         region_type: "report".to_string(),
         owner_id: r.id,
@@ -203,6 +202,11 @@ pub fn reregister_report_region(state: &AppState, r: &SavedReport) {
         end_row: r.end_row,
         end_col: r.end_col,
     });
+    drop(regions);
+
+    let mut changes = crate::change_events::ChangeBatch::new();
+    changes.push_region(r.sheet_index, "report");
+    changes.flush();
 }
 
 // ============================================================================
@@ -363,20 +367,8 @@ fn write_report_to_grid(
                 }
             }
 
-            let active_sheet = *state.active_sheet.lock().unwrap();
-            let merges = if sheet_idx == active_sheet {
-                let mut active_grid = state.grid.lock().unwrap();
-                if let Some(ref r) = old {
-                    if r.sheet_index == sheet_idx {
-                        active_grid.clear_region(r.start_row, r.start_col, r.end_row, r.end_col);
-                    }
-                }
-                let m = write_pivot_to_grid(dest_grid, Some(&mut active_grid), view, dest, &mut styles);
-                active_grid.recalculate_bounds();
-                m
-            } else {
-                write_pivot_to_grid(dest_grid, None, view, dest, &mut styles)
-            };
+            let merges = write_pivot_to_grid(dest_grid, view, dest, &mut styles);
+            dest_grid.recalculate_bounds();
 
             let (dest_row, dest_col) = dest;
             let visible_rows = view.rows.iter().filter(|r| r.visible).count() as u32;
@@ -405,14 +397,14 @@ fn write_report_to_grid(
         }
     }
 
-    // Re-register the protected region (region_type "report").
+    // Re-register the protected region (region_type "report"), resized in
+    // place when it already exists so its id stays stable.
     let (dest_row, dest_col) = dest;
     let visible_rows = view.rows.iter().filter(|r| r.visible).count() as u32;
     let end_row = dest_row + visible_rows.max(1) - 1;
     let end_col = dest_col + view.col_count.max(1) as u32 - 1;
     let mut regions = state.protected_regions.lock().unwrap();
-    regions.retain(|r| !(r.region_type == "report" && r.owner_id == report_id));
-    regions.push(ProtectedRegion {
+    regions.upsert(ProtectedRegion {
         id: format!("report-{}", report_id),
         region_type: "report".to_string(),
         owner_id: report_id,
@@ -431,14 +423,9 @@ fn clear_report_region(state: &AppState, report_id: ReportId) {
             let mut grids = state.grids.lock().unwrap();
             if let Some(dest_grid) = grids.get_mut(r.sheet_index) {
                 clear_pivot_region_from_grid(dest_grid, r.start_row, r.start_col, r.end_row, r.end_col);
+                dest_grid.recalculate_bounds();
             }
         }
-        let active_sheet = *state.active_sheet.lock().unwrap();
-        if r.sheet_index == active_sheet {
-            let mut active_grid = state.grid.lock().unwrap();
-            active_grid.clear_region(r.start_row, r.start_col, r.end_row, r.end_col);
-            active_grid.recalculate_bounds();
-        }
         // Merge bookkeeping on the report's own sheet (not the visible one).
         with_sheet_merges(state, r.sheet_index, |merged| {
             merged.retain(|m| {
@@ -15,6 +15,7 @@ use crate::{
     evaluate_formula_multi_sheet, format_cell_value, get_column_row_dependents,
     get_recalculation_order, AppState,
 };
+use crate::sheet_access::check_sheet_index;
 use engine::{Cell, CellValue, Grid, StyleRegistry};
 
 // ============================================================================
@@ -53,6 +54,7 @@ fn build_cell_data(
         sheet_index: None,
         rich_text: None,
         accounting_layout: None,
+        error_detail: None,
     })
 }
 
@@ -232,9 +234,7 @@ pub fn scenario_show(
     };
 
     // Acquire grid locks
-    let mut grid = state.grid.lock().unwrap();
     let mut grids = state.grids.lock().unwrap();
-    let active_sheet = *state.active_sheet.lock().unwrap();
     let sheet_names = state.sheet_names.lock().unwrap();
     let styles = state.style_registry.lock().unwrap();
     let dependents_map = state.dependents.lock().unwrap();
@@ -244,6 +244,9 @@ pub fn scenario_show(
     let locale = state.locale.lock().unwrap();
 
     let sheet_idx = params.sheet_index;
+    if let Err(e) = check_sheet_index(grids.len(), sheet_idx) {
+        return ScenarioShowResult { updated_cells: Vec::new(), error: Some(e.to_string()) };
+    }
     let mut all_affected = Vec::new();
 
     // Apply each changing cell value (skip writeback-protected cells)
@@ -265,10 +268,7 @@ pub fn scenario_show(
         };
         new_cell.style_index = style_index;
 
-        grids[sheet_idx].set_cell(sc.row, sc.col, new_cell.clone());
-        if sheet_idx == active_sheet {
-            grid.set_cell(sc.row, sc.col, new_cell);
-        }
+        grids[sheet_idx].set_cell(sc.row, sc.col, new_cell);
 
         all_affected.push((sc.row, sc.col));
 
@@ -307,17 +307,14 @@ pub fn scenario_show(
                             sheet_idx,
                             &engine_ast,
                             eval_ctx,
-                            None,
+                            Some(&styles),
                         )
                     }
                     Err(_) => CellValue::Error(engine::CellError::Value),
                 };
                 let mut updated = cell;
                 updated.value = new_value;
-                grids[sheet_idx].set_cell(r, c, updated.clone());
-                if sheet_idx == active_sheet {
-                    grid.set_cell(r, c, updated);
-                }
+                grids[sheet_idx].set_cell(r, c, updated);
             }
         }
     }
@@ -357,9 +354,7 @@ pub fn scenario_summary(
     };
     drop(scenarios_store);
 
-    let mut grid = state.grid.lock().unwrap();
     let mut grids = state.grids.lock().unwrap();
-    let active_sheet = *state.active_sheet.lock().unwrap();
     let sheet_names = state.sheet_names.lock().unwrap();
     let styles = state.style_registry.lock().unwrap();
     let dependents_map = state.dependents.lock().unwrap();
@@ -368,6 +363,9 @@ pub fn scenario_summary(
     let locale = state.locale.lock().unwrap();
 
     let sheet_idx = params.sheet_index;
+    if let Err(e) = check_sheet_index(grids.len(), sheet_idx) {
+        return ScenarioSummaryResult { scenario_names: Vec::new(), rows: Vec::new(), error: Some(e.to_string()) };
+    }
     let scenario_names: Vec<String> = sheet_scenarios.iter().map(|s| s.name.clone()).collect();
 
     // Collect all unique changing cells across all scenarios
@@ -466,8 +464,13 @@ pub fn scenario_summary(
         for &(r, c) in &all_deps {
             if let Some(cell) = grids[sheet_idx].get_cell(r, c).cloned() {
                 if let Some(formula) = cell.formula_string() {
-                    let new_value =
-                        evaluate_formula_multi_sheet(&grids, &sheet_names, sheet_idx, &formula);
+                    let new_value = evaluate_formula_multi_sheet(
+                        &grids,
+                        &sheet_names,
+                        sheet_idx,
+                        &formula,
+                        engine::EvalContext::at(r, c),
+                    );
                     let mut updated = cell;
                     updated.value = new_value;
                     grids[sheet_idx].set_cell(r, c, updated);
@@ -514,14 +517,16 @@ pub fn scenario_summary(
         for &(r, c) in &all_deps {
             if let Some(cell) = grids[sheet_idx].get_cell(r, c).cloned() {
                 if let Some(formula) = cell.formula_string() {
-                    let new_value =
-                        evaluate_formula_multi_sheet(&grids, &sheet_names, sheet_idx, &formula);
+                    let new_value = evaluate_formula_multi_sheet(
+                        &grids,
+                        &sheet_names,
+                        sheet_idx,
+                        &formula,
+                        engine::EvalContext::at(r, c),
+                    );
                     let mut updated = cell;
                     updated.value = new_value;
-                    grids[sheet_idx].set_cell(r, c, updated.clone());
-                    if sheet_idx == active_sheet {
-                        grid.set_cell(r, c, updated);
-                    }
+                    grids[sheet_idx].set_cell(r, c, updated);
                 }
             }
         }
@@ -422,6 +422,7 @@ pub(crate) fn apply_script_modified_grids(
                 v
             };
             app_grids[idx] = after_grid.clone();
+            app_grids[idx].mark_all_changed();
             non_active_writes.push(NonActiveWrite { sheet_index: idx, before_cells, diff });
         }
         drop(app_grids);
@@ -458,10 +459,10 @@ pub(crate) fn apply_script_modified_grids(
         // + range. Recorded before the move into update_cells_batch.
         record_script_grid_mutation(state, surface, surface_id, active_sheet, cell_count as u32, &updates);
         let r = crate::commands::data::update_cells_batch_with_controls(
-            state.clone(),
-            file_state.clone(),
-            user_files_state.clone(),
-            pivot_state.clone(),
+            state,
+            file_state,
+            user_files_state,
+            pivot_state,
             updates,
             None,
             Some(control_values),
@@ -573,7 +574,7 @@ pub fn run_script(
     let sheet_names = state.sheet_names.lock().map_err(|e| e.to_string())?.clone();
     let active_sheet = *state.active_sheet.lock().map_err(|e| e.to_string())?;
     let locale = state.locale.lock().map_err(|e| e.to_string())?.clone();
-    let calculation_mode = state.calculation_mode.lock().map_err(|e| e.to_string())?.clone();
+    let calculation_mode = state.calculation_settings.lock().map_err(|e| e.to_string())?.mode.as_str().to_string();
 
     // Build Application info from current AppState
     let app_info = script_engine::types::AppInfo {
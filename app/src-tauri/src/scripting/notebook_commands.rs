@@ -223,16 +223,7 @@ async fn run_cell_internal(
                     *cells_modified,
                     &[],
                 );
-                let active_grid_clone = modified_grids.get(active_sheet).cloned();
-
-                let mut app_grids = app_state.grids.lock().map_err(|e| e.to_string())?;
-                *app_grids = modified_grids;
-                drop(app_grids);
-
-                if let Some(grid) = active_grid_clone {
-                    let mut app_grid = app_state.grid.lock().map_err(|e| e.to_string())?;
-                    *app_grid = grid;
-                }
+                *app_state.grids.lock().map_err(|e| e.to_string())? = modified_grids;
             }
         }
         _ => {}
@@ -454,19 +445,7 @@ async fn notebook_rewind_internal(
     }
 
     // 2. Restore the snapshot to AppState
-    let active_sheet = *app_state.active_sheet.lock().map_err(|e| e.to_string())?;
-    {
-        let active_grid_clone = snapshot_grids.get(active_sheet).cloned();
-
-        let mut app_grids = app_state.grids.lock().map_err(|e| e.to_string())?;
-        *app_grids = snapshot_grids;
-        drop(app_grids);
-
-        if let Some(grid) = active_grid_clone {
-            let mut app_grid = app_state.grid.lock().map_err(|e| e.to_string())?;
-            *app_grid = grid;
-        }
-    }
+    *app_state.grids.lock().map_err(|e| e.to_string())? = snapshot_grids;
 
     // 3. Reset the runtime (drop the JS session) and clear checkpoints.
     // Baseline is deliberately kept: it still describes the state before the
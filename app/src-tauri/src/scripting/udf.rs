@@ -56,6 +56,8 @@ fn cell_error_to_str(e: &CellError) -> &'static str {
         CellError::Circular => "#CIRCULAR!",
         CellError::Conflict => "#CONFLICT",
         CellError::Blocked => "#BLOCKED!",
+        CellError::Spill => "#SPILL!",
+        CellError::Num => "#NUM!",
     }
 }
 
@@ -71,6 +73,8 @@ fn parse_cell_error(s: &str) -> CellError {
         "#CIRCULAR!" => CellError::Circular,
         "#CONFLICT" => CellError::Conflict,
         "#BLOCKED!" => CellError::Blocked,
+        "#SPILL!" => CellError::Spill,
+        "#NUM!" => CellError::Num,
         _ => CellError::Value,
     }
 }
@@ -481,6 +485,8 @@ mod tests {
             (CellError::Name, "#NAME?"),
             (CellError::Value, "#VALUE!"),
             (CellError::NA, "#N/A"),
+            (CellError::Spill, "#SPILL!"),
+            (CellError::Num, "#NUM!"),
         ] {
             let r = EvalResult::Error(err.clone());
             let u = eval_to_udf(&r);
@@ -0,0 +1,266 @@
+//! FILENAME: app/src-tauri/src/session.rs
+//! PURPOSE: Headless workbook API. `WorkbookSession` owns the same state the
+//! Tauri app manages (AppState, FileState, pivot/slicer/BI stores, ...) and
+//! drives it through the command bodies the IPC handlers use, so a CLI tool,
+//! batch job or test can open, edit, recalculate and save a workbook without a
+//! window or a running Tauri app.
+//! CONTEXT: Built only with the `headless` feature. Nothing in this API names
+//! a Tauri type. Sheets are addressed by name (case-insensitive) and cells in
+//! A1 notation. Edits go through the read-only guard like IPC edits do.
+//! Operations on a sheet other than the active one switch to it for the call
+//! and switch back afterwards, so the saved active sheet does not move.
+
+use std::path::Path;
+use std::sync::atomic::AtomicBool;
+
+use crate::bi::types::BiState;
+use crate::pane_control::PaneControlState;
+use crate::persistence::{open_file_internal, save_file_internal, FileState, UserFilesState};
+use crate::pivot::types::{CreatePivotRequest, PivotState};
+use crate::pivot::utils::{parse_cell_ref, parse_range};
+use crate::ribbon_filter::RibbonFilterState;
+use crate::scripting::types::ScriptState;
+use crate::slicer::SlicerState;
+use crate::tables::{CreateTableParams, Table};
+use crate::{create_app_state, format_cell_value, AppState};
+
+pub use pivot_engine::{AggregationType, PivotId};
+
+/// One open workbook and all of its per-workbook state.
+pub struct WorkbookSession {
+    state: AppState,
+    file_state: FileState,
+    user_files_state: UserFilesState,
+    slicer_state: SlicerState,
+    ribbon_filter_state: RibbonFilterState,
+    pane_control_state: PaneControlState,
+    script_state: ScriptState,
+    pivot_state: PivotState,
+    bi_state: BiState,
+}
+
+impl WorkbookSession {
+    /// A new, empty workbook with one sheet ("Sheet1").
+    pub fn new() -> Self {
+        WorkbookSession {
+            state: create_app_state(),
+            file_state: FileState::default(),
+            user_files_state: UserFilesState::default(),
+            slicer_state: SlicerState::new(),
+            ribbon_filter_state: RibbonFilterState::new(),
+            pane_control_state: PaneControlState::new(),
+            script_state: ScriptState::new(),
+            pivot_state: PivotState::new(),
+            bi_state: BiState::new(),
+        }
+    }
+
+    /// Open a `.cala` or `.xlsx` workbook. Encrypted `.cala` files fail with
+    /// the `ENC_NEEDS_PASSWORD` sentinel; use `open_with_password`.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, String> {
+        Self::open_with_password(path, None)
+    }
+
+    /// Open a workbook, decrypting an encrypted `.cala` with `password`.
+    pub fn open_with_password(path: impl AsRef<Path>, password: Option<&str>) -> Result<Self, String> {
+        let session = Self::new();
+        open_file_internal(
+            &session.state,
+            &session.file_state,
+            &session.user_files_state,
+            &session.slicer_state,
+            &session.ribbon_filter_state,
+            &session.pane_control_state,
+            &session.script_state,
+            &session.pivot_state,
+            &session.bi_state,
+            path.as_ref().to_string_lossy().into_owned(),
+            password.map(str::to_string),
+            None,
+        )?;
+        Ok(session)
+    }
+
+    /// Save to `path`; the extension picks the format (`.cala`, else xlsx).
+    /// Refused with `WORKBOOK_READ_ONLY` when the workbook was opened
+    /// read-only and `path` is the file it came from.
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), String> {
+        save_file_internal(
+            &self.state,
+            &self.file_state,
+            &self.user_files_state,
+            &self.slicer_state,
+            &self.ribbon_filter_state,
+            &self.pane_control_state,
+            &self.script_state,
+            &self.pivot_state,
+            &self.bi_state,
+            path.as_ref().to_string_lossy().into_owned(),
+            None,
+            None,
+        )
+    }
+
+    /// Sheet names in tab order.
+    pub fn sheet_names(&self) -> Vec<String> {
+        self.state.sheet_names.lock().unwrap().clone()
+    }
+
+    /// Enter `input` into a cell exactly as typed in the grid: a leading "="
+    /// makes a formula, anything else is parsed as a value. Dependents
+    /// recalculate as they do after an edit in the app.
+    pub fn set_cell(&self, sheet: &str, a1: &str, input: &str) -> Result<(), String> {
+        crate::read_only::guard_command("update_cell", &self.file_state)?;
+        let sheet_index = self.sheet_index(sheet)?;
+        let (row, col) = parse_cell_ref(a1)?;
+        let result = self.on_sheet(sheet_index, || {
+            crate::commands::data::update_cell_impl(
+                &self.state,
+                &self.file_state,
+                &self.user_files_state,
+                &self.slicer_state,
+                &self.pivot_state,
+                &self.pane_control_state,
+                &self.ribbon_filter_state,
+                row,
+                col,
+                input.to_string(),
+                None,
+                None,
+            )
+        })?;
+        crate::pivot::source_watch::source_cells_changed(
+            &self.state,
+            &self.pivot_state,
+            Some((&self.pane_control_state, &self.ribbon_filter_state)),
+            &result.cells,
+        );
+        Ok(())
+    }
+
+    /// The cell's text as the grid shows it (number format and locale
+    /// applied). An empty cell displays as "".
+    pub fn get_display(&self, sheet: &str, a1: &str) -> Result<String, String> {
+        let sheet_index = self.sheet_index(sheet)?;
+        let (row, col) = parse_cell_ref(a1)?;
+        let grids = self.state.grids.lock().unwrap();
+        let grid = crate::sheet_access::sheet_at(&grids, sheet_index)?;
+        let Some(cell) = grid.get_cell(row, col) else {
+            return Ok(String::new());
+        };
+        let styles = self.state.style_registry.lock().unwrap();
+        let locale = self.state.locale.lock().unwrap();
+        Ok(format_cell_value(&cell.value, styles.get(cell.style_index), &locale))
+    }
+
+    /// Recalculate every formula in the workbook (Calculate Now), then
+    /// refresh the pivots fed by the changed cells.
+    pub fn recalculate(&self) -> Result<(), String> {
+        crate::calculation::calculate_and_refresh_pivots(
+            &self.state,
+            &self.user_files_state,
+            &self.pivot_state,
+            &self.pane_control_state,
+            &self.ribbon_filter_state,
+            None,
+            &AtomicBool::new(false),
+            &mut |_| {},
+        )?;
+        Ok(())
+    }
+
+    /// Turn `range` (e.g. "A1:C10") on `sheet` into a table. An empty `name`
+    /// generates one ("Table1", ...).
+    pub fn create_table(&self, sheet: &str, range: &str, name: &str, has_headers: bool) -> Result<Table, String> {
+        crate::read_only::guard_command("create_table", &self.file_state)?;
+        let sheet_index = self.sheet_index(sheet)?;
+        let ((start_row, start_col), (end_row, end_col)) = parse_range(range)?;
+        let result = self.on_sheet(sheet_index, || {
+            Ok(crate::tables::create_table_internal(
+                &self.state,
+                CreateTableParams {
+                    name: name.to_string(),
+                    start_row,
+                    start_col,
+                    end_row,
+                    end_col,
+                    has_headers,
+                    style_options: None,
+                    style_name: None,
+                },
+            ))
+        })?;
+        match result.table {
+            Some(table) if result.success => Ok(table),
+            _ => Err(result.error.unwrap_or_else(|| "Table was not created".to_string())),
+        }
+    }
+
+    /// Create a pivot table from `source_range` (first row = headers) with its
+    /// top-left corner at `destination_cell`. Fields are named by their
+    /// header text; the output is written into the destination sheet.
+    pub fn create_pivot(
+        &self,
+        source_sheet: &str,
+        source_range: &str,
+        destination_sheet: &str,
+        destination_cell: &str,
+        row_fields: &[&str],
+        value_fields: &[(&str, AggregationType)],
+    ) -> Result<PivotId, String> {
+        crate::read_only::guard_command("create_pivot_table", &self.file_state)?;
+        let request = CreatePivotRequest {
+            source_range: source_range.to_string(),
+            destination_cell: destination_cell.to_string(),
+            source_sheet: Some(self.sheet_index(source_sheet)?),
+            destination_sheet: Some(self.sheet_index(destination_sheet)?),
+            has_headers: Some(true),
+            name: None,
+            source_table_name: None,
+        };
+        let response = crate::pivot::commands::create_pivot_inner(
+            &self.state,
+            &self.pivot_state,
+            request,
+            row_fields.iter().map(|f| f.to_string()).collect(),
+            value_fields.iter().map(|(f, agg)| (f.to_string(), *agg)).collect(),
+        )?;
+        Ok(response.pivot_id)
+    }
+
+    /// The underlying app state, for operations this API does not wrap yet.
+    pub fn app_state(&self) -> &AppState {
+        &self.state
+    }
+
+    /// Index of the sheet named `name` (case-insensitive, like formulas).
+    fn sheet_index(&self, name: &str) -> Result<usize, String> {
+        let wanted = name.to_uppercase();
+        self.state
+            .sheet_names
+            .lock()
+            .unwrap()
+            .iter()
+            .position(|n| n.to_uppercase() == wanted)
+            .ok_or_else(|| format!("Sheet '{}' not found", name))
+    }
+
+    /// Run `f` with `sheet_index` active, then restore the previously active
+    /// sheet. Most command bodies act on the active sheet.
+    fn on_sheet<T>(&self, sheet_index: usize, f: impl FnOnce() -> Result<T, String>) -> Result<T, String> {
+        let previous = *self.state.active_sheet.lock().unwrap();
+        if previous == sheet_index {
+            return f();
+        }
+        crate::sheets::set_active_sheet_internal(&self.state, sheet_index)?;
+        let result = f();
+        crate::sheets::set_active_sheet_internal(&self.state, previous)?;
+        result
+    }
+}
+
+impl Default for WorkbookSession {
+    fn default() -> Self {
+        Self::new()
+    }
+}
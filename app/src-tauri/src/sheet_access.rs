@@ -0,0 +1,188 @@
+//! FILENAME: app/src-tauri/src/sheet_access.rs
+//! PURPOSE: Validated access to per-sheet state, and the workbook revision.
+//! Commands take a raw `sheet_index` (or a sheet id) from the frontend. After
+//! a sheet is deleted or moved, a request already in flight can carry an index
+//! that is out of range or now names a different sheet. `resolve_sheet` and
+//! `sheet_at`/`sheet_at_mut` turn that into `ApiError::SheetNotFound` instead
+//! of a panic on `grids[index]`.
+//! CONTEXT: Commands mostly return `Result<_, String>`; `ApiError` converts
+//! with `?` into the `SHEET_NOT_FOUND {...}` sentinel string (sentinel plus a
+//! JSON payload), which the frontend recognises like `WORKBOOK_READ_ONLY`.
+//! The revision increases once per `Edit` command (see `read_only`) and when
+//! another workbook is loaded, so the frontend can tell that the workbook
+//! changed under a pending request.
+
+use std::fmt;
+
+use identity::SheetId;
+use serde::{Deserialize, Serialize};
+use tauri::{Manager, State};
+
+use crate::read_only::{command_access, CommandAccess};
+use crate::AppState;
+
+/// Sentinel that starts the error string of `ApiError::SheetNotFound`.
+pub const SHEET_NOT_FOUND: &str = "SHEET_NOT_FOUND";
+
+/// A sheet as the frontend names it: by position or by stable id.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum SheetRef {
+    Index(usize),
+    Id(String),
+}
+
+impl From<usize> for SheetRef {
+    fn from(index: usize) -> Self {
+        SheetRef::Index(index)
+    }
+}
+
+/// Structured command error.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "code")]
+pub enum ApiError {
+    /// The requested sheet does not exist (any more).
+    #[serde(rename = "SHEET_NOT_FOUND", rename_all = "camelCase")]
+    SheetNotFound { requested: SheetRef, current_count: usize },
+}
+
+impl ApiError {
+    fn sentinel(&self) -> &'static str {
+        match self {
+            ApiError::SheetNotFound { .. } => SHEET_NOT_FOUND,
+        }
+    }
+}
+
+/// `SHEET_NOT_FOUND {"code":"SHEET_NOT_FOUND","requested":4,"currentCount":3}`
+impl fmt::Display for ApiError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let payload = serde_json::to_string(self).map_err(|_| fmt::Error)?;
+        write!(f, "{} {}", self.sentinel(), payload)
+    }
+}
+
+impl std::error::Error for ApiError {}
+
+impl From<ApiError> for String {
+    fn from(error: ApiError) -> Self {
+        error.to_string()
+    }
+}
+
+/// A sheet that existed when it was resolved.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SheetHandle {
+    pub index: usize,
+    pub id: SheetId,
+}
+
+/// Resolve `sheet` against the current sheet order.
+pub fn resolve_sheet(state: &AppState, sheet: &SheetRef) -> Result<SheetHandle, ApiError> {
+    resolve_sheet_in(&state.sheet_ids.lock().unwrap(), sheet)
+}
+
+/// `resolve_sheet` for callers already holding `sheet_ids`.
+pub fn resolve_sheet_in(sheet_ids: &[SheetId], sheet: &SheetRef) -> Result<SheetHandle, ApiError> {
+    let index = match sheet {
+        SheetRef::Index(index) => Some(*index).filter(|&i| i < sheet_ids.len()),
+        SheetRef::Id(id) => SheetId::parse(id).and_then(|id| sheet_ids.iter().position(|&s| s == id)),
+    };
+    match index {
+        Some(index) => Ok(SheetHandle { index, id: sheet_ids[index] }),
+        None => Err(ApiError::SheetNotFound { requested: sheet.clone(), current_count: sheet_ids.len() }),
+    }
+}
+
+/// Fail with `SheetNotFound` unless `index` is below `count`.
+pub fn check_sheet_index(count: usize, index: usize) -> Result<(), ApiError> {
+    if index < count {
+        Ok(())
+    } else {
+        Err(ApiError::SheetNotFound { requested: SheetRef::Index(index), current_count: count })
+    }
+}
+
+/// `check_sheet_index` for every index, before a multi-sheet command starts
+/// writing (so a stale index cannot leave it half applied).
+pub fn check_sheet_indices(count: usize, indices: &[usize]) -> Result<(), ApiError> {
+    indices.iter().try_for_each(|&index| check_sheet_index(count, index))
+}
+
+/// The entry for sheet `index` of a per-sheet Vec (grids, names, ...).
+pub fn sheet_at<T>(items: &[T], index: usize) -> Result<&T, ApiError> {
+    check_sheet_index(items.len(), index)?;
+    Ok(&items[index])
+}
+
+/// Mutable `sheet_at`.
+pub fn sheet_at_mut<T>(items: &mut [T], index: usize) -> Result<&mut T, ApiError> {
+    check_sheet_index(items.len(), index)?;
+    Ok(&mut items[index])
+}
+
+// ============================================================================
+// WORKBOOK REVISION
+// ============================================================================
+
+/// Current workbook revision.
+pub fn workbook_revision(state: &AppState) -> u64 {
+    *state.workbook_revision.lock().unwrap()
+}
+
+/// Commands that swap in a different workbook. They are not `Edit` commands
+/// (they stay available in read-only mode) but invalidate every sheet index.
+const WORKBOOK_REPLACING_COMMANDS: &[&str] = &["open_file", "new_file", "reload_file"];
+
+/// Advance the revision when `command` edits or replaces the workbook.
+/// Returns the new revision, or None for commands that do neither.
+pub(crate) fn record_command_revision(state: &AppState, command: &str) -> Option<u64> {
+    if command_access(command) != CommandAccess::Edit && !WORKBOOK_REPLACING_COMMANDS.contains(&command) {
+        return None;
+    }
+    let mut revision = state.workbook_revision.lock().unwrap();
+    *revision += 1;
+    Some(*revision)
+}
+
+/// Wrap the invoke handler so every `Edit` command (and open/new/reload)
+/// advances the revision before it runs; results built inside the command
+/// (`SheetsResult`) carry the revision the edit produces. Sits inside
+/// `read_only::guard_invokes`, so rejected edits do not count. After the
+/// command it seals the cell writes it made into viewport revisions (see
+/// viewport_revisions) together with their UI side-effects.
+pub fn revision_invokes<R: tauri::Runtime>(
+    handler: impl Fn(tauri::ipc::Invoke<R>) -> bool + Send + Sync + 'static,
+) -> impl Fn(tauri::ipc::Invoke<R>) -> bool + Send + Sync + 'static {
+    move |invoke| {
+        let webview = invoke.message.webview();
+        let state = webview.state::<AppState>();
+        let replaces_workbook = WORKBOOK_REPLACING_COMMANDS.contains(&invoke.message.command());
+        record_command_revision(&state, invoke.message.command());
+        if replaces_workbook {
+            // Formatting applied by the old workbook's SET.* formulas is part
+            // of what gets replaced; the new workbook's formulas register
+            // their own effects when the seal below goes over every cell.
+            state.ui_effects.lock().unwrap().reset();
+        }
+        let handled = handler(invoke);
+        if replaces_workbook {
+            for grid in state.grids.lock().unwrap().iter_mut() {
+                grid.mark_all_changed();
+            }
+        }
+        crate::viewport_revisions::seal_viewport_changes(&state);
+        if replaces_workbook {
+            state.viewport_revisions.lock().unwrap().reset_all();
+        }
+        handled
+    }
+}
+
+/// Current workbook revision. It only grows; a changed value means some edit
+/// ran since the caller last looked.
+#[tauri::command]
+pub fn get_workbook_revision(state: State<AppState>) -> u64 {
+    workbook_revision(&state)
+}
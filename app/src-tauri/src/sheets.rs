@@ -6,6 +6,8 @@
 use std::collections::{HashMap, HashSet};
 use tauri::State;
 use crate::AppState;
+use crate::sheet_access::{check_sheet_index, resolve_sheet, workbook_revision, SheetRef};
+use crate::api_types::{SheetDefaults, SheetViewOptions};
 use identity;
 use crate::pivot::types::PivotState;
 use pivot_engine::PivotId;
@@ -55,6 +57,9 @@ fn default_visibility() -> String {
 pub struct SheetsResult {
     pub sheets: Vec<SheetInfo>,
     pub active_index: usize,
+    /// Workbook revision after this call (see sheet_access).
+    #[serde(default)]
+    pub revision: u64,
 }
 
 // ============================================================================
@@ -179,12 +184,17 @@ fn remap_sheet_keyed_stores(state: &AppState, remap: impl Fn(usize) -> Option<us
     // mis-target spill protection).
     remap_cell_keyed_map(&mut state.spill_hosts.lock().unwrap(), &remap);
     remap_cell_keyed_map(&mut state.spill_ranges.lock().unwrap(), &remap);
+    // Typed formulas of name/table-referencing cells (name_dependents.rs).
+    remap_cell_keyed_map(&mut state.name_ref_sources.lock().unwrap(), &remap);
     // Protection stores are sheet-index-keyed like CF/DV. Without remapping,
     // deleting/reordering sheets leaves protection attached to the WRONG index
     // — and now that protection persists, a stale index serializes under a
     // freshly-minted bogus SheetId and reattaches to sheet 0 on reopen.
     remap_indexed_map(&mut state.sheet_protection.lock().unwrap(), &remap);
     remap_indexed_map(&mut state.cell_protection.lock().unwrap(), &remap);
+    remap_indexed_map(&mut state.range_protection.lock().unwrap(), &remap);
+    // Print area / print titles are per-sheet built-in names keyed by index.
+    crate::named_ranges::remap_builtin_name_sheets(&mut state.named_ranges.lock().unwrap(), &remap);
 }
 
 // ============================================================================
@@ -202,6 +212,7 @@ pub fn get_sheets(state: State<AppState>) -> SheetsResult {
     SheetsResult {
         sheets: build_sheet_list(&sheet_names, &freeze_configs, &tab_colors, &sheet_visibility),
         active_index,
+        revision: workbook_revision(&state),
     }
 }
 
@@ -225,32 +236,64 @@ pub fn get_sheet_ids(state: State<AppState>) -> Vec<String> {
         .collect()
 }
 
+/// Get the view options (zoom, gridlines, headings, direction) for the active sheet.
+#[tauri::command]
+pub fn get_sheet_view_options(state: State<AppState>) -> SheetViewOptions {
+    get_sheet_view_options_internal(&state)
+}
+
+/// Set the view options for the active sheet. Zoom is clamped to 10%..500%.
+#[tauri::command]
+pub fn set_sheet_view_options(state: State<AppState>, options: SheetViewOptions) -> SheetViewOptions {
+    set_sheet_view_options_internal(&state, options)
+}
+
+pub(crate) fn get_sheet_view_options_internal(state: &AppState) -> SheetViewOptions {
+    let active = *state.active_sheet.lock().unwrap();
+    let views = state.sheet_view_options.lock().unwrap();
+    views.get(active).cloned().unwrap_or_default()
+}
+
+pub(crate) fn set_sheet_view_options_internal(state: &AppState, mut options: SheetViewOptions) -> SheetViewOptions {
+    options.zoom = if options.zoom.is_finite() { options.zoom.clamp(0.1, 5.0) } else { 1.0 };
+    let active = *state.active_sheet.lock().unwrap();
+    let mut views = state.sheet_view_options.lock().unwrap();
+    ensure_vec_len_with(&mut views, active + 1, SheetViewOptions::default);
+    views[active] = options.clone();
+    options
+}
+
 /// Get the gridlines visibility setting for the active sheet.
 #[tauri::command]
 pub fn get_show_gridlines(state: State<AppState>) -> bool {
-    let active = *state.active_sheet.lock().unwrap();
-    let gridlines = state.show_gridlines.lock().unwrap();
-    gridlines.get(active).copied().unwrap_or(true)
+    get_sheet_view_options_internal(&state).show_gridlines
 }
 
 /// Set the gridlines visibility for the active sheet.
 #[tauri::command]
 pub fn set_show_gridlines(state: State<AppState>, visible: bool) {
-    let active = *state.active_sheet.lock().unwrap();
-    let mut gridlines = state.show_gridlines.lock().unwrap();
-    while gridlines.len() <= active {
-        gridlines.push(true);
-    }
-    gridlines[active] = visible;
+    let options = SheetViewOptions {
+        show_gridlines: visible,
+        ..get_sheet_view_options_internal(&state)
+    };
+    set_sheet_view_options_internal(&state, options);
 }
 
+/// `index` is a sheet index or a sheet id (see `get_sheet_ids`); an id keeps
+/// pointing at the same sheet when others are deleted or moved.
 #[tauri::command]
-pub fn set_active_sheet(state: State<AppState>, index: usize) -> Result<SheetsResult, String> {
+pub fn set_active_sheet(state: State<AppState>, index: SheetRef) -> Result<SheetsResult, String> {
+    let index = resolve_sheet(&state, &index)?.index;
+    set_active_sheet_internal(&state, index)
+}
+
+/// Switching only swaps the per-sheet dimension/merge slots and the index —
+/// the grids stay in place, so the cost does not grow with the sheet size.
+pub(crate) fn set_active_sheet_internal(state: &AppState, index: usize) -> Result<SheetsResult, String> {
     let (result, switched) = {
     let sheet_names = state.sheet_names.lock().unwrap();
     let mut grids = state.grids.lock().unwrap();
     let mut active_sheet = state.active_sheet.lock().unwrap();
-    let mut current_grid = state.grid.lock().unwrap();
     let freeze_configs = state.freeze_configs.lock().unwrap();
     let tab_colors = state.tab_colors.lock().unwrap();
     let sheet_visibility = state.sheet_visibility.lock().unwrap();
@@ -261,9 +304,7 @@ pub fn set_active_sheet(state: State<AppState>, index: usize) -> Result<SheetsRe
     let mut merged_regions = state.merged_regions.lock().unwrap();
     let mut all_merged_regions = state.all_merged_regions.lock().unwrap();
 
-    if index >= sheet_names.len() {
-        return Err(format!("Sheet index {} out of range", index));
-    }
+    check_sheet_index(sheet_names.len(), index)?;
 
     while grids.len() <= index {
         grids.push(engine::grid::Grid::new());
@@ -283,11 +324,6 @@ pub fn set_active_sheet(state: State<AppState>, index: usize) -> Result<SheetsRe
     let old_index = *active_sheet;
 
     if old_index != index {
-        if old_index < grids.len() {
-            grids[old_index] = current_grid.clone();
-        }
-        *current_grid = grids[index].clone();
-
         // Swap dimensions: save current to old sheet, load from new sheet
         if old_index < all_column_widths.len() {
             all_column_widths[old_index] = std::mem::take(&mut *column_widths);
@@ -312,6 +348,7 @@ pub fn set_active_sheet(state: State<AppState>, index: usize) -> Result<SheetsRe
         SheetsResult {
             sheets: build_sheet_list(&sheet_names, &freeze_configs, &tab_colors, &sheet_visibility),
             active_index: index,
+            revision: workbook_revision(&state),
         },
         switched,
     )
@@ -322,7 +359,7 @@ pub fn set_active_sheet(state: State<AppState>, index: usize) -> Result<SheetsRe
     // sheet, otherwise edits here recalc against the previous sheet's edges
     // (BUG-0016: stale dependents -> silently wrong totals).
     if switched {
-        crate::undo_commands::rebuild_all_dependencies(&state);
+        crate::undo_commands::rebuild_all_dependencies(state);
     }
 
     Ok(result)
@@ -330,11 +367,14 @@ pub fn set_active_sheet(state: State<AppState>, index: usize) -> Result<SheetsRe
 
 #[tauri::command]
 pub fn add_sheet(state: State<AppState>, name: Option<String>) -> Result<SheetsResult, String> {
+    add_sheet_internal(&state, name)
+}
+
+pub(crate) fn add_sheet_internal(state: &AppState, name: Option<String>) -> Result<SheetsResult, String> {
     let result = {
     let mut sheet_names = state.sheet_names.lock().unwrap();
     let mut grids = state.grids.lock().unwrap();
     let mut active_sheet = state.active_sheet.lock().unwrap();
-    let mut current_grid = state.grid.lock().unwrap();
     let mut freeze_configs = state.freeze_configs.lock().unwrap();
     let mut tab_colors = state.tab_colors.lock().unwrap();
     let mut sheet_visibility = state.sheet_visibility.lock().unwrap();
@@ -360,10 +400,6 @@ pub fn add_sheet(state: State<AppState>, name: Option<String>) -> Result<SheetsR
 
     let old_index = *active_sheet;
 
-    if old_index < grids.len() {
-        grids[old_index] = current_grid.clone();
-    }
-
     // Save current sheet's dimensions before switching
     while all_column_widths.len() <= old_index {
         all_column_widths.push(HashMap::new());
@@ -375,8 +411,7 @@ pub fn add_sheet(state: State<AppState>, name: Option<String>) -> Result<SheetsR
     all_row_heights[old_index] = std::mem::take(&mut *row_heights);
 
     sheet_names.push(new_name);
-    let new_grid = engine::grid::Grid::new();
-    grids.push(new_grid.clone());
+    grids.push(engine::grid::Grid::new());
     freeze_configs.push(FreezeConfig::default());
     {
         let mut split_configs = state.split_configs.lock().unwrap();
@@ -399,10 +434,17 @@ pub fn add_sheet(state: State<AppState>, name: Option<String>) -> Result<SheetsR
     }
     tab_colors.push(String::new());
     sheet_visibility.push("visible".to_string());
-    // New sheet shows gridlines by default
+    // New sheet starts with the default view (100%, gridlines and headings on)
     {
-        let mut gridlines = state.show_gridlines.lock().unwrap();
-        gridlines.push(true);
+        let mut views = state.sheet_view_options.lock().unwrap();
+        ensure_vec_len_with(&mut views, sheet_names.len() - 1, SheetViewOptions::default);
+        views.push(SheetViewOptions::default());
+    }
+    // ...and the workbook default sizes
+    {
+        let mut defaults = state.sheet_defaults.lock().unwrap();
+        ensure_vec_len(&mut defaults, sheet_names.len() - 1);
+        defaults.push(SheetDefaults::default());
     }
     // New sheet gets empty dimensions and merged regions
     all_column_widths.push(HashMap::new());
@@ -420,28 +462,35 @@ pub fn add_sheet(state: State<AppState>, name: Option<String>) -> Result<SheetsR
 
     let new_index = sheet_names.len() - 1;
     *active_sheet = new_index;
-    *current_grid = new_grid;
 
     SheetsResult {
         sheets: build_sheet_list(&sheet_names, &freeze_configs, &tab_colors, &sheet_visibility),
         active_index: *active_sheet,
+        revision: workbook_revision(&state),
     }
     }; // drop all locks before rebuilding dependency maps
 
     // The new (empty) sheet is now active — rebuild the single-sheet
-    // dependency maps for it (see set_active_sheet / BUG-0016).
-    crate::undo_commands::rebuild_all_dependencies(&state);
+    // dependency maps for it (see set_active_sheet / BUG-0016). 3D references
+    // now cover the new sheet too.
+    crate::undo_commands::rebuild_all_dependencies(state);
+    crate::undo_commands::rebuild_cross_sheet_dependencies(state);
 
     Ok(result)
 }
 
+/// `index` as in `set_active_sheet`.
 #[tauri::command]
-pub fn delete_sheet(state: State<AppState>, pivot_state: State<'_, PivotState>, index: usize) -> Result<SheetsResult, String> {
+pub fn delete_sheet(state: State<AppState>, pivot_state: State<'_, PivotState>, index: SheetRef) -> Result<SheetsResult, String> {
+    let index = resolve_sheet(&state, &index)?.index;
+    delete_sheet_internal(&state, &pivot_state, index)
+}
+
+pub(crate) fn delete_sheet_internal(state: &AppState, pivot_state: &PivotState, index: usize) -> Result<SheetsResult, String> {
     let result = {
     let mut sheet_names = state.sheet_names.lock().unwrap();
     let mut grids = state.grids.lock().unwrap();
     let mut active_sheet = state.active_sheet.lock().unwrap();
-    let mut current_grid = state.grid.lock().unwrap();
     let mut freeze_configs = state.freeze_configs.lock().unwrap();
     let mut tab_colors = state.tab_colors.lock().unwrap();
     let mut sheet_visibility = state.sheet_visibility.lock().unwrap();
@@ -456,17 +505,11 @@ pub fn delete_sheet(state: State<AppState>, pivot_state: State<'_, PivotState>,
         return Err("Cannot delete the last sheet".to_string());
     }
 
-    if index >= sheet_names.len() {
-        return Err(format!("Sheet index {} out of range", index));
-    }
+    check_sheet_index(sheet_names.len(), index)?;
 
     let old_active = *active_sheet;
     let deleted_name = sheet_names[index].clone();
 
-    if old_active < grids.len() {
-        grids[old_active] = current_grid.clone();
-    }
-
     // Save current dimensions to per-sheet storage before deletion
     while all_column_widths.len() <= old_active {
         all_column_widths.push(HashMap::new());
@@ -549,7 +592,7 @@ pub fn delete_sheet(state: State<AppState>, pivot_state: State<'_, PivotState>,
             }
         }
     }
-    crate::report::sync_reports_to_extension_data(&state);
+    crate::report::sync_reports_to_extension_data(state);
 
     // The sheet-index-keyed HashMap stores (comments, scenarios, outlines,
     // conditional formats, data validations, cell types, on-grid controls,
@@ -557,7 +600,7 @@ pub fn delete_sheet(state: State<AppState>, pivot_state: State<'_, PivotState>,
     // Vecs: drop the deleted sheet's entries and shift the indices above it
     // down by one, exactly like the report/table remaps around this. Comment
     // and Scenario payloads carry a sheet_index field too — re-stamped inside.
-    remap_sheet_keyed_stores(&state, |i| {
+    remap_sheet_keyed_stores(state, |i| {
         if i == index {
             None
         } else if i > index {
@@ -585,6 +628,8 @@ pub fn delete_sheet(state: State<AppState>, pivot_state: State<'_, PivotState>,
         }
     }
 
+    // Bookend repair needs the tab order the 3D spans were written against.
+    let names_before = sheet_names.clone();
     sheet_names.remove(index);
     if index < grids.len() {
         grids.remove(index);
@@ -597,9 +642,8 @@ pub fn delete_sheet(state: State<AppState>, pivot_state: State<'_, PivotState>,
     }
 
     // Repair 3D reference bookends in all formulas
-    let names_after = sheet_names.clone();
     crate::repair_all_formulas(&mut grids, &|formula| {
-        crate::repair_3d_refs_on_delete(formula, &deleted_name, &names_after)
+        crate::repair_3d_refs_on_delete(formula, &deleted_name, &names_before)
     });
     if index < freeze_configs.len() {
         freeze_configs.remove(index);
@@ -623,9 +667,15 @@ pub fn delete_sheet(state: State<AppState>, pivot_state: State<'_, PivotState>,
         sheet_visibility.remove(index);
     }
     {
-        let mut gridlines = state.show_gridlines.lock().unwrap();
-        if index < gridlines.len() {
-            gridlines.remove(index);
+        let mut views = state.sheet_view_options.lock().unwrap();
+        if index < views.len() {
+            views.remove(index);
+        }
+    }
+    {
+        let mut defaults = state.sheet_defaults.lock().unwrap();
+        if index < defaults.len() {
+            defaults.remove(index);
         }
     }
     if index < all_column_widths.len() {
@@ -663,12 +713,6 @@ pub fn delete_sheet(state: State<AppState>, pivot_state: State<'_, PivotState>,
 
     *active_sheet = new_active;
 
-    if new_active < grids.len() {
-        *current_grid = grids[new_active].clone();
-    } else {
-        *current_grid = engine::grid::Grid::new();
-    }
-
     // Load new active sheet's dimensions
     if new_active < all_column_widths.len() {
         *column_widths = std::mem::take(&mut all_column_widths[new_active]);
@@ -688,29 +732,31 @@ pub fn delete_sheet(state: State<AppState>, pivot_state: State<'_, PivotState>,
     SheetsResult {
         sheets: build_sheet_list(&sheet_names, &freeze_configs, &tab_colors, &sheet_visibility),
         active_index: *active_sheet,
+        revision: workbook_revision(&state),
     }
     }; // drop all locks before rebuilding dependency maps
 
     // The active sheet (or its index) changed — rebuild the single-sheet
-    // dependency maps (see set_active_sheet / BUG-0016).
-    crate::undo_commands::rebuild_all_dependencies(&state);
+    // dependency maps (see set_active_sheet / BUG-0016), and the cross-sheet
+    // edges whose sheet indices and 3D coverage shifted.
+    crate::undo_commands::rebuild_all_dependencies(state);
+    crate::undo_commands::rebuild_cross_sheet_dependencies(state);
 
     Ok(result)
 }
 
 #[tauri::command]
 pub fn rename_sheet(state: State<AppState>, index: usize, new_name: String) -> Result<SheetsResult, String> {
+    let result = {
     let mut sheet_names = state.sheet_names.lock().unwrap();
     let active_sheet = *state.active_sheet.lock().unwrap();
     let freeze_configs = state.freeze_configs.lock().unwrap();
     let tab_colors = state.tab_colors.lock().unwrap();
     let sheet_visibility = state.sheet_visibility.lock().unwrap();
+    let mut hyperlinks = state.hyperlinks.lock().unwrap();
     let mut grids = state.grids.lock().unwrap();
-    let mut current_grid = state.grid.lock().unwrap();
 
-    if index >= sheet_names.len() {
-        return Err(format!("Sheet index {} out of range", index));
-    }
+    check_sheet_index(sheet_names.len(), index)?;
 
     let trimmed_name = new_name.trim().to_string();
     if trimmed_name.is_empty() {
@@ -726,11 +772,6 @@ pub fn rename_sheet(state: State<AppState>, index: usize, new_name: String) -> R
     let old_name = sheet_names[index].clone();
     sheet_names[index] = trimmed_name.clone();
 
-    // Sync current grid before repairing formulas
-    if active_sheet < grids.len() {
-        grids[active_sheet] = current_grid.clone();
-    }
-
     // Repair cross-sheet and 3D reference bookends in all formulas
     let old = old_name.clone();
     let new_n = trimmed_name.clone();
@@ -738,15 +779,20 @@ pub fn rename_sheet(state: State<AppState>, index: usize, new_name: String) -> R
         Some(crate::repair_3d_refs_on_rename(formula, &old, &new_n))
     });
 
-    // Sync back the active grid
-    if active_sheet < grids.len() {
-        *current_grid = grids[active_sheet].clone();
-    }
+    // Internal hyperlinks name their target sheet
+    crate::hyperlinks::rename_target_sheet(&mut hyperlinks, &old_name, &trimmed_name);
 
-    Ok(SheetsResult {
+    SheetsResult {
         sheets: build_sheet_list(&sheet_names, &freeze_configs, &tab_colors, &sheet_visibility),
         active_index: active_sheet,
-    })
+        revision: workbook_revision(&state),
+    }
+    }; // drop all locks before rebuilding dependency maps
+
+    // Cross-sheet edges are keyed by sheet name.
+    crate::undo_commands::rebuild_cross_sheet_dependencies(&state);
+
+    Ok(result)
 }
 
 #[tauri::command]
@@ -778,6 +824,7 @@ pub fn set_freeze_panes(
     let result = SheetsResult {
         sheets: build_sheet_list(&sheet_names, &freeze_configs, &tab_colors, &sheet_visibility),
         active_index: active_sheet,
+        revision: workbook_revision(&state),
     };
     drop(freeze_configs);
     crate::undo_commands::record_freeze_undo(&state, active_sheet, previous, "Freeze panes");
@@ -838,10 +885,10 @@ pub fn move_sheet(
     from_index: usize,
     to_index: usize,
 ) -> Result<SheetsResult, String> {
+    let result = {
     let mut sheet_names = state.sheet_names.lock().unwrap();
     let mut grids = state.grids.lock().unwrap();
     let mut active_sheet = state.active_sheet.lock().unwrap();
-    let mut current_grid = state.grid.lock().unwrap();
     let mut freeze_configs = state.freeze_configs.lock().unwrap();
     let mut tab_colors = state.tab_colors.lock().unwrap();
     let mut sheet_visibility = state.sheet_visibility.lock().unwrap();
@@ -852,24 +899,18 @@ pub fn move_sheet(
     let mut page_setups = state.page_setups.lock().unwrap();
 
     let count = sheet_names.len();
-    if from_index >= count {
-        return Err(format!("Source sheet index {} out of range", from_index));
-    }
-    if to_index >= count {
-        return Err(format!("Target sheet index {} out of range", to_index));
-    }
+    check_sheet_index(count, from_index)?;
+    check_sheet_index(count, to_index)?;
     if from_index == to_index {
         return Ok(SheetsResult {
             sheets: build_sheet_list(&sheet_names, &freeze_configs, &tab_colors, &sheet_visibility),
             active_index: *active_sheet,
+            revision: workbook_revision(&state),
         });
     }
 
-    // Sync active grid to storage first
+    // Save the active sheet's dimensions to storage first
     let old_active = *active_sheet;
-    if old_active < grids.len() {
-        grids[old_active] = current_grid.clone();
-    }
     ensure_vec_len(&mut all_column_widths, count);
     ensure_vec_len(&mut all_row_heights, count);
     if old_active < all_column_widths.len() {
@@ -920,11 +961,14 @@ pub fn move_sheet(
     rotate_element(&mut *all_row_heights, from_index, to_index);
     rotate_element(&mut *page_setups, from_index, to_index);
     {
-        let mut gridlines = state.show_gridlines.lock().unwrap();
-        while gridlines.len() < count {
-            gridlines.push(true);
-        }
-        rotate_element(&mut *gridlines, from_index, to_index);
+        let mut views = state.sheet_view_options.lock().unwrap();
+        ensure_vec_len_with(&mut *views, count, SheetViewOptions::default);
+        rotate_element(&mut *views, from_index, to_index);
+    }
+    {
+        let mut defaults = state.sheet_defaults.lock().unwrap();
+        ensure_vec_len(&mut defaults, count);
+        rotate_element(&mut *defaults, from_index, to_index);
     }
     {
         let mut all_merged = state.all_merged_regions.lock().unwrap();
@@ -954,7 +998,6 @@ pub fn move_sheet(
     };
 
     *active_sheet = new_active;
-    *current_grid = grids[new_active].clone();
     *column_widths = std::mem::take(&mut all_column_widths[new_active]);
     *row_heights = std::mem::take(&mut all_row_heights[new_active]);
     {
@@ -1006,10 +1049,18 @@ pub fn move_sheet(
         remap_sheet_keyed_stores(&state, |i| Some(remap(i)));
     }
 
-    Ok(SheetsResult {
+    SheetsResult {
         sheets: build_sheet_list(&sheet_names, &freeze_configs, &tab_colors, &sheet_visibility),
         active_index: new_active,
-    })
+        revision: workbook_revision(&state),
+    }
+    }; // drop all locks before rebuilding dependency maps
+
+    // Formula cells moved to new sheet indices, and 3D references cover
+    // whichever sheets now lie between their bookends.
+    crate::undo_commands::rebuild_cross_sheet_dependencies(&state);
+
+    Ok(result)
 }
 
 /// Copy a sheet to a new position.
@@ -1019,10 +1070,10 @@ pub fn copy_sheet(
     source_index: usize,
     new_name: Option<String>,
 ) -> Result<SheetsResult, String> {
+    let result = {
     let mut sheet_names = state.sheet_names.lock().unwrap();
     let mut grids = state.grids.lock().unwrap();
     let mut active_sheet = state.active_sheet.lock().unwrap();
-    let mut current_grid = state.grid.lock().unwrap();
     let mut freeze_configs = state.freeze_configs.lock().unwrap();
     let mut tab_colors = state.tab_colors.lock().unwrap();
     let mut sheet_visibility = state.sheet_visibility.lock().unwrap();
@@ -1033,15 +1084,10 @@ pub fn copy_sheet(
     let mut page_setups = state.page_setups.lock().unwrap();
 
     let count = sheet_names.len();
-    if source_index >= count {
-        return Err(format!("Source sheet index {} out of range", source_index));
-    }
+    check_sheet_index(count, source_index)?;
 
-    // Sync active grid
+    // Save the active sheet's dimensions
     let old_active = *active_sheet;
-    if old_active < grids.len() {
-        grids[old_active] = current_grid.clone();
-    }
     ensure_vec_len(&mut all_column_widths, count);
     ensure_vec_len(&mut all_row_heights, count);
     if old_active < all_column_widths.len() {
@@ -1084,7 +1130,7 @@ pub fn copy_sheet(
     // Insert right after the source
     let insert_at = source_index + 1;
     sheet_names.insert(insert_at, copy_name);
-    grids.insert(insert_at, cloned_grid.clone());
+    grids.insert(insert_at, cloned_grid);
     freeze_configs.insert(insert_at, cloned_freeze);
     {
         let mut split_configs = state.split_configs.lock().unwrap();
@@ -1107,12 +1153,18 @@ pub fn copy_sheet(
         sheet_ids.insert(insert_at, identity::SheetId::from_bytes(identity::generate_uuid_v7()));
     }
     {
-        let mut gridlines = state.show_gridlines.lock().unwrap();
-        while gridlines.len() < count {
-            gridlines.push(true);
-        }
-        let cloned_gridlines = gridlines[source_index];
-        gridlines.insert(insert_at, cloned_gridlines);
+        // The copy keeps the source's zoom, gridlines, headings and direction
+        let mut views = state.sheet_view_options.lock().unwrap();
+        ensure_vec_len_with(&mut *views, count, SheetViewOptions::default);
+        let cloned_view = views[source_index].clone();
+        views.insert(insert_at, cloned_view);
+    }
+    {
+        // ...and its default sizes
+        let mut defaults = state.sheet_defaults.lock().unwrap();
+        ensure_vec_len(&mut defaults, count);
+        let cloned_defaults = defaults[source_index];
+        defaults.insert(insert_at, cloned_defaults);
     }
     all_column_widths.insert(insert_at, cloned_widths);
     all_row_heights.insert(insert_at, cloned_heights);
@@ -1129,7 +1181,6 @@ pub fn copy_sheet(
     // Switch to the new copy
     let new_index = insert_at;
     *active_sheet = new_index;
-    *current_grid = cloned_grid;
     *column_widths = std::mem::take(&mut all_column_widths[new_index]);
     *row_heights = std::mem::take(&mut all_row_heights[new_index]);
     {
@@ -1171,12 +1222,23 @@ pub fn copy_sheet(
         remap_sheet_keyed_stores(&state, |i| {
             Some(if i >= insert_at { i + 1 } else { i })
         });
+        // Unlike the stores above, the copy keeps the source's print area and
+        // print titles (they travel with the page setup).
+        crate::named_ranges::copy_builtin_names(&mut state.named_ranges.lock().unwrap(), source_index, insert_at);
     }
 
-    Ok(SheetsResult {
+    SheetsResult {
         sheets: build_sheet_list(&sheet_names, &freeze_configs, &tab_colors, &sheet_visibility),
         active_index: new_index,
-    })
+        revision: workbook_revision(&state),
+    }
+    }; // drop all locks before rebuilding dependency maps
+
+    // The copy's formulas gain cross-sheet edges, later sheets shift index,
+    // and 3D references may now cover the copy.
+    crate::undo_commands::rebuild_cross_sheet_dependencies(&state);
+
+    Ok(result)
 }
 
 /// Hide a sheet. Cannot hide the last visible sheet.
@@ -1195,9 +1257,7 @@ pub fn hide_sheet(
     let tab_colors = state.tab_colors.lock().unwrap();
     let mut sheet_visibility = state.sheet_visibility.lock().unwrap();
 
-    if index >= sheet_names.len() {
-        return Err(format!("Sheet index {} out of range", index));
-    }
+    check_sheet_index(sheet_names.len(), index)?;
 
     let hide_level = level.unwrap_or_else(|| "hidden".to_string());
     if hide_level != "hidden" && hide_level != "veryHidden" {
@@ -1228,6 +1288,7 @@ pub fn hide_sheet(
     Ok(SheetsResult {
         sheets: build_sheet_list(&sheet_names, &freeze_configs, &tab_colors, &sheet_visibility),
         active_index: recommended_active,
+        revision: workbook_revision(&state),
     })
 }
 
@@ -1243,9 +1304,7 @@ pub fn unhide_sheet(
     let tab_colors = state.tab_colors.lock().unwrap();
     let mut sheet_visibility = state.sheet_visibility.lock().unwrap();
 
-    if index >= sheet_names.len() {
-        return Err(format!("Sheet index {} out of range", index));
-    }
+    check_sheet_index(sheet_names.len(), index)?;
 
     ensure_vec_len(&mut sheet_visibility, sheet_names.len());
     sheet_visibility[index] = "visible".to_string();
@@ -1253,6 +1312,7 @@ pub fn unhide_sheet(
     Ok(SheetsResult {
         sheets: build_sheet_list(&sheet_names, &freeze_configs, &tab_colors, &sheet_visibility),
         active_index: active_sheet,
+        revision: workbook_revision(&state),
     })
 }
 
@@ -1269,9 +1329,7 @@ pub fn set_tab_color(
     let mut tab_colors = state.tab_colors.lock().unwrap();
     let sheet_visibility = state.sheet_visibility.lock().unwrap();
 
-    if index >= sheet_names.len() {
-        return Err(format!("Sheet index {} out of range", index));
-    }
+    check_sheet_index(sheet_names.len(), index)?;
 
     ensure_vec_len(&mut tab_colors, sheet_names.len());
     tab_colors[index] = color;
@@ -1279,6 +1337,7 @@ pub fn set_tab_color(
     Ok(SheetsResult {
         sheets: build_sheet_list(&sheet_names, &freeze_configs, &tab_colors, &sheet_visibility),
         active_index: active_sheet,
+        revision: workbook_revision(&state),
     })
 }
 
@@ -3,6 +3,7 @@
 //! CONTEXT: Manages slicer state and bridges to table/pivot data sources.
 
 use crate::pivot::PivotState;
+use crate::sheet_access::sheet_at;
 use crate::slicer::types::*;
 use crate::{format_cell_value, AppState};
 use std::collections::HashMap;
@@ -664,10 +665,7 @@ fn get_table_column_values(state: &State<AppState>, source_id: identity::EntityI
         table.start_row
     };
 
-    if table.sheet_index >= grids.len() {
-        return Err("Invalid sheet index".to_string());
-    }
-    let grid = &grids[table.sheet_index];
+    let grid = sheet_at(&grids, table.sheet_index)?;
 
     let mut seen = HashMap::new();
     for row in data_start_row..=table.end_row {
@@ -732,10 +730,7 @@ fn get_table_available_values(
         table.start_row
     };
 
-    if table.sheet_index >= grids.len() {
-        return Err("Invalid sheet index".to_string());
-    }
-    let grid = &grids[table.sheet_index];
+    let grid = sheet_at(&grids, table.sheet_index)?;
 
     let mut available = std::collections::HashSet::new();
 
@@ -16,6 +16,7 @@ use crate::{
     evaluate_formula_multi_sheet, format_cell_value, get_column_row_dependents,
     get_recalculation_order, AppState,
 };
+use crate::sheet_access::check_sheet_index;
 use engine::{Cell, CellValue, Grid, StyleRegistry};
 
 // ============================================================================
@@ -54,6 +55,7 @@ fn build_cell_data(
         sheet_index: None,
         rich_text: None,
         accounting_layout: None,
+        error_detail: None,
     })
 }
 
@@ -70,6 +72,7 @@ fn set_variables_and_evaluate(
     values: &[f64],
     style_indices: &[usize],
     objective_formula: &str,
+    objective_pos: (u32, u32),
 ) -> Option<f64> {
     // Set all variable cells
     for (i, var) in variables.iter().enumerate() {
@@ -78,8 +81,14 @@ fn set_variables_and_evaluate(
         grids[sheet_idx].set_cell(var.row, var.col, cell);
     }
 
-    // Evaluate objective
-    let result = evaluate_formula_multi_sheet(grids, sheet_names, sheet_idx, objective_formula);
+    // Evaluate objective at its own cell
+    let result = evaluate_formula_multi_sheet(
+        grids,
+        sheet_names,
+        sheet_idx,
+        objective_formula,
+        engine::EvalContext::at(objective_pos.0, objective_pos.1),
+    );
     match result {
         CellValue::Number(n) if n.is_finite() => Some(n),
         _ => None,
@@ -96,7 +105,13 @@ fn evaluate_constraint_cell(
 ) -> f64 {
     if let Some(cell) = grids[sheet_idx].get_cell(row, col) {
         if let Some(formula) = cell.formula_string() {
-            match evaluate_formula_multi_sheet(grids, sheet_names, sheet_idx, &formula) {
+            match evaluate_formula_multi_sheet(
+                grids,
+                sheet_names,
+                sheet_idx,
+                &formula,
+                engine::EvalContext::at(row, col),
+            ) {
                 CellValue::Number(n) => return n,
                 _ => {}
             }
@@ -238,6 +253,7 @@ fn solve_grg(
     variables: &[SolverVariableCell],
     style_indices: &[usize],
     objective_formula: &str,
+    objective_pos: (u32, u32),
     objective: SolverObjective,
     target_value: Option<f64>,
     constraints: &[SolverConstraint],
@@ -266,7 +282,7 @@ fn solve_grg(
 
     let eval_objective = |grids: &mut [Grid], x: &[f64]| -> f64 {
         let val = set_variables_and_evaluate(
-            grids, sheet_names, sheet_idx, variables, x, style_indices, objective_formula,
+            grids, sheet_names, sheet_idx, variables, x, style_indices, objective_formula, objective_pos,
         )
         .unwrap_or(f64::MAX);
 
@@ -348,12 +364,12 @@ fn solve_grg(
 
     // Apply best solution
     set_variables_and_evaluate(
-        grids, sheet_names, sheet_idx, variables, &best_x, style_indices, objective_formula,
+        grids, sheet_names, sheet_idx, variables, &best_x, style_indices, objective_formula, objective_pos,
     );
 
     let feasible = check_constraints(grids, sheet_names, sheet_idx, constraints, variables, &best_x);
     let final_obj = set_variables_and_evaluate(
-        grids, sheet_names, sheet_idx, variables, &best_x, style_indices, objective_formula,
+        grids, sheet_names, sheet_idx, variables, &best_x, style_indices, objective_formula, objective_pos,
     )
     .unwrap_or(f64::NAN);
 
@@ -379,6 +395,7 @@ fn solve_simplex(
     variables: &[SolverVariableCell],
     style_indices: &[usize],
     objective_formula: &str,
+    objective_pos: (u32, u32),
     objective: SolverObjective,
     target_value: Option<f64>,
     constraints: &[SolverConstraint],
@@ -390,7 +407,7 @@ fn solve_simplex(
     // We fall back to the GRG method since it handles both cases.
     solve_grg(
         grids, sheet_names, sheet_idx, variables, style_indices,
-        objective_formula, objective, target_value, constraints,
+        objective_formula, objective_pos, objective, target_value, constraints,
         max_iterations, tolerance,
     )
 }
@@ -408,6 +425,7 @@ fn solve_evolutionary(
     variables: &[SolverVariableCell],
     style_indices: &[usize],
     objective_formula: &str,
+    objective_pos: (u32, u32),
     objective: SolverObjective,
     target_value: Option<f64>,
     constraints: &[SolverConstraint],
@@ -441,7 +459,7 @@ fn solve_evolutionary(
 
     let eval = |grids: &mut [Grid], x: &[f64]| -> f64 {
         let val = set_variables_and_evaluate(
-            grids, sheet_names, sheet_idx, variables, x, style_indices, objective_formula,
+            grids, sheet_names, sheet_idx, variables, x, style_indices, objective_formula, objective_pos,
         )
         .unwrap_or(f64::MAX);
 
@@ -563,12 +581,12 @@ fn solve_evolutionary(
 
     // Apply best solution
     set_variables_and_evaluate(
-        grids, sheet_names, sheet_idx, variables, &best_x, style_indices, objective_formula,
+        grids, sheet_names, sheet_idx, variables, &best_x, style_indices, objective_formula, objective_pos,
     );
 
     let feasible = check_constraints(grids, sheet_names, sheet_idx, constraints, variables, &best_x);
     let final_obj = set_variables_and_evaluate(
-        grids, sheet_names, sheet_idx, variables, &best_x, style_indices, objective_formula,
+        grids, sheet_names, sheet_idx, variables, &best_x, style_indices, objective_formula, objective_pos,
     )
     .unwrap_or(f64::NAN);
 
@@ -635,9 +653,7 @@ pub fn solver_solve(
     }
 
     // Acquire locks
-    let mut grid = state.grid.lock().unwrap();
     let mut grids = state.grids.lock().unwrap();
-    let active_sheet = *state.active_sheet.lock().unwrap();
     let sheet_names = state.sheet_names.lock().unwrap();
     let styles = state.style_registry.lock().unwrap();
     let dependents_map = state.dependents.lock().unwrap();
@@ -647,6 +663,9 @@ pub fn solver_solve(
     let locale = state.locale.lock().unwrap();
 
     let sheet_idx = params.sheet_index;
+    if let Err(e) = check_sheet_index(grids.len(), sheet_idx) {
+        return failed_result(e.to_string());
+    }
 
     // Validate objective cell has a formula
     let objective_formula = match grids[sheet_idx]
@@ -721,6 +740,7 @@ pub fn solver_solve(
             &params.variable_cells,
             &style_indices,
             &objective_formula,
+            (params.objective_row, params.objective_col),
             params.objective,
             params.target_value,
             &params.constraints,
@@ -734,6 +754,7 @@ pub fn solver_solve(
             &params.variable_cells,
             &style_indices,
             &objective_formula,
+            (params.objective_row, params.objective_col),
             params.objective,
             params.target_value,
             &params.constraints,
@@ -747,6 +768,7 @@ pub fn solver_solve(
             &params.variable_cells,
             &style_indices,
             &objective_formula,
+            (params.objective_row, params.objective_col),
             params.objective,
             params.target_value,
             &params.constraints,
@@ -755,15 +777,6 @@ pub fn solver_solve(
         ),
     };
 
-    // Sync grid if on active sheet
-    if sheet_idx == active_sheet {
-        for var in params.variable_cells.iter() {
-            if let Some(cell) = grids[sheet_idx].get_cell(var.row, var.col).cloned() {
-                grid.set_cell(var.row, var.col, cell);
-            }
-        }
-    }
-
     // Re-evaluate all dependents
     let mut all_deps = Vec::new();
     for var in &params.variable_cells {
@@ -783,14 +796,16 @@ pub fn solver_solve(
     for &(r, c) in &all_deps {
         if let Some(cell) = grids[sheet_idx].get_cell(r, c).cloned() {
             if let Some(formula) = cell.formula_string() {
-                let new_value =
-                    evaluate_formula_multi_sheet(&grids, &sheet_names, sheet_idx, &formula);
+                let new_value = evaluate_formula_multi_sheet(
+                    &grids,
+                    &sheet_names,
+                    sheet_idx,
+                    &formula,
+                    engine::EvalContext::at(r, c),
+                );
                 let mut updated = cell;
                 updated.value = new_value;
-                grids[sheet_idx].set_cell(r, c, updated.clone());
-                if sheet_idx == active_sheet {
-                    grid.set_cell(r, c, updated);
-                }
+                grids[sheet_idx].set_cell(r, c, updated);
             }
         }
     }
@@ -855,6 +870,20 @@ pub fn solver_solve(
     }
 }
 
+/// A result that reports `msg` without touching the sheet.
+fn failed_result(msg: String) -> SolverResult {
+    SolverResult {
+        found_solution: false,
+        objective_value: f64::NAN,
+        variable_values: Vec::new(),
+        iterations: 0,
+        status_message: msg.clone(),
+        updated_cells: Vec::new(),
+        original_values: Vec::new(),
+        error: Some(msg),
+    }
+}
+
 /// Revert solver results to original values.
 #[tauri::command]
 pub fn solver_revert(
@@ -862,9 +891,7 @@ pub fn solver_revert(
     sheet_index: usize,
     original_values: Vec<SolverVariableValue>,
 ) -> SolverResult {
-    let mut grid = state.grid.lock().unwrap();
     let mut grids = state.grids.lock().unwrap();
-    let active_sheet = *state.active_sheet.lock().unwrap();
     let sheet_names = state.sheet_names.lock().unwrap();
     let styles = state.style_registry.lock().unwrap();
     let dependents_map = state.dependents.lock().unwrap();
@@ -873,6 +900,10 @@ pub fn solver_revert(
     let merged_regions = state.merged_regions.lock().unwrap();
     let locale = state.locale.lock().unwrap();
 
+    if let Err(e) = check_sheet_index(grids.len(), sheet_index) {
+        return failed_result(e.to_string());
+    }
+
     // Restore original values
     for orig in &original_values {
         let style_index = grids[sheet_index]
@@ -880,10 +911,7 @@ pub fn solver_revert(
             .map_or(0, |c| c.style_index);
         let mut cell = Cell::new_number(orig.value);
         cell.style_index = style_index;
-        grids[sheet_index].set_cell(orig.row, orig.col, cell.clone());
-        if sheet_index == active_sheet {
-            grid.set_cell(orig.row, orig.col, cell);
-        }
+        grids[sheet_index].set_cell(orig.row, orig.col, cell);
     }
 
     // Recalculate dependents
@@ -905,14 +933,16 @@ pub fn solver_revert(
     for &(r, c) in &all_deps {
         if let Some(cell) = grids[sheet_index].get_cell(r, c).cloned() {
             if let Some(formula) = cell.formula_string() {
-                let new_value =
-                    evaluate_formula_multi_sheet(&grids, &sheet_names, sheet_index, &formula);
+                let new_value = evaluate_formula_multi_sheet(
+                    &grids,
+                    &sheet_names,
+                    sheet_index,
+                    &formula,
+                    engine::EvalContext::at(r, c),
+                );
                 let mut updated = cell;
                 updated.value = new_value;
-                grids[sheet_index].set_cell(r, c, updated.clone());
-                if sheet_index == active_sheet {
-                    grid.set_cell(r, c, updated);
-                }
+                grids[sheet_index].set_cell(r, c, updated);
             }
         }
     }
@@ -123,6 +123,8 @@ pub struct WorkbookStateDigest {
     pub sheet_protection: BTreeMap<String, Value>,
     /// "sheet:row:col" -> CellProtection.
     pub cell_protection: BTreeMap<String, Value>,
+    /// Sheet index -> RangeProtection records, in apply order.
+    pub range_protection: BTreeMap<String, Value>,
     pub workbook_protection: Value,
     /// Sheet index -> hidden row indices (advanced filter), sorted.
     pub advanced_filter_hidden_rows: BTreeMap<String, Vec<u32>>,
@@ -132,6 +134,7 @@ pub struct WorkbookStateDigest {
     pub pivot_layouts: Vec<Value>,
     pub object_scripts: Vec<Value>,
     pub theme: Value,
+    pub calculation_settings: Value,
     pub defaults: Value,
 }
 
@@ -193,9 +196,8 @@ fn digest_cells(
 
 /// Build a canonical digest of the full workbook state for testing oracles.
 ///
-/// Reads the active sheet from the `state.grid` mirror (NOT `grids[active]`,
-/// which is stale — see get_watch_cells in commands/data.rs) and all other
-/// sheets from `state.grids`.
+/// Reads every sheet's cells from `state.grids`; the active sheet's column
+/// widths, row heights and merges come from their active-sheet copies.
 #[tauri::command]
 pub fn get_workbook_state_digest(
     state: State<AppState>,
@@ -218,7 +220,6 @@ pub fn get_workbook_state_digest(
     // ---- Per-sheet content ----
     {
         let grids = state.grids.lock().map_err(|e| e.to_string())?;
-        let active_grid = state.grid.lock().map_err(|e| e.to_string())?;
         let styles = state.style_registry.lock().map_err(|e| e.to_string())?;
         let all_cw = state.all_column_widths.lock().map_err(|e| e.to_string())?;
         let all_rh = state.all_row_heights.lock().map_err(|e| e.to_string())?;
@@ -230,20 +231,12 @@ pub fn get_workbook_state_digest(
         let split_configs = state.split_configs.lock().map_err(|e| e.to_string())?;
         let tab_colors = state.tab_colors.lock().map_err(|e| e.to_string())?;
         let visibility = state.sheet_visibility.lock().map_err(|e| e.to_string())?;
-        let gridlines = state.show_gridlines.lock().map_err(|e| e.to_string())?;
+        let views = state.sheet_view_options.lock().map_err(|e| e.to_string())?;
         let page_setups = state.page_setups.lock().map_err(|e| e.to_string())?;
         let scroll_areas = state.scroll_areas.lock().map_err(|e| e.to_string())?;
 
         for i in 0..sheet_count {
-            // The active-sheet mirror is authoritative for the active sheet.
-            let grid: &engine::Grid = if i == active_sheet {
-                &active_grid
-            } else {
-                match grids.get(i) {
-                    Some(g) => g,
-                    None => continue,
-                }
-            };
+            let Some(grid) = grids.get(i) else { continue };
 
             let cells = digest_cells(grid, &styles, &locale, &mut used_styles);
 
@@ -314,7 +307,7 @@ pub fn get_workbook_state_digest(
                     .get(i)
                     .cloned()
                     .unwrap_or_else(|| "visible".to_string()),
-                show_gridlines: gridlines.get(i).copied().unwrap_or(true),
+                show_gridlines: views.get(i).map_or(true, |v| v.show_gridlines),
                 page_setup: page_setups
                     .get(i)
                     .map(to_value_or_null)
@@ -354,12 +347,14 @@ pub fn get_workbook_state_digest(
         computed_properties: BTreeMap::new(),
         sheet_protection: BTreeMap::new(),
         cell_protection: BTreeMap::new(),
+        range_protection: BTreeMap::new(),
         workbook_protection: Value::Null,
         advanced_filter_hidden_rows: BTreeMap::new(),
         protected_regions: Vec::new(),
         pivot_layouts: Vec::new(),
         object_scripts: Vec::new(),
         theme: Value::Null,
+        calculation_settings: Value::Null,
         defaults: Value::Null,
     };
 
@@ -560,6 +555,15 @@ pub fn get_workbook_state_digest(
             }
         }
     }
+    if let Ok(range_protection) = state.range_protection.lock() {
+        for (sheet, ranges) in range_protection.iter() {
+            if !ranges.is_empty() {
+                digest
+                    .range_protection
+                    .insert(sheet.to_string(), to_value_or_null(ranges));
+            }
+        }
+    }
     if let Ok(wp) = state.workbook_protection.lock() {
         digest.workbook_protection = to_value_or_null(&*wp);
     }
@@ -600,13 +604,18 @@ pub fn get_workbook_state_digest(
     if let Ok(theme) = state.theme.lock() {
         digest.theme = to_value_or_null(&*theme);
     }
+    if let Ok(settings) = state.calculation_settings.lock() {
+        digest.calculation_settings = to_value_or_null(&*settings);
+    }
 
     let default_row_height = *state.default_row_height.lock().map_err(|e| e.to_string())?;
     let default_column_width = *state.default_column_width.lock().map_err(|e| e.to_string())?;
+    let sheet_defaults = state.sheet_defaults.lock().map_err(|e| e.to_string())?.clone();
     let reference_style = state.reference_style.lock().map_err(|e| e.to_string())?.clone();
     digest.defaults = serde_json::json!({
         "defaultRowHeight": default_row_height,
         "defaultColumnWidth": default_column_width,
+        "sheetDefaults": sheet_defaults,
         "referenceStyle": reference_style,
     });
 
@@ -0,0 +1,933 @@
+//! FILENAME: app/src-tauri/src/structural_change.rs
+//! PURPOSE: One notification for row/column insertion and deletion, dispatched
+//! to every feature store that keeps its own sheet coordinates.
+//! CONTEXT: insert_rows/delete_rows/insert_columns/delete_columns shift the
+//! grid and the dependency maps themselves; everything else (named ranges,
+//! conditional formats, validations, merges, tables, comments, notes,
+//! hyperlinks and their internal targets, autofilters, outlines, protected
+//! regions and pivot definitions)
+//! goes through `ApplyStructuralChange` so every store follows the same rules:
+//!
+//! - Insert: spans starting at or after the insertion point shift; spans that
+//!   straddle it (start < at <= end) grow.
+//! - Delete: spans fully inside the deleted band are dropped; spans partially
+//!   inside are clamped to what survives; spans after it shift back.
+//!
+//! The pre-change state of every store is captured in one
+//! `"obj_structural_features"` CustomRestore recorded in the same undo
+//! transaction as the grid snapshot, so one undo restores everything.
+
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+
+use crate::api_types::MergedRegion;
+use crate::autofilter::{AutoFilter, AutoFilterStorage};
+use crate::comments::{Comment, CommentStorage};
+use crate::conditional_formatting::{ConditionalFormatDefinition, ConditionalFormatStorage};
+use crate::data_validation::{ValidationRange, ValidationStorage};
+use crate::grouping::{OutlineStorage, SheetOutline};
+use crate::hyperlinks::{Hyperlink, HyperlinkStorage, TargetRange};
+use crate::named_ranges::NamedRange;
+use crate::notes::{Note, NoteStorage};
+use crate::pivot::types::PivotState;
+use crate::tables::{Table, TableStorage};
+use crate::{AppState, ProtectedRegion};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Axis {
+    Rows,
+    Columns,
+}
+
+/// A row/column insertion (`count > 0`) or deletion (`count < 0`) of
+/// `|count|` lines starting at 0-based index `at` on sheet `sheet`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StructuralChange {
+    pub sheet: usize,
+    /// Name of `sheet`, for stores that hold sheet-qualified reference text.
+    pub sheet_name: String,
+    pub axis: Axis,
+    pub at: u32,
+    pub count: i32,
+}
+
+impl StructuralChange {
+    pub fn insert(sheet: usize, sheet_name: &str, axis: Axis, at: u32, count: u32) -> Self {
+        Self { sheet, sheet_name: sheet_name.to_string(), axis, at, count: count as i32 }
+    }
+
+    pub fn delete(sheet: usize, sheet_name: &str, axis: Axis, at: u32, count: u32) -> Self {
+        Self { sheet, sheet_name: sheet_name.to_string(), axis, at, count: -(count as i32) }
+    }
+
+    pub fn is_insert(&self) -> bool {
+        self.count > 0
+    }
+
+    fn magnitude(&self) -> u32 {
+        self.count.unsigned_abs()
+    }
+
+    /// New position of one row/column index; None when it was deleted.
+    pub fn map_index(&self, index: u32) -> Option<u32> {
+        let n = self.magnitude();
+        if index < self.at {
+            Some(index)
+        } else if self.is_insert() {
+            Some(index.saturating_add(n))
+        } else if index < self.at.saturating_add(n) {
+            None
+        } else {
+            Some(index - n)
+        }
+    }
+
+    /// New bounds of an inclusive span along the change axis; None when the
+    /// whole span was deleted.
+    pub fn map_span(&self, start: u32, end: u32) -> Option<(u32, u32)> {
+        let n = self.magnitude();
+        if end < self.at {
+            return Some((start, end));
+        }
+        if self.is_insert() {
+            return if start >= self.at {
+                Some((start.saturating_add(n), end.saturating_add(n)))
+            } else {
+                Some((start, end.saturating_add(n)))
+            };
+        }
+        let delete_end = self.at.saturating_add(n);
+        if start >= delete_end {
+            Some((start - n, end - n))
+        } else if start >= self.at {
+            if end < delete_end {
+                None
+            } else {
+                Some((self.at, end - n))
+            }
+        } else if end >= delete_end {
+            Some((start, end - n))
+        } else {
+            Some((start, self.at - 1))
+        }
+    }
+
+    /// New bounds of an inclusive rectangle; None when it was deleted.
+    pub fn map_rect(&self, start_row: u32, start_col: u32, end_row: u32, end_col: u32) -> Option<(u32, u32, u32, u32)> {
+        match self.axis {
+            Axis::Rows => self
+                .map_span(start_row, end_row)
+                .map(|(sr, er)| (sr, start_col, er, end_col)),
+            Axis::Columns => self
+                .map_span(start_col, end_col)
+                .map(|(sc, ec)| (start_row, sc, end_row, ec)),
+        }
+    }
+
+    /// New position of a single cell; None when its row/column was deleted.
+    pub fn map_cell(&self, row: u32, col: u32) -> Option<(u32, u32)> {
+        match self.axis {
+            Axis::Rows => self.map_index(row).map(|r| (r, col)),
+            Axis::Columns => self.map_index(col).map(|c| (row, c)),
+        }
+    }
+}
+
+/// Implemented by every store that holds sheet coordinates of its own.
+/// Returns true when anything in the store changed.
+pub trait ApplyStructuralChange {
+    fn apply_structural_change(&mut self, change: &StructuralChange) -> bool;
+}
+
+impl ApplyStructuralChange for ConditionalFormatStorage {
+    fn apply_structural_change(&mut self, change: &StructuralChange) -> bool {
+        let Some(rules) = self.get_mut(&change.sheet) else { return false };
+        let mut changed = false;
+        for rule in rules.iter_mut() {
+            let before = rule.ranges.len();
+            rule.ranges.retain_mut(|range| {
+                match change.map_rect(range.start_row, range.start_col, range.end_row, range.end_col) {
+                    Some((sr, sc, er, ec)) => {
+                        changed |= (sr, sc, er, ec) != (range.start_row, range.start_col, range.end_row, range.end_col);
+                        range.start_row = sr;
+                        range.start_col = sc;
+                        range.end_row = er;
+                        range.end_col = ec;
+                        true
+                    }
+                    None => false,
+                }
+            });
+            changed |= rule.ranges.len() != before;
+        }
+        // A rule whose every range was deleted has nothing left to format.
+        let before = rules.len();
+        rules.retain(|rule| !rule.ranges.is_empty());
+        changed | (rules.len() != before)
+    }
+}
+
+impl ApplyStructuralChange for ValidationStorage {
+    fn apply_structural_change(&mut self, change: &StructuralChange) -> bool {
+        let Some(ranges) = self.get_mut(&change.sheet) else { return false };
+        let mut changed = false;
+        ranges.retain_mut(|range| {
+            match change.map_rect(range.start_row, range.start_col, range.end_row, range.end_col) {
+                Some((sr, sc, er, ec)) => {
+                    changed |= (sr, sc, er, ec) != (range.start_row, range.start_col, range.end_row, range.end_col);
+                    range.start_row = sr;
+                    range.start_col = sc;
+                    range.end_row = er;
+                    range.end_col = ec;
+                    true
+                }
+                None => {
+                    changed = true;
+                    false
+                }
+            }
+        });
+        changed
+    }
+}
+
+/// Merged regions of the ACTIVE sheet (the `merged_regions` mirror carries no
+/// sheet index; callers only pass it for the active sheet). A merge that
+/// shrinks to a single cell is dropped.
+impl ApplyStructuralChange for HashSet<MergedRegion> {
+    fn apply_structural_change(&mut self, change: &StructuralChange) -> bool {
+        let old: Vec<MergedRegion> = self.drain().collect();
+        let mut changed = false;
+        for region in old {
+            match change.map_rect(region.start_row, region.start_col, region.end_row, region.end_col) {
+                Some((sr, sc, er, ec)) if sr != er || sc != ec => {
+                    changed |= (sr, sc, er, ec) != (region.start_row, region.start_col, region.end_row, region.end_col);
+                    self.insert(MergedRegion { start_row: sr, start_col: sc, end_row: er, end_col: ec });
+                }
+                _ => changed = true,
+            }
+        }
+        changed
+    }
+}
+
+/// Re-key a per-sheet cell map after a structural change; entries on deleted
+/// rows/columns are dropped. `set_pos` updates the value's own coordinates.
+fn rekey_cell_map<V>(
+    cells: &mut HashMap<(u32, u32), V>,
+    change: &StructuralChange,
+    set_pos: impl Fn(&mut V, u32, u32),
+) -> bool {
+    let mut changed = false;
+    let old: Vec<((u32, u32), V)> = cells.drain().collect();
+    for ((row, col), mut value) in old {
+        match change.map_cell(row, col) {
+            Some((r, c)) => {
+                changed |= (r, c) != (row, col);
+                set_pos(&mut value, r, c);
+                cells.insert((r, c), value);
+            }
+            None => changed = true,
+        }
+    }
+    changed
+}
+
+impl ApplyStructuralChange for CommentStorage {
+    fn apply_structural_change(&mut self, change: &StructuralChange) -> bool {
+        let Some(cells) = self.get_mut(&change.sheet) else { return false };
+        rekey_cell_map(cells, change, |c: &mut Comment, r, col| {
+            c.row = r;
+            c.col = col;
+        })
+    }
+}
+
+impl ApplyStructuralChange for NoteStorage {
+    fn apply_structural_change(&mut self, change: &StructuralChange) -> bool {
+        let Some(cells) = self.get_mut(&change.sheet) else { return false };
+        rekey_cell_map(cells, change, |n: &mut Note, r, col| {
+            n.row = r;
+            n.col = col;
+        })
+    }
+}
+
+/// Follow an internal link's target through `change` when it points into
+/// the changed sheet (a target without a sheet name points at `own_sheet`,
+/// where the link lives). A target whose cells were all deleted keeps its
+/// text and is flagged broken.
+fn retarget_internal_link(link: &mut Hyperlink, own_sheet: usize, change: &StructuralChange) -> bool {
+    let Some(internal) = &link.internal_ref else { return false };
+    if link.broken {
+        return false;
+    }
+    let on_changed_sheet = match &internal.sheet_name {
+        Some(name) => name.eq_ignore_ascii_case(&change.sheet_name),
+        None => own_sheet == change.sheet,
+    };
+    if !on_changed_sheet {
+        return false;
+    }
+    let Some(mut range) = TargetRange::parse(&internal.cell_reference) else { return false };
+    match change.map_rect(range.start_row, range.start_col, range.end_row, range.end_col) {
+        Some(rect) if rect == (range.start_row, range.start_col, range.end_row, range.end_col) => false,
+        Some((start_row, start_col, end_row, end_col)) => {
+            range.start_row = start_row;
+            range.start_col = start_col;
+            range.end_row = end_row;
+            range.end_col = end_col;
+            let target_sheet = internal.sheet_name.clone();
+            link.set_internal_target(target_sheet, range.render());
+            true
+        }
+        None => {
+            link.broken = true;
+            true
+        }
+    }
+}
+
+impl ApplyStructuralChange for HyperlinkStorage {
+    fn apply_structural_change(&mut self, change: &StructuralChange) -> bool {
+        let mut changed = match self.get_mut(&change.sheet) {
+            Some(cells) => rekey_cell_map(cells, change, |h: &mut Hyperlink, r, col| {
+                h.row = r;
+                h.col = col;
+            }),
+            None => false,
+        };
+        // Internal targets into the changed sheet can live on any sheet.
+        for (&sheet, cells) in self.iter_mut() {
+            for link in cells.values_mut() {
+                changed |= retarget_internal_link(link, sheet, change);
+            }
+        }
+        changed
+    }
+}
+
+impl ApplyStructuralChange for AutoFilterStorage {
+    fn apply_structural_change(&mut self, change: &StructuralChange) -> bool {
+        let Some(filter) = self.get_mut(&change.sheet) else { return false };
+        let Some((sr, sc, er, ec)) =
+            change.map_rect(filter.start_row, filter.start_col, filter.end_row, filter.end_col)
+        else {
+            self.remove(&change.sheet);
+            return true;
+        };
+        let changed = (sr, sc, er, ec) != (filter.start_row, filter.start_col, filter.end_row, filter.end_col);
+        match change.axis {
+            Axis::Rows => {
+                filter.hidden_rows = filter
+                    .hidden_rows
+                    .iter()
+                    .filter_map(|&r| change.map_index(r))
+                    .collect();
+            }
+            Axis::Columns => {
+                // Criteria are keyed by column offset within the range.
+                let old_start = filter.start_col;
+                filter.column_filters = filter
+                    .column_filters
+                    .drain()
+                    .filter_map(|(offset, criteria)| {
+                        change
+                            .map_index(old_start + offset)
+                            .map(|abs| (abs - sc, criteria))
+                    })
+                    .collect();
+            }
+        }
+        filter.start_row = sr;
+        filter.start_col = sc;
+        filter.end_row = er;
+        filter.end_col = ec;
+        changed
+    }
+}
+
+impl ApplyStructuralChange for OutlineStorage {
+    fn apply_structural_change(&mut self, change: &StructuralChange) -> bool {
+        let Some(outline) = self.get_mut(&change.sheet) else { return false };
+        let mut changed = false;
+        match change.axis {
+            Axis::Rows => outline.row_groups.retain_mut(|g| match change.map_span(g.start_row, g.end_row) {
+                Some((s, e)) => {
+                    changed |= (s, e) != (g.start_row, g.end_row);
+                    g.start_row = s;
+                    g.end_row = e;
+                    true
+                }
+                None => {
+                    changed = true;
+                    false
+                }
+            }),
+            Axis::Columns => outline.column_groups.retain_mut(|g| match change.map_span(g.start_col, g.end_col) {
+                Some((s, e)) => {
+                    changed |= (s, e) != (g.start_col, g.end_col);
+                    g.start_col = s;
+                    g.end_col = e;
+                    true
+                }
+                None => {
+                    changed = true;
+                    false
+                }
+            }),
+        }
+        outline.recalculate_max_levels();
+        changed
+    }
+}
+
+/// Tables on the sheet; fully deleted tables are removed (callers prune the
+/// name registry afterwards). Deleting columns inside a table also removes the
+/// matching `TableColumn`s.
+impl ApplyStructuralChange for TableStorage {
+    fn apply_structural_change(&mut self, change: &StructuralChange) -> bool {
+        let Some(tables) = self.get_mut(&change.sheet) else { return false };
+        let mut changed = false;
+        tables.retain(|_, table| {
+            let Some((sr, sc, er, ec)) =
+                change.map_rect(table.start_row, table.start_col, table.end_row, table.end_col)
+            else {
+                changed = true;
+                return false;
+            };
+            if change.axis == Axis::Columns && !change.is_insert() {
+                let old_start = table.start_col;
+                let mut offset = 0u32;
+                table.columns.retain(|_| {
+                    let keep = change.map_index(old_start + offset).is_some();
+                    offset += 1;
+                    keep
+                });
+            }
+            changed |= (sr, sc, er, ec) != (table.start_row, table.start_col, table.end_row, table.end_col);
+            table.start_row = sr;
+            table.start_col = sc;
+            table.end_row = er;
+            table.end_col = ec;
+            true
+        });
+        changed
+    }
+}
+
+/// Protected regions (pivot, report, chart, ... owners). Regions fully inside
+/// a deletion are removed; owners react to that in `dispatch_structural_change`.
+impl ApplyStructuralChange for Vec<ProtectedRegion> {
+    fn apply_structural_change(&mut self, change: &StructuralChange) -> bool {
+        let mut changed = false;
+        self.retain_mut(|region| {
+            if region.sheet_index != change.sheet {
+                return true;
+            }
+            match change.map_rect(region.start_row, region.start_col, region.end_row, region.end_col) {
+                Some((sr, sc, er, ec)) => {
+                    changed |= (sr, sc, er, ec) != (region.start_row, region.start_col, region.end_row, region.end_col);
+                    region.start_row = sr;
+                    region.start_col = sc;
+                    region.end_row = er;
+                    region.end_col = ec;
+                    true
+                }
+                None => {
+                    changed = true;
+                    false
+                }
+            }
+        });
+        changed
+    }
+}
+
+// Sheet-qualified or bare A1 reference (optionally a range) inside a
+// named-range `refers_to` formula, or a whole-row ("$1:$3") or whole-column
+// ("$A:$C") range. Groups: 1 sheet; 2-9 cell range; 10-13 rows; 14-17 columns.
+static NAME_REF_RE: once_cell::sync::Lazy<regex::Regex> = once_cell::sync::Lazy::new(|| {
+    regex::Regex::new(
+        r"(?:('(?:[^']|'')+'|[A-Za-z_][A-Za-z0-9_.]*)!)?(?:(\$?)([A-Za-z]{1,3})(\$?)(\d+)(?::(\$?)([A-Za-z]{1,3})(\$?)(\d+))?|(\$?)(\d+):(\$?)(\d+)\b|(\$?)([A-Za-z]{1,3}):(\$?)([A-Za-z]{1,3})\b)",
+    )
+    .unwrap()
+});
+
+/// Rewrite the references in a named range's `refers_to` text. Sheet-qualified
+/// references move when they name the changed sheet; bare references move
+/// when the name is scoped to that sheet. Unlike fill/copy shifting, `$`
+/// markers do not pin a reference: the cells themselves moved. A reference
+/// whose cells were all deleted becomes `#REF!`. Whole-row ranges follow row
+/// changes only, whole-column ranges column changes only.
+pub(crate) fn shift_refers_to(refers_to: &str, scope: Option<usize>, change: &StructuralChange) -> String {
+    use crate::pivot::utils::{col_index_to_letter, col_letter_to_index};
+    NAME_REF_RE
+        .replace_all(refers_to, |caps: &regex::Captures| {
+            let whole = caps[0].to_string();
+            let applies = match caps.get(1) {
+                Some(sheet) => {
+                    let name = sheet.as_str().trim_matches('\'').replace("''", "'");
+                    name.eq_ignore_ascii_case(&change.sheet_name)
+                }
+                None => scope == Some(change.sheet),
+            };
+            if !applies {
+                return whole;
+            }
+            let prefix = caps.get(1).map(|m| format!("{}!", m.as_str())).unwrap_or_default();
+            // Whole rows / whole columns: only their own axis moves them.
+            let line_range = if caps.get(11).is_some() {
+                Some((Axis::Rows, &caps[10], &caps[11], &caps[12], &caps[13]))
+            } else if caps.get(15).is_some() {
+                Some((Axis::Columns, &caps[14], &caps[15], &caps[16], &caps[17]))
+            } else {
+                None
+            };
+            if let Some((axis, d1, first, d2, last)) = line_range {
+                if axis != change.axis {
+                    return whole;
+                }
+                let index = |text: &str| match axis {
+                    Axis::Rows => text.parse::<u32>().unwrap_or(1).saturating_sub(1),
+                    Axis::Columns => col_letter_to_index(text),
+                };
+                let label = |i: u32| match axis {
+                    Axis::Rows => (i + 1).to_string(),
+                    Axis::Columns => col_index_to_letter(i),
+                };
+                let Some((start, end)) = change.map_span(index(first), index(last)) else {
+                    return "#REF!".to_string();
+                };
+                return format!("{}{}{}:{}{}", prefix, d1, label(start), d2, label(end));
+            }
+            let sr = caps[5].parse::<u32>().unwrap_or(1).saturating_sub(1);
+            let sc = col_letter_to_index(&caps[3]);
+            let (er, ec) = match caps.get(9) {
+                Some(row) => (row.as_str().parse::<u32>().unwrap_or(1).saturating_sub(1), col_letter_to_index(&caps[7])),
+                None => (sr, sc),
+            };
+            let Some((nsr, nsc, ner, nec)) = change.map_rect(sr, sc, er, ec) else {
+                return "#REF!".to_string();
+            };
+            let start = format!("{}{}{}{}", &caps[2], col_index_to_letter(nsc), &caps[4], nsr + 1);
+            if caps.get(9).is_some() {
+                format!("{}{}:{}{}{}{}", prefix, start, &caps[6], col_index_to_letter(nec), &caps[8], ner + 1)
+            } else {
+                format!("{}{}", prefix, start)
+            }
+        })
+        .to_string()
+}
+
+/// Rewrite the references of a formula that lives on the changed sheet,
+/// through the engine's reference visitor (the same walk fill/copy uses).
+/// Bare references and references naming the changed sheet move; `$` does
+/// not pin them. A reference whose cells were all deleted becomes `#REF!`;
+/// one that straddles the deleted band is clamped to what survives. A 3D
+/// reference moves only when its span is the changed sheet alone.
+/// Returns true when the AST changed.
+pub(crate) fn shift_formula_refs(ast: &mut parser::Expression, change: &StructuralChange) -> bool {
+    shift_sheet_formula_refs(ast, change.sheet, change).changed
+}
+
+/// What `shift_sheet_formula_refs` did to one formula.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub(crate) struct RefShift {
+    /// The AST changed.
+    pub changed: bool,
+    /// At least one reference lost all its cells and became `#REF!`.
+    pub invalidated: bool,
+}
+
+/// `shift_formula_refs` for a formula living on `own_sheet`, which may be
+/// another sheet than the changed one: there, bare references point at the
+/// formula's own sheet and stay put, and only references naming the changed
+/// sheet move.
+pub(crate) fn shift_sheet_formula_refs(
+    ast: &mut parser::Expression,
+    own_sheet: usize,
+    change: &StructuralChange,
+) -> RefShift {
+    use engine::reference_shift::{map_references, RefScope};
+
+    let mut invalidated = false;
+    let changed = map_references(ast, &mut |node, scope| {
+        let applies = match scope {
+            RefScope::Local => own_sheet == change.sheet,
+            RefScope::Sheet(name) => name.eq_ignore_ascii_case(&change.sheet_name),
+            // The other sheets of a 3D span did not move; only a span of the
+            // changed sheet alone follows it.
+            RefScope::Sheets3D(first, last) => {
+                first.eq_ignore_ascii_case(&change.sheet_name) && last.eq_ignore_ascii_case(&change.sheet_name)
+            }
+        };
+        if !applies {
+            return true;
+        }
+        let kept = shift_reference(node, change);
+        invalidated |= !kept;
+        kept
+    });
+    RefShift { changed, invalidated }
+}
+
+/// Move one spatial reference node for `change`; false when all its cells
+/// were deleted.
+fn shift_reference(node: &mut parser::Expression, change: &StructuralChange) -> bool {
+    use engine::coord::{col_to_index, index_to_col};
+    use parser::Expression;
+
+    match node {
+        Expression::CellRef { col, row, .. } => {
+            let Some((r, c)) = change.map_cell(row.saturating_sub(1), col_to_index(col)) else {
+                return false;
+            };
+            *row = r + 1;
+            *col = index_to_col(c);
+            true
+        }
+        Expression::Range { start, end, .. } => {
+            let (
+                Expression::CellRef { col: sc, row: sr, .. },
+                Expression::CellRef { col: ec, row: er, .. },
+            ) = (&mut **start, &mut **end)
+            else {
+                return true;
+            };
+            let Some((nsr, nsc, ner, nec)) =
+                change.map_rect(sr.saturating_sub(1), col_to_index(sc), er.saturating_sub(1), col_to_index(ec))
+            else {
+                return false;
+            };
+            (*sr, *er) = (nsr + 1, ner + 1);
+            (*sc, *ec) = (index_to_col(nsc), index_to_col(nec));
+            true
+        }
+        Expression::ColumnRef { start_col, end_col, .. } => {
+            if change.axis != Axis::Columns {
+                return true;
+            }
+            let Some((s, e)) = change.map_span(col_to_index(start_col), col_to_index(end_col)) else {
+                return false;
+            };
+            (*start_col, *end_col) = (index_to_col(s), index_to_col(e));
+            true
+        }
+        Expression::RowRef { start_row, end_row, .. } => {
+            if change.axis != Axis::Rows {
+                return true;
+            }
+            let Some((s, e)) = change.map_span(start_row.saturating_sub(1), end_row.saturating_sub(1)) else {
+                return false;
+            };
+            (*start_row, *end_row) = (s + 1, e + 1);
+            true
+        }
+        _ => true,
+    }
+}
+
+// ============================================================================
+// DISPATCH + UNDO
+// ============================================================================
+
+/// Serializable mirror of `ProtectedRegion` for the undo snapshot.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct RegionSnapshot {
+    id: String,
+    region_type: String,
+    owner_id: identity::EntityId,
+    sheet_index: usize,
+    start_row: u32,
+    start_col: u32,
+    end_row: u32,
+    end_col: u32,
+}
+
+/// Pre-change coordinates of one pivot definition.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct PivotCoordsSnapshot {
+    id: identity::EntityId,
+    destination: (u32, u32),
+    source_start: (u32, u32),
+    source_end: (u32, u32),
+}
+
+/// Payload of the `"obj_structural_features"` CustomRestore: every feature
+/// store's state for one sheet (named ranges and hyperlinks: the whole
+/// workbook, since links on other sheets can target the changed one).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct StructuralFeaturesSnapshot {
+    sheet: usize,
+    named_ranges: Vec<(String, NamedRange)>,
+    conditional_formats: Vec<ConditionalFormatDefinition>,
+    validations: Vec<ValidationRange>,
+    comments: Vec<Comment>,
+    notes: Vec<Note>,
+    hyperlinks: Vec<(usize, Vec<Hyperlink>)>,
+    auto_filter: Option<AutoFilter>,
+    outline: Option<SheetOutline>,
+    tables: Vec<Table>,
+    regions: Vec<RegionSnapshot>,
+    pivots: Vec<PivotCoordsSnapshot>,
+}
+
+/// Capture the current state of every structural-change participant for
+/// `sheet`. Each store is locked on its own (feature stores only; no grids).
+pub(crate) fn capture_features(state: &AppState, pivot_state: &PivotState, sheet: usize) -> StructuralFeaturesSnapshot {
+    let regions: Vec<RegionSnapshot> = state
+        .protected_regions
+        .lock()
+        .unwrap()
+        .iter()
+        .filter(|r| r.sheet_index == sheet)
+        .map(|r| RegionSnapshot {
+            id: r.id.clone(),
+            region_type: r.region_type.clone(),
+            owner_id: r.owner_id,
+            sheet_index: r.sheet_index,
+            start_row: r.start_row,
+            start_col: r.start_col,
+            end_row: r.end_row,
+            end_col: r.end_col,
+        })
+        .collect();
+    let pivots = {
+        let pivot_tables = pivot_state.pivot_tables.lock().unwrap();
+        regions
+            .iter()
+            .filter(|r| r.region_type == "pivot")
+            .filter_map(|r| pivot_tables.get(&r.owner_id))
+            .map(|(d, _)| PivotCoordsSnapshot {
+                id: d.id,
+                destination: d.destination,
+                source_start: d.source_start,
+                source_end: d.source_end,
+            })
+            .collect()
+    };
+    StructuralFeaturesSnapshot {
+        sheet,
+        named_ranges: state.named_ranges.lock().unwrap().iter().map(|(k, v)| (k.clone(), v.clone())).collect(),
+        conditional_formats: state.conditional_formats.lock().unwrap().get(&sheet).cloned().unwrap_or_default(),
+        validations: state.data_validations.lock().unwrap().get(&sheet).cloned().unwrap_or_default(),
+        comments: state.comments.lock().unwrap().get(&sheet).map(|m| m.values().cloned().collect()).unwrap_or_default(),
+        notes: state.notes.lock().unwrap().get(&sheet).map(|m| m.values().cloned().collect()).unwrap_or_default(),
+        hyperlinks: state
+            .hyperlinks
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(&s, links)| (s, links.values().cloned().collect()))
+            .collect(),
+        auto_filter: state.auto_filters.lock().unwrap().get(&sheet).cloned(),
+        outline: state.outlines.lock().unwrap().get(&sheet).cloned(),
+        tables: state.tables.lock().unwrap().get(&sheet).map(|m| m.values().cloned().collect()).unwrap_or_default(),
+        regions,
+        pivots,
+    }
+}
+
+/// Write a captured snapshot back (undo/redo of a structural change).
+pub(crate) fn restore_features(state: &AppState, pivot_state: &PivotState, snap: StructuralFeaturesSnapshot) {
+    let sheet = snap.sheet;
+    *state.named_ranges.lock().unwrap() = snap.named_ranges.into_iter().collect();
+    {
+        let mut cf = state.conditional_formats.lock().unwrap();
+        if snap.conditional_formats.is_empty() { cf.remove(&sheet); } else { cf.insert(sheet, snap.conditional_formats); }
+    }
+    {
+        let mut dv = state.data_validations.lock().unwrap();
+        if snap.validations.is_empty() { dv.remove(&sheet); } else { dv.insert(sheet, snap.validations); }
+    }
+    state.comments.lock().unwrap().insert(sheet, snap.comments.into_iter().map(|c| ((c.row, c.col), c)).collect());
+    state.notes.lock().unwrap().insert(sheet, snap.notes.into_iter().map(|n| ((n.row, n.col), n)).collect());
+    *state.hyperlinks.lock().unwrap() = snap
+        .hyperlinks
+        .into_iter()
+        .map(|(s, links)| (s, links.into_iter().map(|h| ((h.row, h.col), h)).collect()))
+        .collect();
+    {
+        let mut filters = state.auto_filters.lock().unwrap();
+        match snap.auto_filter {
+            Some(f) => { filters.insert(sheet, f); }
+            None => { filters.remove(&sheet); }
+        }
+    }
+    {
+        let mut outlines = state.outlines.lock().unwrap();
+        match snap.outline {
+            Some(o) => { outlines.insert(sheet, o); }
+            None => { outlines.remove(&sheet); }
+        }
+    }
+    {
+        let mut tables = state.tables.lock().unwrap();
+        let mut table_names = state.table_names.lock().unwrap();
+        table_names.retain(|_, (s, _)| *s != sheet);
+        for t in &snap.tables {
+            table_names.insert(t.name.to_uppercase(), (sheet, t.id));
+        }
+        tables.insert(sheet, snap.tables.into_iter().map(|t| (t.id, t)).collect());
+    }
+    {
+        let mut regions = state.protected_regions.lock().unwrap();
+        regions.retain(|r| r.sheet_index != sheet);
+        regions.extend(snap.regions.into_iter().map(|r| ProtectedRegion {
+            id: r.id,
+            region_type: r.region_type,
+            owner_id: r.owner_id,
+            sheet_index: r.sheet_index,
+            start_row: r.start_row,
+            start_col: r.start_col,
+            end_row: r.end_row,
+            end_col: r.end_col,
+        }));
+    }
+    {
+        // Pivots removed by the change are gone with their caches; only the
+        // coordinates of surviving definitions are restored.
+        let mut pivot_tables = pivot_state.pivot_tables.lock().unwrap();
+        for p in snap.pivots {
+            if let Some((definition, _)) = pivot_tables.get_mut(&p.id) {
+                definition.destination = p.destination;
+                definition.source_start = p.source_start;
+                definition.source_end = p.source_end;
+            }
+        }
+    }
+    sync_report_definitions_to_regions(state);
+}
+
+/// Realign report definitions with their (already coordinate-shifted) protected
+/// regions and drop definitions whose region was removed, then persist. A report
+/// definition mirrors its region exactly (anchor = region start, bounds = region
+/// end), so after a generic region shift this brings the definition store — the
+/// source of truth for the NEXT refresh's destination — back in sync. Without it
+/// a refresh would re-materialize the report at its pre-shift coordinates.
+fn sync_report_definitions_to_regions(state: &AppState) {
+    let report_regions: Vec<_> = {
+        let regions = state.protected_regions.lock().unwrap();
+        regions
+            .iter()
+            .filter(|r| r.region_type == "report")
+            .map(|r| (r.owner_id, r.sheet_index, r.start_row, r.start_col, r.end_row, r.end_col))
+            .collect()
+    };
+    {
+        let mut defs = state.report_definitions.lock().unwrap();
+        defs.retain(|d| report_regions.iter().any(|(id, ..)| *id == d.id));
+        for d in defs.iter_mut() {
+            if let Some((_, sheet, sr, sc, er, ec)) =
+                report_regions.iter().find(|(id, ..)| *id == d.id)
+            {
+                d.sheet_index = *sheet;
+                d.anchor_row = *sr;
+                d.anchor_col = *sc;
+                d.end_row = *er;
+                d.end_col = *ec;
+            }
+        }
+    }
+    crate::report::sync_reports_to_extension_data(state);
+}
+
+/// Map a pivot destination/source coordinate; a deleted line clamps to the
+/// first surviving line at the deletion point.
+fn map_pivot_coord(change: &StructuralChange, (row, col): (u32, u32)) -> (u32, u32) {
+    let clamp = |i: u32| change.map_index(i).unwrap_or(change.at);
+    match change.axis {
+        Axis::Rows => (clamp(row), col),
+        Axis::Columns => (row, clamp(col)),
+    }
+}
+
+/// Apply `change` to every feature store and return the serialized pre-change
+/// snapshot for the `"obj_structural_features"` undo record. Must run BEFORE the
+/// caller takes the grid locks (canonical order: feature stores first).
+///
+/// The active sheet's merged regions are NOT handled here: they live in the
+/// grid snapshot the structural commands already record.
+pub(crate) fn dispatch_structural_change(
+    state: &AppState,
+    pivot_state: &PivotState,
+    change: &StructuralChange,
+) -> Vec<u8> {
+    let previous = capture_features(state, pivot_state, change.sheet);
+
+    state.named_ranges.lock().unwrap().apply_structural_change(change);
+    state.conditional_formats.lock().unwrap().apply_structural_change(change);
+    state.data_validations.lock().unwrap().apply_structural_change(change);
+    state.comments.lock().unwrap().apply_structural_change(change);
+    state.notes.lock().unwrap().apply_structural_change(change);
+    state.hyperlinks.lock().unwrap().apply_structural_change(change);
+    state.auto_filters.lock().unwrap().apply_structural_change(change);
+    state.outlines.lock().unwrap().apply_structural_change(change);
+    {
+        let mut tables = state.tables.lock().unwrap();
+        if tables.apply_structural_change(change) {
+            let mut table_names = state.table_names.lock().unwrap();
+            table_names.retain(|_, (s, id)| tables.get(s).is_some_and(|t| t.contains_key(id)));
+        }
+    }
+    {
+        let mut regions = state.protected_regions.lock().unwrap();
+        let mut pivot_tables = pivot_state.pivot_tables.lock().unwrap();
+        let pivots_before: Vec<identity::EntityId> = regions
+            .iter()
+            .filter(|r| r.sheet_index == change.sheet && r.region_type == "pivot")
+            .map(|r| r.owner_id)
+            .collect();
+        regions.apply_structural_change(change);
+        for pid in pivots_before {
+            if !regions.iter().any(|r| r.region_type == "pivot" && r.owner_id == pid) {
+                // The pivot's whole output was deleted.
+                pivot_tables.remove(&pid);
+                continue;
+            }
+            if let Some((definition, _)) = pivot_tables.get_mut(&pid) {
+                definition.destination = map_pivot_coord(change, definition.destination);
+                let (start, end) = (definition.source_start, definition.source_end);
+                let (s, e) = match change.axis {
+                    Axis::Rows => (start.0, end.0),
+                    Axis::Columns => (start.1, end.1),
+                };
+                let (ns, ne) = change.map_span(s, e).unwrap_or((change.at, change.at));
+                match change.axis {
+                    Axis::Rows => {
+                        definition.source_start = (ns, start.1);
+                        definition.source_end = (ne, end.1);
+                    }
+                    Axis::Columns => {
+                        definition.source_start = (start.0, ns);
+                        definition.source_end = (end.0, ne);
+                    }
+                }
+            }
+        }
+    }
+    sync_report_definitions_to_regions(state);
+
+    serde_json::to_vec(&previous).unwrap_or_default()
+}
+
+/// Undo/redo handler for `"obj_structural_features"`: swap the stored state
+/// back in, recording the current state as the inverse.
+pub(crate) fn apply_structural_features_restore(
+    state: &AppState,
+    pivot_state: &PivotState,
+    data: &[u8],
+    inverse_transaction: &mut engine::Transaction,
+) {
+    let snap: StructuralFeaturesSnapshot = match serde_json::from_slice(data) {
+        Ok(s) => s,
+        Err(e) => { eprintln!("[undo] bad obj_structural_features snapshot: {}", e); return; }
+    };
+    let current = capture_features(state, pivot_state, snap.sheet);
+    inverse_transaction.add_change(engine::CellChange::CustomRestore {
+        kind: "obj_structural_features".to_string(),
+        data: serde_json::to_vec(&current).unwrap_or_default(),
+    });
+    restore_features(state, pivot_state, snap);
+}
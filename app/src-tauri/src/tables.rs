@@ -8,6 +8,7 @@ use tauri::State;
 use crate::AppState;
 use crate::autofilter::AutoFilter;
 use crate::persistence::UserFilesState;
+use crate::pivot::PivotState;
 
 // ============================================================================
 // TOTALS ROW FUNCTIONS
@@ -103,6 +104,9 @@ pub struct TableColumn {
     /// Calculated column formula (applied to all data rows)
     #[serde(skip_serializing_if = "Option::is_none")]
     pub calculated_formula: Option<String>,
+    /// Enforced data type for values typed into the column (None = any)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub data_type: Option<ColumnDataType>,
 }
 
 impl TableColumn {
@@ -113,10 +117,63 @@ impl TableColumn {
             totals_row_function: TotalsRowFunction::None,
             totals_row_formula: None,
             calculated_formula: None,
+            data_type: None,
         }
     }
 }
 
+// ============================================================================
+// COLUMN DATA TYPES
+// ============================================================================
+
+/// Data type a table column accepts
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ColumnDataType {
+    Number,
+    Date,
+    Text,
+    Boolean,
+}
+
+impl ColumnDataType {
+    /// Lowercase name used in persistence and messages
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ColumnDataType::Number => "number",
+            ColumnDataType::Date => "date",
+            ColumnDataType::Text => "text",
+            ColumnDataType::Boolean => "boolean",
+        }
+    }
+
+    pub fn from_name(value: &str) -> Option<Self> {
+        match value {
+            "number" => Some(ColumnDataType::Number),
+            "date" => Some(ColumnDataType::Date),
+            "text" => Some(ColumnDataType::Text),
+            "boolean" => Some(ColumnDataType::Boolean),
+            _ => None,
+        }
+    }
+}
+
+/// What happens to input that does not match a column's data type
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum DataTypePolicy {
+    /// Refuse the edit
+    Reject,
+    /// Convert the input when possible, refuse it otherwise
+    Coerce,
+}
+
+impl Default for DataTypePolicy {
+    fn default() -> Self {
+        DataTypePolicy::Reject
+    }
+}
+
 // ============================================================================
 // TABLE
 // ============================================================================
@@ -148,6 +205,9 @@ pub struct Table {
     /// Associated AutoFilter ID (if show_filter_button is true)
     #[serde(skip_serializing_if = "Option::is_none")]
     pub auto_filter_id: Option<u64>,
+    /// How typed values that break a column's data type are handled
+    #[serde(default)]
+    pub data_type_policy: DataTypePolicy,
 }
 
 impl Table {
@@ -261,6 +321,18 @@ pub struct TableResult {
     /// Computed cell values from set_calculated_column, for direct canvas update.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub computed_cells: Option<Vec<ComputedCell>>,
+    /// Cells that do not match a column's new data type (set_table_column_data_type).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub nonconforming_cells: Option<Vec<NonconformingCell>>,
+}
+
+/// A data cell whose value does not match its column's data type.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NonconformingCell {
+    pub row: u32,
+    pub col: u32,
+    pub display: String,
 }
 
 impl TableResult {
@@ -270,6 +342,7 @@ impl TableResult {
             table: Some(table),
             error: None,
             computed_cells: None,
+            nonconforming_cells: None,
         }
     }
 
@@ -279,6 +352,7 @@ impl TableResult {
             table: None,
             error: None,
             computed_cells: None,
+            nonconforming_cells: None,
         }
     }
 
@@ -288,6 +362,7 @@ impl TableResult {
             table: None,
             error: Some(message.into()),
             computed_cells: None,
+            nonconforming_cells: None,
         }
     }
 }
@@ -386,6 +461,17 @@ pub struct SetTotalsRowFunctionParams {
     pub custom_formula: Option<String>,
 }
 
+/// Parameters for setting a column's data type
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SetColumnDataTypeParams {
+    pub table_id: identity::EntityId,
+    pub column_name: String,
+    /// None removes the restriction
+    #[serde(default)]
+    pub data_type: Option<ColumnDataType>,
+}
+
 // ============================================================================
 // HELPER FUNCTIONS
 // ============================================================================
@@ -451,8 +537,8 @@ fn build_subtotal_formula(
     let code = match function {
         TotalsRowFunction::None => return None,
         TotalsRowFunction::Average => 101,
-        TotalsRowFunction::Count => 102,
-        TotalsRowFunction::CountNumbers => 103,
+        TotalsRowFunction::Count => 103,
+        TotalsRowFunction::CountNumbers => 102,
         TotalsRowFunction::Max => 104,
         TotalsRowFunction::Min => 105,
         TotalsRowFunction::Sum => 109,
@@ -497,6 +583,10 @@ pub fn create_table(
     state: State<AppState>,
     params: CreateTableParams,
 ) -> TableResult {
+    create_table_internal(&state, params)
+}
+
+pub(crate) fn create_table_internal(state: &AppState, params: CreateTableParams) -> TableResult {
     let active_sheet = *state.active_sheet.lock().unwrap();
     let mut tables = state.tables.lock().unwrap();
     let mut table_names = state.table_names.lock().unwrap();
@@ -531,7 +621,7 @@ pub fn create_table(
     }
 
     // Read header text from grid cells (or generate generic names)
-    let grid = state.grid.lock().unwrap();
+    let grid = state.get_active_grid();
     let col_count = (max_col - min_col + 1) as usize;
     let mut header_names: Vec<String> = Vec::with_capacity(col_count);
 
@@ -579,6 +669,7 @@ pub fn create_table(
         style_options,
         style_name: params.style_name.unwrap_or_else(|| "TableStyleMedium2".to_string()),
         auto_filter_id: None,
+        data_type_policy: Default::default(),
     };
 
     // Create an AutoFilter for the table range if show_filter_button is enabled
@@ -745,80 +836,92 @@ pub fn update_table_style(
 #[tauri::command]
 pub fn add_table_column(
     state: State<AppState>,
+    user_files_state: State<UserFilesState>,
+    pivot_state: State<'_, PivotState>,
     table_id: identity::EntityId,
     column_name: String,
     position: Option<usize>,
 ) -> TableResult {
-    let active_sheet = *state.active_sheet.lock().unwrap();
-    let mut tables = state.tables.lock().unwrap();
+    let result = {
+        let active_sheet = *state.active_sheet.lock().unwrap();
+        let mut tables = state.tables.lock().unwrap();
 
-    let sheet_tables = match tables.get_mut(&active_sheet) {
-        Some(t) => t,
-        None => return TableResult::err("No tables on this sheet"),
-    };
+        let sheet_tables = match tables.get_mut(&active_sheet) {
+            Some(t) => t,
+            None => return TableResult::err("No tables on this sheet"),
+        };
 
-    let table = match sheet_tables.get_mut(&table_id) {
-        Some(t) => t,
-        None => return TableResult::err("Table not found"),
-    };
+        let table = match sheet_tables.get_mut(&table_id) {
+            Some(t) => t,
+            None => return TableResult::err("Table not found"),
+        };
 
-    // Check for duplicate name
-    if table.get_column_by_name(&column_name).is_some() {
-        return TableResult::err("Column name already exists");
-    }
+        // Check for duplicate name
+        if table.get_column_by_name(&column_name).is_some() {
+            return TableResult::err("Column name already exists");
+        }
 
-    // Generate new column ID
-    let new_id = identity::EntityId::from_bytes(identity::generate_uuid_v7());
-    let new_column = TableColumn::new(new_id, column_name);
+        // Generate new column ID
+        let new_id = identity::EntityId::from_bytes(identity::generate_uuid_v7());
+        let new_column = TableColumn::new(new_id, column_name);
 
-    // Insert at position or end
-    let pos = position.unwrap_or(table.columns.len());
-    if pos > table.columns.len() {
-        table.columns.push(new_column);
-    } else {
-        table.columns.insert(pos, new_column);
-    }
+        // Insert at position or end
+        let pos = position.unwrap_or(table.columns.len());
+        if pos > table.columns.len() {
+            table.columns.push(new_column);
+        } else {
+            table.columns.insert(pos, new_column);
+        }
 
-    // Expand table range
-    table.end_col += 1;
+        // Expand table range
+        table.end_col += 1;
 
-    TableResult::ok(table.clone())
+        TableResult::ok(table.clone())
+    };
+    refresh_table_dependents(&state, &user_files_state, &pivot_state, &result);
+    result
 }
 
 /// Remove a column from a table
 #[tauri::command]
 pub fn remove_table_column(
     state: State<AppState>,
+    user_files_state: State<UserFilesState>,
+    pivot_state: State<'_, PivotState>,
     table_id: identity::EntityId,
     column_name: String,
 ) -> TableResult {
-    let active_sheet = *state.active_sheet.lock().unwrap();
-    let mut tables = state.tables.lock().unwrap();
+    let result = {
+        let active_sheet = *state.active_sheet.lock().unwrap();
+        let mut tables = state.tables.lock().unwrap();
 
-    let sheet_tables = match tables.get_mut(&active_sheet) {
-        Some(t) => t,
-        None => return TableResult::err("No tables on this sheet"),
-    };
+        let sheet_tables = match tables.get_mut(&active_sheet) {
+            Some(t) => t,
+            None => return TableResult::err("No tables on this sheet"),
+        };
 
-    let table = match sheet_tables.get_mut(&table_id) {
-        Some(t) => t,
-        None => return TableResult::err("Table not found"),
-    };
+        let table = match sheet_tables.get_mut(&table_id) {
+            Some(t) => t,
+            None => return TableResult::err("Table not found"),
+        };
 
-    // Can't remove last column
-    if table.columns.len() <= 1 {
-        return TableResult::err("Cannot remove last column");
-    }
+        // Can't remove last column
+        if table.columns.len() <= 1 {
+            return TableResult::err("Cannot remove last column");
+        }
 
-    let idx = match table.get_column_index(&column_name) {
-        Some(i) => i,
-        None => return TableResult::err("Column not found"),
-    };
+        let idx = match table.get_column_index(&column_name) {
+            Some(i) => i,
+            None => return TableResult::err("Column not found"),
+        };
 
-    table.columns.remove(idx);
-    table.end_col -= 1;
+        table.columns.remove(idx);
+        table.end_col -= 1;
 
-    TableResult::ok(table.clone())
+        TableResult::ok(table.clone())
+    };
+    refresh_table_dependents(&state, &user_files_state, &pivot_state, &result);
+    result
 }
 
 /// Rename a table column
@@ -861,16 +964,50 @@ pub fn rename_table_column(
     TableResult::ok(table.clone())
 }
 
+/// Attach the freshly evaluated totals row cells of the active sheet to a
+/// successful result, once the command has released its locks.
+fn with_evaluated_totals(state: &AppState, mut result: TableResult) -> TableResult {
+    if result.success {
+        let active_sheet = *state.active_sheet.lock().unwrap();
+        let computed: Vec<ComputedCell> = recalculate_totals_rows(state, active_sheet)
+            .into_iter()
+            .map(|cell| ComputedCell {
+                row: cell.row,
+                col: cell.col,
+                display: cell.display,
+                formula: cell.formula,
+            })
+            .collect();
+        if !computed.is_empty() {
+            result.computed_cells = Some(computed);
+        }
+    }
+    result
+}
+
 /// Set totals row function for a column.
 /// Also writes the corresponding SUBTOTAL formula into the totals row cell.
 #[tauri::command]
 pub fn set_totals_row_function(
     state: State<AppState>,
     params: SetTotalsRowFunctionParams,
+) -> TableResult {
+    set_totals_row_function_internal(&state, params)
+}
+
+pub(crate) fn set_totals_row_function_internal(
+    state: &AppState,
+    params: SetTotalsRowFunctionParams,
+) -> TableResult {
+    with_evaluated_totals(state, write_totals_row_function(state, params))
+}
+
+fn write_totals_row_function(
+    state: &AppState,
+    params: SetTotalsRowFunctionParams,
 ) -> TableResult {
     let active_sheet = *state.active_sheet.lock().unwrap();
     let mut tables = state.tables.lock().unwrap();
-    let mut grid = state.grid.lock().unwrap();
     let mut grids = state.grids.lock().unwrap();
 
     let sheet_tables = match tables.get_mut(&active_sheet) {
@@ -907,17 +1044,11 @@ pub fn set_totals_row_function(
         match formula {
             Some(formula_str) => {
                 let cell = engine::Cell::new_formula(formula_str);
-                grid.set_cell(totals_row, cell_col, cell.clone());
-                if active_sheet < grids.len() {
-                    grids[active_sheet].set_cell(totals_row, cell_col, cell);
-                }
+                grids[active_sheet].set_cell(totals_row, cell_col, cell);
             }
             None => {
                 // Function is "None" - clear the cell
-                grid.clear_cell(totals_row, cell_col);
-                if active_sheet < grids.len() {
-                    grids[active_sheet].clear_cell(totals_row, cell_col);
-                }
+                grids[active_sheet].clear_cell(totals_row, cell_col);
             }
         }
     }
@@ -933,10 +1064,18 @@ pub fn toggle_totals_row(
     state: State<AppState>,
     table_id: identity::EntityId,
     show: bool,
+) -> TableResult {
+    let result = write_totals_row(&state, table_id, show);
+    with_evaluated_totals(&state, result)
+}
+
+fn write_totals_row(
+    state: &AppState,
+    table_id: identity::EntityId,
+    show: bool,
 ) -> TableResult {
     let active_sheet = *state.active_sheet.lock().unwrap();
     let mut tables = state.tables.lock().unwrap();
-    let mut grid = state.grid.lock().unwrap();
     let mut grids = state.grids.lock().unwrap();
 
     let sheet_tables = match tables.get_mut(&active_sheet) {
@@ -969,10 +1108,7 @@ pub fn toggle_totals_row(
                 };
                 if let Some(formula_str) = formula {
                     let cell = engine::Cell::new_formula(formula_str);
-                    grid.set_cell(totals_row, cell_col, cell.clone());
-                    if active_sheet < grids.len() {
-                        grids[active_sheet].set_cell(totals_row, cell_col, cell);
-                    }
+                    grids[active_sheet].set_cell(totals_row, cell_col, cell);
                 }
             }
         }
@@ -981,10 +1117,7 @@ pub fn toggle_totals_row(
         let totals_row = table.end_row;
         for i in 0..table.columns.len() {
             let cell_col = table.start_col + i as u32;
-            grid.clear_cell(totals_row, cell_col);
-            if active_sheet < grids.len() {
-                grids[active_sheet].clear_cell(totals_row, cell_col);
-            }
+            grids[active_sheet].clear_cell(totals_row, cell_col);
         }
         table.end_row -= 1;
         table.style_options.total_row = false;
@@ -993,63 +1126,91 @@ pub fn toggle_totals_row(
     TableResult::ok(table.clone())
 }
 
+/// After a successful shape change, re-expand and recalculate the formulas
+/// that reference the table (structured references are expanded at edit time).
+fn refresh_table_dependents(
+    state: &AppState,
+    user_files_state: &UserFilesState,
+    pivot_state: &PivotState,
+    result: &TableResult,
+) {
+    if let Some(table) = result.table.as_ref().filter(|_| result.success) {
+        crate::name_dependents::refresh_dependents(state, user_files_state, pivot_state, &[], &[&table.name]);
+    }
+}
+
 /// Resize a table
 #[tauri::command]
 pub fn resize_table(
     state: State<AppState>,
+    user_files_state: State<UserFilesState>,
+    pivot_state: State<'_, PivotState>,
     params: ResizeTableParams,
 ) -> TableResult {
-    let active_sheet = *state.active_sheet.lock().unwrap();
-    let mut tables = state.tables.lock().unwrap();
+    resize_table_internal(&state, &user_files_state, &pivot_state, params)
+}
 
-    let sheet_tables = match tables.get_mut(&active_sheet) {
-        Some(t) => t,
-        None => return TableResult::err("No tables on this sheet"),
-    };
+pub(crate) fn resize_table_internal(
+    state: &AppState,
+    user_files_state: &UserFilesState,
+    pivot_state: &PivotState,
+    params: ResizeTableParams,
+) -> TableResult {
+    let result = {
+        let active_sheet = *state.active_sheet.lock().unwrap();
+        let mut tables = state.tables.lock().unwrap();
 
-    // Check for overlapping tables
-    for (id, existing) in sheet_tables.iter() {
-        if *id != params.table_id {
-            if ranges_overlap(
-                params.start_row, params.start_col, params.end_row, params.end_col,
-                existing.start_row, existing.start_col, existing.end_row, existing.end_col,
-            ) {
-                return TableResult::err("Resized table would overlap with existing table");
+        let sheet_tables = match tables.get_mut(&active_sheet) {
+            Some(t) => t,
+            None => return TableResult::err("No tables on this sheet"),
+        };
+
+        // Check for overlapping tables
+        for (id, existing) in sheet_tables.iter() {
+            if *id != params.table_id {
+                if ranges_overlap(
+                    params.start_row, params.start_col, params.end_row, params.end_col,
+                    existing.start_row, existing.start_col, existing.end_row, existing.end_col,
+                ) {
+                    return TableResult::err("Resized table would overlap with existing table");
+                }
             }
         }
-    }
 
-    let table = match sheet_tables.get_mut(&params.table_id) {
-        Some(t) => t,
-        None => return TableResult::err("Table not found"),
-    };
+        let table = match sheet_tables.get_mut(&params.table_id) {
+            Some(t) => t,
+            None => return TableResult::err("Table not found"),
+        };
 
-    let min_row = params.start_row.min(params.end_row);
-    let max_row = params.start_row.max(params.end_row);
-    let min_col = params.start_col.min(params.end_col);
-    let max_col = params.start_col.max(params.end_col);
+        let min_row = params.start_row.min(params.end_row);
+        let max_row = params.start_row.max(params.end_row);
+        let min_col = params.start_col.min(params.end_col);
+        let max_col = params.start_col.max(params.end_col);
 
-    let new_col_count = (max_col - min_col + 1) as usize;
-    let old_col_count = table.columns.len();
+        let new_col_count = (max_col - min_col + 1) as usize;
+        let old_col_count = table.columns.len();
 
-    // Adjust columns if needed
-    if new_col_count > old_col_count {
-        // Add columns
-        for i in old_col_count..new_col_count {
-            let new_id = identity::EntityId::from_bytes(identity::generate_uuid_v7());
-            table.columns.push(TableColumn::new(new_id, format!("Column{}", i + 1)));
+        // Adjust columns if needed
+        if new_col_count > old_col_count {
+            // Add columns
+            for i in old_col_count..new_col_count {
+                let new_id = identity::EntityId::from_bytes(identity::generate_uuid_v7());
+                table.columns.push(TableColumn::new(new_id, format!("Column{}", i + 1)));
+            }
+        } else if new_col_count < old_col_count {
+            // Remove columns from end
+            table.columns.truncate(new_col_count);
         }
-    } else if new_col_count < old_col_count {
-        // Remove columns from end
-        table.columns.truncate(new_col_count);
-    }
 
-    table.start_row = min_row;
-    table.start_col = min_col;
-    table.end_row = max_row;
-    table.end_col = max_col;
+        table.start_row = min_row;
+        table.start_col = min_col;
+        table.end_row = max_row;
+        table.end_col = max_col;
 
-    TableResult::ok(table.clone())
+        TableResult::ok(table.clone())
+    };
+    refresh_table_dependents(state, user_files_state, pivot_state, &result);
+    result
 }
 
 /// Convert table to range: rewrite all structured references that mention this
@@ -1064,7 +1225,6 @@ pub fn convert_to_range(
     let mut tables = state.tables.lock().unwrap();
     let mut table_names = state.table_names.lock().unwrap();
     let mut grids = state.grids.lock().unwrap();
-    let mut grid = state.grid.lock().unwrap();
 
     // Find the table
     let table = match tables
@@ -1128,12 +1288,7 @@ pub fn convert_to_range(
             if let Some(cell) = sheet_grid.get_cell(row, col) {
                 let mut updated = cell.clone();
                 updated.ast = parser::parse(&new_formula).ok().map(Box::new);
-                sheet_grid.set_cell(row, col, updated.clone());
-
-                // Also update the primary grid if this is the active sheet
-                if sheet_idx == active_sheet {
-                    grid.set_cell(row, col, updated);
-                }
+                sheet_grid.set_cell(row, col, updated);
             }
         }
     }
@@ -1157,7 +1312,6 @@ pub fn check_table_auto_expand(
 ) -> Option<Table> {
     let active_sheet = *state.active_sheet.lock().unwrap();
     let mut tables = state.tables.lock().unwrap();
-    let mut grid = state.grid.lock().unwrap();
     let mut grids = state.grids.lock().unwrap();
 
     let sheet_tables = tables.get_mut(&active_sheet)?;
@@ -1208,7 +1362,7 @@ pub fn check_table_auto_expand(
 
             // Try to read the header cell text from the grid for the new column
             let header_text = if table.style_options.header_row {
-                grid.get_cell(table.start_row, col)
+                grids[active_sheet].get_cell(table.start_row, col)
                     .and_then(|c| match &c.value {
                         engine::CellValue::Text(s) if !s.trim().is_empty() => Some(s.trim().to_string()),
                         engine::CellValue::Number(n) => Some(format!("{}", n)),
@@ -1224,16 +1378,13 @@ pub fn check_table_auto_expand(
             // If the header cell is empty, write the generated column name
             // so it displays with table styling.
             if table.style_options.header_row {
-                let needs_header = match grid.get_cell(table.start_row, col) {
+                let needs_header = match grids[active_sheet].get_cell(table.start_row, col) {
                     None => true,
                     Some(c) => matches!(c.value, engine::CellValue::Empty),
                 };
                 if needs_header {
                     let cell = engine::Cell::new_text(new_name.clone());
-                    grid.set_cell(table.start_row, col, cell.clone());
-                    if active_sheet < grids.len() {
-                        grids[active_sheet].set_cell(table.start_row, col, cell);
-                    }
+                    grids[active_sheet].set_cell(table.start_row, col, cell);
                 }
             }
 
@@ -1492,7 +1643,6 @@ pub fn set_calculated_column(
             }
         };
 
-        let mut grid = state.grid.lock().unwrap();
         let mut grids = state.grids.lock().unwrap();
         let sheet_names = state.sheet_names.lock().unwrap();
         let table_names = state.table_names.lock().unwrap();
@@ -1541,7 +1691,7 @@ pub fn set_calculated_column(
             cell.set_cached_ast(engine_ast);
 
             // Preserve existing style
-            if let Some(existing) = grid.get_cell(row, abs_col) {
+            if let Some(existing) = grids[active_sheet].get_cell(row, abs_col) {
                 cell.style_index = existing.style_index;
             }
 
@@ -1556,10 +1706,7 @@ pub fn set_calculated_column(
                 formula: Some(formula.clone()),
             });
 
-            grid.set_cell(row, abs_col, cell.clone());
-            if active_sheet < grids.len() {
-                grids[active_sheet].set_cell(row, abs_col, cell);
-            }
+            grids[active_sheet].set_cell(row, abs_col, cell);
         }
     }
 
@@ -1568,6 +1715,7 @@ pub fn set_calculated_column(
         table: Some(table_clone),
         error: None,
         computed_cells: if computed.is_empty() { None } else { Some(computed) },
+        nonconforming_cells: None,
     }
 }
 
@@ -1677,10 +1825,413 @@ fn convert_cell_refs_to_table_refs(
     }
 }
 
+// ============================================================================
+// COLUMN DATA TYPE ENFORCEMENT
+// ============================================================================
+
+/// Outcome of checking typed input against a column's data type.
+#[derive(Debug, Clone, PartialEq)]
+pub enum TypeCheck {
+    /// The input fits the column as typed
+    Accept,
+    /// The input was converted; commit this text instead
+    Coerce(String),
+    /// The input cannot go into the column
+    Reject(ColumnDataType),
+}
+
+/// Check typed `input` against `data_type`. Blank input and formulas are
+/// always accepted. Under the Coerce policy, input that does not fit is
+/// converted when there is an obvious conversion and rejected otherwise.
+pub fn check_input_type(
+    input: &str,
+    data_type: ColumnDataType,
+    policy: DataTypePolicy,
+    locale: &engine::LocaleSettings,
+) -> TypeCheck {
+    let trimmed = input.trim();
+    if trimmed.is_empty() || trimmed.starts_with('=') {
+        return TypeCheck::Accept;
+    }
+    let is_date = crate::date_input_number_format(trimmed, locale).is_some();
+    let value = crate::parse_cell_input(trimmed, locale).value;
+    let conforms = match data_type {
+        ColumnDataType::Number => matches!(value, engine::CellValue::Number(_)) && !is_date,
+        ColumnDataType::Date => is_date,
+        ColumnDataType::Text => matches!(value, engine::CellValue::Text(_)),
+        ColumnDataType::Boolean => matches!(value, engine::CellValue::Boolean(_)),
+    };
+    if conforms {
+        return TypeCheck::Accept;
+    }
+    if policy == DataTypePolicy::Coerce {
+        if let Some(coerced) = coerce_input(trimmed, &value, data_type, locale) {
+            return TypeCheck::Coerce(coerced);
+        }
+    }
+    TypeCheck::Reject(data_type)
+}
+
+/// Convert nonconforming input into text that parses as `data_type`:
+/// numbers lose stray characters ("$1,200 kg"), dates become serials and
+/// serials dates, anything can become literal text, and yes/no or numbers
+/// become booleans.
+fn coerce_input(
+    input: &str,
+    value: &engine::CellValue,
+    data_type: ColumnDataType,
+    locale: &engine::LocaleSettings,
+) -> Option<String> {
+    use engine::CellValue;
+
+    let number_text = |n: f64| format!("{}", n).replace('.', &locale.decimal_separator.to_string());
+    match data_type {
+        ColumnDataType::Number => match value {
+            CellValue::Number(n) => Some(number_text(*n)),
+            CellValue::Boolean(b) => Some(if *b { "1" } else { "0" }.to_string()),
+            CellValue::Text(text) => {
+                let digits: String = text
+                    .chars()
+                    .filter(|c| c.is_ascii_digit() || *c == '-' || *c == locale.decimal_separator)
+                    .collect();
+                match crate::parse_cell_input(&digits, locale).value {
+                    CellValue::Number(n) => Some(number_text(n)),
+                    _ => None,
+                }
+            }
+            _ => None,
+        },
+        ColumnDataType::Date => match value {
+            CellValue::Number(n) if n.is_finite() && *n >= 1.0 => {
+                let (year, month, day) = engine::date_serial::serial_to_date(n.trunc() as i64);
+                Some(format!("{:04}-{:02}-{:02}", year, month, day))
+            }
+            _ => None,
+        },
+        ColumnDataType::Text => Some(format!("'{}", input)),
+        ColumnDataType::Boolean => match value {
+            CellValue::Number(n) => Some(if *n != 0.0 { "TRUE" } else { "FALSE" }.to_string()),
+            CellValue::Text(text) => match text.to_lowercase().as_str() {
+                "yes" | "y" | "on" => Some("TRUE".to_string()),
+                "no" | "n" | "off" => Some("FALSE".to_string()),
+                _ => None,
+            },
+            _ => None,
+        },
+    }
+}
+
+/// Whether a stored value matches `data_type`. Dates are numbers shown with
+/// a date format; blanks always conform.
+fn value_conforms(value: &engine::CellValue, style: &engine::CellStyle, data_type: ColumnDataType) -> bool {
+    use engine::CellValue;
+
+    let is_date = engine::is_date_format(&style.number_format);
+    match (data_type, value) {
+        (_, CellValue::Empty) => true,
+        (ColumnDataType::Number, CellValue::Number(_)) => !is_date,
+        (ColumnDataType::Date, CellValue::Number(_)) => is_date,
+        (ColumnDataType::Text, CellValue::Text(_)) => true,
+        (ColumnDataType::Boolean, CellValue::Boolean(_)) => true,
+        _ => false,
+    }
+}
+
+/// The data type and policy enforced on an edit at (row, col): the cell is a
+/// data cell of a typed column, or sits on the row just below the data area,
+/// where typing auto-expands the table and the new row inherits the column
+/// types.
+fn column_type_at(
+    tables: &TableStorage,
+    sheet: usize,
+    row: u32,
+    col: u32,
+) -> Option<(ColumnDataType, DataTypePolicy)> {
+    tables.get(&sheet)?.values().find_map(|table| {
+        if col < table.start_col || col > table.end_col {
+            return None;
+        }
+        let expands = row == table.data_end_row() + 1 && !table.is_totals(row);
+        if !table.is_data(row) && !expands {
+            return None;
+        }
+        let column = table.columns.get((col - table.start_col) as usize)?;
+        column.data_type.map(|data_type| (data_type, table.data_type_policy))
+    })
+}
+
+/// Check a pending edit at (row, col) on `sheet` against the table column
+/// data types. Called by the data validation pending-value check.
+pub(crate) fn check_table_column_type(
+    state: &AppState,
+    sheet: usize,
+    row: u32,
+    col: u32,
+    input: &str,
+) -> TypeCheck {
+    let rule = {
+        let tables = state.tables.lock().unwrap();
+        column_type_at(&tables, sheet, row, col)
+    };
+    let Some((data_type, policy)) = rule else {
+        return TypeCheck::Accept;
+    };
+    let locale = state.locale.lock().unwrap();
+    check_input_type(input, data_type, policy, &locale)
+}
+
+/// Data cells of column `idx` whose values do not match its data type.
+fn scan_column_conformance(state: &AppState, table: &Table, idx: usize) -> Vec<NonconformingCell> {
+    let Some(data_type) = table.columns.get(idx).and_then(|c| c.data_type) else {
+        return Vec::new();
+    };
+    let grids = state.grids.lock().unwrap();
+    let styles = state.style_registry.lock().unwrap();
+    let locale = state.locale.lock().unwrap();
+    let Some(grid) = grids.get(table.sheet_index) else {
+        return Vec::new();
+    };
+
+    let col = table.start_col + idx as u32;
+    (table.data_start_row()..=table.data_end_row())
+        .filter_map(|row| {
+            let cell = grid.get_cell(row, col)?;
+            let style = styles.get(cell.style_index);
+            if value_conforms(&cell.value, style, data_type) {
+                return None;
+            }
+            Some(NonconformingCell {
+                row,
+                col,
+                display: crate::format_cell_value(&cell.value, style, &locale),
+            })
+        })
+        .collect()
+}
+
+/// Set or clear the data type of a table column. Existing values are kept;
+/// the ones that do not match the new type are returned so the UI can flag
+/// them.
+#[tauri::command]
+pub fn set_table_column_data_type(
+    state: State<AppState>,
+    params: SetColumnDataTypeParams,
+) -> TableResult {
+    set_table_column_data_type_internal(&state, params)
+}
+
+pub(crate) fn set_table_column_data_type_internal(
+    state: &AppState,
+    params: SetColumnDataTypeParams,
+) -> TableResult {
+    let active_sheet = *state.active_sheet.lock().unwrap();
+    let (previous, table, idx) = {
+        let mut tables = state.tables.lock().unwrap();
+        let table = match tables
+            .get_mut(&active_sheet)
+            .and_then(|sheet_tables| sheet_tables.get_mut(&params.table_id))
+        {
+            Some(t) => t,
+            None => return TableResult::err("Table not found"),
+        };
+        let idx = match table.get_column_index(&params.column_name) {
+            Some(i) => i,
+            None => return TableResult::err("Column not found"),
+        };
+        let previous = table.clone();
+        table.columns[idx].data_type = params.data_type;
+        (previous, table.clone(), idx)
+    };
+
+    crate::undo_commands::record_table_undo(
+        state,
+        active_sheet,
+        table.id,
+        Some(previous),
+        "Change column data type",
+    );
+
+    let nonconforming = scan_column_conformance(state, &table, idx);
+    let mut result = TableResult::ok(table);
+    if !nonconforming.is_empty() {
+        result.nonconforming_cells = Some(nonconforming);
+    }
+    result
+}
+
+/// Choose whether typed values that break a column's data type are rejected
+/// or coerced.
+#[tauri::command]
+pub fn set_table_data_type_policy(
+    state: State<AppState>,
+    table_id: identity::EntityId,
+    policy: DataTypePolicy,
+) -> TableResult {
+    set_table_data_type_policy_internal(&state, table_id, policy)
+}
+
+pub(crate) fn set_table_data_type_policy_internal(
+    state: &AppState,
+    table_id: identity::EntityId,
+    policy: DataTypePolicy,
+) -> TableResult {
+    let active_sheet = *state.active_sheet.lock().unwrap();
+    let (previous, table) = {
+        let mut tables = state.tables.lock().unwrap();
+        let table = match tables
+            .get_mut(&active_sheet)
+            .and_then(|sheet_tables| sheet_tables.get_mut(&table_id))
+        {
+            Some(t) => t,
+            None => return TableResult::err("Table not found"),
+        };
+        let previous = table.clone();
+        table.data_type_policy = policy;
+        (previous, table.clone())
+    };
+
+    crate::undo_commands::record_table_undo(
+        state,
+        active_sheet,
+        table.id,
+        Some(previous),
+        "Change data type policy",
+    );
+    TableResult::ok(table)
+}
+
 // ============================================================================
 // HELPER FUNCTIONS
 // ============================================================================
 
+/// Re-evaluate the totals row cells of every table on `sheet` with the rows
+/// hidden by filters and outlines excluded, so the SUBTOTAL 101-series
+/// formulas report only the visible data. Returns the cells whose value
+/// changed. Locks the filter state before tables and grids; call it with no
+/// locks held.
+pub(crate) fn recalculate_totals_rows(state: &AppState, sheet: usize) -> Vec<crate::api_types::CellData> {
+    recalculate_visibility_cells(state, sheet, false)
+}
+
+/// Like [`recalculate_totals_rows`], but also re-evaluates every other cell
+/// on `sheet` whose formula calls SUBTOTAL or AGGREGATE. Used after rows were
+/// filtered, grouped or shown again.
+pub(crate) fn recalculate_subtotal_cells(state: &AppState, sheet: usize) -> Vec<crate::api_types::CellData> {
+    recalculate_visibility_cells(state, sheet, true)
+}
+
+fn recalculate_visibility_cells(
+    state: &AppState,
+    sheet: usize,
+    all_subtotals: bool,
+) -> Vec<crate::api_types::CellData> {
+    let hidden_rows = crate::autofilter::sheet_hidden_rows(state, sheet);
+    let tables = state.tables.lock().unwrap();
+    let mut cells: Vec<(u32, u32)> = match tables.get(&sheet) {
+        Some(sheet_tables) => sheet_tables
+            .values()
+            .filter(|t| t.style_options.total_row)
+            .flat_map(|t| (t.start_col..=t.end_col).map(move |col| (t.end_row, col)))
+            .collect(),
+        None => Vec::new(),
+    };
+    if cells.is_empty() && !all_subtotals {
+        return Vec::new();
+    }
+
+    let table_names = state.table_names.lock().unwrap();
+    let named_ranges = state.named_ranges.lock().unwrap();
+    let mut grids = state.grids.lock().unwrap();
+    let sheet_names = state.sheet_names.lock().unwrap();
+    let styles = state.style_registry.lock().unwrap();
+    let locale = state.locale.lock().unwrap();
+    let precision_as_displayed = state.calculation_settings.lock().unwrap().precision_as_displayed;
+    if sheet >= grids.len() {
+        return Vec::new();
+    }
+
+    if all_subtotals {
+        let mut subtotal_cells: Vec<(u32, u32)> = grids[sheet]
+            .cells
+            .iter()
+            .filter(|(pos, cell)| {
+                !cells.contains(*pos) && cell.get_ast().is_some_and(engine::Evaluator::calls_subtotal)
+            })
+            .map(|(pos, _)| *pos)
+            .collect();
+        subtotal_cells.sort_unstable();
+        cells.extend(subtotal_cells);
+    }
+
+    let mut changed = Vec::new();
+    for (row, col) in cells {
+        let Some(cell) = grids[sheet].get_cell(row, col) else { continue };
+        let Some(formula) = cell.formula_string() else { continue };
+        let Ok(parsed) = parser::parse(&formula) else { continue };
+
+        let resolved = if crate::ast_has_named_refs(&parsed) {
+            let mut visited = std::collections::HashSet::new();
+            crate::resolve_names_in_ast(&parsed, &named_ranges, sheet, &mut visited)
+        } else {
+            parsed
+        };
+        let resolved = if crate::ast_has_table_refs(&resolved) {
+            let ctx = crate::TableRefContext {
+                tables: &tables,
+                table_names: &table_names,
+                current_sheet_index: sheet,
+                current_row: row,
+            };
+            crate::resolve_table_refs_in_ast(&resolved, &ctx)
+        } else {
+            resolved
+        };
+
+        let eval_ctx = engine::EvalContext {
+            current_row: Some(row),
+            current_col: Some(col),
+            hidden_rows: Some(hidden_rows.clone()),
+            ..Default::default()
+        };
+        let result = crate::evaluate_formula_raw(
+            &grids,
+            &sheet_names,
+            sheet,
+            &crate::convert_expr(&resolved),
+            eval_ctx,
+            Some(&styles),
+        );
+        let value = crate::calculation::stored_result(
+            result.to_cell_value(),
+            styles.get(cell.style_index),
+            precision_as_displayed,
+        );
+        if value == cell.value {
+            continue;
+        }
+
+        let mut updated = cell.clone();
+        updated.value = value;
+        let display = crate::format_cell_value(&updated.value, styles.get(updated.style_index), &locale);
+        changed.push(crate::api_types::CellData {
+            row,
+            col,
+            display,
+            display_color: None,
+            formula: Some(format!("={}", formula)),
+            style_index: updated.style_index,
+            row_span: 1,
+            col_span: 1,
+            sheet_index: Some(sheet),
+            rich_text: None,
+            accounting_layout: None,
+            error_detail: None,
+        });
+        grids[sheet].set_cell(row, col, updated);
+    }
+    changed
+}
+
 /// Check if two ranges overlap
 fn ranges_overlap(
     r1_start_row: u32, r1_start_col: u32, r1_end_row: u32, r1_end_col: u32,
@@ -1843,6 +2394,7 @@ mod tests {
             style_options: TableStyleOptions::default(),
             style_name: "TableStyleMedium2".to_string(),
             auto_filter_id: None,
+            data_type_policy: Default::default(),
         };
 
         assert!(table.contains(5, 2));
@@ -1870,6 +2422,7 @@ mod tests {
             },
             style_name: "TableStyleMedium2".to_string(),
             auto_filter_id: None,
+            data_type_policy: Default::default(),
         };
 
         assert_eq!(table.data_start_row(), 1);
@@ -1932,6 +2485,7 @@ mod tests {
             style_options: TableStyleOptions::default(),
             style_name: "TableStyleMedium2".to_string(),
             auto_filter_id: None,
+            data_type_policy: Default::default(),
         };
 
         assert!(table.get_column_by_name("Name").is_some());
@@ -1939,4 +2493,43 @@ mod tests {
         assert!(table.get_column_by_name("AMOUNT").is_some());
         assert!(table.get_column_by_name("Missing").is_none());
     }
+
+    #[test]
+    fn test_check_input_type_reject_policy() {
+        let locale = engine::LocaleSettings::invariant();
+        let check = |input: &str, data_type| check_input_type(input, data_type, DataTypePolicy::Reject, &locale);
+
+        assert_eq!(check("42.5", ColumnDataType::Number), TypeCheck::Accept);
+        assert_eq!(check("", ColumnDataType::Number), TypeCheck::Accept);
+        assert_eq!(check("=A1*2", ColumnDataType::Number), TypeCheck::Accept);
+        assert_eq!(check("1,200", ColumnDataType::Number), TypeCheck::Accept);
+        assert_eq!(check("abc", ColumnDataType::Number), TypeCheck::Reject(ColumnDataType::Number));
+        assert_eq!(check("1/15/2024", ColumnDataType::Number), TypeCheck::Reject(ColumnDataType::Number));
+        assert_eq!(check("1/15/2024", ColumnDataType::Date), TypeCheck::Accept);
+        assert_eq!(check("45306", ColumnDataType::Date), TypeCheck::Reject(ColumnDataType::Date));
+        assert_eq!(check("'00123", ColumnDataType::Text), TypeCheck::Accept);
+        assert_eq!(check("123", ColumnDataType::Text), TypeCheck::Reject(ColumnDataType::Text));
+        assert_eq!(check("true", ColumnDataType::Boolean), TypeCheck::Accept);
+        assert_eq!(check("yes", ColumnDataType::Boolean), TypeCheck::Reject(ColumnDataType::Boolean));
+    }
+
+    #[test]
+    fn test_check_input_type_coerce_policy() {
+        let locale = engine::LocaleSettings::invariant();
+        let check = |input: &str, data_type| check_input_type(input, data_type, DataTypePolicy::Coerce, &locale);
+
+        assert_eq!(check("12 kg", ColumnDataType::Number), TypeCheck::Coerce("12".to_string()));
+        assert_eq!(check("$1,200", ColumnDataType::Number), TypeCheck::Coerce("1200".to_string()));
+        assert_eq!(check("TRUE", ColumnDataType::Number), TypeCheck::Coerce("1".to_string()));
+        assert_eq!(check("2024-01-15", ColumnDataType::Number), TypeCheck::Coerce("45306".to_string()));
+        assert_eq!(check("45306", ColumnDataType::Date), TypeCheck::Coerce("2024-01-15".to_string()));
+        assert_eq!(check("123", ColumnDataType::Text), TypeCheck::Coerce("'123".to_string()));
+        assert_eq!(check("yes", ColumnDataType::Boolean), TypeCheck::Coerce("TRUE".to_string()));
+        assert_eq!(check("0", ColumnDataType::Boolean), TypeCheck::Coerce("FALSE".to_string()));
+        // No sensible conversion: still rejected
+        assert_eq!(check("n/a", ColumnDataType::Number), TypeCheck::Reject(ColumnDataType::Number));
+        assert_eq!(check("soon", ColumnDataType::Date), TypeCheck::Reject(ColumnDataType::Date));
+        // Conforming input is left alone
+        assert_eq!(check("7", ColumnDataType::Number), TypeCheck::Accept);
+    }
 }
@@ -109,6 +109,137 @@ fn test_parse_cell_input() {
     // Percentage
     let cell = parse_cell_input("50%", &locale);
     assert!(matches!(cell.value, CellValue::Number(n) if (n - 0.5).abs() < 0.001));
+
+    // Currency and accounting negatives read as VALUE reads them
+    let cell = parse_cell_input("($1,250.50)", &locale);
+    assert!(matches!(cell.value, CellValue::Number(n) if n == -1250.5));
+    let de = engine::LocaleSettings::from_locale_id("de-DE");
+    let cell = parse_cell_input("1.234,5 \u{20AC}", &de);
+    assert!(matches!(cell.value, CellValue::Number(n) if n == 1234.5));
+    let cell = parse_cell_input("1.2.3,4,5", &de);
+    assert!(matches!(cell.value, CellValue::Text(_)));
+}
+
+#[test]
+fn test_parse_cell_input_quote_prefix() {
+    let locale = engine::LocaleSettings::invariant();
+
+    // Leading zeros survive as text; the apostrophe is not stored
+    let cell = parse_cell_input("'00123", &locale);
+    assert!(matches!(cell.value, CellValue::Text(ref s) if s == "00123"));
+    assert!(cell.quote_prefix);
+
+    // A quoted formula is literal text, not a formula
+    let cell = parse_cell_input("'=SUM(A1)", &locale);
+    assert!(matches!(cell.value, CellValue::Text(ref s) if s == "=SUM(A1)"));
+    assert!(!cell.has_formula());
+    let cell = parse_cell_input_invariant("'TRUE", &locale);
+    assert!(matches!(cell.value, CellValue::Text(ref s) if s == "TRUE"));
+    assert!(!parse_cell_input("00123", &locale).quote_prefix);
+
+    // Text-formatted cells keep typed input verbatim
+    let text_style = CellStyle::new().with_number_format(NumberFormat::Custom { format: "@".to_string() });
+    let cell = text_format_input("00123", &text_style).expect("text format");
+    assert!(matches!(cell.value, CellValue::Text(ref s) if s == "00123"));
+    assert!(!cell.quote_prefix);
+    let cell = text_format_input("=SUM(A1)", &text_style).expect("text format");
+    assert!(!cell.has_formula());
+    assert!(text_format_input("00123", &CellStyle::new()).is_none());
+}
+
+/// German function names are accepted on input and shown on display, but the
+/// stored formula is canonical: a file saved under one function language reads
+/// back unchanged under another.
+#[test]
+fn test_localized_function_names_round_trip_through_file() {
+    let mut de = engine::LocaleSettings::from_locale_id("de-DE");
+    de.function_language = "de".to_string();
+    let en = engine::LocaleSettings::invariant();
+
+    let cell = parse_cell_input("=WENN(SUMME(A1;A2)>1,5;\"Summe\";MYUDF(A1))", &de);
+    assert_eq!(cell.formula_string(), Some("IF(SUM(A1,A2)>1.5,\"Summe\",MYUDF(A1))".to_string()));
+    // English names still work with the German table active.
+    assert_eq!(parse_cell_input("=sum(A1;A2)", &de).formula_string(), Some("SUM(A1,A2)".to_string()));
+    let shown = engine::localize_formula(&cell.formula_string().unwrap(), &de);
+    assert_eq!(shown, "WENN(SUMME(A1;A2)>1,5;\"Summe\";MYUDF(A1))");
+    assert_eq!(parse_cell_input(&format!("={}", shown), &de).formula_string(), cell.formula_string());
+    assert_eq!(builtin_function_to_localized_name(&ParserBuiltinFn::CountIf, "de"), "ZÄHLENWENN");
+    assert_eq!(builtin_function_to_localized_name(&ParserBuiltinFn::Max, "de"), "MAX");
+
+    let mut grid = Grid::new();
+    grid.set_cell(0, 2, cell);
+    let workbook = ::persistence::Workbook::from_grid(
+        &grid,
+        &engine::StyleRegistry::new(),
+        &::persistence::DimensionData::default(),
+    );
+    assert_eq!(
+        workbook.sheets[0].cells[&(0, 2)].formula.as_deref(),
+        Some("IF(SUM(A1,A2)>1.5,\"Summe\",MYUDF(A1))")
+    );
+    let dir = tempfile::TempDir::new().unwrap();
+    let path = dir.path().join("names.xlsx");
+    ::persistence::save_xlsx(&workbook, &path).unwrap();
+    let (reloaded, _) = ::persistence::load_xlsx(&path).unwrap().sheets[0].to_grid();
+    let formula = reloaded.get_cell(0, 2).unwrap().formula_string().unwrap();
+    assert_eq!(engine::localize_formula(&formula, &en), "IF(SUM(A1,A2)>1.5,\"Summe\",MYUDF(A1))");
+    assert_eq!(engine::localize_formula(&formula, &de), shown);
+}
+
+#[test]
+fn test_quote_prefixed_cell_shows_apostrophe_in_formula_bar() {
+    use crate::commands::data::get_cell_impl;
+
+    let state = create_app_state();
+    {
+        let locale = state.locale.lock().unwrap();
+        let mut grid = state.get_active_grid();
+        grid.set_cell(0, 0, parse_cell_input("'00123", &locale));
+        grid.set_cell(0, 1, parse_cell_input("'=SUM(A1)", &locale));
+    }
+    let a1 = get_cell_impl(&state, 0, 0).unwrap();
+    assert_eq!(a1.display, "00123");
+    assert_eq!(a1.formula.as_deref(), Some("'00123"));
+    let b1 = get_cell_impl(&state, 0, 1).unwrap();
+    assert_eq!(b1.display, "=SUM(A1)");
+    assert_eq!(b1.formula.as_deref(), Some("'=SUM(A1)"));
+}
+
+#[test]
+fn test_parse_cell_input_dates() {
+    let us = engine::LocaleSettings::invariant();
+    let de = engine::LocaleSettings::from_locale_id("de-DE");
+    let jan15 = engine::date_serial::date_to_serial(2024, 1, 15);
+
+    // Typed dates become serials; slash/dot order follows the locale.
+    for input in ["2024-01-15", "1/15/2024", "15 Jan 2024"] {
+        let cell = parse_cell_input(input, &us);
+        assert!(matches!(cell.value, CellValue::Number(n) if n == jan15), "{}", input);
+    }
+    let cell = parse_cell_input("15.01.2024", &de);
+    assert!(matches!(cell.value, CellValue::Number(n) if n == jan15));
+    let cell = parse_cell_input("2024-01-15 12:00", &us);
+    assert!(matches!(cell.value, CellValue::Number(n) if n == jan15 + 0.5));
+    // Not a valid date: stays text.
+    let cell = parse_cell_input("2024-02-30", &us);
+    assert!(matches!(cell.value, CellValue::Text(_)));
+
+    // The locale's date pattern is applied to a General style only.
+    assert_eq!(
+        date_input_number_format("1/15/2024", &us),
+        Some(NumberFormat::Date { format: "MM/DD/YYYY".to_string() })
+    );
+    assert_eq!(
+        date_input_number_format("2024-01-15 9:30", &de),
+        Some(NumberFormat::DateTime { format: "DD.MM.YYYY HH:MM".to_string() })
+    );
+    assert_eq!(date_input_number_format("42", &us), None);
+
+    let mut styles = StyleRegistry::new();
+    let dated = style_for_date_input(&mut styles, 0, "2024-01-15", &us).expect("date style");
+    assert!(matches!(styles.get(dated).number_format, NumberFormat::Date { .. }));
+    let percent = styles.get_or_create(CellStyle::new().with_number_format(NumberFormat::Percentage { decimal_places: 0 }));
+    assert_eq!(style_for_date_input(&mut styles, percent, "2024-01-15", &us), None);
 }
 
 #[test]
@@ -167,6 +298,31 @@ fn test_evaluate_formula_column_ref() {
     assert!(matches!(result, CellValue::Number(n) if (n - 60.0).abs() < 0.001));
 }
 
+#[test]
+fn test_offset_depends_on_anchor_columns_and_rows() {
+    let grid = Grid::new();
+    let refs = |formula: &str| extract_all_references(&parse_formula(formula).unwrap(), &grid);
+
+    // The anchor's cells, plus its whole columns and rows as a fallback for
+    // the cells OFFSET reads without naming them.
+    let offset = refs("=SUM(OFFSET(B2:B3, 1, 0, 3, 1))");
+    assert!(offset.cells.contains(&(1, 1)) && offset.cells.contains(&(2, 1)));
+    assert_eq!(offset.columns, [1].into_iter().collect());
+    assert_eq!(offset.rows, [1, 2].into_iter().collect());
+
+    let single = refs("=OFFSET(C5, 0, 2)");
+    assert_eq!(single.columns, [2].into_iter().collect());
+    assert_eq!(single.rows, [4].into_iter().collect());
+
+    // Other sheets have no stripes; volatility covers them. Plain ranges
+    // get no stripes either.
+    let cross = refs("=OFFSET(Sheet2!A1, 1, 0)");
+    assert!(cross.cross_sheet_cells.contains(&("Sheet2".to_string(), 0, 0)));
+    assert!(cross.columns.is_empty() && cross.rows.is_empty());
+    let plain = refs("=SUM(B2:B3)");
+    assert!(plain.columns.is_empty() && plain.rows.is_empty());
+}
+
 #[test]
 fn test_evaluate_formula_error() {
     let grid = Grid::new();
@@ -176,6 +332,64 @@ fn test_evaluate_formula_error() {
     assert!(matches!(result, CellValue::Error(CellError::Div0)));
 }
 
+#[test]
+fn test_evaluate_formula_with_overlays() {
+    let mut sheet1 = Grid::new();
+    sheet1.set_cell(0, 0, Cell::new_number(10.0)); // A1 = 10
+    let mut sheet2 = Grid::new();
+    sheet2.set_cell(0, 0, Cell::new_number(3.0)); // Sheet2!A1 = 3
+    let grids = vec![sheet1, sheet2];
+    let names = vec!["Sheet1".to_string(), "Sheet2".to_string()];
+
+    let mut overlays: Vec<engine::OverlayGrid> = grids.iter().map(engine::OverlayGrid::new).collect();
+    overlays[0].set_cell(0, 0, Cell::new_number(4.0));
+
+    // The overridden A1 is seen from the current sheet, the real grids are not.
+    let result = evaluate_formula_with_overlays(&overlays, &names, 0, "=A1*Sheet2!A1", engine::EvalContext::default());
+    assert!(matches!(result, CellValue::Number(n) if (n - 12.0).abs() < 0.001));
+    let result = evaluate_formula_multi_sheet(&grids, &names, 0, "=A1*Sheet2!A1", engine::EvalContext::default());
+    assert!(matches!(result, CellValue::Number(n) if (n - 30.0).abs() < 0.001));
+}
+
+#[test]
+fn test_formulas_evaluate_at_their_own_cell() {
+    let grids = vec![Grid::new()];
+    let names = vec!["Sheet1".to_string()];
+
+    // One shared evaluator, moved to each entry's cell
+    let formulas = [((2, 1), "=ROW()*100+COLUMN()"), ((9, 3), "=ROW()*100+COLUMN()")];
+    let results = batch_evaluate_formulas(&grids, &names, 0, &formulas);
+    assert_eq!(results[0], ((2, 1), CellValue::Number(302.0)));
+    assert_eq!(results[1], ((9, 3), CellValue::Number(1004.0)));
+
+    let result = evaluate_formula_multi_sheet(&grids, &names, 0, "=CELL(\"address\")", engine::EvalContext::at(4, 2));
+    assert_eq!(result, CellValue::Text("$C$5".to_string()));
+}
+
+/// Every evaluation path must say which cell it computes, so ROW()/COLUMN()
+/// stay correct; only the engine's own constructors may omit the context.
+#[test]
+fn test_app_evaluators_are_built_with_context() {
+    fn scan(dir: &std::path::Path, offenders: &mut Vec<String>) {
+        for entry in std::fs::read_dir(dir).unwrap() {
+            let path = entry.unwrap().path();
+            if path.is_dir() {
+                scan(&path, offenders);
+            } else if path.extension().is_some_and(|ext| ext == "rs") && !path.ends_with("tests.rs") {
+                let source = std::fs::read_to_string(&path).unwrap();
+                for (i, line) in source.lines().enumerate() {
+                    if line.contains("Evaluator::new(") || line.contains("Evaluator::with_multi_sheet(") {
+                        offenders.push(format!("{}:{}", path.display(), i + 1));
+                    }
+                }
+            }
+        }
+    }
+    let mut offenders = Vec::new();
+    scan(&std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("src"), &mut offenders);
+    assert!(offenders.is_empty(), "construct with Evaluator::with_context: {:?}", offenders);
+}
+
 #[test]
 fn test_col_letter_to_index() {
     assert_eq!(col_letter_to_index("A"), 0);
@@ -275,6 +489,251 @@ fn test_multi_root_order_includes_and_orders_seeds() {
     assert_eq!(order, vec![a1, b1, c1]);
 }
 
+/// Volatile cells: B1 = RAND()*10, C1 = B1+1, unrelated A5 = 7 -> A6 = A5*2.
+/// Editing A5 recalculates A6, and also B1 and its dependent C1 even though
+/// none of their precedents changed. Replacing B1 with a constant drops it
+/// from the volatile set again.
+#[test]
+fn test_volatile_cells_recalculate_on_unrelated_edit() {
+    let mut dependents = crate::DependencyMap::default();
+    let mut volatile = crate::CoordSet::default();
+    let a5 = (4u32, 0u32);
+    let a6 = (5u32, 0u32);
+    let b1 = (0u32, 1u32);
+    let c1 = (0u32, 2u32);
+    dependents.insert(a5, crate::CoordSet::from_iter([a6]));
+    dependents.insert(b1, crate::CoordSet::from_iter([c1]));
+
+    let rand_cell = Cell::new_formula("RAND()*10".to_string());
+    crate::track_volatile_cell(&mut volatile, b1, Some(&rand_cell));
+    let plain_cell = Cell::new_formula("B1+1".to_string());
+    crate::track_volatile_cell(&mut volatile, c1, Some(&plain_cell));
+    assert_eq!(volatile, crate::CoordSet::from_iter([b1]));
+
+    let order = crate::get_recalculation_order_with_volatile(a5, &volatile, &dependents);
+    assert_eq!(order.len(), 3);
+    assert!(order.contains(&a6));
+    let pos = |cell| order.iter().position(|&c| c == cell).unwrap();
+    assert!(pos(b1) < pos(c1));
+    assert!(!order.contains(&a5));
+
+    // Editing the volatile cell itself never re-evaluates it twice.
+    let order = crate::get_recalculation_order_with_volatile(b1, &volatile, &dependents);
+    assert_eq!(order, vec![c1]);
+
+    crate::track_volatile_cell(&mut volatile, b1, Some(&Cell::new_number(3.0)));
+    assert!(volatile.is_empty());
+    assert_eq!(crate::get_recalculation_order_with_volatile(a5, &volatile, &dependents), vec![a6]);
+}
+
+/// A formula that fails keeps the engine's explanation next to the error
+/// value; cells that merely inherit the error carry none, and fixing the
+/// formula drops the stale explanation.
+#[test]
+fn test_error_details_follow_recalculation() {
+    use crate::commands::data::get_cell_impl;
+
+    let state = create_app_state();
+    let user_files_state = UserFilesState { files: Mutex::new(HashMap::new()) };
+    let pivot_state = crate::pivot::types::PivotState::new();
+    {
+        let mut grid = state.get_active_grid();
+        grid.set_cell(0, 0, Cell::new_formula("SQRT(-1)".to_string()));
+        grid.set_cell(0, 1, Cell::new_formula("A1+1".to_string()));
+    }
+    crate::calculation::recalculate_sheet_values(&state, &user_files_state, &pivot_state, 0, None);
+
+    let a1 = get_cell_impl(&state, 0, 0).unwrap();
+    assert_eq!(a1.display, "#VALUE!");
+    assert_eq!(a1.error_detail.as_deref(), Some("SQRT argument was negative"));
+    let b1 = get_cell_impl(&state, 0, 1).unwrap();
+    assert_eq!(b1.display, "#VALUE!");
+    assert_eq!(b1.error_detail, None);
+
+    state.get_active_grid().set_cell(0, 0, Cell::new_formula("SQRT(4)".to_string()));
+    crate::calculation::recalculate_sheet_values(&state, &user_files_state, &pivot_state, 0, None);
+    assert_eq!(get_cell_impl(&state, 0, 0).unwrap().error_detail, None);
+    assert!(state.error_details.lock().unwrap().is_empty());
+}
+
+/// Batch paste of 5k formulas (B_i = A_i * 2) under a SUM over column B,
+/// through the real batch command: the paste is a single undo step, each
+/// pasted formula is evaluated once when stored and the SUM once afterwards.
+#[test]
+fn test_batch_paste_single_undo_step_and_single_evaluation() {
+    use crate::api_types::CellUpdateInput;
+    use crate::commands::data::update_cells_batch_with_controls;
+    const N: u32 = 5_000;
+    let state = create_app_state();
+    let file_state = FileState::default();
+    let user_files = UserFilesState { files: Mutex::new(HashMap::new()) };
+    let pivot = crate::pivot::types::PivotState::new();
+    let input = |row: u32, col: u32, value: String| CellUpdateInput { row, col, value, style_index: None, invariant: None };
+    let batch = |updates: Vec<CellUpdateInput>| {
+        update_cells_batch_with_controls(&state, &file_state, &user_files, &pivot, updates, None, None).unwrap()
+    };
+
+    let mut setup: Vec<CellUpdateInput> = (0..N).map(|r| input(r, 0, (r + 1).to_string())).collect();
+    setup.push(input(N, 2, format!("=SUM(B1:B{})", N)));
+    batch(setup);
+    let depth = state.undo_stack.lock().unwrap().undo_depth();
+
+    let before = engine::evaluation_count();
+    let cells = batch((0..N).map(|r| input(r, 1, format!("=A{}*2", r + 1))).collect());
+    // Formula evaluations: each of the N pasted formulas once, plus one
+    // recalculation of the SUM that depends on all of them.
+    assert_eq!(engine::evaluation_count() - before, N as u64 + 1);
+    assert_eq!(state.undo_stack.lock().unwrap().undo_depth(), depth + 1);
+
+    // The returned payload holds the N pasted cells and the recalculated SUM.
+    // B_i = 2 * i, so the SUM shows 2 * (1 + ... + N) = N * (N + 1).
+    assert_eq!(cells.len(), N as usize + 1);
+    let total = cells.iter().find(|c| (c.row, c.col) == (N, 2)).unwrap();
+    assert_eq!(total.display, (N * (N + 1)).to_string());
+
+    // A pasted formula reading a cell written later in the same batch is
+    // evaluated again once that cell is in place.
+    let before = engine::evaluation_count();
+    let cells = batch(vec![input(0, 3, "=E1+1".to_string()), input(0, 4, "41".to_string())]);
+    assert_eq!(engine::evaluation_count() - before, 2);
+    assert_eq!(cells.iter().find(|c| (c.row, c.col) == (0, 3)).unwrap().display, "42");
+}
+
+/// Manual mode: a single-cell edit evaluates nothing downstream and leaves
+/// its dependents dirty, so the calculation state reports them as pending.
+#[test]
+fn test_update_cell_in_manual_mode_marks_dependents_dirty() {
+    use crate::commands::data::update_cell_impl;
+    let state = create_app_state();
+    let file_state = FileState::default();
+    let user_files = UserFilesState { files: Mutex::new(HashMap::new()) };
+    let slicer = crate::slicer::SlicerState::new();
+    let pivot = crate::pivot::types::PivotState::new();
+    let pane = PaneControlState::new();
+    let ribbon = RibbonFilterState::new();
+    let edit = |row: u32, col: u32, value: &str| {
+        update_cell_impl(&state, &file_state, &user_files, &slicer, &pivot, &pane, &ribbon, row, col, value.to_string(), None, None)
+            .unwrap()
+    };
+
+    edit(0, 0, "1");
+    edit(0, 1, "=A1*2");
+    edit(0, 2, "=B1+1");
+    edit(0, 3, "=SUM(A:A)");
+    assert!(state.dirty_cells.lock().unwrap().is_empty());
+
+    state.calculation_settings.lock().unwrap().mode = crate::calculation::CalculationMode::Manual;
+    let before = engine::evaluation_count();
+    edit(0, 0, "5");
+    assert_eq!(engine::evaluation_count(), before);
+    let mut dirty: Vec<(usize, u32, u32)> = state.dirty_cells.lock().unwrap().iter().copied().collect();
+    dirty.sort();
+    assert_eq!(dirty, vec![(0, 0, 1), (0, 0, 2), (0, 0, 3)]);
+    assert_eq!(crate::commands::data::get_cell_impl(&state, 0, 1).unwrap().display, "2");
+}
+
+/// Two independent clusters on Sheet1: A1 -> B1=A1*2 -> C1=B1+1 and
+/// A10 -> B10=A10*3 -> C10=B10+1, read from Sheet2 by A1=Sheet1!C1*10 ->
+/// B1=A1+1 and A10=Sheet1!C10. Recalculating Sheet1!B1:B1 evaluates B1, C1
+/// and the Sheet2 cells downstream of them once each; nothing of the second
+/// cluster is evaluated.
+#[test]
+fn test_range_recalc_leaves_other_cluster_untouched() {
+    use crate::calculation::{calculate_range_internal, CalculationMode};
+    use crate::commands::data::update_cell_impl;
+    use crate::sheets::{add_sheet_internal, set_active_sheet_internal};
+    let state = create_app_state();
+    let file_state = FileState::default();
+    let user_files = UserFilesState { files: Mutex::new(HashMap::new()) };
+    let slicer = crate::slicer::SlicerState::new();
+    let pivot = crate::pivot::types::PivotState::new();
+    let pane = PaneControlState::new();
+    let ribbon = RibbonFilterState::new();
+    let edit = |sheet: usize, row: u32, col: u32, value: &str| {
+        set_active_sheet_internal(&state, sheet).unwrap();
+        update_cell_impl(&state, &file_state, &user_files, &slicer, &pivot, &pane, &ribbon, row, col, value.to_string(), None, None)
+            .unwrap();
+    };
+    let value = |sheet: usize, row: u32, col: u32| {
+        state.grids.lock().unwrap()[sheet].get_cell(row, col).map(|c| c.value.clone()).unwrap()
+    };
+    let range = |sheet: usize, rect: (u32, u32, u32, u32)| {
+        calculate_range_internal(&state, &user_files, &pivot, Some((&pane, &ribbon)), sheet, rect).unwrap()
+    };
+
+    add_sheet_internal(&state, Some("Sheet2".to_string())).unwrap();
+    for (top, factor) in [(0u32, "2"), (9u32, "3")] {
+        edit(0, top, 0, "1");
+        edit(0, top, 1, &format!("=A{}*{}", top + 1, factor));
+        edit(0, top, 2, &format!("=B{}+1", top + 1));
+    }
+    edit(1, 0, 0, "=Sheet1!C1*10");
+    edit(1, 0, 1, "=A1+1");
+    edit(1, 9, 0, "=Sheet1!C10");
+    set_active_sheet_internal(&state, 0).unwrap();
+
+    state.calculation_settings.lock().unwrap().mode = CalculationMode::Manual;
+    edit(0, 0, 0, "5");
+    edit(0, 9, 0, "7");
+
+    let before = engine::evaluation_count();
+    let cells = range(0, (0, 1, 0, 1));
+    assert_eq!(engine::evaluation_count() - before, 4);
+    let mut changed: Vec<(Option<usize>, u32, u32, &str)> =
+        cells.iter().map(|c| (c.sheet_index, c.row, c.col, c.display.as_str())).collect();
+    changed.sort();
+    assert_eq!(
+        changed,
+        vec![(None, 0, 1, "10"), (None, 0, 2, "11"), (Some(1), 0, 0, "110"), (Some(1), 0, 1, "111")]
+    );
+    // The second cluster still holds its values from before the edit.
+    assert_eq!(value(0, 9, 1), CellValue::Number(3.0));
+    assert_eq!(value(0, 9, 2), CellValue::Number(4.0));
+    assert_eq!(value(1, 9, 0), CellValue::Number(4.0));
+    let dirty = state.dirty_cells.lock().unwrap().clone();
+    assert!(!dirty.contains(&(0, 0, 1)) && !dirty.contains(&(1, 0, 1)));
+    assert!(dirty.contains(&(0, 9, 1)));
+    drop(dirty);
+
+    // A range on an inactive sheet follows that sheet's own dependents.
+    let before = engine::evaluation_count();
+    let cells = range(1, (0, 0, 0, 0));
+    assert_eq!(engine::evaluation_count() - before, 2);
+    assert!(cells.is_empty());
+
+    // A rectangle with no formula cells evaluates nothing.
+    let before = engine::evaluation_count();
+    assert!(range(0, (0, 0, 20, 0)).is_empty());
+    assert_eq!(engine::evaluation_count(), before);
+    assert!(calculate_range_internal(&state, &user_files, &pivot, None, 5, (0, 0, 0, 0)).is_err());
+}
+
+#[test]
+fn test_consolidate_cell_updates_keeps_last_state_per_cell() {
+    let cell = |row: u32, col: u32, display: &str| CellData {
+        row,
+        col,
+        display: display.to_string(),
+        display_color: None,
+        formula: None,
+        style_index: 0,
+        row_span: 1,
+        col_span: 1,
+        sheet_index: None,
+        rich_text: None,
+        accounting_layout: None,
+        error_detail: None,
+    };
+    let out = crate::commands::data::consolidate_cell_updates(vec![
+        cell(0, 1, "stale"),
+        cell(0, 0, "1"),
+        cell(0, 1, "2"),
+    ]);
+    assert_eq!(out.len(), 2);
+    assert_eq!((out[0].row, out[0].col, out[0].display.as_str()), (0, 1, "2"));
+    assert_eq!((out[1].row, out[1].col, out[1].display.as_str()), (0, 0, "1"));
+}
+
 // ============================================================================
 // PIVOT COMMANDS TESTS
 // ============================================================================
@@ -453,7 +912,7 @@ fn run_go_to_special(
     criteria: &str,
     search_range: Option<(u32, u32, u32, u32)>,
 ) -> Vec<(u32, u32)> {
-    let grid = state.grid.lock().unwrap();
+    let grid = state.get_active_grid();
     let active_sheet = *state.active_sheet.lock().unwrap();
     let (sr, sc, er, ec) = search_range.unwrap_or((0, 0, grid.max_row, grid.max_col));
 
@@ -559,7 +1018,7 @@ fn test_go_to_special_blanks() {
 
     // Set up a small grid: A1=10, A2=empty, A3="hello", B1=empty, B2=20
     {
-        let mut grid = state.grid.lock().unwrap();
+        let mut grid = state.get_active_grid();
         grid.set_cell(0, 0, Cell::new_number(10.0));
         // (1, 0) is empty
         grid.set_cell(2, 0, Cell::new_text("hello".to_string()));
@@ -582,7 +1041,7 @@ fn test_go_to_special_formulas() {
     let state = create_app_state();
 
     {
-        let mut grid = state.grid.lock().unwrap();
+        let mut grid = state.get_active_grid();
         grid.set_cell(0, 0, Cell::new_number(10.0)); // constant
         let mut formula_cell = Cell::new_number(30.0);
         formula_cell.ast = parser::parse("=A1+20").ok().map(Box::new);
@@ -599,7 +1058,7 @@ fn test_go_to_special_constants() {
     let state = create_app_state();
 
     {
-        let mut grid = state.grid.lock().unwrap();
+        let mut grid = state.get_active_grid();
         grid.set_cell(0, 0, Cell::new_number(10.0)); // constant
         let mut formula_cell = Cell::new_number(30.0);
         formula_cell.ast = parser::parse("=10+20").ok().map(Box::new);
@@ -617,7 +1076,7 @@ fn test_go_to_special_errors() {
     let state = create_app_state();
 
     {
-        let mut grid = state.grid.lock().unwrap();
+        let mut grid = state.get_active_grid();
         grid.set_cell(0, 0, Cell::new_number(10.0));
         let mut div0_cell = Cell { value: CellValue::Error(CellError::Div0), ..Cell::default() };
         div0_cell.ast = parser::parse("=1/0").ok().map(Box::new);
@@ -734,7 +1193,7 @@ fn test_go_to_special_with_search_range_filter() {
     let state = create_app_state();
 
     {
-        let mut grid = state.grid.lock().unwrap();
+        let mut grid = state.get_active_grid();
         grid.set_cell(0, 0, Cell::new_number(1.0));
         grid.set_cell(5, 5, Cell::new_number(2.0));
         grid.set_cell(10, 10, Cell::new_number(3.0));
@@ -774,4 +1233,4140 @@ fn test_go_to_special_empty_grid() {
 
     let errors = run_go_to_special(&state, "errors", Some((0, 0, 2, 2)));
     assert!(errors.is_empty());
-}
\ No newline at end of file
+}
+// ============================================================================
+// VIEWPORT SNAPSHOT TESTS
+// ============================================================================
+
+fn viewport_test_sources<'a>(
+    grid: &'a Grid,
+    styles: &'a engine::StyleRegistry,
+    locale: &'a engine::LocaleSettings,
+    merges: &'a HashSet<MergedRegion>,
+    cf_rules: &'a [crate::ConditionalFormatDefinition],
+    hidden_rows: &'a HashSet<u32>,
+    hidden_cols: &'a HashSet<u32>,
+) -> crate::commands::data::ViewportSources<'a> {
+    crate::commands::data::ViewportSources {
+        sheet_index: 0,
+        grid,
+        grids: std::slice::from_ref(grid),
+        sheet_names: &[],
+        styles,
+        locale,
+        merged_regions: merges,
+        cf_rules,
+        comments: None,
+        hyperlinks: None,
+        validations: &[],
+        hidden_rows,
+        hidden_cols,
+    }
+}
+
+/// The snapshot must agree with the individual commands it replaces: cell
+/// data with get_cell, CF overrides with evaluate_conditional_formats, and
+/// merges / hidden rows clipped to the viewport.
+#[test]
+fn test_viewport_snapshot_matches_individual_calls() {
+    use crate::commands::data::{get_cell_impl, get_viewport_snapshot_internal};
+    use crate::conditional_formatting::evaluate_conditional_formats_internal;
+    let state = create_app_state();
+    {
+        let mut grids = state.grids.lock().unwrap();
+        for r in 0..20u32 {
+            grids[0].set_cell(r, 0, Cell::new_number(r as f64));
+        }
+        grids[0].set_cell(2, 1, Cell::new_text("merged".to_string()));
+    }
+    {
+        let mut merges = state.merged_regions.lock().unwrap();
+        merges.insert(MergedRegion { start_row: 2, start_col: 1, end_row: 3, end_col: 2 });
+        merges.insert(MergedRegion { start_row: 50, start_col: 0, end_row: 51, end_col: 1 });
+    }
+    let rule = |id: u64, operator: crate::CellValueOperator, value: &str, color: &str| crate::ConditionalFormatDefinition {
+        id,
+        priority: id as u32,
+        rule: crate::ConditionalFormatRule::CellValue(crate::CellValueRule {
+            operator,
+            value1: value.to_string(),
+            value2: None,
+        }),
+        format: crate::ConditionalFormat {
+            background_color: Some(color.to_string()),
+            ..Default::default()
+        },
+        ranges: vec![crate::ConditionalFormatRange { start_row: 0, start_col: 0, end_row: 19, end_col: 0 }],
+        stop_if_true: false,
+        enabled: true,
+    };
+    state.conditional_formats.lock().unwrap().insert(
+        0,
+        vec![
+            rule(1, crate::CellValueOperator::GreaterThan, "5", "#ff0000"),
+            rule(2, crate::CellValueOperator::LessThan, "2", "#00ff00"),
+        ],
+    );
+    state.advanced_filter_hidden_rows.lock().unwrap().insert(0, vec![4, 30]);
+
+    let snap = get_viewport_snapshot_internal(&state, 0, 0, 0, 9, 3, None).unwrap();
+
+    for cell in &snap.cells {
+        let single = get_cell_impl(&state, cell.row, cell.col).expect("snapshot cell must exist individually");
+        assert_eq!(cell.display, single.display);
+        assert_eq!((cell.row_span, cell.col_span), (single.row_span, single.col_span));
+    }
+    // Rows 0..=9 of column A plus the merge master at B3.
+    assert_eq!(snap.cells.len(), 11);
+
+    let command_cf = evaluate_conditional_formats_internal(&state, 0, 0, 9, 3);
+    assert_eq!(
+        serde_json::to_value(&snap.conditional_formats).unwrap(),
+        serde_json::to_value(&command_cf.cells).unwrap(),
+    );
+    let snap_cf: Vec<(u32, u32)> = snap.conditional_formats.iter().map(|c| (c.row, c.col)).collect();
+    assert_eq!(snap_cf, vec![(0, 0), (1, 0), (6, 0), (7, 0), (8, 0), (9, 0)]);
+
+    assert_eq!(snap.merged_regions.len(), 1);
+    assert_eq!(snap.hidden_rows, vec![4]);
+    assert!(snap.hidden_cols.is_empty());
+}
+
+/// A large styled sheet with a sheet-wide CF rule: the snapshot only touches
+/// the visible rectangle, so its cost tracks the viewport, not the sheet.
+#[test]
+fn test_viewport_snapshot_cost_is_bounded_by_viewport() {
+    let mut grid = Grid::new();
+    let mut styles = engine::StyleRegistry::new();
+    let bold = styles.get_or_create(CellStyle::new().with_bold(true));
+    for r in 0..50_000u32 {
+        for c in 0..10u32 {
+            let mut cell = Cell::new_number((r * 10 + c) as f64);
+            cell.style_index = bold;
+            grid.set_cell(r, c, cell);
+        }
+    }
+    let locale = engine::LocaleSettings::invariant();
+    let merges = HashSet::new();
+    let cf_rules = vec![crate::ConditionalFormatDefinition {
+        id: 1,
+        priority: 1,
+        rule: crate::ConditionalFormatRule::CellValue(crate::CellValueRule {
+            operator: crate::CellValueOperator::GreaterThan,
+            value1: "100".to_string(),
+            value2: None,
+        }),
+        format: crate::ConditionalFormat::default(),
+        ranges: vec![crate::ConditionalFormatRange { start_row: 0, start_col: 0, end_row: 49_999, end_col: 9 }],
+        stop_if_true: false,
+        enabled: true,
+    }];
+    let hidden = HashSet::new();
+    let src = viewport_test_sources(&grid, &styles, &locale, &merges, &cf_rules, &hidden, &hidden);
+
+    let t0 = std::time::Instant::now();
+    let snap = crate::commands::data::build_viewport_snapshot(&src, (20_000, 0, 20_049, 9));
+    let elapsed = t0.elapsed();
+    assert_eq!(snap.cells.len(), 500);
+    assert_eq!(snap.conditional_formats.len(), 500);
+    assert!(elapsed < std::time::Duration::from_secs(2), "snapshot took {:?}", elapsed);
+}
+
+/// Dropdown arrows follow first-match validation precedence: a cell claimed
+/// by an earlier non-list rule gets no arrow from a later list rule, and a
+/// list without the in-cell dropdown shows none.
+#[test]
+fn test_viewport_snapshot_dropdowns_follow_first_matching_rule() {
+    use crate::data_validation::{CustomRule, DataValidation, DataValidationRule, ListRule, ListSource, ValidationRange};
+    let range = |rows: (u32, u32), cols: (u32, u32), rule: DataValidationRule| ValidationRange {
+        start_row: rows.0,
+        start_col: cols.0,
+        end_row: rows.1,
+        end_col: cols.1,
+        validation: DataValidation { rule, ..Default::default() },
+    };
+    let list = |in_cell_dropdown: bool| {
+        DataValidationRule::List(ListRule { source: ListSource::Values(vec!["a".to_string()]), in_cell_dropdown })
+    };
+    // A1 is claimed by a custom rule and B2 by a list without an arrow, both
+    // ahead of the A1:B2 dropdown list.
+    let custom = DataValidationRule::Custom(CustomRule { formula: "TRUE".to_string() });
+    let validations = vec![
+        range((0, 0), (0, 0), custom),
+        range((1, 1), (1, 1), list(false)),
+        range((0, 1), (0, 1), list(true)),
+    ];
+    let grid = Grid::new();
+    let styles = engine::StyleRegistry::new();
+    let locale = engine::LocaleSettings::invariant();
+    let merges = HashSet::new();
+    let hidden = HashSet::new();
+    let mut src = viewport_test_sources(&grid, &styles, &locale, &merges, &[], &hidden, &hidden);
+    src.validations = &validations;
+
+    let snap = crate::commands::data::build_viewport_snapshot(&src, (0, 0, 1, 9));
+    assert_eq!(snap.dropdown_cells, vec![(0, 1), (1, 0)]);
+}
+
+/// Differential snapshots: after an edit, a caller holding the previous
+/// revision gets exactly the edited cell and its recalculated dependent (and
+/// cleared cells by coordinate); the current revision gets NotModified; a
+/// revision the bounded log no longer covers gets the full payload.
+#[test]
+fn test_viewport_snapshot_delta_since_known_revision() {
+    use crate::api_types::SnapshotKind;
+    use crate::commands::data::{get_viewport_snapshot_internal, update_cell_impl};
+    let state = create_app_state();
+    let file_state = FileState::default();
+    let user_files = UserFilesState { files: Mutex::new(HashMap::new()) };
+    let slicer = crate::slicer::SlicerState::new();
+    let pivot = crate::pivot::types::PivotState::new();
+    let pane = PaneControlState::new();
+    let ribbon = RibbonFilterState::new();
+    let edit = |row: u32, col: u32, value: &str| {
+        update_cell_impl(&state, &file_state, &user_files, &slicer, &pivot, &pane, &ribbon, row, col, value.to_string(), None, None)
+            .unwrap();
+        crate::viewport_revisions::seal_viewport_changes(&state);
+    };
+    let snapshot = |known: Option<u64>| get_viewport_snapshot_internal(&state, 0, 0, 0, 19, 9, known).unwrap();
+
+    edit(0, 0, "1");
+    edit(0, 1, "=A1*2");
+    edit(4, 2, "note");
+    let full = snapshot(None);
+    assert_eq!(full.kind, SnapshotKind::Full);
+    assert_eq!(full.cells.len(), 3);
+
+    edit(0, 0, "5");
+    let delta = snapshot(Some(full.revision));
+    assert_eq!(delta.kind, SnapshotKind::Delta);
+    assert!(delta.revision > full.revision);
+    let mut changed: Vec<(u32, u32, String)> = delta.cells.iter().map(|c| (c.row, c.col, c.display.clone())).collect();
+    changed.sort();
+    assert_eq!(changed, vec![(0, 0, "5".to_string()), (0, 1, "10".to_string())]);
+    assert!(delta.cleared_cells.is_empty());
+
+    let same = snapshot(Some(delta.revision));
+    assert_eq!(same.kind, SnapshotKind::NotModified);
+    assert_eq!(same.revision, delta.revision);
+    assert!(same.cells.is_empty());
+
+    edit(4, 2, "");
+    let cleared = snapshot(Some(delta.revision));
+    assert_eq!(cleared.kind, SnapshotKind::Delta);
+    assert!(cleared.cells.is_empty());
+    assert_eq!(cleared.cleared_cells, vec![(4, 2)]);
+
+    // More writes than the per-sheet log holds: older revisions fall out.
+    {
+        let mut grids = state.grids.lock().unwrap();
+        for row in 100..100 + crate::viewport_revisions::MAX_LOGGED_CELLS_PER_SHEET as u32 + 1 {
+            grids[0].set_cell(row, 0, engine::Cell::new_number(row as f64));
+        }
+    }
+    let ancient = snapshot(Some(cleared.revision));
+    assert_eq!(ancient.kind, SnapshotKind::Full);
+    assert_eq!(ancient.cells.len(), 2);
+    assert_eq!(snapshot(Some(0)).kind, SnapshotKind::Full);
+}
+
+/// One row window combining every header source: custom heights on rows 1
+/// and 3, a collapsed group over rows 2..=5 (summary row 5 stays visible),
+/// filter-hidden rows 3 and 7, and a manual hide on row 8. Row 3 is hidden
+/// by both the filter and the group; the filter wins.
+#[test]
+fn test_axis_metadata_merges_sizes_groups_and_filters() {
+    use crate::api_types::HiddenReason;
+    use crate::structural_change::Axis;
+    let state = create_app_state();
+    *state.default_row_height.lock().unwrap() = 20.0;
+    state.row_heights.lock().unwrap().extend([(1, 30.0), (3, 45.0)]);
+    state.advanced_filter_hidden_rows.lock().unwrap().insert(0, vec![3, 7]);
+    let mut outline = grouping::SheetOutline::new();
+    let mut group = grouping::RowGroup::new(2, 5, 1);
+    group.collapsed = true;
+    outline.row_groups.push(group);
+    outline.recalculate_max_levels();
+    state.outlines.lock().unwrap().insert(0, outline);
+
+    let rows = crate::commands::dimensions::get_axis_metadata_internal(&state, 0, Axis::Rows, 9, 0, vec![8])
+        .unwrap();
+    assert_eq!(rows.len(), 10);
+    assert_eq!(rows.iter().map(|r| r.index).collect::<Vec<_>>(), (0..10).collect::<Vec<_>>());
+
+    let sizes: Vec<(f64, bool)> = rows.iter().map(|r| (r.size, r.custom_size)).collect();
+    assert_eq!(sizes[0], (20.0, false));
+    assert_eq!(sizes[1], (30.0, true));
+    assert_eq!(sizes[3], (45.0, true));
+
+    let reasons: Vec<Option<HiddenReason>> = rows.iter().map(|r| r.hidden_reason).collect();
+    assert_eq!(
+        reasons,
+        vec![
+            None,
+            None,
+            Some(HiddenReason::Group),
+            Some(HiddenReason::Filter),
+            Some(HiddenReason::Group),
+            None,
+            None,
+            Some(HiddenReason::Filter),
+            Some(HiddenReason::Manual),
+            None,
+        ]
+    );
+    assert!(rows.iter().all(|r| r.hidden == r.hidden_reason.is_some()));
+
+    for r in &rows {
+        let grouped = (2..=5).contains(&r.index);
+        assert_eq!(r.outline_level, if grouped { 1 } else { 0 }, "row {}", r.index);
+        assert_eq!(r.collapsed, grouped, "row {}", r.index);
+        assert_eq!(r.group_start, r.index == 2, "row {}", r.index);
+        assert_eq!(r.group_end, r.index == 5, "row {}", r.index);
+        assert_eq!(r.is_button, r.index == 5, "row {}", r.index);
+    }
+
+    // Columns ignore the row filter and read the column defaults.
+    let cols = crate::commands::dimensions::get_axis_metadata_internal(&state, 0, Axis::Columns, 3, 3, vec![])
+        .unwrap();
+    assert_eq!(cols.len(), 1);
+    assert!(!cols[0].hidden);
+    assert_eq!(cols[0].size, *state.default_column_width.lock().unwrap());
+
+    assert!(crate::commands::dimensions::get_axis_metadata_internal(&state, 5, Axis::Rows, 0, 1, vec![]).is_err());
+}
+
+/// A sheet default resizes every line without an explicit size, leaves the
+/// explicit ones alone, and goes back to the workbook default on undo.
+#[test]
+fn test_sheet_defaults_apply_below_explicit_sizes() {
+    use crate::api_types::SheetDefaults;
+    use crate::commands::dimensions::{
+        effective_default_dimensions, get_axis_metadata_internal, get_sheet_defaults_internal,
+        set_sheet_defaults_internal,
+    };
+    use crate::structural_change::Axis;
+    let state = create_app_state();
+    *state.default_row_height.lock().unwrap() = 20.0;
+    *state.default_column_width.lock().unwrap() = 64.0;
+    state.row_heights.lock().unwrap().insert(1, 30.0);
+    state.column_widths.lock().unwrap().insert(2, 40.0);
+
+    let set = set_sheet_defaults_internal(
+        &state,
+        0,
+        SheetDefaults { default_col_width: Some(100.0), default_row_height: Some(-5.0) },
+    )
+    .unwrap();
+    // A non-positive size means "follow the workbook default"
+    assert_eq!(set, SheetDefaults { default_col_width: Some(100.0), default_row_height: None });
+    assert_eq!(get_sheet_defaults_internal(&state, 0).unwrap(), set);
+    let effective = effective_default_dimensions(&state, 0);
+    assert_eq!((effective.default_column_width, effective.default_row_height), (100.0, 20.0));
+
+    let cols = get_axis_metadata_internal(&state, 0, Axis::Columns, 1, 3, vec![]).unwrap();
+    let sizes: Vec<(f64, bool)> = cols.iter().map(|c| (c.size, c.custom_size)).collect();
+    assert_eq!(sizes, vec![(100.0, false), (40.0, true), (100.0, false)]);
+    set_sheet_defaults_internal(
+        &state,
+        0,
+        SheetDefaults { default_col_width: Some(100.0), default_row_height: Some(25.0) },
+    )
+    .unwrap();
+    let rows = get_axis_metadata_internal(&state, 0, Axis::Rows, 0, 1, vec![]).unwrap();
+    assert_eq!((rows[0].size, rows[1].size), (25.0, 30.0));
+    assert_eq!(state.row_heights.lock().unwrap().get(&1), Some(&30.0));
+    assert_eq!(state.column_widths.lock().unwrap().len(), 1);
+
+    for _ in 0..2 {
+        crate::undo_commands::undo_internal(
+            &state,
+            &FileState::default(),
+            &UserFilesState { files: Mutex::new(HashMap::new()) },
+            &crate::pivot::types::PivotState::new(),
+            &crate::slicer::SlicerState::new(),
+            &RibbonFilterState::new(),
+            &PaneControlState::new(),
+        );
+    }
+    assert_eq!(get_sheet_defaults_internal(&state, 0).unwrap(), SheetDefaults::default());
+    assert_eq!(effective_default_dimensions(&state, 0).default_column_width, 64.0);
+    assert!(get_sheet_defaults_internal(&state, 3).is_err());
+}
+
+/// The document inspector finds one of every category; removing comments and
+/// names takes one undo step and leaves the other categories alone, while
+/// irreversible categories wait for confirmation.
+#[test]
+fn test_document_inspector_reports_and_removes_categories() {
+    use crate::commands::inspect::{inspect_workbook_internal, remove_inspection_items_internal, InspectionKind};
+
+    let state = create_app_state();
+    let user_files_state = UserFilesState { files: Mutex::new(HashMap::new()) };
+    let pivot_state = crate::pivot::types::PivotState::new();
+    crate::sheets::add_sheet_internal(&state, Some("Secret".to_string())).unwrap();
+    {
+        let mut visibility = state.sheet_visibility.lock().unwrap();
+        visibility.resize(2, "visible".to_string());
+        visibility[1] = "hidden".to_string();
+    }
+    {
+        let mut grid = state.grids.lock().unwrap();
+        grid[0].set_cell(0, 0, Cell::new_text("Region".to_string()));
+        grid[0].set_cell(0, 1, Cell::new_text("Sales".to_string()));
+        grid[0].set_cell(1, 0, Cell::new_text("East".to_string()));
+        grid[0].set_cell(1, 1, Cell::new_number(10.0));
+        grid[0].set_cell(0, 4, Cell::new_formula("1+1".to_string()));
+    }
+    let comment = comments::Comment::new(1, 1, 0, "a@example.com".into(), "A".into(), "c".into());
+    state.comments.lock().unwrap().entry(0).or_default().insert((1, 1), comment);
+    state.hyperlinks.lock().unwrap().entry(0).or_default()
+        .insert((1, 0), hyperlinks::Hyperlink::new_url(1, 0, 0, "https://example.com".into()));
+    state.named_ranges.lock().unwrap().insert("DATA".to_string(), named_ranges::NamedRange {
+        name: "Data".to_string(),
+        sheet_index: None,
+        refers_to: "=Sheet1!$A$1:$B$2".to_string(),
+        comment: None,
+        folder: None,
+    });
+    let mut filter = autofilter::AutoFilter::new(0, 0, 9, 1);
+    filter.hidden_rows.extend([3, 4, 5]);
+    state.auto_filters.lock().unwrap().insert(0, filter);
+    state.cell_protection.lock().unwrap().entry(0).or_default()
+        .insert((0, 4), protection::CellProtection { locked: true, formula_hidden: true });
+    let pivot_id = identity::EntityId::from_bytes(identity::generate_uuid_v7());
+    let (cache, _) = crate::pivot::operations::build_cache_from_grid(&state.grids.lock().unwrap()[0], (0, 0), (1, 1), true).unwrap();
+    pivot_state.pivot_tables.lock().unwrap()
+        .insert(pivot_id, (pivot_engine::PivotDefinition::new(pivot_id, (0, 0), (1, 1)), cache));
+    state.workbook_properties.lock().unwrap().author = "Jo Doe".to_string();
+    state.workbook_properties.lock().unwrap().title = "Budget".to_string();
+
+    let inspection = inspect_workbook_internal(&state, &pivot_state);
+    let kinds: Vec<_> = inspection.items.iter().map(|i| i.kind).collect();
+    assert_eq!(kinds, InspectionKind::ALL.to_vec());
+    let item = |kind| inspection.items.iter().find(|i| i.kind == kind).unwrap().clone();
+    assert_eq!(item(InspectionKind::Comments).locations, vec!["Sheet1!B2"]);
+    assert_eq!(item(InspectionKind::HiddenSheets).locations, vec!["Secret"]);
+    assert_eq!(item(InspectionKind::HiddenRowsColumns).locations, vec!["Sheet1!4:6"]);
+    assert_eq!(item(InspectionKind::HiddenFormulas).locations, vec!["Sheet1!E1"]);
+    assert_eq!(item(InspectionKind::PersonalInfo).locations, vec!["Author: Jo Doe"]);
+    assert_eq!(item(InspectionKind::DocumentProperties).locations, vec!["Title: Budget"]);
+    assert!(!item(InspectionKind::HiddenSheets).reversible);
+
+    let result = remove_inspection_items_internal(
+        &state, &pivot_state, &user_files_state,
+        vec![InspectionKind::Comments, InspectionKind::DefinedNames], false,
+    ).unwrap();
+    assert!(result.success);
+    assert_eq!(result.removed.len(), 2);
+    assert!(state.comments.lock().unwrap().is_empty());
+    assert!(state.named_ranges.lock().unwrap().is_empty());
+    {
+        let undo_stack = state.undo_stack.lock().unwrap();
+        assert_eq!(undo_stack.undo_depth(), 1);
+        assert_eq!(undo_stack.undo_description(), Some("Remove document inspector items"));
+    }
+    let after = inspect_workbook_internal(&state, &pivot_state);
+    let remaining: Vec<_> = after.items.iter().map(|i| (i.kind, i.count)).collect();
+    let expected: Vec<_> = inspection.items.iter()
+        .filter(|i| !matches!(i.kind, InspectionKind::Comments | InspectionKind::DefinedNames))
+        .map(|i| (i.kind, i.count))
+        .collect();
+    assert_eq!(remaining, expected);
+
+    // Irreversible categories need an explicit go-ahead.
+    let result = remove_inspection_items_internal(
+        &state, &pivot_state, &user_files_state, vec![InspectionKind::HiddenSheets], false,
+    ).unwrap();
+    assert!(result.needs_confirmation);
+    assert!(result.warning.unwrap().contains("hidden sheets"));
+    assert_eq!(state.sheet_names.lock().unwrap().len(), 2);
+    let result = remove_inspection_items_internal(
+        &state, &pivot_state, &user_files_state, vec![InspectionKind::HiddenSheets], true,
+    ).unwrap();
+    assert!(result.success);
+    assert_eq!(*state.sheet_names.lock().unwrap(), vec!["Sheet1".to_string()]);
+}
+
+/// Setting document properties keeps the save-maintained timestamps, carries
+/// custom properties into the persisted form, and the inspector strips them
+/// in one undoable step.
+#[test]
+fn test_document_properties_set_save_and_inspect() {
+    use crate::api_types::{CustomProperty, CustomPropertyValue, WorkbookProperties};
+    use crate::commands::inspect::{inspect_workbook_internal, remove_inspection_items_internal, InspectionKind};
+    use crate::persistence::set_workbook_properties_internal;
+
+    let state = create_app_state();
+    let pivot_state = crate::pivot::types::PivotState::new();
+    let user_files_state = UserFilesState { files: Mutex::new(HashMap::new()) };
+    let created = state.workbook_properties.lock().unwrap().created.clone();
+
+    let stored = set_workbook_properties_internal(&state, WorkbookProperties {
+        title: "Budget".to_string(),
+        company: "Acme".to_string(),
+        created: "1999-01-01T00:00:00Z".to_string(),
+        custom: vec![
+            CustomProperty { name: "Project".to_string(), value: CustomPropertyValue::Text("Apollo".to_string()) },
+            CustomProperty { name: "Approved".to_string(), value: CustomPropertyValue::Bool(true) },
+        ],
+        ..Default::default()
+    });
+    assert_eq!(stored.created, created);
+    assert_eq!(stored.title, "Budget");
+
+    let saved = ::persistence::WorkbookProperties::from(&*state.workbook_properties.lock().unwrap());
+    assert_eq!(saved.company, "Acme");
+    assert_eq!(saved.custom[1], ::persistence::CustomProperty {
+        name: "Approved".to_string(),
+        value: ::persistence::CustomPropertyValue::Bool(true),
+    });
+
+    let inspection = inspect_workbook_internal(&state, &pivot_state);
+    let item = |kind| inspection.items.iter().find(|i| i.kind == kind).unwrap().clone();
+    assert_eq!(item(InspectionKind::PersonalInfo).locations.last().unwrap(), "Company: Acme");
+    assert_eq!(
+        item(InspectionKind::DocumentProperties).locations,
+        vec!["Title: Budget", "Custom: Project", "Custom: Approved"],
+    );
+
+    remove_inspection_items_internal(
+        &state, &pivot_state, &user_files_state, vec![InspectionKind::DocumentProperties], false,
+    ).unwrap();
+    {
+        let properties = state.workbook_properties.lock().unwrap();
+        assert!(properties.title.is_empty() && properties.custom.is_empty());
+        assert_eq!(properties.company, "Acme");
+    }
+
+    crate::undo_commands::undo_internal(
+        &state,
+        &FileState::default(),
+        &user_files_state,
+        &pivot_state,
+        &crate::slicer::SlicerState::new(),
+        &RibbonFilterState::new(),
+        &PaneControlState::new(),
+    );
+    let properties = state.workbook_properties.lock().unwrap();
+    assert_eq!(properties.title, "Budget");
+    assert_eq!(properties.custom.len(), 2);
+}
+
+/// Three drifted stores are all reported; repairing two leaves only the one
+/// without an automatic fix.
+#[test]
+fn test_validate_and_repair_workbook() {
+    use crate::commands::consistency::{repair_workbook_internal, validate_workbook_internal, FixId};
+
+    let state = create_app_state();
+    let pivot_state = crate::pivot::types::PivotState::new();
+    assert!(validate_workbook_internal(&state, &pivot_state).issues.is_empty());
+
+    // A rule left behind on a deleted sheet, a dependency edge for a cell
+    // that holds no formula, and a name whose formula does not parse.
+    state.conditional_formats.lock().unwrap().insert(3, vec![conditional_formatting::ConditionalFormatDefinition {
+        id: 1,
+        priority: 1,
+        rule: conditional_formatting::ConditionalFormatRule::BlankCells,
+        format: Default::default(),
+        ranges: vec![conditional_formatting::ConditionalFormatRange { start_row: 0, start_col: 0, end_row: 2, end_col: 1 }],
+        stop_if_true: false,
+        enabled: true,
+    }]);
+    state.dependencies.lock().unwrap().insert((4, 4), crate::CoordSet::from_iter([(0, 0)]));
+    state.named_ranges.lock().unwrap().insert("BROKEN".to_string(), named_ranges::NamedRange {
+        name: "Broken".to_string(),
+        sheet_index: None,
+        refers_to: "=SUM((".to_string(),
+        comment: None,
+        folder: None,
+    });
+
+    let issues = validate_workbook_internal(&state, &pivot_state).issues;
+    let stores: Vec<&str> = issues.iter().map(|i| i.store.as_str()).collect();
+    assert_eq!(stores, ["conditionalFormats", "namedRanges", "dependencies"]);
+    assert_eq!(issues[0].suggested_fix, Some(FixId::DropOrphans));
+    assert_eq!(issues[1].suggested_fix, None);
+    assert_eq!(issues[2].suggested_fix, Some(FixId::RebuildDependencies));
+
+    let result = repair_workbook_internal(&state, &pivot_state, &[FixId::DropOrphans, FixId::RebuildDependencies]);
+    assert_eq!(result.fixed.len(), 2);
+    assert_eq!(result.remaining.len(), 1);
+    assert_eq!(result.remaining[0].store, "namedRanges");
+    assert!(!state.conditional_formats.lock().unwrap().contains_key(&3));
+    assert!(state.dependencies.lock().unwrap().is_empty());
+    // The unparseable name is left for the user
+    assert!(state.named_ranges.lock().unwrap().contains_key("BROKEN"));
+    assert_eq!(validate_workbook_internal(&state, &pivot_state).issues, result.remaining);
+}
+
+/// Matrix over every structural-change participant: a feature above the edit
+/// point stays, one straddling it grows, one below shifts; a following row
+/// deletion drops fully-deleted features and clamps partial ones; undoing the
+/// deletion restores all of them from the one snapshot.
+#[test]
+fn test_structural_change_updates_every_feature() {
+    use crate::structural_change::{dispatch_structural_change, Axis, StructuralChange};
+
+    let state = create_app_state();
+    let pivot_state = crate::pivot::types::PivotState::new();
+    let table_id = identity::EntityId::from_bytes(identity::generate_uuid_v7());
+    {
+        state.named_ranges.lock().unwrap().insert("DATA".to_string(), named_ranges::NamedRange {
+            name: "Data".to_string(),
+            sheet_index: None,
+            refers_to: "=Sheet1!$A$3:$B$6".to_string(),
+            comment: None,
+            folder: None,
+        });
+        state.conditional_formats.lock().unwrap().insert(0, vec![conditional_formatting::ConditionalFormatDefinition {
+            id: 1,
+            priority: 1,
+            rule: conditional_formatting::ConditionalFormatRule::BlankCells,
+            format: Default::default(),
+            ranges: vec![conditional_formatting::ConditionalFormatRange { start_row: 0, start_col: 0, end_row: 2, end_col: 1 }],
+            stop_if_true: false,
+            enabled: true,
+        }]);
+        state.data_validations.lock().unwrap().insert(0, vec![data_validation::ValidationRange {
+            start_row: 9, start_col: 0, end_row: 11, end_col: 0,
+            validation: Default::default(),
+        }]);
+        let comment = comments::Comment::new(12, 0, 0, "a@example.com".into(), "A".into(), "c".into());
+        state.comments.lock().unwrap().entry(0).or_default().insert((12, 0), comment);
+        let link = hyperlinks::Hyperlink::new_url(1, 0, 0, "https://example.com".into());
+        state.hyperlinks.lock().unwrap().entry(0).or_default().insert((1, 0), link);
+        let mut filter = autofilter::AutoFilter::new(2, 0, 8, 3);
+        filter.hidden_rows.insert(6);
+        state.auto_filters.lock().unwrap().insert(0, filter);
+        let mut outline = grouping::SheetOutline::new();
+        outline.row_groups.push(grouping::RowGroup::new(4, 5, 1));
+        state.outlines.lock().unwrap().insert(0, outline);
+        state.tables.lock().unwrap().entry(0).or_default().insert(table_id, tables::Table {
+            id: table_id,
+            name: "Table1".to_string(),
+            sheet_index: 0,
+            start_row: 4,
+            start_col: 5,
+            end_row: 8,
+            end_col: 6,
+            columns: vec![],
+            style_options: Default::default(),
+            style_name: "TableStyleMedium2".to_string(),
+            auto_filter_id: None,
+            data_type_policy: Default::default(),
+        });
+        state.table_names.lock().unwrap().insert("TABLE1".to_string(), (0, table_id));
+        state.protected_regions.lock().unwrap().push(ProtectedRegion {
+            id: "chart-1".to_string(),
+            region_type: "chart".to_string(),
+            owner_id: table_id,
+            sheet_index: 0,
+            start_row: 20,
+            start_col: 0,
+            end_row: 22,
+            end_col: 2,
+        });
+    }
+
+    let rows_of = |state: &AppState| {
+        let named = state.named_ranges.lock().unwrap()["DATA"].refers_to.clone();
+        let cf = state.conditional_formats.lock().unwrap()[&0][0].ranges[0].start_row;
+        let dv = state.data_validations.lock().unwrap()[&0]
+            .iter().map(|v| (v.start_row, v.end_row)).collect::<Vec<_>>();
+        let comment = state.comments.lock().unwrap()[&0].values().map(|c| (c.row, c.col)).collect::<Vec<_>>();
+        let link = state.hyperlinks.lock().unwrap()[&0].keys().copied().collect::<Vec<_>>();
+        let filter = state.auto_filters.lock().unwrap().get(&0).map(|f| {
+            let mut hidden: Vec<u32> = f.hidden_rows.iter().copied().collect();
+            hidden.sort();
+            (f.start_row, f.end_row, hidden)
+        });
+        let groups = state.outlines.lock().unwrap()[&0]
+            .row_groups.iter().map(|g| (g.start_row, g.end_row)).collect::<Vec<_>>();
+        let table = state.tables.lock().unwrap()[&0].get(&table_id).map(|t| (t.start_row, t.end_row));
+        let region = state.protected_regions.lock().unwrap()
+            .iter().map(|r| (r.start_row, r.end_row)).collect::<Vec<_>>();
+        (named, cf, dv, comment, link, filter, groups, table, region)
+    };
+
+    // Insert 2 rows at row 5 (index 4).
+    let insert = StructuralChange::insert(0, "Sheet1", Axis::Rows, 4, 2);
+    dispatch_structural_change(&state, &pivot_state, &insert);
+    let after_insert = rows_of(&state);
+    assert_eq!(after_insert.0, "=Sheet1!$A$3:$B$8"); // straddles: grows
+    assert_eq!(after_insert.1, 0); // above: unchanged
+    assert_eq!(after_insert.2, vec![(11, 13)]); // below: shifts
+    assert_eq!(after_insert.3, vec![(14, 0)]);
+    assert_eq!(after_insert.4, vec![(1, 0)]);
+    assert_eq!(after_insert.5, Some((2, 10, vec![8])));
+    assert_eq!(after_insert.6, vec![(6, 7)]); // starts at the insertion point: shifts
+    assert_eq!(after_insert.7, Some((6, 10)));
+    assert_eq!(after_insert.8, vec![(22, 24)]);
+
+    // Delete rows 7..=10 (indices 6..9).
+    let delete = StructuralChange::delete(0, "Sheet1", Axis::Rows, 6, 4);
+    let undo_bytes = dispatch_structural_change(&state, &pivot_state, &delete);
+    let after_delete = rows_of(&state);
+    assert_eq!(after_delete.0, "=Sheet1!$A$3:$B$6"); // clamped
+    assert_eq!(after_delete.2, vec![(7, 9)]);
+    assert_eq!(after_delete.3, vec![(10, 0)]);
+    assert_eq!(after_delete.5, Some((2, 6, vec![]))); // hidden row 8 was deleted
+    assert!(after_delete.6.is_empty()); // fully deleted group dropped
+    assert_eq!(after_delete.7, Some((6, 6))); // clamped to the surviving row
+    assert_eq!(after_delete.8, vec![(18, 20)]);
+
+    // A table whose rows are all deleted is removed along with its name.
+    let wipe = StructuralChange::delete(0, "Sheet1", Axis::Rows, 6, 1);
+    let wipe_bytes = dispatch_structural_change(&state, &pivot_state, &wipe);
+    assert!(state.tables.lock().unwrap()[&0].get(&table_id).is_none());
+    assert!(!state.table_names.lock().unwrap().contains_key("TABLE1"));
+
+    // Undo both deletions: one snapshot per change restores every store.
+    let mut inverse = engine::Transaction::new("undo");
+    crate::structural_change::apply_structural_features_restore(&state, &pivot_state, &wipe_bytes, &mut inverse);
+    crate::structural_change::apply_structural_features_restore(&state, &pivot_state, &undo_bytes, &mut inverse);
+    assert_eq!(rows_of(&state), after_insert);
+    assert!(state.table_names.lock().unwrap().contains_key("TABLE1"));
+}
+
+#[test]
+fn test_structural_change_merges_and_named_scope() {
+    use crate::structural_change::{shift_refers_to, ApplyStructuralChange, Axis, StructuralChange};
+
+    let mut merges: HashSet<MergedRegion> = HashSet::new();
+    merges.insert(MergedRegion { start_row: 0, start_col: 3, end_row: 0, end_col: 5 });
+    merges.insert(MergedRegion { start_row: 0, start_col: 0, end_row: 1, end_col: 0 });
+    // Deleting column B (index 1) shrinks D1:F1 to C1:E1; A1:A2 is untouched.
+    let change = StructuralChange::delete(0, "Sheet1", Axis::Columns, 1, 1);
+    assert!(merges.apply_structural_change(&change));
+    assert!(merges.contains(&MergedRegion { start_row: 0, start_col: 2, end_row: 0, end_col: 4 }));
+    assert!(merges.contains(&MergedRegion { start_row: 0, start_col: 0, end_row: 1, end_col: 0 }));
+    // Deleting row 2 collapses A1:A2 to a single cell, which is no merge.
+    let change = StructuralChange::delete(0, "Sheet1", Axis::Rows, 1, 1);
+    merges.apply_structural_change(&change);
+    assert_eq!(merges.len(), 1);
+
+    // Other sheets' references and unscoped bare references stay put; a
+    // reference whose cells are all deleted becomes #REF!.
+    let change = StructuralChange::delete(0, "Sheet1", Axis::Rows, 2, 3);
+    assert_eq!(shift_refers_to("=Sheet2!$A$10", None, &change), "=Sheet2!$A$10");
+    assert_eq!(shift_refers_to("=$A$10", None, &change), "=$A$10");
+    assert_eq!(shift_refers_to("=$A$10", Some(0), &change), "=$A$7");
+    assert_eq!(shift_refers_to("='Sheet1'!B3:C4", None, &change), "=#REF!");
+}
+
+/// Inserting rows above a commented, hyperlinked block moves both anchors and
+/// the internal link targets that point into it, from any sheet; deleting a
+/// link's target rows flags it broken, and undo restores every link.
+#[test]
+fn test_structural_change_repairs_comments_and_internal_links() {
+    use crate::structural_change::{apply_structural_features_restore, dispatch_structural_change, Axis, StructuralChange};
+
+    let state = create_app_state();
+    let pivot_state = crate::pivot::types::PivotState::new();
+    {
+        let comment = comments::Comment::new(4, 1, 0, "a@example.com".into(), "A".into(), "check".into());
+        state.comments.lock().unwrap().entry(0).or_default().insert((4, 1), comment);
+        let mut links = state.hyperlinks.lock().unwrap();
+        // Sheet1!A5 -> B5:C6 on its own sheet; Sheet2!A1 -> 'Sheet1'!$B$6.
+        links.entry(0).or_default().insert((4, 0), hyperlinks::Hyperlink::new_internal(4, 0, 0, None, "B5:C6".into()));
+        links.entry(1).or_default().insert(
+            (0, 0),
+            hyperlinks::Hyperlink::new_internal(0, 0, 1, Some("Sheet1".into()), "$B$6".into()),
+        );
+        // A link into another sheet is left alone.
+        links.entry(0).or_default().insert(
+            (0, 0),
+            hyperlinks::Hyperlink::new_internal(0, 0, 0, Some("Sheet2".into()), "B5".into()),
+        );
+    }
+
+    let insert = StructuralChange::insert(0, "Sheet1", Axis::Rows, 1, 3);
+    let undo_bytes = dispatch_structural_change(&state, &pivot_state, &insert);
+    {
+        let comments = state.comments.lock().unwrap();
+        assert_eq!(comments[&0].keys().copied().collect::<Vec<_>>(), vec![(7, 1)]);
+        let links = state.hyperlinks.lock().unwrap();
+        let local = &links[&0][&(7, 0)];
+        assert_eq!(local.internal_ref.as_ref().unwrap().cell_reference, "B8:C9");
+        assert_eq!(local.target, "B8:C9");
+        let remote = &links[&1][&(0, 0)];
+        assert_eq!(remote.target, "'Sheet1'!$B$9");
+        assert_eq!(links[&0][&(0, 0)].target, "'Sheet2'!B5");
+    }
+
+    // Deleting the remote link's target row breaks it; the range link is clamped.
+    let delete = StructuralChange::delete(0, "Sheet1", Axis::Rows, 8, 1);
+    dispatch_structural_change(&state, &pivot_state, &delete);
+    {
+        let links = state.hyperlinks.lock().unwrap();
+        let remote = &links[&1][&(0, 0)];
+        assert!(remote.broken);
+        assert_eq!(remote.target, "'Sheet1'!$B$9");
+        assert_eq!(links[&0][&(7, 0)].target, "B8:C8");
+        assert!(!links[&0][&(7, 0)].broken);
+    }
+    // Deleting the anchor row removes the comment and the link on it.
+    let delete_anchor = StructuralChange::delete(0, "Sheet1", Axis::Rows, 7, 1);
+    dispatch_structural_change(&state, &pivot_state, &delete_anchor);
+    assert!(state.comments.lock().unwrap()[&0].is_empty());
+    assert!(!state.hyperlinks.lock().unwrap()[&0].contains_key(&(7, 0)));
+
+    // Undoing the insert (after the deletions) restores every sheet's links.
+    let mut inverse = engine::Transaction::new("undo");
+    apply_structural_features_restore(&state, &pivot_state, &undo_bytes, &mut inverse);
+    let links = state.hyperlinks.lock().unwrap();
+    assert_eq!(links[&0][&(4, 0)].target, "B5:C6");
+    assert_eq!(links[&1][&(0, 0)].target, "'Sheet1'!$B$6");
+    assert!(!links[&1][&(0, 0)].broken);
+}
+
+#[test]
+fn test_rename_sheet_updates_internal_link_targets() {
+    let state = create_app_state();
+    state.hyperlinks.lock().unwrap().entry(0).or_default().insert(
+        (0, 0),
+        hyperlinks::Hyperlink::new_internal(0, 0, 0, Some("Sheet1".into()), "C3".into()),
+    );
+    let mut links = state.hyperlinks.lock().unwrap();
+    assert!(hyperlinks::rename_target_sheet(&mut links, "Sheet1", "Budget"));
+    let link = &links[&0][&(0, 0)];
+    assert_eq!(link.target, "'Budget'!C3");
+    assert_eq!(link.internal_ref.as_ref().unwrap().sheet_name.as_deref(), Some("Budget"));
+    assert!(!hyperlinks::rename_target_sheet(&mut links, "Sheet1", "Other"));
+}
+
+#[test]
+fn test_formula_shifting_shares_the_ast_visitor() {
+    use crate::commands::structure::{shift_formula_internal, shift_formulas_batch};
+    use crate::structural_change::{shift_formula_refs, Axis, StructuralChange};
+
+    let shift = |formula: &str, change: &StructuralChange| {
+        let mut ast = parser::parse(formula).unwrap();
+        shift_formula_refs(&mut ast, change);
+        engine::ast_render::render_formula(&ast)
+    };
+    // Insert 2 rows at row 3: `$` does not pin, ranges grow, other sheets stay.
+    let insert = StructuralChange::insert(0, "Sheet1", Axis::Rows, 2, 2);
+    assert_eq!(shift("SUM($A$1:A5)+Sheet2!A3", &insert), "SUM($A$1:A7)+SHEET2!A3");
+    assert_eq!(shift("SHEET1!$B$4*3:4", &insert), "SHEET1!$B$6*5:6");
+    assert_eq!(shift("A:A", &insert), "A:A");
+    // Deleting row 3 invalidates references that lived only there.
+    let delete = StructuralChange::delete(0, "Sheet1", Axis::Rows, 2, 1);
+    assert_eq!(shift("A3+A4", &delete), "#REF!+A3");
+
+    // Fill keeps the `=`, the author's spelling and untouched text.
+    assert_eq!(shift_formula_internal("=Sheet2!a1*TaxRate", 1, 0), "=Sheet2!A2*TaxRate");
+    assert_eq!(shift_formula_internal("=$A$1", 3, 3), "=$A$1");
+    assert_eq!(shift_formula_internal("=SUM(", 1, 0), "=SUM(");
+    assert_eq!(shift_formula_internal("=A1", -1, 0), "=#REF!");
+
+    let inputs = (0..3)
+        .map(|i| crate::api_types::FormulaShiftInput {
+            formula: "=(A1+B1)*2".to_string(),
+            row_delta: i,
+            col_delta: 0,
+        })
+        .collect();
+    assert_eq!(
+        shift_formulas_batch(inputs).formulas,
+        vec!["=(A1+B1)*2", "=(A2+B2)*2", "=(A3+B3)*2"]
+    );
+}
+
+#[test]
+fn test_merge_across_creates_one_region_per_row() {
+    use crate::api_types::{MergeMode, MergeOptions};
+    use crate::merge_commands::merge_cells_internal;
+
+    let state = create_app_state();
+    let options = MergeOptions { mode: MergeMode::MergeAcross, ..Default::default() };
+    let result = merge_cells_internal(&state, 0, 0, 2, 3, &options).unwrap();
+    assert!(result.success);
+    assert_eq!(result.updated_cells.len(), 3);
+    let regions = state.merged_regions.lock().unwrap();
+    assert_eq!(regions.len(), 3);
+    for row in 0..3 {
+        assert!(regions.contains(&MergedRegion { start_row: row, start_col: 0, end_row: row, end_col: 3 }));
+    }
+}
+
+#[test]
+fn test_force_merge_discards_and_undo_restores() {
+    use crate::api_types::{MergeContentPolicy, MergeOptions};
+    use crate::merge_commands::{merge_cells_internal, unmerge_cells_internal};
+
+    let state = create_app_state();
+    {
+        let mut grid = state.get_active_grid();
+        grid.set_cell(0, 0, Cell::new_text("Header".to_string()));
+        grid.set_cell(0, 1, Cell::new_number(7.0));
+        grid.set_cell(1, 1, Cell::new_text("lost".to_string()));
+    }
+
+    // Refused without `force`: nothing changes, the would-be losses are listed.
+    let mut options = MergeOptions {
+        content_policy: MergeContentPolicy::RefuseIfMultiple,
+        center: true,
+        ..Default::default()
+    };
+    let refused = merge_cells_internal(&state, 0, 0, 1, 1, &options).unwrap();
+    assert!(!refused.success);
+    assert_eq!(refused.discarded_cells.len(), 2);
+    assert!(state.merged_regions.lock().unwrap().is_empty());
+    assert!(state.get_active_grid().get_cell(0, 1).is_some());
+
+    options.force = true;
+    let merged = merge_cells_internal(&state, 0, 0, 1, 1, &options).unwrap();
+    assert!(merged.success);
+    let lost: Vec<(u32, u32, &str)> =
+        merged.discarded_cells.iter().map(|d| (d.row, d.col, d.display.as_str())).collect();
+    assert_eq!(lost, vec![(0, 1, "7"), (1, 1, "lost")]);
+    {
+        let grid = state.get_active_grid();
+        let styles = state.style_registry.lock().unwrap();
+        assert!(grid.get_cell(0, 1).is_none());
+        let master = grid.get_cell(0, 0).unwrap();
+        assert_eq!(styles.get(master.style_index).text_align, engine::TextAlign::Center);
+    }
+
+    // The merge's undo step carries every discarded cell; replaying the
+    // slave-cell changes restores the lost values.
+    let transaction = state.undo_stack.lock().unwrap().pop_undo().unwrap();
+    let mut grid = state.get_active_grid();
+    for change in &transaction.changes {
+        if let engine::CellChange::SetCell { row, col, previous: Some(cell) } = change {
+            if (*row, *col) != (0, 0) {
+                grid.set_cell(*row, *col, cell.clone());
+            }
+        }
+    }
+    assert_eq!(grid.get_cell(0, 1).unwrap().value, CellValue::Number(7.0));
+    assert_eq!(grid.get_cell(1, 1).unwrap().value, CellValue::Text("lost".to_string()));
+    drop(grid);
+
+    // Unmerge restores nothing but drops the Merge & Center alignment.
+    let unmerged = unmerge_cells_internal(&state, 1, 1).unwrap();
+    assert!(unmerged.success);
+    let grid = state.get_active_grid();
+    let styles = state.style_registry.lock().unwrap();
+    assert_eq!(styles.get(grid.get_cell(0, 0).unwrap().style_index).text_align, engine::TextAlign::General);
+    assert_eq!(grid.get_cell(1, 1).unwrap().value, CellValue::Text("lost".to_string()));
+}
+
+fn test_region(id: &str, sheet_index: usize, start_row: u32, start_col: u32, end_row: u32, end_col: u32) -> ProtectedRegion {
+    ProtectedRegion {
+        id: id.to_string(),
+        region_type: "pivot".to_string(),
+        owner_id: identity::EntityId::ZERO,
+        sheet_index,
+        start_row,
+        start_col,
+        end_row,
+        end_col,
+    }
+}
+
+#[test]
+fn test_region_conflicts_against_cells_and_regions() {
+    use crate::protected_regions::RegionRect;
+
+    let state = create_app_state();
+    state.get_active_grid().set_cell(2, 2, Cell::new_number(1.0));
+    state.get_active_grid().set_cell(9, 9, Cell::new_number(1.0));
+    {
+        let mut regions = state.protected_regions.lock().unwrap();
+        regions.push(test_region("pivot-a", 0, 0, 5, 3, 7));
+        regions.push(test_region("pivot-b", 1, 0, 0, 50, 50));
+    }
+
+    let rect = RegionRect { start_row: 0, start_col: 0, end_row: 4, end_col: 5 };
+    let conflicts = state.check_region_conflicts(0, rect, None).unwrap();
+    let ids: Vec<&str> = conflicts.overlapping_regions.iter().map(|r| r.id.as_str()).collect();
+    assert_eq!(ids, vec!["pivot-a"]);
+    assert_eq!(conflicts.non_empty_cells, vec![(2, 2)]);
+
+    // An owner checking its own resize ignores itself and its current cells.
+    let rect = RegionRect { start_row: 0, start_col: 5, end_row: 5, end_col: 9 };
+    let conflicts = state.check_region_conflicts(0, rect, Some("pivot-a")).unwrap();
+    assert!(conflicts.overlapping_regions.is_empty());
+    assert!(conflicts.non_empty_cells.is_empty());
+}
+
+#[test]
+fn test_region_resize_and_move_keep_id() {
+    use crate::protected_regions::RegionRect;
+
+    let state = create_app_state();
+    {
+        let mut regions = state.protected_regions.lock().unwrap();
+        regions.push(test_region("pivot-a", 0, 0, 0, 3, 3));
+        regions.push(test_region("pivot-b", 0, 10, 0, 12, 3));
+    }
+    let resized = state
+        .update_protected_region("pivot-a", RegionRect { start_row: 0, start_col: 0, end_row: 7, end_col: 4 })
+        .unwrap();
+    assert_eq!((resized.id.as_str(), resized.end_row, resized.end_col), ("pivot-a", 7, 4));
+    assert_eq!(state.get_region_at_cell(0, 6, 4).map(|r| r.id), Some("pivot-a".to_string()));
+    // Position in registration order is unchanged.
+    assert_eq!(state.protected_regions.lock().unwrap()[0].id, "pivot-a");
+
+    let moved = state.move_protected_region("pivot-b", 5, 2).unwrap();
+    assert_eq!((moved.start_row, moved.start_col, moved.end_row, moved.end_col), (15, 2, 17, 5));
+    assert!(state.get_region_at_cell(0, 11, 0).is_none());
+    assert!(state.move_protected_region("pivot-b", -100, 0).is_err());
+    assert!(state.update_protected_region("missing", RegionRect { start_row: 0, start_col: 0, end_row: 0, end_col: 0 }).is_err());
+}
+
+#[test]
+fn test_region_lookup_with_1k_regions() {
+    let state = create_app_state();
+    {
+        let mut regions = state.protected_regions.lock().unwrap();
+        for i in 0..1000u32 {
+            regions.push(test_region(&format!("pivot-{}", i), (i % 2) as usize, i * 10, 0, i * 10 + 5, 3));
+        }
+    }
+    let t0 = std::time::Instant::now();
+    for i in 0..1000u32 {
+        let hit = state.get_region_at_cell((i % 2) as usize, i * 10 + 2, 1).unwrap();
+        assert_eq!(hit.id, format!("pivot-{}", i));
+        assert!(state.get_region_at_cell((i % 2) as usize, i * 10 + 7, 1).is_none());
+        assert!(state.get_region_at_cell(((i + 1) % 2) as usize, i * 10 + 2, 1).is_none());
+    }
+    // 3k indexed lookups: far below a linear scan's cost even in debug builds.
+    assert!(t0.elapsed() < std::time::Duration::from_millis(500), "{:?}", t0.elapsed());
+}
+
+#[test]
+fn test_set_functions_apply_recorded_formatting() {
+    let state = create_app_state();
+    let eval_at = |sheet: usize, col: u32, formula: &str| {
+        let grids = state.grids.lock().unwrap();
+        let sheet_names = state.sheet_names.lock().unwrap();
+        let ast = convert_expr(&parser::parse(formula).unwrap());
+        let ctx = engine::EvalContext { current_row: Some(0), current_col: Some(col), ..Default::default() };
+        let result = evaluate_formula_raw_with_files_and_pivot(
+            &grids, &sheet_names, 0, &ast, ctx, None, &HashMap::new(), None, None, None,
+        );
+        crate::process_ui_effects(&state, sheet, 0, col, engine::take_ui_effects());
+        result.to_cell_value()
+    };
+    let apply = || {
+        let sheet_names = state.sheet_names.lock().unwrap();
+        let mut grids = state.grids.lock().unwrap();
+        let mut styles = state.style_registry.lock().unwrap();
+        let mut rh = state.row_heights.lock().unwrap();
+        let mut cw = state.column_widths.lock().unwrap();
+        crate::apply_pending_ui_effects(&state, &mut grids, &sheet_names, &mut styles, &mut rh, &mut cw, 0)
+    };
+
+    assert_eq!(eval_at(0, 0, "=SET.COLUMN.WIDTH(2, 120)"), CellValue::Number(120.0));
+    assert_eq!(eval_at(0, 1, "=SET.ROW.HEIGHT(3, 40)"), CellValue::Number(40.0));
+    assert_eq!(eval_at(0, 2, "=SET.CELL.FILLCOLOR(B2, 255, 0, 0)"), CellValue::Text("#ff0000".to_string()));
+    // Nothing is applied until the caller applies the pending effects
+    assert!(state.column_widths.lock().unwrap().is_empty());
+
+    let (dims, needs_style_refresh) = apply();
+    assert!(needs_style_refresh);
+    let dims: Vec<_> = dims.iter().map(|d| (d.dimension_type.as_str(), d.index, d.size)).collect();
+    assert_eq!(dims, vec![("column", 1, 120.0), ("row", 2, 40.0)]);
+    assert_eq!(state.column_widths.lock().unwrap().get(&1), Some(&120.0));
+    assert_eq!(state.row_heights.lock().unwrap().get(&2), Some(&40.0));
+    let fill_at = |row: u32, col: u32| {
+        let grids = state.grids.lock().unwrap();
+        let style_index = grids[0].get_cell(row, col).map(|c| c.style_index).unwrap_or(0);
+        state.style_registry.lock().unwrap().get(style_index).fill.clone()
+    };
+    assert_eq!(
+        fill_at(1, 1),
+        engine::Fill::Solid { color: engine::ThemeColor::Absolute(engine::Color::new(255, 0, 0)) }
+    );
+
+    // An empty color removes the fill; nothing is left to apply afterwards
+    eval_at(0, 2, "=SET.CELL.FILLCOLOR(B2, \"\")");
+    let (dims, needs_style_refresh) = apply();
+    assert!(dims.is_empty() && needs_style_refresh);
+    assert_eq!(fill_at(1, 1), engine::Fill::None);
+    assert!(!state.ui_effects.lock().unwrap().has_dirty());
+
+    // Sizes for an inactive sheet go to its per-sheet storage
+    state.all_column_widths.lock().unwrap().push(HashMap::new());
+    eval_at(1, 0, "=SET.COLUMN.WIDTH(1, 80)");
+    let (dims, needs_style_refresh) = apply();
+    assert!(dims.is_empty() && !needs_style_refresh);
+    assert_eq!(state.all_column_widths.lock().unwrap()[1].get(&0), Some(&80.0));
+}
+
+/// SET.* formulas entered through update_cell change what GET.* reads, and
+/// editing them away gives the row height, column width and fill back the
+/// values they had before the formula changed them.
+#[test]
+fn test_set_functions_round_trip_and_revert_when_the_formula_is_removed() {
+    use crate::commands::data::update_cell_impl;
+    let state = create_app_state();
+    let file_state = FileState::default();
+    let user_files = UserFilesState { files: Mutex::new(HashMap::new()) };
+    let slicer = crate::slicer::SlicerState::new();
+    let pivot = crate::pivot::PivotState::new();
+    let pane = crate::pane_control::PaneControlState::new();
+    let ribbon = crate::ribbon_filter::RibbonFilterState::new();
+    let edit = |row: u32, col: u32, value: &str| {
+        update_cell_impl(&state, &file_state, &user_files, &slicer, &pivot, &pane, &ribbon, row, col, value.to_string(), None, None)
+            .unwrap()
+    };
+    let value_at = |row: u32, col: u32| state.grids.lock().unwrap()[0].get_cell(row, col).map(|c| c.value.clone());
+    let fill_at = |row: u32, col: u32| {
+        let grids = state.grids.lock().unwrap();
+        let style_index = grids[0].get_cell(row, col).map(|c| c.style_index).unwrap_or(0);
+        state.style_registry.lock().unwrap().get(style_index).fill.clone()
+    };
+    let dims_of = |result: &crate::api_types::UpdateCellResult| -> Vec<(String, u32, f64)> {
+        result.dimension_changes.iter().map(|d| (d.dimension_type.clone(), d.index, d.size)).collect()
+    };
+    let green = engine::Fill::Solid { color: engine::ThemeColor::Absolute(engine::Color::new(0, 128, 0)) };
+    let red = engine::Fill::Solid { color: engine::ThemeColor::Absolute(engine::Color::new(255, 0, 0)) };
+
+    // Row 3 was resized by hand, column B has the default width, B2 is green.
+    state.row_heights.lock().unwrap().insert(2, 25.0);
+    let mut green_style = CellStyle::new();
+    green_style.fill = green.clone();
+    let green_index = state.style_registry.lock().unwrap().get_or_create(green_style);
+    let mut b2 = Cell::new_text("x".to_string());
+    b2.style_index = green_index;
+    state.grids.lock().unwrap()[0].set_cell(1, 1, b2);
+
+    let result = edit(0, 0, "=SET.ROW.HEIGHT(3, 40)");
+    assert_eq!(dims_of(&result), vec![("row".to_string(), 2, 40.0)]);
+    let result = edit(1, 0, "=SET.COLUMN.WIDTH(2, 120)");
+    assert_eq!(dims_of(&result), vec![("column".to_string(), 1, 120.0)]);
+    let result = edit(2, 0, "=SET.CELL.FILLCOLOR(B2, \"#FF0000\")");
+    assert!(result.needs_style_refresh);
+    assert_eq!(state.row_heights.lock().unwrap().get(&2), Some(&40.0));
+    assert_eq!(state.column_widths.lock().unwrap().get(&1), Some(&120.0));
+    assert_eq!(fill_at(1, 1), red);
+
+    // GET.* reads what the SET.* formulas applied
+    edit(0, 3, "=GET.ROW.HEIGHT(3)");
+    edit(1, 3, "=GET.COLUMN.WIDTH(2)");
+    edit(2, 3, "=GET.CELL.FILLCOLOR(B2)");
+    assert_eq!(value_at(0, 3), Some(CellValue::Number(40.0)));
+    assert_eq!(value_at(1, 3), Some(CellValue::Number(120.0)));
+    assert_eq!(value_at(2, 3), Some(CellValue::Text("#ff0000".to_string())));
+
+    // A plain value, a cleared cell and a formula without SET.* each revert.
+    let result = edit(0, 0, "1");
+    assert_eq!(dims_of(&result), vec![("row".to_string(), 2, 25.0)]);
+    assert_eq!(state.row_heights.lock().unwrap().get(&2), Some(&25.0));
+
+    let result = edit(1, 0, "");
+    assert_eq!(dims_of(&result), vec![("column".to_string(), 1, 0.0)]);
+    assert!(state.column_widths.lock().unwrap().get(&1).is_none());
+
+    let result = edit(2, 0, "=1+1");
+    assert!(result.needs_style_refresh);
+    assert_eq!(fill_at(1, 1), green);
+    assert_eq!(value_at(1, 1), Some(CellValue::Text("x".to_string())));
+
+    // ...and GET.* reads the restored values again
+    edit(0, 3, "=GET.ROW.HEIGHT(3)");
+    edit(1, 3, "=GET.COLUMN.WIDTH(2)");
+    edit(2, 3, "=GET.CELL.FILLCOLOR(B2)");
+    assert_eq!(value_at(0, 3), Some(CellValue::Number(25.0)));
+    assert_eq!(value_at(1, 3), Some(CellValue::Number(100.0)));
+    assert_eq!(value_at(2, 3), Some(CellValue::Text("#008000".to_string())));
+}
+
+/// SET.* formulas are evaluated like any other formula: entering one applies
+/// its request instead of returning #VALUE!, and editing a cell it depends on
+/// re-applies it through the dependent recalculation.
+#[test]
+fn test_set_functions_follow_their_inputs() {
+    use crate::commands::data::update_cell_impl;
+    let state = create_app_state();
+    let file_state = FileState::default();
+    let user_files = UserFilesState { files: Mutex::new(HashMap::new()) };
+    let slicer = crate::slicer::SlicerState::new();
+    let pivot = crate::pivot::PivotState::new();
+    let pane = crate::pane_control::PaneControlState::new();
+    let ribbon = crate::ribbon_filter::RibbonFilterState::new();
+    let edit = |row: u32, col: u32, value: &str| {
+        update_cell_impl(&state, &file_state, &user_files, &slicer, &pivot, &pane, &ribbon, row, col, value.to_string(), None, None)
+            .unwrap()
+    };
+    let value_at = |row: u32, col: u32| state.grids.lock().unwrap()[0].get_cell(row, col).map(|c| c.value.clone());
+    let width = || state.column_widths.lock().unwrap().get(&1).copied();
+
+    edit(0, 0, "=SET.COLUMN.WIDTH(2,120)");
+    assert_eq!(value_at(0, 0), Some(CellValue::Number(120.0)));
+    assert_eq!(width(), Some(120.0));
+
+    edit(1, 0, "100");
+    edit(0, 0, "=SET.COLUMN.WIDTH(2, A2 + 20)");
+    assert_eq!(width(), Some(120.0));
+    let result = edit(1, 0, "200");
+    assert_eq!(value_at(0, 0), Some(CellValue::Number(220.0)));
+    assert_eq!(width(), Some(220.0));
+    let dims: Vec<_> = result.dimension_changes.iter().map(|d| (d.dimension_type.as_str(), d.index, d.size)).collect();
+    assert_eq!(dims, vec![("column", 1, 220.0)]);
+}
+
+/// Two formulas setting row 3 to different heights, under each conflict
+/// policy: the conflict is listed with both cells, only `Error` turns them
+/// into #CONFLICT, and removing one re-applies the other's height.
+#[test]
+fn test_ui_effect_conflict_policies() {
+    use crate::commands::data::update_cell_impl;
+    use crate::ui_effects::{
+        get_ui_effect_conflicts_internal, set_ui_effect_conflict_policy_internal, UiEffectConflictPolicy,
+        UiEffectTarget,
+    };
+    let conflict = CellValue::Error(CellError::Conflict);
+    for (policy, height_applied) in [
+        (UiEffectConflictPolicy::Error, 25.0),
+        (UiEffectConflictPolicy::LastWriterWins, 40.0),
+        (UiEffectConflictPolicy::MaxValue, 50.0),
+    ] {
+        let state = create_app_state();
+        let file_state = FileState::default();
+        let user_files = UserFilesState { files: Mutex::new(HashMap::new()) };
+        let slicer = crate::slicer::SlicerState::new();
+        let pivot = crate::pivot::PivotState::new();
+        let pane = crate::pane_control::PaneControlState::new();
+        let ribbon = crate::ribbon_filter::RibbonFilterState::new();
+        let edit = |row: u32, col: u32, value: &str| {
+            update_cell_impl(&state, &file_state, &user_files, &slicer, &pivot, &pane, &ribbon, row, col, value.to_string(), None, None)
+                .unwrap()
+        };
+        let height = || state.row_heights.lock().unwrap().get(&2).copied();
+        let value_at = |row: u32| state.grids.lock().unwrap()[0].get_cell(row, 0).map(|cell| cell.value.clone());
+        set_ui_effect_conflict_policy_internal(&state, policy);
+        state.row_heights.lock().unwrap().insert(2, 25.0);
+
+        edit(0, 0, "=SET.ROW.HEIGHT(3, 50)");
+        edit(1, 0, "=SET.ROW.HEIGHT(3, 40)");
+        assert_eq!(height(), Some(height_applied), "{:?}", policy);
+        let conflicts = get_ui_effect_conflicts_internal(&state);
+        assert_eq!(conflicts.len(), 1, "{:?}", policy);
+        assert_eq!(conflicts[0].target, UiEffectTarget::RowHeight { sheet: 0, row: 2 });
+        let sources: Vec<_> = conflicts[0].sources.iter().map(|s| (s.sheet_index, s.row, s.col)).collect();
+        assert_eq!(sources, vec![(0, 0, 0), (0, 1, 0)]);
+        if policy == UiEffectConflictPolicy::Error {
+            assert_eq!(value_at(0), Some(conflict.clone()));
+            assert_eq!(value_at(1), Some(conflict.clone()));
+        } else {
+            assert_eq!(value_at(0), Some(CellValue::Number(50.0)), "{:?}", policy);
+            assert_eq!(value_at(1), Some(CellValue::Number(40.0)), "{:?}", policy);
+        }
+
+        // Clearing one source re-applies the survivor's height and gives it
+        // back its own value.
+        edit(1, 0, "");
+        assert_eq!(height(), Some(50.0), "{:?}", policy);
+        assert_eq!(value_at(0), Some(CellValue::Number(50.0)), "{:?}", policy);
+        assert!(get_ui_effect_conflicts_internal(&state).is_empty());
+
+        // Requests that agree are not a conflict.
+        edit(1, 0, "=SET.ROW.HEIGHT(3, 50)");
+        assert_eq!(height(), Some(50.0), "{:?}", policy);
+        assert_eq!(value_at(1), Some(CellValue::Number(50.0)), "{:?}", policy);
+        assert!(get_ui_effect_conflicts_internal(&state).is_empty());
+    }
+}
+
+/// Changing the policy settles existing conflicts right away, the policy is
+/// kept in the workbook's extension data, and overwriting one conflicting
+/// formula with a value releases the other.
+#[test]
+fn test_ui_effect_conflict_policy_change_and_overwritten_source() {
+    use crate::commands::data::update_cell_impl;
+    use crate::ui_effects::{conflict_policy, set_ui_effect_conflict_policy_internal, UiEffectConflictPolicy, UI_EFFECTS_EXT_KEY};
+    let state = create_app_state();
+    let file_state = FileState::default();
+    let user_files = UserFilesState { files: Mutex::new(HashMap::new()) };
+    let slicer = crate::slicer::SlicerState::new();
+    let pivot = crate::pivot::PivotState::new();
+    let pane = crate::pane_control::PaneControlState::new();
+    let ribbon = crate::ribbon_filter::RibbonFilterState::new();
+    let edit = |row: u32, col: u32, value: &str| {
+        update_cell_impl(&state, &file_state, &user_files, &slicer, &pivot, &pane, &ribbon, row, col, value.to_string(), None, None)
+            .unwrap();
+    };
+    let width = || state.column_widths.lock().unwrap().get(&1).copied();
+    let value_at = |col: u32| state.grids.lock().unwrap()[0].get_cell(0, col).map(|cell| cell.value.clone());
+    let conflict = CellValue::Error(CellError::Conflict);
+
+    assert_eq!(conflict_policy(&state), UiEffectConflictPolicy::Error);
+    edit(0, 0, "=SET.COLUMN.WIDTH(2, 90)");
+    edit(0, 1, "=SET.COLUMN.WIDTH(2, 120)");
+    assert_eq!(width(), None);
+    assert_eq!((value_at(0), value_at(1)), (Some(conflict.clone()), Some(conflict.clone())));
+
+    let dims = set_ui_effect_conflict_policy_internal(&state, UiEffectConflictPolicy::MaxValue);
+    assert_eq!(dims.iter().map(|d| (d.index, d.size)).collect::<Vec<_>>(), vec![(1, 120.0)]);
+    assert_eq!(width(), Some(120.0));
+    assert_eq!(value_at(0), Some(CellValue::Number(90.0)));
+    assert_eq!(value_at(1), Some(CellValue::Number(120.0)));
+    assert_eq!(
+        state.extension_data.lock().unwrap().get(UI_EFFECTS_EXT_KEY),
+        Some(&serde_json::json!({ "conflictPolicy": "maxValue" }))
+    );
+    assert_eq!(conflict_policy(&state), UiEffectConflictPolicy::MaxValue);
+
+    set_ui_effect_conflict_policy_internal(&state, UiEffectConflictPolicy::Error);
+    assert_eq!(width(), None);
+    assert_eq!((value_at(0), value_at(1)), (Some(conflict.clone()), Some(conflict)));
+
+    edit(0, 1, "7");
+    assert_eq!(width(), Some(90.0));
+    assert_eq!(value_at(0), Some(CellValue::Number(90.0)));
+    assert_eq!(value_at(1), Some(CellValue::Number(7.0)));
+}
+
+/// A file rewritten by another program between open and save is reported
+/// (once by the watcher): a plain save and a reload over unsaved edits are
+/// refused, while `overwrite` and `force` resolve the conflict. A touch that
+/// leaves the bytes unchanged is not a conflict.
+#[test]
+fn test_external_file_change_detection() {
+    use crate::persistence::{
+        reload_file_internal, save_file_internal, FileState, FILE_CHANGED_EXTERNALLY, RELOAD_DISCARDS_CHANGES,
+    };
+    use crate::bi::types::BiState;
+    use crate::pivot::types::PivotState;
+    use crate::scripting::types::ScriptState;
+    use crate::slicer::SlicerState;
+    fn save(state: &AppState, file_state: &FileState, path: &std::path::Path, overwrite: Option<bool>) -> Result<(), String> {
+        save_file_internal(
+            state, file_state, &UserFilesState::default(), &SlicerState::new(), &RibbonFilterState::new(),
+            &PaneControlState::new(), &ScriptState::new(), &PivotState::new(), &BiState::new(),
+            path.to_string_lossy().to_string(), None, overwrite,
+        )
+    }
+    fn reload(state: &AppState, file_state: &FileState, force: Option<bool>) -> Result<Vec<CellData>, String> {
+        reload_file_internal(
+            state, file_state, &UserFilesState::default(), &SlicerState::new(), &RibbonFilterState::new(),
+            &PaneControlState::new(), &ScriptState::new(), &PivotState::new(), &BiState::new(), force,
+        )
+    }
+    let set_a1 = |state: &AppState, n: f64| state.grids.lock().unwrap()[0].set_cell(0, 0, Cell::new_number(n));
+    let a1 = |state: &AppState| state.grids.lock().unwrap()[0].get_cell(0, 0).map(|c| c.value.clone());
+
+    let dir = tempfile::TempDir::new().unwrap();
+    let path = dir.path().join("book.xlsx");
+    // Another program's version of the workbook, copied over ours below.
+    let rewrite = |n: f64| {
+        let other = dir.path().join("other.xlsx");
+        let other_state = create_app_state();
+        set_a1(&other_state, n);
+        save(&other_state, &FileState::default(), &other, None).unwrap();
+        std::fs::copy(&other, &path).unwrap();
+    };
+
+    let state = create_app_state();
+    let file_state = FileState::default();
+    // A never-saved document has nothing to compare against.
+    assert!(file_state.external_change().unwrap().is_none());
+    set_a1(&state, 1.0);
+    save(&state, &file_state, &path, None).unwrap();
+    assert!(file_state.external_change().unwrap().is_none());
+
+    // Same bytes rewritten (sync client touch): not a change.
+    std::fs::write(&path, std::fs::read(&path).unwrap()).unwrap();
+    assert!(file_state.external_change().unwrap().is_none());
+
+    // Another program edits the file while we have unsaved edits.
+    rewrite(2.0);
+    set_a1(&state, 3.0);
+    *file_state.is_modified.lock().unwrap() = true;
+    let change = file_state.external_change().unwrap().expect("change reported");
+    assert!(!change.deleted);
+    assert!(change.has_unsaved_changes);
+    assert_eq!(change.path, path.to_string_lossy());
+    // The watcher announces it once.
+    assert!(file_state.unreported_external_change().is_some());
+    assert!(file_state.unreported_external_change().is_none());
+
+    // Saving and reloading are refused; neither touches the disk or memory.
+    assert_eq!(save(&state, &file_state, &path, None), Err(FILE_CHANGED_EXTERNALLY.to_string()));
+    assert_eq!(reload(&state, &file_state, None).unwrap_err(), RELOAD_DISCARDS_CHANGES);
+    assert_eq!(a1(&state), Some(CellValue::Number(3.0)));
+
+    // Resolution 1: reload anyway, taking the other program's version.
+    reload(&state, &file_state, Some(true)).unwrap();
+    assert_eq!(a1(&state), Some(CellValue::Number(2.0)));
+    assert!(!*file_state.is_modified.lock().unwrap());
+    assert!(file_state.external_change().unwrap().is_none());
+
+    // Resolution 2: the file changes again and we overwrite it.
+    rewrite(4.0);
+    set_a1(&state, 5.0);
+    *file_state.is_modified.lock().unwrap() = true;
+    assert_eq!(save(&state, &file_state, &path, None), Err(FILE_CHANGED_EXTERNALLY.to_string()));
+    save(&state, &file_state, &path, Some(true)).unwrap();
+    assert!(file_state.external_change().unwrap().is_none());
+    reload(&state, &file_state, None).unwrap();
+    assert_eq!(a1(&state), Some(CellValue::Number(5.0)));
+
+    // Removing the open file is reported as a deletion, and saving recreates it.
+    std::fs::remove_file(&path).unwrap();
+    assert!(file_state.external_change().unwrap().unwrap().deleted);
+    save(&state, &file_state, &path, None).unwrap();
+    assert!(path.exists());
+}
+
+/// Read-only mode rejects edit commands with the uniform sentinel but leaves
+/// view (filters) and calculation commands alone; the lists don't overlap.
+#[test]
+fn test_read_only_mode_blocks_edits_only() {
+    use crate::persistence::FileState;
+    use crate::read_only::{
+        command_access, guard_command, CommandAccess, ReadOnlyReason, CALCULATION_COMMANDS,
+        EDIT_COMMANDS, VIEW_COMMANDS, WORKBOOK_READ_ONLY,
+    };
+
+    for name in EDIT_COMMANDS {
+        assert!(!VIEW_COMMANDS.contains(name) && !CALCULATION_COMMANDS.contains(name), "{name}");
+    }
+    for name in VIEW_COMMANDS {
+        assert!(!CALCULATION_COMMANDS.contains(name), "{name}");
+    }
+    assert_eq!(command_access("save_file"), CommandAccess::Read);
+    assert_eq!(command_access("get_viewport_cells"), CommandAccess::Read);
+
+    let file_state = FileState::default();
+    let edits = ["update_cell", "insert_rows", "add_sheet", "set_cell_style", "create_pivot_table"];
+    let allowed = [
+        "get_viewport_cells",
+        "apply_auto_filter",
+        "set_column_filter_values",
+        "update_slicer_selection",
+        "set_freeze_panes",
+        "calculate_now",
+        "refresh_pivot_cache",
+    ];
+    for name in edits.iter().chain(allowed.iter()) {
+        assert!(guard_command(name, &file_state).is_ok(), "{name} while writable");
+    }
+
+    file_state.set_read_only(Some(ReadOnlyReason::Requested)).unwrap();
+    for name in edits {
+        assert_eq!(guard_command(name, &file_state), Err(WORKBOOK_READ_ONLY.to_string()), "{name}");
+    }
+    for name in allowed {
+        assert!(guard_command(name, &file_state).is_ok(), "{name} while read-only");
+    }
+}
+
+/// A read-only workbook can't be saved over its own file; Save As to a new
+/// path works and leaves read-only mode. The OS attribute is detected.
+#[test]
+fn test_read_only_save_as_leaves_mode() {
+    use crate::persistence::FileState;
+    use crate::read_only::{detect_read_only, guard_command, ReadOnlyReason, WORKBOOK_READ_ONLY};
+
+    let dir = tempfile::TempDir::new().unwrap();
+    let path = dir.path().join("book.cala");
+    std::fs::write(&path, b"original").unwrap();
+    assert_eq!(detect_read_only(&path), None);
+
+    let mut permissions = std::fs::metadata(&path).unwrap().permissions();
+    permissions.set_readonly(true);
+    std::fs::set_permissions(&path, permissions).unwrap();
+    assert_eq!(detect_read_only(&path), Some(ReadOnlyReason::ReadOnlyAttribute));
+
+    // "Open" as open_file does for such a file.
+    let file_state = FileState::default();
+    file_state.set_read_only(detect_read_only(&path)).unwrap();
+    file_state.record_disk_state(&path);
+    *file_state.current_path.lock().unwrap() = Some(path.clone());
+
+    assert_eq!(file_state.check_save_target(&path), Err(WORKBOOK_READ_ONLY.to_string()));
+
+    // Save As.
+    let copy = dir.path().join("book (copy).cala");
+    file_state.check_save_target(&copy).unwrap();
+    std::fs::write(&copy, b"our version").unwrap();
+    file_state.record_save(copy.clone()).unwrap();
+    assert_eq!(file_state.read_only_reason(), None);
+    assert_eq!(*file_state.current_path.lock().unwrap(), Some(copy.clone()));
+    file_state.check_save_target(&copy).unwrap();
+    assert!(guard_command("update_cell", &file_state).is_ok());
+}
+
+/// A 6x3 sheet of "r{row}c{col}" text cells.
+fn print_area_fixture() -> AppState {
+    let state = create_app_state();
+    {
+        let mut grid = state.get_active_grid();
+        for r in 0..6 {
+            for c in 0..3 {
+                grid.set_cell(r, c, Cell::new_text(format!("r{}c{}", r, c)));
+            }
+        }
+    }
+    state
+}
+
+#[test]
+fn test_export_csv_honors_print_area_and_titles() {
+    use crate::commands::export::{export_csv_internal, CsvExportOptions};
+    use crate::commands::print::set_print_areas_internal;
+    use crate::named_ranges::{print_titles, set_print_titles_name, PrintRange};
+
+    let state = print_area_fixture();
+    let options = CsvExportOptions::default();
+    assert_eq!(export_csv_internal(&state, &options).row_count, 6);
+
+    set_print_areas_internal(&state, 0, &[PrintRange { start_row: 2, start_col: 1, end_row: 4, end_col: 2 }]).unwrap();
+    let result = export_csv_internal(&state, &options);
+    assert_eq!(result.content, "r2c1,r2c2\r\nr3c1,r3c2\r\nr4c1,r4c2");
+
+    // Title row 1 repeats at the top of every logical page.
+    {
+        let mut names = state.named_ranges.lock().unwrap();
+        let mut titles = print_titles(&names, 0);
+        titles.rows = Some((0, 0));
+        set_print_titles_name(&mut names, 0, titles);
+    }
+    let paged = CsvExportOptions { rows_per_page: Some(2), line_ending: "\n".to_string(), ..CsvExportOptions::default() };
+    let result = export_csv_internal(&state, &paged);
+    assert_eq!(result.content, "r0c1,r0c2\nr2c1,r2c2\nr0c1,r0c2\nr3c1,r3c2\nr0c1,r0c2\nr4c1,r4c2");
+    assert_eq!(result.row_count, 6);
+
+    // The print area can be bypassed.
+    let all = CsvExportOptions { use_print_area: false, ..CsvExportOptions::default() };
+    assert!(export_csv_internal(&state, &all).content.starts_with("r0c0,r0c1,r0c2\r\nr1c0"));
+}
+
+#[test]
+fn test_print_area_multi_area_storage() {
+    use crate::commands::print::{set_print_areas_internal, sheet_page_setup};
+    use crate::named_ranges::{print_area, PrintRange, PRINT_AREA_NAME};
+
+    let state = print_area_fixture();
+    let areas = [
+        PrintRange { start_row: 0, start_col: 0, end_row: 19, end_col: 5 },
+        PrintRange { start_row: 0, start_col: 7, end_row: 4, end_col: 9 },
+    ];
+    assert_eq!(set_print_areas_internal(&state, 0, &areas).unwrap(), "A1:F20,H1:J5");
+
+    {
+        let names = state.named_ranges.lock().unwrap();
+        let name = names.values().find(|nr| nr.name == PRINT_AREA_NAME).unwrap();
+        assert_eq!(name.sheet_index, Some(0));
+        assert_eq!(name.refers_to, "=$A$1:$F$20,$H$1:$J$5");
+        assert_eq!(print_area(&names, 0), areas.to_vec());
+    }
+    assert_eq!(sheet_page_setup(&state, 0).print_area, "A1:F20,H1:J5");
+    // Built-in names stay out of the Name Manager list.
+    assert!(crate::named_ranges::get_all_named_ranges_internal(&state).iter().all(|nr| !nr.name.starts_with("_xlnm.")));
+}
+
+#[test]
+fn test_print_names_shift_on_row_insert() {
+    use crate::commands::print::{set_print_areas_internal, sheet_page_setup};
+    use crate::commands::structure::insert_rows_internal;
+    use crate::named_ranges::{print_titles, set_print_titles_name, PrintRange};
+
+    let state = print_area_fixture();
+    let pivot_state = crate::pivot::types::PivotState::new();
+    set_print_areas_internal(&state, 0, &[PrintRange { start_row: 2, start_col: 0, end_row: 5, end_col: 2 }]).unwrap();
+    {
+        let mut names = state.named_ranges.lock().unwrap();
+        let mut titles = print_titles(&names, 0);
+        titles.rows = Some((1, 1));
+        titles.cols = Some((0, 0));
+        set_print_titles_name(&mut names, 0, titles);
+    }
+
+    // Insert two rows above the title row: area and titles move down.
+    insert_rows_internal(&state, &pivot_state, 0, 2).unwrap();
+    let setup = sheet_page_setup(&state, 0);
+    assert_eq!(setup.print_area, "A5:C8");
+    assert_eq!(setup.print_titles_rows, "4:4");
+    assert_eq!(setup.print_titles_cols, "A:A");
+
+    // Inserting inside the area grows it.
+    insert_rows_internal(&state, &pivot_state, 6, 1).unwrap();
+    assert_eq!(sheet_page_setup(&state, 0).print_area, "A5:C9");
+}
+
+#[test]
+fn test_change_journal_records_edits_and_undo() {
+    use crate::change_journal::{
+        create_history_sheet_internal, get_change_history_internal, record_committed, set_change_tracking_internal,
+    };
+    use crate::undo_commands::undo_internal;
+
+    let state = create_app_state();
+    let file_state = FileState::default();
+    let user_files_state = UserFilesState { files: Mutex::new(HashMap::new()) };
+    let pivot_state = crate::pivot::types::PivotState::new();
+    let slicer_state = crate::slicer::SlicerState::new();
+    let ribbon_filter_state = crate::ribbon_filter::RibbonFilterState::new();
+    let pane_control_state = crate::pane_control::PaneControlState::new();
+
+    // Off by default: commits are not journaled.
+    state.undo_stack.lock().unwrap().record_cell_change(5, 5, None);
+    record_committed(&state, Some("update_cell"));
+    assert!(get_change_history_internal(&state, None, None, None).unwrap().is_empty());
+
+    set_change_tracking_internal(&state, true, Some(10));
+
+    // A single edit, committed the way commands do: record, write, commit.
+    state.undo_stack.lock().unwrap().record_cell_change(0, 0, None);
+    state.get_active_grid().set_cell(0, 0, Cell::new_number(1.0));
+    record_committed(&state, Some("update_cell"));
+
+    // A batch touching A1 twice and A2 once.
+    {
+        let mut undo_stack = state.undo_stack.lock().unwrap();
+        undo_stack.begin_transaction("Paste 2 cells");
+        undo_stack.record_cell_change(0, 0, Some(Cell::new_number(1.0)));
+        undo_stack.record_cell_change(1, 0, None);
+        undo_stack.record_cell_change(0, 0, Some(Cell::new_number(5.0)));
+        undo_stack.commit_transaction();
+    }
+    {
+        let mut grid = state.get_active_grid();
+        grid.set_cell(0, 0, Cell::new_number(2.0));
+        grid.set_cell(1, 0, Cell::new_formula("A1*2".to_string()));
+    }
+    record_committed(&state, Some("update_cells_batch"));
+
+    // Undo is journaled as new events; the earlier entries stay as they were.
+    let undone = undo_internal(
+        &state, &file_state, &user_files_state, &pivot_state, &slicer_state, &ribbon_filter_state, &pane_control_state,
+    );
+    assert!(undone.success);
+    record_committed(&state, Some("undo"));
+
+    let a1: Vec<(String, String, Option<String>)> = get_change_history_internal(&state, None, Some("a1".to_string()), None)
+        .unwrap()
+        .into_iter()
+        .map(|e| (e.old_value, e.new_value, e.command))
+        .collect();
+    assert_eq!(
+        a1,
+        vec![
+            (String::new(), "1".to_string(), Some("update_cell".to_string())),
+            ("1".to_string(), "2".to_string(), Some("update_cells_batch".to_string())),
+            ("2".to_string(), "1".to_string(), Some("undo".to_string())),
+        ]
+    );
+
+    let all = get_change_history_internal(&state, None, None, None).unwrap();
+    assert_eq!(all.len(), 5);
+    assert!(all.windows(2).all(|w| w[0].seq < w[1].seq));
+    assert_eq!(all[2].cell, "A2");
+    assert_eq!(all[2].new_formula.as_deref(), Some("=A1*2"));
+    assert_eq!(all[2].description, "Paste 2 cells");
+
+    let recent: Vec<(String, Option<String>)> =
+        get_change_history_internal(&state, Some(0), None, Some(2)).unwrap().into_iter().map(|e| (e.cell, e.command)).collect();
+    assert_eq!(recent, vec![("A1".to_string(), Some("undo".to_string())), ("A2".to_string(), Some("undo".to_string()))]);
+    assert!(get_change_history_internal(&state, Some(1), None, None).unwrap().is_empty());
+    assert!(get_change_history_internal(&state, None, Some("1A".to_string()), None).is_err());
+
+    // Shrinking the ring drops the oldest entries.
+    assert_eq!(set_change_tracking_internal(&state, true, Some(3)).entry_count, 3);
+
+    // Export to a new sheet: a header row plus one row per entry.
+    let sheets = create_history_sheet_internal(&state).unwrap();
+    assert_eq!(sheets.sheets.last().unwrap().name, "Change History");
+    let grid = state.get_active_grid();
+    assert_eq!(grid.get_cell(0, 2).unwrap().value, engine::CellValue::Text("Cell".to_string()));
+    assert_eq!(grid.get_cell(2, 2).unwrap().value, engine::CellValue::Text("A1".to_string()));
+    assert_eq!(grid.get_cell(3, 2).unwrap().value, engine::CellValue::Text("A2".to_string()));
+    assert!(grid.get_cell(4, 2).is_none());
+}
+
+#[test]
+fn test_evaluate_formula_previews_expansion_and_budget() {
+    use crate::evaluate_formula::{build_step_state, EvalBudget, EvalSession};
+
+    let mut grid = Grid::new();
+    for r in 0..500 {
+        grid.set_cell(r, 0, Cell::new_number((r + 1) as f64));
+    }
+    let grids = vec![grid];
+    let sheet_names = vec!["Sheet1".to_string()];
+    let formula = "=IF(SUM(A1:A500)>100,TAKE(A1:A500,-3),0)".to_string();
+    let session_for = |budget: EvalBudget| {
+        let ast = convert_expr(&parser::parse(&formula).unwrap());
+        EvalSession::new("$B$1".to_string(), 0, 1, 0, formula.clone(), ast, budget)
+    };
+
+    let mut session = session_for(EvalBudget::default());
+    let state = build_step_state("s", &session, &grids, &sheet_names);
+    // SUM is underlined; its range is resolved before evaluation, previewed
+    // rather than dumped.
+    assert_eq!(state.node_id.as_deref(), Some("0/0.0"));
+    assert_eq!(state.references.len(), 1);
+    let range = &state.references[0];
+    assert_eq!(range.node_id, "0/0.0.0");
+    assert_eq!(range.address.as_deref(), Some("Sheet1!$A$1:$A$500"));
+    let preview = range.preview.as_ref().unwrap();
+    assert_eq!((preview.rows, preview.cols, preview.total), (500, 1, 500));
+    assert_eq!(preview.values.len(), 10);
+    assert!(preview.truncated);
+
+    // Lazy expansion of the tail of the range.
+    let page = session.expand_node("0/0.0.0", 495, 10, &grids, &sheet_names).unwrap();
+    assert_eq!(page.values, vec!["496", "497", "498", "499", "500"]);
+
+    // SUM, the comparison, -3, then TAKE: an array result stays in place
+    // under its stable id and is shown as a preview.
+    for _ in 0..4 {
+        session.evaluate_next(&grids, &sheet_names).unwrap();
+    }
+    let state = build_step_state("s", &session, &grids, &sheet_names);
+    assert_eq!(state.node_id.as_deref(), Some("0/"));
+    assert_eq!(state.array_results.len(), 1);
+    assert_eq!(state.array_results[0].node_id, "0/1");
+    assert_eq!(state.array_results[0].preview.as_ref().unwrap().values, vec!["498", "499", "500"]);
+    assert!(state.formula_display.starts_with("=IF(TRUE, {498, 499, 500}"));
+    let page = session.expand_node("0/1", 1, 5, &grids, &sheet_names).unwrap();
+    assert_eq!(page.values, vec!["499", "500"]);
+
+    session.evaluate_next(&grids, &sheet_names).unwrap();
+    let state = build_step_state("s", &session, &grids, &sheet_names);
+    assert!(state.is_complete);
+    assert_eq!(state.evaluation_result.as_deref(), Some("{498, 499, 500}"));
+    assert_eq!(state.steps_used, 5);
+
+    // A budget too small for the range aborts before evaluating it, and
+    // the session stays stopped.
+    let mut session = session_for(EvalBudget { max_steps: 100, max_cells: 100 });
+    let err = session.evaluate_next(&grids, &sheet_names).unwrap_err();
+    assert!(err.contains("budget exceeded"), "{}", err);
+    assert!(session.evaluate_next(&grids, &sheet_names).is_err());
+    let state = build_step_state("s", &session, &grids, &sheet_names);
+    assert!(!state.can_evaluate);
+    assert_eq!(state.node_id.as_deref(), Some("0/0.0"));
+
+    let mut session = session_for(EvalBudget { max_steps: 1, max_cells: u64::MAX });
+    session.evaluate_next(&grids, &sheet_names).unwrap();
+    assert!(session.evaluate_next(&grids, &sheet_names).unwrap_err().contains("Step limit"));
+}
+
+/// Completions for a workbook with a second, space-named sheet, a defined
+/// name and a table with two columns at B2:C6 on Sheet1.
+#[test]
+fn test_formula_completions() {
+    use crate::api_types::CompletionKind;
+    use crate::formula::compute_completions;
+
+    let state = create_app_state();
+    state.sheet_names.lock().unwrap().push("Q1 Sales".to_string());
+    state.named_ranges.lock().unwrap().insert("SUPPLY".to_string(), named_ranges::NamedRange {
+        name: "Supply".to_string(),
+        sheet_index: None,
+        refers_to: "=Sheet1!$A$1:$A$5".to_string(),
+        comment: None,
+        folder: None,
+    });
+    let table_id = identity::EntityId::from_bytes(identity::generate_uuid_v7());
+    let mut columns = Vec::new();
+    for name in ["Region", "Sales #"] {
+        columns.push(tables::TableColumn::new(
+            identity::EntityId::from_bytes(identity::generate_uuid_v7()),
+            name.to_string(),
+        ));
+    }
+    state.tables.lock().unwrap().entry(0).or_default().insert(table_id, tables::Table {
+        id: table_id,
+        name: "Orders".to_string(),
+        sheet_index: 0,
+        start_row: 1,
+        start_col: 1,
+        end_row: 5,
+        end_col: 2,
+        columns,
+        style_options: Default::default(),
+        style_name: "TableStyleMedium2".to_string(),
+        auto_filter_id: None,
+        data_type_policy: Default::default(),
+    });
+    state.table_names.lock().unwrap().insert("ORDERS".to_string(), (0, table_id));
+
+    // "=SU": prefix matches first (shortest first), then fuzzy matches.
+    let result = compute_completions(&state, 0, 10, 0, "=SU", 3).unwrap();
+    assert_eq!((result.replace_start, result.replace_end), (1, 3));
+    assert_eq!(result.items[0].label, "SUM");
+    assert_eq!(result.items[0].insert_text, "SUM(");
+    let supply = result.items.iter().position(|i| i.label == "Supply").expect("defined name offered");
+    assert_eq!(result.items[supply].kind, CompletionKind::DefinedName);
+    let first_fuzzy = result.items.iter().position(|i| !i.label.to_uppercase().starts_with("SU"));
+    if let Some(first_fuzzy) = first_fuzzy {
+        assert!(result.items[first_fuzzy..].iter().all(|i| !i.label.to_uppercase().starts_with("SU")));
+    }
+
+    // Inside a structured reference: the table's columns, escaped on insert.
+    let formula = "=SUM(Orders[Sa";
+    let result = compute_completions(&state, 0, 10, 0, formula, 14).unwrap();
+    assert_eq!((result.replace_start, result.replace_end), (12, 14));
+    assert_eq!(result.items[0].label, "Sales #");
+    assert_eq!(result.items[0].kind, CompletionKind::Column);
+    assert_eq!(result.items[0].insert_text, "Sales '#]");
+    // A bare "[@" inside the table resolves to the table at the edited cell.
+    let result = compute_completions(&state, 0, 3, 2, "=[@Re", 5).unwrap();
+    assert_eq!(result.replace_start, 3);
+    assert_eq!(result.items[0].label, "Region");
+    // Nested brackets complete the innermost item; special items start with #.
+    let result = compute_completions(&state, 0, 10, 0, "=Orders[[#He", 12).unwrap();
+    assert_eq!(result.items[0].label, "#Headers");
+
+    // After "'": sheet names, quoted on insert.
+    let result = compute_completions(&state, 0, 10, 0, "=SUM('Q", 7).unwrap();
+    assert_eq!((result.replace_start, result.replace_end), (5, 7));
+    assert_eq!(result.items.len(), 1);
+    assert_eq!(result.items[0].kind, CompletionKind::Sheet);
+    assert_eq!(result.items[0].insert_text, "'Q1 Sales'!");
+
+    // No completions inside string literals or for the end of a range.
+    assert!(compute_completions(&state, 0, 10, 0, "=\"SU", 4).unwrap().items.is_empty());
+    assert!(compute_completions(&state, 0, 10, 0, "=A1:B", 5).unwrap().items.is_empty());
+}
+
+/// validate_formula: parse errors carry position and expected tokens; names
+/// and structured references are checked against the workbook.
+#[test]
+fn test_validate_formula_reports_positions() {
+    use crate::api_types::FormulaIssueKind;
+    use crate::formula::validate_formula_internal;
+    let state = create_app_state();
+    state.named_ranges.lock().unwrap().insert("SUPPLY".to_string(), named_ranges::NamedRange {
+        name: "Supply".to_string(),
+        sheet_index: None,
+        refers_to: "=Sheet1!$A$1:$A$5".to_string(),
+        comment: None,
+        folder: None,
+    });
+    let table_id = identity::EntityId::from_bytes(identity::generate_uuid_v7());
+    let column = tables::TableColumn::new(
+        identity::EntityId::from_bytes(identity::generate_uuid_v7()),
+        "Region".to_string(),
+    );
+    state.tables.lock().unwrap().entry(0).or_default().insert(table_id, tables::Table {
+        id: table_id,
+        name: "Orders".to_string(),
+        sheet_index: 0,
+        start_row: 1,
+        start_col: 1,
+        end_row: 5,
+        end_col: 1,
+        columns: vec![column],
+        style_options: Default::default(),
+        style_name: "TableStyleMedium2".to_string(),
+        auto_filter_id: None,
+        data_type_policy: Default::default(),
+    });
+    state.table_names.lock().unwrap().insert("ORDERS".to_string(), (0, table_id));
+
+    let result = validate_formula_internal(&state, 0, 10, "=SUMA1:A3)").unwrap();
+    assert_eq!(result.issues.len(), 1);
+    let issue = &result.issues[0];
+    assert_eq!(issue.kind, FormulaIssueKind::Syntax);
+    assert_eq!(issue.message, "Expected '(' after function name SUM");
+    assert_eq!((issue.start, issue.end, issue.token.as_deref()), (4, 6, Some("A1")));
+    assert_eq!(issue.expected, vec!["("]);
+
+    // Defined names, LET locals and resolvable structured references pass;
+    // everything else is reported where it is written.
+    let formula = "=Supply + Missing + LET(x, 2, x * y) + Orders[Region] + Orders[Nope] + Sales[Q] + [@Region]";
+    let result = validate_formula_internal(&state, 0, 10, formula).unwrap();
+    let found: Vec<_> = result
+        .issues
+        .iter()
+        .map(|i| (i.kind, i.start, i.end, i.token.clone().unwrap()))
+        .collect();
+    assert_eq!(
+        found,
+        vec![
+            (FormulaIssueKind::UnknownName, 10, 17, "Missing".to_string()),
+            (FormulaIssueKind::UnknownName, 34, 35, "y".to_string()),
+            (FormulaIssueKind::UnknownColumn, 56, 68, "Orders[Nope]".to_string()),
+            (FormulaIssueKind::UnknownTable, 71, 79, "Sales[Q]".to_string()),
+            (FormulaIssueKind::UnknownTable, 82, 91, "[@Region]".to_string()),
+        ]
+    );
+    assert_eq!(result.issues[3].message, "Unknown table 'Sales'");
+
+    // Inside the table the implicit reference resolves; values are never checked.
+    assert!(validate_formula_internal(&state, 0, 3, "=[@Region]").unwrap().issues.is_empty());
+    assert!(validate_formula_internal(&state, 0, 3, "Missing + (").unwrap().issues.is_empty());
+}
+
+// ============================================================================
+// WHOLE-COLUMN / WHOLE-ROW FORMATTING TESTS
+// ============================================================================
+
+/// Formatting a whole column stores a column style instead of a cell per row;
+/// cells typed into the column later take it, and cell formats still win.
+#[test]
+fn test_whole_column_format_applies_to_later_entries() {
+    use crate::api_types::FormattingParams;
+    use crate::commands::styles::apply_formatting_internal;
+
+    let state = create_app_state();
+    state.get_active_grid().set_cell(0, 2, Cell::new_number(0.5));
+
+    let percent = FormattingParams {
+        cols: vec![2],
+        full_columns: true,
+        number_format: Some("percentage".to_string()),
+        ..Default::default()
+    };
+    let result = apply_formatting_internal(&state, &percent);
+    // Only the existing cell is touched; the rest of the column stays sparse.
+    assert_eq!(result.cells.len(), 1);
+    assert_eq!(result.cells[0].display, "50.00%");
+    let column_style = {
+        let grid = state.get_active_grid();
+        assert_eq!(grid.cells.len(), 1);
+        grid.col_styles[&2]
+    };
+
+    // A per-cell format on C7 overrides the column format.
+    let per_cell = FormattingParams {
+        rows: vec![6],
+        cols: vec![2],
+        number_format: Some("number".to_string()),
+        ..Default::default()
+    };
+    apply_formatting_internal(&state, &per_cell);
+
+    // Type into the column later: new cells start from the effective style,
+    // as update_cell does.
+    let locale = engine::LocaleSettings::invariant();
+    {
+        let mut grid = state.get_active_grid();
+        for row in [5, 6] {
+            let mut cell = parse_cell_input("0.25", &locale);
+            cell.style_index = grid.effective_style_index(row, 2);
+            grid.set_cell(row, 2, cell);
+        }
+    }
+
+    let grid = state.get_active_grid();
+    let styles = state.style_registry.lock().unwrap();
+    let merges = HashSet::new();
+    let cells = crate::commands::data::collect_viewport_cells(&grid, &styles, &merges, &locale, (0, 0, 9, 3));
+    let at = |row: u32, col: u32| cells.iter().find(|c| c.row == row && c.col == col);
+    assert_eq!(at(5, 2).unwrap().display, "25.00%");
+    assert_eq!(at(6, 2).unwrap().display, "0.25");
+    // Empty cells in the column carry the column style for rendering...
+    assert_eq!(at(3, 2).unwrap().style_index, column_style);
+    assert_eq!(at(3, 2).unwrap().display, "");
+    // ...but neighbouring columns stay empty.
+    assert!(at(3, 1).is_none());
+}
+
+/// A whole-row format outranks the column style, so where the row crosses a
+/// formatted column the empty cell is materialized to carry both formats.
+#[test]
+fn test_whole_row_format_over_formatted_column() {
+    use crate::api_types::FormattingParams;
+    use crate::commands::styles::apply_formatting_internal;
+
+    let state = create_app_state();
+    apply_formatting_internal(&state, &FormattingParams {
+        cols: vec![1],
+        full_columns: true,
+        number_format: Some("percentage".to_string()),
+        ..Default::default()
+    });
+    apply_formatting_internal(&state, &FormattingParams {
+        rows: vec![4],
+        full_rows: true,
+        bold: Some(true),
+        ..Default::default()
+    });
+
+    let grid = state.get_active_grid();
+    let styles = state.style_registry.lock().unwrap();
+    let row_style = styles.get(grid.effective_style_index(4, 0));
+    assert!(row_style.font.bold);
+    assert!(matches!(row_style.number_format, NumberFormat::General));
+    let crossing = styles.get(grid.effective_style_index(4, 1));
+    assert!(crossing.font.bold);
+    assert!(matches!(crossing.number_format, NumberFormat::Percentage { decimal_places: 2 }));
+    assert_eq!(grid.cells.len(), 1);
+    assert!(!styles.get(grid.effective_style_index(5, 1)).font.bold);
+}
+
+/// `=SUM('*'!A1)` depends on A1 of every sheet: an edit on a later sheet
+/// recalculates it, and so does data typed on a sheet added afterwards.
+#[test]
+fn test_wildcard_3d_ref_recalcs_on_every_sheet() {
+    use crate::commands::data::cascade_cross_sheet_dependents;
+    use crate::sheets::add_sheet_internal;
+
+    let state = create_app_state();
+    {
+        *state.sheet_names.lock().unwrap() =
+            vec!["Sheet1".to_string(), "Sheet2".to_string(), "Sheet3".to_string()];
+        let mut grids = state.grids.lock().unwrap();
+        grids.resize_with(3, Grid::new);
+        for (i, grid) in grids.iter_mut().enumerate() {
+            grid.set_cell(0, 0, Cell::new_number(i as f64 + 1.0));
+        }
+        grids[0].set_cell(0, 2, Cell::new_formula("SUM('*'!A1)".to_string()));
+    }
+    crate::undo_commands::rebuild_all_dependencies(&state);
+
+    // Type a number into A1 of the active sheet and run update_cell's
+    // cross-sheet walk from it; returns the recalculated Sheet1!C1.
+    let edit_a1 = |value: f64| {
+        let sheet_names = state.sheet_names.lock().unwrap();
+        let mut grids = state.grids.lock().unwrap();
+        let sheet = *state.active_sheet.lock().unwrap();
+        let styles = state.style_registry.lock().unwrap();
+        let dependents = state.dependents.lock().unwrap();
+        let cross_sheet_dependents = state.cross_sheet_dependents.lock().unwrap();
+        grids[sheet].set_cell(0, 0, Cell::new_number(value));
+        let mut updated = Vec::new();
+        cascade_cross_sheet_dependents(
+            &mut grids,
+            &sheet_names,
+            sheet,
+            &cross_sheet_dependents,
+            &dependents,
+            &HashMap::new(),
+            &std::sync::Arc::new(HashMap::new()),
+            &styles,
+            &engine::LocaleSettings::invariant(),
+            &HashMap::new(),
+            &[(0, 0)],
+            &[],
+            &mut updated,
+            false,
+        );
+        grids[0].get_cell(0, 2).unwrap().value.clone()
+    };
+
+    // Edit on a later sheet.
+    *state.active_sheet.lock().unwrap() = 2;
+    assert_eq!(edit_a1(30.0), CellValue::Number(33.0));
+
+    // A sheet added after the formula was entered is covered too.
+    add_sheet_internal(&state, None).unwrap();
+    assert_eq!(*state.active_sheet.lock().unwrap(), 3);
+    assert_eq!(edit_a1(100.0), CellValue::Number(133.0));
+}
+
+/// Switching away from a 500k-cell sheet moves the active index only: the
+/// grids stay in place, and edits land on whichever sheet is active.
+#[test]
+fn test_sheet_switch_does_not_copy_large_grid() {
+    use crate::sheets::{add_sheet_internal, set_active_sheet_internal};
+
+    let state = create_app_state();
+    {
+        let mut grid = state.get_active_grid();
+        for row in 0..50_000u32 {
+            for col in 0..10u32 {
+                grid.set_cell(row, col, Cell::new_number((row * 10 + col) as f64));
+            }
+        }
+    }
+    add_sheet_internal(&state, None).unwrap();
+    set_active_sheet_internal(&state, 0).unwrap();
+
+    let t0 = std::time::Instant::now();
+    let result = set_active_sheet_internal(&state, 1).unwrap();
+    // A full clone of the large sheet alone takes far longer than this.
+    assert!(t0.elapsed() < std::time::Duration::from_millis(50), "{:?}", t0.elapsed());
+    assert_eq!(result.active_index, 1);
+
+    state.get_active_grid().set_cell(0, 0, Cell::new_text("Sheet2".to_string()));
+    let grids = state.grids.lock().unwrap();
+    assert_eq!(grids[0].cells.len(), 500_000);
+    assert_eq!(grids[0].get_cell(0, 0).unwrap().value, CellValue::Number(0.0));
+    assert_eq!(grids[1].get_cell(0, 0).unwrap().value, CellValue::Text("Sheet2".to_string()));
+}
+
+/// Deleting a 3D bookend shrinks the span to the sheet that was next to it
+/// inside the span; deleting an interior sheet keeps both bookends.
+#[test]
+fn test_delete_sheet_repairs_3d_bookends_by_tab_adjacency() {
+    use crate::sheets::delete_sheet_internal;
+
+    // Summary, Jan..Apr with A1 = 1, 2, 4, 8; Summary!A1 = SUM(Feb:Apr!A1).
+    // Returns Summary!A1's formula text and recalculated value after deleting `index`.
+    let delete_and_recalc = |index: usize| {
+        let state = create_app_state();
+        let pivot_state = crate::pivot::types::PivotState::new();
+        {
+            *state.sheet_names.lock().unwrap() = ["Summary", "Jan", "Feb", "Mar", "Apr"]
+                .iter()
+                .map(|s| s.to_string())
+                .collect();
+            let mut grids = state.grids.lock().unwrap();
+            grids.resize_with(5, Grid::new);
+            for (i, grid) in grids.iter_mut().enumerate().skip(1) {
+                grid.set_cell(0, 0, Cell::new_number((1 << (i - 1)) as f64));
+            }
+            grids[0].set_cell(0, 0, Cell::new_formula("SUM(Feb:Apr!A1)".to_string()));
+        }
+        delete_sheet_internal(&state, &pivot_state, index).unwrap();
+
+        let user_files_state = UserFilesState { files: Mutex::new(HashMap::new()) };
+        crate::calculation::recalculate_sheet_values(&state, &user_files_state, &pivot_state, 0, None);
+        let grids = state.grids.lock().unwrap();
+        let cell = grids[0].get_cell(0, 0).unwrap();
+        (cell.formula_string().unwrap(), cell.value.clone())
+    };
+
+    // Start bookend: Feb:Apr -> Mar:Apr, not a span widened back to Summary.
+    assert_eq!(delete_and_recalc(2), ("SUM(Mar:Apr!A1)".to_string(), CellValue::Number(12.0)));
+    // End bookend: Feb:Apr -> Feb:Mar.
+    assert_eq!(delete_and_recalc(4), ("SUM(Feb:Mar!A1)".to_string(), CellValue::Number(6.0)));
+    // Interior sheet: bookends untouched, the span just loses Mar.
+    assert_eq!(delete_and_recalc(3), ("SUM(Feb:Apr!A1)".to_string(), CellValue::Number(10.0)));
+    // Outside the span: nothing changes.
+    assert_eq!(delete_and_recalc(1), ("SUM(Feb:Apr!A1)".to_string(), CellValue::Number(14.0)));
+}
+
+/// A sheet index that is out of range, or was valid until a delete, fails
+/// with SHEET_NOT_FOUND instead of panicking; a sheet id follows its sheet.
+#[test]
+fn test_stale_sheet_index_reports_sheet_not_found() {
+    use crate::api_types::DataTableOneVarParams;
+    use crate::sheet_access::{
+        record_command_revision, resolve_sheet, workbook_revision, ApiError, SheetRef, SHEET_NOT_FOUND,
+    };
+    use crate::sheets::{add_sheet_internal, delete_sheet_internal, set_active_sheet_internal};
+
+    let state = create_app_state();
+    let pivot_state = crate::pivot::types::PivotState::new();
+    add_sheet_internal(&state, None).unwrap();
+    add_sheet_internal(&state, None).unwrap();
+    let (second_id, third_id) = {
+        let ids = state.sheet_ids.lock().unwrap();
+        (ids[1].to_string(), ids[2].to_string())
+    };
+    delete_sheet_internal(&state, &pivot_state, 1).unwrap();
+
+    // Index 2 was the third sheet a moment ago.
+    let err = set_active_sheet_internal(&state, 2).unwrap_err();
+    assert!(err.starts_with(SHEET_NOT_FOUND), "{}", err);
+    assert!(err.contains(r#""requested":2,"currentCount":2"#), "{}", err);
+    assert!(delete_sheet_internal(&state, &pivot_state, 7).unwrap_err().starts_with(SHEET_NOT_FOUND));
+    assert_eq!(state.sheet_names.lock().unwrap().len(), 2);
+
+    // By id: the third sheet is now at index 1, the deleted one is gone.
+    assert_eq!(resolve_sheet(&state, &SheetRef::Id(third_id)).unwrap().index, 1);
+    assert_eq!(
+        resolve_sheet(&state, &SheetRef::Id(second_id.clone())),
+        Err(ApiError::SheetNotFound { requested: SheetRef::Id(second_id), current_count: 2 })
+    );
+
+    // Feature commands report it in their result.
+    let result = crate::data_tables::data_table_one_var_internal(
+        &state,
+        DataTableOneVarParams {
+            sheet_index: 5,
+            start_row: 0,
+            start_col: 0,
+            end_row: 2,
+            end_col: 1,
+            row_input_row: None,
+            row_input_col: None,
+            col_input_row: Some(0),
+            col_input_col: Some(3),
+        },
+    );
+    assert!(result.error.unwrap().starts_with(SHEET_NOT_FOUND));
+
+    // Edits advance the revision, reads do not; sheet results carry it.
+    let before = workbook_revision(&state);
+    assert_eq!(record_command_revision(&state, "get_sheets"), None);
+    assert_eq!(record_command_revision(&state, "delete_sheet"), Some(before + 1));
+    assert_eq!(set_active_sheet_internal(&state, 0).unwrap().revision, before + 1);
+}
+
+/// Stores `formula` at (row, col) of `sheet` the way update_cell does: names and
+/// structured references expanded into the cached AST, the typed text tracked.
+fn enter_tracked_formula(state: &AppState, sheet: usize, row: u32, col: u32, formula: &str) {
+    let parsed = parser::parse(formula).unwrap();
+    let source = crate::name_dependents::collect_refs(formula, &parsed);
+    let resolved = {
+        let named_ranges = state.named_ranges.lock().unwrap();
+        let tables = state.tables.lock().unwrap();
+        let table_names = state.table_names.lock().unwrap();
+        let resolved = resolve_names_in_ast(&parsed, &named_ranges, sheet, &mut std::collections::HashSet::new());
+        let ctx = TableRefContext { tables: &tables, table_names: &table_names, current_sheet_index: sheet, current_row: row };
+        resolve_table_refs_in_ast(&resolved, &ctx)
+    };
+    let mut cell = Cell::new_formula(formula.trim_start_matches('=').to_string());
+    cell.set_cached_ast(convert_expr(&resolved));
+    state.grids.lock().unwrap()[sheet].set_cell(row, col, cell.clone());
+    crate::name_dependents::track_cell(&mut state.name_ref_sources.lock().unwrap(), (sheet, row, col), source, &cell);
+}
+
+#[test]
+fn test_let_names_shadow_workbook_names_from_their_binding_on() {
+    let mut named_ranges = HashMap::new();
+    named_ranges.insert("RATE".to_string(), named_ranges::NamedRange {
+        name: "Rate".to_string(),
+        sheet_index: None,
+        refers_to: "=0.5".to_string(),
+        comment: None,
+        folder: None,
+    });
+    let grid = Grid::new();
+    let eval = |formula: &str| {
+        let parsed = parser::parse(formula).unwrap();
+        let resolved = resolve_names_in_ast(&parsed, &named_ranges, 0, &mut std::collections::HashSet::new());
+        Evaluator::new(&grid).evaluate(&convert_expr(&resolved)).to_cell_value()
+    };
+
+    // The first value still sees the workbook name; the calculation sees the binding
+    assert_eq!(eval("=LET(a, Rate*2, rate, 5, a+rate)"), CellValue::Number(6.0));
+    assert_eq!(eval("=LET(x, 1, LET(rate, 3, rate)+Rate)"), CellValue::Number(3.5));
+    assert_eq!(eval("=LET(x, 1, x, 2, x)"), CellValue::Error(CellError::Name));
+}
+
+#[test]
+fn test_hyperlink_formula_registers_and_clears_its_link() {
+    let state = create_app_state();
+    let eval_at = |row: u32, col: u32, formula: &str| {
+        let grids = state.grids.lock().unwrap();
+        let sheet_names = state.sheet_names.lock().unwrap();
+        let ast = convert_expr(&parser::parse(formula).unwrap());
+        let ctx = engine::EvalContext { current_row: Some(row), current_col: Some(col), ..Default::default() };
+        let result = evaluate_formula_raw_with_files_and_pivot(
+            &grids, &sheet_names, 0, &ast, ctx, None, &HashMap::new(), None, None, None,
+        );
+        crate::process_ui_effects(&state, 0, row, col, engine::take_ui_effects());
+        result.to_cell_value()
+    };
+    let link_at = |row: u32, col: u32| {
+        state.hyperlinks.lock().unwrap().get(&0).and_then(|links| links.get(&(row, col)).cloned())
+    };
+
+    assert_eq!(eval_at(0, 0, "=HYPERLINK(\"#Sheet1!C5\", \"Totals\")"), CellValue::Text("Totals".to_string()));
+    let link = link_at(0, 0).unwrap();
+    assert_eq!(link.link_type, hyperlinks::HyperlinkType::InternalReference);
+    assert_eq!(link.internal_ref.unwrap().cell_reference, "C5");
+    assert!(link.from_formula);
+
+    // Re-evaluating replaces the link; a formula without HYPERLINK removes it
+    eval_at(0, 0, "=HYPERLINK(\"https://example.com\")");
+    assert_eq!(link_at(0, 0).unwrap().target, "https://example.com");
+    eval_at(0, 0, "=1+1");
+    assert!(link_at(0, 0).is_none());
+
+    // A link added by hand wins over the formula and survives its removal
+    let manual = hyperlinks::Hyperlink::new_url(1, 0, 0, "https://manual.example".into());
+    state.hyperlinks.lock().unwrap().entry(0).or_default().insert((1, 0), manual);
+    eval_at(1, 0, "=HYPERLINK(\"https://example.com\")");
+    crate::clear_ui_effects_for_cell(&state, 0, 1, 0);
+    assert_eq!(link_at(1, 0).unwrap().target, "https://manual.example");
+}
+
+/// SET.* and HYPERLINK effects follow every path that writes a formula cell,
+/// not only single-cell edits: a batch paste, a paste of values over them,
+/// undo and a whole-sheet recalculation all take effect when the command's
+/// writes are sealed.
+#[test]
+fn test_ui_effects_are_committed_for_batch_undo_and_recalc() {
+    use crate::api_types::CellUpdateInput;
+    use crate::commands::data::update_cells_batch_with_controls;
+    use crate::viewport_revisions::seal_viewport_changes;
+    let state = create_app_state();
+    let file_state = FileState::default();
+    let user_files = UserFilesState { files: Mutex::new(HashMap::new()) };
+    let pivot = crate::pivot::types::PivotState::new();
+    let batch = |updates: &[(u32, u32, &str)]| {
+        let updates = updates
+            .iter()
+            .map(|&(row, col, value)| CellUpdateInput { row, col, value: value.to_string(), style_index: None, invariant: None })
+            .collect();
+        update_cells_batch_with_controls(&state, &file_state, &user_files, &pivot, updates, None, None).unwrap();
+        seal_viewport_changes(&state);
+    };
+    let height = || state.row_heights.lock().unwrap().get(&1).copied();
+    let link = || {
+        state.hyperlinks.lock().unwrap().get(&0).and_then(|links| links.get(&(0, 1))).map(|link| link.target.clone())
+    };
+
+    batch(&[(0, 0, "=SET.ROW.HEIGHT(2, 33)"), (0, 1, "=HYPERLINK(\"https://example.com\")")]);
+    assert_eq!(height(), Some(33.0));
+    assert_eq!(link().as_deref(), Some("https://example.com"));
+
+    batch(&[(0, 0, "5"), (0, 1, "x")]);
+    assert_eq!(height(), None);
+    assert_eq!(link(), None);
+
+    // Undo restores the formulas with their stored values; the commit
+    // evaluates them for their effects.
+    crate::undo_commands::undo_internal(
+        &state,
+        &file_state,
+        &user_files,
+        &pivot,
+        &crate::slicer::SlicerState::new(),
+        &RibbonFilterState::new(),
+        &PaneControlState::new(),
+    );
+    seal_viewport_changes(&state);
+    assert_eq!(height(), Some(33.0));
+    assert_eq!(link().as_deref(), Some("https://example.com"));
+
+    state.get_active_grid().set_cell(4, 0, Cell::new_formula("SET.COLUMN.WIDTH(3, 90)".to_string()));
+    crate::calculation::recalculate_sheet_values(&state, &user_files, &pivot, 0, None);
+    assert!(state.column_widths.lock().unwrap().get(&2).is_none());
+    seal_viewport_changes(&state);
+    assert_eq!(state.column_widths.lock().unwrap().get(&2), Some(&90.0));
+}
+
+#[test]
+fn test_clear_full_column_is_sparse_and_undoable() {
+    use crate::calculation::recalc_dependents_of;
+    use crate::commands::data::clear_range_internal;
+    use crate::persistence::FileState;
+    use crate::undo_commands::undo_internal;
+
+    let state = create_app_state();
+    let file_state = FileState::default();
+    let user_files_state = UserFilesState { files: Mutex::new(HashMap::new()) };
+    let pivot_state = crate::pivot::types::PivotState::new();
+    let slicer_state = crate::slicer::SlicerState::new();
+    let ribbon_filter_state = crate::ribbon_filter::RibbonFilterState::new();
+    let pane_control_state = crate::pane_control::PaneControlState::new();
+    {
+        let mut grid = state.get_active_grid();
+        for i in 0..200u32 {
+            grid.set_cell(i * 5000, 0, Cell::new_number(f64::from(i) + 1.0));
+        }
+        let mut doubled = Cell::new_formula("A1*2".to_string());
+        doubled.value = CellValue::Number(2.0);
+        grid.set_cell(0, 2, doubled);
+    }
+    state.dependents.lock().unwrap().entry((0, 0)).or_default().insert((0, 2));
+
+    // All of column A: only the 200 existing cells are visited
+    let cleared = clear_range_internal(&state, &file_state, 0, 0, 1_048_575, 0).unwrap();
+    assert_eq!(cleared.len(), 200);
+    assert_eq!(state.get_active_grid().cells.len(), 1);
+    assert_eq!(state.undo_stack.lock().unwrap().undo_depth(), 1);
+
+    // One recalculation pass updates the formula that read A1
+    let recalculated = recalc_dependents_of(&state, &user_files_state, &pivot_state, None, &cleared);
+    assert_eq!(recalculated.iter().map(|c| (c.row, c.col)).collect::<Vec<_>>(), vec![(0, 2)]);
+    assert_eq!(state.get_active_grid().get_cell(0, 2).unwrap().value, CellValue::Number(0.0));
+
+    // A single undo restores every cleared cell
+    let undone = undo_internal(
+        &state, &file_state, &user_files_state, &pivot_state, &slicer_state, &ribbon_filter_state, &pane_control_state,
+    );
+    assert!(undone.success);
+    let grid = state.get_active_grid();
+    assert!((0..200u32).all(|i| grid.get_cell(i * 5000, 0).map(|c| &c.value) == Some(&CellValue::Number(f64::from(i) + 1.0))));
+}
+
+#[test]
+fn test_named_range_dependents_follow_definition_changes() {
+    use crate::named_ranges::{delete_named_range_internal, update_named_range_internal};
+
+    let state = create_app_state();
+    let user_files_state = UserFilesState { files: Mutex::new(HashMap::new()) };
+    let pivot_state = crate::pivot::types::PivotState::new();
+    {
+        let mut grid = state.get_active_grid();
+        for (row, (a, b)) in [(1.0, 10.0), (2.0, 20.0), (3.0, 30.0)].into_iter().enumerate() {
+            grid.set_cell(row as u32, 0, Cell::new_number(a));
+            grid.set_cell(row as u32, 1, Cell::new_number(b));
+        }
+    }
+    state.named_ranges.lock().unwrap().insert("SALESDATA".to_string(), named_ranges::NamedRange {
+        name: "SalesData".to_string(),
+        sheet_index: None,
+        refers_to: "=Sheet1!$A$1:$A$3".to_string(),
+        comment: None,
+        folder: None,
+    });
+    enter_tracked_formula(&state, 0, 0, 2, "=SUM(SalesData)");
+    crate::calculation::recalculate_sheet_values(&state, &user_files_state, &pivot_state, 0, None);
+    let c1 = |state: &AppState| state.grids.lock().unwrap()[0].get_cell(0, 2).unwrap().value.clone();
+    assert_eq!(c1(&state), CellValue::Number(6.0));
+
+    // Retargeting the name re-expands and recalculates C1.
+    let result = update_named_range_internal(
+        &state, &user_files_state, &pivot_state,
+        "SalesData".to_string(), None, "=Sheet1!$B$1:$B$3".to_string(), None, None,
+    );
+    assert!(result.success);
+    assert_eq!(c1(&state), CellValue::Number(60.0));
+    assert_eq!(state.get_active_grid().get_cell(0, 2).unwrap().value, CellValue::Number(60.0));
+
+    // Deleting it leaves C1 with an unresolved name.
+    assert!(delete_named_range_internal(&state, &user_files_state, &pivot_state, "SalesData".to_string()).success);
+    assert_eq!(c1(&state), CellValue::Error(CellError::Name));
+}
+
+#[test]
+fn test_table_dependents_follow_resize() {
+    use crate::tables::{resize_table_internal, ResizeTableParams};
+
+    let state = create_app_state();
+    let user_files_state = UserFilesState { files: Mutex::new(HashMap::new()) };
+    let pivot_state = crate::pivot::types::PivotState::new();
+    // Table1 at A1:A4 (header + 3 rows); A5 holds a value just below it.
+    {
+        let mut grid = state.get_active_grid();
+        grid.set_cell(0, 0, Cell::new_text("Sales".to_string()));
+        for (row, value) in [1.0, 2.0, 3.0, 4.0].into_iter().enumerate() {
+            grid.set_cell(row as u32 + 1, 0, Cell::new_number(value));
+        }
+    }
+    let table_id = identity::EntityId::from_bytes(identity::generate_uuid_v7());
+    let columns = vec![tables::TableColumn::new(
+        identity::EntityId::from_bytes(identity::generate_uuid_v7()),
+        "Sales".to_string(),
+    )];
+    state.tables.lock().unwrap().entry(0).or_default().insert(table_id, tables::Table {
+        id: table_id,
+        name: "Table1".to_string(),
+        sheet_index: 0,
+        start_row: 0,
+        start_col: 0,
+        end_row: 3,
+        end_col: 0,
+        columns,
+        style_options: Default::default(),
+        style_name: "TableStyleMedium2".to_string(),
+        auto_filter_id: None,
+        data_type_policy: Default::default(),
+    });
+    state.table_names.lock().unwrap().insert("TABLE1".to_string(), (0, table_id));
+
+    enter_tracked_formula(&state, 0, 0, 3, "=SUM(Table1[Sales])");
+    crate::calculation::recalculate_sheet_values(&state, &user_files_state, &pivot_state, 0, None);
+    let d1 = |state: &AppState| state.grids.lock().unwrap()[0].get_cell(0, 3).unwrap().value.clone();
+    assert_eq!(d1(&state), CellValue::Number(6.0));
+
+    // Growing the table by one row pulls A5 into the column reference.
+    let result = resize_table_internal(&state, &user_files_state, &pivot_state, ResizeTableParams {
+        table_id,
+        start_row: 0,
+        start_col: 0,
+        end_row: 4,
+        end_col: 0,
+    });
+    assert!(result.success);
+    assert_eq!(d1(&state), CellValue::Number(10.0));
+    let expanded = state.get_active_grid().get_cell(0, 3).unwrap().formula_string().unwrap();
+    assert!(expanded.contains("$A$2:$A$5"), "{expanded}");
+}
+
+#[test]
+fn test_delete_rows_ref_errors_shifts_and_report() {
+    use crate::commands::structure::delete_rows_internal;
+    use crate::named_ranges::update_named_range_internal;
+    use crate::persistence::FileState;
+
+    let state = create_app_state();
+    let file_state = FileState::default();
+    let user_files_state = UserFilesState { files: Mutex::new(HashMap::new()) };
+    let pivot_state = crate::pivot::types::PivotState::new();
+    {
+        *state.sheet_names.lock().unwrap() = vec!["Sheet1".to_string(), "Sheet2".to_string()];
+        let mut grids = state.grids.lock().unwrap();
+        grids.resize_with(2, Grid::new);
+        // Sheet1!A1:A10 = 1..10
+        for row in 0..10 {
+            grids[0].set_cell(row, 0, Cell::new_number((row + 1) as f64));
+        }
+        grids[0].set_cell(0, 2, Cell::new_formula("A5".to_string()));
+        grids[0].set_cell(1, 2, Cell::new_formula("A8".to_string()));
+        grids[0].set_cell(2, 2, Cell::new_formula("SUM(A4:A7)".to_string()));
+        grids[0].set_cell(3, 2, Cell::new_formula("SUM(A5:A6)".to_string()));
+        grids[0].set_cell(9, 2, Cell::new_formula("SUM(A1:A10)".to_string()));
+        grids[1].set_cell(0, 0, Cell::new_formula("Sheet1!A5".to_string()));
+        grids[1].set_cell(1, 0, Cell::new_formula("Sheet1!A9".to_string()));
+        // A bare reference on Sheet2 means Sheet2 and must not move.
+        grids[1].set_cell(2, 0, Cell::new_formula("B9".to_string()));
+        grids[1].set_cell(8, 1, Cell::new_number(100.0));
+    }
+    state.named_ranges.lock().unwrap().insert("SALESDATA".to_string(), named_ranges::NamedRange {
+        name: "SalesData".to_string(),
+        sheet_index: None,
+        refers_to: "=Sheet1!$A$7:$A$9".to_string(),
+        comment: None,
+        folder: None,
+    });
+    enter_tracked_formula(&state, 1, 3, 0, "=SUM(SalesData)");
+
+    // Delete Sheet1 rows 5:6.
+    let result = delete_rows_internal(&state, &file_state, &pivot_state, &user_files_state, 4, 2).unwrap();
+
+    let mut reported: Vec<(usize, u32, u32)> =
+        result.ref_errors.iter().map(|e| (e.sheet_index, e.row, e.col)).collect();
+    reported.sort_unstable();
+    assert_eq!(reported, vec![(0, 0, 2), (0, 3, 2), (1, 0, 0)]);
+    assert!(result.ref_errors.iter().all(|e| e.formula.contains("#REF!")));
+
+    let grids = state.grids.lock().unwrap();
+    let cell = |sheet: usize, row: u32, col: u32| {
+        let cell = grids[sheet].get_cell(row, col).unwrap();
+        (cell.formula_string().unwrap(), cell.value.clone())
+    };
+    // Same sheet: inside the span -> #REF!, below it -> shifted, straddling -> clamped.
+    assert_eq!(cell(0, 0, 2).1, CellValue::Error(CellError::Ref));
+    assert_eq!(cell(0, 1, 2), ("A6".to_string(), CellValue::Number(8.0)));
+    assert_eq!(cell(0, 2, 2), ("SUM(A4:A5)".to_string(), CellValue::Number(11.0)));
+    assert_eq!(cell(0, 3, 2).1, CellValue::Error(CellError::Ref));
+    assert_eq!(cell(0, 7, 2), ("SUM(A1:A8)".to_string(), CellValue::Number(44.0)));
+    // Other sheet: references into Sheet1 follow, its own bare references stay.
+    assert_eq!(cell(1, 0, 0).1, CellValue::Error(CellError::Ref));
+    assert_eq!(cell(1, 1, 0), ("Sheet1!A7".to_string(), CellValue::Number(9.0)));
+    assert_eq!(cell(1, 2, 0), ("B9".to_string(), CellValue::Number(100.0)));
+    // Named-range-backed: the name and its expansion both move.
+    assert_eq!(cell(1, 3, 0).1, CellValue::Number(24.0));
+    drop(grids);
+    assert_eq!(state.named_ranges.lock().unwrap()["SALESDATA"].refers_to, "=Sheet1!$A$5:$A$7");
+
+    // The typed formula is still tracked, so retargeting the name reaches it.
+    let updated = update_named_range_internal(
+        &state, &user_files_state, &pivot_state,
+        "SalesData".to_string(), None, "=Sheet1!$A$1:$A$2".to_string(), None, None,
+    );
+    assert!(updated.success);
+    assert_eq!(state.grids.lock().unwrap()[1].get_cell(3, 0).unwrap().value, CellValue::Number(3.0));
+}
+
+#[test]
+fn test_pivot_source_edits_refresh_or_mark_stale() {
+    use crate::pivot::source_watch::{get_stale_objects_internal, refresh_grid_pivot, refresh_stale_auto_pivots, source_cells_changed};
+    use pivot_engine::{AggregationType, PivotDefinition, PivotField, ValueField};
+
+    let state = create_app_state();
+    let pivot_state = crate::pivot::types::PivotState::new();
+    let set = |row: u32, col: u32, cell: Cell| {
+        state.grids.lock().unwrap()[0].set_cell(row, col, cell);
+    };
+    // A1:B4 = Region/Sales: (East, 10), (West, 20), (East, 30)
+    set(0, 0, Cell::new_text("Region".to_string()));
+    set(0, 1, Cell::new_text("Sales".to_string()));
+    for (row, (region, sales)) in [("East", 10.0), ("West", 20.0), ("East", 30.0)].iter().enumerate() {
+        set(row as u32 + 1, 0, Cell::new_text(region.to_string()));
+        set(row as u32 + 1, 1, Cell::new_number(*sales));
+    }
+
+    // Two pivots over the same source: D1 refreshes itself, H1 does not.
+    let make_pivot = |dest_col: u32, auto_refresh: bool| {
+        let pivot_id = identity::EntityId::from_bytes(identity::generate_uuid_v7());
+        let mut definition = PivotDefinition::new(pivot_id, (0, 0), (3, 1));
+        definition.row_fields.push(PivotField::new(0, "Region".to_string()));
+        definition.value_fields.push(ValueField::new(1, "Sum of Sales".to_string(), AggregationType::Sum));
+        definition.destination = (0, dest_col);
+        definition.destination_sheet = Some("Sheet1".to_string());
+        definition.source_sheet = Some("Sheet1".to_string());
+        definition.auto_refresh = auto_refresh;
+        let (cache, _) = {
+            let grids = state.grids.lock().unwrap();
+            crate::pivot::operations::build_cache_from_grid(&grids[0], (0, 0), (3, 1), true).unwrap()
+        };
+        pivot_state.pivot_tables.lock().unwrap().insert(pivot_id, (definition, cache));
+        refresh_grid_pivot(&state, &pivot_state, pivot_id, None).unwrap();
+        pivot_id
+    };
+    let auto_id = make_pivot(3, true);
+    let manual_id = make_pivot(7, false);
+
+    // The total next to `label` in the pivot output starting at `col`.
+    let total = |col: u32, label: &str| -> Option<f64> {
+        let grids = state.grids.lock().unwrap();
+        (0..10).find_map(|row| match grids[0].get_cell(row, col).map(|c| &c.value) {
+            Some(CellValue::Text(t)) if t == label => match grids[0].get_cell(row, col + 1)?.value {
+                CellValue::Number(n) => Some(n),
+                _ => None,
+            },
+            _ => None,
+        })
+    };
+    assert_eq!(total(3, "East"), Some(40.0));
+    assert_eq!(total(7, "East"), Some(40.0));
+
+    let edited = |row: u32, col: u32| crate::api_types::CellData {
+        row,
+        col,
+        display: String::new(),
+        display_color: None,
+        formula: None,
+        style_index: 0,
+        row_span: 1,
+        col_span: 1,
+        sheet_index: None,
+        rich_text: None,
+        accounting_layout: None,
+        error_detail: None,
+    };
+
+    // An edit outside both sources touches nothing.
+    set(5, 1, Cell::new_number(99.0));
+    assert!(source_cells_changed(&state, &pivot_state, None, &[edited(5, 1)]).is_empty());
+    assert!(get_stale_objects_internal(&pivot_state).is_empty());
+
+    // B4: 30 -> 35. The auto-refresh pivot picks it up; the other only goes stale.
+    set(3, 1, Cell::new_number(35.0));
+    assert_eq!(source_cells_changed(&state, &pivot_state, None, &[edited(3, 1)]), vec![auto_id]);
+    assert_eq!(total(3, "East"), Some(45.0));
+    assert_eq!(total(7, "East"), Some(40.0));
+    assert_eq!(get_stale_objects_internal(&pivot_state), vec![manual_id]);
+
+    // Manual calculation mode: both go stale until Calculate Now refreshes the
+    // auto-refresh one.
+    let manual = crate::calculation::CalculationSettingsUpdate {
+        mode: Some(crate::calculation::CalculationMode::Manual),
+        ..Default::default()
+    };
+    crate::calculation::update_calculation_settings_internal(&state, manual).unwrap();
+    set(1, 1, Cell::new_number(15.0));
+    assert!(source_cells_changed(&state, &pivot_state, None, &[edited(1, 1)]).is_empty());
+    assert_eq!(total(3, "East"), Some(45.0));
+    let mut both = vec![auto_id, manual_id];
+    both.sort();
+    assert_eq!(get_stale_objects_internal(&pivot_state), both);
+    assert_eq!(refresh_stale_auto_pivots(&state, &pivot_state, None), vec![auto_id]);
+    assert_eq!(total(3, "East"), Some(50.0));
+    assert_eq!(get_stale_objects_internal(&pivot_state), vec![manual_id]);
+}
+
+#[test]
+fn test_pivot_source_fields_report_types_and_header_issues() {
+    use crate::pivot::commands::get_pivot_source_fields_internal;
+    use pivot_engine::{HeaderRenameReason, PivotDefinition, SourceFieldType};
+
+    let state = create_app_state();
+    let pivot_state = crate::pivot::types::PivotState::new();
+    let date_style = state.style_registry.lock().unwrap().get_or_create(
+        CellStyle::new().with_number_format(NumberFormat::Date { format: "MM/DD/YYYY".to_string() }),
+    );
+    {
+        let mut grids = state.grids.lock().unwrap();
+        let grid = &mut grids[0];
+        // Header row: Sales | (blank) | Sales | Date
+        grid.set_cell(0, 0, Cell::new_text("Sales".to_string()));
+        grid.set_cell(0, 2, Cell::new_text("Sales".to_string()));
+        grid.set_cell(0, 3, Cell::new_text("Date".to_string()));
+        let rows: [(f64, Option<&str>, CellValue); 3] = [
+            (10.0, Some("East"), CellValue::Number(5.0)),
+            (20.0, None, CellValue::Text("n/a".to_string())),
+            (10.0, Some("West"), CellValue::Number(7.0)),
+        ];
+        for (i, (sales, region, mixed)) in rows.into_iter().enumerate() {
+            let row = i as u32 + 1;
+            grid.set_cell(row, 0, Cell::new_number(sales));
+            if let Some(region) = region {
+                grid.set_cell(row, 1, Cell::new_text(region.to_string()));
+            }
+            let mut mixed_cell = Cell::new_number(0.0);
+            mixed_cell.value = mixed;
+            grid.set_cell(row, 2, mixed_cell);
+            let mut date = Cell::new_number(45000.0 + i as f64);
+            date.style_index = date_style;
+            grid.set_cell(row, 3, date);
+        }
+    }
+
+    let pivot_id = identity::EntityId::from_bytes(identity::generate_uuid_v7());
+    let (cache, headers) = crate::pivot::operations::build_cache_from_grid(
+        &state.grids.lock().unwrap()[0], (0, 0), (3, 3), true,
+    ).unwrap();
+    assert_eq!(headers, vec!["Sales", "Column2", "Sales2", "Date"]);
+    let mut definition = PivotDefinition::new(pivot_id, (0, 0), (3, 3));
+    definition.source_sheet = Some("Sheet1".to_string());
+    pivot_state.pivot_tables.lock().unwrap().insert(pivot_id, (definition, cache));
+
+    let info = get_pivot_source_fields_internal(&state, &pivot_state, pivot_id).unwrap();
+    let reasons: Vec<_> = info.header_renames.iter().map(|r| (r.index, r.name.as_str(), r.reason)).collect();
+    assert_eq!(reasons, vec![
+        (1, "Column2", HeaderRenameReason::Blank),
+        (2, "Sales2", HeaderRenameReason::Duplicate),
+    ]);
+
+    let field = |i: usize| &info.fields[i];
+    assert_eq!(field(0).data_type, SourceFieldType::Number);
+    assert_eq!(field(0).samples, vec!["10", "20"]);
+    assert_eq!(field(0).distinct_count, 2);
+    assert_eq!(field(0).renamed_from, None);
+    assert_eq!(field(1).data_type, SourceFieldType::Text);
+    assert_eq!(field(1).blank_count, 1);
+    assert_eq!(field(1).renamed_from.as_deref(), Some(""));
+    assert_eq!(field(2).name, "Sales2");
+    assert_eq!(field(2).data_type, SourceFieldType::Mixed);
+    assert_eq!(field(2).renamed_from.as_deref(), Some("Sales"));
+    assert_eq!(field(3).data_type, SourceFieldType::Date);
+    assert_eq!(field(3).distinct_count, 3);
+
+    assert!(get_pivot_source_fields_internal(&state, &pivot_state, identity::EntityId::ZERO).is_err());
+}
+
+/// Sheet1!A1:B4 = Region/Sales: (East, 10), (West, 20), (`third`, 30), with a
+/// Region x Sum of Sales pivot rendered at D1. Returns its rendered region.
+fn pivot_growth_fixture(
+    on_grow: pivot_engine::GrowPolicy,
+    third: &str,
+) -> (AppState, crate::pivot::types::PivotState, pivot_engine::PivotId, ProtectedRegion) {
+    use crate::pivot::source_watch::refresh_grid_pivot;
+    use pivot_engine::{AggregationType, PivotDefinition, PivotField, ValueField};
+
+    let state = create_app_state();
+    let pivot_state = crate::pivot::types::PivotState::new();
+    {
+        let mut grids = state.grids.lock().unwrap();
+        grids[0].set_cell(0, 0, Cell::new_text("Region".to_string()));
+        grids[0].set_cell(0, 1, Cell::new_text("Sales".to_string()));
+        for (row, (region, sales)) in [("East", 10.0), ("West", 20.0), (third, 30.0)].iter().enumerate() {
+            grids[0].set_cell(row as u32 + 1, 0, Cell::new_text(region.to_string()));
+            grids[0].set_cell(row as u32 + 1, 1, Cell::new_number(*sales));
+        }
+    }
+    let pivot_id = identity::EntityId::from_bytes(identity::generate_uuid_v7());
+    let mut definition = PivotDefinition::new(pivot_id, (0, 0), (3, 1));
+    definition.row_fields.push(PivotField::new(0, "Region".to_string()));
+    definition.value_fields.push(ValueField::new(1, "Sum of Sales".to_string(), AggregationType::Sum));
+    definition.destination = (0, 3);
+    definition.destination_sheet = Some("Sheet1".to_string());
+    definition.source_sheet = Some("Sheet1".to_string());
+    definition.on_grow = on_grow;
+    let (cache, _) = crate::pivot::operations::build_cache_from_grid(
+        &state.grids.lock().unwrap()[0], (0, 0), (3, 1), true,
+    ).unwrap();
+    pivot_state.pivot_tables.lock().unwrap().insert(pivot_id, (definition, cache));
+    refresh_grid_pivot(&state, &pivot_state, pivot_id, None).unwrap();
+    let region = crate::pivot::operations::get_pivot_region(&state, pivot_id).unwrap();
+    (state, pivot_state, pivot_id, region)
+}
+
+#[test]
+fn test_pivot_growth_honors_on_grow_policy() {
+    use crate::pivot::operations::get_pivot_region;
+    use crate::pivot::source_watch::refresh_grid_pivot;
+    use pivot_engine::GrowPolicy;
+
+    let note = || CellValue::Text("note".to_string());
+    let value_at = |state: &AppState, row: u32, col: u32| {
+        state.grids.lock().unwrap()[0].get_cell(row, col).map(|c| c.value.clone())
+    };
+
+    // Overwrite: the note under the pivot is written over.
+    let (state, pivot_state, pivot_id, old) = pivot_growth_fixture(GrowPolicy::Overwrite, "East");
+    let below = old.end_row + 1;
+    state.grids.lock().unwrap()[0].set_cell(below, 3, Cell::new_text("note".to_string()));
+    // A4: East -> North adds a row to the output.
+    state.grids.lock().unwrap()[0].set_cell(3, 0, Cell::new_text("North".to_string()));
+    refresh_grid_pivot(&state, &pivot_state, pivot_id, None).unwrap();
+    assert_ne!(value_at(&state, below, 3), Some(note()));
+    assert_eq!(get_pivot_region(&state, pivot_id).unwrap().end_row, below);
+
+    // Fail: refused with the blocking cell and region named; nothing changes.
+    let (state, pivot_state, pivot_id, old) = pivot_growth_fixture(GrowPolicy::Fail, "East");
+    let below = old.end_row + 1;
+    state.grids.lock().unwrap()[0].set_cell(below, 3, Cell::new_text("note".to_string()));
+    state.protected_regions.lock().unwrap().upsert(ProtectedRegion {
+        id: "chart-1".to_string(),
+        region_type: "chart".to_string(),
+        owner_id: identity::EntityId::ZERO,
+        sheet_index: 0,
+        start_row: below,
+        start_col: 4,
+        end_row: below + 5,
+        end_col: 6,
+    });
+    state.grids.lock().unwrap()[0].set_cell(3, 0, Cell::new_text("North".to_string()));
+    let err = refresh_grid_pivot(&state, &pivot_state, pivot_id, None).unwrap_err();
+    assert!(err.contains(&format!("D{}", below + 1)), "{}", err);
+    assert!(err.contains("chart-1"), "{}", err);
+    assert_eq!(value_at(&state, below, 3), Some(note()));
+    assert_eq!(get_pivot_region(&state, pivot_id).unwrap().end_row, old.end_row);
+
+    // InsertCells: rows are inserted below the old output, so the note and
+    // the formula pointing at it both move down.
+    let (state, pivot_state, pivot_id, old) = pivot_growth_fixture(GrowPolicy::InsertCells, "East");
+    let below = old.end_row + 1;
+    {
+        let mut grids = state.grids.lock().unwrap();
+        grids[0].set_cell(below, 3, Cell::new_text("note".to_string()));
+        grids[0].set_cell(0, 9, Cell::new_formula(format!("D{}", below + 1)));
+        grids[0].set_cell(3, 0, Cell::new_text("North".to_string()));
+    }
+    refresh_grid_pivot(&state, &pivot_state, pivot_id, None).unwrap();
+    assert_eq!(value_at(&state, below + 1, 3), Some(note()));
+    assert_ne!(value_at(&state, below, 3), Some(note()));
+    let formula = state.grids.lock().unwrap()[0].get_cell(0, 9).unwrap().formula_string();
+    assert_eq!(formula, Some(format!("D{}", below + 2)));
+    assert_eq!(get_pivot_region(&state, pivot_id).unwrap().end_row, below);
+}
+
+#[test]
+fn test_pivot_shrink_clears_vacated_cells() {
+    use crate::pivot::operations::get_pivot_region;
+    use crate::pivot::source_watch::refresh_grid_pivot;
+
+    let (state, pivot_state, pivot_id, old) =
+        pivot_growth_fixture(pivot_engine::GrowPolicy::Fail, "North");
+    assert!(state.grids.lock().unwrap()[0].get_cell(old.end_row, old.start_col).is_some());
+
+    // A4: North -> East drops a row from the output.
+    state.grids.lock().unwrap()[0].set_cell(3, 0, Cell::new_text("East".to_string()));
+    refresh_grid_pivot(&state, &pivot_state, pivot_id, None).unwrap();
+
+    // The vacated row loses its values and pivot styling (no cell remains).
+    let grids = state.grids.lock().unwrap();
+    for col in old.start_col..=old.end_col {
+        assert!(grids[0].get_cell(old.end_row, col).is_none(), "col {}", col);
+    }
+    drop(grids);
+    // The same region was resized in place.
+    let region = get_pivot_region(&state, pivot_id).unwrap();
+    assert_eq!(region.id, old.id);
+    assert_eq!((region.start_row, region.end_row), (old.start_row, old.end_row - 1));
+    let pivot_regions = state.protected_regions.lock().unwrap()
+        .iter()
+        .filter(|r| r.owner_id == pivot_id)
+        .count();
+    assert_eq!(pivot_regions, 1);
+}
+
+fn protect_active_sheet(state: &AppState, protected: bool) {
+    let sheet = *state.active_sheet.lock().unwrap();
+    state.sheet_protection.lock().unwrap().insert(
+        sheet,
+        crate::protection::SheetProtection { protected, ..Default::default() },
+    );
+}
+
+#[test]
+fn test_hidden_formula_shows_value_only_while_protected() {
+    use crate::commands::data::{get_cell_impl, get_viewport_cells_impl};
+    use crate::protection::{set_range_protection_internal, SetRangeProtectionParams};
+
+    let state = create_app_state();
+    {
+        let mut grid = state.get_active_grid();
+        grid.set_cell(0, 0, Cell::new_number(10.0));
+        let mut formula_cell = Cell::new_number(30.0);
+        formula_cell.ast = parser::parse("=A1+20").ok().map(Box::new);
+        grid.set_cell(0, 1, formula_cell);
+    }
+    let result = set_range_protection_internal(&state, SetRangeProtectionParams {
+        sheet_index: Some(0),
+        start_row: 0,
+        start_col: 1,
+        end_row: 9,
+        end_col: 1,
+        locked: None,
+        formula_hidden: Some(true),
+    });
+    assert!(result.success);
+
+    // Unprotected: the hidden flag has no effect yet.
+    assert_eq!(get_cell_impl(&state, 0, 1).unwrap().formula.as_deref(), Some("=A1+20"));
+
+    protect_active_sheet(&state, true);
+    let cell = get_cell_impl(&state, 0, 1).unwrap();
+    assert_eq!(cell.display, "30");
+    assert!(cell.formula.is_none());
+    let viewport = get_viewport_cells_impl(&state, 0, 0, 0, 1);
+    let b1 = viewport.iter().find(|c| c.col == 1).unwrap();
+    assert_eq!(b1.display, "30");
+    assert!(b1.formula.is_none());
+
+    protect_active_sheet(&state, false);
+    assert_eq!(get_cell_impl(&state, 0, 1).unwrap().formula.as_deref(), Some("=A1+20"));
+}
+
+/// A1:A{rows} hold values and B1:B{rows} hold `formula_at(row)` (1-based);
+/// the new entry goes to A{rows + 1}.
+fn formula_block(state: &AppState, rows: u32, formula_at: impl Fn(u32) -> String) {
+    let mut grid = state.get_active_grid();
+    for row in 0..rows {
+        grid.set_cell(row, 0, Cell::new_number(row as f64 + 1.0));
+        grid.set_cell(row, 1, Cell::new_formula(formula_at(row + 1)));
+    }
+    grid.set_cell(rows, 0, Cell::new_number(100.0));
+}
+
+#[test]
+fn test_formula_extension_needs_three_consistent_rows() {
+    use crate::commands::formula_extension::suggest_formula_extension_internal;
+
+    let state = create_app_state();
+    formula_block(&state, 3, |r| format!("A{}*2+$D$1", r));
+    let suggestions = suggest_formula_extension_internal(&state, 0, 3, 0);
+    assert_eq!(suggestions.len(), 1);
+    assert_eq!((suggestions[0].row, suggestions[0].col), (3, 1));
+    assert_eq!(suggestions[0].formula, "=A4*2+$D$1");
+    // Another sheet index (stale request after a switch) suggests nothing
+    assert!(suggest_formula_extension_internal(&state, 1, 3, 0).is_empty());
+
+    // Only two formula rows above the entry: no pattern yet
+    let state = create_app_state();
+    formula_block(&state, 2, |r| format!("A{}*2", r));
+    assert!(suggest_formula_extension_internal(&state, 0, 2, 0).is_empty());
+}
+
+#[test]
+fn test_formula_extension_rejects_mixed_formulas_and_tables() {
+    use crate::commands::formula_extension::suggest_formula_extension_internal;
+
+    let state = create_app_state();
+    formula_block(&state, 3, |r| if r == 2 { format!("A{}*3", r) } else { format!("A{}*2", r) });
+    assert!(suggest_formula_extension_internal(&state, 0, 3, 0).is_empty());
+
+    // A consistent column inside a table is left to the table's own mechanism
+    let state = create_app_state();
+    formula_block(&state, 3, |r| format!("A{}*2", r));
+    assert_eq!(suggest_formula_extension_internal(&state, 0, 3, 0).len(), 1);
+    let table_id = identity::EntityId::from_bytes(identity::generate_uuid_v7());
+    state.tables.lock().unwrap().entry(0).or_default().insert(table_id, tables::Table {
+        id: table_id,
+        name: "Table1".to_string(),
+        sheet_index: 0,
+        start_row: 0,
+        start_col: 0,
+        end_row: 3,
+        end_col: 1,
+        columns: Vec::new(),
+        style_options: Default::default(),
+        style_name: "TableStyleMedium2".to_string(),
+        auto_filter_id: None,
+        data_type_policy: Default::default(),
+    });
+    assert!(suggest_formula_extension_internal(&state, 0, 3, 0).is_empty());
+}
+
+#[test]
+fn test_unlocked_range_is_editable_under_protection() {
+    use crate::protection::{can_edit_cell_internal, set_range_protection_internal, SetRangeProtectionParams};
+
+    let state = create_app_state();
+    let result = set_range_protection_internal(&state, SetRangeProtectionParams {
+        sheet_index: None,
+        start_row: 2,
+        start_col: 0,
+        end_row: 10_000,
+        end_col: 3,
+        locked: Some(false),
+        formula_hidden: None,
+    });
+    assert!(result.success);
+    // Stored as one range record, not one entry per cell.
+    assert_eq!(state.range_protection.lock().unwrap()[&0].len(), 1);
+    assert!(state.cell_protection.lock().unwrap().get(&0).is_none_or(|c| c.is_empty()));
+
+    protect_active_sheet(&state, true);
+    assert!(can_edit_cell_internal(&state, 2, 0).can_edit);
+    assert!(can_edit_cell_internal(&state, 10_000, 3).can_edit);
+    assert!(!can_edit_cell_internal(&state, 1, 0).can_edit);
+    assert!(!can_edit_cell_internal(&state, 2, 4).can_edit);
+
+    // A cell-level override inside the range wins.
+    let result = set_range_protection_internal(&state, SetRangeProtectionParams {
+        sheet_index: None,
+        start_row: 5,
+        start_col: 1,
+        end_row: 5,
+        end_col: 1,
+        locked: Some(true),
+        formula_hidden: None,
+    });
+    assert!(result.success);
+    assert!(!can_edit_cell_internal(&state, 5, 1).can_edit);
+    assert!(can_edit_cell_internal(&state, 5, 2).can_edit);
+}
+
+#[test]
+fn test_calculation_settings_partial_update_and_validation() {
+    use crate::calculation::{
+        update_calculation_settings_internal, CalculationMode, CalculationSettingsUpdate,
+        IterationSettings, MultiThreadSettings,
+    };
+
+    let state = create_app_state();
+    let update = CalculationSettingsUpdate {
+        mode: Some(CalculationMode::AutomaticExceptTables),
+        precision_as_displayed: Some(true),
+        ..Default::default()
+    };
+    let settings = update_calculation_settings_internal(&state, update).unwrap();
+    assert_eq!(settings.mode, CalculationMode::AutomaticExceptTables);
+    assert!(settings.mode.is_automatic());
+    assert!(settings.precision_as_displayed);
+    // Omitted fields keep their values.
+    assert!(settings.recalc_before_save);
+    assert_eq!(settings.iterative, IterationSettings::default());
+
+    // An invalid field rejects the whole update.
+    let update = CalculationSettingsUpdate {
+        recalc_before_save: Some(false),
+        iterative: Some(IterationSettings { enabled: true, max_iterations: 0, max_change: 0.001 }),
+        ..Default::default()
+    };
+    assert!(update_calculation_settings_internal(&state, update).is_err());
+    let update = CalculationSettingsUpdate {
+        multi_threaded: Some(MultiThreadSettings { enabled: true, thread_cap: Some(0) }),
+        ..Default::default()
+    };
+    assert!(update_calculation_settings_internal(&state, update).is_err());
+    assert!(state.calculation_settings.lock().unwrap().recalc_before_save);
+
+    assert_eq!(CalculationMode::parse("autoNoTable"), Some(CalculationMode::AutomaticExceptTables));
+    assert_eq!(CalculationMode::parse("Manual"), Some(CalculationMode::Manual));
+    assert!(!CalculationMode::Manual.is_automatic());
+
+    // Thread count: a cap wins, disabled means one thread.
+    let capped = MultiThreadSettings { enabled: true, thread_cap: Some(3) };
+    assert_eq!(capped.effective_threads(), 3);
+    assert_eq!(MultiThreadSettings { enabled: false, ..capped }.effective_threads(), 1);
+    assert!(MultiThreadSettings::default().effective_threads() >= 1);
+}
+
+#[test]
+fn test_precision_as_displayed_rounds_stored_results() {
+    use crate::calculation::{update_calculation_settings_internal, CalculationSettingsUpdate};
+
+    let state = create_app_state();
+    let user_files_state = UserFilesState { files: Mutex::new(HashMap::new()) };
+    let pivot_state = crate::pivot::types::PivotState::new();
+    let two_decimals = state.style_registry.lock().unwrap().get_or_create(CellStyle {
+        number_format: NumberFormat::Custom { format: "0.00".to_string() },
+        ..Default::default()
+    });
+    enter_tracked_formula(&state, 0, 0, 0, "=1/3");
+    enter_tracked_formula(&state, 0, 0, 1, "=A1*3");
+    {
+        let mut grids = state.grids.lock().unwrap();
+        let mut cell = grids[0].get_cell(0, 0).unwrap().clone();
+        cell.style_index = two_decimals;
+        grids[0].set_cell(0, 0, cell);
+    }
+    let value = |state: &AppState, col: u32| state.grids.lock().unwrap()[0].get_cell(0, col).unwrap().value.clone();
+
+    // Full precision: A1 keeps every digit, so B1 is exactly 1.
+    crate::calculation::recalculate_sheet_values(&state, &user_files_state, &pivot_state, 0, None);
+    assert_eq!(value(&state, 1), CellValue::Number(1.0));
+
+    // Precision as displayed: A1 stores the 0.33 it shows and B1 sees it.
+    let update = CalculationSettingsUpdate { precision_as_displayed: Some(true), ..Default::default() };
+    update_calculation_settings_internal(&state, update).unwrap();
+    crate::calculation::recalculate_sheet_values(&state, &user_files_state, &pivot_state, 0, None);
+    assert_eq!(value(&state, 0), CellValue::Number(0.33));
+    assert_eq!(value(&state, 1), CellValue::Number(0.99));
+}
+
+#[test]
+fn test_iteration_settings_resolve_circular_references() {
+    use crate::calculation::{update_calculation_settings_internal, CalculationSettingsUpdate, IterationSettings};
+
+    let state = create_app_state();
+    let user_files_state = UserFilesState { files: Mutex::new(HashMap::new()) };
+    let pivot_state = crate::pivot::types::PivotState::new();
+    // A1 = B1/2 + 1 and B1 = A1 converge on 2.
+    enter_tracked_formula(&state, 0, 0, 0, "=B1/2+1");
+    enter_tracked_formula(&state, 0, 0, 1, "=A1");
+    let value = |state: &AppState| state.grids.lock().unwrap()[0].get_cell(0, 0).unwrap().value.clone();
+    let set_iterative = |enabled: bool| {
+        let iterative = IterationSettings { enabled, max_iterations: 100, max_change: 1e-9 };
+        let update = CalculationSettingsUpdate { iterative: Some(iterative), ..Default::default() };
+        update_calculation_settings_internal(&state, update).unwrap();
+    };
+
+    set_iterative(true);
+    crate::calculation::recalculate_sheet_values(&state, &user_files_state, &pivot_state, 0, None);
+    match value(&state) {
+        CellValue::Number(n) => assert!((n - 2.0).abs() < 1e-6, "{}", n),
+        other => panic!("expected a number, got {:?}", other),
+    }
+
+    set_iterative(false);
+    crate::calculation::recalculate_sheet_values(&state, &user_files_state, &pivot_state, 0, None);
+    assert_eq!(value(&state), CellValue::Error(CellError::Circular));
+}
+
+#[test]
+fn test_background_calculation_progress_cancellation_and_concurrent_reads() {
+    use crate::calc_worker::{CalcProgress, CALCULATION_CANCELLED};
+    use crate::calculation::calculate_now_impl;
+    use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+    use std::time::{Duration, Instant};
+
+    let state = create_app_state();
+    let user_files_state = UserFilesState { files: Mutex::new(HashMap::new()) };
+    let pivot_state = crate::pivot::types::PivotState::new();
+    let pane_control_state = crate::pane_control::PaneControlState::new();
+    let ribbon_filter_state = crate::ribbon_filter::RibbonFilterState::new();
+    // B1:B40 = A1 + row, D1 = C1 * 2. Entered without evaluation and marked
+    // dirty, as manual-mode edits leave them.
+    {
+        let mut grids = state.grids.lock().unwrap();
+        grids[0].set_cell(0, 0, Cell::new_number(10.0));
+        grids[0].set_cell(0, 2, Cell::new_number(1.0));
+    }
+    for row in 0..40 {
+        enter_tracked_formula(&state, 0, row, 1, &format!("=A1+{}", row));
+    }
+    enter_tracked_formula(&state, 0, 0, 3, "=C1*2");
+    state.dirty_cells.lock().unwrap().extend((0..40).map(|row| (0, row, 1)).chain([(0, 0, 3)]));
+    let value = |row: u32, col: u32| state.grids.lock().unwrap()[0].get_cell(row, col).unwrap().value.clone();
+    let calculate = |cancel: &AtomicBool, on_progress: &mut dyn FnMut(CalcProgress)| {
+        calculate_now_impl(&state, &user_files_state, &pivot_state, &pane_control_state, &ribbon_filter_state, None, cancel, on_progress)
+    };
+
+    // Cancelled after five cells: nothing applied, every cell still dirty.
+    let cancel = AtomicBool::new(false);
+    let mut last_seen = 0;
+    let result = calculate(&cancel, &mut |progress| {
+        last_seen = progress.evaluated;
+        if progress.evaluated == 5 {
+            cancel.store(true, Ordering::SeqCst);
+        }
+    });
+    assert_eq!(result.unwrap_err(), CALCULATION_CANCELLED);
+    assert_eq!(last_seen, 5);
+    assert_eq!(value(7, 1), CellValue::Empty);
+    assert_eq!(state.dirty_cells.lock().unwrap().len(), 41);
+
+    // A full pass reports every cell and clears the dirty set.
+    let mut progress = Vec::new();
+    let updated = calculate(&AtomicBool::new(false), &mut |p| progress.push(p)).unwrap();
+    assert_eq!(progress.len(), 41);
+    assert_eq!(progress.last(), Some(&CalcProgress { evaluated: 41, total: 41 }));
+    assert_eq!(updated.len(), 41);
+    assert_eq!(value(7, 1), CellValue::Number(17.0));
+    assert_eq!(value(0, 3), CellValue::Number(2.0));
+    assert!(state.dirty_cells.lock().unwrap().is_empty());
+
+    // A slow pass on another thread: reads are not blocked while it
+    // evaluates, and an edit made meanwhile invalidates its dependents.
+    state.grids.lock().unwrap()[0].set_cell(0, 0, Cell::new_number(20.0));
+    let evaluated = AtomicUsize::new(0);
+    let pass_started = Instant::now();
+    let (read_wait, pass_time) = std::thread::scope(|scope| {
+        let pass = scope.spawn(|| {
+            let result = calculate(&AtomicBool::new(false), &mut |p| {
+                evaluated.store(p.evaluated, Ordering::SeqCst);
+                std::thread::sleep(Duration::from_millis(10));
+            });
+            (result, pass_started.elapsed())
+        });
+        while evaluated.load(Ordering::SeqCst) == 0 {
+            std::thread::yield_now();
+        }
+        let read_started = Instant::now();
+        let c1 = state.grids.lock().unwrap()[0].get_cell(0, 2).map(|cell| cell.value.clone());
+        let read_wait = read_started.elapsed();
+        assert_eq!(c1, Some(CellValue::Number(1.0)));
+        state.grids.lock().unwrap()[0].set_cell(0, 2, Cell::new_number(5.0));
+        let (result, pass_time) = pass.join().unwrap();
+        assert_eq!(result.unwrap().len(), 40);
+        (read_wait, pass_time)
+    });
+    assert!(pass_time >= Duration::from_millis(400), "{:?}", pass_time);
+    assert!(read_wait < Duration::from_millis(100), "{:?}", read_wait);
+    assert_eq!(value(7, 1), CellValue::Number(27.0));
+    // D1 read the old C1: its result was dropped and it is left dirty.
+    assert_eq!(value(0, 3), CellValue::Number(2.0));
+    assert_eq!(state.dirty_cells.lock().unwrap().iter().copied().collect::<Vec<_>>(), vec![(0, 0, 3)]);
+}
+
+#[test]
+fn test_calculation_settings_persistence_roundtrip() {
+    use crate::calculation::{CalculationMode, CalculationSettings, IterationSettings, MultiThreadSettings};
+
+    let settings = CalculationSettings {
+        mode: CalculationMode::Manual,
+        recalc_before_save: false,
+        iterative: IterationSettings { enabled: true, max_iterations: 250, max_change: 0.0001 },
+        multi_threaded: MultiThreadSettings { enabled: true, thread_cap: Some(4) },
+        precision_as_displayed: true,
+    };
+    let saved = settings.to_saved();
+    assert_eq!(saved.calc_mode, "manual");
+    assert!(!saved.calc_on_save);
+    assert!(!saved.full_precision);
+    assert_eq!(saved.concurrent_manual_count, Some(4));
+    assert_eq!(CalculationSettings::from_saved(&saved), settings);
+
+    // Defaults map onto the calcPr defaults, so nothing extra is written.
+    assert_eq!(CalculationSettings::default().to_saved(), persistence::SavedCalcProperties::default());
+
+    // Out-of-range values in a file fall back to the defaults.
+    let mut corrupt = saved.clone();
+    corrupt.calc_mode = "sometimes".to_string();
+    corrupt.iterate_count = 0;
+    let loaded = CalculationSettings::from_saved(&corrupt);
+    assert_eq!(loaded.mode, CalculationMode::Automatic);
+    assert_eq!(loaded.iterative, IterationSettings::default());
+    assert_eq!(loaded.multi_threaded, settings.multi_threaded);
+}
+
+/// Employee roster: names, departments, date-formatted hire dates, salaries
+/// with one blank, and a bonus column mixing numbers, text and an error.
+struct EmployeeFixture;
+
+impl EmployeeFixture {
+    const HEADERS: [&'static str; 5] = ["Name", "Department", "Hired", "Salary", "Bonus"];
+
+    /// Writes the block with its header at (row, col) of the active sheet.
+    fn populate(state: &AppState, row: u32, col: u32) {
+        let date_style = state.style_registry.lock().unwrap().get_or_create(CellStyle {
+            number_format: NumberFormat::Date { format: "yyyy-mm-dd".to_string() },
+            ..Default::default()
+        });
+        let rows: [(&str, &str, f64, Option<f64>, CellValue); 6] = [
+            ("Ann", "Sales", 44927.0, Some(52000.0), CellValue::Number(1500.0)),
+            ("Bo", "Sales", 45108.0, Some(48000.0), CellValue::Text("n/a".to_string())),
+            ("Cy", "IT", 43831.0, Some(61000.0), CellValue::Number(2500.0)),
+            ("Di", "it", 44197.0, None, CellValue::Error(CellError::Div0)),
+            ("Ed", "HR", 44562.0, Some(45000.0), CellValue::Number(0.0)),
+            ("Flo", "Sales", 45292.0, Some(50000.0), CellValue::Number(1000.0)),
+        ];
+        let mut grid = state.get_active_grid();
+        for (i, header) in Self::HEADERS.iter().enumerate() {
+            grid.set_cell(row, col + i as u32, Cell::new_text(header.to_string()));
+        }
+        for (r, (name, department, hired, salary, bonus)) in rows.into_iter().enumerate() {
+            let r = row + 1 + r as u32;
+            grid.set_cell(r, col, Cell::new_text(name.to_string()));
+            grid.set_cell(r, col + 1, Cell::new_text(department.to_string()));
+            let mut hired = Cell::new_number(hired);
+            hired.style_index = date_style;
+            grid.set_cell(r, col + 2, hired);
+            if let Some(salary) = salary {
+                grid.set_cell(r, col + 3, Cell::new_number(salary));
+            }
+            let mut bonus_cell = Cell::new_number(0.0);
+            bonus_cell.value = bonus;
+            grid.set_cell(r, col + 4, bonus_cell);
+        }
+    }
+}
+
+#[test]
+fn test_analyze_range_profiles_employee_columns() {
+    use crate::commands::analysis::{analyze_range_internal, AnalyzeRangeParams, ColumnValueType};
+
+    let state = create_app_state();
+    EmployeeFixture::populate(&state, 2, 1);
+    // A full-column-style request: trimmed to the block's last data row.
+    let analysis = analyze_range_internal(&state, AnalyzeRangeParams {
+        sheet_index: None,
+        start_row: 2,
+        start_col: 1,
+        end_row: 1_048_575,
+        end_col: 5,
+    })
+    .unwrap();
+
+    assert!(analysis.has_header);
+    assert_eq!((analysis.end_row, analysis.data_rows), (8, 6));
+    let headers: Vec<_> = analysis.columns.iter().map(|c| c.header.clone().unwrap()).collect();
+    assert_eq!(headers, EmployeeFixture::HEADERS);
+
+    let [name, department, hired, salary, bonus] = &analysis.columns[..] else {
+        panic!("expected 5 columns, got {}", analysis.columns.len());
+    };
+    assert_eq!(name.value_type, ColumnValueType::Text);
+    assert_eq!((name.count, name.distinct_count, name.min), (6, 6, None));
+    // Text is compared case-insensitively: Sales, IT/it, HR.
+    assert_eq!((department.value_type, department.distinct_count), (ColumnValueType::Text, 3));
+
+    assert_eq!(hired.value_type, ColumnValueType::Date);
+    assert_eq!((hired.min, hired.max, hired.sum), (Some(43831.0), Some(45292.0), None));
+
+    assert_eq!(salary.value_type, ColumnValueType::Numeric);
+    assert_eq!((salary.count, salary.blanks, salary.errors), (5, 1, 0));
+    assert_eq!((salary.min, salary.max, salary.sum), (Some(45000.0), Some(61000.0), Some(256000.0)));
+    assert_eq!(salary.average, Some(51200.0));
+
+    assert_eq!(bonus.value_type, ColumnValueType::Mixed);
+    assert_eq!((bonus.count, bonus.blanks, bonus.errors), (6, 0, 1));
+    assert_eq!(bonus.sum, None);
+    assert_eq!(bonus.distinct_count, 6);
+}
+
+#[test]
+fn test_analyze_range_header_detection() {
+    use crate::commands::analysis::{analyze_range_internal, AnalyzeRangeParams, ColumnValueType};
+
+    let state = create_app_state();
+    EmployeeFixture::populate(&state, 0, 0);
+    let analyze = |start_row: u32, start_col: u32, end_col: u32| {
+        analyze_range_internal(&state, AnalyzeRangeParams {
+            sheet_index: Some(0),
+            start_row,
+            start_col,
+            end_row: 6,
+            end_col,
+        })
+        .unwrap()
+    };
+
+    // Starting below the header: the first row is data and counts as such.
+    let body = analyze(1, 2, 3);
+    assert!(!body.has_header);
+    assert_eq!(body.data_rows, 6);
+    assert_eq!(body.columns[1].count, 5);
+    assert_eq!(body.columns[0].header, None);
+
+    // All-text columns: a header whose text never recurs below it.
+    assert!(analyze(0, 0, 1).has_header);
+    // "Sales" reappears under itself, so it is data, not a header.
+    let departments = analyze(1, 1, 1);
+    assert!(!departments.has_header);
+    assert_eq!(departments.columns[0].value_type, ColumnValueType::Text);
+
+    // Nothing stored in the range.
+    let empty = analyze(1, 10, 12);
+    assert_eq!((empty.has_header, empty.data_rows), (false, 0));
+    assert!(empty.columns.iter().all(|c| c.value_type == ColumnValueType::Empty));
+}
+
+/// Month labels down column A, "Sales"/"Cost" headers across row 0.
+fn series_block(state: &AppState) {
+    let mut grid = state.get_active_grid();
+    for (col, header) in ["Month", "Sales", "Cost"].iter().enumerate() {
+        grid.set_cell(0, col as u32, Cell::new_text(header.to_string()));
+    }
+    for (i, (month, sales, cost)) in [("Jan", 10.0, 4.0), ("Feb", 12.0, 5.0), ("Mar", 9.0, 3.0)].iter().enumerate() {
+        let row = i as u32 + 1;
+        grid.set_cell(row, 0, Cell::new_text(month.to_string()));
+        grid.set_cell(row, 1, Cell::new_number(*sales));
+        grid.set_cell(row, 2, Cell::new_number(*cost));
+    }
+}
+
+fn series_params(
+    end_row: u32,
+    end_col: u32,
+    orientation: crate::commands::analysis::SeriesOrientation,
+) -> crate::commands::analysis::ExtractSeriesParams {
+    crate::commands::analysis::ExtractSeriesParams {
+        sheet_index: None,
+        start_row: 0,
+        start_col: 0,
+        end_row,
+        end_col,
+        orientation,
+        hidden_rows: Vec::new(),
+        hidden_cols: Vec::new(),
+    }
+}
+
+#[test]
+fn test_extract_series_header_block_both_orientations() {
+    use crate::commands::analysis::{extract_series_data_internal, SeriesOrientation};
+
+    let state = create_app_state();
+    series_block(&state);
+
+    // Taller than wide: Auto puts the series in columns. A full-column
+    // selection is trimmed to the data.
+    let by_cols = extract_series_data_internal(&state, series_params(1_048_575, 2, SeriesOrientation::Auto)).unwrap();
+    assert_eq!(by_cols.orientation, SeriesOrientation::ByColumns);
+    assert!(by_cols.has_category_labels);
+    assert_eq!(by_cols.categories, vec!["Jan", "Feb", "Mar"]);
+    let series: Vec<_> = by_cols.series.iter().map(|s| (s.name.as_str(), s.values.clone())).collect();
+    assert_eq!(series, vec![
+        ("Sales", vec![Some(10.0), Some(12.0), Some(9.0)]),
+        ("Cost", vec![Some(4.0), Some(5.0), Some(3.0)]),
+    ]);
+
+    let by_rows = extract_series_data_internal(&state, series_params(3, 2, SeriesOrientation::ByRows)).unwrap();
+    assert_eq!(by_rows.orientation, SeriesOrientation::ByRows);
+    assert_eq!(by_rows.categories, vec!["Sales", "Cost"]);
+    let series: Vec<_> = by_rows.series.iter().map(|s| (s.name.as_str(), s.values.clone())).collect();
+    assert_eq!(series, vec![
+        ("Jan", vec![Some(10.0), Some(4.0)]),
+        ("Feb", vec![Some(12.0), Some(5.0)]),
+        ("Mar", vec![Some(9.0), Some(3.0)]),
+    ]);
+
+    // Two rows, three columns: Auto switches to one series per row.
+    let wide = extract_series_data_internal(&state, series_params(1, 2, SeriesOrientation::Auto)).unwrap();
+    assert_eq!(wide.orientation, SeriesOrientation::ByRows);
+    assert_eq!(wide.series.len(), 1);
+    assert_eq!(wide.series[0].name, "Jan");
+}
+
+#[test]
+fn test_extract_series_skips_hidden_rows_and_columns() {
+    use crate::commands::analysis::{extract_series_data_internal, SeriesOrientation};
+
+    let state = create_app_state();
+    series_block(&state);
+    // Feb is filtered out; Cost is hidden by hand in the frontend.
+    state.advanced_filter_hidden_rows.lock().unwrap().insert(0, vec![2]);
+    let mut params = series_params(3, 2, SeriesOrientation::ByColumns);
+    params.hidden_cols = vec![2];
+
+    let extraction = extract_series_data_internal(&state, params).unwrap();
+    assert_eq!(extraction.categories, vec!["Jan", "Mar"]);
+    assert_eq!(extraction.series.len(), 1);
+    assert_eq!(extraction.series[0].name, "Sales");
+    assert_eq!(extraction.series[0].values, vec![Some(10.0), Some(9.0)]);
+}
+
+/// A ragged block: short rows, text and an error inside the data, and a
+/// first column that holds a number (so it is a series, not labels).
+#[test]
+fn test_extract_series_ragged_selection_with_text() {
+    use crate::commands::analysis::{extract_series_data_internal, SeriesOrientation};
+
+    let state = create_app_state();
+    {
+        let mut grid = state.get_active_grid();
+        grid.set_cell(0, 0, Cell::new_text("North".to_string()));
+        grid.set_cell(0, 1, Cell::new_text("South".to_string()));
+        grid.set_cell(1, 0, Cell::new_number(5.0));
+        grid.set_cell(1, 1, Cell::new_text("n/a".to_string()));
+        grid.set_cell(2, 0, Cell::new_number(7.0));
+        let mut error = Cell::new_number(0.0);
+        error.value = CellValue::Error(CellError::Div0);
+        grid.set_cell(2, 1, error);
+        grid.set_cell(3, 1, Cell::new_number(8.0));
+        grid.set_cell(4, 0, Cell::new_boolean(true));
+    }
+
+    let extraction = extract_series_data_internal(&state, series_params(4, 1, SeriesOrientation::Auto)).unwrap();
+    assert_eq!(extraction.orientation, SeriesOrientation::ByColumns);
+    assert!(!extraction.has_category_labels);
+    assert_eq!(extraction.categories, vec!["1", "2", "3", "4"]);
+    let [north, south] = &extraction.series[..] else {
+        panic!("expected 2 series, got {}", extraction.series.len());
+    };
+    assert_eq!(north.name, "North");
+    assert_eq!(north.values, vec![Some(5.0), Some(7.0), None, None]);
+    assert!(north.errors.is_empty());
+    assert_eq!(south.name, "South");
+    assert_eq!(south.values, vec![None, None, Some(8.0), None]);
+    assert_eq!(south.errors, vec![1]);
+
+    // Without the header row every line is data and series are numbered.
+    let mut params = series_params(4, 1, SeriesOrientation::ByColumns);
+    params.start_row = 1;
+    let body = extract_series_data_internal(&state, params).unwrap();
+    assert_eq!(body.series[0].name, "Series 1");
+    assert_eq!(body.series[0].values, vec![Some(5.0), Some(7.0), None, None]);
+}
+
+// ============================================================================
+// AUTOFILTER UNIQUE VALUES
+// ============================================================================
+
+/// Header row 0, then (region, product) pairs in rows 1..=6 of A:B.
+fn filter_block(state: &AppState) {
+    let mut grid = state.get_active_grid();
+    grid.set_cell(0, 0, Cell::new_text("Region".to_string()));
+    grid.set_cell(0, 1, Cell::new_text("Product".to_string()));
+    let rows = [("North", "Apple"), ("North", "Pear"), ("South", "Apple"),
+                ("South", "Apple"), ("East", "Pineapple"), ("North", "Apple")];
+    for (i, (region, product)) in rows.iter().enumerate() {
+        grid.set_cell(i as u32 + 1, 0, Cell::new_text(region.to_string()));
+        grid.set_cell(i as u32 + 1, 1, Cell::new_text(product.to_string()));
+    }
+    drop(grid);
+    state.auto_filters.lock().unwrap().insert(0, autofilter::AutoFilter::new(0, 0, 6, 1));
+}
+
+fn counts(result: &autofilter::UniqueValuesResult) -> Vec<(&str, u32)> {
+    result.values.iter().map(|v| (v.value.as_str(), v.count)).collect()
+}
+
+#[test]
+fn test_filter_unique_values_cascade_from_other_columns() {
+    use autofilter::get_filter_unique_values_internal as unique_values;
+
+    let state = create_app_state();
+    filter_block(&state);
+    let all = unique_values(&state, 1, None);
+    assert!(all.success);
+    assert_eq!(counts(&all), vec![("Apple", 4), ("Pear", 1), ("Pineapple", 1)]);
+
+    // Region = North hides rows 3, 4 and 5
+    {
+        let mut filters = state.auto_filters.lock().unwrap();
+        let af = filters.get_mut(&0).unwrap();
+        af.column_filters.insert(0, autofilter::ColumnFilter {
+            column_index: 0,
+            criteria: autofilter::FilterCriteria {
+                values: vec!["North".to_string()],
+                ..Default::default()
+            },
+        });
+        af.hidden_rows.extend([3, 4, 5]);
+    }
+    let filtered = unique_values(&state, 1, None);
+    assert_eq!(counts(&filtered), vec![("Apple", 2), ("Pear", 1)]);
+
+    // The column's own filter does not shrink its own list
+    let regions = unique_values(&state, 0, None);
+    assert_eq!(counts(&regions), vec![("East", 1), ("North", 3), ("South", 2)]);
+
+    // Advanced Filter hidden rows are excluded as well
+    state.advanced_filter_hidden_rows.lock().unwrap().insert(0, vec![2]);
+    assert_eq!(counts(&unique_values(&state, 1, None)), vec![("Apple", 2)]);
+}
+
+#[test]
+fn test_subtotal_cells_follow_filtered_and_grouped_rows() {
+    let state = create_app_state();
+    {
+        let mut grid = state.get_active_grid();
+        for (row, value) in [10.0, 20.0, 30.0, 40.0].into_iter().enumerate() {
+            grid.set_cell(row as u32, 0, Cell::new_number(value));
+        }
+        grid.set_cell(4, 0, Cell::new_formula("SUBTOTAL(109,A1:A4)".to_string()));
+        grid.set_cell(5, 0, Cell::new_formula("SUBTOTAL(9,A1:A5)".to_string()));
+    }
+    let values = |state: &AppState| {
+        let grid = state.get_active_grid();
+        (grid.get_cell(4, 0).unwrap().value.clone(), grid.get_cell(5, 0).unwrap().value.clone())
+    };
+
+    // The grand total skips the nested SUBTOTAL in A5
+    autofilter::refresh_filtered_totals(&state, 0);
+    assert_eq!(values(&state), (CellValue::Number(100.0), CellValue::Number(100.0)));
+
+    // Filtered rows drop out of 109 but not of 9
+    state.advanced_filter_hidden_rows.lock().unwrap().insert(0, vec![1]);
+    autofilter::refresh_filtered_totals(&state, 0);
+    assert_eq!(values(&state), (CellValue::Number(80.0), CellValue::Number(100.0)));
+
+    // A collapsed row group (summary row A5) hides its detail rows the same way
+    state.advanced_filter_hidden_rows.lock().unwrap().clear();
+    {
+        let mut outline = grouping::SheetOutline::new();
+        let mut group = grouping::RowGroup::new(2, 4, 1);
+        group.collapsed = true;
+        outline.row_groups.push(group);
+        state.outlines.lock().unwrap().insert(0, outline);
+    }
+    autofilter::refresh_filtered_totals(&state, 0);
+    assert_eq!(values(&state), (CellValue::Number(30.0), CellValue::Number(100.0)));
+}
+
+#[test]
+fn test_filter_unique_values_search_and_truncation() {
+    use autofilter::{get_filter_unique_values_internal as unique_values, MAX_UNIQUE_VALUES};
+
+    let state = create_app_state();
+    filter_block(&state);
+    let found = unique_values(&state, 1, Some("APPLE"));
+    assert_eq!(counts(&found), vec![("Apple", 4), ("Pineapple", 1)]);
+    assert!(unique_values(&state, 1, Some("kiwi")).values.is_empty());
+    assert_eq!(unique_values(&state, 1, None).more_count, 0);
+
+    let extra = 25;
+    let last_row = (MAX_UNIQUE_VALUES + extra) as u32;
+    {
+        let mut grid = state.get_active_grid();
+        for row in 1..=last_row {
+            grid.set_cell(row, 0, Cell::new_text(format!("id-{:06}", row)));
+        }
+    }
+    state.auto_filters.lock().unwrap().insert(0, autofilter::AutoFilter::new(0, 0, last_row, 1));
+    let bounded = unique_values(&state, 0, None);
+    assert_eq!(bounded.values.len(), MAX_UNIQUE_VALUES);
+    assert_eq!(bounded.more_count, extra as u32);
+    assert_eq!(bounded.values[0].value, "id-000001");
+    // A search that matches fewer values is not truncated
+    let narrowed = unique_values(&state, 0, Some("id-0100"));
+    assert_eq!((narrowed.values.len(), narrowed.more_count), (extra + 1, 0));
+}
+
+#[test]
+fn test_table_totals_follow_filtered_rows() {
+    use autofilter::{clear_auto_filter_criteria_internal, set_column_filter_values_internal};
+    use tables::{set_totals_row_function_internal, SetTotalsRowFunctionParams, TotalsRowFunction};
+
+    let state = create_app_state();
+    filter_block(&state);
+    {
+        let mut grid = state.get_active_grid();
+        grid.set_cell(0, 1, Cell::new_text("Units".to_string()));
+        for (i, units) in [10.0, 20.0, 30.0, 40.0, 50.0, 60.0].iter().enumerate() {
+            grid.set_cell(i as u32 + 1, 1, Cell::new_number(*units));
+        }
+    }
+    let table_id = identity::EntityId::from_bytes(identity::generate_uuid_v7());
+    let columns = ["Region", "Units"]
+        .iter()
+        .map(|name| tables::TableColumn::new(
+            identity::EntityId::from_bytes(identity::generate_uuid_v7()),
+            name.to_string(),
+        ))
+        .collect();
+    state.tables.lock().unwrap().entry(0).or_default().insert(table_id, tables::Table {
+        id: table_id,
+        name: "Sales".to_string(),
+        sheet_index: 0,
+        start_row: 0,
+        start_col: 0,
+        end_row: 7,
+        end_col: 1,
+        columns,
+        style_options: tables::TableStyleOptions { total_row: true, ..Default::default() },
+        style_name: "TableStyleMedium2".to_string(),
+        auto_filter_id: Some(0),
+        data_type_policy: Default::default(),
+    });
+    state.table_names.lock().unwrap().insert("SALES".to_string(), (0, table_id));
+
+    let set_totals = |column: &str, function| {
+        let result = set_totals_row_function_internal(&state, SetTotalsRowFunctionParams {
+            table_id,
+            column_name: column.to_string(),
+            function,
+            custom_formula: None,
+        });
+        assert!(result.success);
+    };
+    set_totals("Region", TotalsRowFunction::Count);
+    set_totals("Units", TotalsRowFunction::Sum);
+    let totals = |state: &AppState| {
+        let grid = state.get_active_grid();
+        (grid.get_cell(7, 0).unwrap().value.clone(), grid.get_cell(7, 1).unwrap().value.clone())
+    };
+    assert_eq!(totals(&state), (CellValue::Number(6.0), CellValue::Number(210.0)));
+
+    // Region = North leaves rows 1, 2 and 6 visible
+    let filtered = set_column_filter_values_internal(&state, 0, vec!["North".to_string()], false);
+    assert_eq!(filtered.hidden_rows.len(), 3);
+    assert_eq!(totals(&state), (CellValue::Number(3.0), CellValue::Number(90.0)));
+
+    clear_auto_filter_criteria_internal(&state);
+    assert_eq!(totals(&state), (CellValue::Number(6.0), CellValue::Number(210.0)));
+}
+
+#[test]
+fn test_table_column_data_types() {
+    use data_validation::validate_pending_value_internal;
+    use tables::{
+        set_table_column_data_type_internal, set_table_data_type_policy_internal, ColumnDataType,
+        DataTypePolicy, SetColumnDataTypeParams,
+    };
+
+    let state = create_app_state();
+    {
+        let date_style = state.style_registry.lock().unwrap().get_or_create(
+            CellStyle::new().with_number_format(NumberFormat::Date { format: "YYYY-MM-DD".to_string() }),
+        );
+        let mut grid = state.get_active_grid();
+        grid.set_cell(0, 0, Cell::new_text("Qty".to_string()));
+        grid.set_cell(0, 1, Cell::new_text("Shipped".to_string()));
+        grid.set_cell(1, 0, Cell::new_number(5.0));
+        grid.set_cell(2, 0, Cell::new_text("ten".to_string()));
+        let mut shipped = Cell::new_number(45306.0);
+        shipped.style_index = date_style;
+        grid.set_cell(3, 0, shipped);
+        grid.set_cell(1, 1, Cell::new_number(45306.0));
+    }
+    let table_id = identity::EntityId::from_bytes(identity::generate_uuid_v7());
+    let columns = ["Qty", "Shipped"]
+        .iter()
+        .map(|name| tables::TableColumn::new(
+            identity::EntityId::from_bytes(identity::generate_uuid_v7()),
+            name.to_string(),
+        ))
+        .collect();
+    state.tables.lock().unwrap().entry(0).or_default().insert(table_id, tables::Table {
+        id: table_id,
+        name: "Orders".to_string(),
+        sheet_index: 0,
+        start_row: 0,
+        start_col: 0,
+        end_row: 3,
+        end_col: 1,
+        columns,
+        style_options: Default::default(),
+        style_name: "TableStyleMedium2".to_string(),
+        auto_filter_id: None,
+        data_type_policy: Default::default(),
+    });
+    let set_type = |column: &str, data_type| {
+        set_table_column_data_type_internal(&state, SetColumnDataTypeParams {
+            table_id,
+            column_name: column.to_string(),
+            data_type,
+        })
+    };
+
+    // Conformance scan: the text and the date in Qty do not fit Number
+    let result = set_type("Qty", Some(ColumnDataType::Number));
+    assert!(result.success);
+    let table = result.table.unwrap();
+    assert_eq!(table.columns[0].data_type, Some(ColumnDataType::Number));
+    let offending: Vec<(u32, u32, String)> = result
+        .nonconforming_cells
+        .unwrap()
+        .into_iter()
+        .map(|cell| (cell.row, cell.col, cell.display))
+        .collect();
+    assert_eq!(offending, vec![(2, 0, "ten".to_string()), (3, 0, "2024-01-15".to_string())]);
+    // Shipped holds a plain number, not a formatted date
+    let result = set_type("Shipped", Some(ColumnDataType::Date));
+    assert_eq!(result.nonconforming_cells.map(|cells| cells.len()), Some(1));
+    let result = set_type("Shipped", None);
+    assert!(result.nonconforming_cells.is_none());
+
+    // Reject policy: the pending-value check refuses text in a Number column
+    let rejected = validate_pending_value_internal(&state, 1, 0, "lots".to_string());
+    assert!(!rejected.is_valid);
+    assert_eq!(rejected.error_alert.unwrap().message, "This table column only accepts numbers.");
+    assert!(validate_pending_value_internal(&state, 1, 0, "12".to_string()).is_valid);
+    // Columns without a type accept anything
+    assert!(validate_pending_value_internal(&state, 1, 1, "lots".to_string()).is_valid);
+
+    // Coerce policy: the cleaned-up value is handed back for committing
+    set_table_data_type_policy_internal(&state, table_id, DataTypePolicy::Coerce);
+    let coerced = validate_pending_value_internal(&state, 1, 0, "12 pcs".to_string());
+    assert!(coerced.is_valid);
+    assert_eq!(coerced.coerced_value.as_deref(), Some("12"));
+    assert!(!validate_pending_value_internal(&state, 1, 0, "lots".to_string()).is_valid);
+
+    // The row just below the table auto-expands it and inherits the types;
+    // further down and beside the table nothing is enforced
+    let inherited = validate_pending_value_internal(&state, 4, 0, "7 boxes".to_string());
+    assert_eq!(inherited.coerced_value.as_deref(), Some("7"));
+    set_table_data_type_policy_internal(&state, table_id, DataTypePolicy::Reject);
+    assert!(!validate_pending_value_internal(&state, 4, 0, "lots".to_string()).is_valid);
+    assert!(validate_pending_value_internal(&state, 5, 0, "lots".to_string()).is_valid);
+    assert!(validate_pending_value_internal(&state, 4, 2, "lots".to_string()).is_valid);
+}
+
+// ============================================================================
+// SHEET VIEW OPTIONS
+// ============================================================================
+
+#[test]
+fn test_sheet_view_options_are_per_sheet() {
+    use crate::api_types::SheetViewOptions;
+    use crate::sheets::{get_sheet_view_options_internal, set_sheet_view_options_internal};
+
+    let state = create_app_state();
+    assert_eq!(get_sheet_view_options_internal(&state), SheetViewOptions::default());
+
+    let first = SheetViewOptions { zoom: 1.5, show_headings: false, ..Default::default() };
+    set_sheet_view_options_internal(&state, first.clone());
+
+    *state.active_sheet.lock().unwrap() = 1;
+    assert_eq!(get_sheet_view_options_internal(&state), SheetViewOptions::default());
+    let stored = set_sheet_view_options_internal(
+        &state,
+        SheetViewOptions { zoom: 9.0, right_to_left: true, show_gridlines: false, ..Default::default() },
+    );
+    assert_eq!(stored.zoom, 5.0);
+
+    *state.active_sheet.lock().unwrap() = 0;
+    assert_eq!(get_sheet_view_options_internal(&state), first);
+    let views = state.sheet_view_options.lock().unwrap();
+    assert_eq!(views.len(), 2);
+    assert!(views[1].right_to_left && !views[1].show_gridlines);
+}
+
+// ============================================================================
+// FIND NEXT CELL
+// ============================================================================
+
+fn find_next(
+    state: &AppState,
+    from: (u32, u32),
+    direction: commands::nav::NavDirection,
+    predicate: commands::nav::NavPredicate,
+    wrap: bool,
+) -> Option<(u32, u32)> {
+    use commands::nav::{find_next_cell_internal, CellCoord, FindNextCellParams};
+
+    let params = FindNextCellParams { from: CellCoord { row: from.0, col: from.1 }, direction, predicate, wrap };
+    find_next_cell_internal(state, params).map(|cell| (cell.row, cell.col))
+}
+
+#[test]
+fn test_find_next_unlocked_cell() {
+    use commands::nav::{NavDirection::*, NavPredicate::Unlocked};
+    use protection::{AllowEditRange, CellProtection, RangeProtection, SheetProtection};
+
+    let state = create_app_state();
+    state.sheet_protection.lock().unwrap().insert(0, SheetProtection { protected: true, ..Default::default() });
+    // Nothing unlocked yet: every cell is locked
+    assert_eq!(find_next(&state, (0, 0), Right, Unlocked, true), None);
+
+    {
+        let mut cells = state.cell_protection.lock().unwrap();
+        let sheet = cells.entry(0).or_default();
+        sheet.insert((1, 1), CellProtection::unlocked());
+        sheet.insert((3, 0), CellProtection::unlocked());
+    }
+    let block = |start: (u32, u32), end: (u32, u32), locked| RangeProtection {
+        start_row: start.0,
+        start_col: start.1,
+        end_row: end.0,
+        end_col: end.1,
+        locked: Some(locked),
+        formula_hidden: None,
+    };
+    // A5:C6 unlocked, then B6 locked again by a later record
+    state.range_protection.lock().unwrap().insert(0, vec![block((5, 0), (6, 2), false), block((5, 1), (5, 1), true)]);
+
+    // Tab order walks rows; the relocked cell is skipped
+    assert_eq!(find_next(&state, (0, 0), Right, Unlocked, false), Some((1, 1)));
+    assert_eq!(find_next(&state, (1, 1), Right, Unlocked, false), Some((3, 0)));
+    assert_eq!(find_next(&state, (3, 0), Right, Unlocked, false), Some((5, 0)));
+    assert_eq!(find_next(&state, (5, 0), Right, Unlocked, false), Some((5, 2)));
+    assert_eq!(find_next(&state, (5, 0), Left, Unlocked, false), Some((3, 0)));
+    // Enter order walks columns
+    assert_eq!(find_next(&state, (1, 1), Down, Unlocked, false), Some((6, 1)));
+    assert_eq!(find_next(&state, (6, 1), Up, Unlocked, false), Some((1, 1)));
+
+    // Past the last unlocked cell: none, or back to the first when wrapping
+    assert_eq!(find_next(&state, (6, 2), Right, Unlocked, false), None);
+    assert_eq!(find_next(&state, (6, 2), Right, Unlocked, true), Some((1, 1)));
+    assert_eq!(find_next(&state, (1, 1), Left, Unlocked, true), Some((6, 2)));
+
+    // Allow-edit ranges count as editable on the protected sheet
+    state.sheet_protection.lock().unwrap().get_mut(&0).unwrap().allow_edit_ranges.push(AllowEditRange {
+        title: "Inputs".to_string(),
+        start_row: 10,
+        start_col: 4,
+        end_row: 10,
+        end_col: 5,
+        password_hash: None,
+        password_salt: None,
+    });
+    assert_eq!(find_next(&state, (6, 2), Right, Unlocked, false), Some((10, 4)));
+}
+
+#[test]
+fn test_find_next_cell_by_feature() {
+    use commands::nav::{NavDirection::*, NavPredicate::*};
+
+    let state = create_app_state();
+    {
+        let mut grid = state.get_active_grid();
+        grid.set_cell(4, 0, Cell::new_formula("1+1".to_string()));
+        let mut error = Cell::new_number(0.0);
+        error.value = CellValue::Error(CellError::Div0);
+        grid.set_cell(2, 2, error);
+        grid.set_cell(0, 0, Cell::new_number(1.0));
+    }
+    state.data_validations.lock().unwrap().insert(0, vec![data_validation::ValidationRange {
+        start_row: 2, start_col: 1, end_row: 4, end_col: 1,
+        validation: Default::default(),
+    }]);
+    for (row, col) in [(0, 3), (7, 0)] {
+        let comment = comments::Comment::new(row, col, 0, "a@example.com".into(), "A".into(), "c".into());
+        state.comments.lock().unwrap().entry(0).or_default().insert((row, col), comment);
+    }
+
+    assert_eq!(find_next(&state, (0, 0), Right, HasValidation, false), Some((2, 1)));
+    assert_eq!(find_next(&state, (2, 1), Right, HasValidation, false), Some((3, 1)));
+    assert_eq!(find_next(&state, (3, 1), Up, HasValidation, false), Some((2, 1)));
+    assert_eq!(find_next(&state, (2, 1), Up, HasValidation, false), None);
+    assert_eq!(find_next(&state, (2, 1), Up, HasValidation, true), Some((4, 1)));
+
+    assert_eq!(find_next(&state, (0, 3), Right, HasComment, false), Some((7, 0)));
+    assert_eq!(find_next(&state, (7, 0), Right, HasComment, false), None);
+    assert_eq!(find_next(&state, (7, 0), Right, HasComment, true), Some((0, 3)));
+    // Column order: A8 comes before D1
+    assert_eq!(find_next(&state, (0, 0), Down, HasComment, false), Some((7, 0)));
+
+    assert_eq!(find_next(&state, (0, 0), Right, HasError, false), Some((2, 2)));
+    assert_eq!(find_next(&state, (2, 2), Right, HasError, true), Some((2, 2)));
+
+    assert_eq!(find_next(&state, (0, 0), Down, HasFormula, false), Some((4, 0)));
+    assert_eq!(find_next(&state, (0, 0), Left, HasFormula, false), None);
+    assert_eq!(find_next(&state, (0, 0), Left, HasFormula, true), Some((4, 0)));
+}
+
+#[test]
+fn test_create_names_from_selection_headers() {
+    use crate::named_ranges::{create_names_from_selection_internal, NameHeaders};
+
+    let state = create_app_state();
+    let user_files_state = UserFilesState { files: Mutex::new(HashMap::new()) };
+    let pivot_state = crate::pivot::types::PivotState::new();
+    {
+        let mut grid = state.get_active_grid();
+        for (col, header) in ["Region", "Unit Price", "Region", "Total"].into_iter().enumerate() {
+            grid.set_cell(0, col as u32, Cell::new_text(header.to_string()));
+        }
+        grid.set_cell(0, 4, Cell::new_number(2024.0));
+        grid.set_cell(0, 6, Cell::new_text("Spare".to_string()));
+    }
+    state.named_ranges.lock().unwrap().insert("TOTAL".to_string(), named_ranges::NamedRange {
+        name: "Total".to_string(),
+        sheet_index: None,
+        refers_to: "=1".to_string(),
+        comment: None,
+        folder: None,
+    });
+
+    // A1:F4 with the top row as headers; F1 is blank
+    let result = create_names_from_selection_internal(&state, &user_files_state, &pivot_state, 0, 0, 3, 5, NameHeaders::TopRow).unwrap();
+    let created: Vec<(&str, &str)> = result.created.iter().map(|nr| (nr.name.as_str(), nr.refers_to.as_str())).collect();
+    assert_eq!(created, vec![
+        ("Region", "=Sheet1!$A$2:$A$4"),
+        ("Unit_Price", "=Sheet1!$B$2:$B$4"),
+        ("Region_2", "=Sheet1!$C$2:$C$4"),
+        ("_2024", "=Sheet1!$E$2:$E$4"),
+    ]);
+    let skipped: Vec<&str> = result.skipped.iter().map(|s| s.reason.as_str()).collect();
+    assert_eq!(skipped, vec!["A named range 'Total' already exists.", "Header cell F1 is blank."]);
+    assert_eq!(state.named_ranges.lock().unwrap()["TOTAL"].refers_to, "=1");
+
+    // The left column names each row across the columns right of it
+    let result = create_names_from_selection_internal(&state, &user_files_state, &pivot_state, 0, 6, 1, 7, NameHeaders::LeftColumn).unwrap();
+    assert_eq!(result.created[0].name, "Spare");
+    assert_eq!(result.created[0].refers_to, "=Sheet1!$H$1");
+    assert!(create_names_from_selection_internal(&state, &user_files_state, &pivot_state, 0, 0, 0, 3, NameHeaders::Both).is_err());
+
+    // Each call is a single undo step
+    crate::undo_commands::undo_internal(
+        &state,
+        &FileState::default(),
+        &user_files_state,
+        &pivot_state,
+        &crate::slicer::SlicerState::new(),
+        &RibbonFilterState::new(),
+        &PaneControlState::new(),
+    );
+    crate::undo_commands::undo_internal(
+        &state,
+        &FileState::default(),
+        &user_files_state,
+        &pivot_state,
+        &crate::slicer::SlicerState::new(),
+        &RibbonFilterState::new(),
+        &PaneControlState::new(),
+    );
+    let names = state.named_ranges.lock().unwrap();
+    assert_eq!(names.len(), 1);
+    assert!(names.contains_key("TOTAL"));
+}
+
+#[test]
+fn test_import_named_ranges_collision_policies() {
+    use crate::named_ranges::{
+        export_named_ranges_internal, import_named_ranges_internal, NameCollisionPolicy, RenamedName,
+    };
+
+    let define = |state: &AppState, name: &str, refers_to: &str| {
+        state.named_ranges.lock().unwrap().insert(name.to_uppercase(), named_ranges::NamedRange {
+            name: name.to_string(),
+            sheet_index: None,
+            refers_to: refers_to.to_string(),
+            comment: None,
+            folder: None,
+        });
+    };
+    let source = create_app_state();
+    define(&source, "Rate", "=0.2");
+    define(&source, "Region", "=Sheet1!$A$2:$A$9");
+    let json = export_named_ranges_internal(&source).unwrap();
+
+    let user_files_state = UserFilesState { files: Mutex::new(HashMap::new()) };
+    let pivot_state = crate::pivot::types::PivotState::new();
+    let target = || {
+        let state = create_app_state();
+        define(&state, "Rate", "=0.1");
+        define(&state, "Rate_2", "=0.3");
+        state
+    };
+    let refers_to = |state: &AppState, key: &str| state.named_ranges.lock().unwrap()[key].refers_to.clone();
+
+    let state = target();
+    let result = import_named_ranges_internal(&state, &user_files_state, &pivot_state, &json, NameCollisionPolicy::Skip).unwrap();
+    assert_eq!(result.created.len(), 1);
+    assert_eq!(result.skipped[0].name, "Rate");
+    assert_eq!(refers_to(&state, "RATE"), "=0.1");
+    assert_eq!(refers_to(&state, "REGION"), "=Sheet1!$A$2:$A$9");
+
+    let state = target();
+    let result = import_named_ranges_internal(&state, &user_files_state, &pivot_state, &json, NameCollisionPolicy::Overwrite).unwrap();
+    assert_eq!(result.overwritten, vec!["Rate".to_string()]);
+    assert_eq!(refers_to(&state, "RATE"), "=0.2");
+
+    let state = target();
+    let result = import_named_ranges_internal(&state, &user_files_state, &pivot_state, &json, NameCollisionPolicy::Rename).unwrap();
+    assert_eq!(result.renamed, vec![RenamedName { from: "Rate".to_string(), to: "Rate_3".to_string() }]);
+    assert_eq!(refers_to(&state, "RATE"), "=0.1");
+    assert_eq!(refers_to(&state, "RATE_3"), "=0.2");
+
+    assert!(import_named_ranges_internal(&state, &user_files_state, &pivot_state, "not json", NameCollisionPolicy::Skip).is_err());
+}
+
+/// Sorting a table carries each row's comment and hyperlink along with it;
+/// a plain range keeps them in place unless asked, and undo puts them back.
+#[test]
+fn test_table_sort_carries_annotations_with_rows() {
+    use crate::api_types::{SortField, SortRangeParams};
+    use crate::commands::data::sort_range_internal;
+    use crate::persistence::FileState;
+
+    let state = create_app_state();
+    let file_state = FileState::default();
+    {
+        let mut grid = state.get_active_grid();
+        grid.set_cell(0, 0, Cell::new_text("Name".to_string()));
+        grid.set_cell(0, 1, Cell::new_text("Salary".to_string()));
+        for (row, (name, salary)) in [("Ann", 50.0), ("Bob", 90.0), ("Cid", 70.0)].into_iter().enumerate() {
+            grid.set_cell(row as u32 + 1, 0, Cell::new_text(name.to_string()));
+            grid.set_cell(row as u32 + 1, 1, Cell::new_number(salary));
+        }
+    }
+    let comment = comments::Comment::new(2, 0, 0, "a@example.com".into(), "A".into(), "Bob's review".into());
+    state.comments.lock().unwrap().entry(0).or_default().insert((2, 0), comment);
+    state.hyperlinks.lock().unwrap().entry(0).or_default()
+        .insert((3, 1), hyperlinks::Hyperlink::new_url(3, 1, 0, "https://example.com/cid".into()));
+
+    let params = |preserve: Option<bool>| SortRangeParams {
+        start_row: 0,
+        start_col: 0,
+        end_row: 3,
+        end_col: 1,
+        fields: vec![SortField {
+            key: 1,
+            ascending: false,
+            sort_on: Default::default(),
+            color: None,
+            data_option: Default::default(),
+            sub_field: None,
+            custom_order: None,
+        }],
+        match_case: false,
+        has_headers: true,
+        orientation: Default::default(),
+        sort_preserves_annotations: preserve,
+    };
+    let name_at = |state: &AppState, row: u32| state.get_active_grid().get_cell(row, 0).unwrap().value.clone();
+    let comment_rows = |state: &AppState| -> Vec<u32> {
+        state.comments.lock().unwrap()[&0].values().map(|c| c.row).collect()
+    };
+
+    // Not a table: annotations stay on their cells by default
+    let result = sort_range_internal(&state, &file_state, params(None)).unwrap();
+    assert!(result.success);
+    assert_eq!(name_at(&state, 1), CellValue::Text("Bob".to_string()));
+    assert!(state.comments.lock().unwrap()[&0].contains_key(&(2, 0)));
+    crate::undo_commands::undo_internal(
+        &state,
+        &file_state,
+        &UserFilesState { files: Mutex::new(HashMap::new()) },
+        &crate::pivot::types::PivotState::new(),
+        &crate::slicer::SlicerState::new(),
+        &RibbonFilterState::new(),
+        &PaneControlState::new(),
+    );
+    assert_eq!(name_at(&state, 2), CellValue::Text("Bob".to_string()));
+
+    // Inside a table the comment follows Bob to the top, the link follows Cid
+    let table_id = identity::EntityId::from_bytes(identity::generate_uuid_v7());
+    let columns = ["Name", "Salary"].iter().map(|header| tables::TableColumn::new(
+        identity::EntityId::from_bytes(identity::generate_uuid_v7()),
+        header.to_string(),
+    )).collect();
+    state.tables.lock().unwrap().entry(0).or_default().insert(table_id, tables::Table {
+        id: table_id,
+        name: "Staff".to_string(),
+        sheet_index: 0,
+        start_row: 0,
+        start_col: 0,
+        end_row: 3,
+        end_col: 1,
+        columns,
+        style_options: Default::default(),
+        style_name: "TableStyleMedium2".to_string(),
+        auto_filter_id: None,
+        data_type_policy: Default::default(),
+    });
+    sort_range_internal(&state, &file_state, params(None)).unwrap();
+    assert_eq!(name_at(&state, 1), CellValue::Text("Bob".to_string()));
+    {
+        let comments = state.comments.lock().unwrap();
+        assert_eq!(comments[&0].len(), 1);
+        assert_eq!(comments[&0][&(1, 0)].content, "Bob's review");
+        assert_eq!(comments[&0][&(1, 0)].row, 1);
+        let links = state.hyperlinks.lock().unwrap();
+        assert_eq!(links[&0][&(2, 1)].target, "https://example.com/cid");
+        assert_eq!(links[&0][&(2, 1)].row, 2);
+    }
+
+    crate::undo_commands::undo_internal(
+        &state,
+        &file_state,
+        &UserFilesState { files: Mutex::new(HashMap::new()) },
+        &crate::pivot::types::PivotState::new(),
+        &crate::slicer::SlicerState::new(),
+        &RibbonFilterState::new(),
+        &PaneControlState::new(),
+    );
+    assert_eq!(name_at(&state, 2), CellValue::Text("Bob".to_string()));
+    assert_eq!(comment_rows(&state), vec![2]);
+    assert!(state.hyperlinks.lock().unwrap()[&0].contains_key(&(3, 1)));
+
+    // An explicit opt-out wins over the table default
+    sort_range_internal(&state, &file_state, params(Some(false))).unwrap();
+    assert_eq!(comment_rows(&state), vec![2]);
+}
+
+/// A spill into an occupied cell leaves the origin #SPILL! on every write
+/// path — a single edit, a dependent recalculation and a batch — and clearing
+/// the blocker lets the same formula spill.
+#[test]
+fn test_blocked_spill_reports_spill_error() {
+    use crate::api_types::CellUpdateInput;
+    use crate::commands::data::{update_cell_impl, update_cells_batch_with_controls};
+    let state = create_app_state();
+    let file_state = FileState::default();
+    let user_files = UserFilesState { files: Mutex::new(HashMap::new()) };
+    let slicer = crate::slicer::SlicerState::new();
+    let pivot = crate::pivot::types::PivotState::new();
+    let pane = PaneControlState::new();
+    let ribbon = RibbonFilterState::new();
+    let edit = |row: u32, col: u32, value: &str| {
+        update_cell_impl(&state, &file_state, &user_files, &slicer, &pivot, &pane, &ribbon, row, col, value.to_string(), None, None)
+            .unwrap();
+    };
+    let batch = |row: u32, col: u32, value: &str| {
+        let update = CellUpdateInput { row, col, value: value.to_string(), style_index: None, invariant: None };
+        update_cells_batch_with_controls(&state, &file_state, &user_files, &pivot, vec![update], None, None).unwrap();
+    };
+    let value = |row: u32, col: u32| state.grids.lock().unwrap()[0].get_cell(row, col).map(|c| c.value.clone());
+    let spill = Some(CellValue::Error(CellError::Spill));
+
+    // Single edit: B3 blocks SEQUENCE(3) in B1.
+    edit(2, 1, "x");
+    edit(0, 1, "=SEQUENCE(3)");
+    assert_eq!(value(0, 1), spill);
+    assert_eq!(value(1, 1), None);
+    edit(2, 1, "");
+    edit(0, 1, "=SEQUENCE(3)");
+    assert_eq!(value(2, 1), Some(CellValue::Number(3.0)));
+
+    // Dependent recalculation: growing A1 pushes D1's spill into D3.
+    edit(0, 0, "2");
+    edit(2, 3, "x");
+    edit(0, 3, "=SEQUENCE(A1)");
+    assert_eq!(value(1, 3), Some(CellValue::Number(2.0)));
+    edit(0, 0, "3");
+    assert_eq!(value(0, 3), spill);
+    assert_eq!(value(1, 3), None);
+
+    // Batch: F2 blocks SEQUENCE(2) in F1.
+    batch(1, 5, "x");
+    batch(0, 5, "=SEQUENCE(2)");
+    assert_eq!(value(0, 5), spill);
+    batch(1, 5, "");
+    batch(0, 5, "=SEQUENCE(2)");
+    assert_eq!(value(1, 5), Some(CellValue::Number(2.0)));
+}
@@ -108,13 +108,14 @@ fn group_into_ranges(
 /// Reads from the `dependencies` map (what this formula references).
 #[tauri::command]
 pub fn trace_precedents(state: State<AppState>, row: u32, col: u32) -> TraceResult {
-    let grid = state.grid.lock().unwrap();
+    let active_sheet = *state.active_sheet.lock().unwrap();
+    let grids = state.grids.lock().unwrap();
+    let grid = &grids[active_sheet];
     let styles = state.style_registry.lock().unwrap();
     let dependencies = state.dependencies.lock().unwrap();
     let column_dependencies = state.column_dependencies.lock().unwrap();
     let row_dependencies = state.row_dependencies.lock().unwrap();
     let cross_sheet_deps = state.cross_sheet_dependencies.lock().unwrap();
-    let active_sheet = *state.active_sheet.lock().unwrap();
     let sheet_names = state.sheet_names.lock().unwrap();
     let locale = state.locale.lock().unwrap();
 
@@ -174,8 +175,6 @@ pub fn trace_precedents(state: State<AppState>, row: u32, col: u32) -> TraceResu
                 .unwrap_or(0);
 
             // Check if the referenced cell is an error
-            // We need to look at the other grid if it exists
-            let grids = state.grids.lock().unwrap();
             let is_error = if sheet_idx < grids.len() {
                 cell_is_error(&grids[sheet_idx], cs_row, cs_col)
             } else {
@@ -210,13 +209,14 @@ pub fn trace_precedents(state: State<AppState>, row: u32, col: u32) -> TraceResu
 /// Reads from the `dependents` map (what formulas reference this cell).
 #[tauri::command]
 pub fn trace_dependents(state: State<AppState>, row: u32, col: u32) -> TraceResult {
-    let grid = state.grid.lock().unwrap();
+    let active_sheet = *state.active_sheet.lock().unwrap();
+    let grids = state.grids.lock().unwrap();
+    let grid = &grids[active_sheet];
     let styles = state.style_registry.lock().unwrap();
     let dependents = state.dependents.lock().unwrap();
     let column_dependents = state.column_dependents.lock().unwrap();
     let row_dependents = state.row_dependents.lock().unwrap();
     let cross_sheet_deps = state.cross_sheet_dependents.lock().unwrap();
-    let active_sheet = *state.active_sheet.lock().unwrap();
     let sheet_names = state.sheet_names.lock().unwrap();
     let locale = state.locale.lock().unwrap();
 
@@ -276,7 +276,6 @@ pub fn trace_dependents(state: State<AppState>, row: u32, col: u32) -> TraceResu
             };
 
             // Check if the dependent cell is an error
-            let grids = state.grids.lock().unwrap();
             let is_error = if sheet_idx < grids.len() {
                 cell_is_error(&grids[sheet_idx], cs_row, cs_col)
             } else {
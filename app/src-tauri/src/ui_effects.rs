@@ -0,0 +1,394 @@
+//! FILENAME: app/src-tauri/src/ui_effects.rs
+//! PURPOSE: Bookkeeping for the formatting SET.* formulas apply (row heights,
+//! column widths, cell fills): which formula cells currently request what,
+//! and the value each target had before the first of them changed it, so
+//! removing the last requesting formula restores it.
+//! CONTEXT: `process_ui_effects` records what an evaluation requested and
+//! `clear_ui_effects_for_cell` forgets a cell that no longer holds its
+//! formula; both only mark targets dirty. `apply_pending_ui_effects` later
+//! writes the resolved value of every dirty target, or its original once no
+//! formula requests it any more. Formula cells requesting different values
+//! for one target are settled by the workbook's `UiEffectConflictPolicy`
+//! (kept in `extension_data["calcula.uiEffects"]`); `get_ui_effect_conflicts`
+//! lists them. `commit_ui_effects` ties it to the cell writes of every
+//! command (see `viewport_revisions::seal_viewport_changes`), so effects
+//! follow pastes, recalculations, undo/redo and loads alike.
+
+use rustc_hash::{FxHashMap, FxHashSet};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use tauri::State;
+
+use crate::api_types::DimensionData;
+use crate::AppState;
+
+/// Key under which the UI-effect settings persist in `AppState::extension_data`.
+pub const UI_EFFECTS_EXT_KEY: &str = "calcula.uiEffects";
+
+/// (sheet, row, col) of a formula cell.
+pub type SourceCell = (usize, u32, u32);
+
+/// What a formatting effect changes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum UiEffectTarget {
+    RowHeight {
+        #[serde(rename = "sheetIndex")]
+        sheet: usize,
+        row: u32,
+    },
+    ColumnWidth {
+        #[serde(rename = "sheetIndex")]
+        sheet: usize,
+        col: u32,
+    },
+    CellFill {
+        #[serde(rename = "sheetIndex")]
+        sheet: usize,
+        row: u32,
+        col: u32,
+    },
+}
+
+/// How a target requested with different values by several formula cells
+/// is settled. A workbook setting.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum UiEffectConflictPolicy {
+    /// None of the requests applies and every requesting cell shows #CONFLICT.
+    #[default]
+    Error,
+    /// The most recently evaluated request applies.
+    LastWriterWins,
+    /// The largest size applies. Fills have no order; the most recent wins.
+    MaxValue,
+}
+
+impl UiEffectConflictPolicy {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            UiEffectConflictPolicy::Error => "error",
+            UiEffectConflictPolicy::LastWriterWins => "lastWriterWins",
+            UiEffectConflictPolicy::MaxValue => "maxValue",
+        }
+    }
+
+    pub fn parse(policy: &str) -> Option<Self> {
+        match policy.to_lowercase().as_str() {
+            "error" => Some(UiEffectConflictPolicy::Error),
+            "lastwriterwins" => Some(UiEffectConflictPolicy::LastWriterWins),
+            "maxvalue" => Some(UiEffectConflictPolicy::MaxValue),
+            _ => None,
+        }
+    }
+}
+
+/// The value an effect gives its target.
+#[derive(Debug, Clone, PartialEq)]
+pub enum UiEffectValue {
+    /// Row height or column width in pixels.
+    Size(f64),
+    /// Fill color; None removes the fill.
+    Fill(Option<engine::Color>),
+}
+
+impl UiEffectTarget {
+    /// Split a formatting effect requested on `sheet`. None for effects that
+    /// are not formatting (HYPERLINK).
+    pub fn of(sheet: usize, effect: &engine::UiEffect) -> Option<(UiEffectTarget, UiEffectValue)> {
+        match *effect {
+            engine::UiEffect::SetRowHeight { row, height } => {
+                Some((UiEffectTarget::RowHeight { sheet, row }, UiEffectValue::Size(height)))
+            }
+            engine::UiEffect::SetColumnWidth { col, width } => {
+                Some((UiEffectTarget::ColumnWidth { sheet, col }, UiEffectValue::Size(width)))
+            }
+            engine::UiEffect::SetCellFillColor { row, col, color } => {
+                Some((UiEffectTarget::CellFill { sheet, row, col }, UiEffectValue::Fill(color)))
+            }
+            engine::UiEffect::SetHyperlink { .. } => None,
+        }
+    }
+
+    pub fn sheet(&self) -> usize {
+        match *self {
+            UiEffectTarget::RowHeight { sheet, .. }
+            | UiEffectTarget::ColumnWidth { sheet, .. }
+            | UiEffectTarget::CellFill { sheet, .. } => sheet,
+        }
+    }
+}
+
+/// What a target looked like before a formula first changed it.
+#[derive(Debug, Clone, PartialEq)]
+pub enum UiEffectOriginal {
+    /// Row height or column width; None when it was the default size.
+    Size(Option<f64>),
+    Fill(engine::Fill),
+}
+
+/// One formula cell's request for a target.
+#[derive(Debug, Clone, PartialEq)]
+pub struct UiEffectSource {
+    /// (sheet, row, col) of the formula cell.
+    pub cell: (usize, u32, u32),
+    /// Order of the evaluation that made the request; later is higher.
+    pub seq: u64,
+    pub value: UiEffectValue,
+}
+
+/// Formatting requested by formula cells, per target.
+#[derive(Debug, Default)]
+pub struct UiEffectRegistry {
+    /// Targets each formula cell requested at its last evaluation.
+    by_cell: FxHashMap<(usize, u32, u32), Vec<UiEffectTarget>>,
+    /// Requests per target, oldest first.
+    sources: FxHashMap<UiEffectTarget, Vec<UiEffectSource>>,
+    /// Value of every target a formula changed, from before the change.
+    originals: FxHashMap<UiEffectTarget, UiEffectOriginal>,
+    /// Targets whose requests changed since the last `take_dirty`.
+    dirty: BTreeSet<UiEffectTarget>,
+    /// Formula cells currently showing #CONFLICT in place of their value.
+    flagged: BTreeSet<SourceCell>,
+    /// Cells whose effects were recorded since the last `take_evaluated`.
+    evaluated: FxHashSet<SourceCell>,
+    next_seq: u64,
+}
+
+impl UiEffectRegistry {
+    /// Replace the formatting `cell` requests with `effects` (the result of
+    /// evaluating it).
+    pub fn set_cell_effects(&mut self, cell: (usize, u32, u32), effects: &[engine::UiEffect]) {
+        self.clear_cell(cell);
+        let mut targets = Vec::new();
+        for (target, value) in effects.iter().filter_map(|effect| UiEffectTarget::of(cell.0, effect)) {
+            self.next_seq += 1;
+            let requests = self.sources.entry(target).or_default();
+            // A formula calling the same SET function twice keeps its last call.
+            requests.retain(|source| source.cell != cell);
+            requests.push(UiEffectSource { cell, seq: self.next_seq, value });
+            self.dirty.insert(target);
+            targets.push(target);
+        }
+        if !targets.is_empty() {
+            self.by_cell.insert(cell, targets);
+        }
+    }
+
+    /// Forget what `cell` requested. Returns whether it requested anything.
+    pub fn clear_cell(&mut self, cell: (usize, u32, u32)) -> bool {
+        let Some(targets) = self.by_cell.remove(&cell) else { return false };
+        for target in targets {
+            if let Some(requests) = self.sources.get_mut(&target) {
+                requests.retain(|source| source.cell != cell);
+                if requests.is_empty() {
+                    self.sources.remove(&target);
+                }
+            }
+            self.dirty.insert(target);
+        }
+        true
+    }
+
+    /// Note that `cell` was just evaluated and its effects recorded.
+    pub fn mark_evaluated(&mut self, cell: SourceCell) {
+        self.evaluated.insert(cell);
+    }
+
+    /// Cells evaluated since the last call.
+    pub fn take_evaluated(&mut self) -> FxHashSet<SourceCell> {
+        std::mem::take(&mut self.evaluated)
+    }
+
+    /// Formula cells that currently request any formatting.
+    pub fn cells(&self) -> impl Iterator<Item = &SourceCell> {
+        self.by_cell.keys()
+    }
+
+    /// Forget everything without reverting anything (the workbook was
+    /// replaced, so the recorded originals no longer apply).
+    pub fn reset(&mut self) {
+        *self = Self::default();
+    }
+
+    /// Formula cells currently requesting `target`, oldest first.
+    pub fn sources(&self, target: &UiEffectTarget) -> &[UiEffectSource] {
+        self.sources.get(target).map_or(&[], Vec::as_slice)
+    }
+
+    /// The value to apply to `target`. Requests that disagree are settled by
+    /// `policy`. None when no formula requests it, or when the requests
+    /// conflict under `UiEffectConflictPolicy::Error`.
+    pub fn resolve(&self, target: &UiEffectTarget, policy: UiEffectConflictPolicy) -> Option<UiEffectValue> {
+        let requests = self.sources(target);
+        let latest = requests.iter().max_by_key(|source| source.seq)?;
+        if !disagree(requests) {
+            return Some(latest.value.clone());
+        }
+        match (policy, &latest.value) {
+            (UiEffectConflictPolicy::Error, _) => None,
+            (UiEffectConflictPolicy::MaxValue, UiEffectValue::Size(_)) => requests
+                .iter()
+                .filter_map(|source| match source.value {
+                    UiEffectValue::Size(size) => Some(size),
+                    UiEffectValue::Fill(_) => None,
+                })
+                .max_by(f64::total_cmp)
+                .map(UiEffectValue::Size),
+            _ => Some(latest.value.clone()),
+        }
+    }
+
+    /// Targets requested with different values by several formula cells, in
+    /// target order, each with its requesting cells in request order.
+    pub fn conflicts(&self) -> Vec<(UiEffectTarget, Vec<SourceCell>)> {
+        let mut conflicts: Vec<_> = self
+            .sources
+            .iter()
+            .filter(|(_, requests)| disagree(requests))
+            .map(|(&target, requests)| (target, requests.iter().map(|source| source.cell).collect()))
+            .collect();
+        conflicts.sort_unstable_by_key(|(target, _)| *target);
+        conflicts
+    }
+
+    /// Mark every requested target dirty, so the next apply settles them
+    /// again (the conflict policy changed).
+    pub fn mark_all_dirty(&mut self) {
+        self.dirty.extend(self.sources.keys().copied());
+    }
+
+    /// Replace the set of cells showing #CONFLICT with `cells`. Returns the
+    /// cells that no longer show it.
+    pub fn set_flagged(&mut self, cells: BTreeSet<SourceCell>) -> Vec<SourceCell> {
+        let released = self.flagged.difference(&cells).copied().collect();
+        self.flagged = cells;
+        released
+    }
+
+    /// Targets whose requests changed since the last call.
+    pub fn take_dirty(&mut self) -> Vec<UiEffectTarget> {
+        std::mem::take(&mut self.dirty).into_iter().collect()
+    }
+
+    pub fn has_dirty(&self) -> bool {
+        !self.dirty.is_empty()
+    }
+
+    /// Remember `target`'s value from before a formula first changes it.
+    /// Later calls keep the first value.
+    pub fn record_original(&mut self, target: UiEffectTarget, original: impl FnOnce() -> UiEffectOriginal) {
+        self.originals.entry(target).or_insert_with(original);
+    }
+
+    /// Take `target`'s original value once no formula requests it.
+    pub fn take_original(&mut self, target: &UiEffectTarget) -> Option<UiEffectOriginal> {
+        self.originals.remove(target)
+    }
+}
+
+/// Whether requests for one target ask for different values.
+fn disagree(requests: &[UiEffectSource]) -> bool {
+    requests.iter().any(|source| source.value != requests[0].value)
+}
+
+/// The workbook's conflict policy; `Error` unless one was set.
+pub fn conflict_policy(state: &AppState) -> UiEffectConflictPolicy {
+    state
+        .extension_data
+        .lock()
+        .unwrap()
+        .get(UI_EFFECTS_EXT_KEY)
+        .and_then(|settings| settings.get("conflictPolicy"))
+        .and_then(|policy| policy.as_str())
+        .and_then(UiEffectConflictPolicy::parse)
+        .unwrap_or_default()
+}
+
+/// A formula cell requesting a conflicted target.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UiEffectConflictSource {
+    pub sheet_index: usize,
+    pub row: u32,
+    pub col: u32,
+}
+
+/// A target several formula cells request with different values.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UiEffectConflict {
+    pub target: UiEffectTarget,
+    pub sources: Vec<UiEffectConflictSource>,
+}
+
+pub(crate) fn get_ui_effect_conflicts_internal(state: &AppState) -> Vec<UiEffectConflict> {
+    state
+        .ui_effects
+        .lock()
+        .unwrap()
+        .conflicts()
+        .into_iter()
+        .map(|(target, cells)| UiEffectConflict {
+            target,
+            sources: cells
+                .into_iter()
+                .map(|(sheet_index, row, col)| UiEffectConflictSource { sheet_index, row, col })
+                .collect(),
+        })
+        .collect()
+}
+
+/// Store `policy` and settle every requested target under it. Returns the
+/// active sheet's dimension changes.
+pub(crate) fn set_ui_effect_conflict_policy_internal(
+    state: &AppState,
+    policy: UiEffectConflictPolicy,
+) -> Vec<DimensionData> {
+    state.extension_data.lock().unwrap().insert(
+        UI_EFFECTS_EXT_KEY.to_string(),
+        serde_json::json!({ "conflictPolicy": policy.as_str() }),
+    );
+    state.ui_effects.lock().unwrap().mark_all_dirty();
+
+    let active_sheet = *state.active_sheet.lock().unwrap();
+    let sheet_names = state.sheet_names.lock().unwrap();
+    let mut grids = state.grids.lock().unwrap();
+    let mut styles = state.style_registry.lock().unwrap();
+    let mut row_heights = state.row_heights.lock().unwrap();
+    let mut column_widths = state.column_widths.lock().unwrap();
+    crate::apply_pending_ui_effects(
+        state,
+        &mut grids,
+        &sheet_names,
+        &mut styles,
+        &mut row_heights,
+        &mut column_widths,
+        active_sheet,
+    )
+    .0
+}
+
+/// Targets (row heights, column widths, cell fills) that several SET.*
+/// formulas request with different values, with the cells requesting each.
+/// Listed whatever the conflict policy, so competing formulas can be found
+/// even when the policy picks a winner.
+#[tauri::command]
+pub fn get_ui_effect_conflicts(state: State<AppState>) -> Vec<UiEffectConflict> {
+    get_ui_effect_conflicts_internal(&state)
+}
+
+/// Get how competing SET.* requests are settled: "error", "lastWriterWins"
+/// or "maxValue".
+#[tauri::command]
+pub fn get_ui_effect_conflict_policy(state: State<AppState>) -> String {
+    conflict_policy(&state).as_str().to_string()
+}
+
+/// Set how competing SET.* requests are settled and apply the outcome right
+/// away. Returns the active sheet's row height and column width changes.
+#[tauri::command]
+pub fn set_ui_effect_conflict_policy(state: State<AppState>, policy: String) -> Result<Vec<DimensionData>, String> {
+    let policy = UiEffectConflictPolicy::parse(&policy).ok_or_else(|| format!("Unknown conflict policy: {}", policy))?;
+    Ok(set_ui_effect_conflict_policy_internal(&state, policy))
+}
@@ -9,7 +9,7 @@ use crate::pivot::types::PivotState;
 use crate::ribbon_filter::types::{RibbonFilter, RibbonFilterState};
 use crate::slicer::types::{Slicer, SlicerState};
 use crate::{
-    extract_all_references, format_cell_value, update_column_dependencies,
+    extract_all_references, format_cell_value, resolve_cross_sheet_refs, update_column_dependencies,
     update_cross_sheet_dependencies, update_dependencies, update_row_dependencies, AppState,
 };
 use engine::{CellChange, GridSnapshot, Transaction, UndoMergeRegion};
@@ -86,25 +86,25 @@ fn to_undo_region(r: &MergedRegion) -> UndoMergeRegion {
 }
 
 /// Rebuild all formula dependency maps from scratch by scanning all cells of
-/// the ACTIVE sheet (the state.grid mirror).
+/// the ACTIVE sheet.
 /// Called after a structural restore (undo of insert/delete rows/cols) and
 /// after every sheet switch: the dependency maps are keyed by (row, col)
 /// without a sheet dimension, so they only ever describe one sheet — leaving
 /// them stale across switches made edits on the new sheet recalc against the
 /// previous sheet's edges (BUG-0016).
 pub(crate) fn rebuild_all_dependencies(state: &AppState) {
-    let grid = state.grid.lock().unwrap();
-    let active_sheet = *state.active_sheet.lock().unwrap();
-    rebuild_all_dependencies_from_grid(&grid, active_sheet, state);
+    let grid = state.get_active_grid();
+    rebuild_all_dependencies_from_grid(&grid, grid.index(), state);
 }
 
 /// Same as rebuild_all_dependencies but for callers that already hold the
-/// grid lock (passing it avoids a deadlock). Locks only the dependency maps.
+/// grids lock (passing it avoids a deadlock). Locks only the dependency maps.
 pub(crate) fn rebuild_all_dependencies_from_grid(
     grid: &engine::Grid,
     active_sheet: usize,
     state: &AppState,
 ) {
+    let sheet_names = state.sheet_names.lock().unwrap().clone();
     let mut dependents_map = state.dependents.lock().unwrap();
     let mut dependencies_map = state.dependencies.lock().unwrap();
     let mut column_dependents_map = state.column_dependents.lock().unwrap();
@@ -113,9 +113,11 @@ pub(crate) fn rebuild_all_dependencies_from_grid(
     let mut row_dependencies_map = state.row_dependencies.lock().unwrap();
     let mut cross_sheet_dependents = state.cross_sheet_dependents.lock().unwrap();
     let mut cross_sheet_dependencies = state.cross_sheet_dependencies.lock().unwrap();
+    let mut volatile_cells = state.volatile_cells.lock().unwrap();
 
     // Clear the single-sheet maps (they describe only the active sheet).
     dependents_map.clear();
+    volatile_cells.clear();
     dependencies_map.clear();
     column_dependents_map.clear();
     column_dependencies_map.clear();
@@ -150,7 +152,11 @@ pub(crate) fn rebuild_all_dependencies_from_grid(
     // Scan all cells and rebuild
     for (&(row, col), cell) in &grid.cells {
         if let Some(ast) = &cell.ast {
+            if engine::is_volatile(ast) {
+                volatile_cells.insert((row, col));
+            }
             let refs = extract_all_references(ast, &grid);
+            let cross_sheet_refs = resolve_cross_sheet_refs(&refs, &sheet_names);
 
             if !refs.cells.is_empty() {
                 update_dependencies(
@@ -176,10 +182,10 @@ pub(crate) fn rebuild_all_dependencies_from_grid(
                     &mut row_dependents_map,
                 );
             }
-            if !refs.cross_sheet_cells.is_empty() {
+            if !cross_sheet_refs.is_empty() {
                 update_cross_sheet_dependencies(
                     (active_sheet, row, col),
-                    refs.cross_sheet_cells,
+                    cross_sheet_refs,
                     &mut cross_sheet_dependencies,
                     &mut cross_sheet_dependents,
                 );
@@ -188,6 +194,39 @@ pub(crate) fn rebuild_all_dependencies_from_grid(
     }
 }
 
+/// Re-register the cross-sheet dependencies of every formula on EVERY sheet.
+/// Sheet operations invalidate those edges wholesale: formula cells are keyed
+/// by sheet index, precedents by sheet name, and a 3D reference covers
+/// whichever sheets currently lie between its bookends (all of them for
+/// `'*'!A1`). Called after a sheet is added, copied, deleted, renamed or
+/// moved, once the caller has released its locks.
+pub(crate) fn rebuild_cross_sheet_dependencies(state: &AppState) {
+    let sheet_names = state.sheet_names.lock().unwrap();
+    let grids = state.grids.lock().unwrap();
+    let mut cross_sheet_dependents = state.cross_sheet_dependents.lock().unwrap();
+    let mut cross_sheet_dependencies = state.cross_sheet_dependencies.lock().unwrap();
+
+    cross_sheet_dependents.clear();
+    cross_sheet_dependencies.clear();
+
+    for (sheet_index, sheet_grid) in grids.iter().enumerate() {
+        for (&(row, col), cell) in &sheet_grid.cells {
+            if let Some(ast) = &cell.ast {
+                let refs = extract_all_references(ast, sheet_grid);
+                let cross_sheet_refs = resolve_cross_sheet_refs(&refs, &sheet_names);
+                if !cross_sheet_refs.is_empty() {
+                    update_cross_sheet_dependencies(
+                        (sheet_index, row, col),
+                        cross_sheet_refs,
+                        &mut cross_sheet_dependencies,
+                        &mut cross_sheet_dependents,
+                    );
+                }
+            }
+        }
+    }
+}
+
 /// Begin a transaction for batching multiple changes.
 #[tauri::command]
 pub fn begin_undo_transaction(state: State<AppState>, description: String) {
@@ -237,9 +276,8 @@ fn apply_changes(
     is_undo: bool,
 ) -> UndoResult {
     let undo_stack = state.undo_stack.lock().unwrap();
-    let mut grid = state.grid.lock().unwrap();
-    let mut grids = state.grids.lock().unwrap();
-    let active_sheet = *state.active_sheet.lock().unwrap();
+    let mut grid = state.get_active_grid();
+    let active_sheet = grid.index();
     let styles = state.style_registry.lock().unwrap();
     let mut column_widths = state.column_widths.lock().unwrap();
     let mut row_heights = state.row_heights.lock().unwrap();
@@ -289,9 +327,6 @@ fn apply_changes(
                 match previous {
                     Some(cell) => {
                         grid.set_cell(*row, *col, cell.clone());
-                        if active_sheet < grids.len() {
-                            grids[active_sheet].set_cell(*row, *col, cell.clone());
-                        }
                         let style = styles.get(cell.style_index);
                         let display = format_cell_value(&cell.value, style, &locale);
                         updated_cells.push(CellData {
@@ -306,13 +341,11 @@ fn apply_changes(
                             sheet_index: None,
                             rich_text: None,
                             accounting_layout: None,
+                            error_detail: None,
                         });
                     }
                     None => {
                         grid.clear_cell(*row, *col);
-                        if active_sheet < grids.len() {
-                            grids[active_sheet].clear_cell(*row, *col);
-                        }
                         updated_cells.push(CellData {
                             row: *row,
                             col: *col,
@@ -325,6 +358,7 @@ fn apply_changes(
                             sheet_index: None,
                             rich_text: None,
                             accounting_layout: None,
+                            error_detail: None,
                         });
                     }
                 }
@@ -351,6 +385,30 @@ fn apply_changes(
                     None => { row_heights.remove(row); }
                 }
             }
+            CellChange::SetColumnStyle { col, previous } => {
+                let current = grid.col_styles.get(col).copied();
+                inverse_transaction.add_change(CellChange::SetColumnStyle {
+                    col: *col,
+                    previous: current,
+                });
+                match previous {
+                    Some(index) => { grid.col_styles.insert(*col, *index); }
+                    None => { grid.col_styles.remove(col); }
+                }
+                grid.mark_all_changed();
+            }
+            CellChange::SetRowStyle { row, previous } => {
+                let current = grid.row_styles.get(row).copied();
+                inverse_transaction.add_change(CellChange::SetRowStyle {
+                    row: *row,
+                    previous: current,
+                });
+                match previous {
+                    Some(index) => { grid.row_styles.insert(*row, *index); }
+                    None => { grid.row_styles.remove(row); }
+                }
+                grid.mark_all_changed();
+            }
             // The inverse keeps the SAME change variant; the apply direction
             // (is_undo) decides the operation. Storing the opposite variant
             // AND flipping on is_undo was a double negation: redo after undo
@@ -427,13 +485,6 @@ fn apply_changes(
                     merged_regions.insert(to_api_region(r));
                 }
 
-                // Sync grids vector
-                if active_sheet < grids.len() {
-                    grids[active_sheet].cells = grid.cells.clone();
-                    grids[active_sheet].max_row = grid.max_row;
-                    grids[active_sheet].max_col = grid.max_col;
-                }
-
                 structural_restore = true;
                 merge_changed = true;
             }
@@ -474,13 +525,26 @@ fn apply_changes(
     drop(row_heights);
     drop(column_widths);
     drop(styles);
-    drop(grids);
     drop(grid);
     drop(undo_stack);
 
     // Keep subscriber overrides in step with the restored cells (no-op when
     // the active sheet isn't subscribed).
     crate::calp_commands::record_subscription_override_edits(state, active_sheet, &override_edits);
+    // A restored cell that no longer calls HYPERLINK or a SET.* function
+    // drops the link and formatting its formula registered.
+    for (row, col, _, restored) in &override_edits {
+        let has_effects = restored
+            .as_ref()
+            .and_then(|cell| cell.formula_string())
+            .is_some_and(|formula| crate::formula_has_ui_effects(&formula));
+        if !has_effects {
+            crate::clear_ui_effects_for_cell(state, active_sheet, *row, *col);
+        }
+    }
+    // The change journal records undo/redo as new events (they never commit
+    // a transaction, so the commit log does not see them).
+    crate::change_journal::record_undo_redo(state, is_undo, &description, active_sheet, &override_edits);
 
     // Process deferred pivot/slicer/ribbon_filter restores (now safe to acquire locks)
     for (kind, data) in deferred_restores {
@@ -615,6 +679,7 @@ fn r_comment(s: &AppState, _p: &PivotState, _sl: &SlicerState, _rf: &RibbonFilte
 fn r_note(s: &AppState, _p: &PivotState, _sl: &SlicerState, _rf: &RibbonFilterState, _pc: &PaneControlState, _k: &str, d: &[u8], inv: &mut Transaction) { apply_note_restore(s, d, inv); }
 fn r_hyperlink(s: &AppState, _p: &PivotState, _sl: &SlicerState, _rf: &RibbonFilterState, _pc: &PaneControlState, _k: &str, d: &[u8], inv: &mut Transaction) { apply_hyperlink_restore(s, d, inv); }
 fn r_default_dim(s: &AppState, _p: &PivotState, _sl: &SlicerState, _rf: &RibbonFilterState, _pc: &PaneControlState, k: &str, d: &[u8], inv: &mut Transaction) { apply_default_dimension_restore(s, k, d, inv); }
+fn r_sheet_defaults(s: &AppState, _p: &PivotState, _sl: &SlicerState, _rf: &RibbonFilterState, _pc: &PaneControlState, _k: &str, d: &[u8], inv: &mut Transaction) { apply_sheet_defaults_restore(s, d, inv); }
 fn r_pivot_definition(s: &AppState, p: &PivotState, _sl: &SlicerState, rf: &RibbonFilterState, pc: &PaneControlState, _k: &str, d: &[u8], inv: &mut Transaction) { apply_pivot_definition_restore(s, p, rf, pc, d, inv); }
 fn r_pivot_create(s: &AppState, p: &PivotState, _sl: &SlicerState, _rf: &RibbonFilterState, _pc: &PaneControlState, _k: &str, d: &[u8], inv: &mut Transaction) { apply_pivot_create_restore(s, p, d, inv); }
 fn r_pivot_delete(s: &AppState, p: &PivotState, _sl: &SlicerState, rf: &RibbonFilterState, pc: &PaneControlState, _k: &str, d: &[u8], inv: &mut Transaction) { apply_pivot_delete_restore(s, p, rf, pc, d, inv); }
@@ -630,6 +695,7 @@ fn r_pane_control_delete(_s: &AppState, _p: &PivotState, _sl: &SlicerState, _rf:
 fn r_object_swap(s: &AppState, _p: &PivotState, _sl: &SlicerState, _rf: &RibbonFilterState, _pc: &PaneControlState, k: &str, d: &[u8], inv: &mut Transaction) { apply_object_swap_restore(s, k, d, inv); }
 fn r_script_grid_cells(s: &AppState, _p: &PivotState, _sl: &SlicerState, _rf: &RibbonFilterState, _pc: &PaneControlState, _k: &str, d: &[u8], inv: &mut Transaction) { apply_script_grid_cells_restore(s, d, inv); }
 fn r_report_restore(s: &AppState, _p: &PivotState, _sl: &SlicerState, _rf: &RibbonFilterState, _pc: &PaneControlState, _k: &str, d: &[u8], inv: &mut Transaction) { apply_report_restore(s, d, inv); }
+fn r_structural_features(s: &AppState, p: &PivotState, _sl: &SlicerState, _rf: &RibbonFilterState, _pc: &PaneControlState, _k: &str, d: &[u8], inv: &mut Transaction) { crate::structural_change::apply_structural_features_restore(s, p, d, inv); }
 fn r_calp_reset(s: &AppState, _p: &PivotState, _sl: &SlicerState, _rf: &RibbonFilterState, _pc: &PaneControlState, _k: &str, d: &[u8], inv: &mut Transaction) { apply_calp_reset_restore(s, d, inv); }
 
 /// The kind → spec table, built once.
@@ -642,6 +708,7 @@ static RESTORE_REGISTRY: Lazy<HashMap<&'static str, RestoreSpec>> = Lazy::new(||
     m.insert("hyperlink", RestoreSpec { restore: r_hyperlink, change_class: Other, defer: false });
     m.insert("default_row_height", RestoreSpec { restore: r_default_dim, change_class: Other, defer: false });
     m.insert("default_column_width", RestoreSpec { restore: r_default_dim, change_class: Other, defer: false });
+    m.insert("sheet_defaults", RestoreSpec { restore: r_sheet_defaults, change_class: Other, defer: false });
     // Deferred (defer: true) — acquire other state locks; run after grid locks drop.
     m.insert("pivot_definition", RestoreSpec { restore: r_pivot_definition, change_class: Pivot, defer: true });
     m.insert("pivot_create", RestoreSpec { restore: r_pivot_create, change_class: Pivot, defer: true });
@@ -658,7 +725,7 @@ static RESTORE_REGISTRY: Lazy<HashMap<&'static str, RestoreSpec>> = Lazy::new(||
     for k in [
         "obj_chart", "obj_sparklines", "obj_table", "obj_autofilter",
         "obj_validation", "obj_named_range", "obj_freeze", "obj_extension_data",
-        "obj_cell_types", "obj_cell_behaviors",
+        "obj_cell_types", "obj_cell_behaviors", "obj_workbook_properties",
     ] {
         m.insert(k, RestoreSpec { restore: r_object_swap, change_class: Objects, defer: true });
     }
@@ -675,6 +742,10 @@ static RESTORE_REGISTRY: Lazy<HashMap<&'static str, RestoreSpec>> = Lazy::new(||
     // override-layer swap for the reset sheets. Deferred (re-acquires grid
     // locks); tagged Objects so the frontend fires grid:refresh on undo/redo.
     m.insert("calp_reset", RestoreSpec { restore: r_calp_reset, change_class: Objects, defer: true });
+    // Row/column insert/delete: every feature store's pre-change state for the
+    // sheet (structural_change.rs). Deferred (locks pivot state); Objects so
+    // the frontend refreshes overlays on undo/redo.
+    m.insert("obj_structural_features", RestoreSpec { restore: r_structural_features, change_class: Objects, defer: true });
     m
 });
 
@@ -715,12 +786,10 @@ pub(crate) struct ScriptGridCellsSnapshot {
 
 /// Restore (undo/redo) an off-active-sheet script/AI cell write.
 ///
-/// Writes each captured cell back into `grids[sheet_index]` (and the active
-/// mirror when that sheet happens to be active at undo time), capturing the
+/// Writes each captured cell back into `grids[sheet_index]`, capturing the
 /// CURRENT cells as the symmetric inverse so redo re-applies the post-write
 /// state. No recalc is needed: each restored `Cell` already carries its cached
-/// value. Lock order matches `recalculate_sheet_values` (grid → grids →
-/// active_sheet) to stay deadlock-consistent.
+/// value.
 fn apply_script_grid_cells_restore(
     state: &AppState,
     data: &[u8],
@@ -734,14 +803,11 @@ fn apply_script_grid_cells_restore(
         }
     };
 
-    let mut mirror = state.grid.lock().unwrap();
     let mut grids = state.grids.lock().unwrap();
-    let active_sheet = *state.active_sheet.lock().unwrap();
 
     if snapshot.sheet_index >= grids.len() {
         return;
     }
-    let is_active = snapshot.sheet_index == active_sheet;
 
     let mut inverse_cells: Vec<(u32, u32, Option<engine::Cell>)> =
         Vec::with_capacity(snapshot.cells.len());
@@ -753,21 +819,14 @@ fn apply_script_grid_cells_restore(
         match restore_to {
             Some(cell) => {
                 grids[snapshot.sheet_index].set_cell(*row, *col, cell.clone());
-                if is_active {
-                    mirror.set_cell(*row, *col, cell.clone());
-                }
             }
             None => {
                 grids[snapshot.sheet_index].clear_cell(*row, *col);
-                if is_active {
-                    mirror.clear_cell(*row, *col);
-                }
             }
         }
     }
 
     drop(grids);
-    drop(mirror);
 
     inverse_transaction.add_change(CellChange::CustomRestore {
         kind: "script_grid_cells".to_string(),
@@ -800,32 +859,17 @@ fn apply_report_restore(
     let mut inverse_cells: Vec<(u32, u32, Option<engine::Cell>)> =
         Vec::with_capacity(snapshot.cells.len());
     {
-        let mut mirror = state.grid.lock().unwrap();
         let mut grids = state.grids.lock().unwrap();
-        let active_sheet = *state.active_sheet.lock().unwrap();
-        if snapshot.sheet_index < grids.len() {
-            let is_active = snapshot.sheet_index == active_sheet;
+        if let Some(grid) = grids.get_mut(snapshot.sheet_index) {
             for (row, col, restore_to) in &snapshot.cells {
-                let current = grids[snapshot.sheet_index].get_cell(*row, *col).cloned();
+                let current = grid.get_cell(*row, *col).cloned();
                 inverse_cells.push((*row, *col, current));
                 match restore_to {
-                    Some(cell) => {
-                        grids[snapshot.sheet_index].set_cell(*row, *col, cell.clone());
-                        if is_active {
-                            mirror.set_cell(*row, *col, cell.clone());
-                        }
-                    }
-                    None => {
-                        grids[snapshot.sheet_index].clear_cell(*row, *col);
-                        if is_active {
-                            mirror.clear_cell(*row, *col);
-                        }
-                    }
+                    Some(cell) => grid.set_cell(*row, *col, cell.clone()),
+                    None => grid.clear_cell(*row, *col),
                 }
             }
-            if is_active {
-                mirror.recalculate_bounds();
-            }
+            grid.recalculate_bounds();
         }
     }
 
@@ -904,14 +948,13 @@ fn apply_calp_reset_restore(
         let idx = sheet.sheet_index;
 
         // --- Cells + widths/heights (locks scoped per sheet, in the
-        // set_active_sheet canonical order: grids, active_sheet, grid mirror,
+        // set_active_sheet canonical order: grids, active_sheet,
         // column_widths, row_heights, all_cw, all_rh). The ACTIVE sheet's
         // widths/heights live in the MIRRORS (take-semantics) — capture and
         // restore through them for that sheet.
         let mut inverse = {
             let mut grids = state.grids.lock().unwrap();
             let active = *state.active_sheet.lock().unwrap();
-            let mut mirror = state.grid.lock().unwrap();
             let mut mirror_cw = state.column_widths.lock().unwrap();
             let mut mirror_rh = state.row_heights.lock().unwrap();
             let mut all_cw = state.all_column_widths.lock().unwrap();
@@ -946,6 +989,7 @@ fn apply_calp_reset_restore(
                 restored.set_cell(*row, *col, cell.clone());
             }
             grids[idx] = restored;
+            grids[idx].mark_all_changed();
             if idx < all_cw.len() {
                 all_cw[idx] = sheet.column_widths.clone();
             }
@@ -953,7 +997,6 @@ fn apply_calp_reset_restore(
                 all_rh[idx] = sheet.row_heights.clone();
             }
             if is_active {
-                *mirror = grids[idx].clone();
                 *mirror_cw = sheet.column_widths.clone();
                 *mirror_rh = sheet.row_heights.clone();
                 active_affected = true;
@@ -1146,6 +1189,26 @@ fn apply_hyperlink_restore(
     }
 }
 
+/// Restore a sheet's default column width / row height for undo/redo.
+fn apply_sheet_defaults_restore(state: &AppState, data: &[u8], inverse_transaction: &mut Transaction) {
+    let (sheet, previous): (usize, crate::api_types::SheetDefaults) = match serde_json::from_slice(data) {
+        Ok(v) => v,
+        Err(e) => {
+            eprintln!("[undo] Failed to deserialize sheet defaults: {}", e);
+            return;
+        }
+    };
+    let mut all = state.sheet_defaults.lock().unwrap();
+    while all.len() <= sheet {
+        all.push(crate::api_types::SheetDefaults::default());
+    }
+    let current = std::mem::replace(&mut all[sheet], previous);
+    inverse_transaction.add_change(CellChange::CustomRestore {
+        kind: "sheet_defaults".to_string(),
+        data: serde_json::to_vec(&(sheet, current)).unwrap_or_default(),
+    });
+}
+
 /// Restore default row height or column width for undo/redo.
 fn apply_default_dimension_restore(
     state: &AppState,
@@ -1194,6 +1257,18 @@ pub fn undo(
     slicer_state: State<'_, SlicerState>,
     ribbon_filter_state: State<'_, RibbonFilterState>,
     pane_control_state: State<'_, PaneControlState>,
+) -> UndoResult {
+    undo_internal(&state, &file_state, &user_files_state, &pivot_state, &slicer_state, &ribbon_filter_state, &pane_control_state)
+}
+
+pub(crate) fn undo_internal(
+    state: &AppState,
+    file_state: &FileState,
+    user_files_state: &UserFilesState,
+    pivot_state: &PivotState,
+    slicer_state: &SlicerState,
+    ribbon_filter_state: &RibbonFilterState,
+    pane_control_state: &PaneControlState,
 ) -> UndoResult {
     let transaction = {
         let mut undo_stack = state.undo_stack.lock().unwrap();
@@ -1218,7 +1293,7 @@ pub fn undo(
         }
     };
 
-    apply_changes(&state, &file_state, &user_files_state, &pivot_state, &slicer_state, &ribbon_filter_state, &pane_control_state, transaction, true)
+    apply_changes(state, file_state, user_files_state, pivot_state, slicer_state, ribbon_filter_state, pane_control_state, transaction, true)
 }
 
 /// Perform redo operation.
@@ -1231,6 +1306,18 @@ pub fn redo(
     slicer_state: State<'_, SlicerState>,
     ribbon_filter_state: State<'_, RibbonFilterState>,
     pane_control_state: State<'_, PaneControlState>,
+) -> UndoResult {
+    redo_internal(&state, &file_state, &user_files_state, &pivot_state, &slicer_state, &ribbon_filter_state, &pane_control_state)
+}
+
+pub(crate) fn redo_internal(
+    state: &AppState,
+    file_state: &FileState,
+    user_files_state: &UserFilesState,
+    pivot_state: &PivotState,
+    slicer_state: &SlicerState,
+    ribbon_filter_state: &RibbonFilterState,
+    pane_control_state: &PaneControlState,
 ) -> UndoResult {
     let transaction = {
         let mut undo_stack = state.undo_stack.lock().unwrap();
@@ -1255,7 +1342,7 @@ pub fn redo(
         }
     };
 
-    apply_changes(&state, &file_state, &user_files_state, &pivot_state, &slicer_state, &ribbon_filter_state, &pane_control_state, transaction, false)
+    apply_changes(state, file_state, user_files_state, pivot_state, slicer_state, ribbon_filter_state, pane_control_state, transaction, false)
 }
 
 /// Clear undo/redo history (e.g., when opening a new file).
@@ -1356,13 +1443,6 @@ fn apply_pivot_definition_restore(
                     dest_grid.set_cell(sc.row, sc.col, sc.cell.clone());
                 }
             }
-            let active_sheet = *state.active_sheet.lock().unwrap();
-            if snapshot.dest_sheet_idx == active_sheet {
-                let mut grid = state.grid.lock().unwrap();
-                for sc in &snapshot.overwritten_cells {
-                    grid.set_cell(sc.row, sc.col, sc.cell.clone());
-                }
-            }
         }
     } else {
         eprintln!("[undo] Pivot table {} not found for definition restore", pivot_id);
@@ -1412,17 +1492,6 @@ fn apply_pivot_create_restore(
                     region.start_row, region.start_col,
                     region.end_row, region.end_col,
                 );
-
-                let active_sheet = *state.active_sheet.lock().unwrap();
-                if dest_sheet_idx == active_sheet {
-                    let mut grid = state.grid.lock().unwrap();
-                    for row in region.start_row..=region.end_row {
-                        for col in region.start_col..=region.end_col {
-                            grid.clear_cell(row, col);
-                        }
-                    }
-                    grid.recalculate_bounds();
-                }
             }
         }
     }
@@ -1902,6 +1971,11 @@ struct FreezeObjSnapshot {
     previous: crate::sheets::FreezeConfig,
 }
 
+#[derive(serde::Serialize, serde::Deserialize)]
+struct WorkbookPropertiesObjSnapshot {
+    previous: crate::api_types::WorkbookProperties,
+}
+
 /// Snapshot for the "obj_extension_data" CustomRestore — the prior JSON value of
 /// one extension's persisted state (None = it had none). Used by the undoable
 /// per-extension persistence path (set_extension_data_undoable).
@@ -2005,6 +2079,8 @@ fn apply_object_swap_restore(
             if let Some(prev) = snap.previous {
                 auto_filters.insert(snap.sheet_index, prev);
             }
+            drop(auto_filters);
+            crate::autofilter::refresh_filtered_totals(state, snap.sheet_index);
         }
         "obj_validation" => {
             let snap: ValidationObjSnapshot = match serde_json::from_slice(data) {
@@ -2081,6 +2157,15 @@ fn apply_object_swap_restore(
             });
             freeze_configs[snap.sheet_index] = snap.previous;
         }
+        "obj_workbook_properties" => {
+            let snap: WorkbookPropertiesObjSnapshot = match serde_json::from_slice(data) {
+                Ok(s) => s,
+                Err(e) => { eprintln!("[undo] bad obj_workbook_properties snapshot: {}", e); return; }
+            };
+            let mut properties = state.workbook_properties.lock().unwrap();
+            let current = std::mem::replace(&mut *properties, snap.previous);
+            push_obj_inverse(inverse_transaction, kind, &WorkbookPropertiesObjSnapshot { previous: current });
+        }
         "obj_extension_data" => {
             let snap: ExtensionDataObjSnapshot = match serde_json::from_slice(data) {
                 Ok(s) => s,
@@ -2226,6 +2311,15 @@ pub(crate) fn record_freeze_undo(
     record_object_undo(state, "obj_freeze", serde_json::to_vec(&snap).unwrap_or_default(), description);
 }
 
+pub(crate) fn record_workbook_properties_undo(
+    state: &AppState,
+    previous: crate::api_types::WorkbookProperties,
+    description: &str,
+) {
+    let snap = WorkbookPropertiesObjSnapshot { previous };
+    record_object_undo(state, "obj_workbook_properties", serde_json::to_vec(&snap).unwrap_or_default(), description);
+}
+
 #[cfg(test)]
 mod restore_registry_tests {
     use super::*;
@@ -2240,6 +2334,7 @@ mod restore_registry_tests {
             ("hyperlink", false, CustomRestoreKind::Other),
             ("default_row_height", false, CustomRestoreKind::Other),
             ("default_column_width", false, CustomRestoreKind::Other),
+            ("sheet_defaults", false, CustomRestoreKind::Other),
             ("pivot_definition", true, CustomRestoreKind::Pivot),
             ("pivot_create", true, CustomRestoreKind::Pivot),
             ("pivot_delete", true, CustomRestoreKind::Pivot),
@@ -2263,8 +2358,10 @@ mod restore_registry_tests {
             ("obj_extension_data", true, CustomRestoreKind::Objects),
             ("obj_cell_types", true, CustomRestoreKind::Objects),
             ("obj_cell_behaviors", true, CustomRestoreKind::Objects),
+            ("obj_workbook_properties", true, CustomRestoreKind::Objects),
             ("report_restore", true, CustomRestoreKind::Objects),
             ("calp_reset", true, CustomRestoreKind::Objects),
+            ("obj_structural_features", true, CustomRestoreKind::Objects),
         ];
         for (kind, defer, class) in expected {
             let spec = restore_spec(kind).unwrap_or_else(|| panic!("missing restore kind: {kind}"));
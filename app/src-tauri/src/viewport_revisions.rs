@@ -0,0 +1,149 @@
+//! FILENAME: app/src-tauri/src/viewport_revisions.rs
+//! PURPOSE: Per-sheet revisions for differential viewport updates. Every
+//! batch of cell writes on a sheet gets a new revision and a log entry with
+//! the written coordinates, so `get_viewport_snapshot` can answer a caller
+//! that already holds revision N with "not modified" or just the cells that
+//! changed since N.
+//! CONTEXT: The grids record their own writes (`Grid::take_changed_cells`);
+//! `seal_viewport_changes` turns whatever accumulated into revisions. It runs
+//! after every IPC command (see `sheet_access::revision_invokes`), after the
+//! command's transaction committed and its dependents recalculated, and
+//! again before a snapshot is built, which picks up writes made outside a
+//! command (scripts, MCP, background tasks). Revision numbers come from one
+//! workbook-wide counter, so a revision issued for one sheet is never valid
+//! for another. The log is bounded; a caller whose revision fell out of it,
+//! or predates a structural change or a reload, gets the full payload.
+
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use engine::CellChangeSet;
+use identity::SheetId;
+
+use crate::AppState;
+
+/// Most logged cell coordinates per sheet. Older entries are dropped first.
+pub const MAX_LOGGED_CELLS_PER_SHEET: usize = 50_000;
+
+/// What changed on a sheet since the revision a caller holds.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RevisionDelta {
+    /// The caller is up to date.
+    NotModified,
+    /// Only these cells changed.
+    Cells(HashSet<(u32, u32)>),
+    /// The log cannot tell; send everything.
+    Full,
+}
+
+#[derive(Debug, Default)]
+struct SheetChangeLog {
+    revision: u64,
+    /// Oldest revision the log can diff from.
+    floor: u64,
+    /// (revision, cells written in it), oldest first.
+    entries: VecDeque<(u64, Vec<(u32, u32)>)>,
+    cell_count: usize,
+}
+
+/// Revision state of every sheet, keyed by SheetId so it follows sheets that
+/// are moved or renamed.
+#[derive(Debug, Default)]
+pub struct ViewportRevisions {
+    last_revision: u64,
+    sheets: HashMap<SheetId, SheetChangeLog>,
+}
+
+impl ViewportRevisions {
+    fn next_revision(&mut self) -> u64 {
+        self.last_revision += 1;
+        self.last_revision
+    }
+
+    fn sheet(&mut self, sheet: SheetId) -> &mut SheetChangeLog {
+        if !self.sheets.contains_key(&sheet) {
+            let revision = self.next_revision();
+            self.sheets.insert(sheet, SheetChangeLog { revision, floor: revision, ..Default::default() });
+        }
+        self.sheets.get_mut(&sheet).unwrap()
+    }
+
+    /// Current revision of `sheet`.
+    pub fn revision(&mut self, sheet: SheetId) -> u64 {
+        self.sheet(sheet).revision
+    }
+
+    /// Log `changes` on `sheet` under a new revision. Overflowed or oversized
+    /// change sets restart the log, so every older revision gets a full payload.
+    pub fn record(&mut self, sheet: SheetId, changes: CellChangeSet) {
+        let revision = self.next_revision();
+        let log = self.sheet(sheet);
+        log.revision = revision;
+        if changes.overflowed || changes.cells.len() > MAX_LOGGED_CELLS_PER_SHEET {
+            log.floor = revision;
+            log.entries.clear();
+            log.cell_count = 0;
+            return;
+        }
+        log.cell_count += changes.cells.len();
+        log.entries.push_back((revision, changes.cells.into_iter().collect()));
+        while log.cell_count > MAX_LOGGED_CELLS_PER_SHEET {
+            let Some((dropped, cells)) = log.entries.pop_front() else { break };
+            log.cell_count -= cells.len();
+            log.floor = dropped;
+        }
+    }
+
+    /// Restart every sheet's log (another workbook was loaded).
+    pub fn reset_all(&mut self) {
+        let sheets: Vec<SheetId> = self.sheets.keys().copied().collect();
+        for sheet in sheets {
+            self.record(sheet, CellChangeSet { overflowed: true, ..Default::default() });
+        }
+    }
+
+    /// Forget sheets that no longer exist.
+    pub fn retain_sheets(&mut self, sheets: &[SheetId]) {
+        self.sheets.retain(|id, _| sheets.contains(id));
+    }
+
+    /// What changed on `sheet` since `known`. A revision this sheet never
+    /// issued (another sheet's, one from before a reset, or one dropped from
+    /// the log) yields `Full`.
+    pub fn delta_since(&mut self, sheet: SheetId, known: Option<u64>) -> RevisionDelta {
+        let log = self.sheet(sheet);
+        let Some(known) = known else { return RevisionDelta::Full };
+        if known == log.revision {
+            return RevisionDelta::NotModified;
+        }
+        let covered = known == log.floor || log.entries.iter().any(|(revision, _)| *revision == known);
+        if !covered {
+            return RevisionDelta::Full;
+        }
+        let cells = log
+            .entries
+            .iter()
+            .filter(|(revision, _)| *revision > known)
+            .flat_map(|(_, cells)| cells.iter().copied())
+            .collect();
+        RevisionDelta::Cells(cells)
+    }
+}
+
+/// Turn the cell writes every grid accumulated into revisions: the commit
+/// point of the viewport log. UI side-effects of the written cells are
+/// committed first (`commit_ui_effects`), so fills they change are part of
+/// the same revision.
+pub(crate) fn seal_viewport_changes(state: &AppState) {
+    crate::commit_ui_effects(state);
+    let sheet_ids = state.sheet_ids.lock().unwrap().clone();
+    let mut revisions = state.viewport_revisions.lock().unwrap();
+    let mut grids = state.grids.lock().unwrap();
+    for (grid, sheet) in grids.iter_mut().zip(&sheet_ids) {
+        let changes = grid.take_changed_cells();
+        if !changes.is_empty() {
+            revisions.record(*sheet, changes);
+        }
+    }
+    drop(grids);
+    revisions.retain_sheets(&sheet_ids);
+}
@@ -57,17 +57,12 @@ impl TestHarness {
 
     /// Set a cell value directly.
     pub fn set_cell(&self, row: u32, col: u32, cell: Cell) {
-        let mut grid = self.state.grid.lock().unwrap();
-        let mut grids = self.state.grids.lock().unwrap();
-        grid.set_cell(row, col, cell);
-        if !grids.is_empty() {
-            grids[0].set_cell(row, col, grid.get_cell(row, col).unwrap().clone());
-        }
+        self.state.get_active_grid().set_cell(row, col, cell);
     }
 
     /// Get cell value (raw).
     pub fn get_cell_value(&self, row: u32, col: u32) -> Option<CellValue> {
-        let grid = self.state.grid.lock().unwrap();
+        let grid = self.state.get_active_grid();
         grid.get_cell(row, col).map(|c| c.value.clone())
     }
 }
@@ -92,7 +87,7 @@ impl TestHarness {
         end: (u32, u32),
         has_headers: bool,
     ) -> (PivotCache, Vec<String>) {
-        let grid = self.state.grid.lock().unwrap();
+        let grid = self.state.get_active_grid();
         let (start_row, start_col) = start;
         let (end_row, end_col) = end;
         let col_count = (end_col - start_col + 1) as usize;
@@ -0,0 +1,129 @@
+//! FILENAME: tests/test_headless.rs
+//! Integration tests for the headless `WorkbookSession` API (feature
+//! `headless`): cell edits and recalculation, tables, pivots and a save /
+//! reopen round trip, all driven through the public API only.
+
+#![cfg(feature = "headless")]
+
+#[allow(dead_code)]
+mod common;
+
+use app_lib::session::AggregationType;
+use app_lib::WorkbookSession;
+use common::SalesFixture;
+
+/// A session with the 12-row sales data in Sheet1!A1:E13.
+fn sales_session() -> WorkbookSession {
+    let session = WorkbookSession::new();
+    for (col, header) in SalesFixture::headers().iter().enumerate() {
+        session.set_cell("Sheet1", &cell_ref(0, col), header).unwrap();
+    }
+    for (i, (region, product, quarter, sales, quantity)) in SalesFixture::data().iter().enumerate() {
+        let row = i + 1;
+        session.set_cell("Sheet1", &cell_ref(row, 0), region).unwrap();
+        session.set_cell("Sheet1", &cell_ref(row, 1), product).unwrap();
+        session.set_cell("Sheet1", &cell_ref(row, 2), quarter).unwrap();
+        session.set_cell("Sheet1", &cell_ref(row, 3), &sales.to_string()).unwrap();
+        session.set_cell("Sheet1", &cell_ref(row, 4), &quantity.to_string()).unwrap();
+    }
+    session
+}
+
+/// A1 reference for a 0-based (row, col) in the first 26 columns.
+fn cell_ref(row: usize, col: usize) -> String {
+    format!("{}{}", (b'A' + col as u8) as char, row + 1)
+}
+
+fn display_number(session: &WorkbookSession, sheet: &str, a1: &str) -> f64 {
+    let text = session.get_display(sheet, a1).unwrap();
+    text.replace(',', "").parse().unwrap_or_else(|_| panic!("{}!{} is not a number: {:?}", sheet, a1, text))
+}
+
+// ============================================================================
+// CELLS & RECALCULATION
+// ============================================================================
+
+#[test]
+fn test_headless_formula_follows_edits() {
+    let session = WorkbookSession::new();
+    session.set_cell("Sheet1", "A1", "2").unwrap();
+    session.set_cell("Sheet1", "B1", "=A1*10").unwrap();
+    assert_eq!(session.get_display("Sheet1", "B1").unwrap(), "20");
+
+    session.set_cell("Sheet1", "A1", "5").unwrap();
+    assert_eq!(session.get_display("Sheet1", "B1").unwrap(), "50");
+
+    session.recalculate().unwrap();
+    assert_eq!(session.get_display("Sheet1", "B1").unwrap(), "50");
+    assert_eq!(session.get_display("Sheet1", "C1").unwrap(), "");
+}
+
+#[test]
+fn test_headless_unknown_sheet_is_an_error() {
+    let session = WorkbookSession::new();
+    assert!(session.set_cell("Nope", "A1", "1").is_err());
+    assert!(session.get_display("Nope", "A1").is_err());
+    // Sheet names match case-insensitively.
+    session.set_cell("sheet1", "A1", "1").unwrap();
+    assert_eq!(session.get_display("SHEET1", "A1").unwrap(), "1");
+}
+
+// ============================================================================
+// TABLES & PIVOTS
+// ============================================================================
+
+#[test]
+fn test_headless_table_takes_header_names() {
+    let session = sales_session();
+    let table = session.create_table("Sheet1", "A1:E13", "Sales", true).unwrap();
+    assert_eq!(table.name, "Sales");
+    let names: Vec<&str> = table.columns.iter().map(|c| c.name.as_str()).collect();
+    assert_eq!(names, SalesFixture::headers());
+
+    // A second table over the same cells is refused.
+    assert!(session.create_table("Sheet1", "A1:B3", "", true).is_err());
+}
+
+#[test]
+fn test_headless_sales_pivot_grand_total() {
+    let session = sales_session();
+    session
+        .create_pivot("Sheet1", "A1:E13", "Sheet1", "G1", &["Region"], &[("Sales", AggregationType::Sum)])
+        .unwrap();
+
+    let labels: Vec<String> = (1..=10)
+        .map(|row| session.get_display("Sheet1", &format!("G{}", row)).unwrap())
+        .collect();
+    for region in ["North", "South", "East"] {
+        assert!(labels.iter().any(|l| l == region), "missing {} in {:?}", region, labels);
+    }
+
+    let grand_row = labels.iter().position(|l| l == "Grand Total").expect("grand total row") + 1;
+    let expected: f64 = SalesFixture::data().iter().map(|r| r.3).sum();
+    let grand = display_number(&session, "Sheet1", &format!("H{}", grand_row));
+    assert!((grand - expected).abs() < 0.01);
+}
+
+// ============================================================================
+// SAVE & REOPEN
+// ============================================================================
+
+#[test]
+fn test_headless_save_and_reopen() {
+    let dir = tempfile::tempdir().unwrap();
+    for file_name in ["book.cala", "book.xlsx"] {
+        let path = dir.path().join(file_name);
+        let session = sales_session();
+        session.set_cell("Sheet1", "F2", "=D2*2").unwrap();
+        session.save(&path).unwrap();
+
+        let reopened = WorkbookSession::open(&path).unwrap();
+        assert_eq!(reopened.sheet_names(), vec!["Sheet1".to_string()]);
+        assert_eq!(reopened.get_display("Sheet1", "A2").unwrap(), "North");
+        assert_eq!(display_number(&reopened, "Sheet1", "F2"), 20000.0, "{}", file_name);
+
+        // Formulas survive the round trip and still follow their inputs.
+        reopened.set_cell("Sheet1", "D2", "100").unwrap();
+        assert_eq!(display_number(&reopened, "Sheet1", "F2"), 200.0, "{}", file_name);
+    }
+}
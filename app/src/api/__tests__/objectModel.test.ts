@@ -30,6 +30,7 @@ const SHEETS = {
     { index: 2, name: "Hidden", visibility: "hidden" as const },
   ],
   activeIndex: 1,
+  revision: 0,
 };
 
 beforeEach(() => {
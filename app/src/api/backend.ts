@@ -313,6 +313,15 @@ export async function getPivotHierarchies<TResponse>(
   return invoke<TResponse>("get_pivot_hierarchies", { pivotId });
 }
 
+/**
+ * Get field-list metadata (data types, samples, header issues) for a pivot's source fields.
+ */
+export async function getPivotSourceFields<TResponse>(
+  pivotId: PivotId
+): Promise<TResponse> {
+  return invoke<TResponse>("get_pivot_source_fields", { pivotId });
+}
+
 /**
  * Add a field to a hierarchy.
  */
@@ -418,6 +427,13 @@ export async function refreshAllPivotTables<TResponse>(): Promise<TResponse> {
   return invoke<TResponse>("refresh_all_pivot_tables", {});
 }
 
+/**
+ * Get the pivot tables whose source cells changed since their last refresh.
+ */
+export async function getStaleObjects<TResponse>(): Promise<TResponse> {
+  return invoke<TResponse>("get_stale_objects", {});
+}
+
 /**
  * Set a pivot item's expand/collapse state.
  */
@@ -619,6 +635,7 @@ export async function sortRange<TResult>(
     matchCase?: boolean;
     hasHeaders?: boolean;
     orientation?: SortOrientation;
+    sortPreservesAnnotations?: boolean;
   }
 ): Promise<TResult> {
   return invoke<TResult>("sort_range", {
@@ -631,6 +648,7 @@ export async function sortRange<TResult>(
       matchCase: options?.matchCase ?? false,
       hasHeaders: options?.hasHeaders ?? false,
       orientation: options?.orientation ?? "rows",
+      sortPreservesAnnotations: options?.sortPreservesAnnotations,
     },
   });
 }
@@ -791,6 +809,8 @@ export interface UniqueValuesResult {
   success: boolean;
   values: UniqueValue[];
   hasBlanks: boolean;
+  /** Distinct values omitted once the backend limit is reached ("and N more"). */
+  moreCount: number;
   error?: string;
 }
 
@@ -961,13 +981,19 @@ export async function isRowFiltered(row: number): Promise<boolean> {
 
 /**
  * Get unique values for a column in the AutoFilter range.
+ * Counts reflect the rows left visible by the other columns' filters.
  * @param columnIndex - Column index (relative to AutoFilter range, 0-based)
+ * @param search - Optional case-insensitive substring to narrow the values
  * @returns Unique values with counts
  */
 export async function getFilterUniqueValues(
-  columnIndex: number
+  columnIndex: number,
+  search?: string
 ): Promise<UniqueValuesResult> {
-  return invoke<UniqueValuesResult>("get_filter_unique_values", { columnIndex });
+  return invoke<UniqueValuesResult>("get_filter_unique_values", {
+    columnIndex,
+    search: search ?? null,
+  });
 }
 
 /**
@@ -1075,6 +1101,8 @@ export interface Hyperlink {
   internalRef?: InternalReference;
   displayText?: string;
   tooltip?: string;
+  /** Internal target whose cells were deleted; the link no longer navigates. */
+  broken?: boolean;
 }
 
 /**
@@ -1407,6 +1435,14 @@ export interface SetCellProtectionParams {
   formulaHidden?: boolean;
 }
 
+/**
+ * Parameters for setting protection on a range of any sheet.
+ */
+export interface SetRangeProtectionParams extends SetCellProtectionParams {
+  /** Target sheet (defaults to the active sheet). */
+  sheetIndex?: number;
+}
+
 /**
  * Protect the current sheet.
  * @param params - Protection parameters
@@ -1521,6 +1557,18 @@ export async function setCellProtection(
   return invoke<ProtectionResult>("set_cell_protection", { params });
 }
 
+/**
+ * Set the locked / formula-hidden flags of a range on a sheet. Large ranges
+ * are stored as a single range record rather than per cell.
+ * @param params - Range protection parameters
+ * @returns Result
+ */
+export async function setRangeProtection(
+  params: SetRangeProtectionParams
+): Promise<ProtectionResult> {
+  return invoke<ProtectionResult>("set_range_protection", { params });
+}
+
 /**
  * Get cell protection for a specific cell.
  * @param row - Row index (0-based)
@@ -2447,8 +2495,20 @@ export interface TableColumn {
   totalsRowFunction: TotalsRowFunction;
   totalsRowFormula?: string;
   calculatedFormula?: string;
+  /** Enforced data type of typed values (absent = any) */
+  dataType?: ColumnDataType;
 }
 
+/**
+ * Data type a table column accepts.
+ */
+export type ColumnDataType = "number" | "date" | "text" | "boolean";
+
+/**
+ * What happens to typed values that break a column's data type.
+ */
+export type DataTypePolicy = "reject" | "coerce";
+
 /**
  * A table definition.
  */
@@ -2464,6 +2524,7 @@ export interface Table {
   styleOptions: TableStyleOptions;
   styleName: string;
   autoFilterId?: number;
+  dataTypePolicy?: DataTypePolicy;
 }
 
 /**
@@ -2476,11 +2537,19 @@ export interface ComputedCell {
   formula?: string | null;
 }
 
+export interface NonconformingCell {
+  row: number;
+  col: number;
+  display: string;
+}
+
 export interface TableResult {
   success: boolean;
   table?: Table;
   error?: string;
   computedCells?: ComputedCell[];
+  /** Cells that do not match a column's new data type */
+  nonconformingCells?: NonconformingCell[];
 }
 
 /**
@@ -2547,6 +2616,16 @@ export interface SetTotalsRowFunctionParams {
   customFormula?: string;
 }
 
+/**
+ * Parameters for setting a column's data type.
+ */
+export interface SetColumnDataTypeParams {
+  tableId: string;
+  columnName: string;
+  /** null removes the restriction */
+  dataType: ColumnDataType | null;
+}
+
 /**
  * Create a new table.
  * @param params - Table creation parameters
@@ -2643,6 +2722,30 @@ export async function setTotalsRowFunction(
   return invoke<TableResult>("set_totals_row_function", { params });
 }
 
+/**
+ * Set or clear the data type of a table column.
+ * @param params - Column and data type
+ * @returns Result with the updated table and the cells that do not match the new type
+ */
+export async function setTableColumnDataType(
+  params: SetColumnDataTypeParams
+): Promise<TableResult> {
+  return invoke<TableResult>("set_table_column_data_type", { params });
+}
+
+/**
+ * Choose whether values that break a column's data type are rejected or coerced.
+ * @param tableId - ID of the table
+ * @param policy - Enforcement policy
+ * @returns Result with the updated table
+ */
+export async function setTableDataTypePolicy(
+  tableId: string,
+  policy: DataTypePolicy
+): Promise<TableResult> {
+  return invoke<TableResult>("set_table_data_type_policy", { tableId, policy });
+}
+
 /**
  * Toggle totals row visibility.
  * @param tableId - ID of the table
@@ -3012,6 +3115,42 @@ export interface EvalStepState {
   stepInTarget: string | null;
   evaluationResult: string | null;
   error: string | null;
+  /** Id of the underlined node; stable across steps within a frame. */
+  nodeId: string | null;
+  /** References read by the underlined node, resolved before evaluation. */
+  references: EvalNodeInfo[];
+  /** Evaluated nodes of the current frame whose value is an array. */
+  arrayResults: EvalNodeInfo[];
+  stepsUsed: number;
+  stepLimit: number;
+}
+
+/** Truncated, row-major preview of a range or array value. */
+export interface EvalValuePreview {
+  rows: number;
+  cols: number;
+  total: number;
+  values: string[];
+  truncated: boolean;
+}
+
+/** A reference or array-valued node in the Evaluate Formula dialog. */
+export interface EvalNodeInfo {
+  nodeId: string;
+  label: string;
+  /** Resolved reference, e.g. "Sheet1!$A$1:$A$500"; null for arrays. */
+  address: string | null;
+  preview: EvalValuePreview | null;
+}
+
+/** One page of a node's values. */
+export interface EvalNodeExpansion {
+  nodeId: string;
+  rows: number;
+  cols: number;
+  total: number;
+  offset: number;
+  values: string[];
 }
 
 /**
@@ -3061,6 +3200,24 @@ export async function evalFormulaRestart(
   return invoke<EvalStepState>("eval_formula_restart", { sessionId });
 }
 
+/**
+ * Fetch a page of a reference or array node's values (row-major), so large
+ * ranges are expanded only on demand.
+ */
+export async function evalFormulaExpandNode(
+  sessionId: string,
+  nodeId: string,
+  offset: number,
+  limit: number,
+): Promise<EvalNodeExpansion> {
+  return invoke<EvalNodeExpansion>("eval_formula_expand_node", {
+    sessionId,
+    nodeId,
+    offset,
+    limit,
+  });
+}
+
 /**
  * Close and clean up the evaluation session.
  */
@@ -5478,6 +5635,25 @@ export async function setPageSetup(setup: PageSetup): Promise<void> {
   return invoke<void>("set_page_setup", { setup });
 }
 
+/** On-screen view preferences of a sheet, saved with the workbook. */
+export interface SheetViewOptions {
+  /** Zoom factor (1.0 = 100%) */
+  zoom: number;
+  showGridlines: boolean;
+  showHeadings: boolean;
+  rightToLeft: boolean;
+}
+
+/** Get the view options for the active sheet. */
+export async function getSheetViewOptions(): Promise<SheetViewOptions> {
+  return invoke<SheetViewOptions>("get_sheet_view_options", {});
+}
+
+/** Set the view options for the active sheet; returns them as stored (zoom clamped). */
+export async function setSheetViewOptions(options: SheetViewOptions): Promise<SheetViewOptions> {
+  return invoke<SheetViewOptions>("set_sheet_view_options", { options });
+}
+
 /** Get all data needed for printing the active sheet. */
 export async function getPrintData(): Promise<PrintData> {
   return invoke<PrintData>("get_print_data", {});
@@ -5515,6 +5691,16 @@ export async function setPrintArea(
   return invoke<string>("set_print_area", { startRow, startCol, endRow, endCol });
 }
 
+/**
+ * Set a multi-area print area from a multi-rect selection (0-based ranges).
+ * Returns the areas as text (e.g., "A1:F20,H1:J5").
+ */
+export async function setPrintAreas(ranges: Array<{
+  startRow: number; startCol: number; endRow: number; endCol: number;
+}>): Promise<string> {
+  return invoke<string>("set_print_areas", { ranges });
+}
+
 /** Clear the print area for the active sheet. */
 export async function clearPrintArea(): Promise<void> {
   return invoke<void>("clear_print_area", {});
@@ -6028,6 +6214,22 @@ export interface WorkbookProperties {
   created: string;
   /** ISO 8601 date string */
   lastModified: string;
+  lastModifiedBy: string;
+  status: string;
+  company: string;
+  manager: string;
+  hyperlinkBase: string;
+  custom: CustomProperty[];
+}
+
+/** A user-defined document property. */
+export interface CustomProperty {
+  name: string;
+  value:
+    | { type: "text"; value: string }
+    | { type: "number"; value: number }
+    | { type: "bool"; value: boolean }
+    | { type: "date"; value: string };
 }
 
 /** Get workbook document properties. */
@@ -6035,7 +6237,7 @@ export async function getWorkbookProperties(): Promise<WorkbookProperties> {
   return invoke<WorkbookProperties>("get_workbook_properties");
 }
 
-/** Set workbook document properties. Returns updated properties (with refreshed lastModified). */
+/** Set workbook document properties. `created` and `lastModified` are kept: save maintains them. */
 export async function setWorkbookProperties(
   props: WorkbookProperties,
 ): Promise<WorkbookProperties> {
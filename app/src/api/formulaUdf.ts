@@ -82,8 +82,8 @@ function udfValueToJs(v: UdfValue): unknown {
 function jsToUdfValue(x: unknown): UdfValue {
   if (x === null || x === undefined) return { kind: "empty" };
   if (typeof x === "number") {
-    // The engine has no #NUM! error variant; a non-finite result surfaces as
-    // #VALUE! (what udf_to_eval would resolve it to anyway).
+    // A non-finite result surfaces as #VALUE!, the error udf_to_eval falls
+    // back to for anything it does not recognize.
     return Number.isFinite(x)
       ? { kind: "number", value: x }
       : { kind: "error", value: "#VALUE!" };
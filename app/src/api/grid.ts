@@ -70,6 +70,7 @@ import {
   setSplitWindow as backendSetSplitWindow,
   getSplitWindow as backendGetSplitWindow,
   goToSpecial as backendGoToSpecial,
+  findNextCell as backendFindNextCell,
   applyBorderPreset,
   fillRange as backendFillRange,
 } from "../core/lib/tauri-api";
@@ -157,6 +158,14 @@ export type GoToSpecialCriteria =
   | "conditionalFormats"
   | "dataValidation";
 
+/** Which cells findNextCell stops at. */
+export type NavPredicate =
+  | "unlocked"
+  | "hasValidation"
+  | "hasComment"
+  | "hasError"
+  | "hasFormula";
+
 // ============================================================================
 // Scroll / Navigation API
 // ============================================================================
@@ -230,6 +239,20 @@ export async function goToSpecial(
   return await backendGoToSpecial(criteria, range);
 }
 
+/**
+ * Find the next cell that is unlocked (on a protected sheet), validated,
+ * commented, in error or holding a formula, scanning from a cell in Tab
+ * ("right"/"left") or Enter ("down"/"up") order. Returns null when none matches.
+ */
+export async function findNextCell(
+  from: { row: number; col: number },
+  direction: "up" | "down" | "left" | "right",
+  predicate: NavPredicate,
+  options?: { wrap?: boolean }
+): Promise<{ row: number; col: number } | null> {
+  return await backendFindNextCell(from, direction, predicate, options?.wrap ?? false);
+}
+
 // ============================================================================
 // Fill Operations (Ctrl+D, Ctrl+R, etc.)
 // ============================================================================
@@ -144,6 +144,9 @@ export {
   shiftFormulasBatch,
   // Status bar aggregation
   getSelectionAggregations,
+  // Quick Analysis
+  analyzeRange,
+  extractSeriesData,
   // Merge cells
   mergeCells,
   unmergeCells,
@@ -158,6 +161,8 @@ export {
   getDefaultDimensions,
   setDefaultRowHeight,
   setDefaultColumnWidth,
+  getSheetDefaults,
+  setSheetDefaults,
   // Fill
   fillRange,
   // Sorting
@@ -173,6 +178,8 @@ export {
   getAllFunctions,
   getFunctionsByCategory,
   getFunctionTemplate,
+  getFunctionSignature,
+  getCompletions,
 } from "./lib";
 
 export type {
@@ -187,6 +194,14 @@ export type {
   FormulaShiftInput,
   ClipboardData,
   SelectionAggregationResult,
+  ColumnValueType,
+  ColumnProfile,
+  RangeAnalysis,
+  AnalyzeRangeParams,
+  SeriesOrientation,
+  ExtractSeriesParams,
+  SeriesData,
+  SeriesExtraction,
 } from "./lib";
 
 // ============================================================================
@@ -300,12 +315,18 @@ export {
   getNamedRangeForSelection,
   renameNamedRange,
   applyNamesToFormulas,
+  createNamesFromSelection,
+  exportNamedRanges,
+  importNamedRanges,
 } from "./lib";
 
 export type {
   NamedRange,
   NamedRangeResult,
   ApplyNamesResult,
+  BulkNamesResult,
+  NameHeaders,
+  NameCollisionPolicy,
 } from "./lib";
 
 // ============================================================================
@@ -614,10 +635,10 @@ export type { ContextMenuRequestPayload } from "./contextMenuTypes";
 export { freezePanes, loadFreezePanesConfig } from "./grid";
 export { splitWindow, loadSplitWindowConfig, removeSplitWindow } from "./grid";
 export { navigateToCell, navigateToRange } from "./grid";
-export { goToSpecial } from "./grid";
+export { goToSpecial, findNextCell } from "./grid";
 export { borderAround } from "./grid";
 export { fillDown, fillRight, fillUp, fillLeft } from "./grid";
-export type { GoToSpecialResult, GoToSpecialCriteria } from "./grid";
+export type { GoToSpecialResult, GoToSpecialCriteria, NavPredicate } from "./grid";
 
 // Zoom control (programmatic, non-React)
 export { getZoom, setZoomLevel } from "./grid";
@@ -1013,12 +1034,15 @@ export {
   getLocaleSettings,
   setLocale,
   getSupportedLocales,
+  setFunctionLanguage,
+  getFunctionLanguages,
   getCachedLocale,
   onLocaleChanged,
 } from "./locale";
 export type {
   LocaleSettings,
   SupportedLocaleEntry,
+  FunctionLanguageEntry,
 } from "./locale";
 
 // ============================================================================
@@ -1638,6 +1662,7 @@ export {
   canEditCell,
   canPerformAction,
   setCellProtection,
+  setRangeProtection,
   getCellProtection,
   verifyEditRangePassword,
   // Workbook protection
@@ -1661,6 +1686,7 @@ export type {
   ProtectSheetParams,
   AddAllowEditRangeParams,
   SetCellProtectionParams,
+  SetRangeProtectionParams,
   WorkbookProtectionStatus,
   WorkbookProtectionResult,
 } from "./lib";
@@ -1691,11 +1717,15 @@ export {
   evalFormulaStepIn,
   evalFormulaStepOut,
   evalFormulaRestart,
+  evalFormulaExpandNode,
   evalFormulaClose,
 } from "./lib";
 
 export type {
   EvalStepState,
+  EvalNodeInfo,
+  EvalValuePreview,
+  EvalNodeExpansion,
 } from "./lib";
 
 // ============================================================================
@@ -1750,12 +1780,24 @@ export {
   calculateNow,
   recalcWithCube,
   calculateSheet,
+  cancelCalculation,
   getIterationSettings,
   setIterationSettings,
+  getUiEffectConflictPolicy,
+  setUiEffectConflictPolicy,
+  getUiEffectConflicts,
+  getCalculationSettings,
+  updateCalculationSettings,
 } from "./lib";
 
 export type {
   IterationSettings,
+  UiEffectConflictPolicy,
+  UiEffectTarget,
+  UiEffectConflict,
+  CalculationMode,
+  CalculationSettings,
+  MultiThreadSettings,
 } from "./lib";
 
 // ============================================================================
@@ -1907,6 +1949,7 @@ export {
 
 export type {
   WorkbookProperties,
+  CustomProperty,
 } from "./lib";
 
 // ============================================================================
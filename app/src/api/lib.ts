@@ -50,6 +50,8 @@ export {
   getDefaultDimensions,
   setDefaultRowHeight,
   setDefaultColumnWidth,
+  getSheetDefaults,
+  setSheetDefaults,
 
   // Styles
   getStyle,
@@ -69,6 +71,8 @@ export {
   getFunctionsByCategory,
   getAllFunctions,
   getFunctionTemplate,
+  getFunctionSignature,
+  getCompletions,
 
   // Calculation
   setCalculationMode,
@@ -77,12 +81,18 @@ export {
   calculateNow,
   recalcWithCube,
   calculateSheet,
+  cancelCalculation,
   getIterationSettings,
   setIterationSettings,
+  getCalculationSettings,
+  updateCalculationSettings,
   getPrecisionAsDisplayed,
   setPrecisionAsDisplayed,
   getCalculateBeforeSave,
   setCalculateBeforeSave,
+  getUiEffectConflictPolicy,
+  setUiEffectConflictPolicy,
+  getUiEffectConflicts,
 
   // Sheets
   getSheets,
@@ -115,6 +125,11 @@ export {
   commitUndoTransaction,
   cancelUndoTransaction,
 
+  // Change journal
+  setChangeTracking,
+  getChangeHistory,
+  createHistorySheet,
+
   // Find & Replace
   findAll,
   countMatches,
@@ -143,6 +158,9 @@ export {
   getNamedRangeForSelection,
   renameNamedRange,
   applyNamesToFormulas,
+  createNamesFromSelection,
+  exportNamedRanges,
+  importNamedRanges,
 
   // Data validation
   setDataValidation,
@@ -215,6 +233,10 @@ export {
   // Status bar aggregation
   getSelectionAggregations,
 
+  // Quick Analysis
+  analyzeRange,
+  extractSeriesData,
+
   // Auto-recover
   getAutoRecoverSettings,
   setAutoRecoverSettings,
@@ -232,12 +254,18 @@ export type {
   SheetsResult,
   UndoState,
   UndoResult,
+  ChangeEntry,
+  ChangeTrackingState,
   FindResult,
   ReplaceResult,
   FindOptions,
   FreezeConfig as TauriFreezeConfig,
   MergedRegion as TauriMergedRegion,
   MergeResult,
+  MergeOptions,
+  DiscardedCell,
+  DeleteLinesResult,
+  RefErrorCell,
   CellUpdateInput,
   FormulaShiftInput,
   // Grouping types
@@ -251,7 +279,21 @@ export type {
   SheetOutline,
   PreviewResult,
   SelectionAggregationResult,
+  ColumnValueType,
+  ColumnProfile,
+  RangeAnalysis,
+  AnalyzeRangeParams,
+  SeriesOrientation,
+  ExtractSeriesParams,
+  SeriesData,
+  SeriesExtraction,
   IterationSettings,
+  UiEffectConflictPolicy,
+  UiEffectTarget,
+  UiEffectConflict,
+  CalculationMode,
+  CalculationSettings,
+  MultiThreadSettings,
   AutoRecoverSettings,
 } from "../core/lib/tauri-api";
 
@@ -260,6 +302,9 @@ export type {
   NamedRange,
   NamedRangeResult,
   ApplyNamesResult,
+  BulkNamesResult,
+  NameHeaders,
+  NameCollisionPolicy,
 } from "../core/types";
 
 // Data validation type exports
@@ -565,6 +610,7 @@ export {
   canEditCell,
   canPerformAction,
   setCellProtection,
+  setRangeProtection,
   getCellProtection,
   verifyEditRangePassword,
   // Workbook protection
@@ -588,6 +634,7 @@ export type {
   ProtectSheetParams,
   AddAllowEditRangeParams,
   SetCellProtectionParams,
+  SetRangeProtectionParams,
   WorkbookProtectionStatus,
   WorkbookProtectionResult,
 } from "./backend";
@@ -618,11 +665,15 @@ export {
   evalFormulaStepIn,
   evalFormulaStepOut,
   evalFormulaRestart,
+  evalFormulaExpandNode,
   evalFormulaClose,
 } from "./backend";
 
 export type {
   EvalStepState,
+  EvalNodeInfo,
+  EvalValuePreview,
+  EvalNodeExpansion,
 } from "./backend";
 
 // ============================================================================
@@ -678,6 +729,8 @@ export type {
 export {
   getPageSetup,
   setPageSetup,
+  getSheetViewOptions,
+  setSheetViewOptions,
   getPrintData,
   writeBinaryFile,
   insertRowPageBreak,
@@ -686,6 +739,7 @@ export {
   removeColPageBreak,
   resetAllPageBreaks,
   setPrintArea,
+  setPrintAreas,
   clearPrintArea,
   setPrintTitleRows,
   clearPrintTitleRows,
@@ -697,6 +751,7 @@ export {
 export type {
   PageSetup,
   PrintData,
+  SheetViewOptions,
 } from "./backend";
 
 // ============================================================================
@@ -788,4 +843,5 @@ export {
 
 export type {
   WorkbookProperties,
+  CustomProperty,
 } from "./backend";
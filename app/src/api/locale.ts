@@ -28,6 +28,8 @@ export interface LocaleSettings {
   currencySymbol: string;
   /** Currency position: "before" or "after" */
   currencyPosition: "before" | "after";
+  /** Language function names are typed and displayed in, e.g. "en", "de" */
+  functionLanguage: string;
 }
 
 /** A supported locale entry for the settings UI. */
@@ -36,6 +38,12 @@ export interface SupportedLocaleEntry {
   displayName: string;
 }
 
+/** A function-name language entry for the settings UI. */
+export interface FunctionLanguageEntry {
+  language: string;
+  displayName: string;
+}
+
 // ============================================================================
 // Cached State
 // ============================================================================
@@ -43,6 +51,7 @@ export interface SupportedLocaleEntry {
 let cachedLocale: LocaleSettings | null = null;
 
 const LOCALE_OVERRIDE_KEY = "calcula.locale";
+const FUNCTION_LANGUAGE_KEY = "calcula.functionLanguage";
 
 // ============================================================================
 // Locale API
@@ -64,6 +73,12 @@ export async function getLocaleSettings(): Promise<LocaleSettings> {
   } else {
     cachedLocale = await invoke<LocaleSettings>("get_locale_settings");
   }
+  const functionLanguage = localStorage.getItem(FUNCTION_LANGUAGE_KEY);
+  if (functionLanguage && functionLanguage !== cachedLocale.functionLanguage) {
+    cachedLocale = await invoke<LocaleSettings>("set_function_language", {
+      language: functionLanguage,
+    }).catch(() => cachedLocale as LocaleSettings);
+  }
   return cachedLocale;
 }
 
@@ -84,6 +99,29 @@ export async function setLocale(localeId: string): Promise<LocaleSettings> {
   return cachedLocale;
 }
 
+/**
+ * Set the language function names are typed and displayed in ("en", "de").
+ * Formulas are always stored with English names, so files stay portable.
+ * Persists the choice in localStorage and notifies all listeners.
+ */
+export async function setFunctionLanguage(
+  language: string
+): Promise<LocaleSettings> {
+  cachedLocale = await invoke<LocaleSettings>("set_function_language", {
+    language,
+  });
+  localStorage.setItem(FUNCTION_LANGUAGE_KEY, language);
+  emitAppEvent(AppEvents.LOCALE_CHANGED, cachedLocale);
+  return cachedLocale;
+}
+
+/**
+ * List the languages function names can be shown in.
+ */
+export async function getFunctionLanguages(): Promise<FunctionLanguageEntry[]> {
+  return invoke<FunctionLanguageEntry[]>("get_function_languages");
+}
+
 /**
  * List all supported locales for the settings UI dropdown.
  */
@@ -32,6 +32,7 @@ const mSetActive = vi.mocked(setActiveSheet);
 
 const SHEETS = {
   activeIndex: 1,
+  revision: 0,
   sheets: [
     { index: 0, name: "Intro", visibility: "visible", tabColor: undefined },
     { index: 1, name: "Data", visibility: "visible", tabColor: "#FF0000" },
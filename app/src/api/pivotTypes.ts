@@ -627,6 +627,9 @@ export type PivotLayoutType = "compact" | "tabular" | "outline";
 /** Subtotal location type (Excel: SubtotalLocationType) */
 export type SubtotalLocationType = "atTop" | "atBottom" | "off";
 
+/** What a refresh does when the output grows into occupied cells */
+export type GrowPolicy = "overwrite" | "insertCells" | "fail";
+
 /** Aggregation function (Excel: AggregationFunction) */
 export type AggregationFunction =
   | "automatic"
@@ -752,6 +755,8 @@ export interface PivotTableInfo {
   allowMultipleFiltersPerField: boolean;
   enableDataValueEditing: boolean;
   refreshOnOpen: boolean;
+  autoRefresh: boolean;
+  onGrow: GrowPolicy;
   useCustomSortLists: boolean;
   hasHeaders: boolean;
   sourceTableName?: string;
@@ -826,6 +831,37 @@ export interface PivotHierarchiesInfo {
   filterHierarchies: RowColumnHierarchyInfo[];
 }
 
+/** Inferred data type of a pivot source field */
+export type SourceFieldType = "empty" | "number" | "date" | "text" | "boolean" | "mixed";
+
+/** Field-list metadata for one source field */
+export interface SourceFieldDetails {
+  index: number;
+  name: string;
+  dataType: SourceFieldType;
+  /** The first few distinct values, in source order */
+  samples: string[];
+  distinctCount: number;
+  blankCount: number;
+  /** Original header text when the field was renamed (blank or duplicate header) */
+  renamedFrom?: string;
+}
+
+/** A source header cell that was renamed during the cache build */
+export interface HeaderRename {
+  index: number;
+  original: string;
+  name: string;
+  reason: "blank" | "duplicate";
+}
+
+/** Response for getPivotSourceFields */
+export interface PivotSourceFieldsInfo {
+  fields: SourceFieldDetails[];
+  /** Empty when the source header row is healthy */
+  headerRenames: HeaderRename[];
+}
+
 // ============================================================================
 // NEW REQUEST TYPES
 // ============================================================================
@@ -837,6 +873,8 @@ export interface UpdatePivotPropertiesRequest {
   allowMultipleFiltersPerField?: boolean;
   enableDataValueEditing?: boolean;
   refreshOnOpen?: boolean;
+  autoRefresh?: boolean;
+  onGrow?: GrowPolicy;
   useCustomSortLists?: boolean;
 }
 
@@ -1122,6 +1160,7 @@ export interface PivotApi {
   getLayoutRanges(pivotId: PivotId): Promise<PivotLayoutRanges>;
   updateLayout(request: UpdatePivotLayoutRequest): Promise<PivotViewResponse>;
   getHierarchies(pivotId: PivotId): Promise<PivotHierarchiesInfo>;
+  getSourceFields(pivotId: PivotId): Promise<PivotSourceFieldsInfo>;
   addHierarchy(request: AddHierarchyRequest): Promise<PivotViewResponse>;
   removeHierarchy(request: RemoveHierarchyRequest): Promise<PivotViewResponse>;
   moveField(request: MoveFieldRequest): Promise<PivotViewResponse>;
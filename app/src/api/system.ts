@@ -16,13 +16,22 @@ import {
   encryptCurrentFile,
   removeFilePassword,
   registerPasswordPrompt,
+  reloadFile,
+  checkFileExternalChanges,
+  getFileAccessState,
+  isReadOnlyError,
+  exportValuesOnly,
 } from '../core/lib/file-api';
 
 export type {
   PasswordPromptRequest,
   PasswordPromptResult,
+  ExternalFileChange,
+  FileAccessState,
+  ValuesOnlyStrip,
+  ValuesOnlyReport,
 } from '../core/lib/file-api';
-export { ENCRYPTION_STATE_CHANGED } from '../core/lib/file-api';
+export { ENCRYPTION_STATE_CHANGED, FILE_CHANGED_EXTERNALLY } from '../core/lib/file-api';
 
 /**
  * Workspace API Facade.
@@ -50,6 +59,21 @@ export const workspace = {
   /** Gets the current file path, or null if unsaved. */
   getCurrentPath: getCurrentFilePath,
 
+  /** Re-opens the current file from disk. Pass force to discard unsaved changes. */
+  reload: reloadFile,
+
+  /** Whether another program changed the current file on disk since open/save. */
+  checkExternalChanges: checkFileExternalChanges,
+
+  /** Whether the current workbook is open read-only, and why. */
+  getAccessState: getFileAccessState,
+
+  /** Whether an error is the WORKBOOK_READ_ONLY refusal of an edit. */
+  isReadOnlyError,
+
+  /** Exports an .xlsx copy with formulas replaced by their values. */
+  exportValuesOnly,
+
   /** Updates the window title to reflect filename and dirty state. */
   updateTitle: updateWindowTitle,
 
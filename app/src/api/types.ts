@@ -25,13 +25,21 @@ export type {
   StyleDataMap,
   DimensionData,
   DefaultDimensions,
+  SheetDefaults,
 
   // Formatting
   FormattingOptions,
   FormattingResult,
+  FormattingTarget,
 
   // Functions
   FunctionInfo,
+  FunctionArgType,
+  FunctionArgInfo,
+  FunctionSignature,
+  CompletionKind,
+  CompletionItem,
+  CompletionResult,
 
   // Formula references
   FormulaReference,
@@ -10,6 +10,7 @@ import { setViewportDimensions, setAllDimensions, setSelection, setManuallyHidde
 import { invoke } from "@tauri-apps/api/core";
 import { ZOOM_MIN, ZOOM_MAX, ZOOM_STEP } from "../../types";
 import type { Selection, Viewport, VirtualBounds } from "../../types";
+import { columnToLetter } from "../../types";
 import { GridCanvas } from "../Grid";
 import { useActiveGridTheme } from "../../theme/useActiveGridTheme";
 import { InlineEditor } from "../InlineEditor";
@@ -34,6 +35,7 @@ import {
   commitUndoTransaction,
   setSplitWindow as backendSetSplitWindow,
 } from "../../lib/tauri-api";
+import type { RefErrorCell } from "../../lib/tauri-api";
 import { cellEvents } from "../../lib/cellEvents";
 import { getCellFromPixel } from "../../lib/gridRenderer";
 import { calculateFreezePaneLayout, calculateVisibleRange } from "../../lib/gridRenderer/layout/viewport";
@@ -74,6 +76,21 @@ interface SheetState {
  */
 const sheetStatesMap = new Map<number, SheetState>();
 
+/**
+ * Tell the user which formulas a row/column deletion broke. References that
+ * lost all their cells now read #REF!; list a few, count the rest.
+ */
+function warnAboutRefErrors(refErrors: RefErrorCell[]): void {
+  if (refErrors.length === 0) {
+    return;
+  }
+  const shown = refErrors
+    .slice(0, 5)
+    .map((e) => `Sheet ${e.sheetIndex + 1}, ${columnToLetter(e.col)}${e.row + 1}: ${e.formula}`);
+  const more = refErrors.length > shown.length ? `\n...and ${refErrors.length - shown.length} more` : "";
+  alert(`${refErrors.length} formula(s) now return #REF! because they referred to deleted cells:\n\n${shown.join("\n")}${more}`);
+}
+
 function SpreadsheetContent({
   className,
 }: SpreadsheetContentProps): React.ReactElement {
@@ -697,6 +714,7 @@ function SpreadsheetContent({
       });
 
       canvasRef.current?.redraw();
+      warnAboutRefErrors(refErrors);
     } catch (error) {
       const msg = typeof error === "string" ? error : (error as Error)?.message || String(error);
       alert(msg);
@@ -742,6 +760,7 @@ function SpreadsheetContent({
       });
 
       canvasRef.current?.redraw();
+      warnAboutRefErrors(refErrors);
     } catch (error) {
       const msg = typeof error === "string" ? error : (error as Error)?.message || String(error);
       alert(msg);
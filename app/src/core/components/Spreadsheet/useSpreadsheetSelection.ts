@@ -39,7 +39,7 @@ import {
   getAllRowHeights,
   getDefaultDimensions,
 } from "../../lib/tauri-api";
-import type { FormattingOptions } from "../../types";
+import type { FormattingOptions, FormattingTarget } from "../../types";
 import { measureOptimalColumnWidth, measureOptimalRowHeight } from "../../lib/gridRenderer";
 import { getActiveGridTheme } from "../../theme/skinLoader";
 import { checkCellClickInterceptors } from "../../lib/cellClickInterceptors";
@@ -400,14 +400,15 @@ export function useSpreadsheetSelection({
             config.defaultCellHeight,
             r
           );
-          // Excel: an empty row resets to the default height
-          const targetHeight = optimalHeight ?? config.defaultCellHeight;
+          // Excel: an empty row resets to the default height. Clearing the
+          // explicit height (0) keeps it following the sheet default.
+          const targetHeight = optimalHeight ?? 0;
           dispatch(setRowHeight(r, targetHeight));
           await setRowHeightApi(r, targetHeight);
           emitAppEvent(AppEvents.ROW_RESIZED, {
             sheetIndex: sheetContext.activeSheetIndex,
             row: r,
-            height: targetHeight,
+            height: optimalHeight ?? config.defaultCellHeight,
           });
         }
 
@@ -945,18 +946,24 @@ export function useSpreadsheetSelection({
   /**
    * Helper: Get rows and cols arrays from the current selection.
    */
-  const getSelectionRowsCols = useCallback((): { rows: number[]; cols: number[] } | null => {
+  const getSelectionRowsCols = useCallback((): { rows: number[]; cols: number[]; target: FormattingTarget } | null => {
     if (!selection) return null;
     const minRow = Math.min(selection.startRow, selection.endRow);
     const maxRow = Math.max(selection.startRow, selection.endRow);
     const minCol = Math.min(selection.startCol, selection.endCol);
     const maxCol = Math.max(selection.startCol, selection.endCol);
 
+    // Whole columns/rows become line styles on the backend, so the spanning
+    // axis is not enumerated.
+    const target: FormattingTarget =
+      selection.type === "columns" ? { fullColumns: true }
+        : selection.type === "rows" ? { fullRows: true }
+        : {};
     const rows: number[] = [];
     const cols: number[] = [];
-    for (let r = minRow; r <= maxRow; r++) rows.push(r);
-    for (let c = minCol; c <= maxCol; c++) cols.push(c);
-    return { rows, cols };
+    if (!target.fullColumns) for (let r = minRow; r <= maxRow; r++) rows.push(r);
+    if (!target.fullRows) for (let c = minCol; c <= maxCol; c++) cols.push(c);
+    return { rows, cols, target };
   }, [selection]);
 
   /**
@@ -967,7 +974,7 @@ export function useSpreadsheetSelection({
     if (!rc) return;
 
     try {
-      await applyFormatting(rc.rows, rc.cols, formatting);
+      await applyFormatting(rc.rows, rc.cols, formatting, rc.target);
 
       // Refresh canvas to show updated styles
       const canvas = canvasRef.current;
@@ -96,6 +96,18 @@ function encErrorKind(error: unknown): 'needs' | 'wrong' | 'corrupt' | null {
   return null;
 }
 
+/** Whether save_file refused because another program changed the open file. */
+function isExternalChangeError(error: unknown): boolean {
+  const m = error instanceof Error ? error.message : String(error);
+  return m.includes('FILE_CHANGED_EXTERNALLY');
+}
+
+/** Whether a command was refused because the workbook is open read-only. */
+export function isReadOnlyError(error: unknown): boolean {
+  const m = error instanceof Error ? error.message : String(error);
+  return m.includes('WORKBOOK_READ_ONLY');
+}
+
 // ============================================================================
 // Save
 // ============================================================================
@@ -151,7 +163,21 @@ export async function saveFile(password?: string): Promise<string | null> {
 
     if (currentPath) {
       emitAppEvent(AppEvents.BEFORE_SAVE, { path: currentPath });
-      await tracedInvoke('save_file', { path: currentPath, password });
+      try {
+        await tracedInvoke('save_file', { path: currentPath, password });
+      } catch (error) {
+        // A read-only workbook can only be saved under a new name.
+        if (isReadOnlyError(error)) return saveFileAs(password);
+        if (!isExternalChangeError(error)) throw error;
+        // Another program (or a sync client) changed the file since it was
+        // opened: overwriting loses their edits, so ask; otherwise Save As.
+        const overwrite = window.confirm(
+          `"${currentPath}" was changed by another program since you opened it.\n\n` +
+            `OK overwrites those changes. Cancel saves your version under a new name.`,
+        );
+        if (!overwrite) return saveFileAs(password);
+        await tracedInvoke('save_file', { path: currentPath, password, overwrite: true });
+      }
       emitAppEvent(AppEvents.AFTER_SAVE, { path: currentPath });
       emitAppEvent(AppEvents.DIRTY_STATE_CHANGED, { isDirty: false });
       emitAppEvent(ENCRYPTION_STATE_CHANGED);
@@ -166,6 +192,37 @@ export async function saveFile(password?: string): Promise<string | null> {
   }
 }
 
+// ============================================================================
+// Values-only export
+// ============================================================================
+
+/** Metadata categories to leave out of a values-only export. */
+export interface ValuesOnlyStrip {
+  comments?: boolean;
+  names?: boolean;
+  validations?: boolean;
+  hiddenSheets?: boolean;
+}
+
+/** What a values-only export changed in its copy of the workbook. */
+export interface ValuesOnlyReport {
+  formulasFlattened: number;
+  commentsRemoved: number;
+  namesRemoved: number;
+  validationsRemoved: number;
+  hiddenSheetsRemoved: number;
+}
+
+/**
+ * Export an .xlsx copy where every formula is replaced by its value. Prompts
+ * for a path; the open workbook stays as it is. Returns null if cancelled.
+ */
+export async function exportValuesOnly(strip: ValuesOnlyStrip = {}): Promise<ValuesOnlyReport | null> {
+  const path = await save({ filters: [XLSX_FILTER], defaultPath: 'Values.xlsx' });
+  if (!path) return null;
+  return tracedInvoke<ValuesOnlyReport>('export_values_only', { path, strip });
+}
+
 // ============================================================================
 // Open
 // ============================================================================
@@ -194,9 +251,13 @@ export async function openFile(): Promise<CellData[] | null> {
 
 /**
  * Load a workbook from a known path (no file picker). Drives the same
- * encryption unlock flow as {@link openFile}.
+ * encryption unlock flow as {@link openFile}. `readOnly` opens it read-only;
+ * the backend also does so when the file is OS read-only or locked.
  */
-export async function openFileAtPath(path: string): Promise<CellData[] | null> {
+export async function openFileAtPath(
+  path: string,
+  options: { readOnly?: boolean } = {},
+): Promise<CellData[] | null> {
   emitAppEvent(AppEvents.BEFORE_OPEN, { path });
 
   // Try a remembered passphrase first (only encrypted files ever have one).
@@ -207,7 +268,11 @@ export async function openFileAtPath(path: string): Promise<CellData[] | null> {
 
   for (;;) {
     try {
-      const cells = await tracedInvoke<CellData[]>('open_file', { path, password });
+      const cells = await tracedInvoke<CellData[]>('open_file', {
+        path,
+        password,
+        readOnly: options.readOnly ?? false,
+      });
 
       // Success. Persist the passphrase if the user asked us to remember it.
       if (pendingRemember && password) {
@@ -256,6 +321,20 @@ export async function openFileAtPath(path: string): Promise<CellData[] | null> {
   }
 }
 
+/**
+ * Re-open the current file from disk. Without `force` the backend refuses
+ * (RELOAD_DISCARDS_CHANGES) when there are unsaved changes.
+ */
+export async function reloadFile(force = false): Promise<CellData[]> {
+  const path = await getCurrentFilePath();
+  emitAppEvent(AppEvents.BEFORE_OPEN, { path });
+  const cells = await tracedInvoke<CellData[]>('reload_file', { force });
+  emitAppEvent(AppEvents.AFTER_OPEN, { path });
+  emitAppEvent(AppEvents.DIRTY_STATE_CHANGED, { isDirty: false });
+  updateWindowTitle();
+  return cells;
+}
+
 // ============================================================================
 // New
 // ============================================================================
@@ -328,6 +407,31 @@ export async function getCurrentFilePath(): Promise<string | null> {
   return tracedInvoke<string | null>('get_current_file_path', {});
 }
 
+/** The open document as changed on disk by another program (see file-changed-externally). */
+export interface ExternalFileChange {
+  path: string;
+  deleted: boolean;
+  modifiedAt?: string;
+  hasUnsavedChanges: boolean;
+}
+
+/** Event the backend emits when it notices an external change to the open file. */
+export const FILE_CHANGED_EXTERNALLY = 'file-changed-externally';
+
+export async function checkFileExternalChanges(): Promise<ExternalFileChange | null> {
+  return tracedInvoke<ExternalFileChange | null>('check_file_external_changes', {});
+}
+
+/** Read-only state of the open workbook (see get_file_access_state). */
+export interface FileAccessState {
+  readOnly: boolean;
+  reason: 'requested' | 'readOnlyAttribute' | 'lockedByAnotherProcess' | null;
+}
+
+export async function getFileAccessState(): Promise<FileAccessState> {
+  return tracedInvoke<FileAccessState>('get_file_access_state', {});
+}
+
 export async function isFileModified(): Promise<boolean> {
   return tracedInvoke<boolean>('is_file_modified', {});
 }
@@ -338,13 +442,15 @@ export async function markFileModified(): Promise<void> {
 }
 
 /**
- * Update the window title to reflect the current file name and dirty state.
- * Format: "filename - Calcula" or "filename * - Calcula" when dirty.
+ * Update the window title to reflect the current file name, dirty state and
+ * read-only mode. Format: "filename - Calcula", "filename * - Calcula" when
+ * dirty, "filename [Read-Only] - Calcula" when read-only.
  */
 export async function updateWindowTitle(): Promise<void> {
-  const [filePath, isDirty] = await Promise.all([
+  const [filePath, isDirty, access] = await Promise.all([
     getCurrentFilePath(),
     isFileModified(),
+    getFileAccessState(),
   ]);
 
   const fileName = filePath
@@ -352,5 +458,6 @@ export async function updateWindowTitle(): Promise<void> {
     : 'Untitled';
 
   const dirtyIndicator = isDirty ? ' *' : '';
-  document.title = `${fileName}${dirtyIndicator} - Calcula`;
+  const readOnlyBadge = access.readOnly ? ' [Read-Only]' : '';
+  document.title = `${fileName}${readOnlyBadge}${dirtyIndicator} - Calcula`;
 }
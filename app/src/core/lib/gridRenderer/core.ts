@@ -472,7 +472,7 @@ function drawCellTextZone(
         config, viewport, dimensions,
         display: cell?.display ?? "",
         styleIndex, styleCache,
-        hasFormula: !!cell?.formula,
+        hasFormula: cell?.formula?.startsWith("=") === true,
       })) {
         ctx.restore();
         baseX += colWidth;
@@ -955,7 +955,7 @@ export function drawCellText(state: RenderState): void {
         config, viewport, dimensions,
         display: rawDisplay,
         styleIndex, styleCache,
-        hasFormula: !!cell.formula,
+        hasFormula: cell.formula?.startsWith("=") === true,
       })) {
         ctx.restore();
         baseX += colWidth;
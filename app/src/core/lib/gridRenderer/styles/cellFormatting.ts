@@ -20,7 +20,7 @@ export function isNumericValue(value: string): boolean {
  * Determine if a string represents an error value.
  */
 export function isErrorValue(value: string): boolean {
-  const errorPatterns = ["#VALUE!", "#REF!", "#NAME?", "#DIV/0!", "#NULL!", "#N/A", "#NUM!", "#ERROR"];
+  const errorPatterns = ["#VALUE!", "#REF!", "#NAME?", "#DIV/0!", "#NULL!", "#N/A", "#NUM!", "#SPILL", "#ERROR"];
   const upper = value.toUpperCase();
   return errorPatterns.some((pattern) => upper.startsWith(pattern.replace("?", "")));
 }
\ No newline at end of file
@@ -11,9 +11,14 @@ import type {
   StyleData,
   DimensionData,
   DefaultDimensions,
+  SheetDefaults,
   FormattingOptions,
+  FormattingTarget,
   FormattingResult,
   FunctionInfo,
+  FunctionSignature,
+  CompletionResult,
+  FormulaValidation,
   UpdateCellResult,
   SpillRangeInfo,
   UsedRangeResult,
@@ -505,6 +510,22 @@ export async function setDefaultColumnWidth(width: number): Promise<DefaultDimen
   return invoke<DefaultDimensions>("set_default_column_width", { width });
 }
 
+/**
+ * Get a sheet's own default sizes. `getDefaultDimensions` returns the sizes in
+ * effect on the active sheet.
+ */
+export async function getSheetDefaults(sheet: number): Promise<SheetDefaults> {
+  return invoke<SheetDefaults>("get_sheet_defaults", { sheet });
+}
+
+/**
+ * Set a sheet's default sizes (null = follow the workbook default). Columns
+ * and rows with an explicit size keep it.
+ */
+export async function setSheetDefaults(sheet: number, defaults: SheetDefaults): Promise<SheetDefaults> {
+  return invoke<SheetDefaults>("set_sheet_defaults", { sheet, defaults });
+}
+
 // ============================================================================
 // Style Operations
 // ============================================================================
@@ -544,7 +565,8 @@ export async function setCellRichText(
 export async function applyFormatting(
   rows: number[],
   cols: number[],
-  formatting: FormattingOptions
+  formatting: FormattingOptions,
+  target: FormattingTarget = {}
 ): Promise<FormattingResult> {
   console.log(
     "[tauri-api] applyFormatting:",
@@ -580,6 +602,8 @@ export async function applyFormatting(
       fill: formatting.fill,
       locked: formatting.locked,
       formulaHidden: formatting.formulaHidden,
+      fullColumns: target.fullColumns,
+      fullRows: target.fullRows,
     },
   });
   console.log(
@@ -597,7 +621,8 @@ export async function applyFormatting(
         getSelectedSheetIndices(),
         rows,
         cols,
-        formatting
+        formatting,
+        target
       );
       console.log("[tauri-api] Replicated formatting to grouped sheets");
     } catch (err) {
@@ -670,7 +695,8 @@ export async function applyFormattingToSheets(
   sheetIndices: number[],
   rows: number[],
   cols: number[],
-  formatting: FormattingOptions
+  formatting: FormattingOptions,
+  target: FormattingTarget = {}
 ): Promise<void> {
   return invoke<void>("apply_formatting_to_sheets", {
     sheetIndices,
@@ -703,6 +729,8 @@ export async function applyFormattingToSheets(
       fill: formatting.fill,
       locked: formatting.locked,
       formulaHidden: formatting.formulaHidden,
+      fullColumns: target.fullColumns,
+      fullRows: target.fullRows,
     },
   });
 }
@@ -747,11 +775,52 @@ export async function getFunctionTemplate(functionName: string): Promise<string>
   return invoke<string>("get_function_template", { functionName });
 }
 
+export async function getFunctionSignature(
+  functionName: string
+): Promise<FunctionSignature | null> {
+  return invoke<FunctionSignature | null>("get_function_signature", { functionName });
+}
+
+/**
+ * Ranked completions for the token at `cursorPos` in a partial formula typed
+ * into (row, col) of `sheet`, with the span to replace.
+ */
+export async function getCompletions(
+  sheet: number,
+  row: number,
+  col: number,
+  partialFormula: string,
+  cursorPos: number
+): Promise<CompletionResult> {
+  return invoke<CompletionResult>("get_completions", {
+    sheet,
+    row,
+    col,
+    partialFormula,
+    cursorPos,
+  });
+}
+
+/**
+ * Check a formula typed into `row` of `sheet` without entering it: syntax
+ * errors with their position and expected tokens, plus names, tables and
+ * table columns that do not resolve.
+ */
+export async function validateFormula(
+  sheet: number,
+  row: number,
+  formula: string
+): Promise<FormulaValidation> {
+  return invoke<FormulaValidation>("validate_formula", { sheet, row, formula });
+}
+
 // ============================================================================
 // Calculation Mode Operations
 // ============================================================================
 
-export async function setCalculationMode(mode: "automatic" | "manual"): Promise<string> {
+export type CalculationMode = "automatic" | "automaticExceptTables" | "manual";
+
+export async function setCalculationMode(mode: CalculationMode): Promise<string> {
   return invoke<string>("set_calculation_mode", { mode });
 }
 
@@ -803,6 +872,18 @@ export async function calculateSheet(): Promise<CellData[]> {
   return result;
 }
 
+/** Progress of a running calculation (the "calc-progress" event payload). */
+export interface CalcProgress {
+  evaluated: number;
+  total: number;
+}
+
+/** Stop the running calculation; the previous values stay and the pending
+ *  cells remain dirty. Resolves false when no calculation was running. */
+export async function cancelCalculation(): Promise<boolean> {
+  return invoke<boolean>("cancel_calculation");
+}
+
 /**
  * Targeted recalc of GET.CONTROLVALUE dependents after a control/ribbon-filter
  * value change. `changedNames` limits the recalc to formulas bound to those
@@ -845,6 +926,38 @@ export async function setIterationSettings(
   });
 }
 
+// ============================================================================
+// Calculation Settings
+// ============================================================================
+
+export interface MultiThreadSettings {
+  enabled: boolean;
+  /** Maximum calculation threads; null = one per processor. */
+  threadCap: number | null;
+}
+
+export interface CalculationSettings {
+  mode: CalculationMode;
+  recalcBeforeSave: boolean;
+  iterative: IterationSettings;
+  multiThreaded: MultiThreadSettings;
+  precisionAsDisplayed: boolean;
+}
+
+export async function getCalculationSettings(): Promise<CalculationSettings> {
+  return invoke<CalculationSettings>("get_calculation_settings");
+}
+
+/**
+ * Update the workbook calculation settings. Omitted fields keep their value;
+ * an invalid field rejects the whole update.
+ */
+export async function updateCalculationSettings(
+  update: Partial<CalculationSettings>,
+): Promise<CalculationSettings> {
+  return invoke<CalculationSettings>("update_calculation_settings", { update });
+}
+
 // ============================================================================
 // Precision As Displayed
 // ============================================================================
@@ -869,6 +982,50 @@ export async function setCalculateBeforeSave(enabled: boolean): Promise<boolean>
   return invoke<boolean>("set_calculate_before_save", { enabled });
 }
 
+// ============================================================================
+// SET.* Conflicts
+// ============================================================================
+
+/**
+ * How SET.* formulas requesting different values for the same row, column or
+ * cell are settled. "error": none applies and the formulas show #CONFLICT.
+ */
+export type UiEffectConflictPolicy = "error" | "lastWriterWins" | "maxValue";
+
+/** A row height, column width or cell fill set by SET.* formulas. */
+export type UiEffectTarget =
+  | { kind: "rowHeight"; sheetIndex: number; row: number }
+  | { kind: "columnWidth"; sheetIndex: number; col: number }
+  | { kind: "cellFill"; sheetIndex: number; row: number; col: number };
+
+export interface UiEffectConflict {
+  target: UiEffectTarget;
+  /** The formula cells requesting the target, in request order. */
+  sources: { sheetIndex: number; row: number; col: number }[];
+}
+
+export async function getUiEffectConflictPolicy(): Promise<UiEffectConflictPolicy> {
+  return invoke<UiEffectConflictPolicy>("get_ui_effect_conflict_policy");
+}
+
+/**
+ * Set the workbook's conflict policy. Returns the active sheet's row height
+ * and column width changes.
+ */
+export async function setUiEffectConflictPolicy(
+  policy: UiEffectConflictPolicy
+): Promise<DimensionData[]> {
+  return invoke<DimensionData[]>("set_ui_effect_conflict_policy", { policy });
+}
+
+/**
+ * Targets several SET.* formulas request with different values, whatever the
+ * conflict policy.
+ */
+export async function getUiEffectConflicts(): Promise<UiEffectConflict[]> {
+  return invoke<UiEffectConflict[]>("get_ui_effect_conflicts");
+}
+
 // ============================================================================
 // Sheet Operations
 // ============================================================================
@@ -886,6 +1043,38 @@ export interface SheetInfo {
 export interface SheetsResult {
   sheets: SheetInfo[];
   activeIndex: number;
+  /** Workbook revision after the call (see getWorkbookRevision). */
+  revision: number;
+}
+
+/** Payload of a SHEET_NOT_FOUND error: a stale or out-of-range sheet. */
+export interface SheetNotFoundError {
+  code: "SHEET_NOT_FOUND";
+  /** The sheet index or sheet id the command was given. */
+  requested: number | string;
+  currentCount: number;
+}
+
+/** The SHEET_NOT_FOUND payload of a command error, or null for other errors. */
+export function sheetNotFoundError(error: unknown): SheetNotFoundError | null {
+  const m = error instanceof Error ? error.message : String(error);
+  const at = m.indexOf("SHEET_NOT_FOUND {");
+  if (at < 0) return null;
+  try {
+    return JSON.parse(m.slice(at + "SHEET_NOT_FOUND ".length)) as SheetNotFoundError;
+  } catch {
+    return null;
+  }
+}
+
+/** Workbook revision: grows with every edit and when another workbook is loaded. */
+export async function getWorkbookRevision(): Promise<number> {
+  return invoke<number>("get_workbook_revision");
+}
+
+/** Stable sheet ids, parallel to the sheet list. */
+export async function getSheetIds(): Promise<string[]> {
+  return invoke<string[]>("get_sheet_ids");
 }
 
 export async function getSheets(): Promise<SheetsResult> {
@@ -896,7 +1085,8 @@ export async function getActiveSheet(): Promise<number> {
   return invoke<number>("get_active_sheet");
 }
 
-export async function setActiveSheet(index: number): Promise<SheetsResult> {
+/** `index` may also be a sheet id, which survives other sheets being deleted. */
+export async function setActiveSheet(index: number | string): Promise<SheetsResult> {
   return invoke<SheetsResult>("set_active_sheet", { index });
 }
 
@@ -904,7 +1094,8 @@ export async function addSheet(name?: string): Promise<SheetsResult> {
   return invoke<SheetsResult>("add_sheet", { name: name ?? null });
 }
 
-export async function deleteSheet(index: number): Promise<SheetsResult> {
+/** `index` may also be a sheet id (see setActiveSheet). */
+export async function deleteSheet(index: number | string): Promise<SheetsResult> {
   return invoke<SheetsResult>("delete_sheet", { index });
 }
 
@@ -964,15 +1155,30 @@ export async function insertColumns(col: number, count: number): Promise<CellDat
   return result;
 }
 
+/** A formula cell that became #REF! because its referenced cells were deleted. */
+export interface RefErrorCell {
+  sheetIndex: number;
+  row: number;
+  col: number;
+  /** The rewritten formula, including the "=" prefix */
+  formula: string;
+}
+
+export interface DeleteLinesResult {
+  updatedCells: CellData[];
+  /** Formula cells on any sheet that now evaluate to #REF! */
+  refErrors: RefErrorCell[];
+}
+
 /**
  * Delete rows at the specified position, shifting remaining rows up.
  * @param row - The row index where deletion starts
  * @param count - Number of rows to delete
  */
-export async function deleteRows(row: number, count: number): Promise<CellData[]> {
+export async function deleteRows(row: number, count: number): Promise<DeleteLinesResult> {
   console.log(`[tauri-api] deleteRows(${row}, ${count})`);
-  const result = await invoke<CellData[]>("delete_rows", { row, count });
-  console.log(`[tauri-api] deleteRows returned ${result.length} updated cells`);
+  const result = await invoke<DeleteLinesResult>("delete_rows", { row, count });
+  console.log(`[tauri-api] deleteRows returned ${result.updatedCells.length} updated cells, ${result.refErrors.length} #REF! formulas`);
   return result;
 }
 
@@ -981,10 +1187,10 @@ export async function deleteRows(row: number, count: number): Promise<CellData[]
  * @param col - The column index where deletion starts
  * @param count - Number of columns to delete
  */
-export async function deleteColumns(col: number, count: number): Promise<CellData[]> {
+export async function deleteColumns(col: number, count: number): Promise<DeleteLinesResult> {
   console.log(`[tauri-api] deleteColumns(${col}, ${count})`);
-  const result = await invoke<CellData[]>("delete_columns", { col, count });
-  console.log(`[tauri-api] deleteColumns returned ${result.length} updated cells`);
+  const result = await invoke<DeleteLinesResult>("delete_columns", { col, count });
+  console.log(`[tauri-api] deleteColumns returned ${result.updatedCells.length} updated cells, ${result.refErrors.length} #REF! formulas`);
   return result;
 }
 
@@ -1072,6 +1278,64 @@ export async function redo(): Promise<UndoResult> {
   return result;
 }
 
+// ============================================================================
+// Change Journal (session audit trail)
+// ============================================================================
+
+/** One cell change recorded by the change journal. */
+export interface ChangeEntry {
+  seq: number;
+  /** RFC 3339, UTC. */
+  timestamp: string;
+  sheetIndex: number;
+  sheetName: string;
+  row: number;
+  col: number;
+  /** A1 address, e.g. "B3". */
+  cell: string;
+  oldValue: string;
+  oldFormula: string | null;
+  newValue: string;
+  newFormula: string | null;
+  /** Command that committed the change ("undo"/"redo" for those); null outside a command. */
+  command: string | null;
+  /** Undo description of the transaction. */
+  description: string;
+}
+
+export interface ChangeTrackingState {
+  enabled: boolean;
+  capacity: number;
+  entryCount: number;
+}
+
+/**
+ * Turn the session change journal on or off. `capacity` resizes the ring of
+ * retained entries (oldest dropped first).
+ */
+export async function setChangeTracking(enabled: boolean, capacity?: number): Promise<ChangeTrackingState> {
+  return invoke<ChangeTrackingState>("set_change_tracking", { enabled, capacity: capacity ?? null });
+}
+
+/**
+ * Journal entries, oldest first, optionally for one sheet and/or cell ("B3"),
+ * limited to the most recent `limit`.
+ */
+export async function getChangeHistory(
+  options: { sheet?: number; cell?: string; limit?: number } = {},
+): Promise<ChangeEntry[]> {
+  return invoke<ChangeEntry[]>("get_change_history", {
+    sheet: options.sheet ?? null,
+    cell: options.cell ?? null,
+    limit: options.limit ?? null,
+  });
+}
+
+/** Write the journal to a new "Change History" sheet, which becomes active. */
+export async function createHistorySheet(): Promise<SheetsResult> {
+  return invoke<SheetsResult>("create_history_sheet");
+}
+
 // ============================================================================
 // Find & Replace Operations
 // ============================================================================
@@ -1263,6 +1527,29 @@ export async function goToSpecial(
   return await invoke<GoToSpecialResult>("go_to_special", { criteria, searchRange });
 }
 
+// ============================================================================
+// FIND NEXT CELL API
+// ============================================================================
+
+export type NavPredicate = "unlocked" | "hasValidation" | "hasComment" | "hasError" | "hasFormula";
+
+/**
+ * Find the next cell matching a predicate. "right"/"left" scan row by row
+ * (Tab/Shift+Tab), "down"/"up" column by column (Enter/Shift+Enter).
+ * @param wrap - Continue from the other end of the sheet when nothing lies ahead
+ * @returns The cell, or null when none matches
+ */
+export async function findNextCell(
+  from: { row: number; col: number },
+  direction: ArrowDirection,
+  predicate: NavPredicate,
+  wrap = false
+): Promise<{ row: number; col: number } | null> {
+  return invoke<{ row: number; col: number } | null>("find_next_cell", {
+    params: { from, direction, predicate, wrap },
+  });
+}
+
 // ============================================================================
 // MERGE CELLS API
 // ============================================================================
@@ -1274,21 +1561,42 @@ export interface MergedRegion {
   endCol: number;
 }
 
+export type MergeMode = "mergeAll" | "mergeAcross";
+
+export type MergeContentPolicy = "keepTopLeft" | "refuseIfMultiple";
+
+export interface MergeOptions {
+  mode?: MergeMode;
+  contentPolicy?: MergeContentPolicy;
+  force?: boolean;
+  center?: boolean;
+}
+
+export interface DiscardedCell {
+  row: number;
+  col: number;
+  display: string;
+  formula: string | null;
+}
+
 export interface MergeResult {
   success: boolean;
   mergedRegions: MergedRegion[];
   updatedCells: CellData[];
+  discardedCells: DiscardedCell[];
 }
 
 /**
  * Merge cells in the specified range.
- * The top-left cell becomes the master cell.
+ * The top-left cell of each region becomes the master cell; values the merge
+ * drops are returned in `discardedCells`.
  */
 export async function mergeCells(
   startRow: number,
   startCol: number,
   endRow: number,
-  endCol: number
+  endCol: number,
+  options?: MergeOptions
 ): Promise<MergeResult> {
   console.log(`[tauri-api] mergeCells(${startRow}, ${startCol}, ${endRow}, ${endCol})`);
   const result = await invoke<MergeResult>("merge_cells", {
@@ -1296,6 +1604,7 @@ export async function mergeCells(
     startCol,
     endRow,
     endCol,
+    options: options ?? null,
   });
   console.log(`[tauri-api] mergeCells result:`, result);
   return result;
@@ -1438,6 +1747,9 @@ import type {
   NamedRange,
   NamedRangeResult,
   ApplyNamesResult,
+  BulkNamesResult,
+  NameHeaders,
+  NameCollisionPolicy,
   DataValidation,
   DataValidationResult,
   DataValidationPrompt,
@@ -1559,6 +1871,43 @@ export async function applyNamesToFormulas(
   });
 }
 
+/**
+ * Define one name per column and/or row of a selection from its header cells
+ * (Excel's "Create from Selection"). Existing names are reported, not replaced.
+ */
+export async function createNamesFromSelection(
+  startRow: number,
+  startCol: number,
+  endRow: number,
+  endCol: number,
+  headers: NameHeaders
+): Promise<BulkNamesResult> {
+  return invoke<BulkNamesResult>("create_names_from_selection", {
+    startRow,
+    startCol,
+    endRow,
+    endCol,
+    headers,
+  });
+}
+
+/**
+ * Export the workbook's named ranges as JSON.
+ */
+export async function exportNamedRanges(): Promise<string> {
+  return invoke<string>("export_named_ranges");
+}
+
+/**
+ * Import named ranges exported from another workbook.
+ */
+export async function importNamedRanges(
+  json: string,
+  policy: NameCollisionPolicy
+): Promise<BulkNamesResult> {
+  return invoke<BulkNamesResult>("import_named_ranges", { json, policy });
+}
+
 // ============================================================================
 // Data Validation
 // ============================================================================
@@ -2250,4 +2599,129 @@ export async function getSelectionAggregations(
     endCol,
     selectionType,
   });
-}
\ No newline at end of file
+}
+
+// ============================================================================
+// Quick Analysis
+// ============================================================================
+
+export type ColumnValueType = "empty" | "numeric" | "text" | "date" | "mixed";
+
+/** Profile of one column of an analyzed range (header row excluded). */
+export interface ColumnProfile {
+  col: number;
+  /** Header text, when the first row was detected as a header. */
+  header: string | null;
+  valueType: ColumnValueType;
+  /** Non-blank cells, errors included. */
+  count: number;
+  blanks: number;
+  errors: number;
+  /** Set for numeric and date columns. */
+  min: number | null;
+  max: number | null;
+  /** Set for numeric columns. */
+  sum: number | null;
+  average: number | null;
+  /** Distinct non-blank values, capped. */
+  distinctCount: number;
+  distinctCapped: boolean;
+}
+
+export interface RangeAnalysis {
+  sheetIndex: number;
+  startRow: number;
+  startCol: number;
+  /** Last row holding data (the requested end row is trimmed to it). */
+  endRow: number;
+  endCol: number;
+  hasHeader: boolean;
+  dataRows: number;
+  columns: ColumnProfile[];
+}
+
+export interface AnalyzeRangeParams {
+  /** Defaults to the active sheet. */
+  sheetIndex?: number;
+  startRow: number;
+  startCol: number;
+  endRow: number;
+  endCol: number;
+}
+
+/** Per-column type, counts and stats for a range, for Quick Analysis suggestions. */
+export async function analyzeRange(params: AnalyzeRangeParams): Promise<RangeAnalysis> {
+  return invoke<RangeAnalysis>("analyze_range", { params });
+}
+
+/** Which axis of the selection holds the series; "auto" picks the one with fewer entries. */
+export type SeriesOrientation = "auto" | "byRows" | "byColumns";
+
+export interface ExtractSeriesParams {
+  /** Defaults to the active sheet. */
+  sheetIndex?: number;
+  startRow: number;
+  startCol: number;
+  endRow: number;
+  endCol: number;
+  orientation?: SeriesOrientation;
+  /** Manually hidden rows; filter- and group-hidden rows are added by the backend. */
+  hiddenRows?: number[];
+  hiddenCols?: number[];
+}
+
+export interface SeriesData {
+  /** Header cell text, or "Series N". */
+  name: string;
+  /** One entry per category; null for blank, text, boolean and error cells. */
+  values: (number | null)[];
+  /** Positions in `values` whose cell holds an error. */
+  errors: number[];
+}
+
+export interface SeriesExtraction {
+  sheetIndex: number;
+  /** The orientation used (never "auto"). */
+  orientation: SeriesOrientation;
+  /** False when `categories` are the positions "1", "2", ... */
+  hasCategoryLabels: boolean;
+  categories: string[];
+  series: SeriesData[];
+}
+
+/** Category labels and numeric series of a selection, skipping hidden rows and columns. */
+export async function extractSeriesData(params: ExtractSeriesParams): Promise<SeriesExtraction> {
+  return invoke<SeriesExtraction>("extract_series_data", { params });
+}
+// ============================================================================
+// Table-less formula auto-extend
+// ============================================================================
+
+/** A formula the adjacent formula column would extend into the entered row. */
+export interface FormulaExtension {
+  row: number;
+  col: number;
+  /** With its leading "=", in the user's locale. */
+  formula: string;
+}
+
+/**
+ * After a value is entered at (row, col), the formulas of adjacent columns
+ * whose three cells above hold the same formula pattern. Empty otherwise.
+ */
+export async function suggestFormulaExtension(
+  sheet: number,
+  row: number,
+  col: number
+): Promise<FormulaExtension[]> {
+  return invoke<FormulaExtension[]>("suggest_formula_extension", { sheet, row, col });
+}
+
+/** Writes the suggested formulas for (row, col) as one undoable batch edit. */
+export async function applyFormulaExtension(
+  sheet: number,
+  row: number,
+  col: number
+): Promise<CellData[]> {
+  return invoke<CellData[]>("apply_formula_extension", { sheet, row, col });
+}
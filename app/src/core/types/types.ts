@@ -302,7 +302,8 @@ export interface CellData {
   display: string;
   /** Color override from custom number format tokens like [Red] */
   displayColor?: string;
-  /** Original formula if cell contains a formula */
+  /** Original formula if cell contains a formula, or the apostrophe-prefixed
+   *  entry ("'00123") for quote-prefixed text */
   formula: string | null;
   /** Style index for looking up formatting */
   styleIndex: number;
@@ -316,6 +317,8 @@ export interface CellData {
   richText?: RichTextRun[];
   /** Accounting layout for split rendering (symbol left, value right) */
   accountingLayout?: AccountingLayout;
+  /** Human-readable explanation when the cell shows an error value */
+  errorDetail?: string;
 }
 
 /** Bounding box of all non-empty cells in the active sheet. */
@@ -372,6 +375,17 @@ export interface DefaultDimensions {
   defaultColumnWidth: number;
 }
 
+/**
+ * A sheet's own default column width and row height (xlsx `<sheetFormatPr>`).
+ * Null follows the workbook default.
+ */
+export interface SheetDefaults {
+  /** Default column width in pixels */
+  defaultColWidth: number | null;
+  /** Default row height in pixels */
+  defaultRowHeight: number | null;
+}
+
 /**
  * Underline style for font rendering (Excel-compatible).
  * Matches Rust's UnderlineStyle enum with camelCase serialization.
@@ -647,6 +661,16 @@ export interface FormattingOptions {
   formulaHidden?: boolean;
 }
 
+/**
+ * Whole-line target for applyFormatting. With fullColumns the cols are entire
+ * columns (rows are ignored) and the formatting is stored as the columns'
+ * default style, so cells entered later inherit it; fullRows likewise.
+ */
+export interface FormattingTarget {
+  fullColumns?: boolean;
+  fullRows?: boolean;
+}
+
 /**
  * Style entry with index and style data.
  */
@@ -679,6 +703,93 @@ export interface FunctionInfo {
   category: string;
 }
 
+/**
+ * Coarse expected type of a function argument, inferred from its name.
+ */
+export type FunctionArgType =
+  | "number"
+  | "text"
+  | "logical"
+  | "date"
+  | "range"
+  | "array"
+  | "criteria"
+  | "lambda"
+  | "any";
+
+/**
+ * One parameter of a built-in function signature.
+ */
+export interface FunctionArgInfo {
+  name: string;
+  optional: boolean;
+  /** Part of the repeating tail (e.g., "number2" in SUM) */
+  variadic: boolean;
+  typeHint: FunctionArgType;
+}
+
+/**
+ * Structured signature of a built-in function, used by argument tooltips.
+ */
+export interface FunctionSignature extends FunctionInfo {
+  args: FunctionArgInfo[];
+  /** Example formula using the required arguments (e.g., "=ROUND(A1, B1)") */
+  example: string;
+}
+
+/**
+ * What a formula completion refers to.
+ */
+export type CompletionKind = "function" | "definedName" | "table" | "column" | "sheet";
+
+/**
+ * One ranked completion for the token at the cursor.
+ */
+export interface CompletionItem {
+  label: string;
+  kind: CompletionKind;
+  /** Text to splice over the replacement span (e.g., "SUM(", "'My Sheet'!") */
+  insertText: string;
+  /** Syntax, refers-to formula, or sheet name, for display */
+  detail: string | null;
+}
+
+/**
+ * Completions plus the span they replace (character offsets, end exclusive).
+ */
+export interface CompletionResult {
+  items: CompletionItem[];
+  replaceStart: number;
+  replaceEnd: number;
+}
+
+/**
+ * What a formula diagnostic reports.
+ */
+export type FormulaIssueKind = "syntax" | "unknownName" | "unknownTable" | "unknownColumn";
+
+/**
+ * One problem in a formula, with the span to underline (character offsets,
+ * end exclusive).
+ */
+export interface FormulaIssue {
+  kind: FormulaIssueKind;
+  message: string;
+  start: number;
+  end: number;
+  /** The offending text; null when the formula ended early */
+  token: string | null;
+  /** What the parser would have accepted instead (e.g., ["("]) */
+  expected: string[];
+}
+
+/**
+ * Result of validateFormula. No issues means the formula is valid.
+ */
+export interface FormulaValidation {
+  issues: FormulaIssue[];
+}
+
 /**
  * Number format presets for the format picker.
  * Phase 6.4: Used by the Ribbon UI.
@@ -698,6 +809,7 @@ export const NUMBER_FORMAT_PRESETS = [
   { id: "date_eu", label: "Date (EU)", example: "15/01/2025" },
   { id: "time_24", label: "Time (24h)", example: "14:30:00" },
   { id: "time_12", label: "Time (12h)", example: "02:30:00 PM" },
+  { id: "datetime", label: "Date & Time", example: "2025-01-15 14:30" },
 ] as const;
 
 /**
@@ -1221,11 +1333,37 @@ export interface MergedRegion {
   endCol: number;
 }
 
+/** How mergeCells divides the selection */
+export type MergeMode = "mergeAll" | "mergeAcross";
+
+/** What mergeCells does with values outside each region's top-left cell */
+export type MergeContentPolicy = "keepTopLeft" | "refuseIfMultiple";
+
+/** Optional mergeCells parameters (omitted = plain Merge Cells) */
+export interface MergeOptions {
+  mode?: MergeMode;
+  contentPolicy?: MergeContentPolicy;
+  /** Merge anyway under "refuseIfMultiple" */
+  force?: boolean;
+  /** Merge & Center */
+  center?: boolean;
+}
+
+/** A value dropped (or that would be dropped) by a merge */
+export interface DiscardedCell {
+  row: number;
+  col: number;
+  display: string;
+  formula: string | null;
+}
+
 /** Result of merge operations */
 export interface MergeResult {
   success: boolean;
   mergedRegions: MergedRegion[];
   updatedCells: CellData[];
+  /** Discarded values; for a refused merge, the values it would discard */
+  discardedCells: DiscardedCell[];
 }
 
 // ============================================================================
@@ -1268,6 +1406,28 @@ export interface ApplyNamesResult {
   cells: CellData[];
 }
 
+/**
+ * Which header cells of a selection name its columns and rows.
+ */
+export type NameHeaders = "topRow" | "leftColumn" | "both";
+
+/**
+ * What an import does with a name the workbook already has.
+ */
+export type NameCollisionPolicy = "skip" | "overwrite" | "rename";
+
+/**
+ * Result of creating or importing several named ranges at once.
+ */
+export interface BulkNamesResult {
+  /** Every name stored, as stored (including overwrites and renames) */
+  created: NamedRange[];
+  /** Names whose previous definition was replaced */
+  overwritten: string[];
+  renamed: { from: string; to: string }[];
+  skipped: { name: string; reason: string }[];
+}
+
 // ============================================================================
 // Data Validation
 // ============================================================================
@@ -1461,6 +1621,8 @@ export interface InvalidCellsResult {
 export interface CellValidationResult {
   isValid: boolean;
   errorAlert: DataValidationErrorAlert | null;
+  /** Replacement input from a table column's Coerce data type policy. */
+  coercedValue?: string | null;
 }
 
 /**
@@ -1978,6 +2140,11 @@ export interface SortRangeParams {
   hasHeaders?: boolean;
   /** Sort orientation (rows or columns). Default: "rows" */
   orientation?: SortOrientation;
+  /**
+   * Move comments, notes, hyperlinks and data validation with their rows
+   * (row sorts only). Default: true when the range lies inside a table.
+   */
+  sortPreservesAnnotations?: boolean;
 }
 
 /**
@@ -2182,6 +2349,8 @@ export interface UniqueValuesResult {
   success: boolean;
   values: UniqueValue[];
   hasBlanks: boolean;
+  /** Distinct values omitted once the backend limit is reached ("and N more"). */
+  moreCount: number;
   error?: string;
 }
 
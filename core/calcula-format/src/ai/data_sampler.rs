@@ -275,7 +275,7 @@ mod tests {
     use engine::cell::Cell;
 
     fn make_cell(value: CellValue) -> Cell {
-        Cell { ast: None, value, style_index: 0, rich_text: None }
+        Cell { ast: None, value, style_index: 0, rich_text: None, quote_prefix: false }
     }
 
     #[test]
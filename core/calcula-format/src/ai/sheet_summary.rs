@@ -261,7 +261,7 @@ mod tests {
             cell.value = value;
             cell
         } else {
-            Cell { ast: None, value, style_index: 0, rich_text: None }
+            Cell { ast: None, value, style_index: 0, rich_text: None, quote_prefix: false }
         }
     }
 
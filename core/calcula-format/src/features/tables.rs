@@ -20,6 +20,8 @@ pub struct TableDef {
     pub columns: Vec<TableColumnDef>,
     pub style_options: TableStyleOptionsDef,
     pub style_name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub data_type_policy: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -32,6 +34,8 @@ pub struct TableColumnDef {
     pub totals_row_formula: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub calculated_formula: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub data_type: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -62,6 +66,7 @@ impl From<&SavedTable> for TableDef {
                 totals_row_function: c.totals_row_function.clone(),
                 totals_row_formula: c.totals_row_formula.clone(),
                 calculated_formula: c.calculated_formula.clone(),
+                data_type: c.data_type.clone(),
             }).collect(),
             style_options: TableStyleOptionsDef {
                 banded_rows: t.style_options.banded_rows,
@@ -73,6 +78,7 @@ impl From<&SavedTable> for TableDef {
                 show_filter_button: t.style_options.show_filter_button,
             },
             style_name: t.style_name.clone(),
+            data_type_policy: t.data_type_policy.clone(),
         }
     }
 }
@@ -93,6 +99,7 @@ impl From<&TableDef> for SavedTable {
                 totals_row_function: c.totals_row_function.clone(),
                 totals_row_formula: c.totals_row_formula.clone(),
                 calculated_formula: c.calculated_formula.clone(),
+                data_type: c.data_type.clone(),
             }).collect(),
             style_options: SavedTableStyleOptions {
                 banded_rows: t.style_options.banded_rows,
@@ -104,6 +111,7 @@ impl From<&TableDef> for SavedTable {
                 show_filter_button: t.style_options.show_filter_button,
             },
             style_name: t.style_name.clone(),
+            data_type_policy: t.data_type_policy.clone(),
         }
     }
 }
@@ -48,6 +48,10 @@ pub struct CellEntry {
     /// Rich text runs for partial formatting within the cell.
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub rt: Option<Vec<RichTextRun>>,
+
+    /// Quote prefix: the text was entered with a leading apostrophe.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub qp: bool,
 }
 
 fn is_null_value(v: &serde_json::Value) -> bool {
@@ -102,6 +106,7 @@ fn saved_cell_to_entry(cell: &SavedCell) -> CellEntry {
         f: cell.formula.clone(),
         e,
         rt: cell.rich_text.clone(),
+        qp: cell.quote_prefix,
     }
 }
 
@@ -156,6 +161,7 @@ fn entry_to_saved_cell(entry: &CellEntry) -> SavedCell {
         formula: entry.f.clone(),
         style_index: 0, // Will be set from styles.json
         rich_text: entry.rt.clone(),
+        quote_prefix: entry.qp,
     }
 }
 
@@ -250,6 +256,7 @@ mod tests {
                 formula: None,
                 style_index: 0,
                 rich_text: None,
+                quote_prefix: false,
             },
         );
         cells.insert(
@@ -259,6 +266,7 @@ mod tests {
                 formula: Some("=B1*2".to_string()),
                 style_index: 1,
                 rich_text: None,
+                quote_prefix: false,
             },
         );
 
@@ -287,6 +295,7 @@ mod tests {
                 formula: None,
                 style_index: 0,
                 rich_text: None,
+                quote_prefix: false,
             },
         );
         // Cell with only style should be kept
@@ -297,6 +306,7 @@ mod tests {
                 formula: None,
                 style_index: 5,
                 rich_text: None,
+                quote_prefix: false,
             },
         );
 
@@ -315,6 +325,7 @@ mod tests {
                 formula: None,
                 style_index: 1,
                 rich_text: None,
+                quote_prefix: false,
             },
         );
         cells.insert(
@@ -324,6 +335,7 @@ mod tests {
                 formula: None,
                 style_index: 1,
                 rich_text: None,
+                quote_prefix: false,
             },
         );
         cells.insert(
@@ -333,6 +345,7 @@ mod tests {
                 formula: None,
                 style_index: 1,
                 rich_text: None,
+                quote_prefix: false,
             },
         );
         cells.insert(
@@ -345,6 +358,7 @@ mod tests {
                 formula: None,
                 style_index: 1,
                 rich_text: None,
+                quote_prefix: false,
             },
         );
 
@@ -377,6 +391,7 @@ mod tests {
                         ..RichTextRun::plain(String::new())
                     },
                 ]),
+                quote_prefix: false,
             },
         );
 
@@ -395,6 +410,34 @@ mod tests {
         assert!(rt[1].superscript);
     }
 
+    #[test]
+    fn test_roundtrip_quote_prefix() {
+        let mut cells = HashMap::new();
+        for (col, text, quoted) in [(0, "00123", true), (1, "=SUM(A1)", true), (2, "plain", false)] {
+            cells.insert(
+                (0, col),
+                SavedCell {
+                    value: SavedCellValue::Text(text.to_string()),
+                    formula: None,
+                    style_index: 0,
+                    rich_text: None,
+                    quote_prefix: quoted,
+                },
+            );
+        }
+
+        let data = cells_to_sheet_data(&cells);
+        let json = serde_json::to_string(&data).unwrap();
+        assert_eq!(json.matches("\"qp\":true").count(), 2);
+        let restored = sheet_data_to_cells(&serde_json::from_str(&json).unwrap());
+
+        assert!(restored[&(0, 0)].quote_prefix);
+        assert!(matches!(&restored[&(0, 0)].value, SavedCellValue::Text(s) if s == "00123"));
+        assert!(restored[&(0, 1)].quote_prefix);
+        assert!(restored[&(0, 1)].formula.is_none());
+        assert!(!restored[&(0, 2)].quote_prefix);
+    }
+
     #[test]
     fn test_rich_text_with_full_formatting_roundtrip() {
         use engine::style::Color;
@@ -432,6 +475,7 @@ mod tests {
                         subscript: true,
                     },
                 ]),
+                        quote_prefix: false,
             },
         );
 
@@ -460,6 +504,7 @@ mod tests {
                 formula: None,
                 style_index: 0,
                 rich_text: None,
+                quote_prefix: false,
             },
         );
 
@@ -478,6 +523,7 @@ mod tests {
                 formula: None,
                 style_index: 0,
                 rich_text: Some(vec![RichTextRun::plain("test".to_string())]),
+                quote_prefix: false,
             },
         );
 
@@ -1,7 +1,8 @@
 //! FILENAME: core/calcula-format/src/sheet_metadata.rs
 //! Per-sheet metadata (metadata.json): merged regions, freeze panes, hidden
-//! rows/cols, tab color, visibility, notes, hyperlinks, page setup and
-//! gridlines. Before this file existed, the .cala format silently dropped
+//! rows/cols, tab color, visibility, notes, hyperlinks, page setup, view
+//! options (gridlines, headings, zoom, direction) and default column width /
+//! row height. Before this file existed, the .cala format silently dropped
 //! all of these on save/reload (found by the save/reload round-trip oracle:
 //! BUG-0018 freeze panes, plus merges/notes/hyperlinks).
 
@@ -10,7 +11,7 @@ use serde::{Deserialize, Serialize};
 use std::collections::HashSet;
 
 /// Sheet-level metadata for a single sheet (metadata.json).
-#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct SheetMetadata {
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
@@ -36,6 +37,42 @@ pub struct SheetMetadata {
     pub page_setup: Option<SavedPageSetup>,
     #[serde(default = "default_true")]
     pub show_gridlines: bool,
+    /// Zoom factor (1.0 = 100%).
+    #[serde(default = "default_zoom", skip_serializing_if = "is_default_zoom")]
+    pub zoom: f64,
+    #[serde(default = "default_true")]
+    pub show_headings: bool,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub right_to_left: bool,
+    /// Sheet default column width in pixels (absent = the workbook default).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default_column_width: Option<f64>,
+    /// Sheet default row height in pixels (absent = the workbook default).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default_row_height: Option<f64>,
+}
+
+impl Default for SheetMetadata {
+    fn default() -> Self {
+        SheetMetadata {
+            merged_regions: Vec::new(),
+            freeze_row: None,
+            freeze_col: None,
+            hidden_rows: Vec::new(),
+            hidden_cols: Vec::new(),
+            tab_color: String::new(),
+            visibility: default_visibility(),
+            notes: Vec::new(),
+            hyperlinks: Vec::new(),
+            page_setup: None,
+            show_gridlines: true,
+            zoom: default_zoom(),
+            show_headings: true,
+            right_to_left: false,
+            default_column_width: None,
+            default_row_height: None,
+        }
+    }
 }
 
 fn default_visibility() -> String {
@@ -46,6 +83,14 @@ fn default_true() -> bool {
     true
 }
 
+fn default_zoom() -> f64 {
+    1.0
+}
+
+fn is_default_zoom(zoom: &f64) -> bool {
+    *zoom == 1.0
+}
+
 impl SheetMetadata {
     pub fn from_sheet(sheet: &Sheet) -> Self {
         let mut hidden_rows: Vec<u32> = sheet.hidden_rows.iter().copied().collect();
@@ -64,6 +109,11 @@ impl SheetMetadata {
             hyperlinks: sheet.hyperlinks.clone(),
             page_setup: sheet.page_setup.clone(),
             show_gridlines: sheet.show_gridlines,
+            zoom: sheet.zoom,
+            show_headings: sheet.show_headings,
+            right_to_left: sheet.right_to_left,
+            default_column_width: sheet.default_column_width,
+            default_row_height: sheet.default_row_height,
         }
     }
 
@@ -80,6 +130,11 @@ impl SheetMetadata {
             && self.hyperlinks.is_empty()
             && self.page_setup.is_none()
             && self.show_gridlines
+            && is_default_zoom(&self.zoom)
+            && self.show_headings
+            && !self.right_to_left
+            && self.default_column_width.is_none()
+            && self.default_row_height.is_none()
     }
 
     pub fn apply_to_sheet(&self, sheet: &mut Sheet) {
@@ -94,6 +149,11 @@ impl SheetMetadata {
         sheet.hyperlinks = self.hyperlinks.clone();
         sheet.page_setup = self.page_setup.clone();
         sheet.show_gridlines = self.show_gridlines;
+        sheet.zoom = self.zoom;
+        sheet.show_headings = self.show_headings;
+        sheet.right_to_left = self.right_to_left;
+        sheet.default_column_width = self.default_column_width;
+        sheet.default_row_height = self.default_row_height;
     }
 }
 
@@ -125,4 +185,36 @@ mod tests {
         assert_eq!(parsed.freeze_row, Some(1));
         assert_eq!(parsed.freeze_col, None);
     }
+
+    #[test]
+    fn test_view_options_roundtrip() {
+        let meta = SheetMetadata {
+            zoom: 1.5,
+            show_headings: false,
+            right_to_left: true,
+            ..Default::default()
+        };
+        assert!(!meta.is_default());
+        let json = serde_json::to_string(&meta).unwrap();
+        let parsed: SheetMetadata = serde_json::from_str(&json).unwrap();
+        assert_eq!((parsed.zoom, parsed.show_headings, parsed.right_to_left), (1.5, false, true));
+        // Files written before these options existed load with the defaults
+        let old: SheetMetadata = serde_json::from_str(r#"{"freezeRow":1}"#).unwrap();
+        assert_eq!((old.zoom, old.show_headings, old.right_to_left), (1.0, true, false));
+    }
+
+    #[test]
+    fn test_default_sizes_roundtrip() {
+        let meta = SheetMetadata {
+            default_column_width: Some(100.0),
+            default_row_height: Some(30.0),
+            ..Default::default()
+        };
+        assert!(!meta.is_default());
+        let json = serde_json::to_string(&meta).unwrap();
+        let parsed: SheetMetadata = serde_json::from_str(&json).unwrap();
+        assert_eq!((parsed.default_column_width, parsed.default_row_height), (Some(100.0), Some(30.0)));
+        let old: SheetMetadata = serde_json::from_str(r#"{"freezeRow":1}"#).unwrap();
+        assert_eq!((old.default_column_width, old.default_row_height), (None, None));
+    }
 }
@@ -2,7 +2,8 @@
 //! Per-sheet style index mapping and style registry serialization.
 //!
 //! styles/registry.json contains the full StyleRegistry (all unique styles).
-//! sheets/X/styles.json maps cell references to style indices.
+//! sheets/X/styles.json maps cell references to style indices, plus the
+//! whole-column and whole-row default styles.
 
 use crate::cell_ref;
 use engine::style::CellStyle;
@@ -15,6 +16,12 @@ use std::collections::{BTreeMap, HashMap};
 pub struct SheetStyles {
     /// Cell reference -> style index. Only non-default (index > 0) entries are stored.
     pub cells: BTreeMap<String, usize>,
+    /// Column index -> default style of the whole column.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub columns: BTreeMap<u32, usize>,
+    /// Row index -> default style of the whole row.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub rows: BTreeMap<u32, usize>,
 }
 
 /// Convert a cell map to SheetStyles (only cells with non-default styles).
@@ -30,6 +37,8 @@ pub fn cells_to_sheet_styles(cells: &HashMap<(u32, u32), SavedCell>) -> SheetSty
 
     SheetStyles {
         cells: style_cells,
+        columns: BTreeMap::new(),
+        rows: BTreeMap::new(),
     }
 }
 
@@ -51,6 +60,7 @@ pub fn apply_sheet_styles(
                         formula: None,
                         style_index,
                         rich_text: None,
+                        quote_prefix: false,
                     },
                 );
             }
@@ -88,6 +98,7 @@ mod tests {
                 formula: None,
                 style_index: 3,
                 rich_text: None,
+                quote_prefix: false,
             },
         );
         cells.insert(
@@ -97,6 +108,7 @@ mod tests {
                 formula: None,
                 style_index: 0, // Default — should not appear in styles.json
                 rich_text: None,
+                quote_prefix: false,
             },
         );
 
@@ -113,6 +125,7 @@ mod tests {
                 formula: None,
                 style_index: 0,
                 rich_text: None,
+                quote_prefix: false,
             },
         );
         loaded_cells.insert(
@@ -122,6 +135,7 @@ mod tests {
                 formula: None,
                 style_index: 0,
                 rich_text: None,
+                quote_prefix: false,
             },
         );
 
@@ -122,9 +122,11 @@ pub fn write_calcula_bytes(workbook: &Workbook) -> Result<Vec<u8>, FormatError>
         zip.start_file(format!("{}/data.json", base_path), options.clone())?;
         zip.write_all(data_json.as_bytes())?;
 
-        // styles.json — cell style index assignments
-        let sheet_styles = cells_to_sheet_styles(&sheet.cells);
-        if !sheet_styles.cells.is_empty() {
+        // styles.json — cell style index assignments and column/row styles
+        let mut sheet_styles = cells_to_sheet_styles(&sheet.cells);
+        sheet_styles.columns = sheet.column_styles.iter().map(|(&c, &s)| (c, s)).collect();
+        sheet_styles.rows = sheet.row_styles.iter().map(|(&r, &s)| (r, s)).collect();
+        if !sheet_styles.cells.is_empty() || !sheet_styles.columns.is_empty() || !sheet_styles.rows.is_empty() {
             let styles_json = serde_json::to_string_pretty(&sheet_styles)?;
             zip.start_file(format!("{}/styles.json", base_path), options.clone())?;
             zip.write_all(styles_json.as_bytes())?;
@@ -320,6 +322,11 @@ pub fn write_calcula_bytes(workbook: &Workbook) -> Result<Vec<u8>, FormatError>
         zip.start_file("workbook_protection.json", options.clone())?;
         zip.write_all(wb_prot_json.as_bytes())?;
     }
+    if workbook.calc_properties != persistence::SavedCalcProperties::default() {
+        let calc_json = serde_json::to_string_pretty(&workbook.calc_properties)?;
+        zip.start_file("calc_properties.json", options.clone())?;
+        zip.write_all(calc_json.as_bytes())?;
+    }
     if !workbook.controls.is_empty() {
         let controls_json = serde_json::to_string_pretty(&workbook.controls)?;
         zip.start_file("controls.json", options.clone())?;
@@ -426,10 +433,14 @@ pub fn read_calcula_bytes(bytes: &[u8]) -> Result<Workbook, FormatError> {
         let mut cells = sheet_data_to_cells(&sheet_data);
 
         // styles.json
+        let mut column_styles = std::collections::HashMap::new();
+        let mut row_styles = std::collections::HashMap::new();
         if let Some(sheet_styles) =
             read_optional_json::<SheetStyles>(&mut archive, &format!("{}/styles.json", base_path))?
         {
             apply_sheet_styles(&mut cells, &sheet_styles);
+            column_styles.extend(sheet_styles.columns);
+            row_styles.extend(sheet_styles.rows);
         }
 
         // layout.json
@@ -454,6 +465,8 @@ pub fn read_calcula_bytes(bytes: &[u8]) -> Result<Workbook, FormatError> {
             cells,
             column_widths: col_widths,
             row_heights: row_heights,
+            column_styles,
+            row_styles,
             styles: style_list.clone(),
             merged_regions: Vec::new(),
             freeze_row: None,
@@ -466,10 +479,15 @@ pub fn read_calcula_bytes(bytes: &[u8]) -> Result<Workbook, FormatError> {
             hyperlinks: Vec::new(),
             page_setup: None,
             show_gridlines: true,
+            zoom: 1.0,
+            show_headings: true,
+            right_to_left: false,
+            default_column_width: None,
+            default_row_height: None,
         };
 
         // metadata.json — merges, freeze, hidden rows/cols, tab color,
-        // visibility, notes, hyperlinks, page setup, gridlines
+        // visibility, notes, hyperlinks, page setup, view options
         if let Some(metadata) = read_optional_json::<crate::sheet_metadata::SheetMetadata>(
             &mut archive,
             &format!("{}/metadata.json", base_path),
@@ -817,6 +835,9 @@ pub fn read_calcula_bytes(bytes: &[u8]) -> Result<Workbook, FormatError> {
         .unwrap_or_default();
     let workbook_protection: Option<serde_json::Value> =
         read_optional_json::<serde_json::Value>(&mut archive, "workbook_protection.json")?;
+    let calc_properties: persistence::SavedCalcProperties =
+        read_optional_json::<persistence::SavedCalcProperties>(&mut archive, "calc_properties.json")?
+            .unwrap_or_default();
 
     // Read user files (files/ prefix)
     let mut user_files = std::collections::HashMap::new();
@@ -890,6 +911,7 @@ pub fn read_calcula_bytes(bytes: &[u8]) -> Result<Workbook, FormatError> {
         outlines,
         sheet_protections,
         workbook_protection,
+        calc_properties,
     })
 }
 
@@ -936,6 +958,7 @@ mod tests {
                 formula: None,
                 style_index: 1,
                 rich_text: None,
+                quote_prefix: false,
             },
         );
         cells.insert(
@@ -945,6 +968,7 @@ mod tests {
                 formula: None,
                 style_index: 1,
                 rich_text: None,
+                quote_prefix: false,
             },
         );
         cells.insert(
@@ -954,6 +978,7 @@ mod tests {
                 formula: None,
                 style_index: 0,
                 rich_text: None,
+                quote_prefix: false,
             },
         );
         cells.insert(
@@ -963,6 +988,7 @@ mod tests {
                 formula: Some("=SUM(C2:C100)".to_string()),
                 style_index: 2,
                 rich_text: None,
+                quote_prefix: false,
             },
         );
         cells.insert(
@@ -972,6 +998,7 @@ mod tests {
                 formula: None,
                 style_index: 0,
                 rich_text: None,
+                quote_prefix: false,
             },
         );
 
@@ -999,6 +1026,8 @@ mod tests {
             cells,
             column_widths: col_widths,
             row_heights: row_heights,
+            column_styles: HashMap::from([(2, 1)]),
+            row_styles: HashMap::from([(5, 2)]),
             styles,
             merged_regions: Vec::new(),
             freeze_row: None,
@@ -1011,6 +1040,11 @@ mod tests {
             hyperlinks: Vec::new(),
             page_setup: None,
             show_gridlines: true,
+            zoom: 1.0,
+            show_headings: true,
+            right_to_left: false,
+            default_column_width: None,
+            default_row_height: None,
         };
 
         Workbook {
@@ -1048,6 +1082,7 @@ mod tests {
             outlines: Vec::new(),
             sheet_protections: Vec::new(),
             workbook_protection: None,
+            calc_properties: persistence::SavedCalcProperties::default(),
         }
     }
 
@@ -1091,6 +1126,10 @@ mod tests {
         assert_eq!(loaded.sheets[0].column_widths[&1], 120.0);
         assert_eq!(loaded.sheets[0].row_heights[&0], 25.0);
 
+        // Check column/row default styles
+        assert_eq!(loaded.sheets[0].column_styles, HashMap::from([(2, 1)]));
+        assert_eq!(loaded.sheets[0].row_styles, HashMap::from([(5, 2)]));
+
         // Check styles
         assert_eq!(loaded.sheets[0].styles.len(), 3);
         assert!(loaded.sheets[0].styles[1].font.bold);
@@ -1379,6 +1418,46 @@ mod tests {
         assert_eq!(loaded.workbook_protection, workbook.workbook_protection);
     }
 
+    #[test]
+    fn test_roundtrip_calc_properties() {
+        let mut workbook = make_test_workbook();
+        workbook.calc_properties.calc_mode = "autoNoTable".to_string();
+        workbook.calc_properties.calc_on_save = false;
+        workbook.calc_properties.iterate = true;
+        workbook.calc_properties.iterate_count = 7;
+        workbook.calc_properties.full_precision = false;
+        workbook.calc_properties.concurrent_manual_count = Some(2);
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("calc.cala");
+        write_calcula(&workbook, &path).unwrap();
+        assert_eq!(read_calcula(&path).unwrap().calc_properties, workbook.calc_properties);
+
+        // Files without the artifact load Excel's defaults.
+        let path = dir.path().join("plain.cala");
+        write_calcula(&make_test_workbook(), &path).unwrap();
+        assert_eq!(read_calcula(&path).unwrap().calc_properties, persistence::SavedCalcProperties::default());
+    }
+
+    #[test]
+    fn test_roundtrip_sheet_view_options() {
+        let mut workbook = make_test_workbook();
+        workbook.sheets[0].zoom = 1.25;
+        workbook.sheets[0].show_headings = false;
+        let mut second = persistence::Sheet::new("Second".to_string());
+        second.show_gridlines = false;
+        second.right_to_left = true;
+        workbook.sheets.push(second);
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("view.cala");
+        write_calcula(&workbook, &path).unwrap();
+        let loaded = read_calcula(&path).unwrap();
+        let view = |s: &persistence::Sheet| (s.zoom, s.show_gridlines, s.show_headings, s.right_to_left);
+        assert_eq!(view(&loaded.sheets[0]), (1.25, true, false, false));
+        assert_eq!(view(&loaded.sheets[1]), (1.0, false, true, true));
+    }
+
     #[test]
     fn test_roundtrip_comments_scenarios_outlines() {
         // Regression (.calp fidelity Wave B): threaded comments, what-if
@@ -1535,6 +1614,7 @@ mod tests {
                     totals_row_function: "none".to_string(),
                     totals_row_formula: None,
                     calculated_formula: None,
+                    data_type: None,
                 },
                 persistence::SavedTableColumn {
                     id: identity::EntityId::from_bytes(identity::generate_uuid_v7()),
@@ -1542,6 +1622,7 @@ mod tests {
                     totals_row_function: "sum".to_string(),
                     totals_row_formula: None,
                     calculated_formula: None,
+                    data_type: Some("number".to_string()),
                 },
             ],
             style_options: persistence::SavedTableStyleOptions {
@@ -1554,6 +1635,7 @@ mod tests {
                 show_filter_button: true,
             },
             style_name: "TableStyleMedium2".to_string(),
+            data_type_policy: Some("coerce".to_string()),
         });
 
         let dir = tempfile::tempdir().unwrap();
@@ -1567,6 +1649,9 @@ mod tests {
         assert_eq!(loaded.tables[0].sheet_id, sheet_id);
         assert_eq!(loaded.tables[0].columns.len(), 2);
         assert_eq!(loaded.tables[0].style_options.banded_rows, true);
+        assert_eq!(loaded.tables[0].columns[0].data_type, None);
+        assert_eq!(loaded.tables[0].columns[1].data_type.as_deref(), Some("number"));
+        assert_eq!(loaded.tables[0].data_type_policy.as_deref(), Some("coerce"));
     }
 
     #[test]
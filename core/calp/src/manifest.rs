@@ -305,6 +305,18 @@ pub struct PublishedSheetMetadata {
     pub page_setup: Option<persistence::SavedPageSetup>,
     #[serde(default = "default_true")]
     pub show_gridlines: bool,
+    /// Zoom factor (1.0 = 100%).
+    #[serde(default = "default_zoom")]
+    pub zoom: f64,
+    #[serde(default = "default_true")]
+    pub show_headings: bool,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub right_to_left: bool,
+    /// Sheet default column width / row height in pixels (None = workbook default).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default_column_width: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default_row_height: Option<f64>,
 }
 
 fn default_visibility() -> String {
@@ -313,6 +325,9 @@ fn default_visibility() -> String {
 fn default_true() -> bool {
     true
 }
+fn default_zoom() -> f64 {
+    1.0
+}
 
 impl Default for PublishedSheetMetadata {
     fn default() -> Self {
@@ -328,6 +343,11 @@ impl Default for PublishedSheetMetadata {
             hyperlinks: Vec::new(),
             page_setup: None,
             show_gridlines: true,
+            zoom: 1.0,
+            show_headings: true,
+            right_to_left: false,
+            default_column_width: None,
+            default_row_height: None,
         }
     }
 }
@@ -347,6 +367,11 @@ impl PublishedSheetMetadata {
             hyperlinks: sheet.hyperlinks.clone(),
             page_setup: sheet.page_setup.clone(),
             show_gridlines: sheet.show_gridlines,
+            zoom: sheet.zoom,
+            show_headings: sheet.show_headings,
+            right_to_left: sheet.right_to_left,
+            default_column_width: sheet.default_column_width,
+            default_row_height: sheet.default_row_height,
         }
     }
 
@@ -336,6 +336,10 @@ pub fn pull(
             cells,
             column_widths,
             row_heights,
+            default_column_width: metadata.default_column_width,
+            default_row_height: metadata.default_row_height,
+            column_styles: HashMap::new(),
+            row_styles: HashMap::new(),
             styles,
             merged_regions: metadata.merged_regions,
             freeze_row: metadata.freeze_row,
@@ -348,6 +352,9 @@ pub fn pull(
             hyperlinks: metadata.hyperlinks,
             page_setup: metadata.page_setup,
             show_gridlines: metadata.show_gridlines,
+            zoom: metadata.zoom,
+            show_headings: metadata.show_headings,
+            right_to_left: metadata.right_to_left,
         };
 
         pulled_sheets.push(PulledSheet {
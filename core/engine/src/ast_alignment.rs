@@ -245,7 +245,7 @@ pub fn mint_all_ids(expr: &mut Expression, registry: &mut IdRegistry) {
         Expression::ImplicitIntersection { operand } => {
             mint_all_ids(operand, registry);
         }
-        Expression::Literal(_) => {}
+        Expression::Literal(_) | Expression::ArrayLiteral { .. } => {}
     }
 }
 
@@ -286,7 +286,7 @@ pub fn all_ids_assigned(expr: &Expression) -> bool {
             entries.iter().all(|(k, v)| all_ids_assigned(k) && all_ids_assigned(v))
         }
         Expression::ImplicitIntersection { operand } => all_ids_assigned(operand),
-        Expression::Literal(_) => true,
+        Expression::Literal(_) | Expression::ArrayLiteral { .. } => true,
     }
 }
 
@@ -350,7 +350,7 @@ fn collect_ids_recursive(expr: &Expression, ids: &mut Vec<RefSiteId>) {
         Expression::ImplicitIntersection { operand } => {
             collect_ids_recursive(operand, ids);
         }
-        Expression::Literal(_) => {}
+        Expression::Literal(_) | Expression::ArrayLiteral { .. } => {}
     }
 }
 
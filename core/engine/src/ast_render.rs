@@ -10,7 +10,7 @@
 //! - Absolute reference markers ($) are preserved
 //! - Sheet names with spaces or apostrophes are quoted
 
-use parser::ast::{BuiltinFunction, Expression, TableSpecifier, Value};
+use parser::ast::{BinaryOperator, BuiltinFunction, Expression, TableSpecifier, Value};
 
 /// Render a formula AST to its canonical string representation.
 /// Does NOT include a leading '=' — the caller adds it if needed for display.
@@ -74,11 +74,37 @@ fn render_expr(expr: &Expression, collapse: bool) -> String {
         }
 
         Expression::BinaryOp { left, op, right } => {
-            format!("{}{}{}", render_expr(left, collapse), op, render_expr(right, collapse))
+            // Parenthesize operands that bind looser than `op` (the AST has no
+            // paren node; grouping is implied by the tree shape). `^` takes a
+            // primary on the left and a unary-or-tighter operand on the right.
+            let prec = binary_precedence(op);
+            let (wrap_left, wrap_right) = if *op == BinaryOperator::Power {
+                (
+                    operator_precedence(left).is_some_and(|p| p < PERCENT_PRECEDENCE),
+                    operator_precedence(right).is_some_and(|p| p < UNARY_PRECEDENCE),
+                )
+            } else {
+                (
+                    operator_precedence(left).is_some_and(|p| p < prec),
+                    operator_precedence(right).is_some_and(|p| p <= prec),
+                )
+            };
+            format!(
+                "{}{}{}",
+                parenthesize(render_expr(left, collapse), wrap_left),
+                op,
+                parenthesize(render_expr(right, collapse), wrap_right),
+            )
+        }
+
+        Expression::UnaryOp { op, operand } if op.is_postfix() => {
+            let wrap = operator_precedence(operand).is_some_and(|p| p < PERCENT_PRECEDENCE);
+            format!("{}{}", parenthesize(render_expr(operand, collapse), wrap), op)
         }
 
         Expression::UnaryOp { op, operand } => {
-            format!("{}{}", op, render_expr(operand, collapse))
+            let wrap = operator_precedence(operand).is_some_and(|p| p < UNARY_PRECEDENCE);
+            format!("{}{}", op, parenthesize(render_expr(operand, collapse), wrap))
         }
 
         Expression::FunctionCall { func, args, .. } => {
@@ -125,6 +151,14 @@ fn render_expr(expr: &Expression, collapse: bool) -> String {
             format!("{{{}}}", inner.join(", "))
         }
 
+        Expression::ArrayLiteral { rows } => {
+            let rows: Vec<String> = rows
+                .iter()
+                .map(|row| row.iter().map(render_value).collect::<Vec<_>>().join(","))
+                .collect();
+            format!("{{{}}}", rows.join(";"))
+        }
+
         Expression::DictLiteral { entries } => {
             let inner: Vec<String> = entries.iter()
                 .map(|(k, v)| format!("{}: {}", render_expr(k, collapse), render_expr(v, collapse)))
@@ -142,6 +176,41 @@ fn render_expr(expr: &Expression, collapse: bool) -> String {
     }
 }
 
+/// Binding strength of a unary minus (between `*`/`/` and `^`).
+const UNARY_PRECEDENCE: u8 = 5;
+/// Postfix `%` binds tighter than `^`.
+const PERCENT_PRECEDENCE: u8 = 7;
+
+/// Binding strength of a binary operator, mirroring the parser's levels.
+fn binary_precedence(op: &BinaryOperator) -> u8 {
+    match op {
+        BinaryOperator::Equal
+        | BinaryOperator::NotEqual
+        | BinaryOperator::LessThan
+        | BinaryOperator::GreaterThan
+        | BinaryOperator::LessEqual
+        | BinaryOperator::GreaterEqual => 1,
+        BinaryOperator::Concat => 2,
+        BinaryOperator::Add | BinaryOperator::Subtract => 3,
+        BinaryOperator::Multiply | BinaryOperator::Divide => 4,
+        BinaryOperator::Power => 6,
+    }
+}
+
+/// Precedence of an operator node; None for atoms (never parenthesized).
+fn operator_precedence(expr: &Expression) -> Option<u8> {
+    match expr {
+        Expression::BinaryOp { op, .. } => Some(binary_precedence(op)),
+        Expression::UnaryOp { op, .. } if op.is_postfix() => Some(PERCENT_PRECEDENCE),
+        Expression::UnaryOp { .. } => Some(UNARY_PRECEDENCE),
+        _ => None,
+    }
+}
+
+fn parenthesize(s: String, wrap: bool) -> String {
+    if wrap { format!("({})", s) } else { s }
+}
+
 /// If `func`/`args` are the named-function invocation marker
 /// `__INVOKE__("Name", lambda, arg1, ...)`, render it back to `Name(arg1, ...)`.
 /// Returns `None` for the inline-lambda shape `__INVOKE__(lambda, args)` (no
@@ -188,7 +257,7 @@ fn render_value(val: &Value) -> String {
     }
 }
 
-fn render_sheet_prefix(sheet: &Option<String>) -> String {
+pub(crate) fn render_sheet_prefix(sheet: &Option<String>) -> String {
     match sheet {
         Some(name) if needs_quoting(name) => format!("'{}'!", name),
         Some(name) => format!("{}!", name),
@@ -34,6 +34,8 @@ pub enum CellError {
     Blocked,    // Code the user refused to run (denied/declined capability) — a
                 // refused value, not a computation error. Transparency: the user
                 // must see #BLOCKED! rather than a stale number or a generic error.
+    Spill,      // A dynamic array result could not spill: a target cell is occupied
+    Num,        // Invalid numeric argument or result (domain, range, no convergence)
 }
 
 /// Represents the calculated result or raw data within a cell.
@@ -131,6 +133,12 @@ pub struct Cell {
     /// bold, italic, color, font, superscript, subscript, etc.
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub rich_text: Option<Vec<RichTextRun>>,
+    /// True when the text was entered with a leading apostrophe (or into a
+    /// Text-formatted cell). The apostrophe itself is not part of `value`;
+    /// it is reproduced in the formula bar and persisted as the xlsx
+    /// `quotePrefix` style attribute.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub quote_prefix: bool,
 }
 
 impl Clone for Cell {
@@ -140,6 +148,7 @@ impl Clone for Cell {
             value: self.value.clone(),
             style_index: self.style_index,
             rich_text: self.rich_text.clone(),
+            quote_prefix: self.quote_prefix,
         }
     }
 }
@@ -151,6 +160,7 @@ impl Cell {
             value: CellValue::Empty,
             style_index: 0,
             rich_text: None,
+            quote_prefix: false,
         }
     }
 
@@ -160,6 +170,7 @@ impl Cell {
             value: CellValue::Number(num),
             style_index: 0,
             rich_text: None,
+            quote_prefix: false,
         }
     }
 
@@ -169,6 +180,16 @@ impl Cell {
             value: CellValue::Text(text),
             style_index: 0,
             rich_text: None,
+            quote_prefix: false,
+        }
+    }
+
+    /// Creates a text cell from quote-prefixed input (`'00123`). The value is
+    /// stored verbatim as text and never parsed as a number or formula.
+    pub fn new_quoted_text(text: String) -> Self {
+        Cell {
+            quote_prefix: true,
+            ..Cell::new_text(text)
         }
     }
 
@@ -180,12 +201,14 @@ impl Cell {
                 value: CellValue::Empty,
                 style_index: 0,
                 rich_text: None,
+                quote_prefix: false,
             },
             Err(_) => Cell {
                 ast: None,
                 value: CellValue::Text(formula),
                 style_index: 0,
                 rich_text: None,
+                quote_prefix: false,
             },
         }
     }
@@ -197,6 +220,7 @@ impl Cell {
             value: CellValue::Empty,
             style_index: 0,
             rich_text: None,
+            quote_prefix: false,
         }
     }
 
@@ -206,6 +230,7 @@ impl Cell {
             value: CellValue::Boolean(value),
             style_index: 0,
             rich_text: None,
+            quote_prefix: false,
         }
     }
 
@@ -291,6 +316,8 @@ impl Cell {
                 CellError::NA => "#N/A".to_string(),
                 CellError::Conflict => "#CONFLICT".to_string(),
                 CellError::Blocked => "#BLOCKED!".to_string(),
+                CellError::Spill => "#SPILL!".to_string(),
+                CellError::Num => "#NUM!".to_string(),
                 other => format!("#{:?}", other).to_uppercase(),
             },
             CellValue::List(items) => format!("[List({})]", items.len()),
@@ -446,4 +473,18 @@ mod tests {
         // rich_text is None, so should not appear in JSON
         assert!(!json.contains("richText"));
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_spill_error_display() {
+        let mut cell = Cell::new_number(0.0);
+        cell.value = CellValue::Error(CellError::Spill);
+        assert_eq!(cell.display_value(), "#SPILL!");
+    }
+
+    #[test]
+    fn test_num_error_display() {
+        let mut cell = Cell::new_number(0.0);
+        cell.value = CellValue::Error(CellError::Num);
+        assert_eq!(cell.display_value(), "#NUM!");
+    }
+}
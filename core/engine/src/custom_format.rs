@@ -283,6 +283,55 @@ pub fn parse_custom_format(format_str: &str) -> Result<ParsedCustomFormat, Strin
     })
 }
 
+/// Parse a format code supplied by a formula (TEXT). The cell-format path is
+/// lenient and renders malformed codes as best it can; a formula must instead
+/// reject what Excel rejects: more than four sections, an unterminated quote
+/// or bracket, and an unknown `[...]` token.
+pub fn parse_format_code(format_str: &str) -> Result<ParsedCustomFormat, String> {
+    let sections = split_sections(format_str);
+    if sections.len() > 4 {
+        return Err("A format code has at most four sections".to_string());
+    }
+    for section in &sections {
+        let chars: Vec<char> = section.chars().collect();
+        let mut i = 0;
+        while i < chars.len() {
+            match chars[i] {
+                // The next character is taken literally
+                '\\' | '_' | '*' => i += 2,
+                '"' => match chars[i + 1..].iter().position(|&c| c == '"') {
+                    Some(n) => i += n + 2,
+                    None => return Err("Unterminated quoted literal".to_string()),
+                },
+                '[' => {
+                    let end = find_bracket_end(&chars, i).ok_or_else(|| "Unterminated bracket token".to_string())?;
+                    let content: String = chars[i + 1..end].iter().collect();
+                    if !is_known_bracket_token(&content) {
+                        return Err(format!("Unknown format token [{}]", content));
+                    }
+                    i = end + 1;
+                }
+                _ => i += 1,
+            }
+        }
+    }
+    parse_custom_format(format_str)
+}
+
+/// Bracket tokens Excel accepts: elapsed time, colors (named or `ColorN`),
+/// conditions and `[$...]` currency/locale tags.
+fn is_known_bracket_token(content: &str) -> bool {
+    let lower = content.to_lowercase();
+    matches!(lower.as_str(), "h" | "hh" | "m" | "mm" | "s" | "ss")
+        || parse_color_name(content).is_some()
+        || lower
+            .strip_prefix("color")
+            .and_then(|n| n.parse::<u8>().ok())
+            .is_some_and(|n| (1..=56).contains(&n))
+        || parse_condition(content).is_some()
+        || content.starts_with('$')
+}
+
 /// Split a format string by unquoted, unescaped semicolons.
 fn split_sections(s: &str) -> Vec<String> {
     let mut sections = Vec::new();
@@ -815,6 +864,11 @@ fn remove_trailing_scale_commas(tokens: &mut Vec<FormatToken>) {
 // FORMATTER — NUMBER
 // ============================================================================
 
+/// Whether `value` would be rendered by a date/time section of `format`.
+pub fn formats_as_datetime(value: f64, format: &ParsedCustomFormat) -> bool {
+    select_section_for_number(value, format).is_datetime
+}
+
 /// Apply a parsed format to a numeric value.
 pub fn apply_custom_format_number(value: f64, format: &ParsedCustomFormat, locale: &LocaleSettings) -> FormatResult {
     // Select the appropriate section
@@ -1313,38 +1367,57 @@ fn render_literals_only(section: &FormatSection) -> String {
 fn format_scientific_section(value: f64, section: &FormatSection) -> FormatResult {
     let num = value.abs();
 
-    // Count digit placeholders after E
-    let (_, dec_places) = count_digit_placeholders(&section.tokens);
-
-    // Find the scientific token to determine show_plus
-    let show_plus = section
+    // The mantissa's placeholders come before the E, the exponent's after it.
+    let e_pos = section
         .tokens
         .iter()
-        .find_map(|t| match t {
-            FormatToken::Scientific { show_plus } => Some(*show_plus),
-            _ => None,
-        })
-        .unwrap_or(true);
-
-    // Format using Rust's scientific notation
-    let formatted = if dec_places > 0 {
-        format!("{:.prec$E}", num, prec = dec_places)
-    } else {
-        format!("{:.0E}", num)
+        .position(|t| matches!(t, FormatToken::Scientific { .. }))
+        .unwrap_or(section.tokens.len());
+    let (mantissa, exponent) = section.tokens.split_at(e_pos);
+    let (int_places, dec_places) = count_digit_placeholders(mantissa);
+    let int_zeros = mantissa
+        .iter()
+        .take_while(|t| !matches!(t, FormatToken::DecimalPoint))
+        .filter(|t| matches!(t, FormatToken::DigitZero))
+        .count();
+    let has_point = mantissa.iter().any(|t| matches!(t, FormatToken::DecimalPoint));
+    let exp_digits = exponent.iter().filter(|t| matches!(t, FormatToken::DigitZero)).count().max(1);
+    let show_plus = match exponent.first() {
+        Some(FormatToken::Scientific { show_plus }) => *show_plus,
+        _ => true,
     };
 
-    // Adjust the E notation format
-    let text = if show_plus {
-        formatted.replace('E', "E+").replace("E+-", "E-")
-    } else {
-        formatted.replace("E+", "E").replace("E-", "E-")
-    };
-
-    let text = if value < 0.0 {
-        format!("-{}", text)
-    } else {
-        text
-    };
+    // The exponent is a multiple of the integer placeholder count, so
+    // "##0.0E+0" is engineering notation and "0.0E+0" keeps one integer digit.
+    let group = int_places.max(1) as i32;
+    let mut exp = if num == 0.0 { 0 } else { (num.log10().floor() as i32).div_euclid(group) * group };
+    let mut scaled = num / 10f64.powi(exp);
+    let factor = 10f64.powi(dec_places as i32);
+    if (scaled * factor).round() / factor >= 10f64.powi(group) {
+        exp += group;
+        scaled = num / 10f64.powi(exp);
+    }
+
+    let digits = format!("{:.prec$}", scaled, prec = dec_places);
+    let (int_part, frac_part) = digits.split_once('.').unwrap_or((&digits, ""));
+    let int_part = if int_part == "0" && int_zeros == 0 { "" } else { int_part };
+    let mut text = String::new();
+    if value < 0.0 {
+        text.push('-');
+    }
+    text.push_str(&"0".repeat(int_zeros.saturating_sub(int_part.len())));
+    text.push_str(int_part);
+    if has_point {
+        text.push('.');
+        text.push_str(frac_part);
+    }
+    text.push('E');
+    if exp < 0 {
+        text.push('-');
+    } else if show_plus {
+        text.push('+');
+    }
+    text.push_str(&format!("{:0width$}", exp.unsigned_abs(), width = exp_digits));
 
     FormatResult {
         text,
@@ -1543,9 +1616,10 @@ fn format_datetime_section(value: f64, section: &FormatSection) -> FormatResult
     let minutes = (total_seconds % 3600) / 60;
     let seconds = total_seconds % 60;
 
-    // For elapsed time formats
-    let total_minutes = total_seconds / 60;
-    let total_hours = total_seconds / 3600;
+    // Elapsed time counts the whole days as well ([h] of 1.5 is 36)
+    let elapsed_seconds = (value.abs() * 86400.0).round() as u64;
+    let total_minutes = elapsed_seconds / 60;
+    let total_hours = elapsed_seconds / 3600;
 
     // Date components
     let date_parts = if days >= 1 {
@@ -1644,7 +1718,7 @@ fn format_datetime_section(value: f64, section: &FormatSection) -> FormatResult
                 result.push_str(&total_minutes.to_string());
             }
             FormatToken::ElapsedSeconds => {
-                result.push_str(&total_seconds.to_string());
+                result.push_str(&elapsed_seconds.to_string());
             }
             FormatToken::Literal(s) => {
                 result.push_str(s);
@@ -1656,6 +1730,10 @@ fn format_datetime_section(value: f64, section: &FormatSection) -> FormatResult
                 // In date/time context, commas are literal
                 result.push(',');
             }
+            FormatToken::FractionSeparator => {
+                // ...and so is the slash (m/d/yyyy)
+                result.push('/');
+            }
             _ => {}
         }
     }
@@ -2338,4 +2416,16 @@ mod tests {
         let result = format_custom_value(0.0, "# ?/?", &LocaleSettings::invariant());
         assert_eq!(result.text.trim(), "0");
     }
+
+    #[test]
+    fn test_parse_format_code_is_strict() {
+        for ok in ["#,##0.00", "[Red]0;[Color10]-0", "[>=100]0;0", "[$€-407] 0.00", "[h]:mm", "\\[0\\]", "0;0;0;@"] {
+            assert!(parse_format_code(ok).is_ok(), "{}", ok);
+        }
+        for bad in ["0;0;0;@;0", "[Purple]0", "[Red0", "0\"abc", "[Color57]0"] {
+            assert!(parse_format_code(bad).is_err(), "{}", bad);
+            // The cell-format path still renders them.
+            assert!(parse_custom_format(bad).is_ok(), "{}", bad);
+        }
+    }
 }
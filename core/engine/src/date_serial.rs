@@ -134,10 +134,9 @@ fn is_leap_year_excel(year: i32) -> bool {
 
 /// Returns the day of week: 0=Sunday, 1=Monday, ..., 6=Saturday.
 pub fn weekday(serial: i64) -> i32 {
-    // Serial 1 = Jan 1, 1900 = Sunday (day 0)
-    // But we need to account for the Excel leap year bug
-    let adj = if serial >= 60 { serial } else { serial };
-    ((adj - 1) % 7) as i32  // 0=Sunday for serial 1
+    // Serial 1 = Jan 1, 1900 = Sunday (day 0); the phantom Feb 29 (serial 60)
+    // keeps later serials on their true weekdays. Serial 0 is a Saturday.
+    (serial - 1).rem_euclid(7) as i32
 }
 
 /// Is the given serial a weekend (Saturday or Sunday)?
@@ -201,41 +200,118 @@ pub fn datedif_months(sy: i32, sm: u32, sd: u32, ey: i32, em: u32, ed: u32) -> i
     months.max(0)
 }
 
-/// Parse a date string like "2024-01-15", "01/15/2024", "January 15, 2024".
+/// Parse a date string like "2024-01-15", "01/15/2024", "15 Jan 2024" or
+/// "January 15, 2024".
 pub fn parse_date_string(text: &str) -> Option<f64> {
-    let text = text.trim();
+    parse_date_part(text.trim(), false)
+}
 
-    // Try ISO format: YYYY-MM-DD
-    if let Some(serial) = try_parse_iso(text) {
-        return Some(serial);
+/// Recognizes a date typed into a cell, optionally followed by a time
+/// ("2024-01-15", "1/15/2024", "15 Jan 2024", "2024-01-15 14:30").
+/// `day_first` reads slash and dot dates as d/m/yyyy, for locales whose date
+/// format starts with the day. Returns the serial and whether a time was
+/// given; None for anything that is not an unambiguous, valid date.
+pub fn parse_date_input(text: &str, day_first: bool) -> Option<(f64, bool)> {
+    let text = text.trim();
+    if let Some(serial) = parse_date_part(text, day_first) {
+        return Some((serial, false));
     }
-
-    // Try US format: MM/DD/YYYY or M/D/YYYY
-    if let Some(serial) = try_parse_us(text) {
-        return Some(serial);
+    // A time follows the date after a space: "2024-01-15 14:30[:00] [PM]".
+    let (date, time) = text.split_once(' ')?;
+    let time = time.trim();
+    if !time.contains(':') {
+        return None;
     }
+    let serial = parse_date_part(date, day_first)?;
+    Some((serial + parse_clock_time(time)?, true))
+}
 
-    None
+/// A date without a time part, in any of the forms `parse_date_input` takes.
+fn parse_date_part(text: &str, day_first: bool) -> Option<f64> {
+    let (year, month, day) = try_parse_iso(text)
+        .or_else(|| try_parse_numeric(text, '/', day_first))
+        .or_else(|| try_parse_numeric(text, '.', day_first))
+        .or_else(|| try_parse_month_name(text))?;
+    if !(1900..=9999).contains(&year) || !(1..=12).contains(&month) {
+        return None;
+    }
+    if day < 1 || day > days_in_month(year, month as u32) as i32 {
+        return None;
+    }
+    Some(date_to_serial(year, month, day))
 }
 
-fn try_parse_iso(text: &str) -> Option<f64> {
+fn try_parse_iso(text: &str) -> Option<(i32, i32, i32)> {
     let parts: Vec<&str> = text.split('-').collect();
-    if parts.len() != 3 { return None; }
-    let year = parts[0].parse::<i32>().ok()?;
-    let month = parts[1].parse::<i32>().ok()?;
-    let day = parts[2].parse::<i32>().ok()?;
-    if month < 1 || month > 12 || day < 1 || day > 31 { return None; }
-    Some(date_to_serial(year, month, day))
+    if parts.len() != 3 || parts[0].len() != 4 { return None; }
+    Some((parts[0].parse().ok()?, parts[1].parse().ok()?, parts[2].parse().ok()?))
 }
 
-fn try_parse_us(text: &str) -> Option<f64> {
-    let parts: Vec<&str> = text.split('/').collect();
-    if parts.len() != 3 { return None; }
-    let month = parts[0].parse::<i32>().ok()?;
-    let day = parts[1].parse::<i32>().ok()?;
+/// m/d/yyyy (or d/m/yyyy when `day_first`) with the given separator.
+fn try_parse_numeric(text: &str, sep: char, day_first: bool) -> Option<(i32, i32, i32)> {
+    let parts: Vec<&str> = text.split(sep).collect();
+    if parts.len() != 3 || parts[2].len() != 4 { return None; }
+    let a = parts[0].parse::<i32>().ok()?;
+    let b = parts[1].parse::<i32>().ok()?;
     let year = parts[2].parse::<i32>().ok()?;
-    if month < 1 || month > 12 || day < 1 || day > 31 { return None; }
-    Some(date_to_serial(year, month, day))
+    Some(if day_first { (year, b, a) } else { (year, a, b) })
+}
+
+/// "15 Jan 2024", "15-Jan-2024", "Jan 15, 2024", "January 15 2024".
+fn try_parse_month_name(text: &str) -> Option<(i32, i32, i32)> {
+    let parts: Vec<&str> = text
+        .split([' ', '-', ','])
+        .filter(|p| !p.is_empty())
+        .collect();
+    if parts.len() != 3 || parts[2].len() != 4 { return None; }
+    let year = parts[2].parse::<i32>().ok()?;
+    if let Some(month) = month_from_name(parts[1]) {
+        return Some((year, month, parts[0].parse().ok()?));
+    }
+    Some((year, month_from_name(parts[0])?, parts[1].parse().ok()?))
+}
+
+/// 1-based month for an English month name or its three-letter abbreviation.
+fn month_from_name(name: &str) -> Option<i32> {
+    const MONTHS: [&str; 12] = [
+        "january", "february", "march", "april", "may", "june",
+        "july", "august", "september", "october", "november", "december",
+    ];
+    let lower = name.to_lowercase();
+    let position = if lower.len() == 3 {
+        MONTHS.iter().position(|m| m[..3] == lower)
+    } else {
+        MONTHS.iter().position(|m| *m == lower)
+    };
+    position.map(|i| i as i32 + 1)
+}
+
+/// A clock time ("14:30", "2:30:15 PM") as a fraction of a day. Unlike
+/// `parse_time_string`, out-of-range fields are rejected.
+fn parse_clock_time(text: &str) -> Option<f64> {
+    let upper = text.to_uppercase();
+    let (clock, meridiem) = match upper.strip_suffix("AM").or_else(|| upper.strip_suffix("PM")) {
+        Some(clock) => (clock.trim(), Some(upper.ends_with("PM"))),
+        None => (upper.as_str(), None),
+    };
+    let parts: Vec<&str> = clock.split(':').collect();
+    if parts.len() < 2 || parts.len() > 3 { return None; }
+    let hours = parts[0].parse::<u32>().ok()?;
+    let minutes = parts[1].parse::<u32>().ok()?;
+    let seconds = match parts.get(2) {
+        Some(s) => s.parse::<u32>().ok()?,
+        None => 0,
+    };
+    if minutes > 59 || seconds > 59 {
+        return None;
+    }
+    let hours = match meridiem {
+        Some(pm) if (1..=12).contains(&hours) => hours % 12 + if pm { 12 } else { 0 },
+        Some(_) => return None,
+        None if hours < 24 => hours,
+        None => return None,
+    };
+    Some((hours as f64 * 3600.0 + minutes as f64 * 60.0 + seconds as f64) / 86400.0)
 }
 
 /// Parse a time string like "14:30:00" or "2:30 PM".
@@ -419,6 +495,29 @@ mod tests {
         assert_eq!((y, m, d), (2024, 1, 1));
     }
 
+    #[test]
+    fn test_parse_date_input() {
+        let jan15 = date_to_serial(2024, 1, 15);
+        for text in ["2024-01-15", "1/15/2024", "01/15/2024", "15 Jan 2024", "15-jan-2024", "Jan 15, 2024", "January 15 2024"] {
+            assert_eq!(parse_date_input(text, false), Some((jan15, false)), "{}", text);
+        }
+        // Day-first locales read slash and dot dates as d/m/yyyy; ISO is unaffected.
+        assert_eq!(parse_date_input("15/1/2024", true), Some((jan15, false)));
+        assert_eq!(parse_date_input("15.01.2024", true), Some((jan15, false)));
+        assert_eq!(parse_date_input("2024-01-15", true), Some((jan15, false)));
+
+        // A trailing time makes a date-time.
+        assert_eq!(parse_date_input("2024-01-15 13:30", false), Some((jan15 + 0.5625, true)));
+        assert_eq!(parse_date_input("1/15/2024 1:30 PM", false), Some((jan15 + 0.5625, true)));
+        assert_eq!(parse_date_input("1/15/2024 12:00 AM", false), Some((jan15, true)));
+
+        // Not dates: impossible days, short years, bad times, plain text.
+        for text in ["2024-02-30", "1/15/24", "13/15/2024", "2024-01-15 25:00", "2024-01-15 10:75", "15 Foo 2024", "hello", "2024"] {
+            assert_eq!(parse_date_input(text, false), None, "{}", text);
+        }
+        assert_eq!(parse_date_string("Feb 29, 2024"), Some(date_to_serial(2024, 2, 29)));
+    }
+
     #[test]
     fn test_weekday() {
         // Jan 1, 1900 = Sunday
@@ -117,7 +117,7 @@ pub fn extract_dependencies_with_sheets(
 /// Recursive helper for dependency extraction (backward compatible, ignores sheets).
 fn extract_recursive(expr: &Expression, deps: &mut CoordSet, bounds: GridBounds) {
     match expr {
-        Expression::Literal(_) => {
+        Expression::Literal(_) | Expression::ArrayLiteral { .. } => {
             // Literals don't reference any cells
         }
 
@@ -261,7 +261,7 @@ fn extract_recursive_with_sheets(
     bounds: GridBounds,
 ) {
     match expr {
-        Expression::Literal(_) => {
+        Expression::Literal(_) | Expression::ArrayLiteral { .. } => {
             // Literals don't reference any cells
         }
 
@@ -455,10 +455,58 @@ pub fn extract_3d_dependencies(
     deps
 }
 
+/// True if the formula calls a volatile function (RAND, NOW, OFFSET, ...):
+/// its value can change although none of its precedents did, so it is
+/// recalculated on every recalculation pass, not only when a precedent changes.
+pub fn is_volatile(expr: &Expression) -> bool {
+    match expr {
+        Expression::FunctionCall { func, args, .. } => {
+            matches!(
+                func,
+                BuiltinFunction::Rand
+                    | BuiltinFunction::RandBetween
+                    | BuiltinFunction::RandArray
+                    | BuiltinFunction::Now
+                    | BuiltinFunction::Today
+                    | BuiltinFunction::Offset
+                    | BuiltinFunction::Indirect
+            ) || args.iter().any(is_volatile)
+        }
+        Expression::BinaryOp { left, right, .. } => is_volatile(left) || is_volatile(right),
+        Expression::UnaryOp { operand, .. } => is_volatile(operand),
+        Expression::Sheet3DRef { reference, .. } => is_volatile(reference),
+        Expression::IndexAccess { target, index } => is_volatile(target) || is_volatile(index),
+        Expression::ListLiteral { elements } => elements.iter().any(is_volatile),
+        Expression::DictLiteral { entries } => {
+            entries.iter().any(|(key, value)| is_volatile(key) || is_volatile(value))
+        }
+        Expression::SpillRef { cell, .. } => is_volatile(cell),
+        Expression::ImplicitIntersection { operand } => is_volatile(operand),
+        Expression::Literal(_) | Expression::ArrayLiteral { .. }
+        | Expression::CellRef { .. }
+        | Expression::Range { .. }
+        | Expression::ColumnRef { .. }
+        | Expression::RowRef { .. }
+        | Expression::TableRef { .. }
+        | Expression::NamedRef { .. } => false,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_is_volatile() {
+        let volatile = |formula: &str| is_volatile(&parser::parse(formula).unwrap());
+        assert!(volatile("=RAND()"));
+        assert!(volatile("=A1+RANDBETWEEN(1, 6)"));
+        assert!(volatile("=IF(A1>0, -NOW(), 0)"));
+        assert!(volatile("=SUM(OFFSET(A1, 1, 0, 3, 1))"));
+        assert!(!volatile("=SUM(A1:A3)*2"));
+        assert!(!volatile("=ROUND(PI(), 2)"));
+    }
+
     fn set_of(coords: &[CellCoord]) -> CoordSet {
         coords.iter().cloned().collect()
     }
@@ -18,19 +18,55 @@
 //!              LOWER, TRIM, CONCATENATE, LEFT, RIGHT, MID
 //!
 
-use crate::cell::{CellError, CellValue, DictKey};
+use crate::cell::{Cell, CellError, CellValue, DictKey};
 use crate::control_values::ControlValue;
 use crate::coord::col_to_index;
+use crate::custom_format;
 use crate::cube::{cube_call_key, CubeBinding, CubeCallResult, CubePrefetch, CubeResolver};
 use crate::date_serial;
 use crate::dependency_extractor::{BinaryOperator, BuiltinFunction, Expression, UnaryOperator, Value};
 use crate::grid::Grid;
+use crate::overlay::{GridView, OverlayGrid};
+use crate::locale::LocaleSettings;
 use crate::lookup_cache;
-use crate::style::StyleRegistry;
+use crate::number_format;
+use crate::style::{Color, CurrencyPosition, NumberFormat, StyleRegistry};
 
 use std::cell::RefCell;
 use std::collections::{HashMap, HashSet};
 
+thread_local! {
+    /// Explanation of the error the last top-level `Evaluator::evaluate` on
+    /// this thread returned, e.g. "SQRT argument was negative". None when the
+    /// result was not an error or the failing site gave no reason.
+    static LAST_ERROR_DETAIL: RefCell<Option<String>> = const { RefCell::new(None) };
+    /// UI side-effects requested by the last top-level `Evaluator::evaluate`
+    /// on this thread, in evaluation order.
+    static LAST_UI_EFFECTS: RefCell<Vec<UiEffect>> = const { RefCell::new(Vec::new()) };
+    /// Top-level `Evaluator::evaluate` calls made on this thread so far.
+    static EVALUATION_COUNT: std::cell::Cell<u64> = const { std::cell::Cell::new(0) };
+}
+
+/// Take the explanation left by the last top-level evaluation on this thread
+/// (see `LAST_ERROR_DETAIL`). Call right after the evaluation whose result is
+/// being stored; a second call returns None.
+pub fn take_error_detail() -> Option<String> {
+    LAST_ERROR_DETAIL.with(|d| d.borrow_mut().take())
+}
+
+/// Take the UI side-effects left by the last top-level evaluation on this
+/// thread (see `LAST_UI_EFFECTS`). Like `take_error_detail`, call right after
+/// the evaluation whose result is being stored.
+pub fn take_ui_effects() -> Vec<UiEffect> {
+    LAST_UI_EFFECTS.with(|e| std::mem::take(&mut *e.borrow_mut()))
+}
+
+/// Number of top-level evaluations (formulas computed) on this thread so
+/// far. Compare two readings to count the work a recalculation did.
+pub fn evaluation_count() -> u64 {
+    EVALUATION_COUNT.with(|n| n.get())
+}
+
 /// Comparison operator for criteria matching in SUMIF/COUNTIF etc.
 #[derive(Debug, Clone)]
 enum CriteriaOp {
@@ -50,6 +86,44 @@ enum CriteriaMatch {
     TextNotEqual(String),
     Compare(CriteriaOp, f64),
     Wildcard(String),
+    WildcardNotEqual(String),
+}
+
+/// Hit/miss counters of an evaluator's batch cache (see `BatchCache`).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct BatchCacheStats {
+    /// Criteria strings served already compiled / compiled afresh.
+    pub criteria_hits: u64,
+    pub criteria_misses: u64,
+    /// Reference ranges served already collected / read from the grid.
+    pub range_hits: u64,
+    pub range_misses: u64,
+    /// COUNTIF aggregate indexes served already built / built afresh.
+    pub index_hits: u64,
+    pub index_misses: u64,
+}
+
+/// A reference's cells row-major, blanks as None (the `eval_flat_blanks` shape).
+type FlatCells = std::sync::Arc<[Option<EvalResult>]>;
+
+/// Soft caps on the batch cache; past them results are computed uncached.
+const BATCH_CACHE_MAX_CRITERIA: usize = 4096;
+const BATCH_CACHE_MAX_RANGES: usize = 256;
+
+/// Memoizes the criteria family's inputs for the lifetime of one evaluator,
+/// so a batch of COUNTIF/SUMIFS-style formulas over the same column parses
+/// each criteria string once and reads each range once. The evaluator only
+/// borrows its grids, which cannot change while it lives, so entries never go
+/// stale; the next batch builds a new evaluator and starts empty. This is the
+/// fallback for callers outside a recalculation pass — inside one, the
+/// pass-scoped `lookup_cache` (which survives mid-pass writes) serves first.
+#[derive(Default)]
+struct BatchCache {
+    criteria: HashMap<String, CriteriaMatch>,
+    /// Keyed by (grid address, min_row, min_col, max_row, max_col).
+    ranges: HashMap<(usize, u32, u32, u32, u32), FlatCells>,
+    indexes: HashMap<lookup_cache::EntryKey, std::sync::Arc<lookup_cache::CriteriaIndex>>,
+    stats: BatchCacheStats,
 }
 
 /// The result of evaluating an expression.
@@ -199,6 +273,27 @@ impl EvalResult {
         }
     }
 
+    /// The element at (row, col) when this value is broadcast to a larger
+    /// array: scalars repeat everywhere, a single row (one row array) or
+    /// column (a flat array) repeats along the other axis, and positions
+    /// outside the array are #N/A.
+    pub fn broadcast_at(&self, row: usize, col: usize) -> EvalResult {
+        let EvalResult::Array(items) = self else { return self.clone() };
+        let (rows, cols) = self.spill_dimensions();
+        let row = if rows == 1 { 0 } else { row };
+        let col = if cols == 1 { 0 } else { col };
+        if col >= cols {
+            return EvalResult::Error(CellError::NA);
+        }
+        match items.get(row) {
+            Some(EvalResult::Array(inner)) => {
+                inner.get(col).cloned().unwrap_or(EvalResult::Error(CellError::NA))
+            }
+            Some(value) => value.clone(),
+            None => EvalResult::Error(CellError::NA),
+        }
+    }
+
     /// Extract a 2D grid of CellValues for spilling.
     /// Returns Vec of (row_offset, col_offset, CellValue).
     pub fn to_spill_values(&self) -> Vec<(u32, u32, CellValue)> {
@@ -279,6 +374,9 @@ pub struct MultiSheetContext<'a> {
     /// Ordered list of sheet names matching the workbook's tab order.
     /// Required for 3D references to determine which sheets fall between bookends.
     pub sheet_order: Vec<String>,
+    /// What-if overlays by uppercased sheet name; reads of these sheets see
+    /// the overridden cells.
+    overlays: HashMap<String, &'a OverlayGrid<'a>>,
 }
 
 impl<'a> MultiSheetContext<'a> {
@@ -288,7 +386,24 @@ impl<'a> MultiSheetContext<'a> {
             grids: HashMap::new(),
             current_sheet,
             sheet_order: Vec::new(),
+            overlays: HashMap::new(),
+        }
+    }
+
+    /// Creates a context over `overlays`, paired with `sheet_names` in tab
+    /// order, so formulas see each sheet with its overrides applied. Nothing
+    /// is copied: evaluation cost grows with the overrides, not the sheets.
+    pub fn with_overlays(
+        current_sheet: String,
+        sheet_names: &[String],
+        overlays: &'a [OverlayGrid<'a>],
+    ) -> Self {
+        let mut context = MultiSheetContext::new(current_sheet);
+        for (name, overlay) in sheet_names.iter().zip(overlays) {
+            context.add_overlay(name.clone(), overlay);
         }
+        context.sheet_order = sheet_names.to_vec();
+        context
     }
 
     /// Adds a grid to the context.
@@ -297,6 +412,13 @@ impl<'a> MultiSheetContext<'a> {
         self.grids.insert(name.to_uppercase(), grid);
     }
 
+    /// Adds a sheet seen through an overlay. `get_grid` returns its base.
+    pub fn add_overlay(&mut self, name: String, overlay: &'a OverlayGrid<'a>) {
+        let key = name.to_uppercase();
+        self.grids.insert(key.clone(), overlay.base);
+        self.overlays.insert(key, overlay);
+    }
+
     /// Gets a grid by name (case-insensitive).
     pub fn get_grid(&self, name: &str) -> Option<&&'a Grid> {
         self.grids.get(&name.to_uppercase())
@@ -307,6 +429,24 @@ impl<'a> MultiSheetContext<'a> {
         self.grids.get(&self.current_sheet.to_uppercase())
     }
 
+    /// Gets the read view of a sheet by name, overlay included.
+    fn get_view(&self, name: &str) -> Option<GridView<'a>> {
+        let key = name.to_uppercase();
+        match self.overlays.get(&key) {
+            Some(overlay) => Some(overlay.view()),
+            None => self.grids.get(&key).map(|g| GridView::from(*g)),
+        }
+    }
+
+    /// The view to read `grid` through: its overlay if one registered here
+    /// wraps it, otherwise the grid itself.
+    fn view_of(&self, grid: &'a Grid) -> GridView<'a> {
+        self.overlays
+            .values()
+            .find(|o| std::ptr::eq(o.base, grid))
+            .map_or_else(|| GridView::from(grid), |o| o.view())
+    }
+
     /// Gets all sheet names between start and end (inclusive) based on tab order.
     /// Returns an empty Vec if either sheet is not found in the order.
     pub fn get_sheets_in_range(&self, start: &str, end: &str) -> Vec<String> {
@@ -330,6 +470,20 @@ impl<'a> MultiSheetContext<'a> {
 }
 
 /// A UI side-effect produced during formula evaluation.
+#[derive(Debug, Clone, PartialEq)]
+pub enum UiEffect {
+    /// SET.ROW.HEIGHT: give 0-indexed `row` a height of `height` pixels.
+    SetRowHeight { row: u32, height: f64 },
+    /// SET.COLUMN.WIDTH: give 0-indexed `col` a width of `width` pixels.
+    SetColumnWidth { col: u32, width: f64 },
+    /// SET.CELL.FILLCOLOR: fill the cell at (row, col) with `color`, or
+    /// remove its fill when None.
+    SetCellFillColor { row: u32, col: u32, color: Option<Color> },
+    /// HYPERLINK: link the cell being evaluated to `target` (a URL, file path,
+    /// mailto: address or in-workbook reference such as "Sheet2!A1").
+    SetHyperlink { row: u32, col: u32, target: String },
+}
+
 /// Optional evaluation context providing the current cell's position
 /// and external state needed by GET/UI functions.
 #[derive(Debug, Clone, Default)]
@@ -360,6 +514,13 @@ pub struct EvalContext {
     pub control_values: Option<std::sync::Arc<HashMap<String, ControlValue>>>,
 }
 
+impl EvalContext {
+    /// A context for the formula in the 0-indexed cell (row, col).
+    pub fn at(row: u32, col: u32) -> Self {
+        EvalContext { current_row: Some(row), current_col: Some(col), ..Default::default() }
+    }
+}
+
 /// Pre-fetched data for a single writeback region, used by GATHER functions.
 #[derive(Debug, Clone, Default)]
 pub struct GatherRegionData {
@@ -387,12 +548,12 @@ pub struct GatherSubmission {
 /// The formula evaluator.
 /// Holds a reference to the grid for cell lookups.
 pub struct Evaluator<'a> {
-    grid: &'a Grid,
+    grid: GridView<'a>,
     /// Optional multi-sheet context for cross-sheet references
     multi_sheet: Option<MultiSheetContext<'a>>,
     /// Evaluation context: current cell position + external state for GET/UI functions.
     context: EvalContext,
-    /// Optional style registry reference for GET.CELL.FILLCOLOR.
+    /// Optional style registry reference for GET.CELL.FILLCOLOR and CELL("format").
     styles: Option<&'a StyleRegistry>,
     /// Optional file reader for FILEREAD/FILELINES/FILEEXISTS functions.
     /// The closure takes a file path and returns the file content if it exists.
@@ -415,15 +576,28 @@ pub struct Evaluator<'a> {
     /// Scope for LAMBDA/LET name bindings. Names are stored uppercased.
     /// Uses RefCell for interior mutability so evaluate() can stay &self.
     scope: RefCell<HashMap<String, EvalResult>>,
+    /// UI side-effects requested during the current top-level evaluation.
+    ui_effects: RefCell<Vec<UiEffect>>,
+    /// Criteria/range memoization shared by every formula this evaluator runs.
+    batch_cache: RefCell<BatchCache>,
+    /// Errors raised during the current top-level evaluation, first occurrence
+    /// of each kind, with the reason when the raising site gave one. An error
+    /// read from a referenced cell carries no reason, so another cell's
+    /// explanation never leaks into this one.
+    error_details: RefCell<Vec<(CellError, Option<String>)>>,
+    /// Nesting depth of `evaluate`; 0 outside any evaluation.
+    eval_depth: std::cell::Cell<u32>,
+    /// Iteration cap for RATE; see `set_rate_iteration_limit`.
+    rate_iterations: u32,
 }
 
 /// Adapter that lets the evaluator resolve cube arguments through the shared
 /// `crate::cube::CubeResolver` machinery, so the evaluator and the async
-/// pre-pass compute identical call keys. Borrows only `Grid` + `CubePrefetch`
+/// pre-pass compute identical call keys. Borrows only the grid view + `CubePrefetch`
 /// (both `Sync`) — NOT the whole `Evaluator` (whose `RefCell` scope is `!Sync`).
 struct EvalCubeResolver<'e> {
     prefetch: &'e CubePrefetch,
-    grid: &'e Grid,
+    grid: GridView<'e>,
 }
 
 impl<'e> CubeResolver for EvalCubeResolver<'e> {
@@ -446,7 +620,7 @@ impl<'a> Evaluator<'a> {
     /// For single-sheet evaluation (backward compatible).
     pub fn new(grid: &'a Grid) -> Self {
         Evaluator {
-            grid,
+            grid: GridView::from(grid),
             multi_sheet: None,
             context: EvalContext::default(),
             styles: None,
@@ -455,28 +629,29 @@ impl<'a> Evaluator<'a> {
             gather_fn: None,
             udf_fn: None,
             scope: RefCell::new(HashMap::new()),
+            ui_effects: RefCell::new(Vec::new()),
+            batch_cache: RefCell::new(BatchCache::default()),
+            error_details: RefCell::new(Vec::new()),
+            eval_depth: std::cell::Cell::new(0),
+            rate_iterations: RATE_MAX_ITERATIONS,
         }
     }
 
-    /// Creates a new Evaluator with multi-sheet support.
+    /// Creates a new Evaluator with multi-sheet support and no current cell,
+    /// so ROW()/COLUMN() without arguments are #VALUE!. Evaluating a cell's
+    /// formula should go through `with_context` instead.
+    /// If `context` holds an overlay over `grid`, the current sheet is read
+    /// through it.
     pub fn with_multi_sheet(grid: &'a Grid, context: MultiSheetContext<'a>) -> Self {
-        Evaluator {
-            grid,
-            multi_sheet: Some(context),
-            context: EvalContext::default(),
-            styles: None,
-            file_reader: None,
-            pivot_data_fn: None,
-            gather_fn: None,
-            udf_fn: None,
-            scope: RefCell::new(HashMap::new()),
-        }
+        Self::with_context(grid, context, EvalContext::default())
     }
 
     /// Creates a new Evaluator with multi-sheet support and evaluation context.
+    /// The standard constructor: `eval_ctx` names the cell being computed
+    /// (see `EvalContext::at`), which position-dependent functions read.
     pub fn with_context(grid: &'a Grid, multi_sheet: MultiSheetContext<'a>, eval_ctx: EvalContext) -> Self {
         Evaluator {
-            grid,
+            grid: multi_sheet.view_of(grid),
             multi_sheet: Some(multi_sheet),
             context: eval_ctx,
             styles: None,
@@ -485,10 +660,15 @@ impl<'a> Evaluator<'a> {
             gather_fn: None,
             udf_fn: None,
             scope: RefCell::new(HashMap::new()),
+            ui_effects: RefCell::new(Vec::new()),
+            batch_cache: RefCell::new(BatchCache::default()),
+            error_details: RefCell::new(Vec::new()),
+            eval_depth: std::cell::Cell::new(0),
+            rate_iterations: RATE_MAX_ITERATIONS,
         }
     }
 
-    /// Sets the style registry reference for GET.CELL.FILLCOLOR.
+    /// Sets the style registry reference for GET.CELL.FILLCOLOR and CELL("format").
     pub fn set_styles(&mut self, style_registry: &'a StyleRegistry) {
         self.styles = Some(style_registry);
     }
@@ -544,6 +724,20 @@ impl<'a> Evaluator<'a> {
         self.context.control_values = Some(values);
     }
 
+    /// Moves the evaluator to another cell, for callers that reuse one
+    /// evaluator across many formulas. Memoized criteria stay valid: they
+    /// depend on the grid, not on the position.
+    pub fn set_current_cell(&mut self, row: u32, col: u32) {
+        self.context.current_row = Some(row);
+        self.context.current_col = Some(col);
+    }
+
+    /// Caps the iterations RATE may take before giving up
+    /// (default `RATE_MAX_ITERATIONS`, Excel's limit).
+    pub fn set_rate_iteration_limit(&mut self, limit: u32) {
+        self.rate_iterations = limit.max(1);
+    }
+
     /// Binds a name to a value in the evaluation scope, so a bare identifier
     /// (`NamedRef`) in the expression resolves to it — the same mechanism
     /// LET/LAMBDA use. Enables scope-injected expression evaluation (e.g. a
@@ -553,18 +747,80 @@ impl<'a> Evaluator<'a> {
         self.scope.borrow_mut().insert(name.to_uppercase(), value);
     }
 
+    /// Hit/miss counters of the criteria/range cache this evaluator has
+    /// accumulated across every formula it evaluated.
+    pub fn batch_cache_stats(&self) -> BatchCacheStats {
+        self.batch_cache.borrow().stats
+    }
+
     /// Gets the grid for a given sheet name, or the current grid if None.
-    fn get_grid_for_sheet(&self, sheet: &Option<String>) -> &'a Grid {
+    fn get_grid_for_sheet(&self, sheet: &Option<String>) -> GridView<'a> {
         match (sheet, &self.multi_sheet) {
             (Some(sheet_name), Some(ctx)) => {
-                ctx.get_grid(sheet_name).copied().unwrap_or(self.grid)
+                ctx.get_view(sheet_name).unwrap_or(self.grid)
             }
             _ => self.grid,
         }
     }
 
-    /// Evaluates an AST expression and returns the result.
+    /// #REF! for a reference to a sheet the workbook does not have. Only
+    /// checked with a multi-sheet context; a single-sheet evaluator reads
+    /// every reference from its own grid.
+    fn missing_sheet(&self, sheet: &Option<String>) -> Option<EvalResult> {
+        match (sheet, &self.multi_sheet) {
+            (Some(name), Some(ctx)) if ctx.get_view(name).is_none() => {
+                Some(self.fail(CellError::Ref, format!("Sheet '{}' not found", name)))
+            }
+            _ => None,
+        }
+    }
+
+    /// Evaluates an AST expression and returns the result. At the top level
+    /// the explanation of an error result is left for `take_error_detail` and
+    /// the requested UI side-effects for `take_ui_effects`.
     pub fn evaluate(&self, expr: &Expression) -> EvalResult {
+        let depth = self.eval_depth.get();
+        if depth == 0 {
+            self.error_details.borrow_mut().clear();
+            self.ui_effects.borrow_mut().clear();
+            EVALUATION_COUNT.with(|n| n.set(n.get() + 1));
+        }
+        self.eval_depth.set(depth + 1);
+        let result = self.evaluate_expr(expr);
+        self.eval_depth.set(depth);
+        if depth == 0 {
+            let detail = match &result {
+                EvalResult::Error(e) => self
+                    .error_details
+                    .borrow()
+                    .iter()
+                    .find(|(kind, _)| kind == e)
+                    .and_then(|(_, detail)| detail.clone()),
+                _ => None,
+            };
+            LAST_ERROR_DETAIL.with(|d| *d.borrow_mut() = detail);
+            let effects = std::mem::take(&mut *self.ui_effects.borrow_mut());
+            LAST_UI_EFFECTS.with(|e| *e.borrow_mut() = effects);
+        }
+        result
+    }
+
+    /// Return `error`, recording why it was raised.
+    fn fail(&self, error: CellError, detail: impl Into<String>) -> EvalResult {
+        self.note_error(&error, Some(detail.into()));
+        EvalResult::Error(error)
+    }
+
+    /// Record that `error` occurred, unless an earlier error of the same kind
+    /// already did (the first occurrence is the likely origin).
+    fn note_error(&self, error: &CellError, detail: Option<String>) {
+        let mut details = self.error_details.borrow_mut();
+        if !details.iter().any(|(kind, _)| kind == error) {
+            details.push((error.clone(), detail));
+        }
+    }
+
+    fn evaluate_expr(&self, expr: &Expression) -> EvalResult {
         match expr {
             Expression::Literal(value) => self.eval_literal(value),
             Expression::CellRef { sheet, col, row, .. } => self.eval_cell_ref(sheet, col, *row),
@@ -577,7 +833,7 @@ impl<'a> Evaluator<'a> {
             }
             Expression::BinaryOp { left, op, right } => self.eval_binary_op(left, op, right),
             Expression::UnaryOp { op, operand } => self.eval_unary_op(op, operand),
-            Expression::FunctionCall { func, args, .. } => self.eval_function(func, args),
+            Expression::FunctionCall { func, args, .. } => self.eval_function_checked(func, args),
             Expression::Sheet3DRef { start_sheet, end_sheet, reference, .. } => {
                 self.eval_3d_ref(start_sheet, end_sheet, reference)
             }
@@ -592,10 +848,15 @@ impl<'a> Evaluator<'a> {
             Expression::ListLiteral { elements } => {
                 self.eval_list_literal(elements)
             }
+            Expression::ArrayLiteral { rows } => self.eval_array_literal(rows),
             Expression::DictLiteral { entries } => {
                 self.eval_dict_literal(entries)
             }
             Expression::NamedRef { name, .. } => {
+                // A reference invalidated by a structural change or fill.
+                if name == "#REF!" {
+                    return EvalResult::Error(CellError::Ref);
+                }
                 // Check scope first (LAMBDA/LET bindings)
                 let key = name.to_uppercase();
                 let scope = self.scope.borrow();
@@ -603,7 +864,7 @@ impl<'a> Evaluator<'a> {
                     val.clone()
                 } else {
                     // Unresolved name → #NAME? error
-                    EvalResult::Error(CellError::Name)
+                    self.fail(CellError::Name, format!("Unknown name '{}'", name))
                 }
             }
             Expression::SpillRef { .. } => {
@@ -706,6 +967,9 @@ impl<'a> Evaluator<'a> {
 
     /// Evaluates a cell reference by looking up its value in the grid.
     fn eval_cell_ref(&self, sheet: &Option<String>, col: &str, row: u32) -> EvalResult {
+        if let Some(missing) = self.missing_sheet(sheet) {
+            return missing;
+        }
         let grid = self.get_grid_for_sheet(sheet);
         let col_idx = col_to_index(col);
         let row_idx = row - 1; // Convert 1-based to 0-based
@@ -723,7 +987,10 @@ impl<'a> Evaluator<'a> {
             CellValue::Number(n) => EvalResult::Number(*n),
             CellValue::Text(s) => EvalResult::Text(s.clone()),
             CellValue::Boolean(b) => EvalResult::Boolean(*b),
-            CellValue::Error(e) => EvalResult::Error(e.clone()),
+            CellValue::Error(e) => {
+                self.note_error(e, None);
+                EvalResult::Error(e.clone())
+            }
             CellValue::List(items) => {
                 EvalResult::List(items.iter().map(|i| self.cell_value_to_result(i)).collect())
             }
@@ -740,6 +1007,9 @@ impl<'a> Evaluator<'a> {
         start: &Expression,
         end: &Expression,
     ) -> EvalResult {
+        if let Some(missing) = self.missing_sheet(sheet) {
+            return missing;
+        }
         let grid = self.get_grid_for_sheet(sheet);
 
         // Extract start and end coordinates
@@ -775,7 +1045,7 @@ impl<'a> Evaluator<'a> {
         // Output is positionally identical either way: row-major, absent cells
         // materialize as Number(0.0), same conversions.
         let area = num_rows as u64 * num_cols as u64;
-        let flat: Vec<EvalResult> = if area <= grid.cells.len() as u64 {
+        let flat: Vec<EvalResult> = if area <= grid.cell_count() as u64 {
             let mut flat = Vec::with_capacity(area as usize);
             for r in min_row..=max_row {
                 for c in min_col..=max_col {
@@ -788,7 +1058,7 @@ impl<'a> Evaluator<'a> {
             flat
         } else {
             let mut flat = vec![EvalResult::Number(0.0); area as usize];
-            for (&(r, c), cell) in grid.cells.iter() {
+            for (&(r, c), cell) in grid.cells() {
                 if r >= min_row && r <= max_row && c >= min_col && c <= max_col {
                     let idx = (r - min_row) as u64 * num_cols as u64 + (c - min_col) as u64;
                     flat[idx as usize] = self.cell_value_to_result(&cell.value);
@@ -797,8 +1067,9 @@ impl<'a> Evaluator<'a> {
             flat
         };
 
-        if num_rows > 1 && num_cols > 1 {
-            // Multi-row, multi-column range → 2D array (array of row arrays)
+        if num_cols > 1 {
+            // Multi-column range → 2D array (array of row arrays), a single
+            // row included, so it broadcasts and spills as a row.
             // This is needed for VLOOKUP/HLOOKUP/INDEX to work correctly
             let mut rows = Vec::with_capacity(num_rows as usize);
             let mut iter = flat.into_iter();
@@ -808,7 +1079,7 @@ impl<'a> Evaluator<'a> {
             }
             EvalResult::Array(rows)
         } else {
-            // Single-row or single-column range → flat 1D array
+            // Single-column range → flat 1D array
             EvalResult::Array(flat)
         }
     }
@@ -824,6 +1095,9 @@ impl<'a> Evaluator<'a> {
         start_col: &str,
         end_col: &str,
     ) -> EvalResult {
+        if let Some(missing) = self.missing_sheet(sheet) {
+            return missing;
+        }
         let grid = self.get_grid_for_sheet(sheet);
         let start_col_idx = col_to_index(start_col);
         let end_col_idx = col_to_index(end_col);
@@ -846,7 +1120,7 @@ impl<'a> Evaluator<'a> {
         // The multi-column branch below still sorts column-major then row-major
         // (that order IS consumed positionally and must be preserved).
         if min_col == max_col {
-            if (grid.max_row as usize) <= grid.cells.len() {
+            if (grid.max_row as usize) <= grid.cell_count() {
                 // Dense enough: the walk costs <= populated-cell count probes.
                 let mut values = Vec::new();
                 for row in 0..=grid.max_row {
@@ -859,8 +1133,7 @@ impl<'a> Evaluator<'a> {
             // Sparse/tall: iterate only the populated cells of this column + sort
             // by row, avoiding the O(max_row) walk.
             let mut col_cells: Vec<(u32, &crate::cell::Cell)> = grid
-                .cells
-                .iter()
+                .cells()
                 .filter_map(|((row, col), cell)| {
                     if *col == min_col {
                         Some((*row, cell))
@@ -880,8 +1153,7 @@ impl<'a> Evaluator<'a> {
         // OPTIMIZED: Collect cells from the HashMap that fall within the column range
         // This avoids iterating over potentially thousands of empty rows
         let mut cell_list: Vec<(u32, u32, &crate::cell::Cell)> = grid
-            .cells
-            .iter()
+            .cells()
             .filter_map(|((row, col), cell)| {
                 if *col >= min_col && *col <= max_col {
                     Some((*row, *col, cell))
@@ -913,6 +1185,9 @@ impl<'a> Evaluator<'a> {
     /// OPTIMIZED: Instead of iterating 0..max_col, we iterate directly over the
     /// grid's HashMap and filter by row range. This is O(n) where n = number of cells.
     fn eval_row_ref(&self, sheet: &Option<String>, start_row: u32, end_row: u32) -> EvalResult {
+        if let Some(missing) = self.missing_sheet(sheet) {
+            return missing;
+        }
         let grid = self.get_grid_for_sheet(sheet);
         let start_row_idx = start_row - 1; // Convert to 0-based
         let end_row_idx = end_row - 1;
@@ -922,8 +1197,7 @@ impl<'a> Evaluator<'a> {
 
         // OPTIMIZED: Collect cells from the HashMap that fall within the row range
         let mut cell_list: Vec<(u32, u32, &crate::cell::Cell)> = grid
-            .cells
-            .iter()
+            .cells()
             .filter_map(|((row, col), cell)| {
                 if *row >= min_row && *row <= max_row {
                     Some((*row, *col, cell))
@@ -971,6 +1245,18 @@ impl<'a> Evaluator<'a> {
         EvalResult::List(items)
     }
 
+    /// Evaluates an array constant: {1, 2; 3, 4} → EvalResult::Array. One
+    /// column is a flat array; anything wider, a single row included, is an
+    /// array of row arrays, so it spills to the written shape.
+    fn eval_array_literal(&self, rows: &[Vec<Value>]) -> EvalResult {
+        let row_of = |row: &Vec<Value>| row.iter().map(|v| self.eval_literal(v)).collect::<Vec<_>>();
+        if rows.first().is_some_and(|row| row.len() > 1) {
+            EvalResult::Array(rows.iter().map(|row| EvalResult::Array(row_of(row))).collect())
+        } else {
+            EvalResult::Array(rows.iter().flat_map(row_of).collect())
+        }
+    }
+
     /// Evaluates a dict literal: {"a": 1, "b": 2} → EvalResult::Dict
     fn eval_dict_literal(&self, entries: &[(Expression, Expression)]) -> EvalResult {
         let mut result: Vec<(DictKey, EvalResult)> = Vec::with_capacity(entries.len());
@@ -1105,8 +1391,9 @@ impl<'a> Evaluator<'a> {
         // against that sheet's grid using a temporary single-sheet evaluator.
         let mut all_values = Vec::new();
         for sheet_name in &sheets {
-            if let Some(grid) = ctx.get_grid(sheet_name) {
-                let sheet_eval = Evaluator::new(grid);
+            if let Some(grid) = ctx.get_view(sheet_name) {
+                let mut sheet_eval = Evaluator::new(grid.base());
+                sheet_eval.grid = grid;
                 let result = sheet_eval.evaluate(reference);
                 match result {
                     EvalResult::Array(vals) => all_values.extend(vals),
@@ -1128,75 +1415,128 @@ impl<'a> Evaluator<'a> {
         let left_val = self.evaluate(left);
         let right_val = self.evaluate(right);
 
+        if matches!(left_val, EvalResult::Array(_)) || matches!(right_val, EvalResult::Array(_)) {
+            return self.eval_array_binary_op(&left_val, op, &right_val);
+        }
+        self.apply_binary_op(&left_val, op, &right_val)
+    }
+
+    /// Applies a binary operator element by element, so (A1:A5>3)*B1:B5 yields
+    /// an array. A scalar, single row or single column is broadcast across the
+    /// other operand; positions outside the smaller operand are #N/A.
+    fn eval_array_binary_op(
+        &self,
+        left: &EvalResult,
+        op: &BinaryOperator,
+        right: &EvalResult,
+    ) -> EvalResult {
+        let (l_rows, l_cols) = left.spill_dimensions();
+        let (r_rows, r_cols) = right.spill_dimensions();
+        let span = |l: usize, r: usize| if l == 1 { r } else if r == 1 { l } else { l.max(r) };
+        let (rows, cols) = (span(l_rows, r_rows), span(l_cols, r_cols));
+
+        let result: Vec<EvalResult> = (0..rows)
+            .map(|r| {
+                let mut row: Vec<EvalResult> = (0..cols)
+                    .map(|c| self.apply_binary_op(&left.broadcast_at(r, c), op, &right.broadcast_at(r, c)))
+                    .collect();
+                if cols == 1 { row.pop().unwrap() } else { EvalResult::Array(row) }
+            })
+            .collect();
+        EvalResult::Array(result)
+    }
+
+    fn apply_binary_op(
+        &self,
+        left_val: &EvalResult,
+        op: &BinaryOperator,
+        right_val: &EvalResult,
+    ) -> EvalResult {
         // Propagate errors
-        if let EvalResult::Error(e) = &left_val {
+        if let EvalResult::Error(e) = left_val {
             return EvalResult::Error(e.clone());
         }
-        if let EvalResult::Error(e) = &right_val {
+        if let EvalResult::Error(e) = right_val {
             return EvalResult::Error(e.clone());
         }
 
         match op {
             // Arithmetic operations
-            BinaryOperator::Add => self.eval_add(&left_val, &right_val),
-            BinaryOperator::Subtract => self.eval_subtract(&left_val, &right_val),
-            BinaryOperator::Multiply => self.eval_multiply(&left_val, &right_val),
-            BinaryOperator::Divide => self.eval_divide(&left_val, &right_val),
-            BinaryOperator::Power => self.eval_power(&left_val, &right_val),
+            BinaryOperator::Add => self.eval_add(left_val, right_val),
+            BinaryOperator::Subtract => self.eval_subtract(left_val, right_val),
+            BinaryOperator::Multiply => self.eval_multiply(left_val, right_val),
+            BinaryOperator::Divide => self.eval_divide(left_val, right_val),
+            BinaryOperator::Power => self.eval_power(left_val, right_val),
 
             // String concatenation
-            BinaryOperator::Concat => self.eval_concat(&left_val, &right_val),
+            BinaryOperator::Concat => self.eval_concat(left_val, right_val),
 
             // Comparison operations
-            BinaryOperator::Equal => self.eval_equal(&left_val, &right_val),
-            BinaryOperator::NotEqual => self.eval_not_equal(&left_val, &right_val),
-            BinaryOperator::LessThan => self.eval_less_than(&left_val, &right_val),
-            BinaryOperator::GreaterThan => self.eval_greater_than(&left_val, &right_val),
-            BinaryOperator::LessEqual => self.eval_less_equal(&left_val, &right_val),
-            BinaryOperator::GreaterEqual => self.eval_greater_equal(&left_val, &right_val),
+            BinaryOperator::Equal => self.eval_equal(left_val, right_val),
+            BinaryOperator::NotEqual => self.eval_not_equal(left_val, right_val),
+            BinaryOperator::LessThan => self.eval_less_than(left_val, right_val),
+            BinaryOperator::GreaterThan => self.eval_greater_than(left_val, right_val),
+            BinaryOperator::LessEqual => self.eval_less_equal(left_val, right_val),
+            BinaryOperator::GreaterEqual => self.eval_greater_equal(left_val, right_val),
         }
     }
 
     fn eval_add(&self, left: &EvalResult, right: &EvalResult) -> EvalResult {
         match (left.as_number(), right.as_number()) {
             (Some(l), Some(r)) => EvalResult::Number(l + r),
-            _ => EvalResult::Error(CellError::Value),
+            _ => self.non_numeric_operand("+", left, right),
         }
     }
 
     fn eval_subtract(&self, left: &EvalResult, right: &EvalResult) -> EvalResult {
         match (left.as_number(), right.as_number()) {
             (Some(l), Some(r)) => EvalResult::Number(l - r),
-            _ => EvalResult::Error(CellError::Value),
+            _ => self.non_numeric_operand("-", left, right),
         }
     }
 
     fn eval_multiply(&self, left: &EvalResult, right: &EvalResult) -> EvalResult {
         match (left.as_number(), right.as_number()) {
             (Some(l), Some(r)) => EvalResult::Number(l * r),
-            _ => EvalResult::Error(CellError::Value),
+            _ => self.non_numeric_operand("*", left, right),
         }
     }
 
     fn eval_divide(&self, left: &EvalResult, right: &EvalResult) -> EvalResult {
         match (left.as_number(), right.as_number()) {
-            (Some(_), Some(r)) if r == 0.0 => EvalResult::Error(CellError::Div0),
+            (Some(_), Some(0.0)) => self.fail(CellError::Div0, "Division by zero"),
             (Some(l), Some(r)) => EvalResult::Number(l / r),
-            _ => EvalResult::Error(CellError::Value),
+            _ => self.non_numeric_operand("/", left, right),
         }
     }
 
+    /// #VALUE! for an arithmetic operator given an operand that is not a number.
+    fn non_numeric_operand(&self, op: &str, left: &EvalResult, right: &EvalResult) -> EvalResult {
+        let operand = if left.as_number().is_none() { left } else { right };
+        let got = match operand {
+            EvalResult::Text(s) => format!("the text \"{}\"", s),
+            EvalResult::List(_) | EvalResult::Dict(_) => "a collection".to_string(),
+            EvalResult::Lambda { .. } => "a LAMBDA".to_string(),
+            _ => "a value that is not a number".to_string(),
+        };
+        self.fail(CellError::Value, format!("The {} operator needs numbers, got {}", op, got))
+    }
+
     fn eval_power(&self, left: &EvalResult, right: &EvalResult) -> EvalResult {
         match (left.as_number(), right.as_number()) {
             (Some(l), Some(r)) => {
                 let result = l.powf(r);
-                if result.is_nan() || result.is_infinite() {
-                    EvalResult::Error(CellError::Value)
+                if l == 0.0 && r < 0.0 {
+                    self.fail(CellError::Div0, format!("{}^{} divides by zero", l, r))
+                } else if l == 0.0 && r == 0.0 {
+                    self.fail(CellError::Num, "0^0 is undefined")
+                } else if result.is_nan() || result.is_infinite() {
+                    self.fail(CellError::Num, format!("{}^{} has no real result", l, r))
                 } else {
                     EvalResult::Number(result)
                 }
             }
-            _ => EvalResult::Error(CellError::Value),
+            _ => self.non_numeric_operand("^", left, right),
         }
     }
 
@@ -1292,8 +1632,21 @@ impl<'a> Evaluator<'a> {
         }
 
         match op {
-            UnaryOperator::Negate => match val.as_number() {
-                Some(n) => EvalResult::Number(-n),
+            UnaryOperator::Negate => Self::map_number(val, |n| -n),
+            // Excel's unary plus is a no-op: =+"abc" is "abc".
+            UnaryOperator::Plus => val,
+            UnaryOperator::Percent => Self::map_number(val, |n| n / 100.0),
+        }
+    }
+
+    /// Numeric unary operator, element by element for arrays so --(A1:A5>3)
+    /// yields 1s and 0s.
+    fn map_number(val: EvalResult, f: fn(f64) -> f64) -> EvalResult {
+        match val {
+            EvalResult::Array(items) => EvalResult::Array(items.into_iter().map(|v| Self::map_number(v, f)).collect()),
+            EvalResult::Error(e) => EvalResult::Error(e),
+            other => match other.as_number() {
+                Some(n) => EvalResult::Number(f(n)),
                 None => EvalResult::Error(CellError::Value),
             },
         }
@@ -1301,6 +1654,21 @@ impl<'a> Evaluator<'a> {
 
     /// Evaluates a function call via fast enum dispatch.
     /// No heap allocations or string comparisons - just integer matching.
+    /// `eval_function`, explaining a #VALUE! that a wrong argument count
+    /// caused. The count is only checked once the call has failed.
+    fn eval_function_checked(&self, func: &BuiltinFunction, args: &[Expression]) -> EvalResult {
+        let result = self.eval_function(func, args);
+        if matches!(result, EvalResult::Error(CellError::Value)) {
+            if let Some(detail) = arity_mismatch(func, args.len()) {
+                // Outranks anything the arguments raised: the call never ran.
+                let mut details = self.error_details.borrow_mut();
+                details.retain(|(kind, _)| *kind != CellError::Value);
+                details.insert(0, (CellError::Value, Some(detail)));
+            }
+        }
+        result
+    }
+
     fn eval_function(&self, func: &BuiltinFunction, args: &[Expression]) -> EvalResult {
         match func {
             // Aggregate functions
@@ -1478,6 +1846,9 @@ impl<'a> Evaluator<'a> {
             BuiltinFunction::GetRowHeight => self.fn_get_row_height(args),
             BuiltinFunction::GetColumnWidth => self.fn_get_column_width(args),
             BuiltinFunction::GetCellFillColor => self.fn_get_cell_fillcolor(args),
+            BuiltinFunction::SetRowHeight => self.fn_set_row_height(args),
+            BuiltinFunction::SetColumnWidth => self.fn_set_column_width(args),
+            BuiltinFunction::SetCellFillColor => self.fn_set_cell_fillcolor(args),
             BuiltinFunction::GetControlValue => self.fn_get_control_value(args),
 
             // Reference functions
@@ -1758,6 +2129,7 @@ impl<'a> Evaluator<'a> {
 
             // Web functions
             BuiltinFunction::EncodeUrl => self.fn_encodeurl(args),
+            BuiltinFunction::Hyperlink => self.fn_hyperlink(args),
 
             // Additional math functions
             BuiltinFunction::MRound => self.fn_mround(args),
@@ -1781,6 +2153,7 @@ impl<'a> Evaluator<'a> {
             BuiltinFunction::IsRef => self.fn_isref(args),
             BuiltinFunction::Sheet => self.fn_sheet(args),
             BuiltinFunction::Sheets => self.fn_sheets(args),
+            BuiltinFunction::Info => self.fn_info(args),
 
             // Engineering functions - Base conversion
             BuiltinFunction::Bin2Dec => self.fn_bin2dec(args),
@@ -1953,36 +2326,56 @@ impl<'a> Evaluator<'a> {
     }
 
     /// Collects numeric values from evaluated arguments, flattening arrays and unpacking List/Dict.
+    ///
+    /// Excel semantics: a scalar argument is coerced (TRUE counts as 1, "3"
+    /// as 3, other text is #VALUE!), while values read through a reference
+    /// or out of an array count only when they are genuine numbers; text,
+    /// booleans and blank cells there are skipped.
     fn collect_numbers(&self, args: &[Expression]) -> Result<Vec<f64>, CellError> {
         let mut numbers = Vec::new();
 
         for arg in args {
+            if self.reference_extent(arg).is_some() && self.missing_reference_sheet(arg).is_none() {
+                for value in self.eval_flat_blanks(arg).iter().flatten() {
+                    Self::collect_numbers_recursive(value.clone(), &mut numbers, true)?;
+                }
+                continue;
+            }
             let result = self.evaluate(arg);
-            Self::collect_numbers_recursive(result, &mut numbers)?;
+            let numbers_only = Self::is_reference_expr(arg);
+            Self::collect_numbers_recursive(result, &mut numbers, numbers_only)?;
         }
 
         Ok(numbers)
     }
 
     /// Recursively collects numbers from an EvalResult, unpacking Arrays, Lists, and Dict values.
-    fn collect_numbers_recursive(result: EvalResult, numbers: &mut Vec<f64>) -> Result<(), CellError> {
+    /// With `numbers_only`, anything but a number is skipped instead of coerced;
+    /// array elements are always read that way.
+    fn collect_numbers_recursive(result: EvalResult, numbers: &mut Vec<f64>, numbers_only: bool) -> Result<(), CellError> {
         match result {
             EvalResult::Error(e) => return Err(e),
             EvalResult::Array(arr) => {
                 for item in arr {
-                    Self::collect_numbers_recursive(item, numbers)?;
+                    Self::collect_numbers_recursive(item, numbers, true)?;
                 }
             }
             EvalResult::List(items) => {
                 for item in items {
-                    Self::collect_numbers_recursive(item, numbers)?;
+                    Self::collect_numbers_recursive(item, numbers, numbers_only)?;
                 }
             }
             EvalResult::Dict(entries) => {
                 for (_, value) in entries {
-                    Self::collect_numbers_recursive(value, numbers)?;
+                    Self::collect_numbers_recursive(value, numbers, numbers_only)?;
                 }
             }
+            EvalResult::Number(n) => numbers.push(n),
+            _ if numbers_only => {}
+            EvalResult::Text(s) => match s.trim().parse::<f64>() {
+                Ok(n) => numbers.push(n),
+                Err(_) => return Err(CellError::Value),
+            },
             other => {
                 if let Some(n) = other.as_number() {
                     numbers.push(n);
@@ -2088,11 +2481,30 @@ impl<'a> Evaluator<'a> {
     }
 
     fn fn_count(&self, args: &[Expression]) -> EvalResult {
-        // COUNT counts only numeric values
-        match self.collect_numbers(args) {
-            Ok(numbers) => EvalResult::Number(numbers.len() as f64),
-            Err(e) => EvalResult::Error(e),
+        // COUNT counts only numeric values. Like collect_numbers, scalar
+        // arguments are coerced and referenced/array values must be genuine
+        // numbers, but errors and unconvertible text are ignored, not raised.
+        fn count(result: EvalResult, numbers_only: bool) -> usize {
+            match result {
+                EvalResult::Array(items) => items.into_iter().map(|v| count(v, true)).sum(),
+                EvalResult::List(items) => items.into_iter().map(|v| count(v, numbers_only)).sum(),
+                EvalResult::Dict(entries) => entries.into_iter().map(|(_, v)| count(v, numbers_only)).sum(),
+                EvalResult::Number(_) => 1,
+                EvalResult::Boolean(_) if !numbers_only => 1,
+                EvalResult::Text(s) if !numbers_only => usize::from(s.trim().parse::<f64>().is_ok()),
+                _ => 0,
+            }
+        }
+
+        let mut total = 0;
+        for arg in args {
+            if self.reference_extent(arg).is_some() && self.missing_reference_sheet(arg).is_none() {
+                total += self.eval_flat_blanks(arg).iter().flatten().map(|v| count(v.clone(), true)).sum::<usize>();
+            } else {
+                total += count(self.evaluate(arg), Self::is_reference_expr(arg));
+            }
         }
+        EvalResult::Number(total as f64)
     }
 
     fn fn_counta(&self, args: &[Expression]) -> EvalResult {
@@ -2112,8 +2524,10 @@ impl<'a> Evaluator<'a> {
     // ==================== SUBTOTAL Function ====================
 
     /// SUBTOTAL(function_num, ref1, [ref2], ...)
-    /// Codes 1-11: include manually hidden rows, exclude rows hidden by other SUBTOTAL
-    /// Codes 101-111: exclude all hidden rows
+    /// Codes 1-11: include hidden rows
+    /// Codes 101-111: exclude rows in `EvalContext.hidden_rows` (filtered, grouped, hidden)
+    /// Either way, cells that hold a SUBTOTAL or AGGREGATE formula themselves are
+    /// skipped, so a grand total over a list with subtotal rows does not double count.
     /// 1/101=AVERAGE, 2/102=COUNT, 3/103=COUNTA, 4/104=MAX, 5/105=MIN,
     /// 6/106=PRODUCT, 7/107=STDEV, 8/108=STDEVP, 9/109=SUM, 10/110=VAR, 11/111=VARP
     fn fn_subtotal(&self, args: &[Expression]) -> EvalResult {
@@ -2129,49 +2543,50 @@ impl<'a> Evaluator<'a> {
         };
 
         // Determine whether to exclude hidden rows (101-111) or not (1-11)
-        let (base_func, exclude_hidden) = if func_num >= 101 && func_num <= 111 {
+        let (base_func, exclude_hidden) = if (101..=111).contains(&func_num) {
             (func_num - 100, true)
-        } else if func_num >= 1 && func_num <= 11 {
+        } else if (1..=11).contains(&func_num) {
             (func_num, false)
         } else {
             return EvalResult::Error(CellError::Value);
         };
 
-        // Collect values from the range arguments, optionally filtering hidden rows
-        let range_args = &args[1..];
-
-        if exclude_hidden {
-            // Codes 101-111: collect values while skipping hidden rows
-            let values = self.collect_subtotal_values_filtered(range_args);
-            self.apply_subtotal_aggregate(base_func, values)
-        } else {
-            // Codes 1-11: collect all values (don't filter hidden rows)
-            let values = self.collect_subtotal_values(range_args);
-            self.apply_subtotal_aggregate(base_func, values)
-        }
-    }
-
-    /// Collects values from range arguments without filtering hidden rows (codes 1-11).
-    fn collect_subtotal_values(&self, args: &[Expression]) -> Vec<EvalResult> {
-        let mut values = Vec::new();
-        for arg in args {
-            let result = self.evaluate(arg);
-            Self::flatten_into(&mut values, result);
-        }
-        values
+        let values = self.collect_subtotal_values(&args[1..], exclude_hidden, true);
+        self.apply_subtotal_aggregate(base_func, values)
     }
 
-    /// Collects values from range arguments, skipping values in hidden rows (codes 101-111).
-    fn collect_subtotal_values_filtered(&self, args: &[Expression]) -> Vec<EvalResult> {
+    /// Collects the inputs of SUBTOTAL/AGGREGATE. Reference arguments are walked
+    /// cell by cell so rows in `hidden_rows` (when `skip_hidden`) and cells whose
+    /// formula calls SUBTOTAL or AGGREGATE (when `skip_nested`) can be left out.
+    /// Blank cells contribute nothing. Other arguments are evaluated and flattened.
+    fn collect_subtotal_values(
+        &self,
+        args: &[Expression],
+        skip_hidden: bool,
+        skip_nested: bool,
+    ) -> Vec<EvalResult> {
         let hidden = match &self.context.hidden_rows {
-            Some(h) if !h.is_empty() => h,
-            _ => return self.collect_subtotal_values(args), // No hidden rows, just collect all
+            Some(h) if skip_hidden && !h.is_empty() => Some(h),
+            _ => None,
+        };
+        let include = |row: u32, cell: &Cell| -> bool {
+            if hidden.is_some_and(|h| h.contains(&row)) {
+                return false;
+            }
+            if skip_nested && cell.get_ast().is_some_and(Self::calls_subtotal) {
+                return false;
+            }
+            !matches!(cell.value, CellValue::Empty)
         };
 
         let mut values = Vec::new();
         for arg in args {
             match arg {
                 Expression::Range { sheet, start, end, .. } => {
+                    if let Some(missing) = self.missing_sheet(sheet) {
+                        values.push(missing);
+                        continue;
+                    }
                     let grid = self.get_grid_for_sheet(sheet);
                     if let (
                         Expression::CellRef { col: start_col, row: start_row, .. },
@@ -2186,51 +2601,62 @@ impl<'a> Evaluator<'a> {
                         let min_c = sc.min(ec);
                         let max_c = sc.max(ec);
                         for r in min_r..=max_r {
-                            if hidden.contains(&r) {
-                                continue; // Skip hidden rows
+                            if hidden.is_some_and(|h| h.contains(&r)) {
+                                continue;
                             }
                             for c in min_c..=max_c {
-                                let result = match grid.get_cell(r, c) {
-                                    Some(cell) => self.cell_value_to_result(&cell.value),
-                                    None => EvalResult::Number(0.0),
-                                };
-                                values.push(result);
+                                if let Some(cell) = grid.get_cell(r, c) {
+                                    if include(r, cell) {
+                                        values.push(self.cell_value_to_result(&cell.value));
+                                    }
+                                }
                             }
                         }
+                    } else {
+                        Self::flatten_into(&mut values, self.evaluate(arg));
                     }
                 }
                 Expression::CellRef { sheet, col, row, .. } => {
+                    if let Some(missing) = self.missing_sheet(sheet) {
+                        values.push(missing);
+                        continue;
+                    }
+                    let grid = self.get_grid_for_sheet(sheet);
                     let row_idx = row - 1;
-                    if !hidden.contains(&row_idx) {
-                        let grid = self.get_grid_for_sheet(sheet);
-                        let col_idx = col_to_index(col);
-                        let result = match grid.get_cell(row_idx, col_idx) {
-                            Some(cell) => self.cell_value_to_result(&cell.value),
-                            None => EvalResult::Number(0.0),
-                        };
-                        values.push(result);
+                    if let Some(cell) = grid.get_cell(row_idx, col_to_index(col)) {
+                        if include(row_idx, cell) {
+                            values.push(self.cell_value_to_result(&cell.value));
+                        }
                     }
                 }
                 Expression::ColumnRef { sheet, start_col, end_col, .. } => {
+                    if let Some(missing) = self.missing_sheet(sheet) {
+                        values.push(missing);
+                        continue;
+                    }
                     let grid = self.get_grid_for_sheet(sheet);
                     let sc = col_to_index(start_col);
                     let ec = col_to_index(end_col);
                     let min_c = sc.min(ec);
                     let max_c = sc.max(ec);
-                    for (&(r, c), cell) in &grid.cells {
-                        if c >= min_c && c <= max_c && !hidden.contains(&r) {
+                    for (&(r, c), cell) in grid.cells() {
+                        if c >= min_c && c <= max_c && include(r, cell) {
                             values.push(self.cell_value_to_result(&cell.value));
                         }
                     }
                 }
                 Expression::RowRef { sheet, start_row, end_row, .. } => {
+                    if let Some(missing) = self.missing_sheet(sheet) {
+                        values.push(missing);
+                        continue;
+                    }
                     let grid = self.get_grid_for_sheet(sheet);
                     let sr = start_row - 1;
                     let er = end_row - 1;
                     let min_r = sr.min(er);
                     let max_r = sr.max(er);
-                    for (&(r, _c), cell) in &grid.cells {
-                        if r >= min_r && r <= max_r && !hidden.contains(&r) {
+                    for (&(r, _c), cell) in grid.cells() {
+                        if r >= min_r && r <= max_r && include(r, cell) {
                             values.push(self.cell_value_to_result(&cell.value));
                         }
                     }
@@ -2245,6 +2671,28 @@ impl<'a> Evaluator<'a> {
         values
     }
 
+    /// True when a formula calls SUBTOTAL or AGGREGATE anywhere in its AST.
+    /// Such cells depend on row visibility and are left out of enclosing
+    /// SUBTOTAL/AGGREGATE ranges.
+    pub fn calls_subtotal(expr: &Expression) -> bool {
+        match expr {
+            Expression::FunctionCall { func, args, .. } => {
+                matches!(func, BuiltinFunction::Subtotal | BuiltinFunction::Aggregate)
+                    || args.iter().any(Self::calls_subtotal)
+            }
+            Expression::BinaryOp { left, right, .. } => {
+                Self::calls_subtotal(left) || Self::calls_subtotal(right)
+            }
+            Expression::UnaryOp { operand, .. }
+            | Expression::ImplicitIntersection { operand, .. } => Self::calls_subtotal(operand),
+            Expression::IndexAccess { target, index, .. } => {
+                Self::calls_subtotal(target) || Self::calls_subtotal(index)
+            }
+            Expression::ListLiteral { elements, .. } => elements.iter().any(Self::calls_subtotal),
+            _ => false,
+        }
+    }
+
     /// Flattens an EvalResult into a Vec, expanding arrays/lists.
     fn flatten_into(values: &mut Vec<EvalResult>, result: EvalResult) {
         match result {
@@ -2395,46 +2843,129 @@ impl<'a> Evaluator<'a> {
         }
     }
 
+    /// The logical values AND, OR and XOR see in one argument, in order.
+    /// References and arrays contribute booleans and numbers (nonzero is
+    /// TRUE) and skip text and blank cells; a direct text argument must read
+    /// "TRUE" or "FALSE". The first error wins.
+    fn logical_values(&self, arg: &Expression) -> Result<Vec<bool>, CellError> {
+        let sheet = match arg {
+            Expression::CellRef { sheet, .. }
+            | Expression::Range { sheet, .. }
+            | Expression::ColumnRef { sheet, .. }
+            | Expression::RowRef { sheet, .. } => Some(sheet),
+            _ => None,
+        };
+        if let Some(EvalResult::Error(e)) = sheet.and_then(|sheet| self.missing_sheet(sheet)) {
+            return Err(e);
+        }
+        if let Some((grid, min_row, min_col, max_row, max_col)) = self.reference_extent(arg) {
+            // Only stored cells can hold a logical value: probe the rectangle
+            // or walk the sparse map, whichever is smaller, in row-major order.
+            let max_row = max_row.min(grid.max_row);
+            let max_col = max_col.min(grid.max_col);
+            let mut cells: Vec<((u32, u32), &CellValue)> = Vec::new();
+            if min_row <= max_row && min_col <= max_col {
+                let area = (max_row - min_row + 1) as u64 * (max_col - min_col + 1) as u64;
+                if area <= grid.cell_count() as u64 {
+                    for r in min_row..=max_row {
+                        for c in min_col..=max_col {
+                            if let Some(cell) = grid.get_cell(r, c) {
+                                cells.push(((r, c), &cell.value));
+                            }
+                        }
+                    }
+                } else {
+                    cells.extend(
+                        grid.cells()
+                            .filter(|(&(r, c), _)| r >= min_row && r <= max_row && c >= min_col && c <= max_col)
+                            .map(|(&pos, cell)| (pos, &cell.value)),
+                    );
+                    cells.sort_unstable_by_key(|(pos, _)| *pos);
+                }
+            }
+            let mut values = Vec::new();
+            for (_, value) in cells {
+                match value {
+                    CellValue::Number(n) => values.push(*n != 0.0),
+                    CellValue::Boolean(b) => values.push(*b),
+                    CellValue::Error(e) => {
+                        self.note_error(e, None);
+                        return Err(e.clone());
+                    }
+                    _ => {}
+                }
+            }
+            return Ok(values);
+        }
+
+        match self.evaluate(arg) {
+            EvalResult::Error(e) => Err(e),
+            array @ EvalResult::Array(_) => {
+                let mut values = Vec::new();
+                for item in array.into_flatten() {
+                    match item {
+                        EvalResult::Error(e) => return Err(e),
+                        EvalResult::Number(n) => values.push(n != 0.0),
+                        EvalResult::Boolean(b) => values.push(b),
+                        _ => {}
+                    }
+                }
+                Ok(values)
+            }
+            scalar => scalar.as_boolean().map(|b| vec![b]).ok_or(CellError::Value),
+        }
+    }
+
+    /// AND short-circuits: arguments after the first FALSE are not evaluated.
     fn fn_and(&self, args: &[Expression]) -> EvalResult {
         if args.is_empty() {
             return EvalResult::Error(CellError::Value);
         }
 
+        let mut any = false;
         for arg in args {
-            let result = self.evaluate(arg);
-            if let EvalResult::Error(e) = result {
-                return EvalResult::Error(e);
-            }
-
-            match result.as_boolean() {
-                Some(false) => return EvalResult::Boolean(false),
-                None => return EvalResult::Error(CellError::Value),
-                _ => {}
+            match self.logical_values(arg) {
+                Err(e) => return EvalResult::Error(e),
+                Ok(values) => {
+                    any |= !values.is_empty();
+                    if values.contains(&false) {
+                        return EvalResult::Boolean(false);
+                    }
+                }
             }
         }
 
-        EvalResult::Boolean(true)
+        if any {
+            EvalResult::Boolean(true)
+        } else {
+            EvalResult::Error(CellError::Value)
+        }
     }
 
+    /// OR short-circuits: arguments after the first TRUE are not evaluated.
     fn fn_or(&self, args: &[Expression]) -> EvalResult {
         if args.is_empty() {
             return EvalResult::Error(CellError::Value);
         }
 
+        let mut any = false;
         for arg in args {
-            let result = self.evaluate(arg);
-            if let EvalResult::Error(e) = result {
-                return EvalResult::Error(e);
-            }
-
-            match result.as_boolean() {
-                Some(true) => return EvalResult::Boolean(true),
-                None => return EvalResult::Error(CellError::Value),
-                _ => {}
+            match self.logical_values(arg) {
+                Err(e) => return EvalResult::Error(e),
+                Ok(values) => {
+                    any |= !values.is_empty();
+                    if values.contains(&true) {
+                        return EvalResult::Boolean(true);
+                    }
+                }
             }
         }
 
-        EvalResult::Boolean(false)
+        if any {
+            EvalResult::Boolean(false)
+        } else {
+            EvalResult::Error(CellError::Value)
+        }
     }
 
     fn fn_not(&self, args: &[Expression]) -> EvalResult {
@@ -2544,10 +3075,13 @@ impl<'a> Evaluator<'a> {
             return EvalResult::Error(CellError::Value);
         }
 
-        let num = match self.evaluate(&args[0]).as_number() {
-            Some(n) if n < 0.0 => return EvalResult::Error(CellError::Value),
-            Some(n) => n,
-            None => return EvalResult::Error(CellError::Value),
+        let num = match self.evaluate(&args[0]) {
+            EvalResult::Error(e) => return EvalResult::Error(e),
+            v => match v.as_number() {
+                Some(n) if n < 0.0 => return self.fail(CellError::Value, "SQRT argument was negative"),
+                Some(n) => n,
+                None => return self.fail(CellError::Value, "SQRT argument is not a number"),
+            },
         };
 
         EvalResult::Number(num.sqrt())
@@ -2569,8 +3103,10 @@ impl<'a> Evaluator<'a> {
         };
 
         let result = base.powf(exponent);
-        if result.is_nan() || result.is_infinite() {
-            EvalResult::Error(CellError::Value)
+        if base == 0.0 && exponent < 0.0 {
+            EvalResult::Error(CellError::Div0)
+        } else if (base == 0.0 && exponent == 0.0) || result.is_nan() || result.is_infinite() {
+            EvalResult::Error(CellError::Num)
         } else {
             EvalResult::Number(result)
         }
@@ -2642,10 +3178,8 @@ impl<'a> Evaluator<'a> {
         match &val {
             EvalResult::List(items) => EvalResult::Number(items.len() as f64),
             EvalResult::Dict(entries) => EvalResult::Number(entries.len() as f64),
-            _ => {
-                let text = val.as_text();
-                EvalResult::Number(text.len() as f64)
-            }
+            EvalResult::Error(e) => EvalResult::Error(e.clone()),
+            _ => EvalResult::Number(val.as_text().chars().count() as f64),
         }
     }
 
@@ -2678,20 +3212,42 @@ impl<'a> Evaluator<'a> {
         EvalResult::Text(trimmed)
     }
 
+    /// CONCATENATE / CONCAT: ranges and arrays contribute every value in
+    /// row-major order; blank cells contribute nothing.
     fn fn_concatenate(&self, args: &[Expression]) -> EvalResult {
         let mut result = String::new();
 
         for arg in args {
-            let val = self.evaluate(arg);
-            if let EvalResult::Error(e) = val {
-                return EvalResult::Error(e);
+            match self.text_parts(arg) {
+                Ok(parts) => {
+                    for part in parts.into_iter().flatten() {
+                        result.push_str(&part);
+                    }
+                }
+                Err(e) => return EvalResult::Error(e),
             }
-            result.push_str(&val.as_text());
         }
 
+        if result.chars().count() > 32767 {
+            return EvalResult::Error(CellError::Value);
+        }
         EvalResult::Text(result)
     }
 
+    /// Text of every value an argument contributes, row-major. Blank cells of
+    /// a reference come back as `None` (see `eval_flat_blanks`) so callers can
+    /// tell them apart from empty strings; the first error value is returned.
+    fn text_parts(&self, arg: &Expression) -> Result<Vec<Option<String>>, CellError> {
+        self.eval_flat_blanks(arg)
+            .iter()
+            .map(|value| match value {
+                Some(EvalResult::Error(e)) => Err(e.clone()),
+                Some(value) => Ok(Some(value.as_text())),
+                None => Ok(None),
+            })
+            .collect()
+    }
+
     fn fn_left(&self, args: &[Expression]) -> EvalResult {
         if args.is_empty() || args.len() > 2 {
             return EvalResult::Error(CellError::Value);
@@ -2781,11 +3337,43 @@ impl<'a> Evaluator<'a> {
         }
 
         let value = self.evaluate(&args[0]);
-        let _format = self.evaluate(&args[1]).as_text();
+        if let EvalResult::Error(e) = value {
+            return EvalResult::Error(e);
+        }
+        let format_code = match self.evaluate(&args[1]) {
+            EvalResult::Error(e) => return EvalResult::Error(e),
+            v => v.as_text(),
+        };
+
+        // The code is read like a Custom number format; "General" is the one
+        // built-in name Excel also accepts here. Color tokens are dropped.
+        let (format, parsed) = if format_code.eq_ignore_ascii_case("general") {
+            (NumberFormat::General, None)
+        } else if let Ok(parsed) = custom_format::parse_format_code(&format_code) {
+            (NumberFormat::Custom { format: format_code }, Some(parsed))
+        } else {
+            return EvalResult::Error(CellError::Value);
+        };
+        let locale = LocaleSettings::invariant();
 
-        // Simplified TEXT function - just converts to string
-        // Full implementation would parse format codes
-        EvalResult::Text(value.as_text())
+        // Numeric text and dates typed as text are formatted as numbers;
+        // other text goes through the format's text section.
+        let number = match &value {
+            EvalResult::Number(n) => Some(*n),
+            EvalResult::Boolean(_) => None,
+            EvalResult::Text(s) => s.trim().parse::<f64>().ok().or_else(|| date_serial::parse_date_string(s)),
+            other => other.as_number(),
+        };
+        let result = match (number, &value) {
+            // Dates and times start at serial 0; a negative one has no rendering.
+            (Some(n), _) if n < 0.0 && parsed.as_ref().is_some_and(|p| custom_format::formats_as_datetime(n, p)) => {
+                return EvalResult::Error(CellError::Value);
+            }
+            (Some(n), _) => number_format::format_number_with_color(n, &format, &locale),
+            (None, EvalResult::Text(s)) => number_format::format_text_with_color(s, &format),
+            (None, other) => return EvalResult::Text(other.as_text()),
+        };
+        EvalResult::Text(result.text)
     }
 
     // ==================== Information Functions ====================
@@ -2808,24 +3396,87 @@ impl<'a> Evaluator<'a> {
         EvalResult::Boolean(matches!(result, EvalResult::Text(_)))
     }
 
-    fn fn_isblank(&self, args: &[Expression]) -> EvalResult {
-        if args.len() != 1 {
-            return EvalResult::Error(CellError::Value);
-        }
-
-        // Check if the cell reference points to an empty cell
-        match &args[0] {
+    /// Resolves a reference argument WITHOUT evaluating it: the grid it points
+    /// into and its normalized 0-based rect (min_row, min_col, max_row,
+    /// max_col). Whole columns/rows extend to the grid's used area. `None` when
+    /// the argument is not structurally a single-sheet reference, so ISBLANK,
+    /// COUNTBLANK and ISFORMULA can see empty cells and formulas that value
+    /// evaluation hides (an empty cell evaluates to 0).
+    fn reference_extent(&self, expr: &Expression) -> Option<(GridView<'a>, u32, u32, u32, u32)> {
+        match expr {
             Expression::CellRef { sheet, col, row, .. } => {
-                let grid = self.get_grid_for_sheet(sheet);
-                let col_idx = col_to_index(col);
-                let row_idx = row - 1;
-                let is_blank = grid.get_cell(row_idx, col_idx).is_none();
-                EvalResult::Boolean(is_blank)
-            }
-            _ => {
-                let result = self.evaluate(&args[0]);
-                let is_blank = matches!(result, EvalResult::Text(ref s) if s.is_empty());
-                EvalResult::Boolean(is_blank)
+                let (r, c) = (row - 1, col_to_index(col));
+                Some((self.get_grid_for_sheet(sheet), r, c, r, c))
+            }
+            Expression::Range { sheet, start, end, .. } => match (start.as_ref(), end.as_ref()) {
+                (
+                    Expression::CellRef { col: sc, row: sr, .. },
+                    Expression::CellRef { col: ec, row: er, .. },
+                ) => {
+                    let (sc, ec) = (col_to_index(sc), col_to_index(ec));
+                    let (sr, er) = (sr - 1, er - 1);
+                    Some((self.get_grid_for_sheet(sheet), sr.min(er), sc.min(ec), sr.max(er), sc.max(ec)))
+                }
+                _ => None,
+            },
+            Expression::ColumnRef { sheet, start_col, end_col, .. } => {
+                let grid = self.get_grid_for_sheet(sheet);
+                let (sc, ec) = (col_to_index(start_col), col_to_index(end_col));
+                Some((grid, 0, sc.min(ec), grid.max_row, sc.max(ec)))
+            }
+            Expression::RowRef { sheet, start_row, end_row, .. } => {
+                let grid = self.get_grid_for_sheet(sheet);
+                let (sr, er) = (start_row - 1, end_row - 1);
+                Some((grid, sr.min(er), 0, sr.max(er), grid.max_col))
+            }
+            _ => None,
+        }
+    }
+
+    /// Whether an argument is syntactically a reference (including 3D and
+    /// @-intersected ones), whatever it evaluates to.
+    fn is_reference_expr(expr: &Expression) -> bool {
+        match expr {
+            Expression::CellRef { .. }
+            | Expression::Range { .. }
+            | Expression::ColumnRef { .. }
+            | Expression::RowRef { .. }
+            | Expression::Sheet3DRef { .. } => true,
+            Expression::ImplicitIntersection { operand } => Self::is_reference_expr(operand),
+            _ => false,
+        }
+    }
+
+    /// `missing_sheet` for the sheet a reference argument points into.
+    fn missing_reference_sheet(&self, expr: &Expression) -> Option<EvalResult> {
+        match expr {
+            Expression::CellRef { sheet, .. }
+            | Expression::Range { sheet, .. }
+            | Expression::ColumnRef { sheet, .. }
+            | Expression::RowRef { sheet, .. } => self.missing_sheet(sheet),
+            _ => None,
+        }
+    }
+
+    /// A cell counts as blank when it is absent or holds no value and no
+    /// formula (a formatted-but-empty cell is still blank).
+    fn is_blank_cell(cell: Option<&crate::cell::Cell>) -> bool {
+        cell.is_none_or(|c| !c.has_formula() && matches!(c.value, CellValue::Empty))
+    }
+
+    fn fn_isblank(&self, args: &[Expression]) -> EvalResult {
+        if args.len() != 1 {
+            return EvalResult::Error(CellError::Value);
+        }
+
+        // A reference is inspected directly (its top-left cell); anything else
+        // is blank only when it evaluates to empty text.
+        match self.reference_extent(&args[0]) {
+            Some((grid, row, col, ..)) => EvalResult::Boolean(Self::is_blank_cell(grid.get_cell(row, col))),
+            None => {
+                let result = self.evaluate(&args[0]);
+                let is_blank = matches!(result, EvalResult::Text(ref s) if s.is_empty());
+                EvalResult::Boolean(is_blank)
             }
         }
     }
@@ -3562,6 +4213,104 @@ impl<'a> Evaluator<'a> {
         EvalResult::Text(style.fill.background_color().to_css_default())
     }
 
+    /// The 0-indexed row/column and pixel size of a SET.ROW.HEIGHT or
+    /// SET.COLUMN.WIDTH call: a whole index of at least 1 and a positive size.
+    fn dimension_args(&self, args: &[Expression]) -> Result<(u32, f64), CellError> {
+        if args.len() != 2 {
+            return Err(CellError::Value);
+        }
+        let index = match self.evaluate(&args[0]) {
+            EvalResult::Error(e) => return Err(e),
+            v => match v.as_number() {
+                Some(n) if n >= 1.0 && n == n.floor() => n as u32 - 1,
+                _ => return Err(CellError::Value),
+            },
+        };
+        match self.evaluate(&args[1]) {
+            EvalResult::Error(e) => Err(e),
+            v => match v.as_number() {
+                Some(size) if size > 0.0 => Ok((index, size)),
+                _ => Err(CellError::Value),
+            },
+        }
+    }
+
+    /// SET.ROW.HEIGHT(row_number, height)
+    /// Asks the host to set the height of the specified row (1-indexed) in
+    /// pixels and returns the height.
+    fn fn_set_row_height(&self, args: &[Expression]) -> EvalResult {
+        match self.dimension_args(args) {
+            Ok((row, height)) => {
+                self.ui_effects.borrow_mut().push(UiEffect::SetRowHeight { row, height });
+                EvalResult::Number(height)
+            }
+            Err(e) => EvalResult::Error(e),
+        }
+    }
+
+    /// SET.COLUMN.WIDTH(col_number, width)
+    /// Asks the host to set the width of the specified column (1-indexed) in
+    /// pixels and returns the width.
+    fn fn_set_column_width(&self, args: &[Expression]) -> EvalResult {
+        match self.dimension_args(args) {
+            Ok((col, width)) => {
+                self.ui_effects.borrow_mut().push(UiEffect::SetColumnWidth { col, width });
+                EvalResult::Number(width)
+            }
+            Err(e) => EvalResult::Error(e),
+        }
+    }
+
+    /// SET.CELL.FILLCOLOR(cell_ref, color) or SET.CELL.FILLCOLOR(cell_ref, red, green, blue)
+    /// Asks the host to fill a cell on this sheet and returns the color the way
+    /// GET.CELL.FILLCOLOR reports it ("" when the fill is removed). The color is
+    /// "#RGB", "#RRGGBB" or "#RRGGBBAA" text ("" removes the fill) or three
+    /// whole components from 0 to 255.
+    fn fn_set_cell_fillcolor(&self, args: &[Expression]) -> EvalResult {
+        if args.len() != 2 && args.len() != 4 {
+            return EvalResult::Error(CellError::Value);
+        }
+        let (row, col) = match &args[0] {
+            Expression::CellRef { sheet: None, col, row, .. } => (row - 1, col_to_index(col)),
+            _ => return EvalResult::Error(CellError::Value),
+        };
+        let color = if args.len() == 4 {
+            let mut rgb = [0u8; 3];
+            for (component, arg) in rgb.iter_mut().zip(&args[1..]) {
+                *component = match self.evaluate(arg) {
+                    EvalResult::Error(e) => return EvalResult::Error(e),
+                    v => match v.as_number() {
+                        Some(n) if (0.0..=255.0).contains(&n) && n == n.floor() => n as u8,
+                        _ => return EvalResult::Error(CellError::Value),
+                    },
+                };
+            }
+            Some(Color::new(rgb[0], rgb[1], rgb[2]))
+        } else {
+            let text = match self.evaluate(&args[1]) {
+                EvalResult::Error(e) => return EvalResult::Error(e),
+                v => v.as_text(),
+            };
+            let trimmed = text.trim();
+            if trimmed.is_empty() {
+                None
+            } else {
+                let hex = match trimmed.strip_prefix('#') {
+                    Some(hex) if hex.len() == 3 => hex.chars().flat_map(|c| [c, c]).collect(),
+                    Some(hex) => hex.to_string(),
+                    None => return EvalResult::Error(CellError::Value),
+                };
+                match Color::from_hex(&hex) {
+                    Some(color) => Some(color),
+                    None => return EvalResult::Error(CellError::Value),
+                }
+            }
+        };
+        let display = color.map_or(String::new(), |c| c.to_css());
+        self.ui_effects.borrow_mut().push(UiEffect::SetCellFillColor { row, col, color });
+        EvalResult::Text(display)
+    }
+
     /// GET.CONTROLVALUE(name, [default])
     /// Returns the current value of the named UI control (pane control, ribbon
     /// filter, or named on-grid control). Lookup is case-insensitive against
@@ -3621,9 +4370,52 @@ impl<'a> Evaluator<'a> {
 
     // ==================== Criteria Matching Infrastructure ====================
 
-    /// Parses a criteria value into a typed matcher.
-    /// Handles: ">5", "<=10", "<>apple", "A*", "?x?", exact match.
+    /// Whether two range arguments pair element-by-element (SUMIF's range and
+    /// sum_range, SUMIFS' sum and criteria ranges). References must have the
+    /// same rows x cols; anything else (arrays, computed ranges) is compared by
+    /// flattened length. Excel returns #VALUE! for a mismatch.
+    fn criteria_ranges_aligned(
+        &self,
+        a: &Expression,
+        b: &Expression,
+        a_vals: Option<&[EvalResult]>,
+        b_vals: Option<&[EvalResult]>,
+    ) -> bool {
+        let shape = |e: &Expression| {
+            self.reference_extent(e).map(|(_, r0, c0, r1, c1)| (r1 - r0 + 1, c1 - c0 + 1))
+        };
+        match (shape(a), shape(b)) {
+            (Some(sa), Some(sb)) => sa == sb,
+            _ => match (a_vals, b_vals) {
+                (Some(av), Some(bv)) => av.len() == bv.len(),
+                _ => true,
+            },
+        }
+    }
+
+    /// Parses a criteria value into a typed matcher. Text criteria are
+    /// compiled once per evaluator and then served from the batch cache.
     fn parse_criteria(&self, criteria: &EvalResult) -> CriteriaMatch {
+        let EvalResult::Text(s) = criteria else {
+            return Self::compile_criteria(criteria);
+        };
+        let mut cache = self.batch_cache.borrow_mut();
+        if let Some(compiled) = cache.criteria.get(s.as_str()) {
+            let compiled = compiled.clone();
+            cache.stats.criteria_hits += 1;
+            return compiled;
+        }
+        cache.stats.criteria_misses += 1;
+        let compiled = Self::compile_criteria(criteria);
+        if cache.criteria.len() < BATCH_CACHE_MAX_CRITERIA {
+            cache.criteria.insert(s.clone(), compiled.clone());
+        }
+        compiled
+    }
+
+    /// Compiles a criteria value into a typed matcher.
+    /// Handles: ">5", "<=10", "<>apple", "A*", "?x?", exact match.
+    fn compile_criteria(criteria: &EvalResult) -> CriteriaMatch {
         match criteria {
             EvalResult::Number(n) => CriteriaMatch::ExactNumber(*n),
             EvalResult::Boolean(b) => CriteriaMatch::ExactBool(*b),
@@ -3634,7 +4426,11 @@ impl<'a> Evaluator<'a> {
                     if let Ok(n) = rest.trim().parse::<f64>() {
                         return CriteriaMatch::Compare(CriteriaOp::NotEqual, n);
                     }
-                    return CriteriaMatch::TextNotEqual(rest.trim().to_uppercase());
+                    let rest = rest.trim().to_uppercase();
+                    if rest.contains('*') || rest.contains('?') {
+                        return CriteriaMatch::WildcardNotEqual(rest);
+                    }
+                    return CriteriaMatch::TextNotEqual(rest);
                 }
                 if let Some(rest) = trimmed.strip_prefix("<=") {
                     if let Ok(n) = rest.trim().parse::<f64>() {
@@ -3660,7 +4456,11 @@ impl<'a> Evaluator<'a> {
                     if let Ok(n) = rest.trim().parse::<f64>() {
                         return CriteriaMatch::ExactNumber(n);
                     }
-                    return CriteriaMatch::ExactText(rest.trim().to_uppercase());
+                    let rest = rest.trim().to_uppercase();
+                    if rest.contains('*') || rest.contains('?') {
+                        return CriteriaMatch::Wildcard(rest);
+                    }
+                    return CriteriaMatch::ExactText(rest);
                 }
                 // Check for wildcards
                 if trimmed.contains('*') || trimmed.contains('?') {
@@ -3708,7 +4508,56 @@ impl<'a> Evaluator<'a> {
                 let text = value.as_text().to_uppercase();
                 self.xlookup_wildcard_match(pattern, &text)
             }
+            CriteriaMatch::WildcardNotEqual(pattern) => {
+                let text = value.as_text().to_uppercase();
+                !self.xlookup_wildcard_match(pattern, &text)
+            }
+        }
+    }
+
+    /// Tests whether a cell from `eval_flat_blanks` matches a criteria. As in
+    /// Excel, a blank cell only satisfies `""`/`"="` and the not-equal forms
+    /// (`"<>"` excepted): it is neither 0 nor text for any other criteria.
+    fn matches_criteria_cell(&self, value: Option<&EvalResult>, criteria: &CriteriaMatch) -> bool {
+        match value {
+            Some(v) => self.matches_criteria(v, criteria),
+            None => match criteria {
+                CriteriaMatch::ExactText(s) => s.is_empty(),
+                CriteriaMatch::TextNotEqual(s) => !s.is_empty(),
+                CriteriaMatch::WildcardNotEqual(_) | CriteriaMatch::Compare(CriteriaOp::NotEqual, _) => true,
+                _ => false,
+            },
+        }
+    }
+
+    /// The numbers in `target` whose cells satisfy every `criteria_range,
+    /// criteria` pair in `pairs` (SUMIFS, AVERAGEIF(S), MINIFS, MAXIFS). Both
+    /// sides keep their blanks, so whole columns pair row by row. `None` when
+    /// a criteria range does not pair element-by-element with `target`, which
+    /// Excel reports as #VALUE!.
+    fn ifs_matched_numbers(&self, target: &Expression, pairs: &[Expression]) -> Option<Vec<f64>> {
+        let target_vals = self.eval_flat_blanks(target);
+        let mut matched = vec![true; target_vals.len()];
+        for pair in pairs.chunks(2) {
+            let range_vals = self.eval_flat_blanks(&pair[0]);
+            if range_vals.len() != target_vals.len()
+                || !self.criteria_ranges_aligned(target, &pair[0], None, None)
+            {
+                return None;
+            }
+            let criteria = self.parse_criteria(&self.evaluate(&pair[1]));
+            for (m, v) in matched.iter_mut().zip(range_vals.iter()) {
+                *m = *m && self.matches_criteria_cell(v.as_ref(), &criteria);
+            }
         }
+        Some(
+            target_vals
+                .iter()
+                .zip(matched)
+                .filter(|(_, m)| *m)
+                .filter_map(|(v, _)| v.as_ref().and_then(|v| v.as_number()))
+                .collect(),
+        )
     }
 
     /// Evaluates an argument and flattens it into a Vec of individual values.
@@ -3716,6 +4565,69 @@ impl<'a> Evaluator<'a> {
         self.evaluate(arg).into_flatten()
     }
 
+    /// Like `eval_flat`, but blank cells of a reference come back as `None`
+    /// instead of `Number(0.0)`, so COUNTIF/COUNTIFS criteria can tell them
+    /// apart from zeros. References are read row-major straight from the grid
+    /// (whole columns/rows up to the used area) once per evaluator and then
+    /// served from the batch cache; anything else is evaluated.
+    fn eval_flat_blanks(&self, arg: &Expression) -> FlatCells {
+        let Some((grid, min_row, min_col, max_row, max_col)) = self.reference_extent(arg) else {
+            return self.eval_flat(arg).into_iter().map(Some).collect();
+        };
+        let key = (Self::grid_addr(grid), min_row, min_col, max_row, max_col);
+        {
+            let mut cache = self.batch_cache.borrow_mut();
+            if let Some(flat) = cache.ranges.get(&key) {
+                let flat = flat.clone();
+                cache.stats.range_hits += 1;
+                return flat;
+            }
+            cache.stats.range_misses += 1;
+        }
+        let flat: FlatCells =
+            self.read_flat_blanks(grid, min_row, min_col, max_row, max_col).into();
+        let mut cache = self.batch_cache.borrow_mut();
+        if cache.ranges.len() < BATCH_CACHE_MAX_RANGES {
+            cache.ranges.insert(key, flat.clone());
+        }
+        flat
+    }
+
+    /// Reads a reference's cells row-major, blanks as None.
+    fn read_flat_blanks(
+        &self,
+        grid: GridView<'_>,
+        min_row: u32,
+        min_col: u32,
+        max_row: u32,
+        max_col: u32,
+    ) -> Vec<Option<EvalResult>> {
+        let num_cols = (max_col - min_col + 1) as u64;
+        let area = (max_row - min_row + 1) as u64 * num_cols;
+        let value_of = |cell: &crate::cell::Cell| {
+            (!Self::is_blank_cell(Some(cell))).then(|| self.cell_value_to_result(&cell.value))
+        };
+        // Same adaptive extraction as eval_range.
+        if area <= grid.cell_count() as u64 {
+            let mut flat = Vec::with_capacity(area as usize);
+            for r in min_row..=max_row {
+                for c in min_col..=max_col {
+                    flat.push(grid.get_cell(r, c).and_then(value_of));
+                }
+            }
+            flat
+        } else {
+            let mut flat = vec![None; area as usize];
+            for (&(r, c), cell) in grid.cells() {
+                if r >= min_row && r <= max_row && c >= min_col && c <= max_col {
+                    let idx = (r - min_row) as u64 * num_cols + (c - min_col) as u64;
+                    flat[idx as usize] = value_of(cell);
+                }
+            }
+            flat
+        }
+    }
+
     // ==================== Conditional Aggregate Functions ====================
 
     fn fn_sumif(&self, args: &[Expression]) -> EvalResult {
@@ -3732,6 +4644,9 @@ impl<'a> Evaluator<'a> {
                 Some((rect, axis, None))
             }
         });
+        if args.len() == 3 && !self.criteria_ranges_aligned(&args[0], &args[2], None, None) {
+            return EvalResult::Error(CellError::Value);
+        }
         let range_vals_pre = if fast.is_none() { Some(self.eval_flat(&args[0])) } else { None };
         let criteria = self.parse_criteria(&self.evaluate(&args[1]));
         if let Some((rect, axis, value_desc)) = fast {
@@ -3744,6 +4659,11 @@ impl<'a> Evaluator<'a> {
             None => self.eval_flat(&args[0]),
         };
         let sum_vals_owned = if args.len() == 3 { Some(self.eval_flat(&args[2])) } else { None };
+        if let Some(sum_vals) = &sum_vals_owned {
+            if !self.criteria_ranges_aligned(&args[0], &args[2], Some(&range_vals), Some(sum_vals)) {
+                return EvalResult::Error(CellError::Value);
+            }
+        }
         let sum_vals: &[EvalResult] = sum_vals_owned.as_deref().unwrap_or(&range_vals);
         let mut total = 0.0;
         for (i, val) in range_vals.iter().enumerate() {
@@ -3761,46 +4681,40 @@ impl<'a> Evaluator<'a> {
         if args.len() < 3 || (args.len() - 1) % 2 != 0 {
             return EvalResult::Error(CellError::Value);
         }
-        let sum_vals = self.eval_flat(&args[0]);
-        let num_criteria = (args.len() - 1) / 2;
-        let mut criteria_data: Vec<(Vec<EvalResult>, CriteriaMatch)> = Vec::new();
-        for i in 0..num_criteria {
-            let range_vals = self.eval_flat(&args[1 + i * 2]);
-            let criteria = self.parse_criteria(&self.evaluate(&args[2 + i * 2]));
-            criteria_data.push((range_vals, criteria));
-        }
-        let mut total = 0.0;
-        for i in 0..sum_vals.len() {
-            let all_match = criteria_data.iter().all(|(range_vals, criteria)| {
-                range_vals.get(i).map_or(false, |v| self.matches_criteria(v, criteria))
-            });
-            if all_match {
-                if let Some(n) = sum_vals[i].as_number() {
-                    total += n;
-                }
-            }
+        match self.ifs_matched_numbers(&args[0], &args[1..]) {
+            Some(nums) => EvalResult::Number(nums.iter().sum()),
+            None => EvalResult::Error(CellError::Value),
         }
-        EvalResult::Number(total)
     }
 
     fn fn_countif(&self, args: &[Expression]) -> EvalResult {
         if args.len() != 2 {
             return EvalResult::Error(CellError::Value);
         }
-        // FAST PATH (PERF-14): aggregate index over a literal vector.
+        // FAST PATH (PERF-14): aggregate index over a literal vector. The
+        // index sees blanks as 0 (rects) or not at all (whole columns), so it
+        // only serves criteria for which that makes no difference.
         let fast = self.literal_vector_desc(&args[0]);
-        let range_vals_pre = if fast.is_none() { Some(self.eval_flat(&args[0])) } else { None };
+        let range_vals_pre = if fast.is_none() { Some(self.eval_flat_blanks(&args[0])) } else { None };
         let criteria = self.parse_criteria(&self.evaluate(&args[1]));
         if let Some((rect, axis)) = fast {
-            if let Some(count) = self.criteria_count_cached(rect, axis, &criteria) {
-                return EvalResult::Number(count as f64);
+            let blank = self.matches_criteria_cell(None, &criteria);
+            let zero = self.matches_criteria(&EvalResult::Number(0.0), &criteria);
+            let blank_safe = match axis {
+                lookup_cache::Axis::WholeCol(_) => !blank && !zero,
+                _ => blank == zero,
+            };
+            if blank_safe {
+                if let Some(count) = self.criteria_count_cached(rect, axis, &criteria) {
+                    return EvalResult::Number(count as f64);
+                }
             }
         }
         let range_vals = match range_vals_pre {
             Some(v) => v,
-            None => self.eval_flat(&args[0]),
+            None => self.eval_flat_blanks(&args[0]),
         };
-        let count = range_vals.iter().filter(|v| self.matches_criteria(v, &criteria)).count();
+        let count = range_vals.iter().filter(|v| self.matches_criteria_cell(v.as_ref(), &criteria)).count();
         EvalResult::Number(count as f64)
     }
 
@@ -3810,9 +4724,16 @@ impl<'a> Evaluator<'a> {
             return EvalResult::Error(CellError::Value);
         }
         let num_criteria = args.len() / 2;
-        let mut criteria_data: Vec<(Vec<EvalResult>, CriteriaMatch)> = Vec::new();
+        let mut criteria_data: Vec<(FlatCells, CriteriaMatch)> = Vec::new();
         for i in 0..num_criteria {
-            let range_vals = self.eval_flat(&args[i * 2]);
+            let range_vals = self.eval_flat_blanks(&args[i * 2]);
+            if let Some((first, _)) = criteria_data.first() {
+                if first.len() != range_vals.len()
+                    || !self.criteria_ranges_aligned(&args[0], &args[i * 2], None, None)
+                {
+                    return EvalResult::Error(CellError::Value);
+                }
+            }
             let criteria = self.parse_criteria(&self.evaluate(&args[i * 2 + 1]));
             criteria_data.push((range_vals, criteria));
         }
@@ -3820,7 +4741,7 @@ impl<'a> Evaluator<'a> {
         let mut count = 0usize;
         for i in 0..len {
             let all_match = criteria_data.iter().all(|(range_vals, criteria)| {
-                range_vals.get(i).map_or(false, |v| self.matches_criteria(v, criteria))
+                self.matches_criteria_cell(range_vals[i].as_ref(), criteria)
             });
             if all_match {
                 count += 1;
@@ -3830,137 +4751,140 @@ impl<'a> Evaluator<'a> {
     }
 
     fn fn_averageif(&self, args: &[Expression]) -> EvalResult {
+        // AVERAGEIF(range, criteria, [average_range]): averages the range itself
+        // when average_range is omitted.
         if args.len() < 2 || args.len() > 3 {
             return EvalResult::Error(CellError::Value);
         }
-        let range_vals = self.eval_flat(&args[0]);
-        let criteria = self.parse_criteria(&self.evaluate(&args[1]));
-        let avg_vals_owned = if args.len() == 3 { Some(self.eval_flat(&args[2])) } else { None };
-        let avg_vals: &[EvalResult] = avg_vals_owned.as_deref().unwrap_or(&range_vals);
-        let mut total = 0.0;
-        let mut count = 0usize;
-        for (i, val) in range_vals.iter().enumerate() {
-            if self.matches_criteria(val, &criteria) {
-                if let Some(n) = avg_vals.get(i).and_then(|v| v.as_number()) {
-                    total += n;
-                    count += 1;
-                }
-            }
+        let target = args.get(2).unwrap_or(&args[0]);
+        match self.ifs_matched_numbers(target, &args[..2]) {
+            Some(nums) if nums.is_empty() => EvalResult::Error(CellError::Div0),
+            Some(nums) => EvalResult::Number(nums.iter().sum::<f64>() / nums.len() as f64),
+            None => EvalResult::Error(CellError::Value),
         }
-        if count == 0 { EvalResult::Error(CellError::Div0) } else { EvalResult::Number(total / count as f64) }
     }
 
     fn fn_averageifs(&self, args: &[Expression]) -> EvalResult {
         if args.len() < 3 || (args.len() - 1) % 2 != 0 {
             return EvalResult::Error(CellError::Value);
         }
-        let avg_vals = self.eval_flat(&args[0]);
-        let num_criteria = (args.len() - 1) / 2;
-        let mut criteria_data: Vec<(Vec<EvalResult>, CriteriaMatch)> = Vec::new();
-        for i in 0..num_criteria {
-            let range_vals = self.eval_flat(&args[1 + i * 2]);
-            let criteria = self.parse_criteria(&self.evaluate(&args[2 + i * 2]));
-            criteria_data.push((range_vals, criteria));
-        }
-        let mut total = 0.0;
-        let mut count = 0usize;
-        for i in 0..avg_vals.len() {
-            let all_match = criteria_data.iter().all(|(range_vals, criteria)| {
-                range_vals.get(i).map_or(false, |v| self.matches_criteria(v, criteria))
-            });
-            if all_match {
-                if let Some(n) = avg_vals[i].as_number() {
-                    total += n;
-                    count += 1;
-                }
-            }
+        match self.ifs_matched_numbers(&args[0], &args[1..]) {
+            Some(nums) if nums.is_empty() => EvalResult::Error(CellError::Div0),
+            Some(nums) => EvalResult::Number(nums.iter().sum::<f64>() / nums.len() as f64),
+            None => EvalResult::Error(CellError::Value),
         }
-        if count == 0 { EvalResult::Error(CellError::Div0) } else { EvalResult::Number(total / count as f64) }
     }
 
     fn fn_countblank(&self, args: &[Expression]) -> EvalResult {
         if args.len() != 1 {
             return EvalResult::Error(CellError::Value);
         }
-        let vals = self.eval_flat(&args[0]);
-        let count = vals.iter().filter(|v| {
-            matches!(v, EvalResult::Text(s) if s.is_empty()) || matches!(v, EvalResult::Array(a) if a.is_empty())
-        }).count();
-        // Also count truly empty cells: eval_flat doesn't produce them, but cells not in grid
-        // return empty text. The above check handles it.
-        EvalResult::Number(count as f64)
+        let Some((grid, min_row, min_col, max_row, max_col)) = self.reference_extent(&args[0]) else {
+            // Not a reference (e.g. an array expression): only empty text is blank.
+            let vals = self.eval_flat(&args[0]);
+            let count = vals.iter().filter(|v| matches!(v, EvalResult::Text(s) if s.is_empty())).count();
+            return EvalResult::Number(count as f64);
+        };
+        // Blank = absent/empty cells plus formulas returning "" (as Excel counts
+        // them). Count the non-blank cells in the rect and subtract them from
+        // its full size (A:A is 1,048,576 cells); only populated cells inside
+        // the used range are looked up, since everything past it is blank.
+        let (rows, cols) = Self::reference_dimensions(&args[0])
+            .unwrap_or(((max_row - min_row + 1) as usize, (max_col - min_col + 1) as usize));
+        let area = rows as u64 * cols as u64;
+        let (last_row, last_col) = (max_row.min(grid.max_row), max_col.min(grid.max_col));
+        let is_filled = |cell: &crate::cell::Cell| {
+            !Self::is_blank_cell(Some(cell)) && !matches!(&cell.value, CellValue::Text(t) if t.is_empty())
+        };
+        let used_area = (last_row.saturating_sub(min_row) as u64 + 1) * (last_col.saturating_sub(min_col) as u64 + 1);
+        let filled = if used_area <= grid.cell_count() as u64 {
+            (min_row..=last_row)
+                .flat_map(|r| (min_col..=last_col).map(move |c| (r, c)))
+                .filter(|&(r, c)| grid.get_cell(r, c).is_some_and(is_filled))
+                .count()
+        } else {
+            grid.cells()
+                .filter(|((r, c), cell)| {
+                    (min_row..=max_row).contains(r) && (min_col..=max_col).contains(c) && is_filled(cell)
+                })
+                .count()
+        };
+        EvalResult::Number((area - filled as u64) as f64)
     }
 
     fn fn_minifs(&self, args: &[Expression]) -> EvalResult {
         if args.len() < 3 || (args.len() - 1) % 2 != 0 {
             return EvalResult::Error(CellError::Value);
         }
-        let min_vals = self.eval_flat(&args[0]);
-        let num_criteria = (args.len() - 1) / 2;
-        let mut criteria_data: Vec<(Vec<EvalResult>, CriteriaMatch)> = Vec::new();
-        for i in 0..num_criteria {
-            let range_vals = self.eval_flat(&args[1 + i * 2]);
-            let criteria = self.parse_criteria(&self.evaluate(&args[2 + i * 2]));
-            criteria_data.push((range_vals, criteria));
-        }
-        let mut result = f64::INFINITY;
-        let mut found = false;
-        for i in 0..min_vals.len() {
-            let all_match = criteria_data.iter().all(|(range_vals, criteria)| {
-                range_vals.get(i).map_or(false, |v| self.matches_criteria(v, criteria))
-            });
-            if all_match {
-                if let Some(n) = min_vals[i].as_number() {
-                    result = result.min(n);
-                    found = true;
-                }
-            }
+        // No matching numbers gives 0, as in Excel.
+        match self.ifs_matched_numbers(&args[0], &args[1..]) {
+            Some(nums) => EvalResult::Number(nums.into_iter().reduce(f64::min).unwrap_or(0.0)),
+            None => EvalResult::Error(CellError::Value),
         }
-        if found { EvalResult::Number(result) } else { EvalResult::Number(0.0) }
     }
 
     fn fn_maxifs(&self, args: &[Expression]) -> EvalResult {
         if args.len() < 3 || (args.len() - 1) % 2 != 0 {
             return EvalResult::Error(CellError::Value);
         }
-        let max_vals = self.eval_flat(&args[0]);
-        let num_criteria = (args.len() - 1) / 2;
-        let mut criteria_data: Vec<(Vec<EvalResult>, CriteriaMatch)> = Vec::new();
-        for i in 0..num_criteria {
-            let range_vals = self.eval_flat(&args[1 + i * 2]);
-            let criteria = self.parse_criteria(&self.evaluate(&args[2 + i * 2]));
-            criteria_data.push((range_vals, criteria));
-        }
-        let mut result = f64::NEG_INFINITY;
-        let mut found = false;
-        for i in 0..max_vals.len() {
-            let all_match = criteria_data.iter().all(|(range_vals, criteria)| {
-                range_vals.get(i).map_or(false, |v| self.matches_criteria(v, criteria))
-            });
-            if all_match {
-                if let Some(n) = max_vals[i].as_number() {
-                    result = result.max(n);
-                    found = true;
-                }
-            }
+        // No matching numbers gives 0, as in Excel.
+        match self.ifs_matched_numbers(&args[0], &args[1..]) {
+            Some(nums) => EvalResult::Number(nums.into_iter().reduce(f64::max).unwrap_or(0.0)),
+            None => EvalResult::Error(CellError::Value),
         }
-        if found { EvalResult::Number(result) } else { EvalResult::Number(0.0) }
     }
 
     // ==================== Error Handling & Logic Functions (Batch 4) ====================
 
     fn fn_iferror(&self, args: &[Expression]) -> EvalResult {
-        if args.len() != 2 { return EvalResult::Error(CellError::Value); }
-        let val = self.evaluate(&args[0]);
-        if matches!(val, EvalResult::Error(_)) { self.evaluate(&args[1]) } else { val }
+        self.trap_errors(args, |_| true)
     }
 
     fn fn_ifna(&self, args: &[Expression]) -> EvalResult {
+        self.trap_errors(args, |e| matches!(e, CellError::NA))
+    }
+
+    /// IFERROR/IFNA: the first argument with every `trapped` error replaced by
+    /// the second, which is only evaluated when something is trapped. Arrays
+    /// are trapped element by element, so SUM(IFERROR(A1:A9, 0)) skips the
+    /// errors; an array fallback supplies the element at the same position
+    /// (a single row or column is broadcast, anything outside it is #N/A).
+    fn trap_errors(&self, args: &[Expression], trapped: fn(&CellError) -> bool) -> EvalResult {
         if args.len() != 2 { return EvalResult::Error(CellError::Value); }
-        let val = self.evaluate(&args[0]);
-        if matches!(val, EvalResult::Error(CellError::NA)) { self.evaluate(&args[1]) } else { val }
+        fn any_trapped(val: &EvalResult, trapped: fn(&CellError) -> bool) -> bool {
+            match val {
+                EvalResult::Error(e) => trapped(e),
+                EvalResult::Array(items) => items.iter().any(|v| any_trapped(v, trapped)),
+                _ => false,
+            }
+        }
+
+        let value = self.evaluate(&args[0]);
+        if !any_trapped(&value, trapped) {
+            return value;
+        }
+        let fallback = self.evaluate(&args[1]);
+        let EvalResult::Array(rows) = value else { return fallback };
+
+        let replace = |v: EvalResult, r: usize, c: usize| match &v {
+            EvalResult::Error(e) if trapped(e) => fallback.broadcast_at(r, c),
+            _ => v,
+        };
+        EvalResult::Array(
+            rows.into_iter()
+                .enumerate()
+                .map(|(r, item)| match item {
+                    EvalResult::Array(row) => EvalResult::Array(
+                        row.into_iter().enumerate().map(|(c, v)| replace(v, r, c)).collect(),
+                    ),
+                    v => replace(v, r, 0),
+                })
+                .collect(),
+        )
     }
 
+    /// The value paired with the first TRUE condition, #N/A if none is.
+    /// Conditions stop at the first TRUE and only its value is evaluated.
     fn fn_ifs(&self, args: &[Expression]) -> EvalResult {
         if args.is_empty() || args.len() % 2 != 0 {
             return EvalResult::Error(CellError::Value);
@@ -3968,6 +4892,10 @@ impl<'a> Evaluator<'a> {
         for i in (0..args.len()).step_by(2) {
             let cond = self.evaluate(&args[i]);
             if let EvalResult::Error(e) = cond { return EvalResult::Error(e); }
+            // Text that is not TRUE/FALSE is not a condition (#VALUE!, as in Excel).
+            if matches!(&cond, EvalResult::Text(_)) && cond.as_boolean().is_none() {
+                return EvalResult::Error(CellError::Value);
+            }
             if cond.as_boolean().unwrap_or(false) {
                 return self.evaluate(&args[i + 1]);
             }
@@ -4006,14 +4934,18 @@ impl<'a> Evaluator<'a> {
 
     fn fn_xor(&self, args: &[Expression]) -> EvalResult {
         if args.is_empty() { return EvalResult::Error(CellError::Value); }
+        let mut any = false;
         let mut true_count = 0usize;
         for arg in args {
-            let val = self.evaluate(arg);
-            for item in val.flatten() {
-                if let EvalResult::Error(e) = item { return EvalResult::Error(e); }
-                if item.as_boolean().unwrap_or(false) { true_count += 1; }
+            match self.logical_values(arg) {
+                Err(e) => return EvalResult::Error(e),
+                Ok(values) => {
+                    any |= !values.is_empty();
+                    true_count += values.iter().filter(|v| **v).count();
+                }
             }
         }
+        if !any { return EvalResult::Error(CellError::Value); }
         EvalResult::Boolean(true_count % 2 != 0)
     }
 
@@ -4021,7 +4953,15 @@ impl<'a> Evaluator<'a> {
 
     fn fn_sumproduct(&self, args: &[Expression]) -> EvalResult {
         if args.is_empty() { return EvalResult::Error(CellError::Value); }
-        let arrays: Vec<Vec<EvalResult>> = args.iter().map(|a| self.eval_flat(a)).collect();
+        let values: Vec<EvalResult> = args.iter().map(|a| self.evaluate(a)).collect();
+        let dims = values[0].spill_dimensions();
+        if let Some(other) = values.iter().map(EvalResult::spill_dimensions).find(|d| *d != dims) {
+            return self.fail(
+                CellError::Value,
+                format!("SUMPRODUCT range sizes differ: {}x{} vs {}x{}", dims.0, dims.1, other.0, other.1),
+            );
+        }
+        let arrays: Vec<Vec<EvalResult>> = values.into_iter().map(EvalResult::into_flatten).collect();
         let len = arrays[0].len();
         if arrays.iter().any(|a| a.len() != len) {
             return EvalResult::Error(CellError::Value);
@@ -4030,7 +4970,13 @@ impl<'a> Evaluator<'a> {
         for i in 0..len {
             let mut product = 1.0;
             for arr in &arrays {
-                product *= arr[i].as_number().unwrap_or(0.0);
+                // Only real numbers count: text (even numeric text), booleans
+                // and blanks are zero, which is why --(cond) is needed.
+                match &arr[i] {
+                    EvalResult::Number(n) => product *= n,
+                    EvalResult::Error(e) => return EvalResult::Error(e.clone()),
+                    _ => product = 0.0,
+                }
             }
             total += product;
         }
@@ -4087,7 +5033,8 @@ impl<'a> Evaluator<'a> {
         let mut hasher = s.build_hasher();
         hasher.write_u64(0);
         let bits = hasher.finish();
-        EvalResult::Number((bits as f64) / (u64::MAX as f64))
+        // Top 53 bits over 2^53: uniform in [0, 1), never exactly 1
+        EvalResult::Number((bits >> 11) as f64 / (1u64 << 53) as f64)
     }
 
     fn fn_randbetween(&self, args: &[Expression]) -> EvalResult {
@@ -4100,7 +5047,8 @@ impl<'a> Evaluator<'a> {
             Some(n) => n.floor() as i64,
             None => return EvalResult::Error(CellError::Value),
         };
-        if bottom > top { return EvalResult::Error(CellError::Value); }
+        // An empty interval is #NUM!
+        if bottom > top { return EvalResult::Error(CellError::Num); }
         // Generate random using hashing
         use std::collections::hash_map::RandomState;
         use std::hash::{BuildHasher, Hasher};
@@ -4115,14 +5063,19 @@ impl<'a> Evaluator<'a> {
 
     fn fn_log(&self, args: &[Expression]) -> EvalResult {
         if args.is_empty() || args.len() > 2 { return EvalResult::Error(CellError::Value); }
+        // Non-positive number or base: #NUM!
         let n = match self.evaluate(&args[0]).as_number() {
             Some(n) if n > 0.0 => n,
-            _ => return EvalResult::Error(CellError::Value),
+            Some(_) => return EvalResult::Error(CellError::Num),
+            None => return EvalResult::Error(CellError::Value),
         };
         let base = if args.len() == 2 {
             match self.evaluate(&args[1]).as_number() {
-                Some(b) if b > 0.0 && (b - 1.0).abs() > 1e-10 => b,
-                _ => return EvalResult::Error(CellError::Value),
+                // ln(1) = 0 is the divisor
+                Some(1.0) => return EvalResult::Error(CellError::Div0),
+                Some(b) if b > 0.0 => b,
+                Some(_) => return EvalResult::Error(CellError::Num),
+                None => return EvalResult::Error(CellError::Value),
             }
         } else {
             10.0
@@ -4134,7 +5087,8 @@ impl<'a> Evaluator<'a> {
         if args.len() != 1 { return EvalResult::Error(CellError::Value); }
         match self.evaluate(&args[0]).as_number() {
             Some(n) if n > 0.0 => EvalResult::Number(n.log10()),
-            _ => EvalResult::Error(CellError::Value),
+            Some(_) => EvalResult::Error(CellError::Num),
+            None => EvalResult::Error(CellError::Value),
         }
     }
 
@@ -4142,35 +5096,45 @@ impl<'a> Evaluator<'a> {
         if args.len() != 1 { return EvalResult::Error(CellError::Value); }
         match self.evaluate(&args[0]).as_number() {
             Some(n) if n > 0.0 => EvalResult::Number(n.ln()),
-            _ => EvalResult::Error(CellError::Value),
+            Some(_) => EvalResult::Error(CellError::Num),
+            None => EvalResult::Error(CellError::Value),
         }
     }
 
     fn fn_exp(&self, args: &[Expression]) -> EvalResult {
         if args.len() != 1 { return EvalResult::Error(CellError::Value); }
         match self.evaluate(&args[0]).as_number() {
-            Some(n) => EvalResult::Number(n.exp()),
+            Some(n) if n.exp().is_finite() => EvalResult::Number(n.exp()),
+            // Overflow past ~709.78
+            Some(_) => EvalResult::Error(CellError::Num),
             None => EvalResult::Error(CellError::Value),
         }
     }
 
-    fn fn_sin(&self, args: &[Expression]) -> EvalResult {
+    /// SIN/COS/TAN of an angle below Excel's 2^27 argument limit.
+    fn trig_of_angle(&self, args: &[Expression], f: fn(f64) -> f64) -> EvalResult {
         if args.len() != 1 { return EvalResult::Error(CellError::Value); }
-        match self.evaluate(&args[0]).as_number() { Some(n) => EvalResult::Number(n.sin()), None => EvalResult::Error(CellError::Value) }
+        match self.evaluate(&args[0]).as_number() {
+            Some(n) if n.abs() < TRIG_ARG_LIMIT => EvalResult::Number(f(n)),
+            Some(_) => EvalResult::Error(CellError::Num),
+            None => EvalResult::Error(CellError::Value),
+        }
+    }
+    fn fn_sin(&self, args: &[Expression]) -> EvalResult {
+        self.trig_of_angle(args, f64::sin)
     }
     fn fn_cos(&self, args: &[Expression]) -> EvalResult {
-        if args.len() != 1 { return EvalResult::Error(CellError::Value); }
-        match self.evaluate(&args[0]).as_number() { Some(n) => EvalResult::Number(n.cos()), None => EvalResult::Error(CellError::Value) }
+        self.trig_of_angle(args, f64::cos)
     }
     fn fn_tan(&self, args: &[Expression]) -> EvalResult {
-        if args.len() != 1 { return EvalResult::Error(CellError::Value); }
-        match self.evaluate(&args[0]).as_number() { Some(n) => EvalResult::Number(n.tan()), None => EvalResult::Error(CellError::Value) }
+        self.trig_of_angle(args, f64::tan)
     }
     fn fn_asin(&self, args: &[Expression]) -> EvalResult {
         if args.len() != 1 { return EvalResult::Error(CellError::Value); }
         match self.evaluate(&args[0]).as_number() {
             Some(n) if (-1.0..=1.0).contains(&n) => EvalResult::Number(n.asin()),
-            Some(_) => EvalResult::Error(CellError::Value),
+            // Outside [-1, 1]
+            Some(_) => EvalResult::Error(CellError::Num),
             None => EvalResult::Error(CellError::Value),
         }
     }
@@ -4178,7 +5142,8 @@ impl<'a> Evaluator<'a> {
         if args.len() != 1 { return EvalResult::Error(CellError::Value); }
         match self.evaluate(&args[0]).as_number() {
             Some(n) if (-1.0..=1.0).contains(&n) => EvalResult::Number(n.acos()),
-            Some(_) => EvalResult::Error(CellError::Value),
+            // Outside [-1, 1]
+            Some(_) => EvalResult::Error(CellError::Num),
             None => EvalResult::Error(CellError::Value),
         }
     }
@@ -4198,16 +5163,16 @@ impl<'a> Evaluator<'a> {
         if args.len() != 2 { return EvalResult::Error(CellError::Value); }
         let n = match self.evaluate(&args[0]).as_number() { Some(n) => n, None => return EvalResult::Error(CellError::Value) };
         let digits = match self.evaluate(&args[1]).as_number() { Some(n) => n as i32, None => return EvalResult::Error(CellError::Value) };
-        let factor = 10f64.powi(digits);
-        EvalResult::Number(if n >= 0.0 { (n * factor).ceil() / factor } else { (n * factor).floor() / factor })
+        // Away from zero: ROUNDUP(-2.31, 1) = -2.4
+        EvalResult::Number(round_magnitude(n, digits, f64::ceil))
     }
 
     fn fn_rounddown(&self, args: &[Expression]) -> EvalResult {
         if args.len() != 2 { return EvalResult::Error(CellError::Value); }
         let n = match self.evaluate(&args[0]).as_number() { Some(n) => n, None => return EvalResult::Error(CellError::Value) };
         let digits = match self.evaluate(&args[1]).as_number() { Some(n) => n as i32, None => return EvalResult::Error(CellError::Value) };
-        let factor = 10f64.powi(digits);
-        EvalResult::Number(if n >= 0.0 { (n * factor).floor() / factor } else { (n * factor).ceil() / factor })
+        // Toward zero: ROUNDDOWN(-2.39, 1) = -2.3
+        EvalResult::Number(round_magnitude(n, digits, f64::trunc))
     }
 
     fn fn_trunc(&self, args: &[Expression]) -> EvalResult {
@@ -4216,8 +5181,7 @@ impl<'a> Evaluator<'a> {
         let digits = if args.len() == 2 {
             match self.evaluate(&args[1]).as_number() { Some(d) => d as i32, None => return EvalResult::Error(CellError::Value) }
         } else { 0 };
-        let factor = 10f64.powi(digits);
-        EvalResult::Number((n * factor).trunc() / factor)
+        EvalResult::Number(round_magnitude(n, digits, f64::trunc))
     }
 
     fn fn_even(&self, args: &[Expression]) -> EvalResult {
@@ -4245,54 +5209,70 @@ impl<'a> Evaluator<'a> {
         }
     }
 
+    /// GCD/LCM operands: ranges and arrays flattened, each truncated to an
+    /// integer. Negative values and values of 2^53 or more are #NUM!, and
+    /// non-numeric text is #VALUE!.
+    fn collect_whole_numbers(&self, args: &[Expression]) -> Result<Vec<u64>, CellError> {
+        if args.is_empty() { return Err(CellError::Value); }
+        self.collect_values(args)?
+            .iter()
+            .map(|v| match v.as_number().map(f64::trunc) {
+                Some(n) if (0.0..9007199254740992.0).contains(&n) => Ok(n as u64),
+                Some(_) => Err(CellError::Num),
+                None => Err(CellError::Value),
+            })
+            .collect()
+    }
+
+    /// GCD(number1, ...): greatest common divisor; GCD(0, n) = n.
     fn fn_gcd(&self, args: &[Expression]) -> EvalResult {
-        if args.is_empty() { return EvalResult::Error(CellError::Value); }
-        fn gcd(a: u64, b: u64) -> u64 { if b == 0 { a } else { gcd(b, a % b) } }
-        let mut result = 0u64;
-        for arg in args {
-            match self.evaluate(arg).as_number() {
-                Some(n) if n >= 0.0 => result = gcd(result, n as u64),
-                _ => return EvalResult::Error(CellError::Value),
-            }
+        match self.collect_whole_numbers(args) {
+            Ok(numbers) => EvalResult::Number(numbers.into_iter().fold(0, gcd_u64) as f64),
+            Err(e) => EvalResult::Error(e),
         }
-        EvalResult::Number(result as f64)
     }
 
+    /// LCM(number1, ...): least common multiple; any zero makes it 0. A
+    /// result of 2^53 or more is #NUM!.
     fn fn_lcm(&self, args: &[Expression]) -> EvalResult {
-        if args.is_empty() { return EvalResult::Error(CellError::Value); }
-        fn gcd(a: u64, b: u64) -> u64 { if b == 0 { a } else { gcd(b, a % b) } }
-        fn lcm(a: u64, b: u64) -> u64 { if a == 0 || b == 0 { 0 } else { a / gcd(a, b) * b } }
+        let numbers = match self.collect_whole_numbers(args) {
+            Ok(numbers) => numbers,
+            Err(e) => return EvalResult::Error(e),
+        };
         let mut result = 1u64;
-        for arg in args {
-            match self.evaluate(arg).as_number() {
-                Some(n) if n >= 0.0 => result = lcm(result, n as u64),
-                _ => return EvalResult::Error(CellError::Value),
-            }
+        for n in numbers {
+            if n == 0 { return EvalResult::Number(0.0); }
+            result = match (result / gcd_u64(result, n)).checked_mul(n) {
+                Some(m) if m < 1 << 53 => m,
+                _ => return EvalResult::Error(CellError::Num),
+            };
         }
         EvalResult::Number(result as f64)
     }
 
+    /// COMBIN(number, number_chosen), both truncated. Negative counts or
+    /// chosen > number are #NUM!, as is a result beyond f64.
     fn fn_combin(&self, args: &[Expression]) -> EvalResult {
         if args.len() != 2 { return EvalResult::Error(CellError::Value); }
-        let n = match self.evaluate(&args[0]).as_number() { Some(v) => v as u64, None => return EvalResult::Error(CellError::Value) };
-        let k = match self.evaluate(&args[1]).as_number() { Some(v) => v as u64, None => return EvalResult::Error(CellError::Value) };
-        if k > n { return EvalResult::Error(CellError::Value); }
-        let k = k.min(n - k);
-        let mut result = 1u64;
-        for i in 0..k { result = result * (n - i) / (i + 1); }
-        EvalResult::Number(result as f64)
+        let n = match self.evaluate(&args[0]).as_number() { Some(v) => v.trunc(), None => return EvalResult::Error(CellError::Value) };
+        let k = match self.evaluate(&args[1]).as_number() { Some(v) => v.trunc(), None => return EvalResult::Error(CellError::Value) };
+        match combinations(n, k) {
+            Some(c) => EvalResult::Number(c),
+            None => EvalResult::Error(CellError::Num),
+        }
     }
 
+    /// FACT(number), truncated. Negative numbers and 171! and above (past
+    /// f64) are #NUM!.
     fn fn_fact(&self, args: &[Expression]) -> EvalResult {
         if args.len() != 1 { return EvalResult::Error(CellError::Value); }
-        match self.evaluate(&args[0]).as_number() {
-            Some(n) if n >= 0.0 => {
-                let n = n as u64;
-                let mut result = 1u64;
-                for i in 2..=n { result = result.saturating_mul(i); }
-                EvalResult::Number(result as f64)
-            }
-            _ => EvalResult::Error(CellError::Value),
+        match self.evaluate(&args[0]).as_number().map(f64::trunc) {
+            Some(n) if n >= 0.0 => match permutations(n, n) {
+                Some(f) => EvalResult::Number(f),
+                None => EvalResult::Error(CellError::Num),
+            },
+            Some(_) => EvalResult::Error(CellError::Num),
+            None => EvalResult::Error(CellError::Value),
         }
     }
 
@@ -4556,11 +5536,10 @@ impl<'a> Evaluator<'a> {
         let k = match self.evaluate(&args[1]).as_number() { Some(v) => v as u64, None => return EvalResult::Error(CellError::Value) };
         // COMBINA(n, k) = COMBIN(n + k - 1, k)
         if n == 0 && k == 0 { return EvalResult::Number(1.0); }
-        let nn = n + k - 1;
-        let kk = k.min(nn - k);
-        let mut result = 1u64;
-        for i in 0..kk { result = result * (nn - i) / (i + 1); }
-        EvalResult::Number(result as f64)
+        match combinations((n + k - 1) as f64, k as f64) {
+            Some(c) => EvalResult::Number(c),
+            None => EvalResult::Error(CellError::Value),
+        }
     }
 
     fn fn_factdouble(&self, args: &[Expression]) -> EvalResult {
@@ -4592,6 +5571,10 @@ impl<'a> Evaluator<'a> {
 
     // ==================== AGGREGATE Function ====================
 
+    /// AGGREGATE(function_num, options, ref1, [ref2], ...) or
+    /// AGGREGATE(function_num, options, array, k) for 14-19.
+    /// Options: 0-3 skip nested SUBTOTAL/AGGREGATE cells, 1/3/5/7 skip hidden
+    /// rows, 2/3/6/7 skip error values.
     fn fn_aggregate(&self, args: &[Expression]) -> EvalResult {
         if args.len() < 3 {
             return EvalResult::Error(CellError::Value);
@@ -4617,16 +5600,14 @@ impl<'a> Evaluator<'a> {
             return EvalResult::Error(CellError::Value);
         }
 
-        let skip_errors = matches!(options, 1 | 2 | 3 | 5 | 6 | 7);
+        let skip_nested = options <= 3;
+        let skip_hidden = options % 2 == 1;
+        let skip_errors = matches!(options, 2 | 3 | 6 | 7);
 
         let range_args = &args[2..];
 
         // Collect values from ranges
-        let mut values = Vec::new();
-        for arg in range_args {
-            let result = self.evaluate(arg);
-            Self::flatten_into(&mut values, result);
-        }
+        let mut values = self.collect_subtotal_values(range_args, skip_hidden, skip_nested);
 
         // Optionally skip errors
         if skip_errors {
@@ -4756,16 +5737,11 @@ impl<'a> Evaluator<'a> {
                     Some(k) => k as usize,
                     None => return EvalResult::Error(CellError::Value),
                 };
-                // Recollect values from all but last arg
+                // Recollect values from all but last arg (the k argument)
                 let mut nums = Vec::new();
-                for arg in &args[2..args.len()-1] {
-                    let result = self.evaluate(arg);
-                    let mut flat = Vec::new();
-                    Self::flatten_into(&mut flat, result);
-                    for v in flat {
-                        if skip_errors && matches!(v, EvalResult::Error(_)) { continue; }
-                        if let Some(n) = v.as_number() { nums.push(n); }
-                    }
+                for v in self.collect_subtotal_values(&args[2..args.len()-1], skip_hidden, skip_nested) {
+                    if skip_errors && matches!(v, EvalResult::Error(_)) { continue; }
+                    if let Some(n) = v.as_number() { nums.push(n); }
                 }
                 if k_val < 1 || k_val > nums.len() { return EvalResult::Error(CellError::Value); }
                 nums.sort_by(|a, b| b.partial_cmp(a).unwrap_or(std::cmp::Ordering::Equal));
@@ -4779,14 +5755,9 @@ impl<'a> Evaluator<'a> {
                     None => return EvalResult::Error(CellError::Value),
                 };
                 let mut nums = Vec::new();
-                for arg in &args[2..args.len()-1] {
-                    let result = self.evaluate(arg);
-                    let mut flat = Vec::new();
-                    Self::flatten_into(&mut flat, result);
-                    for v in flat {
-                        if skip_errors && matches!(v, EvalResult::Error(_)) { continue; }
-                        if let Some(n) = v.as_number() { nums.push(n); }
-                    }
+                for v in self.collect_subtotal_values(&args[2..args.len()-1], skip_hidden, skip_nested) {
+                    if skip_errors && matches!(v, EvalResult::Error(_)) { continue; }
+                    if let Some(n) = v.as_number() { nums.push(n); }
                 }
                 if k_val < 1 || k_val > nums.len() { return EvalResult::Error(CellError::Value); }
                 nums.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
@@ -4800,14 +5771,9 @@ impl<'a> Evaluator<'a> {
                     _ => return EvalResult::Error(CellError::Value),
                 };
                 let mut nums = Vec::new();
-                for arg in &args[2..args.len()-1] {
-                    let result = self.evaluate(arg);
-                    let mut flat = Vec::new();
-                    Self::flatten_into(&mut flat, result);
-                    for v in flat {
-                        if skip_errors && matches!(v, EvalResult::Error(_)) { continue; }
-                        if let Some(n) = v.as_number() { nums.push(n); }
-                    }
+                for v in self.collect_subtotal_values(&args[2..args.len()-1], skip_hidden, skip_nested) {
+                    if skip_errors && matches!(v, EvalResult::Error(_)) { continue; }
+                    if let Some(n) = v.as_number() { nums.push(n); }
                 }
                 if nums.is_empty() { return EvalResult::Error(CellError::Value); }
                 nums.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
@@ -4830,14 +5796,9 @@ impl<'a> Evaluator<'a> {
                 };
                 let k_val = q as f64 / 4.0;
                 let mut nums = Vec::new();
-                for arg in &args[2..args.len()-1] {
-                    let result = self.evaluate(arg);
-                    let mut flat = Vec::new();
-                    Self::flatten_into(&mut flat, result);
-                    for v in flat {
-                        if skip_errors && matches!(v, EvalResult::Error(_)) { continue; }
-                        if let Some(n) = v.as_number() { nums.push(n); }
-                    }
+                for v in self.collect_subtotal_values(&args[2..args.len()-1], skip_hidden, skip_nested) {
+                    if skip_errors && matches!(v, EvalResult::Error(_)) { continue; }
+                    if let Some(n) = v.as_number() { nums.push(n); }
                 }
                 if nums.is_empty() { return EvalResult::Error(CellError::Value); }
                 nums.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
@@ -4859,14 +5820,9 @@ impl<'a> Evaluator<'a> {
                     _ => return EvalResult::Error(CellError::Value),
                 };
                 let mut nums = Vec::new();
-                for arg in &args[2..args.len()-1] {
-                    let result = self.evaluate(arg);
-                    let mut flat = Vec::new();
-                    Self::flatten_into(&mut flat, result);
-                    for v in flat {
-                        if skip_errors && matches!(v, EvalResult::Error(_)) { continue; }
-                        if let Some(n) = v.as_number() { nums.push(n); }
-                    }
+                for v in self.collect_subtotal_values(&args[2..args.len()-1], skip_hidden, skip_nested) {
+                    if skip_errors && matches!(v, EvalResult::Error(_)) { continue; }
+                    if let Some(n) = v.as_number() { nums.push(n); }
                 }
                 if nums.is_empty() { return EvalResult::Error(CellError::Value); }
                 nums.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
@@ -4890,14 +5846,9 @@ impl<'a> Evaluator<'a> {
                 };
                 let k_val = q as f64 / 4.0;
                 let mut nums = Vec::new();
-                for arg in &args[2..args.len()-1] {
-                    let result = self.evaluate(arg);
-                    let mut flat = Vec::new();
-                    Self::flatten_into(&mut flat, result);
-                    for v in flat {
-                        if skip_errors && matches!(v, EvalResult::Error(_)) { continue; }
-                        if let Some(n) = v.as_number() { nums.push(n); }
-                    }
+                for v in self.collect_subtotal_values(&args[2..args.len()-1], skip_hidden, skip_nested) {
+                    if skip_errors && matches!(v, EvalResult::Error(_)) { continue; }
+                    if let Some(n) = v.as_number() { nums.push(n); }
                 }
                 if nums.is_empty() { return EvalResult::Error(CellError::Value); }
                 nums.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
@@ -4935,115 +5886,179 @@ impl<'a> Evaluator<'a> {
         EvalResult::Text(encoded)
     }
 
+    /// HYPERLINK(link_location, [friendly_name]): shows `friendly_name` (the
+    /// link itself when omitted) and asks the UI to link the evaluating cell
+    /// to `link_location`.
+    fn fn_hyperlink(&self, args: &[Expression]) -> EvalResult {
+        if args.is_empty() || args.len() > 2 {
+            return EvalResult::Error(CellError::Value);
+        }
+        let target = match self.text_arg(&args[0]) {
+            Ok(target) => target,
+            Err(e) => return EvalResult::Error(e),
+        };
+        let display = match args.get(1) {
+            Some(arg) => match self.evaluate(arg) {
+                EvalResult::Error(e) => return EvalResult::Error(e),
+                value => value,
+            },
+            None => EvalResult::Text(target.clone()),
+        };
+        if let (Some(row), Some(col)) = (self.context.current_row, self.context.current_col) {
+            if !target.is_empty() {
+                self.ui_effects.borrow_mut().push(UiEffect::SetHyperlink { row, col, target });
+            }
+        }
+        display
+    }
+
     // ==================== Text Functions (Batch 6) ====================
 
-    fn fn_find(&self, args: &[Expression]) -> EvalResult {
-        if args.len() < 2 || args.len() > 3 { return EvalResult::Error(CellError::Value); }
-        let find_text = self.evaluate(&args[0]).as_text();
-        let within_text = self.evaluate(&args[1]).as_text();
-        let start = if args.len() == 3 {
-            match self.evaluate(&args[2]).as_number() { Some(n) if n >= 1.0 => (n as usize) - 1, _ => return EvalResult::Error(CellError::Value) }
-        } else { 0 };
-        if start > within_text.len() { return EvalResult::Error(CellError::Value); }
-        match within_text[start..].find(&find_text) {
-            Some(pos) => EvalResult::Number((start + pos + 1) as f64),
-            None => EvalResult::Error(CellError::Value),
+    /// A text argument; errors propagate.
+    fn text_arg(&self, arg: &Expression) -> Result<String, CellError> {
+        match self.evaluate(arg) {
+            EvalResult::Error(e) => Err(e),
+            v => Ok(v.as_text()),
         }
     }
 
-    fn fn_search(&self, args: &[Expression]) -> EvalResult {
-        if args.len() < 2 || args.len() > 3 { return EvalResult::Error(CellError::Value); }
-        let find_text = self.evaluate(&args[0]).as_text().to_uppercase();
-        let within_text = self.evaluate(&args[1]).as_text().to_uppercase();
-        let start = if args.len() == 3 {
-            match self.evaluate(&args[2]).as_number() { Some(n) if n >= 1.0 => (n as usize) - 1, _ => return EvalResult::Error(CellError::Value) }
-        } else { 0 };
-        if start > within_text.len() { return EvalResult::Error(CellError::Value); }
-        // SEARCH supports wildcards
-        if find_text.contains('*') || find_text.contains('?') {
-            // Try matching at each position
-            for pos in start..within_text.len() {
-                if self.xlookup_wildcard_match(&find_text, &within_text[pos..]) {
-                    return EvalResult::Number((pos + 1) as f64);
-                }
+    /// A whole-number argument (position or count) of at least `min`;
+    /// errors propagate, anything else is #VALUE!.
+    fn whole_arg(&self, arg: &Expression, min: f64) -> Result<usize, CellError> {
+        match self.evaluate(arg) {
+            EvalResult::Error(e) => Err(e),
+            v => match v.as_number() {
+                Some(n) if n >= min => Ok(n.trunc() as usize),
+                _ => Err(CellError::Value),
+            },
+        }
+    }
+
+    /// Shared body of FIND and SEARCH: the 1-based character position of
+    /// find_text in within_text at or after start_num.
+    fn find_position(&self, args: &[Expression], search: bool) -> Result<usize, CellError> {
+        if args.len() < 2 || args.len() > 3 { return Err(CellError::Value); }
+        let find_text = self.text_arg(&args[0])?;
+        let within_text = self.text_arg(&args[1])?;
+        let start = match args.get(2) { Some(arg) => self.whole_arg(arg, 1.0)? - 1, None => 0 };
+        let chars = |text: &str| -> Vec<char> {
+            if search { text.chars().map(fold_case).collect() } else { text.chars().collect() }
+        };
+        let within = chars(&within_text);
+        let find = chars(&find_text);
+        if start > within.len() { return Err(CellError::Value); }
+
+        let found = if search {
+            let pattern = wildcard_tokens(&find);
+            let literal: Option<Vec<char>> = pattern
+                .iter()
+                .map(|t| match t { WildcardToken::Literal(c) => Some(*c), _ => None })
+                .collect();
+            match literal {
+                Some(literal) => find_chars(&within, &literal, start),
+                None => (start..=within.len()).find(|&i| wildcard_prefix_match(&pattern, &within[i..])),
             }
-            EvalResult::Error(CellError::Value)
         } else {
-            match within_text[start..].find(&find_text) {
-                Some(pos) => EvalResult::Number((start + pos + 1) as f64),
-                None => EvalResult::Error(CellError::Value),
-            }
+            find_chars(&within, &find, start)
+        };
+        found.map(|i| i + 1).ok_or(CellError::Value)
+    }
+
+    fn fn_find(&self, args: &[Expression]) -> EvalResult {
+        // FIND(find_text, within_text, [start_num]): case-sensitive, no wildcards
+        match self.find_position(args, false) {
+            Ok(pos) => EvalResult::Number(pos as f64),
+            Err(e) => EvalResult::Error(e),
+        }
+    }
+
+    fn fn_search(&self, args: &[Expression]) -> EvalResult {
+        // SEARCH(find_text, within_text, [start_num]): case-insensitive, * and ? with ~ escapes
+        match self.find_position(args, true) {
+            Ok(pos) => EvalResult::Number(pos as f64),
+            Err(e) => EvalResult::Error(e),
         }
     }
 
     fn fn_substitute(&self, args: &[Expression]) -> EvalResult {
         if args.len() < 3 || args.len() > 4 { return EvalResult::Error(CellError::Value); }
-        let text = self.evaluate(&args[0]).as_text();
-        let old_text = self.evaluate(&args[1]).as_text();
-        let new_text = self.evaluate(&args[2]).as_text();
+        let text = match self.text_arg(&args[0]) { Ok(t) => t, Err(e) => return EvalResult::Error(e) };
+        let old_text = match self.text_arg(&args[1]) { Ok(t) => t, Err(e) => return EvalResult::Error(e) };
+        let new_text = match self.text_arg(&args[2]) { Ok(t) => t, Err(e) => return EvalResult::Error(e) };
+        let instance = match args.get(3).map(|arg| self.whole_arg(arg, 1.0)) {
+            Some(Ok(n)) => Some(n),
+            Some(Err(e)) => return EvalResult::Error(e),
+            None => None,
+        };
         if old_text.is_empty() { return EvalResult::Text(text); }
-        if args.len() == 4 {
-            let instance = match self.evaluate(&args[3]).as_number() { Some(n) if n >= 1.0 => n as usize, _ => return EvalResult::Error(CellError::Value) };
-            let mut count = 0usize;
-            let mut result = String::new();
-            let mut remaining = text.as_str();
-            while let Some(pos) = remaining.find(&old_text) {
-                count += 1;
-                if count == instance {
-                    result.push_str(&remaining[..pos]);
+        match instance {
+            None => EvalResult::Text(text.replace(&old_text, &new_text)),
+            Some(n) => match text.match_indices(&old_text).nth(n - 1) {
+                Some((pos, _)) => {
+                    let mut result = String::with_capacity(text.len() + new_text.len());
+                    result.push_str(&text[..pos]);
                     result.push_str(&new_text);
-                    result.push_str(&remaining[pos + old_text.len()..]);
-                    return EvalResult::Text(result);
+                    result.push_str(&text[pos + old_text.len()..]);
+                    EvalResult::Text(result)
                 }
-                result.push_str(&remaining[..pos + old_text.len()]);
-                remaining = &remaining[pos + old_text.len()..];
-            }
-            result.push_str(remaining);
-            EvalResult::Text(result)
-        } else {
-            EvalResult::Text(text.replace(&old_text, &new_text))
+                None => EvalResult::Text(text),
+            },
         }
     }
 
     fn fn_replace(&self, args: &[Expression]) -> EvalResult {
+        // REPLACE(old_text, start_num, num_chars, new_text), counted in characters
         if args.len() != 4 { return EvalResult::Error(CellError::Value); }
-        let text = self.evaluate(&args[0]).as_text();
-        let start = match self.evaluate(&args[1]).as_number() { Some(n) if n >= 1.0 => (n as usize) - 1, _ => return EvalResult::Error(CellError::Value) };
-        let num_chars = match self.evaluate(&args[2]).as_number() { Some(n) if n >= 0.0 => n as usize, _ => return EvalResult::Error(CellError::Value) };
-        let new_text = self.evaluate(&args[3]).as_text();
+        let text = match self.text_arg(&args[0]) { Ok(t) => t, Err(e) => return EvalResult::Error(e) };
+        let start = match self.whole_arg(&args[1], 1.0) { Ok(n) => n - 1, Err(e) => return EvalResult::Error(e) };
+        let num_chars = match self.whole_arg(&args[2], 0.0) { Ok(n) => n, Err(e) => return EvalResult::Error(e) };
+        let new_text = match self.text_arg(&args[3]) { Ok(t) => t, Err(e) => return EvalResult::Error(e) };
         let chars: Vec<char> = text.chars().collect();
-        let mut result = String::new();
-        for (i, c) in chars.iter().enumerate() {
-            if i == start { result.push_str(&new_text); }
-            if i < start || i >= start + num_chars { result.push(*c); }
-        }
-        if start >= chars.len() { result.push_str(&new_text); }
+        let start = start.min(chars.len());
+        let end = start.saturating_add(num_chars).min(chars.len());
+        let mut result: String = chars[..start].iter().collect();
+        result.push_str(&new_text);
+        result.extend(&chars[end..]);
         EvalResult::Text(result)
     }
 
+    /// Reads numbers the way typed input does ("1,234.5", "$12", "(7)",
+    /// "15%", "1E3"), then dates, date-times and times as their serials.
     fn fn_value(&self, args: &[Expression]) -> EvalResult {
         if args.len() != 1 { return EvalResult::Error(CellError::Value); }
-        let text = self.evaluate(&args[0]).as_text();
-        match text.trim().parse::<f64>() {
-            Ok(n) => EvalResult::Number(n),
-            Err(_) => EvalResult::Error(CellError::Value),
+        let text = match self.evaluate(&args[0]) {
+            EvalResult::Number(n) => return EvalResult::Number(n),
+            EvalResult::Error(e) => return EvalResult::Error(e),
+            EvalResult::Text(t) => t,
+            _ => return EvalResult::Error(CellError::Value),
+        };
+        let number = number_format::parse_number_text(&text, '.', Some(','), Some("$"))
+            .or_else(|| date_serial::parse_date_input(&text, false).map(|(serial, _)| serial))
+            .or_else(|| date_serial::parse_time_string(&text));
+        match number {
+            Some(n) => EvalResult::Number(n),
+            None => self.fail(CellError::Value, "VALUE could not read the text as a number"),
         }
     }
 
     fn fn_exact(&self, args: &[Expression]) -> EvalResult {
         if args.len() != 2 { return EvalResult::Error(CellError::Value); }
-        let a = self.evaluate(&args[0]).as_text();
-        let b = self.evaluate(&args[1]).as_text();
-        EvalResult::Boolean(a == b) // case-sensitive
+        match (self.text_arg(&args[0]), self.text_arg(&args[1])) {
+            (Ok(a), Ok(b)) => EvalResult::Boolean(a == b), // case-sensitive
+            (Err(e), _) | (_, Err(e)) => EvalResult::Error(e),
+        }
     }
 
+    /// Capitalizes the first letter of every word, where a word starts at
+    /// any letter that follows a non-letter ("2nd" -> "2Nd", "o'neil" ->
+    /// "O'Neil", as in Excel).
     fn fn_proper(&self, args: &[Expression]) -> EvalResult {
         if args.len() != 1 { return EvalResult::Error(CellError::Value); }
-        let text = self.evaluate(&args[0]).as_text();
-        let mut result = String::new();
+        let text = match self.text_arg(&args[0]) { Ok(t) => t, Err(e) => return EvalResult::Error(e) };
+        let mut result = String::with_capacity(text.len());
         let mut capitalize_next = true;
         for c in text.chars() {
-            if c.is_alphanumeric() {
+            if c.is_alphabetic() {
                 if capitalize_next { result.extend(c.to_uppercase()); capitalize_next = false; }
                 else { result.extend(c.to_lowercase()); }
             } else {
@@ -5054,38 +6069,63 @@ impl<'a> Evaluator<'a> {
         EvalResult::Text(result)
     }
 
+    /// CHAR(1..255) in the Windows-1252 character set, like Excel on Windows.
     fn fn_char(&self, args: &[Expression]) -> EvalResult {
         if args.len() != 1 { return EvalResult::Error(CellError::Value); }
-        match self.evaluate(&args[0]).as_number() {
-            Some(n) if n >= 1.0 && n <= 255.0 => EvalResult::Text(String::from(n as u8 as char)),
-            _ => EvalResult::Error(CellError::Value),
+        match self.whole_arg(&args[0], 1.0) {
+            Ok(n) if n <= 255 => EvalResult::Text(cp1252_char(n as u8).to_string()),
+            Ok(_) => self.fail(CellError::Value, "CHAR needs a number from 1 to 255"),
+            Err(e) => EvalResult::Error(e),
         }
     }
 
+    /// Windows-1252 code of the first character; characters outside that
+    /// set give 63 ("?"), as Excel does.
     fn fn_code(&self, args: &[Expression]) -> EvalResult {
         if args.len() != 1 { return EvalResult::Error(CellError::Value); }
-        let text = self.evaluate(&args[0]).as_text();
+        let text = match self.text_arg(&args[0]) { Ok(t) => t, Err(e) => return EvalResult::Error(e) };
         match text.chars().next() {
-            Some(c) => EvalResult::Number(c as u32 as f64),
-            None => EvalResult::Error(CellError::Value),
+            Some(c) => EvalResult::Number(cp1252_code(c).unwrap_or(b'?') as f64),
+            None => self.fail(CellError::Value, "CODE needs at least one character"),
         }
     }
 
+    /// Removes the non-printable ASCII control characters 0-31 (Excel leaves
+    /// 127 and the Unicode controls alone).
     fn fn_clean(&self, args: &[Expression]) -> EvalResult {
         if args.len() != 1 { return EvalResult::Error(CellError::Value); }
-        let text = self.evaluate(&args[0]).as_text();
-        EvalResult::Text(text.chars().filter(|c| *c as u32 >= 32).collect())
+        match self.text_arg(&args[0]) {
+            Ok(text) => EvalResult::Text(text.chars().filter(|c| *c as u32 >= 32).collect()),
+            Err(e) => EvalResult::Error(e),
+        }
     }
 
+    /// NUMBERVALUE(text, [decimal_separator], [group_separator]). Only the
+    /// first character of a separator counts and "" means the default.
+    /// Spaces are ignored anywhere; empty text is 0.
     fn fn_numbervalue(&self, args: &[Expression]) -> EvalResult {
         if args.is_empty() || args.len() > 3 { return EvalResult::Error(CellError::Value); }
-        let text = self.evaluate(&args[0]).as_text();
-        let decimal_sep = if args.len() >= 2 { self.evaluate(&args[1]).as_text() } else { ".".to_string() };
-        let group_sep = if args.len() == 3 { self.evaluate(&args[2]).as_text() } else { ",".to_string() };
-        let cleaned = text.replace(&group_sep, "").replace(&decimal_sep, ".");
-        match cleaned.trim().parse::<f64>() {
-            Ok(n) => EvalResult::Number(n),
-            Err(_) => EvalResult::Error(CellError::Value),
+        let text = match self.text_arg(&args[0]) { Ok(t) => t, Err(e) => return EvalResult::Error(e) };
+        let separator = |index: usize, default: char| -> Result<char, CellError> {
+            match args.get(index) {
+                Some(arg) => Ok(self.text_arg(arg)?.chars().next().unwrap_or(default)),
+                None => Ok(default),
+            }
+        };
+        let (decimal, group) = match (separator(1, '.'), separator(2, ',')) {
+            (Ok(d), Ok(g)) => (d, g),
+            (Err(e), _) | (_, Err(e)) => return EvalResult::Error(e),
+        };
+        if decimal == group {
+            return self.fail(CellError::Value, "NUMBERVALUE needs different decimal and group separators");
+        }
+        let compact: String = text.chars().filter(|c| !c.is_whitespace()).collect();
+        if compact.is_empty() {
+            return EvalResult::Number(0.0);
+        }
+        match number_format::parse_number_text(&compact, decimal, Some(group), None) {
+            Some(n) => EvalResult::Number(n),
+            None => self.fail(CellError::Value, "NUMBERVALUE could not read the text as a number"),
         }
     }
 
@@ -5183,51 +6223,87 @@ impl<'a> Evaluator<'a> {
         }
     }
 
+    /// A date argument as a whole serial: errors propagate, date text is
+    /// parsed, a negative serial is #NUM! and anything else non-numeric is
+    /// #VALUE!.
+    fn date_arg(&self, arg: &Expression) -> Result<i64, CellError> {
+        Self::date_serial_of(&self.evaluate(arg))
+    }
+
+    fn date_serial_of(value: &EvalResult) -> Result<i64, CellError> {
+        let n = match value {
+            EvalResult::Error(e) => return Err(e.clone()),
+            EvalResult::Text(s) => s.trim().parse::<f64>().ok().or_else(|| date_serial::parse_date_string(s)),
+            other => other.as_number(),
+        };
+        match n {
+            Some(n) if n >= 0.0 => Ok(n.trunc() as i64),
+            // A negative serial is a number out of range, not a type mismatch.
+            Some(_) => Err(CellError::Num),
+            None => Err(CellError::Value),
+        }
+    }
+
+    /// A whole-number count argument (months, working days); errors propagate.
+    fn count_arg(&self, arg: &Expression) -> Result<i64, CellError> {
+        match self.evaluate(arg) {
+            EvalResult::Error(e) => Err(e),
+            v => v.as_number().map(|n| n.trunc() as i64).ok_or(CellError::Value),
+        }
+    }
+
+    /// The holidays argument (a date, range or array) flattened to serials.
+    /// Blank cells read as 0, which no working day matches.
+    fn holidays_arg(&self, arg: Option<&Expression>) -> Result<Vec<i64>, CellError> {
+        match arg {
+            Some(arg) => self.eval_flat(arg).iter().map(Self::date_serial_of).collect(),
+            None => Ok(Vec::new()),
+        }
+    }
+
     fn fn_edate(&self, args: &[Expression]) -> EvalResult {
         if args.len() != 2 { return EvalResult::Error(CellError::Value); }
-        let serial = match self.evaluate(&args[0]).as_number() { Some(n) => n as i64, None => return EvalResult::Error(CellError::Value) };
-        let months = match self.evaluate(&args[1]).as_number() { Some(n) => n as i32, None => return EvalResult::Error(CellError::Value) };
+        let serial = match self.date_arg(&args[0]) { Ok(n) => n, Err(e) => return EvalResult::Error(e) };
+        let months = match self.count_arg(&args[1]) { Ok(n) => n as i32, Err(e) => return EvalResult::Error(e) };
         let (y, m, d) = date_serial::serial_to_date(serial);
         let (ny, nm, nd) = date_serial::add_months(y, m as i32, d, months);
+        if ny < 1900 { return EvalResult::Error(CellError::Num); }
         EvalResult::Number(date_serial::date_to_serial(ny, nm, nd as i32))
     }
 
     fn fn_eomonth(&self, args: &[Expression]) -> EvalResult {
         if args.len() != 2 { return EvalResult::Error(CellError::Value); }
-        let serial = match self.evaluate(&args[0]).as_number() { Some(n) => n as i64, None => return EvalResult::Error(CellError::Value) };
-        let months = match self.evaluate(&args[1]).as_number() { Some(n) => n as i32, None => return EvalResult::Error(CellError::Value) };
+        let serial = match self.date_arg(&args[0]) { Ok(n) => n, Err(e) => return EvalResult::Error(e) };
+        let months = match self.count_arg(&args[1]) { Ok(n) => n as i32, Err(e) => return EvalResult::Error(e) };
         let (y, m, _) = date_serial::serial_to_date(serial);
         let (ny, nm, _) = date_serial::add_months(y, m as i32, 1, months);
+        if ny < 1900 { return EvalResult::Error(CellError::Num); }
         let last_day = date_serial::days_in_month(ny, nm as u32);
         EvalResult::Number(date_serial::date_to_serial(ny, nm, last_day as i32))
     }
 
     fn fn_networkdays(&self, args: &[Expression]) -> EvalResult {
         if args.len() < 2 || args.len() > 3 { return EvalResult::Error(CellError::Value); }
-        let start = match self.evaluate(&args[0]).as_number() { Some(n) => n as i64, None => return EvalResult::Error(CellError::Value) };
-        let end = match self.evaluate(&args[1]).as_number() { Some(n) => n as i64, None => return EvalResult::Error(CellError::Value) };
-        let holidays: Vec<i64> = if args.len() == 3 {
-            self.eval_flat(&args[2]).iter().filter_map(|v| v.as_number().map(|n| n as i64)).collect()
-        } else { vec![] };
+        let start = match self.date_arg(&args[0]) { Ok(n) => n, Err(e) => return EvalResult::Error(e) };
+        let end = match self.date_arg(&args[1]) { Ok(n) => n, Err(e) => return EvalResult::Error(e) };
+        let holidays = match self.holidays_arg(args.get(2)) { Ok(h) => h, Err(e) => return EvalResult::Error(e) };
         EvalResult::Number(date_serial::networkdays(start, end, &holidays) as f64)
     }
 
     fn fn_workday(&self, args: &[Expression]) -> EvalResult {
         if args.len() < 2 || args.len() > 3 { return EvalResult::Error(CellError::Value); }
-        let start = match self.evaluate(&args[0]).as_number() { Some(n) => n as i64, None => return EvalResult::Error(CellError::Value) };
-        let days = match self.evaluate(&args[1]).as_number() { Some(n) => n as i64, None => return EvalResult::Error(CellError::Value) };
-        let holidays: Vec<i64> = if args.len() == 3 {
-            self.eval_flat(&args[2]).iter().filter_map(|v| v.as_number().map(|n| n as i64)).collect()
-        } else { vec![] };
+        let start = match self.date_arg(&args[0]) { Ok(n) => n, Err(e) => return EvalResult::Error(e) };
+        let days = match self.count_arg(&args[1]) { Ok(n) => n, Err(e) => return EvalResult::Error(e) };
+        let holidays = match self.holidays_arg(args.get(2)) { Ok(h) => h, Err(e) => return EvalResult::Error(e) };
         EvalResult::Number(date_serial::workday(start, days, &holidays) as f64)
     }
 
     fn fn_datedif(&self, args: &[Expression]) -> EvalResult {
         if args.len() != 3 { return EvalResult::Error(CellError::Value); }
-        let start = match self.evaluate(&args[0]).as_number() { Some(n) => n as i64, None => return EvalResult::Error(CellError::Value) };
-        let end = match self.evaluate(&args[1]).as_number() { Some(n) => n as i64, None => return EvalResult::Error(CellError::Value) };
+        let start = match self.date_arg(&args[0]) { Ok(n) => n, Err(e) => return EvalResult::Error(e) };
+        let end = match self.date_arg(&args[1]) { Ok(n) => n, Err(e) => return EvalResult::Error(e) };
         let unit = self.evaluate(&args[2]).as_text().to_uppercase();
-        if start > end { return EvalResult::Error(CellError::Value); }
+        if start > end { return EvalResult::Error(CellError::Num); }
         let (sy, sm, sd) = date_serial::serial_to_date(start);
         let (ey, em, ed) = date_serial::serial_to_date(end);
         match unit.as_str() {
@@ -5249,26 +6325,29 @@ impl<'a> Evaluator<'a> {
             "MD" => {
                 let diff = ed as i32 - sd as i32;
                 EvalResult::Number(if diff >= 0 { diff as f64 } else {
-                    let prev_month_days = date_serial::days_in_month(if em > 1 { ey } else { ey - 1 }, if em > 1 { em - 1 } else { 12 });
-                    (prev_month_days as i32 + diff) as f64
+                    // A start day past the end of that month counts from its last day.
+                    let prev_month_days = date_serial::days_in_month(if em > 1 { ey } else { ey - 1 }, if em > 1 { em - 1 } else { 12 }) as i32;
+                    (prev_month_days - (sd as i32).min(prev_month_days) + ed as i32) as f64
                 })
             }
-            _ => EvalResult::Error(CellError::Value),
+            _ => EvalResult::Error(CellError::Num),
         }
     }
 
     fn fn_weekday(&self, args: &[Expression]) -> EvalResult {
         if args.is_empty() || args.len() > 2 { return EvalResult::Error(CellError::Value); }
-        let serial = match self.evaluate(&args[0]).as_number() { Some(n) => n as i64, None => return EvalResult::Error(CellError::Value) };
+        let serial = match self.date_arg(&args[0]) { Ok(n) => n, Err(e) => return EvalResult::Error(e) };
         let return_type = if args.len() == 2 {
-            match self.evaluate(&args[1]).as_number() { Some(n) => n as i32, None => return EvalResult::Error(CellError::Value) }
+            match self.count_arg(&args[1]) { Ok(n) => n, Err(e) => return EvalResult::Error(e) }
         } else { 1 };
         let dow = date_serial::weekday(serial); // 0=Sunday .. 6=Saturday
         let result = match return_type {
             1 => dow + 1,       // 1=Sunday .. 7=Saturday
             2 => if dow == 0 { 7 } else { dow }, // 1=Monday .. 7=Sunday
             3 => if dow == 0 { 6 } else { dow - 1 }, // 0=Monday .. 6=Sunday
-            _ => return EvalResult::Error(CellError::Value),
+            // 11=Monday-first .. 16=Saturday-first, 17=Sunday-first
+            11..=17 => (dow - (return_type as i32 - 10) % 7).rem_euclid(7) + 1,
+            _ => return EvalResult::Error(CellError::Num),
         };
         EvalResult::Number(result as f64)
     }
@@ -5302,19 +6381,31 @@ impl<'a> Evaluator<'a> {
         EvalResult::Boolean(matches!(self.evaluate(&args[0]), EvalResult::Boolean(_)))
     }
 
+    /// ISODD/ISEVEN argument, truncated toward zero like Excel. Errors
+    /// propagate; logicals and non-numeric text are #VALUE!.
+    fn parity_arg(&self, name: &str, args: &[Expression]) -> Result<i64, EvalResult> {
+        if args.len() != 1 { return Err(EvalResult::Error(CellError::Value)); }
+        match self.evaluate(&args[0]) {
+            EvalResult::Error(e) => Err(EvalResult::Error(e)),
+            EvalResult::Boolean(_) => Err(self.fail(CellError::Value, format!("{} needs a number, got a logical value", name))),
+            other => other
+                .as_number()
+                .map(|n| n.trunc() as i64)
+                .ok_or_else(|| self.fail(CellError::Value, format!("{} needs a number", name))),
+        }
+    }
+
     fn fn_isodd(&self, args: &[Expression]) -> EvalResult {
-        if args.len() != 1 { return EvalResult::Error(CellError::Value); }
-        match self.evaluate(&args[0]).as_number() {
-            Some(n) => EvalResult::Boolean(n as i64 % 2 != 0),
-            None => EvalResult::Error(CellError::Value),
+        match self.parity_arg("ISODD", args) {
+            Ok(n) => EvalResult::Boolean(n % 2 != 0),
+            Err(e) => e,
         }
     }
 
     fn fn_iseven(&self, args: &[Expression]) -> EvalResult {
-        if args.len() != 1 { return EvalResult::Error(CellError::Value); }
-        match self.evaluate(&args[0]).as_number() {
-            Some(n) => EvalResult::Boolean(n as i64 % 2 == 0),
-            None => EvalResult::Error(CellError::Value),
+        match self.parity_arg("ISEVEN", args) {
+            Ok(n) => EvalResult::Boolean(n % 2 == 0),
+            Err(e) => e,
         }
     }
 
@@ -5346,14 +6437,15 @@ impl<'a> Evaluator<'a> {
 
     fn fn_isformula(&self, args: &[Expression]) -> EvalResult {
         if args.len() != 1 { return EvalResult::Error(CellError::Value); }
-        // Check if the referenced cell contains a formula
-        if let Expression::CellRef { col, row, .. } = &args[0] {
-            let col_idx = col_to_index(col);
-            let row_idx = row - 1;
-            let has_formula = self.grid.get_cell(row_idx, col_idx).map_or(false, |c| c.has_formula());
-            EvalResult::Boolean(has_formula)
-        } else {
-            EvalResult::Boolean(false)
+        if let Some(missing) = self.missing_reference_sheet(&args[0]) {
+            return missing;
+        }
+        // Inspect the referenced cell (top-left of a range) on its own sheet.
+        match self.reference_extent(&args[0]) {
+            Some((grid, row, col, ..)) => {
+                EvalResult::Boolean(grid.get_cell(row, col).is_some_and(|c| c.has_formula()))
+            }
+            None => EvalResult::Boolean(false),
         }
     }
 
@@ -5388,64 +6480,78 @@ impl<'a> Evaluator<'a> {
     fn fn_index(&self, args: &[Expression]) -> EvalResult {
         if args.len() < 2 || args.len() > 3 { return EvalResult::Error(CellError::Value); }
 
-        // Fast path: INDEX over a literal same-sheet range fetches ONE cell
-        // directly instead of materializing (and double-copying) the whole
-        // range. Replicates the slow path's addressing exactly, INCLUDING its
-        // quirks: flat row-major indexing (a col_num overflow WRAPS into the
-        // next row rather than erroring — do not "fix" this here), the 1-D
-        // branch ignoring col_num entirely, and row/col_num 0 behaving as 1.
-        // Skipping the array evaluation is unobservable (a pure grid read);
-        // args[1]/args[2] evaluate in the same order as the slow path.
-        if let Some((min_row, _, min_col, _, rows, cols)) = self.literal_range_rect(&args[0]) {
-            let row_num = match self.evaluate(&args[1]).as_number() {
-                Some(n) if n >= 1.0 => (n as usize) - 1,
-                Some(n) if n == 0.0 => 0,
-                _ => return EvalResult::Error(CellError::Value),
-            };
-            let col_num = if args.len() == 3 {
-                match self.evaluate(&args[2]).as_number() {
-                    Some(n) if n >= 1.0 => (n as usize) - 1,
-                    Some(n) if n == 0.0 => 0,
-                    _ => return EvalResult::Error(CellError::Value),
+        // Positions truncate; 0 selects the whole row/column.
+        let position = |arg: &Expression| match self.evaluate(arg) {
+            EvalResult::Error(e) => Err(e),
+            v => match v.as_number() {
+                Some(n) if n >= 0.0 => Ok(n as usize),
+                _ => Err(CellError::Value),
+            },
+        };
+        let row_num = match position(&args[1]) {
+            Ok(n) => n,
+            Err(e) => return EvalResult::Error(e),
+        };
+        let col_num = match args.get(2).map(position) {
+            None => None,
+            Some(Ok(n)) => Some(n),
+            Some(Err(e)) => return EvalResult::Error(e),
+        };
+
+        // A reference is addressed in place: only the selected cells are read
+        // from the grid, and its rect (not the flattened value) gives the shape.
+        // Anything else is evaluated and addressed by its spill shape.
+        enum Source<'g> {
+            Cells(GridView<'g>, u32, u32),
+            Values(Vec<EvalResult>),
+        }
+        let (source, rows, cols) = match self.reference_extent(&args[0]) {
+            Some((grid, min_row, min_col, max_row, max_col)) => (
+                Source::Cells(grid, min_row, min_col),
+                (max_row - min_row + 1) as usize,
+                (max_col - min_col + 1) as usize,
+            ),
+            None => {
+                let value = self.evaluate(&args[0]);
+                if value.is_error() {
+                    return value;
                 }
-            } else {
-                0
-            };
-            let flat_idx = if cols <= 1 { row_num } else { row_num * cols + col_num };
-            if flat_idx >= rows * cols {
-                return EvalResult::Error(CellError::Ref);
+                let (rows, cols) = value.spill_dimensions();
+                (Source::Values(value.into_flatten()), rows, cols)
             }
-            let r = min_row + (flat_idx / cols) as u32;
-            let c = min_col + (flat_idx % cols) as u32;
-            let grid = self.get_grid_for_sheet(&None);
-            return match grid.get_cell(r, c) {
+        };
+        let at = |r: usize, c: usize| match &source {
+            Source::Cells(grid, min_row, min_col) => match grid.get_cell(min_row + r as u32, min_col + c as u32) {
                 Some(cell) => self.cell_value_to_result(&cell.value),
                 None => EvalResult::Number(0.0),
-            };
-        }
+            },
+            Source::Values(values) => values.get(r * cols + c).cloned().unwrap_or(EvalResult::Error(CellError::NA)),
+        };
 
-        let array = self.eval_flat(&args[0]);
-        let row_num = match self.evaluate(&args[1]).as_number() {
-            Some(n) if n >= 1.0 => (n as usize) - 1,
-            Some(n) if n == 0.0 => 0, // Return entire column/row - simplified to first
-            _ => return EvalResult::Error(CellError::Value),
+        // With one index, a single row is indexed by column; anything taller
+        // by row (returning the whole row when it is wider than one column).
+        let (row_num, col_num) = match col_num {
+            Some(col_num) => (row_num, col_num),
+            None if rows == 1 => (0, row_num),
+            None => (row_num, 0),
         };
-        // Determine array dimensions from the range expression
-        let (_rows, cols) = self.get_range_dimensions(&args[0]);
-        let col_num = if args.len() == 3 {
-            match self.evaluate(&args[2]).as_number() {
-                Some(n) if n >= 1.0 => (n as usize) - 1,
-                Some(n) if n == 0.0 => 0,
-                _ => return EvalResult::Error(CellError::Value),
-            }
-        } else { 0 };
-        if cols <= 1 {
-            // 1D array (single column or row)
-            array.get(row_num).cloned().unwrap_or(EvalResult::Error(CellError::Ref))
-        } else {
-            // 2D array
-            let idx = row_num * cols + col_num;
-            array.get(idx).cloned().unwrap_or(EvalResult::Error(CellError::Ref))
+        if row_num > rows || col_num > cols {
+            return EvalResult::Error(CellError::Ref);
+        }
+        let pick = |num: usize, len: usize| -> Vec<usize> {
+            if num == 0 { (0..len).collect() } else { vec![num - 1] }
+        };
+        let (pick_rows, pick_cols) = (pick(row_num, rows), pick(col_num, cols));
+        match (pick_rows.as_slice(), pick_cols.as_slice()) {
+            ([r], [c]) => at(*r, *c),
+            // A column comes back flat (spills down), a row or block as rows.
+            (_, [c]) => EvalResult::Array(pick_rows.iter().map(|&r| at(r, *c)).collect()),
+            _ => EvalResult::Array(
+                pick_rows
+                    .iter()
+                    .map(|&r| EvalResult::Array(pick_cols.iter().map(|&c| at(r, c)).collect()))
+                    .collect(),
+            ),
         }
     }
 
@@ -5473,18 +6579,30 @@ impl<'a> Evaluator<'a> {
         let lookup_val = self.evaluate(&args[0]);
         // FAST PATH (PERF-03): literal vector -> pass-cache index/binary search.
         let fast_vec = self.literal_vector_desc(&args[1]);
-        let lookup_array_pre = if fast_vec.is_none() { Some(self.eval_flat(&args[1])) } else { None };
+        let lookup_value_pre = if fast_vec.is_none() { Some(self.evaluate(&args[1])) } else { None };
+        // Only the sign of match_type counts.
         let match_type = if args.len() == 3 {
-            match self.evaluate(&args[2]).as_number() { Some(n) => n as i32, None => return EvalResult::Error(CellError::Value) }
+            match self.evaluate(&args[2]).as_number() { Some(n) => (n as i32).signum(), None => return EvalResult::Error(CellError::Value) }
         } else { 1 };
+        // The lookup array must be one row or one column.
+        let two_d = match self.reference_extent(&args[1]) {
+            Some((_, min_row, min_col, max_row, max_col)) => max_row > min_row && max_col > min_col,
+            None => lookup_value_pre.as_ref().is_some_and(|v| {
+                let (rows, cols) = v.spill_dimensions();
+                rows > 1 && cols > 1
+            }),
+        };
+        if two_d {
+            return EvalResult::Error(CellError::NA);
+        }
 
         if let Some((rect, axis)) = fast_vec {
             if let Some(result) = self.match_cached(&lookup_val, rect, axis, match_type) {
                 return result;
             }
         }
-        let lookup_array = match lookup_array_pre {
-            Some(v) => v,
+        let lookup_array = match lookup_value_pre {
+            Some(v) => v.into_flatten(),
             None => self.eval_flat(&args[1]),
         };
 
@@ -5541,9 +6659,13 @@ impl<'a> Evaluator<'a> {
 
     fn fn_choose(&self, args: &[Expression]) -> EvalResult {
         if args.len() < 2 { return EvalResult::Error(CellError::Value); }
-        let idx = match self.evaluate(&args[0]).as_number() {
-            Some(n) if n >= 1.0 && (n as usize) < args.len() => n as usize,
-            _ => return EvalResult::Error(CellError::Value),
+        // The index truncates; an error index propagates.
+        let idx = match self.evaluate(&args[0]) {
+            EvalResult::Error(e) => return EvalResult::Error(e),
+            v => match v.as_number() {
+                Some(n) if n >= 1.0 && (n as usize) < args.len() => n as usize,
+                _ => return EvalResult::Error(CellError::Value),
+            },
         };
         self.evaluate(&args[idx])
     }
@@ -5580,71 +6702,192 @@ impl<'a> Evaluator<'a> {
         }
     }
 
+    /// OFFSET(reference, rows, cols, [height], [width])
+    /// The reference moved by rows/cols and resized to height x width (by
+    /// default the reference's own size). A 1x1 region is that cell's value;
+    /// a larger one is an array shaped like the equivalent range, so
+    /// aggregates consume it directly. A region reaching past an edge of the
+    /// sheet is #REF!.
     fn fn_offset(&self, args: &[Expression]) -> EvalResult {
-        // OFFSET(reference, rows, cols, [height], [width])
+        use crate::reference_shift::{MAX_COL, MAX_ROW};
         if args.len() < 3 || args.len() > 5 { return EvalResult::Error(CellError::Value); }
-        // Get the base cell reference
-        let (base_row, base_col) = match &args[0] {
-            Expression::CellRef { col, row, .. } => ((*row as i64) - 1, col_to_index(col) as i64),
-            _ => return EvalResult::Error(CellError::Value),
-        };
-        let row_offset = match self.evaluate(&args[1]).as_number() { Some(n) => n as i64, None => return EvalResult::Error(CellError::Value) };
-        let col_offset = match self.evaluate(&args[2]).as_number() { Some(n) => n as i64, None => return EvalResult::Error(CellError::Value) };
-        let new_row = base_row + row_offset;
-        let new_col = base_col + col_offset;
-        if new_row < 0 || new_col < 0 { return EvalResult::Error(CellError::Ref); }
-        // For single cell (no height/width), return the cell value
-        let height = if args.len() >= 4 { match self.evaluate(&args[3]).as_number() { Some(n) => n as usize, None => return EvalResult::Error(CellError::Value) } } else { 1 };
-        let width = if args.len() == 5 { match self.evaluate(&args[4]).as_number() { Some(n) => n as usize, None => return EvalResult::Error(CellError::Value) } } else { 1 };
-        if height == 1 && width == 1 {
-            match self.grid.get_cell(new_row as u32, new_col as u32) {
-                Some(cell) => self.cell_value_to_result(&cell.value),
-                None => EvalResult::Number(0.0),
+        let not_a_reference = "OFFSET: reference must be a cell or range";
+        // Anchor: sheet, top row and left column (0-based), height, width.
+        let (sheet, top, left, anchor_rows, anchor_cols) = match &args[0] {
+            Expression::CellRef { sheet, col, row, .. } => {
+                (sheet, *row as i64 - 1, col_to_index(col) as i64, 1, 1)
             }
-        } else {
-            // Return array of values
-            let mut values = Vec::new();
-            for r in 0..height {
-                for c in 0..width {
-                    let cell_row = (new_row + r as i64) as u32;
-                    let cell_col = (new_col + c as i64) as u32;
-                    match self.grid.get_cell(cell_row, cell_col) {
-                        Some(cell) => values.push(self.cell_value_to_result(&cell.value)),
-                        None => values.push(EvalResult::Number(0.0)),
-                    }
+            Expression::Range { sheet, start, end, .. } => match (start.as_ref(), end.as_ref()) {
+                (
+                    Expression::CellRef { col: sc, row: sr, .. },
+                    Expression::CellRef { col: ec, row: er, .. },
+                ) => {
+                    let (sc, ec) = (col_to_index(sc), col_to_index(ec));
+                    (
+                        sheet,
+                        (*sr).min(*er) as i64 - 1,
+                        sc.min(ec) as i64,
+                        sr.abs_diff(*er) as i64 + 1,
+                        sc.abs_diff(ec) as i64 + 1,
+                    )
                 }
+                _ => return self.fail(CellError::Value, not_a_reference),
+            },
+            _ => return self.fail(CellError::Value, not_a_reference),
+        };
+
+        let whole = |arg: &Expression| -> Result<i64, CellError> {
+            match self.evaluate(arg) {
+                EvalResult::Error(e) => Err(e),
+                v => v.as_number().map(|n| n.trunc() as i64).ok_or(CellError::Value),
             }
-            EvalResult::Array(values)
+        };
+        let sized = |arg: Option<&Expression>, default: i64| match arg {
+            Some(arg) => whole(arg),
+            None => Ok(default),
+        };
+        let (rows, cols, height, width) = match (
+            whole(&args[1]),
+            whole(&args[2]),
+            sized(args.get(3), anchor_rows),
+            sized(args.get(4), anchor_cols),
+        ) {
+            (Ok(r), Ok(c), Ok(h), Ok(w)) => (r, c, h, w),
+            (Err(e), ..) | (_, Err(e), ..) | (_, _, Err(e), _) | (.., Err(e)) => return EvalResult::Error(e),
+        };
+        if height < 1 || width < 1 {
+            return self.fail(CellError::Ref, format!("OFFSET: {}x{} is not a valid size", height, width));
+        }
+
+        let (top, left) = (top + rows, left + cols);
+        let (bottom, right) = (top + height - 1, left + width - 1);
+        if top < 0 || left < 0 || bottom >= MAX_ROW as i64 || right > MAX_COL as i64 {
+            return self.fail(CellError::Ref, "OFFSET: the region falls outside the sheet");
         }
+
+        if height == 1 && width == 1 {
+            return self.eval_cell_ref(sheet, &crate::coord::index_to_col(left as u32), top as u32 + 1);
+        }
+        let cell = |row: i64, col: i64| Expression::CellRef {
+            sheet: None,
+            col: crate::coord::index_to_col(col as u32),
+            row: row as u32 + 1,
+            col_absolute: false,
+            row_absolute: false,
+            ref_site_id: Default::default(),
+        };
+        self.eval_range(sheet, &cell(top, left), &cell(bottom, right))
     }
 
+    /// ADDRESS(row_num, column_num, [abs_num], [a1], [sheet_text])
+    /// abs_num: 1 = $A$1 (default), 2 = A$1, 3 = $A1, 4 = A1. With a1 FALSE
+    /// the address is R1C1-style, relative parts in brackets (R[1]C1).
     fn fn_address(&self, args: &[Expression]) -> EvalResult {
+        use crate::reference_shift::{MAX_COL, MAX_ROW};
         if args.len() < 2 || args.len() > 5 { return EvalResult::Error(CellError::Value); }
-        let row = match self.evaluate(&args[0]).as_number() { Some(n) if n >= 1.0 => n as u32, _ => return EvalResult::Error(CellError::Value) };
-        let col = match self.evaluate(&args[1]).as_number() { Some(n) if n >= 1.0 => n as u32, _ => return EvalResult::Error(CellError::Value) };
-        let abs_type = if args.len() >= 3 { match self.evaluate(&args[2]).as_number() { Some(n) => n as i32, None => return EvalResult::Error(CellError::Value) } } else { 1 };
-        // Convert col number to letter(s)
-        let col_str = crate::coord::index_to_col(col - 1);
-        let result = match abs_type {
-            1 => format!("${}${}", col_str, row),
-            2 => format!("{}${}", col_str, row),
-            3 => format!("${}{}", col_str, row),
-            4 => format!("{}{}", col_str, row),
-            _ => return EvalResult::Error(CellError::Value),
+        let row = match self.whole_arg(&args[0], 1.0) { Ok(r) => r, Err(e) => return EvalResult::Error(e) };
+        let col = match self.whole_arg(&args[1], 1.0) { Ok(c) => c, Err(e) => return EvalResult::Error(e) };
+        if row > MAX_ROW as usize || col > MAX_COL as usize + 1 {
+            return self.fail(CellError::Value, format!("ADDRESS: R{}C{} is outside the sheet", row, col));
+        }
+        let abs_num = match args.get(2) {
+            Some(arg) => match self.whole_arg(arg, 1.0) {
+                Ok(n @ 1..=4) => n,
+                Ok(n) => return self.fail(CellError::Value, format!("ADDRESS: abs_num must be 1-4, got {}", n)),
+                Err(e) => return EvalResult::Error(e),
+            },
+            None => 1,
         };
-        EvalResult::Text(result)
+        let a1 = match args.get(3).map(|arg| self.evaluate(arg)) {
+            None => true,
+            Some(EvalResult::Error(e)) => return EvalResult::Error(e),
+            Some(v) => match v.as_boolean() {
+                Some(b) => b,
+                None => return EvalResult::Error(CellError::Value),
+            },
+        };
+        let (abs_row, abs_col) = (matches!(abs_num, 1 | 2), matches!(abs_num, 1 | 3));
+
+        let address = if a1 {
+            let dollar = |abs: bool| if abs { "$" } else { "" };
+            format!(
+                "{}{}{}{}",
+                dollar(abs_col),
+                crate::coord::index_to_col(col as u32 - 1),
+                dollar(abs_row),
+                row
+            )
+        } else {
+            let part = |abs: bool, n: usize| if abs { n.to_string() } else { format!("[{}]", n) };
+            format!("R{}C{}", part(abs_row, row), part(abs_col, col))
+        };
+
+        match args.get(4) {
+            None => EvalResult::Text(address),
+            Some(arg) => match self.text_arg(arg) {
+                Ok(sheet) if sheet.contains(' ') || sheet.contains('\'') => {
+                    EvalResult::Text(format!("'{}'!{}", sheet.replace('\'', "''"), address))
+                }
+                Ok(sheet) => EvalResult::Text(format!("{}!{}", sheet, address)),
+                Err(e) => EvalResult::Error(e),
+            },
+        }
+    }
+
+    /// Rows and columns spanned by a reference argument, read from the
+    /// unevaluated expression so whole columns/rows report their full size
+    /// (A:A is 1,048,576 rows) rather than the populated area. `None` when the
+    /// argument is not structurally a reference.
+    fn reference_dimensions(expr: &Expression) -> Option<(usize, usize)> {
+        use crate::reference_shift::{MAX_COL, MAX_ROW};
+        let span = |a: u32, b: u32| (a.abs_diff(b) + 1) as usize;
+        match expr {
+            Expression::CellRef { .. } => Some((1, 1)),
+            Expression::Range { start, end, .. } => match (start.as_ref(), end.as_ref()) {
+                (
+                    Expression::CellRef { col: sc, row: sr, .. },
+                    Expression::CellRef { col: ec, row: er, .. },
+                ) => Some((span(*sr, *er), span(col_to_index(sc), col_to_index(ec)))),
+                _ => None,
+            },
+            Expression::ColumnRef { start_col, end_col, .. } => {
+                Some((MAX_ROW as usize, span(col_to_index(start_col), col_to_index(end_col))))
+            }
+            Expression::RowRef { start_row, end_row, .. } => {
+                Some((span(*start_row, *end_row), MAX_COL as usize + 1))
+            }
+            Expression::Sheet3DRef { reference, .. } => Self::reference_dimensions(reference),
+            _ => None,
+        }
+    }
+
+    /// Shared body of ROWS and COLUMNS: the (rows, cols) of a reference, or
+    /// of the array an expression evaluates to. Errors propagate.
+    fn array_dimensions(&self, args: &[Expression]) -> Result<(usize, usize), CellError> {
+        if args.len() != 1 { return Err(CellError::Value); }
+        if let Some(dims) = Self::reference_dimensions(&args[0]) {
+            return Ok(dims);
+        }
+        match self.evaluate(&args[0]) {
+            EvalResult::Error(e) => Err(e),
+            value => Ok(value.spill_dimensions()),
+        }
     }
 
+    /// ROWS(array)
     fn fn_rows(&self, args: &[Expression]) -> EvalResult {
-        if args.len() != 1 { return EvalResult::Error(CellError::Value); }
-        let (rows, _) = self.get_range_dimensions(&args[0]);
-        EvalResult::Number(rows as f64)
+        match self.array_dimensions(args) {
+            Ok((rows, _)) => EvalResult::Number(rows as f64),
+            Err(e) => EvalResult::Error(e),
+        }
     }
 
+    /// COLUMNS(array)
     fn fn_columns(&self, args: &[Expression]) -> EvalResult {
-        if args.len() != 1 { return EvalResult::Error(CellError::Value); }
-        let (_, cols) = self.get_range_dimensions(&args[0]);
-        EvalResult::Number(cols as f64)
+        match self.array_dimensions(args) {
+            Ok((_, cols)) => EvalResult::Number(cols as f64),
+            Err(e) => EvalResult::Error(e),
+        }
     }
 
     fn fn_transpose(&self, args: &[Expression]) -> EvalResult {
@@ -5730,17 +6973,27 @@ impl<'a> Evaluator<'a> {
     fn fn_large(&self, args: &[Expression]) -> EvalResult {
         if args.len() != 2 { return EvalResult::Error(CellError::Value); }
         let mut numbers = match self.collect_numbers(&args[0..1]) { Ok(n) => n, Err(e) => return EvalResult::Error(e) };
-        let k = match self.evaluate(&args[1]).as_number() { Some(n) if n >= 1.0 => n as usize, _ => return EvalResult::Error(CellError::Value) };
-        if k > numbers.len() { return EvalResult::Error(CellError::Value); }
-        numbers.sort_by(|a, b| b.partial_cmp(a).unwrap_or(std::cmp::Ordering::Equal));
+        let k = match self.evaluate(&args[1]).as_number() {
+            Some(n) if n >= 1.0 => n as usize,
+            Some(_) => return EvalResult::Error(CellError::Num),
+            None => return EvalResult::Error(CellError::Value),
+        };
+        // k out of bounds (including no numbers) is #NUM!
+        if k > numbers.len() { return EvalResult::Error(CellError::Num); }
+        numbers.sort_by(|a, b| b.partial_cmp(a).unwrap_or(std::cmp::Ordering::Equal));
         EvalResult::Number(numbers[k - 1])
     }
 
     fn fn_small(&self, args: &[Expression]) -> EvalResult {
         if args.len() != 2 { return EvalResult::Error(CellError::Value); }
         let mut numbers = match self.collect_numbers(&args[0..1]) { Ok(n) => n, Err(e) => return EvalResult::Error(e) };
-        let k = match self.evaluate(&args[1]).as_number() { Some(n) if n >= 1.0 => n as usize, _ => return EvalResult::Error(CellError::Value) };
-        if k > numbers.len() { return EvalResult::Error(CellError::Value); }
+        let k = match self.evaluate(&args[1]).as_number() {
+            Some(n) if n >= 1.0 => n as usize,
+            Some(_) => return EvalResult::Error(CellError::Num),
+            None => return EvalResult::Error(CellError::Value),
+        };
+        // k out of bounds (including no numbers) is #NUM!
+        if k > numbers.len() { return EvalResult::Error(CellError::Num); }
         numbers.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
         EvalResult::Number(numbers[k - 1])
     }
@@ -5766,8 +7019,14 @@ impl<'a> Evaluator<'a> {
     fn fn_percentile(&self, args: &[Expression]) -> EvalResult {
         if args.len() != 2 { return EvalResult::Error(CellError::Value); }
         let mut numbers = match self.collect_numbers(&args[0..1]) { Ok(n) => n, Err(e) => return EvalResult::Error(e) };
-        let k = match self.evaluate(&args[1]).as_number() { Some(n) if (0.0..=1.0).contains(&n) => n, _ => return EvalResult::Error(CellError::Value) };
-        if numbers.is_empty() { return EvalResult::Error(CellError::Value); }
+        // k outside [0, 1] or no numbers is #NUM!
+        let k = match self.evaluate(&args[1]).as_number() {
+            Some(n) if (0.0..=1.0).contains(&n) => n,
+            Some(_) => return EvalResult::Error(CellError::Num),
+            None => return EvalResult::Error(CellError::Value),
+        };
+        if numbers.is_empty() { return EvalResult::Error(CellError::Num); }
+        // Linear interpolation between the ranks around k*(n-1)
         numbers.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
         let n = numbers.len() as f64;
         let rank = k * (n - 1.0);
@@ -5780,9 +7039,11 @@ impl<'a> Evaluator<'a> {
 
     fn fn_quartile(&self, args: &[Expression]) -> EvalResult {
         if args.len() != 2 { return EvalResult::Error(CellError::Value); }
+        // quart is truncated to 0..=4; anything outside is #NUM!
         let quart = match self.evaluate(&args[1]).as_number() {
-            Some(n) if (0.0..=4.0).contains(&n) => n,
-            _ => return EvalResult::Error(CellError::Value),
+            Some(n) if (0.0..5.0).contains(&n) => n.trunc(),
+            Some(_) => return EvalResult::Error(CellError::Num),
+            None => return EvalResult::Error(CellError::Value),
         };
         // Reuse PERCENTILE with k = quart/4
         let k_val = Expression::Literal(Value::Number(quart / 4.0));
@@ -5809,7 +7070,8 @@ impl<'a> Evaluator<'a> {
                 }
                 EvalResult::Error(CellError::NA)
             }
-            Ok(_) => EvalResult::Error(CellError::Value),
+            // No numbers at all: nothing repeats either
+            Ok(_) => EvalResult::Error(CellError::NA),
             Err(e) => EvalResult::Error(e),
         }
     }
@@ -5832,131 +7094,136 @@ impl<'a> Evaluator<'a> {
 
     // ==================== Financial Functions (Batch 8) ====================
 
+    /// Reads the numeric arguments of a time-value-of-money function: between
+    /// `min` and `max` of them, errors propagated. Omitted trailing optionals
+    /// are simply absent from the result.
+    fn tvm_numbers(&self, args: &[Expression], min: usize, max: usize) -> Result<Vec<f64>, CellError> {
+        if args.len() < min || args.len() > max { return Err(CellError::Value); }
+        args.iter()
+            .map(|arg| match self.evaluate(arg) {
+                EvalResult::Error(e) => Err(e),
+                v => v.as_number().ok_or(CellError::Value),
+            })
+            .collect()
+    }
+
     fn fn_pmt(&self, args: &[Expression]) -> EvalResult {
-        if args.len() < 3 || args.len() > 5 { return EvalResult::Error(CellError::Value); }
-        let rate = match self.evaluate(&args[0]).as_number() { Some(n) => n, None => return EvalResult::Error(CellError::Value) };
-        let nper = match self.evaluate(&args[1]).as_number() { Some(n) => n, None => return EvalResult::Error(CellError::Value) };
-        let pv = match self.evaluate(&args[2]).as_number() { Some(n) => n, None => return EvalResult::Error(CellError::Value) };
-        let fv = if args.len() >= 4 { self.evaluate(&args[3]).as_number().unwrap_or(0.0) } else { 0.0 };
-        let pmt_type = if args.len() == 5 { self.evaluate(&args[4]).as_number().unwrap_or(0.0) as i32 } else { 0 };
-        if nper == 0.0 { return EvalResult::Error(CellError::Value); }
-        let pmt = if rate.abs() < 1e-10 {
-            -(pv + fv) / nper
-        } else {
-            let pvif = (1.0 + rate).powf(nper);
-            let pmt = rate * (pv * pvif + fv) / (pvif - 1.0);
-            if pmt_type == 1 { -pmt / (1.0 + rate) } else { -pmt }
-        };
-        EvalResult::Number(pmt)
+        // PMT(rate, nper, pv, [fv], [type])
+        let n = match self.tvm_numbers(args, 3, 5) { Ok(n) => n, Err(e) => return EvalResult::Error(e) };
+        let (rate, nper, pv) = (n[0], n[1], n[2]);
+        let fv = n.get(3).copied().unwrap_or(0.0);
+        let due = n.get(4).is_some_and(|&t| t != 0.0);
+        if nper == 0.0 { return self.fail(CellError::Num, "PMT needs a non-zero number of periods"); }
+        if rate == 0.0 { return EvalResult::Number(-(pv + fv) / nper); }
+        let pvif = (1.0 + rate).powf(nper);
+        let pmt = -rate * (pv * pvif + fv) / (pvif - 1.0);
+        EvalResult::Number(if due { pmt / (1.0 + rate) } else { pmt })
     }
 
     fn fn_pv(&self, args: &[Expression]) -> EvalResult {
-        if args.len() < 3 || args.len() > 5 { return EvalResult::Error(CellError::Value); }
-        let rate = match self.evaluate(&args[0]).as_number() { Some(n) => n, None => return EvalResult::Error(CellError::Value) };
-        let nper = match self.evaluate(&args[1]).as_number() { Some(n) => n, None => return EvalResult::Error(CellError::Value) };
-        let pmt = match self.evaluate(&args[2]).as_number() { Some(n) => n, None => return EvalResult::Error(CellError::Value) };
-        let fv = if args.len() >= 4 { self.evaluate(&args[3]).as_number().unwrap_or(0.0) } else { 0.0 };
-        let pmt_type = if args.len() == 5 { self.evaluate(&args[4]).as_number().unwrap_or(0.0) as i32 } else { 0 };
-        if rate.abs() < 1e-10 {
-            EvalResult::Number(-pmt * nper - fv)
-        } else {
-            let pvif = (1.0 + rate).powf(nper);
-            let pv_factor = if pmt_type == 1 { 1.0 + rate } else { 1.0 };
-            EvalResult::Number((-pmt * pv_factor * (pvif - 1.0) / rate - fv) / pvif)
-        }
+        // PV(rate, nper, pmt, [fv], [type])
+        let n = match self.tvm_numbers(args, 3, 5) { Ok(n) => n, Err(e) => return EvalResult::Error(e) };
+        let (rate, nper, pmt) = (n[0], n[1], n[2]);
+        let fv = n.get(3).copied().unwrap_or(0.0);
+        let due = n.get(4).is_some_and(|&t| t != 0.0);
+        if rate == 0.0 { return EvalResult::Number(-pmt * nper - fv); }
+        let pvif = (1.0 + rate).powf(nper);
+        let pmt_factor = if due { 1.0 + rate } else { 1.0 };
+        EvalResult::Number((-pmt * pmt_factor * (pvif - 1.0) / rate - fv) / pvif)
     }
 
     fn fn_fv(&self, args: &[Expression]) -> EvalResult {
-        if args.len() < 3 || args.len() > 5 { return EvalResult::Error(CellError::Value); }
-        let rate = match self.evaluate(&args[0]).as_number() { Some(n) => n, None => return EvalResult::Error(CellError::Value) };
-        let nper = match self.evaluate(&args[1]).as_number() { Some(n) => n, None => return EvalResult::Error(CellError::Value) };
-        let pmt = match self.evaluate(&args[2]).as_number() { Some(n) => n, None => return EvalResult::Error(CellError::Value) };
-        let pv = if args.len() >= 4 { self.evaluate(&args[3]).as_number().unwrap_or(0.0) } else { 0.0 };
-        let pmt_type = if args.len() == 5 { self.evaluate(&args[4]).as_number().unwrap_or(0.0) as i32 } else { 0 };
-        if rate.abs() < 1e-10 {
-            EvalResult::Number(-pv - pmt * nper)
-        } else {
-            let pvif = (1.0 + rate).powf(nper);
-            let pmt_factor = if pmt_type == 1 { 1.0 + rate } else { 1.0 };
-            EvalResult::Number(-pv * pvif - pmt * pmt_factor * (pvif - 1.0) / rate)
+        // FV(rate, nper, pmt, [pv], [type])
+        let n = match self.tvm_numbers(args, 3, 5) { Ok(n) => n, Err(e) => return EvalResult::Error(e) };
+        let (rate, nper, pmt) = (n[0], n[1], n[2]);
+        let pv = n.get(3).copied().unwrap_or(0.0);
+        let due = n.get(4).is_some_and(|&t| t != 0.0);
+        if rate == 0.0 { return EvalResult::Number(-pv - pmt * nper); }
+        let pvif = (1.0 + rate).powf(nper);
+        let pmt_factor = if due { 1.0 + rate } else { 1.0 };
+        EvalResult::Number(-pv * pvif - pmt * pmt_factor * (pvif - 1.0) / rate)
+    }
+
+    /// Numeric cash flows an argument contributes, row-major. Text, logicals
+    /// and blank cells are skipped; the first error value is returned.
+    fn cash_flows(&self, arg: &Expression) -> Result<Vec<f64>, CellError> {
+        let mut flows = Vec::new();
+        for value in self.eval_flat_blanks(arg).iter() {
+            match value {
+                Some(EvalResult::Error(e)) => return Err(e.clone()),
+                Some(EvalResult::Number(n)) => flows.push(*n),
+                _ => {}
+            }
         }
+        Ok(flows)
     }
 
     fn fn_npv(&self, args: &[Expression]) -> EvalResult {
+        // NPV(rate, value1, [value2], ...)
         if args.len() < 2 { return EvalResult::Error(CellError::Value); }
-        let rate = match self.evaluate(&args[0]).as_number() { Some(n) => n, None => return EvalResult::Error(CellError::Value) };
-        let mut total = 0.0;
-        let mut period = 1;
+        let rate = match self.evaluate(&args[0]) {
+            EvalResult::Error(e) => return EvalResult::Error(e),
+            v => match v.as_number() { Some(n) => n, None => return EvalResult::Error(CellError::Value) },
+        };
+        if rate == -1.0 { return self.fail(CellError::Div0, "NPV rate of -100% discounts by zero"); }
+        let mut flows = Vec::new();
         for arg in &args[1..] {
-            for val in self.eval_flat(arg) {
-                if let Some(cf) = val.as_number() {
-                    total += cf / (1.0 + rate).powi(period);
-                    period += 1;
-                }
-            }
+            match self.cash_flows(arg) { Ok(f) => flows.extend(f), Err(e) => return EvalResult::Error(e) }
         }
+        let total = flows.iter().enumerate().map(|(i, cf)| cf / (1.0 + rate).powi(i as i32 + 1)).sum();
         EvalResult::Number(total)
     }
 
     fn fn_irr(&self, args: &[Expression]) -> EvalResult {
+        // IRR(values, [guess])
         if args.is_empty() || args.len() > 2 { return EvalResult::Error(CellError::Value); }
-        let cashflows: Vec<f64> = self.eval_flat(&args[0]).iter().filter_map(|v| v.as_number()).collect();
-        if cashflows.len() < 2 { return EvalResult::Error(CellError::Value); }
-        let mut guess = if args.len() == 2 { self.evaluate(&args[1]).as_number().unwrap_or(0.1) } else { 0.1 };
-        // Newton's method
-        for _ in 0..100 {
-            let mut npv = 0.0;
-            let mut dnpv = 0.0;
-            for (i, &cf) in cashflows.iter().enumerate() {
-                let pv_factor = (1.0 + guess).powi(i as i32);
-                npv += cf / pv_factor;
-                if i > 0 { dnpv -= (i as f64) * cf / (1.0 + guess).powi(i as i32 + 1); }
-            }
-            if dnpv.abs() < 1e-15 { return EvalResult::Error(CellError::Value); }
-            let new_guess = guess - npv / dnpv;
-            if (new_guess - guess).abs() < 1e-10 { return EvalResult::Number(new_guess); }
-            guess = new_guess;
+        let values = match self.cash_flows(&args[0]) { Ok(v) => v, Err(e) => return EvalResult::Error(e) };
+        let guess = match args.get(1).map(|g| self.evaluate(g)) {
+            None => 0.1,
+            Some(EvalResult::Error(e)) => return EvalResult::Error(e),
+            Some(g) => g.as_number().unwrap_or(0.1),
+        };
+        if !has_sign_change(&values) {
+            return self.fail(CellError::Num, "IRR needs at least one positive and one negative cash flow");
+        }
+        let flows: Vec<(f64, f64)> = values.iter().enumerate().map(|(i, &v)| (i as f64, v)).collect();
+        match discount_rate_root(&flows, guess) {
+            Some(rate) => EvalResult::Number(rate),
+            None => self.fail(CellError::Num, format!("IRR did not converge after {} iterations", IRR_MAX_ITERATIONS)),
         }
-        EvalResult::Error(CellError::Value) // Did not converge
     }
 
     fn fn_rate(&self, args: &[Expression]) -> EvalResult {
-        if args.len() < 3 || args.len() > 6 { return EvalResult::Error(CellError::Value); }
-        let nper = match self.evaluate(&args[0]).as_number() { Some(n) => n, None => return EvalResult::Error(CellError::Value) };
-        let pmt = match self.evaluate(&args[1]).as_number() { Some(n) => n, None => return EvalResult::Error(CellError::Value) };
-        let pv = match self.evaluate(&args[2]).as_number() { Some(n) => n, None => return EvalResult::Error(CellError::Value) };
-        let fv = if args.len() >= 4 { self.evaluate(&args[3]).as_number().unwrap_or(0.0) } else { 0.0 };
-        let pmt_type = if args.len() >= 5 { self.evaluate(&args[4]).as_number().unwrap_or(0.0) as i32 } else { 0 };
-        let mut guess = if args.len() == 6 { self.evaluate(&args[5]).as_number().unwrap_or(0.1) } else { 0.1 };
-        // Newton's method
-        for _ in 0..100 {
-            let pvif = (1.0 + guess).powf(nper);
-            let pmt_factor = if pmt_type == 1 { 1.0 + guess } else { 1.0 };
-            let f = pv * pvif + pmt * pmt_factor * (pvif - 1.0) / guess + fv;
-            let df = nper * pv * (1.0 + guess).powf(nper - 1.0) + pmt * pmt_factor * (nper * guess * (1.0 + guess).powf(nper - 1.0) - (pvif - 1.0)) / (guess * guess);
-            if df.abs() < 1e-15 { return EvalResult::Error(CellError::Value); }
-            let new_guess = guess - f / df;
-            if (new_guess - guess).abs() < 1e-10 { return EvalResult::Number(new_guess); }
-            guess = new_guess;
+        // RATE(nper, pmt, pv, [fv], [type], [guess])
+        let n = match self.tvm_numbers(args, 3, 6) { Ok(n) => n, Err(e) => return EvalResult::Error(e) };
+        let (nper, pmt, pv) = (n[0], n[1], n[2]);
+        let fv = n.get(3).copied().unwrap_or(0.0);
+        let due = n.get(4).is_some_and(|&t| t != 0.0);
+        let guess = n.get(5).copied().unwrap_or(0.1);
+        if nper <= 0.0 { return self.fail(CellError::Num, "RATE needs a positive number of periods"); }
+        let balance = |rate: f64| tvm_balance(rate, nper, pmt, pv, fv, due);
+        match rate_root(balance, guess, self.rate_iterations) {
+            Some(rate) => EvalResult::Number(rate),
+            None => self.fail(CellError::Num, format!("RATE did not converge after {} iterations", self.rate_iterations)),
         }
-        EvalResult::Error(CellError::Value)
     }
 
     fn fn_nper(&self, args: &[Expression]) -> EvalResult {
-        if args.len() < 3 || args.len() > 5 { return EvalResult::Error(CellError::Value); }
-        let rate = match self.evaluate(&args[0]).as_number() { Some(n) => n, None => return EvalResult::Error(CellError::Value) };
-        let pmt = match self.evaluate(&args[1]).as_number() { Some(n) => n, None => return EvalResult::Error(CellError::Value) };
-        let pv = match self.evaluate(&args[2]).as_number() { Some(n) => n, None => return EvalResult::Error(CellError::Value) };
-        let fv = if args.len() >= 4 { self.evaluate(&args[3]).as_number().unwrap_or(0.0) } else { 0.0 };
-        let pmt_type = if args.len() == 5 { self.evaluate(&args[4]).as_number().unwrap_or(0.0) as i32 } else { 0 };
-        if rate.abs() < 1e-10 {
-            if pmt.abs() < 1e-10 { return EvalResult::Error(CellError::Value); }
+        // NPER(rate, pmt, pv, [fv], [type])
+        let n = match self.tvm_numbers(args, 3, 5) { Ok(n) => n, Err(e) => return EvalResult::Error(e) };
+        let (rate, pmt, pv) = (n[0], n[1], n[2]);
+        let fv = n.get(3).copied().unwrap_or(0.0);
+        let due = n.get(4).is_some_and(|&t| t != 0.0);
+        if rate == 0.0 {
+            if pmt == 0.0 { return self.fail(CellError::Num, "NPER needs a non-zero payment when the rate is 0"); }
             return EvalResult::Number(-(pv + fv) / pmt);
         }
-        let pmt_factor = if pmt_type == 1 { 1.0 + rate } else { 1.0 };
-        let num = -fv + pmt * pmt_factor / rate;
-        let den = pv + pmt * pmt_factor / rate;
-        if num / den <= 0.0 { return EvalResult::Error(CellError::Value); }
+        let pmt_factor = if due { 1.0 + rate } else { 1.0 };
+        let num = pmt * pmt_factor / rate - fv;
+        let den = pmt * pmt_factor / rate + pv;
+        if den == 0.0 || num / den <= 0.0 || rate <= -1.0 {
+            return self.fail(CellError::Num, "NPER has no solution for these cash flows");
+        }
         EvalResult::Number((num / den).ln() / (1.0 + rate).ln())
     }
 
@@ -6013,72 +7280,55 @@ impl<'a> Evaluator<'a> {
     // ==================== Advanced Functions ====================
 
     /// LET(name1, value1, [name2, value2, ...], calculation)
-    /// Assigns names to intermediate results for use in a final calculation.
-    /// Since our AST doesn't have a "name binding" node, LET works by evaluating
-    /// value expressions and substituting them into the calculation expression.
-    /// In practice, the parser passes all arguments as positional expressions.
-    /// We evaluate pairs of (name_expr, value_expr) and the final calculation.
-    ///
-    /// Implementation note: LET requires at least 3 arguments (name, value, calculation),
-    /// the total argument count must be odd, and there can be at most 126 name/value pairs.
-    /// Since our Expression AST doesn't support name binding, we use a workaround:
-    /// LET(name1, value1, [name2, value2, ...], calculation)
-    /// Binds names to values in a local scope, then evaluates the calculation.
+    /// Binds each name to its value in the local scope, in order, so a value
+    /// can use the names bound before it; the calculation sees them all.
+    /// Bindings shadow outer LET/LAMBDA names and are removed afterwards.
+    /// Needs an odd argument count of at least 3 (at most 126 pairs); a
+    /// name bound twice in the same LET is #NAME?.
     fn fn_let(&self, args: &[Expression]) -> EvalResult {
-        // LET needs at least 3 args: name1, value1, calculation
-        // Total must be odd (pairs of name/value + final calculation)
-        if args.len() < 3 || args.len() % 2 == 0 {
-            return EvalResult::Error(CellError::Value);
-        }
-        // Max 126 name/value pairs (252 args + 1 calculation = 253)
-        if args.len() > 253 {
+        if args.len() < 3 || args.len().is_multiple_of(2) || args.len() > 253 {
             return EvalResult::Error(CellError::Value);
         }
 
-        // Save previous scope values that might be shadowed
+        // Previous scope values of the names bound here, restored on exit
         let pair_count = (args.len() - 1) / 2;
         let mut saved: Vec<(String, Option<EvalResult>)> = Vec::new();
 
         for i in 0..pair_count {
-            let name = self.extract_param_name(&args[i * 2]);
-            if name.is_empty() {
+            let key = self.extract_param_name(&args[i * 2]);
+            if key.is_empty() {
+                self.restore_scope(saved);
                 return EvalResult::Error(CellError::Value);
             }
+            if saved.iter().any(|(bound, _)| *bound == key) {
+                self.restore_scope(saved);
+                return self.fail(CellError::Name, format!("LET binds '{}' more than once", key));
+            }
             let value = self.evaluate(&args[i * 2 + 1]);
             if let EvalResult::Error(e) = &value {
-                // Restore saved scope entries before returning error
-                let mut scope = self.scope.borrow_mut();
-                for (k, old) in saved.into_iter().rev() {
-                    match old {
-                        Some(v) => { scope.insert(k, v); }
-                        None => { scope.remove(&k); }
-                    }
-                }
-                return EvalResult::Error(e.clone());
-            }
-            let key = name.to_uppercase();
-            {
-                let mut scope = self.scope.borrow_mut();
-                saved.push((key.clone(), scope.get(&key).cloned()));
-                scope.insert(key, value);
+                let e = e.clone();
+                self.restore_scope(saved);
+                return EvalResult::Error(e);
             }
+            let mut scope = self.scope.borrow_mut();
+            saved.push((key.clone(), scope.get(&key).cloned()));
+            scope.insert(key, value);
         }
 
-        // Evaluate the calculation expression with bindings in scope
         let result = self.evaluate(args.last().unwrap());
+        self.restore_scope(saved);
+        result
+    }
 
-        // Restore previous scope
-        {
-            let mut scope = self.scope.borrow_mut();
-            for (k, old) in saved.into_iter().rev() {
-                match old {
-                    Some(v) => { scope.insert(k, v); }
-                    None => { scope.remove(&k); }
-                }
+    /// Undo LET bindings, newest first.
+    fn restore_scope(&self, saved: Vec<(String, Option<EvalResult>)>) {
+        let mut scope = self.scope.borrow_mut();
+        for (key, old) in saved.into_iter().rev() {
+            match old {
+                Some(value) => { scope.insert(key, value); }
+                None => { scope.remove(&key); }
             }
         }
-
-        result
     }
 
     // ==================== Lambda Helper ====================
@@ -6439,23 +7689,30 @@ impl<'a> Evaluator<'a> {
             _ => true, // default to TRUE
         };
 
-        // Collect text values from remaining arguments, iterating over range cells
-        // directly so we can detect truly empty cells (which eval_flat maps to 0.0).
+        // Blank cells stay distinguishable from zeros and empty strings, so
+        // ignore_empty=FALSE keeps a slot for each of them.
         let mut parts: Vec<String> = Vec::new();
         for arg in &args[2..] {
-            self.textjoin_collect(arg, ignore_empty, &mut parts);
+            let arg_parts = match self.text_parts(arg) {
+                Ok(p) => p,
+                Err(e) => return EvalResult::Error(e),
+            };
+            for part in arg_parts {
+                let part = part.unwrap_or_default();
+                if !ignore_empty || !part.is_empty() {
+                    parts.push(part);
+                }
+            }
         }
 
         let result = parts.join(&delimiter);
         // Excel returns #VALUE! if result exceeds 32767 characters
-        if result.len() > 32767 {
+        if result.chars().count() > 32767 {
             return EvalResult::Error(CellError::Value);
         }
         EvalResult::Text(result)
     }
 
-    /// Helper for TEXTJOIN: collects string parts from an expression,
-    /// detecting truly empty cells when iterating over ranges.
     // ==================== Dynamic Array Functions ====================
 
     /// Helper: extract a 2D grid of values from a range expression.
@@ -8011,54 +9268,68 @@ impl<'a> Evaluator<'a> {
         }
     }
 
-    /// TAKE(list, n) - returns first n elements as a new list
+    /// TAKE(array, rows, [columns]) - leading (or, for negative counts,
+    /// trailing) rows/columns of an array. TAKE(list, n) keeps returning the
+    /// first n list items.
     fn fn_take(&self, args: &[Expression]) -> EvalResult {
-        if args.len() != 2 {
+        self.slice_array(args, true)
+    }
+
+    /// DROP(array, rows, [columns]) - the array without its leading (or, for
+    /// negative counts, trailing) rows/columns. DROP(list, n) keeps removing
+    /// the first n list items.
+    fn fn_drop(&self, args: &[Expression]) -> EvalResult {
+        self.slice_array(args, false)
+    }
+
+    /// Shared by TAKE/DROP.
+    fn slice_array(&self, args: &[Expression], take: bool) -> EvalResult {
+        if args.len() < 2 || args.len() > 3 {
             return EvalResult::Error(CellError::Value);
         }
         let collection = self.evaluate(&args[0]);
-        let n_val = self.evaluate(&args[1]);
-
         if let EvalResult::Error(_) = &collection { return collection; }
-        if let EvalResult::Error(_) = &n_val { return n_val; }
-
-        let n = match n_val.as_number() {
-            Some(n) => n as usize,
-            None => return EvalResult::Error(CellError::Value),
-        };
 
-        match collection {
-            EvalResult::List(items) => {
-                let taken: Vec<EvalResult> = items.into_iter().take(n).collect();
-                EvalResult::List(taken)
+        let mut counts = Vec::with_capacity(2);
+        for arg in &args[1..] {
+            match self.evaluate(arg) {
+                EvalResult::Error(e) => return EvalResult::Error(e),
+                v => match v.as_number() {
+                    Some(n) => counts.push(n.trunc() as i64),
+                    None => return EvalResult::Error(CellError::Value),
+                },
             }
-            _ => EvalResult::Error(CellError::Value),
         }
-    }
 
-    /// DROP(list, n) - removes first n elements, returns rest as a new list
-    fn fn_drop(&self, args: &[Expression]) -> EvalResult {
-        if args.len() != 2 {
-            return EvalResult::Error(CellError::Value);
+        if let EvalResult::List(items) = collection {
+            if counts.len() != 1 { return EvalResult::Error(CellError::Value); }
+            let n = counts[0].max(0) as usize;
+            return if take {
+                EvalResult::List(items.into_iter().take(n).collect())
+            } else {
+                EvalResult::List(items.into_iter().skip(n).collect())
+            };
         }
-        let collection = self.evaluate(&args[0]);
-        let n_val = self.evaluate(&args[1]);
-
-        if let EvalResult::Error(_) = &collection { return collection; }
-        if let EvalResult::Error(_) = &n_val { return n_val; }
 
-        let n = match n_val.as_number() {
-            Some(n) => n as usize,
-            None => return EvalResult::Error(CellError::Value),
+        let matrix = self.matrix_of(&args[0], collection);
+        let width = matrix.first().map_or(0, |r| r.len());
+        // Which part of an axis of length `len` survives a count of `n`.
+        let span = |len: usize, n: Option<i64>| -> Option<std::ops::Range<usize>> {
+            let Some(n) = n else { return Some(0..len) };
+            let k = (n.unsigned_abs() as usize).min(len);
+            let range = match (take, n >= 0) {
+                (true, true) => 0..k,
+                (true, false) => len - k..len,
+                (false, true) => k..len,
+                (false, false) => 0..len - k,
+            };
+            // TAKE of 0 and DROP of everything are #CALC! in Excel.
+            (!(range.is_empty() || (take && n == 0))).then_some(range)
         };
-
-        match collection {
-            EvalResult::List(items) => {
-                let dropped: Vec<EvalResult> = items.into_iter().skip(n).collect();
-                EvalResult::List(dropped)
-            }
-            _ => EvalResult::Error(CellError::Value),
-        }
+        let (Some(rows), Some(cols)) = (span(matrix.len(), Some(counts[0])), span(width, counts.get(1).copied())) else {
+            return EvalResult::Error(CellError::Value);
+        };
+        Self::rows_to_result(matrix[rows].iter().map(|row| row[cols.clone()].to_vec()).collect())
     }
 
     /// APPEND(list, value) - returns a new list with value appended
@@ -8107,24 +9378,43 @@ impl<'a> Evaluator<'a> {
         }
     }
 
-    /// HSTACK(list1, list2) - concatenate two lists
+    /// HSTACK(array1, [array2], ...) - arrays side by side, shorter ones
+    /// padded with #N/A. HSTACK(list1, list2) keeps concatenating two lists.
     fn fn_hstack(&self, args: &[Expression]) -> EvalResult {
-        if args.len() != 2 {
+        if args.is_empty() {
             return EvalResult::Error(CellError::Value);
         }
-        let list1 = self.evaluate(&args[0]);
-        let list2 = self.evaluate(&args[1]);
-
-        if let EvalResult::Error(_) = &list1 { return list1; }
-        if let EvalResult::Error(_) = &list2 { return list2; }
-
-        match (list1, list2) {
-            (EvalResult::List(mut items1), EvalResult::List(items2)) => {
-                items1.extend(items2);
-                EvalResult::List(items1)
-            }
-            _ => EvalResult::Error(CellError::Value),
+        let values: Vec<EvalResult> = args.iter().map(|a| self.evaluate(a)).collect();
+        if let Some(err) = values.iter().find(|v| v.is_error()) {
+            return err.clone();
+        }
+        if values.iter().any(|v| matches!(v, EvalResult::List(_))) {
+            return match <[EvalResult; 2]>::try_from(values) {
+                Ok([EvalResult::List(mut items1), EvalResult::List(items2)]) => {
+                    items1.extend(items2);
+                    EvalResult::List(items1)
+                }
+                _ => EvalResult::Error(CellError::Value),
+            };
         }
+
+        let blocks: Vec<Vec<Vec<EvalResult>>> =
+            args.iter().zip(values).map(|(arg, value)| self.matrix_of(arg, value)).collect();
+        let height = blocks.iter().map(|b| b.len()).max().unwrap_or(0);
+        let rows = (0..height)
+            .map(|r| {
+                let mut row = Vec::new();
+                for block in &blocks {
+                    let width = block.first().map_or(0, |b| b.len());
+                    match block.get(r) {
+                        Some(cells) => row.extend(cells.iter().cloned()),
+                        None => row.extend(std::iter::repeat_n(EvalResult::Error(CellError::NA), width)),
+                    }
+                }
+                row
+            })
+            .collect();
+        Self::rows_to_result(rows)
     }
 
     // ========================================================================
@@ -8173,108 +9463,59 @@ impl<'a> Evaluator<'a> {
         }
     }
 
-    fn textjoin_collect(&self, expr: &Expression, ignore_empty: bool, parts: &mut Vec<String>) {
-        match expr {
-            Expression::Range { start, end, .. } => {
-                // Iterate raw cells in the range to detect empties
-                if let (
-                    Expression::CellRef { col: sc, row: sr, .. },
-                    Expression::CellRef { col: ec, row: er, .. },
-                ) = (start.as_ref(), end.as_ref()) {
-                    let sc_idx = col_to_index(sc);
-                    let ec_idx = col_to_index(ec);
-                    let sr_idx = sr - 1;
-                    let er_idx = er - 1;
-                    let r_start = sr_idx.min(er_idx);
-                    let r_end = sr_idx.max(er_idx);
-                    let c_start = sc_idx.min(ec_idx);
-                    let c_end = sc_idx.max(ec_idx);
-                    for r in r_start..=r_end {
-                        for c in c_start..=c_end {
-                            match self.grid.get_cell(r, c) {
-                                Some(cell) => match &cell.value {
-                                    CellValue::Empty => {
-                                        if !ignore_empty { parts.push(String::new()); }
-                                    }
-                                    CellValue::Text(s) => {
-                                        if !ignore_empty || !s.is_empty() {
-                                            parts.push(s.clone());
-                                        }
-                                    }
-                                    CellValue::Number(n) => parts.push(format!("{}", n)),
-                                    CellValue::Boolean(b) => parts.push(if *b { "TRUE".to_string() } else { "FALSE".to_string() }),
-                                    CellValue::Error(e) => parts.push(format!("{:?}", e)),
-                                    CellValue::List(items) => parts.push(format!("[List({})]", items.len())),
-                                    CellValue::Dict(entries) => parts.push(format!("[Dict({})]", entries.len())),
-                                },
-                                None => {
-                                    if !ignore_empty { parts.push(String::new()); }
-                                }
-                            }
-                        }
-                    }
-                }
-            }
-            _ => {
-                // Non-range: evaluate normally
-                match self.evaluate(expr) {
-                    EvalResult::Text(s) => {
-                        if !ignore_empty || !s.is_empty() {
-                            parts.push(s);
-                        }
-                    }
-                    EvalResult::Number(n) => parts.push(format!("{}", n)),
-                    EvalResult::Boolean(b) => parts.push(if b { "TRUE".to_string() } else { "FALSE".to_string() }),
-                    EvalResult::Error(_) => {} // skip errors in TEXTJOIN
-                    EvalResult::List(items) => parts.push(format!("[List({})]", items.len())),
-                    EvalResult::Dict(entries) => parts.push(format!("[Dict({})]", entries.len())),
-                    EvalResult::Lambda { .. } => parts.push("#LAMBDA".to_string()),
-                    EvalResult::Array(arr) => {
-                        for val in arr {
-                            match val {
-                                EvalResult::Text(s) => {
-                                    if !ignore_empty || !s.is_empty() {
-                                        parts.push(s);
-                                    }
-                                }
-                                EvalResult::Number(n) => parts.push(format!("{}", n)),
-                                EvalResult::Boolean(b) => parts.push(if b { "TRUE".to_string() } else { "FALSE".to_string() }),
-                                _ => {}
-                            }
-                        }
-                    }
-                }
-            }
-        }
-    }
-
     // ==================== Text Parsing/Conversion Functions ====================
 
     fn fn_textsplit(&self, args: &[Expression]) -> EvalResult {
         // TEXTSPLIT(text, col_delimiter, [row_delimiter], [ignore_empty], [match_mode], [pad_with])
-        if args.is_empty() || args.len() > 6 { return EvalResult::Error(CellError::Value); }
-        let text = self.evaluate(&args[0]).as_text();
-        let col_delim = if args.len() >= 2 { self.evaluate(&args[1]).as_text() } else { return EvalResult::Error(CellError::Value); };
+        if args.len() < 2 || args.len() > 6 { return EvalResult::Error(CellError::Value); }
+        let text = match self.evaluate(&args[0]) {
+            EvalResult::Error(e) => return EvalResult::Error(e),
+            v => v.as_text(),
+        };
+        let col_delim = self.evaluate(&args[1]).as_text();
         let row_delim = if args.len() >= 3 {
             let v = self.evaluate(&args[2]);
             if matches!(v, EvalResult::Boolean(false)) || matches!(v, EvalResult::Number(n) if n == 0.0) || v.as_text().is_empty() {
                 None
             } else { Some(v.as_text()) }
         } else { None };
-        let _ignore_empty = if args.len() >= 4 { { let _v = self.evaluate(&args[3]); matches!(_v, EvalResult::Boolean(true)) || matches!(_v, EvalResult::Number(n) if n != 0.0) } } else { false };
-        // Split by rows first, then cols
-        if let Some(ref rd) = row_delim {
-            let rows: Vec<&str> = text.split(rd.as_str()).collect();
-            let mut result: Vec<EvalResult> = Vec::new();
-            for row in &rows {
-                let cols: Vec<&str> = row.split(col_delim.as_str()).collect();
-                result.push(EvalResult::Array(cols.iter().map(|s| EvalResult::Text(s.to_string())).collect()));
+        if col_delim.is_empty() && row_delim.is_none() {
+            return EvalResult::Error(CellError::Value);
+        }
+        let ignore_empty = args.len() >= 4 && self.evaluate(&args[3]).as_boolean().unwrap_or(false);
+        let ignore_case = args.len() >= 5 && self.evaluate(&args[4]).as_number() == Some(1.0);
+        let pad_with = if args.len() == 6 { self.evaluate(&args[5]) } else { EvalResult::Error(CellError::NA) };
+
+        let split = |s: &str, delim: &str| -> Vec<String> {
+            let mut parts = split_text(s, delim, ignore_case);
+            if ignore_empty {
+                parts.retain(|p| !p.is_empty());
             }
-            EvalResult::Array(result)
-        } else {
-            let parts: Vec<&str> = text.split(col_delim.as_str()).collect();
-            EvalResult::Array(parts.iter().map(|s| EvalResult::Text(s.to_string())).collect())
+            parts
+        };
+        let mut rows: Vec<Vec<String>> = match &row_delim {
+            Some(rd) => split(&text, rd).iter().map(|row| split(row, &col_delim)).collect(),
+            None => vec![split(&text, &col_delim)],
+        };
+        rows.retain(|row| !row.is_empty());
+        if rows.is_empty() {
+            return EvalResult::Error(CellError::Value);
+        }
+
+        // Ragged rows are padded out to the widest one.
+        let width = rows.iter().map(Vec::len).max().unwrap_or(0);
+        let to_row = |row: Vec<String>| -> Vec<EvalResult> {
+            let pad = width - row.len();
+            row.into_iter()
+                .map(EvalResult::Text)
+                .chain(std::iter::repeat_n(pad_with.clone(), pad))
+                .collect()
+        };
+        if row_delim.is_none() {
+            let row = rows.pop().unwrap_or_default();
+            return EvalResult::Array(to_row(row));
         }
+        EvalResult::Array(rows.into_iter().map(|row| EvalResult::Array(to_row(row))).collect())
     }
 
     fn fn_textbefore(&self, args: &[Expression]) -> EvalResult {
@@ -8415,9 +9656,7 @@ impl<'a> Evaluator<'a> {
                 }
             }
         } else { vec![0, 6] };
-        let holidays: Vec<i64> = if args.len() == 4 {
-            self.eval_flat(&args[3]).iter().filter_map(|v| v.as_number().map(|n| n as i64)).collect()
-        } else { vec![] };
+        let holidays = match self.holidays_arg(args.get(3)) { Ok(h) => h, Err(e) => return EvalResult::Error(e) };
         EvalResult::Number(date_serial::networkdays_intl(start, end, &weekend_days, &holidays) as f64)
     }
 
@@ -8435,9 +9674,7 @@ impl<'a> Evaluator<'a> {
                 }
             }
         } else { vec![0, 6] };
-        let holidays: Vec<i64> = if args.len() == 4 {
-            self.eval_flat(&args[3]).iter().filter_map(|v| v.as_number().map(|n| n as i64)).collect()
-        } else { vec![] };
+        let holidays = match self.holidays_arg(args.get(3)) { Ok(h) => h, Err(e) => return EvalResult::Error(e) };
         EvalResult::Number(date_serial::workday_intl(start, days, &weekend_days, &holidays) as f64)
     }
 
@@ -8491,11 +9728,14 @@ impl<'a> Evaluator<'a> {
         if args.len() < 2 || args.len() > 3 { return EvalResult::Error(CellError::Value); }
         let mut numbers = match self.collect_numbers(&args[0..1]) { Ok(n) => n, Err(e) => return EvalResult::Error(e) };
         let x = match self.evaluate(&args[1]).as_number() { Some(n) => n, None => return EvalResult::Error(CellError::Value) };
-        let significance = if args.len() == 3 { self.evaluate(&args[2]).as_number().unwrap_or(3.0) as usize } else { 3 };
-        if numbers.is_empty() { return EvalResult::Error(CellError::Value); }
+        let significance = if args.len() == 3 { self.evaluate(&args[2]).as_number().unwrap_or(3.0).trunc() } else { 3.0 };
+        // No numbers or significance < 1 is #NUM!
+        if numbers.is_empty() || significance < 1.0 { return EvalResult::Error(CellError::Num); }
         numbers.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
         if x < numbers[0] || x > numbers[numbers.len() - 1] { return EvalResult::Error(CellError::NA); }
         let n = numbers.len();
+        // A single value is its own whole range
+        if n == 1 { return EvalResult::Number(1.0); }
         // Find position by interpolation
         let below = numbers.iter().filter(|&&v| v < x).count();
         let equal = numbers.iter().filter(|&&v| (v - x).abs() < 1e-10).count();
@@ -9499,13 +10739,14 @@ impl<'a> Evaluator<'a> {
     }
 
     fn fn_permut(&self, args: &[Expression]) -> EvalResult {
-        // PERMUT(number, number_chosen)
+        // PERMUT(number, number_chosen), both truncated
         if args.len() != 2 { return EvalResult::Error(CellError::Value); }
-        let n = match self.evaluate(&args[0]).as_number() { Some(v) if v >= 0.0 => v.floor() as u64, _ => return EvalResult::Error(CellError::Value) };
-        let k = match self.evaluate(&args[1]).as_number() { Some(v) if v >= 0.0 => v.floor() as u64, _ => return EvalResult::Error(CellError::Value) };
-        if k > n { return EvalResult::Error(CellError::Value); }
-        let result = (gamma_ln(n as f64 + 1.0) - gamma_ln((n - k) as f64 + 1.0)).exp();
-        EvalResult::Number(result.round())
+        let n = match self.evaluate(&args[0]).as_number() { Some(v) => v.trunc(), None => return EvalResult::Error(CellError::Value) };
+        let k = match self.evaluate(&args[1]).as_number() { Some(v) => v.trunc(), None => return EvalResult::Error(CellError::Value) };
+        match permutations(n, k) {
+            Some(p) => EvalResult::Number(p),
+            None => EvalResult::Error(CellError::Num),
+        }
     }
 
     fn fn_permutationa(&self, args: &[Expression]) -> EvalResult {
@@ -9804,28 +11045,29 @@ impl<'a> Evaluator<'a> {
     fn fn_xirr(&self, args: &[Expression]) -> EvalResult {
         // XIRR(values, dates, [guess])
         if args.len() < 2 || args.len() > 3 { return EvalResult::Error(CellError::Value); }
-        let values: Vec<f64> = self.eval_flat(&args[0]).iter().filter_map(|v| v.as_number()).collect();
-        let dates: Vec<f64> = self.eval_flat(&args[1]).iter().filter_map(|v| v.as_number()).collect();
-        if values.len() != dates.len() || values.is_empty() { return EvalResult::Error(CellError::Value); }
-        let mut rate = if args.len() == 3 { self.evaluate(&args[2]).as_number().unwrap_or(0.1) } else { 0.1 };
-        let d0 = dates[0];
-        // Newton's method
-        for _ in 0..200 {
-            let mut f = 0.0;
-            let mut df = 0.0;
-            for i in 0..values.len() {
-                let years = (dates[i] - d0) / 365.0;
-                let denom = (1.0 + rate).powf(years);
-                if denom.abs() < 1e-15 { continue; }
-                f += values[i] / denom;
-                df -= years * values[i] / ((1.0 + rate) * denom);
-            }
-            if df.abs() < 1e-15 { return EvalResult::Error(CellError::Value); }
-            let new_rate = rate - f / df;
-            if (new_rate - rate).abs() < 1e-10 { return EvalResult::Number(new_rate); }
-            rate = new_rate;
+        let values = match self.cash_flows(&args[0]) { Ok(v) => v, Err(e) => return EvalResult::Error(e) };
+        let dates = match self.cash_flows(&args[1]) { Ok(d) => d, Err(e) => return EvalResult::Error(e) };
+        let guess = match args.get(2).map(|g| self.evaluate(g)) {
+            None => 0.1,
+            Some(EvalResult::Error(e)) => return EvalResult::Error(e),
+            Some(g) => g.as_number().unwrap_or(0.1),
+        };
+        if values.len() != dates.len() || values.is_empty() {
+            return self.fail(CellError::Num, "XIRR needs one date per cash flow");
+        }
+        let start = dates[0].trunc();
+        if dates.iter().any(|&d| d.trunc() < start) {
+            return self.fail(CellError::Num, "XIRR dates must not precede the first date");
+        }
+        if !has_sign_change(&values) {
+            return self.fail(CellError::Num, "XIRR needs at least one positive and one negative cash flow");
+        }
+        let flows: Vec<(f64, f64)> =
+            dates.iter().zip(&values).map(|(&d, &v)| ((d.trunc() - start) / 365.0, v)).collect();
+        match discount_rate_root(&flows, guess) {
+            Some(rate) => EvalResult::Number(rate),
+            None => self.fail(CellError::Num, format!("XIRR did not converge after {} iterations", IRR_MAX_ITERATIONS)),
         }
-        EvalResult::Error(CellError::Value)
     }
 
     fn fn_mirr(&self, args: &[Expression]) -> EvalResult {
@@ -10848,7 +12090,7 @@ impl<'a> Evaluator<'a> {
         let number = match self.evaluate(&args[0]).as_number() { Some(n) => n, None => return EvalResult::Error(CellError::Value) };
         let from = self.evaluate(&args[1]).as_text();
         let to = self.evaluate(&args[2]).as_text();
-        match convert_units(number, &from, &to) {
+        match crate::units::convert(number, &from, &to) {
             Some(v) => EvalResult::Number(v),
             None => EvalResult::Error(CellError::NA),
         }
@@ -11133,31 +12375,101 @@ impl<'a> Evaluator<'a> {
         EvalResult::Number(1.0)
     }
 
+    /// CELL(info_type, [reference]) for the upper-left cell of `reference`,
+    /// or of the cell being evaluated when it is omitted. info_type is one of
+    /// "address", "col", "row", "contents", "type" ("b" blank, "l" text
+    /// constant, "v" anything else) or "format" (see
+    /// `number_format::cell_format_code`; "G" without a style registry).
     fn fn_cell(&self, args: &[Expression]) -> EvalResult {
-        // CELL(info_type, [reference]) - simplified implementation
         if args.is_empty() || args.len() > 2 { return EvalResult::Error(CellError::Value); }
-        let info_type = self.evaluate(&args[0]).as_text().to_uppercase();
-        if args.len() == 2 {
-            match &args[1] {
-                Expression::CellRef { col, row, .. } => {
-                    match info_type.as_str() {
-                        "ADDRESS" => EvalResult::Text(format!("${}${}", col, row)),
-                        "COL" => EvalResult::Number(col_to_index(col) as f64 + 1.0),
-                        "ROW" => EvalResult::Number(*row as f64),
-                        "CONTENTS" => self.evaluate(&args[1]),
-                        _ => EvalResult::Text(String::new()),
-                    }
+        let info_type = match self.evaluate(&args[0]) {
+            EvalResult::Error(e) => return EvalResult::Error(e),
+            v => v.as_text().to_lowercase(),
+        };
+        let (sheet, row, col) = match args.get(1) {
+            Some(Expression::CellRef { sheet, col, row, .. }) => (sheet.clone(), row - 1, col_to_index(col)),
+            Some(Expression::Range { sheet, start, .. }) => match start.as_ref() {
+                Expression::CellRef { sheet: start_sheet, col, row, .. } => {
+                    (sheet.clone().or_else(|| start_sheet.clone()), row - 1, col_to_index(col))
                 }
-                _ => EvalResult::Error(CellError::Value),
-            }
-        } else {
-            EvalResult::Error(CellError::Value)
+                _ => return EvalResult::Error(CellError::Value),
+            },
+            Some(_) => return EvalResult::Error(CellError::Value),
+            None => match (self.context.current_row, self.context.current_col) {
+                (Some(row), Some(col)) => (None, row, col),
+                _ => return EvalResult::Error(CellError::Value),
+            },
+        };
+        if let Some(missing) = self.missing_sheet(&sheet) {
+            return missing;
+        }
+        let grid = self.get_grid_for_sheet(&sheet);
+        let cell = grid.get_cell(row, col);
+        match info_type.as_str() {
+            "address" => EvalResult::Text(format!(
+                "{}${}${}",
+                crate::ast_render::render_sheet_prefix(&sheet),
+                crate::coord::index_to_col(col),
+                row + 1
+            )),
+            "col" => EvalResult::Number(col as f64 + 1.0),
+            "row" => EvalResult::Number(row as f64 + 1.0),
+            "contents" => match cell {
+                Some(c) => self.cell_value_to_result(&c.value),
+                None => EvalResult::Number(0.0),
+            },
+            "type" => EvalResult::Text(
+                match cell {
+                    Some(c) if c.has_formula() => "v",
+                    None => "b",
+                    Some(c) if matches!(c.value, CellValue::Empty) => "b",
+                    Some(c) if matches!(c.value, CellValue::Text(_)) => "l",
+                    Some(_) => "v",
+                }
+                .to_string(),
+            ),
+            "format" => EvalResult::Text(match self.styles {
+                Some(styles) => {
+                    let style = styles.get(cell.map_or(0, |c| c.style_index));
+                    number_format::cell_format_code(&style.number_format)
+                }
+                None => "G".to_string(),
+            }),
+            _ => self.fail(CellError::Value, format!("CELL: unsupported info_type \"{}\"", info_type)),
         }
     }
 
-    fn fn_formulatext(&self, _args: &[Expression]) -> EvalResult {
-        // FORMULATEXT requires access to the raw formula string which isn't available in the evaluator
-        EvalResult::Error(CellError::NA)
+    /// INFO(type_text): "numfile" is the number of sheets; "recalc" reports
+    /// "Automatic", as the evaluator does not see the workbook's calculation
+    /// mode. Other types are #VALUE!.
+    fn fn_info(&self, args: &[Expression]) -> EvalResult {
+        if args.len() != 1 { return EvalResult::Error(CellError::Value); }
+        let type_text = match self.evaluate(&args[0]) {
+            EvalResult::Error(e) => return EvalResult::Error(e),
+            v => v.as_text().to_lowercase(),
+        };
+        match type_text.as_str() {
+            "numfile" => EvalResult::Number(self.multi_sheet.as_ref().map_or(1, |ctx| ctx.sheet_order.len().max(1)) as f64),
+            "recalc" => EvalResult::Text("Automatic".to_string()),
+            _ => self.fail(CellError::Value, format!("INFO: unsupported type_text \"{}\"", type_text)),
+        }
+    }
+
+    /// FORMULATEXT(reference): the formula of the referenced cell (top-left
+    /// of a range) with its leading "=", read from the cell's AST rather than
+    /// its value. #N/A when the cell holds no formula.
+    fn fn_formulatext(&self, args: &[Expression]) -> EvalResult {
+        if args.len() != 1 { return EvalResult::Error(CellError::Value); }
+        if let Some(missing) = self.missing_reference_sheet(&args[0]) {
+            return missing;
+        }
+        match self.reference_extent(&args[0]) {
+            Some((grid, row, col, ..)) => match grid.get_cell(row, col).and_then(|c| c.formula_string()) {
+                Some(formula) => EvalResult::Text(format!("={}", formula)),
+                None => self.fail(CellError::NA, "FORMULATEXT: the cell does not contain a formula"),
+            },
+            None => self.fail(CellError::Value, "FORMULATEXT: reference must be a cell or range"),
+        }
     }
 
     // ==================== Array Reshaping Functions ====================
@@ -11186,129 +12498,171 @@ impl<'a> Evaluator<'a> {
     }
 
     fn fn_vstack(&self, args: &[Expression]) -> EvalResult {
-        // VSTACK(array1, array2, ...) - stack arrays vertically
+        // VSTACK(array1, [array2], ...) - stack arrays vertically, narrower
+        // ones padded with #N/A
         if args.is_empty() { return EvalResult::Error(CellError::Value); }
-        let mut result: Vec<EvalResult> = Vec::new();
+        let mut rows: Vec<Vec<EvalResult>> = Vec::new();
         for arg in args {
-            let rows = self.eval_as_2d(arg);
-            for row in rows {
-                result.push(if row.len() == 1 { row.into_iter().next().unwrap() } else { EvalResult::Array(row) });
+            match self.evaluate(arg) {
+                EvalResult::Error(e) => return EvalResult::Error(e),
+                value => rows.extend(self.matrix_of(arg, value)),
             }
         }
-        if result.len() == 1 { result.into_iter().next().unwrap() } else { EvalResult::Array(result) }
+        let width = rows.iter().map(|r| r.len()).max().unwrap_or(0);
+        for row in &mut rows {
+            row.resize(width, EvalResult::Error(CellError::NA));
+        }
+        Self::rows_to_result(rows)
     }
 
     fn fn_tocol(&self, args: &[Expression]) -> EvalResult {
         // TOCOL(array, [ignore], [scan_by_column])
-        if args.is_empty() || args.len() > 3 { return EvalResult::Error(CellError::Value); }
-        let rows = self.eval_as_2d(&args[0]);
-        let ignore = if args.len() >= 2 { self.evaluate(&args[1]).as_number().unwrap_or(0.0) as i32 } else { 0 };
-        let by_col = if args.len() == 3 { { let _v = self.evaluate(&args[2]); matches!(_v, EvalResult::Boolean(true)) || matches!(_v, EvalResult::Number(n) if n != 0.0) } } else { false };
-        let mut flat: Vec<EvalResult> = Vec::new();
-        if by_col {
-            let max_cols = rows.iter().map(|r| r.len()).max().unwrap_or(0);
-            for c in 0..max_cols {
-                for row in &rows {
-                    if c < row.len() {
-                        flat.push(row[c].clone());
-                    }
-                }
-            }
-        } else {
-            for row in &rows {
-                flat.extend(row.iter().cloned());
-            }
+        match self.flatten_for_vector(args) {
+            Ok(values) => Self::rows_to_result(values.into_iter().map(|v| vec![v]).collect()),
+            Err(e) => e,
         }
-        // Filter based on ignore param
-        let filtered: Vec<EvalResult> = flat.into_iter().filter(|v| {
-            match ignore {
-                1 => !matches!(v, EvalResult::Text(s) if s.is_empty()),
-                2 => !matches!(v, EvalResult::Error(_)),
-                3 => !matches!(v, EvalResult::Text(s) if s.is_empty()) && !matches!(v, EvalResult::Error(_)),
-                _ => true,
-            }
-        }).collect();
-        // Return as vertical array (each element is a single-value row)
-        EvalResult::Array(filtered)
     }
 
     fn fn_torow(&self, args: &[Expression]) -> EvalResult {
         // TOROW(array, [ignore], [scan_by_column])
-        if args.is_empty() || args.len() > 3 { return EvalResult::Error(CellError::Value); }
-        let rows = self.eval_as_2d(&args[0]);
-        let ignore = if args.len() >= 2 { self.evaluate(&args[1]).as_number().unwrap_or(0.0) as i32 } else { 0 };
-        let by_col = if args.len() == 3 { { let _v = self.evaluate(&args[2]); matches!(_v, EvalResult::Boolean(true)) || matches!(_v, EvalResult::Number(n) if n != 0.0) } } else { false };
-        let mut flat: Vec<EvalResult> = Vec::new();
-        if by_col {
-            let max_cols = rows.iter().map(|r| r.len()).max().unwrap_or(0);
-            for c in 0..max_cols {
-                for row in &rows {
-                    if c < row.len() {
-                        flat.push(row[c].clone());
-                    }
-                }
-            }
+        match self.flatten_for_vector(args) {
+            Ok(values) => Self::rows_to_result(vec![values]),
+            Err(e) => e,
+        }
+    }
+
+    /// Shared by TOCOL/TOROW: the array's values in scan order, minus the
+    /// ones `ignore` drops (1 = blanks, 2 = errors, 3 = both). Blanks are only
+    /// recognisable on references, since an empty cell evaluates to 0.
+    fn flatten_for_vector(&self, args: &[Expression]) -> Result<Vec<EvalResult>, EvalResult> {
+        if args.is_empty() || args.len() > 3 { return Err(EvalResult::Error(CellError::Value)); }
+        let ignore = match args.get(1).map(|a| self.evaluate(a)) {
+            None => 0,
+            Some(EvalResult::Error(e)) => return Err(EvalResult::Error(e)),
+            Some(v) => match v.as_number() {
+                Some(n) if (0.0..4.0).contains(&n.trunc()) => n.trunc() as u8,
+                _ => return Err(EvalResult::Error(CellError::Value)),
+            },
+        };
+        let by_col = match args.get(2).map(|a| self.evaluate(a)) {
+            None => false,
+            Some(EvalResult::Error(e)) => return Err(EvalResult::Error(e)),
+            Some(v) => v.as_boolean().ok_or(EvalResult::Error(CellError::Value))?,
+        };
+
+        // (value, blank) per cell, row by row.
+        let cells: Vec<Vec<(EvalResult, bool)>> = match self.reference_extent(&args[0]) {
+            Some((grid, min_row, min_col, max_row, max_col)) => (min_row..=max_row)
+                .map(|r| {
+                    (min_col..=max_col)
+                        .map(|c| {
+                            let cell = grid.get_cell(r, c);
+                            let value = cell.map_or(EvalResult::Number(0.0), |c| self.cell_value_to_result(&c.value));
+                            (value, Self::is_blank_cell(cell))
+                        })
+                        .collect()
+                })
+                .collect(),
+            None => match self.evaluate(&args[0]) {
+                EvalResult::Error(e) => return Err(EvalResult::Error(e)),
+                value => self
+                    .matrix_of(&args[0], value)
+                    .into_iter()
+                    .map(|row| row.into_iter().map(|v| (v, false)).collect())
+                    .collect(),
+            },
+        };
+
+        let cols = cells.first().map_or(0, |r| r.len());
+        let order: Vec<(usize, usize)> = if by_col {
+            (0..cols).flat_map(|c| (0..cells.len()).map(move |r| (r, c))).collect()
         } else {
-            for row in &rows {
-                flat.extend(row.iter().cloned());
-            }
-        }
-        let filtered: Vec<EvalResult> = flat.into_iter().filter(|v| {
-            match ignore {
-                1 => !matches!(v, EvalResult::Text(s) if s.is_empty()),
-                2 => !matches!(v, EvalResult::Error(_)),
-                3 => !matches!(v, EvalResult::Text(s) if s.is_empty()) && !matches!(v, EvalResult::Error(_)),
-                _ => true,
-            }
-        }).collect();
-        // Return as a single-row array
-        EvalResult::Array(filtered)
+            (0..cells.len()).flat_map(|r| (0..cols).map(move |c| (r, c))).collect()
+        };
+        let values: Vec<EvalResult> = order
+            .into_iter()
+            .filter_map(|(r, c)| {
+                let (value, blank) = cells[r].get(c)?;
+                let skip = (ignore & 1 != 0 && *blank) || (ignore & 2 != 0 && value.is_error());
+                (!skip).then(|| value.clone())
+            })
+            .collect();
+        // Excel reports #CALC! when everything was dropped.
+        if values.is_empty() { return Err(EvalResult::Error(CellError::Value)); }
+        Ok(values)
     }
 
     fn fn_wrapcols(&self, args: &[Expression]) -> EvalResult {
         // WRAPCOLS(vector, wrap_count, [pad_with])
-        if args.len() < 2 || args.len() > 3 { return EvalResult::Error(CellError::Value); }
-        let flat = self.eval_flat(&args[0]);
-        let wrap_count = match self.evaluate(&args[1]).as_number() { Some(n) if n >= 1.0 => n as usize, _ => return EvalResult::Error(CellError::Value) };
-        let pad = if args.len() == 3 { self.evaluate(&args[2]) } else { EvalResult::Error(CellError::NA) };
-        let num_cols = (flat.len() + wrap_count - 1) / wrap_count;
-        let mut result: Vec<EvalResult> = Vec::new();
-        for r in 0..wrap_count {
-            let mut row: Vec<EvalResult> = Vec::new();
-            for c in 0..num_cols {
-                let idx = c * wrap_count + r;
-                if idx < flat.len() {
-                    row.push(flat[idx].clone());
-                } else {
-                    row.push(pad.clone());
-                }
+        match self.wrap_vector(args) {
+            Ok(columns) => {
+                let height = columns.first().map_or(0, |c| c.len());
+                let rows = (0..height).map(|r| columns.iter().map(|col| col[r].clone()).collect()).collect();
+                Self::rows_to_result(rows)
             }
-            result.push(if row.len() == 1 { row.into_iter().next().unwrap() } else { EvalResult::Array(row) });
+            Err(e) => e,
         }
-        if result.len() == 1 { result.into_iter().next().unwrap() } else { EvalResult::Array(result) }
     }
 
     fn fn_wraprows(&self, args: &[Expression]) -> EvalResult {
         // WRAPROWS(vector, wrap_count, [pad_with])
-        if args.len() < 2 || args.len() > 3 { return EvalResult::Error(CellError::Value); }
-        let flat = self.eval_flat(&args[0]);
-        let wrap_count = match self.evaluate(&args[1]).as_number() { Some(n) if n >= 1.0 => n as usize, _ => return EvalResult::Error(CellError::Value) };
-        let pad = if args.len() == 3 { self.evaluate(&args[2]) } else { EvalResult::Error(CellError::NA) };
-        let mut result: Vec<EvalResult> = Vec::new();
-        let mut i = 0;
-        while i < flat.len() {
-            let mut row: Vec<EvalResult> = Vec::new();
-            for j in 0..wrap_count {
-                if i + j < flat.len() {
-                    row.push(flat[i + j].clone());
-                } else {
-                    row.push(pad.clone());
-                }
-            }
-            result.push(if row.len() == 1 { row.into_iter().next().unwrap() } else { EvalResult::Array(row) });
-            i += wrap_count;
+        match self.wrap_vector(args) {
+            Ok(rows) => Self::rows_to_result(rows),
+            Err(e) => e,
+        }
+    }
+
+    /// Shared by WRAPROWS/WRAPCOLS: the vector cut into chunks of
+    /// `wrap_count`, the last one padded with `pad_with` (default #N/A).
+    fn wrap_vector(&self, args: &[Expression]) -> Result<Vec<Vec<EvalResult>>, EvalResult> {
+        if args.len() < 2 || args.len() > 3 { return Err(EvalResult::Error(CellError::Value)); }
+        let vector = self.evaluate(&args[0]);
+        if let EvalResult::Error(_) = vector { return Err(vector); }
+        let (rows, cols) = vector.spill_dimensions();
+        if rows > 1 && cols > 1 { return Err(EvalResult::Error(CellError::Value)); }
+        let wrap_count = match self.evaluate(&args[1]) {
+            EvalResult::Error(e) => return Err(EvalResult::Error(e)),
+            v => match v.as_number() {
+                Some(n) if n.trunc() >= 1.0 => n.trunc() as usize,
+                Some(_) => return Err(EvalResult::Error(CellError::Num)),
+                None => return Err(EvalResult::Error(CellError::Value)),
+            },
+        };
+        let pad = match args.get(2) {
+            Some(a) => self.evaluate(a),
+            None => EvalResult::Error(CellError::NA),
+        };
+        let flat = vector.into_flatten();
+        Ok(flat
+            .chunks(wrap_count)
+            .map(|chunk| {
+                let mut chunk = chunk.to_vec();
+                chunk.resize(wrap_count, pad.clone());
+                chunk
+            })
+            .collect())
+    }
+
+    /// Build a spillable result from rows: a scalar for 1x1, a flat array
+    /// for a single column, and an array of row arrays otherwise (so a single
+    /// row keeps its orientation).
+    fn rows_to_result(rows: Vec<Vec<EvalResult>>) -> EvalResult {
+        if rows.iter().all(|r| r.len() == 1) {
+            let mut column: Vec<EvalResult> = rows.into_iter().flatten().collect();
+            return if column.len() == 1 { column.pop().unwrap() } else { EvalResult::Array(column) };
         }
-        if result.len() == 1 { result.into_iter().next().unwrap() } else { EvalResult::Array(result) }
+        EvalResult::Array(rows.into_iter().map(EvalResult::Array).collect())
+    }
+
+    /// Rows of `value`, the evaluated form of `expr`. Ranges take their
+    /// shape from the reference.
+    fn matrix_of(&self, expr: &Expression, value: EvalResult) -> Vec<Vec<EvalResult>> {
+        let (rows, cols) = match expr {
+            Expression::Range { .. } => self.get_range_dimensions(expr),
+            _ => value.spill_dimensions(),
+        };
+        let mut flat = value.into_flatten().into_iter();
+        (0..rows).map(|_| flat.by_ref().take(cols).collect()).collect()
     }
 
     /// Helper: evaluate an expression as a 2D array (vector of rows, each row is a vector of EvalResult)
@@ -11340,7 +12694,281 @@ fn calc_pmt(rate: f64, nper: f64, pv: f64, fv: f64, pmt_type: i32) -> f64 {
     }
 }
 
+/// "ROUND expects 1 to 2 arguments, got 3" when `given` is outside the
+/// catalog signature of `func`; None when it fits or `func` has no entry.
+fn arity_mismatch(func: &BuiltinFunction, given: usize) -> Option<String> {
+    let name = func.to_canonical_name();
+    let sig = parser::function_signature(name)?;
+    let min = sig.args.iter().filter(|a| !a.optional).count();
+    let max = (!sig.args.iter().any(|a| a.variadic)).then_some(sig.args.len());
+    if given >= min && max.is_none_or(|max| given <= max) {
+        return None;
+    }
+    let plural = |n: usize| if n == 1 { "" } else { "s" };
+    let expected = match max {
+        Some(max) if max == min => format!("{} argument{}", min, plural(min)),
+        Some(max) => format!("{} to {} arguments", min, max),
+        None => format!("at least {} argument{}", min, plural(min)),
+    };
+    Some(format!("{} expects {}, got {}", name, expected, given))
+}
+
+/// Newton iterations IRR and XIRR take before reporting failure.
+const IRR_MAX_ITERATIONS: u32 = 100;
+
+/// Whether cash flows contain both an inflow and an outflow; without one
+/// of each no discount rate can bring their present value to zero.
+fn has_sign_change(values: &[f64]) -> bool {
+    values.iter().any(|&v| v > 0.0) && values.iter().any(|&v| v < 0.0)
+}
+
+/// Rate at which `sum(v / (1+r)^t)` over `(t, v)` flows is zero, by Newton's
+/// method from `guess`. None when it leaves the domain (r <= -1), stalls
+/// or does not settle within `IRR_MAX_ITERATIONS`.
+fn discount_rate_root(flows: &[(f64, f64)], guess: f64) -> Option<f64> {
+    let mut rate = guess;
+    for _ in 0..IRR_MAX_ITERATIONS {
+        if rate <= -1.0 || !rate.is_finite() { return None; }
+        let (mut f, mut df) = (0.0, 0.0);
+        for &(t, v) in flows {
+            let factor = (1.0 + rate).powf(t);
+            f += v / factor;
+            df -= t * v / (factor * (1.0 + rate));
+        }
+        if df == 0.0 || !df.is_finite() { return None; }
+        let next = rate - f / df;
+        if (next - rate).abs() < 1e-10 { return Some(next); }
+        rate = next;
+    }
+    None
+}
+
+/// Iterations RATE takes by default before reporting failure (Excel's own
+/// limit).
+pub const RATE_MAX_ITERATIONS: u32 = 20;
+
+/// Root of the annuity balance `f` (value and derivative in the rate) from
+/// `guess`. Newton steps are safeguarded by bisection of a bracket around the
+/// root: a step that would leave the bracket or that shrinks the residual too
+/// slowly (far from the root the balance grows like (1+r)^nper, so plain
+/// Newton crawls) bisects instead. Without a bracket it is plain Newton.
+/// None when it leaves the domain (r <= -1) or does not settle within
+/// `max_iterations`.
+fn rate_root(f: impl Fn(f64) -> (f64, f64), guess: f64, max_iterations: u32) -> Option<f64> {
+    if guess <= -1.0 { return None; }
+    let Some((mut lo, mut hi)) = bracket_root(|rate| f(rate).0, guess) else {
+        let mut rate = guess;
+        for _ in 0..max_iterations {
+            if rate <= -1.0 { return None; }
+            let (v, dv) = f(rate);
+            if dv == 0.0 || !dv.is_finite() { return None; }
+            let next = rate - v / dv;
+            if (next - rate).abs() < 1e-10 { return Some(next); }
+            rate = next;
+        }
+        return None;
+    };
+    if lo == hi { return Some(lo); }
+    // Orient the bracket so that f(lo) < 0 < f(hi).
+    if f(lo).0 > 0.0 { std::mem::swap(&mut lo, &mut hi); }
+    let mut rate = if guess > lo.min(hi) && guess < lo.max(hi) { guess } else { (lo + hi) / 2.0 };
+    let mut step = (hi - lo).abs();
+    let mut prev_step = step;
+    let (mut v, mut dv) = f(rate);
+    for _ in 0..max_iterations {
+        let leaves_bracket = ((rate - hi) * dv - v) * ((rate - lo) * dv - v) > 0.0;
+        let too_slow = (2.0 * v).abs() > (prev_step * dv).abs();
+        prev_step = step;
+        if leaves_bracket || too_slow || !dv.is_finite() {
+            step = (hi - lo) / 2.0;
+            rate = lo + step;
+        } else {
+            step = v / dv;
+            rate -= step;
+        }
+        if step.abs() < 1e-10 { return Some(rate); }
+        (v, dv) = f(rate);
+        if v < 0.0 { lo = rate } else { hi = rate }
+    }
+    None
+}
+
+/// Two rates on either side of a sign change of `f`, found by walking from
+/// `guess` up in doubling steps and down toward -1 in halving ones; the
+/// change nearer the guess wins. `(guess, guess)` when the guess is a root,
+/// None when neither walk finds a change.
+fn bracket_root(f: impl Fn(f64) -> f64, guess: f64) -> Option<(f64, f64)> {
+    let at_guess = f(guess);
+    if at_guess == 0.0 { return Some((guess, guess)); }
+    let walk = |points: &mut dyn Iterator<Item = f64>| {
+        let mut prev = guess;
+        for rate in points {
+            let v = f(rate);
+            if v.is_nan() { return None; }
+            if (v > 0.0) != (at_guess > 0.0) { return Some((prev, rate)); }
+            prev = rate;
+        }
+        None
+    };
+    let up = walk(&mut (0..40).map(|k| guess + 0.1 * 2f64.powi(k)));
+    let down = walk(&mut (1..60).map(|k| -1.0 + (1.0 + guess) / 2f64.powi(k)));
+    match (up, down) {
+        (Some(up), Some(down)) => Some(if up.1 - guess <= guess - down.1 { up } else { down }),
+        (up, down) => up.or(down),
+    }
+}
+
+/// Residual of the annuity balance equation
+/// `pv*(1+r)^n + pmt*(1+r*type)*((1+r)^n - 1)/r + fv` and its derivative in
+/// `r`, with the closed-form limits at `r = 0` so a zero rate never divides.
+/// `(1+r)^n - 1` goes through `exp_m1`/`ln_1p` so rates close to zero keep
+/// their precision.
+fn tvm_balance(rate: f64, nper: f64, pmt: f64, pv: f64, fv: f64, due: bool) -> (f64, f64) {
+    let t = if due { 1.0 } else { 0.0 };
+    if rate.abs() < 1e-12 {
+        let f = pv + pmt * nper + fv;
+        let df = pv * nper + pmt * (nper * (nper - 1.0) / 2.0 + t * nper);
+        return (f, df);
+    }
+    let growth = nper * rate.ln_1p();
+    let pvif = growth.exp();
+    let dpvif = nper * pvif / (1.0 + rate);
+    let annuity = growth.exp_m1() / rate;
+    let dannuity = (dpvif * rate - growth.exp_m1()) / (rate * rate);
+    let f = pv * pvif + pmt * (1.0 + rate * t) * annuity + fv;
+    let df = pv * dpvif + pmt * (t * annuity + (1.0 + rate * t) * dannuity);
+    (f, df)
+}
+
+/// Largest angle magnitude (exclusive) SIN/COS/TAN accept, as in Excel.
+const TRIG_ARG_LIMIT: f64 = 134_217_728.0; // 2^27
+
+/// Round to the 15 significant digits a cell holds, so binary noise such as
+/// 1.1*10 = 11.000000000000002 does not push ROUNDUP/MROUND a step too far.
+fn snap_15_digits(x: f64) -> f64 {
+    if x == 0.0 || !x.is_finite() {
+        return x;
+    }
+    format!("{:.14e}", x).parse().unwrap_or(x)
+}
+
+/// Helper: round `n` to `digits` places by applying `step` to its magnitude
+/// (ceil = away from zero, trunc = toward zero), as ROUNDUP/ROUNDDOWN/TRUNC do.
+fn round_magnitude(n: f64, digits: i32, step: fn(f64) -> f64) -> f64 {
+    let factor = 10f64.powi(digits);
+    let scaled = snap_15_digits(n.abs() * factor);
+    let result = n.signum() * step(scaled) / factor;
+    if result.is_finite() { result } else { n }
+}
+
 /// Format a number cleanly (no trailing zeros for integers)
+/// Windows-1252 characters for codes 0x80-0x9F (the rest of the set matches
+/// Latin-1). The five unassigned codes map to the C1 control they name.
+const CP1252_HIGH: [char; 32] = [
+    '\u{20AC}', '\u{0081}', '\u{201A}', '\u{0192}', '\u{201E}', '\u{2026}', '\u{2020}', '\u{2021}',
+    '\u{02C6}', '\u{2030}', '\u{0160}', '\u{2039}', '\u{0152}', '\u{008D}', '\u{017D}', '\u{008F}',
+    '\u{0090}', '\u{2018}', '\u{2019}', '\u{201C}', '\u{201D}', '\u{2022}', '\u{2013}', '\u{2014}',
+    '\u{02DC}', '\u{2122}', '\u{0161}', '\u{203A}', '\u{0153}', '\u{009D}', '\u{017E}', '\u{0178}',
+];
+
+fn cp1252_char(code: u8) -> char {
+    match code {
+        0x80..=0x9F => CP1252_HIGH[(code - 0x80) as usize],
+        _ => code as char,
+    }
+}
+
+/// Inverse of `cp1252_char`; None for characters outside Windows-1252.
+fn cp1252_code(c: char) -> Option<u8> {
+    if let Some(i) = CP1252_HIGH.iter().position(|&h| h == c) {
+        return Some(0x80 + i as u8);
+    }
+    match c as u32 {
+        code @ (0..=0x7F | 0xA0..=0xFF) => Some(code as u8),
+        _ => None,
+    }
+}
+
+/// Digit alphabet of radixes 2-36 (BASE, DECIMAL): a radix uses its first
+/// `radix` symbols.
+const RADIX_DIGITS: &[u8; 36] = b"0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZ";
+
+/// `n` written in `radix` (2-36), most significant digit first.
+fn to_radix(mut n: u64, radix: u32) -> String {
+    let radix = u64::from(radix);
+    let mut digits = Vec::new();
+    loop {
+        digits.push(RADIX_DIGITS[(n % radix) as usize]);
+        n /= radix;
+        if n == 0 {
+            break;
+        }
+    }
+    digits.iter().rev().map(|&d| d as char).collect()
+}
+
+/// Inverse of `to_radix`, case-insensitive; None on a symbol outside the
+/// radix's alphabet or a value of 2^53 or more.
+fn from_radix(text: &str, radix: u32) -> Option<f64> {
+    let alphabet = &RADIX_DIGITS[..radix as usize];
+    let mut value = 0.0;
+    for c in text.chars() {
+        let c = u8::try_from(c.to_ascii_uppercase()).ok()?;
+        let digit = alphabet.iter().position(|&d| d == c)?;
+        value = value * f64::from(radix) + digit as f64;
+    }
+    (value < 9007199254740992.0).then_some(value)
+}
+
+/// Roman numeral symbols, largest first (ROMAN, ARABIC). Even indices are
+/// the powers of ten, odd ones the fives between them.
+const ROMAN_DIGITS: [(char, i64); 7] =
+    [('M', 1000), ('D', 500), ('C', 100), ('L', 50), ('X', 10), ('V', 5), ('I', 1)];
+
+fn roman_value(c: char) -> Option<i64> {
+    ROMAN_DIGITS.iter().find(|&&(symbol, _)| symbol == c).map(|&(_, value)| value)
+}
+
+fn gcd_u64(a: u64, b: u64) -> u64 {
+    if b == 0 { a } else { gcd_u64(b, a % b) }
+}
+
+/// n! / (n - k)! for whole n >= k >= 0 (PERMUT, FACT), multiplied out
+/// directly; None on invalid counts or once the product passes f64.
+fn permutations(n: f64, k: f64) -> Option<f64> {
+    if n < 0.0 || k < 0.0 || k > n { return None; }
+    let mut result = 1.0;
+    let mut i = 0.0;
+    while i < k {
+        result *= n - i;
+        if !result.is_finite() { return None; }
+        i += 1.0;
+    }
+    Some(result)
+}
+
+/// n! / (k! (n - k)!) for whole n >= k >= 0 (COMBIN, COMBINA) by the
+/// multiplicative formula, so large n stays within f64 when the result does.
+/// Each step is exact while the partial product fits; past that it divides
+/// first. None on invalid counts or a result beyond f64.
+fn combinations(n: f64, k: f64) -> Option<f64> {
+    if n < 0.0 || k < 0.0 || k > n { return None; }
+    let k = k.min(n - k);
+    let mut result = 1.0;
+    let mut i = 0.0;
+    while i < k {
+        let product = result * (n - i);
+        result = if product < 9007199254740992.0 {
+            product / (i + 1.0)
+        } else {
+            result * ((n - i) / (i + 1.0))
+        };
+        if !result.is_finite() { return None; }
+        i += 1.0;
+    }
+    Some(result.round())
+}
+
 fn format_number_clean(n: f64) -> String {
     if n == n.floor() && n.abs() < 1e15 {
         format!("{}", n as i64)
@@ -11749,88 +13377,6 @@ fn bessel_k(x: f64, n: i32) -> f64 {
     }
 }
 
-/// Unit conversion for CONVERT function
-fn convert_units(value: f64, from: &str, to: &str) -> Option<f64> {
-    let from_factor = unit_to_base(from)?;
-    let to_factor = unit_to_base(to)?;
-    if from_factor.1 != to_factor.1 { return None; }
-    // Special handling for temperature
-    if from_factor.1 == 8 {
-        let celsius = match from_factor.0 as i32 {
-            1 => value,            // C
-            2 => (value - 32.0) * 5.0 / 9.0,  // F
-            3 => value - 273.15,   // K
-            _ => return None,
-        };
-        return Some(match to_factor.0 as i32 {
-            1 => celsius,
-            2 => celsius * 9.0 / 5.0 + 32.0,
-            3 => celsius + 273.15,
-            _ => return None,
-        });
-    }
-    Some(value * from_factor.0 / to_factor.0)
-}
-
-/// Returns (multiplier_to_base, category_id)
-fn unit_to_base(unit: &str) -> Option<(f64, i32)> {
-    match unit {
-        // Weight (base: kg, category 1)
-        "g" => Some((0.001, 1)), "kg" => Some((1.0, 1)), "mg" => Some((1e-6, 1)),
-        "lbm" | "lb" => Some((0.45359237, 1)), "ozm" | "oz" => Some((0.028349523125, 1)),
-        "stone" => Some((6.35029318, 1)), "ton" => Some((907.18474, 1)),
-        "sg" | "slug" => Some((14.593903, 1)), "grain" => Some((6.479891e-5, 1)),
-        "u" | "Da" => Some((1.66053906660e-27, 1)),
-        // Distance (base: m, category 2)
-        "m" => Some((1.0, 2)), "km" => Some((1000.0, 2)), "cm" => Some((0.01, 2)),
-        "mm" => Some((0.001, 2)), "um" => Some((1e-6, 2)), "nm" => Some((1e-9, 2)),
-        "mi" => Some((1609.344, 2)), "yd" => Some((0.9144, 2)), "ft" => Some((0.3048, 2)),
-        "in" => Some((0.0254, 2)), "Nmi" => Some((1852.0, 2)),
-        "ang" | "Ang" => Some((1e-10, 2)), "ly" => Some((9.4607304725808e15, 2)),
-        "pc" | "parsec" => Some((3.08567758149137e16, 2)),
-        // Time (base: s, category 3)
-        "s" | "sec" => Some((1.0, 3)), "min" | "mn" => Some((60.0, 3)),
-        "hr" => Some((3600.0, 3)), "day" => Some((86400.0, 3)),
-        "yr" => Some((31557600.0, 3)),
-        // Pressure (base: Pa, category 4)
-        "Pa" | "pa" => Some((1.0, 4)), "atm" | "at" => Some((101325.0, 4)),
-        "mmHg" => Some((133.322, 4)), "psi" => Some((6894.757, 4)),
-        "Torr" => Some((133.3224, 4)),
-        // Force (base: N, category 5)
-        "N" => Some((1.0, 5)), "dyn" | "dy" => Some((1e-5, 5)),
-        "lbf" => Some((4.44822, 5)),
-        // Energy (base: J, category 6)
-        "J" => Some((1.0, 6)), "kJ" => Some((1000.0, 6)),
-        "cal" => Some((4.1868, 6)), "kcal" => Some((4186.8, 6)),
-        "eV" | "ev" => Some((1.602176634e-19, 6)), "BTU" | "btu" => Some((1055.06, 6)),
-        "Wh" | "wh" => Some((3600.0, 6)), "kWh" | "kwh" => Some((3600000.0, 6)),
-        "HPh" | "hph" => Some((2684519.5, 6)),
-        // Power (base: W, category 7)
-        "W" | "w" => Some((1.0, 7)), "kW" | "kw" => Some((1000.0, 7)),
-        "HP" | "hp" => Some((745.69987, 7)),
-        // Temperature (special handling, category 8)
-        "C" | "cel" => Some((1.0, 8)), "F" | "fah" => Some((2.0, 8)), "K" | "kel" => Some((3.0, 8)),
-        // Volume (base: L, category 9)
-        "l" | "L" | "lt" => Some((1.0, 9)), "ml" => Some((0.001, 9)),
-        "gal" => Some((3.785411784, 9)), "qt" => Some((0.946352946, 9)),
-        "pt" | "us_pt" => Some((0.473176473, 9)), "cup" => Some((0.236588236, 9)),
-        "tsp" => Some((0.00492892159, 9)), "tbs" => Some((0.0147867648, 9)),
-        "oz_fl" | "fl_oz" => Some((0.0295735296, 9)),
-        "m3" | "m^3" => Some((1000.0, 9)), "ft3" | "ft^3" => Some((28.3168466, 9)),
-        "in3" | "in^3" => Some((0.016387064, 9)),
-        // Speed (base: m/s, category 10)
-        "m/s" => Some((1.0, 10)), "m/h" => Some((1.0 / 3600.0, 10)),
-        "mph" => Some((0.44704, 10)), "kn" | "kph" => Some((0.277778, 10)),
-        // Area (base: m^2, category 11)
-        "m2" | "m^2" => Some((1.0, 11)), "km2" | "km^2" => Some((1e6, 11)),
-        "ft2" | "ft^2" => Some((0.09290304, 11)), "in2" | "in^2" => Some((0.00064516, 11)),
-        "yd2" | "yd^2" => Some((0.83612736, 11)), "mi2" | "mi^2" => Some((2589988.11, 11)),
-        "ha" => Some((10000.0, 11)), "ac" | "acre" => Some((4046.8564224, 11)),
-        "ar" => Some((100.0, 11)), "Morgen" => Some((2500.0, 11)),
-        _ => None,
-    }
-}
-
 /// Matrix determinant using LU decomposition
 fn matrix_determinant(m: &[Vec<f64>]) -> f64 {
     let n = m.len();
@@ -11891,6 +13437,101 @@ fn matrix_inverse(m: &[Vec<f64>]) -> Option<Vec<Vec<f64>>> {
     Some(aug.iter().map(|row| row[n..].to_vec()).collect())
 }
 
+/// Lowercases one character, keeping it when its lowercase form is not a
+/// single character, so case-insensitive positions stay character-aligned.
+fn fold_case(c: char) -> char {
+    let mut lower = c.to_lowercase();
+    match (lower.next(), lower.next()) {
+        (Some(l), None) => l,
+        _ => c,
+    }
+}
+
+/// Splits `text` on every occurrence of `delim` (TEXTSPLIT), optionally
+/// ignoring case. An empty delimiter leaves the text whole.
+fn split_text(text: &str, delim: &str, ignore_case: bool) -> Vec<String> {
+    if delim.is_empty() {
+        return vec![text.to_string()];
+    }
+    let fold = |s: &str| -> Vec<char> {
+        if ignore_case { s.chars().map(fold_case).collect() } else { s.chars().collect() }
+    };
+    let chars: Vec<char> = text.chars().collect();
+    let haystack = fold(text);
+    let needle = fold(delim);
+    let mut parts = Vec::new();
+    let mut start = 0;
+    while let Some(i) = find_chars(&haystack, &needle, start) {
+        parts.push(chars[start..i].iter().collect());
+        start = i + needle.len();
+    }
+    parts.push(chars[start..].iter().collect());
+    parts
+}
+
+/// First character index at or after `from` where `needle` occurs.
+fn find_chars(haystack: &[char], needle: &[char], from: usize) -> Option<usize> {
+    if needle.len() > haystack.len() {
+        return None;
+    }
+    (from..=haystack.len() - needle.len()).find(|&i| haystack[i..i + needle.len()] == *needle)
+}
+
+/// One element of a SEARCH pattern.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum WildcardToken {
+    /// `*`: any run of characters, possibly empty.
+    AnyRun,
+    /// `?`: exactly one character.
+    AnyOne,
+    /// A character matched as-is (`~*`, `~?` and `~~` escape the wildcards).
+    Literal(char),
+}
+
+fn wildcard_tokens(pattern: &[char]) -> Vec<WildcardToken> {
+    let mut tokens = Vec::with_capacity(pattern.len());
+    let mut chars = pattern.iter().copied();
+    while let Some(c) = chars.next() {
+        tokens.push(match c {
+            '*' => WildcardToken::AnyRun,
+            '?' => WildcardToken::AnyOne,
+            '~' => WildcardToken::Literal(chars.next().unwrap_or('~')),
+            c => WildcardToken::Literal(c),
+        });
+    }
+    tokens
+}
+
+/// Whether `pattern` matches some prefix of `text`. Tracks the set of text
+/// positions reachable after each token, so it is linear in each.
+fn wildcard_prefix_match(pattern: &[WildcardToken], text: &[char]) -> bool {
+    let mut reach = vec![false; text.len() + 1];
+    reach[0] = true;
+    for token in pattern {
+        let mut next = vec![false; text.len() + 1];
+        match token {
+            WildcardToken::AnyRun => {
+                let mut reached = false;
+                for (slot, &r) in next.iter_mut().zip(&reach) {
+                    reached |= r;
+                    *slot = reached;
+                }
+            }
+            WildcardToken::AnyOne => next[1..].copy_from_slice(&reach[..text.len()]),
+            WildcardToken::Literal(c) => {
+                for k in 0..text.len() {
+                    next[k + 1] = reach[k] && text[k] == *c;
+                }
+            }
+        }
+        if !next.contains(&true) {
+            return false;
+        }
+        reach = next;
+    }
+    true
+}
+
 /// Simple wildcard matching (* and ?)
 fn wildcard_match(pattern: &str, text: &str) -> bool {
     let pattern = pattern.to_uppercase();
@@ -11933,8 +13574,8 @@ impl<'a> Evaluator<'a> {
 
     /// Cache identity of a grid for the duration of one pass.
     #[inline]
-    fn grid_addr(grid: &Grid) -> usize {
-        grid as *const Grid as usize
+    fn grid_addr(grid: GridView<'_>) -> usize {
+        grid.id()
     }
 
     /// Describes a cache-eligible key vector: a literal same-sheet rect
@@ -12003,7 +13644,7 @@ impl<'a> Evaluator<'a> {
     /// eval_column_ref's populated-only ascending order for whole columns).
     fn cache_vector(
         &self,
-        grid: &Grid,
+        grid: GridView<'_>,
         rect: &lookup_cache::Rect,
         axis: lookup_cache::Axis,
     ) -> Vec<EvalResult> {
@@ -12030,7 +13671,7 @@ impl<'a> Evaluator<'a> {
                 out
             }
             lookup_cache::Axis::WholeCol(col) => {
-                if (grid.max_row as usize) <= grid.cells.len() {
+                if (grid.max_row as usize) <= grid.cell_count() {
                     let mut out = Vec::new();
                     for row in 0..=grid.max_row {
                         if let Some(cell) = grid.get_cell(row, col) {
@@ -12040,8 +13681,7 @@ impl<'a> Evaluator<'a> {
                     out
                 } else {
                     let mut rows: Vec<(u32, &crate::cell::Cell)> = grid
-                        .cells
-                        .iter()
+                        .cells()
                         .filter_map(|((r, c), cell)| if *c == col { Some((*r, cell)) } else { None })
                         .collect();
                     rows.sort_by_key(|(r, _)| *r);
@@ -12276,7 +13916,8 @@ impl<'a> Evaluator<'a> {
         Some((k_rect, k_axis, r_rect, r_axis, ret_len))
     }
 
-    /// Serve COUNTIF over a literal vector from the pass cache.
+    /// Serve COUNTIF over a literal vector from the pass cache, or — outside
+    /// a pass — from an index kept in this evaluator's batch cache.
     fn criteria_count_cached(
         &self,
         rect: lookup_cache::Rect,
@@ -12284,40 +13925,59 @@ impl<'a> Evaluator<'a> {
         criteria: &CriteriaMatch,
     ) -> Option<u32> {
         use crate::lookup_cache as lc;
-        if matches!(criteria, CriteriaMatch::Wildcard(_)) {
+        if matches!(criteria, CriteriaMatch::Wildcard(_) | CriteriaMatch::WildcardNotEqual(_)) {
             return None;
         }
         let grid = self.get_grid_for_sheet(&None);
         let watch = Self::axis_watch_rect(&rect, axis);
-        let served = lc::with_active(|cache| {
-            let key = lc::EntryKey {
-                grid: Self::grid_addr(grid),
-                rect,
-                kind: lc::EntryKind::Criteria { axis, value: None },
-            };
-            cache
-                .criteria(key, [Some(watch), None], || {
-                    lc::CriteriaIndex::build(&self.cache_vector(grid, &rect, axis), None)
-                })
-                .map(|ci| match criteria {
-                    CriteriaMatch::ExactNumber(n) => ci.count_exact_number(*n),
-                    CriteriaMatch::ExactBool(b) => ci.count_exact_bool(*b),
-                    CriteriaMatch::ExactText(s) => ci.count_exact_text(s),
-                    CriteriaMatch::TextNotEqual(s) => ci.count_text_not_equal(s),
-                    CriteriaMatch::Compare(op, n) => match op {
-                        CriteriaOp::Greater => ci.count_greater(*n),
-                        CriteriaOp::GreaterEqual => ci.count_greater_equal(*n),
-                        CriteriaOp::Less => ci.count_less(*n),
-                        CriteriaOp::LessEqual => ci.count_less_equal(*n),
-                        CriteriaOp::NotEqual => ci.count_not_equal(*n),
-                    },
-                    CriteriaMatch::Wildcard(_) => unreachable!(),
-                })
-        });
-        match served {
-            Some(Some(count)) => Some(count),
-            _ => None,
+        let key = lc::EntryKey {
+            grid: Self::grid_addr(grid),
+            rect,
+            kind: lc::EntryKind::Criteria { axis, value: None },
+        };
+        let build = || lc::CriteriaIndex::build(&self.cache_vector(grid, &rect, axis), None);
+        let count = |ci: &lc::CriteriaIndex| match criteria {
+            CriteriaMatch::ExactNumber(n) => ci.count_exact_number(*n),
+            CriteriaMatch::ExactBool(b) => ci.count_exact_bool(*b),
+            CriteriaMatch::ExactText(s) => ci.count_exact_text(s),
+            CriteriaMatch::TextNotEqual(s) => ci.count_text_not_equal(s),
+            CriteriaMatch::Compare(op, n) => match op {
+                CriteriaOp::Greater => ci.count_greater(*n),
+                CriteriaOp::GreaterEqual => ci.count_greater_equal(*n),
+                CriteriaOp::Less => ci.count_less(*n),
+                CriteriaOp::LessEqual => ci.count_less_equal(*n),
+                CriteriaOp::NotEqual => ci.count_not_equal(*n),
+            },
+            CriteriaMatch::Wildcard(_) | CriteriaMatch::WildcardNotEqual(_) => unreachable!(),
+        };
+        match lc::with_active(|cache| cache.criteria(key, [Some(watch), None], build).map(count)) {
+            Some(served) => served,
+            None => self.batch_criteria_index(key, build).map(|ci| count(&ci)),
+        }
+    }
+
+    /// Fetch or build a criteria index in this evaluator's batch cache. None
+    /// when the cache is full and the key is absent (callers scan).
+    fn batch_criteria_index(
+        &self,
+        key: lookup_cache::EntryKey,
+        build: impl FnOnce() -> lookup_cache::CriteriaIndex,
+    ) -> Option<std::sync::Arc<lookup_cache::CriteriaIndex>> {
+        {
+            let mut cache = self.batch_cache.borrow_mut();
+            if let Some(index) = cache.indexes.get(&key) {
+                let index = index.clone();
+                cache.stats.index_hits += 1;
+                return Some(index);
+            }
+            if cache.indexes.len() >= BATCH_CACHE_MAX_RANGES {
+                return None;
+            }
+            cache.stats.index_misses += 1;
         }
+        let index = std::sync::Arc::new(build());
+        self.batch_cache.borrow_mut().indexes.insert(key, index.clone());
+        Some(index)
     }
 
     /// Serve text/bool exact-match SUMIF from the pass cache. Bucket sums are
@@ -12382,9 +14042,8 @@ impl<'a> Evaluator<'a> {
         }
 
         // FAST PATH eligibility (PERF-03): literal same-sheet TRUE-2D rect.
-        // 1-D rects keep the legacy path on purpose: eval_range materializes
-        // them FLAT and table_row_views then treats the vector as a single
-        // ROW — a load-bearing quirk this fast path must not change.
+        // 1-D rects keep the legacy path: eval_range materializes them FLAT
+        // and lookup_table_rows re-derives their shape from the reference.
         let fast_rect = self
             .literal_range_rect(&args[1])
             .filter(|&(_, _, _, _, rows, cols)| rows > 1 && cols > 1);
@@ -12428,7 +14087,7 @@ impl<'a> Evaluator<'a> {
         };
 
         // View table as 2D rows (borrowed — no per-call table clone)
-        let rows = self.table_row_views(&table);
+        let rows = self.lookup_table_rows(&args[1], &table);
         if rows.is_empty() {
             return EvalResult::Error(CellError::NA);
         }
@@ -12516,7 +14175,7 @@ impl<'a> Evaluator<'a> {
             None => self.evaluate(&args[1]),
         };
 
-        let rows = self.table_row_views(&table);
+        let rows = self.lookup_table_rows(&args[1], &table);
         if rows.is_empty() || row_index > rows.len() {
             return EvalResult::Error(CellError::Ref);
         }
@@ -12548,7 +14207,7 @@ impl<'a> Evaluator<'a> {
 
     fn fn_lookup(&self, args: &[Expression]) -> EvalResult {
         // LOOKUP(lookup_value, lookup_vector, [result_vector])
-        if args.is_empty() || args.len() > 3 {
+        if args.len() < 2 || args.len() > 3 {
             return EvalResult::Error(CellError::Value);
         }
 
@@ -12583,6 +14242,23 @@ impl<'a> Evaluator<'a> {
         }
     }
 
+    /// `table_row_views` for a VLOOKUP/HLOOKUP table argument. eval_range
+    /// flattens a one-column range into a single vector, which would read as
+    /// one ROW; the reference itself says it is a column, so split it into
+    /// one-cell rows.
+    fn lookup_table_rows<'t>(&self, table_expr: &Expression, table: &'t EvalResult) -> Vec<&'t [EvalResult]> {
+        let rows = self.table_row_views(table);
+        let single_column = matches!(
+            self.reference_extent(table_expr),
+            Some((_, min_row, min_col, max_row, max_col)) if min_col == max_col && max_row > min_row
+        );
+        if single_column && rows.len() == 1 {
+            rows[0].iter().map(std::slice::from_ref).collect()
+        } else {
+            rows
+        }
+    }
+
     /// Borrowing counterpart of `extract_2d_rows`: views the table as rows
     /// WITHOUT cloning any element (same shape rules, including the flat-1D
     /// "single row" interpretation). Callers clone only the matched cell.
@@ -12679,11 +14355,12 @@ impl<'a> Evaluator<'a> {
         if multiple == 0.0 {
             return EvalResult::Number(0.0);
         }
-        // Number and multiple must have the same sign
+        // Number and multiple must have the same sign, else #NUM!
         if number > 0.0 && multiple < 0.0 || number < 0.0 && multiple > 0.0 {
-            return EvalResult::Error(CellError::Value);
+            return EvalResult::Error(CellError::Num);
         }
-        EvalResult::Number((number / multiple).round() * multiple)
+        // Halves round away from zero: MROUND(-5, -2) = -6
+        EvalResult::Number(snap_15_digits(snap_15_digits(number / multiple).round() * multiple))
     }
 
     fn fn_quotient(&self, args: &[Expression]) -> EvalResult {
@@ -12699,7 +14376,7 @@ impl<'a> Evaluator<'a> {
         if denominator == 0.0 {
             return EvalResult::Error(CellError::Div0);
         }
-        EvalResult::Number((numerator / denominator).trunc())
+        EvalResult::Number(snap_15_digits(numerator / denominator).trunc())
     }
 
     fn fn_sumsq(&self, args: &[Expression]) -> EvalResult {
@@ -12712,55 +14389,84 @@ impl<'a> Evaluator<'a> {
         }
     }
 
+    /// ROMAN(number, [form]). `form` 0 (or TRUE) is classic; 1-4 (FALSE = 4)
+    /// allow progressively wider subtractive pairs: ROMAN(499) = "CDXCIX",
+    /// ROMAN(499, 4) = "ID".
     fn fn_roman(&self, args: &[Expression]) -> EvalResult {
         if args.is_empty() || args.len() > 2 { return EvalResult::Error(CellError::Value); }
         let number = match self.evaluate(&args[0]).as_number() {
-            Some(n) => n as i64,
+            Some(n) => n.trunc() as i64,
             None => return EvalResult::Error(CellError::Value),
         };
-        if number < 0 || number > 3999 {
+        if !(0..=3999).contains(&number) {
             return EvalResult::Error(CellError::Value);
         }
-        if number == 0 {
-            return EvalResult::Text(String::new());
-        }
-
-        let values = [1000, 900, 500, 400, 100, 90, 50, 40, 10, 9, 5, 4, 1];
-        let symbols = ["M", "CM", "D", "CD", "C", "XC", "L", "XL", "X", "IX", "V", "IV", "I"];
+        let form = match args.get(1).map(|a| self.evaluate(a)) {
+            None => 0,
+            Some(EvalResult::Boolean(classic)) => if classic { 0 } else { 4 },
+            Some(v) => match v.as_number().map(f64::trunc) {
+                Some(f) if (0.0..=4.0).contains(&f) => f as usize,
+                _ => return EvalResult::Error(CellError::Value),
+            },
+        };
 
+        let symbol = |i: usize| ROMAN_DIGITS[i].0;
+        let value = |i: usize| ROMAN_DIGITS[i].1;
         let mut result = String::new();
         let mut n = number;
-        for (i, &val) in values.iter().enumerate() {
-            while n >= val as i64 {
-                result.push_str(symbols[i]);
-                n -= val as i64;
+        // One pass per power of ten (M, C, X, I).
+        for index in (0..ROMAN_DIGITS.len()).step_by(2) {
+            let digit = n / value(index);
+            if digit % 5 == 4 {
+                // 4 or 9: a subtractive pair. Higher forms widen it while the
+                // wider pair still fits, e.g. 499 -> XD -> VD -> ID.
+                let upper = if digit == 4 { index - 1 } else { index - 2 };
+                let mut lower = index;
+                for _ in 0..form {
+                    if lower + 1 < ROMAN_DIGITS.len() && value(upper) - value(lower + 1) <= n {
+                        lower += 1;
+                    } else {
+                        break;
+                    }
+                }
+                result.push(symbol(lower));
+                result.push(symbol(upper));
+                n = n + value(lower) - value(upper);
+            } else {
+                if digit > 4 {
+                    result.push(symbol(index - 1));
+                }
+                for _ in 0..digit % 5 {
+                    result.push(symbol(index));
+                }
+                n %= value(index);
             }
         }
         EvalResult::Text(result)
     }
 
+    /// ARABIC(text). Case-insensitive, an optional leading minus; like Excel it
+    /// also reads non-canonical numerals ("IIII", "MIM").
     fn fn_arabic(&self, args: &[Expression]) -> EvalResult {
         if args.len() != 1 { return EvalResult::Error(CellError::Value); }
         let text = self.evaluate(&args[0]).as_text().trim().to_uppercase();
         if text.is_empty() {
             return EvalResult::Number(0.0);
         }
+        if text.chars().count() > 255 {
+            return EvalResult::Error(CellError::Value);
+        }
 
         let mut result: i64 = 0;
         let mut prev_value: i64 = 0;
-        let negative = text.starts_with('-');
-        let chars: Vec<char> = if negative { text[1..].chars().collect() } else { text.chars().collect() };
-
-        for ch in chars.iter().rev() {
-            let value = match ch {
-                'I' => 1,
-                'V' => 5,
-                'X' => 10,
-                'L' => 50,
-                'C' => 100,
-                'D' => 500,
-                'M' => 1000,
-                _ => return EvalResult::Error(CellError::Value),
+        let (negative, digits) = match text.strip_prefix('-') {
+            Some(rest) => (true, rest),
+            None => (false, text.as_str()),
+        };
+
+        for ch in digits.chars().rev() {
+            let Some(value) = roman_value(ch) else {
+                return EvalResult::Error(CellError::Value);
             };
             if value < prev_value {
                 result -= value;
@@ -12777,60 +14483,48 @@ impl<'a> Evaluator<'a> {
     fn fn_base(&self, args: &[Expression]) -> EvalResult {
         // BASE(number, radix, [min_length])
         if args.len() < 2 || args.len() > 3 { return EvalResult::Error(CellError::Value); }
+        // Non-numeric arguments are #VALUE!, out-of-range ones #NUM!.
         let number = match self.evaluate(&args[0]).as_number() {
-            Some(n) if n >= 0.0 => n as u64,
-            _ => return EvalResult::Error(CellError::Value),
+            Some(n) if (0.0..9007199254740992.0).contains(&n) => n.trunc() as u64,
+            Some(_) => return EvalResult::Error(CellError::Num),
+            None => return EvalResult::Error(CellError::Value),
         };
-        let radix = match self.evaluate(&args[1]).as_number() {
-            Some(r) if r >= 2.0 && r <= 36.0 => r as u32,
-            _ => return EvalResult::Error(CellError::Value),
+        let radix = match self.evaluate(&args[1]).as_number().map(f64::trunc) {
+            Some(r) if (2.0..=36.0).contains(&r) => r as u32,
+            Some(_) => return EvalResult::Error(CellError::Num),
+            None => return EvalResult::Error(CellError::Value),
         };
         let min_length = if args.len() == 3 {
-            match self.evaluate(&args[2]).as_number() {
-                Some(n) if n >= 0.0 => n as usize,
-                _ => return EvalResult::Error(CellError::Value),
+            match self.evaluate(&args[2]).as_number().map(f64::trunc) {
+                Some(n) if (0.0..=255.0).contains(&n) => n as usize,
+                Some(_) => return EvalResult::Error(CellError::Num),
+                None => return EvalResult::Error(CellError::Value),
             }
         } else {
             0
         };
 
-        let mut result = String::new();
-        let mut n = number;
-        if n == 0 {
-            result.push('0');
-        } else {
-            while n > 0 {
-                let digit = (n % radix as u64) as u32;
-                let ch = if digit < 10 {
-                    (b'0' + digit as u8) as char
-                } else {
-                    (b'A' + (digit - 10) as u8) as char
-                };
-                result.push(ch);
-                n /= radix as u64;
-            }
-            result = result.chars().rev().collect();
-        }
-
-        while result.len() < min_length {
-            result.insert(0, '0');
-        }
-
-        EvalResult::Text(result)
+        EvalResult::Text(format!("{:0>width$}", to_radix(number, radix), width = min_length))
     }
 
     fn fn_decimal(&self, args: &[Expression]) -> EvalResult {
         // DECIMAL(text, radix)
         if args.len() != 2 { return EvalResult::Error(CellError::Value); }
-        let text = self.evaluate(&args[0]).as_text().trim().to_uppercase();
-        let radix = match self.evaluate(&args[1]).as_number() {
-            Some(r) if r >= 2.0 && r <= 36.0 => r as u32,
-            _ => return EvalResult::Error(CellError::Value),
+        let text = self.evaluate(&args[0]).as_text().trim().to_string();
+        let radix = match self.evaluate(&args[1]).as_number().map(f64::trunc) {
+            Some(r) if (2.0..=36.0).contains(&r) => r as u32,
+            Some(_) => return EvalResult::Error(CellError::Num),
+            None => return EvalResult::Error(CellError::Value),
         };
+        if text.chars().count() > 255 {
+            return EvalResult::Error(CellError::Value);
+        }
 
-        match u64::from_str_radix(&text, radix) {
-            Ok(n) => EvalResult::Number(n as f64),
-            Err(_) => EvalResult::Error(CellError::Value),
+        // Digits only (no sign). A digit outside the radix or a value past
+        // 2^53 is #NUM!.
+        match from_radix(&text, radix) {
+            Some(value) => EvalResult::Number(value),
+            None => EvalResult::Error(CellError::Num),
         }
     }
 
@@ -12844,138 +14538,90 @@ impl<'a> Evaluator<'a> {
         self.fn_currency(args, "\u{20AC}")
     }
 
+    /// DOLLAR/EURO(number, [decimals]) as currency text, negatives in
+    /// parentheses. Negative decimals round left of the decimal point.
     fn fn_currency(&self, args: &[Expression], symbol: &str) -> EvalResult {
-        // DOLLAR/EURO(number, [decimals])
         if args.is_empty() || args.len() > 2 { return EvalResult::Error(CellError::Value); }
-        let number = match self.evaluate(&args[0]).as_number() {
-            Some(n) => n,
-            None => return EvalResult::Error(CellError::Value),
-        };
-        let decimals = if args.len() == 2 {
-            match self.evaluate(&args[1]).as_number() {
-                Some(d) => d as i32,
-                None => return EvalResult::Error(CellError::Value),
-            }
-        } else {
-            2
-        };
-
-        let multiplier = 10_f64.powi(decimals);
-        let rounded = (number * multiplier).round() / multiplier;
-        let abs_val = rounded.abs();
-
-        let formatted = if decimals > 0 {
-            format!("{:.prec$}", abs_val, prec = decimals as usize)
-        } else {
-            format!("{}", abs_val as i64)
+        let (number, decimals) = match self.rounded_text_args(args) {
+            Ok(v) => v,
+            Err(e) => return EvalResult::Error(e),
         };
-
-        // Add thousands separators
-        let parts: Vec<&str> = formatted.splitn(2, '.').collect();
-        let integer_part = parts[0];
-        let mut with_commas = String::new();
-        for (i, ch) in integer_part.chars().rev().enumerate() {
-            if i > 0 && i % 3 == 0 {
-                with_commas.push(',');
-            }
-            with_commas.push(ch);
-        }
-        let with_commas: String = with_commas.chars().rev().collect();
-
-        let result = if parts.len() > 1 {
-            format!("{}{}.{}", symbol, with_commas, parts[1])
-        } else {
-            format!("{}{}", symbol, with_commas)
+        let format = NumberFormat::Currency {
+            decimal_places: decimals.max(0) as u8,
+            symbol: symbol.to_string(),
+            symbol_position: CurrencyPosition::Before,
         };
-
-        if rounded < 0.0 {
-            EvalResult::Text(format!("({})", result))
-        } else {
-            EvalResult::Text(result)
-        }
+        EvalResult::Text(number_format::format_number(number, &format, &LocaleSettings::invariant()))
     }
 
+    /// FIXED(number, [decimals], [no_commas]) as text with a plain minus sign.
     fn fn_fixed(&self, args: &[Expression]) -> EvalResult {
-        // FIXED(number, [decimals], [no_commas])
         if args.is_empty() || args.len() > 3 { return EvalResult::Error(CellError::Value); }
-        let number = match self.evaluate(&args[0]).as_number() {
-            Some(n) => n,
-            None => return EvalResult::Error(CellError::Value),
+        let (number, decimals) = match self.rounded_text_args(args) {
+            Ok(v) => v,
+            Err(e) => return EvalResult::Error(e),
         };
-        let decimals = if args.len() >= 2 {
-            match self.evaluate(&args[1]).as_number() {
-                Some(d) => d as i32,
+        let no_commas = match args.get(2).map(|arg| self.evaluate(arg)) {
+            None => false,
+            Some(EvalResult::Error(e)) => return EvalResult::Error(e),
+            Some(v) => match v.as_boolean() {
+                Some(b) => b,
                 None => return EvalResult::Error(CellError::Value),
-            }
-        } else {
-            2
+            },
         };
-        let no_commas = if args.len() == 3 {
-            match self.evaluate(&args[2]) {
-                EvalResult::Boolean(b) => b,
-                EvalResult::Number(n) => n != 0.0,
-                _ => false,
-            }
-        } else {
-            false
+        let format = NumberFormat::Number {
+            decimal_places: decimals.max(0) as u8,
+            use_thousands_separator: !no_commas,
         };
+        EvalResult::Text(number_format::format_number(number, &format, &LocaleSettings::invariant()))
+    }
 
-        let multiplier = 10_f64.powi(decimals);
-        let rounded = (number * multiplier).round() / multiplier;
-
-        let formatted = if decimals > 0 {
-            format!("{:.prec$}", rounded.abs(), prec = decimals as usize)
-        } else {
-            format!("{}", rounded.abs() as i64)
+    /// The number and decimals (default 2, at most 127) of DOLLAR, EURO and
+    /// FIXED, with the number rounded half away from zero to those decimals.
+    fn rounded_text_args(&self, args: &[Expression]) -> Result<(f64, i32), CellError> {
+        let number = match self.evaluate(&args[0]) {
+            EvalResult::Error(e) => return Err(e),
+            v => v.as_number().ok_or(CellError::Value)?,
         };
-
-        let result = if no_commas {
-            formatted
-        } else {
-            let parts: Vec<&str> = formatted.splitn(2, '.').collect();
-            let integer_part = parts[0];
-            let mut with_commas = String::new();
-            for (i, ch) in integer_part.chars().rev().enumerate() {
-                if i > 0 && i % 3 == 0 {
-                    with_commas.push(',');
-                }
-                with_commas.push(ch);
-            }
-            let with_commas: String = with_commas.chars().rev().collect();
-            if parts.len() > 1 {
-                format!("{}.{}", with_commas, parts[1])
-            } else {
-                with_commas
-            }
+        let decimals = match args.get(1).map(|arg| self.evaluate(arg)) {
+            None => 2,
+            Some(EvalResult::Error(e)) => return Err(e),
+            Some(v) => match v.as_number() {
+                Some(d) if d <= 127.0 => d.trunc().max(-308.0) as i32,
+                _ => return Err(CellError::Value),
+            },
         };
-
-        if rounded < 0.0 {
-            EvalResult::Text(format!("-{}", result))
+        let rounded = if decimals > 15 {
+            number
         } else {
-            EvalResult::Text(result)
-        }
+            let factor = 10_f64.powi(decimals);
+            (number * factor).round() / factor
+        };
+        // -0.001 rounds to "0.00", not "-0.00"
+        Ok((if rounded == 0.0 { 0.0 } else { rounded }, decimals))
     }
 
+    /// The character for a Unicode scalar value. Out of range is #VALUE!;
+    /// a lone surrogate (not a character by itself) is #N/A, as in Excel.
     fn fn_unichar(&self, args: &[Expression]) -> EvalResult {
         if args.len() != 1 { return EvalResult::Error(CellError::Value); }
-        match self.evaluate(&args[0]).as_number() {
-            Some(n) => {
-                let code = n as u32;
-                match char::from_u32(code) {
-                    Some(c) => EvalResult::Text(c.to_string()),
-                    None => EvalResult::Error(CellError::Value),
-                }
-            }
-            None => EvalResult::Error(CellError::Value),
+        match self.whole_arg(&args[0], 1.0) {
+            Ok(code) if code <= 0x10FFFF => match char::from_u32(code as u32) {
+                Some(c) => EvalResult::Text(c.to_string()),
+                None => self.fail(CellError::NA, format!("{} is a surrogate code point, not a character", code)),
+            },
+            Ok(_) => self.fail(CellError::Value, "UNICHAR needs a number from 1 to 1114111"),
+            Err(e) => EvalResult::Error(e),
         }
     }
 
+    /// The Unicode scalar value of the first character.
     fn fn_unicode(&self, args: &[Expression]) -> EvalResult {
         if args.len() != 1 { return EvalResult::Error(CellError::Value); }
-        let text = self.evaluate(&args[0]).as_text();
+        let text = match self.text_arg(&args[0]) { Ok(t) => t, Err(e) => return EvalResult::Error(e) };
         match text.chars().next() {
             Some(c) => EvalResult::Number(c as u32 as f64),
-            None => EvalResult::Error(CellError::Value),
+            None => self.fail(CellError::Value, "UNICODE needs at least one character"),
         }
     }
 
@@ -13031,8 +14677,11 @@ impl<'a> Evaluator<'a> {
                     CellError::Value => 3,
                     CellError::Ref => 4,
                     CellError::Name => 5,
+                    CellError::Num => 6,
                     CellError::NA => 7,
-                    _ => 3, // Default to #VALUE! type for other errors
+                    CellError::Spill => 9,
+                    CellError::Blocked => 11,
+                    _ => 3, // Default to #VALUE! type for errors Excel lacks
                 };
                 EvalResult::Number(type_num as f64)
             }
@@ -13048,12 +14697,9 @@ impl<'a> Evaluator<'a> {
 
     fn fn_isref(&self, args: &[Expression]) -> EvalResult {
         if args.len() != 1 { return EvalResult::Error(CellError::Value); }
-        // Check if the argument is a cell reference or range reference
-        let is_ref = matches!(&args[0],
-            Expression::CellRef { .. } | Expression::Range { .. } |
-            Expression::ColumnRef { .. } | Expression::RowRef { .. }
-        );
-        EvalResult::Boolean(is_ref)
+        // Structural: a reference (including 3D and @-intersected ones), never
+        // the value it evaluates to.
+        EvalResult::Boolean(Self::is_reference_expr(&args[0]))
     }
 
     fn fn_sheet(&self, args: &[Expression]) -> EvalResult {
@@ -15498,7 +17144,103 @@ mod tests {
         assert_eq!(eval.evaluate(&expr), EvalResult::Number(300.0));
     }
 
-    // ==================== Helper for new function tests ====================
+    /// Helper: A1:A4 = 10, 20, SUBTOTAL(9,A1:A2) cached as 30, 40
+    fn make_grid_with_subtotal_row() -> Grid {
+        let mut grid = Grid::new();
+        grid.set_cell(0, 0, Cell::new_number(10.0));
+        grid.set_cell(1, 0, Cell::new_number(20.0));
+        let mut subtotal = Cell::new_formula("SUBTOTAL(9,A1:A2)".to_string());
+        subtotal.value = CellValue::Number(30.0);
+        grid.set_cell(2, 0, subtotal);
+        grid.set_cell(3, 0, Cell::new_number(40.0));
+        grid
+    }
+
+    fn range_a1_a4() -> Expression {
+        Expression::Range {
+            sheet: None,
+            start: Box::new(Expression::CellRef { sheet: None, col: "A".to_string(), row: 1, col_absolute: false, row_absolute: false, ref_site_id: Default::default() }),
+            end: Box::new(Expression::CellRef { sheet: None, col: "A".to_string(), row: 4, col_absolute: false, row_absolute: false, ref_site_id: Default::default() }),
+            ref_site_id: Default::default(),
+        }
+    }
+
+    fn aggregate_expr(func_num: f64, options: f64, range: Expression) -> Expression {
+        Expression::FunctionCall {
+            func: BuiltinFunction::Aggregate,
+            args: vec![
+                Expression::Literal(Value::Number(func_num)),
+                Expression::Literal(Value::Number(options)),
+                range,
+            ],
+            ref_site_id: Default::default(),
+        }
+    }
+
+    fn eval_with_hidden(grid: &Grid, hidden: &[u32], expr: &Expression) -> EvalResult {
+        let ctx = EvalContext {
+            hidden_rows: Some(hidden.iter().copied().collect()),
+            ..Default::default()
+        };
+        let ms = MultiSheetContext::new("Sheet1".to_string());
+        Evaluator::with_context(grid, ms, ctx).evaluate(expr)
+    }
+
+    #[test]
+    fn test_subtotal_skips_nested_subtotals() {
+        // The cached 30 in A3 is itself a SUBTOTAL and must not be counted again.
+        let grid = make_grid_with_subtotal_row();
+        assert_eq!(eval_with_hidden(&grid, &[], &subtotal_expr(9.0, range_a1_a4())), EvalResult::Number(70.0));
+        assert_eq!(eval_with_hidden(&grid, &[], &subtotal_expr(2.0, range_a1_a4())), EvalResult::Number(3.0));
+        assert_eq!(eval_with_hidden(&grid, &[0], &subtotal_expr(109.0, range_a1_a4())), EvalResult::Number(60.0));
+    }
+
+    #[test]
+    fn test_subtotal_count_ignores_blank_cells() {
+        // A1:A5 has one blank row; COUNT/COUNTA see only the three values.
+        let grid = make_grid();
+        let range = Expression::Range {
+            sheet: None,
+            start: Box::new(Expression::CellRef { sheet: None, col: "A".to_string(), row: 1, col_absolute: false, row_absolute: false, ref_site_id: Default::default() }),
+            end: Box::new(Expression::CellRef { sheet: None, col: "A".to_string(), row: 5, col_absolute: false, row_absolute: false, ref_site_id: Default::default() }),
+            ref_site_id: Default::default(),
+        };
+        assert_eq!(eval_with_hidden(&grid, &[], &subtotal_expr(2.0, range.clone())), EvalResult::Number(3.0));
+        assert_eq!(eval_with_hidden(&grid, &[1], &subtotal_expr(103.0, range)), EvalResult::Number(2.0));
+    }
+
+    #[test]
+    fn test_aggregate_options_hidden_and_nested() {
+        let grid = make_grid_with_subtotal_row();
+        // 0: skip nested only; the hidden row still counts.
+        assert_eq!(eval_with_hidden(&grid, &[1], &aggregate_expr(9.0, 0.0, range_a1_a4())), EvalResult::Number(70.0));
+        // 1 and 5: skip hidden rows; 5 keeps the nested subtotal.
+        assert_eq!(eval_with_hidden(&grid, &[1], &aggregate_expr(9.0, 1.0, range_a1_a4())), EvalResult::Number(50.0));
+        assert_eq!(eval_with_hidden(&grid, &[1], &aggregate_expr(9.0, 5.0, range_a1_a4())), EvalResult::Number(80.0));
+        // 4: ignore nothing.
+        assert_eq!(eval_with_hidden(&grid, &[1], &aggregate_expr(9.0, 4.0, range_a1_a4())), EvalResult::Number(100.0));
+        // LARGE over visible, non-subtotal rows.
+        let large = Expression::FunctionCall {
+            func: BuiltinFunction::Aggregate,
+            args: vec![num(14.0), num(3.0), range_a1_a4(), num(2.0)],
+            ref_site_id: Default::default(),
+        };
+        assert_eq!(eval_with_hidden(&grid, &[3], &large), EvalResult::Number(10.0));
+    }
+
+    #[test]
+    fn test_aggregate_error_options() {
+        let mut grid = make_grid();
+        let mut error = Cell::new_number(0.0);
+        error.value = CellValue::Error(CellError::Div0);
+        grid.set_cell(1, 0, error);
+        // 6 ignores errors; 5 only ignores hidden rows, so the error propagates.
+        assert_eq!(eval_with_hidden(&grid, &[], &aggregate_expr(9.0, 6.0, range_a1_a3())), EvalResult::Number(40.0));
+        assert_eq!(eval_with_hidden(&grid, &[], &aggregate_expr(9.0, 5.0, range_a1_a3())), EvalResult::Error(CellError::Div0));
+        assert_eq!(eval_with_hidden(&grid, &[1], &aggregate_expr(9.0, 5.0, range_a1_a3())), EvalResult::Number(40.0));
+    }
+
+    // ==================== Helper for new function tests ====================
 
     fn make_fn_expr(func: BuiltinFunction, args: Vec<Expression>) -> Expression {
         Expression::FunctionCall { func, args,
@@ -15533,6 +17275,155 @@ mod tests {
         assert!(matches!(result, EvalResult::Error(_)), "Expected Error, got {:?}", result);
     }
 
+    // ==================== Text Search Tests ====================
+
+    #[test]
+    fn test_find_counts_characters_not_bytes() {
+        let grid = Grid::new();
+        let eval = Evaluator::new(&grid);
+        let expr = make_fn_expr(BuiltinFunction::Find, vec![text("w"), text("héllo wörld")]);
+        assert_num(&eval.evaluate(&expr), 7.0, 1e-9);
+        let expr = make_fn_expr(BuiltinFunction::Find, vec![text("テ"), text("日本語テキスト"), num(2.0)]);
+        assert_num(&eval.evaluate(&expr), 4.0, 1e-9);
+        // A start position inside a multibyte run no longer panics.
+        let expr = make_fn_expr(BuiltinFunction::Find, vec![text("語"), text("日本語"), num(2.0)]);
+        assert_num(&eval.evaluate(&expr), 3.0, 1e-9);
+    }
+
+    #[test]
+    fn test_find_is_case_sensitive_and_literal() {
+        let grid = Grid::new();
+        let eval = Evaluator::new(&grid);
+        let expr = make_fn_expr(BuiltinFunction::Find, vec![text("W"), text("héllo wörld")]);
+        assert_error(&eval.evaluate(&expr));
+        let expr = make_fn_expr(BuiltinFunction::Find, vec![text("?"), text("why?")]);
+        assert_num(&eval.evaluate(&expr), 4.0, 1e-9);
+        let expr = make_fn_expr(BuiltinFunction::Find, vec![text(""), text("abc"), num(3.0)]);
+        assert_num(&eval.evaluate(&expr), 3.0, 1e-9);
+    }
+
+    #[test]
+    fn test_find_start_and_not_found_are_value_errors() {
+        let grid = Grid::new();
+        let eval = Evaluator::new(&grid);
+        for args in [
+            vec![text("x"), text("日本語")],
+            vec![text("日"), text("日本語"), num(5.0)],
+            vec![text("日"), text("日本語"), num(0.0)],
+        ] {
+            match eval.evaluate(&make_fn_expr(BuiltinFunction::Find, args)) {
+                EvalResult::Error(CellError::Value) => {}
+                other => panic!("expected #VALUE!, got {:?}", other),
+            }
+        }
+    }
+
+    #[test]
+    fn test_search_case_insensitive_by_character() {
+        let grid = Grid::new();
+        let eval = Evaluator::new(&grid);
+        let expr = make_fn_expr(BuiltinFunction::Search, vec![text("WÖR"), text("héllo wörld")]);
+        assert_num(&eval.evaluate(&expr), 7.0, 1e-9);
+        let expr = make_fn_expr(BuiltinFunction::Search, vec![text("l"), text("héllo wörld"), num(5.0)]);
+        assert_num(&eval.evaluate(&expr), 10.0, 1e-9);
+    }
+
+    #[test]
+    fn test_search_wildcards_match_a_prefix() {
+        let grid = Grid::new();
+        let eval = Evaluator::new(&grid);
+        let expr = make_fn_expr(BuiltinFunction::Search, vec![text("w?r"), text("héllo wörld")]);
+        assert_num(&eval.evaluate(&expr), 7.0, 1e-9);
+        let expr = make_fn_expr(BuiltinFunction::Search, vec![text("本*キ"), text("日本語テキスト")]);
+        assert_num(&eval.evaluate(&expr), 2.0, 1e-9);
+        let expr = make_fn_expr(BuiltinFunction::Search, vec![text("~?"), text("why? not")]);
+        assert_num(&eval.evaluate(&expr), 4.0, 1e-9);
+        let expr = make_fn_expr(BuiltinFunction::Search, vec![text("x*"), text("abc")]);
+        assert_error(&eval.evaluate(&expr));
+    }
+
+    #[test]
+    fn test_substitute_multibyte_and_instance() {
+        let grid = Grid::new();
+        let eval = Evaluator::new(&grid);
+        let expr = make_fn_expr(BuiltinFunction::Substitute, vec![text("ö-ö-ö"), text("ö"), text("oe")]);
+        assert_text_eq(&eval.evaluate(&expr), "oe-oe-oe");
+        let expr = make_fn_expr(BuiltinFunction::Substitute, vec![text("ö-ö-ö"), text("ö"), text("oe"), num(2.0)]);
+        assert_text_eq(&eval.evaluate(&expr), "ö-oe-ö");
+        let expr = make_fn_expr(BuiltinFunction::Substitute, vec![text("ö-ö"), text("ö"), text("oe"), num(3.0)]);
+        assert_text_eq(&eval.evaluate(&expr), "ö-ö");
+        let expr = make_fn_expr(BuiltinFunction::Substitute, vec![text("ö"), text("ö"), text("oe"), num(0.0)]);
+        assert_error(&eval.evaluate(&expr));
+    }
+
+    #[test]
+    fn test_replace_and_len_count_characters() {
+        let grid = Grid::new();
+        let eval = Evaluator::new(&grid);
+        let expr = make_fn_expr(BuiltinFunction::Replace, vec![text("日本語テキスト"), num(4.0), num(4.0), text("text")]);
+        assert_text_eq(&eval.evaluate(&expr), "日本語text");
+        let expr = make_fn_expr(BuiltinFunction::Replace, vec![text("héllo"), num(9.0), num(1.0), text("!")]);
+        assert_text_eq(&eval.evaluate(&expr), "héllo!");
+        let expr = make_fn_expr(BuiltinFunction::Len, vec![text("héllo wörld")]);
+        assert_num(&eval.evaluate(&expr), 11.0, 1e-9);
+        let expr = make_fn_expr(BuiltinFunction::Len, vec![text("日本語テキスト")]);
+        assert_num(&eval.evaluate(&expr), 7.0, 1e-9);
+    }
+
+    #[test]
+    fn test_text_search_functions_propagate_errors() {
+        let grid = Grid::new();
+        let eval = Evaluator::new(&grid);
+        for formula in [
+            "FIND(\"a\",1/0)",
+            "SEARCH(1/0,\"abc\")",
+            "SUBSTITUTE(\"abc\",\"a\",1/0)",
+            "REPLACE(\"abc\",1/0,1,\"x\")",
+            "LEN(1/0)",
+        ] {
+            let ast = parser::parse(formula).unwrap();
+            assert_eq!(eval.evaluate(&ast), EvalResult::Error(CellError::Div0), "{}", formula);
+        }
+    }
+
+    #[test]
+    fn test_character_code_functions() {
+        let grid = Grid::new();
+        let eval = |formula: &str| eval_str(&grid, formula);
+        let text = |t: &str| EvalResult::Text(t.to_string());
+
+        assert_eq!(eval("CHAR(65)"), text("A"));
+        assert_eq!(eval("CHAR(65.9)"), text("A"));
+        assert_eq!(eval("CHAR(128)"), text("\u{20AC}"));
+        assert_eq!(eval("CHAR(233)"), text("\u{E9}"));
+        assert_eq!(eval("CHAR(0)"), EvalResult::Error(CellError::Value));
+        assert_eq!(eval("CHAR(256)"), EvalResult::Error(CellError::Value));
+        assert_eq!(eval("CODE(\"Abc\")"), EvalResult::Number(65.0));
+        assert_eq!(eval("CODE(CHAR(128))"), EvalResult::Number(128.0));
+        assert_eq!(eval("CODE(UNICHAR(960))"), EvalResult::Number(63.0));
+        assert_eq!(eval("CODE(\"\")"), EvalResult::Error(CellError::Value));
+
+        assert_eq!(eval("UNICHAR(960)"), text("\u{3C0}"));
+        assert_eq!(eval("UNICHAR(128512)"), text("\u{1F600}"));
+        assert_eq!(eval("UNICODE(UNICHAR(128512))"), EvalResult::Number(128512.0));
+        assert_eq!(eval("UNICHAR(0)"), EvalResult::Error(CellError::Value));
+        assert_eq!(eval("UNICHAR(1114112)"), EvalResult::Error(CellError::Value));
+        assert_eq!(eval("UNICHAR(55296)"), EvalResult::Error(CellError::NA));
+
+        assert_eq!(eval("CLEAN(CHAR(9)&\"caf\"&UNICHAR(233)&CHAR(10)&CHAR(127))"), text("caf\u{E9}\u{7F}"));
+        assert_eq!(eval("EXACT(\"Word\",\"Word\")"), EvalResult::Boolean(true));
+        assert_eq!(eval("EXACT(\"Word\",\"word\")"), EvalResult::Boolean(false));
+        assert_eq!(eval("EXACT(1,\"1\")"), EvalResult::Boolean(true));
+
+        assert_eq!(eval("PROPER(\"hello WORLD\")"), text("Hello World"));
+        assert_eq!(eval("PROPER(\"2nd o'neil-smith\")"), text("2Nd O'Neil-Smith"));
+        assert_eq!(eval("PROPER(\"\u{E9}cole \u{FC}BER\")"), text("\u{C9}cole \u{DC}ber"));
+
+        for formula in ["CHAR(1/0)", "CODE(1/0)", "UNICHAR(1/0)", "UNICODE(1/0)", "CLEAN(1/0)", "EXACT(\"a\",1/0)", "PROPER(1/0)"] {
+            assert_eq!(eval(formula), EvalResult::Error(CellError::Div0), "{}", formula);
+        }
+    }
+
     // ==================== Text Parsing Tests ====================
 
     #[test]
@@ -15600,6 +17491,57 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_textsplit_rows_pad_and_ignore_empty() {
+        let grid = Grid::new();
+        let eval = Evaluator::new(&grid);
+        let expr = make_fn_expr(BuiltinFunction::TextSplit, vec![text("a,b;c"), text(","), text(";")]);
+        let row = |items: &[EvalResult]| EvalResult::Array(items.to_vec());
+        let t = |s: &str| EvalResult::Text(s.to_string());
+        assert_eq!(
+            eval.evaluate(&expr),
+            EvalResult::Array(vec![row(&[t("a"), t("b")]), row(&[t("c"), EvalResult::Error(CellError::NA)])])
+        );
+
+        let expr = make_fn_expr(
+            BuiltinFunction::TextSplit,
+            vec![text("aXbxx,c"), text("x"), text(""), bool_val(true), num(1.0)],
+        );
+        assert_eq!(eval.evaluate(&expr), EvalResult::Array(vec![t("a"), t("b"), t(",c")]));
+
+        let expr = make_fn_expr(BuiltinFunction::TextSplit, vec![text("abc"), text("")]);
+        assert_error(&eval.evaluate(&expr));
+    }
+
+    #[test]
+    fn test_textjoin_and_concat_flatten_ranges() {
+        let mut grid = Grid::new();
+        grid.set_cell(0, 0, Cell::new_text("a".to_string()));
+        // A2 is blank; A3 holds a zero, which is not empty.
+        grid.set_cell(2, 0, Cell::new_number(0.0));
+        grid.set_cell(3, 0, Cell::new_text("d".to_string()));
+        let eval = Evaluator::new(&grid);
+
+        let join = |ignore_empty: bool| {
+            make_fn_expr(BuiltinFunction::TextJoin, vec![text("-"), bool_val(ignore_empty), col_range("A", 1, 4), text("e")])
+        };
+        assert_text_eq(&eval.evaluate(&join(true)), "a-0-d-e");
+        assert_text_eq(&eval.evaluate(&join(false)), "a--0-d-e");
+
+        let concat = make_fn_expr(BuiltinFunction::Concatenate, vec![col_range("A", 1, 4), text("!")]);
+        assert_text_eq(&eval.evaluate(&concat), "a0d!");
+    }
+
+    #[test]
+    fn test_textjoin_propagates_errors() {
+        let mut grid = Grid::new();
+        grid.set_cell(0, 0, Cell::new_text("a".to_string()));
+        grid.set_cell(1, 0, Cell { value: CellValue::Error(CellError::Div0), ..Cell::default() });
+        let eval = Evaluator::new(&grid);
+        let expr = make_fn_expr(BuiltinFunction::TextJoin, vec![text(","), bool_val(true), col_range("A", 1, 2)]);
+        assert_eq!(eval.evaluate(&expr), EvalResult::Error(CellError::Div0));
+    }
+
     #[test]
     fn test_valuetotext_number() {
         let grid = Grid::new();
@@ -15635,6 +17577,55 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_text_number_format_codes() {
+        let grid = Grid::new();
+        let t = |f: &str| eval_str(&grid, f);
+        assert_eq!(t("TEXT(1234567.891,\"#,##0.00\")"), EvalResult::Text("1,234,567.89".to_string()));
+        assert_eq!(t("TEXT(0.1234,\"0.00%\")"), EvalResult::Text("12.34%".to_string()));
+        assert_eq!(t("TEXT(3.14159,\"0.000\")"), EvalResult::Text("3.142".to_string()));
+        assert_eq!(t("TEXT(7,\"000\")"), EvalResult::Text("007".to_string()));
+        assert_eq!(t("TEXT(1234,\"0.0E+00\")"), EvalResult::Text("1.2E+03".to_string()));
+        assert_eq!(t("TEXT(0.00012,\"0.00E+0\")"), EvalResult::Text("1.20E-4".to_string()));
+        assert_eq!(t("TEXT(12345,\"##0.0E+0\")"), EvalResult::Text("12.3E+3".to_string()));
+        assert_eq!(t("TEXT(-5,\"0;(0)\")"), EvalResult::Text("(5)".to_string()));
+        assert_eq!(t("TEXT(2.5,\"General\")"), EvalResult::Text("2.5".to_string()));
+        // Color tokens only affect cell rendering.
+        assert_eq!(t("TEXT(-2,\"[Red]0.0\")"), EvalResult::Text("-2.0".to_string()));
+        // Numeric text is formatted as a number, other text via the text section.
+        assert_eq!(t("TEXT(\"12.5\",\"0.00\")"), EvalResult::Text("12.50".to_string()));
+        let mut grid = Grid::new();
+        grid.set_cell(0, 0, Cell::new_text("0;0;0;\"<\"@\">\"".to_string()));
+        assert_eq!(eval_str(&grid, "TEXT(\"abc\",A1)"), EvalResult::Text("<abc>".to_string()));
+    }
+
+    #[test]
+    fn test_text_date_format_codes() {
+        let grid = Grid::new();
+        let t = |f: &str| eval_str(&grid, f);
+        assert_eq!(t("TEXT(DATE(2024,1,15),\"yyyy-mm-dd\")"), EvalResult::Text("2024-01-15".to_string()));
+        assert_eq!(t("TEXT(DATE(2024,1,15),\"dd mmm yyyy\")"), EvalResult::Text("15 Jan 2024".to_string()));
+        assert_eq!(t("TEXT(DATE(2024,1,15),\"dddd\")"), EvalResult::Text("Monday".to_string()));
+        assert_eq!(t("TEXT(0.75,\"hh:mm\")"), EvalResult::Text("18:00".to_string()));
+        assert_eq!(t("TEXT(1.5,\"[h]:mm\")"), EvalResult::Text("36:00".to_string()));
+        assert_eq!(t("TEXT(1.5,\"[m]:ss\")"), EvalResult::Text("2160:00".to_string()));
+        assert_eq!(t("TEXT(\"2024-03-05\",\"m/d/yyyy\")"), EvalResult::Text("3/5/2024".to_string()));
+        assert_eq!(t("TEXT(-1,\"yyyy\")"), EvalResult::Error(CellError::Value));
+    }
+
+    #[test]
+    fn test_text_invalid_format_code() {
+        let grid = Grid::new();
+        let t = |f: &str| eval_str(&grid, f);
+        assert_eq!(t("TEXT(1,\"0;0;0;@;0\")"), EvalResult::Error(CellError::Value));
+        assert_eq!(t("TEXT(1,\"[Purple]0\")"), EvalResult::Error(CellError::Value));
+        assert_eq!(t("TEXT(1,\"[Red0\")"), EvalResult::Error(CellError::Value));
+        assert_eq!(t("TEXT(1/0,\"0\")"), EvalResult::Error(CellError::Div0));
+        let mut grid = Grid::new();
+        grid.set_cell(0, 0, Cell::new_text("0\"abc".to_string()));
+        assert_eq!(eval_str(&grid, "TEXT(1,A1)"), EvalResult::Error(CellError::Value));
+    }
+
     // ==================== Date Function Tests ====================
 
     #[test]
@@ -15728,6 +17719,94 @@ mod tests {
         assert_num(&result, expected, 0.1);
     }
 
+    #[test]
+    fn test_edate_eomonth_clamp_to_month_end() {
+        let grid = Grid::new();
+        let d = |y, m, dd| EvalResult::Number(date_serial::date_to_serial(y, m, dd));
+        // Jan 31 + 1 month lands on the last day of February, leap or not.
+        assert_eq!(eval_str(&grid, "EDATE(DATE(2024,1,31),1)"), d(2024, 2, 29));
+        assert_eq!(eval_str(&grid, "EDATE(DATE(2023,1,31),1)"), d(2023, 2, 28));
+        assert_eq!(eval_str(&grid, "EDATE(DATE(2024,2,29),12)"), d(2025, 2, 28));
+        assert_eq!(eval_str(&grid, "EDATE(DATE(2024,3,31),-1)"), d(2024, 2, 29));
+        assert_eq!(eval_str(&grid, "EDATE(\"2024-05-31\",1)"), d(2024, 6, 30));
+        assert_eq!(eval_str(&grid, "EOMONTH(DATE(2024,1,15),1)"), d(2024, 2, 29));
+        assert_eq!(eval_str(&grid, "EOMONTH(DATE(2100,1,15),1)"), d(2100, 2, 28));
+        assert_eq!(eval_str(&grid, "EOMONTH(DATE(2024,3,10),-3)"), d(2023, 12, 31));
+        // Before 1900, bad input and argument errors.
+        assert_eq!(eval_str(&grid, "EDATE(DATE(1900,1,15),-1)"), EvalResult::Error(CellError::Num));
+        assert_eq!(eval_str(&grid, "EOMONTH(DATE(1900,1,15),-1)"), EvalResult::Error(CellError::Num));
+        assert_eq!(eval_str(&grid, "EDATE(-1,1)"), EvalResult::Error(CellError::Num));
+        assert_eq!(eval_str(&grid, "EOMONTH(-1,0)"), EvalResult::Error(CellError::Num));
+        assert_eq!(eval_str(&grid, "EDATE(\"soon\",1)"), EvalResult::Error(CellError::Value));
+        assert_eq!(eval_str(&grid, "EOMONTH(NA(),1)"), EvalResult::Error(CellError::NA));
+    }
+
+    #[test]
+    fn test_datedif_units() {
+        let grid = Grid::new();
+        let dd = |unit: &str| eval_str(&grid, &format!("DATEDIF(DATE(2020,2,29),DATE(2024,3,15),\"{}\")", unit));
+        assert_eq!(dd("Y"), EvalResult::Number(4.0));
+        assert_eq!(dd("M"), EvalResult::Number(48.0));
+        assert_eq!(dd("D"), EvalResult::Number(1476.0));
+        assert_eq!(dd("MD"), EvalResult::Number(15.0));
+        assert_eq!(dd("YM"), EvalResult::Number(0.0));
+        assert_eq!(dd("YD"), EvalResult::Number(15.0));
+        assert_eq!(dd("ym"), EvalResult::Number(0.0));
+        assert_eq!(dd("W"), EvalResult::Error(CellError::Num));
+        // MD borrows the length of the month before the end date.
+        assert_eq!(eval_str(&grid, "DATEDIF(DATE(2024,1,25),DATE(2024,3,5),\"MD\")"), EvalResult::Number(9.0));
+        assert_eq!(eval_str(&grid, "DATEDIF(DATE(2024,1,31),DATE(2024,3,1),\"MD\")"), EvalResult::Number(1.0));
+        assert_eq!(eval_str(&grid, "DATEDIF(DATE(2023,1,30),DATE(2023,3,2),\"MD\")"), EvalResult::Number(2.0));
+        assert_eq!(eval_str(&grid, "DATEDIF(DATE(2024,3,1),DATE(2024,1,31),\"D\")"), EvalResult::Error(CellError::Num));
+        assert_eq!(eval_str(&grid, "DATEDIF(\"later\",DATE(2024,1,31),\"D\")"), EvalResult::Error(CellError::Value));
+    }
+
+    #[test]
+    fn test_weekday_return_types() {
+        let grid = Grid::new();
+        // 2024-01-17 is a Wednesday.
+        let w = |t: &str| eval_str(&grid, &format!("WEEKDAY(DATE(2024,1,17){})", t));
+        assert_eq!(w(""), EvalResult::Number(4.0));
+        assert_eq!(w(",2"), EvalResult::Number(3.0));
+        assert_eq!(w(",3"), EvalResult::Number(2.0));
+        assert_eq!(w(",11"), EvalResult::Number(3.0));
+        assert_eq!(w(",13"), EvalResult::Number(1.0));
+        assert_eq!(w(",16"), EvalResult::Number(5.0));
+        assert_eq!(w(",17"), EvalResult::Number(4.0));
+        assert_eq!(w(",4"), EvalResult::Error(CellError::Num));
+        assert_eq!(w(",\"x\""), EvalResult::Error(CellError::Value));
+        // Serial 0 is a Saturday; 1900-03-01 (serial 61) a Thursday.
+        assert_eq!(eval_str(&grid, "WEEKDAY(0)"), EvalResult::Number(7.0));
+        assert_eq!(eval_str(&grid, "WEEKDAY(61)"), EvalResult::Number(5.0));
+        assert_eq!(eval_str(&grid, "WEEKDAY(-1)"), EvalResult::Error(CellError::Num));
+        assert_eq!(eval_str(&grid, "WEEKDAY(\"someday\")"), EvalResult::Error(CellError::Value));
+    }
+
+    #[test]
+    fn test_networkdays_workday_with_holiday_range() {
+        let mut grid = Grid::new();
+        // Holidays in A1:A3 (A2 left blank): Mon 2024-01-01 and Sat 2024-01-06.
+        grid.set_cell(0, 0, Cell::new_number(date_serial::date_to_serial(2024, 1, 1)));
+        grid.set_cell(2, 0, Cell::new_number(date_serial::date_to_serial(2024, 1, 6)));
+        let d = |y, m, dd| EvalResult::Number(date_serial::date_to_serial(y, m, dd));
+
+        assert_eq!(eval_str(&grid, "NETWORKDAYS(DATE(2024,1,1),DATE(2024,1,31))"), EvalResult::Number(23.0));
+        // Only the weekday holiday removes a working day.
+        assert_eq!(eval_str(&grid, "NETWORKDAYS(DATE(2024,1,1),DATE(2024,1,31),A1:A3)"), EvalResult::Number(22.0));
+        assert_eq!(eval_str(&grid, "NETWORKDAYS(DATE(2024,1,31),DATE(2024,1,1),A1:A3)"), EvalResult::Number(-22.0));
+        assert_eq!(eval_str(&grid, "NETWORKDAYS(-5,3)"), EvalResult::Error(CellError::Num));
+        assert_eq!(eval_str(&grid, "WORKDAY(DATE(2023,12,29),1)"), d(2024, 1, 1));
+        assert_eq!(eval_str(&grid, "WORKDAY(DATE(2023,12,29),1,A1:A3)"), d(2024, 1, 2));
+        assert_eq!(eval_str(&grid, "WORKDAY(DATE(2024,1,2),-1,A1:A3)"), d(2023, 12, 29));
+        assert_eq!(eval_str(&grid, "WORKDAY(DATE(2024,1,5),1,\"2024-01-08\")"), d(2024, 1, 9));
+
+        // An error among the holidays propagates.
+        let mut c = Cell::new_number(0.0);
+        c.value = CellValue::Error(CellError::NA);
+        grid.set_cell(1, 0, c);
+        assert_eq!(eval_str(&grid, "WORKDAY(DATE(2023,12,29),1,A1:A3)"), EvalResult::Error(CellError::NA));
+    }
+
     // ==================== Statistical Tests ====================
 
     #[test]
@@ -15834,6 +17913,334 @@ mod tests {
         assert_num(&result, 100.0, 0.01); // var.s of {10,20,30} = 100
     }
 
+    fn make_stats_grid() -> Grid {
+        let mut grid = Grid::new();
+        for (row, n) in [1.0, 2.0, 4.0, 7.0, 2.0, 10.0].iter().enumerate() {
+            grid.set_cell(row as u32, 0, Cell::new_number(*n));
+        }
+        grid.set_cell(0, 1, Cell::new_number(1.0));
+        grid.set_cell(1, 1, Cell::new_number(2.0));
+        grid.set_cell(2, 1, Cell::new_text("x".to_string()));
+        grid
+    }
+
+    #[test]
+    fn test_median_and_mode() {
+        let grid = make_stats_grid();
+        assert_eq!(eval_str(&grid, "MEDIAN(A1:A6)"), EvalResult::Number(3.0));
+        assert_eq!(eval_str(&grid, "MEDIAN(A1:A5)"), EvalResult::Number(2.0));
+        assert_eq!(eval_str(&grid, "MODE.SNGL(A1:A6)"), EvalResult::Number(2.0));
+        assert_eq!(eval_str(&grid, "MODE(A1:A6)"), EvalResult::Number(2.0));
+        // No repeated value, or no numbers at all
+        assert_eq!(eval_str(&grid, "MODE(B1:B3)"), EvalResult::Error(CellError::NA));
+        assert_eq!(eval_str(&grid, "MODE.SNGL(B3)"), EvalResult::Error(CellError::NA));
+    }
+
+    #[test]
+    fn test_stdev_and_var_variants() {
+        let grid = make_stats_grid();
+        // {1,2,4,7,2,10}: mean 13/3, sum of squared deviations 184/3
+        assert_num(&eval_str(&grid, "VAR.S(A1:A6)"), 184.0 / 15.0, 1e-9);
+        assert_num(&eval_str(&grid, "VAR.P(A1:A6)"), 184.0 / 18.0, 1e-9);
+        assert_num(&eval_str(&grid, "STDEV.S(A1:A6)"), (184.0f64 / 15.0).sqrt(), 1e-9);
+        assert_num(&eval_str(&grid, "STDEV.P(A1:A6)"), (184.0f64 / 18.0).sqrt(), 1e-9);
+        // Compatibility names are the sample variants
+        assert_eq!(eval_str(&grid, "STDEV(A1:A6)"), eval_str(&grid, "STDEV.S(A1:A6)"));
+        assert_eq!(eval_str(&grid, "VAR(A1:A6)"), eval_str(&grid, "VAR.S(A1:A6)"));
+        // A sample needs two numbers; text in ranges is ignored
+        assert_eq!(eval_str(&grid, "STDEV.S(B3)"), EvalResult::Error(CellError::Div0));
+        assert_num(&eval_str(&grid, "VAR.S(B1:B3)"), 0.5, 1e-9);
+    }
+
+    #[test]
+    fn test_percentile_and_quartile_inc() {
+        let grid = make_stats_grid();
+        // Sorted {1,2,2,4,7,10}: rank k*(n-1) interpolates between neighbours
+        assert_num(&eval_str(&grid, "PERCENTILE.INC(A1:A6, 0.5)"), 3.0, 1e-9);
+        assert_num(&eval_str(&grid, "PERCENTILE.INC(A1:A6, 0.3)"), 2.0, 1e-9);
+        assert_num(&eval_str(&grid, "PERCENTILE.INC(A1:A6, 0.9)"), 8.5, 1e-9);
+        assert_num(&eval_str(&grid, "PERCENTILE(A1:A6, 1)"), 10.0, 1e-9);
+        assert_num(&eval_str(&grid, "QUARTILE.INC(A1:A6, 1)"), 2.0, 1e-9);
+        assert_num(&eval_str(&grid, "QUARTILE.INC(A1:A6, 3)"), 6.25, 1e-9);
+        // quart is truncated
+        assert_eq!(eval_str(&grid, "QUARTILE(A1:A6, 3.9)"), eval_str(&grid, "QUARTILE(A1:A6, 3)"));
+        // Out of range
+        assert_eq!(eval_str(&grid, "PERCENTILE.INC(A1:A6, 1.5)"), EvalResult::Error(CellError::Num));
+        assert_eq!(eval_str(&grid, "PERCENTILE.INC(A1:A6, -0.1)"), EvalResult::Error(CellError::Num));
+        assert_eq!(eval_str(&grid, "QUARTILE.INC(A1:A6, 5)"), EvalResult::Error(CellError::Num));
+        assert_eq!(eval_str(&grid, "PERCENTILE(B3, 0.5)"), EvalResult::Error(CellError::Num));
+        assert_eq!(eval_str(&grid, "QUARTILE(A1:A6, \"x\")"), EvalResult::Error(CellError::Value));
+    }
+
+    #[test]
+    fn test_rank_ties_share_a_rank_and_skip_the_next() {
+        let grid = make_stats_grid();
+        // {1,2,4,7,2,10}: the two 2s tie
+        assert_eq!(eval_str(&grid, "RANK(10, A1:A6)"), EvalResult::Number(1.0));
+        assert_eq!(eval_str(&grid, "RANK(2, A1:A6)"), EvalResult::Number(4.0));
+        assert_eq!(eval_str(&grid, "RANK(1, A1:A6)"), EvalResult::Number(6.0));
+        assert_eq!(eval_str(&grid, "RANK.EQ(2, A1:A6, 1)"), EvalResult::Number(2.0));
+        assert_eq!(eval_str(&grid, "RANK(4, A1:A6, 1)"), EvalResult::Number(4.0));
+        assert_eq!(eval_str(&grid, "RANK(3, A1:A6)"), EvalResult::Error(CellError::NA));
+    }
+
+    #[test]
+    fn test_large_and_small_with_duplicates() {
+        let grid = make_stats_grid();
+        assert_eq!(eval_str(&grid, "LARGE(A1:A6, 1)"), EvalResult::Number(10.0));
+        assert_eq!(eval_str(&grid, "LARGE(A1:A6, 4)"), EvalResult::Number(2.0));
+        assert_eq!(eval_str(&grid, "LARGE(A1:A6, 5)"), EvalResult::Number(2.0));
+        assert_eq!(eval_str(&grid, "SMALL(A1:A6, 2)"), EvalResult::Number(2.0));
+        assert_eq!(eval_str(&grid, "SMALL(A1:A6, 3)"), EvalResult::Number(2.0));
+        assert_eq!(eval_str(&grid, "SMALL(A1:A6, 4)"), EvalResult::Number(4.0));
+        // Out of bounds or no numbers
+        assert_eq!(eval_str(&grid, "LARGE(A1:A6, 7)"), EvalResult::Error(CellError::Num));
+        assert_eq!(eval_str(&grid, "SMALL(A1:A6, 0)"), EvalResult::Error(CellError::Num));
+        assert_eq!(eval_str(&grid, "LARGE(B3, 1)"), EvalResult::Error(CellError::Num));
+        assert_eq!(eval_str(&grid, "SMALL(A1:A6, \"x\")"), EvalResult::Error(CellError::Value));
+    }
+
+    #[test]
+    fn test_percentrank_interpolates_between_values() {
+        let grid = make_stats_grid();
+        // Sorted {1,2,2,4,7,10}: values below x over n-1
+        assert_num(&eval_str(&grid, "PERCENTRANK(A1:A6, 2)"), 0.2, 1e-9);
+        assert_num(&eval_str(&grid, "PERCENTRANK(A1:A6, 3)"), 0.5, 1e-9);
+        assert_num(&eval_str(&grid, "PERCENTRANK.INC(A1:A6, 10)"), 1.0, 1e-9);
+        assert_num(&eval_str(&grid, "PERCENTRANK(A1:A6, 5, 1)"), 0.6, 1e-9);
+        assert_num(&eval_str(&grid, "PERCENTRANK(A1, 1)"), 1.0, 1e-9);
+        assert_eq!(eval_str(&grid, "PERCENTRANK(A1:A6, 11)"), EvalResult::Error(CellError::NA));
+        assert_eq!(eval_str(&grid, "PERCENTRANK(A1:A6, 2, 0)"), EvalResult::Error(CellError::Num));
+        assert_eq!(eval_str(&grid, "PERCENTRANK(B3, 1)"), EvalResult::Error(CellError::Num));
+    }
+
+    /// A1:A5 = {1,2,4,7,2}, B1:B5 = {10,20,30,40,50}, C1 = "5", C2 = "x".
+    fn make_product_grid() -> Grid {
+        let mut grid = Grid::new();
+        for (row, (a, b)) in [(1.0, 10.0), (2.0, 20.0), (4.0, 30.0), (7.0, 40.0), (2.0, 50.0)]
+            .iter()
+            .enumerate()
+        {
+            grid.set_cell(row as u32, 0, Cell::new_number(*a));
+            grid.set_cell(row as u32, 1, Cell::new_number(*b));
+        }
+        grid.set_cell(0, 2, Cell::new_text("5".to_string()));
+        grid.set_cell(1, 2, Cell::new_text("x".to_string()));
+        grid
+    }
+
+    fn numbers(values: &[f64]) -> EvalResult {
+        EvalResult::Array(values.iter().map(|n| EvalResult::Number(*n)).collect())
+    }
+
+    #[test]
+    fn test_binary_ops_broadcast_over_arrays() {
+        let grid = make_product_grid();
+        assert_eq!(eval_str(&grid, "(A1:A5>3)*B1:B5"), numbers(&[0.0, 0.0, 30.0, 40.0, 0.0]));
+        assert_eq!(eval_str(&grid, "A1:A5*2"), numbers(&[2.0, 4.0, 8.0, 14.0, 4.0]));
+        assert_eq!(eval_str(&grid, "10-A1:A2"), numbers(&[9.0, 8.0]));
+        assert_eq!(eval_str(&grid, "-A1:A2"), numbers(&[-1.0, -2.0]));
+        assert_eq!(
+            eval_str(&grid, "A1:A2&\"!\""),
+            EvalResult::Array(vec![EvalResult::Text("1!".into()), EvalResult::Text("2!".into())])
+        );
+        // Errors stay per element
+        assert_eq!(
+            eval_str(&grid, "1/(A1:A2-1)"),
+            EvalResult::Array(vec![EvalResult::Error(CellError::Div0), EvalResult::Number(1.0)])
+        );
+        // Mismatched lengths pad with #N/A
+        assert_eq!(
+            eval_str(&grid, "A1:A3+A1:A2"),
+            EvalResult::Array(vec![
+                EvalResult::Number(2.0),
+                EvalResult::Number(4.0),
+                EvalResult::Error(CellError::NA),
+            ])
+        );
+        // A column is broadcast across a 2D range
+        assert_eq!(
+            eval_str(&grid, "A1:B2*A1:A2"),
+            EvalResult::Array(vec![numbers(&[1.0, 10.0]), numbers(&[4.0, 40.0])])
+        );
+        // A row range pairs with a row constant element by element, and the
+        // row is broadcast down a 2D range
+        assert_eq!(eval_str(&grid, "A1:B1*{1,2}"), EvalResult::Array(vec![numbers(&[1.0, 20.0])]));
+        assert_eq!(
+            eval_str(&grid, "A1:B2*{1,2}"),
+            EvalResult::Array(vec![numbers(&[1.0, 20.0]), numbers(&[2.0, 40.0])])
+        );
+        assert_eq!(eval_str(&grid, "SUM((A1:A5>3)*B1:B5)"), EvalResult::Number(70.0));
+        // Scalars are unaffected
+        assert_eq!(eval_str(&grid, "A1*B1"), EvalResult::Number(10.0));
+        assert_eq!(eval_str(&grid, "C2*2"), EvalResult::Error(CellError::Value));
+    }
+
+    #[test]
+    fn test_sumproduct_and_product() {
+        let grid = make_product_grid();
+        assert_eq!(eval_str(&grid, "SUMPRODUCT(A1:A5, B1:B5)"), EvalResult::Number(550.0));
+        // Conditional sums via boolean arithmetic
+        assert_eq!(eval_str(&grid, "SUMPRODUCT((A1:A5>3)*B1:B5)"), EvalResult::Number(70.0));
+        assert_eq!(eval_str(&grid, "SUMPRODUCT(--(A1:A5=2), B1:B5)"), EvalResult::Number(70.0));
+        // Uncoerced booleans and text count as zero
+        assert_eq!(eval_str(&grid, "SUMPRODUCT(A1:A5>3, B1:B5)"), EvalResult::Number(0.0));
+        assert_eq!(eval_str(&grid, "SUMPRODUCT(C1:C2, A1:A2)"), EvalResult::Number(0.0));
+        // Sizes must match
+        assert_eq!(eval_str(&grid, "SUMPRODUCT(A1:A5, B1:B4)"), EvalResult::Error(CellError::Value));
+        assert_eq!(eval_str(&grid, "SUMPRODUCT(A1:B2, A1:A4)"), EvalResult::Error(CellError::Value));
+        // A row range matches a row constant of the same width, not a column
+        assert_eq!(eval_str(&grid, "SUMPRODUCT(B1:D1, {1,2,3})"), EvalResult::Number(10.0));
+        assert_eq!(eval_str(&grid, "SUMPRODUCT(A1:B1, {1,2})"), EvalResult::Number(21.0));
+        assert_eq!(eval_str(&grid, "SUMPRODUCT(A1:B1, {1;2})"), EvalResult::Error(CellError::Value));
+        assert_eq!(eval_str(&grid, "SUMPRODUCT(1/(A1:A2-1))"), EvalResult::Error(CellError::Div0));
+
+        assert_eq!(eval_str(&grid, "PRODUCT(A1:A5, 3)"), EvalResult::Number(336.0));
+        assert_eq!(eval_str(&grid, "PRODUCT(A1:A2*3)"), EvalResult::Number(18.0));
+    }
+
+    #[test]
+    fn test_directed_rounding_of_negatives() {
+        let grid = Grid::new();
+        let num = |f: &str| match eval_str(&grid, f) {
+            EvalResult::Number(n) => n,
+            other => panic!("{} gave {:?}", f, other),
+        };
+        // ROUNDUP goes away from zero, ROUNDDOWN/TRUNC toward zero
+        assert_eq!(num("ROUNDUP(2.31, 1)"), 2.4);
+        assert_eq!(num("ROUNDUP(-2.31, 1)"), -2.4);
+        assert_eq!(num("ROUNDDOWN(-2.39, 1)"), -2.3);
+        assert_eq!(num("TRUNC(-8.9)"), -8.0);
+        assert_eq!(num("TRUNC(-8.96, 1)"), -8.9);
+        assert_eq!(num("ROUNDUP(-1234, -2)"), -1300.0);
+        assert_eq!(num("ROUNDDOWN(1299, -2)"), 1200.0);
+        // Binary noise does not add a step: 1.1*10 is 11.000000000000002
+        assert_eq!(num("ROUNDUP(1.1, 1)"), 1.1);
+        assert_eq!(num("ROUNDDOWN(-4.35*10, 0)"), -43.0);
+        assert_eq!(num("ROUNDUP(0, 2)"), 0.0);
+    }
+
+    #[test]
+    fn test_mround_quotient_and_logs() {
+        let grid = Grid::new();
+        assert_eq!(eval_str(&grid, "MROUND(10, 3)"), EvalResult::Number(9.0));
+        assert_eq!(eval_str(&grid, "MROUND(-5, -2)"), EvalResult::Number(-6.0));
+        assert_eq!(eval_str(&grid, "MROUND(1.3, 0.2)"), EvalResult::Number(1.4));
+        assert_eq!(eval_str(&grid, "MROUND(5, -2)"), EvalResult::Error(CellError::Num));
+        assert_eq!(eval_str(&grid, "MROUND(-5, 2)"), EvalResult::Error(CellError::Num));
+        assert_eq!(eval_str(&grid, "MROUND(-10, 3)"), EvalResult::Error(CellError::Num));
+
+        assert_eq!(eval_str(&grid, "QUOTIENT(-10, 3)"), EvalResult::Number(-3.0));
+        assert_eq!(eval_str(&grid, "QUOTIENT(0.6, 0.2)"), EvalResult::Number(3.0));
+        assert_eq!(eval_str(&grid, "QUOTIENT(1, 0)"), EvalResult::Error(CellError::Div0));
+
+        assert_num(&eval_str(&grid, "EXP(1)"), std::f64::consts::E, 1e-12);
+        assert_num(&eval_str(&grid, "LN(EXP(2))"), 2.0, 1e-12);
+        assert_num(&eval_str(&grid, "LOG(8, 2)"), 3.0, 1e-12);
+        assert_num(&eval_str(&grid, "LOG(1000)"), 3.0, 1e-12);
+        assert_num(&eval_str(&grid, "LOG10(0.01)"), -2.0, 1e-12);
+        // Domain errors
+        assert_eq!(eval_str(&grid, "LN(0)"), EvalResult::Error(CellError::Num));
+        assert_eq!(eval_str(&grid, "LOG10(-1)"), EvalResult::Error(CellError::Num));
+        assert_eq!(eval_str(&grid, "LOG(10, -2)"), EvalResult::Error(CellError::Num));
+        assert_eq!(eval_str(&grid, "LOG(10, 1)"), EvalResult::Error(CellError::Div0));
+        assert_eq!(eval_str(&grid, "EXP(1000)"), EvalResult::Error(CellError::Num));
+    }
+
+    #[test]
+    fn test_trig_identities() {
+        let grid = Grid::new();
+        let pi = std::f64::consts::PI;
+        assert_eq!(eval_str(&grid, "PI()"), EvalResult::Number(pi));
+        assert_num(&eval_str(&grid, "SIN(PI()/2)"), 1.0, 1e-12);
+        assert_num(&eval_str(&grid, "COS(PI())"), -1.0, 1e-12);
+        assert_num(&eval_str(&grid, "TAN(PI()/4)"), 1.0, 1e-12);
+        assert_num(&eval_str(&grid, "SIN(0.7)^2+COS(0.7)^2"), 1.0, 1e-12);
+        assert_num(&eval_str(&grid, "ASIN(SIN(0.5))"), 0.5, 1e-12);
+        assert_num(&eval_str(&grid, "ACOS(-1)"), pi, 1e-12);
+        assert_num(&eval_str(&grid, "ATAN(1)*4"), pi, 1e-12);
+        // ATAN2 takes x first: the point (-1, 1) lies at 135 degrees
+        assert_num(&eval_str(&grid, "DEGREES(ATAN2(-1, 1))"), 135.0, 1e-9);
+        assert_num(&eval_str(&grid, "ATAN2(1, -1)"), -pi / 4.0, 1e-12);
+        assert_num(&eval_str(&grid, "RADIANS(180)"), pi, 1e-12);
+        assert_num(&eval_str(&grid, "DEGREES(RADIANS(37))"), 37.0, 1e-9);
+        // Domain errors
+        assert_eq!(eval_str(&grid, "ASIN(1.5)"), EvalResult::Error(CellError::Num));
+        assert_eq!(eval_str(&grid, "ACOS(-2)"), EvalResult::Error(CellError::Num));
+        assert_eq!(eval_str(&grid, "ATAN2(0, 0)"), EvalResult::Error(CellError::Div0));
+        assert_eq!(eval_str(&grid, "SIN(2^27)"), EvalResult::Error(CellError::Num));
+        assert!(matches!(eval_str(&grid, "COS(2^27-1)"), EvalResult::Number(_)));
+    }
+
+    #[test]
+    fn test_error_details() {
+        use super::take_error_detail;
+        let mut grid = Grid::new();
+        grid.set_cell(0, 0, Cell::new_number(4.0));
+        grid.set_cell(1, 0, Cell::new_text("abc".to_string()));
+        let mut failed = Cell::new_formula("SQRT(-1)".to_string());
+        failed.value = CellValue::Error(CellError::Value);
+        grid.set_cell(2, 0, failed);
+        let run = |formula: &str| (eval_str(&grid, formula), take_error_detail());
+        let value_err = |detail: &str| (EvalResult::Error(CellError::Value), Some(detail.to_string()));
+
+        assert_eq!(run("SQRT(-4)"), value_err("SQRT argument was negative"));
+        assert_eq!(run("A1/0"), (EvalResult::Error(CellError::Div0), Some("Division by zero".to_string())));
+        assert_eq!(run("A1+A2"), value_err("The + operator needs numbers, got the text \"abc\""));
+        assert_eq!(run("ABS(1, 2)"), value_err("ABS expects 1 argument, got 2"));
+        assert_eq!(run("SUMPRODUCT(B1:B5, B1:B7)"), value_err("SUMPRODUCT range sizes differ: 5x1 vs 7x1"));
+        // The detail is taken once.
+        assert_eq!(take_error_detail(), None);
+
+        // A3 already holds #VALUE!; formulas reading it get no explanation.
+        assert_eq!(run("A3+1"), (EvalResult::Error(CellError::Value), None));
+        assert_eq!(run("SQRT(A3)"), (EvalResult::Error(CellError::Value), None));
+        // An error caught by IFERROR leaves nothing behind...
+        assert_eq!(run("IFERROR(SQRT(-1), 2)"), (EvalResult::Number(2.0), None));
+        // ...and does not displace the error that reaches the result.
+        assert_eq!(run("SQRT(-1)+IFERROR(1/0, 0)"), value_err("SQRT argument was negative"));
+
+        let mut context = MultiSheetContext::new("Sheet1".to_string());
+        context.add_grid("Sheet1".to_string(), &grid);
+        let eval = Evaluator::with_multi_sheet(&grid, context);
+        let result = eval.evaluate(&parser::parse("SUM(Summary!A1:A3)").unwrap());
+        assert_eq!(result, EvalResult::Error(CellError::Ref));
+        assert_eq!(take_error_detail(), Some("Sheet 'SUMMARY' not found".to_string()));
+        assert_eq!(eval.evaluate(&parser::parse("sheet1!A1").unwrap()), EvalResult::Number(4.0));
+        assert_eq!(take_error_detail(), None);
+    }
+
+    #[test]
+    fn test_evaluation_count_counts_top_level_evaluations() {
+        use super::evaluation_count;
+        let mut grid = Grid::new();
+        grid.set_cell(0, 0, Cell::new_number(2.0));
+        let before = evaluation_count();
+        eval_str(&grid, "SUM(A1, IF(A1>1, A1*2, 0), SQRT(A1))");
+        assert_eq!(evaluation_count() - before, 1);
+        eval_str(&grid, "A1+1");
+        assert_eq!(evaluation_count() - before, 2);
+    }
+
+    #[test]
+    fn test_rand_and_randbetween_bounds() {
+        let grid = Grid::new();
+        for _ in 0..50 {
+            match eval_str(&grid, "RAND()") {
+                EvalResult::Number(n) => assert!((0.0..1.0).contains(&n), "RAND gave {}", n),
+                other => panic!("RAND gave {:?}", other),
+            }
+            match eval_str(&grid, "RANDBETWEEN(-2, 3.5)") {
+                EvalResult::Number(n) => assert!(n.fract() == 0.0 && (-2.0..=3.0).contains(&n)),
+                other => panic!("RANDBETWEEN gave {:?}", other),
+            }
+        }
+        assert_eq!(eval_str(&grid, "RANDBETWEEN(7, 7)"), EvalResult::Number(7.0));
+        // Empty intervals
+        assert_eq!(eval_str(&grid, "RANDBETWEEN(6, 1)"), EvalResult::Error(CellError::Num));
+        assert_eq!(eval_str(&grid, "RANDBETWEEN(1.2, 1.8)"), EvalResult::Error(CellError::Num));
+    }
+
     #[test]
     fn test_trend_linear() {
         let mut grid = Grid::new();
@@ -15977,6 +18384,93 @@ mod tests {
         assert_num(&result, -191.47, 2.0);
     }
 
+    #[test]
+    fn test_time_value_of_money() {
+        // Expected values are Excel's, to 6 decimal places.
+        let grid = Grid::new();
+        let check = |formula: &str, expected: f64| assert_num(&eval_str(&grid, formula), expected, 1e-6);
+        check("PMT(0.08/12, 10, 10000)", -1037.032089);
+        check("PMT(0.08/12, 10, 10000, 0, 1)", -1030.164327);
+        check("PMT(0.06/12, 18*12, 0, 50000)", -129.081161);
+        check("FV(0.06/12, 10, -200, -500, 1)", 2581.403374);
+        check("FV(0.12/12, 12, -1000)", 12682.503013);
+        check("FV(0.11/12, 35, -2000, 0, 1)", 82846.246372);
+        check("PV(0.08/12, 12*20, 500, 0)", -59777.145851);
+        check("NPER(0.12/12, -100, -1000, 10000, 1)", 59.673866);
+        check("NPER(0.12/12, -100, -1000, 10000)", 60.082123);
+        check("NPER(0.12/12, -100, -1000)", -9.578594);
+        check("RATE(4*12, -200, 8000)", 0.007701);
+        check("RATE(10, 0, -1000, 2000)", 0.071773);
+        // A 30-year loan: from the default guess of 10% the balance is ~1e20,
+        // where unguarded Newton steps crawl.
+        check("RATE(360, -1073.64, 200000)", 0.0041667);
+        check("RATE(360, -1073.64, 200000, 0, 0, 0.9)", 0.0041667);
+        check("RATE(60, -500, 20000, -5000)", 0.017942);
+
+        // A zero rate takes the straight-line form instead of dividing by it.
+        check("PMT(0, 10, 1000)", -100.0);
+        check("PMT(0, 10, 1000, 0, 1)", -100.0);
+        check("PV(0, 10, -100)", 1000.0);
+        check("FV(0, 10, -100, -500)", 1500.0);
+        check("NPER(0, -100, 1000)", 10.0);
+        check("RATE(10, -100, 1000)", 0.0);
+        check("RATE(10, -100, 1000, 0, 0, 0)", 0.0);
+
+        assert_eq!(eval_str(&grid, "PMT(0.1, 0, 1000)"), EvalResult::Error(CellError::Num));
+        assert_eq!(eval_str(&grid, "NPER(0, 0, 1000)"), EvalResult::Error(CellError::Num));
+        assert_eq!(eval_str(&grid, "PMT(0.1, 10, 1/0)"), EvalResult::Error(CellError::Div0));
+        assert_eq!(eval_str(&grid, "FV(0.1, 10, -100, \"x\")"), EvalResult::Error(CellError::Value));
+
+        // The iteration cap is configurable; two iterations are not enough here.
+        let mut eval = Evaluator::new(&grid);
+        eval.set_rate_iteration_limit(2);
+        let expr = parser::parse("RATE(4*12, -200, 8000)").unwrap();
+        assert_eq!(eval.evaluate(&expr), EvalResult::Error(CellError::Num));
+        assert_eq!(super::take_error_detail().as_deref(), Some("RATE did not converge after 2 iterations"));
+    }
+
+    #[test]
+    fn test_npv_irr_xirr() {
+        // Irregular cash flows: A1:A6 values (a text note in A4 is skipped),
+        // B1:B5 the dates 2008-01-01, 2008-03-01, 2008-10-30, 2009-02-15, 2009-04-01.
+        let mut grid = Grid::new();
+        let column = |grid: &mut Grid, col: u32, values: &[f64]| {
+            for (row, &v) in values.iter().enumerate() {
+                grid.set_cell(row as u32, col, Cell::new_number(v));
+            }
+        };
+        column(&mut grid, 0, &[-10000.0, 2750.0, 4250.0, 0.0, 3250.0, 2750.0]);
+        grid.set_cell(3, 0, Cell::new_text("note".to_string()));
+        column(&mut grid, 1, &[39448.0, 39508.0, 39751.0, 39859.0, 39904.0]);
+        column(&mut grid, 2, &[-70000.0, 12000.0, 15000.0, 18000.0, 21000.0, 26000.0]);
+        column(&mut grid, 3, &[100.0, 200.0, 300.0]);
+        column(&mut grid, 4, &[-100.0, 300.0, -250.0]);
+        column(&mut grid, 5, &[39508.0, 39448.0]);
+        let check = |formula: &str, expected: f64| assert_num(&eval_str(&grid, formula), expected, 1e-6);
+
+        check("NPV(0.1, -10000, 3000, 4200, 6800)", 1188.443412);
+        check("NPV(0.1, A1:A6)", -10000.0 / 1.1 + 2750.0 / 1.21 + 4250.0 / 1.331 + 3250.0 / 1.4641 + 2750.0 / 1.61051);
+        check("IRR(C1:C5)", -0.021245);
+        check("IRR(C1:C6)", 0.086631);
+        check("IRR(C1:C3, -0.1)", -0.443507);
+        check("XIRR(A1:A6, B1:B5)", 0.373363);
+        check("XIRR(A1:A6, B1:B5, 0.5)", 0.373363);
+
+        // Without both an inflow and an outflow there is no rate to find.
+        let num_err = EvalResult::Error(CellError::Num);
+        assert_eq!(eval_str(&grid, "IRR(D1:D3)"), num_err);
+        assert_eq!(eval_str(&grid, "XIRR(D1:D2, B1:B2)"), num_err);
+        // These flows never discount to zero, so Newton's method cannot settle.
+        let eval = Evaluator::new(&grid);
+        assert_eq!(eval.evaluate(&parser::parse("IRR(E1:E3)").unwrap()), num_err);
+        assert_eq!(super::take_error_detail().as_deref(), Some("IRR did not converge after 100 iterations"));
+        // Dates before the first one and mismatched lengths are rejected.
+        assert_eq!(eval_str(&grid, "XIRR(E1:E2, F1:F2)"), num_err);
+        assert_eq!(eval_str(&grid, "XIRR(A1:A6, B1:B4)"), num_err);
+        assert_eq!(eval_str(&grid, "NPV(-1, 100)"), EvalResult::Error(CellError::Div0));
+        assert_eq!(eval_str(&grid, "NPV(0.1, C1:C2, 1/0)"), EvalResult::Error(CellError::Div0));
+    }
+
     #[test]
     fn test_fvschedule() {
         // FVSCHEDULE(1000, {0.09, 0.11, 0.10})
@@ -16206,11 +18700,8 @@ mod tests {
                         ref_site_id: Default::default(),
             },
         ]);
-        let result = eval.evaluate(&expr);
-        match &result {
-            EvalResult::Array(items) => assert_eq!(items.len(), 3),
-            _ => panic!("Expected Array, got {:?}", result),
-        }
+        let result = eval.evaluate(&expr);
+        assert_eq!(result.spill_dimensions(), (1, 3));
     }
 
     #[test]
@@ -16815,6 +19306,1202 @@ mod tests {
         );
         assert_eq!(eval.evaluate(&expr), EvalResult::Error(CellError::Value));
     }
+
+    // ==================== GET.* / SET.* UI functions ====================
+
+    #[test]
+    fn test_get_dimension_functions_read_injected_maps() {
+        let grid = Grid::new();
+        let ctx = EvalContext {
+            row_heights: Some(HashMap::from([(2u32, 35.0)])),
+            column_widths: Some(HashMap::from([(0u32, 150.0)])),
+            ..Default::default()
+        };
+        let eval = control_eval(&grid, ctx);
+        let run = |formula: &str| eval.evaluate(&parser::parse(formula).unwrap());
+
+        assert_eq!(run("GET.ROW.HEIGHT(3)"), EvalResult::Number(35.0));
+        assert_eq!(run("GET.ROW.HEIGHT(4)"), EvalResult::Number(20.0));
+        assert_eq!(run("GET.COLUMN.WIDTH(1)"), EvalResult::Number(150.0));
+        assert_eq!(run("GET.COLUMN.WIDTH(2)"), EvalResult::Number(100.0));
+        assert_eq!(run("GET.ROW.HEIGHT(0)"), EvalResult::Error(CellError::Value));
+        assert_eq!(run("GET.COLUMN.WIDTH(1.5)"), EvalResult::Error(CellError::Value));
+        assert_eq!(run("GET.ROW.HEIGHT()"), EvalResult::Error(CellError::Value));
+    }
+
+    #[test]
+    fn test_get_cell_fillcolor_reads_style_registry() {
+        let mut grid = Grid::new();
+        let mut styles = StyleRegistry::new();
+        let mut style = crate::style::CellStyle::new();
+        style.fill = crate::style::Fill::Solid { color: crate::theme::ThemeColor::absolute(0x12, 0xab, 0xef) };
+        let mut cell = Cell::new_number(1.0);
+        cell.style_index = styles.get_or_create(style);
+        grid.set_cell(1, 1, cell);
+
+        let mut eval = Evaluator::new(&grid);
+        // No registry attached: empty text rather than an error.
+        assert_eq!(eval.evaluate(&parser::parse("GET.CELL.FILLCOLOR(B2)").unwrap()), EvalResult::Text(String::new()));
+        eval.set_styles(&styles);
+        assert_eq!(eval.evaluate(&parser::parse("GET.CELL.FILLCOLOR(B2)").unwrap()), EvalResult::Text("#12abef".to_string()));
+        assert_eq!(eval.evaluate(&parser::parse("GET.CELL.FILLCOLOR(1)").unwrap()), EvalResult::Error(CellError::Value));
+    }
+
+    /// The same AST evaluates per cell: ROW()/COLUMN() and CELL("address")
+    /// follow the context, including for an evaluator moved between cells.
+    #[test]
+    fn test_same_ast_is_position_dependent() {
+        let grid = Grid::new();
+        let ast = parser::parse("ROW()*100+COLUMN()&CELL(\"address\")").unwrap();
+        let context = || {
+            let mut ms = MultiSheetContext::new("Sheet1".to_string());
+            ms.add_grid("Sheet1".to_string(), &grid);
+            ms
+        };
+        let at_b3 = Evaluator::with_context(&grid, context(), EvalContext::at(2, 1));
+        let at_d10 = Evaluator::with_context(&grid, context(), EvalContext::at(9, 3));
+        assert_eq!(at_b3.evaluate(&ast), EvalResult::Text("302$B$3".to_string()));
+        assert_eq!(at_d10.evaluate(&ast), EvalResult::Text("1004$D$10".to_string()));
+
+        let mut reused = Evaluator::with_multi_sheet(&grid, context());
+        assert_eq!(reused.evaluate(&parser::parse("ROW()").unwrap()), EvalResult::Error(CellError::Value));
+        reused.set_current_cell(2, 1);
+        assert_eq!(reused.evaluate(&ast), EvalResult::Text("302$B$3".to_string()));
+        reused.set_current_cell(9, 3);
+        assert_eq!(reused.evaluate(&ast), EvalResult::Text("1004$D$10".to_string()));
+    }
+
+    #[test]
+    fn test_cell_info_types_and_format() {
+        let mut sheet1 = Grid::new();
+        let mut styles = StyleRegistry::new();
+        let mut percent = crate::style::CellStyle::new();
+        percent.number_format = NumberFormat::Percentage { decimal_places: 1 };
+        let mut cell = Cell::new_number(0.25);
+        cell.style_index = styles.get_or_create(percent);
+        sheet1.set_cell(1, 2, cell);
+        sheet1.set_cell(2, 2, Cell::new_text("label".to_string()));
+        sheet1.set_cell(3, 2, Cell::new_formula("\"x\"".to_string()));
+        let mut data = Grid::new();
+        data.set_cell(0, 0, Cell::new_number(7.0));
+
+        let mut context = MultiSheetContext::new("Sheet1".to_string());
+        context.add_grid("Sheet1".to_string(), &sheet1);
+        context.add_grid("My Data".to_string(), &data);
+        context.sheet_order = vec!["Sheet1".to_string(), "My Data".to_string()];
+        let ctx = EvalContext { current_row: Some(4), current_col: Some(1), ..Default::default() };
+        let mut eval = Evaluator::with_context(&sheet1, context, ctx);
+        let run = |eval: &Evaluator, formula: &str| eval.evaluate(&parser::parse(formula).unwrap());
+
+        assert_eq!(run(&eval, "CELL(\"format\", C2)"), EvalResult::Text("G".to_string()));
+        eval.set_styles(&styles);
+        assert_eq!(run(&eval, "CELL(\"format\", C2)"), EvalResult::Text("P1".to_string()));
+        assert_eq!(run(&eval, "CELL(\"FORMAT\", C2:D9)"), EvalResult::Text("P1".to_string()));
+        assert_eq!(run(&eval, "CELL(\"format\", Z99)"), EvalResult::Text("G".to_string()));
+        assert_eq!(run(&eval, "CELL(\"address\", C2)"), EvalResult::Text("$C$2".to_string()));
+        assert_eq!(run(&eval, "CELL(\"address\", 'My Data'!A1)"), EvalResult::Text("'My Data'!$A$1".to_string()));
+        assert_eq!(run(&eval, "CELL(\"contents\", 'My Data'!A1)"), EvalResult::Number(7.0));
+        assert_eq!(run(&eval, "CELL(\"contents\", C2:C3)"), EvalResult::Number(0.25));
+        assert_eq!(run(&eval, "CELL(\"row\", C2:D9)"), EvalResult::Number(2.0));
+        assert_eq!(run(&eval, "CELL(\"col\", C2)"), EvalResult::Number(3.0));
+        assert_eq!(run(&eval, "CELL(\"type\", C2)"), EvalResult::Text("v".to_string()));
+        assert_eq!(run(&eval, "CELL(\"type\", C3)"), EvalResult::Text("l".to_string()));
+        assert_eq!(run(&eval, "CELL(\"type\", C4)"), EvalResult::Text("v".to_string()));
+        assert_eq!(run(&eval, "CELL(\"type\", C5)"), EvalResult::Text("b".to_string()));
+        // Without a reference: the cell being evaluated (B5)
+        assert_eq!(run(&eval, "CELL(\"address\")"), EvalResult::Text("$B$5".to_string()));
+        assert_eq!(run(&eval, "CELL(\"width\", C2)"), EvalResult::Error(CellError::Value));
+        assert_eq!(run(&eval, "CELL(\"row\", 5)"), EvalResult::Error(CellError::Value));
+        assert_eq!(run(&eval, "CELL(\"row\", Missing!A1)"), EvalResult::Error(CellError::Ref));
+
+        assert_eq!(run(&eval, "INFO(\"numfile\")"), EvalResult::Number(2.0));
+        assert_eq!(run(&eval, "INFO(\"recalc\")"), EvalResult::Text("Automatic".to_string()));
+        assert_eq!(run(&eval, "INFO(\"osversion\")"), EvalResult::Error(CellError::Value));
+    }
+
+    #[test]
+    fn test_set_functions_request_formatting_effects() {
+        use super::{take_ui_effects, UiEffect};
+        use crate::style::Color;
+        let grid = Grid::new();
+        let eval = Evaluator::new(&grid);
+        let run = |formula: &str| (eval.evaluate(&parser::parse(formula).unwrap()), take_ui_effects());
+        let fill = |color: Option<Color>| vec![UiEffect::SetCellFillColor { row: 1, col: 1, color }];
+
+        assert_eq!(
+            run("SET.COLUMN.WIDTH(2, 120)"),
+            (EvalResult::Number(120.0), vec![UiEffect::SetColumnWidth { col: 1, width: 120.0 }])
+        );
+        assert_eq!(
+            run("SET.ROW.HEIGHT(3, 40)"),
+            (EvalResult::Number(40.0), vec![UiEffect::SetRowHeight { row: 2, height: 40.0 }])
+        );
+        // Colors are hex text or RGB components; the result reads like GET.CELL.FILLCOLOR
+        let red = Some(Color::new(255, 0, 0));
+        assert_eq!(run("SET.CELL.FILLCOLOR(B2, \"#f00\")"), (EvalResult::Text("#ff0000".to_string()), fill(red)));
+        assert_eq!(run("SET.CELL.FILLCOLOR(B2, \"#FF0000\")"), (EvalResult::Text("#ff0000".to_string()), fill(red)));
+        assert_eq!(run("SET.CELL.FILLCOLOR(B2, 255, 0, 0)"), (EvalResult::Text("#ff0000".to_string()), fill(red)));
+        assert_eq!(run("SET.CELL.FILLCOLOR(B2, \"\")"), (EvalResult::Text(String::new()), fill(None)));
+        // Effects belong to one evaluation only
+        assert_eq!(run("1+1"), (EvalResult::Number(2.0), vec![]));
+
+        // Bad arguments are #VALUE! and request nothing; errors propagate.
+        for formula in [
+            "SET.COLUMN.WIDTH(0, 120)",
+            "SET.COLUMN.WIDTH(1.5, 120)",
+            "SET.ROW.HEIGHT(2, -5)",
+            "SET.ROW.HEIGHT(2)",
+            "SET.CELL.FILLCOLOR(B2, \"red\")",
+            "SET.CELL.FILLCOLOR(B2, 256, 0, 0)",
+            "SET.CELL.FILLCOLOR(B2, 255, 0)",
+            "SET.CELL.FILLCOLOR(2, \"#ff0000\")",
+        ] {
+            assert_eq!(run(formula), (EvalResult::Error(CellError::Value), vec![]), "{}", formula);
+        }
+        assert_eq!(run("SET.ROW.HEIGHT(1/0, 40)"), (EvalResult::Error(CellError::Div0), vec![]));
+    }
+
+    /// A1 = 0, A2 empty, A3 = =1+1, A4 = formula returning "", A5 formatted
+    /// but empty, B1 = "x".
+    fn make_info_grid() -> Grid {
+        let mut grid = Grid::new();
+        grid.set_cell(0, 0, Cell::new_number(0.0));
+        grid.set_cell(2, 0, Cell::new_formula_with_ast(parser::parse("1+1").unwrap()));
+        let mut empty_text = Cell::new_formula_with_ast(parser::parse("\"\"").unwrap());
+        empty_text.value = CellValue::Text(String::new());
+        grid.set_cell(3, 0, empty_text);
+        let mut formatted = Cell::new_number(0.0);
+        formatted.value = CellValue::Empty;
+        formatted.style_index = 1;
+        grid.set_cell(4, 0, formatted);
+        grid.set_cell(0, 1, Cell::new_text("x".to_string()));
+        grid
+    }
+
+    fn eval_str(grid: &Grid, formula: &str) -> EvalResult {
+        Evaluator::new(grid).evaluate(&parser::parse(formula).unwrap())
+    }
+
+    #[test]
+    fn test_let_binds_names_in_order() {
+        let grid = Grid::new();
+        assert_eq!(eval_str(&grid, "LET(x, 2, x*3)"), EvalResult::Number(6.0));
+        assert_eq!(eval_str(&grid, "LET(x, 2, y, x+1, x*y)"), EvalResult::Number(6.0));
+        // Names are case-insensitive; inner LETs shadow and then restore
+        assert_eq!(eval_str(&grid, "LET(total, 1, LET(TOTAL, 10, total)+total)"), EvalResult::Number(11.0));
+        // Bindings do not leak out of the LET
+        assert_eq!(eval_str(&grid, "LET(x, 1, x)+x"), EvalResult::Error(CellError::Name));
+    }
+
+    #[test]
+    fn test_let_name_errors() {
+        let grid = Grid::new();
+        // Rebinding within one LET
+        assert_eq!(eval_str(&grid, "LET(x, 1, x, 2, x)"), EvalResult::Error(CellError::Name));
+        // Undefined names, including ones bound only later
+        assert_eq!(eval_str(&grid, "LET(x, 1, y)"), EvalResult::Error(CellError::Name));
+        assert_eq!(eval_str(&grid, "LET(x, y, y, 1, x)"), EvalResult::Error(CellError::Name));
+        // Missing calculation
+        assert_eq!(eval_str(&grid, "LET(x, 1)"), EvalResult::Error(CellError::Value));
+    }
+
+    #[test]
+    fn test_hyperlink_shows_friendly_name_and_requests_link() {
+        use super::{take_ui_effects, UiEffect};
+        let mut grid = Grid::new();
+        grid.set_cell(0, 0, Cell::new_text("https://example.com".to_string()));
+        let ctx = EvalContext { current_row: Some(4), current_col: Some(1), ..Default::default() };
+        let eval = control_eval(&grid, ctx);
+        let run = |formula: &str| (eval.evaluate(&parser::parse(formula).unwrap()), take_ui_effects());
+        let link = |target: &str| vec![UiEffect::SetHyperlink { row: 4, col: 1, target: target.to_string() }];
+
+        assert_eq!(run("HYPERLINK(A1, \"Home\")"), (EvalResult::Text("Home".to_string()), link("https://example.com")));
+        // Without a friendly name the link itself is shown
+        assert_eq!(run("HYPERLINK(\"Sheet2!B3\")"), (EvalResult::Text("Sheet2!B3".to_string()), link("Sheet2!B3")));
+        assert_eq!(run("HYPERLINK(\"#A1\", 42)"), (EvalResult::Number(42.0), link("#A1")));
+        // Errors propagate and request nothing
+        assert_eq!(run("HYPERLINK(1/0, \"x\")"), (EvalResult::Error(CellError::Div0), vec![]));
+        assert_eq!(run("HYPERLINK(A1, 1/0)"), (EvalResult::Error(CellError::Div0), vec![]));
+        assert_eq!(run("HYPERLINK()"), (EvalResult::Error(CellError::Value), vec![]));
+        // Effects belong to one evaluation only
+        assert_eq!(run("1+1"), (EvalResult::Number(2.0), vec![]));
+
+        // Outside a cell there is nothing to link
+        assert_eq!(eval_str(&grid, "HYPERLINK(A1)"), EvalResult::Text("https://example.com".to_string()));
+        assert!(take_ui_effects().is_empty());
+    }
+
+    #[test]
+    fn test_countblank_counts_empty_cells_not_zeros() {
+        let grid = make_info_grid();
+        // A2, A4 ("" result), A5 (formatted), A6 are blank; A1 = 0 and A3 are not.
+        assert_eq!(eval_str(&grid, "COUNTBLANK(A1:A6)"), EvalResult::Number(4.0));
+        assert_eq!(eval_str(&grid, "COUNTBLANK(A1)"), EvalResult::Number(0.0));
+        // 2x2 with one value in each column: B2 and A2 are blank.
+        assert_eq!(eval_str(&grid, "COUNTBLANK(A1:B2)"), EvalResult::Number(2.0));
+        // Reversed and large ranges only look up the used part of the rect.
+        assert_eq!(eval_str(&grid, "COUNTBLANK(C100000:C1)"), EvalResult::Number(100000.0));
+        assert_eq!(eval_str(&grid, "COUNTBLANK(A100000:A1)"), EvalResult::Number(99998.0));
+        // Whole columns and rows count every cell of the sheet, not just the
+        // used range.
+        assert_eq!(eval_str(&grid, "COUNTBLANK(A:A)"), EvalResult::Number(1_048_574.0));
+        assert_eq!(eval_str(&grid, "COUNTBLANK(A:B)"), EvalResult::Number(2.0 * 1_048_576.0 - 3.0));
+        assert_eq!(eval_str(&grid, "COUNTBLANK(1:1)"), EvalResult::Number(16_382.0));
+        assert_eq!(eval_str(&grid, "COUNTBLANK(2:3)"), EvalResult::Number(2.0 * 16_384.0 - 1.0));
+    }
+
+    #[test]
+    fn test_isblank_and_isformula_inspect_the_cell() {
+        let grid = make_info_grid();
+        assert_eq!(eval_str(&grid, "ISBLANK(A1)"), EvalResult::Boolean(false));
+        assert_eq!(eval_str(&grid, "ISBLANK(A2)"), EvalResult::Boolean(true));
+        assert_eq!(eval_str(&grid, "ISBLANK(A4)"), EvalResult::Boolean(false));
+        assert_eq!(eval_str(&grid, "ISBLANK(A5)"), EvalResult::Boolean(true));
+
+        assert_eq!(eval_str(&grid, "ISFORMULA(A3)"), EvalResult::Boolean(true));
+        assert_eq!(eval_str(&grid, "ISFORMULA(A1)"), EvalResult::Boolean(false));
+        assert_eq!(eval_str(&grid, "ISFORMULA(A2)"), EvalResult::Boolean(false));
+        assert_eq!(eval_str(&grid, "ISFORMULA(A3:A4)"), EvalResult::Boolean(true));
+        assert_eq!(eval_str(&grid, "ISFORMULA(1+1)"), EvalResult::Boolean(false));
+    }
+
+    #[test]
+    fn test_formulatext_reads_the_cell_formula() {
+        let grid = make_info_grid();
+        assert_eq!(eval_str(&grid, "FORMULATEXT(A3)"), EvalResult::Text("=1+1".to_string()));
+        assert_eq!(eval_str(&grid, "FORMULATEXT(A3:B9)"), EvalResult::Text("=1+1".to_string()));
+        assert_eq!(eval_str(&grid, "FORMULATEXT(A1)"), EvalResult::Error(CellError::NA));
+        assert_eq!(eval_str(&grid, "FORMULATEXT(A2)"), EvalResult::Error(CellError::NA));
+        assert_eq!(eval_str(&grid, "FORMULATEXT(1+1)"), EvalResult::Error(CellError::Value));
+
+        // Other sheets are read through their own grid
+        let mut data = Grid::new();
+        data.set_cell(1, 1, Cell::new_formula_with_ast(parser::parse("SUM(A1:A3)*2").unwrap()));
+        data.set_cell(0, 0, Cell::new_number(5.0));
+        let mut context = MultiSheetContext::new("Sheet1".to_string());
+        context.add_grid("Sheet1".to_string(), &grid);
+        context.add_grid("My Data".to_string(), &data);
+        let eval = Evaluator::with_context(&grid, context, EvalContext::default());
+        let run = |formula: &str| eval.evaluate(&parser::parse(formula).unwrap());
+        assert_eq!(run("FORMULATEXT('My Data'!B2)"), EvalResult::Text("=SUM(A1:A3)*2".to_string()));
+        assert_eq!(run("ISFORMULA('My Data'!B2)"), EvalResult::Boolean(true));
+        assert_eq!(run("ISFORMULA('My Data'!A1)"), EvalResult::Boolean(false));
+        assert_eq!(run("FORMULATEXT('My Data'!A1)"), EvalResult::Error(CellError::NA));
+        assert_eq!(run("FORMULATEXT(Missing!A1)"), EvalResult::Error(CellError::Ref));
+        assert_eq!(run("ISFORMULA(Missing!A1)"), EvalResult::Error(CellError::Ref));
+    }
+
+    #[test]
+    fn test_array_constants_evaluate_to_shaped_arrays() {
+        let grid = Grid::new();
+        let row = eval_str(&grid, "{1,2,3}");
+        assert_eq!(row.spill_dimensions(), (1, 3));
+        assert_eq!(eval_str(&grid, "{1;2;3}").spill_dimensions(), (3, 1));
+        let square = eval_str(&grid, "{1,\"b\";TRUE,-4}");
+        assert_eq!(square.spill_dimensions(), (2, 2));
+        assert_eq!(square.broadcast_at(1, 0), EvalResult::Boolean(true));
+        assert_eq!(square.broadcast_at(1, 1), EvalResult::Number(-4.0));
+
+        assert_eq!(eval_str(&grid, "SUM({1,2,3})"), EvalResult::Number(6.0));
+        assert_eq!(eval_str(&grid, "SUM({1,TRUE;\"2\",4})"), EvalResult::Number(5.0));
+        assert_eq!(
+            eval_str(&grid, "XLOOKUP(2,{1,2,3},{\"a\",\"b\",\"c\"})"),
+            EvalResult::Text("b".to_string())
+        );
+        assert_eq!(
+            crate::ast_render::render_formula(&parser::parse("=SUM({1,2;3,\"x\"})").unwrap()),
+            "SUM({1,2;3,\"x\"})"
+        );
+
+        // A row constant lines up with a row range element by element
+        let mut grid = Grid::new();
+        grid.set_cell(0, 0, Cell::new_number(10.0));
+        assert_eq!(eval_str(&grid, "SUM({1,2,3}*A1:C1)"), EvalResult::Number(10.0));
+        assert_eq!(
+            eval_str(&grid, "{1,2,3}*A1:C1"),
+            EvalResult::Array(vec![EvalResult::Array(vec![
+                EvalResult::Number(10.0),
+                EvalResult::Number(0.0),
+                EvalResult::Number(0.0),
+            ])])
+        );
+        assert_eq!(eval_str(&grid, "COLUMNS(A1:C1*1)"), EvalResult::Number(3.0));
+        assert_eq!(eval_str(&grid, "ROWS(A1:C1*1)"), EvalResult::Number(1.0));
+    }
+
+    #[test]
+    fn test_percent_and_unary_plus_operators() {
+        let mut grid = Grid::new();
+        grid.set_cell(0, 0, Cell::new_number(50.0));
+        grid.set_cell(0, 1, Cell::new_text("abc".to_string()));
+        assert_eq!(eval_str(&grid, "A1*10%"), EvalResult::Number(5.0));
+        assert_eq!(eval_str(&grid, "50%+1"), EvalResult::Number(1.5));
+        assert_eq!(eval_str(&grid, "2^10%"), eval_str(&grid, "2^0.1"));
+        assert_eq!(eval_str(&grid, "(-8)^0.5"), EvalResult::Error(CellError::Num));
+        assert_eq!(eval_str(&grid, "10^400"), EvalResult::Error(CellError::Num));
+        assert_eq!(eval_str(&grid, "0^-1"), EvalResult::Error(CellError::Div0));
+        assert_eq!(eval_str(&grid, "0^0"), EvalResult::Error(CellError::Num));
+        assert_eq!(eval_str(&grid, "POWER(-8, 0.5)"), EvalResult::Error(CellError::Num));
+        assert_eq!(eval_str(&grid, "POWER(0, -1)"), EvalResult::Error(CellError::Div0));
+        assert_eq!(eval_str(&grid, "POWER(0, 0)"), EvalResult::Error(CellError::Num));
+        assert_eq!(eval_str(&grid, "-A1%"), EvalResult::Number(-0.5));
+        assert_eq!(eval_str(&grid, "+A1"), EvalResult::Number(50.0));
+        assert_eq!(eval_str(&grid, "+B1"), EvalResult::Text("abc".to_string()));
+        assert_eq!(eval_str(&grid, "B1%"), EvalResult::Error(CellError::Value));
+        assert_eq!(eval_str(&grid, "SUM({100,250}%)"), EvalResult::Number(3.5));
+
+        let render = |formula: &str| crate::ast_render::render_formula(&parser::parse(formula).unwrap());
+        assert_eq!(render("=A1*10%"), "A1*10%");
+        assert_eq!(render("=+A1"), "+A1");
+        assert_eq!(render("=(A1+1)%"), "(A1+1)%");
+        assert_eq!(render("=2^10%"), "2^10%");
+        assert_eq!(render("=A1%^2"), "A1%^2");
+    }
+
+    #[test]
+    fn test_aggregates_skip_text_and_booleans_in_references() {
+        // A1:A6 = 10, TRUE, "5", (blank), 20, #DIV/0!
+        let mut grid = Grid::new();
+        grid.set_cell(0, 0, Cell::new_number(10.0));
+        grid.set_cell(1, 0, Cell::new_boolean(true));
+        grid.set_cell(2, 0, Cell::new_text("5".to_string()));
+        grid.set_cell(4, 0, Cell::new_number(20.0));
+        grid.set_cell(5, 0, Cell { value: CellValue::Error(CellError::Div0), ..Cell::default() });
+        let num = |formula: &str| eval_str(&grid, formula);
+
+        // Referenced values count only when they are numbers (blanks too are skipped)
+        assert_eq!(num("SUM(A1:A5)"), EvalResult::Number(30.0));
+        assert_eq!(num("AVERAGE(A1:A5)"), EvalResult::Number(15.0));
+        assert_eq!(num("COUNT(A1:A5)"), EvalResult::Number(2.0));
+        assert_eq!(num("MIN(A1:A5)"), EvalResult::Number(10.0));
+        assert_eq!(num("MAX(A1:A5)"), EvalResult::Number(20.0));
+        assert_eq!(num("SUM(A2)"), EvalResult::Number(0.0));
+        assert_eq!(num("SUM(A2,A3)"), EvalResult::Number(0.0));
+        assert_eq!(num("MAX(A2:A4)"), EvalResult::Number(0.0));
+        assert_eq!(num("AVERAGE(A2:A4)"), EvalResult::Error(CellError::Div0));
+        assert_eq!(num("SUM(A1:A5&\"\")"), EvalResult::Number(0.0));
+
+        // Scalar arguments are coerced; unconvertible text is #VALUE!
+        assert_eq!(num("SUM(A1:A5,TRUE,\"5\")"), EvalResult::Number(36.0));
+        assert_eq!(num("AVERAGE(A1:A5,TRUE)"), EvalResult::Number(31.0 / 3.0));
+        assert_eq!(num("MIN(A1:A5,TRUE)"), EvalResult::Number(1.0));
+        assert_eq!(num("MAX(A1:A5,\"25\")"), EvalResult::Number(25.0));
+        assert_eq!(num("SUM(1,\"abc\")"), EvalResult::Error(CellError::Value));
+        assert_eq!(num("AVERAGE(\"x\",1)"), EvalResult::Error(CellError::Value));
+
+        // COUNT coerces the same way but ignores what it cannot count
+        assert_eq!(num("COUNT(A1:A5,TRUE,\"5\",\"x\")"), EvalResult::Number(4.0));
+        assert_eq!(num("COUNT(A1:A6)"), EvalResult::Number(2.0));
+        assert_eq!(num("SUM(A1:A6)"), EvalResult::Error(CellError::Div0));
+    }
+
+    #[test]
+    fn test_isref_is_structural() {
+        let grid = make_info_grid();
+        assert_eq!(eval_str(&grid, "ISREF(A2)"), EvalResult::Boolean(true));
+        assert_eq!(eval_str(&grid, "ISREF(A1:B2)"), EvalResult::Boolean(true));
+        assert_eq!(eval_str(&grid, "ISREF(A:A)"), EvalResult::Boolean(true));
+        assert_eq!(eval_str(&grid, "ISREF(Sheet1:Sheet3!A1)"), EvalResult::Boolean(true));
+        assert_eq!(eval_str(&grid, "ISREF(A1+0)"), EvalResult::Boolean(false));
+        assert_eq!(eval_str(&grid, "ISREF(\"A1\")"), EvalResult::Boolean(false));
+    }
+
+    #[test]
+    fn test_information_functions_classify_values() {
+        let mut grid = make_info_grid();
+        grid.set_cell(0, 2, Cell::new_boolean(true));
+        grid.set_cell(1, 2, Cell::new_number(-3.7));
+        grid.set_cell(2, 2, Cell::new_text("4".to_string()));
+        let value = |formula: &str| eval_str(&grid, formula);
+        let t = EvalResult::Boolean(true);
+        let f = EvalResult::Boolean(false);
+
+        // Untouched (A2), empty-string (A4) and zero (A1) cells: ISNONTEXT
+        // agrees with ISBLANK wherever a cell is blank, and "" is text.
+        assert_eq!((value("ISBLANK(A2)"), value("ISNONTEXT(A2)")), (t.clone(), t.clone()));
+        assert_eq!((value("ISBLANK(A4)"), value("ISNONTEXT(A4)")), (f.clone(), f.clone()));
+        assert_eq!((value("ISBLANK(A1)"), value("ISNONTEXT(A1)")), (f.clone(), t.clone()));
+        assert_eq!(value("ISNONTEXT(B1)"), f);
+        assert_eq!(value("ISNONTEXT(1/0)"), t);
+
+        assert_eq!(value("ISLOGICAL(C1)"), t);
+        assert_eq!(value("ISLOGICAL(A1)"), f);
+        assert_eq!(value("ISLOGICAL(\"TRUE\")"), f);
+
+        // ISEVEN/ISODD truncate, coerce numeric text, count a blank as 0 and
+        // reject logicals and other text.
+        assert_eq!(value("ISODD(C2)"), t);
+        assert_eq!(value("ISEVEN(2.9)"), t);
+        assert_eq!(value("ISEVEN(C3)"), t);
+        assert_eq!(value("ISEVEN(A2)"), t);
+        assert_eq!(value("ISEVEN(C1)"), EvalResult::Error(CellError::Value));
+        assert_eq!(value("ISODD(B1)"), EvalResult::Error(CellError::Value));
+        assert_eq!(value("ISODD(A4)"), EvalResult::Error(CellError::Value));
+        assert_eq!(value("ISEVEN(NA())"), EvalResult::Error(CellError::NA));
+
+        assert_eq!(value("N(C2)"), EvalResult::Number(-3.7));
+        assert_eq!(value("N(C1)"), EvalResult::Number(1.0));
+        assert_eq!(value("N(C3)"), EvalResult::Number(0.0));
+        assert_eq!(value("N(A2)"), EvalResult::Number(0.0));
+        assert_eq!(value("N(NA())"), EvalResult::Error(CellError::NA));
+
+        assert_eq!(value("T(B1)"), EvalResult::Text("x".to_string()));
+        assert_eq!(value("T(A1)"), EvalResult::Text(String::new()));
+        assert_eq!(value("T(A2)"), EvalResult::Text(String::new()));
+        assert_eq!(value("T(C1)"), EvalResult::Text(String::new()));
+
+        assert_eq!(value("TYPE(A1)"), EvalResult::Number(1.0));
+        assert_eq!(value("TYPE(A2)"), EvalResult::Number(1.0));
+        assert_eq!(value("TYPE(B1)"), EvalResult::Number(2.0));
+        assert_eq!(value("TYPE(A4)"), EvalResult::Number(2.0));
+        assert_eq!(value("TYPE(C1)"), EvalResult::Number(4.0));
+        assert_eq!(value("TYPE(1/0)"), EvalResult::Number(16.0));
+        assert_eq!(value("TYPE(A1:B2)"), EvalResult::Number(64.0));
+    }
+
+    #[test]
+    fn test_quote_prefixed_text_coerces_in_arithmetic() {
+        let mut grid = Grid::new();
+        grid.set_cell(0, 0, Cell::new_quoted_text("00123".to_string()));
+        assert_eq!(eval_str(&grid, "A1"), EvalResult::Text("00123".to_string()));
+        assert_eq!(eval_str(&grid, "A1+0"), EvalResult::Number(123.0));
+        assert_eq!(eval_str(&grid, "A1*2"), EvalResult::Number(246.0));
+    }
+
+    #[test]
+    fn test_roman_forms_and_arabic_round_trip() {
+        let grid = Grid::new();
+        assert_eq!(eval_str(&grid, "ROMAN(1994)"), EvalResult::Text("MCMXCIV".to_string()));
+        let forms = ["CDXCIX", "LDVLIV", "XDIX", "VDIV", "ID"];
+        for (form, expected) in forms.iter().enumerate() {
+            let formula = format!("ROMAN(499, {})", form);
+            assert_eq!(eval_str(&grid, &formula), EvalResult::Text(expected.to_string()), "{}", formula);
+        }
+        assert_eq!(eval_str(&grid, "ROMAN(499, FALSE)"), EvalResult::Text("ID".to_string()));
+        assert_eq!(eval_str(&grid, "ROMAN(0)"), EvalResult::Text(String::new()));
+        assert_eq!(eval_str(&grid, "ROMAN(4000)"), EvalResult::Error(CellError::Value));
+        assert_eq!(eval_str(&grid, "ROMAN(-1)"), EvalResult::Error(CellError::Value));
+        assert_eq!(eval_str(&grid, "ROMAN(10, 5)"), EvalResult::Error(CellError::Value));
+
+        for n in [1, 4, 9, 14, 40, 90, 400, 499, 999, 1994, 2024, 3999] {
+            for form in 0..=4 {
+                let formula = format!("ARABIC(ROMAN({}, {}))", n, form);
+                assert_eq!(eval_str(&grid, &formula), EvalResult::Number(n as f64), "{}", formula);
+            }
+        }
+        assert_eq!(eval_str(&grid, "ARABIC(\"mxmvii\")"), EvalResult::Number(1997.0));
+        assert_eq!(eval_str(&grid, "ARABIC(\"ABC\")"), EvalResult::Error(CellError::Value));
+    }
+
+    #[test]
+    fn test_base_and_decimal() {
+        let grid = Grid::new();
+        assert_eq!(eval_str(&grid, "BASE(255, 16, 4)"), EvalResult::Text("00FF".to_string()));
+        assert_eq!(eval_str(&grid, "BASE(7, 2)"), EvalResult::Text("111".to_string()));
+        assert_eq!(eval_str(&grid, "BASE(0, 36)"), EvalResult::Text("0".to_string()));
+        assert_eq!(eval_str(&grid, "BASE(-1, 2)"), EvalResult::Error(CellError::Num));
+        assert_eq!(eval_str(&grid, "BASE(10, 37)"), EvalResult::Error(CellError::Num));
+        assert_eq!(eval_str(&grid, "BASE(\"x\", 2)"), EvalResult::Error(CellError::Value));
+
+        assert_eq!(eval_str(&grid, "DECIMAL(\"ZZ\", 36)"), EvalResult::Number(1295.0));
+        assert_eq!(eval_str(&grid, "DECIMAL(\"ff\", 16)"), EvalResult::Number(255.0));
+        assert_eq!(eval_str(&grid, "DECIMAL(\"102\", 2)"), EvalResult::Error(CellError::Num));
+        assert_eq!(eval_str(&grid, "DECIMAL(\"-1\", 10)"), EvalResult::Error(CellError::Num));
+        assert_eq!(eval_str(&grid, "DECIMAL(\"1\", 1)"), EvalResult::Error(CellError::Num));
+        assert_eq!(eval_str(&grid, "DECIMAL(BASE(123456, 7), 7)"), EvalResult::Number(123456.0));
+        assert_eq!(eval_str(&grid, "DECIMAL(\"\", 2)"), EvalResult::Number(0.0));
+        assert_eq!(eval_str(&grid, "DECIMAL(\"1\", 37)"), EvalResult::Error(CellError::Num));
+        assert_eq!(eval_str(&grid, "DECIMAL(REPT(\"Z\", 11), 36)"), EvalResult::Error(CellError::Num));
+    }
+
+    #[test]
+    fn test_gcd_and_lcm_over_ranges() {
+        let mut grid = Grid::new();
+        for (row, n) in [12.0, 18.0, 30.5].iter().enumerate() {
+            grid.set_cell(row as u32, 0, Cell::new_number(*n));
+        }
+        assert_eq!(eval_str(&grid, "GCD(A1:A3)"), EvalResult::Number(6.0));
+        assert_eq!(eval_str(&grid, "GCD(A1:A3, 4)"), EvalResult::Number(2.0));
+        assert_eq!(eval_str(&grid, "LCM(A1:A3)"), EvalResult::Number(180.0));
+        assert_eq!(eval_str(&grid, "LCM({4,6}, 10)"), EvalResult::Number(60.0));
+        assert_eq!(eval_str(&grid, "GCD(0, 7)"), EvalResult::Number(7.0));
+        assert_eq!(eval_str(&grid, "LCM(0, 7)"), EvalResult::Number(0.0));
+        assert_eq!(eval_str(&grid, "GCD(-4, 2)"), EvalResult::Error(CellError::Num));
+        assert_eq!(eval_str(&grid, "LCM(4, \"x\")"), EvalResult::Error(CellError::Value));
+        assert_eq!(eval_str(&grid, "GCD(2^53, 2)"), EvalResult::Error(CellError::Num));
+        assert_eq!(eval_str(&grid, "GCD(1/0, 2)"), EvalResult::Error(CellError::Div0));
+        // Largest LCM below 2^53, then one past it
+        assert_eq!(eval_str(&grid, "LCM(2^52, 2)"), EvalResult::Number(4503599627370496.0));
+        assert_eq!(eval_str(&grid, "LCM(2^52, 3)"), EvalResult::Error(CellError::Num));
+    }
+
+    #[test]
+    fn test_fact_combin_permut_limits() {
+        let grid = Grid::new();
+        assert_eq!(eval_str(&grid, "FACT(5.9)"), EvalResult::Number(120.0));
+        assert_eq!(eval_str(&grid, "FACT(0)"), EvalResult::Number(1.0));
+        assert_eq!(eval_str(&grid, "FACT(-1)"), EvalResult::Error(CellError::Num));
+        // 170! is the last factorial an f64 holds; 21! no longer fits a u64
+        assert_eq!(eval_str(&grid, "FACT(21)"), EvalResult::Number(51090942171709440000.0));
+        assert!(matches!(eval_str(&grid, "FACT(170)"), EvalResult::Number(n) if (n / 7.257415615307994e306 - 1.0).abs() < 1e-12));
+        assert_eq!(eval_str(&grid, "FACT(171)"), EvalResult::Error(CellError::Num));
+
+        assert_eq!(eval_str(&grid, "COMBIN(8, 2)"), EvalResult::Number(28.0));
+        assert_eq!(eval_str(&grid, "COMBIN(8.7, 2.2)"), EvalResult::Number(28.0));
+        assert_eq!(eval_str(&grid, "COMBIN(0, 0)"), EvalResult::Number(1.0));
+        assert_eq!(eval_str(&grid, "COMBIN(50, 25)"), EvalResult::Number(126410606437752.0));
+        assert!(matches!(eval_str(&grid, "COMBIN(60, 30)"), EvalResult::Number(n) if (n / 1.1826458156486142e17 - 1.0).abs() < 1e-15));
+        assert_eq!(eval_str(&grid, "COMBIN(2, 3)"), EvalResult::Error(CellError::Num));
+        assert_eq!(eval_str(&grid, "COMBIN(-1, 0)"), EvalResult::Error(CellError::Num));
+        // Far past 170 the result still fits, up to C(1029, 514) ~ 1.4e308
+        assert!(matches!(eval_str(&grid, "COMBIN(1000, 500)"), EvalResult::Number(n) if (n / 2.7028824094543655e299 - 1.0).abs() < 1e-9));
+        assert!(matches!(eval_str(&grid, "COMBIN(1029, 514)"), EvalResult::Number(n) if n.is_finite()));
+        assert_eq!(eval_str(&grid, "COMBIN(1030, 515)"), EvalResult::Error(CellError::Num));
+        assert_eq!(eval_str(&grid, "COMBINA(4, 3)"), EvalResult::Number(20.0));
+
+        assert_eq!(eval_str(&grid, "PERMUT(100, 3)"), EvalResult::Number(970200.0));
+        assert_eq!(eval_str(&grid, "PERMUT(3, 0)"), EvalResult::Number(1.0));
+        assert_eq!(eval_str(&grid, "PERMUT(2, 3)"), EvalResult::Error(CellError::Num));
+        let expected: f64 = (9924..=10000).map(f64::from).product();
+        assert!(matches!(eval_str(&grid, "PERMUT(10000, 77)"), EvalResult::Number(n) if (n / expected - 1.0).abs() < 1e-12));
+        assert_eq!(eval_str(&grid, "PERMUT(10000, 78)"), EvalResult::Error(CellError::Num));
+    }
+
+    /// DECIMAL(BASE(n, r), r) == n over every radix, for small numbers, powers
+    /// of the radix and a spread of pseudo-random values up to 2^53.
+    #[test]
+    fn test_base_decimal_round_trip() {
+        let grid = Grid::new();
+        let mut seed: u64 = 0x9E37_79B9_7F4A_7C15;
+        for radix in 2..=36u64 {
+            let mut numbers = vec![0, 1, radix - 1, radix, radix * radix - 1, (1 << 53) - 1];
+            for _ in 0..20 {
+                // xorshift64
+                seed ^= seed << 13;
+                seed ^= seed >> 7;
+                seed ^= seed << 17;
+                numbers.push(seed >> (11 + seed % 40));
+            }
+            for n in numbers {
+                let formula = format!("DECIMAL(BASE({}, {}), {})", n, radix, radix);
+                assert_eq!(eval_str(&grid, &formula), EvalResult::Number(n as f64), "{}", formula);
+                let lower = format!("DECIMAL(LOWER(BASE({}, {})), {})", n, radix, radix);
+                assert_eq!(eval_str(&grid, &lower), EvalResult::Number(n as f64), "{}", lower);
+            }
+        }
+    }
+
+    #[test]
+    fn test_convert_uses_unit_registry() {
+        let grid = Grid::new();
+        assert_eq!(eval_str(&grid, "CONVERT(1, \"km\", \"m\")"), EvalResult::Number(1000.0));
+        assert_eq!(eval_str(&grid, "CONVERT(100, \"C\", \"F\")"), EvalResult::Number(212.0));
+        assert_eq!(eval_str(&grid, "CONVERT(1, \"m\", \"kg\")"), EvalResult::Error(CellError::NA));
+        assert_eq!(eval_str(&grid, "CONVERT(1, \"kft\", \"m\")"), EvalResult::Error(CellError::NA));
+    }
+
+    #[test]
+    fn test_tocol_ignores_blanks_and_errors() {
+        let mut grid = Grid::new();
+        grid.set_cell(0, 0, Cell::new_number(1.0));
+        grid.set_cell(0, 1, Cell::new_text("x".to_string()));
+        grid.set_cell(1, 1, Cell::new_number(0.0));
+        grid.set_cell(2, 0, Cell::new_number(3.0));
+        // A1:B3 holds 1, "x", 0 and 3 around two blanks.
+        assert_eq!(eval_str(&grid, "COUNTA(TOCOL(A1:B3))"), EvalResult::Number(6.0));
+        assert_eq!(eval_str(&grid, "COUNTA(TOCOL(A1:B3, 1))"), EvalResult::Number(4.0));
+        assert_eq!(eval_str(&grid, "INDEX(TOCOL(A1:B3, 1), 2)"), EvalResult::Text("x".to_string()));
+        assert_eq!(eval_str(&grid, "INDEX(TOCOL(A1:B3, 1, TRUE), 2)"), EvalResult::Number(3.0));
+        assert_eq!(eval_str(&grid, "TOCOL(A1:B3, 4)"), EvalResult::Error(CellError::Value));
+
+        let mut cell = Cell::new_number(0.0);
+        cell.value = CellValue::Error(CellError::NA);
+        grid.set_cell(2, 1, cell);
+        assert_eq!(eval_str(&grid, "COUNTA(TOCOL(A1:B3, 3))"), EvalResult::Number(4.0));
+        assert_eq!(eval_str(&grid, "TOROW(A1:B3, 3)").spill_dimensions(), (1, 4));
+    }
+
+    #[test]
+    fn test_wraprows_pads_last_row() {
+        let mut grid = Grid::new();
+        for i in 0..5 {
+            grid.set_cell(i, 0, Cell::new_number((i + 1) as f64));
+        }
+        assert_eq!(eval_str(&grid, "WRAPROWS(A1:A5, 2, 0)").spill_dimensions(), (3, 2));
+        assert_eq!(eval_str(&grid, "INDEX(WRAPROWS(A1:A5, 2, 0), 3, 1)"), EvalResult::Number(5.0));
+        assert_eq!(eval_str(&grid, "INDEX(WRAPROWS(A1:A5, 2, 0), 3, 2)"), EvalResult::Number(0.0));
+        assert_eq!(eval_str(&grid, "INDEX(WRAPROWS(A1:A5, 2), 3, 2)"), EvalResult::Error(CellError::NA));
+        assert_eq!(eval_str(&grid, "INDEX(WRAPCOLS(A1:A5, 2, 0), 1, 3)"), EvalResult::Number(5.0));
+        assert_eq!(eval_str(&grid, "WRAPROWS(A1:A3, 5)").spill_dimensions(), (1, 5));
+        assert_eq!(eval_str(&grid, "WRAPROWS(A1:B2, 2)"), EvalResult::Error(CellError::Value));
+        assert_eq!(eval_str(&grid, "WRAPROWS(A1:A3, 0)"), EvalResult::Error(CellError::Num));
+        assert_eq!(eval_str(&grid, "WRAPCOLS(A1:A3, -2)"), EvalResult::Error(CellError::Num));
+        assert_eq!(eval_str(&grid, "WRAPCOLS(A1:A3, \"x\")"), EvalResult::Error(CellError::Value));
+    }
+
+    #[test]
+    fn test_vstack_and_hstack_pad_ragged_arrays() {
+        let mut grid = Grid::new();
+        // A1:C1 = 1,2,3; A2:B3 = 4,5 / 6,7
+        for (i, v) in [1.0, 2.0, 3.0].into_iter().enumerate() {
+            grid.set_cell(0, i as u32, Cell::new_number(v));
+        }
+        for (r, c, v) in [(1, 0, 4.0), (1, 1, 5.0), (2, 0, 6.0), (2, 1, 7.0)] {
+            grid.set_cell(r, c, Cell::new_number(v));
+        }
+        let stacked = eval_str(&grid, "VSTACK(A1:C1, A2:B3)");
+        assert_eq!(stacked.spill_dimensions(), (3, 3));
+        assert_eq!(eval_str(&grid, "INDEX(VSTACK(A1:C1, A2:B3), 1, 3)"), EvalResult::Number(3.0));
+        assert_eq!(eval_str(&grid, "INDEX(VSTACK(A1:C1, A2:B3), 3, 2)"), EvalResult::Number(7.0));
+        assert_eq!(eval_str(&grid, "INDEX(VSTACK(A1:C1, A2:B3), 2, 3)"), EvalResult::Error(CellError::NA));
+
+        assert_eq!(eval_str(&grid, "HSTACK(A1:C1, A2:B3)").spill_dimensions(), (2, 5));
+        assert_eq!(eval_str(&grid, "INDEX(HSTACK(A1:C1, A2:B3), 1, 5)"), EvalResult::Number(5.0));
+        assert_eq!(eval_str(&grid, "INDEX(HSTACK(A1:C1, A2:B3), 2, 1)"), EvalResult::Error(CellError::NA));
+    }
+
+    #[test]
+    fn test_take_and_drop_count_from_either_end() {
+        let mut grid = Grid::new();
+        for r in 0..4 {
+            grid.set_cell(r, 0, Cell::new_number((r + 1) as f64));
+            grid.set_cell(r, 1, Cell::new_number((r + 1) as f64 * 10.0));
+        }
+        assert_eq!(eval_str(&grid, "TAKE(A1:B4, -2)").spill_dimensions(), (2, 2));
+        assert_eq!(eval_str(&grid, "INDEX(TAKE(A1:B4, -2), 1, 1)"), EvalResult::Number(3.0));
+        assert_eq!(eval_str(&grid, "INDEX(TAKE(A1:B4, -2), 2, 2)"), EvalResult::Number(40.0));
+        assert_eq!(eval_str(&grid, "TAKE(A1:B4, 1, -1)"), EvalResult::Number(10.0));
+        assert_eq!(eval_str(&grid, "TAKE(A1:B4, 9)").spill_dimensions(), (4, 2));
+        assert_eq!(eval_str(&grid, "TAKE(A1:B4, 0)"), EvalResult::Error(CellError::Value));
+
+        assert_eq!(eval_str(&grid, "INDEX(DROP(A1:B4, 1), 1, 2)"), EvalResult::Number(20.0));
+        assert_eq!(eval_str(&grid, "DROP(A1:B4, -3, 1)"), EvalResult::Number(10.0));
+        assert_eq!(eval_str(&grid, "DROP(A1:B4, 4)"), EvalResult::Error(CellError::Value));
+    }
+
+    #[test]
+    fn test_sumif_and_sumifs_criteria_and_alignment() {
+        let mut grid = Grid::new();
+        // A1:A10 = 10, 20, ..., 100; B1:B10 = 1..10; C1:C10 = Apple/Banana alternating
+        for r in 0..10 {
+            grid.set_cell(r, 0, Cell::new_number((r + 1) as f64 * 10.0));
+            grid.set_cell(r, 1, Cell::new_number((r + 1) as f64));
+            let fruit = if r % 2 == 0 { "Apple" } else { "Banana" };
+            grid.set_cell(r, 2, Cell::new_text(fruit.to_string()));
+        }
+        grid.set_cell(0, 4, Cell::new_number(50.0));
+
+        // 50..100 pair with 5..10.
+        assert_eq!(eval_str(&grid, "SUMIF(A1:A10, \">=50\", B1:B10)"), EvalResult::Number(45.0));
+        assert_eq!(eval_str(&grid, "SUMIF(A1:A10, \"<>50\")"), EvalResult::Number(500.0));
+        assert_eq!(eval_str(&grid, "SUMIF(A1:A10, E1, B1:B10)"), EvalResult::Number(5.0));
+        assert_eq!(eval_str(&grid, "SUMIF(C1:C10, \"apple\", B1:B10)"), EvalResult::Number(25.0));
+        assert_eq!(eval_str(&grid, "SUMIF(C1:C10, \"B*\", B1:B10)"), EvalResult::Number(30.0));
+        assert_eq!(
+            eval_str(&grid, "SUMIFS(B1:B10, A1:A10, \">30\", C1:C10, \"Apple\")"),
+            EvalResult::Number(21.0)
+        );
+
+        // Ranges that do not pair element-by-element.
+        assert_eq!(eval_str(&grid, "SUMIF(A1:A10, \">=50\", B1:B9)"), EvalResult::Error(CellError::Value));
+        assert_eq!(
+            eval_str(&grid, "SUMIFS(B1:B10, A1:A9, \">30\")"),
+            EvalResult::Error(CellError::Value)
+        );
+        assert_eq!(
+            eval_str(&grid, "SUMIFS(B1:B10, A1:A10, \">30\", A1:J1, \">0\")"),
+            EvalResult::Error(CellError::Value)
+        );
+    }
+
+    #[test]
+    fn test_countif_blanks_wildcards_and_countifs_alignment() {
+        let mut grid = Grid::new();
+        // A1:A6 = 0, (blank), apple, a*b, 5, (blank); B1:B6 = 1..6
+        grid.set_cell(0, 0, Cell::new_number(0.0));
+        grid.set_cell(2, 0, Cell::new_text("apple".to_string()));
+        grid.set_cell(3, 0, Cell::new_text("a*b".to_string()));
+        grid.set_cell(4, 0, Cell::new_number(5.0));
+        for r in 0..6 {
+            grid.set_cell(r, 1, Cell::new_number((r + 1) as f64));
+        }
+
+        // Blanks match "" and "<>x" but are not zeros.
+        assert_eq!(eval_str(&grid, "COUNTIF(A1:A6, 0)"), EvalResult::Number(1.0));
+        assert_eq!(eval_str(&grid, "COUNTIF(A1:A6, \"\")"), EvalResult::Number(2.0));
+        assert_eq!(eval_str(&grid, "COUNTIF(A1:A6, \"=\")"), EvalResult::Number(2.0));
+        assert_eq!(eval_str(&grid, "COUNTIF(A1:A6, \"<>\")"), EvalResult::Number(4.0));
+        assert_eq!(eval_str(&grid, "COUNTIF(A1:A6, \"<>5\")"), EvalResult::Number(3.0));
+        assert_eq!(eval_str(&grid, "COUNTIF(A1:A6, \"<1\")"), EvalResult::Number(1.0));
+
+        // Wildcards, including the = and <> forms and the ~ escape.
+        assert_eq!(eval_str(&grid, "COUNTIF(A1:A6, \"a*\")"), EvalResult::Number(2.0));
+        assert_eq!(eval_str(&grid, "COUNTIF(A1:A6, \"=?pple\")"), EvalResult::Number(1.0));
+        assert_eq!(eval_str(&grid, "COUNTIF(A1:A6, \"a~*b\")"), EvalResult::Number(1.0));
+        assert_eq!(eval_str(&grid, "COUNTIF(A1:A6, \"<>a*\")"), EvalResult::Number(4.0));
+        assert_eq!(eval_str(&grid, "COUNTIF(A1:A6, \"*\")"), EvalResult::Number(4.0));
+
+        assert_eq!(
+            eval_str(&grid, "COUNTIFS(A1:A6, \"\", B1:B6, \">3\")"),
+            EvalResult::Number(1.0)
+        );
+        assert_eq!(eval_str(&grid, "COUNTIFS(A1:A6, \"<>\", B1:B5, \">0\")"), EvalResult::Error(CellError::Value));
+        assert_eq!(eval_str(&grid, "COUNTIFS(A1:A6, \"<>\", A1:B3, \">0\")"), EvalResult::Error(CellError::Value));
+        assert_eq!(eval_str(&grid, "COUNTBLANK(A1:B6)"), EvalResult::Number(2.0));
+    }
+
+    #[test]
+    fn test_averageif_maxifs_minifs_criteria() {
+        let mut grid = Grid::new();
+        // A1:A5 = x, y, x, (blank), x; B1:B5 = 10, 20, 30, 40, (blank)
+        for (r, t) in [(0, "x"), (1, "y"), (2, "x"), (4, "x")] {
+            grid.set_cell(r, 0, Cell::new_text(t.to_string()));
+        }
+        for r in 0..4 {
+            grid.set_cell(r, 1, Cell::new_number(((r + 1) * 10) as f64));
+        }
+
+        assert_eq!(eval_str(&grid, "AVERAGEIF(A1:A5, \"x\", B1:B5)"), EvalResult::Number(20.0));
+        assert_eq!(eval_str(&grid, "AVERAGEIF(B1:B5, \">15\")"), EvalResult::Number(30.0));
+        assert_eq!(eval_str(&grid, "AVERAGEIF(A1:A5, \"\", B1:B5)"), EvalResult::Number(40.0));
+        assert_eq!(eval_str(&grid, "AVERAGEIF(A1:A5, \"z\", B1:B5)"), EvalResult::Error(CellError::Div0));
+        assert_eq!(
+            eval_str(&grid, "AVERAGEIFS(B1:B5, A1:A5, \"x\", B1:B5, \">10\")"),
+            EvalResult::Number(30.0)
+        );
+        assert_eq!(eval_str(&grid, "AVERAGEIFS(B1:B5, A1:A5, \"z\")"), EvalResult::Error(CellError::Div0));
+
+        assert_eq!(eval_str(&grid, "MAXIFS(B1:B5, A1:A5, \"x\")"), EvalResult::Number(30.0));
+        assert_eq!(eval_str(&grid, "MINIFS(B1:B5, A1:A5, \"<>x\")"), EvalResult::Number(20.0));
+        assert_eq!(eval_str(&grid, "MAXIFS(B1:B5, A1:A5, \"x*\", B1:B5, \"<25\")"), EvalResult::Number(10.0));
+        // No matches gives 0, not an error.
+        assert_eq!(eval_str(&grid, "MAXIFS(B1:B5, A1:A5, \"z\")"), EvalResult::Number(0.0));
+        assert_eq!(eval_str(&grid, "MINIFS(B1:B5, A1:A5, \"z\")"), EvalResult::Number(0.0));
+
+        // Criteria ranges must pair element-by-element with the target.
+        assert_eq!(eval_str(&grid, "AVERAGEIF(A1:A5, \"x\", B1:B4)"), EvalResult::Error(CellError::Value));
+        assert_eq!(eval_str(&grid, "AVERAGEIFS(B1:B5, A1:A4, \"x\")"), EvalResult::Error(CellError::Value));
+        assert_eq!(eval_str(&grid, "MAXIFS(B1:B5, A1:A4, \"x\")"), EvalResult::Error(CellError::Value));
+        assert_eq!(eval_str(&grid, "MINIFS(B1:B5, A1:A5, \"x\", B1:B3, \">0\")"), EvalResult::Error(CellError::Value));
+        // Whole columns pair row by row despite the blanks.
+        assert_eq!(eval_str(&grid, "MAXIFS(B:B, A:A, \"x\")"), EvalResult::Number(30.0));
+    }
+
+    #[test]
+    fn test_batch_cache_reuses_criteria_and_ranges_across_formulas() {
+        let mut grid = Grid::new();
+        // A1:A6 = x, y, x, (blank), x, 3; B1:B6 = 1..6
+        for (r, t) in [(0, "x"), (1, "y"), (2, "x"), (4, "x")] {
+            grid.set_cell(r, 0, Cell::new_text(t.to_string()));
+        }
+        grid.set_cell(5, 0, Cell::new_number(3.0));
+        for r in 0..6 {
+            grid.set_cell(r, 1, Cell::new_number((r + 1) as f64));
+        }
+
+        let formulas = [
+            "COUNTIFS(A1:A6, \"x\", B1:B6, \">2\")",
+            "SUMIFS(B1:B6, A1:A6, \"x\")",
+            "COUNTIFS(A1:A6, \"x\", B1:B6, \">2\")",
+            "AVERAGEIF(A1:A6, \"x\", B1:B6)",
+            "COUNTIF(A:A, \"x\")",
+            "COUNTIF(A:A, \"y\")",
+        ];
+        let evaluator = Evaluator::new(&grid);
+        let batched: Vec<EvalResult> = formulas
+            .iter()
+            .map(|f| evaluator.evaluate(&parser::parse(f).unwrap()))
+            .collect();
+        let fresh: Vec<EvalResult> = formulas.iter().map(|f| eval_str(&grid, f)).collect();
+        assert_eq!(batched, fresh);
+        assert_eq!(batched[0], EvalResult::Number(2.0));
+        assert_eq!(batched[1], EvalResult::Number(9.0));
+        assert_eq!(batched[5], EvalResult::Number(1.0));
+
+        let stats = evaluator.batch_cache_stats();
+        // "x", ">2" and "y" are compiled once each.
+        assert_eq!(stats.criteria_misses, 3);
+        assert_eq!(stats.criteria_hits, 5);
+        // A1:A6 and B1:B6 are read once each; every later use is a hit.
+        assert_eq!(stats.range_misses, 2);
+        assert_eq!(stats.range_hits, 6);
+        // Both whole-column COUNTIFs share one index.
+        assert_eq!((stats.index_misses, stats.index_hits), (1, 1));
+    }
+
+    #[test]
+    fn test_batch_cache_does_not_outlive_its_evaluator() {
+        let mut grid = Grid::new();
+        for r in 0..5 {
+            grid.set_cell(r, 0, Cell::new_number((r + 1) as f64));
+        }
+        let batch = ["COUNTIF(A1:A5, \">2\")", "COUNTIFS(A1:A5, \">2\")", "SUMIFS(A1:A5, A1:A5, \">2\")"];
+        let run = |grid: &Grid| {
+            let evaluator = Evaluator::new(grid);
+            let results: Vec<EvalResult> =
+                batch.iter().map(|f| evaluator.evaluate(&parser::parse(f).unwrap())).collect();
+            (results, evaluator.batch_cache_stats())
+        };
+
+        let (first, _) = run(&grid);
+        assert_eq!(first, vec![EvalResult::Number(3.0), EvalResult::Number(3.0), EvalResult::Number(12.0)]);
+
+        grid.set_cell(0, 0, Cell::new_number(10.0));
+        grid.clear_cell(4, 0);
+        let (second, stats) = run(&grid);
+        assert_eq!(second, vec![EvalResult::Number(3.0), EvalResult::Number(3.0), EvalResult::Number(17.0)]);
+        // The new evaluator started empty and read the mutated grid.
+        assert_eq!(stats.criteria_misses, 1);
+        assert_eq!(stats.range_misses, 1);
+        assert_eq!(stats.index_misses, 1);
+    }
+
+    #[test]
+    fn test_batch_cache_countif_cost_is_independent_of_formula_count() {
+        const ROWS: u32 = 2_000;
+        const FORMULAS: u32 = 10_000;
+        let mut grid = Grid::new();
+        for r in 0..ROWS {
+            grid.set_cell(r, 0, Cell::new_number((r % 100) as f64));
+        }
+
+        let evaluator = Evaluator::new(&grid);
+        let asts: Vec<Expression> = (0..100)
+            .map(|k| parser::parse(&format!("COUNTIF(A1:A{}, \">={}\")", ROWS, k + 1)).unwrap())
+            .collect();
+        for i in 0..FORMULAS {
+            let k = i % 100;
+            assert_eq!(
+                evaluator.evaluate(&asts[k as usize]),
+                EvalResult::Number((20 * (99 - k)) as f64)
+            );
+        }
+
+        // The column is indexed once and each distinct criteria compiled once;
+        // every other formula is a lookup, not a scan of ROWS cells.
+        let stats = evaluator.batch_cache_stats();
+        assert_eq!((stats.index_misses, stats.index_hits), (1, (FORMULAS - 1) as u64));
+        assert_eq!((stats.criteria_misses, stats.criteria_hits), (100, (FORMULAS - 100) as u64));
+        assert_eq!(stats.range_misses, 0);
+    }
+
+    #[test]
+    fn test_vlookup_hlookup_lookup_shapes_and_errors() {
+        let mut grid = Grid::new();
+        // A1:B4 = (10, a), (20, b), (30, c), (40, d); D1:G1 = 1, 2, 3, 4
+        for r in 0..4 {
+            grid.set_cell(r, 0, Cell::new_number(((r + 1) * 10) as f64));
+            grid.set_cell(r, 1, Cell::new_text(((b'a' + r as u8) as char).to_string()));
+            grid.set_cell(0, 3 + r, Cell::new_number((r + 1) as f64));
+        }
+        let text = |s: &str| EvalResult::Text(s.to_string());
+
+        // Approximate match is the default and takes the largest key <= value.
+        assert_eq!(eval_str(&grid, "VLOOKUP(25, A1:B4, 2)"), text("b"));
+        assert_eq!(eval_str(&grid, "VLOOKUP(25, A1:B4, 2, TRUE)"), text("b"));
+        assert_eq!(eval_str(&grid, "VLOOKUP(5, A1:B4, 2)"), EvalResult::Error(CellError::NA));
+        assert_eq!(eval_str(&grid, "VLOOKUP(30, A1:B4, 2, FALSE)"), text("c"));
+        assert_eq!(eval_str(&grid, "VLOOKUP(25, A1:B4, 2, FALSE)"), EvalResult::Error(CellError::NA));
+        assert_eq!(eval_str(&grid, "VLOOKUP(30, A1:B4, 3, FALSE)"), EvalResult::Error(CellError::Ref));
+        assert_eq!(eval_str(&grid, "VLOOKUP(30, A1:B4, 0, FALSE)"), EvalResult::Error(CellError::Value));
+
+        // A one-column table is a column, not a single row.
+        assert_eq!(eval_str(&grid, "VLOOKUP(30, A1:A4, 1, FALSE)"), EvalResult::Number(30.0));
+        assert_eq!(eval_str(&grid, "VLOOKUP(35, A1:A4, 1)"), EvalResult::Number(30.0));
+        assert_eq!(eval_str(&grid, "VLOOKUP(30, A1:A4, 2, FALSE)"), EvalResult::Error(CellError::Ref));
+        assert_eq!(eval_str(&grid, "HLOOKUP(10, A1:A4, 3, FALSE)"), EvalResult::Number(30.0));
+        assert_eq!(eval_str(&grid, "HLOOKUP(30, A1:A4, 1, FALSE)"), EvalResult::Error(CellError::NA));
+
+        assert_eq!(eval_str(&grid, "HLOOKUP(3, D1:G1, 1, FALSE)"), EvalResult::Number(3.0));
+        assert_eq!(eval_str(&grid, "HLOOKUP(2.5, D1:G1, 1)"), EvalResult::Number(2.0));
+        assert_eq!(eval_str(&grid, "HLOOKUP(3, D1:G1, 2, FALSE)"), EvalResult::Error(CellError::Ref));
+
+        // Vector form of LOOKUP.
+        assert_eq!(eval_str(&grid, "LOOKUP(35, A1:A4, B1:B4)"), text("c"));
+        assert_eq!(eval_str(&grid, "LOOKUP(40, A1:A4)"), EvalResult::Number(40.0));
+        assert_eq!(eval_str(&grid, "LOOKUP(3.5, D1:G1, A1:A4)"), EvalResult::Number(30.0));
+        assert_eq!(eval_str(&grid, "LOOKUP(5, A1:A4, B1:B4)"), EvalResult::Error(CellError::NA));
+        assert_eq!(eval_str(&grid, "LOOKUP(5)"), EvalResult::Error(CellError::Value));
+    }
+
+    #[test]
+    fn test_index_match_rows_columns_and_match_types() {
+        let mut grid = Grid::new();
+        // A1:B3 = (1, 10), (2, 20), (3, 30); D1:D4 = apple, banana, cherry, date;
+        // E1:E4 = 40, 30, 20, 10
+        for r in 0..3 {
+            grid.set_cell(r, 0, Cell::new_number((r + 1) as f64));
+            grid.set_cell(r, 1, Cell::new_number(((r + 1) * 10) as f64));
+        }
+        for (r, s) in ["apple", "banana", "cherry", "date"].iter().enumerate() {
+            grid.set_cell(r as u32, 3, Cell::new_text(s.to_string()));
+            grid.set_cell(r as u32, 4, Cell::new_number((40 - r * 10) as f64));
+        }
+
+        assert_eq!(eval_str(&grid, "INDEX(A1:B3, 3, 2)"), EvalResult::Number(30.0));
+        // Row or column 0 selects the whole column or row.
+        assert_eq!(eval_str(&grid, "SUM(INDEX(A1:B3, 0, 2))"), EvalResult::Number(60.0));
+        assert_eq!(eval_str(&grid, "SUM(INDEX(A1:B3, 2, 0))"), EvalResult::Number(22.0));
+        // One index into a single row picks a column.
+        assert_eq!(eval_str(&grid, "INDEX(D1:E1, 2)"), EvalResult::Number(40.0));
+        assert_eq!(eval_str(&grid, "INDEX(B1:B3, 2)"), EvalResult::Number(20.0));
+        assert_eq!(eval_str(&grid, "INDEX(A1:B3, 4, 1)"), EvalResult::Error(CellError::Ref));
+        assert_eq!(eval_str(&grid, "INDEX(A1:B3, 1, 3)"), EvalResult::Error(CellError::Ref));
+        assert_eq!(eval_str(&grid, "INDEX(A1:B3, -1, 1)"), EvalResult::Error(CellError::Value));
+
+        assert_eq!(eval_str(&grid, "MATCH(\"cherry\", D1:D4, 0)"), EvalResult::Number(3.0));
+        assert_eq!(eval_str(&grid, "MATCH(\"b*\", D1:D4, 0)"), EvalResult::Number(2.0));
+        assert_eq!(eval_str(&grid, "MATCH(\"fig\", D1:D4, 0)"), EvalResult::Error(CellError::NA));
+        assert_eq!(eval_str(&grid, "MATCH(25, B1:B3)"), EvalResult::Number(2.0));
+        assert_eq!(eval_str(&grid, "MATCH(25, B1:B3, 2)"), EvalResult::Number(2.0));
+        assert_eq!(eval_str(&grid, "MATCH(5, B1:B3, 1)"), EvalResult::Error(CellError::NA));
+        assert_eq!(eval_str(&grid, "MATCH(25, E1:E4, -1)"), EvalResult::Number(2.0));
+        assert_eq!(eval_str(&grid, "MATCH(50, E1:E4, -1)"), EvalResult::Error(CellError::NA));
+        assert_eq!(eval_str(&grid, "MATCH(20, A1:B3, 0)"), EvalResult::Error(CellError::NA));
+
+        assert_eq!(
+            eval_str(&grid, "INDEX(E1:E4, MATCH(\"cherry\", D1:D4, 0))"),
+            EvalResult::Number(20.0)
+        );
+    }
+
+    #[test]
+    fn test_rows_columns_and_address() {
+        let grid = make_info_grid();
+        let text = |s: &str| EvalResult::Text(s.to_string());
+
+        // Dimensions come from the reference, not the populated cells.
+        assert_eq!(eval_str(&grid, "ROWS(C3:E10)"), EvalResult::Number(8.0));
+        assert_eq!(eval_str(&grid, "COLUMNS(C3:E10)"), EvalResult::Number(3.0));
+        assert_eq!(eval_str(&grid, "ROWS(E10:C3)"), EvalResult::Number(8.0));
+        assert_eq!(eval_str(&grid, "ROWS(A:A)"), EvalResult::Number(1_048_576.0));
+        assert_eq!(eval_str(&grid, "COLUMNS(A:A)"), EvalResult::Number(1.0));
+        assert_eq!(eval_str(&grid, "COLUMNS(B:D)"), EvalResult::Number(3.0));
+        assert_eq!(eval_str(&grid, "ROWS(2:4)"), EvalResult::Number(3.0));
+        assert_eq!(eval_str(&grid, "COLUMNS(2:4)"), EvalResult::Number(16_384.0));
+        assert_eq!(eval_str(&grid, "ROWS(Z99)"), EvalResult::Number(1.0));
+        assert_eq!(eval_str(&grid, "COLUMNS(Sheet1:Sheet3!A1:D2)"), EvalResult::Number(4.0));
+        // Arrays report their shape; errors propagate.
+        assert_eq!(eval_str(&grid, "ROWS(SEQUENCE(4, 2))"), EvalResult::Number(4.0));
+        assert_eq!(eval_str(&grid, "COLUMNS(SEQUENCE(4, 2))"), EvalResult::Number(2.0));
+        assert_eq!(eval_str(&grid, "ROWS(5)"), EvalResult::Number(1.0));
+        assert_eq!(eval_str(&grid, "ROWS(1/0)"), EvalResult::Error(CellError::Div0));
+        assert_eq!(eval_str(&grid, "ROWS()"), EvalResult::Error(CellError::Value));
+
+        assert_eq!(eval_str(&grid, "ADDRESS(2, 3)"), text("$C$2"));
+        assert_eq!(eval_str(&grid, "ADDRESS(2, 3, 2)"), text("C$2"));
+        assert_eq!(eval_str(&grid, "ADDRESS(2, 3, 3)"), text("$C2"));
+        assert_eq!(eval_str(&grid, "ADDRESS(2, 3, 4)"), text("C2"));
+        assert_eq!(eval_str(&grid, "ADDRESS(1, 28, 4)"), text("AB1"));
+        assert_eq!(eval_str(&grid, "ADDRESS(2.9, 3.9)"), text("$C$2"));
+        assert_eq!(eval_str(&grid, "ADDRESS(2, 3, 1, FALSE)"), text("R2C3"));
+        assert_eq!(eval_str(&grid, "ADDRESS(2, 3, 2, FALSE)"), text("R2C[3]"));
+        assert_eq!(eval_str(&grid, "ADDRESS(2, 3, 4, FALSE)"), text("R[2]C[3]"));
+        assert_eq!(eval_str(&grid, "ADDRESS(2, 3, 1, TRUE, \"Data\")"), text("Data!$C$2"));
+        assert_eq!(eval_str(&grid, "ADDRESS(2, 3, 1, TRUE, \"My Sheet\")"), text("'My Sheet'!$C$2"));
+        assert_eq!(eval_str(&grid, "ADDRESS(1048576, 16384)"), text("$XFD$1048576"));
+        assert_eq!(eval_str(&grid, "ADDRESS(1048577, 1)"), EvalResult::Error(CellError::Value));
+        assert_eq!(eval_str(&grid, "ADDRESS(1, 16385)"), EvalResult::Error(CellError::Value));
+        assert_eq!(eval_str(&grid, "ADDRESS(0, 1)"), EvalResult::Error(CellError::Value));
+        assert_eq!(eval_str(&grid, "ADDRESS(1, 1, 5)"), EvalResult::Error(CellError::Value));
+        assert_eq!(eval_str(&grid, "ADDRESS(1/0, 1)"), EvalResult::Error(CellError::Div0));
+        assert_eq!(eval_str(&grid, "ADDRESS(1, 1, 1, \"x\")"), EvalResult::Error(CellError::Value));
+    }
+
+    #[test]
+    fn test_offset_builds_shifted_regions() {
+        let mut grid = Grid::new();
+        for row in 1..=4 {
+            grid.set_cell(row, 1, Cell::new_number(row as f64));
+        }
+        grid.set_cell(1, 2, Cell::new_text("x".to_string()));
+        let n = EvalResult::Number;
+
+        // 1x1 regions are plain values; offsets truncate.
+        assert_eq!(eval_str(&grid, "OFFSET(B2, 2, 0)"), n(3.0));
+        assert_eq!(eval_str(&grid, "OFFSET(B2, 1.9, 0)"), n(2.0));
+        assert_eq!(eval_str(&grid, "OFFSET(B2:B5, 0, 1, 1, 1)"), EvalResult::Text("x".to_string()));
+        assert_eq!(eval_str(&grid, "OFFSET(B2, -1, -1)"), n(0.0));
+        // Larger regions are arrays aggregates consume; size defaults to the anchor's.
+        assert_eq!(eval_str(&grid, "SUM(OFFSET(B2, 1, 0, 3, 1))"), n(9.0));
+        assert_eq!(eval_str(&grid, "SUM(OFFSET(B2:B3, 2, 0))"), n(7.0));
+        assert_eq!(eval_str(&grid, "ROWS(OFFSET(A1, 0, 0, 3, 2))"), n(3.0));
+        assert_eq!(eval_str(&grid, "COLUMNS(OFFSET(A1, 0, 0, 3, 2))"), n(2.0));
+        // Regions outside the sheet, or empty ones, are #REF!.
+        assert_eq!(eval_str(&grid, "OFFSET(B2, -2, 0)"), EvalResult::Error(CellError::Ref));
+        assert_eq!(eval_str(&grid, "OFFSET(B2, 0, -2)"), EvalResult::Error(CellError::Ref));
+        assert_eq!(eval_str(&grid, "OFFSET(A1, 1048575, 0)"), n(0.0));
+        assert_eq!(eval_str(&grid, "OFFSET(A1, 1048576, 0)"), EvalResult::Error(CellError::Ref));
+        assert_eq!(eval_str(&grid, "OFFSET(A1, 0, 16383, 1, 2)"), EvalResult::Error(CellError::Ref));
+        assert_eq!(eval_str(&grid, "OFFSET(B2, 0, 0, 0, 1)"), EvalResult::Error(CellError::Ref));
+        // Errors propagate; the anchor must be a reference.
+        assert_eq!(eval_str(&grid, "OFFSET(B2, 1/0, 0)"), EvalResult::Error(CellError::Div0));
+        assert_eq!(eval_str(&grid, "OFFSET(5, 0, 0)"), EvalResult::Error(CellError::Value));
+        assert_eq!(eval_str(&grid, "OFFSET(B2, 0)"), EvalResult::Error(CellError::Value));
+    }
+
+    #[test]
+    fn test_and_or_xor_skip_text_and_blanks_in_ranges() {
+        let mut grid = Grid::new();
+        // A1:A5 = TRUE, "text", blank, 1, "TRUE"; B1:B3 = FALSE, 0, "x";
+        // C1 = "a" (C2 blank); D1 = #DIV/0!
+        grid.set_cell(0, 0, Cell::new_boolean(true));
+        grid.set_cell(1, 0, Cell::new_text("text".to_string()));
+        grid.set_cell(3, 0, Cell::new_number(1.0));
+        grid.set_cell(4, 0, Cell::new_text("TRUE".to_string()));
+        grid.set_cell(0, 1, Cell::new_boolean(false));
+        grid.set_cell(1, 1, Cell::new_number(0.0));
+        grid.set_cell(2, 1, Cell::new_text("x".to_string()));
+        grid.set_cell(0, 2, Cell::new_text("a".to_string()));
+        let mut error = Cell::new_number(0.0);
+        error.value = CellValue::Error(CellError::Div0);
+        grid.set_cell(0, 3, error);
+        let b = EvalResult::Boolean;
+
+        // Text (even "TRUE") and blanks inside references are skipped.
+        assert_eq!(eval_str(&grid, "AND(A1:A5)"), b(true));
+        assert_eq!(eval_str(&grid, "AND(A:A)"), b(true));
+        assert_eq!(eval_str(&grid, "AND(A1:A5, B1:B3)"), b(false));
+        assert_eq!(eval_str(&grid, "OR(B1:B3)"), b(false));
+        assert_eq!(eval_str(&grid, "OR(B1:B3, A1:A5)"), b(true));
+        assert_eq!(eval_str(&grid, "AND(C1:C2, TRUE)"), b(true));
+        // A large, mostly empty rectangle.
+        assert_eq!(eval_str(&grid, "OR(A1:C1000)"), b(true));
+
+        // Nothing logical at all.
+        assert_eq!(eval_str(&grid, "OR(C1:C2)"), EvalResult::Error(CellError::Value));
+        assert_eq!(eval_str(&grid, "AND(A2)"), EvalResult::Error(CellError::Value));
+        assert_eq!(eval_str(&grid, "AND(A3)"), EvalResult::Error(CellError::Value));
+
+        // Direct arguments coerce: numbers and "TRUE"/"FALSE" text.
+        assert_eq!(eval_str(&grid, "AND(\"TRUE\", 1)"), b(true));
+        assert_eq!(eval_str(&grid, "OR(\"false\", 0)"), b(false));
+        assert_eq!(eval_str(&grid, "AND(\"yes\")"), EvalResult::Error(CellError::Value));
+
+        // Errors propagate until the result is decided.
+        assert_eq!(eval_str(&grid, "AND(A1:A5, D1)"), EvalResult::Error(CellError::Div0));
+        assert_eq!(eval_str(&grid, "AND(FALSE, D1)"), b(false));
+        assert_eq!(eval_str(&grid, "OR(TRUE, D1)"), b(true));
+
+        // XOR counts TRUE values across every argument.
+        assert_eq!(eval_str(&grid, "XOR(A1:A5)"), b(false));
+        assert_eq!(eval_str(&grid, "XOR(A1:A5, TRUE)"), b(true));
+        assert_eq!(eval_str(&grid, "XOR(B1:B3, 1)"), b(true));
+        assert_eq!(eval_str(&grid, "XOR(C1:C2)"), EvalResult::Error(CellError::Value));
+        assert_eq!(eval_str(&grid, "XOR(FALSE, D1)"), EvalResult::Error(CellError::Div0));
+    }
+
+    #[test]
+    fn test_value_numbervalue_dollar_fixed() {
+        let grid = Grid::new();
+        let n = EvalResult::Number;
+        let t = |s: &str| EvalResult::Text(s.to_string());
+        let value_err = EvalResult::Error(CellError::Value);
+
+        assert_eq!(eval_str(&grid, "VALUE(\"1,234.5\")"), n(1234.5));
+        assert_eq!(eval_str(&grid, "VALUE(\" $1,000 \")"), n(1000.0));
+        assert_eq!(eval_str(&grid, "VALUE(\"($12.50)\")"), n(-12.5));
+        assert_eq!(eval_str(&grid, "VALUE(\"75%\")"), n(0.75));
+        assert_eq!(eval_str(&grid, "VALUE(\"2024-01-15\")"), n(45306.0));
+        assert_eq!(eval_str(&grid, "VALUE(\"2024-01-15 12:00\")"), n(45306.5));
+        assert_eq!(eval_str(&grid, "VALUE(\"6:00 PM\")"), n(0.75));
+        assert_eq!(eval_str(&grid, "VALUE(42)"), n(42.0));
+        assert_eq!(eval_str(&grid, "VALUE(\"abc\")"), value_err);
+        assert_eq!(eval_str(&grid, "VALUE(\"inf\")"), value_err);
+        assert_eq!(eval_str(&grid, "VALUE(1/0)"), EvalResult::Error(CellError::Div0));
+
+        assert_eq!(eval_str(&grid, "NUMBERVALUE(\"2.500,27\", \",\", \".\")"), n(2500.27));
+        assert_eq!(eval_str(&grid, "NUMBERVALUE(\"3.5%\")"), n(0.035));
+        assert_eq!(eval_str(&grid, "NUMBERVALUE(\" 1 234 \")"), n(1234.0));
+        assert_eq!(eval_str(&grid, "NUMBERVALUE(\"\")"), n(0.0));
+        assert_eq!(eval_str(&grid, "NUMBERVALUE(\"1,5\", \",\", \",\")"), value_err);
+        assert_eq!(eval_str(&grid, "NUMBERVALUE(\"1.000,5\")"), value_err);
+        assert_eq!(eval_str(&grid, "NUMBERVALUE(\"$5\")"), value_err);
+
+        assert_eq!(eval_str(&grid, "DOLLAR(1234.567)"), t("$1,234.57"));
+        assert_eq!(eval_str(&grid, "DOLLAR(-1234.567, 1)"), t("($1,234.6)"));
+        assert_eq!(eval_str(&grid, "DOLLAR(1234.567, -2)"), t("$1,200"));
+        assert_eq!(eval_str(&grid, "FIXED(1234.567, 1)"), t("1,234.6"));
+        assert_eq!(eval_str(&grid, "FIXED(-1234.567, -1, TRUE)"), t("-1230"));
+        assert_eq!(eval_str(&grid, "FIXED(-0.001)"), t("0.00"));
+        assert_eq!(eval_str(&grid, "FIXED(2.5, 0)"), t("3"));
+        assert_eq!(eval_str(&grid, "FIXED(1, 128)"), value_err);
+        // What VALUE reads back is what DOLLAR and FIXED wrote
+        assert_eq!(eval_str(&grid, "VALUE(DOLLAR(-1234.5))"), n(-1234.5));
+        assert_eq!(eval_str(&grid, "VALUE(FIXED(98765.4321, 3))"), n(98765.432));
+    }
+
+    #[test]
+    fn test_iferror_ifna_error_type() {
+        let mut grid = Grid::new();
+        // A1:A4 = 1, #DIV/0!, 3, #N/A; B1:B4 = 10, 20, 30, 40
+        let error = |e: CellError| {
+            let mut cell = Cell::new_number(0.0);
+            cell.value = CellValue::Error(e);
+            cell
+        };
+        grid.set_cell(0, 0, Cell::new_number(1.0));
+        grid.set_cell(1, 0, error(CellError::Div0));
+        grid.set_cell(2, 0, Cell::new_number(3.0));
+        grid.set_cell(3, 0, error(CellError::NA));
+        for r in 0..4 {
+            grid.set_cell(r, 1, Cell::new_number(((r + 1) * 10) as f64));
+        }
+
+        assert_eq!(eval_str(&grid, "IFERROR(1/0, 5)"), EvalResult::Number(5.0));
+        assert_eq!(eval_str(&grid, "IFERROR(A3, 5)"), EvalResult::Number(3.0));
+        assert_eq!(eval_str(&grid, "IFERROR(A2 + 1, \"bad\")"), EvalResult::Text("bad".to_string()));
+        // The fallback is not evaluated when nothing is trapped.
+        assert_eq!(eval_str(&grid, "IFERROR(2, 1/0)"), EvalResult::Number(2.0));
+        assert_eq!(eval_str(&grid, "IFNA(A4, 0)"), EvalResult::Number(0.0));
+        assert_eq!(eval_str(&grid, "IFNA(A2, 0)"), EvalResult::Error(CellError::Div0));
+        assert_eq!(eval_str(&grid, "IFERROR(1/0)"), EvalResult::Error(CellError::Value));
+
+        // Arrays are trapped element by element.
+        assert_eq!(eval_str(&grid, "SUM(IFERROR(A1:A4, 0))"), EvalResult::Number(4.0));
+        assert_eq!(eval_str(&grid, "SUM(IFERROR(A1:A4, B1:B4))"), EvalResult::Number(64.0));
+        let ifna = eval_str(&grid, "IFNA(A1:A4, 0)").flatten();
+        assert_eq!((&ifna[1], &ifna[3]), (&EvalResult::Error(CellError::Div0), &EvalResult::Number(0.0)));
+        assert_eq!(eval_str(&grid, "IFERROR(A1:A4, B1:B2)").flatten()[3], EvalResult::Error(CellError::NA));
+
+        assert_eq!(eval_str(&grid, "ERROR.TYPE(1/0)"), EvalResult::Number(2.0));
+        assert_eq!(eval_str(&grid, "ERROR.TYPE(\"x\" + 1)"), EvalResult::Number(3.0));
+        assert_eq!(eval_str(&grid, "ERROR.TYPE(A4)"), EvalResult::Number(7.0));
+        assert_eq!(eval_str(&grid, "ERROR.TYPE(NOSUCHNAME)"), EvalResult::Number(5.0));
+        assert_eq!(eval_str(&grid, "ERROR.TYPE(BASE(-1, 2))"), EvalResult::Number(6.0));
+        assert_eq!(eval_str(&grid, "ERROR.TYPE(A1)"), EvalResult::Error(CellError::NA));
+    }
+
+    #[test]
+    fn test_ifs_switch_choose_select_lazily() {
+        let mut grid = Grid::new();
+        grid.set_cell(0, 0, Cell::new_number(2.0));
+        grid.set_cell(1, 0, Cell::new_text("b".to_string()));
+
+        // Only the selected branch is evaluated: the 1/0s are never reached.
+        assert_eq!(eval_str(&grid, "IFS(A1>1, \"big\", 1/0, \"x\")"), EvalResult::Text("big".to_string()));
+        assert_eq!(eval_str(&grid, "IFS(A1>5, 1/0, TRUE, 7)"), EvalResult::Number(7.0));
+        assert_eq!(eval_str(&grid, "SWITCH(A2, \"a\", 1/0, \"B\", 2, 1/0)"), EvalResult::Number(2.0));
+        assert_eq!(eval_str(&grid, "SWITCH(A1, 2, \"two\", 1/0, \"x\")"), EvalResult::Text("two".to_string()));
+        assert_eq!(eval_str(&grid, "CHOOSE(A1, 1/0, SUM(A1:A3), 1/0)"), EvalResult::Number(2.0));
+
+        // No match: IFS and a default-less SWITCH give #N/A; SWITCH's default.
+        assert_eq!(eval_str(&grid, "IFS(A1>5, 1, A1<0, 2)"), EvalResult::Error(CellError::NA));
+        assert_eq!(eval_str(&grid, "SWITCH(A1, 1, \"one\", 3, \"three\")"), EvalResult::Error(CellError::NA));
+        assert_eq!(eval_str(&grid, "SWITCH(A1, 1, \"one\", \"other\")"), EvalResult::Text("other".to_string()));
+
+        // Conditions: errors propagate, numbers coerce, other text is #VALUE!.
+        assert_eq!(eval_str(&grid, "IFS(1/0, 1, TRUE, 2)"), EvalResult::Error(CellError::Div0));
+        assert_eq!(eval_str(&grid, "IFS(0, 1, A1, 2)"), EvalResult::Number(2.0));
+        assert_eq!(eval_str(&grid, "IFS(A2, 1, TRUE, 2)"), EvalResult::Error(CellError::Value));
+        assert_eq!(eval_str(&grid, "IFS(TRUE)"), EvalResult::Error(CellError::Value));
+
+        // CHOOSE truncates its index; out of range is #VALUE!, an error propagates.
+        assert_eq!(eval_str(&grid, "CHOOSE(2.9, \"a\", \"b\", \"c\")"), EvalResult::Text("b".to_string()));
+        assert_eq!(eval_str(&grid, "CHOOSE(4, \"a\", \"b\", \"c\")"), EvalResult::Error(CellError::Value));
+        assert_eq!(eval_str(&grid, "CHOOSE(0, \"a\", \"b\")"), EvalResult::Error(CellError::Value));
+        assert_eq!(eval_str(&grid, "CHOOSE(NA(), \"a\", \"b\")"), EvalResult::Error(CellError::NA));
+    }
 }
 
 #[cfg(test)]
@@ -17042,14 +20729,16 @@ mod lookup_cache_differential_tests {
         "=COUNTIF(A1:A12,\"<>apple\")",
         "=COUNTIF(A1:A12,TRUE)",
         "=COUNTIF(A1:A12,\"z*\")",                // wildcard -> scan
-        "=COUNTIF(A1:A12,0)",                        // counts the EMPTY cell too
+        "=COUNTIF(A1:A12,0)",                        // EMPTY cell is not 0 -> scan
+        "=COUNTIF(A1:A12,\"\")",                   // blanks -> scan
+        "=COUNTIF(A1:A12,\"<>\")",
         "=COUNTIF(H:H,\"x\")",
         // ---- SUMIF ----
         "=SUMIF(A1:A12,\"apple\",B1:B12)",
         "=SUMIF(A1:A12,TRUE,B1:B12)",
         "=SUMIF(A1:A12,\"apple\")",               // 2-arg: sums the range itself
         "=SUMIF(A1:A12,5,B1:B12)",                   // numeric -> scan fallback
-        "=SUMIF(A1:A12,\"apple\",B1:B3)",          // shorter sum range
+        "=SUMIF(A1:A12,\"apple\",B1:B3)",          // mismatched sum range -> #VALUE!
     ];
 
     #[test]
@@ -17095,27 +20784,29 @@ mod lookup_cache_differential_tests {
     }
 
     #[test]
-    fn index_fast_path_replicates_flat_addressing_quirks() {
+    fn index_reads_range_cells_in_place() {
         let grid = nasty_grid();
         // Plain hit.
         assert_eq!(
             eval_formula(&grid, "=INDEX(A1:C12,2,2)"),
             EvalResult::Number(101.0)
         );
-        // Column-overflow WRAPS into the next row (flat row-major addressing):
-        // A1:B3 flat = [A1,B1,A2,B2,A3,B3]; (1,3) -> idx 0*2+2 = 2 -> A2 = 5.
+        // A column past the range's width is #REF!, not the next row.
         assert_eq!(
             eval_formula(&grid, "=INDEX(A1:B3,1,3)"),
-            EvalResult::Number(5.0)
+            EvalResult::Error(CellError::Ref)
         );
-        // Past the end -> #REF.
         assert_eq!(
             eval_formula(&grid, "=INDEX(A1:B3,3,3)"),
             EvalResult::Error(CellError::Ref)
         );
-        // 1-D branch ignores col_num entirely.
+        // A single column has no column 5.
         assert_eq!(
             eval_formula(&grid, "=INDEX(B1:B12,3,5)"),
+            EvalResult::Error(CellError::Ref)
+        );
+        assert_eq!(
+            eval_formula(&grid, "=INDEX(B1:B12,3,1)"),
             EvalResult::Number(102.0)
         );
         // Empty cell inside the range -> 0.0.
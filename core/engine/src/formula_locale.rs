@@ -1,12 +1,72 @@
 //! FILENAME: core/engine/src/formula_locale.rs
 //! PURPOSE: Translates formula strings between locale format and invariant (US) format.
 //! CONTEXT: Formulas are always stored internally in invariant format (decimal: '.',
-//!          list separator: ','). This module converts at the input/output boundary:
+//!          list separator: ',', canonical function names). This module converts at
+//!          the input/output boundary:
 //!          - delocalize: user input (locale) -> storage (invariant)
 //!          - localize: storage (invariant) -> display (locale)
+//!          Function names are translated when the locale's function language has
+//!          a table in `function_names`; input accepts localized and canonical names.
 
+use crate::function_names::{canonical_function_name, has_function_names, localized_function_name};
 use crate::locale::LocaleSettings;
 
+fn is_name_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_' || c == '.'
+}
+
+/// Replace every function name - a name directly followed by '(' - for which
+/// `translate` has a replacement. String literals, quoted sheet names and
+/// structured-reference brackets are copied verbatim.
+fn translate_function_names(formula: &str, translate: impl Fn(&str) -> Option<&'static str>) -> String {
+    let chars: Vec<char> = formula.chars().collect();
+    let mut result = String::with_capacity(formula.len());
+    let mut i = 0;
+    let mut brackets = 0usize;
+    while i < chars.len() {
+        let ch = chars[i];
+        if ch == '"' || ch == '\'' {
+            // Copy through the closing quote; doubled quotes are escapes.
+            result.push(ch);
+            i += 1;
+            while i < chars.len() {
+                result.push(chars[i]);
+                i += 1;
+                if chars[i - 1] == ch {
+                    if chars.get(i) == Some(&ch) {
+                        result.push(ch);
+                        i += 1;
+                    } else {
+                        break;
+                    }
+                }
+            }
+            continue;
+        }
+        match ch {
+            '[' => brackets += 1,
+            ']' => brackets = brackets.saturating_sub(1),
+            _ => {}
+        }
+        let starts_name = (ch.is_alphabetic() || ch == '_') && (i == 0 || !is_name_char(chars[i - 1]));
+        if brackets == 0 && starts_name {
+            let start = i;
+            while i < chars.len() && is_name_char(chars[i]) {
+                i += 1;
+            }
+            let name: String = chars[start..i].iter().collect();
+            match translate(&name) {
+                Some(translated) if chars.get(i) == Some(&'(') => result.push_str(translated),
+                _ => result.push_str(&name),
+            }
+            continue;
+        }
+        result.push(ch);
+        i += 1;
+    }
+    result
+}
+
 /// Convert a formula from locale format to invariant (US) format for internal storage.
 ///
 /// Example (sv-SE): `=SUMMA(A1;B1;1,5)` -> `=SUMMA(A1,B1,1.5)`
@@ -15,7 +75,19 @@ use crate::locale::LocaleSettings;
 ///   - ';' -> ',' (argument separator)
 ///   - ',' -> '.' (decimal separator)
 /// When `list_separator` is ',': no translation needed.
+///
+/// With a function language (de): `=SUMME(A1;B1)` -> `=SUM(A1,B1)`;
+/// canonical names are accepted as typed.
 pub fn delocalize_formula(input: &str, locale: &LocaleSettings) -> String {
+    let translated;
+    let input = if has_function_names(&locale.function_language) {
+        translated = translate_function_names(input, |name| {
+            canonical_function_name(name, &locale.function_language)
+        });
+        translated.as_str()
+    } else {
+        input
+    };
     if locale.list_separator == ',' && locale.decimal_separator == '.' {
         // Already invariant format
         return input.to_string();
@@ -53,7 +125,18 @@ pub fn delocalize_formula(input: &str, locale: &LocaleSettings) -> String {
 ///   - ',' -> ';' (argument separator)
 ///   - '.' in numeric contexts -> ',' (decimal separator)
 /// When `list_separator` is ',': no translation needed.
+///
+/// With a function language (de): `=SUM(A1,B1)` -> `=SUMME(A1;B1)`.
 pub fn localize_formula(invariant: &str, locale: &LocaleSettings) -> String {
+    let translated;
+    let invariant = if has_function_names(&locale.function_language) {
+        translated = translate_function_names(invariant, |name| {
+            localized_function_name(name, &locale.function_language)
+        });
+        translated.as_str()
+    } else {
+        invariant
+    };
     if locale.list_separator == ',' && locale.decimal_separator == '.' {
         return invariant.to_string();
     }
@@ -183,6 +266,46 @@ mod tests {
         assert_eq!(delocalized, original);
     }
 
+    fn de_names() -> LocaleSettings {
+        LocaleSettings { function_language: "de".to_string(), ..LocaleSettings::from_locale_id("de-DE") }
+    }
+
+    #[test]
+    fn test_delocalize_function_names() {
+        let locale = de_names();
+        assert_eq!(
+            delocalize_formula("=WENN(summe(A1:A3)>1,5;ZÄHLENWENN(B:B;\"x\");0)", &locale),
+            "=IF(SUM(A1:A3)>1.5,COUNTIF(B:B,\"x\"),0)"
+        );
+        // Canonical names are accepted as typed; custom names pass through
+        assert_eq!(delocalize_formula("=SUM(A1;MYUDF(2))", &locale), "=SUM(A1,MYUDF(2))");
+    }
+
+    #[test]
+    fn test_function_names_leave_text_sheets_and_references_alone() {
+        let locale = de_names();
+        // A string, a quoted sheet name, a name not followed by '(' and a
+        // structured-reference column
+        let invariant = "=IF(A1=\"SUM(x)\",'SUM (1)'!A1+Sum,Sales[IF(])";
+        assert_eq!(
+            localize_formula(invariant, &locale),
+            "=WENN(A1=\"SUM(x)\";'SUM (1)'!A1+Sum;Sales[IF(])"
+        );
+        assert_eq!(delocalize_formula(&localize_formula(invariant, &locale), &locale), invariant);
+    }
+
+    #[test]
+    fn test_localize_function_names_round_trip() {
+        let locale = de_names();
+        let original = "=IFERROR(VLOOKUP(A1,Data!$A:$C,3,FALSE()),NORM.S.DIST(0.5,TRUE))";
+        let localized = localize_formula(original, &locale);
+        assert_eq!(localized, "=WENNFEHLER(SVERWEIS(A1;Data!$A:$C;3;FALSCH());NORM.S.VERT(0,5;TRUE))");
+        assert_eq!(delocalize_formula(&localized, &locale), original);
+        // Separators stay invariant when only the function language changes
+        let en_us = LocaleSettings { function_language: "de".to_string(), ..us() };
+        assert_eq!(localize_formula("=SUM(A1,1.5)", &en_us), "=SUMME(A1,1.5)");
+    }
+
     #[test]
     fn test_delocalize_simple_number() {
         let locale = se();
@@ -0,0 +1,485 @@
+//! FILENAME: core/engine/src/function_names.rs
+//! PURPOSE: Localized function names: per-language tables mapping canonical
+//! (English) function names to the names Excel uses in that language.
+//! CONTEXT: Formulas are stored with canonical names only; `formula_locale`
+//! consults these tables at the input/output boundary when the locale's
+//! function language has one. Only names that differ from the canonical one
+//! are listed - everything else, including custom and unknown names, passes
+//! through untouched. Names are matched case-insensitively.
+
+/// Function languages with a translation table, as (code, display name).
+/// "en" uses the canonical names.
+pub const FUNCTION_LANGUAGES: [(&str, &str); 2] = [("en", "English"), ("de", "Deutsch")];
+
+/// (canonical, German) - Excel's German function names.
+static GERMAN: &[(&str, &str)] = &[
+    // Math & trigonometry
+    ("SUM", "SUMME"),
+    ("AVERAGE", "MITTELWERT"),
+    ("COUNT", "ANZAHL"),
+    ("COUNTA", "ANZAHL2"),
+    ("PRODUCT", "PRODUKT"),
+    ("SUBTOTAL", "TEILERGEBNIS"),
+    ("SUMIF", "SUMMEWENN"),
+    ("SUMIFS", "SUMMEWENNS"),
+    ("COUNTIF", "ZÄHLENWENN"),
+    ("COUNTIFS", "ZÄHLENWENNS"),
+    ("AVERAGEIF", "MITTELWERTWENN"),
+    ("AVERAGEIFS", "MITTELWERTWENNS"),
+    ("COUNTBLANK", "ANZAHLLEEREZELLEN"),
+    ("MINIFS", "MINWENNS"),
+    ("MAXIFS", "MAXWENNS"),
+    ("SUMPRODUCT", "SUMMENPRODUKT"),
+    ("SUMX2MY2", "SUMMEX2MY2"),
+    ("SUMX2PY2", "SUMMEX2PY2"),
+    ("SUMXMY2", "SUMMEXMY2"),
+    ("ROUND", "RUNDEN"),
+    ("ROUNDUP", "AUFRUNDEN"),
+    ("ROUNDDOWN", "ABRUNDEN"),
+    ("FLOOR", "UNTERGRENZE"),
+    ("CEILING", "OBERGRENZE"),
+    ("SQRT", "WURZEL"),
+    ("POWER", "POTENZ"),
+    ("MOD", "REST"),
+    ("INT", "GANZZAHL"),
+    ("SIGN", "VORZEICHEN"),
+    ("TRUNC", "KÜRZEN"),
+    ("EVEN", "GERADE"),
+    ("ODD", "UNGERADE"),
+    ("GCD", "GGT"),
+    ("LCM", "KGV"),
+    ("COMBIN", "KOMBINATIONEN"),
+    ("COMBINA", "KOMBINATIONEN2"),
+    ("FACT", "FAKULTÄT"),
+    ("FACTDOUBLE", "ZWEIFAKULTÄT"),
+    ("MULTINOMIAL", "POLYNOMIAL"),
+    ("RAND", "ZUFALLSZAHL"),
+    ("RANDBETWEEN", "ZUFALLSBEREICH"),
+    ("ASIN", "ARCSIN"),
+    ("ACOS", "ARCCOS"),
+    ("ATAN", "ARCTAN"),
+    ("ATAN2", "ARCTAN2"),
+    ("ACOT", "ARCCOT"),
+    ("SINH", "SINHYP"),
+    ("COSH", "COSHYP"),
+    ("TANH", "TANHYP"),
+    ("COTH", "COTHYP"),
+    ("CSC", "COSEC"),
+    ("CSCH", "COSECHYP"),
+    ("SECH", "SECHYP"),
+    ("DEGREES", "GRAD"),
+    ("RADIANS", "BOGENMASS"),
+    ("MROUND", "VRUNDEN"),
+    ("SUMSQ", "QUADRATESUMME"),
+    ("ROMAN", "RÖMISCH"),
+    ("ARABIC", "ARABISCH"),
+    ("BASE", "BASIS"),
+    ("DECIMAL", "DEZIMAL"),
+    ("SQRTPI", "WURZELPI"),
+    ("AGGREGATE", "AGGREGAT"),
+    ("CEILING.MATH", "OBERGRENZE.MATHEMATIK"),
+    ("CEILING.PRECISE", "OBERGRENZE.GENAU"),
+    ("FLOOR.MATH", "UNTERGRENZE.MATHEMATIK"),
+    ("FLOOR.PRECISE", "UNTERGRENZE.GENAU"),
+    ("ISO.CEILING", "ISO.OBERGRENZE"),
+    ("MDETERM", "MDET"),
+    ("MINVERSE", "MINV"),
+    ("MUNIT", "MEINHEIT"),
+    ("SERIESSUM", "POTENZREIHE"),
+    // Logical
+    ("IF", "WENN"),
+    ("AND", "UND"),
+    ("OR", "ODER"),
+    ("NOT", "NICHT"),
+    ("TRUE", "WAHR"),
+    ("FALSE", "FALSCH"),
+    ("IFERROR", "WENNFEHLER"),
+    ("IFNA", "WENNNV"),
+    ("IFS", "WENNS"),
+    ("SWITCH", "ERSTERWERT"),
+    ("XOR", "XODER"),
+    ("BYROW", "NACHZEILE"),
+    ("BYCOL", "NACHSPALTE"),
+    ("MAKEARRAY", "ARRAYERSTELLEN"),
+    // Text
+    ("CONCATENATE", "VERKETTEN"),
+    ("CONCAT", "TEXTKETTE"),
+    ("LEFT", "LINKS"),
+    ("RIGHT", "RECHTS"),
+    ("MID", "TEIL"),
+    ("LEN", "LÄNGE"),
+    ("UPPER", "GROSS"),
+    ("LOWER", "KLEIN"),
+    ("PROPER", "GROSS2"),
+    ("TRIM", "GLÄTTEN"),
+    ("FIND", "FINDEN"),
+    ("SEARCH", "SUCHEN"),
+    ("SUBSTITUTE", "WECHSELN"),
+    ("REPLACE", "ERSETZEN"),
+    ("VALUE", "WERT"),
+    ("EXACT", "IDENTISCH"),
+    ("CHAR", "ZEICHEN"),
+    ("CLEAN", "SÄUBERN"),
+    ("NUMBERVALUE", "ZAHLENWERT"),
+    ("REPT", "WIEDERHOLEN"),
+    ("TEXTJOIN", "TEXTVERKETTEN"),
+    ("DOLLAR", "DM"),
+    ("FIXED", "FEST"),
+    ("UNICHAR", "UNIZEICHEN"),
+    ("ENCODEURL", "URLCODIEREN"),
+    ("TEXTSPLIT", "TEXTTEILEN"),
+    ("TEXTBEFORE", "TEXTVOR"),
+    ("TEXTAFTER", "TEXTNACH"),
+    ("VALUETOTEXT", "WERTZUTEXT"),
+    // Date & time
+    ("TODAY", "HEUTE"),
+    ("NOW", "JETZT"),
+    ("DATE", "DATUM"),
+    ("YEAR", "JAHR"),
+    ("MONTH", "MONAT"),
+    ("DAY", "TAG"),
+    ("HOUR", "STUNDE"),
+    ("SECOND", "SEKUNDE"),
+    ("DATEVALUE", "DATWERT"),
+    ("TIMEVALUE", "ZEITWERT"),
+    ("EDATE", "EDATUM"),
+    ("EOMONTH", "MONATSENDE"),
+    ("NETWORKDAYS", "NETTOARBEITSTAGE"),
+    ("NETWORKDAYS.INTL", "NETTOARBEITSTAGE.INTL"),
+    ("WORKDAY", "ARBEITSTAG"),
+    ("WORKDAY.INTL", "ARBEITSTAG.INTL"),
+    ("WEEKDAY", "WOCHENTAG"),
+    ("WEEKNUM", "KALENDERWOCHE"),
+    ("ISOWEEKNUM", "ISOKALENDERWOCHE"),
+    ("DAYS", "TAGE"),
+    ("DAYS360", "TAGE360"),
+    ("TIME", "ZEIT"),
+    ("YEARFRAC", "BRTEILJAHRE"),
+    // Information
+    ("ISNUMBER", "ISTZAHL"),
+    ("ISTEXT", "ISTTEXT"),
+    ("ISNONTEXT", "ISTKTEXT"),
+    ("ISBLANK", "ISTLEER"),
+    ("ISERROR", "ISTFEHLER"),
+    ("ISERR", "ISTFEHL"),
+    ("ISNA", "ISTNV"),
+    ("ISLOGICAL", "ISTLOG"),
+    ("ISODD", "ISTUNGERADE"),
+    ("ISEVEN", "ISTGERADE"),
+    ("ISFORMULA", "ISTFORMEL"),
+    ("ISREF", "ISTBEZUG"),
+    ("TYPE", "TYP"),
+    ("NA", "NV"),
+    ("ERROR.TYPE", "FEHLER.TYP"),
+    ("SHEET", "BLATT"),
+    ("SHEETS", "BLÄTTER"),
+    ("CELL", "ZELLE"),
+    // Lookup & reference
+    ("XLOOKUP", "XVERWEIS"),
+    ("VLOOKUP", "SVERWEIS"),
+    ("HLOOKUP", "WVERWEIS"),
+    ("LOOKUP", "VERWEIS"),
+    ("MATCH", "VERGLEICH"),
+    ("XMATCH", "XVERGLEICH"),
+    ("CHOOSE", "WAHL"),
+    ("CHOOSECOLS", "SPALTENWAHL"),
+    ("CHOOSEROWS", "ZEILENWAHL"),
+    ("INDIRECT", "INDIREKT"),
+    ("OFFSET", "BEREICH.VERSCHIEBEN"),
+    ("ADDRESS", "ADRESSE"),
+    ("ROW", "ZEILE"),
+    ("COLUMN", "SPALTE"),
+    ("ROWS", "ZEILEN"),
+    ("COLUMNS", "SPALTEN"),
+    ("AREAS", "BEREICHE"),
+    ("TRANSPOSE", "MTRANS"),
+    ("FORMULATEXT", "FORMELTEXT"),
+    ("GETPIVOTDATA", "PIVOTDATENZUORDNEN"),
+    ("CUBEVALUE", "CUBEWERT"),
+    ("CUBEMEMBER", "CUBEELEMENT"),
+    ("CUBESET", "CUBEMENGE"),
+    ("CUBESETCOUNT", "CUBEMENGENANZAHL"),
+    ("CUBERANKEDMEMBER", "CUBERANGELEMENT"),
+    ("CUBEMEMBERPROPERTY", "CUBEELEMENTEIGENSCHAFT"),
+    ("CUBEKPIMEMBER", "CUBEKPIELEMENT"),
+    // Dynamic arrays
+    ("SORT", "SORTIEREN"),
+    ("SORTBY", "SORTIERENNACH"),
+    ("UNIQUE", "EINDEUTIG"),
+    ("SEQUENCE", "SEQUENZ"),
+    ("RANDARRAY", "ZUFALLSMATRIX"),
+    ("GROUPBY", "GRUPPIERENNACH"),
+    ("PIVOTBY", "PIVOTMIT"),
+    ("VSTACK", "VSTAPELN"),
+    ("HSTACK", "HSTAPELN"),
+    ("EXPAND", "ERWEITERN"),
+    ("TOCOL", "ZUSPALTE"),
+    ("TOROW", "ZUZEILE"),
+    ("WRAPCOLS", "UMBRUCHSPALTEN"),
+    ("WRAPROWS", "UMBRUCHZEILEN"),
+    ("TAKE", "ÜBERNEHMEN"),
+    ("DROP", "WEGLASSEN"),
+    // Statistical
+    ("STDEV", "STABW"),
+    ("STDEVP", "STABWN"),
+    ("STDEVA", "STABWA"),
+    ("STDEVPA", "STABWNA"),
+    ("STDEV.P", "STABW.N"),
+    ("STDEV.S", "STABW.S"),
+    ("VAR", "VARIANZ"),
+    ("VARP", "VARIANZEN"),
+    ("VARA", "VARIANZA"),
+    ("VARPA", "VARIANZENA"),
+    ("AVERAGEA", "MITTELWERTA"),
+    ("LARGE", "KGRÖSSTE"),
+    ("SMALL", "KKLEINSTE"),
+    ("RANK", "RANG"),
+    ("RANK.EQ", "RANG.GLEICH"),
+    ("RANK.AVG", "RANG.MITTELW"),
+    ("PERCENTILE", "QUANTIL"),
+    ("PERCENTILE.INC", "QUANTIL.INKL"),
+    ("PERCENTILE.EXC", "QUANTIL.EXKL"),
+    ("QUARTILE.INC", "QUARTILE.INKL"),
+    ("QUARTILE.EXC", "QUARTILE.EXKL"),
+    ("PERCENTRANK", "QUANTILSRANG"),
+    ("PERCENTRANK.INC", "QUANTILSRANG.INKL"),
+    ("PERCENTRANK.EXC", "QUANTILSRANG.EXKL"),
+    ("MODE", "MODALWERT"),
+    ("MODE.SNGL", "MODUS.EINF"),
+    ("MODE.MULT", "MODUS.VIELF"),
+    ("FREQUENCY", "HÄUFIGKEIT"),
+    ("GROWTH", "VARIATION"),
+    ("LINEST", "RGP"),
+    ("LOGEST", "RKP"),
+    ("CORREL", "KORREL"),
+    ("RSQ", "BESTIMMTHEITSMASS"),
+    ("SLOPE", "STEIGUNG"),
+    ("INTERCEPT", "ACHSENABSCHNITT"),
+    ("STEYX", "STFEHLERYX"),
+    ("COVAR", "KOVAR"),
+    ("COVARIANCE.P", "KOVARIANZ.P"),
+    ("COVARIANCE.S", "KOVARIANZ.S"),
+    ("SKEW", "SCHIEFE"),
+    ("SKEW.P", "SCHIEFE.P"),
+    ("AVEDEV", "MITTELABW"),
+    ("DEVSQ", "SUMQUADABW"),
+    ("GEOMEAN", "GEOMITTEL"),
+    ("HARMEAN", "HARMITTEL"),
+    ("TRIMMEAN", "GESTUTZTMITTEL"),
+    ("STANDARDIZE", "STANDARDISIERUNG"),
+    ("PROB", "WAHRSCHBEREICH"),
+    ("PERMUT", "VARIATIONEN"),
+    ("PERMUTATIONA", "VARIATIONEN2"),
+    ("NORM.DIST", "NORM.VERT"),
+    ("NORM.S.DIST", "NORM.S.VERT"),
+    ("T.DIST", "T.VERT"),
+    ("T.DIST.2T", "T.VERT.2S"),
+    ("T.DIST.RT", "T.VERT.RE"),
+    ("T.INV.2T", "T.INV.2S"),
+    ("CHISQ.DIST", "CHIQU.VERT"),
+    ("CHISQ.DIST.RT", "CHIQU.VERT.RE"),
+    ("CHISQ.INV", "CHIQU.INV"),
+    ("CHISQ.INV.RT", "CHIQU.INV.RE"),
+    ("CHISQ.TEST", "CHIQU.TEST"),
+    ("F.DIST", "F.VERT"),
+    ("F.DIST.RT", "F.VERT.RE"),
+    ("F.INV.RT", "F.INV.RE"),
+    ("BINOM.DIST", "BINOM.VERT"),
+    ("BINOM.DIST.RANGE", "BINOM.VERT.BEREICH"),
+    ("POISSON.DIST", "POISSON.VERT"),
+    ("BETA.DIST", "BETA.VERT"),
+    ("GAMMA.DIST", "GAMMA.VERT"),
+    ("GAMMALN.PRECISE", "GAMMALN.GENAU"),
+    ("WEIBULL.DIST", "WEIBULL.VERT"),
+    ("EXPON.DIST", "EXPON.VERT"),
+    ("LOGNORM.DIST", "LOGNORM.VERT"),
+    ("HYPGEOM.DIST", "HYPGEOM.VERT"),
+    ("NEGBINOM.DIST", "NEGBINOM.VERT"),
+    ("CONFIDENCE.NORM", "KONFIDENZ.NORM"),
+    ("CONFIDENCE.T", "KONFIDENZ.T"),
+    ("FORECAST", "SCHÄTZER"),
+    ("FORECAST.LINEAR", "PROGNOSE.LINEAR"),
+    ("FORECAST.ETS", "PROGNOSE.ETS"),
+    ("FORECAST.ETS.CONFINT", "PROGNOSE.ETS.KONFINT"),
+    ("FORECAST.ETS.SEASONALITY", "PROGNOSE.ETS.SAISONALITÄT"),
+    ("FORECAST.ETS.STAT", "PROGNOSE.ETS.STAT"),
+    ("NORMDIST", "NORMVERT"),
+    ("NORMSDIST", "STANDNORMVERT"),
+    ("NORMSINV", "STANDNORMINV"),
+    ("TDIST", "TVERT"),
+    ("CHIDIST", "CHIVERT"),
+    ("FDIST", "FVERT"),
+    ("BINOMDIST", "BINOMVERT"),
+    ("CRITBINOM", "KRITBINOM"),
+    ("BETADIST", "BETAVERT"),
+    ("GAMMADIST", "GAMMAVERT"),
+    ("EXPONDIST", "EXPONVERT"),
+    ("LOGNORMDIST", "LOGNORMVERT"),
+    ("HYPGEOMDIST", "HYPGEOMVERT"),
+    ("NEGBINOMDIST", "NEGBINOMVERT"),
+    // Financial
+    ("PMT", "RMZ"),
+    ("PV", "BW"),
+    ("FV", "ZW"),
+    ("NPV", "NBW"),
+    ("IRR", "IKV"),
+    ("RATE", "ZINS"),
+    ("NPER", "ZZR"),
+    ("SLN", "LIA"),
+    ("DB", "GDA2"),
+    ("DDB", "GDA"),
+    ("SYD", "DIA"),
+    ("IPMT", "ZINSZ"),
+    ("PPMT", "KAPZ"),
+    ("FVSCHEDULE", "ZW2"),
+    ("XNPV", "XKAPITALWERT"),
+    ("XIRR", "XINTZINSFUSS"),
+    ("MIRR", "QIKV"),
+    ("CUMIPMT", "KUMZINSZ"),
+    ("CUMPRINC", "KUMKAPITAL"),
+    ("EFFECT", "EFFEKTIV"),
+    ("ACCRINT", "AUFGELZINS"),
+    ("ACCRINTM", "AUFGELZINSF"),
+    ("PRICE", "KURS"),
+    ("PRICEDISC", "KURSDISAGIO"),
+    ("PRICEMAT", "KURSFÄLLIG"),
+    ("YIELD", "RENDITE"),
+    ("YIELDDISC", "RENDITEDIS"),
+    ("YIELDMAT", "RENDITEFÄLL"),
+    ("DISC", "DISAGIO"),
+    ("INTRATE", "ZINSSATZ"),
+    ("RECEIVED", "AUSZAHLUNG"),
+    ("COUPDAYBS", "ZINSTERMTAGVA"),
+    ("COUPDAYS", "ZINSTERMTAGE"),
+    ("COUPDAYSNC", "ZINSTERMTAGNZ"),
+    ("COUPNCD", "ZINSTERMNZ"),
+    ("COUPNUM", "ZINSTERMZAHL"),
+    ("COUPPCD", "ZINSTERMVZ"),
+    ("TBILLEQ", "TBILLÄQUIV"),
+    ("TBILLPRICE", "TBILLKURS"),
+    ("TBILLYIELD", "TBILLRENDITE"),
+    ("DOLLARDE", "NOTIERUNGDEZ"),
+    ("DOLLARFR", "NOTIERUNGBRU"),
+    ("RRI", "ZSATZINVEST"),
+    ("AMORDEGRC", "AMORDEGRK"),
+    ("AMORLINC", "AMORLINEARK"),
+    ("ODDFPRICE", "UNREGER.KURS"),
+    ("ODDFYIELD", "UNREGER.REND"),
+    ("ODDLPRICE", "UNREGLE.KURS"),
+    ("ODDLYIELD", "UNREGLE.REND"),
+    // Engineering
+    ("BIN2DEC", "BININDEZ"),
+    ("BIN2HEX", "BININHEX"),
+    ("BIN2OCT", "BININOKT"),
+    ("DEC2BIN", "DEZINBIN"),
+    ("DEC2HEX", "DEZINHEX"),
+    ("DEC2OCT", "DEZINOKT"),
+    ("HEX2BIN", "HEXINBIN"),
+    ("HEX2DEC", "HEXINDEZ"),
+    ("HEX2OCT", "HEXINOKT"),
+    ("OCT2BIN", "OKTINBIN"),
+    ("OCT2DEC", "OKTINDEZ"),
+    ("OCT2HEX", "OKTINHEX"),
+    ("BITAND", "BITUND"),
+    ("BITOR", "BITODER"),
+    ("BITXOR", "BITXODER"),
+    ("BITLSHIFT", "BITLVERSCHIEB"),
+    ("BITRSHIFT", "BITRVERSCHIEB"),
+    ("COMPLEX", "KOMPLEXE"),
+    ("IMAGINARY", "IMAGINÄRTEIL"),
+    ("IMREAL", "IMREALTEIL"),
+    ("IMCONJUGATE", "IMKONJUGIERTE"),
+    ("IMCOSH", "IMCOSHYP"),
+    ("IMCSC", "IMCOSEC"),
+    ("IMCSCH", "IMCOSECHYP"),
+    ("IMPOWER", "IMAPOTENZ"),
+    ("IMPRODUCT", "IMPRODUKT"),
+    ("IMSECH", "IMSECHYP"),
+    ("IMSINH", "IMSINHYP"),
+    ("IMSQRT", "IMWURZEL"),
+    ("IMSUM", "IMSUMME"),
+    ("CONVERT", "UMWANDELN"),
+    ("ERF", "GAUSSFEHLER"),
+    ("ERF.PRECISE", "GAUSSF.GENAU"),
+    ("ERFC", "GAUSSFKOMPL"),
+    ("ERFC.PRECISE", "GAUSSFKOMPL.GENAU"),
+    ("GESTEP", "GGANZZAHL"),
+    // Database
+    ("DAVERAGE", "DBMITTELWERT"),
+    ("DCOUNT", "DBANZAHL"),
+    ("DCOUNTA", "DBANZAHL2"),
+    ("DGET", "DBAUSZUG"),
+    ("DMAX", "DBMAX"),
+    ("DMIN", "DBMIN"),
+    ("DPRODUCT", "DBPRODUKT"),
+    ("DSTDEV", "DBSTDABW"),
+    ("DSTDEVP", "DBSTDABWN"),
+    ("DSUM", "DBSUMME"),
+    ("DVAR", "DBVARIANZ"),
+    ("DVARP", "DBVARIANZEN"),
+];
+
+fn table(language: &str) -> Option<&'static [(&'static str, &'static str)]> {
+    match language.to_ascii_lowercase().as_str() {
+        "de" => Some(GERMAN),
+        _ => None,
+    }
+}
+
+/// The name of canonical function `name` in `language`; None when the
+/// language keeps the canonical name.
+pub fn localized_function_name(name: &str, language: &str) -> Option<&'static str> {
+    let upper = name.to_uppercase();
+    table(language)?.iter().find(|(canonical, _)| *canonical == upper).map(|&(_, localized)| localized)
+}
+
+/// The canonical name of function `name` written in `language`; None when
+/// `name` is not a localized name there (canonical and unknown names).
+pub fn canonical_function_name(name: &str, language: &str) -> Option<&'static str> {
+    let upper = name.to_uppercase();
+    table(language)?.iter().find(|(_, localized)| *localized == upper).map(|&(canonical, _)| canonical)
+}
+
+/// Whether `language` has a translation table (English never needs one).
+pub fn has_function_names(language: &str) -> bool {
+    table(language).is_some()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use parser::BuiltinFunction;
+
+    #[test]
+    fn test_german_names_both_ways() {
+        assert_eq!(localized_function_name("sum", "de"), Some("SUMME"));
+        assert_eq!(localized_function_name("SUM", "en"), None);
+        assert_eq!(localized_function_name("MIN", "de"), None);
+        assert_eq!(canonical_function_name("zählenwenn", "de"), Some("COUNTIF"));
+        assert_eq!(canonical_function_name("SUM", "de"), None);
+        assert_eq!(canonical_function_name("MYUDF", "de"), None);
+    }
+
+    /// Every canonical name is a builtin, and no localized name is itself the
+    /// canonical name of a different function - either would make input
+    /// ambiguous once names are accepted in both languages.
+    #[test]
+    fn test_german_table_is_unambiguous() {
+        let mut seen = std::collections::HashSet::new();
+        for &(canonical, localized) in GERMAN {
+            assert!(
+                !matches!(BuiltinFunction::from_name(canonical), BuiltinFunction::Custom(_)),
+                "{} is not a builtin",
+                canonical
+            );
+            assert!(
+                matches!(BuiltinFunction::from_name(localized), BuiltinFunction::Custom(_)),
+                "{} ({}) collides with a canonical name",
+                localized,
+                canonical
+            );
+            assert_eq!(localized, localized.to_uppercase(), "{}", localized);
+            assert!(seen.insert(localized), "{} is listed twice", localized);
+        }
+    }
+}
@@ -4,7 +4,7 @@
 //! for all cell data. It uses a sparse storage strategy (HashMap) to
 //! efficiently handle massive spreadsheets where most cells are empty.
 
-use rustc_hash::FxHashMap;
+use rustc_hash::{FxHashMap, FxHashSet};
 use crate::cell::{Cell, CellValue};
 
 /// Sparse cell storage keyed by (row, col). Uses FxHash — every formula
@@ -12,6 +12,38 @@ use crate::cell::{Cell, CellValue};
 /// costs 2-3x more per probe for these tiny keys.
 pub type CellMap = FxHashMap<(u32, u32), Cell>;
 
+/// Most coordinates a grid remembers between two `take_changed_cells`
+/// calls. Past this the set is dropped and only `overflowed` is kept.
+pub const MAX_TRACKED_CHANGES: usize = 65_536;
+
+/// Coordinates written since the last `take_changed_cells`. Consumers that
+/// keep a copy of the sheet (viewport diffs) use it to send only what moved.
+#[derive(Debug, Clone, Default)]
+pub struct CellChangeSet {
+    pub cells: FxHashSet<(u32, u32)>,
+    /// Too many cells changed to list, or the change was structural (rows
+    /// shifted, a whole-line style). Treat every cell as changed.
+    pub overflowed: bool,
+}
+
+impl CellChangeSet {
+    pub fn is_empty(&self) -> bool {
+        self.cells.is_empty() && !self.overflowed
+    }
+
+    fn insert(&mut self, row: u32, col: u32) {
+        if self.overflowed {
+            return;
+        }
+        if self.cells.len() >= MAX_TRACKED_CHANGES {
+            self.cells = FxHashSet::default();
+            self.overflowed = true;
+            return;
+        }
+        self.cells.insert((row, col));
+    }
+}
+
 /// The Grid struct holds the state of the spreadsheet data.
 /// It uses a sparse representation (HashMap) mapping coordinates to Cells.
 /// Row and Col are 0-based indices.
@@ -26,6 +58,19 @@ pub struct Grid {
     
     /// Tracks the highest column index currently in use.
     pub max_col: u32,
+
+    /// Whole-column default styles (col -> style index). Empty cells in the
+    /// column display with this style and new entries inherit it.
+    pub col_styles: FxHashMap<u32, usize>,
+
+    /// Whole-row default styles (row -> style index). Take precedence over
+    /// column styles where both apply.
+    pub row_styles: FxHashMap<u32, usize>,
+
+    /// Cells written through the Grid methods since the last
+    /// `take_changed_cells`. Code that edits `cells` directly reports its
+    /// writes with `mark_changed` / `mark_all_changed`.
+    changed: CellChangeSet,
 }
 
 impl Grid {
@@ -35,6 +80,28 @@ impl Grid {
             cells: CellMap::default(),
             max_row: 0,
             max_col: 0,
+            col_styles: FxHashMap::default(),
+            row_styles: FxHashMap::default(),
+            changed: CellChangeSet::default(),
+        }
+    }
+
+    /// Style an empty cell at (row, col) takes: the row style, else the
+    /// column style, else the default style (0).
+    pub fn default_style_index(&self, row: u32, col: u32) -> usize {
+        self.row_styles
+            .get(&row)
+            .or_else(|| self.col_styles.get(&col))
+            .copied()
+            .unwrap_or(0)
+    }
+
+    /// Effective style of (row, col): the cell's own style if it exists,
+    /// otherwise the row/column default.
+    pub fn effective_style_index(&self, row: u32, col: u32) -> usize {
+        match self.cells.get(&(row, col)) {
+            Some(cell) => cell.style_index,
+            None => self.default_style_index(row, col),
         }
     }
 
@@ -48,6 +115,7 @@ impl Grid {
             self.max_col = col;
         }
         crate::lookup_cache::notify_write(row, col);
+        self.changed.insert(row, col);
         self.cells.insert((row, col), cell);
     }
 
@@ -56,6 +124,7 @@ impl Grid {
     #[inline(always)]
     pub fn set_cell_unchecked(&mut self, row: u32, col: u32, cell: Cell) {
         crate::lookup_cache::notify_write(row, col);
+        self.changed.insert(row, col);
         self.cells.insert((row, col), cell);
     }
 
@@ -81,7 +150,9 @@ impl Grid {
     pub fn clear_cell(&mut self, row: u32, col: u32) {
         let was_at_boundary = row == self.max_row || col == self.max_col;
         crate::lookup_cache::notify_write(row, col);
-        self.cells.remove(&(row, col));
+        if self.cells.remove(&(row, col)).is_some() {
+            self.changed.insert(row, col);
+        }
         
         // Only recalculate bounds if we cleared a cell at a boundary
         if was_at_boundary {
@@ -95,7 +166,9 @@ impl Grid {
         crate::lookup_cache::notify_write_rect(start_row, end_row, start_col, end_col);
         for row in start_row..=end_row {
             for col in start_col..=end_col {
-                self.cells.remove(&(row, col));
+                if self.cells.remove(&(row, col)).is_some() {
+                    self.changed.insert(row, col);
+                }
             }
         }
         self.recalculate_bounds();
@@ -127,6 +200,33 @@ impl Grid {
         self.max_col = new_max_col;
     }
 
+    // ========================================================================
+    // CHANGE TRACKING
+    // ========================================================================
+
+    /// Report a write made directly on `cells`.
+    pub fn mark_changed(&mut self, row: u32, col: u32) {
+        self.changed.insert(row, col);
+    }
+
+    /// Report a change that moves or restyles cells wholesale (inserted or
+    /// deleted rows, a whole-column style, a replaced grid).
+    pub fn mark_all_changed(&mut self) {
+        self.changed.cells = FxHashSet::default();
+        self.changed.overflowed = true;
+    }
+
+    /// The cells changed since the last `take_changed_cells`, without
+    /// resetting the tracking.
+    pub fn changed_cells(&self) -> &CellChangeSet {
+        &self.changed
+    }
+
+    /// The cells changed since the last call; tracking starts over.
+    pub fn take_changed_cells(&mut self) -> CellChangeSet {
+        std::mem::take(&mut self.changed)
+    }
+
     // ========================================================================
     // FIND & REPLACE
     // ========================================================================
@@ -305,4 +405,42 @@ mod tests {
         let results = grid.find_all("123", false, false, false);
         assert_eq!(results.len(), 2); // 123 and 1234
     }
+
+    #[test]
+    fn test_style_precedence_cell_over_row_over_column() {
+        let mut grid = Grid::new();
+        grid.col_styles.insert(2, 5);
+        grid.row_styles.insert(3, 7);
+        let mut cell = Cell::new_number(1.0);
+        cell.style_index = 9;
+        grid.set_cell(3, 2, cell);
+
+        assert_eq!(grid.effective_style_index(0, 2), 5); // column only
+        assert_eq!(grid.effective_style_index(3, 0), 7); // row only
+        assert_eq!(grid.default_style_index(3, 2), 7); // row beats column
+        assert_eq!(grid.effective_style_index(3, 2), 9); // cell beats both
+        assert_eq!(grid.effective_style_index(0, 0), 0);
+    }
+
+    #[test]
+    fn test_changed_cells_track_writes_until_taken() {
+        let mut grid = Grid::new();
+        grid.set_cell(0, 0, Cell::new_number(1.0));
+        grid.set_cell(2, 1, Cell::new_number(2.0));
+        grid.clear_cell(5, 5); // nothing there: not a change
+        let changed = grid.take_changed_cells();
+        assert!(!changed.overflowed);
+        assert_eq!(changed.cells.len(), 2);
+        assert!(changed.cells.contains(&(2, 1)));
+        assert!(grid.take_changed_cells().is_empty());
+
+        grid.clear_region(0, 0, 3, 3);
+        let changed = grid.take_changed_cells();
+        assert_eq!(changed.cells.len(), 2);
+
+        grid.mark_all_changed();
+        grid.set_cell(1, 1, Cell::new_number(3.0));
+        let changed = grid.take_changed_cells();
+        assert!(changed.overflowed && changed.cells.is_empty());
+    }
 }
\ No newline at end of file
@@ -14,16 +14,20 @@ pub mod dependency_extractor;
 pub mod dependency_graph;
 pub mod evaluator;
 pub mod formula_locale;
+pub mod function_names;
 pub mod id_operations;
 pub mod identity_graph;
 pub mod grid;
 pub mod locale;
 pub mod lookup_cache;
 pub mod number_format;
+pub mod overlay;
+pub mod reference_shift;
 pub mod style;
 pub mod text_cmp;
 pub mod theme;
 pub mod undo;
+pub mod units;
 
 // Re-export commonly used types at the crate root
 pub use cell::{Cell, CellError, CellValue, DictKey, RichTextRun};
@@ -34,15 +38,18 @@ pub use cube::{
     CubeBindingKind, CubeCallResult, CubeError, CubePrefetch, CubeResolver,
 };
 pub use custom_format::{FormatColor, FormatResult, format_color_to_css};
-pub use dependency_extractor::{extract_dependencies, BinaryOperator, BuiltinFunction, Expression, TableSpecifier, UnaryOperator, Value};
+pub use dependency_extractor::{extract_dependencies, is_volatile, BinaryOperator, BuiltinFunction, Expression, TableSpecifier, UnaryOperator, Value};
 pub use dependency_graph::{CoordSet, CycleError, DependencyGraph};
 pub use grid::CellMap;
-pub use evaluator::{EvalContext, EvalResult, Evaluator, GatherRegionData, GatherSubmission};
+pub use evaluator::{evaluation_count, take_error_detail, take_ui_effects, BatchCacheStats, RATE_MAX_ITERATIONS, EvalContext, EvalResult, Evaluator, GatherRegionData, GatherSubmission, UiEffect};
 pub use grid::Grid;
+pub use grid::CellChangeSet;
 pub use lookup_cache::{begin_pass as begin_lookup_pass, PassGuard as LookupPassGuard};
 pub use formula_locale::{delocalize_formula, localize_formula};
+pub use function_names::{canonical_function_name, localized_function_name, FUNCTION_LANGUAGES};
 pub use locale::{LocaleCurrencyPosition, LocaleSettings};
-pub use number_format::{format_number, format_number_with_color, format_text_with_color};
+pub use overlay::OverlayGrid;
+pub use number_format::{format_number, format_number_with_color, format_text_with_color, is_date_format, parse_number_text, round_to_displayed};
 pub use style::{
     BorderLineStyle, BorderStyle, Borders, CellStyle, Color, CurrencyPosition, Fill,
     FontStyle, GradientDirection, NumberFormat, PatternType, StyleRegistry, TextAlign,
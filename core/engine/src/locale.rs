@@ -35,6 +35,15 @@ pub struct LocaleSettings {
     pub currency_symbol: String,
     /// Whether currency symbol appears before or after the number
     pub currency_position: LocaleCurrencyPosition,
+    /// Language of function names in formulas, e.g. "en" or "de" (see
+    /// `function_names::FUNCTION_LANGUAGES`). Chosen separately from the
+    /// regional format, as in Excel; every locale starts out with "en".
+    #[serde(default = "default_function_language")]
+    pub function_language: String,
+}
+
+fn default_function_language() -> String {
+    "en".to_string()
 }
 
 impl LocaleSettings {
@@ -50,6 +59,7 @@ impl LocaleSettings {
             date_format: "MM/DD/YYYY".to_string(),
             currency_symbol: "$".to_string(),
             currency_position: LocaleCurrencyPosition::Before,
+            function_language: "en".to_string(),
         }
     }
 
@@ -73,6 +83,7 @@ impl LocaleSettings {
                 date_format: "DD/MM/YYYY".to_string(),
                 currency_symbol: "\u{00A3}".to_string(), // GBP
                 currency_position: LocaleCurrencyPosition::Before,
+                function_language: "en".to_string(),
             },
 
             "sv-se" | "sv" => Self {
@@ -84,6 +95,7 @@ impl LocaleSettings {
                 date_format: "YYYY-MM-DD".to_string(),
                 currency_symbol: " kr".to_string(),
                 currency_position: LocaleCurrencyPosition::After,
+                function_language: "en".to_string(),
             },
 
             "de-de" | "de-at" | "de" => Self {
@@ -95,6 +107,7 @@ impl LocaleSettings {
                 date_format: "DD.MM.YYYY".to_string(),
                 currency_symbol: "\u{20AC} ".to_string(), // EUR
                 currency_position: LocaleCurrencyPosition::Before,
+                function_language: "en".to_string(),
             },
 
             "de-ch" => Self {
@@ -106,6 +119,7 @@ impl LocaleSettings {
                 date_format: "DD.MM.YYYY".to_string(),
                 currency_symbol: "CHF ".to_string(),
                 currency_position: LocaleCurrencyPosition::Before,
+                function_language: "en".to_string(),
             },
 
             "fr-fr" | "fr" => Self {
@@ -117,6 +131,7 @@ impl LocaleSettings {
                 date_format: "DD/MM/YYYY".to_string(),
                 currency_symbol: " \u{20AC}".to_string(), // EUR after
                 currency_position: LocaleCurrencyPosition::After,
+                function_language: "en".to_string(),
             },
 
             "nb-no" | "nn-no" | "nb" | "nn" | "no" => Self {
@@ -128,6 +143,7 @@ impl LocaleSettings {
                 date_format: "DD.MM.YYYY".to_string(),
                 currency_symbol: " kr".to_string(),
                 currency_position: LocaleCurrencyPosition::After,
+                function_language: "en".to_string(),
             },
 
             "da-dk" | "da" => Self {
@@ -139,6 +155,7 @@ impl LocaleSettings {
                 date_format: "DD-MM-YYYY".to_string(),
                 currency_symbol: " kr.".to_string(),
                 currency_position: LocaleCurrencyPosition::After,
+                function_language: "en".to_string(),
             },
 
             "fi-fi" | "fi" => Self {
@@ -150,6 +167,7 @@ impl LocaleSettings {
                 date_format: "DD.MM.YYYY".to_string(),
                 currency_symbol: " \u{20AC}".to_string(),
                 currency_position: LocaleCurrencyPosition::After,
+                function_language: "en".to_string(),
             },
 
             "nl-nl" | "nl" | "nl-be" => Self {
@@ -161,6 +179,7 @@ impl LocaleSettings {
                 date_format: "DD-MM-YYYY".to_string(),
                 currency_symbol: "\u{20AC} ".to_string(),
                 currency_position: LocaleCurrencyPosition::Before,
+                function_language: "en".to_string(),
             },
 
             "it-it" | "it" => Self {
@@ -172,6 +191,7 @@ impl LocaleSettings {
                 date_format: "DD/MM/YYYY".to_string(),
                 currency_symbol: "\u{20AC} ".to_string(),
                 currency_position: LocaleCurrencyPosition::Before,
+                function_language: "en".to_string(),
             },
 
             "es-es" | "es" => Self {
@@ -183,6 +203,7 @@ impl LocaleSettings {
                 date_format: "DD/MM/YYYY".to_string(),
                 currency_symbol: " \u{20AC}".to_string(),
                 currency_position: LocaleCurrencyPosition::After,
+                function_language: "en".to_string(),
             },
 
             "pt-br" | "pt" => Self {
@@ -194,6 +215,7 @@ impl LocaleSettings {
                 date_format: "DD/MM/YYYY".to_string(),
                 currency_symbol: "R$ ".to_string(),
                 currency_position: LocaleCurrencyPosition::Before,
+                function_language: "en".to_string(),
             },
 
             "ja-jp" | "ja" => Self {
@@ -205,6 +227,7 @@ impl LocaleSettings {
                 date_format: "YYYY/MM/DD".to_string(),
                 currency_symbol: "\u{00A5}".to_string(),
                 currency_position: LocaleCurrencyPosition::Before,
+                function_language: "en".to_string(),
             },
 
             "zh-cn" | "zh" => Self {
@@ -216,6 +239,7 @@ impl LocaleSettings {
                 date_format: "YYYY/MM/DD".to_string(),
                 currency_symbol: "\u{00A5}".to_string(),
                 currency_position: LocaleCurrencyPosition::Before,
+                function_language: "en".to_string(),
             },
 
             "ko-kr" | "ko" => Self {
@@ -227,6 +251,7 @@ impl LocaleSettings {
                 date_format: "YYYY-MM-DD".to_string(),
                 currency_symbol: "\u{20A9}".to_string(),
                 currency_position: LocaleCurrencyPosition::Before,
+                function_language: "en".to_string(),
             },
 
             "pl-pl" | "pl" => Self {
@@ -238,6 +263,7 @@ impl LocaleSettings {
                 date_format: "DD.MM.YYYY".to_string(),
                 currency_symbol: " z\u{0142}".to_string(),
                 currency_position: LocaleCurrencyPosition::After,
+                function_language: "en".to_string(),
             },
 
             "ru-ru" | "ru" => Self {
@@ -249,6 +275,7 @@ impl LocaleSettings {
                 date_format: "DD.MM.YYYY".to_string(),
                 currency_symbol: " \u{20BD}".to_string(),
                 currency_position: LocaleCurrencyPosition::After,
+                function_language: "en".to_string(),
             },
 
             // Fallback: English (US)
@@ -31,8 +31,9 @@ pub fn format_number(value: f64, format: &NumberFormat, locale: &LocaleSettings)
         } => format_fraction(value, *denominator, *max_digits),
         NumberFormat::Percentage { decimal_places } => format_percentage(value, *decimal_places, locale),
         NumberFormat::Scientific { decimal_places } => format_scientific(value, *decimal_places),
-        NumberFormat::Date { format: date_fmt } => format_date_number(value, date_fmt),
-        NumberFormat::Time { format: time_fmt } => format_time_number(value, time_fmt),
+        NumberFormat::Date { format: fmt }
+        | NumberFormat::Time { format: fmt }
+        | NumberFormat::DateTime { format: fmt } => format_date_time(value, fmt, locale),
         NumberFormat::Custom { format: custom_fmt } => format_custom(value, custom_fmt, locale),
     }
 }
@@ -337,104 +338,15 @@ fn format_scientific(value: f64, decimal_places: u8) -> String {
         .replace("e", "E")
 }
 
-/// Format a number as a date (Excel serial date number).
-/// Excel dates: 1 = January 1, 1900
-fn format_date_number(value: f64, format: &str) -> String {
-    // Excel serial date conversion
-    // Note: Excel has a bug where it thinks 1900 was a leap year
-    let days = value.floor() as i64;
-    
-    if days < 1 {
-        return value.to_string(); // Not a valid date
-    }
-
-    // Calculate date from serial number
-    // Adjust for Excel's leap year bug (day 60 = Feb 29, 1900 which didn't exist)
-    let adjusted_days = if days >= 60 { days - 1 } else { days };
-    
-    // Days since Dec 31, 1899
-    let base_date = chrono_lite_date(adjusted_days);
-    
-    match base_date {
-        Some((year, month, day)) => {
-            format
-                .replace("YYYY", &format!("{:04}", year))
-                .replace("YY", &format!("{:02}", year % 100))
-                .replace("MM", &format!("{:02}", month))
-                .replace("DD", &format!("{:02}", day))
-                .replace("M", &month.to_string())
-                .replace("D", &day.to_string())
-        }
-        None => value.to_string(),
-    }
-}
-
-/// Simple date calculation without external dependencies.
-/// Returns (year, month, day) for a given number of days since Dec 31, 1899.
-fn chrono_lite_date(days: i64) -> Option<(i32, u32, u32)> {
-    if days < 1 {
-        return None;
-    }
-
-    let mut remaining = days;
-    let mut year = 1900i32;
-
-    // Find the year
-    loop {
-        let days_in_year = if is_leap_year(year) { 366 } else { 365 };
-        if remaining <= days_in_year as i64 {
-            break;
-        }
-        remaining -= days_in_year as i64;
-        year += 1;
-    }
-
-    // Find the month and day
-    let months_days: [u32; 12] = if is_leap_year(year) {
-        [31, 29, 31, 30, 31, 30, 31, 31, 30, 31, 30, 31]
-    } else {
-        [31, 28, 31, 30, 31, 30, 31, 31, 30, 31, 30, 31]
-    };
-
-    let mut month = 1u32;
-    for &days_in_month in &months_days {
-        if remaining <= days_in_month as i64 {
-            return Some((year, month, remaining as u32));
-        }
-        remaining -= days_in_month as i64;
-        month += 1;
+/// Format a serial date/time (Excel serials: 1 = January 1, 1900; the
+/// fraction is the time of day). The pattern's y/m/d/h/s tokens are matched
+/// case-insensitively by the custom format engine, which also handles month
+/// names and AM/PM. Negative serials are not dates and fall back to General.
+fn format_date_time(value: f64, format: &str, locale: &LocaleSettings) -> String {
+    if value < 0.0 {
+        return format_general(value, locale);
     }
-
-    None
-}
-
-fn is_leap_year(year: i32) -> bool {
-    (year % 4 == 0 && year % 100 != 0) || (year % 400 == 0)
-}
-
-/// Format a number as time (fraction of a day).
-fn format_time_number(value: f64, format: &str) -> String {
-    let fraction = value.fract();
-    let total_seconds = (fraction * 86400.0).round() as u32;
-    
-    let hours = total_seconds / 3600;
-    let minutes = (total_seconds % 3600) / 60;
-    let seconds = total_seconds % 60;
-
-    let is_pm = hours >= 12;
-    let hours_12 = if hours == 0 { 12 } else if hours > 12 { hours - 12 } else { hours };
-
-    format
-        .replace("HH", &format!("{:02}", hours))
-        .replace("H", &hours.to_string())
-        .replace("hh", &format!("{:02}", hours_12))
-        .replace("h", &hours_12.to_string())
-        .replace("MM", &format!("{:02}", minutes))
-        .replace("mm", &format!("{:02}", minutes))
-        .replace("SS", &format!("{:02}", seconds))
-        .replace("ss", &format!("{:02}", seconds))
-        .replace("AM/PM", if is_pm { "PM" } else { "AM" })
-        .replace("am/pm", if is_pm { "pm" } else { "am" })
+    custom_format::format_custom_value(value, format, locale).text
 }
 
 /// Format a number using a custom format string (full Excel-compatible engine).
@@ -489,6 +401,280 @@ pub fn format_text_with_color(text: &str, format: &NumberFormat) -> FormatResult
     }
 }
 
+/// Whether `format` renders its number as a date and/or time (built-in date,
+/// time and date-time formats, or a custom code with date/time tokens).
+pub fn is_date_format(format: &NumberFormat) -> bool {
+    match format {
+        NumberFormat::Date { .. } | NumberFormat::Time { .. } | NumberFormat::DateTime { .. } => true,
+        NumberFormat::Custom { format } => custom_format::parse_custom_format(format)
+            .is_ok_and(|parsed| parsed.positive.is_datetime),
+        _ => false,
+    }
+}
+
+/// The code CELL("format", ...) reports for `format`, as Excel does: "G"
+/// (General), "F2" (fixed), ",2" (thousands), "C2" (currency), "P2"
+/// (percent), "S2" (scientific), "D1"-"D9" (dates and times) or "@" (text),
+/// the digit being the decimals shown. A custom code appends "-" when
+/// negatives have a color and "()" when positives show parentheses.
+pub fn cell_format_code(format: &NumberFormat) -> String {
+    match format {
+        NumberFormat::General | NumberFormat::Fraction { .. } => "G".to_string(),
+        NumberFormat::Number { decimal_places, use_thousands_separator } => {
+            format!("{}{}", if *use_thousands_separator { "," } else { "F" }, decimal_places)
+        }
+        NumberFormat::Currency { decimal_places, .. }
+        | NumberFormat::Accounting { decimal_places, .. } => format!("C{}", decimal_places),
+        NumberFormat::Percentage { decimal_places } => format!("P{}", decimal_places),
+        NumberFormat::Scientific { decimal_places } => format!("S{}", decimal_places),
+        NumberFormat::Date { format } => {
+            let f = format.to_lowercase();
+            date_format_code(f.contains("mmm"), f.contains('d'), f.contains('m'), f.contains('y')).to_string()
+        }
+        NumberFormat::Time { format } => {
+            let f = format.to_lowercase();
+            time_format_code(f.contains("am/pm"), f.contains('s')).to_string()
+        }
+        NumberFormat::DateTime { .. } => "D4".to_string(),
+        NumberFormat::Custom { format } => match custom_format::parse_custom_format(format) {
+            Ok(parsed) => custom_cell_format_code(&parsed),
+            Err(_) => "G".to_string(),
+        },
+    }
+}
+
+fn custom_cell_format_code(parsed: &custom_format::ParsedCustomFormat) -> String {
+    use custom_format::FormatToken as T;
+    let section = &parsed.positive;
+    let has = |pred: fn(&T) -> bool| section.tokens.iter().any(pred);
+    let code = if section.is_datetime {
+        let month_name = has(|t| matches!(t, T::DateMonthName1 | T::DateMonthName3 | T::DateMonthName4));
+        let day = has(|t| matches!(t, T::DateDay1 | T::DateDay2));
+        let month = has(|t| matches!(t, T::DateMonth1 | T::DateMonth2));
+        let year = has(|t| matches!(t, T::DateYear2 | T::DateYear4));
+        if month_name || day || month || year {
+            date_format_code(month_name, day, month, year).to_string()
+        } else {
+            let seconds = has(|t| matches!(t, T::TimeSecond1 | T::TimeSecond2 | T::ElapsedSeconds));
+            time_format_code(has(|t| matches!(t, T::AmPm)), seconds).to_string()
+        }
+    } else if !section.has_digits {
+        if section.has_text_placeholder { "@" } else { "G" }.to_string()
+    } else {
+        let decimals = section
+            .tokens
+            .iter()
+            .skip_while(|t| !matches!(t, T::DecimalPoint))
+            .take_while(|t| !matches!(t, T::Scientific { .. }))
+            .filter(|t| matches!(t, T::DigitZero | T::DigitHash | T::DigitSpace))
+            .count();
+        let currency = has(|t| matches!(t, T::Literal(l) if l.contains(['$', '€', '£', '¥'])));
+        let letter = if section.has_scientific {
+            "S"
+        } else if section.has_percent {
+            "P"
+        } else if currency {
+            "C"
+        } else if has(|t| matches!(t, T::Comma)) && section.scale_divisor == 0 {
+            ","
+        } else {
+            "F"
+        };
+        format!("{}{}", letter, decimals)
+    };
+    let negative_color = parsed.negative.as_ref().is_some_and(|n| n.color.is_some());
+    let parentheses = has(|t| matches!(t, T::Literal(l) if l.contains('(')));
+    format!("{}{}{}", code, if negative_color { "-" } else { "" }, if parentheses { "()" } else { "" })
+}
+
+/// D1 d-mmm-yy, D2 d-mmm, D3 mmm-yy, D4 m/d/yy, D5 m/d.
+fn date_format_code(month_name: bool, day: bool, month: bool, year: bool) -> &'static str {
+    match (month_name, day, year) {
+        (true, true, true) => "D1",
+        (true, true, false) => "D2",
+        (true, false, _) => "D3",
+        (false, true, false) if month => "D5",
+        _ => "D4",
+    }
+}
+
+/// D6 h:mm:ss AM/PM, D7 h:mm AM/PM, D8 h:mm:ss, D9 h:mm.
+fn time_format_code(am_pm: bool, seconds: bool) -> &'static str {
+    match (am_pm, seconds) {
+        (true, true) => "D6",
+        (true, false) => "D7",
+        (false, true) => "D8",
+        (false, false) => "D9",
+    }
+}
+
+/// The value as `format` displays it, for precision-as-displayed calculation:
+/// rounded to the decimals shown (two more for a percentage, three fewer per
+/// scaling comma; significant digits for scientific). Formats without a
+/// fixed precision — General, fractions, dates and times — leave it as is.
+pub fn round_to_displayed(value: f64, format: &NumberFormat) -> f64 {
+    if !value.is_finite() || value == 0.0 {
+        return value;
+    }
+    let decimals = match format {
+        NumberFormat::Number { decimal_places, .. }
+        | NumberFormat::Currency { decimal_places, .. }
+        | NumberFormat::Accounting { decimal_places, .. } => *decimal_places as i32,
+        NumberFormat::Percentage { decimal_places } => *decimal_places as i32 + 2,
+        NumberFormat::Scientific { decimal_places } => *decimal_places as i32 - exponent_of(value),
+        NumberFormat::Custom { format } => match custom_displayed_decimals(value, format) {
+            Some(d) => d,
+            None => return value,
+        },
+        _ => return value,
+    };
+    if decimals > 15 {
+        return value;
+    }
+    let factor = 10f64.powi(decimals);
+    (value * factor).round() / factor
+}
+
+fn exponent_of(value: f64) -> i32 {
+    value.abs().log10().floor() as i32
+}
+
+/// Decimals shown by the custom-format section that renders `value`, or None
+/// when the section does not show a fixed-precision number.
+fn custom_displayed_decimals(value: f64, format: &str) -> Option<i32> {
+    use custom_format::FormatToken;
+    let parsed = custom_format::parse_custom_format(format).ok()?;
+    if parsed.has_conditions {
+        return None;
+    }
+    let section = match &parsed.negative {
+        Some(negative) if value < 0.0 => negative,
+        _ => &parsed.positive,
+    };
+    if section.is_datetime || section.has_fraction || !section.has_digits {
+        return None;
+    }
+    let mut decimals = section
+        .tokens
+        .iter()
+        .skip_while(|t| !matches!(t, FormatToken::DecimalPoint))
+        .take_while(|t| !matches!(t, FormatToken::Scientific { .. }))
+        .filter(|t| matches!(t, FormatToken::DigitZero | FormatToken::DigitHash | FormatToken::DigitSpace))
+        .count() as i32;
+    if section.has_scientific {
+        return Some(decimals - exponent_of(value));
+    }
+    if section.has_percent {
+        decimals += 2;
+    }
+    Some(decimals - 3 * section.scale_divisor as i32)
+}
+
+// ============================================================================
+// NUMBER TEXT PARSING
+// ============================================================================
+
+/// Currency symbols read on any locale, besides the locale's own.
+const COMMON_CURRENCY_SYMBOLS: [&str; 4] = ["$", "\u{20AC}", "\u{00A3}", "\u{00A5}"];
+
+/// Parses text that shows a number: an optional sign or accounting
+/// parentheses, `group` separators in the integer part, `decimal` as the
+/// decimal point, an exponent, and trailing percent signs (each divides by
+/// 100). With `currency`, that symbol and the common ones are accepted before
+/// or after the number. Typed cell input, VALUE and NUMBERVALUE all read
+/// numbers through this. None for anything else, including "inf" and "NaN".
+pub fn parse_number_text(text: &str, decimal: char, group: Option<char>, currency: Option<&str>) -> Option<f64> {
+    let mut s = text.trim();
+    let mut percents = 0;
+    while let Some(rest) = s.strip_suffix('%') {
+        s = rest.trim_end();
+        percents += 1;
+    }
+    let parenthesized = s.len() >= 2 && s.starts_with('(') && s.ends_with(')');
+    if parenthesized {
+        s = s[1..s.len() - 1].trim();
+    }
+
+    let symbols = currency.map(|own| {
+        std::iter::once(own.trim()).chain(COMMON_CURRENCY_SYMBOLS).filter(|c| !c.is_empty())
+    });
+    let strip_currency = |s: &mut &str, prefix: bool| -> bool {
+        let Some(symbols) = symbols.clone() else { return false };
+        for symbol in symbols {
+            let rest = if prefix { s.strip_prefix(symbol) } else { s.strip_suffix(symbol) };
+            if let Some(rest) = rest {
+                *s = rest.trim();
+                return true;
+            }
+        }
+        false
+    };
+
+    // "-$5", "$-5" and "5 $" all read as signed amounts
+    let take_sign = |s: &mut &str| -> bool {
+        if let Some(rest) = s.strip_prefix('-') {
+            *s = rest.trim_start();
+            true
+        } else {
+            *s = s.strip_prefix('+').unwrap_or(s).trim_start();
+            false
+        }
+    };
+    let mut negative = take_sign(&mut s);
+    if strip_currency(&mut s, true) {
+        if !negative {
+            negative = take_sign(&mut s);
+        }
+    } else {
+        strip_currency(&mut s, false);
+    }
+    if parenthesized && negative {
+        return None;
+    }
+
+    // Rebuild as invariant text: digits, '.', and an exponent
+    let mut invariant = String::with_capacity(s.len());
+    let mut seen_digit = false;
+    let mut seen_decimal = false;
+    let mut chars = s.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '0'..='9' => {
+                seen_digit = true;
+                invariant.push(c);
+            }
+            c if c == decimal && !seen_decimal => {
+                seen_decimal = true;
+                invariant.push('.');
+            }
+            c if Some(c) == group && seen_digit && !seen_decimal => {}
+            'e' | 'E' if seen_digit => {
+                invariant.push('e');
+                if let Some(sign) = chars.next_if(|c| *c == '+' || *c == '-') {
+                    invariant.push(sign);
+                }
+                let exponent: String = chars.by_ref().collect();
+                if exponent.is_empty() || !exponent.chars().all(|c| c.is_ascii_digit()) {
+                    return None;
+                }
+                invariant.push_str(&exponent);
+            }
+            _ => return None,
+        }
+    }
+    if !seen_digit {
+        return None;
+    }
+    let mut value: f64 = invariant.parse().ok()?;
+    if negative || parenthesized {
+        value = -value;
+    }
+    for _ in 0..percents {
+        value /= 100.0;
+    }
+    value.is_finite().then_some(value)
+}
+
 /// Predefined number formats for common use cases.
 pub mod presets {
     use super::*;
@@ -572,6 +758,12 @@ pub mod presets {
             format: "hh:MM:SS AM/PM".to_string(),
         }
     }
+
+    pub fn datetime_iso() -> NumberFormat {
+        NumberFormat::DateTime {
+            format: "YYYY-MM-DD HH:MM".to_string(),
+        }
+    }
 }
 
 #[cfg(test)]
@@ -581,6 +773,84 @@ mod tests {
     fn us() -> LocaleSettings { LocaleSettings::invariant() }
     fn se() -> LocaleSettings { LocaleSettings::from_locale_id("sv-SE") }
 
+    #[test]
+    fn test_cell_format_code() {
+        let custom = |f: &str| cell_format_code(&NumberFormat::Custom { format: f.to_string() });
+        assert_eq!(cell_format_code(&NumberFormat::General), "G");
+        assert_eq!(cell_format_code(&presets::number(2)), "F2");
+        assert_eq!(
+            cell_format_code(&NumberFormat::Number { decimal_places: 0, use_thousands_separator: true }),
+            ",0"
+        );
+        assert_eq!(cell_format_code(&NumberFormat::Percentage { decimal_places: 1 }), "P1");
+        assert_eq!(cell_format_code(&NumberFormat::Scientific { decimal_places: 2 }), "S2");
+        assert_eq!(cell_format_code(&NumberFormat::Date { format: "YYYY-MM-DD".to_string() }), "D4");
+        assert_eq!(cell_format_code(&NumberFormat::Date { format: "DD-MMM".to_string() }), "D2");
+        assert_eq!(cell_format_code(&presets::time_12h()), "D6");
+        assert_eq!(custom("0.00"), "F2");
+        assert_eq!(custom("#,##0"), ",0");
+        assert_eq!(custom("$#,##0.00"), "C2");
+        assert_eq!(custom("0%"), "P0");
+        assert_eq!(custom("0.00E+00"), "S2");
+        assert_eq!(custom("#,##0;[Red]-#,##0"), ",0-");
+        assert_eq!(custom("(#,##0.00)"), ",2()");
+        assert_eq!(custom("d-mmm-yy"), "D1");
+        assert_eq!(custom("mmm-yy"), "D3");
+        assert_eq!(custom("m/d"), "D5");
+        assert_eq!(custom("h:mm"), "D9");
+        assert_eq!(custom("@"), "@");
+    }
+
+    #[test]
+    fn test_is_date_format() {
+        let custom = |f: &str| NumberFormat::Custom { format: f.to_string() };
+        assert!(is_date_format(&NumberFormat::Date { format: "yyyy-mm-dd".to_string() }));
+        assert!(is_date_format(&NumberFormat::Time { format: "hh:mm".to_string() }));
+        assert!(is_date_format(&custom("dd/mm/yyyy")));
+        assert!(!is_date_format(&custom("#,##0.00")));
+        assert!(!is_date_format(&NumberFormat::General));
+    }
+
+    #[test]
+    fn test_parse_number_text() {
+        let us = |s: &str| parse_number_text(s, '.', Some(','), Some("$"));
+        assert_eq!(us("1,234.5"), Some(1234.5));
+        assert_eq!(us(" $1,234 "), Some(1234.0));
+        assert_eq!(us("-$5"), Some(-5.0));
+        assert_eq!(us("$-5"), Some(-5.0));
+        assert_eq!(us("($1,000.50)"), Some(-1000.5));
+        assert_eq!(us("12.5%"), Some(0.125));
+        assert_eq!(us("1.5E3"), Some(1500.0));
+        assert_eq!(us("\u{20AC}7"), Some(7.0));
+        assert_eq!(us(".5"), Some(0.5));
+        for bad in ["abc", "", "$", "1.2.3", "1.2,3", "inf", "NaN", "1e", "-(5)", "5-", ",5"] {
+            assert_eq!(us(bad), None, "{bad}");
+        }
+        // Comma-decimal locales, no currency
+        let se = |s: &str| parse_number_text(s, ',', Some(' '), None);
+        assert_eq!(se("1 234,5"), Some(1234.5));
+        assert_eq!(se("50%%"), Some(0.005));
+        assert_eq!(se("$5"), None);
+        assert_eq!(parse_number_text("12 kr", ',', Some(' '), Some("kr")), Some(12.0));
+    }
+
+    #[test]
+    fn test_round_to_displayed() {
+        let r = round_to_displayed;
+        assert_eq!(r(1.0 / 3.0, &presets::number(2)), 0.33);
+        assert_eq!(r(2.675_1, &presets::currency_usd(0)), 3.0);
+        assert_eq!(r(0.123_456, &presets::percentage(1)), 0.123);
+        assert_eq!(r(123_456.0, &presets::scientific(2)), 123_000.0);
+        let custom = |f: &str| NumberFormat::Custom { format: f.to_string() };
+        assert_eq!(r(1234.5678, &custom("#,##0.0")), 1234.6);
+        assert_eq!(r(1_234_567.0, &custom("#,##0,")), 1_235_000.0);
+        assert_eq!(r(-1.256, &custom("0.000;(0.0)")), -1.3);
+        // No fixed precision: unchanged.
+        assert_eq!(r(1.0 / 3.0, &NumberFormat::General), 1.0 / 3.0);
+        assert_eq!(r(45306.75, &custom("yyyy-mm-dd")), 45306.75);
+        assert_eq!(r(0.5, &NumberFormat::Fraction { denominator: None, max_digits: 1 }), 0.5);
+    }
+
     #[test]
     fn test_format_general() {
         let l = us();
@@ -642,6 +912,28 @@ mod tests {
         assert_eq!(format_scientific(0.00123, 3), "1.230E-3");
     }
 
+    #[test]
+    fn test_format_date_time() {
+        let l = us();
+        // 45306 = 2024-01-15; .5625 = 13:30.
+        let serial = 45306.5625;
+        assert_eq!(format_number(serial, &presets::date_iso(), &l), "2024-01-15");
+        assert_eq!(format_number(serial, &presets::date_us(), &l), "01/15/2024");
+        assert_eq!(format_number(serial, &presets::date_eu(), &l), "15/01/2024");
+        assert_eq!(format_number(serial, &presets::time_24h(), &l), "13:30:00");
+        assert_eq!(format_number(serial, &presets::time_12h(), &l), "01:30:00 PM");
+        assert_eq!(format_number(serial, &presets::datetime_iso(), &l), "2024-01-15 13:30");
+        // Tokens are case-insensitive, and month names render.
+        let lower = NumberFormat::Date { format: "yyyy-mm-dd".to_string() };
+        assert_eq!(format_number(serial, &lower, &l), "2024-01-15");
+        let named = NumberFormat::Date { format: "d mmm yyyy".to_string() };
+        assert_eq!(format_number(serial, &named, &l), "15 Jan 2024");
+        // Early serials, and a negative (non-date) serial.
+        assert_eq!(format_number(1.0, &presets::date_iso(), &l), "1900-01-01");
+        assert_eq!(format_number(61.0, &presets::date_iso(), &l), "1900-03-01");
+        assert_eq!(format_number(-1.0, &presets::date_iso(), &l), "-1");
+    }
+
     #[test]
     fn test_thousands_separator() {
         let l = us();
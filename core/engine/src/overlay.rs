@@ -0,0 +1,241 @@
+//! FILENAME: core/engine/src/overlay.rs
+//! PURPOSE: What-if views over a Grid without copying it.
+//! CONTEXT: Goal seek (and data tables, scenarios, solver) must evaluate the
+//! workbook as if a few cells held trial values. Cloning every `Grid` for
+//! that costs O(cells) per trial; an `OverlayGrid` instead borrows the base
+//! grid and keeps only the overridden cells, so a trial costs O(overrides).
+//! The evaluator reads through `GridView`, which answers `get_cell`, cell
+//! iteration and the used extent with the overrides merged in. Register
+//! overlays with `MultiSheetContext::with_overlays` to evaluate against them.
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use crate::cell::Cell;
+use crate::grid::{CellMap, Grid};
+
+/// Source of overlay identities for the pass-scoped lookup cache. Ids are
+/// odd, so they never collide with a grid address (always even).
+static NEXT_OVERLAY_ID: AtomicUsize = AtomicUsize::new(0);
+
+/// A borrowed grid with a sparse set of cells replaced.
+#[derive(Debug)]
+pub struct OverlayGrid<'a> {
+    /// The grid being overridden. Never modified.
+    pub base: &'a Grid,
+    /// Replacement cells keyed by (row, col).
+    overrides: CellMap,
+    id: usize,
+}
+
+impl<'a> OverlayGrid<'a> {
+    /// Creates an overlay with no overrides; it reads exactly like `base`.
+    pub fn new(base: &'a Grid) -> Self {
+        OverlayGrid {
+            base,
+            overrides: CellMap::default(),
+            id: NEXT_OVERLAY_ID.fetch_add(1, Ordering::Relaxed) * 2 + 1,
+        }
+    }
+
+    /// Overrides the cell at (row, col). The base grid is left untouched.
+    pub fn set_cell(&mut self, row: u32, col: u32, cell: Cell) {
+        crate::lookup_cache::notify_write(row, col);
+        self.overrides.insert((row, col), cell);
+    }
+
+    /// Drops the override at (row, col), exposing the base cell again.
+    pub fn remove_override(&mut self, row: u32, col: u32) -> Option<Cell> {
+        crate::lookup_cache::notify_write(row, col);
+        self.overrides.remove(&(row, col))
+    }
+
+    /// The cell at (row, col) as seen through the overlay.
+    pub fn get_cell(&self, row: u32, col: u32) -> Option<&Cell> {
+        self.view().get_cell(row, col)
+    }
+
+    /// The overridden cells.
+    pub fn overrides(&self) -> &CellMap {
+        &self.overrides
+    }
+
+    /// Read view with the overrides merged in.
+    pub(crate) fn view(&self) -> GridView<'_> {
+        let (mut max_row, mut max_col) = (self.base.max_row, self.base.max_col);
+        for &(r, c) in self.overrides.keys() {
+            max_row = max_row.max(r);
+            max_col = max_col.max(c);
+        }
+        GridView {
+            base: self.base,
+            overrides: Some(&self.overrides),
+            id: self.id,
+            max_row,
+            max_col,
+        }
+    }
+}
+
+/// The read interface the evaluator uses: a plain grid, or a grid seen
+/// through an overlay. Cheap to copy.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct GridView<'a> {
+    base: &'a Grid,
+    overrides: Option<&'a CellMap>,
+    id: usize,
+    /// Highest used row, overrides included.
+    pub(crate) max_row: u32,
+    /// Highest used column, overrides included.
+    pub(crate) max_col: u32,
+}
+
+impl<'a> From<&'a Grid> for GridView<'a> {
+    fn from(grid: &'a Grid) -> Self {
+        GridView {
+            base: grid,
+            overrides: None,
+            id: grid as *const Grid as usize,
+            max_row: grid.max_row,
+            max_col: grid.max_col,
+        }
+    }
+}
+
+impl<'a> GridView<'a> {
+    /// The grid underneath any overrides.
+    pub(crate) fn base(&self) -> &'a Grid {
+        self.base
+    }
+
+    /// Identity for cache keys: the grid address, or the overlay id.
+    pub(crate) fn id(&self) -> usize {
+        self.id
+    }
+
+    pub(crate) fn get_cell(&self, row: u32, col: u32) -> Option<&'a Cell> {
+        if let Some(cell) = self.overrides.and_then(|o| o.get(&(row, col))) {
+            return Some(cell);
+        }
+        self.base.get_cell(row, col)
+    }
+
+    /// Every stored cell, overrides replacing the base cells they shadow.
+    pub(crate) fn cells(&self) -> impl Iterator<Item = (&'a (u32, u32), &'a Cell)> + 'a {
+        let overrides = self.overrides;
+        let base = self
+            .base
+            .cells
+            .iter()
+            .filter(move |(pos, _)| overrides.is_none_or(|o| !o.contains_key(*pos)));
+        base.chain(overrides.into_iter().flat_map(|o| o.iter()))
+    }
+
+    /// Upper bound on the number of stored cells (exact for a plain grid).
+    pub(crate) fn cell_count(&self) -> usize {
+        self.base.cells.len() + self.overrides.map_or(0, |o| o.len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::evaluator::{EvalResult, Evaluator, MultiSheetContext};
+
+    fn workbook() -> Vec<Grid> {
+        let mut data = Grid::new();
+        for r in 0..50 {
+            data.set_cell(r, 0, Cell::new_number(r as f64));
+            data.set_cell(r, 1, Cell::new_text(if r % 2 == 0 { "even" } else { "odd" }.to_string()));
+        }
+        let mut other = Grid::new();
+        other.set_cell(0, 0, Cell::new_number(7.0));
+        vec![data, other]
+    }
+
+    fn names() -> Vec<String> {
+        vec!["Data".to_string(), "Other".to_string()]
+    }
+
+    fn eval_with(grids: &[Grid], overlays: Option<&[OverlayGrid<'_>]>, formula: &str) -> EvalResult {
+        let names = names();
+        let ast = parser::parse(formula).unwrap();
+        match overlays {
+            Some(overlays) => {
+                let ctx = MultiSheetContext::with_overlays(names[0].clone(), &names, overlays);
+                Evaluator::with_multi_sheet(overlays[0].base, ctx).evaluate(&ast)
+            }
+            None => {
+                let mut ctx = MultiSheetContext::new(names[0].clone());
+                for (name, grid) in names.iter().zip(grids) {
+                    ctx.add_grid(name.clone(), grid);
+                }
+                ctx.sheet_order = names.clone();
+                Evaluator::with_multi_sheet(&grids[0], ctx).evaluate(&ast)
+            }
+        }
+    }
+
+    #[test]
+    fn test_overlay_evaluation_matches_real_mutation() {
+        let grids = workbook();
+        let mut overlays: Vec<OverlayGrid> = grids.iter().map(OverlayGrid::new).collect();
+        overlays[0].set_cell(3, 0, Cell::new_number(1000.0));
+        overlays[0].set_cell(80, 0, Cell::new_number(5.0));
+        overlays[0].set_cell(4, 1, Cell::new_text("odd".to_string()));
+        overlays[1].set_cell(0, 0, Cell::new_number(-2.0));
+
+        let mut mutated = grids.clone();
+        mutated[0].set_cell(3, 0, Cell::new_number(1000.0));
+        mutated[0].set_cell(80, 0, Cell::new_number(5.0));
+        mutated[0].set_cell(4, 1, Cell::new_text("odd".to_string()));
+        mutated[1].set_cell(0, 0, Cell::new_number(-2.0));
+
+        for formula in [
+            "A4*2",
+            "SUM(A:A)",
+            "SUM(A1:A100)",
+            "COUNTIF(B:B,\"odd\")",
+            "SUMIF(B1:B50,\"even\",A1:A50)",
+            "VLOOKUP(1000,A1:B50,2,FALSE)",
+            "MATCH(5,A:A,0)",
+            "Other!A1*A4",
+            "SUM(Data:Other!A1)",
+        ] {
+            let expected = eval_with(&mutated, None, formula);
+            assert_eq!(eval_with(&grids, Some(&overlays), formula), expected, "{}", formula);
+        }
+        // The base grids are untouched.
+        assert_eq!(eval_with(&grids, None, "A4"), EvalResult::Number(3.0));
+    }
+
+    #[test]
+    fn test_overlay_does_not_copy_the_base_grid() {
+        let grids = workbook();
+        let mut overlay = OverlayGrid::new(&grids[0]);
+        overlay.set_cell(0, 0, Cell::new_number(42.0));
+
+        // Only the override is stored; every other cell is the base's own.
+        assert_eq!(overlay.overrides().len(), 1);
+        assert!(std::ptr::eq(overlay.base, &grids[0]));
+        for r in 1..50 {
+            assert!(std::ptr::eq(overlay.get_cell(r, 0).unwrap(), grids[0].get_cell(r, 0).unwrap()));
+        }
+        assert_eq!(overlay.get_cell(0, 0).unwrap().value, crate::cell::CellValue::Number(42.0));
+        assert_eq!(overlay.view().cells().count(), grids[0].cells.len());
+
+        overlay.remove_override(0, 0);
+        assert!(std::ptr::eq(overlay.get_cell(0, 0).unwrap(), grids[0].get_cell(0, 0).unwrap()));
+    }
+
+    #[test]
+    fn test_overlay_writes_invalidate_the_lookup_pass() {
+        let grids = workbook();
+        let _pass = crate::lookup_cache::begin_pass();
+        let mut overlays: Vec<OverlayGrid> = grids.iter().map(OverlayGrid::new).collect();
+        let formula = "COUNTIF(A1:A50,5)";
+        assert_eq!(eval_with(&grids, Some(&overlays), formula), EvalResult::Number(1.0));
+        overlays[0].set_cell(6, 0, Cell::new_number(5.0));
+        assert_eq!(eval_with(&grids, Some(&overlays), formula), EvalResult::Number(2.0));
+        assert_eq!(eval_with(&grids, None, formula), EvalResult::Number(1.0));
+    }
+}
@@ -0,0 +1,402 @@
+//! FILENAME: core/engine/src/reference_shift.rs
+//! PURPOSE: Reference shifting on the formula AST.
+//! CONTEXT: One visitor (`map_references`) walks every spatial reference in a
+//! formula; callers supply the per-reference mapping. Fill/copy uses
+//! `shift_for_fill` (relative components move, `$` components stay); the
+//! Tauri layer's row/column insert/delete passes its own structural mapping
+//! through the same visitor.
+//!
+//! Names (NamedRef) and structured table references (TableRef) are never
+//! visited: they resolve by name, not by position. A reference the mapping
+//! rejects is replaced by the `#REF!` marker (see `ref_error_marker`), which
+//! renders as `#REF!`.
+
+use crate::coord::{col_to_index, index_to_col};
+use parser::ast::Expression;
+
+/// Largest 1-based row number a reference may use.
+pub const MAX_ROW: u32 = 1_048_576;
+/// Largest 0-based column index a reference may use (XFD).
+pub const MAX_COL: u32 = 16_383;
+
+/// The node a rejected reference is replaced with. The parser has no error
+/// literal, so the marker is a name that renders as `#REF!`.
+pub fn ref_error_marker() -> Expression {
+    Expression::NamedRef { name: "#REF!".to_string(), ref_site_id: Default::default() }
+}
+
+/// True when `expr` is the `#REF!` marker.
+pub fn is_ref_error_marker(expr: &Expression) -> bool {
+    matches!(expr, Expression::NamedRef { name, .. } if name == "#REF!")
+}
+
+/// Sheet scope of a reference handed to a `map_references` callback.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RefScope<'a> {
+    /// No sheet prefix: the formula's own sheet.
+    Local,
+    /// `Sheet!ref`.
+    Sheet(&'a str),
+    /// `First:Last!ref`.
+    Sheets3D(&'a str, &'a str),
+}
+
+/// Walk every spatial reference (CellRef, Range, ColumnRef, RowRef — the
+/// whole node, not its endpoints) and let `f` rewrite it in place. When `f`
+/// returns false the reference is replaced with the `#REF!` marker. Returns
+/// true when any reference was rewritten or rejected.
+pub fn map_references(
+    expr: &mut Expression,
+    f: &mut dyn FnMut(&mut Expression, RefScope) -> bool,
+) -> bool {
+    let mut changed = false;
+    visit(expr, None, &mut changed, f);
+    changed
+}
+
+fn visit(
+    expr: &mut Expression,
+    scope_3d: Option<(&str, &str)>,
+    changed: &mut bool,
+    f: &mut dyn FnMut(&mut Expression, RefScope) -> bool,
+) {
+    match expr {
+        Expression::CellRef { .. }
+        | Expression::Range { .. }
+        | Expression::ColumnRef { .. }
+        | Expression::RowRef { .. } => {
+            let before = expr.clone();
+            let sheet = match expr {
+                Expression::CellRef { sheet, .. }
+                | Expression::Range { sheet, .. }
+                | Expression::ColumnRef { sheet, .. }
+                | Expression::RowRef { sheet, .. } => sheet.clone(),
+                _ => None,
+            };
+            let scope = match (scope_3d, sheet.as_deref()) {
+                (Some((a, b)), _) => RefScope::Sheets3D(a, b),
+                (None, Some(s)) => RefScope::Sheet(s),
+                (None, None) => RefScope::Local,
+            };
+            if !f(expr, scope) {
+                *expr = ref_error_marker();
+            }
+            if *expr != before {
+                *changed = true;
+            }
+        }
+        Expression::Sheet3DRef { start_sheet, end_sheet, reference, .. } => {
+            let (a, b) = (start_sheet.clone(), end_sheet.clone());
+            let before = reference.as_ref().clone();
+            visit(reference, Some((&a, &b)), changed, f);
+            // A rejected inner reference invalidates the whole 3D reference.
+            if is_ref_error_marker(reference) {
+                *expr = ref_error_marker();
+                *changed = true;
+            } else if **reference != before {
+                *changed = true;
+            }
+        }
+        Expression::BinaryOp { left, right, .. } => {
+            visit(left, scope_3d, changed, f);
+            visit(right, scope_3d, changed, f);
+        }
+        Expression::UnaryOp { operand, .. } | Expression::ImplicitIntersection { operand } => {
+            visit(operand, scope_3d, changed, f);
+        }
+        Expression::FunctionCall { args, .. } => {
+            for arg in args {
+                visit(arg, scope_3d, changed, f);
+            }
+        }
+        Expression::IndexAccess { target, index } => {
+            visit(target, scope_3d, changed, f);
+            visit(index, scope_3d, changed, f);
+        }
+        Expression::ListLiteral { elements } => {
+            for e in elements {
+                visit(e, scope_3d, changed, f);
+            }
+        }
+        Expression::DictLiteral { entries } => {
+            for (k, v) in entries {
+                visit(k, scope_3d, changed, f);
+                visit(v, scope_3d, changed, f);
+            }
+        }
+        Expression::SpillRef { cell, .. } => {
+            visit(cell, scope_3d, changed, f);
+            if is_ref_error_marker(cell) {
+                *expr = ref_error_marker();
+            }
+        }
+        Expression::Literal(_) | Expression::ArrayLiteral { .. } | Expression::NamedRef { .. } | Expression::TableRef { .. } => {}
+    }
+}
+
+/// Shift a 1-based row by `delta`; None when it leaves the sheet.
+fn shift_row(row: u32, delta: i32) -> Option<u32> {
+    let shifted = row as i64 + delta as i64;
+    (1..=MAX_ROW as i64).contains(&shifted).then_some(shifted as u32)
+}
+
+/// Shift a 0-based column by `delta`; None when it leaves the sheet.
+fn shift_col(col: u32, delta: i32) -> Option<u32> {
+    let shifted = col as i64 + delta as i64;
+    (0..=MAX_COL as i64).contains(&shifted).then_some(shifted as u32)
+}
+
+/// Fill/copy shift of one CellRef: relative components move, `$` components
+/// stay. False when a moved component falls off the sheet.
+fn shift_cell_for_fill(expr: &mut Expression, row_delta: i32, col_delta: i32) -> bool {
+    let Expression::CellRef { col, row, col_absolute, row_absolute, .. } = expr else {
+        return true;
+    };
+    if !*row_absolute && row_delta != 0 {
+        match shift_row(*row, row_delta) {
+            Some(r) => *row = r,
+            None => return false,
+        }
+    }
+    if !*col_absolute && col_delta != 0 {
+        match shift_col(col_to_index(col), col_delta) {
+            Some(c) => *col = index_to_col(c),
+            None => return false,
+        }
+    }
+    true
+}
+
+/// Swap range endpoints per axis when shifting inverted them (a relative
+/// endpoint crossing an absolute anchor: I10:$I$11 filled down 3 rows gives
+/// I13:$I$11, normalized to I$11:$I13). Each axis carries its `$` marker.
+fn normalize_range(start: &mut Expression, end: &mut Expression) {
+    if let (
+        Expression::CellRef { col: sc, row: sr, col_absolute: sca, row_absolute: sra, .. },
+        Expression::CellRef { col: ec, row: er, col_absolute: eca, row_absolute: era, .. },
+    ) = (start, end)
+    {
+        if *sr > *er {
+            std::mem::swap(sr, er);
+            std::mem::swap(sra, era);
+        }
+        if col_to_index(sc) > col_to_index(ec) {
+            std::mem::swap(sc, ec);
+            std::mem::swap(sca, eca);
+        }
+    }
+}
+
+/// Fill/copy mapping for one reference node (a `map_references` callback
+/// body). Whole-column references ignore row deltas and whole-row references
+/// ignore column deltas.
+pub fn shift_reference_for_fill(expr: &mut Expression, row_delta: i32, col_delta: i32) -> bool {
+    match expr {
+        Expression::CellRef { .. } => shift_cell_for_fill(expr, row_delta, col_delta),
+        Expression::Range { start, end, .. } => {
+            if !shift_cell_for_fill(start, row_delta, col_delta)
+                || !shift_cell_for_fill(end, row_delta, col_delta)
+            {
+                return false;
+            }
+            normalize_range(start, end);
+            true
+        }
+        Expression::ColumnRef { start_col, end_col, start_absolute, end_absolute, .. } => {
+            if col_delta == 0 {
+                return true;
+            }
+            for (col, absolute) in [(&mut *start_col, *start_absolute), (&mut *end_col, *end_absolute)] {
+                if !absolute {
+                    match shift_col(col_to_index(col), col_delta) {
+                        Some(c) => *col = index_to_col(c),
+                        None => return false,
+                    }
+                }
+            }
+            if col_to_index(start_col) > col_to_index(end_col) {
+                std::mem::swap(start_col, end_col);
+                std::mem::swap(start_absolute, end_absolute);
+            }
+            true
+        }
+        Expression::RowRef { start_row, end_row, start_absolute, end_absolute, .. } => {
+            if row_delta == 0 {
+                return true;
+            }
+            for (row, absolute) in [(&mut *start_row, *start_absolute), (&mut *end_row, *end_absolute)] {
+                if !absolute {
+                    match shift_row(*row, row_delta) {
+                        Some(r) => *row = r,
+                        None => return false,
+                    }
+                }
+            }
+            if start_row > end_row {
+                std::mem::swap(start_row, end_row);
+                std::mem::swap(start_absolute, end_absolute);
+            }
+            true
+        }
+        _ => true,
+    }
+}
+
+/// Shift every reference in `expr` for a fill/copy by (`row_delta`,
+/// `col_delta`). Cross-sheet and 3D references shift like local ones; names
+/// and table references never shift. Returns true when anything changed.
+pub fn shift_for_fill(expr: &mut Expression, row_delta: i32, col_delta: i32) -> bool {
+    if row_delta == 0 && col_delta == 0 {
+        return false;
+    }
+    map_references(expr, &mut |node, _scope| shift_reference_for_fill(node, row_delta, col_delta))
+}
+
+/// Byte ranges of the identifier-like words in formula text: quoted sheet
+/// names (`'My Sheet'`) and bare words (`Sheet1`, `TaxRate`, `SUM`, `A1`).
+/// String literals are skipped.
+fn words(text: &str) -> Vec<std::ops::Range<usize>> {
+    let is_word_char = |c: char| c.is_alphanumeric() || c == '_' || c == '.' || c == '\\';
+    let mut out = Vec::new();
+    let mut chars = text.char_indices().peekable();
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '"' | '\'' => {
+                // Doubled quotes escape themselves inside both forms.
+                let mut end = text.len();
+                while let Some((j, d)) = chars.next() {
+                    if d == c {
+                        if matches!(chars.peek(), Some((_, n)) if *n == c) {
+                            chars.next();
+                            continue;
+                        }
+                        end = j + 1;
+                        break;
+                    }
+                }
+                if c == '\'' {
+                    out.push(i..end);
+                }
+            }
+            c if is_word_char(c) => {
+                let mut end = i + c.len_utf8();
+                while let Some(&(j, d)) = chars.peek() {
+                    if !is_word_char(d) {
+                        break;
+                    }
+                    end = j + d.len_utf8();
+                    chars.next();
+                }
+                out.push(i..end);
+            }
+            _ => {}
+        }
+    }
+    out
+}
+
+/// The parser uppercases identifiers, so a rendered AST loses the author's
+/// spelling (`Sheet2!B4` comes back as `SHEET2!B4`). Give every word of
+/// `rendered` that also occurs in `original` (case-insensitively) its
+/// original spelling back.
+pub fn restore_identifier_case(rendered: &str, original: &str) -> String {
+    let mut spelling: std::collections::HashMap<String, &str> = std::collections::HashMap::new();
+    for range in words(original) {
+        let word = &original[range];
+        spelling.entry(word.to_uppercase()).or_insert(word);
+    }
+    let mut out = String::with_capacity(rendered.len());
+    let mut last = 0;
+    for range in words(rendered) {
+        let word = &rendered[range.clone()];
+        out.push_str(&rendered[last..range.start]);
+        out.push_str(spelling.get(&word.to_uppercase()).copied().unwrap_or(word));
+        last = range.end;
+    }
+    out.push_str(&rendered[last..]);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ast_render::render_formula;
+
+    fn fill(formula: &str, row_delta: i32, col_delta: i32) -> String {
+        let mut ast = parser::parse(formula).unwrap();
+        shift_for_fill(&mut ast, row_delta, col_delta);
+        restore_identifier_case(&render_formula(&ast), formula)
+    }
+
+    #[test]
+    fn test_fill_matrix_of_reference_forms() {
+        // (formula, down 2, right 1, up 1, left 1)
+        let cases: &[(&str, &str, &str, &str, &str)] = &[
+            ("B2", "B4", "C2", "B1", "A2"),
+            ("$B$2", "$B$2", "$B$2", "$B$2", "$B$2"),
+            ("$B2", "$B4", "$B2", "$B1", "$B2"),
+            ("B$2", "B$2", "C$2", "B$2", "A$2"),
+            ("B2:C3", "B4:C5", "C2:D3", "B1:C2", "A2:B3"),
+            ("$B$2:C3", "$B$2:C5", "$B$2:D3", "$B$2:C2", "$B$2:B3"),
+            ("B:B", "B:B", "C:C", "B:B", "A:A"),
+            ("$B:C", "$B:C", "$B:D", "$B:C", "$B:B"),
+            ("2:3", "4:5", "2:3", "1:2", "2:3"),
+            ("2:$3", "$3:4", "2:$3", "1:$3", "2:$3"),
+            ("Sheet2!B2", "Sheet2!B4", "Sheet2!C2", "Sheet2!B1", "Sheet2!A2"),
+            ("'My Sheet'!$B2", "'My Sheet'!$B4", "'My Sheet'!$B2", "'My Sheet'!$B1", "'My Sheet'!$B2"),
+            ("Sheet1:Sheet3!B2", "Sheet1:Sheet3!B4", "Sheet1:Sheet3!C2", "Sheet1:Sheet3!B1", "Sheet1:Sheet3!A2"),
+            ("TaxRate*B2", "TaxRate*B4", "TaxRate*C2", "TaxRate*B1", "TaxRate*A2"),
+            ("SUM(Table1[Amount])", "SUM(Table1[Amount])", "SUM(Table1[Amount])", "SUM(Table1[Amount])", "SUM(Table1[Amount])"),
+            ("B2#", "B4#", "C2#", "B1#", "A2#"),
+        ];
+        for (formula, down, right, up, left) in cases {
+            assert_eq!(fill(formula, 2, 0), *down, "{} filled down", formula);
+            assert_eq!(fill(formula, 0, 1), *right, "{} filled right", formula);
+            assert_eq!(fill(formula, -1, 0), *up, "{} filled up", formula);
+            assert_eq!(fill(formula, 0, -1), *left, "{} filled left", formula);
+        }
+    }
+
+    #[test]
+    fn test_fill_past_sheet_edges_is_ref_error() {
+        assert_eq!(fill("A1+1", -1, 0), "#REF!+1");
+        assert_eq!(fill("A1+1", 0, -1), "#REF!+1");
+        assert_eq!(fill("SUM(A1:B2)", -1, 0), "SUM(#REF!)");
+        assert_eq!(fill("A:B", 0, -1), "#REF!");
+        assert_eq!(fill("1:2", -1, 0), "#REF!");
+        assert_eq!(fill("Sheet1:Sheet3!A1", -1, 0), "#REF!");
+        assert_eq!(fill("A1048576", 1, 0), "#REF!");
+        assert_eq!(fill("XFD1", 0, 1), "#REF!");
+        // Absolute components never move, so they cannot fall off.
+        assert_eq!(fill("$A$1", -5, -5), "$A$1");
+        assert_eq!(fill("$A1", 0, -5), "$A1");
+    }
+
+    #[test]
+    fn test_fill_normalizes_inverted_ranges() {
+        assert_eq!(fill("I10:$I$11", 3, 0), "I$11:$I13");
+        assert_eq!(fill("SUM($A$1:A1)", 4, 0), "SUM($A$1:A5)");
+        assert_eq!(fill("$B1:C1", 0, -2), "A1:$B1");
+    }
+
+    #[test]
+    fn test_fill_preserves_precedence() {
+        assert_eq!(fill("(A1+B1)*2", 1, 0), "(A2+B2)*2");
+        assert_eq!(fill("-(A1-B1)^2", 1, 0), "-(A2-B2)^2");
+        assert_eq!(fill("A1-(B1-C1)", 1, 0), "A2-(B2-C2)");
+    }
+
+    #[test]
+    fn test_restore_identifier_case() {
+        assert_eq!(
+            restore_identifier_case("SUM('MY SHEET'!A2,TAXRATE,\"it's\")", "sum('My Sheet'!a1,TaxRate,\"it's\")"),
+            "sum('My Sheet'!A2,TaxRate,\"it's\")"
+        );
+    }
+
+    #[test]
+    fn test_zero_shift_is_untouched() {
+        let mut ast = parser::parse("A1+$B$2").unwrap();
+        assert!(!shift_for_fill(&mut ast, 0, 0));
+    }
+}
@@ -76,11 +76,21 @@ pub enum NumberFormat {
     Time {
         format: String, // e.g., "HH:MM:SS", "HH:MM AM/PM"
     },
+    DateTime {
+        format: String, // e.g., "YYYY-MM-DD HH:MM", "M/D/YYYY h:mm AM/PM"
+    },
     Custom {
         format: String,
     },
 }
 
+impl NumberFormat {
+    /// True for the Text format (`@`): input is stored verbatim as text.
+    pub fn is_text(&self) -> bool {
+        matches!(self, NumberFormat::Custom { format } if format.trim() == "@")
+    }
+}
+
 /// Position of currency symbol relative to the number.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, Default)]
 pub enum CurrencyPosition {
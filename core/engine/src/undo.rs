@@ -52,6 +52,18 @@ pub enum CellChange {
         row: u32,
         previous: Option<f64>,
     },
+    /// A whole-column default style was changed: (col, previous_style_index)
+    /// If previous is None, the column had no style of its own.
+    SetColumnStyle {
+        col: u32,
+        previous: Option<usize>,
+    },
+    /// A whole-row default style was changed: (row, previous_style_index)
+    /// If previous is None, the row had no style of its own.
+    SetRowStyle {
+        row: u32,
+        previous: Option<usize>,
+    },
     /// A merge region was added (undo = remove it).
     AddMergeRegion(UndoMergeRegion),
     /// A merge region was removed (undo = add it back).
@@ -102,6 +114,9 @@ pub struct UndoStack {
     current_transaction: Option<Transaction>,
     /// Maximum size of undo history
     max_size: usize,
+    /// Cell changes of committed transactions awaiting `take_committed`.
+    /// None unless a consumer asked for them with `set_commit_log(true)`.
+    committed_log: Option<Vec<Transaction>>,
 }
 
 impl UndoStack {
@@ -111,6 +126,7 @@ impl UndoStack {
             redo_stack: VecDeque::with_capacity(MAX_HISTORY_SIZE),
             current_transaction: None,
             max_size: MAX_HISTORY_SIZE,
+            committed_log: None,
         }
     }
 
@@ -120,6 +136,7 @@ impl UndoStack {
             redo_stack: VecDeque::with_capacity(max_size),
             current_transaction: None,
             max_size,
+            committed_log: None,
         }
     }
 
@@ -194,6 +211,32 @@ impl UndoStack {
         }
     }
 
+    /// Record a whole-column style change.
+    pub fn record_column_style_change(&mut self, col: u32, previous: Option<usize>) {
+        let change = CellChange::SetColumnStyle { col, previous };
+
+        if let Some(ref mut transaction) = self.current_transaction {
+            transaction.add_change(change);
+        } else {
+            let mut transaction = Transaction::new(format!("Format column {}", col));
+            transaction.add_change(change);
+            self.push_transaction(transaction);
+        }
+    }
+
+    /// Record a whole-row style change.
+    pub fn record_row_style_change(&mut self, row: u32, previous: Option<usize>) {
+        let change = CellChange::SetRowStyle { row, previous };
+
+        if let Some(ref mut transaction) = self.current_transaction {
+            transaction.add_change(change);
+        } else {
+            let mut transaction = Transaction::new(format!("Format row {}", row));
+            transaction.add_change(change);
+            self.push_transaction(transaction);
+        }
+    }
+
     /// Record that a merge region was added (for undo of merge).
     pub fn record_merge_region_added(&mut self, region: UndoMergeRegion) {
         let change = CellChange::AddMergeRegion(region);
@@ -251,8 +294,37 @@ impl UndoStack {
         // Snapshots should always be within a transaction (caller must begin one)
     }
 
+    /// Start or stop logging committed transactions for `take_committed`.
+    /// Only their `SetCell` changes are kept. Undo/redo do not commit, so
+    /// they never appear in the log.
+    pub fn set_commit_log(&mut self, enabled: bool) {
+        self.committed_log = enabled.then(Vec::new);
+    }
+
+    /// Whether committed transactions are being logged.
+    pub fn is_commit_log_enabled(&self) -> bool {
+        self.committed_log.is_some()
+    }
+
+    /// Take the transactions committed since the last call, oldest first.
+    pub fn take_committed(&mut self) -> Vec<Transaction> {
+        self.committed_log.as_mut().map(std::mem::take).unwrap_or_default()
+    }
+
     /// Push a completed transaction onto the undo stack.
     fn push_transaction(&mut self, transaction: Transaction) {
+        if let Some(log) = self.committed_log.as_mut() {
+            let changes: Vec<CellChange> = transaction
+                .changes
+                .iter()
+                .filter(|c| matches!(c, CellChange::SetCell { .. }))
+                .cloned()
+                .collect();
+            if !changes.is_empty() {
+                log.push(Transaction { description: transaction.description.clone(), changes });
+            }
+        }
+
         // Clear redo stack when new action is performed
         self.redo_stack.clear();
         
@@ -328,6 +400,9 @@ impl UndoStack {
         self.undo_stack.clear();
         self.redo_stack.clear();
         self.current_transaction = None;
+        if let Some(log) = self.committed_log.as_mut() {
+            log.clear();
+        }
     }
 
     /// Get current stack sizes (for debugging).
@@ -353,6 +428,7 @@ mod tests {
             value: CellValue::Number(val),
             style_index: 0,
             rich_text: None,
+            quote_prefix: false,
         }
     }
 
@@ -453,4 +529,30 @@ mod tests {
         assert!(stack.can_redo()); // Redo should still be available
         assert!(stack.can_undo());
     }
+
+    #[test]
+    fn test_commit_log_keeps_cell_changes_of_commits() {
+        let mut stack = UndoStack::new();
+        stack.record_cell_change(0, 0, None);
+        assert!(stack.take_committed().is_empty()); // not enabled
+
+        stack.set_commit_log(true);
+        stack.begin_transaction("Paste 2 cells");
+        stack.record_cell_change(1, 0, None);
+        stack.record_column_width_change(0, Some(80.0));
+        stack.record_cell_change(1, 1, Some(make_cell(3.0)));
+        stack.commit_transaction();
+        stack.record_cell_change(2, 0, None);
+
+        // Undo/redo bookkeeping is not a commit.
+        let txn = stack.pop_undo().unwrap();
+        stack.push_redo(txn);
+
+        let committed = stack.take_committed();
+        assert_eq!(committed.len(), 2);
+        assert_eq!(committed[0].description, "Paste 2 cells");
+        assert_eq!(committed[0].changes.len(), 2);
+        assert_eq!(committed[1].description, "Edit cell (2, 0)");
+        assert!(stack.take_committed().is_empty());
+    }
 }
\ No newline at end of file
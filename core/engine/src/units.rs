@@ -0,0 +1,340 @@
+//! FILENAME: core/engine/src/units.rs
+//! PURPOSE: Unit registry behind CONVERT: Excel's unit tables with metric and
+//! binary prefixes.
+//! CONTEXT: Every unit is stored as a factor to its category's base unit
+//! (g, m, s, Pa, N, J, W, T, K, m^3, m^2, bit, m/s). Names are case-sensitive,
+//! as in Excel. A name that is not a unit itself may be a prefix ("k", "da",
+//! "Mi") followed by a unit that accepts one; squared and cubed units apply
+//! the prefix to each dimension ("km2" = 1e6 m2). Temperatures are affine
+//! (kelvin = value * factor + offset), everything else is pure scaling.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UnitCategory {
+    Mass,
+    Length,
+    Time,
+    Pressure,
+    Force,
+    Energy,
+    Power,
+    Magnetism,
+    Temperature,
+    Volume,
+    Area,
+    Information,
+    Speed,
+}
+
+/// Which prefixes a unit accepts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Prefixes {
+    None,
+    Metric,
+    /// Metric and binary (information units only).
+    MetricAndBinary,
+}
+
+struct UnitDef {
+    names: &'static [&'static str],
+    category: UnitCategory,
+    factor: f64,
+    /// Added after scaling; non-zero only for temperatures.
+    offset: f64,
+    prefixes: Prefixes,
+    /// Dimension the prefix applies to (2 for areas, 3 for volumes).
+    power: i32,
+}
+
+/// A resolved unit, prefix included.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Unit {
+    pub category: UnitCategory,
+    /// Multiplier to the category's base unit.
+    pub factor: f64,
+    pub offset: f64,
+}
+
+const fn unit(names: &'static [&'static str], category: UnitCategory, factor: f64, prefixes: Prefixes) -> UnitDef {
+    UnitDef { names, category, factor, offset: 0.0, prefixes, power: 1 }
+}
+
+const fn unit_pow(names: &'static [&'static str], category: UnitCategory, factor: f64, prefixes: Prefixes, power: i32) -> UnitDef {
+    UnitDef { names, category, factor, offset: 0.0, prefixes, power }
+}
+
+const fn temperature(names: &'static [&'static str], factor: f64, offset: f64, prefixes: Prefixes) -> UnitDef {
+    UnitDef { names, category: UnitCategory::Temperature, factor, offset, prefixes, power: 1 }
+}
+
+use Prefixes::{Metric, MetricAndBinary, None as Plain};
+use UnitCategory::*;
+
+const INCH: f64 = 0.0254;
+const LIGHT_YEAR: f64 = 9.4607304725808e15;
+const PICA_POINT: f64 = INCH / 72.0;
+const MILE: f64 = 1609.344;
+const NAUTICAL_MILE: f64 = 1852.0;
+const HORSEPOWER: f64 = 745.699871582270;
+
+static UNITS: &[UnitDef] = &[
+    // Mass (base: gram)
+    unit(&["g"], Mass, 1.0, Metric),
+    unit(&["sg"], Mass, 14593.902937206364, Plain),
+    unit(&["lbm"], Mass, 453.59237, Plain),
+    unit(&["u"], Mass, 1.66053906660e-24, Metric),
+    unit(&["ozm"], Mass, 28.349523125, Plain),
+    unit(&["grain"], Mass, 0.06479891, Plain),
+    unit(&["cwt", "shweight"], Mass, 45359.237, Plain),
+    unit(&["uk_cwt", "lcwt", "hweight"], Mass, 50802.34544, Plain),
+    unit(&["stone"], Mass, 6350.29318, Plain),
+    unit(&["ton"], Mass, 907184.74, Plain),
+    unit(&["uk_ton", "LTON", "brton"], Mass, 1016046.9088, Plain),
+    // Length (base: meter)
+    unit(&["m"], Length, 1.0, Metric),
+    unit(&["mi"], Length, MILE, Plain),
+    unit(&["Nmi"], Length, NAUTICAL_MILE, Plain),
+    unit(&["in"], Length, INCH, Plain),
+    unit(&["ft"], Length, 0.3048, Plain),
+    unit(&["yd"], Length, 0.9144, Plain),
+    unit(&["ang"], Length, 1e-10, Metric),
+    unit(&["ell"], Length, 1.143, Plain),
+    unit(&["ly"], Length, LIGHT_YEAR, Metric),
+    unit(&["parsec", "pc"], Length, 3.085_677_581_491_367e16, Metric),
+    unit(&["Pica", "Picapt"], Length, PICA_POINT, Plain),
+    unit(&["pica"], Length, INCH / 6.0, Plain),
+    unit(&["survey_mi"], Length, 1609.3472186944373, Plain),
+    // Time (base: second)
+    unit(&["yr"], Time, 31557600.0, Plain),
+    unit(&["day", "d"], Time, 86400.0, Plain),
+    unit(&["hr"], Time, 3600.0, Plain),
+    unit(&["mn", "min"], Time, 60.0, Plain),
+    unit(&["sec", "s"], Time, 1.0, Metric),
+    // Pressure (base: pascal)
+    unit(&["Pa", "p"], Pressure, 1.0, Metric),
+    unit(&["atm", "at"], Pressure, 101325.0, Metric),
+    unit(&["mmHg"], Pressure, 133.322387415, Metric),
+    unit(&["psi"], Pressure, 6894.757293168361, Plain),
+    unit(&["Torr"], Pressure, 101325.0 / 760.0, Plain),
+    // Force (base: newton)
+    unit(&["N"], Force, 1.0, Metric),
+    unit(&["dyn", "dy"], Force, 1e-5, Metric),
+    unit(&["lbf"], Force, 4.4482216152605, Plain),
+    unit(&["pond"], Force, 9.80665e-3, Metric),
+    // Energy (base: joule)
+    unit(&["J"], Energy, 1.0, Metric),
+    unit(&["e"], Energy, 1e-7, Metric),
+    unit(&["c"], Energy, 4.184, Metric),
+    unit(&["cal"], Energy, 4.1868, Metric),
+    unit(&["eV", "ev"], Energy, 1.602176634e-19, Metric),
+    unit(&["HPh", "hh"], Energy, HORSEPOWER * 3600.0, Plain),
+    unit(&["Wh", "wh"], Energy, 3600.0, Metric),
+    unit(&["flb"], Energy, 1.3558179483314004, Plain),
+    unit(&["BTU", "btu"], Energy, 1055.05585262, Plain),
+    // Power (base: watt)
+    unit(&["HP", "h"], Power, HORSEPOWER, Plain),
+    unit(&["PS"], Power, 735.49875, Plain),
+    unit(&["W", "w"], Power, 1.0, Metric),
+    // Magnetism (base: tesla)
+    unit(&["T"], Magnetism, 1.0, Metric),
+    unit(&["ga"], Magnetism, 1e-4, Metric),
+    // Temperature (base: kelvin)
+    temperature(&["C", "cel"], 1.0, 273.15, Plain),
+    temperature(&["F", "fah"], 5.0 / 9.0, 273.15 - 32.0 * 5.0 / 9.0, Plain),
+    temperature(&["K", "kel"], 1.0, 0.0, Metric),
+    temperature(&["Rank"], 5.0 / 9.0, 0.0, Plain),
+    temperature(&["Reau"], 1.25, 273.15, Plain),
+    // Volume (base: cubic meter)
+    unit(&["tsp"], Volume, 4.92892159375e-6, Plain),
+    unit(&["tspm"], Volume, 5e-6, Plain),
+    unit(&["tbs"], Volume, 1.478676478125e-5, Plain),
+    unit(&["oz"], Volume, 2.95735295625e-5, Plain),
+    unit(&["cup"], Volume, 2.365882365e-4, Plain),
+    unit(&["pt", "us_pt"], Volume, 4.73176473e-4, Plain),
+    unit(&["uk_pt"], Volume, 5.6826125e-4, Plain),
+    unit(&["qt"], Volume, 9.46352946e-4, Plain),
+    unit(&["uk_qt"], Volume, 1.1365225e-3, Plain),
+    unit(&["gal"], Volume, 3.785411784e-3, Plain),
+    unit(&["uk_gal"], Volume, 4.54609e-3, Plain),
+    unit(&["l", "L", "lt"], Volume, 1e-3, Metric),
+    unit_pow(&["ang3", "ang^3"], Volume, 1e-30, Metric, 3),
+    unit(&["barrel"], Volume, 0.158987294928, Plain),
+    unit(&["bushel"], Volume, 0.03523907016688, Plain),
+    unit(&["ft3", "ft^3"], Volume, 0.028316846592, Plain),
+    unit(&["in3", "in^3"], Volume, INCH * INCH * INCH, Plain),
+    unit_pow(&["ly3", "ly^3"], Volume, LIGHT_YEAR * LIGHT_YEAR * LIGHT_YEAR, Metric, 3),
+    unit_pow(&["m3", "m^3"], Volume, 1.0, Metric, 3),
+    unit(&["mi3", "mi^3"], Volume, MILE * MILE * MILE, Plain),
+    unit(&["yd3", "yd^3"], Volume, 0.764554857984, Plain),
+    unit(&["Nmi3", "Nmi^3"], Volume, NAUTICAL_MILE * NAUTICAL_MILE * NAUTICAL_MILE, Plain),
+    unit(&["Pica3", "Picapt3", "Pica^3", "Picapt^3"], Volume, PICA_POINT * PICA_POINT * PICA_POINT, Plain),
+    unit(&["GRT", "regton"], Volume, 2.8316846592, Plain),
+    unit(&["MTON"], Volume, 1.13267386368, Plain),
+    // Area (base: square meter)
+    unit(&["uk_acre"], Area, 4046.8564224, Plain),
+    unit(&["us_acre"], Area, 4046.872609874252, Plain),
+    unit_pow(&["ang2", "ang^2"], Area, 1e-20, Metric, 2),
+    unit(&["ar"], Area, 100.0, Metric),
+    unit(&["ft2", "ft^2"], Area, 0.09290304, Plain),
+    unit(&["ha"], Area, 10000.0, Plain),
+    unit(&["in2", "in^2"], Area, INCH * INCH, Plain),
+    unit_pow(&["ly2", "ly^2"], Area, LIGHT_YEAR * LIGHT_YEAR, Metric, 2),
+    unit_pow(&["m2", "m^2"], Area, 1.0, Metric, 2),
+    unit(&["Morgen"], Area, 2500.0, Plain),
+    unit(&["mi2", "mi^2"], Area, MILE * MILE, Plain),
+    unit(&["Nmi2", "Nmi^2"], Area, NAUTICAL_MILE * NAUTICAL_MILE, Plain),
+    unit(&["Pica2", "Picapt2", "Pica^2", "Picapt^2"], Area, PICA_POINT * PICA_POINT, Plain),
+    unit(&["yd2", "yd^2"], Area, 0.83612736, Plain),
+    // Information (base: bit)
+    unit(&["bit"], Information, 1.0, MetricAndBinary),
+    unit(&["byte"], Information, 8.0, MetricAndBinary),
+    // Speed (base: meter per second)
+    unit(&["admkn"], Speed, 1853.184 / 3600.0, Plain),
+    unit(&["kn"], Speed, NAUTICAL_MILE / 3600.0, Plain),
+    unit(&["m/h", "m/hr"], Speed, 1.0 / 3600.0, Metric),
+    unit(&["m/s", "m/sec"], Speed, 1.0, Metric),
+    unit(&["mph"], Speed, MILE / 3600.0, Plain),
+];
+
+/// Metric prefixes; "da" is listed first so it wins over "d".
+static METRIC_PREFIXES: &[(&str, f64)] = &[
+    ("da", 1e1),
+    ("Y", 1e24), ("Z", 1e21), ("E", 1e18), ("P", 1e15), ("T", 1e12), ("G", 1e9),
+    ("M", 1e6), ("k", 1e3), ("h", 1e2), ("e", 1e1),
+    ("d", 1e-1), ("c", 1e-2), ("m", 1e-3), ("u", 1e-6), ("n", 1e-9),
+    ("p", 1e-12), ("f", 1e-15), ("a", 1e-18), ("z", 1e-21), ("y", 1e-24),
+];
+
+/// Binary prefixes, accepted by information units only.
+static BINARY_PREFIXES: &[(&str, f64)] = &[
+    ("ki", 1024.0),
+    ("Mi", 1048576.0),
+    ("Gi", 1073741824.0),
+    ("Ti", 1099511627776.0),
+    ("Pi", 1125899906842624.0),
+    ("Ei", 1152921504606846976.0),
+    ("Zi", 1180591620717411303424.0),
+    ("Yi", 1208925819614629174706176.0),
+];
+
+fn find(name: &str) -> Option<&'static UnitDef> {
+    UNITS.iter().find(|u| u.names.contains(&name))
+}
+
+/// Resolve a unit name, with an optional prefix. An exact unit name always
+/// wins over a prefix reading ("mi" is a mile, not a milli-i).
+pub fn lookup(name: &str) -> Option<Unit> {
+    if let Some(def) = find(name) {
+        return Some(Unit { category: def.category, factor: def.factor, offset: def.offset });
+    }
+    let prefixed = |prefixes: &[(&str, f64)], binary: bool| {
+        prefixes.iter().find_map(|&(prefix, scale)| {
+            let def = find(name.strip_prefix(prefix)?)?;
+            let accepted = match def.prefixes {
+                Prefixes::None => false,
+                Prefixes::Metric => !binary,
+                Prefixes::MetricAndBinary => true,
+            };
+            accepted.then(|| Unit {
+                category: def.category,
+                factor: def.factor * scale.powi(def.power),
+                offset: def.offset,
+            })
+        })
+    };
+    prefixed(BINARY_PREFIXES, true).or_else(|| prefixed(METRIC_PREFIXES, false))
+}
+
+/// Category of a unit name (prefix included), if it is one.
+pub fn category_of(name: &str) -> Option<UnitCategory> {
+    lookup(name).map(|u| u.category)
+}
+
+/// Convert `value` between two units of the same category. `None` for an
+/// unknown unit or a cross-category pair (CONVERT's #N/A).
+pub fn convert(value: f64, from: &str, to: &str) -> Option<f64> {
+    let from = lookup(from)?;
+    let to = lookup(to)?;
+    if from.category != to.category {
+        return None;
+    }
+    let result = if from.category == UnitCategory::Temperature {
+        let kelvin = value * from.factor + from.offset;
+        (kelvin - to.offset) / to.factor
+    } else {
+        value * (from.factor / to.factor)
+    };
+    // Factors and prefixes leave noise in the last bits (100 C -> 211.99..F,
+    // 1 l -> 1000.0000000000001 ml); round to the 15 significant digits a
+    // cell can show.
+    Some(format!("{:.14e}", result).parse().unwrap_or(result))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn close(actual: f64, expected: f64) -> bool {
+        (actual - expected).abs() <= 1e-9 * expected.abs().max(1.0)
+    }
+
+    #[test]
+    fn test_one_conversion_per_category() {
+        let cases: &[(f64, &str, &str, f64)] = &[
+            (1.0, "lbm", "kg", 0.45359237),
+            (1.0, "mi", "ft", 5280.0),
+            (1.0, "day", "hr", 24.0),
+            (1.0, "atm", "Pa", 101325.0),
+            (1.0, "lbf", "N", 4.4482216152605),
+            (1.0, "kcal", "J", 4186.8),
+            (1.0, "HP", "W", 745.69987158227),
+            (1.0, "T", "ga", 10000.0),
+            (1.0, "gal", "l", 3.785411784),
+            (1.0, "l", "ml", 1000.0),
+            (1.0, "ha", "m2", 10000.0),
+            (1.0, "byte", "bit", 8.0),
+            (100.0, "km/h", "m/s", 27.7777777777778),
+        ];
+        for &(value, from, to, expected) in cases {
+            let got = convert(value, from, to).unwrap_or(f64::NAN);
+            assert_eq!(got, expected, "CONVERT({}, {}, {}) = {}, want {}", value, from, to, got, expected);
+        }
+    }
+
+    #[test]
+    fn test_prefixes() {
+        assert!(close(convert(1.0, "km", "m").unwrap(), 1000.0));
+        assert!(close(convert(1.0, "dam", "m").unwrap(), 10.0));
+        assert!(close(convert(1.0, "Mibyte", "byte").unwrap(), 1048576.0));
+        assert!(close(convert(1.0, "kibit", "bit").unwrap(), 1024.0));
+        assert!(close(convert(1.0, "km2", "m2").unwrap(), 1e6));
+        assert!(close(convert(1.0, "cm^3", "ml").unwrap(), 1.0));
+        assert!(close(convert(1.0, "mK", "K").unwrap(), 1e-3));
+        // Exact names win over a prefix reading; case matters.
+        assert_eq!(category_of("mi"), Some(UnitCategory::Length));
+        assert_eq!(category_of("min"), Some(UnitCategory::Time));
+        assert_eq!(category_of("pc"), Some(UnitCategory::Length));
+        assert_eq!(lookup("KM"), None);
+        // Binary prefixes only on information units; prefixes only where allowed.
+        assert_eq!(lookup("Mim"), None);
+        assert_eq!(lookup("kft"), None);
+    }
+
+    #[test]
+    fn test_temperature_is_affine() {
+        assert!(close(convert(212.0, "F", "C").unwrap(), 100.0));
+        assert!(close(convert(-40.0, "C", "F").unwrap(), -40.0));
+        assert!(close(convert(0.0, "C", "K").unwrap(), 273.15));
+        assert!(close(convert(0.0, "K", "Rank").unwrap(), 0.0));
+        assert!(close(convert(80.0, "Reau", "cel").unwrap(), 100.0));
+        assert!(close(convert(32.0, "fah", "kel").unwrap(), 273.15));
+    }
+
+    #[test]
+    fn test_errors() {
+        assert_eq!(convert(1.0, "m", "kg"), None); // cross-category
+        assert_eq!(convert(1.0, "C", "J"), None);
+        assert_eq!(convert(1.0, "furlong", "m"), None); // unknown
+        assert_eq!(convert(1.0, "m", ""), None);
+        assert_eq!(convert(1.0, "kHP", "W"), None); // prefix not allowed
+    }
+}
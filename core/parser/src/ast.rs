@@ -159,13 +159,22 @@ pub enum Expression {
         index: Box<Expression>,
     },
 
-    /// List literal: ={1, 2, 3}
+    /// List literal: ={A1, 2, 3}
     /// Creates an EvalResult::List from comma-separated expressions.
+    /// Braces holding only constants parse as an ArrayLiteral instead.
     #[serde(rename = "list_literal")]
     ListLiteral {
         elements: Vec<Expression>,
     },
 
+    /// Array constant: ={1, 2, 3} or ={1, 2; 3, 4}
+    /// Commas separate columns and semicolons separate rows; every row has
+    /// the same number of columns. Evaluates to a dimensioned (spilling) Array.
+    #[serde(rename = "array_literal")]
+    ArrayLiteral {
+        rows: Vec<Vec<Value>>,
+    },
+
     /// Dict literal: ={"name": "Alice", "age": 30}
     /// Creates an EvalResult::Dict from colon-separated key:value pairs.
     #[serde(rename = "dict_literal")]
@@ -399,6 +408,11 @@ pub enum BuiltinFunction {
     GetCellFillColor,
     GetControlValue,
 
+    // UI SET functions (change worksheet formatting)
+    SetRowHeight,
+    SetColumnWidth,
+    SetCellFillColor,
+
     // Reference functions
     Row,
     Column,
@@ -677,6 +691,7 @@ pub enum BuiltinFunction {
 
     // Web functions
     EncodeUrl,
+    Hyperlink,
 
     // Additional math functions
     MRound,
@@ -700,6 +715,7 @@ pub enum BuiltinFunction {
     IsRef,
     Sheet,
     Sheets,
+    Info,
 
     // Array reshaping functions
     Expand,
@@ -978,6 +994,9 @@ impl BuiltinFunction {
             "GET.ROW.HEIGHT" | "GETROWHEIGHT" => BuiltinFunction::GetRowHeight,
             "GET.COLUMN.WIDTH" | "GETCOLUMNWIDTH" => BuiltinFunction::GetColumnWidth,
             "GET.CELL.FILLCOLOR" | "GETCELLFILLCOLOR" => BuiltinFunction::GetCellFillColor,
+            "SET.ROW.HEIGHT" | "SETROWHEIGHT" => BuiltinFunction::SetRowHeight,
+            "SET.COLUMN.WIDTH" | "SETCOLUMNWIDTH" => BuiltinFunction::SetColumnWidth,
+            "SET.CELL.FILLCOLOR" | "SETCELLFILLCOLOR" => BuiltinFunction::SetCellFillColor,
             "GET.CONTROLVALUE" | "GET.CONTROL.VALUE" | "GETCONTROLVALUE" => BuiltinFunction::GetControlValue,
 
             // Reference functions
@@ -1257,6 +1276,7 @@ impl BuiltinFunction {
 
             // Web functions
             "ENCODEURL" => BuiltinFunction::EncodeUrl,
+            "HYPERLINK" => BuiltinFunction::Hyperlink,
 
             // Additional math functions
             "MROUND" => BuiltinFunction::MRound,
@@ -1280,6 +1300,7 @@ impl BuiltinFunction {
             "ISREF" => BuiltinFunction::IsRef,
             "SHEET" => BuiltinFunction::Sheet,
             "SHEETS" => BuiltinFunction::Sheets,
+            "INFO" => BuiltinFunction::Info,
 
             // Engineering functions - Base conversion
             "BIN2DEC" => BuiltinFunction::Bin2Dec,
@@ -1537,6 +1558,9 @@ impl BuiltinFunction {
             BuiltinFunction::GetRowHeight => "GET.ROW.HEIGHT",
             BuiltinFunction::GetColumnWidth => "GET.COLUMN.WIDTH",
             BuiltinFunction::GetCellFillColor => "GET.CELL.FILLCOLOR",
+            BuiltinFunction::SetRowHeight => "SET.ROW.HEIGHT",
+            BuiltinFunction::SetColumnWidth => "SET.COLUMN.WIDTH",
+            BuiltinFunction::SetCellFillColor => "SET.CELL.FILLCOLOR",
             BuiltinFunction::GetControlValue => "GET.CONTROLVALUE",
             BuiltinFunction::Row => "ROW",
             BuiltinFunction::Column => "COLUMN",
@@ -1760,6 +1784,7 @@ impl BuiltinFunction {
             BuiltinFunction::SqrtPi => "SQRTPI",
             BuiltinFunction::Aggregate => "AGGREGATE",
             BuiltinFunction::EncodeUrl => "ENCODEURL",
+            BuiltinFunction::Hyperlink => "HYPERLINK",
             BuiltinFunction::MRound => "MROUND",
             BuiltinFunction::Quotient => "QUOTIENT",
             BuiltinFunction::SumSq => "SUMSQ",
@@ -1777,6 +1802,7 @@ impl BuiltinFunction {
             BuiltinFunction::IsRef => "ISREF",
             BuiltinFunction::Sheet => "SHEET",
             BuiltinFunction::Sheets => "SHEETS",
+            BuiltinFunction::Info => "INFO",
             BuiltinFunction::Expand => "EXPAND",
             BuiltinFunction::VStack => "VSTACK",
             BuiltinFunction::ToCol => "TOCOL",
@@ -1864,7 +1890,10 @@ impl BuiltinFunction {
     ///
     /// To add a new function, add ONE entry here (plus the enum variant,
     /// `from_name()` match, and evaluator dispatch as usual).
-    /// The template (e.g. `=SUM()`) is auto-generated from the syntax string.
+    /// The template (e.g. `=SUM()`) and the structured signature (see
+    /// `crate::signature`) are derived from the syntax string, so its
+    /// `[optional]` and `...` markers matter. A test checks that every
+    /// variant has an entry here and that every entry names a real variant.
     pub fn all_catalog_entries() -> Vec<FunctionMeta> {
         vec![
             // ================================================================
@@ -2074,6 +2103,7 @@ impl BuiltinFunction {
             FunctionMeta::new("ISREF", "Information", "ISREF(value)", "Returns TRUE if the value is a reference"),
             FunctionMeta::new("SHEET", "Information", "SHEET([value])", "Returns the sheet number of the referenced sheet"),
             FunctionMeta::new("SHEETS", "Information", "SHEETS([reference])", "Returns the number of sheets in a reference or workbook"),
+            FunctionMeta::new("INFO", "Information", "INFO(type_text)", "Returns information about the current operating environment"),
 
             // ================================================================
             // Lookup & Reference functions
@@ -2083,7 +2113,7 @@ impl BuiltinFunction {
             FunctionMeta::new("VLOOKUP", "Lookup & Reference", "VLOOKUP(lookup_value, table_array, col_index_num, [range_lookup])", "Looks up a value in the first column of a table and returns a value in the same row"),
             FunctionMeta::new("HLOOKUP", "Lookup & Reference", "HLOOKUP(lookup_value, table_array, row_index_num, [range_lookup])", "Looks up a value in the first row of a table and returns a value in the same column"),
             FunctionMeta::new("LOOKUP", "Lookup & Reference", "LOOKUP(lookup_value, lookup_vector, [result_vector])", "Looks up a value in a one-row or one-column range"),
-            FunctionMeta::new("INDEX", "Lookup & Reference", "INDEX(array, row_num, [column_num])", "Returns a value at a given position in a range"),
+            FunctionMeta::new("INDEX", "Lookup & Reference", "INDEX(array, row_num, [column_num])", "Returns the value at a row and column of a range; 0 returns the whole row or column"),
             FunctionMeta::new("MATCH", "Lookup & Reference", "MATCH(lookup_value, lookup_array, [match_type])", "Returns the position of a value in a range"),
             FunctionMeta::new("XMATCH", "Lookup & Reference", "XMATCH(lookup_value, lookup_array, [match_mode], [search_mode])", "Searches for a specified item and returns its relative position"),
             FunctionMeta::new("CHOOSE", "Lookup & Reference", "CHOOSE(index_num, value1, [value2], ...)", "Returns a value from a list based on index"),
@@ -2092,6 +2122,7 @@ impl BuiltinFunction {
             FunctionMeta::new("INDIRECT", "Lookup & Reference", "INDIRECT(ref_text, [a1])", "Returns the reference specified by a text string"),
             FunctionMeta::new("OFFSET", "Lookup & Reference", "OFFSET(reference, rows, cols, [height], [width])", "Returns a reference offset from a given reference"),
             FunctionMeta::new("ADDRESS", "Lookup & Reference", "ADDRESS(row_num, column_num, [abs_num], [a1], [sheet_text])", "Creates a cell address as text"),
+            FunctionMeta::new("HYPERLINK", "Lookup & Reference", "HYPERLINK(link_location, [friendly_name])", "Creates a link to a web page, file or cell in this workbook"),
             FunctionMeta::new("ROW", "Lookup & Reference", "ROW([cell_ref])", "Returns the row number of a cell reference"),
             FunctionMeta::new("COLUMN", "Lookup & Reference", "COLUMN([cell_ref])", "Returns the column number of a cell reference"),
             FunctionMeta::new("ROWS", "Lookup & Reference", "ROWS(array)", "Returns the number of rows in a reference"),
@@ -2410,9 +2441,10 @@ impl BuiltinFunction {
             FunctionMeta::new("WRAPROWS", "Dynamic Array", "WRAPROWS(vector, wrap_count, [pad_with])", "Wraps a row or column vector into rows"),
             // Writeback aggregation
             FunctionMeta::new("GATHER", "Writeback", "GATHER(region_id)", "Returns all visible writeback submissions for a region"),
-            FunctionMeta::new("GATHER.FROM", "Writeback", "GATHER.FROM(region_id, submitter_id)", "Returns one submitter's writeback value"),
-            FunctionMeta::new("GATHER.COUNT", "Writeback", "GATHER.COUNT(region_id)", "Counts writeback submissions for a region"),
-            FunctionMeta::new("GATHER.SUBMITTERS", "Writeback", "GATHER.SUBMITTERS(region_id)", "Lists submitter identities for a region"),
+            FunctionMeta::new("GATHER.FROM", "Writeback", "GATHER.FROM(region_id, submitter_id, [row, col])", "Returns one submitter's writeback value"),
+            FunctionMeta::new("GATHER.COUNT", "Writeback", "GATHER.COUNT(region_id, [row, col])", "Counts writeback submissions for a region"),
+            FunctionMeta::new("GATHER.SUBMITTERS", "Writeback", "GATHER.SUBMITTERS(region_id, [row, col])", "Lists submitter identities for a region"),
+            FunctionMeta::new("GATHER.AT", "Writeback", "GATHER.AT(region_id, row, col)", "Returns every submitter's value for one cell of a region"),
             // Collections
             FunctionMeta::new("COLLECT", "Dynamic Array", "COLLECT(value)", "Wraps an array result into a contained List cell"),
             FunctionMeta::new("DICT", "Dynamic Array", "DICT(key1, value1, [key2, value2], ...)", "Creates a Dict cell from alternating key-value pairs"),
@@ -2450,6 +2482,9 @@ impl BuiltinFunction {
             FunctionMeta::new("GET.COLUMN.WIDTH", "UI", "GET.COLUMN.WIDTH(col)", "Returns the width in pixels of the specified column"),
             FunctionMeta::new("GET.CELL.FILLCOLOR", "UI", "GET.CELL.FILLCOLOR(cell_ref)", "Returns the background fill color of a cell as a CSS color string"),
             FunctionMeta::new("GET.CONTROLVALUE", "UI", "GET.CONTROLVALUE(name, [default])", "Returns the current value of the named UI control (pane control, ribbon filter, or named on-grid control)"),
+            FunctionMeta::new("SET.ROW.HEIGHT", "UI", "SET.ROW.HEIGHT(row, height)", "Sets the height in pixels of the specified row"),
+            FunctionMeta::new("SET.COLUMN.WIDTH", "UI", "SET.COLUMN.WIDTH(col, width)", "Sets the width in pixels of the specified column"),
+            FunctionMeta::new("SET.CELL.FILLCOLOR", "UI", "SET.CELL.FILLCOLOR(cell_ref, color)", "Sets the background fill color of a cell from a hex color string or red, green and blue components (\"\" removes the fill)"),
 
             // ================================================================
             // File functions
@@ -2579,7 +2614,16 @@ pub enum BinaryOperator {
 /// Unary operators.
 #[derive(Debug, PartialEq, Clone, Copy, Serialize, Deserialize)]
 pub enum UnaryOperator {
-    Negate, // -
+    Negate,  // -
+    Plus,    // + (no-op, kept so the formula renders as typed)
+    Percent, // postfix %: divides by 100, binds tighter than ^
+}
+
+impl UnaryOperator {
+    /// True for operators written after their operand (`%`).
+    pub fn is_postfix(&self) -> bool {
+        matches!(self, UnaryOperator::Percent)
+    }
 }
 
 impl std::fmt::Display for BinaryOperator {
@@ -2605,6 +2649,8 @@ impl std::fmt::Display for UnaryOperator {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             UnaryOperator::Negate => write!(f, "-"),
+            UnaryOperator::Plus => write!(f, "+"),
+            UnaryOperator::Percent => write!(f, "%"),
         }
     }
 }
@@ -5,7 +5,7 @@
 //! for sheet names, and multi-character operators like <= and <>.
 //!
 //! SUPPORTED OPERATORS:
-//! - Single char: + - * / ^ & ( ) , : = < > ! $
+//! - Single char: + - * / ^ % & ( ) , ; : = < > ! $
 //! - Multi char: <= >= <>
 //! - Quoted identifiers: 'Sheet Name'
 
@@ -15,29 +15,44 @@ use std::str::Chars;
 
 pub struct Lexer<'a> {
     input: Peekable<Chars<'a>>,
+    /// Characters consumed so far.
+    pos: usize,
+    /// Character offset where the last returned token starts.
+    token_start: usize,
 }
 
 impl<'a> Lexer<'a> {
     pub fn new(input: &'a str) -> Self {
         Lexer {
             input: input.chars().peekable(),
+            pos: 0,
+            token_start: 0,
         }
     }
 
+    /// Character span (start, end exclusive) of the last returned token.
+    /// EOF is an empty span at the end of the input.
+    pub fn token_span(&self) -> (usize, usize) {
+        (self.token_start, self.pos)
+    }
+
     /// Advances the lexer and returns the next token.
     pub fn next_token(&mut self) -> Token {
         self.skip_whitespace();
+        self.token_start = self.pos;
 
-        match self.input.next() {
+        match self.bump() {
             Some('+') => Token::Plus,
             Some('-') => Token::Minus,
             Some('*') => Token::Asterisk,
             Some('/') => Token::Slash,
             Some('^') => Token::Caret,
+            Some('%') => Token::Percent,
             Some('&') => Token::Ampersand,
             Some('(') => Token::LParen,
             Some(')') => Token::RParen,
             Some(',') => Token::Comma,
+            Some(';') => Token::Semicolon,
             Some(':') => Token::Colon,
             Some('!') => Token::Exclamation,
             Some('$') => Token::Dollar,
@@ -77,12 +92,21 @@ impl<'a> Lexer<'a> {
         }
     }
 
+    /// Consumes one character.
+    fn bump(&mut self) -> Option<char> {
+        let ch = self.input.next();
+        if ch.is_some() {
+            self.pos += 1;
+        }
+        ch
+    }
+
     fn skip_whitespace(&mut self) {
         while let Some(&ch) = self.input.peek() {
             if !ch.is_whitespace() {
                 break;
             }
-            self.input.next();
+            self.bump();
         }
     }
 
@@ -90,11 +114,11 @@ impl<'a> Lexer<'a> {
     fn read_less_than_operator(&mut self) -> Token {
         match self.input.peek() {
             Some('=') => {
-                self.input.next();
+                self.bump();
                 Token::LessEqual
             }
             Some('>') => {
-                self.input.next();
+                self.bump();
                 Token::NotEqual
             }
             _ => Token::LessThan,
@@ -105,7 +129,7 @@ impl<'a> Lexer<'a> {
     fn read_greater_than_operator(&mut self) -> Token {
         match self.input.peek() {
             Some('=') => {
-                self.input.next();
+                self.bump();
                 Token::GreaterEqual
             }
             _ => Token::GreaterThan,
@@ -117,11 +141,11 @@ impl<'a> Lexer<'a> {
         // Consume chars until we hit another quote or EOF
         while let Some(&ch) = self.input.peek() {
             if ch == '"' {
-                self.input.next(); // Consume the closing quote
+                self.bump(); // Consume the closing quote
                 return Token::String(result);
             }
             result.push(ch);
-            self.input.next();
+            self.bump();
         }
         // If we hit EOF without closing quote, return what we have.
         Token::String(result)
@@ -133,18 +157,18 @@ impl<'a> Lexer<'a> {
         while let Some(&ch) = self.input.peek() {
             if ch == '\'' {
                 // Check for escaped single quote ('')
-                self.input.next();
+                self.bump();
                 if self.input.peek() == Some(&'\'') {
                     // Escaped quote - add one quote and continue
                     result.push('\'');
-                    self.input.next();
+                    self.bump();
                 } else {
                     // End of quoted identifier
                     return Token::QuotedIdentifier(result);
                 }
             } else {
                 result.push(ch);
-                self.input.next();
+                self.bump();
             }
         }
         // If we hit EOF without closing quote, return what we have
@@ -158,11 +182,11 @@ impl<'a> Lexer<'a> {
         while let Some(&ch) = self.input.peek() {
             if ch.is_ascii_digit() {
                 number_str.push(ch);
-                self.input.next();
+                self.bump();
             } else if ch == '.' && !has_dot {
                 has_dot = true;
                 number_str.push(ch);
-                self.input.next();
+                self.bump();
             } else {
                 break;
             }
@@ -184,7 +208,7 @@ impl<'a> Lexer<'a> {
             // '.' supports defined names like "Q1.Sales".
             if is_letter(ch) || ch.is_ascii_digit() || ch == '.' {
                 ident.push(ch);
-                self.input.next();
+                self.bump();
             } else {
                 break;
             }
@@ -18,6 +18,7 @@
 pub mod ast;
 pub mod lexer;
 pub mod parser;
+pub mod signature;
 pub mod token;
 
 // Register the separate tests module
@@ -28,4 +29,5 @@ mod tests;
 pub use ast::{BinaryOperator, BuiltinFunction, Expression, FunctionMeta, UnaryOperator, Value};
 pub use lexer::Lexer;
 pub use parser::{parse, ParseError, ParseResult, Parser};
+pub use signature::{function_signature, ArgType, FunctionArg, FunctionSignature};
 pub use token::Token;
\ No newline at end of file
@@ -9,8 +9,9 @@
 //!   concatenation  --> additive ( "&" additive )*
 //!   additive       --> multiplicative ( ("+" | "-") multiplicative )*
 //!   multiplicative --> unary ( ("*" | "/") unary )*
-//!   unary          --> "-" unary | power
-//!   power          --> primary ( "^" unary )?
+//!   unary          --> ("-" | "+") unary | power
+//!   power          --> percent ( "^" unary )?
+//!   percent        --> primary "%"*
 //!   primary        --> NUMBER | STRING | BOOLEAN | reference | function_call | "(" expression ")"
 //!   reference      --> [sheet_prefix] (cell_or_range | column_ref | row_ref)
 //!   sheet_prefix   --> (IDENTIFIER | QUOTED_IDENTIFIER) "!"
@@ -29,18 +30,35 @@ use crate::lexer::Lexer;
 use crate::token::Token;
 use identity::RefSiteId;
 
-/// Parser errors with descriptive messages.
+/// Parser errors with descriptive messages, located at the token the parser
+/// stopped on so an editor can underline it.
 #[derive(Debug, PartialEq, Clone)]
 pub struct ParseError {
     pub message: String,
+    /// Character offset of the offending token in the input (the end of the
+    /// input when the formula stopped early). Set by `Parser::parse`.
+    pub offset: Option<usize>,
+    /// Source text of the offending token; None at the end of the input.
+    pub token: Option<String>,
+    /// Tokens the parser would have accepted instead, e.g. `["("]`.
+    pub expected: Vec<String>,
 }
 
 impl ParseError {
     pub fn new(message: impl Into<String>) -> Self {
         ParseError {
             message: message.into(),
+            offset: None,
+            token: None,
+            expected: Vec::new(),
         }
     }
+
+    /// Adds what the parser would have accepted at the error position.
+    pub fn expecting<S: Into<String>>(mut self, expected: impl IntoIterator<Item = S>) -> Self {
+        self.expected = expected.into_iter().map(Into::into).collect();
+        self
+    }
 }
 
 impl std::fmt::Display for ParseError {
@@ -55,8 +73,14 @@ pub type ParseResult<T> = Result<T, ParseError>;
 
 /// The Parser struct holds the lexer and current token state.
 pub struct Parser<'a> {
+    input: &'a str,
     lexer: Lexer<'a>,
     current_token: Token,
+    /// Character span of `current_token`.
+    token_span: (usize, usize),
+    /// A function name run into its first argument (`SUMA1` for `SUM(A1`),
+    /// reported instead of whatever error the parse ends in.
+    missing_paren: Option<ParseError>,
     /// Track if we've consumed the leading '=' to distinguish formula mode
     #[allow(dead_code)]
     is_formula_mode: bool,
@@ -68,16 +92,43 @@ impl<'a> Parser<'a> {
     pub fn new(input: &'a str) -> Self {
         let mut lexer = Lexer::new(input);
         let current_token = lexer.next_token();
+        let token_span = lexer.token_span();
         Parser {
+            input,
             lexer,
             current_token,
+            token_span,
+            missing_paren: None,
             is_formula_mode: false,
         }
     }
 
-    /// Parses the entire input and returns the AST.
-    /// Handles the optional leading '=' that indicates a formula.
+    /// Parses the entire input and returns the AST. Errors carry the offset
+    /// and text of the token the parser stopped on.
     pub fn parse(&mut self) -> ParseResult<Expression> {
+        self.parse_formula().map_err(|err| {
+            let err = self.missing_paren.take().unwrap_or(err);
+            self.locate(err)
+        })
+    }
+
+    /// Points `err` at the current token unless it already has a position.
+    fn locate(&self, mut err: ParseError) -> ParseError {
+        if err.offset.is_none() {
+            let (start, end) = self.token_span;
+            err.offset = Some(start);
+            err.token = (end > start).then(|| self.source_text(start, end));
+        }
+        err
+    }
+
+    /// Input characters `start..end`.
+    fn source_text(&self, start: usize, end: usize) -> String {
+        self.input.chars().skip(start).take(end - start).collect()
+    }
+
+    /// Handles the optional leading '=' that indicates a formula.
+    fn parse_formula(&mut self) -> ParseResult<Expression> {
         // Skip the leading '=' if present (formula indicator)
         if self.current_token == Token::Equals {
             self.is_formula_mode = true;
@@ -92,11 +143,15 @@ impl<'a> Parser<'a> {
         let expr = self.parse_expression()?;
 
         // Ensure we consumed all tokens
+        if self.current_token == Token::RParen {
+            return Err(ParseError::new("Unmatched ')'"));
+        }
         if self.current_token != Token::EOF {
             return Err(ParseError::new(format!(
-                "Unexpected token after expression: {:?}",
-                self.current_token
-            )));
+                "Unexpected {} after expression",
+                describe(&self.current_token)
+            ))
+            .expecting(["operator"]));
         }
 
         Ok(expr)
@@ -105,6 +160,7 @@ impl<'a> Parser<'a> {
     /// Advances to the next token.
     fn advance(&mut self) {
         self.current_token = self.lexer.next_token();
+        self.token_span = self.lexer.token_span();
     }
 
     /// Checks if the current token matches the expected token.
@@ -115,9 +171,11 @@ impl<'a> Parser<'a> {
             Ok(())
         } else {
             Err(ParseError::new(format!(
-                "Expected {:?}, found {:?}",
-                expected, self.current_token
-            )))
+                "Expected '{}', found {}",
+                expected,
+                describe(&self.current_token)
+            ))
+            .expecting([expected.to_string()]))
         }
     }
 
@@ -220,18 +278,19 @@ impl<'a> Parser<'a> {
         Ok(left)
     }
 
-    /// Parses unary expressions (negation).
+    /// Parses unary expressions (negation and unary plus).
     fn parse_unary(&mut self) -> ParseResult<Expression> {
-        if self.current_token == Token::Minus {
-            self.advance();
-            let operand = self.parse_unary()?;
-            return Ok(Expression::UnaryOp {
-                op: UnaryOperator::Negate,
-                operand: Box::new(operand),
-            });
-        }
-
-        self.parse_power()
+        let op = match self.current_token {
+            Token::Minus => UnaryOperator::Negate,
+            Token::Plus => UnaryOperator::Plus,
+            _ => return self.parse_power(),
+        };
+        self.advance();
+        let operand = self.parse_unary()?;
+        Ok(Expression::UnaryOp {
+            op,
+            operand: Box::new(operand),
+        })
     }
 
     /// Parses power/exponentiation expressions (^).
@@ -242,6 +301,9 @@ impl<'a> Parser<'a> {
         // Only valid after CellRef, FunctionCall, NamedRef, IndexAccess
         let left = self.parse_index_access_chain(left)?;
 
+        // Postfix percent binds tighter than ^: 2^10% is 2^0.1
+        let left = self.parse_percent(left);
+
         if self.current_token == Token::Caret {
             self.advance();
             let right = self.parse_unary()?;
@@ -256,6 +318,60 @@ impl<'a> Parser<'a> {
         Ok(left)
     }
 
+    /// Wraps `operand` in one Percent node per trailing `%` (10%% is 0.001).
+    fn parse_percent(&mut self, operand: Expression) -> Expression {
+        let mut result = operand;
+        while self.current_token == Token::Percent {
+            self.advance();
+            result = Expression::UnaryOp {
+                op: UnaryOperator::Percent,
+                operand: Box::new(result),
+            };
+        }
+        result
+    }
+
+    /// Builds the expression for a `{...}` literal from its rows of elements:
+    /// an ArrayLiteral when every element is a constant, otherwise a
+    /// single-row ListLiteral (lists have no rows).
+    fn brace_literal(rows: Vec<Vec<Expression>>) -> ParseResult<Expression> {
+        fn constant(expr: &Expression) -> Option<Value> {
+            match expr {
+                Expression::Literal(value) => Some(value.clone()),
+                Expression::UnaryOp { op: UnaryOperator::Negate, operand } => match operand.as_ref() {
+                    Expression::Literal(Value::Number(n)) => Some(Value::Number(-n)),
+                    _ => None,
+                },
+                _ => None,
+            }
+        }
+
+        let values: Option<Vec<Vec<Value>>> = rows
+            .iter()
+            .map(|row| row.iter().map(constant).collect())
+            .collect();
+        let Some(values) = values else {
+            if rows.len() > 1 {
+                return Err(ParseError::new(
+                    "Array constants may only contain numbers, text and TRUE/FALSE",
+                ));
+            }
+            let elements = rows.into_iter().next().unwrap_or_default();
+            return Ok(Expression::ListLiteral { elements });
+        };
+
+        let width = values[0].len();
+        if let Some((index, row)) = values.iter().enumerate().find(|(_, row)| row.len() != width) {
+            return Err(ParseError::new(format!(
+                "Array constant rows must all have the same number of columns: row 1 has {}, row {} has {}",
+                width,
+                index + 1,
+                row.len()
+            )));
+        }
+        Ok(Expression::ArrayLiteral { rows: values })
+    }
+
     /// Parses zero or more trailing [index] subscript accesses and (args) invocations.
     /// Only applies to expressions where subscript/invocation makes sense
     /// (CellRef, FunctionCall, NamedRef, IndexAccess).
@@ -385,9 +501,25 @@ impl<'a> Parser<'a> {
                 self.parse_sheet_reference(name)
             }
 
+            // `#REF!`: a reference invalidated by a structural change or a
+            // fill past the sheet edge. Kept as a name so it round-trips.
+            Token::Hash => {
+                self.advance();
+                if self.current_token != Token::Identifier("REF".to_string()) {
+                    return Err(ParseError::new(format!(
+                        "Unexpected token after '#': {:?}",
+                        self.current_token
+                    )));
+                }
+                self.advance();
+                self.expect(Token::Exclamation)?;
+                Ok(Expression::NamedRef { name: "#REF!".to_string(), ref_site_id: RefSiteId::ZERO })
+            }
+
             // Identifier: could be a cell reference, range, column reference,
             // function call, sheet reference prefix, table reference, or named reference
             Token::Identifier(name) => {
+                self.note_missing_paren(&name);
                 self.advance();
 
                 // Check if it's a sheet reference (followed by '!')
@@ -516,7 +648,8 @@ impl<'a> Parser<'a> {
                 Ok(expr)
             }
 
-            // Curly-brace literal: list {1, 2, 3} or dict {"a": 1, "b": 2}
+            // Curly-brace literal: array constant {1, 2; 3, 4}, list {A1, 2}
+            // or dict {"a": 1, "b": 2}
             Token::LBrace => {
                 self.advance();
 
@@ -550,29 +683,39 @@ impl<'a> Parser<'a> {
                     self.expect(Token::RBrace)?;
                     Ok(Expression::DictLiteral { entries })
                 } else {
-                    // List mode
-                    let mut elements = vec![first];
-
-                    while self.current_token == Token::Comma {
-                        self.advance();
-                        // Allow trailing comma before }
-                        if self.current_token == Token::RBrace {
-                            break;
+                    // List or array mode: commas separate columns, semicolons rows
+                    let mut rows = vec![vec![first]];
+
+                    loop {
+                        match self.current_token {
+                            Token::Comma => {
+                                self.advance();
+                                // Allow trailing comma before }
+                                if self.current_token == Token::RBrace {
+                                    break;
+                                }
+                                let element = self.parse_expression()?;
+                                rows.last_mut().unwrap().push(element);
+                            }
+                            Token::Semicolon => {
+                                self.advance();
+                                rows.push(vec![self.parse_expression()?]);
+                            }
+                            _ => break,
                         }
-                        elements.push(self.parse_expression()?);
                     }
 
                     self.expect(Token::RBrace)?;
-                    Ok(Expression::ListLiteral { elements })
+                    Self::brace_literal(rows)
                 }
             }
 
             // Error cases
-            Token::EOF => Err(ParseError::new("Unexpected end of expression")),
+            Token::EOF => Err(ParseError::new("Unexpected end of expression").expecting(["value"])),
 
             Token::Illegal(ch) => Err(ParseError::new(format!("Illegal character: {}", ch))),
 
-            token => Err(ParseError::new(format!("Unexpected token: {:?}", token))),
+            token => Err(ParseError::new(format!("Unexpected {}", describe(&token))).expecting(["value"])),
         }
     }
 
@@ -1120,7 +1263,15 @@ impl<'a> Parser<'a> {
         }
 
         // Expect closing ')'
-        self.expect(Token::RParen)?;
+        if self.current_token != Token::RParen {
+            return Err(ParseError::new(format!(
+                "Expected ',' or ')' in the arguments of {}, found {}",
+                name,
+                describe(&self.current_token)
+            ))
+            .expecting([",", ")"]));
+        }
+        self.advance();
 
         Ok(Expression::FunctionCall { func, args, ref_site_id: RefSiteId::ZERO })
     }
@@ -1378,6 +1529,7 @@ impl<'a> Parser<'a> {
                 Token::Minus => { content.push('-'); self.advance(); }
                 Token::Asterisk => { content.push('*'); self.advance(); }
                 Token::Slash => { content.push('/'); self.advance(); }
+                Token::Percent => { content.push('%'); self.advance(); }
                 Token::Ampersand => { content.push('&'); self.advance(); }
                 Token::Dollar => { content.push('$'); self.advance(); }
                 Token::Exclamation => { content.push('!'); self.advance(); }
@@ -1456,6 +1608,30 @@ impl<'a> Parser<'a> {
         result
     }
 
+    /// Remembers a "missing '('" error when `name` (the current token) is a
+    /// builtin function name fused with a cell reference, as in `=SUMA1:A3)`.
+    /// Kept once per parse; the first such identifier wins.
+    fn note_missing_paren(&mut self, name: &str) {
+        if self.missing_paren.is_some() || Self::is_valid_cell_ref_identifier(name) {
+            return;
+        }
+        let split = (1..name.len()).rev().find(|&i| {
+            let (func, rest) = name.split_at(i);
+            rest.starts_with(|c: char| c.is_ascii_alphabetic())
+                && rest.ends_with(|c: char| c.is_ascii_digit())
+                && Self::is_valid_cell_ref_identifier(rest)
+                && !matches!(BuiltinFunction::from_name(func), BuiltinFunction::Custom(_))
+        });
+        if let Some(i) = split {
+            // Identifiers are ASCII, so byte and character offsets agree.
+            let (start, end) = self.token_span;
+            let mut err = ParseError::new(format!("Expected '(' after function name {}", &name[..i])).expecting(["("]);
+            err.offset = Some(start + i);
+            err.token = Some(self.source_text(start + i, end));
+            self.missing_paren = Some(err);
+        }
+    }
+
     /// Splits a cell reference string like "A1" or "AA100" into column and row parts.
     fn split_cell_reference(&self, identifier: &str) -> ParseResult<(String, u32)> {
         let mut col = String::new();
@@ -1512,6 +1688,15 @@ impl<'a> Parser<'a> {
     }
 }
 
+/// How a token reads in an error message: `')'`, `'A1'`, "end of formula".
+fn describe(token: &Token) -> String {
+    match token {
+        Token::EOF => "end of formula".to_string(),
+        Token::Illegal(ch) => format!("'{}'", ch),
+        token => format!("'{}'", token),
+    }
+}
+
 /// Convenience function to parse a formula string directly.
 pub fn parse(input: &str) -> ParseResult<Expression> {
     let mut parser = Parser::new(input);
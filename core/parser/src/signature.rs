@@ -0,0 +1,227 @@
+//! FILENAME: core/parser/src/signature.rs
+//! PURPOSE: Structured function signatures (argument names, optional/variadic
+//! flags, type hints, example formula) for the formula bar's argument tooltips.
+//! CONTEXT: Derived from `BuiltinFunction::all_catalog_entries()`, so the
+//! catalog's syntax strings stay the single source of truth. The syntax
+//! conventions are Excel's: `[arg]` is optional, `[a, b]` is an optional
+//! group, and a trailing `...` repeats the preceding argument (or group).
+
+use serde::Serialize;
+
+use crate::ast::{BuiltinFunction, FunctionMeta};
+
+/// Coarse expected type of an argument, inferred from its name.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ArgType {
+    Number,
+    Text,
+    Logical,
+    Date,
+    /// A cell range or reference (SUMIF's range, OFFSET's reference, ...).
+    Range,
+    /// A range or an array value (lookup arrays, known_x's, ...).
+    Array,
+    /// A criteria string such as ">0" (SUMIF, COUNTIFS, ...).
+    Criteria,
+    Lambda,
+    Any,
+}
+
+/// One parameter of a function signature.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FunctionArg {
+    pub name: String,
+    pub optional: bool,
+    /// Part of the repeating tail (e.g. `number2` in SUM, or both
+    /// `criteria_range1`/`criteria1` in SUMIFS).
+    pub variadic: bool,
+    pub type_hint: ArgType,
+}
+
+/// Structured metadata for one built-in function.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FunctionSignature {
+    pub name: String,
+    pub category: String,
+    pub description: String,
+    pub syntax: String,
+    pub args: Vec<FunctionArg>,
+    /// Example formula using the required arguments, e.g. `=ROUND(A1, B1)`.
+    pub example: String,
+}
+
+/// Signature of the built-in function `name` (case-insensitive). Aliases
+/// resolve to their canonical function. None for unknown names.
+pub fn function_signature(name: &str) -> Option<FunctionSignature> {
+    let func = BuiltinFunction::from_name(name);
+    if matches!(func, BuiltinFunction::Custom(_)) {
+        return None;
+    }
+    // Modern names such as STDEV.P share a variant with their legacy form but
+    // keep their own catalog entry, so prefer an exact match.
+    let upper = name.to_uppercase();
+    let entries = BuiltinFunction::all_catalog_entries();
+    entries
+        .iter()
+        .filter(|m| !m.is_alias)
+        .find(|m| m.name == upper)
+        .or_else(|| entries.iter().find(|m| !m.is_alias && m.name == func.to_canonical_name()))
+        .map(signature_of)
+}
+
+/// Build the structured signature of a catalog entry from its syntax string.
+pub fn signature_of(meta: &FunctionMeta) -> FunctionSignature {
+    let params = match (meta.syntax.find('('), meta.syntax.rfind(')')) {
+        (Some(open), Some(close)) if close > open => &meta.syntax[open + 1..close],
+        _ => "",
+    };
+    let args = parse_params(params, false);
+    let example = example_formula(meta.name, &args);
+    FunctionSignature {
+        name: meta.name.to_string(),
+        category: meta.category.to_string(),
+        description: meta.description.to_string(),
+        syntax: meta.syntax.to_string(),
+        args,
+        example,
+    }
+}
+
+/// Parse a parameter list such as `number1, [number2], ...`.
+fn parse_params(params: &str, optional: bool) -> Vec<FunctionArg> {
+    let mut args: Vec<FunctionArg> = Vec::new();
+    // Args produced by the previous top-level item; `...` repeats them.
+    let mut last_item = 0..0;
+    for item in split_top_level(params) {
+        if item == "..." {
+            mark_variadic(&mut args, last_item.clone());
+            continue;
+        }
+        let start = args.len();
+        match item.strip_prefix('[').and_then(|s| s.strip_suffix(']')) {
+            Some(inner) => args.extend(parse_params(inner, true)),
+            None => args.push(FunctionArg {
+                name: item.to_string(),
+                optional,
+                variadic: false,
+                type_hint: infer_type(item),
+            }),
+        }
+        last_item = start..args.len();
+    }
+    args
+}
+
+/// Split on commas that are not inside `[...]`.
+fn split_top_level(params: &str) -> Vec<&str> {
+    let mut items = Vec::new();
+    let mut depth = 0usize;
+    let mut start = 0;
+    for (i, c) in params.char_indices() {
+        match c {
+            '[' => depth += 1,
+            ']' => depth = depth.saturating_sub(1),
+            ',' if depth == 0 => {
+                items.push(params[start..i].trim());
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    items.push(params[start..].trim());
+    items.retain(|s| !s.is_empty());
+    items
+}
+
+/// Flag the arguments a `...` repeats. A bracketed group repeats as a whole;
+/// otherwise the run of arguments sharing the last one's numeric suffix
+/// (`criteria_range1, criteria1`) repeats, or just the last argument.
+fn mark_variadic(args: &mut [FunctionArg], last_item: std::ops::Range<usize>) {
+    if last_item.is_empty() {
+        return;
+    }
+    if last_item.len() > 1 {
+        args[last_item].iter_mut().for_each(|a| a.variadic = true);
+        return;
+    }
+    let last = last_item.start;
+    let suffix = numeric_suffix(&args[last].name).to_string();
+    args[last].variadic = true;
+    if suffix.is_empty() {
+        return;
+    }
+    for arg in args[..last].iter_mut().rev() {
+        if numeric_suffix(&arg.name) != suffix {
+            break;
+        }
+        arg.variadic = true;
+    }
+}
+
+fn numeric_suffix(name: &str) -> &str {
+    let stem = name.trim_end_matches(|c: char| c.is_ascii_digit());
+    &name[stem.len()..]
+}
+
+/// Best-effort type hint from the Excel parameter name.
+fn infer_type(name: &str) -> ArgType {
+    let stem = name.trim_end_matches(|c: char| c.is_ascii_digit()).to_ascii_lowercase();
+    match stem.as_str() {
+        "criteria" => ArgType::Criteria,
+        "lambda" | "function" => ArgType::Lambda,
+        "logical" | "cumulative" | "ignore_empty" | "by_col" | "range_lookup" | "exactly_once"
+        | "scan_by_column" | "stats" | "no_commas" | "condition" | "const" | "match_end" => {
+            ArgType::Logical
+        }
+        "date" | "start_date" | "end_date" | "settlement" | "maturity" | "issue" | "serial_number"
+        | "target_date" | "first_coupon" | "last_interest" | "first_interest"
+        | "date_purchased" => ArgType::Date,
+        "range" | "ref" | "reference" | "cell_ref" | "database" | "pivot_table" => ArgType::Range,
+        "values" | "vector" | "dates" | "timeline" | "include" | "list" | "collection"
+        | "coefficients" | "field_headers" | "row_fields" | "col_fields" | "holidays"
+        | "schedule" | "known_x" | "known_y" | "new_x" => ArgType::Array,
+        "value" | "result" | "default" | "expression" | "lookup_value" | "key" | "initial_value"
+        | "if_empty" | "if_not_found" | "pad_with" | "error_val" | "calculation"
+        | "calculation_or_name" | "name_value" | "field" | "parameter" => ArgType::Any,
+        "text" | "delimiter" | "col_delimiter" | "row_delimiter" | "name" | "from_unit"
+        | "to_unit" | "format" | "unit" | "connection" | "member_expression"
+        | "set_expression" | "caption" | "kpi_name" | "kpi_property" | "property"
+        | "info_type" | "region_id" | "submitter_id" | "data_field" | "path" | "suffix"
+        | "decimal_separator" | "group_separator" => ArgType::Text,
+        s if s.ends_with("_range") => ArgType::Range,
+        s if s.contains("array") => ArgType::Array,
+        s if s.ends_with("_text") => ArgType::Text,
+        s if s.starts_with("value_if") => ArgType::Any,
+        _ => ArgType::Number,
+    }
+}
+
+/// Example call using the required arguments: ranges and cells advance one
+/// column per argument, e.g. `=SUMIFS(A1:A10, B1:B10, ">0")`.
+fn example_formula(name: &str, args: &[FunctionArg]) -> String {
+    let mut column = b'A';
+    let mut parts = Vec::new();
+    for arg in args.iter().filter(|a| !a.optional) {
+        let col = column as char;
+        let part = match arg.type_hint {
+            ArgType::Range | ArgType::Array => format!("{col}1:{col}10"),
+            ArgType::Criteria => "\">0\"".to_string(),
+            ArgType::Text => "\"text\"".to_string(),
+            ArgType::Logical => "TRUE".to_string(),
+            ArgType::Lambda => "LAMBDA(x, x)".to_string(),
+            ArgType::Number | ArgType::Date | ArgType::Any => format!("{col}1"),
+        };
+        if matches!(
+            arg.type_hint,
+            ArgType::Range | ArgType::Array | ArgType::Number | ArgType::Date | ArgType::Any
+        ) && column < b'Z'
+        {
+            column += 1;
+        }
+        parts.push(part);
+    }
+    format!("={}({})", name, parts.join(", "))
+}
@@ -630,6 +630,50 @@ fn parser_parses_negation_with_power() {
     );
 }
 
+#[test]
+fn parser_parses_unary_plus() {
+    let result = parse("=+5").unwrap();
+    assert_eq!(
+        result,
+        Expression::UnaryOp {
+            op: UnaryOperator::Plus,
+            operand: Box::new(Expression::Literal(Value::Number(5.0)))
+        }
+    );
+}
+
+#[test]
+fn parser_parses_percent_tighter_than_power() {
+    // 2 ^ 10% is 2 ^ (10%), and 10%*3 is (10%)*3
+    let percent = |n: f64| Expression::UnaryOp {
+        op: UnaryOperator::Percent,
+        operand: Box::new(Expression::Literal(Value::Number(n))),
+    };
+    assert_eq!(
+        parse("=2 ^ 10%").unwrap(),
+        Expression::BinaryOp {
+            left: Box::new(Expression::Literal(Value::Number(2.0))),
+            op: BinaryOperator::Power,
+            right: Box::new(percent(10.0))
+        }
+    );
+    assert_eq!(
+        parse("=10%*3").unwrap(),
+        Expression::BinaryOp {
+            left: Box::new(percent(10.0)),
+            op: BinaryOperator::Multiply,
+            right: Box::new(Expression::Literal(Value::Number(3.0)))
+        }
+    );
+    assert_eq!(
+        parse("=-50%").unwrap(),
+        Expression::UnaryOp {
+            op: UnaryOperator::Negate,
+            operand: Box::new(percent(50.0))
+        }
+    );
+}
+
 // ========================================
 // PARSER TESTS - FUNCTION CALLS
 // ========================================
@@ -912,6 +956,38 @@ fn parser_parses_complex_formula_with_all_operators() {
     );
 }
 
+// ========================================
+// PARSER TESTS - ARRAY CONSTANTS
+// ========================================
+
+#[test]
+fn parser_array_constant_rows_and_columns() {
+    let num = Value::Number;
+    assert_eq!(
+        parse("={1,2,3}").unwrap(),
+        Expression::ArrayLiteral { rows: vec![vec![num(1.0), num(2.0), num(3.0)]] }
+    );
+    assert_eq!(
+        parse("={1, -2; \"a\", TRUE}").unwrap(),
+        Expression::ArrayLiteral {
+            rows: vec![
+                vec![num(1.0), num(-2.0)],
+                vec![Value::String("a".to_string()), Value::Boolean(true)],
+            ],
+        }
+    );
+    // Anything but constants stays a list
+    assert!(matches!(parse("={A1, 2}").unwrap(), Expression::ListLiteral { .. }));
+}
+
+#[test]
+fn parser_array_constant_errors() {
+    let err = parse("={1,2;3}").unwrap_err();
+    assert!(err.message.contains("row 1 has 2, row 2 has 1"), "{}", err.message);
+    assert!(parse("={1;A1}").is_err());
+    assert!(parse("=SUM(1;2)").is_err());
+}
+
 // ========================================
 // PARSER TESTS - ERROR CASES
 // ========================================
@@ -950,8 +1026,35 @@ fn parser_error_on_trailing_operator() {
 
 #[test]
 fn parser_error_on_double_operator() {
-    let result = parse("=1 + + 2");
+    let result = parse("=1 * / 2");
     assert!(result.is_err());
+    // + doubles as unary plus: 1 + (+2)
+    assert!(parse("=1 + + 2").is_ok());
+}
+
+#[test]
+fn parser_error_reports_position_and_expected_tokens() {
+    // Missing '(' after a function name that runs into its argument.
+    let err = parse("=SUMA1:A3)").unwrap_err();
+    assert_eq!(err.message, "Expected '(' after function name SUM");
+    assert_eq!((err.offset, err.token.as_deref()), (Some(4), Some("A1")));
+    assert_eq!(err.expected, vec!["("]);
+
+    let err = parse("=SUM(A1 A2)").unwrap_err();
+    assert_eq!((err.offset, err.token.as_deref()), (Some(8), Some("A2")));
+    assert_eq!(err.expected, vec![",", ")"]);
+
+    let err = parse("=(1+2").unwrap_err();
+    assert_eq!((err.offset, err.token), (Some(5), None));
+    assert_eq!(err.expected, vec![")"]);
+
+    let err = parse("= 1 + 2)").unwrap_err();
+    assert_eq!(err.message, "Unmatched ')'");
+    assert_eq!((err.offset, err.token.as_deref()), (Some(7), Some(")")));
+
+    // Offsets count characters, not bytes.
+    let err = parse("=\"é\" ?").unwrap_err();
+    assert_eq!((err.offset, err.token.as_deref()), (Some(5), Some("?")));
 }
 
 // ========================================
@@ -1700,3 +1803,142 @@ fn test_get_controlvalue_catalog_entry_and_aliases() {
         assert!(meta.is_alias, "{} must be a hidden alias entry", alias);
     }
 }
+
+#[test]
+fn test_ref_error_parses_as_marker() {
+    match parse("#REF!+1").unwrap() {
+        Expression::BinaryOp { left, .. } => {
+            assert!(matches!(*left, Expression::NamedRef { ref name, .. } if name == "#REF!"));
+        }
+        other => panic!("Expected BinaryOp, got {:?}", other),
+    }
+    assert!(parse("#N/A").is_err());
+}
+
+// ========================================
+// FUNCTION METADATA TESTS
+// ========================================
+
+/// Variant names of `BuiltinFunction`, read from the enum's source so a new
+/// variant cannot be added without the catalog noticing.
+fn builtin_variant_names() -> Vec<String> {
+    let src = include_str!("ast.rs");
+    let start = src.find("pub enum BuiltinFunction {").expect("BuiltinFunction enum");
+    let body = &src[start..];
+    let body = &body[body.find('{').unwrap() + 1..body.find("\n}\n").unwrap()];
+    body.lines()
+        .map(|line| line.split("//").next().unwrap().trim())
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .flat_map(|line| line.split(','))
+        .map(|item| item.split('(').next().unwrap().trim().to_string())
+        .filter(|name| !name.is_empty() && name != "Custom")
+        .collect()
+}
+
+#[test]
+fn test_every_builtin_has_catalog_entry() {
+    let variants = builtin_variant_names();
+    assert!(variants.len() > 400, "enum parse found only {} variants", variants.len());
+    let covered: std::collections::HashSet<String> = BuiltinFunction::all_catalog_entries()
+        .iter()
+        .map(|m| format!("{:?}", BuiltinFunction::from_name(m.name)))
+        .collect();
+    let missing: Vec<&String> = variants.iter().filter(|v| !covered.contains(*v)).collect();
+    assert!(missing.is_empty(), "BuiltinFunction variants without catalog metadata: {:?}", missing);
+}
+
+#[test]
+fn test_every_catalog_entry_is_a_builtin() {
+    let mut seen = std::collections::HashSet::new();
+    for meta in BuiltinFunction::all_catalog_entries() {
+        let func = BuiltinFunction::from_name(meta.name);
+        assert!(
+            !matches!(func, BuiltinFunction::Custom(_)),
+            "catalog entry {} does not parse to a built-in",
+            meta.name
+        );
+        assert!(seen.insert(meta.name), "duplicate catalog entry {}", meta.name);
+        if !meta.is_alias {
+            assert_eq!(
+                BuiltinFunction::from_name(func.to_canonical_name()),
+                func,
+                "canonical name of {} does not round-trip",
+                meta.name
+            );
+            assert!(
+                meta.syntax.starts_with(&format!("{}(", meta.name)) && meta.syntax.ends_with(')'),
+                "malformed syntax for {}: {}",
+                meta.name,
+                meta.syntax
+            );
+            assert!(!meta.description.is_empty(), "missing description for {}", meta.name);
+        }
+    }
+}
+
+#[test]
+fn test_function_signature_args() {
+    use crate::signature::{function_signature, ArgType};
+
+    let sumifs = function_signature("sumifs").expect("SUMIFS signature");
+    let shape: Vec<(&str, bool, bool, ArgType)> = sumifs
+        .args
+        .iter()
+        .map(|a| (a.name.as_str(), a.optional, a.variadic, a.type_hint))
+        .collect();
+    assert_eq!(
+        shape,
+        vec![
+            ("sum_range", false, false, ArgType::Range),
+            ("criteria_range1", false, true, ArgType::Range),
+            ("criteria1", false, true, ArgType::Criteria),
+        ]
+    );
+    assert_eq!(sumifs.example, "=SUMIFS(A1:A10, B1:B10, \">0\")");
+
+    let sum = function_signature("SUM").unwrap();
+    assert_eq!(
+        sum.args.iter().map(|a| (a.optional, a.variadic)).collect::<Vec<_>>(),
+        vec![(false, false), (true, true)]
+    );
+
+    // Optional groups repeat as a whole; a trailing optional stays optional.
+    let switch = function_signature("SWITCH").unwrap();
+    let names: Vec<(&str, bool)> = switch.args.iter().map(|a| (a.name.as_str(), a.variadic)).collect();
+    assert_eq!(
+        names,
+        vec![
+            ("expression", false),
+            ("value1", false),
+            ("result1", false),
+            ("value2", true),
+            ("result2", true),
+            ("default", false),
+        ]
+    );
+    assert!(switch.args[5].optional);
+
+    // Aliases resolve to the canonical function; unknown names have none.
+    assert_eq!(function_signature("AVG").unwrap().name, "AVERAGE");
+    assert_eq!(function_signature("stdev.p").unwrap().name, "STDEV.P");
+    assert!(function_signature("NOT_A_FUNCTION").is_none());
+    assert_eq!(function_signature("PI").unwrap().example, "=PI()");
+}
+
+#[test]
+fn test_every_catalog_entry_has_signature() {
+    use crate::signature::signature_of;
+
+    for meta in BuiltinFunction::all_catalog_entries().iter().filter(|m| !m.is_alias) {
+        let sig = signature_of(meta);
+        for arg in &sig.args {
+            assert!(
+                !arg.name.is_empty() && !arg.name.contains(['[', ']', ',']),
+                "bad argument name in {}: {:?}",
+                meta.name,
+                arg.name
+            );
+        }
+        assert!(sig.example.starts_with(&format!("={}(", meta.name)), "example for {}", meta.name);
+    }
+}
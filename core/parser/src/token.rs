@@ -19,6 +19,8 @@ pub enum Token {
     Asterisk,
     Slash,
     Caret,
+    /// Postfix percent: 10% is 0.1
+    Percent,
     Ampersand,
     Equals,
     NotEqual,
@@ -35,6 +37,8 @@ pub enum Token {
     LBrace,
     RBrace,
     Comma,
+    /// Row separator inside array constants: {1,2;3,4}
+    Semicolon,
     Colon,
     /// Sheet reference separator: !
     Exclamation,
@@ -63,6 +67,7 @@ impl std::fmt::Display for Token {
             Token::Asterisk => write!(f, "*"),
             Token::Slash => write!(f, "/"),
             Token::Caret => write!(f, "^"),
+            Token::Percent => write!(f, "%"),
             Token::Ampersand => write!(f, "&"),
             Token::Equals => write!(f, "="),
             Token::NotEqual => write!(f, "<>"),
@@ -73,6 +78,7 @@ impl std::fmt::Display for Token {
             Token::LParen => write!(f, "("),
             Token::RParen => write!(f, ")"),
             Token::Comma => write!(f, ","),
+            Token::Semicolon => write!(f, ";"),
             Token::Colon => write!(f, ":"),
             Token::Exclamation => write!(f, "!"),
             Token::Dollar => write!(f, "$"),
@@ -13,6 +13,9 @@ pub enum PersistenceError {
     #[error("XLSX read error: {0}")]
     XlsxRead(#[from] calamine::XlsxError),
 
+    #[error("ZIP error: {0}")]
+    Zip(#[from] zip::result::ZipError),
+
     #[error("Invalid file format: {0}")]
     InvalidFormat(String),
 
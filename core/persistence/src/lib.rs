@@ -7,9 +7,11 @@ mod error;
 mod xlsx_chart_reader;
 mod xlsx_reader;
 mod xlsx_style_reader;
+mod values_only;
 mod xlsx_writer;
 
 pub use error::PersistenceError;
+pub use values_only::{export_values_only, flatten_to_values, ValuesOnlyReport, ValuesOnlyStrip};
 pub use xlsx_reader::load_xlsx;
 pub use xlsx_writer::save_xlsx;
 
@@ -139,6 +141,48 @@ pub struct Workbook {
     /// Workbook structure protection (opaque app-owned JSON payload; None when
     /// the workbook is unprotected).
     pub workbook_protection: Option<serde_json::Value>,
+    /// Calculation settings. Typed (not opaque) because they map onto the
+    /// xlsx `<calcPr>` element.
+    pub calc_properties: SavedCalcProperties,
+}
+
+/// Workbook calculation settings, shaped after the xlsx `<calcPr>` element
+/// so both formats carry the same thing. Defaults are Excel's.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct SavedCalcProperties {
+    /// "auto", "autoNoTable" or "manual" (`calcMode`)
+    pub calc_mode: String,
+    /// Recalculate before saving (`calcOnSave`)
+    pub calc_on_save: bool,
+    /// Iterative calculation of circular references (`iterate`)
+    pub iterate: bool,
+    /// `iterateCount`
+    pub iterate_count: u32,
+    /// `iterateDelta`
+    pub iterate_delta: f64,
+    /// False = precision as displayed (`fullPrecision`)
+    pub full_precision: bool,
+    /// Multi-threaded calculation (`concurrentCalc`)
+    pub concurrent_calc: bool,
+    /// Thread cap; None = one per processor (`concurrentManualCount`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub concurrent_manual_count: Option<u32>,
+}
+
+impl Default for SavedCalcProperties {
+    fn default() -> Self {
+        Self {
+            calc_mode: "auto".to_string(),
+            calc_on_save: true,
+            iterate: false,
+            iterate_count: 100,
+            iterate_delta: 0.001,
+            full_precision: true,
+            concurrent_calc: true,
+            concurrent_manual_count: None,
+        }
+    }
 }
 
 /// Conditional-formatting rules for one sheet. `rules` is the opaque app-owned
@@ -318,8 +362,10 @@ pub struct SavedPivotDefinition {
     pub definition: serde_json::Value,
 }
 
-/// Workbook-level document properties.
-#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+/// Workbook-level document properties: the core (docProps/core.xml) and
+/// extended (docProps/app.xml) metadata plus user-defined custom properties
+/// (docProps/custom.xml).
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct WorkbookProperties {
     #[serde(default)]
@@ -340,6 +386,41 @@ pub struct WorkbookProperties {
     /// ISO 8601 date string
     #[serde(default)]
     pub last_modified: String,
+    #[serde(default)]
+    pub last_modified_by: String,
+    /// Document status (`cp:contentStatus`), e.g. "Draft" or "Final".
+    #[serde(default)]
+    pub status: String,
+    #[serde(default)]
+    pub company: String,
+    #[serde(default)]
+    pub manager: String,
+    #[serde(default)]
+    pub hyperlink_base: String,
+    /// Custom properties in document order.
+    #[serde(default)]
+    pub custom: Vec<CustomProperty>,
+}
+
+/// A user-defined document property.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CustomProperty {
+    pub name: String,
+    pub value: CustomPropertyValue,
+}
+
+/// The value of a custom document property. OOXML variant types without a
+/// direct counterpart are read into the nearest kind (integers and decimals
+/// as `Number`, other strings as `Text`) so they survive a save.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", content = "value", rename_all = "camelCase")]
+pub enum CustomPropertyValue {
+    Text(String),
+    Number(f64),
+    Bool(bool),
+    /// ISO 8601 date-time string
+    Date(String),
 }
 
 /// A chart entry persisted in the workbook.
@@ -511,6 +592,7 @@ impl Workbook {
             outlines: Vec::new(),
             sheet_protections: Vec::new(),
             workbook_protection: None,
+            calc_properties: SavedCalcProperties::default(),
         }
     }
 
@@ -550,6 +632,7 @@ impl Workbook {
             outlines: Vec::new(),
             sheet_protections: Vec::new(),
             workbook_protection: None,
+            calc_properties: SavedCalcProperties::default(),
         }
     }
 }
@@ -573,6 +656,14 @@ pub struct Sheet {
     pub cells: HashMap<(u32, u32), SavedCell>,
     pub column_widths: HashMap<u32, f64>,
     pub row_heights: HashMap<u32, f64>,
+    /// Sheet default column width in pixels (None = the workbook default)
+    pub default_column_width: Option<f64>,
+    /// Sheet default row height in pixels (None = the workbook default)
+    pub default_row_height: Option<f64>,
+    /// Whole-column default styles (col -> style index)
+    pub column_styles: HashMap<u32, usize>,
+    /// Whole-row default styles (row -> style index)
+    pub row_styles: HashMap<u32, usize>,
     pub styles: Vec<CellStyle>,
     /// Merged cell regions
     pub merged_regions: Vec<SavedMergedRegion>,
@@ -596,6 +687,12 @@ pub struct Sheet {
     pub page_setup: Option<SavedPageSetup>,
     /// Whether gridlines should be shown (default true)
     pub show_gridlines: bool,
+    /// Zoom factor (1.0 = 100%)
+    pub zoom: f64,
+    /// Whether row and column headings should be shown (default true)
+    pub show_headings: bool,
+    /// Right-to-left sheet layout (column A at the right edge)
+    pub right_to_left: bool,
 }
 
 impl Sheet {
@@ -606,6 +703,10 @@ impl Sheet {
             cells: HashMap::new(),
             column_widths: HashMap::new(),
             row_heights: HashMap::new(),
+            default_column_width: None,
+            default_row_height: None,
+            column_styles: HashMap::new(),
+            row_styles: HashMap::new(),
             styles: vec![CellStyle::new()],
             merged_regions: Vec::new(),
             freeze_row: None,
@@ -618,6 +719,9 @@ impl Sheet {
             hyperlinks: Vec::new(),
             page_setup: None,
             show_gridlines: true,
+            zoom: 1.0,
+            show_headings: true,
+            right_to_left: false,
         }
     }
 
@@ -640,6 +744,10 @@ impl Sheet {
             cells,
             column_widths: dimensions.column_widths.clone(),
             row_heights: dimensions.row_heights.clone(),
+            default_column_width: None,
+            default_row_height: None,
+            column_styles: grid.col_styles.iter().map(|(&c, &s)| (c, s)).collect(),
+            row_styles: grid.row_styles.iter().map(|(&r, &s)| (r, s)).collect(),
             styles: styles.all_styles().to_vec(),
             merged_regions: Vec::new(),
             freeze_row: None,
@@ -652,6 +760,9 @@ impl Sheet {
             hyperlinks: Vec::new(),
             page_setup: None,
             show_gridlines: true,
+            zoom: 1.0,
+            show_headings: true,
+            right_to_left: false,
         }
     }
 
@@ -669,6 +780,8 @@ impl Sheet {
             let cell = saved_cell.to_cell();
             grid.set_cell(*row, *col, cell);
         }
+        grid.col_styles.extend(self.column_styles.iter().map(|(&c, &s)| (c, s)));
+        grid.row_styles.extend(self.row_styles.iter().map(|(&r, &s)| (r, s)));
 
         (grid, style_registry)
     }
@@ -697,6 +810,8 @@ pub struct SavedCell {
     pub style_index: usize,
     /// Rich text runs for partial formatting within the cell.
     pub rich_text: Option<Vec<RichTextRun>>,
+    /// Text entered with a leading apostrophe (xlsx `quotePrefix`).
+    pub quote_prefix: bool,
 }
 
 impl SavedCell {
@@ -710,6 +825,7 @@ impl SavedCell {
             formula: cell.formula_string_raw(),
             style_index: cell.style_index,
             rich_text: cell.rich_text.clone(),
+            quote_prefix: cell.quote_prefix,
         }
     }
 
@@ -726,6 +842,7 @@ impl SavedCell {
                 value: self.value.to_value(),
                 style_index: self.style_index,
                 rich_text: self.rich_text.clone(),
+                quote_prefix: self.quote_prefix,
             }
         }
     }
@@ -831,6 +948,9 @@ pub struct SavedTable {
     pub columns: Vec<SavedTableColumn>,
     pub style_options: SavedTableStyleOptions,
     pub style_name: String,
+    /// "reject" or "coerce"; None means the default ("reject")
+    #[serde(default)]
+    pub data_type_policy: Option<String>,
 }
 
 /// Serializable table column
@@ -841,6 +961,9 @@ pub struct SavedTableColumn {
     pub totals_row_function: String,
     pub totals_row_formula: Option<String>,
     pub calculated_formula: Option<String>,
+    /// "number", "date", "text" or "boolean"; None when unrestricted
+    #[serde(default)]
+    pub data_type: Option<String>,
 }
 
 /// Serializable table style options
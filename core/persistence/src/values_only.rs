@@ -0,0 +1,237 @@
+//! FILENAME: core/persistence/src/values_only.rs
+//! PURPOSE: "Values only" export — a copy of the workbook where every formula
+//! cell keeps just its computed value, optionally without comments, names,
+//! data validations and hidden sheets.
+//! CONTEXT: Works on a `Workbook` snapshot (the same one a normal save builds),
+//! never on live app state. The flattened copy goes through `save_xlsx`.
+
+use crate::{save_xlsx, PersistenceError, Workbook};
+use identity::SheetId;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::path::Path;
+
+/// Metadata categories to leave out of a values-only export.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ValuesOnlyStrip {
+    /// Notes and threaded comments.
+    pub comments: bool,
+    /// Defined names (workbook- and sheet-scoped).
+    pub names: bool,
+    /// Data-validation ranges.
+    pub validations: bool,
+    /// Hidden and very hidden sheets.
+    pub hidden_sheets: bool,
+}
+
+/// What a values-only export changed in its copy of the workbook.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ValuesOnlyReport {
+    pub formulas_flattened: usize,
+    pub comments_removed: usize,
+    pub names_removed: usize,
+    pub validations_removed: usize,
+    pub hidden_sheets_removed: usize,
+}
+
+/// Length of an opaque per-sheet payload that serializes a list.
+fn payload_len(payload: &serde_json::Value) -> usize {
+    payload.as_array().map_or(0, Vec::len)
+}
+
+/// Replace every formula in `workbook` with its cached value and drop the
+/// categories selected in `strip`.
+pub fn flatten_to_values(workbook: &mut Workbook, strip: &ValuesOnlyStrip) -> ValuesOnlyReport {
+    let mut report = ValuesOnlyReport::default();
+
+    // Hidden sheets go first so nothing below counts items that live on them.
+    // A workbook needs one sheet, so they stay if nothing else is visible.
+    if strip.hidden_sheets && workbook.sheets.iter().any(|s| s.visibility == "visible") {
+        let active_id = workbook.sheets.get(workbook.active_sheet).map(|s| s.id);
+        let removed: HashSet<SheetId> = workbook
+            .sheets
+            .iter()
+            .filter(|s| s.visibility != "visible")
+            .map(|s| s.id)
+            .collect();
+        workbook.sheets.retain(|s| !removed.contains(&s.id));
+        report.hidden_sheets_removed = removed.len();
+
+        workbook.active_sheet = active_id
+            .and_then(|id| workbook.sheets.iter().position(|s| s.id == id))
+            .unwrap_or(0);
+        workbook.tables.retain(|t| !removed.contains(&t.sheet_id));
+        workbook.charts.retain(|c| !removed.contains(&c.sheet_id));
+        workbook.sparklines.retain(|s| !removed.contains(&s.sheet_id));
+        workbook.named_ranges.retain(|n| n.sheet_id.is_none_or(|id| !removed.contains(&id)));
+        workbook.comments.retain(|c| !removed.contains(&c.sheet_id));
+        workbook.data_validations.retain(|v| !removed.contains(&v.sheet_id));
+        workbook.conditional_formats.retain(|c| !removed.contains(&c.sheet_id));
+        workbook.sheet_protections.retain(|p| !removed.contains(&p.sheet_id));
+    }
+
+    for sheet in &mut workbook.sheets {
+        for cell in sheet.cells.values_mut() {
+            if cell.formula.take().is_some() {
+                report.formulas_flattened += 1;
+            }
+        }
+    }
+    // Table formulas would otherwise be written back into the flattened cells.
+    for table in &mut workbook.tables {
+        for column in &mut table.columns {
+            column.calculated_formula = None;
+            column.totals_row_formula = None;
+        }
+    }
+
+    if strip.comments {
+        for sheet in &mut workbook.sheets {
+            report.comments_removed += sheet.notes.len();
+            sheet.notes.clear();
+        }
+        report.comments_removed += workbook.comments.iter().map(|c| payload_len(&c.comments)).sum::<usize>();
+        workbook.comments.clear();
+    }
+    if strip.names {
+        report.names_removed = workbook.named_ranges.len();
+        workbook.named_ranges.clear();
+    }
+    if strip.validations {
+        report.validations_removed =
+            workbook.data_validations.iter().map(|v| payload_len(&v.ranges)).sum();
+        workbook.data_validations.clear();
+    }
+
+    report
+}
+
+/// Flatten a copy of `workbook` (see `flatten_to_values`) and save it as xlsx
+/// to `path`. `workbook` itself is left untouched.
+pub fn export_values_only(
+    workbook: &Workbook,
+    path: &Path,
+    strip: &ValuesOnlyStrip,
+) -> Result<ValuesOnlyReport, PersistenceError> {
+    let mut snapshot = workbook.clone();
+    let report = flatten_to_values(&mut snapshot, strip);
+    save_xlsx(&snapshot, path)?;
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{load_xlsx, SavedCell, SavedCellValue, SavedNamedRange, SavedNote, SavedSheetDataValidations, Sheet};
+
+    fn cell(value: SavedCellValue, formula: Option<&str>) -> SavedCell {
+        SavedCell { value, formula: formula.map(String::from), style_index: 0, rich_text: None, quote_prefix: false }
+    }
+
+    fn note(row: u32, col: u32, text: &str) -> SavedNote {
+        SavedNote {
+            row,
+            col,
+            text: text.to_string(),
+            author: "Ann".to_string(),
+            rich_content: None,
+            width: 0.0,
+            height: 0.0,
+            visible: false,
+            created_at: String::new(),
+            modified_at: String::new(),
+        }
+    }
+
+    fn sample_workbook() -> Workbook {
+        let mut data = Sheet::new("Data".to_string());
+        data.cells.insert((0, 0), cell(SavedCellValue::Number(2.0), None));
+        data.cells.insert((0, 1), cell(SavedCellValue::Number(4.0), Some("=A1*2")));
+        data.cells.insert((1, 0), cell(SavedCellValue::Text("total: 6".to_string()), Some("=\"total: \"&(A1+B1)")));
+        data.cells.insert((1, 1), cell(SavedCellValue::Boolean(true), Some("=B1>A1")));
+        data.notes.push(note(0, 0, "input"));
+
+        let mut hidden = Sheet::new("Lookup".to_string());
+        hidden.visibility = "hidden".to_string();
+        hidden.cells.insert((0, 0), cell(SavedCellValue::Number(7.0), Some("=3+4")));
+
+        let mut workbook = Workbook::new();
+        let data_id = data.id;
+        workbook.sheets = vec![data, hidden];
+        workbook.named_ranges.push(SavedNamedRange {
+            name: "Rate".to_string(),
+            refers_to: "Data!$A$1".to_string(),
+            sheet_id: None,
+            comment: None,
+            folder: None,
+        });
+        workbook.data_validations.push(SavedSheetDataValidations {
+            sheet_id: data_id,
+            ranges: serde_json::json!([{ "startRow": 0 }]),
+        });
+        workbook
+    }
+
+    #[test]
+    fn test_flatten_keeps_values_and_strips_selected_categories() {
+        let mut workbook = sample_workbook();
+        let report = flatten_to_values(
+            &mut workbook,
+            &ValuesOnlyStrip { comments: true, names: true, validations: true, hidden_sheets: true },
+        );
+        assert_eq!(
+            report,
+            ValuesOnlyReport {
+                formulas_flattened: 3,
+                comments_removed: 1,
+                names_removed: 1,
+                validations_removed: 1,
+                hidden_sheets_removed: 1,
+            }
+        );
+        assert_eq!(workbook.sheets.len(), 1);
+        assert!(workbook.sheets[0].cells.values().all(|c| c.formula.is_none()));
+        assert!(matches!(workbook.sheets[0].cells[&(0, 1)].value, SavedCellValue::Number(n) if n == 4.0));
+
+        // Nothing selected: only formulas change.
+        let mut workbook = sample_workbook();
+        let report = flatten_to_values(&mut workbook, &ValuesOnlyStrip::default());
+        assert_eq!(report.formulas_flattened, 4);
+        assert_eq!(report.hidden_sheets_removed + report.names_removed + report.comments_removed, 0);
+        assert_eq!(workbook.sheets.len(), 2);
+        assert_eq!(workbook.named_ranges.len(), 1);
+    }
+
+    #[test]
+    fn test_export_values_only_roundtrip() {
+        let workbook = sample_workbook();
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("values.xlsx");
+        let report = export_values_only(
+            &workbook,
+            &path,
+            &ValuesOnlyStrip { comments: true, names: true, validations: false, hidden_sheets: true },
+        )
+        .unwrap();
+        assert_eq!(report.formulas_flattened, 3);
+
+        let loaded = load_xlsx(&path).unwrap();
+        assert_eq!(loaded.sheets.len(), 1);
+        let sheet = &loaded.sheets[0];
+        assert_eq!(sheet.name, "Data");
+        assert!(sheet.cells.values().all(|c| c.formula.is_none()));
+        assert!(matches!(sheet.cells[&(0, 1)].value, SavedCellValue::Number(n) if n == 4.0));
+        assert!(matches!(&sheet.cells[&(1, 0)].value, SavedCellValue::Text(s) if s == "total: 6"));
+        assert!(matches!(sheet.cells[&(1, 1)].value, SavedCellValue::Boolean(true)));
+        assert!(sheet.notes.is_empty());
+        assert!(loaded.named_ranges.is_empty());
+
+        // The source workbook keeps its formulas, note, name and hidden sheet.
+        assert_eq!(workbook.sheets.len(), 2);
+        assert_eq!(workbook.sheets[0].cells[&(0, 1)].formula.as_deref(), Some("=A1*2"));
+        assert_eq!(workbook.sheets[0].notes.len(), 1);
+        assert_eq!(workbook.named_ranges.len(), 1);
+    }
+}
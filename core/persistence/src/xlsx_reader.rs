@@ -134,9 +134,11 @@ pub fn load_xlsx(path: &Path) -> Result<Workbook, PersistenceError> {
 
                 // Look up the XLSX style index for this cell (using absolute coords
                 // since the XML parser stores absolute positions)
-                let style_index = sheet_meta
+                let xlsx_xf = sheet_meta
                     .and_then(|m| m.cell_styles.get(&(actual_row, actual_col)))
-                    .and_then(|xlsx_xf| xf_to_calcula.get(xlsx_xf))
+                    .copied();
+                let style_index = xlsx_xf
+                    .and_then(|xf| xf_to_calcula.get(&xf))
                     .copied()
                     .unwrap_or(0);
 
@@ -159,6 +161,13 @@ pub fn load_xlsx(path: &Path) -> Result<Workbook, PersistenceError> {
                     }
                 });
 
+                // Literal text typed with a leading apostrophe (xf quotePrefix)
+                let quote_prefix = formula.is_none()
+                    && matches!(saved_value, SavedCellValue::Text(_))
+                    && xlsx_xf
+                        .and_then(|xf| style_data.as_ref()?.cell_xfs.get(xf as usize))
+                        .is_some_and(|xf| xf.quote_prefix);
+
                 cells.insert(
                     (actual_row, actual_col),
                     SavedCell {
@@ -166,6 +175,7 @@ pub fn load_xlsx(path: &Path) -> Result<Workbook, PersistenceError> {
                         formula,
                         style_index,
                         rich_text: None,
+                        quote_prefix,
                     },
                 );
             }
@@ -188,6 +198,7 @@ pub fn load_xlsx(path: &Path) -> Result<Workbook, PersistenceError> {
                                 formula: None,
                                 style_index: calcula_idx,
                                 rich_text: None,
+                                quote_prefix: false,
                             },
                         );
                     }
@@ -205,6 +216,27 @@ pub fn load_xlsx(path: &Path) -> Result<Workbook, PersistenceError> {
             .map(|m| m.row_heights.clone())
             .unwrap_or_default();
 
+        // Sheet default sizes from <sheetFormatPr>
+        let default_column_width = sheet_meta.and_then(|m| m.default_column_width);
+        let default_row_height = sheet_meta.and_then(|m| m.default_row_height);
+
+        // Whole-column/row default styles, mapped to Calcula style indices
+        let map_line_styles = |lines: &HashMap<u32, u32>| -> HashMap<u32, usize> {
+            lines
+                .iter()
+                .filter_map(|(line, xf)| match xf_to_calcula.get(xf) {
+                    Some(&idx) if idx != 0 => Some((*line, idx)),
+                    _ => None,
+                })
+                .collect()
+        };
+        let column_styles = sheet_meta
+            .map(|m| map_line_styles(&m.column_styles))
+            .unwrap_or_default();
+        let row_styles = sheet_meta
+            .map(|m| map_line_styles(&m.row_styles))
+            .unwrap_or_default();
+
         // Merged regions
         let merged_regions = sheet_meta
             .map(|m| {
@@ -244,6 +276,14 @@ pub fn load_xlsx(path: &Path) -> Result<Workbook, PersistenceError> {
             .map(|m| m.show_gridlines)
             .unwrap_or(true);
 
+        // Remaining sheet view options (zoomScale is a percentage)
+        let zoom = sheet_meta
+            .and_then(|m| m.zoom_scale)
+            .filter(|z| (10..=400).contains(z))
+            .map_or(1.0, |z| z as f64 / 100.0);
+        let show_headings = sheet_meta.map(|m| m.show_headings).unwrap_or(true);
+        let right_to_left = sheet_meta.map(|m| m.right_to_left).unwrap_or(false);
+
         // Tab color, notes, hyperlinks, page setup from the XML parse; sheet
         // visibility from workbook.xml state (hidden sheets stay hidden
         // instead of silently unhiding on import).
@@ -264,6 +304,10 @@ pub fn load_xlsx(path: &Path) -> Result<Workbook, PersistenceError> {
             cells,
             column_widths,
             row_heights,
+            default_column_width,
+            default_row_height,
+            column_styles,
+            row_styles,
             styles: calcula_styles.clone(),
             merged_regions,
             freeze_row,
@@ -276,6 +320,9 @@ pub fn load_xlsx(path: &Path) -> Result<Workbook, PersistenceError> {
             hyperlinks,
             page_setup,
             show_gridlines,
+            zoom,
+            show_headings,
+            right_to_left,
         });
     }
 
@@ -314,6 +361,7 @@ pub fn load_xlsx(path: &Path) -> Result<Workbook, PersistenceError> {
         outlines: Vec::new(),
         sheet_protections: Vec::new(),
         workbook_protection: None,
+        calc_properties: crate::SavedCalcProperties::default(),
     };
 
     // Sparklines have no native xlsx form — the meta carry is the only source.
@@ -327,9 +375,13 @@ pub fn load_xlsx(path: &Path) -> Result<Workbook, PersistenceError> {
         });
     }
 
-    // Second ZIP pass: native charts + defined names.
+    // Second ZIP pass: calculation settings, document properties, native
+    // charts + defined names.
     if let Ok(file) = std::fs::File::open(path) {
         if let Ok(mut archive) = zip::ZipArchive::new(file) {
+            wb.calc_properties = crate::xlsx_style_reader::parse_calc_properties(&mut archive);
+            wb.properties = crate::xlsx_style_reader::parse_doc_properties(&mut archive);
+
             // Charts come from TWO sources that must be reconciled:
             // - the native OOXML charts in the file (what Excel sees/edits),
             // - the _calcula_meta carry (lossless ChartDefinitions, but STALE
@@ -523,25 +575,29 @@ fn apply_print_defined_name(
         let range = part.rsplit_once('!').map(|(_, r)| r).unwrap_or(part);
         range.replace('$', "")
     };
-    if refers_to.contains(',') {
-        return; // multi-range: unsupported, keep whatever is already set
-    }
-    let value = strip(refers_to.trim());
-    if value.is_empty() {
+    // Multi-area names ("Sheet1!$A$1:$B$2,Sheet1!$D$1:$E$2") keep every area.
+    let parts: Vec<String> = refers_to
+        .split(',')
+        .map(|part| strip(part.trim()))
+        .filter(|part| !part.is_empty())
+        .collect();
+    if parts.is_empty() {
         return;
     }
     let ps = sheet
         .page_setup
         .get_or_insert_with(crate::xlsx_style_reader::default_page_setup);
     if name == "_xlnm.Print_Area" {
-        ps.print_area = value;
+        ps.print_area = parts.join(",");
     } else {
-        // Print_Titles: only a pure row range ("1:2") maps to repeat-rows.
-        let is_row_range = value
-            .split(':')
-            .all(|p| !p.is_empty() && p.chars().all(|c| c.is_ascii_digit()));
-        if is_row_range {
-            ps.print_titles_rows = value;
+        // Print_Titles: only the pure row range ("1:2") maps to repeat-rows.
+        let is_row_range = |value: &String| {
+            value
+                .split(':')
+                .all(|p| !p.is_empty() && p.chars().all(|c| c.is_ascii_digit()))
+        };
+        if let Some(rows) = parts.into_iter().find(is_row_range) {
+            ps.print_titles_rows = rows;
         }
     }
 }
@@ -99,6 +99,8 @@ pub struct ParsedXf {
     pub apply_border: bool,
     pub apply_number_format: bool,
     pub apply_alignment: bool,
+    /// `quotePrefix="1"`: cell text was entered with a leading apostrophe.
+    pub quote_prefix: bool,
 }
 
 /// Per-sheet metadata that calamine doesn't provide.
@@ -112,6 +114,14 @@ pub struct SheetMeta {
     pub column_widths: HashMap<u32, f64>,
     /// Custom row heights keyed by 0-based row index (in pixels, converted from Excel points)
     pub row_heights: HashMap<u32, f64>,
+    /// `<sheetFormatPr defaultColWidth=>` in pixels (same conversion as `<col width=>`)
+    pub default_column_width: Option<f64>,
+    /// `<sheetFormatPr defaultRowHeight= customHeight="1">` in pixels
+    pub default_row_height: Option<f64>,
+    /// Column default style index per 0-based column (`<col style=>`)
+    pub column_styles: HashMap<u32, u32>,
+    /// Row default style index per 0-based row (`<row s= customFormat="1">`)
+    pub row_styles: HashMap<u32, u32>,
     /// Freeze pane position (frozen_rows, frozen_cols)
     pub freeze_pane: Option<(u32, u32)>,
     /// Hidden columns (0-based)
@@ -120,6 +130,12 @@ pub struct SheetMeta {
     pub hidden_rows: Vec<u32>,
     /// Whether gridlines should be shown (default true)
     pub show_gridlines: bool,
+    /// `zoomScale` percentage, when the sheet view sets one
+    pub zoom_scale: Option<u32>,
+    /// Whether row/column headings should be shown (default true)
+    pub show_headings: bool,
+    /// Right-to-left sheet layout
+    pub right_to_left: bool,
     /// Sheet tab color as "#RRGGBB" (from sheetPr/tabColor rgb, alpha stripped)
     pub tab_color: Option<String>,
     /// Page setup / print settings (Some only when the sheet carries any)
@@ -435,6 +451,201 @@ pub fn parse_defined_names(
     result
 }
 
+/// Parse `<calcPr>` from xl/workbook.xml. Absent attributes keep Excel's
+/// defaults; a missing element yields the defaults outright.
+pub fn parse_calc_properties(archive: &mut zip::ZipArchive<std::fs::File>) -> crate::SavedCalcProperties {
+    let mut props = crate::SavedCalcProperties::default();
+    let wb_xml = match read_zip_entry(archive, "xl/workbook.xml") {
+        Ok(xml) => xml,
+        Err(_) => return props,
+    };
+    let mut reader = Reader::from_str(&wb_xml);
+    reader.trim_text(true);
+    let mut buf = Vec::new();
+    let flag = |v: String| v == "1" || v == "true";
+
+    loop {
+        match reader.read_event_into(&mut buf) {
+            Ok(Event::Eof) => break,
+            Ok(Event::Start(ref e)) | Ok(Event::Empty(ref e)) => {
+                let local = e.local_name();
+                if local.as_ref() == b"calcPr" {
+                    if let Some(mode) = get_attr(e, "calcMode") {
+                        props.calc_mode = mode;
+                    }
+                    if let Some(v) = get_attr(e, "calcOnSave") {
+                        props.calc_on_save = flag(v);
+                    }
+                    if let Some(v) = get_attr(e, "iterate") {
+                        props.iterate = flag(v);
+                    }
+                    if let Some(n) = get_attr(e, "iterateCount").and_then(|v| v.parse().ok()) {
+                        props.iterate_count = n;
+                    }
+                    if let Some(d) = get_attr(e, "iterateDelta").and_then(|v| v.parse().ok()) {
+                        props.iterate_delta = d;
+                    }
+                    if let Some(v) = get_attr(e, "fullPrecision") {
+                        props.full_precision = flag(v);
+                    }
+                    if let Some(v) = get_attr(e, "concurrentCalc") {
+                        props.concurrent_calc = flag(v);
+                    }
+                    props.concurrent_manual_count =
+                        get_attr(e, "concurrentManualCount").and_then(|v| v.parse().ok());
+                    break;
+                }
+            }
+            Err(_) => break,
+            _ => {}
+        }
+        buf.clear();
+    }
+    props
+}
+
+/// Parse the document properties from docProps/core.xml, docProps/app.xml
+/// and docProps/custom.xml. Missing parts leave their fields empty.
+pub fn parse_doc_properties(archive: &mut zip::ZipArchive<std::fs::File>) -> crate::WorkbookProperties {
+    let mut props = crate::WorkbookProperties::default();
+    if let Ok(xml) = read_zip_entry(archive, "docProps/core.xml") {
+        for (tag, text) in leaf_elements(&xml) {
+            let field = match tag.as_str() {
+                "title" => &mut props.title,
+                "creator" => &mut props.author,
+                "subject" => &mut props.subject,
+                "description" => &mut props.description,
+                "keywords" => &mut props.keywords,
+                "category" => &mut props.category,
+                "created" => &mut props.created,
+                "modified" => &mut props.last_modified,
+                "lastModifiedBy" => &mut props.last_modified_by,
+                "contentStatus" => &mut props.status,
+                _ => continue,
+            };
+            *field = text;
+        }
+    }
+    if let Ok(xml) = read_zip_entry(archive, "docProps/app.xml") {
+        for (tag, text) in leaf_elements(&xml) {
+            let field = match tag.as_str() {
+                "Company" => &mut props.company,
+                "Manager" => &mut props.manager,
+                "HyperlinkBase" => &mut props.hyperlink_base,
+                _ => continue,
+            };
+            *field = text;
+        }
+    }
+    if let Ok(xml) = read_zip_entry(archive, "docProps/custom.xml") {
+        props.custom = parse_custom_properties(&xml);
+    }
+    props
+}
+
+/// `<property name="..."><vt:TYPE>value</vt:TYPE></property>` entries in
+/// document order.
+fn parse_custom_properties(xml: &str) -> Vec<crate::CustomProperty> {
+    use crate::CustomPropertyValue;
+    let mut reader = Reader::from_str(xml);
+    let mut buf = Vec::new();
+    let mut result = Vec::new();
+    let mut name: Option<String> = None;
+    let mut value: Option<CustomPropertyValue> = None;
+    let mut value_type = String::new();
+    let mut text = String::new();
+
+    let to_value = |value_type: &str, text: String| match value_type {
+        "bool" => CustomPropertyValue::Bool(text.trim() == "true" || text.trim() == "1"),
+        "filetime" | "date" => CustomPropertyValue::Date(text),
+        "i1" | "i2" | "i4" | "i8" | "int" | "ui1" | "ui2" | "ui4" | "ui8" | "uint" | "r4" | "r8"
+        | "decimal" | "cy" => match text.trim().parse::<f64>() {
+            Ok(n) => CustomPropertyValue::Number(n),
+            Err(_) => CustomPropertyValue::Text(text),
+        },
+        _ => CustomPropertyValue::Text(text),
+    };
+
+    loop {
+        match reader.read_event_into(&mut buf) {
+            Ok(Event::Eof) => break,
+            Ok(Event::Start(ref e)) => {
+                let local = String::from_utf8_lossy(e.local_name().as_ref()).into_owned();
+                if local == "property" {
+                    name = get_attr(e, "name").map(|n| unescape_xml(&n));
+                    value = None;
+                } else if name.is_some() && value.is_none() {
+                    value_type = local;
+                }
+                text.clear();
+            }
+            Ok(Event::Empty(ref e)) => {
+                if name.is_some() && value.is_none() {
+                    let local = String::from_utf8_lossy(e.local_name().as_ref()).into_owned();
+                    value = Some(to_value(&local, String::new()));
+                }
+            }
+            Ok(Event::Text(ref t)) => {
+                if let Ok(s) = t.unescape() {
+                    text.push_str(&s);
+                }
+            }
+            Ok(Event::End(ref e)) => {
+                let local = e.local_name();
+                if local.as_ref() == b"property" {
+                    if let (Some(name), Some(value)) = (name.take(), value.take()) {
+                        result.push(crate::CustomProperty { name, value });
+                    }
+                } else if name.is_some() && value.is_none() && local.as_ref() == value_type.as_bytes() {
+                    value = Some(to_value(&value_type, std::mem::take(&mut text)));
+                }
+            }
+            Err(_) => break,
+            _ => {}
+        }
+        buf.clear();
+    }
+    result
+}
+
+/// (local name, text) of every element without child elements.
+fn leaf_elements(xml: &str) -> Vec<(String, String)> {
+    let mut reader = Reader::from_str(xml);
+    let mut buf = Vec::new();
+    let mut result = Vec::new();
+    let mut current: Option<String> = None;
+    let mut text = String::new();
+    loop {
+        match reader.read_event_into(&mut buf) {
+            Ok(Event::Eof) => break,
+            Ok(Event::Start(ref e)) => {
+                current = Some(String::from_utf8_lossy(e.local_name().as_ref()).into_owned());
+                text.clear();
+            }
+            Ok(Event::Empty(ref e)) => {
+                result.push((String::from_utf8_lossy(e.local_name().as_ref()).into_owned(), String::new()));
+                current = None;
+            }
+            Ok(Event::Text(ref t)) => {
+                if let Ok(s) = t.unescape() {
+                    text.push_str(&s);
+                }
+            }
+            Ok(Event::End(ref e)) => {
+                if let Some(tag) = current.take() {
+                    if tag.as_bytes() == e.local_name().as_ref() {
+                        result.push((tag, std::mem::take(&mut text)));
+                    }
+                }
+            }
+            Err(_) => break,
+            _ => {}
+        }
+        buf.clear();
+    }
+    result
+}
+
 fn read_zip_entry(archive: &mut zip::ZipArchive<std::fs::File>, name: &str) -> Result<String, ()> {
     let mut entry = archive.by_name(name).map_err(|_| ())?;
     let mut buf = String::new();
@@ -656,6 +867,9 @@ fn parse_styles_xml(xml: &str, data: &mut XlsxStyleData) {
                                     current_xf.apply_alignment =
                                         val == "1" || val == "true"
                                 }
+                                "quotePrefix" => {
+                                    current_xf.quote_prefix = val == "1" || val == "true"
+                                }
                                 _ => {}
                             }
                         }
@@ -761,6 +975,7 @@ enum StyleParseContext {
 fn parse_sheet_xml(xml: &str) -> SheetMeta {
     let mut meta = SheetMeta {
         show_gridlines: true, // Default is to show gridlines
+        show_headings: true,
         ..Default::default()
     };
     let mut reader = Reader::from_str(xml);
@@ -904,10 +1119,18 @@ fn parse_sheet_xml(xml: &str) -> SheetMeta {
                     }
                     "sheetViews" => in_sheet_views = true,
                     "sheetView" if in_sheet_views => {
-                        // <sheetView showGridLines="0" ...>
+                        // <sheetView showGridLines="0" showRowColHeaders="0"
+                        //            rightToLeft="1" zoomScale="150" ...>
                         if let Some(v) = get_attr(e, "showGridLines") {
                             meta.show_gridlines = v != "0" && v != "false";
                         }
+                        if let Some(v) = get_attr(e, "showRowColHeaders") {
+                            meta.show_headings = v != "0" && v != "false";
+                        }
+                        if let Some(v) = get_attr(e, "rightToLeft") {
+                            meta.right_to_left = v == "1" || v == "true";
+                        }
+                        meta.zoom_scale = get_attr(e, "zoomScale").and_then(|v| v.parse().ok());
                     }
                     "pane" if in_sheet_views => {
                         // Freeze pane: <pane xSplit="1" ySplit="2" state="frozen" ...>
@@ -922,6 +1145,22 @@ fn parse_sheet_xml(xml: &str) -> SheetMeta {
                             }
                         }
                     }
+                    "sheetFormatPr" => {
+                        // <sheetFormatPr defaultColWidth="12.5" defaultRowHeight="18"
+                        //                customHeight="1"/> — defaultRowHeight
+                        // without customHeight is just the font-derived height
+                        if let Some(w) = get_attr(e, "defaultColWidth").and_then(|v| v.parse::<f64>().ok()) {
+                            meta.default_column_width = Some((w * 7.0 + 5.0).round());
+                        }
+                        let custom_height = get_attr(e, "customHeight")
+                            .map(|v| v == "1" || v == "true")
+                            .unwrap_or(false);
+                        if custom_height {
+                            if let Some(ht) = get_attr(e, "defaultRowHeight").and_then(|v| v.parse::<f64>().ok()) {
+                                meta.default_row_height = Some((ht * 1.333).round());
+                            }
+                        }
+                    }
                     "sheetData" => in_sheet_data = true,
                     "row" if in_sheet_data => {
                         // Row element: <row r="5" ht="20" customHeight="1" hidden="1">
@@ -935,13 +1174,20 @@ fn parse_sheet_xml(xml: &str) -> SheetMeta {
                             if let Ok(ht) = ht_str.parse::<f64>() {
                                 // Excel stores row height in points; convert to pixels (1 pt = 1.333 px)
                                 let px = (ht * 1.333).round();
-                                // Only store if different from the default (15pt = 20px)
-                                // to avoid bloating the map with thousands of default-height rows
-                                if (px - 20.0).abs() > 1.0 {
+                                // Only store if different from the sheet default
+                                // (15pt = 20px unless <sheetFormatPr> sets one) to avoid
+                                // bloating the map with thousands of default-height rows
+                                if (px - meta.default_row_height.unwrap_or(20.0)).abs() > 1.0 {
                                     meta.row_heights.insert(current_row, px);
                                 }
                             }
                         }
+                        // Row style: only applies to the whole row with customFormat
+                        if get_attr(e, "customFormat").map(|v| v == "1" || v == "true").unwrap_or(false) {
+                            if let Some(s) = get_attr(e, "s").and_then(|v| v.parse::<u32>().ok()) {
+                                meta.row_styles.insert(current_row, s);
+                            }
+                        }
                         // Hidden row
                         if get_attr(e, "hidden").map(|v| v == "1" || v == "true").unwrap_or(false) {
                             meta.hidden_rows.push(current_row);
@@ -990,6 +1236,13 @@ fn parse_sheet_xml(xml: &str) -> SheetMeta {
                                 meta.hidden_columns.push(c - 1);
                             }
                         }
+                        if let Some(style) = get_attr(e, "style").and_then(|v| v.parse::<u32>().ok()) {
+                            if style != 0 {
+                                for c in min..=max {
+                                    meta.column_styles.insert(c - 1, style);
+                                }
+                            }
+                        }
                     }
                     "mergeCells" => in_merge_cells = true,
                     "mergeCell" if in_merge_cells => {
@@ -1684,7 +1937,7 @@ fn convert_number_format(num_fmt_id: u32, custom_formats: &HashMap<u32, String>)
         21 => NumberFormat::Time {
             format: "HH:mm:ss".to_string(),
         },
-        22 => NumberFormat::Date {
+        22 => NumberFormat::DateTime {
             format: "M/D/YYYY HH:mm".to_string(),
         },
         37 | 38 | 39 | 40 => NumberFormat::Number {
@@ -1728,7 +1981,11 @@ fn parse_format_code(full_code: &str) -> NumberFormat {
 
     // Date patterns
     if lower.contains('y') || lower.contains("mmm") || lower.contains('d') {
-        // If it also contains time elements, it could be datetime
+        if lower.contains('h') || lower.contains('s') {
+            return NumberFormat::DateTime {
+                format: code.to_string(),
+            };
+        }
         return NumberFormat::Date {
             format: code.to_string(),
         };
@@ -1934,6 +2191,10 @@ mod tests {
             parse_format_code("HH:mm:ss"),
             NumberFormat::Time { .. }
         ));
+        assert!(matches!(
+            parse_format_code("yyyy-mm-dd hh:mm"),
+            NumberFormat::DateTime { .. }
+        ));
         assert!(matches!(
             parse_format_code("0.00E+00"),
             NumberFormat::Scientific { decimal_places: 2 }
@@ -1,12 +1,15 @@
 //! FILENAME: core/persistence/src/xlsx_writer.rs
 
-use crate::{CalculaMeta, PersistenceError, SavedCellValue, SavedPageSetup, Workbook, META_SHEET_NAME};
+use crate::{
+    CalculaMeta, CustomPropertyValue, PersistenceError, SavedCalcProperties, SavedCellValue,
+    SavedPageSetup, Workbook, WorkbookProperties, META_SHEET_NAME,
+};
 use engine::style::{
     BorderLineStyle, BorderStyle, CellStyle, NumberFormat, TextAlign, TextRotation, VerticalAlign,
 };
 use rust_xlsxwriter::{
-    Chart, ChartLegendPosition, ChartSeries, ChartType, DocProperties, Format, FormatAlign,
-    FormatBorder, FormatDiagonalBorder, Note, Workbook as XlsxWorkbook,
+    Chart, ChartLegendPosition, ChartSeries, ChartType, DocProperties, ExcelDateTime, Format,
+    FormatAlign, FormatBorder, FormatDiagonalBorder, Note, Workbook as XlsxWorkbook,
 };
 use std::path::Path;
 
@@ -42,6 +45,37 @@ pub fn save_xlsx(workbook: &Workbook, path: &Path) -> Result<(), PersistenceErro
         if !props.category.is_empty() {
             doc_props = doc_props.set_category(&props.category);
         }
+        if !props.status.is_empty() {
+            doc_props = doc_props.set_status(&props.status);
+        }
+        if !props.company.is_empty() {
+            doc_props = doc_props.set_company(&props.company);
+        }
+        if !props.manager.is_empty() {
+            doc_props = doc_props.set_manager(&props.manager);
+        }
+        if !props.hyperlink_base.is_empty() {
+            doc_props = doc_props.set_hyperlink_base(&props.hyperlink_base);
+        }
+        if let Some(created) = parse_iso_datetime(&props.created) {
+            doc_props = doc_props.set_creation_datetime(&created);
+        }
+        for prop in &props.custom {
+            doc_props = match &prop.value {
+                CustomPropertyValue::Text(text) => doc_props.set_custom_property(&prop.name, text),
+                CustomPropertyValue::Number(n)
+                    if n.fract() == 0.0 && *n >= i32::MIN as f64 && *n <= i32::MAX as f64 =>
+                {
+                    doc_props.set_custom_property(&prop.name, *n as i32)
+                }
+                CustomPropertyValue::Number(n) => doc_props.set_custom_property(&prop.name, *n),
+                CustomPropertyValue::Bool(b) => doc_props.set_custom_property(&prop.name, *b),
+                CustomPropertyValue::Date(date) => match parse_iso_datetime(date) {
+                    Some(datetime) => doc_props.set_custom_property(&prop.name, &datetime),
+                    None => doc_props.set_custom_property(&prop.name, date),
+                },
+            };
+        }
         xlsx.set_properties(&doc_props);
     }
 
@@ -52,10 +86,17 @@ pub fn save_xlsx(workbook: &Workbook, path: &Path) -> Result<(), PersistenceErro
         let worksheet = xlsx.add_worksheet();
         worksheet.set_name(&sheet.name)?;
 
-        // ---- Gridlines visibility ----
+        // ---- Sheet view: gridlines, zoom, direction ----
         if !sheet.show_gridlines {
             worksheet.set_screen_gridlines(false);
         }
+        let zoom = (sheet.zoom * 100.0).round().clamp(10.0, 400.0) as u16;
+        if zoom != 100 {
+            worksheet.set_zoom(zoom);
+        }
+        if sheet.right_to_left {
+            worksheet.set_right_to_left(true);
+        }
 
         // ---- Tab color ----
         if !sheet.tab_color.is_empty() {
@@ -91,8 +132,27 @@ pub fn save_xlsx(workbook: &Workbook, path: &Path) -> Result<(), PersistenceErro
         }
 
         // ---- Row heights ----
+        // Pixels to points, the inverse of the reader's pt * 1.333.
         for (row, height) in &sheet.row_heights {
-            worksheet.set_row_height(*row, *height)?;
+            worksheet.set_row_height(*row, *height * 0.75)?;
+        }
+
+        // ---- Sheet default sizes (<sheetFormatPr>) ----
+        // defaultColWidth has no rust_xlsxwriter API; it is added after save.
+        if let Some(height) = sheet.default_row_height {
+            worksheet.set_default_row_height(height * 0.75);
+        }
+
+        // ---- Column/row default styles (<col style=>, <row s=>) ----
+        for (col, &style_index) in &sheet.column_styles {
+            if let Some(style) = sheet.styles.get(style_index).filter(|_| style_index > 0) {
+                worksheet.set_column_format(*col as u16, &convert_style_to_format(style))?;
+            }
+        }
+        for (row, &style_index) in &sheet.row_styles {
+            if let Some(style) = sheet.styles.get(style_index).filter(|_| style_index > 0) {
+                worksheet.set_row_format(*row, &convert_style_to_format(style))?;
+            }
         }
 
         // ---- Hidden rows ----
@@ -126,11 +186,15 @@ pub fn save_xlsx(workbook: &Workbook, path: &Path) -> Result<(), PersistenceErro
 
         // ---- Write cells ----
         for ((row, col), cell) in &sheet.cells {
-            let format = if cell.style_index > 0 && cell.style_index < sheet.styles.len() {
+            let mut format = if cell.style_index > 0 && cell.style_index < sheet.styles.len() {
                 Some(convert_style_to_format(&sheet.styles[cell.style_index]))
             } else {
                 None
             };
+            // Apostrophe-entered text carries Excel's quotePrefix style flag
+            if cell.quote_prefix {
+                format = Some(format.unwrap_or_default().set_quote_prefix());
+            }
 
             match &cell.value {
                 SavedCellValue::Empty => {
@@ -249,7 +313,12 @@ pub fn save_xlsx(workbook: &Workbook, path: &Path) -> Result<(), PersistenceErro
         }
 
         // ---- Hyperlinks ----
+        // Links on formula cells are skipped: write_url would replace the
+        // formula with text (a HYPERLINK formula carries its link anyway).
         for link in &sheet.hyperlinks {
+            if sheet.cells.get(&(link.row, link.col)).is_some_and(|c| c.formula.is_some()) {
+                continue;
+            }
             if let Some(ref display) = link.display_text {
                 worksheet.write_url_with_text(
                     link.row,
@@ -378,6 +447,33 @@ pub fn save_xlsx(workbook: &Workbook, path: &Path) -> Result<(), PersistenceErro
 
     let wrote_meta_charts = !workbook.charts.is_empty();
     xlsx.save(path)?;
+    if workbook.calc_properties != SavedCalcProperties::default() {
+        write_calc_properties(path, &workbook.calc_properties)?;
+    }
+    // Worksheets were added in workbook order, so sheet i is sheet{i+1}.xml.
+    let hidden_headings: Vec<usize> = workbook
+        .sheets
+        .iter()
+        .enumerate()
+        .filter(|(_, s)| !s.show_headings)
+        .map(|(i, _)| i + 1)
+        .collect();
+    if !hidden_headings.is_empty() {
+        write_hidden_headings(path, &hidden_headings)?;
+    }
+    let default_widths: Vec<(usize, f64)> = workbook
+        .sheets
+        .iter()
+        .enumerate()
+        .filter_map(|(i, s)| s.default_column_width.map(|w| (i + 1, w)))
+        .collect();
+    if !default_widths.is_empty() {
+        write_default_column_widths(path, &default_widths)?;
+    }
+    let props = &workbook.properties;
+    if !props.last_modified.is_empty() || !props.last_modified_by.is_empty() {
+        write_modification_properties(path, props)?;
+    }
 
     // Freshness marker: an ORPHAN zip part (valid .xml content type, but no
     // OPC relationship). Excel/LibreOffice rebuild the package on save and
@@ -394,6 +490,175 @@ pub fn save_xlsx(workbook: &Workbook, path: &Path) -> Result<(), PersistenceErro
     Ok(())
 }
 
+/// rust_xlsxwriter always emits `<calcPr calcId="124519" fullCalcOnLoad="1"/>`
+/// and has no API for the other attributes, so non-default settings are
+/// written by rewriting xl/workbook.xml in the saved package. Every other
+/// part is copied as-is (still compressed).
+fn write_calc_properties(path: &Path, props: &SavedCalcProperties) -> Result<(), PersistenceError> {
+    rewrite_package_parts(path, |name| name == "xl/workbook.xml", |_, xml| {
+        let element = calc_pr_element(props);
+        match xml.find("<calcPr") {
+            Some(start) => match xml[start..].find("/>") {
+                Some(len) => format!("{}{}{}", &xml[..start], element, &xml[start + len + 2..]),
+                None => xml,
+            },
+            None => xml.replace("</workbook>", &format!("{}</workbook>", element)),
+        }
+    })
+}
+
+/// rust_xlsxwriter writes the creation time as `dcterms:modified` and the
+/// author as `cp:lastModifiedBy`, so the real values are written by rewriting
+/// docProps/core.xml in the saved package.
+fn write_modification_properties(path: &Path, props: &WorkbookProperties) -> Result<(), PersistenceError> {
+    let modified = iso_datetime_head(&props.last_modified)
+        .filter(|head| ExcelDateTime::parse_from_str(head).is_ok())
+        .map(|head| if head.len() == 10 { format!("{}T00:00:00Z", head) } else { format!("{}Z", head) });
+    rewrite_package_parts(path, |name| name == "docProps/core.xml", |_, xml| {
+        let xml = match &modified {
+            Some(modified) => replace_element_text(xml, "dcterms:modified", modified),
+            None => xml,
+        };
+        if props.last_modified_by.is_empty() {
+            xml
+        } else {
+            replace_element_text(xml, "cp:lastModifiedBy", &escape_xml_text(&props.last_modified_by))
+        }
+    })
+}
+
+/// Replaces the text content of the first `<tag ...>text</tag>` in `xml`.
+fn replace_element_text(xml: String, tag: &str, text: &str) -> String {
+    let open = format!("<{}", tag);
+    let close = format!("</{}>", tag);
+    let Some(start) = xml.find(&open) else { return xml };
+    let Some(content_start) = xml[start..].find('>').map(|i| start + i + 1) else { return xml };
+    let Some(end) = xml[content_start..].find(&close).map(|i| content_start + i) else { return xml };
+    format!("{}{}{}", &xml[..content_start], text, &xml[end..])
+}
+
+fn escape_xml_text(s: &str) -> String {
+    s.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;")
+}
+
+/// Parses the date-time part of an ISO 8601 string ("2024-05-01T09:30:00Z",
+/// "2024-05-01T09:30:00.123+02:00", "2024-05-01"), ignoring fractional
+/// seconds and the offset.
+fn parse_iso_datetime(s: &str) -> Option<ExcelDateTime> {
+    iso_datetime_head(s).and_then(|head| ExcelDateTime::parse_from_str(head).ok())
+}
+
+/// The "YYYY-MM-DDTHH:MM:SS" (or bare "YYYY-MM-DD") prefix of an ISO 8601 string.
+fn iso_datetime_head(s: &str) -> Option<&str> {
+    let s = s.trim();
+    if s.len() < 10 || !s.is_char_boundary(10) || s.as_bytes()[4] != b'-' {
+        return None;
+    }
+    if s.len() >= 19 && s.is_char_boundary(19) && s.as_bytes()[10] == b'T' {
+        Some(&s[..19])
+    } else {
+        Some(&s[..10])
+    }
+}
+
+/// rust_xlsxwriter has no API for hiding row/column headings on screen, so
+/// `showRowColHeaders="0"` is added to the `<sheetView>` of the given
+/// worksheets (1-based part numbers) in the saved package.
+fn write_hidden_headings(path: &Path, sheet_numbers: &[usize]) -> Result<(), PersistenceError> {
+    let parts: Vec<String> = sheet_numbers
+        .iter()
+        .map(|n| format!("xl/worksheets/sheet{}.xml", n))
+        .collect();
+    rewrite_package_parts(
+        path,
+        |name| parts.iter().any(|p| p == name),
+        |_, xml| xml.replacen("<sheetView ", r#"<sheetView showRowColHeaders="0" "#, 1),
+    )
+}
+
+/// rust_xlsxwriter has no API for the sheet default column width, so
+/// `defaultColWidth` is added to the `<sheetFormatPr>` of the given worksheets
+/// (1-based part number, width in pixels) in the saved package.
+fn write_default_column_widths(path: &Path, widths: &[(usize, f64)]) -> Result<(), PersistenceError> {
+    let parts: Vec<(String, f64)> = widths
+        .iter()
+        .map(|(n, px)| (format!("xl/worksheets/sheet{}.xml", n), ((px - 5.0) / 7.0).max(0.0)))
+        .collect();
+    rewrite_package_parts(
+        path,
+        |name| parts.iter().any(|(p, _)| p == name),
+        |name, xml| match parts.iter().find(|(p, _)| p == name) {
+            Some((_, width)) => xml.replacen(
+                "<sheetFormatPr ",
+                &format!(r#"<sheetFormatPr defaultColWidth="{}" "#, width),
+                1,
+            ),
+            None => xml,
+        },
+    )
+}
+
+/// Rewrites the parts of the saved package picked by `selects` through
+/// `rewrite` (given the part name and its XML), in place. Every other part is copied as-is (still compressed).
+fn rewrite_package_parts(
+    path: &Path,
+    selects: impl Fn(&str) -> bool,
+    rewrite: impl Fn(&str, String) -> String,
+) -> Result<(), PersistenceError> {
+    use std::io::{Read, Write};
+    let bytes = std::fs::read(path)?;
+    let mut archive = zip::ZipArchive::new(std::io::Cursor::new(bytes))?;
+    let mut out = zip::ZipWriter::new(std::io::Cursor::new(Vec::new()));
+    for i in 0..archive.len() {
+        let mut entry = archive.by_index(i)?;
+        if !selects(entry.name()) {
+            out.raw_copy_file(entry)?;
+            continue;
+        }
+        let name = entry.name().to_string();
+        let mut xml = String::new();
+        entry.read_to_string(&mut xml)?;
+        out.start_file(name.as_str(), zip::write::SimpleFileOptions::default())?;
+        out.write_all(rewrite(&name, xml).as_bytes())?;
+    }
+    let package = out.finish()?.into_inner();
+    std::fs::write(path, package)?;
+    Ok(())
+}
+
+/// The `<calcPr>` element for `props`, attributes in schema order and only
+/// where they differ from the defaults.
+fn calc_pr_element(props: &SavedCalcProperties) -> String {
+    let defaults = SavedCalcProperties::default();
+    let mut attrs = vec![r#"calcId="124519""#.to_string()];
+    if props.calc_mode != defaults.calc_mode {
+        attrs.push(format!(r#"calcMode="{}""#, props.calc_mode));
+    }
+    attrs.push(r#"fullCalcOnLoad="1""#.to_string());
+    if props.iterate {
+        attrs.push(r#"iterate="1""#.to_string());
+    }
+    if props.iterate_count != defaults.iterate_count {
+        attrs.push(format!(r#"iterateCount="{}""#, props.iterate_count));
+    }
+    if props.iterate_delta != defaults.iterate_delta {
+        attrs.push(format!(r#"iterateDelta="{}""#, props.iterate_delta));
+    }
+    if !props.full_precision {
+        attrs.push(r#"fullPrecision="0""#.to_string());
+    }
+    if !props.calc_on_save {
+        attrs.push(r#"calcOnSave="0""#.to_string());
+    }
+    if !props.concurrent_calc {
+        attrs.push(r#"concurrentCalc="0""#.to_string());
+    }
+    if let Some(n) = props.concurrent_manual_count {
+        attrs.push(format!(r#"concurrentManualCount="{}""#, n));
+    }
+    format!("<calcPr {}/>", attrs.join(" "))
+}
+
 /// The orphan-part path checked by the reader (see save_xlsx).
 pub const XLSX_FRESHNESS_MARKER: &str = "calculaMeta/marker.xml";
 
@@ -605,9 +870,11 @@ fn write_page_setup(
         worksheet.set_footer(&ps.footer);
     }
 
-    // Print area
+    // Print area (the writer supports a single area: a multi-area print area
+    // exports its first area)
     if !ps.print_area.is_empty() {
-        if let Some((start, end)) = parse_cell_range(&ps.print_area) {
+        let first_area = ps.print_area.split(',').next().unwrap_or_default();
+        if let Some((start, end)) = parse_cell_range(first_area) {
             let _ = worksheet.set_print_area(start.0, start.1 as u16, end.0, end.1 as u16);
         }
     }
@@ -985,6 +1252,7 @@ fn convert_number_format(format: &NumberFormat) -> String {
         }
         NumberFormat::Date { format: fmt } => fmt.clone(),
         NumberFormat::Time { format: fmt } => fmt.clone(),
+        NumberFormat::DateTime { format: fmt } => fmt.clone(),
         NumberFormat::Custom { format: fmt } => fmt.clone(),
     }
 }
@@ -1013,3 +1281,241 @@ fn is_default_background(color: &engine::theme::ThemeColor) -> bool {
         _ => false,
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{load_xlsx, SavedCell, SavedCellValue, Sheet};
+    use engine::style::{CellStyle, NumberFormat};
+
+    #[test]
+    fn test_column_and_row_styles_roundtrip() {
+        let percent = CellStyle::new().with_number_format(NumberFormat::Percentage { decimal_places: 2 });
+        let bold = CellStyle::new().with_bold(true);
+
+        let mut sheet = Sheet::new("Sheet1".to_string());
+        sheet.styles = vec![CellStyle::new(), percent, bold];
+        sheet.column_styles.insert(2, 1);
+        sheet.row_styles.insert(4, 2);
+        sheet.cells.insert(
+            (0, 0),
+            SavedCell { value: SavedCellValue::Number(1.0), formula: None, style_index: 0, rich_text: None, quote_prefix: false },
+        );
+        let mut workbook = Workbook::new();
+        workbook.sheets = vec![sheet];
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("line_styles.xlsx");
+        save_xlsx(&workbook, &path).unwrap();
+        let loaded = load_xlsx(&path).unwrap();
+
+        let sheet = &loaded.sheets[0];
+        let col_style = &sheet.styles[sheet.column_styles[&2]];
+        assert!(matches!(col_style.number_format, NumberFormat::Percentage { decimal_places: 2 }));
+        assert!(sheet.styles[sheet.row_styles[&4]].font.bold);
+        assert!(!sheet.column_styles.contains_key(&0));
+        assert!(!sheet.row_styles.contains_key(&0));
+    }
+
+    #[test]
+    fn test_calc_properties_roundtrip() {
+        let dir = tempfile::tempdir().unwrap();
+
+        // Defaults leave the writer's own calcPr alone and read back as defaults.
+        let path = dir.path().join("calc_default.xlsx");
+        save_xlsx(&Workbook::new(), &path).unwrap();
+        assert_eq!(load_xlsx(&path).unwrap().calc_properties, SavedCalcProperties::default());
+
+        let mut workbook = Workbook::new();
+        workbook.sheets[0].cells.insert(
+            (0, 0),
+            SavedCell { value: SavedCellValue::Number(2.0), formula: None, style_index: 0, rich_text: None, quote_prefix: false },
+        );
+        workbook.calc_properties = SavedCalcProperties {
+            calc_mode: "manual".to_string(),
+            calc_on_save: false,
+            iterate: true,
+            iterate_count: 250,
+            iterate_delta: 0.0001,
+            full_precision: false,
+            concurrent_calc: true,
+            concurrent_manual_count: Some(4),
+        };
+        let path = dir.path().join("calc_custom.xlsx");
+        save_xlsx(&workbook, &path).unwrap();
+        let loaded = load_xlsx(&path).unwrap();
+        assert_eq!(loaded.calc_properties, workbook.calc_properties);
+        // The rest of the package survives the rewrite.
+        assert!(matches!(loaded.sheets[0].cells[&(0, 0)].value, SavedCellValue::Number(n) if n == 2.0));
+    }
+
+    #[test]
+    fn test_document_properties_roundtrip() {
+        use crate::CustomProperty;
+        let dir = tempfile::tempdir().unwrap();
+        let mut workbook = Workbook::new();
+        workbook.properties = WorkbookProperties {
+            title: "Q3 <Budget>".to_string(),
+            author: "Ana".to_string(),
+            subject: "Finance".to_string(),
+            description: "Quarterly numbers".to_string(),
+            keywords: "budget; q3".to_string(),
+            category: "Reports".to_string(),
+            created: "2024-03-01T08:00:00+00:00".to_string(),
+            last_modified: "2024-05-02T17:45:10.123+00:00".to_string(),
+            last_modified_by: "Ben & Co".to_string(),
+            status: "Draft".to_string(),
+            company: "Acme".to_string(),
+            manager: "Cleo".to_string(),
+            hyperlink_base: "https://example.com/docs/".to_string(),
+            custom: vec![
+                CustomProperty { name: "Project".to_string(), value: CustomPropertyValue::Text("Apollo".to_string()) },
+                CustomProperty { name: "Revision".to_string(), value: CustomPropertyValue::Number(7.0) },
+                CustomProperty { name: "Ratio".to_string(), value: CustomPropertyValue::Number(0.25) },
+                CustomProperty { name: "Approved".to_string(), value: CustomPropertyValue::Bool(true) },
+                CustomProperty {
+                    name: "Due".to_string(),
+                    value: CustomPropertyValue::Date("2024-06-30T00:00:00Z".to_string()),
+                },
+            ],
+        };
+        let path = dir.path().join("doc_props.xlsx");
+        save_xlsx(&workbook, &path).unwrap();
+        let loaded = load_xlsx(&path).unwrap().properties;
+
+        assert_eq!(
+            loaded,
+            WorkbookProperties {
+                created: "2024-03-01T08:00:00Z".to_string(),
+                last_modified: "2024-05-02T17:45:10Z".to_string(),
+                ..workbook.properties.clone()
+            }
+        );
+    }
+
+    #[test]
+    fn test_foreign_custom_properties_survive_resave() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("foreign.xlsx");
+
+        // A package authored elsewhere, with variant types we do not model.
+        let mut xlsx = XlsxWorkbook::new();
+        xlsx.add_worksheet().write_number(0, 0, 1.0).unwrap();
+        xlsx.set_properties(&DocProperties::new().set_custom_property("Placeholder", "x"));
+        xlsx.save(&path).unwrap();
+        rewrite_package_parts(&path, |name| name == "docProps/custom.xml", |_, _| {
+            r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<Properties xmlns="http://schemas.openxmlformats.org/officeDocument/2006/custom-properties" xmlns:vt="http://schemas.openxmlformats.org/officeDocument/2006/docPropsVTypes">
+<property fmtid="{D5CDD505-2E9C-101B-9397-08002B2CF9AE}" pid="2" name="Client &amp; Co"><vt:lpwstr>Globex</vt:lpwstr></property>
+<property fmtid="{D5CDD505-2E9C-101B-9397-08002B2CF9AE}" pid="3" name="Legacy Id"><vt:i8>9000000000</vt:i8></property>
+<property fmtid="{D5CDD505-2E9C-101B-9397-08002B2CF9AE}" pid="4" name="Cost"><vt:decimal>12.5</vt:decimal></property>
+<property fmtid="{D5CDD505-2E9C-101B-9397-08002B2CF9AE}" pid="5" name="Code"><vt:lpstr>ZX-9</vt:lpstr></property>
+<property fmtid="{D5CDD505-2E9C-101B-9397-08002B2CF9AE}" pid="6" name="Signed"><vt:bool>false</vt:bool></property>
+<property fmtid="{D5CDD505-2E9C-101B-9397-08002B2CF9AE}" pid="7" name="Reviewed"><vt:filetime>2023-11-05T10:00:00Z</vt:filetime></property>
+</Properties>"#
+                .to_string()
+        })
+        .unwrap();
+
+        let first = load_xlsx(&path).unwrap();
+        let resaved = dir.path().join("resaved.xlsx");
+        save_xlsx(&first, &resaved).unwrap();
+        let custom = load_xlsx(&resaved).unwrap().properties.custom;
+
+        let expected = [
+            ("Client & Co", CustomPropertyValue::Text("Globex".to_string())),
+            ("Legacy Id", CustomPropertyValue::Number(9_000_000_000.0)),
+            ("Cost", CustomPropertyValue::Number(12.5)),
+            ("Code", CustomPropertyValue::Text("ZX-9".to_string())),
+            ("Signed", CustomPropertyValue::Bool(false)),
+            ("Reviewed", CustomPropertyValue::Date("2023-11-05T10:00:00Z".to_string())),
+        ];
+        assert_eq!(custom.len(), expected.len());
+        for (prop, (name, value)) in custom.iter().zip(expected) {
+            assert_eq!(prop.name, name);
+            assert_eq!(prop.value, value);
+        }
+    }
+
+    #[test]
+    fn test_quote_prefix_roundtrip() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut workbook = Workbook::new();
+        for (col, text, quoted) in [(0, "00123", true), (1, "=SUM(A1)", true), (2, "plain", false)] {
+            workbook.sheets[0].cells.insert(
+                (0, col),
+                SavedCell {
+                    value: SavedCellValue::Text(text.to_string()),
+                    formula: None,
+                    style_index: 0,
+                    rich_text: None,
+                    quote_prefix: quoted,
+                },
+            );
+        }
+        let path = dir.path().join("quote_prefix.xlsx");
+        save_xlsx(&workbook, &path).unwrap();
+        let loaded = load_xlsx(&path).unwrap();
+        let cells = &loaded.sheets[0].cells;
+
+        assert!(cells[&(0, 0)].quote_prefix);
+        assert!(matches!(&cells[&(0, 0)].value, SavedCellValue::Text(s) if s == "00123"));
+        assert!(cells[&(0, 1)].quote_prefix);
+        assert!(cells[&(0, 1)].formula.is_none());
+        assert!(matches!(&cells[&(0, 1)].value, SavedCellValue::Text(s) if s == "=SUM(A1)"));
+        assert!(!cells[&(0, 2)].quote_prefix);
+    }
+
+    #[test]
+    fn test_sheet_view_options_roundtrip() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut workbook = Workbook::new();
+        workbook.sheets[0].zoom = 1.5;
+        workbook.sheets[0].show_gridlines = false;
+        let mut second = Sheet::new("Second".to_string());
+        second.show_headings = false;
+        second.right_to_left = true;
+        second.zoom = 0.75;
+        workbook.sheets.push(second);
+        workbook.sheets.push(Sheet::new("Plain".to_string()));
+
+        let path = dir.path().join("sheet_view.xlsx");
+        save_xlsx(&workbook, &path).unwrap();
+        let loaded = load_xlsx(&path).unwrap();
+        let view = |s: &Sheet| (s.zoom, s.show_gridlines, s.show_headings, s.right_to_left);
+
+        assert_eq!(view(&loaded.sheets[0]), (1.5, false, true, false));
+        assert_eq!(view(&loaded.sheets[1]), (0.75, true, false, true));
+        assert_eq!(view(&loaded.sheets[2]), (1.0, true, true, false));
+    }
+
+    #[test]
+    fn test_sheet_default_sizes_roundtrip() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut workbook = Workbook::new();
+        workbook.sheets[0].default_column_width = Some(96.0);
+        workbook.sheets[0].default_row_height = Some(28.0);
+        workbook.sheets[0].column_widths.insert(2, 40.0);
+        workbook.sheets[0].row_heights.insert(4, 12.0);
+        workbook.sheets[0].cells.insert(
+            (0, 0),
+            SavedCell { value: SavedCellValue::Number(1.0), formula: None, style_index: 0, rich_text: None, quote_prefix: false },
+        );
+        workbook.sheets.push(Sheet::new("Plain".to_string()));
+
+        let path = dir.path().join("default_sizes.xlsx");
+        save_xlsx(&workbook, &path).unwrap();
+        let loaded = load_xlsx(&path).unwrap();
+
+        let sized = &loaded.sheets[0];
+        assert_eq!((sized.default_column_width, sized.default_row_height), (Some(96.0), Some(28.0)));
+        // Explicit sizes keep their own values, and rows at the sheet
+        // default are not turned into explicit heights
+        assert!(sized.column_widths.get(&2).is_some_and(|w| *w < 96.0));
+        assert_eq!(sized.column_widths.get(&0), None);
+        assert_eq!(sized.row_heights.get(&4), Some(&12.0));
+        assert_eq!(sized.row_heights.get(&0), None);
+        let plain = &loaded.sheets[1];
+        assert_eq!((plain.default_column_width, plain.default_row_height), (None, None));
+    }
+}
@@ -166,6 +166,12 @@ pub struct FieldCache {
     /// When present, get_value_label uses this map instead of the raw CacheValue display.
     #[serde(default)]
     pub label_map: HashMap<ValueId, String>,
+
+    /// Number of source records whose value for this field is empty.
+    /// Counted during `PivotCache::add_record`; feeds the field list's
+    /// source-column health stats.
+    #[serde(default)]
+    pub blank_count: usize,
 }
 
 impl FieldCache {
@@ -178,6 +184,7 @@ impl FieldCache {
             sorted_ids_asc: Vec::new(),
             sort_dirty: true,
             label_map: HashMap::new(),
+            blank_count: 0,
         }
     }
     
@@ -646,6 +653,11 @@ pub struct PivotCache {
     /// See [`TotalOverride`].
     #[serde(default)]
     pub total_overrides: Vec<TotalOverride>,
+
+    /// Header cells that were renamed while building the cache because they
+    /// were blank or duplicated an earlier header. See [`disambiguate_headers`].
+    #[serde(default)]
+    pub header_renames: Vec<HeaderRename>,
 }
 
 /// One engine-evaluated total cell for a BI-backed pivot.
@@ -699,6 +711,7 @@ impl PivotCache {
             virtual_records: Vec::new(),
             stats: CacheStats::default(),
             total_overrides: Vec::new(),
+            header_renames: Vec::new(),
         }
     }
     
@@ -717,12 +730,16 @@ impl PivotCache {
             if i < self.fields.len() {
                 let cache_value = CacheValue::from(value);
                 let value_id = self.fields[i].intern(cache_value);
+                if value_id == VALUE_ID_EMPTY {
+                    self.fields[i].blank_count += 1;
+                }
                 interned_values.push(value_id);
             }
         }
         
         // Pad with empty if needed
         while interned_values.len() < self.fields.len() {
+            self.fields[interned_values.len()].blank_count += 1;
             interned_values.push(VALUE_ID_EMPTY);
         }
         
@@ -1407,6 +1424,152 @@ impl PivotCache {
     }
 }
 
+// ============================================================================
+// SOURCE FIELD PROFILING
+// ============================================================================
+
+/// Inferred data type of a source field, derived from its interned values.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum SourceFieldType {
+    /// Every record is blank (or holds only errors).
+    Empty,
+    Number,
+    /// Numbers whose source cells carry a date number format.
+    Date,
+    Text,
+    Boolean,
+    /// More than one of the above kinds.
+    Mixed,
+}
+
+/// Cheap per-field statistics for the pivot field list.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FieldProfile {
+    pub data_type: SourceFieldType,
+    /// The first distinct values in source order, as display labels.
+    pub samples: Vec<String>,
+    /// Number of distinct non-empty values.
+    pub distinct_count: usize,
+    /// Number of records whose value is empty.
+    pub blank_count: usize,
+}
+
+/// Why a source header was renamed during the cache build.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum HeaderRenameReason {
+    Blank,
+    Duplicate,
+}
+
+/// A source header that could not be used as-is for a field name.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HeaderRename {
+    /// Field (column) index within the source range.
+    pub index: FieldIndex,
+    /// Header text as it appears in the source (trimmed; empty when blank).
+    pub original: String,
+    /// Field name assigned in the cache.
+    pub name: String,
+    pub reason: HeaderRenameReason,
+}
+
+/// Makes source header names unique and non-empty.
+///
+/// Blank headers become `Column{n}` (1-based position in the source range).
+/// A header equal (case-insensitively) to an earlier one gets the smallest
+/// numeric suffix that is still free, so two "Sales" columns become "Sales"
+/// and "Sales2". Returns the final names and one entry per renamed header.
+pub fn disambiguate_headers(raw: &[String]) -> (Vec<String>, Vec<HeaderRename>) {
+    let mut taken: HashSet<String> = raw
+        .iter()
+        .map(|h| h.trim().to_lowercase())
+        .filter(|h| !h.is_empty())
+        .collect();
+    let mut used: HashSet<String> = HashSet::with_capacity(raw.len());
+    let mut names = Vec::with_capacity(raw.len());
+    let mut renames = Vec::new();
+
+    for (index, header) in raw.iter().enumerate() {
+        let original = header.trim().to_string();
+        let (base, reason) = if original.is_empty() {
+            (format!("Column{}", index + 1), Some(HeaderRenameReason::Blank))
+        } else if used.contains(&original.to_lowercase()) {
+            (original.clone(), Some(HeaderRenameReason::Duplicate))
+        } else {
+            (original.clone(), None)
+        };
+
+        // A renamed header must not collide with any header, earlier or later.
+        let mut name = base.clone();
+        if reason.is_some() {
+            let mut suffix = 2;
+            while taken.contains(&name.to_lowercase()) {
+                name = format!("{}{}", base, suffix);
+                suffix += 1;
+            }
+        }
+
+        let key = name.to_lowercase();
+        used.insert(key.clone());
+        taken.insert(key);
+        if let Some(reason) = reason {
+            renames.push(HeaderRename { index, original, name: name.clone(), reason });
+        }
+        names.push(name);
+    }
+
+    (names, renames)
+}
+
+impl PivotCache {
+    /// Profiles a source field: inferred type, up to `sample_limit` sample
+    /// values, distinct and blank counts. Numbers are reported as dates when
+    /// `date_formatted` is set (the host knows the source cell formats; the
+    /// cache only holds raw values). Returns None for an unknown field.
+    pub fn field_profile(
+        &self,
+        field_index: FieldIndex,
+        sample_limit: usize,
+        date_formatted: bool,
+    ) -> Option<FieldProfile> {
+        let field = self.fields.get(field_index)?;
+
+        let mut kinds: Vec<SourceFieldType> = Vec::new();
+        for value in &field.id_to_value {
+            let kind = match value {
+                CacheValue::Number(_) if date_formatted => SourceFieldType::Date,
+                CacheValue::Number(_) => SourceFieldType::Number,
+                CacheValue::Text(_) => SourceFieldType::Text,
+                CacheValue::Boolean(_) => SourceFieldType::Boolean,
+                CacheValue::Empty | CacheValue::Error(_) => continue,
+            };
+            if !kinds.contains(&kind) {
+                kinds.push(kind);
+            }
+        }
+        let data_type = match kinds.as_slice() {
+            [] => SourceFieldType::Empty,
+            [kind] => *kind,
+            _ => SourceFieldType::Mixed,
+        };
+
+        let samples = (0..field.unique_count().min(sample_limit) as ValueId)
+            .filter_map(|id| self.get_value_label(field_index, id))
+            .collect();
+
+        Some(FieldProfile {
+            data_type,
+            samples,
+            distinct_count: field.unique_count(),
+            blank_count: field.blank_count,
+        })
+    }
+}
+
 // ============================================================================
 // DATE PARSING HELPERS
 // ============================================================================
@@ -1811,4 +1974,83 @@ mod total_override_tests {
             .compute(AggregationType::Sum);
         assert_eq!(gt_pct, 0.1 + 0.2);
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod field_profile_tests {
+    use super::*;
+    use engine::CellValue;
+
+    fn headers(names: &[&str]) -> Vec<String> {
+        names.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn duplicate_and_blank_headers_are_disambiguated() {
+        let (names, renames) =
+            disambiguate_headers(&headers(&["Sales", "", "sales", "Sales2", "  "]));
+        assert_eq!(names, vec!["Sales", "Column2", "sales3", "Sales2", "Column5"]);
+        assert_eq!(
+            renames,
+            vec![
+                HeaderRename {
+                    index: 1,
+                    original: String::new(),
+                    name: "Column2".to_string(),
+                    reason: HeaderRenameReason::Blank,
+                },
+                HeaderRename {
+                    index: 2,
+                    original: "sales".to_string(),
+                    name: "sales3".to_string(),
+                    reason: HeaderRenameReason::Duplicate,
+                },
+                HeaderRename {
+                    index: 4,
+                    original: String::new(),
+                    name: "Column5".to_string(),
+                    reason: HeaderRenameReason::Blank,
+                },
+            ]
+        );
+
+        let (names, renames) = disambiguate_headers(&headers(&["Region", "Sales"]));
+        assert_eq!(names, vec!["Region", "Sales"]);
+        assert!(renames.is_empty());
+    }
+
+    #[test]
+    fn field_profile_infers_types_and_counts() {
+        let mut cache = PivotCache::new(PivotId::from_bytes([0; 16]), 4);
+        let rows = [
+            [CellValue::Text("North".to_string()), CellValue::Number(10.0), CellValue::Number(45000.0), CellValue::Empty],
+            [CellValue::Text("South".to_string()), CellValue::Text("n/a".to_string()), CellValue::Number(45001.0), CellValue::Empty],
+            [CellValue::Text("North".to_string()), CellValue::Empty, CellValue::Number(45000.0), CellValue::Empty],
+        ];
+        for (i, row) in rows.iter().enumerate() {
+            cache.add_record(i as u32, row);
+        }
+        // Short record: the missing trailing field counts as blank.
+        cache.add_record(3, &[CellValue::Text("East".to_string())]);
+
+        let region = cache.field_profile(0, 2, false).unwrap();
+        assert_eq!(region.data_type, SourceFieldType::Text);
+        assert_eq!(region.samples, vec!["North", "South"]);
+        assert_eq!(region.distinct_count, 3);
+        assert_eq!(region.blank_count, 0);
+
+        let mixed = cache.field_profile(1, 5, false).unwrap();
+        assert_eq!(mixed.data_type, SourceFieldType::Mixed);
+        assert_eq!(mixed.samples, vec!["10", "n/a"]);
+        assert_eq!(mixed.blank_count, 2);
+
+        assert_eq!(cache.field_profile(2, 5, false).unwrap().data_type, SourceFieldType::Number);
+        assert_eq!(cache.field_profile(2, 5, true).unwrap().data_type, SourceFieldType::Date);
+
+        let empty = cache.field_profile(3, 5, false).unwrap();
+        assert_eq!(empty.data_type, SourceFieldType::Empty);
+        assert_eq!(empty.distinct_count, 0);
+        assert_eq!(empty.blank_count, 4);
+
+        assert!(cache.field_profile(4, 5, false).is_none());
+    }
+}
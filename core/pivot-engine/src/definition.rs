@@ -645,6 +645,11 @@ pub struct PivotDefinition {
     /// Destination sheet name (if different from source).
     pub destination_sheet: Option<String>,
 
+    /// Sheet holding the source range. None on pivots saved before it was
+    /// recorded; callers fall back to the destination sheet.
+    #[serde(default)]
+    pub source_sheet: Option<String>,
+
     /// Version for cache invalidation.
     pub version: u64,
 
@@ -664,10 +669,19 @@ pub struct PivotDefinition {
     #[serde(default)]
     pub refresh_on_open: bool,
 
+    /// Refresh automatically when cells in the source range change (only in
+    /// automatic calculation mode; otherwise the pivot is marked stale).
+    #[serde(default)]
+    pub auto_refresh: bool,
+
     /// Use custom sort lists when sorting (Excel: useCustomSortLists).
     #[serde(default)]
     pub use_custom_sort_lists: bool,
 
+    /// What a refresh does when the output grows into occupied cells.
+    #[serde(default)]
+    pub on_grow: GrowPolicy,
+
     /// If the pivot source is a Table, stores the table name (e.g. "Table1").
     /// When set, `refresh_pivot_cache` resolves the table's current range
     /// dynamically so that table expansions propagate automatically.
@@ -695,6 +709,20 @@ pub struct PivotDefinition {
     pub hierarchy_configs: Vec<HierarchyConfig>,
 }
 
+/// How a refresh handles output that grows past its previous extent into
+/// non-empty cells or other protected regions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum GrowPolicy {
+    /// Write over whatever is in the way (historical behavior).
+    #[default]
+    Overwrite,
+    /// Insert rows/columns so the obstructing cells move down/right.
+    InsertCells,
+    /// Refuse the refresh and report the blocking cells.
+    Fail,
+}
+
 // ============================================================================
 // HIERARCHY CONFIGURATION
 // ============================================================================
@@ -761,11 +789,14 @@ impl PivotDefinition {
             layout: PivotLayout::default(),
             destination: (0, 0),
             destination_sheet: None,
+            source_sheet: None,
             version: 0,
             allow_multiple_filters_per_field: false,
             enable_data_value_editing: false,
             refresh_on_open: false,
+            auto_refresh: false,
             use_custom_sort_lists: false,
+            on_grow: GrowPolicy::default(),
             source_table_name: None,
             calculated_fields: Vec::new(),
             calculated_items: Vec::new(),
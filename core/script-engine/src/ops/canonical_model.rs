@@ -293,6 +293,7 @@ fn write_cell(ctx: &mut ScriptContext, sheet_index: usize, row: u32, col: u32, v
             value: string_to_cell_value(value),
             style_index,
             rich_text: None,
+            quote_prefix: false,
         };
         grid.set_cell(row, col, cell);
         *ctx.cells_modified.borrow_mut() += 1;
@@ -497,6 +498,7 @@ mod tests {
                 value: CellValue::Text(text.to_string()),
                 style_index: 0,
                 rich_text: None,
+                quote_prefix: false,
             },
         );
     }
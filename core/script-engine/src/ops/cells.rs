@@ -69,6 +69,7 @@ pub fn register_cell_ops<'js>(
                         value: cell_value,
                         style_index,
                         rich_text: None,
+                        quote_prefix: false,
                     };
                     grid.set_cell(row as u32, col as u32, cell);
                     *ctx.cells_modified.borrow_mut() += 1;
@@ -149,6 +150,7 @@ pub fn register_cell_ops<'js>(
                                 value: cell_value,
                                 style_index,
                                 rich_text: None,
+                                quote_prefix: false,
                             };
                             grid.set_cell(r, c, cell);
                             modified_count += 1;